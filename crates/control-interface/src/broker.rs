@@ -45,6 +45,13 @@ pub mod v1 {
         )
     }
 
+    pub fn validate_link(topic_prefix: &Option<String>, lattice: &str, host_id: &str) -> String {
+        format!(
+            "{}.link.validate.{host_id}",
+            prefix(topic_prefix, lattice, CTL_API_VERSION_1)
+        )
+    }
+
     pub fn publish_registries(topic_prefix: &Option<String>, lattice: &str) -> String {
         format!(
             "{}.registry.put",
@@ -70,6 +77,65 @@ pub mod v1 {
         )
     }
 
+    pub fn rollback_config(
+        topic_prefix: &Option<String>,
+        lattice: &str,
+        config_name: &str,
+    ) -> String {
+        format!(
+            "{}.config.rollback.{config_name}",
+            prefix(topic_prefix, lattice, CTL_API_VERSION_1)
+        )
+    }
+
+    pub fn import_claims(topic_prefix: &Option<String>, lattice: &str) -> String {
+        format!(
+            "{}.claims.import",
+            prefix(topic_prefix, lattice, CTL_API_VERSION_1)
+        )
+    }
+
+    pub fn put_traffic_split(topic_prefix: &Option<String>, lattice: &str) -> String {
+        format!(
+            "{}.traffic.put",
+            prefix(topic_prefix, lattice, CTL_API_VERSION_1)
+        )
+    }
+
+    pub fn delete_traffic_split(
+        topic_prefix: &Option<String>,
+        lattice: &str,
+        target: &str,
+    ) -> String {
+        format!(
+            "{}.traffic.del.{target}",
+            prefix(topic_prefix, lattice, CTL_API_VERSION_1)
+        )
+    }
+
+    pub fn put_payload_capture(
+        topic_prefix: &Option<String>,
+        lattice: &str,
+        host_id: &str,
+    ) -> String {
+        format!(
+            "{}.capture.put.{host_id}",
+            prefix(topic_prefix, lattice, CTL_API_VERSION_1)
+        )
+    }
+
+    pub fn delete_payload_capture(
+        topic_prefix: &Option<String>,
+        lattice: &str,
+        host_id: &str,
+        source_id: &str,
+    ) -> String {
+        format!(
+            "{}.capture.del.{host_id}.{source_id}",
+            prefix(topic_prefix, lattice, CTL_API_VERSION_1)
+        )
+    }
+
     pub fn put_label(topic_prefix: &Option<String>, lattice: &str, host_id: &str) -> String {
         format!(
             "{}.label.put.{host_id}",
@@ -122,6 +188,17 @@ pub mod v1 {
             )
         }
 
+        pub fn update_provider(
+            topic_prefix: &Option<String>,
+            lattice: &str,
+            host_id: &str,
+        ) -> String {
+            format!(
+                "{}.provider.update.{host_id}",
+                prefix(topic_prefix, lattice, CTL_API_VERSION_1)
+            )
+        }
+
         pub fn update_component(
             topic_prefix: &Option<String>,
             lattice: &str,
@@ -153,6 +230,25 @@ pub mod v1 {
             )
         }
 
+        pub fn captured_payloads(
+            topic_prefix: &Option<String>,
+            lattice: &str,
+            host_id: &str,
+            source_id: &str,
+        ) -> String {
+            format!(
+                "{}.capture.get.{host_id}.{source_id}",
+                prefix(topic_prefix, lattice, CTL_API_VERSION_1)
+            )
+        }
+
+        pub fn traffic_splits(topic_prefix: &Option<String>, lattice: &str) -> String {
+            format!(
+                "{}.traffic.get",
+                prefix(topic_prefix, lattice, CTL_API_VERSION_1)
+            )
+        }
+
         pub fn claims(topic_prefix: &Option<String>, lattice: &str) -> String {
             format!(
                 "{}.claims.get",
@@ -160,6 +256,13 @@ pub mod v1 {
             )
         }
 
+        pub fn export_claims(topic_prefix: &Option<String>, lattice: &str) -> String {
+            format!(
+                "{}.claims.export",
+                prefix(topic_prefix, lattice, CTL_API_VERSION_1)
+            )
+        }
+
         pub fn host_inventory(
             topic_prefix: &Option<String>,
             lattice: &str,
@@ -184,5 +287,16 @@ pub mod v1 {
                 prefix(topic_prefix, lattice, CTL_API_VERSION_1),
             )
         }
+
+        pub fn config_history(
+            topic_prefix: &Option<String>,
+            lattice: &str,
+            config_name: &str,
+        ) -> String {
+            format!(
+                "{}.config.history.{config_name}",
+                prefix(topic_prefix, lattice, CTL_API_VERSION_1),
+            )
+        }
     }
 }
@@ -7,22 +7,28 @@ use std::collections::{BTreeMap, HashMap};
 
 use async_nats::Subscriber;
 use cloudevents::event::Event;
+use futures::stream::BoxStream;
 use futures::{StreamExt, TryFutureExt};
 use serde::de::DeserializeOwned;
 use tokio::sync::mpsc::Receiver;
 use tracing::{debug, error, instrument, trace};
 
+use crate::types::capture::{CapturedPayload, PayloadCapture};
+use crate::types::claims::ClaimsBundle;
+use crate::types::config::{ConfigRevision, ConfigRollbackRequest};
 use crate::types::ctl::{
     CtlResponse, ScaleComponentCommand, StartProviderCommand, StopHostCommand, StopProviderCommand,
-    UpdateComponentCommand,
+    UpdateComponentCommand, UpdateProviderCommand,
 };
+use crate::types::event::{subject_lattice, EventFilter, LatticeEvent};
 use crate::types::host::{Host, HostInventory, HostLabel};
-use crate::types::link::Link;
+use crate::types::link::{Link, LinkValidation};
 use crate::types::registry::RegistryCredential;
 use crate::types::rpc::{
     ComponentAuctionAck, ComponentAuctionRequest, DeleteInterfaceLinkDefinitionRequest,
     ProviderAuctionAck, ProviderAuctionRequest,
 };
+use crate::types::traffic::TrafficSplit;
 use crate::{
     broker, json_deserialize, json_serialize, otel, HostLabelIdentifier, IdentifierKind, Result,
 };
@@ -209,6 +215,39 @@ impl Client {
         }
     }
 
+    /// Exports the full set of all cached claims and their issuers in the lattice as a
+    /// [`ClaimsBundle`] signed by the responding host, which can be imported into another
+    /// lattice using [`Client::import_claims`].
+    #[instrument(level = "debug", skip_all)]
+    pub async fn export_claims(&self) -> Result<CtlResponse<ClaimsBundle>> {
+        let subject = broker::v1::queries::export_claims(&self.topic_prefix, &self.lattice);
+        debug!(%subject, "Exporting claims");
+        match self.request_timeout(subject, vec![], self.timeout).await {
+            Ok(msg) => json_deserialize(&msg.payload),
+            Err(e) => Err(format!("Did not receive claims bundle from lattice: {e}").into()),
+        }
+    }
+
+    /// Imports the claims of a [`ClaimsBundle`] exported from another lattice into the claims
+    /// cache of the lattice.
+    ///
+    /// The host handling the request verifies the signature of the bundle and rejects bundles
+    /// signed by a key it was not configured to trust.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if it was unable to import the claims bundle.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn import_claims(&self, bundle: ClaimsBundle) -> Result<CtlResponse<()>> {
+        let subject = broker::v1::import_claims(&self.topic_prefix, &self.lattice);
+        debug!(%subject, signer = bundle.signer(), "Importing claims");
+        let bytes = json_serialize(bundle)?;
+        match self.request_timeout(subject, bytes, self.timeout).await {
+            Ok(msg) => json_deserialize(&msg.payload),
+            Err(e) => Err(format!("Did not receive import claims acknowledgement: {e}").into()),
+        }
+    }
+
     /// Performs an component auction within the lattice, publishing a set of constraints and the
     /// metadata for the component in question. This will always wait for the full period specified by
     /// _duration_, and then return the set of gathered results. It is then up to the client to
@@ -378,6 +417,37 @@ impl Client {
         }
     }
 
+    /// Validates a link with the provider it connects, which runs on host `host_id`, without
+    /// putting the link into the lattice.
+    ///
+    /// The host resolves the link configuration and secrets as it would when establishing the
+    /// link, and forwards them to the provider, which checks them and returns the errors found.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the host did not respond to the request
+    #[instrument(level = "debug", skip_all)]
+    pub async fn validate_link(
+        &self,
+        host_id: &str,
+        link: Link,
+    ) -> Result<CtlResponse<LinkValidation>> {
+        // Validate link parameters
+        IdentifierKind::is_component_id(&link.source_id)?;
+        IdentifierKind::is_component_id(&link.target)?;
+        IdentifierKind::is_link_name(&link.name)?;
+        let host_id = IdentifierKind::is_host_id(host_id)?;
+
+        let subject = broker::v1::validate_link(&self.topic_prefix, &self.lattice, &host_id);
+        debug!("validate_link:request {}", &subject);
+
+        let bytes = crate::json_serialize(link)?;
+        match self.request_timeout(subject, bytes, self.timeout).await {
+            Ok(msg) => Ok(json_deserialize(&msg.payload)?),
+            Err(e) => Err(format!("Did not receive validate link response: {e}").into()),
+        }
+    }
+
     /// Deletes a link from the lattice metadata keyvalue bucket.
     ///
     /// This is an idempotent operation.
@@ -422,6 +492,155 @@ impl Client {
         }
     }
 
+    /// Split the invocations sent over links to the target of `split` between the target and the
+    /// canary of `split`, replacing any existing split of the target.
+    ///
+    /// Splits apply to all links to the target. Setting the weight of a split to `100` sends all
+    /// invocations to the canary, while deleting the split sends all invocations to the target.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if it was unable to put the traffic split.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn put_traffic_split(&self, split: TrafficSplit) -> Result<CtlResponse<()>> {
+        IdentifierKind::is_component_id(&split.target)?;
+        IdentifierKind::is_component_id(&split.canary)?;
+
+        let subject = broker::v1::put_traffic_split(&self.topic_prefix, &self.lattice);
+        debug!(%subject, target = split.target, canary = split.canary, weight = split.weight, "Putting traffic split");
+        let bytes = json_serialize(split)?;
+        match self.request_timeout(subject, bytes, self.timeout).await {
+            Ok(msg) => json_deserialize(&msg.payload),
+            Err(e) => Err(format!("Did not receive put traffic split acknowledgement: {e}").into()),
+        }
+    }
+
+    /// Delete the traffic split of `target`, so that all invocations are sent to `target`.
+    ///
+    /// This is an idempotent operation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if it was unable to delete the traffic split.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn delete_traffic_split(&self, target: &str) -> Result<CtlResponse<()>> {
+        let subject = broker::v1::delete_traffic_split(
+            &self.topic_prefix,
+            &self.lattice,
+            &IdentifierKind::is_component_id(target)?,
+        );
+        debug!(%subject, %target, "Deleting traffic split");
+        match self
+            .request_timeout(subject, Vec::default(), self.timeout)
+            .await
+        {
+            Ok(msg) => json_deserialize(&msg.payload),
+            Err(e) => {
+                Err(format!("Did not receive delete traffic split acknowledgement: {e}").into())
+            }
+        }
+    }
+
+    /// Retrieves the traffic splits of the lattice
+    #[instrument(level = "debug", skip_all)]
+    pub async fn get_traffic_splits(&self) -> Result<CtlResponse<Vec<TrafficSplit>>> {
+        let subject = broker::v1::queries::traffic_splits(&self.topic_prefix, &self.lattice);
+        debug!(%subject, "Getting traffic splits");
+        match self.request_timeout(subject, vec![], self.timeout).await {
+            Ok(msg) => json_deserialize(&msg.payload),
+            Err(e) => Err(format!("Did not receive a response to get traffic splits: {e}").into()),
+        }
+    }
+
+    /// Start capturing a sample of the payloads of invocations sent over a link on the given host,
+    /// replacing any existing capture of the source component of `capture` on the host.
+    ///
+    /// Captured payloads are kept in memory by the host, and can be retrieved with
+    /// [`Client::get_captured_payloads`] until the capture is deleted.
+    ///
+    /// # Arguments
+    ///
+    /// * `host_id` - ID of the host running the source component
+    /// * `capture` - Link, sampling and redaction rules of the capture
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if it was unable to put the payload capture.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn put_payload_capture(
+        &self,
+        host_id: &str,
+        capture: PayloadCapture,
+    ) -> Result<CtlResponse<()>> {
+        let host_id = IdentifierKind::is_host_id(host_id)?;
+        IdentifierKind::is_component_id(&capture.source_id)?;
+
+        let subject = broker::v1::put_payload_capture(&self.topic_prefix, &self.lattice, &host_id);
+        debug!(%subject, source_id = capture.source_id, link_name = capture.link_name, "Putting payload capture");
+        let bytes = json_serialize(capture)?;
+        match self.request_timeout(subject, bytes, self.timeout).await {
+            Ok(msg) => json_deserialize(&msg.payload),
+            Err(e) => {
+                Err(format!("Did not receive put payload capture acknowledgement: {e}").into())
+            }
+        }
+    }
+
+    /// Stop capturing the payloads of invocations sent by `source_id` on the given host,
+    /// discarding any captured payloads.
+    ///
+    /// This is an idempotent operation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if it was unable to delete the payload capture.
+    #[instrument(level = "debug", skip_all)]
+    pub async fn delete_payload_capture(
+        &self,
+        host_id: &str,
+        source_id: &str,
+    ) -> Result<CtlResponse<()>> {
+        let subject = broker::v1::delete_payload_capture(
+            &self.topic_prefix,
+            &self.lattice,
+            &IdentifierKind::is_host_id(host_id)?,
+            &IdentifierKind::is_component_id(source_id)?,
+        );
+        debug!(%subject, %source_id, "Deleting payload capture");
+        match self
+            .request_timeout(subject, Vec::default(), self.timeout)
+            .await
+        {
+            Ok(msg) => json_deserialize(&msg.payload),
+            Err(e) => {
+                Err(format!("Did not receive delete payload capture acknowledgement: {e}").into())
+            }
+        }
+    }
+
+    /// Retrieves the payloads captured on the given host for invocations sent by `source_id`,
+    /// oldest first
+    #[instrument(level = "debug", skip_all)]
+    pub async fn get_captured_payloads(
+        &self,
+        host_id: &str,
+        source_id: &str,
+    ) -> Result<CtlResponse<Vec<CapturedPayload>>> {
+        let subject = broker::v1::queries::captured_payloads(
+            &self.topic_prefix,
+            &self.lattice,
+            &IdentifierKind::is_host_id(host_id)?,
+            &IdentifierKind::is_component_id(source_id)?,
+        );
+        debug!(%subject, %source_id, "Getting captured payloads");
+        match self.request_timeout(subject, vec![], self.timeout).await {
+            Ok(msg) => json_deserialize(&msg.payload),
+            Err(e) => {
+                Err(format!("Did not receive a response to get captured payloads: {e}").into())
+            }
+        }
+    }
+
     /// Puts a named config, replacing any data that is already present.
     ///
     /// Config names must be valid NATS subject strings and not contain any `.` or `>` characters.
@@ -526,6 +745,60 @@ impl Client {
         }
     }
 
+    /// Get the retained revisions of the named config item, oldest first.
+    ///
+    /// Every put or delete of a config item is stored as a new revision. Hosts retain a bounded
+    /// number of revisions for each config item, older revisions are discarded.
+    ///
+    /// # Arguments
+    ///
+    /// * `config_name` - The name of the config to fetch the history of
+    ///
+    #[instrument(level = "debug", skip_all)]
+    pub async fn get_config_history(
+        &self,
+        config_name: &str,
+    ) -> Result<CtlResponse<Vec<ConfigRevision>>> {
+        let subject =
+            broker::v1::queries::config_history(&self.topic_prefix, &self.lattice, config_name);
+        debug!(%subject, %config_name, "Getting config history");
+        match self
+            .request_timeout(subject, Vec::default(), self.timeout)
+            .await
+        {
+            Ok(msg) => json_deserialize(&msg.payload),
+            Err(e) => {
+                Err(format!("Did not receive a response to get config history request: {e}").into())
+            }
+        }
+    }
+
+    /// Restore the named config item to the contents it had at a previous revision.
+    ///
+    /// The restored contents are stored as a new revision, so a rollback can itself be rolled back.
+    ///
+    /// # Arguments
+    ///
+    /// * `config_name` - Name of the configuration that should be rolled back
+    /// * `revision` - The revision to restore, as returned by [`Client::get_config_history`]
+    ///
+    #[instrument(level = "debug", skip_all)]
+    pub async fn rollback_config(
+        &self,
+        config_name: &str,
+        revision: u64,
+    ) -> Result<CtlResponse<()>> {
+        let subject = broker::v1::rollback_config(&self.topic_prefix, &self.lattice, config_name);
+        debug!(%subject, %config_name, revision, "Rolling back config");
+        let bytes = json_serialize(ConfigRollbackRequest::new(revision))?;
+        match self.request_timeout(subject, bytes, self.timeout).await {
+            Ok(msg) => json_deserialize(&msg.payload),
+            Err(e) => {
+                Err(format!("Did not receive a response to rollback config request: {e}").into())
+            }
+        }
+    }
+
     /// Put a new (or update an existing) label on the given host.
     ///
     /// # Arguments
@@ -708,6 +981,49 @@ impl Client {
         }
     }
 
+    /// Issues a command to a host to hot-swap a running provider for a new image reference.
+    ///
+    /// The host starts the new provider version alongside the existing one, re-delivers all links
+    /// to it and only then drains and stops the old instance, so established links are kept.
+    ///
+    /// The host will acknowledge this request as soon as it verifies that the target provider is
+    /// running, **before** the new provider is downloaded. To verify that a provider has been
+    /// updated, monitor the control event stream for the `provider_updated` event
+    ///
+    /// # Arguments
+    ///
+    /// * `host_id` - ID of the host on which the provider should be updated
+    /// * `provider_id` - ID of the running provider
+    /// * `new_provider_ref` - New provider reference that should be used
+    /// * `annotations` - Annotations to place on the updated provider
+    ///
+    #[instrument(level = "debug", skip_all)]
+    pub async fn update_provider(
+        &self,
+        host_id: &str,
+        provider_id: &str,
+        new_provider_ref: &str,
+        annotations: Option<BTreeMap<String, String>>,
+    ) -> Result<CtlResponse<()>> {
+        let host_id = IdentifierKind::is_host_id(host_id)?;
+        let subject = broker::v1::commands::update_provider(
+            &self.topic_prefix,
+            &self.lattice,
+            host_id.as_str(),
+        );
+        debug!("update_provider:request {}", &subject);
+        let bytes = json_serialize(UpdateProviderCommand {
+            host_id,
+            provider_id: IdentifierKind::is_component_id(provider_id)?,
+            new_provider_ref: IdentifierKind::is_provider_ref(new_provider_ref)?,
+            annotations,
+        })?;
+        match self.request_timeout(subject, bytes, self.timeout).await {
+            Ok(msg) => Ok(json_deserialize(&msg.payload)?),
+            Err(e) => Err(format!("Did not receive update provider acknowledgement: {e}").into()),
+        }
+    }
+
     /// Issues a command to a specific host to perform a graceful termination.
     ///
     /// The target host will acknowledge receipt of the command before it attempts a shutdown.
@@ -821,6 +1137,68 @@ impl Client {
         });
         Ok(receiver)
     }
+
+    /// Subscribes to the lattice event stream, returning a stream of strongly typed
+    /// [`LatticeEvent`]s matching `filter`.
+    ///
+    /// Events which are not valid CloudEvents or whose data does not match their type are
+    /// logged and skipped. Events without a typed representation are returned as
+    /// [`LatticeEventKind::Other`](crate::LatticeEventKind::Other).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use futures::StreamExt;
+    /// use wasmcloud_control_interface::{Client, EventFilter, LatticeEventKind};
+    /// async {
+    ///   let nc = async_nats::connect("127.0.0.1:4222").await.unwrap();
+    ///   let client = Client::new(nc);
+    ///   let filter = EventFilter::new().event_type("component_scaled");
+    ///   let mut events = client.events(filter).await.unwrap();
+    ///   while let Some(evt) = events.next().await {
+    ///       if let LatticeEventKind::ComponentScaled(scaled) = evt.kind() {
+    ///           println!("{} scaled to {}", scaled.component_id(), scaled.max_instances());
+    ///       }
+    ///   }
+    /// };
+    /// ```
+    #[allow(clippy::missing_errors_doc)] // TODO: Document errors
+    pub async fn events(&self, filter: EventFilter) -> Result<BoxStream<'static, LatticeEvent>> {
+        let futs = filter.subjects(&self.lattice).into_iter().map(|subject| {
+            self.nc
+                .subscribe(subject)
+                .map_err(|err| Box::new(err) as Box<dyn std::error::Error + Send + Sync>)
+        });
+        let subs: Vec<Subscriber> = futures::future::join_all(futs)
+            .await
+            .into_iter()
+            .collect::<Result<_>>()?;
+        Ok(futures::stream::select_all(subs)
+            .filter_map(|msg| async move {
+                let Some(lattice) = subject_lattice(&msg.subject) else {
+                    error!(subject = %msg.subject, "event received on unexpected subject");
+                    return None;
+                };
+                let evt = match json_deserialize::<Event>(&msg.payload) {
+                    Ok(evt) => evt,
+                    Err(error) => {
+                        error!(%error, "object received on event stream was not a CloudEvent");
+                        return None;
+                    }
+                };
+                match LatticeEvent::from_cloud_event(lattice, &evt) {
+                    Ok(evt) => {
+                        trace!(?evt, "received event");
+                        Some(evt)
+                    }
+                    Err(error) => {
+                        error!(%error, "failed to parse lattice event");
+                        None
+                    }
+                }
+            })
+            .boxed())
+    }
 }
 
 /// Collect `T` values until timeout has elapsed
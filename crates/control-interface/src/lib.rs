@@ -26,13 +26,18 @@ pub mod client;
 pub use client::{Client, ClientBuilder};
 
 mod types;
+pub use types::capture::*;
+pub use types::claims::*;
 pub use types::component::*;
+pub use types::config::*;
 pub use types::ctl::*;
+pub use types::event::*;
 pub use types::host::*;
 pub use types::link::*;
 pub use types::provider::*;
 pub use types::registry::*;
 pub use types::rpc::*;
+pub use types::traffic::*;
 
 // NOTE(brooksmtownsend): These are included to avoid a major breaking change
 // in this crate by removing the public type aliases. They should be removed
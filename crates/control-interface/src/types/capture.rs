@@ -0,0 +1,434 @@
+//! Data types used when capturing sampled invocation payloads on a wasmCloud host for debugging
+
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+
+/// Default number of captured payloads kept by a host for a link
+pub const DEFAULT_PAYLOAD_CAPTURE_CAPACITY: usize = 100;
+
+/// Maximum number of captured payloads a host keeps for a link
+pub const MAX_PAYLOAD_CAPTURE_CAPACITY: usize = 10_000;
+
+/// A request to capture a sample of the payloads of invocations sent over a link.
+///
+/// Hosts running [`PayloadCapture::source_id`] capture one of every
+/// [`PayloadCapture::sample_rate`] invocations sent over the link named
+/// [`PayloadCapture::link_name`], keeping at most [`PayloadCapture::capacity`] payloads, with the
+/// oldest payloads being dropped first. Values of JSON payloads selected by any of the JSONPath
+/// expressions in [`PayloadCapture::json_redactions`] and parts of payloads matching any of the
+/// regular expressions in [`PayloadCapture::redactions`] are replaced before the payload is stored.
+/// Redactions apply to each `string` and `list<u8>` value of the wRPC-encoded parameters of an
+/// invocation, which remain decodable once redacted.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct PayloadCapture {
+    /// ID of the component sending the invocations to capture
+    pub(crate) source_id: String,
+    /// Name of the link the invocations are sent over
+    #[serde(default = "default_link_name")]
+    pub(crate) link_name: String,
+    /// Capture one of every `sample_rate` invocations
+    #[serde(default = "default_sample_rate")]
+    pub(crate) sample_rate: u32,
+    /// Number of captured payloads to keep
+    #[serde(default = "default_capacity")]
+    pub(crate) capacity: usize,
+    /// Regular expressions matching parts of payloads to redact
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) redactions: Vec<String>,
+    /// JSONPath expressions selecting values of JSON payloads to redact (ex. `$..password`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) json_redactions: Vec<String>,
+}
+
+fn default_link_name() -> String {
+    "default".into()
+}
+
+fn default_sample_rate() -> u32 {
+    1
+}
+
+fn default_capacity() -> usize {
+    DEFAULT_PAYLOAD_CAPTURE_CAPACITY
+}
+
+impl PayloadCapture {
+    /// Get the ID of the component sending the invocations to capture
+    #[must_use]
+    pub fn source_id(&self) -> &str {
+        &self.source_id
+    }
+
+    /// Get the name of the link the invocations are sent over
+    #[must_use]
+    pub fn link_name(&self) -> &str {
+        &self.link_name
+    }
+
+    /// Get the rate at which invocations are sampled, one of every `sample_rate` invocations is
+    /// captured
+    #[must_use]
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Get the number of captured payloads to keep
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Get the regular expressions matching parts of payloads to redact
+    #[must_use]
+    pub fn redactions(&self) -> &[String] {
+        &self.redactions
+    }
+
+    /// Get the JSONPath expressions selecting values of JSON payloads to redact
+    #[must_use]
+    pub fn json_redactions(&self) -> &[String] {
+        &self.json_redactions
+    }
+
+    #[must_use]
+    pub fn builder() -> PayloadCaptureBuilder {
+        PayloadCaptureBuilder::default()
+    }
+}
+
+#[derive(Default, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PayloadCaptureBuilder {
+    source_id: Option<String>,
+    link_name: Option<String>,
+    sample_rate: Option<u32>,
+    capacity: Option<usize>,
+    redactions: Vec<String>,
+    json_redactions: Vec<String>,
+}
+
+impl PayloadCaptureBuilder {
+    #[must_use]
+    pub fn source_id(mut self, v: &str) -> Self {
+        self.source_id = Some(v.into());
+        self
+    }
+
+    #[must_use]
+    pub fn link_name(mut self, v: &str) -> Self {
+        self.link_name = Some(v.into());
+        self
+    }
+
+    #[must_use]
+    pub fn sample_rate(mut self, v: u32) -> Self {
+        self.sample_rate = Some(v);
+        self
+    }
+
+    #[must_use]
+    pub fn capacity(mut self, v: usize) -> Self {
+        self.capacity = Some(v);
+        self
+    }
+
+    #[must_use]
+    pub fn redaction(mut self, v: &str) -> Self {
+        self.redactions.push(v.into());
+        self
+    }
+
+    #[must_use]
+    pub fn redactions(mut self, v: Vec<String>) -> Self {
+        self.redactions = v;
+        self
+    }
+
+    #[must_use]
+    pub fn json_redaction(mut self, v: &str) -> Self {
+        self.json_redactions.push(v.into());
+        self
+    }
+
+    #[must_use]
+    pub fn json_redactions(mut self, v: Vec<String>) -> Self {
+        self.json_redactions = v;
+        self
+    }
+
+    pub fn build(self) -> Result<PayloadCapture> {
+        let source_id = self
+            .source_id
+            .ok_or_else(|| "source id is required".to_string())?;
+        let sample_rate = self.sample_rate.unwrap_or_else(default_sample_rate);
+        if sample_rate == 0 {
+            return Err("sample rate must be at least 1".into());
+        }
+        let capacity = self.capacity.unwrap_or_else(default_capacity);
+        if capacity == 0 || capacity > MAX_PAYLOAD_CAPTURE_CAPACITY {
+            return Err(format!(
+                "capacity must be between 1 and {MAX_PAYLOAD_CAPTURE_CAPACITY}, got {capacity}"
+            )
+            .into());
+        }
+        Ok(PayloadCapture {
+            source_id,
+            link_name: self.link_name.unwrap_or_else(default_link_name),
+            sample_rate,
+            capacity,
+            redactions: self.redactions,
+            json_redactions: self.json_redactions,
+        })
+    }
+}
+
+/// The (redacted) payload of an invocation captured by a host
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct CapturedPayload {
+    /// Time the invocation was captured at, in milliseconds since the UNIX epoch
+    #[serde(default)]
+    pub(crate) captured_at: u64,
+    /// Name of the link the invocation was sent over
+    #[serde(default)]
+    pub(crate) link_name: String,
+    /// ID of the target the invocation was sent to
+    #[serde(default)]
+    pub(crate) target: String,
+    /// WIT instance the invocation was sent to (ex. `wasi:keyvalue/store@0.2.0-draft`)
+    #[serde(default)]
+    pub(crate) instance: String,
+    /// Name of the invoked function
+    #[serde(default)]
+    pub(crate) function: String,
+    /// Size of the payload before redaction, in bytes
+    #[serde(default)]
+    pub(crate) size: usize,
+    /// The payload with redactions applied, encoded as base64 when serialized
+    #[serde(default, with = "base64_bytes")]
+    pub(crate) payload: Vec<u8>,
+    /// Whether any part of the payload was redacted
+    #[serde(default)]
+    pub(crate) redacted: bool,
+}
+
+impl CapturedPayload {
+    /// Get the time the invocation was captured at, in milliseconds since the UNIX epoch
+    #[must_use]
+    pub fn captured_at(&self) -> u64 {
+        self.captured_at
+    }
+
+    /// Get the name of the link the invocation was sent over
+    #[must_use]
+    pub fn link_name(&self) -> &str {
+        &self.link_name
+    }
+
+    /// Get the ID of the target the invocation was sent to
+    #[must_use]
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// Get the WIT instance the invocation was sent to
+    #[must_use]
+    pub fn instance(&self) -> &str {
+        &self.instance
+    }
+
+    /// Get the name of the invoked function
+    #[must_use]
+    pub fn function(&self) -> &str {
+        &self.function
+    }
+
+    /// Get the size of the payload before redaction, in bytes
+    #[must_use]
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Get the payload with redactions applied
+    #[must_use]
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    /// Get whether any part of the payload was redacted
+    #[must_use]
+    pub fn redacted(&self) -> bool {
+        self.redacted
+    }
+
+    #[must_use]
+    pub fn builder() -> CapturedPayloadBuilder {
+        CapturedPayloadBuilder::default()
+    }
+}
+
+/// Builds [`CapturedPayload`]s
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct CapturedPayloadBuilder {
+    captured_at: Option<u64>,
+    link_name: Option<String>,
+    target: Option<String>,
+    instance: Option<String>,
+    function: Option<String>,
+    size: Option<usize>,
+    payload: Option<Vec<u8>>,
+    redacted: bool,
+}
+
+impl CapturedPayloadBuilder {
+    #[must_use]
+    pub fn captured_at(mut self, v: u64) -> Self {
+        self.captured_at = Some(v);
+        self
+    }
+
+    #[must_use]
+    pub fn link_name(mut self, v: &str) -> Self {
+        self.link_name = Some(v.into());
+        self
+    }
+
+    #[must_use]
+    pub fn target(mut self, v: &str) -> Self {
+        self.target = Some(v.into());
+        self
+    }
+
+    #[must_use]
+    pub fn instance(mut self, v: &str) -> Self {
+        self.instance = Some(v.into());
+        self
+    }
+
+    #[must_use]
+    pub fn function(mut self, v: &str) -> Self {
+        self.function = Some(v.into());
+        self
+    }
+
+    #[must_use]
+    pub fn size(mut self, v: usize) -> Self {
+        self.size = Some(v);
+        self
+    }
+
+    #[must_use]
+    pub fn payload(mut self, v: Vec<u8>) -> Self {
+        self.payload = Some(v);
+        self
+    }
+
+    #[must_use]
+    pub fn redacted(mut self, v: bool) -> Self {
+        self.redacted = v;
+        self
+    }
+
+    /// Build a [`CapturedPayload`]
+    pub fn build(self) -> Result<CapturedPayload> {
+        let payload = self.payload.unwrap_or_default();
+        Ok(CapturedPayload {
+            captured_at: self.captured_at.unwrap_or_default(),
+            link_name: self.link_name.unwrap_or_else(default_link_name),
+            target: self
+                .target
+                .ok_or_else(|| "target is required".to_string())?,
+            instance: self
+                .instance
+                .ok_or_else(|| "instance is required".to_string())?,
+            function: self
+                .function
+                .ok_or_else(|| "function is required".to_string())?,
+            size: self.size.unwrap_or(payload.len()),
+            payload,
+            redacted: self.redacted,
+        })
+    }
+}
+
+/// (De)serializes bytes as a base64 string
+mod base64_bytes {
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine as _;
+    use serde::{Deserialize as _, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        STANDARD.decode(s).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CapturedPayload, PayloadCapture, DEFAULT_PAYLOAD_CAPTURE_CAPACITY};
+
+    #[test]
+    fn payload_capture_builder() {
+        assert_eq!(
+            PayloadCapture {
+                source_id: "echo".into(),
+                link_name: "default".into(),
+                sample_rate: 1,
+                capacity: DEFAULT_PAYLOAD_CAPTURE_CAPACITY,
+                redactions: vec!["password=\\S+".into()],
+                json_redactions: vec!["$..password".into()],
+            },
+            PayloadCapture::builder()
+                .source_id("echo")
+                .redaction("password=\\S+")
+                .json_redaction("$..password")
+                .build()
+                .unwrap()
+        );
+        assert!(PayloadCapture::builder().build().is_err());
+        assert!(PayloadCapture::builder()
+            .source_id("echo")
+            .sample_rate(0)
+            .build()
+            .is_err());
+        assert!(PayloadCapture::builder()
+            .source_id("echo")
+            .capacity(0)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn payload_capture_defaults() {
+        let capture: PayloadCapture =
+            serde_json::from_str(r#"{"source_id":"echo"}"#).expect("failed to deserialize");
+        assert_eq!(capture.link_name(), "default");
+        assert_eq!(capture.sample_rate(), 1);
+        assert_eq!(capture.capacity(), DEFAULT_PAYLOAD_CAPTURE_CAPACITY);
+        assert!(capture.redactions().is_empty());
+        assert!(capture.json_redactions().is_empty());
+    }
+
+    #[test]
+    fn captured_payload_base64() {
+        let payload = CapturedPayload::builder()
+            .target("kv")
+            .instance("wasi:keyvalue/store")
+            .function("get")
+            .payload(b"\xffkey".to_vec())
+            .build()
+            .unwrap();
+        assert_eq!(payload.size(), 4);
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(json["payload"], "/2tleQ==");
+        let decoded: CapturedPayload = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded.payload(), b"\xffkey");
+    }
+}
@@ -0,0 +1,177 @@
+//! Data types used when exporting and importing the claims of a wasmCloud lattice
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+
+/// The claims cache and issuer trust set of a lattice, as exported in a [`ClaimsBundle`]
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct ClaimsBundleContents {
+    /// The lattice the claims were exported from
+    #[serde(default)]
+    pub(crate) lattice: String,
+    /// The time at which the claims were exported, in seconds since the Unix epoch
+    #[serde(default)]
+    pub(crate) exported_at: u64,
+    /// The sorted, deduplicated issuers trusted by the lattice, including the issuers of all claims
+    /// in the bundle
+    #[serde(default)]
+    pub(crate) issuers: Vec<String>,
+    /// The claims of all components and providers, as returned by
+    /// [`Client::get_claims`](crate::Client::get_claims)
+    #[serde(default)]
+    pub(crate) claims: Vec<HashMap<String, String>>,
+}
+
+impl ClaimsBundleContents {
+    /// Create [`ClaimsBundleContents`] from the `claims` of a lattice exported at `exported_at`,
+    /// the issuers are collected from the claims
+    #[must_use]
+    pub fn new(
+        lattice: impl Into<String>,
+        exported_at: u64,
+        claims: Vec<HashMap<String, String>>,
+    ) -> Self {
+        let mut issuers: Vec<String> = claims
+            .iter()
+            .filter_map(|claims| claims.get("issuer").cloned())
+            .collect();
+        issuers.sort();
+        issuers.dedup();
+        Self {
+            lattice: lattice.into(),
+            exported_at,
+            issuers,
+            claims,
+        }
+    }
+
+    /// Add `issuers` trusted by the lattice the claims are exported from, which may not have
+    /// issued any of the claims
+    #[must_use]
+    pub fn with_issuers(mut self, issuers: impl IntoIterator<Item = String>) -> Self {
+        self.issuers.extend(issuers);
+        self.issuers.sort();
+        self.issuers.dedup();
+        self
+    }
+
+    /// Get the lattice the claims were exported from
+    #[must_use]
+    pub fn lattice(&self) -> &str {
+        &self.lattice
+    }
+
+    /// Get the time at which the claims were exported, in seconds since the Unix epoch
+    #[must_use]
+    pub fn exported_at(&self) -> u64 {
+        self.exported_at
+    }
+
+    /// Get the sorted, deduplicated issuers trusted by the lattice, including the issuers of all
+    /// claims in the bundle
+    #[must_use]
+    pub fn issuers(&self) -> &[String] {
+        &self.issuers
+    }
+
+    /// Get the claims of all components and providers
+    #[must_use]
+    pub fn claims(&self) -> &[HashMap<String, String>] {
+        &self.claims
+    }
+}
+
+/// Claims exported from a lattice, signed by the host which exported them so that they can be
+/// verified when imported into another (e.g. air-gapped) lattice
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct ClaimsBundle {
+    /// Public key of the host that signed the bundle
+    pub(crate) signer: String,
+    /// JSON-encoded [`ClaimsBundleContents`], exactly as signed
+    pub(crate) payload: String,
+    /// Base64-encoded signature of `payload`
+    pub(crate) signature: String,
+}
+
+impl ClaimsBundle {
+    /// Create a [`ClaimsBundle`] from a JSON-encoded [`ClaimsBundleContents`] payload and the
+    /// signature of the payload by `signer`
+    #[must_use]
+    pub fn new(
+        signer: impl Into<String>,
+        payload: impl Into<String>,
+        signature: impl Into<String>,
+    ) -> Self {
+        Self {
+            signer: signer.into(),
+            payload: payload.into(),
+            signature: signature.into(),
+        }
+    }
+
+    /// Get the public key of the host that signed the bundle
+    #[must_use]
+    pub fn signer(&self) -> &str {
+        &self.signer
+    }
+
+    /// Get the JSON-encoded [`ClaimsBundleContents`], exactly as signed
+    #[must_use]
+    pub fn payload(&self) -> &str {
+        &self.payload
+    }
+
+    /// Get the base64-encoded signature of the payload
+    #[must_use]
+    pub fn signature(&self) -> &str {
+        &self.signature
+    }
+
+    /// Decode the contents of the bundle.
+    ///
+    /// Note that this does not verify the signature of the bundle, which is done by the host the
+    /// bundle is imported into.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the payload is not valid JSON-encoded [`ClaimsBundleContents`]
+    pub fn contents(&self) -> Result<ClaimsBundleContents> {
+        serde_json::from_str(&self.payload)
+            .map_err(|e| format!("invalid claims bundle payload: {e}").into())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::{ClaimsBundle, ClaimsBundleContents};
+
+    #[test]
+    fn test_claims_bundle_contents() {
+        let claims = |subject: &str, issuer: &str| {
+            HashMap::from([
+                ("subject".to_string(), subject.to_string()),
+                ("issuer".to_string(), issuer.to_string()),
+            ])
+        };
+        let contents = ClaimsBundleContents::new(
+            "default",
+            1_700_000_000,
+            vec![claims("MA", "AB"), claims("VB", "AA"), claims("MC", "AB")],
+        );
+        assert_eq!(contents.issuers(), ["AA", "AB"]);
+        let contents = contents.with_issuers(["AC".to_string(), "AA".to_string()]);
+        assert_eq!(contents.issuers(), ["AA", "AB", "AC"]);
+
+        let payload = serde_json::to_string(&contents).unwrap();
+        let bundle = ClaimsBundle::new("NHOST", payload, "sig");
+        assert_eq!(bundle.contents().unwrap(), contents);
+        assert!(ClaimsBundle::new("NHOST", "{", "sig").contents().is_err());
+    }
+}
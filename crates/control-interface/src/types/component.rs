@@ -34,6 +34,15 @@ pub struct ComponentDescription {
     /// The maximum number of concurrent requests this instance can handle
     #[serde(default)]
     pub(crate) max_instances: u32,
+
+    /// The quarantine of this component, if it is quarantined after repeatedly failing to
+    /// handle invocations
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) quarantine: Option<ComponentQuarantine>,
+
+    /// Statistics of the pool of warm instances of this component, if instance pooling is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) instance_pool: Option<ComponentInstancePool>,
 }
 
 #[derive(Default, Clone, PartialEq, Eq)]
@@ -45,6 +54,8 @@ pub struct ComponentDescriptionBuilder {
     annotations: Option<BTreeMap<String, String>>,
     revision: Option<i32>,
     max_instances: Option<u32>,
+    quarantine: Option<ComponentQuarantine>,
+    instance_pool: Option<ComponentInstancePool>,
 }
 
 impl ComponentDescriptionBuilder {
@@ -84,6 +95,18 @@ impl ComponentDescriptionBuilder {
         self
     }
 
+    #[must_use]
+    pub fn quarantine(mut self, v: ComponentQuarantine) -> Self {
+        self.quarantine = Some(v);
+        self
+    }
+
+    #[must_use]
+    pub fn instance_pool(mut self, v: ComponentInstancePool) -> Self {
+        self.instance_pool = Some(v);
+        self
+    }
+
     pub fn build(self) -> Result<ComponentDescription> {
         Ok(ComponentDescription {
             image_ref: self
@@ -94,6 +117,8 @@ impl ComponentDescriptionBuilder {
             revision: self.revision.unwrap_or_default(),
             max_instances: self.max_instances.unwrap_or_default(),
             annotations: self.annotations,
+            quarantine: self.quarantine,
+            instance_pool: self.instance_pool,
         })
     }
 }
@@ -129,12 +154,207 @@ impl ComponentDescription {
         self.max_instances
     }
 
+    /// Get the quarantine of the component, if it is quarantined
+    pub fn quarantine(&self) -> Option<&ComponentQuarantine> {
+        self.quarantine.as_ref()
+    }
+
+    /// Get the statistics of the instance pool of the component, if instance pooling is enabled
+    pub fn instance_pool(&self) -> Option<&ComponentInstancePool> {
+        self.instance_pool.as_ref()
+    }
+
     #[must_use]
     pub fn builder() -> ComponentDescriptionBuilder {
         ComponentDescriptionBuilder::default()
     }
 }
 
+/// Quarantine of a component, which rejects invocations after the component repeatedly failed
+/// to handle them
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct ComponentQuarantine {
+    /// The error of the last failed invocation, e.g. the trap reason
+    #[serde(default)]
+    pub(crate) reason: String,
+
+    /// The number of consecutive failed invocations that caused the quarantine
+    #[serde(default)]
+    pub(crate) failures: u32,
+
+    /// The time the quarantine ends, in seconds since Unix epoch
+    #[serde(default)]
+    pub(crate) until: u64,
+}
+
+impl ComponentQuarantine {
+    /// Get the error of the last failed invocation
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+
+    /// Get the number of consecutive failed invocations that caused the quarantine
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// Get the time the quarantine ends, in seconds since Unix epoch
+    pub fn until(&self) -> u64 {
+        self.until
+    }
+
+    #[must_use]
+    pub fn builder() -> ComponentQuarantineBuilder {
+        ComponentQuarantineBuilder::default()
+    }
+}
+
+#[derive(Default, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ComponentQuarantineBuilder {
+    reason: Option<String>,
+    failures: Option<u32>,
+    until: Option<u64>,
+}
+
+impl ComponentQuarantineBuilder {
+    #[must_use]
+    pub fn reason(mut self, v: String) -> Self {
+        self.reason = Some(v);
+        self
+    }
+
+    #[must_use]
+    pub fn failures(mut self, v: u32) -> Self {
+        self.failures = Some(v);
+        self
+    }
+
+    #[must_use]
+    pub fn until(mut self, v: u64) -> Self {
+        self.until = Some(v);
+        self
+    }
+
+    pub fn build(self) -> Result<ComponentQuarantine> {
+        Ok(ComponentQuarantine {
+            reason: self.reason.unwrap_or_default(),
+            failures: self.failures.unwrap_or_default(),
+            until: self.until.ok_or_else(|| "until is required".to_string())?,
+        })
+    }
+}
+
+/// Statistics of the pool of warm instances of a component
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct ComponentInstancePool {
+    /// The number of idle instances ready to handle an invocation
+    #[serde(default)]
+    pub(crate) warm: u32,
+
+    /// The number of instances currently handling an invocation
+    #[serde(default)]
+    pub(crate) active: u32,
+
+    /// The total number of instances created by the pool
+    #[serde(default)]
+    pub(crate) created: u64,
+
+    /// The total number of invocations handled by a reused instance
+    #[serde(default)]
+    pub(crate) reused: u64,
+
+    /// The total number of idle instances dropped after exceeding the idle TTL
+    #[serde(default)]
+    pub(crate) evicted: u64,
+}
+
+impl ComponentInstancePool {
+    /// Get the number of idle instances ready to handle an invocation
+    pub fn warm(&self) -> u32 {
+        self.warm
+    }
+
+    /// Get the number of instances currently handling an invocation
+    pub fn active(&self) -> u32 {
+        self.active
+    }
+
+    /// Get the total number of instances created by the pool
+    pub fn created(&self) -> u64 {
+        self.created
+    }
+
+    /// Get the total number of invocations handled by a reused instance
+    pub fn reused(&self) -> u64 {
+        self.reused
+    }
+
+    /// Get the total number of idle instances dropped after exceeding the idle TTL
+    pub fn evicted(&self) -> u64 {
+        self.evicted
+    }
+
+    #[must_use]
+    pub fn builder() -> ComponentInstancePoolBuilder {
+        ComponentInstancePoolBuilder::default()
+    }
+}
+
+#[derive(Default, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ComponentInstancePoolBuilder {
+    warm: Option<u32>,
+    active: Option<u32>,
+    created: Option<u64>,
+    reused: Option<u64>,
+    evicted: Option<u64>,
+}
+
+impl ComponentInstancePoolBuilder {
+    #[must_use]
+    pub fn warm(mut self, v: u32) -> Self {
+        self.warm = Some(v);
+        self
+    }
+
+    #[must_use]
+    pub fn active(mut self, v: u32) -> Self {
+        self.active = Some(v);
+        self
+    }
+
+    #[must_use]
+    pub fn created(mut self, v: u64) -> Self {
+        self.created = Some(v);
+        self
+    }
+
+    #[must_use]
+    pub fn reused(mut self, v: u64) -> Self {
+        self.reused = Some(v);
+        self
+    }
+
+    #[must_use]
+    pub fn evicted(mut self, v: u64) -> Self {
+        self.evicted = Some(v);
+        self
+    }
+
+    pub fn build(self) -> Result<ComponentInstancePool> {
+        Ok(ComponentInstancePool {
+            warm: self.warm.unwrap_or_default(),
+            active: self.active.unwrap_or_default(),
+            created: self.created.unwrap_or_default(),
+            reused: self.reused.unwrap_or_default(),
+            evicted: self.evicted.unwrap_or_default(),
+        })
+    }
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 #[non_exhaustive]
 pub struct ComponentInstance {
@@ -264,6 +484,7 @@ mod tests {
                 annotations: Some(BTreeMap::from([("a".into(), "b".into())])),
                 revision: 0,
                 max_instances: 1,
+                quarantine: None,
             },
             ComponentDescription::builder()
                 .id("id".into())
@@ -0,0 +1,155 @@
+//! Data types used when managing named configuration on a wasmCloud lattice
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+
+/// A revision of a named configuration, as retained in the lattice configuration store
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct ConfigRevision {
+    /// The revision number of this change, increasing across all configuration in the lattice
+    #[serde(default)]
+    pub(crate) revision: u64,
+
+    /// The time at which this revision was stored, in RFC 3339 format
+    #[serde(default)]
+    pub(crate) created: String,
+
+    /// The contents of the configuration at this revision, or `None` if the configuration was
+    /// deleted in this revision
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) config: Option<HashMap<String, String>>,
+}
+
+impl ConfigRevision {
+    /// Get the revision number
+    #[must_use]
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Get the time at which this revision was stored, in RFC 3339 format
+    #[must_use]
+    pub fn created(&self) -> &str {
+        &self.created
+    }
+
+    /// Get the contents of the configuration at this revision, or `None` if the configuration
+    /// was deleted
+    #[must_use]
+    pub fn config(&self) -> Option<&HashMap<String, String>> {
+        self.config.as_ref()
+    }
+
+    /// Whether the configuration was deleted in this revision
+    #[must_use]
+    pub fn is_deleted(&self) -> bool {
+        self.config.is_none()
+    }
+
+    #[must_use]
+    pub fn builder() -> ConfigRevisionBuilder {
+        ConfigRevisionBuilder::default()
+    }
+}
+
+#[derive(Default, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ConfigRevisionBuilder {
+    revision: Option<u64>,
+    created: Option<String>,
+    config: Option<HashMap<String, String>>,
+}
+
+impl ConfigRevisionBuilder {
+    #[must_use]
+    pub fn revision(mut self, v: u64) -> Self {
+        self.revision = Some(v);
+        self
+    }
+
+    #[must_use]
+    pub fn created(mut self, v: String) -> Self {
+        self.created = Some(v);
+        self
+    }
+
+    #[must_use]
+    pub fn config(mut self, v: HashMap<String, String>) -> Self {
+        self.config = Some(v);
+        self
+    }
+
+    pub fn build(self) -> Result<ConfigRevision> {
+        Ok(ConfigRevision {
+            revision: self
+                .revision
+                .ok_or_else(|| "revision is required".to_string())?,
+            created: self.created.unwrap_or_default(),
+            config: self.config,
+        })
+    }
+}
+
+/// A request to restore a named configuration to the contents it had at a previous revision
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct ConfigRollbackRequest {
+    /// The revision to restore
+    #[serde(default)]
+    pub(crate) revision: u64,
+}
+
+impl ConfigRollbackRequest {
+    /// Create a [`ConfigRollbackRequest`] restoring `revision`
+    #[must_use]
+    pub fn new(revision: u64) -> Self {
+        Self { revision }
+    }
+
+    /// Get the revision to restore
+    #[must_use]
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::ConfigRevision;
+
+    #[test]
+    fn config_revision_builder() {
+        assert_eq!(
+            ConfigRevision {
+                revision: 3,
+                created: "2024-01-01T00:00:00Z".into(),
+                config: Some(HashMap::from([("a".into(), "b".into())])),
+            },
+            ConfigRevision::builder()
+                .revision(3)
+                .created("2024-01-01T00:00:00Z".into())
+                .config(HashMap::from([("a".into(), "b".into())]))
+                .build()
+                .unwrap()
+        );
+        assert!(ConfigRevision::builder().build().is_err());
+    }
+
+    #[test]
+    fn deleted_revision_roundtrip() {
+        let revision = ConfigRevision::builder().revision(4).build().unwrap();
+        assert!(revision.is_deleted());
+        let json = serde_json::to_string(&revision).unwrap();
+        assert!(!json.contains("config"));
+        assert_eq!(
+            serde_json::from_str::<ConfigRevision>(&json).unwrap(),
+            revision
+        );
+    }
+}
@@ -598,13 +598,117 @@ impl UpdateComponentCommandBuilder {
     }
 }
 
+/// A command instructing a specific host to hot-swap the indicated provider
+/// for a new version. The new provider is started and receives all existing
+/// links before the old instance is drained and stopped
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct UpdateProviderCommand {
+    /// Unique identifier of the provider to update
+    #[serde(default)]
+    pub(crate) provider_id: String,
+    /// Optional set of annotations to place on the updated provider. When not
+    /// supplied, the annotations of the running provider are kept
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) annotations: Option<BTreeMap<String, String>>,
+    /// The host ID of the host to perform the update
+    #[serde(default)]
+    pub(crate) host_id: String,
+    /// The new image reference of the upgraded version of this provider
+    #[serde(default)]
+    pub(crate) new_provider_ref: String,
+}
+
+impl UpdateProviderCommand {
+    #[must_use]
+    pub fn host_id(&self) -> &str {
+        &self.host_id
+    }
+
+    #[must_use]
+    pub fn provider_id(&self) -> &str {
+        &self.provider_id
+    }
+
+    #[must_use]
+    pub fn new_provider_ref(&self) -> &str {
+        &self.new_provider_ref
+    }
+
+    #[must_use]
+    pub fn annotations(&self) -> Option<&BTreeMap<String, String>> {
+        self.annotations.as_ref()
+    }
+
+    #[must_use]
+    pub fn builder() -> UpdateProviderCommandBuilder {
+        UpdateProviderCommandBuilder::default()
+    }
+}
+
+/// Builder for [`UpdateProviderCommand`]s
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct UpdateProviderCommandBuilder {
+    host_id: Option<String>,
+    provider_id: Option<String>,
+    new_provider_ref: Option<String>,
+    annotations: Option<BTreeMap<String, String>>,
+}
+
+impl UpdateProviderCommandBuilder {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn host_id(mut self, v: &str) -> Self {
+        self.host_id = Some(v.into());
+        self
+    }
+
+    #[must_use]
+    pub fn provider_id(mut self, v: &str) -> Self {
+        self.provider_id = Some(v.into());
+        self
+    }
+
+    #[must_use]
+    pub fn new_provider_ref(mut self, v: &str) -> Self {
+        self.new_provider_ref = Some(v.into());
+        self
+    }
+
+    #[must_use]
+    pub fn annotations(mut self, v: impl Into<BTreeMap<String, String>>) -> Self {
+        self.annotations = Some(v.into());
+        self
+    }
+
+    pub fn build(self) -> Result<UpdateProviderCommand> {
+        Ok(UpdateProviderCommand {
+            host_id: self
+                .host_id
+                .ok_or_else(|| "host id is required for updating providers".to_string())?,
+            provider_id: self
+                .provider_id
+                .ok_or_else(|| "provider id is required for updating providers".to_string())?,
+            new_provider_ref: self
+                .new_provider_ref
+                .ok_or_else(|| "new provider ref is required for updating providers".to_string())?,
+            annotations: self.annotations,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::BTreeMap;
 
     use super::{
         ScaleComponentCommand, StartProviderCommand, StopHostCommand, StopProviderCommand,
-        UpdateComponentCommand,
+        UpdateComponentCommand, UpdateProviderCommand,
     };
 
     #[test]
@@ -701,4 +805,23 @@ mod tests {
                 .unwrap()
         )
     }
+
+    #[test]
+    fn update_provider_command_builder() {
+        assert_eq!(
+            UpdateProviderCommand {
+                host_id: "host_id".into(),
+                provider_id: "provider_id".into(),
+                new_provider_ref: "new_provider_ref".into(),
+                annotations: Some(BTreeMap::from([("a".into(), "b".into())])),
+            },
+            UpdateProviderCommand::builder()
+                .host_id("host_id")
+                .provider_id("provider_id")
+                .new_provider_ref("new_provider_ref")
+                .annotations(BTreeMap::from([("a".into(), "b".into())]))
+                .build()
+                .unwrap()
+        )
+    }
 }
@@ -0,0 +1,731 @@
+//! Strongly typed events published by hosts on the lattice event stream (`wasmbus.evt.>`)
+
+use std::collections::{BTreeMap, HashMap};
+
+use cloudevents::event::{AttributesReader, Data, Event};
+use serde::{Deserialize, Serialize};
+
+use crate::types::host::HostInventory;
+use crate::types::link::Link;
+use crate::Result;
+
+/// Prefix of the CloudEvent type of all lattice events
+const EVENT_TYPE_PREFIX: &str = "com.wasmcloud.lattice.";
+
+/// Prefix of the NATS subjects lattice events are published on
+pub(crate) const EVENT_SUBJECT_PREFIX: &str = "wasmbus.evt";
+
+/// An event published by a host on the lattice event stream
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct LatticeEvent {
+    /// Unique identifier of the event
+    pub(crate) id: String,
+    /// Source of the event, which is the ID of the host that published it
+    pub(crate) source: String,
+    /// Lattice the event was published in
+    pub(crate) lattice: String,
+    /// Time at which the event was published, in RFC 3339 format
+    pub(crate) time: Option<String>,
+    /// The typed contents of the event
+    pub(crate) kind: LatticeEventKind,
+}
+
+impl LatticeEvent {
+    /// Parses a lattice event from a CloudEvent received in `lattice`
+    pub fn from_cloud_event(lattice: impl Into<String>, event: &Event) -> Result<Self> {
+        let ty = event.ty();
+        let event_type = ty.strip_prefix(EVENT_TYPE_PREFIX).unwrap_or(ty);
+        let data = match event.data() {
+            Some(Data::Json(data)) => data.clone(),
+            Some(Data::String(data)) => serde_json::from_str(data)
+                .map_err(|e| format!("invalid `{event_type}` event data: {e}"))?,
+            Some(Data::Binary(data)) => serde_json::from_slice(data)
+                .map_err(|e| format!("invalid `{event_type}` event data: {e}"))?,
+            None => serde_json::Value::Null,
+        };
+        let kind = LatticeEventKind::from_data(event_type, data)
+            .map_err(|e| format!("invalid `{event_type}` event data: {e}"))?;
+        Ok(Self {
+            id: event.id().to_string(),
+            source: event.source().to_string(),
+            lattice: lattice.into(),
+            time: event.time().map(|time| time.to_rfc3339()),
+            kind,
+        })
+    }
+
+    /// Get the unique identifier of the event
+    #[must_use]
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Get the source of the event, which is the ID of the host that published it
+    #[must_use]
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Get the lattice the event was published in
+    #[must_use]
+    pub fn lattice(&self) -> &str {
+        &self.lattice
+    }
+
+    /// Get the time at which the event was published, in RFC 3339 format
+    #[must_use]
+    pub fn time(&self) -> Option<&str> {
+        self.time.as_deref()
+    }
+
+    /// Get the typed contents of the event
+    #[must_use]
+    pub fn kind(&self) -> &LatticeEventKind {
+        &self.kind
+    }
+
+    /// Get the type of the event, e.g. `component_scaled`
+    #[must_use]
+    pub fn event_type(&self) -> &str {
+        self.kind.event_type()
+    }
+
+    /// Consume the event, returning its typed contents
+    #[must_use]
+    pub fn into_kind(self) -> LatticeEventKind {
+        self.kind
+    }
+}
+
+/// The typed contents of a [`LatticeEvent`]
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum LatticeEventKind {
+    /// A component was scaled, `component_scaled`
+    ComponentScaled(ComponentScaled),
+    /// Scaling a component failed, `component_scale_failed`
+    ComponentScaleFailed(ComponentScaled),
+    /// A provider was started, `provider_started`
+    ProviderStarted(ProviderStarted),
+    /// Starting a provider failed, `provider_start_failed`
+    ProviderStartFailed(ProviderStartFailed),
+    /// A provider was stopped, `provider_stopped`
+    ProviderStopped(ProviderStopped),
+    /// A link was put, `linkdef_set`
+    LinkPut(Link),
+    /// Putting a link failed, `linkdef_set_failed`
+    LinkPutFailed(LinkPutFailed),
+    /// A link was deleted, `linkdef_deleted`
+    LinkDeleted(LinkDeleted),
+    /// A named configuration was set, `config_set`
+    ConfigSet(ConfigChanged),
+    /// A named configuration was deleted, `config_deleted`
+    ConfigDeleted(ConfigChanged),
+    /// A host started, `host_started`
+    HostStarted(HostStarted),
+    /// A host stopped, `host_stopped`
+    HostStopped(HostStopped),
+    /// A host published its inventory, `host_heartbeat`
+    HostHeartbeat(HostInventory),
+    /// The labels of a host changed, `labels_changed`
+    LabelsChanged(LabelsChanged),
+    /// Any other event, which does not have a typed representation (yet)
+    Other {
+        /// Type of the event
+        event_type: String,
+        /// Raw JSON data of the event
+        data: serde_json::Value,
+    },
+}
+
+impl LatticeEventKind {
+    /// Parses the JSON `data` of an event of type `event_type`, e.g. `component_scaled`
+    pub fn from_data(
+        event_type: &str,
+        data: serde_json::Value,
+    ) -> core::result::Result<Self, serde_json::Error> {
+        Ok(match event_type {
+            "component_scaled" => Self::ComponentScaled(serde_json::from_value(data)?),
+            "component_scale_failed" => Self::ComponentScaleFailed(serde_json::from_value(data)?),
+            "provider_started" => Self::ProviderStarted(serde_json::from_value(data)?),
+            "provider_start_failed" => Self::ProviderStartFailed(serde_json::from_value(data)?),
+            "provider_stopped" => Self::ProviderStopped(serde_json::from_value(data)?),
+            "linkdef_set" => Self::LinkPut(serde_json::from_value(data)?),
+            "linkdef_set_failed" => Self::LinkPutFailed(serde_json::from_value(data)?),
+            "linkdef_deleted" => Self::LinkDeleted(serde_json::from_value(data)?),
+            "config_set" => Self::ConfigSet(serde_json::from_value(data)?),
+            "config_deleted" => Self::ConfigDeleted(serde_json::from_value(data)?),
+            "host_started" => Self::HostStarted(serde_json::from_value(data)?),
+            "host_stopped" => Self::HostStopped(serde_json::from_value(data)?),
+            "host_heartbeat" => Self::HostHeartbeat(serde_json::from_value(data)?),
+            "labels_changed" => Self::LabelsChanged(serde_json::from_value(data)?),
+            _ => Self::Other {
+                event_type: event_type.to_string(),
+                data,
+            },
+        })
+    }
+
+    /// Get the type of the event, e.g. `component_scaled`
+    #[must_use]
+    pub fn event_type(&self) -> &str {
+        match self {
+            Self::ComponentScaled(_) => "component_scaled",
+            Self::ComponentScaleFailed(_) => "component_scale_failed",
+            Self::ProviderStarted(_) => "provider_started",
+            Self::ProviderStartFailed(_) => "provider_start_failed",
+            Self::ProviderStopped(_) => "provider_stopped",
+            Self::LinkPut(_) => "linkdef_set",
+            Self::LinkPutFailed(_) => "linkdef_set_failed",
+            Self::LinkDeleted(_) => "linkdef_deleted",
+            Self::ConfigSet(_) => "config_set",
+            Self::ConfigDeleted(_) => "config_deleted",
+            Self::HostStarted(_) => "host_started",
+            Self::HostStopped(_) => "host_stopped",
+            Self::HostHeartbeat(_) => "host_heartbeat",
+            Self::LabelsChanged(_) => "labels_changed",
+            Self::Other { event_type, .. } => event_type,
+        }
+    }
+}
+
+/// Data of the `component_scaled` and `component_scale_failed` events
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct ComponentScaled {
+    /// ID of the host the component was scaled on
+    pub(crate) host_id: String,
+    /// ID of the component
+    pub(crate) component_id: String,
+    /// Image reference of the component
+    pub(crate) image_ref: String,
+    /// Number of instances the component was scaled to, 0 if it was stopped
+    pub(crate) max_instances: u32,
+    /// Annotations of the component
+    #[serde(default)]
+    pub(crate) annotations: BTreeMap<String, String>,
+    /// Error encountered while scaling, only set if scaling failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+}
+
+impl ComponentScaled {
+    /// Get the ID of the host the component was scaled on
+    #[must_use]
+    pub fn host_id(&self) -> &str {
+        &self.host_id
+    }
+
+    /// Get the ID of the component
+    #[must_use]
+    pub fn component_id(&self) -> &str {
+        &self.component_id
+    }
+
+    /// Get the image reference of the component
+    #[must_use]
+    pub fn image_ref(&self) -> &str {
+        &self.image_ref
+    }
+
+    /// Get the number of instances the component was scaled to, 0 if it was stopped
+    #[must_use]
+    pub fn max_instances(&self) -> u32 {
+        self.max_instances
+    }
+
+    /// Get the annotations of the component
+    #[must_use]
+    pub fn annotations(&self) -> &BTreeMap<String, String> {
+        &self.annotations
+    }
+
+    /// Get the error encountered while scaling, only set if scaling failed
+    #[must_use]
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+}
+
+/// Data of the `provider_started` event
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct ProviderStarted {
+    /// ID of the host the provider was started on
+    pub(crate) host_id: String,
+    /// ID of the provider
+    pub(crate) provider_id: String,
+    /// Image reference of the provider
+    pub(crate) image_ref: String,
+    /// Annotations of the provider
+    #[serde(default)]
+    pub(crate) annotations: BTreeMap<String, String>,
+}
+
+impl ProviderStarted {
+    /// Get the ID of the host the provider was started on
+    #[must_use]
+    pub fn host_id(&self) -> &str {
+        &self.host_id
+    }
+
+    /// Get the ID of the provider
+    #[must_use]
+    pub fn provider_id(&self) -> &str {
+        &self.provider_id
+    }
+
+    /// Get the image reference of the provider
+    #[must_use]
+    pub fn image_ref(&self) -> &str {
+        &self.image_ref
+    }
+
+    /// Get the annotations of the provider
+    #[must_use]
+    pub fn annotations(&self) -> &BTreeMap<String, String> {
+        &self.annotations
+    }
+}
+
+/// Data of the `provider_start_failed` event
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct ProviderStartFailed {
+    /// ID of the host the provider failed to start on
+    pub(crate) host_id: String,
+    /// ID of the provider
+    pub(crate) provider_id: String,
+    /// Image reference of the provider
+    pub(crate) provider_ref: String,
+    /// Error encountered while starting the provider
+    pub(crate) error: String,
+}
+
+impl ProviderStartFailed {
+    /// Get the ID of the host the provider failed to start on
+    #[must_use]
+    pub fn host_id(&self) -> &str {
+        &self.host_id
+    }
+
+    /// Get the ID of the provider
+    #[must_use]
+    pub fn provider_id(&self) -> &str {
+        &self.provider_id
+    }
+
+    /// Get the image reference of the provider
+    #[must_use]
+    pub fn provider_ref(&self) -> &str {
+        &self.provider_ref
+    }
+
+    /// Get the error encountered while starting the provider
+    #[must_use]
+    pub fn error(&self) -> &str {
+        &self.error
+    }
+}
+
+/// Data of the `provider_stopped` event
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct ProviderStopped {
+    /// ID of the host the provider was stopped on
+    pub(crate) host_id: String,
+    /// ID of the provider
+    pub(crate) provider_id: String,
+    /// Reason the provider was stopped
+    #[serde(default)]
+    pub(crate) reason: String,
+    /// Annotations of the provider
+    #[serde(default)]
+    pub(crate) annotations: BTreeMap<String, String>,
+}
+
+impl ProviderStopped {
+    /// Get the ID of the host the provider was stopped on
+    #[must_use]
+    pub fn host_id(&self) -> &str {
+        &self.host_id
+    }
+
+    /// Get the ID of the provider
+    #[must_use]
+    pub fn provider_id(&self) -> &str {
+        &self.provider_id
+    }
+
+    /// Get the reason the provider was stopped
+    #[must_use]
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+
+    /// Get the annotations of the provider
+    #[must_use]
+    pub fn annotations(&self) -> &BTreeMap<String, String> {
+        &self.annotations
+    }
+}
+
+/// Data of the `linkdef_set_failed` event
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct LinkPutFailed {
+    /// The link that failed to be put
+    #[serde(flatten)]
+    pub(crate) link: Link,
+    /// Error encountered while putting the link
+    pub(crate) error: String,
+}
+
+impl LinkPutFailed {
+    /// Get the link that failed to be put
+    #[must_use]
+    pub fn link(&self) -> &Link {
+        &self.link
+    }
+
+    /// Get the error encountered while putting the link
+    #[must_use]
+    pub fn error(&self) -> &str {
+        &self.error
+    }
+}
+
+/// Data of the `linkdef_deleted` event
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct LinkDeleted {
+    /// Source identifier of the deleted link
+    pub(crate) source_id: String,
+    /// Name of the deleted link
+    pub(crate) name: String,
+    /// WIT namespace of the deleted link
+    pub(crate) wit_namespace: String,
+    /// WIT package of the deleted link
+    pub(crate) wit_package: String,
+    /// Target of the deleted link, not known if the link did not exist
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) target: Option<String>,
+    /// WIT interfaces of the deleted link, not known if the link did not exist
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) interfaces: Option<Vec<String>>,
+}
+
+impl LinkDeleted {
+    /// Get the source identifier of the deleted link
+    #[must_use]
+    pub fn source_id(&self) -> &str {
+        &self.source_id
+    }
+
+    /// Get the name of the deleted link
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the WIT namespace of the deleted link
+    #[must_use]
+    pub fn wit_namespace(&self) -> &str {
+        &self.wit_namespace
+    }
+
+    /// Get the WIT package of the deleted link
+    #[must_use]
+    pub fn wit_package(&self) -> &str {
+        &self.wit_package
+    }
+
+    /// Get the target of the deleted link, `None` if the link did not exist
+    #[must_use]
+    pub fn target(&self) -> Option<&str> {
+        self.target.as_deref()
+    }
+
+    /// Get the WIT interfaces of the deleted link, `None` if the link did not exist
+    #[must_use]
+    pub fn interfaces(&self) -> Option<&[String]> {
+        self.interfaces.as_deref()
+    }
+}
+
+/// Data of the `config_set` and `config_deleted` events
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct ConfigChanged {
+    /// Name of the configuration
+    pub(crate) config_name: String,
+}
+
+impl ConfigChanged {
+    /// Get the name of the configuration
+    #[must_use]
+    pub fn config_name(&self) -> &str {
+        &self.config_name
+    }
+}
+
+/// Data of the `host_started` event
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct HostStarted {
+    /// Human-friendly name of the host
+    #[serde(default)]
+    pub(crate) friendly_name: String,
+    /// Labels of the host
+    #[serde(default)]
+    pub(crate) labels: BTreeMap<String, String>,
+    /// Version of the host
+    #[serde(default)]
+    pub(crate) version: String,
+}
+
+impl HostStarted {
+    /// Get the human-friendly name of the host
+    #[must_use]
+    pub fn friendly_name(&self) -> &str {
+        &self.friendly_name
+    }
+
+    /// Get the labels of the host
+    #[must_use]
+    pub fn labels(&self) -> &BTreeMap<String, String> {
+        &self.labels
+    }
+
+    /// Get the version of the host
+    #[must_use]
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+}
+
+/// Data of the `host_stopped` event
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct HostStopped {
+    /// Labels of the host
+    #[serde(default)]
+    pub(crate) labels: BTreeMap<String, String>,
+}
+
+impl HostStopped {
+    /// Get the labels of the host
+    #[must_use]
+    pub fn labels(&self) -> &BTreeMap<String, String> {
+        &self.labels
+    }
+}
+
+/// Data of the `labels_changed` event
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct LabelsChanged {
+    /// ID of the host whose labels changed
+    pub(crate) host_id: String,
+    /// Current labels of the host
+    #[serde(default)]
+    pub(crate) labels: HashMap<String, String>,
+}
+
+impl LabelsChanged {
+    /// Get the ID of the host whose labels changed
+    #[must_use]
+    pub fn host_id(&self) -> &str {
+        &self.host_id
+    }
+
+    /// Get the current labels of the host
+    #[must_use]
+    pub fn labels(&self) -> &HashMap<String, String> {
+        &self.labels
+    }
+}
+
+/// Filter applied to the lattice event stream returned by [`Client::events`](crate::Client::events)
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct EventFilter {
+    /// Lattices to receive events from, the lattice of the client if empty
+    pub(crate) lattices: Vec<String>,
+    /// Types of events to receive, all events if empty
+    pub(crate) event_types: Vec<String>,
+}
+
+impl EventFilter {
+    /// Creates a filter receiving all events in the lattice of the client
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Receive events from `lattice`. May be called multiple times to receive events from
+    /// multiple lattices, `*` receives events from all lattices.
+    #[must_use]
+    pub fn lattice(mut self, lattice: impl Into<String>) -> Self {
+        self.lattices.push(lattice.into());
+        self
+    }
+
+    /// Receive events of type `event_type`, e.g. `component_scaled`. May be called multiple
+    /// times to receive events of multiple types.
+    #[must_use]
+    pub fn event_type(mut self, event_type: impl Into<String>) -> Self {
+        self.event_types.push(event_type.into());
+        self
+    }
+
+    /// Returns the NATS subjects to subscribe to, using `default_lattice` if no lattices are set
+    pub(crate) fn subjects(&self, default_lattice: &str) -> Vec<String> {
+        let default_lattice = [default_lattice.to_string()];
+        let lattices = if self.lattices.is_empty() {
+            &default_lattice[..]
+        } else {
+            &self.lattices[..]
+        };
+        lattices
+            .iter()
+            .flat_map(|lattice| {
+                if self.event_types.is_empty() {
+                    vec![format!("{EVENT_SUBJECT_PREFIX}.{lattice}.*")]
+                } else {
+                    self.event_types
+                        .iter()
+                        .map(|event_type| format!("{EVENT_SUBJECT_PREFIX}.{lattice}.{event_type}"))
+                        .collect()
+                }
+            })
+            .collect()
+    }
+}
+
+/// Returns the lattice an event received on `subject` was published in
+pub(crate) fn subject_lattice(subject: &str) -> Option<&str> {
+    let (lattice, _) = subject
+        .strip_prefix(EVENT_SUBJECT_PREFIX)?
+        .strip_prefix('.')?
+        .rsplit_once('.')?;
+    Some(lattice)
+}
+
+#[cfg(test)]
+mod test {
+    use cloudevents::event::Event;
+    use serde_json::json;
+
+    use super::{subject_lattice, EventFilter, LatticeEvent, LatticeEventKind};
+
+    fn cloud_event(ty: &str, data: serde_json::Value) -> Event {
+        serde_json::from_value(json!({
+            "specversion": "1.0",
+            "id": "01J0000000000000000000000",
+            "source": "NHOST",
+            "type": format!("com.wasmcloud.lattice.{ty}"),
+            "time": "2024-01-01T00:00:00Z",
+            "datacontenttype": "application/json",
+            "data": data,
+        }))
+        .expect("failed to build cloud event")
+    }
+
+    #[test]
+    fn test_parse_events() {
+        let event = LatticeEvent::from_cloud_event(
+            "default",
+            &cloud_event(
+                "component_scaled",
+                json!({
+                    "public_key": "MCOMPONENT",
+                    "annotations": { "foo": "bar" },
+                    "host_id": "NHOST",
+                    "image_ref": "ghcr.io/wasmcloud/components/http-hello-world-rust:0.1.0",
+                    "max_instances": 5,
+                    "component_id": "hello",
+                }),
+            ),
+        )
+        .expect("failed to parse event");
+        assert_eq!(event.lattice(), "default");
+        assert_eq!(event.source(), "NHOST");
+        assert_eq!(event.event_type(), "component_scaled");
+        assert_eq!(event.time(), Some("2024-01-01T00:00:00+00:00"));
+        let LatticeEventKind::ComponentScaled(scaled) = event.kind() else {
+            panic!("unexpected event kind: {:?}", event.kind());
+        };
+        assert_eq!(scaled.component_id(), "hello");
+        assert_eq!(scaled.max_instances(), 5);
+        assert_eq!(
+            scaled.annotations().get("foo").map(String::as_str),
+            Some("bar")
+        );
+        assert_eq!(scaled.error(), None);
+
+        let event = LatticeEvent::from_cloud_event(
+            "default",
+            &cloud_event(
+                "linkdef_set",
+                json!({
+                    "source_id": "hello",
+                    "target": "http-server",
+                    "name": "default",
+                    "wit_namespace": "wasi",
+                    "wit_package": "http",
+                    "interfaces": ["incoming-handler"],
+                    "source_config": [],
+                    "target_config": [],
+                }),
+            ),
+        )
+        .expect("failed to parse event");
+        let LatticeEventKind::LinkPut(link) = event.into_kind() else {
+            panic!("unexpected event kind");
+        };
+        assert_eq!(link.target(), "http-server");
+
+        let event = LatticeEvent::from_cloud_event(
+            "default",
+            &cloud_event("something_new", json!({ "foo": "bar" })),
+        )
+        .expect("failed to parse event");
+        assert_eq!(event.event_type(), "something_new");
+        assert!(matches!(event.kind(), LatticeEventKind::Other { .. }));
+
+        assert!(LatticeEvent::from_cloud_event(
+            "default",
+            &cloud_event("provider_started", json!({ "host_id": 1 })),
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_event_filter() {
+        assert_eq!(
+            EventFilter::new().subjects("default"),
+            ["wasmbus.evt.default.*"]
+        );
+        assert_eq!(
+            EventFilter::new()
+                .lattice("a")
+                .lattice("b")
+                .event_type("component_scaled")
+                .event_type("host_heartbeat")
+                .subjects("default"),
+            [
+                "wasmbus.evt.a.component_scaled",
+                "wasmbus.evt.a.host_heartbeat",
+                "wasmbus.evt.b.component_scaled",
+                "wasmbus.evt.b.host_heartbeat",
+            ]
+        );
+        assert_eq!(
+            subject_lattice("wasmbus.evt.default.host_heartbeat"),
+            Some("default")
+        );
+        assert_eq!(subject_lattice("wasmbus.ctl.default.host_heartbeat"), None);
+    }
+}
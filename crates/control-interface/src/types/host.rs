@@ -243,6 +243,11 @@ pub struct HostInventory {
     /// The host uptime in seconds
     #[serde(default)]
     pub(crate) uptime_seconds: u64,
+
+    /// Hosts in the lattice whose clock is skewed relative to the clock of this host, as observed
+    /// from their heartbeats
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) clock_skew: Vec<HostClockSkew>,
 }
 
 impl HostInventory {
@@ -286,6 +291,11 @@ impl HostInventory {
         self.uptime_seconds
     }
 
+    /// Get the hosts whose clock is skewed relative to the clock of this host
+    pub fn clock_skew(&self) -> &[HostClockSkew] {
+        &self.clock_skew
+    }
+
     #[must_use]
     pub fn builder() -> HostInventoryBuilder {
         HostInventoryBuilder::default()
@@ -303,6 +313,7 @@ pub struct HostInventoryBuilder {
     version: Option<String>,
     uptime_human: Option<String>,
     uptime_seconds: Option<u64>,
+    clock_skew: Vec<HostClockSkew>,
 }
 
 impl HostInventoryBuilder {
@@ -354,6 +365,12 @@ impl HostInventoryBuilder {
         self
     }
 
+    #[must_use]
+    pub fn clock_skew(mut self, v: Vec<HostClockSkew>) -> Self {
+        self.clock_skew = v;
+        self
+    }
+
     pub fn build(self) -> Result<HostInventory> {
         Ok(HostInventory {
             components: self.components.unwrap_or_default(),
@@ -374,10 +391,44 @@ impl HostInventoryBuilder {
             uptime_seconds: self
                 .uptime_seconds
                 .ok_or_else(|| "uptime_seconds is required".to_string())?,
+            clock_skew: self.clock_skew,
         })
     }
 }
 
+/// Skew of the clock of a host relative to the clock of the host reporting it
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct HostClockSkew {
+    /// ID of the host whose clock is skewed
+    #[serde(default)]
+    pub(crate) host_id: String,
+
+    /// Skew in milliseconds, positive if the clock of the host is ahead
+    #[serde(default)]
+    pub(crate) skew_ms: i64,
+}
+
+impl HostClockSkew {
+    /// Create a [`HostClockSkew`] from a host ID and a skew in milliseconds
+    pub fn new(host_id: &str, skew_ms: i64) -> Self {
+        Self {
+            host_id: host_id.into(),
+            skew_ms,
+        }
+    }
+
+    /// Get the ID of the host whose clock is skewed
+    pub fn host_id(&self) -> &str {
+        &self.host_id
+    }
+
+    /// Get the skew in milliseconds, positive if the clock of the host is ahead
+    pub fn skew_ms(&self) -> i64 {
+        self.skew_ms
+    }
+}
+
 /// A label on a given host (ex. "arch=amd64")
 #[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 #[non_exhaustive]
@@ -435,7 +486,7 @@ mod tests {
 
     use crate::{ComponentDescription, ProviderDescription};
 
-    use super::{Host, HostInventory};
+    use super::{Host, HostClockSkew, HostInventory};
 
     #[test]
     fn host_builder() {
@@ -479,7 +530,11 @@ mod tests {
                 labels: BTreeMap::from([("a".into(), "b".into())]),
                 version: "1.0.0".into(),
                 uptime_human: "t".into(),
-                uptime_seconds: 1
+                uptime_seconds: 1,
+                clock_skew: Vec::from([HostClockSkew {
+                    host_id: "other".into(),
+                    skew_ms: -7000,
+                }]),
             },
             HostInventory::builder()
                 .components(Vec::from([ComponentDescription::default()]))
@@ -490,6 +545,7 @@ mod tests {
                 .version("1.0.0".into())
                 .uptime_human("t".into())
                 .uptime_seconds(1)
+                .clock_skew(Vec::from([HostClockSkew::new("other", -7000)]))
                 .build()
                 .unwrap()
         )
@@ -169,6 +169,67 @@ impl LinkBuilder {
     }
 }
 
+/// Result of validating a link with the provider it connects, without establishing the link
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub struct LinkValidation {
+    /// Errors found by the provider, the link is valid if there are none
+    #[serde(default)]
+    pub(crate) errors: Vec<LinkValidationError>,
+}
+
+impl LinkValidation {
+    #[must_use]
+    pub fn new(errors: Vec<LinkValidationError>) -> Self {
+        Self { errors }
+    }
+
+    /// Get the errors found while validating the link
+    #[must_use]
+    pub fn errors(&self) -> &[LinkValidationError] {
+        &self.errors
+    }
+
+    /// Whether the link is valid
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// An error found by a provider while validating the configuration of a link
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub struct LinkValidationError {
+    /// Configuration key or secret name the error relates to, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) field: Option<String>,
+    /// Description of the error
+    pub(crate) message: String,
+}
+
+impl LinkValidationError {
+    #[must_use]
+    pub fn new(field: Option<String>, message: impl Into<String>) -> Self {
+        Self {
+            field,
+            message: message.into(),
+        }
+    }
+
+    /// Get the configuration key or secret name the error relates to, if any
+    #[must_use]
+    pub fn field(&self) -> Option<&str> {
+        self.field.as_deref()
+    }
+
+    /// Get the description of the error
+    #[must_use]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
 /// Helper function to provide a default link name
 pub(crate) fn default_link_name() -> String {
     "default".to_string()
@@ -177,7 +238,7 @@ pub(crate) fn default_link_name() -> String {
 #[cfg(test)]
 mod tests {
 
-    use super::Link;
+    use super::{Link, LinkValidation, LinkValidationError};
 
     #[test]
     fn link_builder() {
@@ -205,4 +266,22 @@ mod tests {
                 .unwrap()
         );
     }
+
+    #[test]
+    fn link_validation() {
+        let validation: LinkValidation = serde_json::from_str(
+            r#"{"errors":[{"field":"URL","message":"unreachable"},{"message":"invalid"}]}"#,
+        )
+        .unwrap();
+        assert!(!validation.is_valid());
+        assert_eq!(
+            validation.errors(),
+            [
+                LinkValidationError::new(Some("URL".into()), "unreachable"),
+                LinkValidationError::new(None, "invalid"),
+            ]
+        );
+        let validation: LinkValidation = serde_json::from_str("{}").unwrap();
+        assert!(validation.is_valid());
+    }
 }
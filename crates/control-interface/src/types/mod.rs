@@ -1,9 +1,14 @@
 //! Collection of types that are commonly used/necessary in control interface operations
 
+pub mod capture;
+pub mod claims;
 pub mod component;
+pub mod config;
 pub mod ctl;
+pub mod event;
 pub mod host;
 pub mod link;
 pub mod provider;
 pub mod registry;
 pub mod rpc;
+pub mod traffic;
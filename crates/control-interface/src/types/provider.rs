@@ -26,6 +26,9 @@ pub struct ProviderDescription {
     /// this provider instance
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub(crate) annotations: Option<BTreeMap<String, String>>,
+    /// Health of the links of the provider, as last reported by the provider
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) link_health: Vec<ProviderLinkHealth>,
 }
 
 impl ProviderDescription {
@@ -54,6 +57,11 @@ impl ProviderDescription {
         self.annotations.as_ref()
     }
 
+    /// Get the health of the links of the provider, as last reported by the provider
+    pub fn link_health(&self) -> &[ProviderLinkHealth] {
+        &self.link_health
+    }
+
     #[must_use]
     pub fn builder() -> ProviderDescriptionBuilder {
         ProviderDescriptionBuilder::default()
@@ -69,6 +77,7 @@ pub struct ProviderDescriptionBuilder {
     name: Option<String>,
     revision: Option<i32>,
     annotations: Option<BTreeMap<String, String>>,
+    link_health: Vec<ProviderLinkHealth>,
 }
 
 impl ProviderDescriptionBuilder {
@@ -108,6 +117,13 @@ impl ProviderDescriptionBuilder {
         self
     }
 
+    /// Health of the links of the provider, as last reported by the provider
+    #[must_use]
+    pub fn link_health(mut self, v: Vec<ProviderLinkHealth>) -> Self {
+        self.link_health = v;
+        self
+    }
+
     /// Build a [`ProviderDescription`]
     pub fn build(self) -> Result<ProviderDescription> {
         Ok(ProviderDescription {
@@ -116,6 +132,142 @@ impl ProviderDescriptionBuilder {
             name: self.name,
             revision: self.revision.unwrap_or_default(),
             annotations: self.annotations,
+            link_health: self.link_health,
+        })
+    }
+}
+
+/// Health status of a link of a provider
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum LinkHealthStatus {
+    /// The link is fully functional
+    #[default]
+    Healthy,
+    /// The link is functional, but impaired, e.g. slow to respond
+    Degraded,
+    /// The link is not functional
+    Unhealthy,
+}
+
+impl core::fmt::Display for LinkHealthStatus {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Healthy => write!(f, "healthy"),
+            Self::Degraded => write!(f, "degraded"),
+            Self::Unhealthy => write!(f, "unhealthy"),
+        }
+    }
+}
+
+/// Health of a link of a provider, as reported by the provider
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct ProviderLinkHealth {
+    /// ID of the source of the link
+    #[serde(default)]
+    pub(crate) source_id: String,
+    /// ID of the target of the link
+    #[serde(default)]
+    pub(crate) target: String,
+    /// Name of the link
+    #[serde(default)]
+    pub(crate) link_name: String,
+    /// Health status of the link
+    #[serde(default)]
+    pub(crate) status: LinkHealthStatus,
+    /// Details on the health of the link, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) message: Option<String>,
+}
+
+impl ProviderLinkHealth {
+    /// Get the ID of the source of the link
+    pub fn source_id(&self) -> &str {
+        &self.source_id
+    }
+
+    /// Get the ID of the target of the link
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// Get the name of the link
+    pub fn link_name(&self) -> &str {
+        &self.link_name
+    }
+
+    /// Get the health status of the link
+    pub fn status(&self) -> LinkHealthStatus {
+        self.status
+    }
+
+    /// Get the details on the health of the link, if any
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    #[must_use]
+    pub fn builder() -> ProviderLinkHealthBuilder {
+        ProviderLinkHealthBuilder::default()
+    }
+}
+
+/// Builds [`ProviderLinkHealth`]s
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct ProviderLinkHealthBuilder {
+    source_id: Option<String>,
+    target: Option<String>,
+    link_name: Option<String>,
+    status: Option<LinkHealthStatus>,
+    message: Option<String>,
+}
+
+impl ProviderLinkHealthBuilder {
+    #[must_use]
+    pub fn source_id(mut self, v: &str) -> Self {
+        self.source_id = Some(v.into());
+        self
+    }
+
+    #[must_use]
+    pub fn target(mut self, v: &str) -> Self {
+        self.target = Some(v.into());
+        self
+    }
+
+    #[must_use]
+    pub fn link_name(mut self, v: &str) -> Self {
+        self.link_name = Some(v.into());
+        self
+    }
+
+    #[must_use]
+    pub fn status(mut self, v: LinkHealthStatus) -> Self {
+        self.status = Some(v);
+        self
+    }
+
+    #[must_use]
+    pub fn message(mut self, v: &str) -> Self {
+        self.message = Some(v.into());
+        self
+    }
+
+    /// Build a [`ProviderLinkHealth`]
+    pub fn build(self) -> Result<ProviderLinkHealth> {
+        Ok(ProviderLinkHealth {
+            source_id: self
+                .source_id
+                .ok_or_else(|| "source id is required".to_string())?,
+            target: self
+                .target
+                .ok_or_else(|| "target is required".to_string())?,
+            link_name: self.link_name.unwrap_or_else(|| "default".into()),
+            status: self.status.unwrap_or_default(),
+            message: self.message,
         })
     }
 }
@@ -124,7 +276,7 @@ impl ProviderDescriptionBuilder {
 mod tests {
     use std::collections::BTreeMap;
 
-    use super::ProviderDescription;
+    use super::{LinkHealthStatus, ProviderDescription, ProviderLinkHealth};
 
     #[test]
     fn provider_description_builder() {
@@ -135,6 +287,13 @@ mod tests {
                 name: Some("name".into()),
                 annotations: Some(BTreeMap::from([("a".into(), "b".into())])),
                 revision: 0,
+                link_health: vec![ProviderLinkHealth {
+                    source_id: "component".into(),
+                    target: "id".into(),
+                    link_name: "default".into(),
+                    status: LinkHealthStatus::Degraded,
+                    message: Some("slow".into()),
+                }],
             },
             ProviderDescription::builder()
                 .id("id")
@@ -142,6 +301,13 @@ mod tests {
                 .name("name")
                 .annotations(BTreeMap::from([("a".into(), "b".into())]))
                 .revision(0)
+                .link_health(vec![ProviderLinkHealth::builder()
+                    .source_id("component")
+                    .target("id")
+                    .status(LinkHealthStatus::Degraded)
+                    .message("slow")
+                    .build()
+                    .unwrap()])
                 .build()
                 .unwrap()
         )
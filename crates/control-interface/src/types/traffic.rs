@@ -0,0 +1,134 @@
+//! Data types used when splitting invocations between versions of a component on a wasmCloud
+//! lattice
+
+use serde::{Deserialize, Serialize};
+
+use crate::Result;
+
+/// A split of the invocations of a link target between two versions of a component.
+///
+/// Of all invocations sent over links to [`TrafficSplit::target`], [`TrafficSplit::weight`]
+/// percent are sent to [`TrafficSplit::canary`] instead, regardless of the source or name of the
+/// link. This allows rolling out a new version of a component (the canary) gradually, by
+/// increasing the weight until all invocations are sent to the canary.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize, Hash)]
+#[non_exhaustive]
+pub struct TrafficSplit {
+    /// The link target whose invocations are split, usually the ID of the current version
+    pub(crate) target: String,
+    /// The ID of the component receiving [`TrafficSplit::weight`] percent of the invocations
+    pub(crate) canary: String,
+    /// The percentage of invocations sent to the canary, between 0 and 100
+    #[serde(default)]
+    pub(crate) weight: u8,
+}
+
+impl TrafficSplit {
+    /// Get the link target whose invocations are split
+    #[must_use]
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+
+    /// Get the ID of the component receiving the canary share of invocations
+    #[must_use]
+    pub fn canary(&self) -> &str {
+        &self.canary
+    }
+
+    /// Get the percentage of invocations sent to the canary
+    #[must_use]
+    pub fn weight(&self) -> u8 {
+        self.weight
+    }
+
+    #[must_use]
+    pub fn builder() -> TrafficSplitBuilder {
+        TrafficSplitBuilder::default()
+    }
+}
+
+#[derive(Default, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct TrafficSplitBuilder {
+    target: Option<String>,
+    canary: Option<String>,
+    weight: Option<u8>,
+}
+
+impl TrafficSplitBuilder {
+    #[must_use]
+    pub fn target(mut self, v: &str) -> Self {
+        self.target = Some(v.into());
+        self
+    }
+
+    #[must_use]
+    pub fn canary(mut self, v: &str) -> Self {
+        self.canary = Some(v.into());
+        self
+    }
+
+    #[must_use]
+    pub fn weight(mut self, v: u8) -> Self {
+        self.weight = Some(v);
+        self
+    }
+
+    pub fn build(self) -> Result<TrafficSplit> {
+        let target = self
+            .target
+            .ok_or_else(|| "target is required".to_string())?;
+        let canary = self
+            .canary
+            .ok_or_else(|| "canary is required".to_string())?;
+        if target == canary {
+            return Err("target and canary must differ".into());
+        }
+        let weight = self.weight.unwrap_or_default();
+        if weight > 100 {
+            return Err(
+                format!("weight must be a percentage between 0 and 100, got {weight}").into(),
+            );
+        }
+        Ok(TrafficSplit {
+            target,
+            canary,
+            weight,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TrafficSplit;
+
+    #[test]
+    fn traffic_split_builder() {
+        assert_eq!(
+            TrafficSplit {
+                target: "echo-v1".into(),
+                canary: "echo-v2".into(),
+                weight: 10,
+            },
+            TrafficSplit::builder()
+                .target("echo-v1")
+                .canary("echo-v2")
+                .weight(10)
+                .build()
+                .unwrap()
+        );
+        assert!(TrafficSplit::builder().target("echo-v1").build().is_err());
+        assert!(TrafficSplit::builder()
+            .target("echo-v1")
+            .canary("echo-v1")
+            .build()
+            .is_err());
+        assert!(TrafficSplit::builder()
+            .target("echo-v1")
+            .canary("echo-v2")
+            .weight(101)
+            .build()
+            .is_err());
+    }
+}
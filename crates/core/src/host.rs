@@ -69,6 +69,10 @@ pub struct HostData {
     pub log_level: Option<Level>,
     #[serde(default)]
     pub otel_config: OtelConfig,
+    /// Prefix of all lattice subjects, isolating the lattice of a tenant on a NATS cluster shared
+    /// by multiple tenants. See [`prefixed_subject`](crate::prefixed_subject)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lattice_subject_prefix: Option<String>,
 }
 
 // Trait implementations that ensure we zeroize the memory of secrets when they are dropped
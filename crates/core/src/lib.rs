@@ -27,6 +27,9 @@ pub use registry::*;
 pub mod rpc;
 pub use rpc::*;
 
+pub mod schema;
+pub use schema::*;
+
 pub mod secrets;
 
 pub mod wit;
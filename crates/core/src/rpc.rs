@@ -9,6 +9,8 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::logging::Level;
+
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct HealthCheckRequest {}
 
@@ -20,6 +22,160 @@ pub struct HealthCheckResponse {
     /// A message containing additional information about the components health
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub message: Option<String>,
+    /// Health of the individual links of a provider, if it checks them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<LinkHealthCheck>,
+}
+
+/// Health status of a link of a provider
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkHealthStatus {
+    /// The link is fully functional
+    #[default]
+    Healthy,
+    /// The link is functional, but impaired, e.g. slow to respond
+    Degraded,
+    /// The link is not functional
+    Unhealthy,
+}
+
+/// Health of a link of a provider, as checked by the provider
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct LinkHealthCheck {
+    /// ID of the source of the link
+    pub source_id: String,
+    /// ID of the target of the link
+    pub target: String,
+    /// Name of the link
+    pub link_name: String,
+    /// Health status of the link
+    #[serde(default)]
+    pub status: LinkHealthStatus,
+    /// Details on the health of the link, e.g. the error of a failed check
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// An error found while validating the configuration of a link
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct LinkValidationError {
+    /// Configuration key or secret name the error relates to, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    /// Description of the error
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct LinkValidationResponse {
+    /// Errors found in the link configuration, the link is valid if there are none
+    #[serde(default)]
+    pub errors: Vec<LinkValidationError>,
+}
+
+impl LinkValidationResponse {
+    /// Whether the link configuration is valid
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// An administrative operation on a provider, requested on the [`provider_admin_subject`]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case", tag = "op")]
+#[non_exhaustive]
+pub enum ProviderAdminRequest {
+    /// List the links the provider is the source or target of
+    DumpLinks,
+    /// Dump the internal state of the provider, if it exposes any
+    DumpState,
+    /// Change the log level of the provider at runtime
+    SetLogLevel { level: Level },
+    /// Flush the caches of the provider, e.g. cached clients or responses
+    FlushCaches,
+    /// Perform an operation specific to the provider, e.g. replaying messages to a component
+    Operation {
+        /// Name of the operation
+        name: String,
+        /// Arguments of the operation, as defined by the provider
+        #[serde(default)]
+        args: serde_json::Value,
+    },
+}
+
+/// A link of a provider, as listed by [`ProviderAdminRequest::DumpLinks`]. Configuration and
+/// secrets of the link are not included
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct ProviderAdminLink {
+    /// ID of the source of the link
+    pub source_id: String,
+    /// ID of the target of the link
+    pub target: String,
+    /// Name of the link
+    pub name: String,
+    /// WIT namespace of the link
+    pub wit_namespace: String,
+    /// WIT package of the link
+    pub wit_package: String,
+    /// WIT interfaces of the link
+    #[serde(default)]
+    pub interfaces: Vec<String>,
+}
+
+/// Response of a provider to a [`ProviderAdminRequest`]
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ProviderAdminResponse {
+    /// Whether the operation succeeded
+    pub success: bool,
+    /// Error of a failed operation, or additional information on the operation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    /// Links of the provider, in response to [`ProviderAdminRequest::DumpLinks`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<ProviderAdminLink>,
+    /// Internal state of the provider, in response to [`ProviderAdminRequest::DumpState`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<serde_json::Value>,
+    /// Output of the operation, in response to [`ProviderAdminRequest::Operation`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<serde_json::Value>,
+}
+
+impl ProviderAdminResponse {
+    /// Construct a response of a successful operation
+    #[must_use]
+    pub fn success() -> Self {
+        Self {
+            success: true,
+            ..Self::default()
+        }
+    }
+
+    /// Construct a response of a failed operation
+    #[must_use]
+    pub fn error(message: impl Into<String>) -> Self {
+        Self {
+            success: false,
+            message: Some(message.into()),
+            ..Self::default()
+        }
+    }
+}
+
+/// Prepend the subject `prefix` of a tenant to a lattice `subject`, if any
+///
+/// Hosts configured with a subject prefix namespace all subjects of their lattice (control
+/// interface, events, wRPC and the RPC subjects in this module) under the prefix, so that
+/// multiple tenants can be isolated on a single NATS cluster with authorization restricting each
+/// tenant to `<prefix>.>`.
+#[must_use]
+pub fn prefixed_subject(prefix: Option<&str>, subject: impl Into<String>) -> String {
+    match prefix {
+        Some(prefix) => format!("{prefix}.{}", subject.into()),
+        None => subject.into(),
+    }
 }
 
 /// Generate the wasmbus RPC subject for putting links on a NATS cluster
@@ -31,6 +187,24 @@ pub fn link_put_subject(lattice: &str, provider_key: &str) -> String {
     format!("wasmbus.rpc.{lattice}.{provider_key}.linkdefs.put")
 }
 
+/// Generate the wasmbus RPC subject for validating links on a NATS cluster
+///
+/// When requests are published on this subject, providers validate the link configuration without
+/// establishing the link, and respond with a [`LinkValidationResponse`].
+#[must_use]
+pub fn link_validate_subject(lattice: &str, provider_key: &str) -> String {
+    format!("wasmbus.rpc.{lattice}.{provider_key}.linkdefs.validate")
+}
+
+/// Generate the wasmbus RPC subject for retrieving the link configuration schema of a provider
+///
+/// When requests are published on this subject, providers respond with their
+/// [`ConfigSchema`](crate::ConfigSchema), or `null` if they do not declare one.
+#[must_use]
+pub fn config_schema_subject(lattice: &str, provider_key: &str) -> String {
+    format!("wasmbus.rpc.{lattice}.{provider_key}.linkdefs.schema")
+}
+
 /// Generate the wasmbus RPC subject for deleting links on a NATS cluster
 ///
 /// When messages are published on this subject, hosts remove link information,
@@ -49,6 +223,15 @@ pub fn health_subject(lattice: &str, provider_key: &str) -> String {
     format!("wasmbus.rpc.{lattice}.{provider_key}.health")
 }
 
+/// Generate the wasmbus RPC subject for administering a given provider
+///
+/// When requests are published on this subject, providers perform the requested
+/// [`ProviderAdminRequest`] and respond with a [`ProviderAdminResponse`].
+#[must_use]
+pub fn provider_admin_subject(lattice: &str, provider_key: &str) -> String {
+    format!("wasmbus.rpc.{lattice}.{provider_key}.admin")
+}
+
 /// Generate the wasmbus RPC subject for shutting down a given provider
 ///
 /// When messages are published on this subject, hosts perform shutdown (cleanly if possible).
@@ -0,0 +1,462 @@
+//! Declarative schemas of the link configuration of providers, used to validate link
+//! configuration and secrets and to show the supported configuration to users
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use url::Url;
+
+use crate::secrets::SecretValue;
+use crate::LinkValidationError;
+
+/// Kind of the value of a [`ConfigField`]
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case", tag = "type")]
+#[non_exhaustive]
+pub enum ConfigFieldKind {
+    /// Any string
+    #[default]
+    String,
+    /// `true` or `false`, matched case-insensitively
+    Boolean,
+    /// A signed integer, optionally within an inclusive range
+    Integer {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min: Option<i64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max: Option<i64>,
+    },
+    /// An absolute URL
+    Url,
+    /// One of a fixed set of values
+    Enum { values: Vec<String> },
+}
+
+impl std::fmt::Display for ConfigFieldKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::String => write!(f, "string"),
+            Self::Boolean => write!(f, "boolean"),
+            Self::Integer {
+                min: None,
+                max: None,
+            } => write!(f, "integer"),
+            Self::Integer { min, max } => write!(
+                f,
+                "integer ({}..={})",
+                min.map(|min| min.to_string()).unwrap_or_default(),
+                max.map(|max| max.to_string()).unwrap_or_default()
+            ),
+            Self::Url => write!(f, "url"),
+            Self::Enum { values } => write!(f, "one of {}", values.join(", ")),
+        }
+    }
+}
+
+/// A configuration value or secret supported by a provider
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ConfigField {
+    /// Name of the field, matched case-insensitively against configuration keys and secret names
+    pub name: String,
+    /// Kind of the value of the field
+    #[serde(default)]
+    pub kind: ConfigFieldKind,
+    /// Whether the field must be set, unless it has a default
+    #[serde(default)]
+    pub required: bool,
+    /// Whether the field is sensitive and should be supplied as a secret
+    #[serde(default)]
+    pub secret: bool,
+    /// Human-readable description of the field
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Value used if the field is not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+}
+
+impl ConfigField {
+    /// Create a field of kind `kind`
+    #[must_use]
+    pub fn new(name: impl Into<String>, kind: ConfigFieldKind) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            ..Self::default()
+        }
+    }
+
+    /// Create a [`ConfigFieldKind::String`] field
+    #[must_use]
+    pub fn string(name: impl Into<String>) -> Self {
+        Self::new(name, ConfigFieldKind::String)
+    }
+
+    /// Create a [`ConfigFieldKind::Boolean`] field
+    #[must_use]
+    pub fn boolean(name: impl Into<String>) -> Self {
+        Self::new(name, ConfigFieldKind::Boolean)
+    }
+
+    /// Create a [`ConfigFieldKind::Integer`] field, accepting values within `min..=max`
+    #[must_use]
+    pub fn integer(name: impl Into<String>, min: Option<i64>, max: Option<i64>) -> Self {
+        Self::new(name, ConfigFieldKind::Integer { min, max })
+    }
+
+    /// Create a [`ConfigFieldKind::Url`] field
+    #[must_use]
+    pub fn url(name: impl Into<String>) -> Self {
+        Self::new(name, ConfigFieldKind::Url)
+    }
+
+    /// Create a [`ConfigFieldKind::Enum`] field, accepting one of `values`
+    #[must_use]
+    pub fn one_of(
+        name: impl Into<String>,
+        values: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        Self::new(
+            name,
+            ConfigFieldKind::Enum {
+                values: values.into_iter().map(Into::into).collect(),
+            },
+        )
+    }
+
+    /// Mark the field as required
+    #[must_use]
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// Mark the field as sensitive, in which case it is looked up in secrets before configuration
+    #[must_use]
+    pub fn secret(mut self) -> Self {
+        self.secret = true;
+        self
+    }
+
+    /// Set the description of the field
+    #[must_use]
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Set the value used if the field is not set
+    #[must_use]
+    pub fn default_value(mut self, default: impl Into<String>) -> Self {
+        self.default = Some(default.into());
+        self
+    }
+
+    /// Look up the value of the field, preferring secrets over configuration for sensitive fields
+    fn lookup<'a>(
+        &self,
+        config: &'a HashMap<String, String>,
+        secrets: &'a HashMap<String, SecretValue>,
+    ) -> Option<&'a str> {
+        let find_config = || {
+            config
+                .iter()
+                .find(|(k, v)| k.eq_ignore_ascii_case(&self.name) && !v.is_empty())
+                .map(|(_, v)| v.as_str())
+        };
+        if !self.secret {
+            return find_config();
+        }
+        if let Some(value) = secrets
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(&self.name))
+            .and_then(|(_, v)| v.as_string())
+        {
+            return Some(value);
+        }
+        let value = find_config()?;
+        warn!(
+            field = self.name,
+            "secret value was found in configuration. Please prefer using secrets for sensitive values."
+        );
+        Some(value)
+    }
+
+    /// Check that `value` is of the kind of the field
+    fn check(&self, value: &str) -> Result<(), String> {
+        match &self.kind {
+            ConfigFieldKind::String => Ok(()),
+            ConfigFieldKind::Boolean => {
+                if value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false") {
+                    Ok(())
+                } else {
+                    Err(format!("expected `true` or `false`, got `{value}`"))
+                }
+            }
+            ConfigFieldKind::Integer { min, max } => {
+                let n: i64 = value
+                    .parse()
+                    .map_err(|_| format!("expected an integer, got `{value}`"))?;
+                match (min, max) {
+                    (Some(min), _) if n < *min => Err(format!("must be at least {min}, got {n}")),
+                    (_, Some(max)) if n > *max => Err(format!("must be at most {max}, got {n}")),
+                    _ => Ok(()),
+                }
+            }
+            ConfigFieldKind::Url => Url::parse(value)
+                .map(|_| ())
+                .map_err(|err| format!("invalid URL: {err}")),
+            ConfigFieldKind::Enum { values } => {
+                if values.iter().any(|v| v == value) {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "expected one of {}, got `{value}`",
+                        values
+                            .iter()
+                            .map(|v| format!("`{v}`"))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ))
+                }
+            }
+        }
+    }
+}
+
+/// Schema of the link configuration and secrets supported by a provider
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ConfigSchema {
+    /// Supported configuration values and secrets
+    #[serde(default)]
+    pub fields: Vec<ConfigField>,
+}
+
+impl ConfigSchema {
+    /// Create an empty schema
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a field to the schema
+    #[must_use]
+    pub fn field(mut self, field: ConfigField) -> Self {
+        self.fields.push(field);
+        self
+    }
+
+    /// Validate configuration and secrets against the schema, returning the values of all
+    /// fields with defaults applied, or all errors found
+    ///
+    /// # Errors
+    ///
+    /// Returns an error for each field which is required but not set, or whose value is not of
+    /// the kind of the field
+    pub fn validate(
+        &self,
+        config: &HashMap<String, String>,
+        secrets: &HashMap<String, SecretValue>,
+    ) -> Result<ValidatedConfig, Vec<LinkValidationError>> {
+        let mut values = BTreeMap::new();
+        let mut errors = Vec::new();
+        for field in &self.fields {
+            match (field.lookup(config, secrets), &field.default) {
+                (Some(value), _) => {
+                    if let Err(message) = field.check(value) {
+                        errors.push(LinkValidationError {
+                            field: Some(field.name.clone()),
+                            message: format!("invalid `{}`: {message}", field.name),
+                        });
+                    } else {
+                        values.insert(field.name.clone(), value.to_string());
+                    }
+                }
+                (None, Some(default)) => {
+                    values.insert(field.name.clone(), default.clone());
+                }
+                (None, None) if field.required => {
+                    errors.push(LinkValidationError {
+                        field: Some(field.name.clone()),
+                        message: format!("`{}` is required", field.name),
+                    });
+                }
+                (None, None) => {}
+            }
+        }
+        if errors.is_empty() {
+            Ok(ValidatedConfig { values })
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Configuration validated against a [`ConfigSchema`], with defaults applied
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct ValidatedConfig {
+    /// Values of the set fields, indexed by field name
+    values: BTreeMap<String, String>,
+}
+
+impl std::fmt::Debug for ValidatedConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Values may be secrets, so only the names of set fields are shown
+        f.debug_struct("ValidatedConfig")
+            .field("fields", &self.values.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl ValidatedConfig {
+    /// Get the value of field `name`, if set
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(String::as_str)
+    }
+
+    /// Get the value of the [`ConfigFieldKind::Boolean`] field `name`, if set
+    #[must_use]
+    pub fn get_bool(&self, name: &str) -> Option<bool> {
+        self.get(name).map(|v| v.eq_ignore_ascii_case("true"))
+    }
+
+    /// Get the value of the [`ConfigFieldKind::Integer`] field `name`, if set
+    #[must_use]
+    pub fn get_integer(&self, name: &str) -> Option<i64> {
+        self.get(name).and_then(|v| v.parse().ok())
+    }
+
+    /// Get the value of the [`ConfigFieldKind::Url`] field `name`, if set
+    #[must_use]
+    pub fn get_url(&self, name: &str) -> Option<Url> {
+        self.get(name).and_then(|v| Url::parse(v).ok())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::{ConfigField, ConfigSchema};
+    use crate::secrets::SecretValue;
+
+    fn schema() -> ConfigSchema {
+        ConfigSchema::new()
+            .field(ConfigField::url("URL").required().description("Address"))
+            .field(ConfigField::integer("POOL_SIZE", Some(1), Some(64)).default_value("8"))
+            .field(ConfigField::boolean("TLS"))
+            .field(ConfigField::one_of("MODE", ["fast", "safe"]).default_value("safe"))
+            .field(ConfigField::string("PASSWORD").secret())
+    }
+
+    #[test]
+    fn validate_config() {
+        let config = HashMap::from([
+            ("url".to_string(), "redis://localhost:6379".to_string()),
+            ("TLS".to_string(), "True".to_string()),
+        ]);
+        let secrets = HashMap::from([(
+            "password".to_string(),
+            SecretValue::String("hunter2".to_string()),
+        )]);
+        let validated = schema()
+            .validate(&config, &secrets)
+            .expect("config should be valid");
+        assert_eq!(validated.get("URL"), Some("redis://localhost:6379"));
+        assert_eq!(validated.get_integer("POOL_SIZE"), Some(8));
+        assert_eq!(validated.get_bool("TLS"), Some(true));
+        assert_eq!(validated.get("MODE"), Some("safe"));
+        assert_eq!(validated.get("PASSWORD"), Some("hunter2"));
+        assert!(!format!("{validated:?}").contains("hunter2"));
+
+        let config = HashMap::from([
+            ("POOL_SIZE".to_string(), "100".to_string()),
+            ("TLS".to_string(), "yes".to_string()),
+            ("MODE".to_string(), "reckless".to_string()),
+        ]);
+        let errors = schema()
+            .validate(&config, &HashMap::new())
+            .expect_err("config should be invalid");
+        let fields: Vec<_> = errors.iter().filter_map(|e| e.field.as_deref()).collect();
+        assert_eq!(fields, ["URL", "POOL_SIZE", "TLS", "MODE"]);
+    }
+
+    #[test]
+    fn validation_errors() {
+        let messages = |config: &[(&str, &str)]| {
+            let config = config
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+            schema()
+                .validate(&config, &HashMap::new())
+                .expect_err("config should be invalid")
+                .into_iter()
+                .map(|e| e.message)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(messages(&[]), ["`URL` is required"]);
+        // Empty values are treated as unset
+        assert_eq!(messages(&[("URL", "")]), ["`URL` is required"]);
+        assert_eq!(
+            messages(&[("URL", "localhost")]),
+            ["invalid `URL`: invalid URL: relative URL without a base"]
+        );
+        assert_eq!(
+            messages(&[("URL", "redis://localhost"), ("POOL_SIZE", "eight")]),
+            ["invalid `POOL_SIZE`: expected an integer, got `eight`"]
+        );
+        assert_eq!(
+            messages(&[("URL", "redis://localhost"), ("POOL_SIZE", "0")]),
+            ["invalid `POOL_SIZE`: must be at least 1, got 0"]
+        );
+        assert_eq!(
+            messages(&[("URL", "redis://localhost"), ("POOL_SIZE", "65")]),
+            ["invalid `POOL_SIZE`: must be at most 64, got 65"]
+        );
+        assert_eq!(
+            messages(&[("URL", "redis://localhost"), ("TLS", "1")]),
+            ["invalid `TLS`: expected `true` or `false`, got `1`"]
+        );
+        assert_eq!(
+            messages(&[("URL", "redis://localhost"), ("MODE", "Fast")]),
+            ["invalid `MODE`: expected one of `fast`, `safe`, got `Fast`"]
+        );
+    }
+
+    #[test]
+    fn lookup_secrets() {
+        let config = HashMap::from([
+            ("URL".to_string(), "redis://localhost".to_string()),
+            ("PASSWORD".to_string(), "from-config".to_string()),
+        ]);
+        // Sensitive values in configuration are accepted, but secrets take precedence
+        let validated = schema()
+            .validate(&config, &HashMap::new())
+            .expect("config should be valid");
+        assert_eq!(validated.get("PASSWORD"), Some("from-config"));
+        let secrets = HashMap::from([(
+            "PASSWORD".to_string(),
+            SecretValue::String("from-secrets".to_string()),
+        )]);
+        let validated = schema()
+            .validate(&config, &secrets)
+            .expect("config should be valid");
+        assert_eq!(validated.get("PASSWORD"), Some("from-secrets"));
+
+        // Values of fields which are not sensitive are never looked up in secrets
+        let secrets = HashMap::from([(
+            "URL".to_string(),
+            SecretValue::String("redis://secret".to_string()),
+        )]);
+        let errors = schema()
+            .validate(&HashMap::new(), &secrets)
+            .expect_err("config should be invalid");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field.as_deref(), Some("URL"));
+    }
+}
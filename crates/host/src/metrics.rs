@@ -1,4 +1,4 @@
-use wasmcloud_tracing::{Counter, Histogram, KeyValue, Meter};
+use wasmcloud_tracing::{Counter, Histogram, KeyValue, Meter, ObservableGauge};
 
 /// `HostMetrics` encapsulates the set of metrics emitted by the wasmcloud host
 #[derive(Clone, Debug)]
@@ -20,6 +20,45 @@ pub struct HostMetrics {
     // Eventually a host will be able to support multiple lattices, so this will need to either be
     // removed or metrics will need to be scoped per-lattice.
     pub lattice_id: String,
+
+    component_max_instances: Option<ObservableGauge<u64>>,
+    component_active_instances: Option<ObservableGauge<u64>>,
+    active_tasks: Option<ObservableGauge<u64>>,
+    scheduler_workers: Option<ObservableGauge<u64>>,
+    scheduler_alive_tasks: Option<ObservableGauge<u64>>,
+    scheduler_global_queue_depth: Option<ObservableGauge<u64>>,
+    cache_entries: Option<ObservableGauge<u64>>,
+}
+
+/// Instance usage of a component running on the host
+#[derive(Clone, Debug)]
+pub(crate) struct ComponentUsage {
+    /// The component's ID.
+    pub id: String,
+    /// The maximum number of concurrent instances of the component.
+    pub max_instances: u64,
+    /// The number of instances of the component currently handling invocations.
+    pub active_instances: u64,
+}
+
+/// Number of long-lived tasks running on the host for an owner
+#[derive(Clone, Debug)]
+pub(crate) struct TaskUsage {
+    /// The kind of the owner of the tasks, e.g. `component` or `provider`.
+    pub owner: &'static str,
+    /// The ID of the owner of the tasks, empty for tasks owned by the host.
+    pub owner_id: String,
+    /// The number of running tasks.
+    pub active_tasks: u64,
+}
+
+/// Number of entries of a cache of the host
+#[derive(Clone, Debug)]
+pub(crate) struct CacheUsage {
+    /// The name of the cache, e.g. `policy_decisions`.
+    pub cache: &'static str,
+    /// The number of entries in the cache.
+    pub entries: u64,
 }
 
 impl HostMetrics {
@@ -48,9 +87,176 @@ impl HostMetrics {
             component_errors: component_error_count,
             host_id,
             lattice_id,
+            component_max_instances: None,
+            component_active_instances: None,
+            active_tasks: None,
+            scheduler_workers: None,
+            scheduler_alive_tasks: None,
+            scheduler_global_queue_depth: None,
+            cache_entries: None,
         }
     }
 
+    /// Register gauges reporting the instance usage of each component running on the host, as returned by `usage`.
+    #[must_use]
+    pub(crate) fn with_component_gauges(
+        mut self,
+        meter: &Meter,
+        usage: impl Fn() -> Vec<ComponentUsage> + Send + Sync + 'static,
+    ) -> Self {
+        let usage = std::sync::Arc::new(usage);
+        let attributes = {
+            let host_id = self.host_id.clone();
+            let lattice_id = self.lattice_id.clone();
+            move |id: &str| {
+                [
+                    KeyValue::new("component.id", id.to_string()),
+                    KeyValue::new("lattice", lattice_id.clone()),
+                    KeyValue::new("host", host_id.clone()),
+                ]
+            }
+        };
+
+        self.component_max_instances = Some({
+            let usage = std::sync::Arc::clone(&usage);
+            let attributes = attributes.clone();
+            meter
+                .u64_observable_gauge("wasmcloud_host.component.max_instances")
+                .with_description("Maximum number of concurrent instances of a component")
+                .with_callback(move |gauge| {
+                    for component in usage() {
+                        gauge.observe(component.max_instances, &attributes(&component.id));
+                    }
+                })
+                .build()
+        });
+        self.component_active_instances = Some(
+            meter
+                .u64_observable_gauge("wasmcloud_host.component.active_instances")
+                .with_description(
+                    "Number of instances of a component currently handling invocations",
+                )
+                .with_callback(move |gauge| {
+                    for component in usage() {
+                        gauge.observe(component.active_instances, &attributes(&component.id));
+                    }
+                })
+                .build(),
+        );
+        self
+    }
+
+    /// Register a gauge reporting the number of long-lived tasks running on the host by owner, as returned by `usage`.
+    #[must_use]
+    pub(crate) fn with_task_gauges(
+        mut self,
+        meter: &Meter,
+        usage: impl Fn() -> Vec<TaskUsage> + Send + Sync + 'static,
+    ) -> Self {
+        let host_id = self.host_id.clone();
+        let lattice_id = self.lattice_id.clone();
+        self.active_tasks = Some(
+            meter
+                .u64_observable_gauge("wasmcloud_host.tasks.active")
+                .with_description("Number of long-lived tasks running on the host by owner")
+                .with_callback(move |gauge| {
+                    for tasks in usage() {
+                        gauge.observe(
+                            tasks.active_tasks,
+                            &[
+                                KeyValue::new("task.owner", tasks.owner),
+                                KeyValue::new("task.owner.id", tasks.owner_id),
+                                KeyValue::new("lattice", lattice_id.clone()),
+                                KeyValue::new("host", host_id.clone()),
+                            ],
+                        );
+                    }
+                })
+                .build(),
+        );
+        self
+    }
+
+    /// Register gauges reporting the state of the scheduler of the async runtime `runtime`.
+    #[must_use]
+    pub(crate) fn with_scheduler_gauges(
+        mut self,
+        meter: &Meter,
+        runtime: tokio::runtime::Handle,
+    ) -> Self {
+        let attributes = [
+            KeyValue::new("lattice", self.lattice_id.clone()),
+            KeyValue::new("host", self.host_id.clone()),
+        ];
+        self.scheduler_workers = Some({
+            let runtime = runtime.clone();
+            let attributes = attributes.clone();
+            meter
+                .u64_observable_gauge("wasmcloud_host.scheduler.workers")
+                .with_description("Number of worker threads of the host's async runtime")
+                .with_callback(move |gauge| {
+                    let workers = runtime.metrics().num_workers();
+                    gauge.observe(u64::try_from(workers).unwrap_or(u64::MAX), &attributes);
+                })
+                .build()
+        });
+        self.scheduler_alive_tasks = Some({
+            let runtime = runtime.clone();
+            let attributes = attributes.clone();
+            meter
+                .u64_observable_gauge("wasmcloud_host.scheduler.alive_tasks")
+                .with_description("Number of tasks alive in the host's async runtime")
+                .with_callback(move |gauge| {
+                    let tasks = runtime.metrics().num_alive_tasks();
+                    gauge.observe(u64::try_from(tasks).unwrap_or(u64::MAX), &attributes);
+                })
+                .build()
+        });
+        self.scheduler_global_queue_depth = Some(
+            meter
+                .u64_observable_gauge("wasmcloud_host.scheduler.global_queue_depth")
+                .with_description(
+                    "Number of tasks scheduled in the global queue of the host's async runtime",
+                )
+                .with_callback(move |gauge| {
+                    let depth = runtime.metrics().global_queue_depth();
+                    gauge.observe(u64::try_from(depth).unwrap_or(u64::MAX), &attributes);
+                })
+                .build(),
+        );
+        self
+    }
+
+    /// Register a gauge reporting the number of entries of each cache of the host, as returned by `usage`.
+    #[must_use]
+    pub(crate) fn with_cache_gauges(
+        mut self,
+        meter: &Meter,
+        usage: impl Fn() -> Vec<CacheUsage> + Send + Sync + 'static,
+    ) -> Self {
+        let host_id = self.host_id.clone();
+        let lattice_id = self.lattice_id.clone();
+        self.cache_entries = Some(
+            meter
+                .u64_observable_gauge("wasmcloud_host.cache.entries")
+                .with_description("Number of entries in a cache of the host")
+                .with_callback(move |gauge| {
+                    for cache in usage() {
+                        gauge.observe(
+                            cache.entries,
+                            &[
+                                KeyValue::new("cache", cache.cache),
+                                KeyValue::new("lattice", lattice_id.clone()),
+                                KeyValue::new("host", host_id.clone()),
+                            ],
+                        );
+                    }
+                })
+                .build(),
+        );
+        self
+    }
+
     /// Record the result of invoking a component, including the elapsed time, any attributes, and whether the invocation resulted in an error.
     pub(crate) fn record_component_invocation(
         &self,
@@ -241,6 +241,14 @@ pub struct Manager {
 }
 
 impl Manager {
+    /// Returns the number of cached policy decisions, or `None` if the cache is being modified
+    pub(crate) fn cached_decisions(&self) -> Option<usize> {
+        self.decision_cache
+            .try_read()
+            .ok()
+            .map(|decisions| decisions.len())
+    }
+
     /// Construct a new policy manager. Can fail if policy_changes_topic is set but we fail to subscribe to it
     #[instrument(skip(nats))]
     pub async fn new(
@@ -13,6 +13,10 @@ use wasmcloud_runtime::capability::secrets::store::SecretValue;
 use wasmcloud_secrets_client::Client as WasmcloudSecretsClient;
 use wasmcloud_secrets_types::{Secret as WasmcloudSecret, SecretConfig};
 
+pub mod kubernetes;
+
+pub use kubernetes::{Access as KubernetesAccess, Config as KubernetesConfig};
+
 #[derive(Debug)]
 /// A manager for fetching secrets from a secret store, caching secrets clients for efficiency.
 pub struct Manager {
@@ -22,14 +26,17 @@ pub struct Manager {
     nats_client: Client,
     /// A map of backend names, e.g. nats-kv or vault, to secrets clients, used to cache clients for efficiency.
     backend_clients: Arc<RwLock<HashMap<String, Arc<WasmcloudSecretsClient>>>>,
+    /// The built-in backend resolving secrets from Kubernetes Secrets, if enabled
+    kubernetes: Option<Arc<kubernetes::Backend>>,
 }
 
 impl Manager {
     /// Create a new secret manager with the given configuration store, secret store topic, and NATS client.
     ///
     /// All secret references will be fetched from this configuration store and the actual secrets will be
-    /// fetched by sending requests to the configured topic. If the provided secret_store_topic is None, this manager
-    /// will always return an error if [`Self::fetch_secrets`] is called with a list of secrets.
+    /// fetched by sending requests to the configured topic. If the provided secret_store_topic is None and the
+    /// Kubernetes backend is not enabled with [`Self::with_kubernetes_backend`], this manager will always return an
+    /// error if [`Self::fetch_secrets`] is called with a list of secrets.
     pub fn new(
         config_store: &Store,
         secret_store_topic: Option<&String>,
@@ -40,9 +47,31 @@ impl Manager {
             secret_store_topic: secret_store_topic.cloned(),
             nats_client: nats_client.clone(),
             backend_clients: Arc::new(RwLock::new(HashMap::new())),
+            kubernetes: None,
         }
     }
 
+    /// Enable the built-in backend resolving secret references with the
+    /// [`kubernetes::BACKEND`] backend from Kubernetes Secrets.
+    ///
+    /// Returns an error if the backend could not be configured, e.g. if the API is enabled but
+    /// the host is not running in a Kubernetes pod.
+    pub async fn with_kubernetes_backend(
+        mut self,
+        config: &KubernetesConfig,
+    ) -> anyhow::Result<Self> {
+        let backend = kubernetes::Backend::new(config)
+            .await
+            .context("failed to configure Kubernetes secrets backend")?;
+        self.kubernetes = Some(Arc::new(backend));
+        Ok(self)
+    }
+
+    /// Returns `true` if any secrets backend is configured
+    pub fn is_configured(&self) -> bool {
+        self.secret_store_topic.is_some() || self.kubernetes.is_some()
+    }
+
     /// Get the secrets client for the provided backend, creating a new client if one does not already exist.
     ///
     /// Returns an error if the secret store topic is not configured, or if the client could not be created.
@@ -88,7 +117,7 @@ impl Manager {
     /// # Arguments
     /// * `secret_names` - A list of secret names to fetch from the secret store
     /// * `entity_jwt` - The JWT of the entity requesting the secrets. Must be provided unless this [`Manager`] is not
-    ///  configured with a secret store topic.
+    ///  configured with any secrets backend.
     /// * `host_jwt` - The JWT of the host requesting the secrets
     /// * `application` - The name of the application the entity is a part of, if any
     ///
@@ -107,15 +136,12 @@ impl Manager {
             return Ok(HashMap::with_capacity(0));
         }
 
-        // Attempting to fetch secrets without a secret store topic is always an error
+        // Attempting to fetch secrets without any secrets backend is always an error
         ensure!(
-            self.secret_store_topic.is_some(),
+            self.is_configured(),
             "secret store not configured, could not fetch secrets"
         );
 
-        // If we don't have an entity JWT, we can't provide its identity to the secrets backend
-        let entity_jwt = entity_jwt.context("entity did not have an embedded JWT, required to fetch secrets (was this entity signed during build?)")?;
-
         let secrets = stream::iter(secret_names.into_iter())
             // Fetch the secret reference from the config store
             .then(|secret_name| async move {
@@ -130,11 +156,21 @@ impl Manager {
             })
             // Retrieve the actual secret from the secrets backend
             .and_then(|secret_config| async move {
-                let secrets_client = self
-                    .get_or_create_secrets_client(&secret_config.backend)
-                    .await?;
+                // If we don't have an entity JWT, we can't provide its identity to the secrets backend
+                let entity_jwt = entity_jwt.context("entity did not have an embedded JWT, required to fetch secrets (was this entity signed during build?)")?;
                 let secret_name = secret_config.name.clone();
+                let backend = secret_config.backend.clone();
                 let request = secret_config.try_into_request(entity_jwt, host_jwt, application).context("failed to create secret request")?;
+                if let (kubernetes::BACKEND, Some(kubernetes_backend)) = (backend.as_str(), &self.kubernetes) {
+                    let secret = kubernetes_backend
+                        .get(request)
+                        .await
+                        .with_context(|| format!("failed to resolve secret {secret_name}"))?;
+                    return Ok((secret_name, secret));
+                }
+                let secrets_client = self
+                    .get_or_create_secrets_client(&backend)
+                    .await?;
                 secrets_client
                     .get(request, nkeys::XKey::new())
                     .await
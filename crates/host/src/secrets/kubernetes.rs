@@ -0,0 +1,481 @@
+//! Built-in secrets backend resolving secret references from Kubernetes Secrets
+//!
+//! Secret references with the backend [`BACKEND`] are resolved by the host itself, instead of by a
+//! secrets backend reachable over NATS, so Kubernetes users do not have to mirror their Secrets
+//! into the lattice. The `key` of a reference names the Kubernetes Secret and its `field` the key
+//! within the data of the Secret. The field can be omitted for Secrets with a single key.
+//!
+//! Secrets are first looked up in [`Config::mount_dir`], in which Kubernetes Secrets are mounted
+//! as volumes of the host pod, each in a directory named after the Secret. Secrets not mounted are
+//! read from the Kubernetes API with the service account of the host pod, if enabled with
+//! [`Config::api`]. The service account must be allowed to `get` the Secrets.
+//!
+//! Like secrets requested from backends over NATS, requests are only served for entities with a
+//! valid JWT signed at build time and a valid host JWT. Each Kubernetes Secret must additionally be
+//! listed in [`Config::access`], which allows individual entities, or all entities of an
+//! application, to read it. Secrets not listed cannot be read by any entity.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::env;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, ensure, Context as _};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use serde::Deserialize;
+use tokio::fs;
+use tracing::{debug, instrument};
+use wascap::jwt::{CapabilityProvider, Claims, Component};
+use wasmcloud_secrets_types::{
+    Context as SecretContext, GetSecretError, Secret as WasmcloudSecret, SecretRequest,
+};
+
+/// Name of the backend in secret references resolved from Kubernetes Secrets
+pub const BACKEND: &str = "kubernetes";
+
+/// Directory the service account credentials are mounted in by Kubernetes
+const SERVICE_ACCOUNT_DIR: &str = "/var/run/secrets/kubernetes.io/serviceaccount";
+
+/// Configuration of the Kubernetes Secrets backend
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+    /// Directory in which Kubernetes Secrets are mounted, each in a directory named after the
+    /// Secret
+    pub mount_dir: Option<PathBuf>,
+    /// Whether Secrets, which are not mounted, are read from the Kubernetes API with the service
+    /// account of the host pod
+    pub api: bool,
+    /// Namespace to read Secrets from the Kubernetes API in, defaults to the namespace of the
+    /// service account
+    pub namespace: Option<String>,
+    /// Entities and applications allowed to read each Kubernetes Secret, by Secret name
+    pub access: HashMap<String, Access>,
+}
+
+/// Entities and applications allowed to read a Kubernetes Secret
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Access {
+    /// Public keys of the components and providers allowed to read the Secret
+    pub entities: HashSet<String>,
+    /// Names of the applications, whose components and providers are allowed to read the Secret
+    pub applications: HashSet<String>,
+}
+
+/// Resolves secret references from Kubernetes Secrets
+#[derive(Debug)]
+pub(crate) struct Backend {
+    mount_dir: Option<PathBuf>,
+    api: Option<ApiClient>,
+    access: HashMap<String, Access>,
+}
+
+/// Client of the Kubernetes API authenticated with the service account of the host pod
+#[derive(Debug)]
+struct ApiClient {
+    http: reqwest::Client,
+    /// Base URL of the Kubernetes API server
+    url: String,
+    namespace: String,
+}
+
+/// Subset of a Kubernetes Secret returned by the API
+#[derive(Deserialize)]
+struct ApiSecret {
+    #[serde(default)]
+    data: BTreeMap<String, String>,
+    #[serde(default)]
+    metadata: ApiMetadata,
+}
+
+#[derive(Default, Deserialize)]
+struct ApiMetadata {
+    #[serde(default, rename = "resourceVersion")]
+    resource_version: Option<String>,
+}
+
+impl Backend {
+    /// Creates a new backend, loading the service account credentials if the API is enabled
+    pub(crate) async fn new(
+        Config {
+            mount_dir,
+            api,
+            namespace,
+            access,
+        }: &Config,
+    ) -> anyhow::Result<Self> {
+        ensure!(
+            mount_dir.is_some() || *api,
+            "Kubernetes secrets backend requires a mount directory or API access"
+        );
+        let api = if *api {
+            Some(
+                ApiClient::new(namespace.clone())
+                    .await
+                    .context("failed to configure Kubernetes API client")?,
+            )
+        } else {
+            None
+        };
+        Ok(Self {
+            mount_dir: mount_dir.clone(),
+            api,
+            access: access.clone(),
+        })
+    }
+
+    /// Resolves `field` of the Kubernetes Secret named by the `key` of `request`, if the entity
+    /// requesting it is allowed to read it
+    #[instrument(level = "debug", skip_all, fields(name = %request.key))]
+    pub(crate) async fn get(&self, request: SecretRequest) -> anyhow::Result<WasmcloudSecret> {
+        let SecretRequest {
+            key: name,
+            field,
+            context,
+            ..
+        } = request;
+        validate_name(&name)?;
+        let field = field.as_deref();
+        if let Some(field) = field {
+            validate_field(field)?;
+        }
+        self.authorize(&name, &context)?;
+        let name = name.as_str();
+        if let Some(mount_dir) = &self.mount_dir {
+            if let Some(secret) = read_mounted(&mount_dir.join(name), name, field).await? {
+                return Ok(secret);
+            }
+        }
+        let Some(api) = &self.api else {
+            bail!("Kubernetes Secret `{name}` is not mounted in the host");
+        };
+        api.get(name, field).await
+    }
+
+    /// Validates the entity and host JWTs of `context`, as the NATS secrets backends do, and checks
+    /// that the entity is allowed to read the Secret named `name`
+    fn authorize(&self, name: &str, context: &SecretContext) -> anyhow::Result<()> {
+        context
+            .valid_claims()
+            .map_err(|err| GetSecretError::InvalidEntityJWT(err.to_string()))?;
+        let entity = entity_subject(&context.entity_jwt)?;
+        let allowed = self.access.get(name).is_some_and(|access| {
+            access.entities.contains(&entity)
+                || context
+                    .application
+                    .name
+                    .as_ref()
+                    .is_some_and(|application| access.applications.contains(application))
+        });
+        if !allowed {
+            return Err(anyhow!(GetSecretError::Unauthorized).context(format!(
+                "entity `{entity}` is not allowed to read Kubernetes Secret `{name}`"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Returns the public key of the component or provider `jwt` was issued for
+fn entity_subject(jwt: &str) -> anyhow::Result<String> {
+    match Claims::<Component>::decode(jwt) {
+        Ok(claims) => Ok(claims.subject),
+        Err(_) => Claims::<CapabilityProvider>::decode(jwt)
+            .map(|claims| claims.subject)
+            .map_err(|err| GetSecretError::InvalidEntityJWT(err.to_string()).into()),
+    }
+}
+
+impl ApiClient {
+    async fn new(namespace: Option<String>) -> anyhow::Result<Self> {
+        let host = env::var("KUBERNETES_SERVICE_HOST")
+            .context("KUBERNETES_SERVICE_HOST is not set, is the host running in Kubernetes?")?;
+        let port = env::var("KUBERNETES_SERVICE_PORT").unwrap_or_else(|_| "443".into());
+        let url = if host.contains(':') {
+            format!("https://[{host}]:{port}")
+        } else {
+            format!("https://{host}:{port}")
+        };
+        let namespace = match namespace {
+            Some(namespace) => namespace,
+            None => fs::read_to_string(Path::new(SERVICE_ACCOUNT_DIR).join("namespace"))
+                .await
+                .context("failed to read service account namespace")?
+                .trim()
+                .to_string(),
+        };
+        let ca = fs::read(Path::new(SERVICE_ACCOUNT_DIR).join("ca.crt"))
+            .await
+            .context("failed to read service account CA certificate")?;
+        let http = reqwest::Client::builder()
+            .add_root_certificate(
+                reqwest::Certificate::from_pem(&ca).context("invalid service account CA")?,
+            )
+            .build()
+            .context("failed to build HTTP client")?;
+        Ok(Self {
+            http,
+            url,
+            namespace,
+        })
+    }
+
+    async fn get(&self, name: &str, field: Option<&str>) -> anyhow::Result<WasmcloudSecret> {
+        // Service account tokens are rotated by Kubernetes, so the token is read on every request
+        let token = fs::read_to_string(Path::new(SERVICE_ACCOUNT_DIR).join("token"))
+            .await
+            .context("failed to read service account token")?;
+        let res = self
+            .http
+            .get(format!(
+                "{}/api/v1/namespaces/{}/secrets/{name}",
+                self.url, self.namespace
+            ))
+            .bearer_auth(token.trim())
+            .send()
+            .await
+            .context("failed to request Secret from Kubernetes API")?;
+        let status = res.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            bail!(
+                "Kubernetes Secret `{name}` not found in namespace `{}`",
+                self.namespace
+            );
+        }
+        ensure!(
+            status.is_success(),
+            "Kubernetes API responded with status {status} to request for Secret `{name}`"
+        );
+        let body = res
+            .bytes()
+            .await
+            .context("failed to read Kubernetes API response")?;
+        let ApiSecret { data, metadata } =
+            serde_json::from_slice(&body).context("failed to parse Kubernetes Secret")?;
+        let (key, value) = select_field(data, name, field)?;
+        let value = STANDARD
+            .decode(value)
+            .with_context(|| format!("invalid value of key `{key}` in Secret `{name}`"))?;
+        debug!(name, key, "resolved secret from Kubernetes API");
+        Ok(secret(value, metadata.resource_version.unwrap_or_default()))
+    }
+}
+
+/// Reads `field` of the Secret mounted in `dir`, returning `None` if the Secret is not mounted
+async fn read_mounted(
+    dir: &Path,
+    name: &str,
+    field: Option<&str>,
+) -> anyhow::Result<Option<WasmcloudSecret>> {
+    if !fs::try_exists(dir).await.unwrap_or(false) {
+        return Ok(None);
+    }
+    let mut entries = fs::read_dir(dir)
+        .await
+        .with_context(|| format!("failed to read mounted Secret `{name}`"))?;
+    let mut keys = BTreeMap::new();
+    while let Some(entry) = entries.next_entry().await? {
+        // Kubernetes manages the files of the volume through hidden `..data` directories
+        let Some(key) = entry.file_name().to_str().map(ToString::to_string) else {
+            continue;
+        };
+        if !key.starts_with('.') {
+            keys.insert(key, entry.path());
+        }
+    }
+    let (key, path) = select_field(keys, name, field)?;
+    let value = fs::read(&path)
+        .await
+        .with_context(|| format!("failed to read key `{key}` of mounted Secret `{name}`"))?;
+    debug!(name, key, "resolved secret from mounted Kubernetes Secret");
+    Ok(Some(secret(value, String::new())))
+}
+
+/// Selects `field` from the `data` of the Secret named `name`, or its only key if no field is
+/// given
+fn select_field<T>(
+    mut data: BTreeMap<String, T>,
+    name: &str,
+    field: Option<&str>,
+) -> anyhow::Result<(String, T)> {
+    if let Some(field) = field {
+        return data
+            .remove_entry(field)
+            .with_context(|| format!("Kubernetes Secret `{name}` has no key `{field}`"));
+    }
+    ensure!(
+        data.len() == 1,
+        "Kubernetes Secret `{name}` has {} keys, the secret reference must specify a field",
+        data.len()
+    );
+    data.pop_first()
+        .with_context(|| format!("Kubernetes Secret `{name}` is empty"))
+}
+
+/// Returns a string secret if `value` is valid UTF-8 and a binary secret otherwise
+fn secret(value: Vec<u8>, version: String) -> WasmcloudSecret {
+    match String::from_utf8(value) {
+        Ok(value) => WasmcloudSecret {
+            version,
+            string_secret: Some(value),
+            binary_secret: None,
+        },
+        Err(err) => WasmcloudSecret {
+            version,
+            string_secret: None,
+            binary_secret: Some(err.into_bytes()),
+        },
+    }
+}
+
+/// Validates that `name` is a valid Kubernetes Secret name, which cannot escape the mount
+/// directory or API path
+fn validate_name(name: &str) -> anyhow::Result<()> {
+    ensure!(
+        !name.is_empty()
+            && name.len() <= 253
+            && name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '.')
+            && !name.starts_with('.'),
+        "invalid Kubernetes Secret name `{name}`"
+    );
+    Ok(())
+}
+
+/// Validates that `field` is a valid key of the data of a Kubernetes Secret
+fn validate_field(field: &str) -> anyhow::Result<()> {
+    ensure!(
+        !field.is_empty()
+            && field
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+            && !field.starts_with(".."),
+        "invalid Kubernetes Secret key `{field}`"
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use wascap::jwt::{ClaimsBuilder, Host};
+    use wascap::prelude::KeyPair;
+    use wasmcloud_secrets_types::Application;
+
+    use super::*;
+
+    /// Returns a request for Secret `name` by a new component of `application`, and the public key
+    /// of the component
+    fn request(name: &str, application: Option<&str>) -> anyhow::Result<(SecretRequest, String)> {
+        let account = KeyPair::new_account();
+        let component = KeyPair::new_module();
+        let entity: Claims<Component> = ClaimsBuilder::new()
+            .issuer(&account.public_key())
+            .subject(&component.public_key())
+            .build();
+        let host: Claims<Host> = ClaimsBuilder::new()
+            .issuer(&account.public_key())
+            .subject(&KeyPair::new_server().public_key())
+            .with_metadata(Host::new("test".into(), HashMap::new()))
+            .build();
+        let request = SecretRequest {
+            key: name.into(),
+            field: None,
+            version: None,
+            context: SecretContext {
+                entity_jwt: entity.encode(&account)?,
+                host_jwt: host.encode(&account)?,
+                application: Application {
+                    name: application.map(Into::into),
+                    policy: String::new(),
+                },
+            },
+        };
+        Ok((request, component.public_key()))
+    }
+
+    fn is_unauthorized(err: &anyhow::Error) -> bool {
+        matches!(
+            err.downcast_ref::<GetSecretError>(),
+            Some(GetSecretError::Unauthorized)
+        )
+    }
+
+    #[tokio::test]
+    async fn secret_access() -> anyhow::Result<()> {
+        let (allowed, entity) = request("db", None)?;
+        let (by_application, _) = request("db", Some("shop"))?;
+        let (other_application, _) = request("db", Some("blog"))?;
+        let (unlisted, _) = request("api", Some("shop"))?;
+        let (mut invalid, _) = request("db", Some("shop"))?;
+        invalid.context.entity_jwt = "not a jwt".into();
+
+        let backend = Backend {
+            mount_dir: None,
+            api: None,
+            access: HashMap::from([(
+                "db".into(),
+                Access {
+                    entities: HashSet::from([entity]),
+                    applications: HashSet::from(["shop".into()]),
+                },
+            )]),
+        };
+        // Allowed requests get past authorization, but the Secret is neither mounted nor read
+        // from the API
+        for request in [allowed, by_application] {
+            let err = backend
+                .get(request)
+                .await
+                .expect_err("Secret is not mounted");
+            assert!(!is_unauthorized(&err), "{err:#}");
+        }
+        for request in [other_application, unlisted] {
+            let err = backend
+                .get(request)
+                .await
+                .expect_err("access should be denied");
+            assert!(is_unauthorized(&err), "{err:#}");
+        }
+        let err = backend
+            .get(invalid)
+            .await
+            .expect_err("invalid JWT should be denied");
+        assert!(
+            matches!(
+                err.downcast_ref::<GetSecretError>(),
+                Some(GetSecretError::InvalidEntityJWT(_))
+            ),
+            "{err:#}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn secret_fields() -> anyhow::Result<()> {
+        let data = BTreeMap::from([("password".to_string(), 1), ("username".to_string(), 2)]);
+        assert_eq!(
+            select_field(data.clone(), "db", Some("password"))?,
+            ("password".into(), 1)
+        );
+        assert!(select_field(data.clone(), "db", Some("token")).is_err());
+        assert!(select_field(data, "db", None).is_err());
+        assert_eq!(
+            select_field(BTreeMap::from([("token".to_string(), 3)]), "api", None)?,
+            ("token".into(), 3)
+        );
+
+        validate_name("db-credentials.v1")?;
+        for name in ["", "../etc", "db/credentials", "DB", ".."] {
+            assert!(validate_name(name).is_err(), "{name}");
+        }
+        validate_field("tls.crt")?;
+        for field in ["", "..data", "a/b"] {
+            assert!(validate_field(field).is_err(), "{field}");
+        }
+
+        let secret = secret(vec![0xff, 0x00], "1".into());
+        assert_eq!(secret.binary_secret, Some(vec![0xff, 0x00]));
+        assert_eq!(secret.string_secret, None);
+        Ok(())
+    }
+}
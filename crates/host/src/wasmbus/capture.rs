@@ -0,0 +1,568 @@
+//! Sampled capture of invocation payloads for debugging
+//!
+//! Captures are put through the control interface on a specific host, for a component running on
+//! it. Of every `sample_rate` invocations the component sends over the captured link, one payload
+//! is stored with redactions applied in a bounded buffer, which drops the oldest payloads first.
+//! Captured payloads are only kept in memory and can be retrieved through the control interface
+//! until the capture is deleted or the component is stopped.
+//!
+//! Payloads are the wRPC-encoded parameters of invocations. Redaction rules apply to each
+//! `string` and `list<u8>` parameter value, which is re-encoded with its redacted length, so that
+//! redacted payloads can still be decoded. Payloads of functions with unknown parameter types or
+//! parameters, which cannot be decoded, are redacted as a whole.
+//!
+//! Values of JSON payloads are redacted with JSONPath expressions supporting child (`.name`,
+//! `['name']`), index (`[0]`), wildcard (`.*`, `[*]`) and descendant (`..name`) selectors, and
+//! any payload is redacted with regular expressions, which are matched against its raw bytes.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, ensure, Context as _};
+use regex::bytes::Regex;
+use serde_json::Value;
+use tracing::{debug, warn};
+use wasmcloud_control_interface::{CapturedPayload, PayloadCapture};
+use wasmcloud_runtime::component::{encoded_bytes, EncodedBytes, Schema};
+
+/// Replacement for the parts of captured payloads matching a redaction rule
+const REDACTED: &str = "[REDACTED]";
+
+/// Payload captures keyed by the ID of the source component
+pub(crate) type PayloadCaptures = HashMap<Box<str>, Arc<Capture>>;
+
+/// Parameter schemas of the functions imported by a component, by instance and function name
+pub(crate) type ImportParams = HashMap<Box<str>, HashMap<Box<str>, Vec<(String, Schema)>>>;
+
+/// Selector of a [`JsonPath`] segment
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Selector {
+    /// Member of an object
+    Name(String),
+    /// Element of an array
+    Index(usize),
+    /// All members of an object or elements of an array
+    Wildcard,
+}
+
+/// Segment of a [`JsonPath`], selecting children or, if `descendant`, descendants of a value
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Segment {
+    descendant: bool,
+    selector: Selector,
+}
+
+/// A JSONPath expression selecting values of JSON payloads to redact
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct JsonPath(Vec<Segment>);
+
+impl core::str::FromStr for JsonPath {
+    type Err = anyhow::Error;
+
+    fn from_str(path: &str) -> anyhow::Result<Self> {
+        let Some(mut rest) = path.strip_prefix('$') else {
+            bail!("JSONPath must start with `$`");
+        };
+        let mut segments = Vec::new();
+        while !rest.is_empty() {
+            let descendant = rest.starts_with("..");
+            if descendant {
+                rest = &rest[2..];
+            } else if let Some(r) = rest.strip_prefix('.') {
+                rest = r;
+            } else if !rest.starts_with('[') {
+                bail!("unexpected `{rest}`");
+            }
+            let selector = if let Some(r) = rest.strip_prefix('[') {
+                let (selector, r) = r.split_once(']').context("unterminated `[`")?;
+                rest = r;
+                match selector.trim() {
+                    "*" => Selector::Wildcard,
+                    selector
+                        if selector.len() >= 2
+                            && (selector.starts_with('\'') && selector.ends_with('\'')
+                                || selector.starts_with('"') && selector.ends_with('"')) =>
+                    {
+                        Selector::Name(selector[1..selector.len() - 1].into())
+                    }
+                    selector => Selector::Index(
+                        selector
+                            .parse()
+                            .with_context(|| format!("invalid selector `[{selector}]`"))?,
+                    ),
+                }
+            } else {
+                let end = rest.find(['.', '[']).unwrap_or(rest.len());
+                let (name, r) = rest.split_at(end);
+                ensure!(!name.is_empty(), "missing member name");
+                rest = r;
+                if name == "*" {
+                    Selector::Wildcard
+                } else {
+                    Selector::Name(name.into())
+                }
+            };
+            segments.push(Segment {
+                descendant,
+                selector,
+            });
+        }
+        Ok(Self(segments))
+    }
+}
+
+impl JsonPath {
+    /// Replace the values selected by the path, returning whether any value was replaced
+    fn redact(&self, value: &mut Value) -> bool {
+        redact_segments(value, &self.0)
+    }
+}
+
+/// Replace the values selected by `segments` within `value`
+fn redact_segments(value: &mut Value, segments: &[Segment]) -> bool {
+    let Some((segment, rest)) = segments.split_first() else {
+        *value = Value::String(REDACTED.into());
+        return true;
+    };
+    let mut redacted = false;
+    if segment.descendant {
+        // Descendant segments also apply to all descendants of the value
+        let children: Vec<&mut Value> = match &mut *value {
+            Value::Object(members) => members.values_mut().collect(),
+            Value::Array(elements) => elements.iter_mut().collect(),
+            _ => Vec::new(),
+        };
+        for child in children {
+            redacted |= redact_segments(child, segments);
+        }
+    }
+    let selected: Vec<&mut Value> = match (&segment.selector, value) {
+        (Selector::Name(name), Value::Object(members)) => {
+            members.get_mut(name).into_iter().collect()
+        }
+        (Selector::Index(i), Value::Array(elements)) => elements.get_mut(*i).into_iter().collect(),
+        (Selector::Wildcard, Value::Object(members)) => members.values_mut().collect(),
+        (Selector::Wildcard, Value::Array(elements)) => elements.iter_mut().collect(),
+        _ => Vec::new(),
+    };
+    for value in selected {
+        redacted |= redact_segments(value, rest);
+    }
+    redacted
+}
+
+/// A [`PayloadCapture`] along with its compiled redaction rules and captured payloads
+#[derive(Debug)]
+pub(crate) struct Capture {
+    capture: PayloadCapture,
+    redactions: Vec<Regex>,
+    json_redactions: Vec<JsonPath>,
+    /// Parameter schemas of the functions invoked by the source component
+    params: ImportParams,
+    invocations: AtomicU64,
+    payloads: Mutex<VecDeque<CapturedPayload>>,
+}
+
+impl TryFrom<PayloadCapture> for Capture {
+    type Error = anyhow::Error;
+
+    fn try_from(capture: PayloadCapture) -> anyhow::Result<Self> {
+        let redactions = capture
+            .redactions()
+            .iter()
+            .map(|rule| {
+                Regex::new(rule).with_context(|| format!("invalid redaction rule `{rule}`"))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        let json_redactions = capture
+            .json_redactions()
+            .iter()
+            .map(|rule| {
+                rule.parse()
+                    .with_context(|| format!("invalid JSONPath redaction rule `{rule}`"))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self {
+            // The buffer grows with the captured payloads, up to the capacity of the capture
+            payloads: Mutex::new(VecDeque::new()),
+            capture,
+            redactions,
+            json_redactions,
+            params: ImportParams::default(),
+            invocations: AtomicU64::default(),
+        })
+    }
+}
+
+impl Capture {
+    /// Sets the parameter schemas of the functions imported by the source component, which are
+    /// used to decode the captured payloads for redaction
+    #[must_use]
+    pub(crate) fn with_params(mut self, params: ImportParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    /// Record the payload of an invocation sent over `link_name`, if the link is captured and the
+    /// invocation is sampled
+    pub(crate) fn record(
+        &self,
+        link_name: &str,
+        target: &str,
+        instance: &str,
+        function: &str,
+        payload: &[u8],
+    ) {
+        if link_name != self.capture.link_name() {
+            return;
+        }
+        let n = self.invocations.fetch_add(1, Ordering::Relaxed);
+        if n % u64::from(self.capture.sample_rate().max(1)) != 0 {
+            return;
+        }
+        let params = self
+            .params
+            .get(instance)
+            .and_then(|funcs| funcs.get(function));
+        let (redacted_payload, redacted) = self.redact(params.map(Vec::as_slice), payload);
+        let captured_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|t| t.as_millis().try_into().unwrap_or(u64::MAX))
+            .unwrap_or_default();
+        let captured = match CapturedPayload::builder()
+            .captured_at(captured_at)
+            .link_name(link_name)
+            .target(target)
+            .instance(instance)
+            .function(function)
+            .size(payload.len())
+            .payload(redacted_payload)
+            .redacted(redacted)
+            .build()
+        {
+            Ok(captured) => captured,
+            Err(err) => {
+                warn!(?err, "failed to build captured payload");
+                return;
+            }
+        };
+        let Ok(mut payloads) = self.payloads.lock() else {
+            warn!("captured payload buffer lock poisoned");
+            return;
+        };
+        while payloads.len() >= self.capture.capacity() {
+            payloads.pop_front();
+        }
+        payloads.push_back(captured);
+    }
+
+    /// Apply all redaction rules to the `string` and `list<u8>` values of a payload of parameters
+    /// with schemas `params`, returning the redacted payload and whether anything was redacted.
+    /// The payload is redacted as a whole, if it cannot be decoded
+    fn redact(&self, params: Option<&[(String, Schema)]>, payload: &[u8]) -> (Vec<u8>, bool) {
+        if self.redactions.is_empty() && self.json_redactions.is_empty() {
+            return (payload.to_vec(), false);
+        }
+        match params.map(|params| encoded_bytes(params, payload)) {
+            Some(Ok(values)) => return self.redact_values(payload, &values),
+            Some(Err(err)) => debug!(%err, "failed to decode captured parameters"),
+            None => debug!("parameters of captured function are unknown"),
+        }
+        self.redact_bytes(payload)
+    }
+
+    /// Redact the `string` and `list<u8>` `values` of a payload, re-encoding the lengths of the
+    /// redacted values
+    fn redact_values(&self, payload: &[u8], values: &[EncodedBytes]) -> (Vec<u8>, bool) {
+        let mut buf = Vec::with_capacity(payload.len());
+        let mut pos = 0;
+        let mut redacted = false;
+        for EncodedBytes { offset, data, .. } in values {
+            let (value, value_redacted) = self.redact_bytes(&payload[data.clone()]);
+            if !value_redacted {
+                continue;
+            }
+            buf.extend_from_slice(&payload[pos..*offset]);
+            write_len(&mut buf, value.len());
+            buf.extend_from_slice(&value);
+            pos = data.end;
+            redacted = true;
+        }
+        buf.extend_from_slice(&payload[pos..]);
+        (buf, redacted)
+    }
+
+    /// Apply all redaction rules to `payload`, returning the redacted payload and whether anything
+    /// was redacted. JSONPath rules only apply to payloads that are valid JSON.
+    fn redact_bytes(&self, payload: &[u8]) -> (Vec<u8>, bool) {
+        let mut payload = payload.to_vec();
+        let mut redacted = false;
+        if !self.json_redactions.is_empty() {
+            if let Ok(mut value) = serde_json::from_slice::<Value>(&payload) {
+                let mut json_redacted = false;
+                for rule in &self.json_redactions {
+                    json_redacted |= rule.redact(&mut value);
+                }
+                if json_redacted {
+                    match serde_json::to_vec(&value) {
+                        Ok(buf) => {
+                            payload = buf;
+                            redacted = true;
+                        }
+                        Err(err) => {
+                            warn!(?err, "failed to encode redacted JSON payload");
+                            payload = REDACTED.into();
+                            redacted = true;
+                        }
+                    }
+                }
+            }
+        }
+        for rule in &self.redactions {
+            if rule.is_match(&payload) {
+                payload = rule.replace_all(&payload, REDACTED.as_bytes()).into_owned();
+                redacted = true;
+            }
+        }
+        (payload, redacted)
+    }
+
+    /// Returns the captured payloads, oldest first
+    pub(crate) fn payloads(&self) -> Vec<CapturedPayload> {
+        self.payloads
+            .lock()
+            .map(|payloads| payloads.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Write the LEB128-encoded length of a `string` or `list<u8>` value to `buf`
+fn write_len(buf: &mut Vec<u8>, mut len: usize) {
+    loop {
+        let byte = (len & 0x7f) as u8;
+        len >>= 7;
+        if len == 0 {
+            buf.push(byte);
+            return;
+        }
+        buf.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capture(sample_rate: u32, capacity: usize) -> Capture {
+        PayloadCapture::builder()
+            .source_id("echo")
+            .sample_rate(sample_rate)
+            .capacity(capacity)
+            .redaction(r"password=\S+")
+            .json_redaction("$..token")
+            .json_redaction("$.users[*]['ssn']")
+            .build()
+            .expect("failed to build payload capture")
+            .try_into()
+            .expect("failed to compile payload capture")
+    }
+
+    #[test]
+    fn sample_and_bound_payloads() {
+        let capture = capture(2, 3);
+        for i in 0..10 {
+            capture.record(
+                "default",
+                "kv",
+                "wasi:keyvalue/store",
+                "get",
+                format!("{i}").as_bytes(),
+            );
+        }
+        // Invocations over other links are not captured
+        capture.record("other", "kv", "wasi:keyvalue/store", "get", b"other");
+        let payloads: Vec<_> = capture
+            .payloads()
+            .into_iter()
+            .map(|p| p.payload().to_vec())
+            .collect();
+        assert_eq!(payloads, [b"4", b"6", b"8"]);
+    }
+
+    #[test]
+    fn redact_payloads() {
+        let capture = capture(1, 10);
+        capture.record(
+            "default",
+            "kv",
+            "wasi:keyvalue/store",
+            "set",
+            b"user=admin password=hunter2",
+        );
+        capture.record("default", "kv", "wasi:keyvalue/store", "get", b"\xffkey");
+        capture.record(
+            "default",
+            "kv",
+            "wasi:keyvalue/store",
+            "set",
+            br#"{"auth":{"token":"abc"},"users":[{"name":"a","ssn":"1"},{"ssn":"2"}],"token":1}"#,
+        );
+        capture.record(
+            "default",
+            "kv",
+            "wasi:keyvalue/store",
+            "set",
+            br#"{"users":[{"name":"a"}],"password=x":true}"#,
+        );
+        let payloads = capture.payloads();
+        assert_eq!(payloads[0].payload(), b"user=admin [REDACTED]");
+        assert!(payloads[0].redacted());
+        assert_eq!(payloads[0].size(), 27);
+        // Payloads are stored as raw bytes
+        assert_eq!(payloads[1].payload(), b"\xffkey");
+        assert!(!payloads[1].redacted());
+        let value: Value = serde_json::from_slice(payloads[2].payload()).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "auth": { "token": "[REDACTED]" },
+                "users": [{ "name": "a", "ssn": "[REDACTED]" }, { "ssn": "[REDACTED]" }],
+                "token": "[REDACTED]",
+            })
+        );
+        assert!(payloads[2].redacted());
+        // Regular expressions apply to JSON payloads without selected values
+        assert_eq!(
+            payloads[3].payload(),
+            br#"{"users":[{"name":"a"}],"[REDACTED]"#
+        );
+        assert!(payloads[3].redacted());
+    }
+
+    #[test]
+    fn redact_encoded_params() {
+        // `wasmcloud:messaging/consumer.publish(msg: broker-message)`
+        let broker_message = Schema::Record(vec![
+            ("subject".into(), Schema::String),
+            ("body".into(), Schema::List(Box::new(Schema::U8))),
+            ("reply-to".into(), Schema::Option(Box::new(Schema::String))),
+        ]);
+        let params = vec![("msg".to_string(), broker_message)];
+        let capture = capture(1, 10).with_params(ImportParams::from([(
+            "wasmcloud:messaging/consumer@0.2.0".into(),
+            HashMap::from([("publish".into(), params.clone())]),
+        )]));
+
+        let body = br#"{"user":"admin","token":"s3cr3t","data":"password=hunter2 x"}"#;
+        let mut payload = vec![6];
+        payload.extend_from_slice(b"events");
+        write_len(&mut payload, body.len());
+        payload.extend_from_slice(body);
+        payload.extend_from_slice(&[1, 5]);
+        payload.extend_from_slice(b"reply");
+        capture.record(
+            "default",
+            "nats",
+            "wasmcloud:messaging/consumer@0.2.0",
+            "publish",
+            &payload,
+        );
+        // Parameters of unknown functions are redacted as a whole
+        capture.record(
+            "default",
+            "nats",
+            "wasmcloud:messaging/consumer@0.2.0",
+            "request",
+            &payload,
+        );
+
+        let payloads = capture.payloads();
+        assert!(payloads[0].redacted());
+        assert_eq!(payloads[0].size(), payload.len());
+        let redacted = payloads[0].payload();
+        // The redacted values are re-encoded, so that the payload can still be decoded
+        let values = encoded_bytes(&params, redacted).expect("redacted payload should decode");
+        let values: Vec<_> = values
+            .into_iter()
+            .map(|EncodedBytes { path, data, .. }| (path, redacted[data].to_vec()))
+            .collect();
+        assert_eq!(values[0], ("msg.subject".to_string(), b"events".to_vec()));
+        assert_eq!(values[1].0, "msg.body");
+        let body: Value = serde_json::from_slice(&values[1].1).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "user": "admin",
+                "token": "[REDACTED]",
+                "data": "[REDACTED] x",
+            })
+        );
+        assert_eq!(
+            values[2],
+            ("msg.reply-to.some".to_string(), b"reply".to_vec())
+        );
+
+        assert!(payloads[1].redacted());
+        assert!(!payloads[1]
+            .payload()
+            .windows(b"hunter2".len())
+            .any(|w| w == b"hunter2"));
+    }
+
+    #[test]
+    fn parse_json_paths() {
+        let path: JsonPath = "$.a['b c'][2]..d.*[*]".parse().unwrap();
+        assert_eq!(
+            path.0,
+            [
+                Segment {
+                    descendant: false,
+                    selector: Selector::Name("a".into())
+                },
+                Segment {
+                    descendant: false,
+                    selector: Selector::Name("b c".into())
+                },
+                Segment {
+                    descendant: false,
+                    selector: Selector::Index(2)
+                },
+                Segment {
+                    descendant: true,
+                    selector: Selector::Name("d".into())
+                },
+                Segment {
+                    descendant: false,
+                    selector: Selector::Wildcard
+                },
+                Segment {
+                    descendant: false,
+                    selector: Selector::Wildcard
+                },
+            ]
+        );
+        assert!("$".parse::<JsonPath>().unwrap().0.is_empty());
+        for invalid in ["a.b", "$.", "$.a[", "$[x]", "$a"] {
+            assert!(invalid.parse::<JsonPath>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn reject_invalid_redactions() {
+        let capture = PayloadCapture::builder()
+            .source_id("echo")
+            .redaction("(")
+            .build()
+            .expect("failed to build payload capture");
+        assert!(Capture::try_from(capture).is_err());
+        let capture = PayloadCapture::builder()
+            .source_id("echo")
+            .json_redaction("password")
+            .build()
+            .expect("failed to build payload capture");
+        assert!(Capture::try_from(capture).is_err());
+    }
+}
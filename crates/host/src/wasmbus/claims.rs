@@ -1,12 +1,27 @@
 //! This module contains structs and logic for managing claims in the host
 
-use std::collections::HashMap;
+use core::time::Duration;
 
-use anyhow::Context as _;
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, ensure, Context as _};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine as _;
+use futures::join;
+use nkeys::KeyPair;
 use serde::{Deserialize, Serialize};
 
 use tracing::{instrument, trace};
 use wascap::{jwt, prelude::ClaimsBuilder};
+use wasmcloud_control_interface::{ClaimsBundle, ClaimsBundleContents};
+
+/// Default maximum age of imported claims bundles
+pub(crate) const DEFAULT_CLAIMS_BUNDLE_MAX_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Maximum time by which claims bundles may be exported ahead of the clock of the host
+const MAX_CLAIMS_BUNDLE_SKEW: Duration = Duration::from_secs(60);
+
 // TODO: remove StoredClaims in #1093
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(untagged)]
@@ -192,6 +207,22 @@ impl From<StoredClaims> for HashMap<String, String> {
     }
 }
 
+#[allow(clippy::implicit_hasher)]
+impl TryFrom<HashMap<String, String>> for StoredClaims {
+    type Error = anyhow::Error;
+
+    fn try_from(mut claims: HashMap<String, String>) -> Result<Self, Self::Error> {
+        // Drop the legacy short keys, which would otherwise be duplicates of the long keys
+        for (alias, field) in [("iss", "issuer"), ("rev", "revision"), ("sub", "subject")] {
+            if claims.contains_key(field) {
+                claims.remove(alias);
+            }
+        }
+        let claims = serde_json::to_value(claims).context("failed to encode claims")?;
+        serde_json::from_value(claims).context("failed to decode claims")
+    }
+}
+
 #[allow(clippy::large_enum_variant)] // Without this clippy complains component is at least 0 bytes while provider is at least 280 bytes. That doesn't make sense
 pub(crate) enum Claims {
     Component(jwt::Claims<jwt::Component>),
@@ -205,6 +236,13 @@ impl Claims {
             Claims::Provider(claims) => &claims.subject,
         }
     }
+
+    pub(crate) fn issuer(&self) -> &str {
+        match self {
+            Claims::Component(claims) => &claims.issuer,
+            Claims::Provider(claims) => &claims.issuer,
+        }
+    }
 }
 
 impl From<StoredClaims> for Claims {
@@ -257,6 +295,19 @@ impl From<StoredClaims> for Claims {
 }
 
 impl super::Host {
+    /// Returns the claims of all components and providers cached by the host
+    pub(crate) async fn cached_claims(&self) -> Vec<HashMap<String, String>> {
+        let (component_claims, provider_claims) =
+            join!(self.component_claims.read(), self.provider_claims.read());
+        let component_claims = component_claims.values().cloned().map(Claims::Component);
+        let provider_claims = provider_claims.values().cloned().map(Claims::Provider);
+        component_claims
+            .chain(provider_claims)
+            .flat_map(StoredClaims::try_from)
+            .map(Into::into)
+            .collect()
+    }
+
     #[instrument(level = "debug", skip_all)]
     pub(crate) async fn store_claims(&self, claims: Claims) -> anyhow::Result<()> {
         match &claims {
@@ -285,6 +336,157 @@ impl super::Host {
             .context("failed to put claims")?;
         Ok(())
     }
+
+    /// Ensures that the `claims` of a component or provider to start are issued by one of the
+    /// configured or imported trusted issuers
+    pub(crate) async fn ensure_trusted_issuer(&self, claims: &Claims) -> anyhow::Result<()> {
+        let imported = self.imported_issuers.read().await;
+        check_trusted_issuer(&self.host_config.trusted_issuers, &imported, claims)
+    }
+
+    /// Stores `issuer` imported with a claims bundle exported from `lattice`, so that all hosts of
+    /// the lattice trust it
+    #[instrument(level = "debug", skip(self))]
+    pub(crate) async fn store_trusted_issuer(
+        &self,
+        issuer: &str,
+        lattice: &str,
+    ) -> anyhow::Result<()> {
+        self.imported_issuers.write().await.insert(issuer.into());
+        self.data
+            .put(format!("ISSUER_{issuer}"), lattice.to_string().into())
+            .await
+            .context("failed to put trusted issuer")?;
+        Ok(())
+    }
+}
+
+/// Checks that `claims` are issued by one of the `configured` issuers or one of the issuers
+/// `imported` with claims bundles. Claims of any issuer are trusted if there are neither
+pub(crate) fn check_trusted_issuer(
+    configured: &[String],
+    imported: &HashSet<String>,
+    claims: &Claims,
+) -> anyhow::Result<()> {
+    if configured.is_empty() && imported.is_empty() {
+        return Ok(());
+    }
+    let issuer = claims.issuer();
+    ensure!(
+        imported.contains(issuer) || configured.iter().any(|trusted| trusted == issuer),
+        "`{}` is issued by `{issuer}`, which is not a trusted issuer",
+        claims.subject()
+    );
+    Ok(())
+}
+
+/// Signs `contents` with the key of the host, producing a [`ClaimsBundle`]
+pub(crate) fn sign_claims_bundle(
+    host_key: &KeyPair,
+    contents: &ClaimsBundleContents,
+) -> anyhow::Result<ClaimsBundle> {
+    let payload =
+        serde_json::to_string(contents).context("failed to serialize claims bundle contents")?;
+    let signature = host_key
+        .sign(payload.as_bytes())
+        .context("failed to sign claims bundle")?;
+    Ok(ClaimsBundle::new(
+        host_key.public_key(),
+        payload,
+        STANDARD.encode(signature),
+    ))
+}
+
+/// Verifies the signature of `bundle` and returns its contents.
+///
+/// The bundle must be signed by one of the keys in `trusted_signers`, so no bundles are accepted
+/// if it is empty.
+pub(crate) fn verify_claims_bundle(
+    bundle: &ClaimsBundle,
+    trusted_signers: &[String],
+) -> anyhow::Result<ClaimsBundleContents> {
+    let signer = bundle.signer();
+    ensure!(
+        !trusted_signers.is_empty(),
+        "no trusted claims bundle signers are configured"
+    );
+    ensure!(
+        trusted_signers.iter().any(|key| key == signer),
+        "claims bundle signer `{signer}` is not trusted"
+    );
+    let key = KeyPair::from_public_key(signer).context("invalid claims bundle signer")?;
+    let signature = STANDARD
+        .decode(bundle.signature())
+        .context("invalid claims bundle signature encoding")?;
+    if key.verify(bundle.payload().as_bytes(), &signature).is_err() {
+        bail!("invalid claims bundle signature");
+    }
+    serde_json::from_str(bundle.payload()).context("invalid claims bundle payload")
+}
+
+/// Checks that the `contents` of a verified claims bundle were exported from one of `lattices` at
+/// most `max_age` before `now`, so that bundles can neither be replayed into other lattices nor
+/// once they are outdated
+pub(crate) fn check_claims_bundle_origin(
+    contents: &ClaimsBundleContents,
+    lattices: &[String],
+    max_age: Duration,
+    now: SystemTime,
+) -> anyhow::Result<()> {
+    let lattice = contents.lattice();
+    ensure!(
+        lattices.iter().any(|trusted| trusted == lattice),
+        "claims bundles exported from lattice `{lattice}` are not trusted"
+    );
+    let exported_at = UNIX_EPOCH
+        .checked_add(Duration::from_secs(contents.exported_at()))
+        .context("invalid claims bundle export time")?;
+    ensure!(
+        exported_at <= now + MAX_CLAIMS_BUNDLE_SKEW,
+        "claims bundle was exported in the future"
+    );
+    let age = now.duration_since(exported_at).unwrap_or_default();
+    ensure!(
+        age <= max_age,
+        "claims bundle was exported {}s ago, which exceeds the maximum age of {}s",
+        age.as_secs(),
+        max_age.as_secs()
+    );
+    Ok(())
+}
+
+/// Validates the issuers in the `contents` of a verified claims bundle, which are trusted by the
+/// lattice the bundle is imported into
+pub(crate) fn claims_bundle_issuers(contents: &ClaimsBundleContents) -> anyhow::Result<&[String]> {
+    for issuer in contents.issuers() {
+        ensure!(
+            issuer.starts_with('A') && KeyPair::from_public_key(issuer).is_ok(),
+            "claims bundle issuer `{issuer}` is not a valid account public key"
+        );
+    }
+    Ok(contents.issuers())
+}
+
+/// Validates all claims in the `contents` of a verified claims bundle, returning them ready to be
+/// stored. Each of the claims must be issued by one of the issuers of the bundle, since only those
+/// are trusted once the bundle is imported
+pub(crate) fn claims_bundle_claims(contents: &ClaimsBundleContents) -> anyhow::Result<Vec<Claims>> {
+    contents
+        .claims()
+        .iter()
+        .map(|claims| {
+            let claims = StoredClaims::try_from(claims.clone())
+                .map(Claims::from)
+                .context("invalid claims in claims bundle")?;
+            let issuer = claims.issuer();
+            ensure!(
+                contents.issuers().iter().any(|trusted| trusted == issuer),
+                "claims of `{}` are issued by `{issuer}`, which is not an issuer of the claims bundle",
+                claims.subject()
+            );
+            Ok(claims)
+        })
+        .collect()
 }
 
 fn deserialize_messy_vec<'de, D: serde::Deserializer<'de>>(
@@ -335,3 +537,161 @@ impl<'de> Deserialize<'de> for MessyVec {
         deserializer.deserialize_any(MessyVecVisitor)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use core::time::Duration;
+
+    use std::collections::{HashMap, HashSet};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use nkeys::KeyPair;
+    use wasmcloud_control_interface::{ClaimsBundle, ClaimsBundleContents};
+
+    use super::{
+        check_claims_bundle_origin, check_trusted_issuer, claims_bundle_claims,
+        claims_bundle_issuers, sign_claims_bundle, verify_claims_bundle, Claims, StoredClaims,
+        StoredComponentClaims, StoredProviderClaims, DEFAULT_CLAIMS_BUNDLE_MAX_AGE,
+    };
+
+    fn component_claims(subject: &str, issuer: &str) -> HashMap<String, String> {
+        StoredClaims::Component(StoredComponentClaims {
+            issuer: issuer.into(),
+            name: "hello".into(),
+            subject: subject.into(),
+            ..Default::default()
+        })
+        .into()
+    }
+
+    #[test]
+    fn test_claims_bundle() {
+        let host_key = KeyPair::new_server();
+        let claims: HashMap<String, String> = StoredClaims::Component(StoredComponentClaims {
+            issuer: "AISSUER".into(),
+            name: "hello".into(),
+            revision: "1".into(),
+            subject: "MHELLO".into(),
+            tags: vec!["a".into(), "b".into()],
+            version: "0.1.0".into(),
+            ..Default::default()
+        })
+        .into();
+        let contents = ClaimsBundleContents::new("default", 1_700_000_000, vec![claims.clone()]);
+        let bundle = sign_claims_bundle(&host_key, &contents).unwrap();
+        assert_eq!(bundle.signer(), host_key.public_key());
+        // Bundles are only accepted from trusted signers
+        assert!(verify_claims_bundle(&bundle, &[]).is_err());
+        assert_eq!(
+            verify_claims_bundle(&bundle, &[host_key.public_key()]).unwrap(),
+            contents
+        );
+        assert!(verify_claims_bundle(&bundle, &[KeyPair::new_server().public_key()]).is_err());
+
+        // Tampering with the payload invalidates the signature
+        let tampered = ClaimsBundle::new(
+            bundle.signer(),
+            bundle.payload().replace("hello", "evil"),
+            bundle.signature(),
+        );
+        assert!(verify_claims_bundle(&tampered, &[host_key.public_key()]).is_err());
+
+        // Claims must be issued by one of the issuers of the bundle
+        assert_eq!(claims_bundle_claims(&contents).unwrap().len(), 1);
+        let untrusted: ClaimsBundleContents = serde_json::from_value(serde_json::json!({
+            "lattice": "default",
+            "issuers": ["AOTHER"],
+            "claims": [claims],
+        }))
+        .unwrap();
+        assert!(claims_bundle_claims(&untrusted).is_err());
+        // Invalid claims reject the whole bundle
+        let invalid = ClaimsBundleContents::new(
+            "default",
+            1_700_000_000,
+            vec![
+                claims.clone(),
+                HashMap::from([("issuer".into(), "AISSUER".into())]),
+            ],
+        );
+        assert!(claims_bundle_claims(&invalid).is_err());
+
+        // Exported claims can be stored again on import
+        let claims = Claims::from(StoredClaims::try_from(claims).unwrap());
+        assert_eq!(claims.subject(), "MHELLO");
+        assert!(matches!(claims, Claims::Component(..)));
+
+        let provider: HashMap<String, String> = StoredClaims::Provider(StoredProviderClaims {
+            issuer: "AISSUER".into(),
+            subject: "VPROVIDER".into(),
+            ..Default::default()
+        })
+        .into();
+        let provider = Claims::from(StoredClaims::try_from(provider).unwrap());
+        assert!(matches!(provider, Claims::Provider(..)));
+    }
+
+    #[test]
+    fn test_claims_bundle_origin() {
+        let now = SystemTime::now();
+        let exported_at = now.duration_since(UNIX_EPOCH).unwrap().as_secs();
+        let lattices = ["default".to_string()];
+        let check = |lattice: &str, exported_at: u64| {
+            let contents = ClaimsBundleContents::new(lattice, exported_at, vec![]);
+            check_claims_bundle_origin(&contents, &lattices, DEFAULT_CLAIMS_BUNDLE_MAX_AGE, now)
+        };
+        assert!(check("default", exported_at).is_ok());
+        assert!(check("default", exported_at - 60 * 60).is_ok());
+        // Bundles exported from other lattices cannot be replayed
+        assert!(check("other", exported_at).is_err());
+        // Outdated bundles and bundles from the future are rejected
+        let max_age = DEFAULT_CLAIMS_BUNDLE_MAX_AGE.as_secs();
+        assert!(check("default", exported_at - max_age - 1).is_err());
+        assert!(check("default", exported_at + 60 * 60).is_err());
+        assert!(check("default", u64::MAX).is_err());
+        let contents = ClaimsBundleContents::new("default", exported_at, vec![]);
+        assert!(check_claims_bundle_origin(&contents, &[], Duration::MAX, now).is_err());
+    }
+
+    #[test]
+    fn test_trusted_issuers() {
+        let trusted = KeyPair::new_account().public_key();
+        let untrusted = KeyPair::new_account().public_key();
+        let claims = |issuer: &str| {
+            Claims::from(StoredClaims::try_from(component_claims("MHELLO", issuer)).unwrap())
+        };
+
+        // Any issuer is trusted until issuers are configured or imported
+        assert!(check_trusted_issuer(&[], &HashSet::new(), &claims(&untrusted)).is_ok());
+        assert!(
+            check_trusted_issuer(&[trusted.clone()], &HashSet::new(), &claims(&trusted)).is_ok()
+        );
+        assert!(
+            check_trusted_issuer(&[trusted.clone()], &HashSet::new(), &claims(&untrusted)).is_err()
+        );
+
+        // Artifacts are verified by the issuers imported with a bundle
+        let contents =
+            ClaimsBundleContents::new("default", 0, vec![component_claims("MHELLO", &trusted)]);
+        let imported: HashSet<String> = claims_bundle_issuers(&contents)
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect();
+        assert!(check_trusted_issuer(&[], &imported, &claims(&trusted)).is_ok());
+        assert!(check_trusted_issuer(&[], &imported, &claims(&untrusted)).is_err());
+
+        // Bundles listing an issuer, which is not a valid account key, are rejected
+        let invalid = contents.clone().with_issuers(["NOTANACCOUNT".to_string()]);
+        assert!(claims_bundle_issuers(&invalid).is_err());
+        // Claims issued by an issuer, which the bundle does not list as trusted, are rejected
+        let unlisted: ClaimsBundleContents = serde_json::from_value(serde_json::json!({
+            "lattice": "default",
+            "issuers": [trusted],
+            "claims": [component_claims("MOTHER", &untrusted)],
+        }))
+        .unwrap();
+        assert!(claims_bundle_issuers(&unlisted).is_ok());
+        assert!(claims_bundle_claims(&unlisted).is_err());
+    }
+}
@@ -0,0 +1,157 @@
+//! Clock skew detection
+//!
+//! Hosts compare the timestamps of the heartbeats of other hosts in the lattice with their own
+//! clock. Skew beyond a threshold silently breaks JWT validity checks and comparisons of
+//! timestamps across hosts, so hosts whose clock is skewed are reported in the inventory, and
+//! changes are published as `host_clock_skew_detected` and `host_clock_skew_resolved` events.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use anyhow::Context as _;
+use serde::Deserialize;
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+/// Default skew between the clocks of hosts above which the skew is reported
+pub(crate) const DEFAULT_CLOCK_SKEW_THRESHOLD: Duration = Duration::from_secs(5);
+
+/// Time after which hosts, which no heartbeat was received from, are forgotten
+const STALE_AFTER: Duration = Duration::from_secs(10 * 60);
+
+/// Transition in the clock skew of a host, which an event should be published for
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ClockSkewChange {
+    Detected { skew_ms: i64 },
+    Resolved,
+}
+
+/// Clock skew of a host, as last observed
+#[derive(Debug)]
+struct Observation {
+    skew_ms: i64,
+    skewed: bool,
+    seen_at: Instant,
+}
+
+/// Clock skew of the hosts in the lattice, as observed from their heartbeats
+#[derive(Debug, Default)]
+pub(crate) struct ClockSkewState(HashMap<String, Observation>);
+
+impl ClockSkewState {
+    /// Record the skew observed for `host_id` at `now`, returning the change in skew, if any
+    pub(crate) fn update(
+        &mut self,
+        host_id: &str,
+        skew_ms: i64,
+        threshold: Duration,
+        now: Instant,
+    ) -> Option<ClockSkewChange> {
+        let skewed = u128::from(skew_ms.unsigned_abs()) > threshold.as_millis();
+        let previous = self.0.insert(
+            host_id.into(),
+            Observation {
+                skew_ms,
+                skewed,
+                seen_at: now,
+            },
+        );
+        self.0
+            .retain(|_, Observation { seen_at, .. }| now.duration_since(*seen_at) < STALE_AFTER);
+        match (previous.is_some_and(|previous| previous.skewed), skewed) {
+            (false, true) => Some(ClockSkewChange::Detected { skew_ms }),
+            (true, false) => Some(ClockSkewChange::Resolved),
+            _ => None,
+        }
+    }
+
+    /// Hosts whose clock is skewed along with their skew in milliseconds, sorted by host ID
+    pub(crate) fn skewed(&self) -> Vec<(&str, i64)> {
+        let mut skewed: Vec<_> = self
+            .0
+            .iter()
+            .filter(|(_, Observation { skewed, .. })| *skewed)
+            .map(|(host_id, Observation { skew_ms, .. })| (host_id.as_str(), *skew_ms))
+            .collect();
+        skewed.sort_unstable();
+        skewed
+    }
+}
+
+/// Parse the ID of the host which published a heartbeat, along with the skew of its clock in
+/// milliseconds relative to `now`, positive if the clock of the host is ahead
+pub(crate) fn heartbeat_skew(
+    heartbeat: &[u8],
+    now: OffsetDateTime,
+) -> anyhow::Result<(String, i64)> {
+    #[derive(Deserialize)]
+    struct Heartbeat {
+        source: String,
+        time: String,
+    }
+
+    let Heartbeat { source, time } =
+        serde_json::from_slice(heartbeat).context("failed to parse heartbeat")?;
+    let time = OffsetDateTime::parse(&time, &Rfc3339).context("failed to parse heartbeat time")?;
+    let skew_ms = i64::try_from((time - now).whole_milliseconds()).unwrap_or(i64::MAX);
+    Ok((source, skew_ms))
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use serde_json::json;
+    use time::OffsetDateTime;
+
+    use super::{heartbeat_skew, ClockSkewChange, ClockSkewState};
+
+    #[test]
+    fn clock_skew() {
+        let threshold = Duration::from_secs(5);
+        let now = Instant::now();
+        let mut state = ClockSkewState::default();
+        assert_eq!(state.update("a", 100, threshold, now), None);
+        assert_eq!(
+            state.update("a", -7000, threshold, now),
+            Some(ClockSkewChange::Detected { skew_ms: -7000 })
+        );
+        assert_eq!(state.update("a", -6000, threshold, now), None);
+        assert_eq!(
+            state.update("b", 9000, threshold, now),
+            Some(ClockSkewChange::Detected { skew_ms: 9000 })
+        );
+        assert_eq!(state.skewed(), [("a", -6000), ("b", 9000)]);
+        assert_eq!(
+            state.update("a", 10, threshold, now),
+            Some(ClockSkewChange::Resolved)
+        );
+        assert_eq!(state.skewed(), [("b", 9000)]);
+
+        // Hosts that stopped sending heartbeats are forgotten
+        let later = now + Duration::from_secs(60 * 60);
+        assert_eq!(state.update("c", 0, threshold, later), None);
+        assert!(state.skewed().is_empty());
+    }
+
+    #[test]
+    fn parse_heartbeat() {
+        let now = OffsetDateTime::parse(
+            "2024-05-01T12:00:00Z",
+            &time::format_description::well_known::Rfc3339,
+        )
+        .unwrap();
+        let heartbeat = json!({
+            "specversion": "1.0",
+            "source": "NHOST",
+            "type": "com.wasmcloud.lattice.host_heartbeat",
+            "time": "2024-05-01T11:59:52.500Z",
+            "data": {},
+        });
+        assert_eq!(
+            heartbeat_skew(&serde_json::to_vec(&heartbeat).unwrap(), now).unwrap(),
+            ("NHOST".into(), -7500)
+        );
+        assert!(heartbeat_skew(b"{}", now).is_err());
+    }
+}
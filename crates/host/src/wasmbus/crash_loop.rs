@@ -0,0 +1,237 @@
+//! Crash loop detection of components
+//!
+//! Detection is opt-in, by configuring a non-zero threshold. A component failing `threshold`
+//! consecutive invocations within `window` by trapping or failing to instantiate, e.g. because a
+//! broken build traps on every invocation, is quarantined: its invocations are rejected without
+//! instantiating it until the quarantine ends. Invocations failing for other reasons, like
+//! invalid parameters or denied policies, are not counted. A component that keeps failing after a quarantine
+//! ended is quarantined again for twice as long, up to [`MAX_QUARANTINE`], while a successful
+//! invocation resets the backoff. Updating a component starts over with a new detector.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use wasmcloud_runtime::component::is_crash;
+
+/// Default number of consecutive failed invocations after which a component is quarantined,
+/// which disables detection
+pub(crate) const DEFAULT_CRASH_LOOP_THRESHOLD: u32 = 0;
+
+/// Default time window in which the consecutive failed invocations must occur
+pub(crate) const DEFAULT_CRASH_LOOP_WINDOW: Duration = Duration::from_secs(60);
+
+/// Default duration of the first quarantine of a component
+pub(crate) const DEFAULT_CRASH_LOOP_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Maximum duration of a quarantine, unless the initial backoff is longer
+const MAX_QUARANTINE: Duration = Duration::from_secs(10 * 60);
+
+/// Quarantine of a component
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Quarantine {
+    /// Error of the last failed invocation
+    pub reason: String,
+    /// Number of consecutive failed invocations which caused the quarantine
+    pub failures: u32,
+    /// Time the quarantine ends
+    pub until: SystemTime,
+}
+
+impl Quarantine {
+    /// Time the quarantine ends, in seconds since Unix epoch
+    pub(crate) fn until_secs(&self) -> u64 {
+        self.until
+            .duration_since(UNIX_EPOCH)
+            .map(|until| until.as_secs())
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Number of consecutive failed invocations
+    failures: u32,
+    /// Time of the first of the consecutive failed invocations
+    first_failure: Option<Instant>,
+    /// Number of consecutive quarantines, which determines the next quarantine duration
+    quarantines: u32,
+    /// End of the current or last quarantine, along with the quarantine
+    quarantine: Option<(Instant, Quarantine)>,
+}
+
+/// Tracks failed invocations of a component to detect crash loops
+#[derive(Debug)]
+pub(crate) struct CrashLoopDetector {
+    threshold: u32,
+    window: Duration,
+    backoff: Duration,
+    state: Mutex<State>,
+}
+
+impl CrashLoopDetector {
+    /// Create a detector quarantining a component for `backoff` after `threshold` consecutive
+    /// failed invocations within `window`. A `threshold` of 0 disables detection
+    pub(crate) fn new(threshold: u32, window: Duration, backoff: Duration) -> Self {
+        Self {
+            threshold,
+            window,
+            backoff,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Returns the active quarantine of the component, if any
+    pub(crate) fn quarantine(&self, now: Instant) -> Option<Quarantine> {
+        let state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+        match &state.quarantine {
+            Some((until, quarantine)) if now < *until => Some(quarantine.clone()),
+            _ => None,
+        }
+    }
+
+    /// Record a successful invocation, resetting the backoff
+    pub(crate) fn record_success(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+        state.failures = 0;
+        state.first_failure = None;
+        state.quarantines = 0;
+    }
+
+    /// Record the failed invocation `err`, if it was caused by the component trapping or failing
+    /// to instantiate. Returns the quarantine if the failure started one
+    pub(crate) fn record_error(&self, err: &anyhow::Error, now: Instant) -> Option<Quarantine> {
+        if !is_crash(err) {
+            return None;
+        }
+        self.record_failure(format!("{err:#}"), now)
+    }
+
+    /// Record a failed invocation with error `reason`, returning the quarantine if the failure
+    /// started one
+    pub(crate) fn record_failure(&self, reason: String, now: Instant) -> Option<Quarantine> {
+        if self.threshold == 0 {
+            return None;
+        }
+        let mut state = self.state.lock().unwrap_or_else(|err| err.into_inner());
+        if matches!(&state.quarantine, Some((until, _)) if now < *until) {
+            // Invocations accepted before the quarantine started may still fail
+            return None;
+        }
+        match state.first_failure {
+            Some(first) if now.duration_since(first) <= self.window => {
+                state.failures = state.failures.saturating_add(1);
+            }
+            _ => {
+                state.failures = 1;
+                state.first_failure = Some(now);
+            }
+        }
+        if state.failures < self.threshold {
+            return None;
+        }
+        let duration = self
+            .backoff
+            .saturating_mul(2u32.saturating_pow(state.quarantines))
+            .min(MAX_QUARANTINE.max(self.backoff));
+        let quarantine = Quarantine {
+            reason,
+            failures: state.failures,
+            until: SystemTime::now() + duration,
+        };
+        state.quarantines = state.quarantines.saturating_add(1);
+        state.failures = 0;
+        state.first_failure = None;
+        state.quarantine = Some((now + duration, quarantine.clone()));
+        Some(quarantine)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn quarantine_after_threshold() {
+        let detector = CrashLoopDetector::new(3, Duration::from_secs(60), Duration::from_secs(30));
+        let now = Instant::now();
+        assert!(detector.record_failure("trap".into(), now).is_none());
+        assert!(detector.record_failure("trap".into(), now).is_none());
+        // A success resets the consecutive failures
+        detector.record_success();
+        assert!(detector.record_failure("trap".into(), now).is_none());
+        assert!(detector.record_failure("trap".into(), now).is_none());
+        let quarantine = detector
+            .record_failure("unreachable".into(), now)
+            .expect("component should be quarantined");
+        assert_eq!(quarantine.reason, "unreachable");
+        assert_eq!(quarantine.failures, 3);
+        assert_eq!(detector.quarantine(now), Some(quarantine));
+        assert!(detector.quarantine(now + Duration::from_secs(29)).is_some());
+        assert!(detector.quarantine(now + Duration::from_secs(30)).is_none());
+    }
+
+    #[test]
+    fn failures_outside_window() {
+        let detector = CrashLoopDetector::new(2, Duration::from_secs(10), Duration::from_secs(30));
+        let now = Instant::now();
+        assert!(detector.record_failure("trap".into(), now).is_none());
+        let later = now + Duration::from_secs(11);
+        assert!(detector.record_failure("trap".into(), later).is_none());
+        assert!(detector.record_failure("trap".into(), later).is_some());
+    }
+
+    #[test]
+    fn backoff() {
+        let backoff = Duration::from_secs(300);
+        let detector = CrashLoopDetector::new(1, Duration::from_secs(60), backoff);
+        let mut now = Instant::now();
+        for expected in [300, 600, 600] {
+            assert!(detector.record_failure("trap".into(), now).is_some());
+            // Failures during a quarantine are ignored
+            assert!(detector.record_failure("trap".into(), now).is_none());
+            assert!(detector
+                .quarantine(now + Duration::from_secs(expected - 1))
+                .is_some());
+            now += Duration::from_secs(expected);
+            assert!(detector.quarantine(now).is_none());
+        }
+        // A success after a quarantine resets the backoff
+        detector.record_success();
+        assert!(detector.record_failure("trap".into(), now).is_some());
+        assert!(detector.quarantine(now + backoff).is_none());
+    }
+
+    #[test]
+    fn count_crashes_only() {
+        let detector = CrashLoopDetector::new(2, Duration::from_secs(60), Duration::from_secs(30));
+        let now = Instant::now();
+        let crash = || {
+            anyhow::anyhow!("memory limit exceeded")
+                .context(wasmcloud_runtime::component::InstantiationError)
+        };
+        assert!(detector.record_error(&crash(), now).is_none());
+        // Invocations failing for other reasons neither count nor reset the failures
+        for _ in 0..10 {
+            assert!(detector
+                .record_error(&anyhow::anyhow!("failed to decode parameters"), now)
+                .is_none());
+        }
+        let quarantine = detector
+            .record_error(&crash(), now)
+            .expect("component should be quarantined");
+        assert_eq!(
+            quarantine.reason,
+            "failed to instantiate component: memory limit exceeded"
+        );
+    }
+
+    #[test]
+    fn disabled() {
+        let detector = CrashLoopDetector::new(0, Duration::from_secs(60), Duration::from_secs(30));
+        let now = Instant::now();
+        for _ in 0..100 {
+            assert!(detector.record_failure("trap".into(), now).is_none());
+        }
+        assert!(detector.quarantine(now).is_none());
+    }
+}
@@ -3,27 +3,36 @@ use core::sync::atomic::Ordering;
 use std::collections::btree_map::Entry as BTreeMapEntry;
 use std::collections::{hash_map, HashMap};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::{anyhow, bail, Context as _};
+use async_nats::jetstream::kv::Operation;
 use bytes::Bytes;
-use futures::join;
+use futures::TryStreamExt as _;
 use serde_json::json;
+use time::format_description::well_known::Rfc3339;
 use tokio::spawn;
 use tokio::time::Instant;
 use tracing::{debug, error, info, instrument, trace, warn};
 use wasmcloud_control_interface::{
-    ComponentAuctionAck, ComponentAuctionRequest, CtlResponse,
+    CapturedPayload, ClaimsBundle, ClaimsBundleContents, ComponentAuctionAck,
+    ComponentAuctionRequest, ConfigRevision, ConfigRollbackRequest, CtlResponse,
     DeleteInterfaceLinkDefinitionRequest, HostInventory, HostLabel, HostLabelIdentifier, Link,
-    ProviderAuctionAck, ProviderAuctionRequest, RegistryCredential, ScaleComponentCommand,
-    StartProviderCommand, StopHostCommand, StopProviderCommand, UpdateComponentCommand,
+    LinkValidation, LinkValidationError, PayloadCapture, ProviderAuctionAck,
+    ProviderAuctionRequest, RegistryCredential, ScaleComponentCommand, StartProviderCommand,
+    StopHostCommand, StopProviderCommand, TrafficSplit, UpdateComponentCommand,
+    UpdateProviderCommand, MAX_PAYLOAD_CAPTURE_CAPACITY,
 };
 use wasmcloud_tracing::context::TraceContextInjector;
 
 use crate::registry::RegistryCredentialExt;
+use crate::wasmbus::capture::Capture;
+use crate::wasmbus::claims::{
+    check_claims_bundle_origin, claims_bundle_claims, claims_bundle_issuers, sign_claims_bundle,
+    verify_claims_bundle,
+};
 use crate::wasmbus::{
-    event, human_friendly_uptime, injector_to_headers, Annotations, Claims, Host, Provider,
-    StoredClaims,
+    event, human_friendly_uptime, injector_to_headers, Annotations, Host, Provider,
 };
 
 /// Implementation for the server-side handling of control interface requests.
@@ -77,6 +86,13 @@ pub(crate) trait ControlInterfaceServer {
         request: StopProviderCommand,
     ) -> anyhow::Result<CtlResponse<()>>;
 
+    /// Handle a request to hot-swap a running provider for a new version. This method should
+    /// return a response indicating success or failure.
+    async fn handle_update_provider(
+        self: Arc<Self>,
+        request: UpdateProviderCommand,
+    ) -> anyhow::Result<CtlResponse<()>>;
+
     /// Handle a request to get the host inventory. This method should return a response containing
     /// the host inventory.
     async fn handle_inventory(&self) -> anyhow::Result<CtlResponse<HostInventory>>;
@@ -85,6 +101,14 @@ pub(crate) trait ControlInterfaceServer {
     /// a response containing the claims.
     async fn handle_claims(&self) -> anyhow::Result<CtlResponse<Vec<HashMap<String, String>>>>;
 
+    /// Handle a request to export the claims for all components and providers as a bundle signed by
+    /// the host. This method should return a response containing the signed bundle.
+    async fn handle_claims_export(&self) -> anyhow::Result<CtlResponse<ClaimsBundle>>;
+
+    /// Handle a request to import the claims of a signed bundle exported from another lattice. This
+    /// method should return a response indicating success or failure.
+    async fn handle_claims_import(&self, bundle: ClaimsBundle) -> anyhow::Result<CtlResponse<()>>;
+
     /// Handle a request to get the links for all components. This method should return a response containing
     /// the links.
     async fn handle_links(&self) -> anyhow::Result<Vec<u8>>;
@@ -97,6 +121,21 @@ pub(crate) trait ControlInterfaceServer {
     /// indicating success or failure.
     async fn handle_config_delete(&self, config_name: &str) -> anyhow::Result<CtlResponse<()>>;
 
+    /// Handle a request to get the retained revisions of the configuration for a specific key. This method
+    /// should return a response containing the revisions, oldest first.
+    async fn handle_config_history(
+        &self,
+        config_name: &str,
+    ) -> anyhow::Result<CtlResponse<Vec<ConfigRevision>>>;
+
+    /// Handle a request to restore the configuration for a specific key to a previous revision. This method
+    /// should return a response indicating success or failure.
+    async fn handle_config_rollback(
+        &self,
+        config_name: &str,
+        request: ConfigRollbackRequest,
+    ) -> anyhow::Result<CtlResponse<()>>;
+
     /// Handle a request to put a label on the host. This method should return a response indicating success
     /// or failure.
     async fn handle_label_put(
@@ -117,6 +156,13 @@ pub(crate) trait ControlInterfaceServer {
     /// or failure.
     async fn handle_link_put(&self, request: Link) -> anyhow::Result<CtlResponse<()>>;
 
+    /// Handle a request to validate a link with the providers running on this host, without putting the
+    /// link. This method should return the errors found by the providers.
+    async fn handle_link_validate(
+        &self,
+        request: Link,
+    ) -> anyhow::Result<CtlResponse<LinkValidation>>;
+
     /// Handle a request to delete a link from a component. This method should return a response indicating success
     /// or failure.
     async fn handle_link_del(
@@ -124,6 +170,39 @@ pub(crate) trait ControlInterfaceServer {
         request: DeleteInterfaceLinkDefinitionRequest,
     ) -> anyhow::Result<CtlResponse<()>>;
 
+    /// Handle a request to put a traffic split of a link target. This method should return a response
+    /// indicating success or failure.
+    async fn handle_traffic_split_put(
+        &self,
+        request: TrafficSplit,
+    ) -> anyhow::Result<CtlResponse<()>>;
+
+    /// Handle a request to delete the traffic split of a link target. This method should return a response
+    /// indicating success or failure.
+    async fn handle_traffic_split_del(&self, target: &str) -> anyhow::Result<CtlResponse<()>>;
+
+    /// Handle a request to get the traffic splits of the lattice. This method should return a response
+    /// containing the traffic splits.
+    async fn handle_traffic_splits(&self) -> anyhow::Result<CtlResponse<Vec<TrafficSplit>>>;
+
+    /// Handle a request to start capturing the payloads of invocations sent by a component on this host. This
+    /// method should return a response indicating success or failure.
+    async fn handle_payload_capture_put(
+        &self,
+        request: PayloadCapture,
+    ) -> anyhow::Result<CtlResponse<()>>;
+
+    /// Handle a request to stop capturing the payloads of invocations sent by a component on this host. This
+    /// method should return a response indicating success or failure.
+    async fn handle_payload_capture_del(&self, source_id: &str) -> anyhow::Result<CtlResponse<()>>;
+
+    /// Handle a request to get the payloads captured for a component on this host. This method should return
+    /// a response containing the captured payloads.
+    async fn handle_captured_payloads(
+        &self,
+        source_id: &str,
+    ) -> anyhow::Result<CtlResponse<Vec<CapturedPayload>>>;
+
     /// Handle a request to put registry credentials. This method should return a response indicating success
     /// or failure.
     async fn handle_registries_put(
@@ -230,6 +309,7 @@ impl ControlInterfaceServer for Host {
         self.ready.store(false, Ordering::Relaxed);
 
         self.heartbeat.abort();
+        self.link_health.abort();
         self.data_watch.abort();
         self.queue.abort();
         self.policy_manager.policy_changes.abort();
@@ -541,6 +621,7 @@ impl ControlInterfaceServer for Host {
             ref annotations,
             mut tasks,
             shutdown,
+            lease,
             ..
         } = entry.remove();
 
@@ -560,10 +641,10 @@ impl ControlInterfaceServer for Host {
         if let Err(e) = self
             .rpc_nats
             .send_request(
-                format!(
+                self.host_config.prefixed_subject(format!(
                     "wasmbus.rpc.{}.{provider_id}.default.shutdown",
                     self.host_config.lattice
-                ),
+                )),
                 req,
             )
             .await
@@ -579,6 +660,14 @@ impl ControlInterfaceServer for Host {
 
         // Stop the provider and health check / config changes tasks
         tasks.abort_all();
+        self.provider_link_health.write().await.remove(provider_id);
+
+        // Hand over a singleton provider to a standby host without waiting for the lease to expire
+        if let Some(lease) = lease {
+            if let Err(e) = lease.release().await {
+                warn!(?e, provider_id, "failed to release provider lease");
+            }
+        }
 
         info!(provider_id, "provider stopped");
         self.publish_event(
@@ -591,6 +680,73 @@ impl ControlInterfaceServer for Host {
         ))
     }
 
+    #[instrument(level = "debug", skip_all)]
+    async fn handle_update_provider(
+        self: Arc<Self>,
+        request: UpdateProviderCommand,
+    ) -> anyhow::Result<CtlResponse<()>> {
+        let provider_id = request.provider_id();
+        let new_provider_ref = request.new_provider_ref();
+
+        info!(provider_id, new_provider_ref, "handling update provider");
+
+        let Some(provider_ref) = self
+            .providers
+            .read()
+            .await
+            .get(provider_id)
+            .map(|provider| provider.image_ref.clone())
+        else {
+            return Ok(CtlResponse::error(&format!(
+                "provider {provider_id} not found"
+            )));
+        };
+
+        if provider_ref == new_provider_ref {
+            return Ok(CtlResponse::<()>::success(format!(
+                "provider {provider_id} already updated to {new_provider_ref}"
+            )));
+        }
+
+        let message =
+            format!("provider {provider_id} updating from {provider_ref} to {new_provider_ref}");
+        spawn(async move {
+            let provider_id = request.provider_id();
+            let new_provider_ref = request.new_provider_ref();
+            if let Err(err) = Arc::clone(&self)
+                .handle_update_provider_task(
+                    provider_id,
+                    new_provider_ref,
+                    request.host_id(),
+                    request.annotations().cloned(),
+                )
+                .await
+            {
+                error!(
+                    provider_id,
+                    new_provider_ref,
+                    ?err,
+                    "failed to update provider"
+                );
+                if let Err(err) = self
+                    .publish_event(
+                        "provider_start_failed",
+                        event::provider_start_failed(
+                            new_provider_ref,
+                            provider_id,
+                            request.host_id(),
+                            &err,
+                        ),
+                    )
+                    .await
+                {
+                    error!(?err, "failed to publish provider_start_failed event");
+                }
+            }
+        });
+        Ok(CtlResponse::<()>::success(message))
+    }
+
     #[instrument(level = "debug", skip_all)]
     async fn handle_inventory(&self) -> anyhow::Result<CtlResponse<HostInventory>> {
         trace!("handling inventory");
@@ -601,19 +757,83 @@ impl ControlInterfaceServer for Host {
     #[instrument(level = "trace", skip_all)]
     async fn handle_claims(&self) -> anyhow::Result<CtlResponse<Vec<HashMap<String, String>>>> {
         trace!("handling claims");
+        Ok(CtlResponse::ok(self.cached_claims().await))
+    }
 
-        let (component_claims, provider_claims) =
-            join!(self.component_claims.read(), self.provider_claims.read());
-        let component_claims = component_claims.values().cloned().map(Claims::Component);
-        let provider_claims = provider_claims.values().cloned().map(Claims::Provider);
-        let claims: Vec<StoredClaims> = component_claims
-            .chain(provider_claims)
-            .flat_map(TryFrom::try_from)
-            .collect();
+    #[instrument(level = "debug", skip_all)]
+    async fn handle_claims_export(&self) -> anyhow::Result<CtlResponse<ClaimsBundle>> {
+        debug!("handling claims export");
+        let exported_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .context("failed to determine current time")?
+            .as_secs();
+        let imported_issuers = self.imported_issuers.read().await.clone();
+        let contents = ClaimsBundleContents::new(
+            self.host_config.lattice.as_ref(),
+            exported_at,
+            self.cached_claims().await,
+        )
+        .with_issuers(imported_issuers)
+        .with_issuers(self.host_config.trusted_issuers.iter().cloned());
+        let bundle = sign_claims_bundle(&self.host_key, &contents)?;
+        Ok(CtlResponse::ok(bundle))
+    }
 
-        Ok(CtlResponse::ok(
-            claims.into_iter().map(std::convert::Into::into).collect(),
-        ))
+    #[instrument(level = "debug", skip_all, fields(signer = bundle.signer()))]
+    async fn handle_claims_import(&self, bundle: ClaimsBundle) -> anyhow::Result<CtlResponse<()>> {
+        debug!("handling claims import");
+        let contents =
+            match verify_claims_bundle(&bundle, &self.host_config.trusted_claims_bundle_signers) {
+                Ok(contents) => contents,
+                Err(err) => {
+                    warn!(?err, "rejected claims bundle");
+                    return Ok(CtlResponse::error(&format!(
+                        "failed to verify claims bundle: {err:#}"
+                    )));
+                }
+            };
+        let lattices = if self.host_config.trusted_claims_bundle_lattices.is_empty() {
+            vec![self.host_config.lattice.to_string()]
+        } else {
+            self.host_config.trusted_claims_bundle_lattices.clone()
+        };
+        // All claims and issuers are validated before any are stored, so that invalid bundles are
+        // rejected as a whole
+        let validated = check_claims_bundle_origin(
+            &contents,
+            &lattices,
+            self.host_config.claims_bundle_max_age,
+            SystemTime::now(),
+        )
+        .and_then(|()| claims_bundle_issuers(&contents))
+        .and_then(|issuers| Ok((issuers, claims_bundle_claims(&contents)?)));
+        let (issuers, claims) = match validated {
+            Ok(validated) => validated,
+            Err(err) => {
+                warn!(?err, "rejected claims bundle");
+                return Ok(CtlResponse::error(&format!(
+                    "failed to validate claims bundle: {err:#}"
+                )));
+            }
+        };
+        let imported = claims.len();
+        for claims in claims {
+            self.store_claims(claims).await?;
+        }
+        for issuer in issuers {
+            self.store_trusted_issuer(issuer, contents.lattice())
+                .await?;
+        }
+        info!(
+            imported,
+            lattice = contents.lattice(),
+            issuers = ?contents.issuers(),
+            "imported claims bundle"
+        );
+        Ok(CtlResponse::<()>::success(format!(
+            "successfully imported {imported} claims from lattice `{}`",
+            contents.lattice()
+        )))
     }
 
     #[instrument(level = "trace", skip_all)]
@@ -648,7 +868,7 @@ impl ControlInterfaceServer for Host {
         debug!("handle config entry deletion");
 
         self.config_data
-            .purge(config_name)
+            .delete(config_name)
             .await
             .context("Unable to delete config data")?;
 
@@ -660,6 +880,106 @@ impl ControlInterfaceServer for Host {
         ))
     }
 
+    #[instrument(level = "debug", skip_all, fields(%config_name))]
+    async fn handle_config_history(
+        &self,
+        config_name: &str,
+    ) -> anyhow::Result<CtlResponse<Vec<ConfigRevision>>> {
+        trace!("handling get config history");
+        // The history of a key without any revisions never completes, so check for one first
+        if self
+            .config_data
+            .entry(config_name)
+            .await
+            .context("unable to get config data")?
+            .is_none()
+        {
+            return Ok(CtlResponse::ok(Vec::default()));
+        }
+        let history = self
+            .config_data
+            .history(config_name)
+            .await
+            .context("unable to get config history")?;
+        let revisions = history
+            .map_err(anyhow::Error::from)
+            .and_then(|entry| async move {
+                let mut revision = ConfigRevision::builder()
+                    .revision(entry.revision)
+                    .created(entry.created.format(&Rfc3339)?);
+                if let Operation::Put = entry.operation {
+                    revision = revision.config(
+                        serde_json::from_slice(&entry.value)
+                            .context("config data should be a map of string -> string")?,
+                    );
+                }
+                revision
+                    .build()
+                    .map_err(|e| anyhow!("failed to build config revision: {e}"))
+            })
+            .try_collect()
+            .await
+            .context("unable to read config history")?;
+        Ok(CtlResponse::ok(revisions))
+    }
+
+    #[instrument(level = "debug", skip_all, fields(%config_name))]
+    async fn handle_config_rollback(
+        &self,
+        config_name: &str,
+        request: ConfigRollbackRequest,
+    ) -> anyhow::Result<CtlResponse<()>> {
+        let revision = request.revision();
+        debug!(revision, "handle config rollback");
+        // The history of a key without any revisions never completes, so check for one first
+        if self
+            .config_data
+            .entry(config_name)
+            .await
+            .context("unable to get config data")?
+            .is_none()
+        {
+            return Ok(CtlResponse::error(&format!(
+                "configuration `{config_name}` not found"
+            )));
+        }
+        let mut history = self
+            .config_data
+            .history(config_name)
+            .await
+            .context("unable to get config history")?;
+        let entry = loop {
+            match history
+                .try_next()
+                .await
+                .context("unable to read config history")?
+            {
+                Some(entry) if entry.revision == revision => break entry,
+                Some(..) => {}
+                None => {
+                    return Ok(CtlResponse::error(&format!(
+                        "revision {revision} of configuration `{config_name}` not found"
+                    )))
+                }
+            }
+        };
+        let Operation::Put = entry.operation else {
+            return Ok(CtlResponse::error(&format!(
+                "configuration `{config_name}` was deleted in revision {revision}"
+            )));
+        };
+        self.config_data
+            .put(config_name, entry.value)
+            .await
+            .context("unable to store config data")?;
+        self.publish_event("config_set", event::config_set(config_name))
+            .await?;
+
+        Ok(CtlResponse::<()>::success(format!(
+            "successfully rolled back config to revision {revision}"
+        )))
+    }
+
     #[instrument(level = "debug", skip_all)]
     async fn handle_label_put(
         &self,
@@ -820,6 +1140,58 @@ impl ControlInterfaceServer for Host {
         }
     }
 
+    #[instrument(level = "debug", skip_all)]
+    async fn handle_link_validate(
+        &self,
+        request: Link,
+    ) -> anyhow::Result<CtlResponse<LinkValidation>> {
+        debug!(
+            source_id = request.source_id(),
+            target = request.target(),
+            wit_namespace = request.wit_namespace(),
+            wit_package = request.wit_package(),
+            name = request.name(),
+            "handling validate wrpc link definition"
+        );
+        self.validate_config(
+            request
+                .source_config()
+                .iter()
+                .chain(request.target_config()),
+        )
+        .await?;
+
+        let providers = self.providers.read().await;
+        let linked: Vec<_> = [request.source_id(), request.target()]
+            .into_iter()
+            .filter_map(|id| providers.get(id).map(|provider| (id, provider)))
+            .collect();
+        if linked.is_empty() {
+            bail!(
+                "neither the source nor the target of the link is a provider running on this host"
+            );
+        }
+        let mut errors = Vec::new();
+        for (id, provider) in linked {
+            match self.validate_provider_link(provider, &request).await {
+                Ok(res) => errors.extend(res.errors.into_iter().map(
+                    |wasmcloud_core::LinkValidationError { field, message }| {
+                        LinkValidationError::new(field, format!("{id}: {message}"))
+                    },
+                )),
+                Err(e) => {
+                    warn!(
+                        provider_id = id,
+                        ?e,
+                        "failed to validate link with provider"
+                    );
+                    errors.push(LinkValidationError::new(None, format!("{id}: {e:#}")));
+                }
+            }
+        }
+        Ok(CtlResponse::ok(LinkValidation::new(errors)))
+    }
+
     #[instrument(level = "debug", skip_all)]
     /// Remove an interface link on a source component for a specific package
     async fn handle_link_del(
@@ -895,6 +1267,154 @@ impl ControlInterfaceServer for Host {
         ))
     }
 
+    /// Handle a new traffic split by storing it in the LATTICEDATA store. Once the split is written,
+    /// each host in the lattice (including this one) routes invocations of the target accordingly via
+    /// [process_traffic_split_put].
+    #[instrument(level = "debug", skip_all)]
+    async fn handle_traffic_split_put(
+        &self,
+        request: TrafficSplit,
+    ) -> anyhow::Result<CtlResponse<()>> {
+        let target = request.target();
+        let canary = request.canary();
+        let weight = request.weight();
+        debug!(target, canary, weight, "handling put traffic split");
+
+        if target == canary {
+            return Ok(CtlResponse::error(
+                "target and canary of a traffic split must differ",
+            ));
+        }
+        if weight > 100 {
+            return Ok(CtlResponse::error(&format!(
+                "traffic split weight must be a percentage between 0 and 100, got {weight}"
+            )));
+        }
+        let bytes = serde_json::to_vec(&request)
+            .context("failed to serialize traffic split")?
+            .into();
+        self.data
+            .put(format!("SPLIT_{target}"), bytes)
+            .await
+            .context("failed to put traffic split")?;
+
+        self.publish_event("traffic_split_set", event::traffic_split_set(&request))
+            .await?;
+        Ok(CtlResponse::<()>::success(
+            "successfully set traffic split".into(),
+        ))
+    }
+
+    #[instrument(level = "debug", skip_all, fields(%target))]
+    async fn handle_traffic_split_del(&self, target: &str) -> anyhow::Result<CtlResponse<()>> {
+        debug!("handling del traffic split");
+
+        self.data
+            .delete(format!("SPLIT_{target}"))
+            .await
+            .context("failed to delete traffic split")?;
+
+        // For idempotency, we always publish the deleted event, even if the split didn't exist
+        self.publish_event(
+            "traffic_split_deleted",
+            event::traffic_split_deleted(target),
+        )
+        .await?;
+        Ok(CtlResponse::<()>::success(
+            "successfully deleted traffic split".into(),
+        ))
+    }
+
+    #[instrument(level = "trace", skip_all)]
+    async fn handle_traffic_splits(&self) -> anyhow::Result<CtlResponse<Vec<TrafficSplit>>> {
+        trace!("handling traffic splits");
+
+        let splits = self
+            .traffic_splits
+            .read()
+            .await
+            .values()
+            .map(|split| split.split().clone())
+            .collect();
+        Ok(CtlResponse::ok(splits))
+    }
+
+    /// Handle a new payload capture by compiling its redaction rules and storing it, replacing any
+    /// existing capture of the source component. Captures only apply to this host.
+    #[instrument(level = "debug", skip_all)]
+    async fn handle_payload_capture_put(
+        &self,
+        request: PayloadCapture,
+    ) -> anyhow::Result<CtlResponse<()>> {
+        let source_id = request.source_id();
+        let link_name = request.link_name();
+        let sample_rate = request.sample_rate();
+        let capacity = request.capacity();
+        debug!(
+            source_id,
+            link_name, sample_rate, capacity, "handling put payload capture"
+        );
+
+        let Some(params) = self
+            .components
+            .read()
+            .await
+            .get(source_id)
+            .map(|component| component.import_params())
+        else {
+            return Ok(CtlResponse::error(&format!(
+                "component [{source_id}] is not running on this host"
+            )));
+        };
+        if sample_rate == 0 {
+            return Ok(CtlResponse::error(
+                "payload capture sample rate must be at least 1",
+            ));
+        }
+        if capacity == 0 || capacity > MAX_PAYLOAD_CAPTURE_CAPACITY {
+            return Ok(CtlResponse::error(&format!(
+                "payload capture capacity must be between 1 and {MAX_PAYLOAD_CAPTURE_CAPACITY}, got {capacity}"
+            )));
+        }
+        let source_id = source_id.into();
+        let capture = match Capture::try_from(request) {
+            Ok(capture) => capture.with_params(params),
+            Err(err) => return Ok(CtlResponse::error(&format!("{err:#}"))),
+        };
+        self.payload_captures
+            .write()
+            .await
+            .insert(source_id, Arc::new(capture));
+        Ok(CtlResponse::<()>::success(
+            "successfully started payload capture".into(),
+        ))
+    }
+
+    #[instrument(level = "debug", skip_all, fields(%source_id))]
+    async fn handle_payload_capture_del(&self, source_id: &str) -> anyhow::Result<CtlResponse<()>> {
+        debug!("handling del payload capture");
+
+        self.payload_captures.write().await.remove(source_id);
+        Ok(CtlResponse::<()>::success(
+            "successfully stopped payload capture".into(),
+        ))
+    }
+
+    #[instrument(level = "trace", skip_all, fields(%source_id))]
+    async fn handle_captured_payloads(
+        &self,
+        source_id: &str,
+    ) -> anyhow::Result<CtlResponse<Vec<CapturedPayload>>> {
+        trace!("handling captured payloads");
+
+        let Some(capture) = self.payload_captures.read().await.get(source_id).cloned() else {
+            return Ok(CtlResponse::error(&format!(
+                "payloads of component [{source_id}] are not being captured on this host"
+            )));
+        };
+        Ok(CtlResponse::ok(capture.payloads()))
+    }
+
     #[instrument(level = "debug", skip_all)]
     async fn handle_registries_put(
         &self,
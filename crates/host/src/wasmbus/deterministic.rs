@@ -0,0 +1,42 @@
+//! Deterministic mode for reproducible test runs
+//!
+//! Components started with the [`DETERMINISTIC_SEED_ANNOTATION`] annotation observe virtualized
+//! `wasi:clocks` and `wasi:random` implementations seeded with the annotation value, so that
+//! e.g. integration tests do not depend on the time or randomness of the host they run on.
+
+use std::collections::BTreeMap;
+
+use anyhow::Context as _;
+
+/// Annotation setting the seed of the deterministic `wasi:clocks` and `wasi:random`
+/// implementations, enabling deterministic mode for the component
+pub(crate) const DETERMINISTIC_SEED_ANNOTATION: &str = "wasmcloud.dev/deterministic-seed";
+
+/// Parses the deterministic mode seed of a component from its `annotations`.
+///
+/// Returns `None` if deterministic mode is not enabled for the component.
+pub(crate) fn deterministic_seed(
+    annotations: &BTreeMap<String, String>,
+) -> anyhow::Result<Option<u64>> {
+    annotations
+        .get(DETERMINISTIC_SEED_ANNOTATION)
+        .map(|seed| seed.parse())
+        .transpose()
+        .with_context(|| format!("invalid `{DETERMINISTIC_SEED_ANNOTATION}` annotation"))
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use super::{deterministic_seed, DETERMINISTIC_SEED_ANNOTATION};
+
+    #[test]
+    fn test_deterministic_seed() {
+        assert_eq!(deterministic_seed(&BTreeMap::new()).unwrap(), None);
+        let annotations = BTreeMap::from([(DETERMINISTIC_SEED_ANNOTATION.into(), "42".into())]);
+        assert_eq!(deterministic_seed(&annotations).unwrap(), Some(42));
+        let annotations = BTreeMap::from([(DETERMINISTIC_SEED_ANNOTATION.into(), "seed".into())]);
+        assert!(deterministic_seed(&annotations).is_err());
+    }
+}
@@ -8,7 +8,8 @@ use tracing::instrument;
 use ulid::Ulid;
 use uuid::Uuid;
 use wascap::jwt;
-use wasmcloud_control_interface::Link;
+use wasmcloud_control_interface::{Link, TrafficSplit};
+use wasmcloud_core::prefixed_subject;
 
 fn format_component_claims(claims: &jwt::Claims<jwt::Component>) -> serde_json::Value {
     let issuer = &claims.issuer;
@@ -156,6 +157,83 @@ pub fn linkdef_deleted(
     }
 }
 
+pub fn link_unhealthy(link: &Link, reason: impl AsRef<str>) -> serde_json::Value {
+    json!({
+        "source_id": link.source_id(),
+        "target": link.target(),
+        "name": link.name(),
+        "wit_namespace": link.wit_namespace(),
+        "wit_package": link.wit_package(),
+        "interfaces": link.interfaces(),
+        "reason": reason.as_ref(),
+    })
+}
+
+pub fn link_recovered(link: &Link) -> serde_json::Value {
+    json!({
+        "source_id": link.source_id(),
+        "target": link.target(),
+        "name": link.name(),
+        "wit_namespace": link.wit_namespace(),
+        "wit_package": link.wit_package(),
+        "interfaces": link.interfaces(),
+    })
+}
+
+pub fn host_clock_skew_detected(
+    host_id: impl AsRef<str>,
+    skewed_host_id: impl AsRef<str>,
+    skew_ms: i64,
+    threshold_ms: u128,
+) -> serde_json::Value {
+    json!({
+        "host_id": host_id.as_ref(),
+        "skewed_host_id": skewed_host_id.as_ref(),
+        "skew_ms": skew_ms,
+        "threshold_ms": threshold_ms,
+    })
+}
+
+pub fn host_clock_skew_resolved(
+    host_id: impl AsRef<str>,
+    skewed_host_id: impl AsRef<str>,
+) -> serde_json::Value {
+    json!({
+        "host_id": host_id.as_ref(),
+        "skewed_host_id": skewed_host_id.as_ref(),
+    })
+}
+
+pub fn component_quarantined(
+    component_id: impl AsRef<str>,
+    image_ref: impl AsRef<str>,
+    reason: impl AsRef<str>,
+    failures: u32,
+    until: u64,
+) -> serde_json::Value {
+    json!({
+        "component_id": component_id.as_ref(),
+        "image_ref": image_ref.as_ref(),
+        "reason": reason.as_ref(),
+        "failures": failures,
+        "until": until,
+    })
+}
+
+pub fn traffic_split_set(split: &TrafficSplit) -> serde_json::Value {
+    json!({
+        "target": split.target(),
+        "canary": split.canary(),
+        "weight": split.weight(),
+    })
+}
+
+pub fn traffic_split_deleted(target: impl AsRef<str>) -> serde_json::Value {
+    json!({
+        "target": target.as_ref(),
+    })
+}
+
 pub fn provider_started(
     claims: Option<&jwt::Claims<jwt::CapabilityProvider>>,
     annotations: &BTreeMap<String, String>,
@@ -235,6 +313,19 @@ pub fn provider_stopped(
     })
 }
 
+pub fn provider_updated(
+    claims: Option<&jwt::Claims<jwt::CapabilityProvider>>,
+    annotations: &BTreeMap<String, String>,
+    host_id: impl AsRef<str>,
+    old_image_ref: impl AsRef<str>,
+    image_ref: impl AsRef<str>,
+    provider_id: impl AsRef<str>,
+) -> serde_json::Value {
+    let mut event = provider_started(claims, annotations, host_id, image_ref, provider_id);
+    event["old_image_ref"] = old_image_ref.as_ref().into();
+    event
+}
+
 pub fn provider_health_check(
     host_id: impl AsRef<str>,
     provider_id: impl AsRef<str>,
@@ -245,6 +336,13 @@ pub fn provider_health_check(
     })
 }
 
+pub fn provider_lease(host_id: impl AsRef<str>, provider_id: impl AsRef<str>) -> serde_json::Value {
+    json!({
+        "host_id": host_id.as_ref(),
+        "provider_id": provider_id.as_ref(),
+    })
+}
+
 pub fn config_set(config_name: impl AsRef<str>) -> serde_json::Value {
     json!({
         "config_name": config_name.as_ref(),
@@ -272,6 +370,7 @@ pub(crate) async fn publish(
     event_builder: &EventBuilderV10,
     ctl_nats: &async_nats::Client,
     lattice: &str,
+    subject_prefix: Option<&str>,
     name: &str,
     data: serde_json::Value,
 ) -> anyhow::Result<()> {
@@ -288,7 +387,10 @@ pub(crate) async fn publish(
         .context("failed to build cloud event")?;
     let ev = serde_json::to_vec(&ev).context("failed to serialize event")?;
     ctl_nats
-        .publish(format!("wasmbus.evt.{lattice}.{name}"), ev.into())
+        .publish(
+            prefixed_subject(subject_prefix, format!("wasmbus.evt.{lattice}.{name}")),
+            ev.into(),
+        )
         .await
         .with_context(|| format!("failed to publish `{name}` event"))
 }
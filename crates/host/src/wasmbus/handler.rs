@@ -1,9 +1,9 @@
 use core::any::Any;
 use core::iter::{repeat, zip};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ops::Deref;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::{anyhow, bail, Context as _};
 use async_nats::header::{IntoHeaderName as _, IntoHeaderValue as _};
@@ -11,21 +11,26 @@ use async_trait::async_trait;
 use bytes::Bytes;
 use secrecy::Secret;
 use tokio::sync::RwLock;
-use tracing::{error, instrument, warn};
+use tracing::{debug, error, instrument, warn};
+use wasmcloud_core::prefixed_subject;
 use wasmcloud_runtime::capability::logging::logging;
 use wasmcloud_runtime::capability::secrets::store::SecretValue;
 use wasmcloud_runtime::capability::{
     self, messaging0_2_0, messaging0_3_0, secrets, CallTargetInterface,
 };
 use wasmcloud_runtime::component::{
-    Bus, Bus1_0_0, Config, InvocationErrorIntrospect, InvocationErrorKind, Logging, Messaging0_2,
-    Messaging0_3, MessagingClient0_3, MessagingGuestMessage0_3, MessagingHostMessage0_3,
-    ReplacedInstanceTarget, Secrets,
+    Bus, Bus1_0_0, Config, EmbeddedBlobstore, InvocationErrorIntrospect, InvocationErrorKind,
+    Logging, MemoryBlobstore, Messaging0_2, Messaging0_3, MessagingClient0_3,
+    MessagingGuestMessage0_3, MessagingHostMessage0_3, ReplacedInstanceTarget, Secrets,
 };
 use wasmcloud_tracing::context::TraceContextInjector;
 use wrpc_transport::InvokeExt as _;
 
+use super::capture::PayloadCaptures;
 use super::config::ConfigBundle;
+use super::hedging::{invoke_hedged, HedgePolicies};
+use super::local::{Incoming, LocalTargets, Outgoing};
+use super::traffic::{Split, TrafficSplits};
 use super::{injector_to_headers, Features};
 
 #[derive(Clone, Debug)]
@@ -41,6 +46,8 @@ pub struct Handler {
     pub secrets: Arc<RwLock<HashMap<String, Secret<SecretValue>>>>,
     /// The lattice this handler will use for RPC
     pub lattice: Arc<str>,
+    /// Prefix of the NATS subjects of the lattice, if any
+    pub subject_prefix: Option<Arc<str>>,
     /// The identifier of the component that this handler is associated with
     pub component_id: Arc<str>,
     /// The current link targets. `instance` -> `link-name`
@@ -60,6 +67,20 @@ pub struct Handler {
     pub instance_links: Arc<RwLock<HashMap<Box<str>, HashMap<Box<str>, Box<str>>>>>,
     /// Link name -> messaging client
     pub messaging_links: Arc<RwLock<HashMap<Box<str>, async_nats::Client>>>,
+    /// Functions served by components on this host, which are invoked in-process.
+    /// `None` if in-process invocations are disabled on the host
+    pub local_targets: Option<LocalTargets>,
+    /// Link names and instances of links, for which in-process invocations are disabled
+    pub local_invocation_opt_outs: Arc<RwLock<HashSet<(Box<str>, Box<str>)>>>,
+    /// Hedging policies of idempotent functions invoked over links, by link name and instance
+    pub hedge_policies: Arc<RwLock<HedgePolicies>>,
+    /// Traffic splits of link targets in the lattice, by link target
+    pub traffic_splits: Arc<RwLock<TrafficSplits>>,
+    /// Payload captures of components on the host, by source component ID
+    pub payload_captures: Arc<RwLock<PayloadCaptures>>,
+    /// In-memory blobstore serving `wasi:blobstore` imports of the component instead of links,
+    /// if enabled for the component
+    pub memory_blobstore: Option<MemoryBlobstore>,
 
     pub invocation_timeout: Duration,
     /// Experimental features enabled in the host for gating handler functionality
@@ -75,10 +96,17 @@ impl Handler {
             config_data: self.config_data.clone(),
             secrets: self.secrets.clone(),
             lattice: self.lattice.clone(),
+            subject_prefix: self.subject_prefix.clone(),
             component_id: self.component_id.clone(),
             targets: Arc::default(),
             instance_links: self.instance_links.clone(),
             messaging_links: self.messaging_links.clone(),
+            local_targets: self.local_targets.clone(),
+            local_invocation_opt_outs: self.local_invocation_opt_outs.clone(),
+            hedge_policies: self.hedge_policies.clone(),
+            traffic_splits: self.traffic_splits.clone(),
+            payload_captures: self.payload_captures.clone(),
+            memory_blobstore: self.memory_blobstore.clone(),
             invocation_timeout: self.invocation_timeout,
             experimental_features: self.experimental_features,
         }
@@ -162,8 +190,8 @@ impl Bus for Handler {
 
 impl wrpc_transport::Invoke for Handler {
     type Context = Option<ReplacedInstanceTarget>;
-    type Outgoing = <wrpc_transport_nats::Client as wrpc_transport::Invoke>::Outgoing;
-    type Incoming = <wrpc_transport_nats::Client as wrpc_transport::Invoke>::Incoming;
+    type Outgoing = Outgoing<<wrpc_transport_nats::Client as wrpc_transport::Invoke>::Outgoing>;
+    type Incoming = Incoming<<wrpc_transport_nats::Client as wrpc_transport::Invoke>::Incoming>;
 
     #[instrument(level = "debug", skip_all)]
     async fn invoke<P>(
@@ -219,18 +247,100 @@ impl wrpc_transport::Invoke for Handler {
             format!("failed to call `{func}` in instance `{instance}` (failed to find a configured link with name `{link_name}` from component `{id}`, please check your configuration)", id = self.component_id)
         })?;
 
+        // Invocations of targets with a traffic split are sent to either version of the target.
+        // The target is copied, so that splits are not locked for the duration of the invocation
+        let id = self
+            .traffic_splits
+            .read()
+            .await
+            .get(id)
+            .map_or(&**id, Split::route)
+            .to_string();
+        let id = id.as_str();
+
+        // Sample the payload of the invocation if it is being captured for debugging
+        if let Some(capture) = self.payload_captures.read().await.get(&*self.component_id) {
+            capture.record(link_name, id, instance, func, &params);
+        }
+
         let mut headers = injector_to_headers(&TraceContextInjector::default_with_span());
         headers.insert("source-id", &*self.component_id);
         headers.insert("link-name", link_name);
+        // Let the target bound any work done on behalf of this invocation by its deadline
+        if let Some(deadline) = SystemTime::now()
+            .checked_add(self.invocation_timeout)
+            .and_then(|deadline| deadline.duration_since(UNIX_EPOCH).ok())
+        {
+            headers.insert("deadline", deadline.as_millis().to_string());
+        }
+
+        // Invocations with nested streams are always sent over NATS
+        if let Some(local_targets) = self
+            .local_targets
+            .as_ref()
+            .filter(|_| paths.as_ref().is_empty())
+        {
+            let opted_out = self
+                .local_invocation_opt_outs
+                .read()
+                .await
+                .contains(&(link_name.into(), target_instance.into()));
+            if !opted_out {
+                if let Some((tx, rx)) = local_targets.invoke(
+                    id,
+                    instance,
+                    func,
+                    headers.clone(),
+                    &params,
+                    self.invocation_timeout,
+                ) {
+                    debug!(instance, func, target = ?id, "invoking component in-process");
+                    return Ok((Outgoing::Local(tx), Incoming::Local(rx)));
+                }
+            }
+        }
+
         let nats = wrpc_transport_nats::Client::new(
             Arc::clone(&self.nats),
-            format!("{}.{id}", &self.lattice),
+            prefixed_subject(
+                self.subject_prefix.as_deref(),
+                format!("{}.{id}", &self.lattice),
+            ),
             None,
         )
         .await?;
-        nats.timeout(self.invocation_timeout)
+
+        // Invocations with nested streams are never hedged
+        if paths.as_ref().is_empty() {
+            let policy = self
+                .hedge_policies
+                .read()
+                .await
+                .get(&(link_name.into(), target_instance.into()))
+                .cloned();
+            if let Some((policy, delay)) =
+                policy.and_then(|policy| policy.delay(func).map(|delay| (policy, delay)))
+            {
+                let (tx, rx) = invoke_hedged(
+                    &nats,
+                    self.invocation_timeout,
+                    &policy,
+                    delay,
+                    headers,
+                    instance,
+                    func,
+                    params,
+                )
+                .await?;
+                return Ok((Outgoing::Nats(tx), rx));
+            }
+        }
+
+        let (tx, rx) = nats
+            .timeout(self.invocation_timeout)
             .invoke(Some(headers), instance, func, params, paths)
-            .await
+            .await?;
+        Ok((Outgoing::Nats(tx), Incoming::Nats(rx)))
     }
 }
 
@@ -263,6 +373,12 @@ impl Config for Handler {
     }
 }
 
+impl EmbeddedBlobstore for Handler {
+    fn memory_blobstore(&self) -> Option<MemoryBlobstore> {
+        self.memory_blobstore.clone()
+    }
+}
+
 #[async_trait]
 impl Logging for Handler {
     #[instrument(level = "trace", skip(self))]
@@ -0,0 +1,300 @@
+//! Hedged invocations of idempotent functions over NATS
+//!
+//! Links opt into hedging by listing idempotent functions, e.g. `get,exists`, in
+//! [`HEDGE_FUNCTIONS_CONFIG_KEY`] of one of the source configurations of the link. If the results
+//! of an invocation of such a function have not started arriving once the
+//! [`HEDGE_PERCENTILE_CONFIG_KEY`] percentile of recently observed latencies has elapsed, a second,
+//! identical invocation is sent to the same lattice target. NATS delivers the second invocation to
+//! any instance of the target, so that a single degraded instance does not hold up the
+//! invocation. The results of whichever invocation responds first are used and the other
+//! invocation is dropped.
+//!
+//! Until enough latencies have been observed, invocations are hedged after
+//! [`HEDGE_DELAY_CONFIG_KEY`] milliseconds.
+
+use core::time::Duration;
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use anyhow::Context as _;
+use bytes::{Bytes, BytesMut};
+use tokio::io::AsyncReadExt as _;
+use tokio::time::Instant;
+use tracing::{debug, warn};
+use wasmcloud_control_interface::Link;
+use wasmcloud_secrets_types::SECRET_PREFIX;
+use wrpc_transport::{Invoke as _, InvokeExt as _};
+
+use super::config::BundleGenerator;
+use super::local::Incoming;
+
+/// Link source configuration key, listing the comma-separated names of idempotent functions,
+/// whose invocations over the link are hedged
+pub(crate) const HEDGE_FUNCTIONS_CONFIG_KEY: &str = "wasmcloud_hedge_functions";
+
+/// Link source configuration key of the percentile of observed latencies, after which a hedged
+/// invocation is sent
+pub(crate) const HEDGE_PERCENTILE_CONFIG_KEY: &str = "wasmcloud_hedge_percentile";
+
+/// Link source configuration key of the delay in milliseconds, after which a hedged invocation is
+/// sent until enough latencies have been observed
+pub(crate) const HEDGE_DELAY_CONFIG_KEY: &str = "wasmcloud_hedge_delay_ms";
+
+const DEFAULT_HEDGE_PERCENTILE: f64 = 95.0;
+
+const DEFAULT_HEDGE_DELAY: Duration = Duration::from_millis(100);
+
+/// Number of most recent latencies the hedging delay is computed from
+const LATENCY_WINDOW_SIZE: usize = 128;
+
+/// Minimum number of observed latencies, before the hedging delay is computed from them
+const MIN_LATENCY_SAMPLES: usize = 16;
+
+type NatsOutgoing = <wrpc_transport_nats::Client as wrpc_transport::Invoke>::Outgoing;
+type NatsIncoming = <wrpc_transport_nats::Client as wrpc_transport::Invoke>::Incoming;
+
+/// Hedging policies keyed by link name and instance
+pub(crate) type HedgePolicies = HashMap<(Box<str>, Box<str>), Arc<HedgePolicy>>;
+
+/// Hedging policy of the functions of an instance invoked over a link
+#[derive(Debug)]
+pub(crate) struct HedgePolicy {
+    functions: HashSet<Box<str>>,
+    percentile: f64,
+    initial_delay: Duration,
+    latencies: Mutex<HashMap<Box<str>, VecDeque<Duration>>>,
+}
+
+impl HedgePolicy {
+    fn new(functions: HashSet<Box<str>>, percentile: f64, initial_delay: Duration) -> Self {
+        Self {
+            functions,
+            percentile,
+            initial_delay,
+            latencies: Mutex::default(),
+        }
+    }
+
+    /// Returns the delay after which an invocation of `func` is hedged, or `None` if invocations
+    /// of `func` are not hedged
+    pub(crate) fn delay(&self, func: &str) -> Option<Duration> {
+        if !self.functions.contains(func) {
+            return None;
+        }
+        let latencies = self
+            .latencies
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let Some(samples) = latencies
+            .get(func)
+            .filter(|samples| samples.len() >= MIN_LATENCY_SAMPLES)
+        else {
+            return Some(self.initial_delay);
+        };
+        let mut samples: Vec<_> = samples.iter().copied().collect();
+        samples.sort_unstable();
+        // Nearest-rank percentile
+        let rank = (self.percentile / 100.0 * samples.len() as f64).ceil() as usize;
+        samples.get(rank.saturating_sub(1)).copied()
+    }
+
+    /// Record the time it took for the results of an invocation of `func` to start arriving
+    fn record(&self, func: &str, latency: Duration) {
+        let mut latencies = self
+            .latencies
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let samples = latencies.entry(func.into()).or_default();
+        if samples.len() == LATENCY_WINDOW_SIZE {
+            samples.pop_front();
+        }
+        samples.push_back(latency);
+    }
+}
+
+/// Invoke `func` over NATS, sending a second invocation if the results of the first one have not
+/// started arriving after `delay`
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn invoke_hedged(
+    nats: &wrpc_transport_nats::Client,
+    timeout: Duration,
+    policy: &HedgePolicy,
+    delay: Duration,
+    headers: async_nats::HeaderMap,
+    instance: &str,
+    func: &str,
+    params: Bytes,
+) -> anyhow::Result<(NatsOutgoing, Incoming<NatsIncoming>)> {
+    let invoke = || async {
+        nats.timeout(timeout)
+            .invoke(
+                Some(headers.clone()),
+                instance,
+                func,
+                params.clone(),
+                Vec::<Box<[Option<usize>]>>::new(),
+            )
+            .await
+    };
+
+    let start = Instant::now();
+    let (tx, mut rx) = invoke().await?;
+    let mut buf = BytesMut::new();
+    if let Ok(res) = tokio::time::timeout(delay, rx.read_buf(&mut buf)).await {
+        res.context("failed to read results")?;
+        policy.record(func, start.elapsed());
+        return Ok((tx, Incoming::Buffered(buf.freeze(), rx)));
+    }
+
+    debug!(instance, func, ?delay, "sending hedged invocation");
+    let hedge_start = Instant::now();
+    let (hedge_tx, mut hedge_rx) = match invoke().await {
+        Ok(hedge) => hedge,
+        Err(err) => {
+            warn!(?err, instance, func, "failed to send hedged invocation");
+            rx.read_buf(&mut buf)
+                .await
+                .context("failed to read results")?;
+            policy.record(func, start.elapsed());
+            return Ok((tx, Incoming::Buffered(buf.freeze(), rx)));
+        }
+    };
+    let mut hedge_buf = BytesMut::new();
+    // The losing invocation is dropped, which stops receiving its results
+    let hedge_won = tokio::select! {
+        res = rx.read_buf(&mut buf) => match res {
+            Ok(..) => false,
+            Err(err) => {
+                debug!(?err, instance, func, "failed to read results of first invocation");
+                hedge_rx.read_buf(&mut hedge_buf).await.context("failed to read results")?;
+                true
+            }
+        },
+        res = hedge_rx.read_buf(&mut hedge_buf) => match res {
+            Ok(..) => true,
+            Err(err) => {
+                debug!(?err, instance, func, "failed to read results of hedged invocation");
+                rx.read_buf(&mut buf).await.context("failed to read results")?;
+                false
+            }
+        },
+    };
+    if hedge_won {
+        debug!(instance, func, "hedged invocation responded first");
+        policy.record(func, hedge_start.elapsed());
+        Ok((hedge_tx, Incoming::Buffered(hedge_buf.freeze(), hedge_rx)))
+    } else {
+        policy.record(func, start.elapsed());
+        Ok((tx, Incoming::Buffered(buf.freeze(), rx)))
+    }
+}
+
+/// Returns the hedging policies of `links`, configured via [`HEDGE_FUNCTIONS_CONFIG_KEY`]
+pub(crate) async fn hedge_policies(
+    config_generator: &BundleGenerator,
+    links: &[Link],
+) -> HedgePolicies {
+    let mut policies = HedgePolicies::new();
+    for link in links {
+        let config_names: Vec<_> = link
+            .source_config()
+            .iter()
+            .filter(|name| !name.starts_with(SECRET_PREFIX))
+            .cloned()
+            .collect();
+        if config_names.is_empty() {
+            continue;
+        }
+        let config = match config_generator.generate(config_names).await {
+            Ok(config) => config,
+            Err(err) => {
+                warn!(
+                    ?err,
+                    source_id = link.source_id(),
+                    name = link.name(),
+                    "failed to fetch link source configuration"
+                );
+                continue;
+            }
+        };
+        let config = config.get_config().await;
+        let Some(functions) = config.get(HEDGE_FUNCTIONS_CONFIG_KEY) else {
+            continue;
+        };
+        let functions: HashSet<Box<str>> = functions
+            .split(',')
+            .map(str::trim)
+            .filter(|func| !func.is_empty())
+            .map(Into::into)
+            .collect();
+        let percentile = match config.get(HEDGE_PERCENTILE_CONFIG_KEY).map(|p| p.parse()) {
+            None => DEFAULT_HEDGE_PERCENTILE,
+            Some(Ok(p)) if p > 0.0 && p <= 100.0 => p,
+            Some(..) => {
+                warn!(
+                    source_id = link.source_id(),
+                    name = link.name(),
+                    "invalid hedging percentile, using {DEFAULT_HEDGE_PERCENTILE}"
+                );
+                DEFAULT_HEDGE_PERCENTILE
+            }
+        };
+        let initial_delay = match config.get(HEDGE_DELAY_CONFIG_KEY).map(|ms| ms.parse()) {
+            None => DEFAULT_HEDGE_DELAY,
+            Some(Ok(ms)) => Duration::from_millis(ms),
+            Some(Err(err)) => {
+                warn!(
+                    ?err,
+                    source_id = link.source_id(),
+                    name = link.name(),
+                    "invalid hedging delay, using {DEFAULT_HEDGE_DELAY:?}"
+                );
+                DEFAULT_HEDGE_DELAY
+            }
+        };
+        let policy = Arc::new(HedgePolicy::new(functions, percentile, initial_delay));
+        for interface in link.interfaces() {
+            policies.insert(
+                (
+                    link.name().into(),
+                    format!(
+                        "{}:{}/{interface}",
+                        link.wit_namespace(),
+                        link.wit_package()
+                    )
+                    .into(),
+                ),
+                Arc::clone(&policy),
+            );
+        }
+    }
+    policies
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn hedge_delay() {
+        let policy = HedgePolicy::new(
+            HashSet::from(["get".into()]),
+            90.0,
+            Duration::from_millis(100),
+        );
+        assert_eq!(policy.delay("set"), None);
+        assert_eq!(policy.delay("get"), Some(Duration::from_millis(100)));
+
+        for ms in 1..=20 {
+            policy.record("get", Duration::from_millis(ms));
+        }
+        assert_eq!(policy.delay("get"), Some(Duration::from_millis(18)));
+
+        // Only the most recent latencies are considered
+        for _ in 0..LATENCY_WINDOW_SIZE {
+            policy.record("get", Duration::from_millis(5));
+        }
+        assert_eq!(policy.delay("get"), Some(Duration::from_millis(5)));
+    }
+}
@@ -1,8 +1,10 @@
+use crate::secrets::KubernetesConfig;
 use crate::OciConfig;
 
 use core::net::SocketAddr;
 
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -11,7 +13,14 @@ use url::Url;
 use wasmcloud_core::{logging::Level as LogLevel, OtelConfig};
 use wasmcloud_runtime::{MAX_COMPONENTS, MAX_COMPONENT_SIZE, MAX_LINEAR_MEMORY};
 
+use crate::wasmbus::claims::DEFAULT_CLAIMS_BUNDLE_MAX_AGE;
+use crate::wasmbus::clock_skew::DEFAULT_CLOCK_SKEW_THRESHOLD;
+use crate::wasmbus::crash_loop::{
+    DEFAULT_CRASH_LOOP_BACKOFF, DEFAULT_CRASH_LOOP_THRESHOLD, DEFAULT_CRASH_LOOP_WINDOW,
+};
 use crate::wasmbus::experimental::Features;
+use crate::wasmbus::memory_blobstore::DEFAULT_MEMORY_BLOBSTORE_QUOTA;
+use crate::wasmbus::scratch::DEFAULT_SCRATCH_DIR_QUOTA;
 
 /// wasmCloud Host configuration
 #[allow(clippy::struct_excessive_bools)]
@@ -39,6 +48,9 @@ pub struct Host {
     pub rpc_tls: bool,
     /// The lattice the host belongs to
     pub lattice: Arc<str>,
+    /// Prefix of all NATS subjects used by the lattice, which isolates the lattice of a tenant on
+    /// a NATS cluster shared with other tenants. Subjects are not prefixed if `None`
+    pub subject_prefix: Option<String>,
     /// The domain to use for host Jetstream operations
     pub js_domain: Option<String>,
     /// Labels (key-value pairs) to add to the host
@@ -63,6 +75,9 @@ pub struct Host {
     pub policy_service_config: PolicyService,
     /// topic for wasmCloud secrets backend
     pub secrets_topic_prefix: Option<String>,
+    /// Configuration of the built-in backend resolving secrets from Kubernetes Secrets, disabled
+    /// if `None`
+    pub kubernetes_secrets: Option<KubernetesConfig>,
     /// The semver version of the host. This is used by a consumer of this crate to indicate the
     /// host version (which may differ from the crate version)
     pub version: String,
@@ -76,14 +91,62 @@ pub struct Host {
     pub max_components: u32,
     /// The interval at which the Host will send heartbeats
     pub heartbeat_interval: Option<Duration>,
+    /// The interval at which the Host probes the targets of links whose source runs on the host,
+    /// defaults to 30 seconds
+    pub link_health_interval: Option<Duration>,
+    /// Skew between the clock of the Host and the heartbeats of other hosts in the lattice above
+    /// which the skew is reported in the inventory and as events, 0 disables detection. Defaults
+    /// to 5 seconds
+    pub clock_skew_threshold: Duration,
+    /// Number of consecutive invocations within `crash_loop_window` failing by trapping or failing
+    /// to instantiate, after which a component is quarantined. Defaults to 0, which disables
+    /// quarantines
+    pub crash_loop_threshold: u32,
+    /// Time window in which consecutive failed invocations of a component are counted, defaults
+    /// to 60 seconds
+    pub crash_loop_window: Duration,
+    /// Duration of the first quarantine of a component, which doubles for every following
+    /// quarantine of a component still failing. Defaults to 30 seconds
+    pub crash_loop_backoff: Duration,
+    /// Public keys of the hosts trusted to sign claims bundles imported into the lattice, claims
+    /// bundles cannot be imported if empty
+    pub trusted_claims_bundle_signers: Vec<String>,
+    /// Lattices claims bundles may be imported from, only the lattice of the host if empty
+    pub trusted_claims_bundle_lattices: Vec<String>,
+    /// Maximum age of imported claims bundles, defaults to 7 days
+    pub claims_bundle_max_age: Duration,
+    /// Issuers trusted to sign the components and providers started on the host, in addition to
+    /// the issuers imported with claims bundles. Components and providers of any issuer are
+    /// started if empty and no claims bundle was imported into the lattice
+    pub trusted_issuers: Vec<String>,
+    /// Directory in which an ephemeral scratch directory is created for every component instance,
+    /// preopened in the component via `wasi:filesystem`. Scratch directories are disabled if `None`
+    pub scratch_dir: Option<PathBuf>,
+    /// Maximum size in bytes of the scratch directory of a component instance, unless lowered by
+    /// the component. Defaults to 64 MiB
+    pub scratch_dir_quota: u64,
+    /// IDs of the components, whose `wasi:blobstore` imports are served by an in-memory blobstore
+    /// embedded in the host instead of a linked provider, `*` matches all components. Intended for
+    /// local development, since objects are lost once the component stops
+    pub memory_blobstore: Vec<String>,
+    /// Maximum total size in bytes of the objects in the in-memory blobstore of a component.
+    /// Defaults to 64 MiB
+    pub memory_blobstore_quota: u64,
     /// Experimental features that can be enabled in the host
     pub experimental_features: Features,
+    /// Whether to validate the parameters of component invocations against the WIT signature of
+    /// the invoked function before dispatch, logging precise diagnostics for invalid payloads.
+    /// Intended for debugging encoding issues, since it adds overhead to each invocation
+    pub validate_invocations: bool,
     /// HTTP administration endpoint address
     pub http_admin: Option<SocketAddr>,
     /// Whether component auctions are enabled
     pub enable_component_auction: bool,
     /// Whether capability provider auctions are enabled
     pub enable_provider_auction: bool,
+    /// Whether invocations between components running on this host are performed in-process,
+    /// bypassing NATS. Can be disabled for individual links via their source configuration
+    pub enable_local_invocations: bool,
 }
 
 /// Configuration for wasmCloud policy service
@@ -113,6 +176,7 @@ impl Default for Host {
             rpc_key: None,
             rpc_tls: false,
             lattice: "default".into(),
+            subject_prefix: None,
             js_domain: None,
             labels: HashMap::default(),
             host_key: None,
@@ -125,6 +189,7 @@ impl Default for Host {
             otel_config: OtelConfig::default(),
             policy_service_config: PolicyService::default(),
             secrets_topic_prefix: None,
+            kubernetes_secrets: None,
             version: env!("CARGO_PKG_VERSION").to_string(),
             max_execution_time: Duration::from_millis(10 * 60 * 1000),
             // 10 MB
@@ -133,10 +198,33 @@ impl Default for Host {
             max_component_size: MAX_COMPONENT_SIZE,
             max_components: MAX_COMPONENTS,
             heartbeat_interval: None,
+            link_health_interval: None,
+            clock_skew_threshold: DEFAULT_CLOCK_SKEW_THRESHOLD,
+            crash_loop_threshold: DEFAULT_CRASH_LOOP_THRESHOLD,
+            crash_loop_window: DEFAULT_CRASH_LOOP_WINDOW,
+            crash_loop_backoff: DEFAULT_CRASH_LOOP_BACKOFF,
+            trusted_claims_bundle_signers: Vec::default(),
+            trusted_claims_bundle_lattices: Vec::default(),
+            claims_bundle_max_age: DEFAULT_CLAIMS_BUNDLE_MAX_AGE,
+            trusted_issuers: Vec::default(),
+            scratch_dir: None,
+            scratch_dir_quota: DEFAULT_SCRATCH_DIR_QUOTA,
+            memory_blobstore: Vec::default(),
+            memory_blobstore_quota: DEFAULT_MEMORY_BLOBSTORE_QUOTA,
             experimental_features: Features::default(),
+            validate_invocations: false,
             http_admin: None,
             enable_component_auction: true,
             enable_provider_auction: true,
+            enable_local_invocations: true,
         }
     }
 }
+
+impl Host {
+    /// Returns `subject` prefixed with the configured [`Host::subject_prefix`], if any
+    #[must_use]
+    pub fn prefixed_subject(&self, subject: impl Into<String>) -> String {
+        wasmcloud_core::prefixed_subject(self.subject_prefix.as_deref(), subject)
+    }
+}
@@ -0,0 +1,81 @@
+//! Per-component instance pool configuration
+//!
+//! Components opt into reusing warm instances across invocations by setting annotations in the
+//! scale request. Since state kept by a component in its memory is preserved across invocations
+//! handled by a pooled instance, pooling is disabled unless at least one of the annotations is set.
+
+use std::collections::BTreeMap;
+use std::num::NonZeroUsize;
+use std::time::Duration;
+
+use anyhow::Context as _;
+use wasmcloud_runtime::component::InstancePoolConfig;
+
+/// Annotation setting the minimum number of idle instances kept warm
+pub(crate) const MIN_WARM_INSTANCES_ANNOTATION: &str = "wasmcloud.dev/min-warm-instances";
+
+/// Annotation setting the number of seconds after which idle instances above the minimum are dropped
+pub(crate) const INSTANCE_IDLE_TTL_ANNOTATION: &str = "wasmcloud.dev/instance-idle-ttl-seconds";
+
+/// Idle TTL used if only [`MIN_WARM_INSTANCES_ANNOTATION`] is set
+const DEFAULT_INSTANCE_IDLE_TTL: Duration = Duration::from_secs(60);
+
+/// Parses the instance pool configuration of a component from its `annotations`, the maximum
+/// number of concurrent instances is `max_instances` from the scale request.
+///
+/// Returns `None` if the component did not opt into instance pooling.
+pub(crate) fn instance_pool_config(
+    annotations: &BTreeMap<String, String>,
+    max_instances: NonZeroUsize,
+) -> anyhow::Result<Option<InstancePoolConfig>> {
+    let min_warm = annotations
+        .get(MIN_WARM_INSTANCES_ANNOTATION)
+        .map(|v| v.parse::<usize>())
+        .transpose()
+        .with_context(|| format!("invalid `{MIN_WARM_INSTANCES_ANNOTATION}` annotation"))?;
+    let idle_ttl = annotations
+        .get(INSTANCE_IDLE_TTL_ANNOTATION)
+        .map(|v| v.parse::<u64>().map(Duration::from_secs))
+        .transpose()
+        .with_context(|| format!("invalid `{INSTANCE_IDLE_TTL_ANNOTATION}` annotation"))?;
+    if min_warm.is_none() && idle_ttl.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(InstancePoolConfig {
+        // Keeping more instances warm than can ever be active would only waste memory
+        min_warm: min_warm.unwrap_or_default().min(max_instances.get()),
+        max_concurrent: Some(max_instances),
+        idle_ttl: idle_ttl.unwrap_or(DEFAULT_INSTANCE_IDLE_TTL),
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use std::num::NonZeroUsize;
+    use std::time::Duration;
+
+    use super::{
+        instance_pool_config, INSTANCE_IDLE_TTL_ANNOTATION, MIN_WARM_INSTANCES_ANNOTATION,
+    };
+
+    #[test]
+    fn test_instance_pool_config() {
+        let max = NonZeroUsize::new(4).unwrap();
+        assert_eq!(instance_pool_config(&BTreeMap::new(), max).unwrap(), None);
+
+        let annotations = BTreeMap::from([(MIN_WARM_INSTANCES_ANNOTATION.into(), "8".into())]);
+        let config = instance_pool_config(&annotations, max).unwrap().unwrap();
+        assert_eq!(config.min_warm, 4);
+        assert_eq!(config.max_concurrent, Some(max));
+        assert_eq!(config.idle_ttl, Duration::from_secs(60));
+
+        let annotations = BTreeMap::from([(INSTANCE_IDLE_TTL_ANNOTATION.into(), "5".into())]);
+        let config = instance_pool_config(&annotations, max).unwrap().unwrap();
+        assert_eq!(config.min_warm, 0);
+        assert_eq!(config.idle_ttl, Duration::from_secs(5));
+
+        let annotations = BTreeMap::from([(MIN_WARM_INSTANCES_ANNOTATION.into(), "-1".into())]);
+        assert!(instance_pool_config(&annotations, max).is_err());
+    }
+}
@@ -5,10 +5,12 @@ use anyhow::{anyhow, ensure, Context as _};
 use async_nats::jetstream::kv::{Entry as KvEntry, Operation, Store};
 use serde::{Deserialize, Serialize};
 use tracing::{debug, error, info, instrument, warn};
-use wasmcloud_control_interface::Link;
+use wasmcloud_control_interface::{Link, TrafficSplit};
 
 use crate::wasmbus::claims::{Claims, StoredClaims};
 use crate::wasmbus::component_import_links;
+use crate::wasmbus::hedging::hedge_policies;
+use crate::wasmbus::local::local_invocation_opt_outs;
 
 #[derive(Debug, Serialize, Deserialize, Default)]
 /// The specification of a component that is or did run in the lattice. This contains all of the information necessary to
@@ -141,8 +143,12 @@ impl super::Host {
         }
 
         // If the component is already running, update the links
+        let opt_outs = local_invocation_opt_outs(&self.config_generator, &spec.links).await;
+        let policies = hedge_policies(&self.config_generator, &spec.links).await;
         if let Some(component) = self.components.write().await.get(id) {
             *component.handler.instance_links.write().await = component_import_links(&spec.links);
+            *component.handler.local_invocation_opt_outs.write().await = opt_outs;
+            *component.handler.hedge_policies.write().await = policies;
             // NOTE(brooksmtownsend): We can consider updating the component if the image URL changes
         };
 
@@ -224,6 +230,61 @@ impl super::Host {
         Ok(())
     }
 
+    #[instrument(level = "debug", skip_all)]
+    pub(crate) async fn process_trusted_issuer_put(
+        &self,
+        issuer: impl AsRef<str>,
+    ) -> anyhow::Result<()> {
+        let issuer = issuer.as_ref();
+        debug!(issuer, "process trusted issuer put");
+
+        self.imported_issuers.write().await.insert(issuer.into());
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    pub(crate) async fn process_trusted_issuer_delete(
+        &self,
+        issuer: impl AsRef<str>,
+    ) -> anyhow::Result<()> {
+        let issuer = issuer.as_ref();
+        debug!(issuer, "process trusted issuer delete");
+
+        self.imported_issuers.write().await.remove(issuer);
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    pub(crate) async fn process_traffic_split_put(
+        &self,
+        target: impl AsRef<str>,
+        value: impl AsRef<[u8]>,
+    ) -> anyhow::Result<()> {
+        let target = target.as_ref();
+        debug!(target, "process traffic split put");
+
+        let split: TrafficSplit = serde_json::from_slice(value.as_ref())
+            .context("failed to deserialize traffic split")?;
+        ensure!(split.target() == target, "target mismatch");
+        self.traffic_splits
+            .write()
+            .await
+            .insert(target.into(), split.into());
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    pub(crate) async fn process_traffic_split_delete(
+        &self,
+        target: impl AsRef<str>,
+    ) -> anyhow::Result<()> {
+        let target = target.as_ref();
+        debug!(target, "process traffic split delete");
+
+        self.traffic_splits.write().await.remove(target);
+        Ok(())
+    }
+
     #[instrument(level = "trace", skip_all)]
     pub(crate) async fn process_entry(
         &self,
@@ -256,6 +317,18 @@ impl super::Host {
             (Operation::Delete, Some(("CLAIMS", pubkey))) => {
                 self.process_claims_delete(pubkey, value).await
             }
+            (Operation::Put, Some(("ISSUER", issuer))) => {
+                self.process_trusted_issuer_put(issuer).await
+            }
+            (Operation::Delete, Some(("ISSUER", issuer))) => {
+                self.process_trusted_issuer_delete(issuer).await
+            }
+            (Operation::Put, Some(("SPLIT", target))) => {
+                self.process_traffic_split_put(target, value).await
+            }
+            (Operation::Delete, Some(("SPLIT", target))) => {
+                self.process_traffic_split_delete(target).await
+            }
             (operation, Some(("REFMAP", id))) => {
                 // TODO: process REFMAP entries
                 debug!(?operation, id, "ignoring REFMAP entry");
@@ -297,3 +370,46 @@ pub(crate) async fn create_bucket(
         Err(err) => Err(anyhow!(err).context(format!("failed to create bucket '{bucket}'"))),
     }
 }
+
+/// Number of revisions retained for each named configuration, which is the maximum history
+/// supported by JetStream KV buckets
+pub(crate) const CONFIG_HISTORY: i64 = 64;
+
+/// Create the named configuration bucket, retaining [`CONFIG_HISTORY`] revisions of each entry.
+/// Buckets created with less history are updated to retain [`CONFIG_HISTORY`] revisions
+#[instrument(level = "debug", skip_all)]
+pub(crate) async fn create_config_bucket(
+    jetstream: &async_nats::jetstream::Context,
+    bucket: &str,
+) -> anyhow::Result<Store> {
+    if let Ok(store) = jetstream.get_key_value(bucket).await {
+        let mut stream = jetstream
+            .get_stream(format!("KV_{bucket}"))
+            .await
+            .with_context(|| format!("failed to get stream of bucket '{bucket}'"))?;
+        let mut config = stream
+            .info()
+            .await
+            .with_context(|| format!("failed to get stream info of bucket '{bucket}'"))?
+            .config
+            .clone();
+        if config.max_messages_per_subject < CONFIG_HISTORY {
+            info!(%bucket, history = CONFIG_HISTORY, "updating bucket history");
+            config.max_messages_per_subject = CONFIG_HISTORY;
+            jetstream
+                .update_stream(config)
+                .await
+                .with_context(|| format!("failed to update history of bucket '{bucket}'"))?;
+        }
+        return Ok(store);
+    }
+
+    jetstream
+        .create_key_value(async_nats::jetstream::kv::Config {
+            bucket: bucket.to_string(),
+            history: CONFIG_HISTORY,
+            ..Default::default()
+        })
+        .await
+        .map_err(|err| anyhow!(err).context(format!("failed to create bucket '{bucket}'")))
+}
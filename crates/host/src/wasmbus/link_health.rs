@@ -0,0 +1,245 @@
+//! Link health probing
+//!
+//! Hosts periodically probe the targets of links whose source runs on the host, by sending a
+//! health check request on the target's health subject. Providers answer these requests
+//! themselves, while components are answered by the hosts running them. Changes in the
+//! reachability of a link target are published as `link_unhealthy` and `link_recovered` events.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_nats::{Client, RequestErrorKind};
+use bytes::Bytes;
+use futures::{Future, StreamExt as _};
+use tracing::{debug, trace, warn};
+use wasmcloud_core::prefixed_subject;
+use wasmcloud_core::rpc::health_subject;
+use wasmcloud_core::HealthCheckResponse;
+use wasmcloud_tracing::context::TraceContextInjector;
+
+use crate::wasmbus::injector_to_headers;
+
+/// Default interval at which link targets are probed
+pub(crate) const DEFAULT_LINK_HEALTH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Maximum time to wait for the target of a link to respond to a probe
+pub(crate) const LINK_HEALTH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Identifies a link by source, WIT namespace, WIT package and name
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct LinkKey {
+    pub source_id: String,
+    pub wit_namespace: String,
+    pub wit_package: String,
+    pub name: String,
+}
+
+/// Result of probing the target of a link
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum LinkHealth {
+    Healthy,
+    Unhealthy { reason: String },
+}
+
+/// Transition in the health of a link, which an event should be published for
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum LinkHealthChange {
+    Unhealthy { reason: String },
+    Recovered,
+}
+
+/// Health of the links probed by a host
+#[derive(Debug, Default)]
+pub(crate) struct LinkHealthState(HashMap<LinkKey, LinkHealth>);
+
+impl LinkHealthState {
+    /// Record the result of a probe, returning the change in health, if any.
+    ///
+    /// Links are assumed healthy until probed, so a link that is healthy on first probe does not
+    /// produce a change.
+    pub(crate) fn update(&mut self, key: LinkKey, health: LinkHealth) -> Option<LinkHealthChange> {
+        let previous = self.0.insert(key, health.clone());
+        match (previous, health) {
+            (None | Some(LinkHealth::Healthy), LinkHealth::Unhealthy { reason }) => {
+                Some(LinkHealthChange::Unhealthy { reason })
+            }
+            (Some(LinkHealth::Unhealthy { .. }), LinkHealth::Healthy) => {
+                Some(LinkHealthChange::Recovered)
+            }
+            _ => None,
+        }
+    }
+
+    /// Forget links, which are no longer probed, for which `f` returns `false`
+    pub(crate) fn retain(&mut self, mut f: impl FnMut(&LinkKey) -> bool) {
+        self.0.retain(|key, _| f(key));
+    }
+}
+
+/// Probe `target` in `lattice`, waiting at most `timeout` for a response
+pub(crate) async fn probe(
+    rpc_nats: &Client,
+    lattice: &str,
+    subject_prefix: Option<&str>,
+    target: &str,
+    timeout: Duration,
+) -> LinkHealth {
+    let request = async_nats::Request::new()
+        .payload(Bytes::new())
+        .headers(injector_to_headers(
+            &TraceContextInjector::default_with_span(),
+        ))
+        .timeout(Some(timeout));
+    match rpc_nats
+        .send_request(
+            prefixed_subject(subject_prefix, health_subject(lattice, target)),
+            request,
+        )
+        .await
+    {
+        Ok(async_nats::Message { payload, .. }) => {
+            match serde_json::from_slice::<HealthCheckResponse>(&payload) {
+                Ok(HealthCheckResponse { healthy: true, .. }) => LinkHealth::Healthy,
+                Ok(HealthCheckResponse {
+                    healthy: false,
+                    message,
+                    ..
+                }) => LinkHealth::Unhealthy {
+                    reason: message.unwrap_or_else(|| "target reported unhealthy".into()),
+                },
+                Err(err) => LinkHealth::Unhealthy {
+                    reason: format!("failed to deserialize target health response: {err}"),
+                },
+            }
+        }
+        Err(err) => LinkHealth::Unhealthy {
+            reason: match err.kind() {
+                RequestErrorKind::NoResponders => "target is not running in the lattice".into(),
+                RequestErrorKind::TimedOut => {
+                    format!("target did not respond within {}ms", timeout.as_millis())
+                }
+                RequestErrorKind::Other => format!("failed to probe target: {err}"),
+            },
+        },
+    }
+}
+
+/// Answer health check requests for a component running on this host.
+///
+/// Returns a future that should be polled for as long as the component runs
+pub(crate) fn serve_component_health(
+    rpc_nats: Arc<Client>,
+    lattice: Arc<str>,
+    subject_prefix: Option<String>,
+    component_id: Arc<str>,
+) -> impl Future<Output = ()> {
+    async move {
+        let subject = prefixed_subject(
+            subject_prefix.as_deref(),
+            health_subject(&lattice, &component_id),
+        );
+        // Multiple hosts may run the same component, a queue group ensures only one responds
+        let mut requests = match rpc_nats
+            .queue_subscribe(subject, format!("{component_id}.health"))
+            .await
+        {
+            Ok(requests) => requests,
+            Err(err) => {
+                warn!(
+                    ?err,
+                    ?component_id,
+                    "failed to subscribe to component health checks"
+                );
+                return;
+            }
+        };
+        let response = match serde_json::to_vec(&HealthCheckResponse {
+            healthy: true,
+            message: None,
+            links: Vec::new(),
+        }) {
+            Ok(response) => Bytes::from(response),
+            Err(err) => {
+                warn!(?err, "failed to serialize component health check response");
+                return;
+            }
+        };
+        while let Some(async_nats::Message { reply, .. }) = requests.next().await {
+            let Some(reply) = reply else {
+                trace!(
+                    ?component_id,
+                    "ignoring health check request without reply subject"
+                );
+                continue;
+            };
+            if let Err(err) = rpc_nats.publish(reply, response.clone()).await {
+                warn!(
+                    ?err,
+                    ?component_id,
+                    "failed to respond to health check request"
+                );
+            }
+        }
+        debug!(?component_id, "component health check subscription ended");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn key(name: &str) -> LinkKey {
+        LinkKey {
+            source_id: "component".into(),
+            wit_namespace: "wasi".into(),
+            wit_package: "keyvalue".into(),
+            name: name.into(),
+        }
+    }
+
+    #[test]
+    fn link_health_changes() {
+        let mut state = LinkHealthState::default();
+        assert_eq!(state.update(key("default"), LinkHealth::Healthy), None);
+        assert_eq!(
+            state.update(
+                key("default"),
+                LinkHealth::Unhealthy {
+                    reason: "gone".into()
+                }
+            ),
+            Some(LinkHealthChange::Unhealthy {
+                reason: "gone".into()
+            })
+        );
+        assert_eq!(
+            state.update(
+                key("default"),
+                LinkHealth::Unhealthy {
+                    reason: "still gone".into()
+                }
+            ),
+            None
+        );
+        assert_eq!(
+            state.update(key("default"), LinkHealth::Healthy),
+            Some(LinkHealthChange::Recovered)
+        );
+
+        // Links which are unhealthy on first probe are reported
+        assert_eq!(
+            state.update(
+                key("other"),
+                LinkHealth::Unhealthy {
+                    reason: "gone".into()
+                }
+            ),
+            Some(LinkHealthChange::Unhealthy {
+                reason: "gone".into()
+            })
+        );
+        state.retain(|key| key.name != "other");
+        assert_eq!(state.update(key("other"), LinkHealth::Healthy), None);
+    }
+}
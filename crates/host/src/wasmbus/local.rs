@@ -0,0 +1,474 @@
+//! In-process invocations between components running on the same host
+//!
+//! Functions without asynchronous parameters served by components on this host are registered
+//! in [`LocalTargets`]. When a component on the same host invokes such a function, and neither
+//! side requires nested streams, parameters and results are exchanged over in-memory pipes
+//! instead of NATS. In-process invocations are still accepted by the serving component's
+//! [`WrpcServer`](super::WrpcServer), so tracing, policy checks and metrics apply exactly as for
+//! invocations received over NATS.
+//!
+//! In-process invocations are disabled for a link by setting [`LOCAL_INVOCATIONS_CONFIG_KEY`] to
+//! `false` in one of the source configurations of the link.
+
+use core::future::Future as _;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use core::time::Duration;
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, PoisonError, RwLock};
+
+use anyhow::bail;
+use bytes::Bytes;
+use tokio::io::{duplex, AsyncRead, AsyncWrite, DuplexStream, ReadBuf};
+use tokio::sync::mpsc;
+use tokio::time::{sleep, Sleep};
+use tracing::{trace, warn};
+use wasmcloud_control_interface::Link;
+use wasmcloud_secrets_types::SECRET_PREFIX;
+
+use super::config::BundleGenerator;
+
+/// Link source configuration key, which disables in-process invocations over the link when set
+/// to `false`
+pub(crate) const LOCAL_INVOCATIONS_CONFIG_KEY: &str = "wasmcloud_local_invocations";
+
+/// Maximum number of in-process invocations of a function waiting to be accepted, before
+/// invocations fall back to NATS
+const LOCAL_INVOCATION_CHANNEL_SIZE: usize = 256;
+
+/// Size of the in-memory pipes used for parameters and results
+const LOCAL_PIPE_SIZE: usize = 64 * 1024;
+
+type LocalTargetMap = HashMap<LocalKey, (u64, mpsc::Sender<LocalInvocation>)>;
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct LocalKey {
+    target: Box<str>,
+    instance: Box<str>,
+    func: Box<str>,
+}
+
+impl LocalKey {
+    fn new(target: &str, instance: &str, func: &str) -> Self {
+        Self {
+            target: target.into(),
+            instance: instance.into(),
+            func: func.into(),
+        }
+    }
+}
+
+/// Invocation received from a component running on this host
+pub(crate) struct LocalInvocation {
+    pub headers: async_nats::HeaderMap,
+    /// Pipe to write results to
+    pub outgoing: DuplexStream,
+    /// Parameters of the invocation
+    pub incoming: LocalIncoming,
+}
+
+/// Functions served by components running on this host
+#[derive(Clone, Debug, Default)]
+pub(crate) struct LocalTargets {
+    next_id: Arc<AtomicU64>,
+    targets: Arc<RwLock<LocalTargetMap>>,
+}
+
+impl LocalTargets {
+    /// Register `func` in `instance` of the `target` component. The function is served in-process
+    /// until the returned [`LocalRegistration`] is dropped.
+    pub(crate) fn register(
+        &self,
+        target: &str,
+        instance: &str,
+        func: &str,
+    ) -> (LocalRegistration, mpsc::Receiver<LocalInvocation>) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = mpsc::channel(LOCAL_INVOCATION_CHANNEL_SIZE);
+        self.targets
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(LocalKey::new(target, instance, func), (id, tx));
+        (
+            LocalRegistration {
+                id,
+                key: Some(LocalKey::new(target, instance, func)),
+                targets: Arc::clone(&self.targets),
+            },
+            rx,
+        )
+    }
+
+    /// Invoke `func` in `instance` of the `target` component in-process, returning the pipe to
+    /// write any remaining parameters to and the results.
+    ///
+    /// Like invocations over NATS, reading the results fails if the component does not start
+    /// responding within `timeout`.
+    ///
+    /// Returns `None` if the function is not served by a component on this host or the component
+    /// is not accepting invocations fast enough, in which case the invocation should be sent
+    /// over NATS.
+    pub(crate) fn invoke(
+        &self,
+        target: &str,
+        instance: &str,
+        func: &str,
+        headers: async_nats::HeaderMap,
+        params: &Bytes,
+        timeout: Duration,
+    ) -> Option<(DuplexStream, LocalIncoming)> {
+        let targets = self.targets.read().unwrap_or_else(PoisonError::into_inner);
+        let (_, tx) = targets.get(&LocalKey::new(target, instance, func))?;
+        let permit = match tx.try_reserve() {
+            Ok(permit) => permit,
+            Err(err) => {
+                trace!(
+                    ?err,
+                    target,
+                    instance,
+                    func,
+                    "local target not accepting invocations"
+                );
+                return None;
+            }
+        };
+        let (params_tx, params_rx) = duplex(LOCAL_PIPE_SIZE);
+        let (results_tx, results_rx) = duplex(LOCAL_PIPE_SIZE);
+        permit.send(LocalInvocation {
+            headers,
+            outgoing: results_tx,
+            incoming: LocalIncoming {
+                buffered: params.clone(),
+                inner: params_rx,
+                deadline: None,
+            },
+        });
+        Some((
+            params_tx,
+            LocalIncoming {
+                buffered: Bytes::new(),
+                inner: results_rx,
+                deadline: Some(Box::pin(sleep(timeout))),
+            },
+        ))
+    }
+}
+
+/// Registration of a function in [`LocalTargets`], which is removed on drop
+#[derive(Debug)]
+pub(crate) struct LocalRegistration {
+    id: u64,
+    key: Option<LocalKey>,
+    targets: Arc<RwLock<LocalTargetMap>>,
+}
+
+impl Drop for LocalRegistration {
+    fn drop(&mut self) {
+        let Some(key) = self.key.take() else {
+            return;
+        };
+        let mut targets = self.targets.write().unwrap_or_else(PoisonError::into_inner);
+        // The function may have been registered again, e.g. by an updated component
+        if targets.get(&key).is_some_and(|(id, _)| *id == self.id) {
+            targets.remove(&key);
+        }
+    }
+}
+
+/// Incoming stream of an in-process invocation, which yields `buffered` before reading from the
+/// pipe
+pub(crate) struct LocalIncoming {
+    buffered: Bytes,
+    inner: DuplexStream,
+    /// Deadline for the first read from the pipe to complete, if any
+    deadline: Option<Pin<Box<Sleep>>>,
+}
+
+impl AsyncRead for LocalIncoming {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        if !self.buffered.is_empty() {
+            let n = buf.remaining().min(self.buffered.len());
+            let chunk = self.buffered.split_to(n);
+            buf.put_slice(&chunk);
+            return Poll::Ready(Ok(()));
+        }
+        if let Poll::Ready(res) = Pin::new(&mut self.inner).poll_read(cx, buf) {
+            self.deadline = None;
+            return Poll::Ready(res);
+        }
+        if let Some(deadline) = self.deadline.as_mut() {
+            if deadline.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(Err(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "in-process invocation timed out",
+                )));
+            }
+        }
+        Poll::Pending
+    }
+}
+
+/// Outgoing stream of an invocation, sent either over NATS or in-process
+pub(crate) enum Outgoing<T> {
+    Nats(T),
+    Local(DuplexStream),
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for Outgoing<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        match self.get_mut() {
+            Self::Nats(tx) => Pin::new(tx).poll_write(cx, buf),
+            Self::Local(tx) => Pin::new(tx).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            Self::Nats(tx) => Pin::new(tx).poll_flush(cx),
+            Self::Local(tx) => Pin::new(tx).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            Self::Nats(tx) => Pin::new(tx).poll_shutdown(cx),
+            Self::Local(tx) => Pin::new(tx).poll_shutdown(cx),
+        }
+    }
+}
+
+impl<T: wrpc_transport::Index<T>> wrpc_transport::Index<Self> for Outgoing<T> {
+    fn index(&self, path: &[usize]) -> anyhow::Result<Self> {
+        match self {
+            Self::Nats(tx) => tx.index(path).map(Self::Nats),
+            Self::Local(..) => bail!("nested streams are not supported by in-process invocations"),
+        }
+    }
+}
+
+/// Incoming stream of an invocation, received either over NATS or in-process
+pub(crate) enum Incoming<T> {
+    Nats(T),
+    /// Stream received over NATS, of which the leading bytes have already been read
+    Buffered(Bytes, T),
+    Local(LocalIncoming),
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for Incoming<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        match self.get_mut() {
+            Self::Nats(rx) => Pin::new(rx).poll_read(cx, buf),
+            Self::Buffered(buffered, rx) => {
+                if !buffered.is_empty() {
+                    let n = buf.remaining().min(buffered.len());
+                    let chunk = buffered.split_to(n);
+                    buf.put_slice(&chunk);
+                    return Poll::Ready(Ok(()));
+                }
+                Pin::new(rx).poll_read(cx, buf)
+            }
+            Self::Local(rx) => Pin::new(rx).poll_read(cx, buf),
+        }
+    }
+}
+
+impl<T: wrpc_transport::Index<T>> wrpc_transport::Index<Self> for Incoming<T> {
+    fn index(&self, path: &[usize]) -> anyhow::Result<Self> {
+        match self {
+            // Nested streams are received separately from the buffered bytes
+            Self::Nats(rx) | Self::Buffered(_, rx) => rx.index(path).map(Self::Nats),
+            Self::Local(..) => bail!("nested streams are not supported by in-process invocations"),
+        }
+    }
+}
+
+/// Returns the link names and instances of `links`, for which in-process invocations are
+/// disabled via [`LOCAL_INVOCATIONS_CONFIG_KEY`]
+pub(crate) async fn local_invocation_opt_outs(
+    config_generator: &BundleGenerator,
+    links: &[Link],
+) -> HashSet<(Box<str>, Box<str>)> {
+    let mut opt_outs = HashSet::new();
+    for link in links {
+        let config_names: Vec<_> = link
+            .source_config()
+            .iter()
+            .filter(|name| !name.starts_with(SECRET_PREFIX))
+            .cloned()
+            .collect();
+        if config_names.is_empty() {
+            continue;
+        }
+        let config = match config_generator.generate(config_names).await {
+            Ok(config) => config,
+            Err(err) => {
+                warn!(
+                    ?err,
+                    source_id = link.source_id(),
+                    name = link.name(),
+                    "failed to fetch link source configuration"
+                );
+                continue;
+            }
+        };
+        let disabled = config
+            .get_config()
+            .await
+            .get(LOCAL_INVOCATIONS_CONFIG_KEY)
+            .is_some_and(|value| value.eq_ignore_ascii_case("false"));
+        if disabled {
+            for interface in link.interfaces() {
+                opt_outs.insert((
+                    link.name().into(),
+                    format!(
+                        "{}:{}/{interface}",
+                        link.wit_namespace(),
+                        link.wit_package()
+                    )
+                    .into(),
+                ));
+            }
+        }
+    }
+    opt_outs
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+    #[tokio::test]
+    async fn local_invocation() {
+        let targets = LocalTargets::default();
+        let headers = async_nats::HeaderMap::new();
+        let params = Bytes::from_static(b"params");
+        assert!(targets
+            .invoke(
+                "callee",
+                "test:test/iface",
+                "f",
+                headers.clone(),
+                &params,
+                Duration::from_secs(10),
+            )
+            .is_none());
+
+        let (registration, mut invocations) = targets.register("callee", "test:test/iface", "f");
+        let (params_tx, mut results) = targets
+            .invoke(
+                "callee",
+                "test:test/iface",
+                "f",
+                headers.clone(),
+                &params,
+                Duration::from_secs(10),
+            )
+            .expect("function should be served in-process");
+        drop(params_tx);
+
+        let LocalInvocation {
+            mut outgoing,
+            mut incoming,
+            ..
+        } = invocations.recv().await.expect("missing invocation");
+        let mut buf = vec![];
+        incoming
+            .read_to_end(&mut buf)
+            .await
+            .expect("failed to read params");
+        assert_eq!(buf, b"params");
+
+        outgoing
+            .write_all(b"results")
+            .await
+            .expect("failed to write results");
+        drop(outgoing);
+        let mut buf = vec![];
+        results
+            .read_to_end(&mut buf)
+            .await
+            .expect("failed to read results");
+        assert_eq!(buf, b"results");
+
+        drop(registration);
+        assert!(targets
+            .invoke(
+                "callee",
+                "test:test/iface",
+                "f",
+                headers,
+                &params,
+                Duration::from_secs(10),
+            )
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn local_invocation_timeout() {
+        let targets = LocalTargets::default();
+        let (_registration, mut invocations) = targets.register("callee", "test:test/iface", "f");
+        let (_params_tx, mut results) = targets
+            .invoke(
+                "callee",
+                "test:test/iface",
+                "f",
+                async_nats::HeaderMap::new(),
+                &Bytes::new(),
+                Duration::from_millis(50),
+            )
+            .expect("function should be served in-process");
+
+        // The component accepts the invocation, but never responds
+        let _invocation = invocations.recv().await.expect("missing invocation");
+        let err = tokio::time::timeout(Duration::from_secs(5), results.read_to_end(&mut vec![]))
+            .await
+            .expect("invocation did not time out")
+            .expect_err("invocation should fail");
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+    }
+
+    #[tokio::test]
+    async fn local_invocation_slow_results() {
+        let targets = LocalTargets::default();
+        let (_registration, mut invocations) = targets.register("callee", "test:test/iface", "f");
+        let (_params_tx, mut results) = targets
+            .invoke(
+                "callee",
+                "test:test/iface",
+                "f",
+                async_nats::HeaderMap::new(),
+                &Bytes::new(),
+                Duration::from_millis(50),
+            )
+            .expect("function should be served in-process");
+
+        let LocalInvocation { mut outgoing, .. } =
+            invocations.recv().await.expect("missing invocation");
+        // Results which started within the timeout may take longer to complete
+        outgoing.write_all(b"res").await.unwrap();
+        let mut buf = [0; 3];
+        results.read_exact(&mut buf).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        outgoing.write_all(b"ults").await.unwrap();
+        drop(outgoing);
+        let mut buf = vec![];
+        results.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, b"ults");
+    }
+}
@@ -0,0 +1,41 @@
+//! Per-component in-memory blobstore for local development
+//!
+//! Components listed in [`HostConfig::memory_blobstore`](super::HostConfig::memory_blobstore)
+//! have their `wasi:blobstore` imports served by a [`MemoryBlobstore`] embedded in the host,
+//! instead of a linked provider. The blobstore is shared by all instances of the component and
+//! kept across scaling and updates, until the component is stopped.
+
+use wasmcloud_runtime::component::MemoryBlobstore;
+
+/// Wildcard matching all components
+pub(crate) const ALL_COMPONENTS: &str = "*";
+
+/// Default maximum total size of the objects in the in-memory blobstore of a component, 64 MiB
+pub(crate) const DEFAULT_MEMORY_BLOBSTORE_QUOTA: u64 = 64 * 1024 * 1024;
+
+/// Returns a new in-memory blobstore holding at most `quota` bytes for the component with
+/// `component_id`, if it is listed in `components`
+pub(crate) fn memory_blobstore(
+    components: &[String],
+    component_id: &str,
+    quota: u64,
+) -> Option<MemoryBlobstore> {
+    components
+        .iter()
+        .any(|id| id == ALL_COMPONENTS || id == component_id)
+        .then(|| MemoryBlobstore::new(quota))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn memory_blobstore_components() {
+        let quota = DEFAULT_MEMORY_BLOBSTORE_QUOTA;
+        assert!(memory_blobstore(&[], "http-hello", quota).is_none());
+        assert!(memory_blobstore(&["uploads".into()], "http-hello", quota).is_none());
+        assert!(memory_blobstore(&["uploads".into()], "uploads", quota).is_some());
+        assert!(memory_blobstore(&[ALL_COMPONENTS.into()], "http-hello", quota).is_some());
+    }
+}
@@ -3,7 +3,7 @@
 use core::sync::atomic::Ordering;
 
 use std::collections::hash_map::Entry;
-use std::collections::{hash_map, BTreeMap, HashMap};
+use std::collections::{hash_map, BTreeMap, HashMap, HashSet};
 use std::env::consts::{ARCH, FAMILY, OS};
 use std::future::Future;
 use std::num::NonZeroUsize;
@@ -35,19 +35,25 @@ use tokio::net::TcpListener;
 use tokio::spawn;
 use tokio::sync::{mpsc, watch, RwLock, Semaphore};
 use tokio::task::{JoinHandle, JoinSet};
-use tokio::time::{interval_at, Instant};
+use tokio::time::{interval_at, sleep, Instant};
 use tokio_stream::wrappers::IntervalStream;
 use tracing::{debug, debug_span, error, info, instrument, trace, warn, Instrument as _};
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 use wascap::jwt;
 use wasmcloud_control_interface::{
-    ComponentAuctionAck, ComponentAuctionRequest, ComponentDescription, CtlResponse,
-    DeleteInterfaceLinkDefinitionRequest, HostInventory, HostLabel, HostLabelIdentifier, Link,
-    ProviderAuctionAck, ProviderAuctionRequest, ProviderDescription, RegistryCredential,
-    ScaleComponentCommand, StartProviderCommand, StopHostCommand, StopProviderCommand,
-    UpdateComponentCommand,
+    CapturedPayload, ClaimsBundle, ComponentAuctionAck, ComponentAuctionRequest,
+    ComponentDescription, ComponentInstancePool, ComponentQuarantine, ConfigRevision,
+    ConfigRollbackRequest, CtlResponse, DeleteInterfaceLinkDefinitionRequest, HostClockSkew,
+    HostInventory, HostLabel, HostLabelIdentifier, Link, LinkValidation, PayloadCapture,
+    ProviderAuctionAck, ProviderAuctionRequest, ProviderDescription, ProviderLinkHealth,
+    RegistryCredential, ScaleComponentCommand, StartProviderCommand, StopHostCommand,
+    StopProviderCommand, TrafficSplit, UpdateComponentCommand, UpdateProviderCommand,
+};
+use wasmcloud_core::rpc::link_validate_subject;
+use wasmcloud_core::{
+    prefixed_subject, ComponentId, LinkHealthCheck, LinkHealthStatus, LinkValidationResponse,
+    CTL_API_VERSION_1,
 };
-use wasmcloud_core::{ComponentId, CTL_API_VERSION_1};
 use wasmcloud_runtime::capability::secrets::store::SecretValue;
 use wasmcloud_runtime::component::WrpcServeEvent;
 use wasmcloud_runtime::Runtime;
@@ -55,20 +61,35 @@ use wasmcloud_secrets_types::SECRET_PREFIX;
 use wasmcloud_tracing::context::TraceContextInjector;
 use wasmcloud_tracing::{global, InstrumentationScope, KeyValue};
 
+use crate::metrics::{CacheUsage, ComponentUsage, TaskUsage};
 use crate::registry::RegistryCredentialExt;
-use crate::wasmbus::jetstream::create_bucket;
+use crate::wasmbus::jetstream::{create_bucket, create_config_bucket};
+use crate::wasmbus::providers::lease::{self, create_lease_bucket, ProviderLease};
 use crate::{
     fetch_component, HostMetrics, OciConfig, PolicyHostInfo, PolicyManager, PolicyResponse,
     RegistryAuth, RegistryConfig, RegistryType, ResourceRef, SecretsManager,
 };
 
+mod capture;
 mod claims;
+mod clock_skew;
+mod crash_loop;
 mod ctl;
+mod deterministic;
 mod event;
 mod experimental;
 mod handler;
+mod hedging;
+mod instance_pool;
 mod jetstream;
+mod link_health;
+mod local;
+mod memory_blobstore;
 mod providers;
+mod scratch;
+mod tasks;
+mod tenant;
+mod traffic;
 
 pub mod config;
 /// wasmCloud host configuration
@@ -76,13 +97,32 @@ pub mod host_config;
 
 pub use self::experimental::Features;
 pub use self::host_config::Host as HostConfig;
+pub use self::tenant::nats_authorization;
 pub use jetstream::ComponentSpecification;
 
+use self::capture::PayloadCaptures;
+use self::clock_skew::{ClockSkewChange, ClockSkewState};
 use self::config::{BundleGenerator, ConfigBundle};
+use self::crash_loop::{CrashLoopDetector, Quarantine};
+use self::deterministic::deterministic_seed;
 use self::handler::Handler;
+use self::hedging::hedge_policies;
+use self::instance_pool::instance_pool_config;
+use self::link_health::{
+    LinkHealth, LinkHealthChange, LinkHealthState, LinkKey, DEFAULT_LINK_HEALTH_INTERVAL,
+    LINK_HEALTH_TIMEOUT,
+};
+use self::local::{local_invocation_opt_outs, LocalInvocation, LocalTargets};
+use self::memory_blobstore::memory_blobstore;
+use self::scratch::scratch_dir_config;
+use self::tasks::{TaskOwner, TaskRegistry};
+use self::traffic::TrafficSplits;
 
 const MAX_INVOCATION_CHANNEL_SIZE: usize = 5000;
 const MIN_INVOCATION_CHANNEL_SIZE: usize = 256;
+/// How long an old provider instance keeps serving after it was replaced by a hot-swap, unless
+/// the provider shutdown delay is configured
+const DEFAULT_PROVIDER_DRAIN_DELAY: Duration = Duration::from_secs(5);
 
 #[derive(Debug)]
 struct Queue {
@@ -160,6 +200,9 @@ impl Queue {
                 format!("{topic_prefix}.{CTL_API_VERSION_1}.{lattice}.link.*"),
                 format!("{topic_prefix}.{CTL_API_VERSION_1}.{lattice}.link",),
             )),
+            Either::Left(nats.subscribe(format!(
+                "{topic_prefix}.{CTL_API_VERSION_1}.{lattice}.link.validate.{host_id}"
+            ))),
             Either::Right(nats.queue_subscribe(
                 format!("{topic_prefix}.{CTL_API_VERSION_1}.{lattice}.claims.get"),
                 format!("{topic_prefix}.{CTL_API_VERSION_1}.{lattice}.claims"),
@@ -217,6 +260,8 @@ struct Component {
     image_reference: Arc<str>,
     events: mpsc::Sender<WrpcServeEvent<<WrpcServer as wrpc_transport::Serve>::Context>>,
     permits: Arc<Semaphore>,
+    /// Detects crash loops of this component, quarantining it
+    crash_loop: Arc<CrashLoopDetector>,
 }
 
 impl Deref for Component {
@@ -236,6 +281,10 @@ struct WrpcServer {
     annotations: Arc<Annotations>,
     policy_manager: Arc<PolicyManager>,
     metrics: Arc<HostMetrics>,
+    /// Registry to serve functions without nested streams in-process in, if enabled
+    local_targets: Option<LocalTargets>,
+    /// Invocations are rejected while the component is quarantined
+    crash_loop: Arc<CrashLoopDetector>,
 }
 
 struct InvocationContext {
@@ -254,8 +303,10 @@ impl Deref for InvocationContext {
 
 impl wrpc_transport::Serve for WrpcServer {
     type Context = InvocationContext;
-    type Outgoing = <wrpc_transport_nats::Client as wrpc_transport::Serve>::Outgoing;
-    type Incoming = <wrpc_transport_nats::Client as wrpc_transport::Serve>::Incoming;
+    type Outgoing =
+        local::Outgoing<<wrpc_transport_nats::Client as wrpc_transport::Serve>::Outgoing>;
+    type Incoming =
+        local::Incoming<<wrpc_transport_nats::Client as wrpc_transport::Serve>::Incoming>;
 
     #[instrument(
         level = "info",
@@ -275,7 +326,35 @@ impl wrpc_transport::Serve for WrpcServer {
             + 'static,
     > {
         debug!("serving invocations");
-        let invocations = self.nats.serve(instance, func, paths).await?;
+        let paths: Arc<[Box<[Option<usize>]>]> = paths.into();
+        let local = self
+            .local_targets
+            .as_ref()
+            .filter(|_| paths.is_empty())
+            .map(|local_targets| local_targets.register(&self.id, instance, func));
+        let invocations = self
+            .nats
+            .serve(instance, func, paths)
+            .await?
+            .map_ok(|(cx, tx, rx)| (cx, local::Outgoing::Nats(tx), local::Incoming::Nats(rx)));
+        // The registration is kept alive for as long as invocations are served
+        let local = stream::unfold(local, |local| async move {
+            let (registration, mut invocations) = local?;
+            let LocalInvocation {
+                headers,
+                outgoing,
+                incoming,
+            } = invocations.recv().await?;
+            Some((
+                Ok((
+                    Some(headers),
+                    local::Outgoing::Local(outgoing),
+                    local::Incoming::Local(incoming),
+                )),
+                Some((registration, invocations)),
+            ))
+        });
+        let invocations = stream::select(invocations, local);
 
         let func: Arc<str> = Arc::from(func);
         let instance: Arc<str> = Arc::from(instance);
@@ -285,8 +364,10 @@ impl wrpc_transport::Serve for WrpcServer {
         let metrics = Arc::clone(&self.metrics);
         let policy_manager = Arc::clone(&self.policy_manager);
         let claims = self.claims.clone();
+        let crash_loop = Arc::clone(&self.crash_loop);
         Ok(invocations.and_then(move |(cx, tx, rx)| {
             let annotations = Arc::clone(&annotations);
+            let crash_loop = Arc::clone(&crash_loop);
             let claims = claims.clone();
             let func = Arc::clone(&func);
             let id = Arc::clone(&id);
@@ -311,6 +392,18 @@ impl wrpc_transport::Serve for WrpcServer {
                     span.set_parent(wasmcloud_tracing::context::get_span_context(&trace_context));
                 }
 
+                if let Some(Quarantine { reason, until, .. }) =
+                    crash_loop.quarantine(std::time::Instant::now())
+                {
+                    let remaining = until
+                        .duration_since(std::time::SystemTime::now())
+                        .unwrap_or_default();
+                    bail!(
+                        "component `{id}` is quarantined for {}s after repeatedly failing to handle invocations, last error: {reason}",
+                        remaining.as_secs()
+                    );
+                }
+
                 let PolicyResponse {
                     request_id,
                     permitted,
@@ -357,6 +450,12 @@ pub struct Host {
     event_builder: EventBuilderV10,
     friendly_name: String,
     heartbeat: AbortHandle,
+    /// Task to probe the health of links whose source runs on this host
+    link_health: AbortHandle,
+    /// Task to compare the heartbeats of other hosts with the clock of this host
+    clock_skew_watch: AbortHandle,
+    /// Clock skew of the other hosts in the lattice, as observed from their heartbeats
+    clock_skew: RwLock<ClockSkewState>,
     host_config: HostConfig,
     host_key: Arc<KeyPair>,
     host_token: Arc<jwt::Token<jwt::Host>>,
@@ -372,11 +471,15 @@ pub struct Host {
     /// Task to watch for changes in the LATTICEDATA store
     data_watch: AbortHandle,
     config_data: Store,
+    /// Leases of singleton providers in the lattice
+    provider_leases: Store,
     config_generator: BundleGenerator,
     policy_manager: Arc<PolicyManager>,
     secrets_manager: Arc<SecretsManager>,
     /// The provider map is a map of provider component ID to provider
     providers: RwLock<HashMap<String, Provider>>,
+    /// Health of the links of providers running on this host, as last reported by the providers
+    provider_link_health: Arc<RwLock<HashMap<String, Vec<LinkHealthCheck>>>>,
     registry_config: RwLock<HashMap<String, RegistryConfig>>,
     runtime: Runtime,
     start_at: Instant,
@@ -385,23 +488,35 @@ pub struct Host {
     queue: AbortHandle,
     // Component ID -> All Links
     links: RwLock<HashMap<String, Vec<Link>>>,
+    /// Traffic splits of link targets in the lattice, shared with component handlers
+    traffic_splits: Arc<RwLock<TrafficSplits>>,
+    /// Payload captures of components running on this host, shared with component handlers
+    payload_captures: Arc<RwLock<PayloadCaptures>>,
     component_claims: Arc<RwLock<HashMap<ComponentId, jwt::Claims<jwt::Component>>>>, // TODO: use a single map once Claims is an enum
     provider_claims: Arc<RwLock<HashMap<String, jwt::Claims<jwt::CapabilityProvider>>>>,
+    /// Issuers trusted by the lattice, imported with claims bundles
+    imported_issuers: RwLock<HashSet<String>>,
     metrics: Arc<HostMetrics>,
     max_execution_time: Duration,
     messaging_links:
         Arc<RwLock<HashMap<Arc<str>, Arc<RwLock<HashMap<Box<str>, async_nats::Client>>>>>>,
     /// Experimental features to enable in the host that gate functionality
     experimental_features: Features,
+    /// Functions served by components on this host, if in-process invocations are enabled
+    local_targets: Option<LocalTargets>,
     ready: Arc<AtomicBool>,
     /// A set of host tasks
     #[allow(unused)]
     tasks: JoinSet<()>,
+    /// Registry of the long-lived tasks spawned by the host, used to detect leaked tasks
+    task_registry: Arc<TaskRegistry>,
 }
 
-/// Given the NATS address, authentication jwt, seed, tls requirement and optional request timeout,
-/// attempt to establish connection.
+/// Given the NATS address, authentication jwt, seed, tls requirement, optional request timeout
+/// and optional subject prefix of the lattice, attempt to establish connection.
 ///
+/// If a subject prefix is given, replies are received on `<prefix>._INBOX.>` instead of `_INBOX.>`,
+/// so that the connection only requires permissions for the subjects under the prefix.
 ///
 /// # Errors
 ///
@@ -414,8 +529,14 @@ async fn connect_nats(
     key: Option<Arc<KeyPair>>,
     require_tls: bool,
     request_timeout: Option<Duration>,
+    subject_prefix: Option<&str>,
 ) -> anyhow::Result<async_nats::Client> {
     let opts = async_nats::ConnectOptions::new().require_tls(require_tls);
+    let opts = if let Some(prefix) = subject_prefix {
+        opts.custom_inbox_prefix(tenant::inbox_prefix(prefix))
+    } else {
+        opts
+    };
     let opts = match (jwt, key) {
         (Some(jwt), Some(key)) => opts.jwt(jwt.to_string(), {
             move |nonce| {
@@ -447,6 +568,7 @@ struct SupplementalConfig {
 async fn load_supplemental_config(
     ctl_nats: &async_nats::Client,
     lattice: &str,
+    subject_prefix: Option<&str>,
     labels: &BTreeMap<String, String>,
 ) -> anyhow::Result<SupplementalConfig> {
     #[derive(Deserialize, Default)]
@@ -455,7 +577,7 @@ async fn load_supplemental_config(
         registry_credentials: Option<HashMap<String, RegistryCredential>>,
     }
 
-    let cfg_topic = format!("wasmbus.cfg.{lattice}.req");
+    let cfg_topic = prefixed_subject(subject_prefix, format!("wasmbus.cfg.{lattice}.req"));
     let cfg_payload = serde_json::to_vec(&json!({
         "labels": labels,
     }))
@@ -598,8 +720,21 @@ impl Host {
     /// Construct a new [Host] returning a tuple of its [Arc] and an async shutdown function.
     #[instrument(level = "debug", skip_all)]
     pub async fn new(
-        config: HostConfig,
+        mut config: HostConfig,
     ) -> anyhow::Result<(Arc<Self>, impl Future<Output = anyhow::Result<()>>)> {
+        tenant::apply_subject_prefix(&mut config).context("invalid subject prefix")?;
+        // Instances are started without a scratch directory if it cannot be created, so the root
+        // is checked upfront
+        if let Some(scratch_dir) = &config.scratch_dir {
+            tokio::fs::create_dir_all(scratch_dir)
+                .await
+                .with_context(|| {
+                    format!(
+                        "failed to create scratch directory root `{}`",
+                        scratch_dir.display()
+                    )
+                })?;
+        }
         let host_key = if let Some(host_key) = &config.host_key {
             ensure!(host_key.key_pair_type() == KeyPairType::Server);
             Arc::clone(host_key)
@@ -650,6 +785,7 @@ impl Host {
                     config.ctl_key.clone(),
                     config.ctl_tls,
                     None,
+                    config.subject_prefix.as_deref(),
                 )
                 .await
                 .context("failed to establish NATS control server connection")?;
@@ -677,6 +813,7 @@ impl Host {
                     config.rpc_key.clone(),
                     config.rpc_tls,
                     Some(config.rpc_timeout),
+                    config.subject_prefix.as_deref(),
                 )
                 .await
                 .context("failed to establish NATS RPC server connection")
@@ -693,6 +830,15 @@ impl Host {
             .context("failed to compute heartbeat start time")?;
         let heartbeat = IntervalStream::new(interval_at(heartbeat_start_at, heartbeat_interval));
 
+        let link_health_interval = config
+            .link_health_interval
+            .unwrap_or(DEFAULT_LINK_HEALTH_INTERVAL);
+        let link_health_start_at = start_at
+            .checked_add(link_health_interval)
+            .context("failed to compute link health check start time")?;
+        let link_health =
+            IntervalStream::new(interval_at(link_health_start_at, link_health_interval));
+
         let (stop_tx, stop_rx) = watch::channel(None);
 
         let (runtime, _epoch) = Runtime::builder()
@@ -701,27 +847,65 @@ impl Host {
             .max_components(config.max_components)
             .max_component_size(config.max_component_size)
             .experimental_features(config.experimental_features.into())
+            .validate_invocations(config.validate_invocations)
             .build()
             .context("failed to build runtime")?;
         let event_builder = EventBuilderV10::new().source(host_key.public_key());
 
+        // Heartbeats of other hosts are compared with the clock of this host, unless disabled
+        let heartbeats = if config.clock_skew_threshold.is_zero() {
+            None
+        } else {
+            let heartbeats = ctl_nats
+                .subscribe(
+                    config
+                        .prefixed_subject(format!("wasmbus.evt.{}.host_heartbeat", config.lattice)),
+                )
+                .await
+                .context("failed to subscribe to host heartbeats")?;
+            Some(heartbeats)
+        };
+
         let ctl_jetstream = if let Some(domain) = config.js_domain.as_ref() {
             async_nats::jetstream::with_domain(ctl_nats.clone(), domain)
         } else {
             async_nats::jetstream::new(ctl_nats.clone())
         };
-        let bucket = format!("LATTICEDATA_{}", config.lattice);
+        let bucket = tenant::lattice_bucket(
+            "LATTICEDATA",
+            config.subject_prefix.as_deref(),
+            &config.lattice,
+        );
         let data = create_bucket(&ctl_jetstream, &bucket).await?;
 
-        let config_bucket = format!("CONFIGDATA_{}", config.lattice);
-        let config_data = create_bucket(&ctl_jetstream, &config_bucket).await?;
+        let config_bucket = tenant::lattice_bucket(
+            "CONFIGDATA",
+            config.subject_prefix.as_deref(),
+            &config.lattice,
+        );
+        let config_data = create_config_bucket(&ctl_jetstream, &config_bucket).await?;
+
+        let lease_bucket = tenant::lattice_bucket(
+            "PROVIDERLEASES",
+            config.subject_prefix.as_deref(),
+            &config.lattice,
+        );
+        let provider_leases = create_lease_bucket(&ctl_jetstream, &lease_bucket).await?;
 
         let (queue_abort, queue_abort_reg) = AbortHandle::new_pair();
         let (heartbeat_abort, heartbeat_abort_reg) = AbortHandle::new_pair();
+        let (link_health_abort, link_health_abort_reg) = AbortHandle::new_pair();
+        let (clock_skew_abort, clock_skew_abort_reg) = AbortHandle::new_pair();
         let (data_watch_abort, data_watch_abort_reg) = AbortHandle::new_pair();
 
         let supplemental_config = if config.config_service_enabled {
-            load_supplemental_config(&ctl_nats, &config.lattice, &labels).await?
+            load_supplemental_config(
+                &ctl_nats,
+                &config.lattice,
+                config.subject_prefix.as_deref(),
+                &labels,
+            )
+            .await?
         } else {
             SupplementalConfig::default()
         };
@@ -753,11 +937,17 @@ impl Host {
             "secrets topic prefix must be non-empty"
         );
 
-        let secrets_manager = Arc::new(SecretsManager::new(
+        let secrets_manager = SecretsManager::new(
             &config_data,
             config.secrets_topic_prefix.as_ref(),
             &ctl_nats,
-        ));
+        );
+        let secrets_manager = if let Some(kubernetes) = &config.kubernetes_secrets {
+            secrets_manager.with_kubernetes_backend(kubernetes).await?
+        } else {
+            secrets_manager
+        };
+        let secrets_manager = Arc::new(secrets_manager);
 
         let scope = InstrumentationScope::builder("wasmcloud-host")
             .with_version(config.version.clone())
@@ -767,7 +957,79 @@ impl Host {
             ])
             .build();
         let meter = global::meter_with_scope(scope);
-        let metrics = HostMetrics::new(&meter, host_key.public_key(), config.lattice.to_string());
+        let components: Arc<RwLock<HashMap<ComponentId, Arc<Component>>>> = Arc::default();
+        let task_registry = Arc::new(TaskRegistry::default());
+        let component_claims: Arc<RwLock<HashMap<ComponentId, jwt::Claims<jwt::Component>>>> =
+            Arc::default();
+        let provider_claims: Arc<RwLock<HashMap<String, jwt::Claims<jwt::CapabilityProvider>>>> =
+            Arc::default();
+        let metrics = HostMetrics::new(&meter, host_key.public_key(), config.lattice.to_string())
+            .with_component_gauges(&meter, {
+                let components = Arc::clone(&components);
+                move || {
+                    // Gauges are observed synchronously, skip the observation if the components
+                    // are being modified
+                    let Ok(components) = components.try_read() else {
+                        return Vec::new();
+                    };
+                    components
+                        .values()
+                        .map(|component| {
+                            let max_instances = component.max_instances.get();
+                            let active_instances =
+                                max_instances.saturating_sub(component.permits.available_permits());
+                            ComponentUsage {
+                                id: component.id.to_string(),
+                                max_instances: u64::try_from(max_instances).unwrap_or(u64::MAX),
+                                active_instances: u64::try_from(active_instances)
+                                    .unwrap_or(u64::MAX),
+                            }
+                        })
+                        .collect()
+                }
+            })
+            .with_task_gauges(&meter, {
+                let task_registry = Arc::clone(&task_registry);
+                move || {
+                    task_registry
+                        .counts()
+                        .into_iter()
+                        .map(|(owner, active_tasks)| TaskUsage {
+                            owner: owner.kind(),
+                            owner_id: owner.id(),
+                            active_tasks,
+                        })
+                        .collect()
+                }
+            })
+            .with_scheduler_gauges(&meter, tokio::runtime::Handle::current())
+            .with_cache_gauges(&meter, {
+                let policy_manager = Arc::clone(&policy_manager);
+                let component_claims = Arc::clone(&component_claims);
+                let provider_claims = Arc::clone(&provider_claims);
+                move || {
+                    // Caches being modified are skipped, like the component gauges
+                    [
+                        ("policy_decisions", policy_manager.cached_decisions()),
+                        (
+                            "component_claims",
+                            component_claims.try_read().ok().map(|claims| claims.len()),
+                        ),
+                        (
+                            "provider_claims",
+                            provider_claims.try_read().ok().map(|claims| claims.len()),
+                        ),
+                    ]
+                    .into_iter()
+                    .filter_map(|(cache, entries)| {
+                        Some(CacheUsage {
+                            cache,
+                            entries: u64::try_from(entries?).unwrap_or(u64::MAX),
+                        })
+                    })
+                    .collect()
+                }
+            });
 
         let config_generator = BundleGenerator::new(config_data.clone());
 
@@ -823,6 +1085,30 @@ impl Host {
                             .body(http_body_util::Full::new(Bytes::from(format!(
                                 "method `{method}` not supported for path `/readyz`"
                             )))),
+                        ("GET", "/metrics") => {
+                            let Some(registry) = wasmcloud_tracing::prometheus_registry() else {
+                                return http::Response::builder()
+                                    .status(http::StatusCode::NOT_FOUND)
+                                    .body(http_body_util::Full::new(Bytes::from(
+                                        "metrics endpoint is not enabled",
+                                    )));
+                            };
+                            match registry.encode() {
+                                Ok(metrics) => http::Response::builder()
+                                    .header(http::header::CONTENT_TYPE, "text/plain; version=0.0.4")
+                                    .body(http_body_util::Full::new(Bytes::from(metrics))),
+                                Err(err) => http::Response::builder()
+                                    .status(http::StatusCode::INTERNAL_SERVER_ERROR)
+                                    .body(http_body_util::Full::new(Bytes::from(format!(
+                                        "{err:#}"
+                                    )))),
+                            }
+                        }
+                        (method, "/metrics") => http::Response::builder()
+                            .status(http::StatusCode::METHOD_NOT_ALLOWED)
+                            .body(http_body_util::Full::new(Bytes::from(format!(
+                                "method `{method}` not supported for path `/metrics`"
+                            )))),
                         (.., path) => http::Response::builder()
                             .status(http::StatusCode::NOT_FOUND)
                             .body(http_body_util::Full::new(Bytes::from(format!(
@@ -850,10 +1136,13 @@ impl Host {
         }
 
         let host = Host {
-            components: Arc::default(),
+            components,
             event_builder,
             friendly_name,
             heartbeat: heartbeat_abort.clone(),
+            link_health: link_health_abort.clone(),
+            clock_skew_watch: clock_skew_abort.clone(),
+            clock_skew: RwLock::default(),
             ctl_topic_prefix: config.ctl_topic_prefix.clone(),
             host_key,
             host_token,
@@ -862,14 +1151,17 @@ impl Host {
             ctl_nats,
             rpc_nats: Arc::new(rpc_nats),
             experimental_features: config.experimental_features,
+            local_targets: config.enable_local_invocations.then(LocalTargets::default),
             host_config: config,
             data: data.clone(),
             data_watch: data_watch_abort.clone(),
             config_data: config_data.clone(),
+            provider_leases,
             config_generator,
             policy_manager,
             secrets_manager,
             providers: RwLock::default(),
+            provider_link_health: Arc::default(),
             registry_config,
             runtime,
             start_at,
@@ -877,17 +1169,21 @@ impl Host {
             stop_tx,
             queue: queue_abort.clone(),
             links: RwLock::default(),
-            component_claims: Arc::default(),
-            provider_claims: Arc::default(),
+            traffic_splits: Arc::default(),
+            payload_captures: Arc::default(),
+            component_claims,
+            provider_claims,
+            imported_issuers: RwLock::default(),
             metrics: Arc::new(metrics),
             max_execution_time: max_execution_time_ms,
             messaging_links: Arc::default(),
             ready: Arc::clone(&ready),
             tasks,
+            task_registry,
         };
 
         let host = Arc::new(host);
-        let queue = spawn({
+        let queue = spawn(host.task_registry.track("ctl_queue", TaskOwner::Host, {
             let host = Arc::clone(&host);
             async move {
                 let mut queue = Abortable::new(queue, queue_abort_reg);
@@ -909,46 +1205,47 @@ impl Host {
                     error!("control interface queue task unexpectedly stopped");
                 }
             }
-        });
+        }));
 
-        let data_watch: JoinHandle<anyhow::Result<_>> = spawn({
-            let data = data.clone();
-            let host = Arc::clone(&host);
-            async move {
-                let data_watch = data
-                    .watch_all()
-                    .await
-                    .context("failed to watch lattice data bucket")?;
-                let mut data_watch = Abortable::new(data_watch, data_watch_abort_reg);
-                data_watch
-                    .by_ref()
-                    .for_each({
-                        let host = Arc::clone(&host);
-                        move |entry| {
+        let data_watch: JoinHandle<anyhow::Result<_>> =
+            spawn(host.task_registry.track("data_watch", TaskOwner::Host, {
+                let data = data.clone();
+                let host = Arc::clone(&host);
+                async move {
+                    let data_watch = data
+                        .watch_all()
+                        .await
+                        .context("failed to watch lattice data bucket")?;
+                    let mut data_watch = Abortable::new(data_watch, data_watch_abort_reg);
+                    data_watch
+                        .by_ref()
+                        .for_each({
                             let host = Arc::clone(&host);
-                            async move {
-                                match entry {
-                                    Err(error) => {
-                                        error!("failed to watch lattice data bucket: {error}");
+                            move |entry| {
+                                let host = Arc::clone(&host);
+                                async move {
+                                    match entry {
+                                        Err(error) => {
+                                            error!("failed to watch lattice data bucket: {error}");
+                                        }
+                                        Ok(entry) => host.process_entry(entry).await,
                                     }
-                                    Ok(entry) => host.process_entry(entry).await,
                                 }
                             }
-                        }
-                    })
-                    .await;
-                let deadline = { *host.stop_rx.borrow() };
-                host.stop_tx.send_replace(deadline);
-                if data_watch.is_aborted() {
-                    info!("data watch task gracefully stopped");
-                } else {
-                    error!("data watch task unexpectedly stopped");
+                        })
+                        .await;
+                    let deadline = { *host.stop_rx.borrow() };
+                    host.stop_tx.send_replace(deadline);
+                    if data_watch.is_aborted() {
+                        info!("data watch task gracefully stopped");
+                    } else {
+                        error!("data watch task unexpectedly stopped");
+                    }
+                    Ok(())
                 }
-                Ok(())
-            }
-        });
+            }));
 
-        let heartbeat = spawn({
+        let heartbeat = spawn(host.task_registry.track("heartbeat", TaskOwner::Host, {
             let host = Arc::clone(&host);
             async move {
                 let mut heartbeat = Abortable::new(heartbeat, heartbeat_abort_reg);
@@ -972,6 +1269,7 @@ impl Host {
                                 {
                                     error!("failed to publish heartbeat: {e}");
                                 }
+                                host.audit_tasks().await;
                             }
                         }
                     })
@@ -984,7 +1282,45 @@ impl Host {
                     error!("heartbeat task unexpectedly stopped");
                 }
             }
-        });
+        }));
+
+        let link_health = spawn(host.task_registry.track("link_health", TaskOwner::Host, {
+            let host = Arc::clone(&host);
+            async move {
+                let mut link_health = Abortable::new(link_health, link_health_abort_reg);
+                let mut state = LinkHealthState::default();
+                while link_health.next().await.is_some() {
+                    host.check_link_health(&mut state).await;
+                }
+                let deadline = { *host.stop_rx.borrow() };
+                host.stop_tx.send_replace(deadline);
+                if link_health.is_aborted() {
+                    info!("link health task gracefully stopped");
+                } else {
+                    error!("link health task unexpectedly stopped");
+                }
+            }
+        }));
+
+        let clock_skew = spawn(host.task_registry.track("clock_skew", TaskOwner::Host, {
+            let host = Arc::clone(&host);
+            async move {
+                let Some(heartbeats) = heartbeats else {
+                    return;
+                };
+                let mut heartbeats = Abortable::new(heartbeats, clock_skew_abort_reg);
+                while let Some(msg) = heartbeats.next().await {
+                    host.observe_heartbeat(&msg.payload).await;
+                }
+                let deadline = { *host.stop_rx.borrow() };
+                host.stop_tx.send_replace(deadline);
+                if heartbeats.is_aborted() {
+                    info!("clock skew task gracefully stopped");
+                } else {
+                    error!("clock skew task unexpectedly stopped");
+                }
+            }
+        }));
 
         // Process existing data without emitting events
         data.keys()
@@ -1015,10 +1351,13 @@ impl Host {
         Ok((Arc::clone(&host), async move {
             ready.store(false, Ordering::Relaxed);
             heartbeat_abort.abort();
+            link_health_abort.abort();
+            clock_skew_abort.abort();
             queue_abort.abort();
             data_watch_abort.abort();
             host.policy_manager.policy_changes.abort();
-            let _ = try_join!(queue, data_watch, heartbeat).context("failed to await tasks")?;
+            let _ = try_join!(queue, data_watch, heartbeat, link_health, clock_skew)
+                .context("failed to await tasks")?;
             host.publish_event(
                 "host_stopped",
                 json!({
@@ -1079,6 +1418,32 @@ impl Host {
                     {
                         description = description.name(name);
                     };
+                    if let Some(quarantine) = component
+                        .crash_loop
+                        .quarantine(std::time::Instant::now())
+                        .and_then(|quarantine| {
+                            ComponentQuarantine::builder()
+                                .until(quarantine.until_secs())
+                                .reason(quarantine.reason)
+                                .failures(quarantine.failures)
+                                .build()
+                                .ok()
+                        })
+                    {
+                        description = description.quarantine(quarantine);
+                    };
+                    if let Some(pool) = component.instance_pool_stats().and_then(|stats| {
+                        ComponentInstancePool::builder()
+                            .warm(stats.warm.try_into().unwrap_or(u32::MAX))
+                            .active(stats.active.try_into().unwrap_or(u32::MAX))
+                            .created(stats.created)
+                            .reused(stats.reused)
+                            .evicted(stats.evicted)
+                            .build()
+                            .ok()
+                    }) {
+                        description = description.instance_pool(pool);
+                    };
 
                     Some(
                         description
@@ -1090,10 +1455,10 @@ impl Host {
                 .await
         };
 
-        let providers: Vec<_> = self
-            .providers
-            .read()
-            .await
+        // NOTE: Locks are acquired in the same order as when stopping providers
+        let providers = self.providers.read().await;
+        let provider_link_health = self.provider_link_health.read().await;
+        let providers: Vec<_> = providers
             .iter()
             .map(
                 |(
@@ -1129,12 +1494,28 @@ impl Host {
                                 .and_then(|jwt::CapabilityProvider { rev, .. }| *rev)
                                 .unwrap_or_default(),
                         )
+                        .link_health(
+                            provider_link_health
+                                .get(provider_id)
+                                .map(Vec::as_slice)
+                                .map(describe_link_health)
+                                .unwrap_or_default(),
+                        )
                         .build()
                         .expect("failed to build provider description")
                 },
             )
             .collect();
 
+        let clock_skew = self
+            .clock_skew
+            .read()
+            .await
+            .skewed()
+            .into_iter()
+            .map(|(host_id, skew_ms)| HostClockSkew::new(host_id, skew_ms))
+            .collect();
+
         let uptime = self.start_at.elapsed();
         HostInventory::builder()
             .components(components)
@@ -1145,6 +1526,7 @@ impl Host {
             .uptime_seconds(uptime.as_secs())
             .version(self.host_config.version.clone())
             .host_id(self.host_key.public_key())
+            .clock_skew(clock_skew)
             .build()
             .expect("failed to build host inventory")
     }
@@ -1161,12 +1543,171 @@ impl Host {
             &self.event_builder,
             &self.ctl_nats,
             &self.host_config.lattice,
+            self.host_config.subject_prefix.as_deref(),
             name,
             data,
         )
         .await
     }
 
+    /// Compare the timestamp of a heartbeat of another host with the clock of this host,
+    /// publishing `host_clock_skew_detected` and `host_clock_skew_resolved` events when the skew
+    /// crosses the configured threshold
+    #[instrument(level = "trace", skip_all)]
+    async fn observe_heartbeat(&self, heartbeat: &[u8]) {
+        let (host_id, skew_ms) =
+            match clock_skew::heartbeat_skew(heartbeat, time::OffsetDateTime::now_utc()) {
+                Ok(skew) => skew,
+                Err(err) => {
+                    debug!(?err, "failed to determine clock skew from heartbeat");
+                    return;
+                }
+            };
+        let public_key = self.host_key.public_key();
+        if host_id == public_key {
+            return;
+        }
+        let threshold = self.host_config.clock_skew_threshold;
+        let change = self.clock_skew.write().await.update(
+            &host_id,
+            skew_ms,
+            threshold,
+            std::time::Instant::now(),
+        );
+        let res = match change {
+            Some(ClockSkewChange::Detected { skew_ms }) => {
+                warn!(
+                    host_id,
+                    skew_ms,
+                    threshold_ms = threshold.as_millis(),
+                    "clock of host is skewed, JWT validation and timestamps may be unreliable"
+                );
+                self.publish_event(
+                    "host_clock_skew_detected",
+                    event::host_clock_skew_detected(
+                        &public_key,
+                        &host_id,
+                        skew_ms,
+                        threshold.as_millis(),
+                    ),
+                )
+                .await
+            }
+            Some(ClockSkewChange::Resolved) => {
+                info!(host_id, skew_ms, "clock skew of host resolved");
+                self.publish_event(
+                    "host_clock_skew_resolved",
+                    event::host_clock_skew_resolved(&public_key, &host_id),
+                )
+                .await
+            }
+            None => return,
+        };
+        if let Err(err) = res {
+            error!(?err, "failed to publish clock skew event");
+        }
+    }
+
+    /// Warn about long-lived tasks outliving the component, provider or link owning them
+    #[instrument(level = "debug", skip_all)]
+    async fn audit_tasks(&self) {
+        let components = self.components.read().await;
+        let providers = self.providers.read().await;
+        let links = self.links.read().await;
+        let orphaned = self.task_registry.audit(|owner| match owner {
+            TaskOwner::Host => true,
+            TaskOwner::Component(id) => components.contains_key(id),
+            TaskOwner::Provider(id) => providers.contains_key(id),
+            TaskOwner::Link {
+                source_id,
+                target,
+                name,
+            } => links.get(source_id).is_some_and(|links| {
+                links
+                    .iter()
+                    .any(|link| link.target() == target.as_str() && link.name() == name.as_str())
+            }),
+        });
+        for task in orphaned {
+            warn!(
+                task = task.name,
+                owner = %task.owner,
+                age = ?task.age,
+                "task outlived its owner, it was likely leaked"
+            );
+        }
+    }
+
+    /// Probe the targets of links whose source runs on this host, publishing `link_unhealthy`
+    /// and `link_recovered` events for links whose health changed since the last probe
+    #[instrument(level = "debug", skip_all)]
+    async fn check_link_health(&self, state: &mut LinkHealthState) {
+        let mut sources: Vec<String> = self.components.read().await.keys().cloned().collect();
+        sources.extend(self.providers.read().await.keys().cloned());
+        let links: Vec<Link> = {
+            let links = self.links.read().await;
+            sources
+                .iter()
+                .filter_map(|source_id| links.get(source_id))
+                .flatten()
+                .cloned()
+                .collect()
+        };
+
+        // Probe each target once, regardless of the number of links to it
+        let mut targets: Vec<&str> = links.iter().map(Link::target).collect();
+        targets.sort_unstable();
+        targets.dedup();
+        let health: HashMap<&str, LinkHealth> =
+            futures::future::join_all(targets.into_iter().map(|target| async move {
+                let health = link_health::probe(
+                    &self.rpc_nats,
+                    &self.host_config.lattice,
+                    self.host_config.subject_prefix.as_deref(),
+                    target,
+                    LINK_HEALTH_TIMEOUT,
+                )
+                .await;
+                (target, health)
+            }))
+            .await
+            .into_iter()
+            .collect();
+
+        let mut probed = Vec::with_capacity(links.len());
+        for link in &links {
+            let key = LinkKey {
+                source_id: link.source_id().to_string(),
+                wit_namespace: link.wit_namespace().to_string(),
+                wit_package: link.wit_package().to_string(),
+                name: link.name().to_string(),
+            };
+            let Some(health) = health.get(link.target()) else {
+                continue;
+            };
+            trace!(?key, target = link.target(), ?health, "probed link target");
+            let res = match state.update(key.clone(), health.clone()) {
+                Some(LinkHealthChange::Unhealthy { reason }) => {
+                    warn!(?key, target = link.target(), reason, "link is unhealthy");
+                    self.publish_event("link_unhealthy", event::link_unhealthy(link, &reason))
+                        .await
+                }
+                Some(LinkHealthChange::Recovered) => {
+                    info!(?key, target = link.target(), "link recovered");
+                    self.publish_event("link_recovered", event::link_recovered(link))
+                        .await
+                }
+                None => Ok(()),
+            };
+            if let Err(err) = res {
+                warn!(?err, ?key, "failed to publish link health event");
+            }
+            probed.push(key);
+        }
+        // Forget links which were deleted or whose source stopped running on this host
+        state.retain(|key| probed.contains(key));
+    }
+
     /// Instantiate a component
     #[allow(clippy::too_many_arguments)] // TODO: refactor into a config struct
     #[instrument(level = "debug", skip_all)]
@@ -1187,13 +1728,43 @@ impl Host {
 
         let max_execution_time = self.max_execution_time;
         component.set_max_execution_time(max_execution_time);
+        component.set_instance_pool(
+            instance_pool_config(annotations, max_instances)
+                .context("failed to configure component instance pool")?,
+        );
+        let seed = deterministic_seed(annotations)
+            .context("failed to configure component deterministic mode")?;
+        if let Some(seed) = seed {
+            warn!(
+                component_id = ?id,
+                seed, "component runs in deterministic mode, clocks and randomness are not real"
+            );
+        }
+        component.set_deterministic_seed(seed);
+        component.set_scratch_dir(
+            scratch_dir_config(
+                annotations,
+                self.host_config.scratch_dir.as_deref(),
+                self.host_config.scratch_dir_quota,
+            )
+            .context("failed to configure component scratch directory")?,
+        );
 
         let (events_tx, mut events_rx) = mpsc::channel(
             max_instances
                 .get()
                 .clamp(MIN_INVOCATION_CHANNEL_SIZE, MAX_INVOCATION_CHANNEL_SIZE),
         );
-        let prefix = Arc::from(format!("{}.{id}", &self.host_config.lattice));
+        let crash_loop = Arc::new(CrashLoopDetector::new(
+            self.host_config.crash_loop_threshold,
+            self.host_config.crash_loop_window,
+            self.host_config.crash_loop_backoff,
+        ));
+        let (quarantines_tx, mut quarantines_rx) = mpsc::unbounded_channel::<Quarantine>();
+        let prefix = Arc::from(
+            self.host_config
+                .prefixed_subject(format!("{}.{id}", &self.host_config.lattice)),
+        );
         let nats = wrpc_transport_nats::Client::new(
             Arc::clone(&self.rpc_nats),
             Arc::clone(&prefix),
@@ -1210,6 +1781,8 @@ impl Host {
                     annotations: Arc::new(annotations.clone()),
                     policy_manager: Arc::clone(&self.policy_manager),
                     metrics: Arc::clone(&self.metrics),
+                    local_targets: self.local_targets.clone(),
+                    crash_loop: Arc::clone(&crash_loop),
                 },
                 handler.clone(),
                 events_tx.clone(),
@@ -1219,87 +1792,157 @@ impl Host {
             usize::from(max_instances).min(Semaphore::MAX_PERMITS),
         ));
         let metrics = Arc::clone(&self.metrics);
+        let health = link_health::serve_component_health(
+            Arc::clone(&self.rpc_nats),
+            Arc::clone(&self.host_config.lattice),
+            self.host_config.subject_prefix.clone(),
+            Arc::clone(&id),
+        );
+        let event_builder = self.event_builder.clone();
+        let ctl_nats = self.ctl_nats.clone();
+        let lattice = Arc::clone(&self.host_config.lattice);
+        let subject_prefix = self.host_config.subject_prefix.clone();
+        let quarantined_id = Arc::clone(&id);
+        let quarantined_ref = Arc::clone(&image_reference);
+        let owner = TaskOwner::Component(id.to_string());
         Ok(Arc::new(Component {
             component,
             id,
             handler,
             events: events_tx,
             permits: Arc::clone(&permits),
-            exports: spawn(async move {
-                join!(
-                    async move {
-                        let mut exports = stream::select_all(exports);
-                        loop {
-                            let permits = Arc::clone(&permits);
-                            if let Some(fut) = exports.next().await {
-                                match fut {
-                                    Ok(fut) => {
-                                        debug!("accepted invocation, acquiring permit");
-                                        let permit = permits.acquire_owned().await;
-                                        spawn(async move {
-                                            let _permit = permit;
-                                            debug!("handling invocation");
-                                            match fut.await {
-                                                Ok(()) => {
-                                                    debug!("successfully handled invocation");
-                                                    Ok(())
-                                                }
-                                                Err(err) => {
-                                                    warn!(?err, "failed to handle invocation");
-                                                    Err(err)
-                                                }
+            crash_loop: Arc::clone(&crash_loop),
+            exports: spawn(
+                self.task_registry
+                    .track("component_exports", owner, async move {
+                        join!(
+                            async move {
+                                let mut exports = stream::select_all(exports);
+                                loop {
+                                    let permits = Arc::clone(&permits);
+                                    if let Some(fut) = exports.next().await {
+                                        match fut {
+                                            Ok(fut) => {
+                                                debug!("accepted invocation, acquiring permit");
+                                                let permit = permits.acquire_owned().await;
+                                                let crash_loop = Arc::clone(&crash_loop);
+                                                let quarantines_tx = quarantines_tx.clone();
+                                                spawn(async move {
+                                                    let _permit = permit;
+                                                    debug!("handling invocation");
+                                                    match fut.await {
+                                                        Ok(()) => {
+                                                            debug!(
+                                                                "successfully handled invocation"
+                                                            );
+                                                            crash_loop.record_success();
+                                                            Ok(())
+                                                        }
+                                                        Err(err) => {
+                                                            warn!(
+                                                                ?err,
+                                                                "failed to handle invocation"
+                                                            );
+                                                            if let Some(quarantine) = crash_loop
+                                                                .record_error(
+                                                                    &err,
+                                                                    std::time::Instant::now(),
+                                                                )
+                                                            {
+                                                                _ = quarantines_tx.send(quarantine);
+                                                            }
+                                                            Err(err)
+                                                        }
+                                                    }
+                                                });
                                             }
-                                        });
-                                    }
-                                    Err(err) => {
-                                        warn!(?err, "failed to accept invocation")
+                                            Err(err) => {
+                                                warn!(?err, "failed to accept invocation")
+                                            }
+                                        }
                                     }
                                 }
-                            }
-                        }
-                    },
-                    async move {
-                        while let Some(evt) = events_rx.recv().await {
-                            match evt {
-                                WrpcServeEvent::HttpIncomingHandlerHandleReturned {
-                                    context:
-                                        InvocationContext {
-                                            start_at,
-                                            ref attributes,
-                                            ..
-                                        },
-                                    success,
+                            },
+                            async move {
+                                while let Some(evt) = events_rx.recv().await {
+                                    match evt {
+                                        WrpcServeEvent::HttpIncomingHandlerHandleReturned {
+                                            context:
+                                                InvocationContext {
+                                                    start_at,
+                                                    ref attributes,
+                                                    ..
+                                                },
+                                            success,
+                                        }
+                                        | WrpcServeEvent::MessagingHandlerHandleMessageReturned {
+                                            context:
+                                                InvocationContext {
+                                                    start_at,
+                                                    ref attributes,
+                                                    ..
+                                                },
+                                            success,
+                                        }
+                                        | WrpcServeEvent::DynamicExportReturned {
+                                            context:
+                                                InvocationContext {
+                                                    start_at,
+                                                    ref attributes,
+                                                    ..
+                                                },
+                                            success,
+                                        } => metrics.record_component_invocation(
+                                            u64::try_from(start_at.elapsed().as_nanos())
+                                                .unwrap_or_default(),
+                                            attributes,
+                                            !success,
+                                        ),
+                                    }
                                 }
-                                | WrpcServeEvent::MessagingHandlerHandleMessageReturned {
-                                    context:
-                                        InvocationContext {
-                                            start_at,
-                                            ref attributes,
-                                            ..
-                                        },
-                                    success,
+                                debug!("serving event stream is done");
+                            },
+                            async move {
+                                while let Some(quarantine) = quarantines_rx.recv().await {
+                                    let until = quarantine.until_secs();
+                                    let Quarantine {
+                                        reason, failures, ..
+                                    } = quarantine;
+                                    warn!(
+                                        component_id = ?quarantined_id,
+                                        failures,
+                                        until,
+                                        reason,
+                                        "component is crash looping, quarantining it"
+                                    );
+                                    if let Err(err) = event::publish(
+                                        &event_builder,
+                                        &ctl_nats,
+                                        &lattice,
+                                        subject_prefix.as_deref(),
+                                        "component_quarantined",
+                                        event::component_quarantined(
+                                            &quarantined_id,
+                                            &quarantined_ref,
+                                            &reason,
+                                            failures,
+                                            until,
+                                        ),
+                                    )
+                                    .await
+                                    {
+                                        warn!(
+                                            ?err,
+                                            "failed to publish component quarantined event"
+                                        );
+                                    }
                                 }
-                                | WrpcServeEvent::DynamicExportReturned {
-                                    context:
-                                        InvocationContext {
-                                            start_at,
-                                            ref attributes,
-                                            ..
-                                        },
-                                    success,
-                                } => metrics.record_component_invocation(
-                                    u64::try_from(start_at.elapsed().as_nanos())
-                                        .unwrap_or_default(),
-                                    attributes,
-                                    !success,
-                                ),
-                            }
-                        }
-                        debug!("serving event stream is done");
-                    },
-                );
-                debug!("export serving task done");
-            }),
+                            },
+                            health,
+                        );
+                        debug!("export serving task done");
+                    }),
+            ),
             annotations: annotations.clone(),
             max_instances,
             image_reference,
@@ -1323,7 +1966,9 @@ impl Host {
         debug!(?component_ref, ?max_instances, "starting new component");
 
         if let Some(ref claims) = claims {
-            self.store_claims(Claims::Component(claims.clone()))
+            let claims = Claims::Component(claims.clone());
+            self.ensure_trusted_issuer(&claims).await?;
+            self.store_claims(claims)
                 .await
                 .context("failed to store claims")?;
         }
@@ -1335,11 +1980,24 @@ impl Host {
         self.store_component_spec(&component_id, &component_spec)
             .await?;
 
+        let memory_blobstore = memory_blobstore(
+            &self.host_config.memory_blobstore,
+            &component_id,
+            self.host_config.memory_blobstore_quota,
+        );
+        if memory_blobstore.is_some() {
+            warn!(
+                ?component_id,
+                "component uses an in-memory blobstore, objects are lost once the component stops"
+            );
+        }
+
         // Map the imports to pull out the result types of the functions for lookup when invoking them
         let handler = Handler {
             nats: Arc::clone(&self.rpc_nats),
             config_data: Arc::new(RwLock::new(config)),
             lattice: Arc::clone(&self.host_config.lattice),
+            subject_prefix: self.host_config.subject_prefix.as_deref().map(Arc::from),
             component_id: Arc::clone(&component_id),
             secrets: Arc::new(RwLock::new(secrets)),
             targets: Arc::default(),
@@ -1348,6 +2006,16 @@ impl Host {
                 let mut links = self.messaging_links.write().await;
                 Arc::clone(links.entry(Arc::clone(&component_id)).or_default())
             },
+            local_targets: self.local_targets.clone(),
+            local_invocation_opt_outs: Arc::new(RwLock::new(
+                local_invocation_opt_outs(&self.config_generator, &component_spec.links).await,
+            )),
+            hedge_policies: Arc::new(RwLock::new(
+                hedge_policies(&self.config_generator, &component_spec.links).await,
+            )),
+            traffic_splits: Arc::clone(&self.traffic_splits),
+            payload_captures: Arc::clone(&self.payload_captures),
+            memory_blobstore,
             invocation_timeout: Duration::from_secs(10), // TODO: Make this configurable
             experimental_features: self.experimental_features,
         };
@@ -1610,6 +2278,8 @@ impl Host {
                 self.stop_component(&component, host_id)
                     .await
                     .context("failed to stop component in response to scale to zero")?;
+                // Payloads captured for the component are dropped along with it
+                self.payload_captures.write().await.remove(&*component.id);
 
                 info!(?component_ref, "component stopped");
                 event::component_scaled(
@@ -1722,7 +2392,9 @@ impl Host {
                 .context("failed to initialize component")?;
             let new_claims = new_component.claims().cloned();
             if let Some(ref claims) = new_claims {
-                self.store_claims(Claims::Component(claims.clone()))
+                let claims = Claims::Component(claims.clone());
+                self.ensure_trusted_issuer(&claims).await?;
+                self.store_claims(claims)
                     .await
                     .context("failed to store claims")?;
             }
@@ -1824,7 +2496,9 @@ impl Host {
         let claims = claims_token.as_ref().map(|t| t.claims.clone());
 
         if let Some(claims) = claims.clone() {
-            self.store_claims(Claims::Provider(claims))
+            let claims = Claims::Provider(claims);
+            self.ensure_trusted_issuer(&claims).await?;
+            self.store_claims(claims)
                 .await
                 .context("failed to store claims")?;
         }
@@ -1877,8 +2551,31 @@ impl Host {
             // Used by provider child tasks (health check, config watch, process restarter) to
             // know when to shutdown.
             let shutdown = Arc::new(AtomicBool::new(false));
-            let tasks = match (path, &provider_ref) {
-                (Some(path), ..) => {
+            let lease = lease::is_singleton(&annotations)
+                .then(|| ProviderLease::new(self.provider_leases.clone(), provider_id, host_id));
+            let tasks = match (path, &provider_ref, &lease) {
+                (Some(path), _, Some(lease)) => {
+                    let provider_xkey_seed = provider_xkey
+                        .seed()
+                        .context("failed to get seed of provider xkey")?;
+                    let mut tasks = JoinSet::new();
+                    tasks.spawn(self.task_registry.track(
+                        "singleton_provider",
+                        TaskOwner::Provider(provider_id.to_string()),
+                        Arc::clone(&self).run_singleton_provider(
+                            lease.clone(),
+                            path,
+                            provider_xkey_seed,
+                            provider_id.to_string(),
+                            config_names.to_vec(),
+                            claims_token.clone(),
+                            annotations.clone(),
+                            shutdown.clone(),
+                        ),
+                    ));
+                    tasks
+                }
+                (Some(path), _, None) => {
                     Arc::clone(&self)
                         .start_binary_provider(
                             path,
@@ -1894,7 +2591,10 @@ impl Host {
                         )
                         .await?
                 }
-                (None, ResourceRef::Builtin(name)) => match *name {
+                (None, ResourceRef::Builtin(..), Some(..)) => {
+                    bail!("builtin providers cannot be started as singletons")
+                }
+                (None, ResourceRef::Builtin(name), None) => match *name {
                     "http-server" if self.experimental_features.builtin_http_server => {
                         self.start_http_server_provider(host_data, provider_xkey, provider_id)
                             .await?
@@ -1937,7 +2637,9 @@ impl Host {
                 claims_token,
                 image_ref: provider_ref.as_ref().to_string(),
                 xkey,
+                config_names: config_names.to_vec(),
                 shutdown,
+                lease,
             });
         } else {
             bail!("provider is already running with that ID")
@@ -1946,6 +2648,164 @@ impl Host {
         Ok(())
     }
 
+    #[instrument(level = "debug", skip_all)]
+    async fn handle_update_provider(
+        self: Arc<Self>,
+        payload: impl AsRef<[u8]>,
+    ) -> anyhow::Result<CtlResponse<()>> {
+        let cmd = serde_json::from_slice::<UpdateProviderCommand>(payload.as_ref())
+            .context("failed to deserialize provider update command")?;
+        <Self as ControlInterfaceServer>::handle_update_provider(self, cmd).await
+    }
+
+    /// Hot-swap a running binary provider for a new version. The new provider is started with
+    /// the same ID and receives all links for that ID on startup. Since both instances serve the
+    /// same wRPC queue group, traffic is shared while the old instance drains, after which it is
+    /// stopped without sending it a shutdown request, which would also reach the new instance.
+    #[instrument(level = "debug", skip_all)]
+    async fn handle_update_provider_task(
+        self: Arc<Self>,
+        provider_id: &str,
+        new_provider_ref: &str,
+        host_id: &str,
+        annotations: Option<BTreeMap<String, String>>,
+    ) -> anyhow::Result<()> {
+        trace!(provider_id, new_provider_ref, "update provider task");
+
+        let new_provider_ref = ResourceRef::try_from(new_provider_ref)
+            .context("failed to parse provider reference")?;
+        if let ResourceRef::Builtin(..) = new_provider_ref {
+            bail!("builtin providers cannot be hot-swapped")
+        }
+        let (path, claims_token) = {
+            let registry_config = self.registry_config.read().await;
+            crate::fetch_provider(
+                &new_provider_ref,
+                host_id,
+                self.host_config.allow_file_load,
+                &registry_config,
+            )
+            .await
+            .context("failed to fetch provider")?
+        };
+        let claims = claims_token.as_ref().map(|t| t.claims.clone());
+        if let Some(claims) = claims.clone() {
+            let claims = Claims::Provider(claims);
+            self.ensure_trusted_issuer(&claims).await?;
+            self.store_claims(claims)
+                .await
+                .context("failed to store claims")?;
+        }
+
+        // NOTE: The write lock is held until the new provider is in place, so that links put in
+        // the meantime are delivered to the new provider rather than the draining one.
+        let mut providers = self.providers.write().await;
+        let hash_map::Entry::Occupied(mut entry) = providers.entry(provider_id.into()) else {
+            bail!("provider is not running with that ID")
+        };
+        ensure!(
+            entry.get().lease.is_none(),
+            "singleton providers cannot be hot-swapped"
+        );
+        let annotations: Annotations = annotations
+            .map(|annotations| annotations.into_iter().collect())
+            .unwrap_or_else(|| entry.get().annotations.clone());
+        let config_names = entry.get().config_names.clone();
+
+        let PolicyResponse {
+            permitted,
+            request_id,
+            message,
+        } = self
+            .policy_manager
+            .evaluate_start_provider(
+                provider_id,
+                new_provider_ref.as_ref(),
+                &annotations,
+                claims.as_ref(),
+            )
+            .await?;
+        ensure!(
+            permitted,
+            "policy denied request to update provider `{request_id}`: `{message:?}`",
+        );
+
+        let provider_xkey = XKey::new();
+        let xkey = XKey::from_public_key(&provider_xkey.public_key())
+            .context("failed to create XKey from provider public key xkey")?;
+        let (host_data, config_bundle) = self
+            .prepare_provider_config(
+                &config_names,
+                claims_token.as_ref(),
+                provider_id,
+                &provider_xkey,
+                &annotations,
+            )
+            .await?;
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let tasks = Arc::clone(&self)
+            .start_binary_provider(
+                path,
+                host_data,
+                Arc::new(RwLock::new(config_bundle)),
+                provider_xkey,
+                provider_id,
+                config_names.clone(),
+                claims_token.clone(),
+                annotations.clone(),
+                Arc::clone(&shutdown),
+            )
+            .await?;
+        let Provider {
+            image_ref: old_image_ref,
+            shutdown: old_shutdown,
+            tasks: mut old_tasks,
+            ..
+        } = entry.insert(Provider {
+            tasks,
+            annotations: annotations.clone(),
+            claims_token,
+            image_ref: new_provider_ref.as_ref().to_string(),
+            xkey,
+            config_names,
+            shutdown,
+            lease: None,
+        });
+        drop(providers);
+
+        // Stop restarting the old provider, then give it time to finish in-flight invocations
+        // while the new provider starts serving
+        old_shutdown.store(true, Ordering::Relaxed);
+        sleep(
+            self.host_config
+                .provider_shutdown_delay
+                .unwrap_or(DEFAULT_PROVIDER_DRAIN_DELAY),
+        )
+        .await;
+        // NOTE: The provider child process is spawned with [tokio::process::Command::kill_on_drop],
+        // so aborting the tasks stops the old provider process.
+        old_tasks.abort_all();
+
+        info!(
+            provider_id,
+            %old_image_ref,
+            new_provider_ref = new_provider_ref.as_ref(),
+            "provider updated"
+        );
+        self.publish_event(
+            "provider_updated",
+            event::provider_updated(
+                claims.as_ref(),
+                &annotations,
+                host_id,
+                &old_image_ref,
+                &new_provider_ref,
+                provider_id,
+            ),
+        )
+        .await
+    }
+
     #[instrument(level = "debug", skip_all)]
     async fn handle_stop_provider(
         &self,
@@ -1966,6 +2826,21 @@ impl Host {
         <Self as ControlInterfaceServer>::handle_claims(self).await
     }
 
+    #[instrument(level = "debug", skip_all)]
+    async fn handle_claims_export(&self) -> anyhow::Result<CtlResponse<ClaimsBundle>> {
+        <Self as ControlInterfaceServer>::handle_claims_export(self).await
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn handle_claims_import(
+        &self,
+        payload: impl AsRef<[u8]>,
+    ) -> anyhow::Result<CtlResponse<()>> {
+        let bundle: ClaimsBundle = serde_json::from_slice(payload.as_ref())
+            .context("failed to deserialize claims bundle")?;
+        <Self as ControlInterfaceServer>::handle_claims_import(self, bundle).await
+    }
+
     #[instrument(level = "trace", skip_all)]
     async fn handle_links(&self) -> anyhow::Result<Vec<u8>> {
         <Self as ControlInterfaceServer>::handle_links(self).await
@@ -2008,6 +2883,18 @@ impl Host {
         <Self as ControlInterfaceServer>::handle_link_put(self, link).await
     }
 
+    /// Validate a link with the providers running on this host, which are the source or target of
+    /// the link, without putting the link
+    #[instrument(level = "debug", skip_all)]
+    async fn handle_link_validate(
+        &self,
+        payload: impl AsRef<[u8]>,
+    ) -> anyhow::Result<CtlResponse<LinkValidation>> {
+        let link: Link = serde_json::from_slice(payload.as_ref())
+            .context("failed to deserialize wrpc link definition")?;
+        <Self as ControlInterfaceServer>::handle_link_validate(self, link).await
+    }
+
     #[instrument(level = "debug", skip_all)]
     /// Remove an interface link on a source component for a specific package
     async fn handle_link_del(&self, payload: impl AsRef<[u8]>) -> anyhow::Result<CtlResponse<()>> {
@@ -2016,6 +2903,49 @@ impl Host {
         <Self as ControlInterfaceServer>::handle_link_del(self, req).await
     }
 
+    #[instrument(level = "debug", skip_all)]
+    async fn handle_traffic_split_put(
+        &self,
+        payload: impl AsRef<[u8]>,
+    ) -> anyhow::Result<CtlResponse<()>> {
+        let split: TrafficSplit = serde_json::from_slice(payload.as_ref())
+            .context("failed to deserialize traffic split")?;
+        <Self as ControlInterfaceServer>::handle_traffic_split_put(self, split).await
+    }
+
+    #[instrument(level = "debug", skip_all, fields(%target))]
+    async fn handle_traffic_split_del(&self, target: &str) -> anyhow::Result<CtlResponse<()>> {
+        <Self as ControlInterfaceServer>::handle_traffic_split_del(self, target).await
+    }
+
+    #[instrument(level = "trace", skip_all)]
+    async fn handle_traffic_splits(&self) -> anyhow::Result<CtlResponse<Vec<TrafficSplit>>> {
+        <Self as ControlInterfaceServer>::handle_traffic_splits(self).await
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn handle_payload_capture_put(
+        &self,
+        payload: impl AsRef<[u8]>,
+    ) -> anyhow::Result<CtlResponse<()>> {
+        let capture: PayloadCapture = serde_json::from_slice(payload.as_ref())
+            .context("failed to deserialize payload capture")?;
+        <Self as ControlInterfaceServer>::handle_payload_capture_put(self, capture).await
+    }
+
+    #[instrument(level = "debug", skip_all, fields(%source_id))]
+    async fn handle_payload_capture_del(&self, source_id: &str) -> anyhow::Result<CtlResponse<()>> {
+        <Self as ControlInterfaceServer>::handle_payload_capture_del(self, source_id).await
+    }
+
+    #[instrument(level = "trace", skip_all, fields(%source_id))]
+    async fn handle_captured_payloads(
+        &self,
+        source_id: &str,
+    ) -> anyhow::Result<CtlResponse<Vec<CapturedPayload>>> {
+        <Self as ControlInterfaceServer>::handle_captured_payloads(self, source_id).await
+    }
+
     #[instrument(level = "debug", skip_all)]
     async fn handle_registries_put(
         &self,
@@ -2044,6 +2974,25 @@ impl Host {
         <Self as ControlInterfaceServer>::handle_config_delete(self, config_name).await
     }
 
+    #[instrument(level = "debug", skip_all, fields(%config_name))]
+    async fn handle_config_history(
+        &self,
+        config_name: &str,
+    ) -> anyhow::Result<CtlResponse<Vec<ConfigRevision>>> {
+        <Self as ControlInterfaceServer>::handle_config_history(self, config_name).await
+    }
+
+    #[instrument(level = "debug", skip_all, fields(%config_name))]
+    async fn handle_config_rollback(
+        &self,
+        config_name: &str,
+        payload: impl AsRef<[u8]>,
+    ) -> anyhow::Result<CtlResponse<()>> {
+        let request = serde_json::from_slice::<ConfigRollbackRequest>(payload.as_ref())
+            .context("failed to deserialize config rollback request")?;
+        <Self as ControlInterfaceServer>::handle_config_rollback(self, config_name, request).await
+    }
+
     #[instrument(level = "debug", skip_all)]
     async fn handle_ping_hosts(
         &self,
@@ -2107,6 +3056,11 @@ impl Host {
                 .await
                 .map(Some)
                 .map(serialize_ctl_response),
+            (Some("provider"), Some("update"), Some(_host_id), None) => Arc::clone(&self)
+                .handle_update_provider(message.payload)
+                .await
+                .map(Some)
+                .map(serialize_ctl_response),
             // Host commands
             (Some("host"), Some("get"), Some(_host_id), None) => self
                 .handle_inventory()
@@ -2129,6 +3083,16 @@ impl Host {
                 .await
                 .map(Some)
                 .map(serialize_ctl_response),
+            (Some("claims"), Some("export"), None, None) => self
+                .handle_claims_export()
+                .await
+                .map(Some)
+                .map(serialize_ctl_response),
+            (Some("claims"), Some("import"), None, None) => self
+                .handle_claims_import(message.payload)
+                .await
+                .map(Some)
+                .map(serialize_ctl_response),
             // Link commands
             (Some("link"), Some("del"), None, None) => self
                 .handle_link_del(message.payload)
@@ -2144,6 +3108,43 @@ impl Host {
                 .await
                 .map(Some)
                 .map(serialize_ctl_response),
+            (Some("link"), Some("validate"), Some(_host_id), None) => self
+                .handle_link_validate(message.payload)
+                .await
+                .map(Some)
+                .map(serialize_ctl_response),
+            // Traffic split commands
+            (Some("traffic"), Some("del"), Some(target), None) => self
+                .handle_traffic_split_del(target)
+                .await
+                .map(Some)
+                .map(serialize_ctl_response),
+            (Some("traffic"), Some("get"), None, None) => self
+                .handle_traffic_splits()
+                .await
+                .map(Some)
+                .map(serialize_ctl_response),
+            (Some("traffic"), Some("put"), None, None) => self
+                .handle_traffic_split_put(message.payload)
+                .await
+                .map(Some)
+                .map(serialize_ctl_response),
+            // Payload capture commands
+            (Some("capture"), Some("del"), Some(_host_id), Some(source_id)) => self
+                .handle_payload_capture_del(source_id)
+                .await
+                .map(Some)
+                .map(serialize_ctl_response),
+            (Some("capture"), Some("get"), Some(_host_id), Some(source_id)) => self
+                .handle_captured_payloads(source_id)
+                .await
+                .map(Some)
+                .map(serialize_ctl_response),
+            (Some("capture"), Some("put"), Some(_host_id), None) => self
+                .handle_payload_capture_put(message.payload)
+                .await
+                .map(Some)
+                .map(serialize_ctl_response),
             // Label commands
             (Some("label"), Some("del"), Some(host_id), None) => self
                 .handle_label_del(host_id, message.payload)
@@ -2176,6 +3177,16 @@ impl Host {
                 .await
                 .map(Some)
                 .map(serialize_ctl_response),
+            (Some("config"), Some("history"), Some(config_name), None) => self
+                .handle_config_history(config_name)
+                .await
+                .map(Some)
+                .map(serialize_ctl_response),
+            (Some("config"), Some("rollback"), Some(config_name), None) => self
+                .handle_config_rollback(config_name, message.payload)
+                .await
+                .map(Some)
+                .map(serialize_ctl_response),
             // Topic fallback
             _ => {
                 warn!(%subject, "received control interface request on unsupported subject");
@@ -2263,7 +3274,10 @@ impl Host {
         if let Err(e) = self
             .rpc_nats
             .publish_with_headers(
-                format!("wasmbus.rpc.{lattice}.{}.linkdefs.put", link.source_id()),
+                self.host_config.prefixed_subject(format!(
+                    "wasmbus.rpc.{lattice}.{}.linkdefs.put",
+                    link.source_id()
+                )),
                 injector_to_headers(&TraceContextInjector::default_with_span()),
                 payload.clone(),
             )
@@ -2278,7 +3292,10 @@ impl Host {
         if let Err(e) = self
             .rpc_nats
             .publish_with_headers(
-                format!("wasmbus.rpc.{lattice}.{}.linkdefs.put", link.target()),
+                self.host_config.prefixed_subject(format!(
+                    "wasmbus.rpc.{lattice}.{}.linkdefs.put",
+                    link.target()
+                )),
                 injector_to_headers(&TraceContextInjector::default_with_span()),
                 payload,
             )
@@ -2312,10 +3329,10 @@ impl Host {
 
         self.rpc_nats
             .publish_with_headers(
-                format!(
+                self.host_config.prefixed_subject(format!(
                     "wasmbus.rpc.{lattice}.{}.linkdefs.put",
                     provider.xkey.public_key()
-                ),
+                )),
                 injector_to_headers(&TraceContextInjector::default_with_span()),
                 payload.clone(),
             )
@@ -2323,6 +3340,45 @@ impl Host {
             .context("failed to publish provider link definition put")
     }
 
+    /// Requests a provider running on this host to validate a link, without establishing it.
+    #[instrument(level = "debug", skip_all)]
+    async fn validate_provider_link(
+        &self,
+        provider: &Provider,
+        link: &Link,
+    ) -> anyhow::Result<LinkValidationResponse> {
+        let provider_link = self
+            .resolve_link_config(
+                link.clone(),
+                provider.claims_token.as_ref().map(|t| &t.jwt),
+                provider.annotations.get("wasmcloud.dev/appspec"),
+                &provider.xkey,
+            )
+            .await
+            .context("failed to resolve link config and secrets")?;
+        let payload: Bytes = serde_json::to_vec(&provider_link)
+            .context("failed to serialize provider link definition")?
+            .into();
+        let request = async_nats::Request::new()
+            .payload(payload)
+            .headers(injector_to_headers(
+                &TraceContextInjector::default_with_span(),
+            ))
+            .timeout(Some(self.host_config.rpc_timeout));
+        let async_nats::Message { payload, .. } = self
+            .rpc_nats
+            .send_request(
+                self.host_config.prefixed_subject(link_validate_subject(
+                    &self.host_config.lattice,
+                    &provider.xkey.public_key(),
+                )),
+                request,
+            )
+            .await
+            .context("failed to request link validation from provider")?;
+        serde_json::from_slice(&payload).context("failed to deserialize link validation response")
+    }
+
     /// Publishes a delete link to the lattice for all instances of a provider to handle
     /// Right now this is publishing _both_ to the source and the target in order to
     /// ensure that the provider is aware of the link delete. This would cause problems if a provider
@@ -2349,12 +3405,14 @@ impl Host {
 
         let (source_result, target_result) = futures::future::join(
             self.rpc_nats.publish_with_headers(
-                format!("wasmbus.rpc.{lattice}.{source_id}.linkdefs.del"),
+                self.host_config
+                    .prefixed_subject(format!("wasmbus.rpc.{lattice}.{source_id}.linkdefs.del")),
                 injector_to_headers(&TraceContextInjector::default_with_span()),
                 payload.clone(),
             ),
             self.rpc_nats.publish_with_headers(
-                format!("wasmbus.rpc.{lattice}.{target}.linkdefs.del"),
+                self.host_config
+                    .prefixed_subject(format!("wasmbus.rpc.{lattice}.{target}.linkdefs.del")),
                 injector_to_headers(&TraceContextInjector::default_with_span()),
                 payload,
             ),
@@ -2557,6 +3615,41 @@ fn serialize_ctl_response<T: Serialize>(
     ctl_response.map(|resp| serde_json::to_vec(&resp).map_err(anyhow::Error::from))
 }
 
+/// Describe the links reported by a provider, which are not healthy, for the host inventory
+fn describe_link_health(links: &[LinkHealthCheck]) -> Vec<ProviderLinkHealth> {
+    links
+        .iter()
+        .filter_map(
+            |LinkHealthCheck {
+                 source_id,
+                 target,
+                 link_name,
+                 status,
+                 message,
+             }| {
+                let status = match status {
+                    LinkHealthStatus::Healthy => return None,
+                    LinkHealthStatus::Degraded => {
+                        wasmcloud_control_interface::LinkHealthStatus::Degraded
+                    }
+                    LinkHealthStatus::Unhealthy => {
+                        wasmcloud_control_interface::LinkHealthStatus::Unhealthy
+                    }
+                };
+                let mut health = ProviderLinkHealth::builder()
+                    .source_id(source_id)
+                    .target(target)
+                    .link_name(link_name)
+                    .status(status);
+                if let Some(message) = message {
+                    health = health.message(message);
+                }
+                health.build().ok()
+            },
+        )
+        .collect()
+}
+
 fn human_friendly_uptime(uptime: Duration) -> String {
     // strip sub-seconds, then convert to human-friendly format
     humantime::format_duration(
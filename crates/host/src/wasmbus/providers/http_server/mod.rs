@@ -17,6 +17,8 @@ use wasmcloud_provider_sdk::provider::{
 };
 use wasmcloud_provider_sdk::ProviderConnection;
 
+use crate::wasmbus::tasks::TaskOwner;
+
 pub(crate) mod address;
 pub(crate) mod path;
 
@@ -66,13 +68,21 @@ impl crate::wasmbus::Host {
         };
 
         let (quit_tx, quit_rx) = broadcast::channel(1);
-        let commands = ProviderCommandReceivers::new(
+        let commands = ProviderCommandReceivers::with_spawner(
             Arc::clone(&self.rpc_nats),
             &quit_tx,
             &self.host_config.lattice,
+            self.host_config.subject_prefix.as_deref(),
             provider_id,
             provider_id,
             &host_id,
+            |name, task| {
+                tokio::spawn(self.task_registry.track(
+                    name,
+                    TaskOwner::Provider(provider_id.to_string()),
+                    task,
+                ));
+            },
         )
         .await?;
         let conn = ProviderConnection::new(
@@ -84,7 +94,8 @@ impl crate::wasmbus::Host {
             provider_xkey,
             Arc::clone(&self.secrets_xkey),
         )
-        .context("failed to establish provider connection")?;
+        .context("failed to establish provider connection")?
+        .with_subject_prefix(self.host_config.subject_prefix.as_deref());
 
         let mut tasks = JoinSet::new();
         match provider {
@@ -98,9 +109,13 @@ impl crate::wasmbus::Host {
                     }
                 }
 
-                tasks.spawn(async move {
-                    handle_provider_commands(provider, &conn, quit_rx, quit_tx, commands).await
-                });
+                tasks.spawn(self.task_registry.track(
+                    "provider_commands",
+                    TaskOwner::Provider(provider_id.to_string()),
+                    async move {
+                        handle_provider_commands(provider, &conn, quit_rx, quit_tx, commands).await
+                    },
+                ));
             }
             HttpServerProvider::Path(provider) => {
                 for ld in host_data.link_definitions {
@@ -112,9 +127,13 @@ impl crate::wasmbus::Host {
                     }
                 }
 
-                tasks.spawn(async move {
-                    handle_provider_commands(provider, &conn, quit_rx, quit_tx, commands).await
-                });
+                tasks.spawn(self.task_registry.track(
+                    "provider_commands",
+                    TaskOwner::Provider(provider_id.to_string()),
+                    async move {
+                        handle_provider_commands(provider, &conn, quit_rx, quit_tx, commands).await
+                    },
+                ));
             }
         }
 
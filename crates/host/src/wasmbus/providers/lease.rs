@@ -0,0 +1,140 @@
+//! Leadership leases for singleton providers
+//!
+//! A provider started with the [`SINGLETON_ANNOTATION`] set to `true` on multiple hosts is only
+//! run by the host holding the lease for that provider ID. Leases are stored in a JetStream KV
+//! bucket with a maximum age of [`LEASE_TTL`], so a lease that is not renewed (e.g. because the
+//! leader host crashed) expires and one of the standby hosts takes over. Standby hosts still
+//! list the provider in their inventory, so that it can be stopped like any other provider.
+
+use std::time::Duration;
+
+use anyhow::{anyhow, Context as _};
+use async_nats::jetstream::kv::{CreateErrorKind, Store};
+use bytes::Bytes;
+use tracing::{info, instrument};
+
+use crate::wasmbus::Annotations;
+
+/// Annotation marking a provider as a singleton in the lattice
+pub(crate) const SINGLETON_ANNOTATION: &str = "wasmcloud.dev/singleton";
+
+/// Duration after which a lease expires if it is not renewed by the leader
+pub(crate) const LEASE_TTL: Duration = Duration::from_secs(15);
+
+/// Interval at which the leader renews its lease and standby hosts attempt to acquire it.
+/// A standby host takes over at most `LEASE_TTL + LEASE_RENEW_INTERVAL` after the leader fails
+pub(crate) const LEASE_RENEW_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Returns whether the provider should be run as a singleton in the lattice
+pub(crate) fn is_singleton(annotations: &Annotations) -> bool {
+    annotations
+        .get(SINGLETON_ANNOTATION)
+        .is_some_and(|v| v.eq_ignore_ascii_case("true"))
+}
+
+/// Lease on a singleton provider ID, held by at most one host in the lattice
+#[derive(Clone, Debug)]
+pub(crate) struct ProviderLease {
+    store: Store,
+    key: String,
+    host_id: Bytes,
+}
+
+impl ProviderLease {
+    pub(crate) fn new(store: Store, provider_id: &str, host_id: &str) -> Self {
+        Self {
+            store,
+            key: format!("PROVIDER_{provider_id}"),
+            host_id: Bytes::copy_from_slice(host_id.as_bytes()),
+        }
+    }
+
+    /// Attempt to acquire the lease, returning the revision of the lease entry if it was acquired
+    /// and `None` if the lease is currently held by another host
+    #[instrument(level = "trace", skip(self), fields(key = %self.key))]
+    pub(crate) async fn acquire(&self) -> anyhow::Result<Option<u64>> {
+        match self.store.create(&self.key, self.host_id.clone()).await {
+            Ok(revision) => Ok(Some(revision)),
+            Err(err) if err.kind() == CreateErrorKind::AlreadyExists => Ok(None),
+            Err(err) => Err(anyhow!(err).context("failed to create lease entry")),
+        }
+    }
+
+    /// Renew a held lease, returning the new revision of the lease entry.
+    ///
+    /// Fails if the lease entry was modified since `revision`, meaning the lease was lost
+    #[instrument(level = "trace", skip(self), fields(key = %self.key))]
+    pub(crate) async fn renew(&self, revision: u64) -> anyhow::Result<u64> {
+        self.store
+            .update(&self.key, self.host_id.clone(), revision)
+            .await
+            .context("failed to renew lease")
+    }
+
+    /// Release the lease if it is held by this host, allowing a standby host to take over
+    /// without waiting for the lease to expire
+    #[instrument(level = "debug", skip(self), fields(key = %self.key))]
+    pub(crate) async fn release(&self) -> anyhow::Result<()> {
+        let Some(entry) = self
+            .store
+            .entry(&self.key)
+            .await
+            .context("failed to get lease entry")?
+        else {
+            return Ok(());
+        };
+        if entry.value != self.host_id {
+            return Ok(());
+        }
+        self.store
+            .delete_expect_revision(&self.key, Some(entry.revision))
+            .await
+            .context("failed to delete lease entry")?;
+        info!("released provider lease");
+        Ok(())
+    }
+}
+
+/// Create the lease bucket for the lattice, unless it already exists
+#[instrument(level = "debug", skip_all)]
+pub(crate) async fn create_lease_bucket(
+    jetstream: &async_nats::jetstream::Context,
+    bucket: &str,
+) -> anyhow::Result<Store> {
+    if let Ok(store) = jetstream.get_key_value(bucket).await {
+        info!(%bucket, "bucket already exists. Skipping creation.");
+        return Ok(store);
+    }
+
+    jetstream
+        .create_key_value(async_nats::jetstream::kv::Config {
+            bucket: bucket.to_string(),
+            history: 1,
+            max_age: LEASE_TTL,
+            ..Default::default()
+        })
+        .await
+        .map_err(|err| anyhow!(err).context(format!("failed to create bucket '{bucket}'")))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn singleton_annotation() {
+        assert!(!is_singleton(&Annotations::default()));
+        assert!(is_singleton(&Annotations::from([(
+            SINGLETON_ANNOTATION.into(),
+            "true".into()
+        )])));
+        assert!(is_singleton(&Annotations::from([(
+            SINGLETON_ANNOTATION.into(),
+            "TRUE".into()
+        )])));
+        assert!(!is_singleton(&Annotations::from([(
+            SINGLETON_ANNOTATION.into(),
+            "false".into()
+        )])));
+    }
+}
@@ -20,6 +20,7 @@ use wasmcloud_provider_sdk::{LinkConfig, LinkDeleteInfo, ProviderConnection};
 use wasmcloud_runtime::capability::wrpc;
 use wasmcloud_tracing::KeyValue;
 
+use crate::wasmbus::tasks::{TaskOwner, TaskRegistry};
 use crate::wasmbus::{Component, InvocationContext};
 
 struct Provider {
@@ -30,6 +31,7 @@ struct Provider {
     subscriptions: Mutex<HashMap<Arc<str>, HashMap<Box<str>, JoinSet<()>>>>,
     lattice_id: Arc<str>,
     host_id: Arc<str>,
+    task_registry: Arc<TaskRegistry>,
 }
 
 impl Provider {
@@ -169,6 +171,7 @@ impl wasmcloud_provider_sdk::Provider for Provider {
     async fn receive_link_config_as_source(
         &self,
         LinkConfig {
+            source_id,
             target_id,
             config,
             link_name,
@@ -176,6 +179,11 @@ impl wasmcloud_provider_sdk::Provider for Provider {
         }: LinkConfig<'_>,
     ) -> anyhow::Result<()> {
         let (nats, config) = self.connect(config).await?;
+        let owner = TaskOwner::Link {
+            source_id: source_id.to_string(),
+            target: target_id.to_string(),
+            name: link_name.to_string(),
+        };
         let mut tasks = JoinSet::new();
         let target_id: Arc<str> = Arc::from(target_id);
         for ConsumerConfig {
@@ -211,32 +219,36 @@ impl wasmcloud_provider_sdk::Provider for Provider {
             let lattice_id = Arc::clone(&self.lattice_id);
             let host_id = Arc::clone(&self.host_id);
             let target_id = Arc::clone(&target_id);
-            tasks.spawn(async move {
-                while let Some(msg) = sub.next().await {
-                    let msg = match msg {
-                        Ok(msg) => msg,
-                        Err(err) => {
-                            error!(?err, "failed to receive message");
-                            continue;
-                        }
-                    };
-                    let (msg, ack) = msg.split();
-                    tokio::spawn(async move {
-                        if let Err(err) = ack.ack().await {
-                            error!(?err, "failed to ACK message");
-                        } else {
-                            debug!("successfully ACK'ed message")
-                        }
-                    });
-                    tokio::spawn(handle_message(
-                        Arc::clone(&components),
-                        Arc::clone(&lattice_id),
-                        Arc::clone(&host_id),
-                        Arc::clone(&target_id),
-                        msg,
-                    ));
-                }
-            });
+            tasks.spawn(self.task_registry.track(
+                "messaging_consumer",
+                owner.clone(),
+                async move {
+                    while let Some(msg) = sub.next().await {
+                        let msg = match msg {
+                            Ok(msg) => msg,
+                            Err(err) => {
+                                error!(?err, "failed to receive message");
+                                continue;
+                            }
+                        };
+                        let (msg, ack) = msg.split();
+                        tokio::spawn(async move {
+                            if let Err(err) = ack.ack().await {
+                                error!(?err, "failed to ACK message");
+                            } else {
+                                debug!("successfully ACK'ed message")
+                            }
+                        });
+                        tokio::spawn(handle_message(
+                            Arc::clone(&components),
+                            Arc::clone(&lattice_id),
+                            Arc::clone(&host_id),
+                            Arc::clone(&target_id),
+                            msg,
+                        ));
+                    }
+                },
+            ));
         }
         for sub in config.subscriptions {
             if sub.is_empty() {
@@ -253,17 +265,21 @@ impl wasmcloud_provider_sdk::Provider for Provider {
             let lattice_id = Arc::clone(&self.lattice_id);
             let host_id = Arc::clone(&self.host_id);
             let target_id = Arc::clone(&target_id);
-            tasks.spawn(async move {
-                while let Some(msg) = sub.next().await {
-                    tokio::spawn(handle_message(
-                        Arc::clone(&components),
-                        Arc::clone(&lattice_id),
-                        Arc::clone(&host_id),
-                        Arc::clone(&target_id),
-                        msg,
-                    ));
-                }
-            });
+            tasks.spawn(self.task_registry.track(
+                "messaging_subscription",
+                owner.clone(),
+                async move {
+                    while let Some(msg) = sub.next().await {
+                        tokio::spawn(handle_message(
+                            Arc::clone(&components),
+                            Arc::clone(&lattice_id),
+                            Arc::clone(&host_id),
+                            Arc::clone(&target_id),
+                            msg,
+                        ));
+                    }
+                },
+            ));
         }
         self.subscriptions
             .lock()
@@ -300,13 +316,21 @@ impl crate::wasmbus::Host {
             ConnectionConfig::from_map(&host_data.config).context("failed to parse config")?;
 
         let (quit_tx, quit_rx) = broadcast::channel(1);
-        let commands = ProviderCommandReceivers::new(
+        let commands = ProviderCommandReceivers::with_spawner(
             Arc::clone(&self.rpc_nats),
             &quit_tx,
             &self.host_config.lattice,
+            self.host_config.subject_prefix.as_deref(),
             provider_id,
             provider_id,
             &host_id,
+            |name, task| {
+                tokio::spawn(self.task_registry.track(
+                    name,
+                    TaskOwner::Provider(provider_id.to_string()),
+                    task,
+                ));
+            },
         )
         .await?;
         let conn = ProviderConnection::new(
@@ -318,7 +342,8 @@ impl crate::wasmbus::Host {
             provider_xkey,
             Arc::clone(&self.secrets_xkey),
         )
-        .context("failed to establish provider connection")?;
+        .context("failed to establish provider connection")?
+        .with_subject_prefix(self.host_config.subject_prefix.as_deref());
         let provider = Provider {
             config,
             components: Arc::clone(&self.components),
@@ -326,6 +351,7 @@ impl crate::wasmbus::Host {
             subscriptions: Mutex::default(),
             host_id: Arc::from(host_id),
             lattice_id: Arc::clone(&self.host_config.lattice),
+            task_registry: Arc::clone(&self.task_registry),
         };
         for ld in host_data.link_definitions {
             if let Err(e) = receive_link_for_provider(&provider, &conn, ld).await {
@@ -336,9 +362,15 @@ impl crate::wasmbus::Host {
             }
         }
         let mut tasks = JoinSet::new();
-        tasks.spawn(async move {
-            handle_provider_commands(provider, &conn, quit_rx, quit_tx, commands).await
-        });
+        tasks.spawn(
+            self.task_registry.track(
+                "provider_commands",
+                TaskOwner::Provider(provider_id.to_string()),
+                async move {
+                    handle_provider_commands(provider, &conn, quit_rx, quit_tx, commands).await
+                },
+            ),
+        );
 
         Ok(tasks)
     }
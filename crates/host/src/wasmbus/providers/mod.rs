@@ -2,7 +2,7 @@
 //!
 //! The root of this module includes functionality for running and managing provider binaries. The
 //! submodules contain builtin implementations of wasmCloud capabilities providers.
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
@@ -22,10 +22,13 @@ use tokio::io::AsyncWriteExt;
 use tokio::process;
 use tokio::sync::RwLock;
 use tokio::task::JoinSet;
-use tracing::{error, instrument, trace, warn};
+use tracing::{error, info, instrument, trace, warn};
 use uuid::Uuid;
 use wascap::jwt::{CapabilityProvider, Token};
-use wasmcloud_core::{provider_config_update_subject, HealthCheckResponse, HostData, OtelConfig};
+use wasmcloud_core::{
+    prefixed_subject, provider_config_update_subject, HealthCheckResponse, HostData,
+    LinkHealthCheck, LinkHealthStatus, OtelConfig,
+};
 use wasmcloud_runtime::capability::secrets::store::SecretValue;
 use wasmcloud_tracing::context::TraceContextInjector;
 
@@ -33,9 +36,13 @@ use crate::jwt;
 use crate::wasmbus::{config::ConfigBundle, Annotations};
 use crate::wasmbus::{event, injector_to_headers};
 
+use super::tasks::TaskOwner;
 use super::Host;
 
+use self::lease::{ProviderLease, LEASE_RENEW_INTERVAL};
+
 mod http_server;
+pub(crate) mod lease;
 mod messaging_nats;
 
 /// An Provider instance
@@ -45,11 +52,15 @@ pub(crate) struct Provider {
     pub(crate) claims_token: Option<jwt::Token<jwt::CapabilityProvider>>,
     pub(crate) xkey: XKey,
     pub(crate) annotations: Annotations,
+    /// Names of the configuration the provider was started with
+    pub(crate) config_names: Vec<String>,
     /// Shutdown signal for the provider, set to `false` initially. When set to `true`, the
     /// tasks running the provider, health check, and config watcher will stop.
     pub(crate) shutdown: Arc<AtomicBool>,
     /// Tasks running the provider, health check, and config watcher
     pub(crate) tasks: JoinSet<()>,
+    /// Lease for the provider, if it is running as a singleton in the lattice
+    pub(crate) lease: Option<ProviderLease>,
 }
 
 impl Host {
@@ -165,7 +176,7 @@ impl Host {
         // The provider itself needs to know its private key
         let provider_xkey_private_key = if let Ok(seed) = provider_xkey.seed() {
             seed
-        } else if self.host_config.secrets_topic_prefix.is_none() {
+        } else if !self.secrets_manager.is_configured() {
             "".to_string()
         } else {
             // This should never happen since this returns an error when an Xkey is
@@ -193,6 +204,7 @@ impl Host {
             log_level: Some(self.host_config.log_level.clone()),
             structured_logging: self.host_config.enable_structured_logging,
             otel_config,
+            lattice_subject_prefix: self.host_config.subject_prefix.clone(),
         };
         Ok((host_data, config))
     }
@@ -219,34 +231,174 @@ impl Host {
         // Spawn a task to ensure the provider is restarted if it exits prematurely,
         // updating the configuration as needed
         tasks.spawn(
-            Arc::clone(&self)
-                .run_provider(
-                    path,
-                    host_data,
-                    Arc::clone(&config),
-                    provider_xkey,
-                    provider_id.to_string(),
-                    config_names,
-                    claims_token,
-                    annotations,
-                    shutdown.clone(),
-                )
-                .await?,
+            self.task_registry.track(
+                "provider_process",
+                TaskOwner::Provider(provider_id.to_string()),
+                Arc::clone(&self)
+                    .run_provider(
+                        path,
+                        host_data,
+                        Arc::clone(&config),
+                        provider_xkey,
+                        provider_id.to_string(),
+                        config_names,
+                        claims_token,
+                        annotations,
+                        shutdown.clone(),
+                    )
+                    .await?,
+            ),
         );
 
         // Spawn a task to check the health of the provider every 30 seconds
-        tasks.spawn(check_health(
-            Arc::clone(&self.rpc_nats),
-            self.ctl_nats.clone(),
-            self.event_builder.clone(),
-            Arc::clone(&self.host_config.lattice),
-            self.host_key.public_key(),
-            provider_id.to_string(),
+        tasks.spawn(self.task_registry.track(
+            "provider_health_check",
+            TaskOwner::Provider(provider_id.to_string()),
+            check_health(
+                Arc::clone(&self.rpc_nats),
+                self.ctl_nats.clone(),
+                self.event_builder.clone(),
+                Arc::clone(&self.host_config.lattice),
+                self.host_config.subject_prefix.clone(),
+                self.host_key.public_key(),
+                provider_id.to_string(),
+                Arc::clone(&self.provider_link_health),
+            ),
         ));
 
         Ok(tasks)
     }
 
+    /// Run a singleton binary provider, which is only running on this host while it holds the
+    /// provider lease. While another host holds the lease, this host stands by and attempts to
+    /// acquire the lease every [`LEASE_RENEW_INTERVAL`], taking over if the leader fails.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn run_singleton_provider(
+        self: Arc<Self>,
+        lease: ProviderLease,
+        path: PathBuf,
+        provider_xkey_seed: String,
+        provider_id: String,
+        config_names: Vec<String>,
+        claims_token: Option<Token<CapabilityProvider>>,
+        annotations: BTreeMap<String, String>,
+        shutdown: Arc<AtomicBool>,
+    ) {
+        let host_id = self.host_key.public_key();
+        while !shutdown.load(Ordering::Relaxed) {
+            let mut revision = match lease.acquire().await {
+                Ok(Some(revision)) => revision,
+                Ok(None) => {
+                    trace!(
+                        ?provider_id,
+                        "provider lease is held by another host, standing by"
+                    );
+                    tokio::time::sleep(LEASE_RENEW_INTERVAL).await;
+                    continue;
+                }
+                Err(err) => {
+                    warn!(?err, ?provider_id, "failed to acquire provider lease");
+                    tokio::time::sleep(LEASE_RENEW_INTERVAL).await;
+                    continue;
+                }
+            };
+            info!(
+                ?provider_id,
+                "acquired provider lease, starting singleton provider"
+            );
+
+            // Links and configuration may have changed while standing by, so the provider
+            // configuration is prepared every time the lease is acquired
+            let provider_shutdown = Arc::new(AtomicBool::new(false));
+            let tasks = async {
+                let provider_xkey = XKey::from_seed(&provider_xkey_seed)
+                    .context("failed to create provider xkey from seed")?;
+                let (host_data, config_bundle) = self
+                    .prepare_provider_config(
+                        &config_names,
+                        claims_token.as_ref(),
+                        &provider_id,
+                        &provider_xkey,
+                        &annotations,
+                    )
+                    .await?;
+                Arc::clone(&self)
+                    .start_binary_provider(
+                        path.clone(),
+                        host_data,
+                        Arc::new(RwLock::new(config_bundle)),
+                        provider_xkey,
+                        &provider_id,
+                        config_names.clone(),
+                        claims_token.clone(),
+                        annotations.clone(),
+                        Arc::clone(&provider_shutdown),
+                    )
+                    .await
+            }
+            .await;
+            match tasks {
+                Ok(mut tasks) => {
+                    if let Err(err) = self
+                        .publish_event(
+                            "provider_lease_acquired",
+                            event::provider_lease(&host_id, &provider_id),
+                        )
+                        .await
+                    {
+                        warn!(
+                            ?err,
+                            provider_id, "failed to publish provider lease acquired event"
+                        );
+                    }
+                    // Renew the lease for as long as the provider is running
+                    loop {
+                        tokio::time::sleep(LEASE_RENEW_INTERVAL).await;
+                        if shutdown.load(Ordering::Relaxed)
+                            || provider_shutdown.load(Ordering::Relaxed)
+                        {
+                            break;
+                        }
+                        match lease.renew(revision).await {
+                            Ok(next) => revision = next,
+                            Err(err) => {
+                                warn!(
+                                    ?err,
+                                    provider_id, "lost provider lease, stopping singleton provider"
+                                );
+                                break;
+                            }
+                        }
+                    }
+                    provider_shutdown.store(true, Ordering::Relaxed);
+                    // NOTE: The provider child process is spawned with [tokio::process::Command::kill_on_drop],
+                    // so aborting the tasks stops the provider process.
+                    tasks.abort_all();
+                    if let Err(err) = self
+                        .publish_event(
+                            "provider_lease_released",
+                            event::provider_lease(&host_id, &provider_id),
+                        )
+                        .await
+                    {
+                        warn!(
+                            ?err,
+                            provider_id, "failed to publish provider lease released event"
+                        );
+                    }
+                }
+                Err(err) => {
+                    error!(?err, ?provider_id, "failed to start singleton provider");
+                }
+            }
+            // Allow a standby host to take over without waiting for the lease to expire
+            if let Err(err) = lease.release().await {
+                warn!(?err, ?provider_id, "failed to release provider lease");
+            }
+            tokio::time::sleep(LEASE_RENEW_INTERVAL).await;
+        }
+    }
+
     /// Run and supervise a binary provider, restarting it if it exits prematurely.
     #[allow(clippy::too_many_arguments)]
     async fn run_provider(
@@ -276,11 +428,16 @@ impl Host {
             // it can be cancelled on drop and replaced with new config
             // when a provider restarts
             let mut config_task = JoinSet::new();
-            config_task.spawn(watch_config(
-                Arc::clone(&self.rpc_nats),
-                Arc::clone(&config_bundle),
-                Arc::clone(&lattice),
-                provider_id.clone(),
+            config_task.spawn(self.task_registry.track(
+                "provider_config_watch",
+                TaskOwner::Provider(provider_id.clone()),
+                watch_config(
+                    Arc::clone(&self.rpc_nats),
+                    Arc::clone(&config_bundle),
+                    Arc::clone(&lattice),
+                    self.host_config.subject_prefix.clone(),
+                    provider_id.clone(),
+                ),
             ));
             loop {
                 let mut child = child.write().await;
@@ -337,11 +494,16 @@ impl Host {
 
                         // Stop the config watcher and start a new one with the new config bundle
                         config_task.abort_all();
-                        config_task.spawn(watch_config(
-                            Arc::clone(&self.rpc_nats),
-                            new_config_bundle,
-                            Arc::clone(&lattice),
-                            provider_id.clone(),
+                        config_task.spawn(self.task_registry.track(
+                            "provider_config_watch",
+                            TaskOwner::Provider(provider_id.clone()),
+                            watch_config(
+                                Arc::clone(&self.rpc_nats),
+                                new_config_bundle,
+                                Arc::clone(&lattice),
+                                self.host_config.subject_prefix.clone(),
+                                provider_id.clone(),
+                            ),
                         ));
 
                         // Restart the provider by attempting to re-execute the binary with the same
@@ -417,17 +579,22 @@ async fn provider_command(path: &Path, host_data: Vec<u8>) -> anyhow::Result<pro
 /// Watch for health check responses from the provider
 ///
 /// Returns a future that should be polled to continually check provider
-/// health every 30 seconds until the health receiver gets a message to stop
+/// health every 30 seconds until the health receiver gets a message to stop.
+/// The health of the links reported by the provider is recorded in `link_health`
 fn check_health(
     rpc_nats: Arc<Client>,
     ctl_nats: Client,
     event_builder: EventBuilderV10,
     lattice: Arc<str>,
+    subject_prefix: Option<String>,
     host_id: String,
     provider_id: String,
+    link_health: Arc<RwLock<HashMap<String, Vec<LinkHealthCheck>>>>,
 ) -> impl Future<Output = ()> {
-    let health_subject =
-        async_nats::Subject::from(format!("wasmbus.rpc.{lattice}.{provider_id}.health"));
+    let health_subject = async_nats::Subject::from(prefixed_subject(
+        subject_prefix.as_deref(),
+        format!("wasmbus.rpc.{lattice}.{provider_id}.health"),
+    ));
 
     // Check the health of the provider every 30 seconds
     let mut health_check = tokio::time::interval(Duration::from_secs(30));
@@ -447,10 +614,28 @@ fn check_health(
             if let Ok(async_nats::Message { payload, .. }) =
                 rpc_nats.send_request(health_subject.clone(), request).await
             {
-                match (
-                    serde_json::from_slice::<HealthCheckResponse>(&payload),
-                    previous_healthy,
-                ) {
+                let response = serde_json::from_slice::<HealthCheckResponse>(&payload);
+                if let Ok(HealthCheckResponse { links, .. }) = &response {
+                    let previous = link_health
+                        .write()
+                        .await
+                        .insert(provider_id.clone(), links.clone())
+                        .unwrap_or_default();
+                    for link in links {
+                        if link.status != LinkHealthStatus::Healthy && !previous.contains(link) {
+                            warn!(
+                                ?provider_id,
+                                source_id = link.source_id,
+                                target = link.target,
+                                link_name = link.link_name,
+                                status = ?link.status,
+                                message = ?link.message,
+                                "provider reported link is not healthy"
+                            );
+                        }
+                    }
+                }
+                match (response, previous_healthy) {
                     (Ok(HealthCheckResponse { healthy: true, .. }), false) => {
                         trace!(?provider_id, "provider health check succeeded");
                         previous_healthy = true;
@@ -458,6 +643,7 @@ fn check_health(
                             &event_builder,
                             &ctl_nats,
                             &lattice,
+                            subject_prefix.as_deref(),
                             "health_check_passed",
                             event::provider_health_check(&host_id, &provider_id),
                         )
@@ -477,6 +663,7 @@ fn check_health(
                             &event_builder,
                             &ctl_nats,
                             &lattice,
+                            subject_prefix.as_deref(),
                             "health_check_failed",
                             event::provider_health_check(&host_id, &provider_id),
                         )
@@ -495,6 +682,7 @@ fn check_health(
                             &event_builder,
                             &ctl_nats,
                             &lattice,
+                            subject_prefix.as_deref(),
                             "health_check_status",
                             event::provider_health_check(&host_id, &provider_id),
                         )
@@ -530,9 +718,13 @@ fn watch_config(
     rpc_nats: Arc<Client>,
     config: Arc<RwLock<ConfigBundle>>,
     lattice: Arc<str>,
+    subject_prefix: Option<String>,
     provider_id: String,
 ) -> impl Future<Output = ()> {
-    let subject = provider_config_update_subject(&lattice, &provider_id);
+    let subject = prefixed_subject(
+        subject_prefix.as_deref(),
+        provider_config_update_subject(&lattice, &provider_id),
+    );
     trace!(?provider_id, "starting config update listener");
     async move {
         loop {
@@ -0,0 +1,84 @@
+//! Per-component scratch directory configuration
+//!
+//! If the host is configured with a scratch directory root, every component instance gets an
+//! ephemeral scratch directory created in it. Components can lower the quota of the host for
+//! their instances by setting [`SCRATCH_DIR_QUOTA_ANNOTATION`] in the scale request.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Context as _;
+use wasmcloud_runtime::component::ScratchDirConfig;
+
+/// Annotation setting the maximum size in bytes of the scratch directory of each instance
+pub(crate) const SCRATCH_DIR_QUOTA_ANNOTATION: &str = "wasmcloud.dev/scratch-dir-quota-bytes";
+
+/// Default maximum size of the scratch directory of each instance, 64 MiB
+pub(crate) const DEFAULT_SCRATCH_DIR_QUOTA: u64 = 64 * 1024 * 1024;
+
+/// Parses the scratch directory configuration of a component from its `annotations`, creating
+/// scratch directories in `root` with a quota of `quota` bytes. Quotas set by components are
+/// capped at `quota`.
+///
+/// Returns `None` if scratch directories are not enabled on the host.
+pub(crate) fn scratch_dir_config(
+    annotations: &BTreeMap<String, String>,
+    root: Option<&Path>,
+    quota: u64,
+) -> anyhow::Result<Option<ScratchDirConfig>> {
+    let Some(root) = root else {
+        return Ok(None);
+    };
+    let quota = annotations
+        .get(SCRATCH_DIR_QUOTA_ANNOTATION)
+        .map(|v| v.parse::<u64>())
+        .transpose()
+        .with_context(|| format!("invalid `{SCRATCH_DIR_QUOTA_ANNOTATION}` annotation"))?
+        .map_or(quota, |component_quota| component_quota.min(quota));
+    Ok(Some(ScratchDirConfig {
+        root: root.to_path_buf(),
+        quota,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use std::path::Path;
+
+    use super::{scratch_dir_config, DEFAULT_SCRATCH_DIR_QUOTA, SCRATCH_DIR_QUOTA_ANNOTATION};
+
+    #[test]
+    fn test_scratch_dir_config() {
+        let root = Path::new("/var/lib/wasmcloud/scratch");
+        assert_eq!(
+            scratch_dir_config(&BTreeMap::new(), None, DEFAULT_SCRATCH_DIR_QUOTA).unwrap(),
+            None
+        );
+
+        let config = scratch_dir_config(&BTreeMap::new(), Some(root), DEFAULT_SCRATCH_DIR_QUOTA)
+            .unwrap()
+            .unwrap();
+        assert_eq!(config.root, root);
+        assert_eq!(config.quota, DEFAULT_SCRATCH_DIR_QUOTA);
+
+        let annotations = BTreeMap::from([(SCRATCH_DIR_QUOTA_ANNOTATION.into(), "1024".into())]);
+        let config = scratch_dir_config(&annotations, Some(root), DEFAULT_SCRATCH_DIR_QUOTA)
+            .unwrap()
+            .unwrap();
+        assert_eq!(config.quota, 1024);
+
+        // Components cannot raise the quota of the host
+        let annotations = BTreeMap::from([(
+            SCRATCH_DIR_QUOTA_ANNOTATION.into(),
+            (DEFAULT_SCRATCH_DIR_QUOTA * 2).to_string(),
+        )]);
+        let config = scratch_dir_config(&annotations, Some(root), DEFAULT_SCRATCH_DIR_QUOTA)
+            .unwrap()
+            .unwrap();
+        assert_eq!(config.quota, DEFAULT_SCRATCH_DIR_QUOTA);
+
+        let annotations = BTreeMap::from([(SCRATCH_DIR_QUOTA_ANNOTATION.into(), "1MiB".into())]);
+        assert!(scratch_dir_config(&annotations, Some(root), DEFAULT_SCRATCH_DIR_QUOTA).is_err());
+    }
+}
@@ -0,0 +1,218 @@
+//! Registry of long-lived tasks spawned by the host
+//!
+//! Tasks are registered with a name and the entity owning them, e.g. the provider whose health
+//! they check, and deregistered once they complete or are aborted. The host periodically audits
+//! the registry and warns about tasks outliving their owner, which indicates leaks like watch
+//! loops that were not aborted when a component was stopped or a link was deleted.
+
+use core::future::Future;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Entity owning a task
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum TaskOwner {
+    /// The host itself, which owns tasks for its lifetime
+    Host,
+    /// A component, by ID
+    Component(String),
+    /// A provider, by ID
+    Provider(String),
+    /// A link, by source ID, target and link name
+    Link {
+        source_id: String,
+        target: String,
+        name: String,
+    },
+}
+
+impl TaskOwner {
+    /// Kind of the owner, used as metric attribute
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            Self::Host => "host",
+            Self::Component(..) => "component",
+            Self::Provider(..) => "provider",
+            Self::Link { .. } => "link",
+        }
+    }
+
+    /// ID of the owner, used as metric attribute
+    pub(crate) fn id(&self) -> String {
+        match self {
+            Self::Host => String::new(),
+            Self::Component(id) | Self::Provider(id) => id.clone(),
+            Self::Link {
+                source_id,
+                target,
+                name,
+            } => format!("{source_id}->{target} ({name})"),
+        }
+    }
+}
+
+impl fmt::Display for TaskOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Host => write!(f, "host"),
+            owner => write!(f, "{} {}", owner.kind(), owner.id()),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Task {
+    name: &'static str,
+    owner: TaskOwner,
+    started_at: Instant,
+    /// Whether the task was already reported as orphaned
+    reported: bool,
+}
+
+/// A task outliving its owner
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct OrphanedTask {
+    pub name: &'static str,
+    pub owner: TaskOwner,
+    /// Time since the task was spawned
+    pub age: Duration,
+}
+
+/// Registry of the running long-lived tasks of the host
+#[derive(Debug, Default)]
+pub(crate) struct TaskRegistry {
+    next_id: AtomicU64,
+    tasks: Mutex<HashMap<u64, Task>>,
+}
+
+/// Deregisters a task when dropped, which happens when the task completes or is aborted
+struct TaskGuard {
+    registry: Arc<TaskRegistry>,
+    id: u64,
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        self.registry
+            .tasks
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .remove(&self.id);
+    }
+}
+
+impl TaskRegistry {
+    /// Register a task named `name` owned by `owner`, returning `fut` wrapped to deregister the
+    /// task once it completes or is dropped. The returned future is meant to be spawned
+    pub(crate) fn track<F: Future>(
+        self: &Arc<Self>,
+        name: &'static str,
+        owner: TaskOwner,
+        fut: F,
+    ) -> impl Future<Output = F::Output> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.tasks
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .insert(
+                id,
+                Task {
+                    name,
+                    owner,
+                    started_at: Instant::now(),
+                    reported: false,
+                },
+            );
+        let guard = TaskGuard {
+            registry: Arc::clone(self),
+            id,
+        };
+        async move {
+            let _guard = guard;
+            fut.await
+        }
+    }
+
+    /// Number of running tasks by owner
+    pub(crate) fn counts(&self) -> BTreeMap<TaskOwner, u64> {
+        let tasks = self.tasks.lock().unwrap_or_else(|err| err.into_inner());
+        let mut counts = BTreeMap::new();
+        for task in tasks.values() {
+            *counts.entry(task.owner.clone()).or_default() += 1;
+        }
+        counts
+    }
+
+    /// Returns the tasks whose owner is not alive, as determined by `alive`, which were not
+    /// returned by a previous audit
+    pub(crate) fn audit(&self, alive: impl Fn(&TaskOwner) -> bool) -> Vec<OrphanedTask> {
+        let mut tasks = self.tasks.lock().unwrap_or_else(|err| err.into_inner());
+        let mut orphaned = Vec::new();
+        for task in tasks.values_mut() {
+            if task.reported || task.owner == TaskOwner::Host || alive(&task.owner) {
+                continue;
+            }
+            task.reported = true;
+            orphaned.push(OrphanedTask {
+                name: task.name,
+                owner: task.owner.clone(),
+                age: task.started_at.elapsed(),
+            });
+        }
+        orphaned
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tokio::sync::oneshot;
+
+    use super::{TaskOwner, TaskRegistry};
+
+    #[tokio::test]
+    async fn track_and_audit_tasks() {
+        let registry = Arc::new(TaskRegistry::default());
+        let provider = TaskOwner::Provider("provider".into());
+        let component = TaskOwner::Component("component".into());
+
+        let (tx, rx) = oneshot::channel::<()>();
+        let done = tokio::spawn(registry.track("done", component.clone(), async {}));
+        let pending = tokio::spawn(registry.track("pending", provider.clone(), async {
+            let _ = rx.await;
+        }));
+        let aborted = tokio::spawn(registry.track(
+            "aborted",
+            component.clone(),
+            std::future::pending::<()>(),
+        ));
+        let _host =
+            tokio::spawn(registry.track("host", TaskOwner::Host, std::future::pending::<()>()));
+        done.await.expect("task should complete");
+        aborted.abort();
+        assert!(aborted.await.is_err());
+
+        let counts = registry.counts();
+        assert_eq!(counts.get(&provider), Some(&1));
+        assert_eq!(counts.get(&component), None);
+        assert_eq!(counts.get(&TaskOwner::Host), Some(&1));
+
+        // Tasks of live owners and the host are never orphaned
+        assert!(registry.audit(|_| true).is_empty());
+        let orphaned = registry.audit(|owner| *owner != provider);
+        assert_eq!(orphaned.len(), 1);
+        assert_eq!(orphaned[0].name, "pending");
+        assert_eq!(orphaned[0].owner, provider);
+        // Orphaned tasks are only reported once
+        assert!(registry.audit(|_| false).is_empty());
+
+        tx.send(()).expect("task should be running");
+        pending.await.expect("task should complete");
+        assert_eq!(registry.counts().get(&provider), None);
+    }
+}
@@ -0,0 +1,241 @@
+//! Isolation of tenants sharing a NATS cluster
+//!
+//! A host configured with a [`subject_prefix`](HostConfig::subject_prefix) namespaces all subjects
+//! of its lattice under the prefix: the control interface, events, wRPC and provider RPC subjects
+//! as well as the inboxes replies are received on. Restricting the NATS users of each tenant to
+//! the subjects under their prefix, as done by the permissions generated with
+//! [`nats_authorization`], isolates the lattices of tenants sharing one NATS cluster.
+//!
+//! JetStream API subjects cannot be prefixed, so access to JetStream is instead restricted to the
+//! key-value buckets of the lattice, which are named after both the prefix and the lattice (see
+//! [`lattice_bucket`]). Tenants using the same lattice name therefore never share buckets.
+//!
+//! Subjects chosen by components and providers, e.g. for `wasmcloud:messaging`, are not prefixed.
+
+use anyhow::{bail, ensure};
+use serde_json::json;
+
+use super::HostConfig;
+
+/// Key-value buckets used by the hosts of a lattice, named by [`lattice_bucket`]
+const LATTICE_BUCKETS: [&str; 3] = ["LATTICEDATA", "CONFIGDATA", "PROVIDERLEASES"];
+
+/// Returns the name of key-value `bucket` of `lattice` isolated under subject `prefix`, if any.
+///
+/// Buckets are named `<BUCKET>_<lattice>` without a prefix and `<BUCKET>_<prefix>_<lattice>` with
+/// one, where the `.` separating the tokens of the prefix are replaced by `_`. Since neither the
+/// tokens of a prefix nor the lattice of a prefixed host may contain `_`, distinct prefixes and
+/// lattices always result in distinct bucket names.
+pub(crate) fn lattice_bucket(bucket: &str, prefix: Option<&str>, lattice: &str) -> String {
+    match prefix {
+        Some(prefix) => format!("{bucket}_{}_{lattice}", prefix.replace('.', "_")),
+        None => format!("{bucket}_{lattice}"),
+    }
+}
+
+/// Returns the inbox prefix used by the NATS connections of a host with subject `prefix`
+pub(crate) fn inbox_prefix(prefix: &str) -> String {
+    format!("{prefix}._INBOX")
+}
+
+/// Validates the subject prefix of `config`, if any, and applies it to the control interface
+/// topic prefix. The lattice of a host with a subject prefix must not contain `_`, see
+/// [`lattice_bucket`].
+///
+/// The topics of the secrets backend and the policy service are served by external services, so
+/// they are not prefixed by the host. Instead, the host refuses to start if they are outside of
+/// the subject prefix, since the tenant would not be authorized to use them.
+pub(crate) fn apply_subject_prefix(config: &mut HostConfig) -> anyhow::Result<()> {
+    let Some(prefix) = config.subject_prefix.as_deref() else {
+        return Ok(());
+    };
+    validate_subject_prefix(prefix)?;
+    validate_lattice(&config.lattice)?;
+    let scoped = |topic: &str| topic.starts_with(&format!("{prefix}."));
+    if !scoped(&config.ctl_topic_prefix) {
+        config.ctl_topic_prefix = format!("{prefix}.{}", config.ctl_topic_prefix);
+    }
+    if let Some(topic) = config.secrets_topic_prefix.as_deref() {
+        ensure!(
+            scoped(topic),
+            "secrets topic prefix `{topic}` must start with the subject prefix `{prefix}.`"
+        );
+    }
+    let policy = &config.policy_service_config;
+    for topic in [&policy.policy_topic, &policy.policy_changes_topic]
+        .into_iter()
+        .flatten()
+    {
+        ensure!(
+            scoped(topic),
+            "policy topic `{topic}` must start with the subject prefix `{prefix}.`"
+        );
+    }
+    Ok(())
+}
+
+/// Validates that `prefix` is a literal NATS subject, which subjects can be nested under.
+///
+/// Tokens are limited to ASCII alphanumerics and `-`, so that the prefix can be part of key-value
+/// bucket names
+pub(crate) fn validate_subject_prefix(prefix: &str) -> anyhow::Result<()> {
+    if prefix.is_empty() {
+        bail!("subject prefix must not be empty");
+    }
+    for token in prefix.split('.') {
+        if token.is_empty() {
+            bail!("subject prefix `{prefix}` must not contain empty tokens");
+        }
+        if token == "*" || token == ">" {
+            bail!("subject prefix `{prefix}` must not contain wildcards");
+        }
+        if !token.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            bail!("subject prefix `{prefix}` must only contain ASCII alphanumerics, `-` and `.`");
+        }
+    }
+    Ok(())
+}
+
+/// Validates that `lattice` can be used with a subject prefix
+fn validate_lattice(lattice: &str) -> anyhow::Result<()> {
+    ensure!(
+        !lattice.is_empty() && !lattice.contains('_'),
+        "lattice `{lattice}` must not be empty or contain `_` when a subject prefix is set"
+    );
+    Ok(())
+}
+
+/// Generates the permissions of the NATS users of the tenant with subject `prefix` running
+/// `lattice`, in the format of the `permissions` of a user in the NATS server `authorization`
+/// configuration. `js_domain` is the JetStream domain the hosts of the lattice are configured
+/// with, if any.
+///
+/// Replies, including JetStream consumer deliveries, are received on the inbox under the prefix,
+/// so subscriptions are limited to the prefix.
+pub fn nats_authorization(
+    prefix: &str,
+    lattice: &str,
+    js_domain: Option<&str>,
+) -> anyhow::Result<serde_json::Value> {
+    validate_subject_prefix(prefix)?;
+    validate_lattice(lattice)?;
+    let js_api = match js_domain {
+        Some(domain) => format!("$JS.{domain}.API"),
+        None => "$JS.API".to_string(),
+    };
+    let mut publish = vec![format!("{prefix}.>")];
+    for bucket in LATTICE_BUCKETS.map(|bucket| lattice_bucket(bucket, Some(prefix), lattice)) {
+        publish.extend([
+            format!("$KV.{bucket}.>"),
+            format!("{js_api}.*.*.KV_{bucket}"),
+            format!("{js_api}.*.*.KV_{bucket}.>"),
+            format!("$JS.ACK.KV_{bucket}.>"),
+            format!("$JS.FC.KV_{bucket}.>"),
+        ]);
+    }
+    Ok(json!({
+        "publish": { "allow": publish },
+        "subscribe": { "allow": [format!("{prefix}.>")] },
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::wasmbus::host_config::PolicyService;
+
+    use super::*;
+
+    #[test]
+    fn subject_prefix_applied() -> anyhow::Result<()> {
+        let mut config = HostConfig::default();
+        apply_subject_prefix(&mut config)?;
+        assert_eq!(config.ctl_topic_prefix, "wasmbus.ctl");
+
+        let mut config = HostConfig {
+            subject_prefix: Some("tenants.acme".into()),
+            secrets_topic_prefix: Some("tenants.acme.wasmcloud.secrets".into()),
+            ..Default::default()
+        };
+        apply_subject_prefix(&mut config)?;
+        assert_eq!(config.ctl_topic_prefix, "tenants.acme.wasmbus.ctl");
+        // Applying the prefix again does not nest it
+        apply_subject_prefix(&mut config)?;
+        assert_eq!(config.ctl_topic_prefix, "tenants.acme.wasmbus.ctl");
+        assert_eq!(
+            config.prefixed_subject("wasmbus.evt.default.>"),
+            "tenants.acme.wasmbus.evt.default.>"
+        );
+
+        let mut config = HostConfig {
+            subject_prefix: Some("tenants.acme".into()),
+            policy_service_config: PolicyService {
+                policy_topic: Some("wasmcloud.policy".into()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(apply_subject_prefix(&mut config).is_err());
+
+        let mut config = HostConfig {
+            subject_prefix: Some("tenants.acme".into()),
+            lattice: "acme_default".into(),
+            ..Default::default()
+        };
+        assert!(apply_subject_prefix(&mut config).is_err());
+
+        for prefix in [
+            "",
+            "tenants..acme",
+            "tenants.*",
+            "tenants.>",
+            "$SYS",
+            "ten ants",
+            "tenants.ac_me",
+            "_INBOX",
+        ] {
+            assert!(validate_subject_prefix(prefix).is_err(), "{prefix}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn authorization_template() -> anyhow::Result<()> {
+        let permissions = nats_authorization("tenants.acme", "default", Some("hub"))?;
+        let publish = permissions["publish"]["allow"]
+            .as_array()
+            .expect("publish permissions missing");
+        assert!(publish.contains(&json!("tenants.acme.>")));
+        assert!(publish.contains(&json!("$KV.LATTICEDATA_tenants_acme_default.>")));
+        assert!(publish.contains(&json!("$JS.hub.API.*.*.KV_CONFIGDATA_tenants_acme_default")));
+        assert!(!publish
+            .iter()
+            .any(|subject| subject == "$KV.LATTICEDATA_default.>"));
+        assert!(!publish
+            .iter()
+            .any(|subject| subject == "$JS.API.>" || subject == ">"));
+        let subscribe = permissions["subscribe"]["allow"]
+            .as_array()
+            .expect("subscribe permissions missing");
+        assert!(subscribe.contains(&json!("tenants.acme.>")));
+        assert!(nats_authorization("tenants.*", "default", None).is_err());
+        assert!(nats_authorization("tenants.acme", "acme_default", None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn lattice_buckets() {
+        assert_eq!(
+            lattice_bucket("LATTICEDATA", None, "default"),
+            "LATTICEDATA_default"
+        );
+        assert_eq!(
+            lattice_bucket("CONFIGDATA", Some("tenants.acme"), "default"),
+            "CONFIGDATA_tenants_acme_default"
+        );
+        // Tenants using the same lattice name do not share buckets
+        assert_ne!(
+            lattice_bucket("LATTICEDATA", Some("tenants.acme"), "default"),
+            lattice_bucket("LATTICEDATA", Some("tenants.globex"), "default"),
+        );
+    }
+}
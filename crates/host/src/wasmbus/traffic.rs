@@ -0,0 +1,91 @@
+//! Weighted splitting of invocations between two versions of a component
+//!
+//! Traffic splits are put through the control interface and stored in the lattice data bucket
+//! under `SPLIT_{target}`, so that every host in the lattice routes invocations over links to
+//! `target` the same way. Of every 100 invocations sent by a component to `target`, `weight` are
+//! sent to the canary instead. Canary invocations are spread evenly instead of being sent in bursts.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use std::collections::HashMap;
+
+use wasmcloud_control_interface::TrafficSplit;
+
+/// Traffic splits keyed by link target
+pub(crate) type TrafficSplits = HashMap<Box<str>, Split>;
+
+/// A [`TrafficSplit`] along with the number of invocations routed by it
+#[derive(Debug)]
+pub(crate) struct Split {
+    split: TrafficSplit,
+    invocations: AtomicU64,
+}
+
+impl From<TrafficSplit> for Split {
+    fn from(split: TrafficSplit) -> Self {
+        Self {
+            split,
+            invocations: AtomicU64::default(),
+        }
+    }
+}
+
+impl Split {
+    pub(crate) fn split(&self) -> &TrafficSplit {
+        &self.split
+    }
+
+    /// Returns the lattice ID the next invocation is sent to, either the target or the canary
+    pub(crate) fn route(&self) -> &str {
+        let n = self.invocations.fetch_add(1, Ordering::Relaxed) % 100;
+        if is_canary(n, self.split.weight()) {
+            self.split.canary()
+        } else {
+            self.split.target()
+        }
+    }
+}
+
+/// Whether the `n`-th of 100 invocations is sent to the canary receiving `weight` percent of
+/// invocations, which is the case whenever the canary share of invocations crosses a whole number
+fn is_canary(n: u64, weight: u8) -> bool {
+    let weight = u64::from(weight);
+    (n + 1) * weight / 100 > n * weight / 100
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn split(weight: u8) -> Split {
+        TrafficSplit::builder()
+            .target("echo-v1")
+            .canary("echo-v2")
+            .weight(weight)
+            .build()
+            .expect("failed to build traffic split")
+            .into()
+    }
+
+    #[test]
+    fn route_by_weight() {
+        for weight in [0, 1, 10, 33, 50, 99, 100] {
+            let split = split(weight);
+            let canary = (0..1000).filter(|_| split.route() == "echo-v2").count();
+            assert_eq!(canary, usize::from(weight) * 10, "weight {weight}");
+        }
+    }
+
+    #[test]
+    fn spread_canary_invocations() {
+        let split = split(25);
+        let routes: Vec<_> = (0..8).map(|_| split.route()).collect();
+        assert_eq!(
+            routes,
+            [
+                "echo-v1", "echo-v1", "echo-v1", "echo-v2", "echo-v1", "echo-v1", "echo-v1",
+                "echo-v2"
+            ]
+        );
+    }
+}
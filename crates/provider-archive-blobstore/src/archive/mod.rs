@@ -0,0 +1,558 @@
+//! Archives stored in a linked blobstore
+//!
+//! Archives are streamed from and to the blobstore, entries are never buffered in memory as a
+//! whole. The paths of entries are validated before they are packed or extracted, so that
+//! entries cannot escape the prefix they are extracted to.
+//!
+//! Archives and extracted entries are written to partial objects, which only replace their
+//! target objects once completely written, so objects are never left truncated.
+
+use core::future::Future;
+use core::pin::Pin;
+
+use std::collections::HashSet;
+
+use anyhow::{bail, ensure, Context as _, Result};
+use futures::StreamExt as _;
+use tokio::io::{AsyncRead, AsyncReadExt as _, AsyncWrite, BufReader};
+use tokio::join;
+use tokio_util::io::{ReaderStream, StreamReader};
+
+use crate::bindings::exports::wasmcloud::archive::archiver::{Entry, Format, Summary};
+use crate::config::ArchiveConfig;
+use crate::object::{BlobstoreContainer, BlobstoreObject, DataStatus, DataStream};
+
+mod tar;
+mod zip;
+
+/// Size of the buffer between the archive encoder and the upload of the archive
+const PIPE_SIZE: usize = 64 * 1024;
+
+/// Maximum size of the central directory of a zip archive, which is read into memory
+const MAX_CENTRAL_DIRECTORY_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Future opening the data of an entry to pack, resolving to its data and status
+pub type EntryData = Pin<Box<dyn Future<Output = Result<(DataStream, DataStatus)>> + Send>>;
+
+/// An entry to pack into an archive
+pub struct Source {
+    /// Validated path of the entry within the archive
+    pub path: String,
+    pub size: u64,
+    /// Data of the entry, which is only opened once the entry is packed
+    pub data: EntryData,
+}
+
+/// Pack `sources` into an archive written to `archive`, inferring the format from the object
+/// name unless `format` is set
+pub async fn pack(
+    sources: Vec<(String, BlobstoreObject)>,
+    archive: BlobstoreObject,
+    format: Option<Format>,
+    config: &ArchiveConfig,
+) -> Result<Summary> {
+    let format = match format {
+        Some(format) => format,
+        None => infer_format(archive.name())?,
+    };
+    ensure!(
+        sources.len() <= config.max_entries as usize,
+        "archive exceeds the maximum of {} entries",
+        config.max_entries
+    );
+    let mut paths = HashSet::with_capacity(sources.len());
+    let mut summary = Summary {
+        entries: 0,
+        size: 0,
+    };
+    let mut entries = Vec::with_capacity(sources.len());
+    for (path, object) in sources {
+        let path = entry_path(&path)?;
+        ensure!(paths.insert(path.clone()), "duplicate entry `{path}`");
+        // Fail before writing the archive, if the sources exceed the limits
+        let size = object
+            .size()
+            .await
+            .with_context(|| format!("failed to get size of entry `{path}`"))?;
+        ensure!(
+            size <= config.max_entry_bytes,
+            "entry `{path}` exceeds the maximum size of {} bytes",
+            config.max_entry_bytes
+        );
+        summary.size = summary.size.saturating_add(size);
+        ensure!(
+            summary.size <= config.max_total_bytes,
+            "archive exceeds the maximum total size of {} bytes",
+            config.max_total_bytes
+        );
+        summary.entries += 1;
+        entries.push(Source {
+            path,
+            size,
+            data: Box::pin(async move { object.stream(size).await }),
+        });
+    }
+    let (writer, reader) = tokio::io::duplex(PIPE_SIZE);
+    // The archive is not an entry, its size is only bounded by the entries. Neither side is
+    // cancelled if the other fails, so that the partial archive is always discarded
+    let (encoded, written) = join!(
+        encode(format, writer, entries),
+        archive.write_partial(reader, u64::MAX)
+    );
+    let (partial, _) = written.context("failed to write archive")?;
+    if let Err(err) = encoded {
+        partial.discard().await;
+        return Err(err.context("failed to write archive"));
+    }
+    partial.commit().await.context("failed to write archive")?;
+    Ok(summary)
+}
+
+/// Write the archive of `entries` in `format` to `writer`
+async fn encode(
+    format: Format,
+    writer: impl AsyncWrite + Unpin + Send + 'static,
+    entries: Vec<Source>,
+) -> Result<()> {
+    match format {
+        Format::Tar => tar::pack(writer, entries).await,
+        Format::TarGzip => {
+            tar::pack(
+                async_compression::tokio::write::GzipEncoder::new(writer),
+                entries,
+            )
+            .await
+        }
+        Format::Zip => zip::pack(writer, entries).await,
+    }
+}
+
+/// Extract the entries of `archive` to objects named by the entry paths prefixed with `prefix`
+/// in `container`, or only list them if `container` is `None`
+pub async fn unpack(
+    archive: BlobstoreObject,
+    format: Option<Format>,
+    container: Option<BlobstoreContainer>,
+    prefix: String,
+    config: &ArchiveConfig,
+) -> Result<(Summary, Vec<Entry>)> {
+    let format = match format {
+        Some(format) => format,
+        None => infer_format(archive.name())?,
+    };
+    let size = archive.size().await?;
+    let symlinks = if matches!(format, Format::Zip) {
+        zip_symlinks(&archive, size).await?
+    } else {
+        HashSet::new()
+    };
+    let (data, status) = archive.stream(size).await?;
+    let mut reader = StreamReader::new(data);
+    let mut unpacker = Unpacker::new(config, container, prefix);
+    decode(format, &mut reader, &symlinks, &mut unpacker)
+        .await
+        .context("failed to read archive")?;
+    finish(reader, status).await?;
+    Ok((unpacker.summary, unpacker.entries))
+}
+
+/// Read the archive in `format` from `reader`, passing its entries to `unpacker`. Entries of zip
+/// archives with paths in `symlinks` are skipped
+async fn decode(
+    format: Format,
+    reader: impl AsyncRead + Unpin + Send,
+    symlinks: &HashSet<String>,
+    unpacker: &mut Unpacker,
+) -> Result<()> {
+    match format {
+        Format::Tar => tar::unpack(reader, unpacker).await,
+        Format::TarGzip => {
+            let decoder =
+                async_compression::tokio::bufread::GzipDecoder::new(BufReader::new(reader));
+            tar::unpack(decoder, unpacker).await
+        }
+        Format::Zip => zip::unpack(BufReader::new(reader), symlinks, unpacker).await,
+    }
+}
+
+/// Read the paths of the symbolic links of zip `archive` of `size` bytes from its central
+/// directory, since the local headers the entries are streamed by do not specify file types
+async fn zip_symlinks(archive: &BlobstoreObject, size: u64) -> Result<HashSet<String>> {
+    let tail_offset = size.saturating_sub(zip::MAX_TAIL_SIZE);
+    let tail = archive
+        .read(tail_offset, size - tail_offset)
+        .await
+        .context("failed to read end of archive")?;
+    let Some(directory) = zip::central_directory(&tail, tail_offset) else {
+        // Not a valid zip archive, which fails once its entries are read
+        return Ok(HashSet::new());
+    };
+    ensure!(
+        directory.size <= MAX_CENTRAL_DIRECTORY_SIZE,
+        "central directory exceeds the maximum size of {MAX_CENTRAL_DIRECTORY_SIZE} bytes"
+    );
+    ensure!(
+        directory.offset.saturating_add(directory.size) <= size,
+        "central directory exceeds the archive"
+    );
+    let directory = archive
+        .read(directory.offset, directory.size)
+        .await
+        .context("failed to read central directory")?;
+    zip::symlinks(&directory)
+}
+
+/// Drain any trailing data of the archive following the end of its entries, so that the status
+/// of the stream can be awaited
+async fn finish(reader: impl AsyncRead + Unpin, status: DataStatus) -> Result<()> {
+    let mut trailing = ReaderStream::new(reader);
+    while let Some(chunk) = trailing.next().await {
+        chunk.context("failed to read archive")?;
+    }
+    status.await
+}
+
+/// Extracts or lists the entries of an archive, enforcing the limits of the configuration
+pub struct Unpacker {
+    config: ArchiveConfig,
+    /// Container entries are extracted to, entries are only listed if `None`
+    destination: Option<BlobstoreContainer>,
+    prefix: String,
+    summary: Summary,
+    entries: Vec<Entry>,
+}
+
+impl Unpacker {
+    fn new(
+        config: &ArchiveConfig,
+        destination: Option<BlobstoreContainer>,
+        prefix: String,
+    ) -> Self {
+        Self {
+            config: config.clone(),
+            destination,
+            prefix,
+            summary: Summary {
+                entries: 0,
+                size: 0,
+            },
+            entries: Vec::new(),
+        }
+    }
+
+    /// Extract or list the file entry at `path` of the archive with contents `data`
+    pub async fn entry(&mut self, path: &str, data: impl AsyncRead + Unpin + Send) -> Result<()> {
+        let path = entry_path(path)?;
+        ensure!(
+            self.summary.entries < self.config.max_entries,
+            "archive exceeds the maximum of {} entries",
+            self.config.max_entries
+        );
+        let remaining = self.config.max_total_bytes - self.summary.size;
+        let limit = self.config.max_entry_bytes.min(remaining);
+        let size = match &self.destination {
+            Some(destination) => {
+                let object = destination.object(format!("{}{path}", self.prefix));
+                object.write(data, limit).await
+            }
+            None => count(data, limit).await,
+        };
+        let size = size.with_context(|| {
+            if limit < self.config.max_entry_bytes {
+                format!(
+                    "failed to read entry `{path}`, archive may exceed the maximum total size of {} bytes",
+                    self.config.max_total_bytes
+                )
+            } else {
+                format!("failed to read entry `{path}`")
+            }
+        })?;
+        self.summary.entries += 1;
+        self.summary.size += size;
+        if self.destination.is_none() {
+            self.entries.push(Entry { path, size });
+        }
+        Ok(())
+    }
+}
+
+/// Read `data` to its end, failing once more than `limit` bytes were read, and return its size
+async fn count(data: impl AsyncRead + Unpin, limit: u64) -> Result<u64> {
+    let mut data = data.take(limit.saturating_add(1));
+    let size = tokio::io::copy(&mut data, &mut tokio::io::sink())
+        .await
+        .context("failed to read data")?;
+    ensure!(
+        size <= limit,
+        "data exceeds the maximum size of {limit} bytes"
+    );
+    Ok(size)
+}
+
+/// Validate the path of an entry, returning it normalized to `/`-separated components without
+/// `.` and empty components.
+///
+/// Absolute paths, paths with `..` components, Windows separators and drive letters and control
+/// characters are rejected, so entries stay within the prefix they are extracted to
+pub fn entry_path(path: &str) -> Result<String> {
+    if path.starts_with('/') {
+        bail!("entry path `{path}` must not be absolute")
+    }
+    if path.contains('\\') {
+        bail!("entry path `{path}` must not contain `\\`")
+    }
+    if path.chars().any(char::is_control) {
+        bail!(
+            "entry path `{}` must not contain control characters",
+            path.escape_debug()
+        )
+    }
+    let mut components = Vec::new();
+    for component in path.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => bail!("entry path `{path}` must not contain `..`"),
+            component if components.is_empty() && component.ends_with(':') => {
+                bail!("entry path `{path}` must not start with a drive letter")
+            }
+            component => components.push(component),
+        }
+    }
+    ensure!(
+        !components.is_empty(),
+        "entry path `{path}` must not be empty"
+    );
+    Ok(components.join("/"))
+}
+
+/// Infer the format of an archive from the extension of its object name
+fn infer_format(name: &str) -> Result<Format> {
+    let name_lower = name.to_ascii_lowercase();
+    if name_lower.ends_with(".zip") {
+        Ok(Format::Zip)
+    } else if name_lower.ends_with(".tar") {
+        Ok(Format::Tar)
+    } else if name_lower.ends_with(".tar.gz") || name_lower.ends_with(".tgz") {
+        Ok(Format::TarGzip)
+    } else {
+        bail!("failed to infer format of object `{name}` from its extension, `format` must be set")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use async_zip::base::write::ZipFileWriter;
+    use async_zip::{Compression, ZipEntryBuilder};
+    use bytes::Bytes;
+    use futures::stream;
+    use tokio_tar::{Builder, EntryType, Header};
+
+    use super::*;
+
+    const FORMATS: [Format; 3] = [Format::Tar, Format::TarGzip, Format::Zip];
+
+    static LARGE: [u8; 200_000] = [7; 200_000];
+
+    /// Returns the source of the entry at `path` with contents `data`
+    fn source(path: &str, data: &'static [u8]) -> Source {
+        Source {
+            path: path.into(),
+            size: data.len() as u64,
+            data: Box::pin(async move {
+                let data: DataStream = Box::pin(stream::iter([Ok(Bytes::from_static(data))]));
+                let status: DataStatus = Box::pin(async { Ok(()) });
+                Ok((data, status))
+            }),
+        }
+    }
+
+    /// Returns the archive of `entries` in `format`
+    async fn pack_entries(format: Format, entries: Vec<Source>) -> Result<Vec<u8>> {
+        let (writer, mut reader) = tokio::io::duplex(PIPE_SIZE);
+        let mut archive = Vec::new();
+        let (encoded, read) = join!(
+            encode(format, writer, entries),
+            reader.read_to_end(&mut archive)
+        );
+        encoded?;
+        read?;
+        Ok(archive)
+    }
+
+    /// Returns the paths of the symbolic links of zip `archive`
+    fn symlinks_of(archive: &[u8]) -> Result<HashSet<String>> {
+        let directory =
+            zip::central_directory(archive, 0).context("central directory not found")?;
+        let start = usize::try_from(directory.offset)?;
+        let end = start + usize::try_from(directory.size)?;
+        zip::symlinks(
+            archive
+                .get(start..end)
+                .context("invalid central directory")?,
+        )
+    }
+
+    /// Lists the paths and sizes of the entries of `archive` in `format`
+    async fn list(
+        format: Format,
+        archive: &[u8],
+        config: &ArchiveConfig,
+    ) -> Result<(Summary, Vec<(String, u64)>)> {
+        let symlinks = if matches!(format, Format::Zip) {
+            symlinks_of(archive)?
+        } else {
+            HashSet::new()
+        };
+        let mut unpacker = Unpacker::new(config, None, String::new());
+        decode(format, archive, &symlinks, &mut unpacker).await?;
+        let entries = unpacker
+            .entries
+            .into_iter()
+            .map(|Entry { path, size }| (path, size))
+            .collect();
+        Ok((unpacker.summary, entries))
+    }
+
+    #[tokio::test]
+    async fn round_trip() -> Result<()> {
+        for format in FORMATS {
+            let archive = pack_entries(
+                format,
+                vec![
+                    source("docs/a.txt", b"hello"),
+                    source("empty", b""),
+                    source("large.bin", &LARGE),
+                ],
+            )
+            .await?;
+            let (summary, entries) = list(format, &archive, &ArchiveConfig::default()).await?;
+            assert_eq!(
+                entries,
+                [
+                    ("docs/a.txt".to_string(), 5),
+                    ("empty".to_string(), 0),
+                    ("large.bin".to_string(), LARGE.len() as u64),
+                ],
+                "{format:?}"
+            );
+            assert_eq!(summary.entries, 3);
+            assert_eq!(summary.size, 5 + LARGE.len() as u64);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unpack_limits() -> Result<()> {
+        for format in FORMATS {
+            let archive = pack_entries(
+                format,
+                vec![
+                    source("a", b"12345"),
+                    source("b", b"12345"),
+                    source("c", b"12345"),
+                ],
+            )
+            .await?;
+            let limited = |max_entries, max_entry_bytes, max_total_bytes| ArchiveConfig {
+                max_entries,
+                max_entry_bytes,
+                max_total_bytes,
+            };
+            list(format, &archive, &limited(3, 5, 15)).await?;
+            for config in [limited(2, 5, 15), limited(3, 4, 15), limited(3, 5, 14)] {
+                assert!(
+                    list(format, &archive, &config).await.is_err(),
+                    "{format:?} {config:?}"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn skip_tar_links() -> Result<()> {
+        let mut builder = Builder::new(Vec::new());
+        let mut header = Header::new_gnu();
+        header.set_entry_type(EntryType::Regular);
+        header.set_size(5);
+        header.set_mode(0o644);
+        builder
+            .append_data(&mut header, "a.txt", &b"hello"[..])
+            .await?;
+        for (path, entry_type) in [
+            ("dir", EntryType::Directory),
+            ("symlink", EntryType::Symlink),
+            ("hardlink", EntryType::Link),
+        ] {
+            let mut header = Header::new_gnu();
+            header.set_entry_type(entry_type);
+            header.set_size(0);
+            header.set_mode(0o777);
+            header.set_link_name("a.txt")?;
+            builder.append_data(&mut header, path, &b""[..]).await?;
+        }
+        let archive = builder.into_inner().await?;
+        let (summary, entries) = list(Format::Tar, &archive, &ArchiveConfig::default()).await?;
+        assert_eq!(entries, [("a.txt".to_string(), 5)]);
+        assert_eq!(summary.entries, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn skip_zip_symlinks() -> Result<()> {
+        let mut zip = ZipFileWriter::with_tokio(Vec::new());
+        zip.write_entry_whole(
+            ZipEntryBuilder::new("a.txt".to_string().into(), Compression::Deflate)
+                .unix_permissions(0o644),
+            b"hello",
+        )
+        .await?;
+        zip.write_entry_whole(
+            ZipEntryBuilder::new("link".to_string().into(), Compression::Stored)
+                .unix_permissions(0o120_777),
+            b"/etc/passwd",
+        )
+        .await?;
+        let archive = zip.close().await?.into_inner();
+        assert_eq!(symlinks_of(&archive)?, HashSet::from(["link".to_string()]));
+        let (summary, entries) = list(Format::Zip, &archive, &ArchiveConfig::default()).await?;
+        assert_eq!(entries, [("a.txt".to_string(), 5)]);
+        assert_eq!(summary.entries, 1);
+
+        // The central directory is only searched at the end of the archive
+        assert!(zip::central_directory(&archive[..archive.len() - 1], 0).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn validate_entry_path() {
+        assert_eq!(entry_path("a.txt").unwrap(), "a.txt");
+        assert_eq!(entry_path("./docs//a.txt").unwrap(), "docs/a.txt");
+        assert_eq!(entry_path("docs/./b/").unwrap(), "docs/b");
+        assert_eq!(entry_path("v1.2/..a").unwrap(), "v1.2/..a");
+        assert!(entry_path("").is_err());
+        assert!(entry_path("./").is_err());
+        assert!(entry_path("/etc/passwd").is_err());
+        assert!(entry_path("../a.txt").is_err());
+        assert!(entry_path("docs/../../a.txt").is_err());
+        assert!(entry_path("docs\\..\\a.txt").is_err());
+        assert!(entry_path("C:/a.txt").is_err());
+        assert!(entry_path("a\0.txt").is_err());
+    }
+
+    #[test]
+    fn infer_archive_format() {
+        assert!(matches!(infer_format("backup.ZIP"), Ok(Format::Zip)));
+        assert!(matches!(infer_format("backup.tar"), Ok(Format::Tar)));
+        assert!(matches!(infer_format("backup.tar.gz"), Ok(Format::TarGzip)));
+        assert!(matches!(infer_format("backup.tgz"), Ok(Format::TarGzip)));
+        assert!(infer_format("backup.gz").is_err());
+        assert!(infer_format("backup").is_err());
+    }
+
+    #[tokio::test]
+    async fn count_limit() {
+        assert_eq!(count(&b"data"[..], 4).await.unwrap(), 4);
+        assert!(count(&b"data"[..], 3).await.is_err());
+    }
+}
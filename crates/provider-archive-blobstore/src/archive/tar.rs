@@ -0,0 +1,68 @@
+//! Tar archives, optionally compressed with gzip
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context as _, Result};
+use futures::StreamExt as _;
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt as _};
+use tokio_tar::{Archive, Builder, EntryType, Header};
+use tokio_util::io::StreamReader;
+use tracing::debug;
+
+use super::{Source, Unpacker};
+
+/// Write the tar archive of `entries` to `writer`, streaming the entries one after another
+pub async fn pack(
+    writer: impl AsyncWrite + Unpin + Send + 'static,
+    entries: Vec<Source>,
+) -> Result<()> {
+    let mtime = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let mut builder = Builder::new(writer);
+    for Source { path, size, data } in entries {
+        let mut header = Header::new_gnu();
+        header.set_entry_type(EntryType::Regular);
+        header.set_size(size);
+        header.set_mode(0o644);
+        header.set_mtime(mtime);
+        let (data, status) = data.await?;
+        builder
+            .append_data(&mut header, &path, StreamReader::new(data))
+            .await
+            .with_context(|| format!("failed to write entry `{path}`"))?;
+        status.await?;
+    }
+    let mut writer = builder
+        .into_inner()
+        .await
+        .context("failed to finish archive")?;
+    writer.shutdown().await.context("failed to finish archive")
+}
+
+/// Read the tar archive from `reader`, passing its regular file entries to `unpacker`.
+///
+/// Directories are implied by the paths of files, links and special files are skipped
+pub async fn unpack(reader: impl AsyncRead + Unpin + Send, unpacker: &mut Unpacker) -> Result<()> {
+    let mut archive = Archive::new(reader);
+    let mut entries = archive.entries().context("failed to read entries")?;
+    while let Some(entry) = entries.next().await {
+        let mut entry = entry.context("failed to read entry")?;
+        let entry_type = entry.header().entry_type();
+        let path = String::from_utf8(entry.path_bytes().into_owned())
+            .context("entry path is not valid UTF-8")?;
+        if entry_type.is_dir() {
+            continue;
+        }
+        if !entry_type.is_file() {
+            debug!(
+                path,
+                ?entry_type,
+                "skipping entry, which is not a regular file"
+            );
+            continue;
+        }
+        unpacker.entry(&path, &mut entry).await?;
+    }
+    Ok(())
+}
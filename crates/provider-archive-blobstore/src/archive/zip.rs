@@ -0,0 +1,197 @@
+//! Zip archives
+//!
+//! Zip archives are read sequentially by their local file headers, as the central directory at
+//! the end of an archive cannot be reached without buffering the archive. Local file headers do
+//! not specify file types though, so symbolic links are read from the central directory up front
+
+use std::collections::HashSet;
+
+use anyhow::{ensure, Context as _, Result};
+use async_zip::base::read::stream::ZipFileReader;
+use async_zip::base::write::ZipFileWriter;
+use async_zip::{Compression, ZipEntryBuilder};
+use tokio::io::{AsyncBufRead, AsyncWrite, AsyncWriteExt as _};
+use tokio_util::compat::{FuturesAsyncReadCompatExt as _, TokioAsyncReadCompatExt as _};
+use tokio_util::io::StreamReader;
+use tracing::debug;
+
+use super::{Source, Unpacker};
+
+/// Unix file type bits of the external attributes of an entry
+const S_IFMT: u32 = 0o170_000;
+
+/// Unix file type of symbolic links
+const S_IFLNK: u32 = 0o120_000;
+
+/// Host system of entries with Unix external attributes
+const HOST_UNIX: u8 = 3;
+
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: [u8; 4] = 0x0605_4b50_u32.to_le_bytes();
+const END_OF_CENTRAL_DIRECTORY_SIZE: usize = 22;
+const ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE: [u8; 4] = 0x0606_4b50_u32.to_le_bytes();
+const ZIP64_END_OF_CENTRAL_DIRECTORY_SIZE: usize = 56;
+const ZIP64_LOCATOR_SIGNATURE: [u8; 4] = 0x0706_4b50_u32.to_le_bytes();
+const ZIP64_LOCATOR_SIZE: usize = 20;
+const CENTRAL_HEADER_SIGNATURE: [u8; 4] = 0x0201_4b50_u32.to_le_bytes();
+const CENTRAL_HEADER_SIZE: usize = 46;
+
+/// Number of bytes at the end of a zip archive, which contain the end of central directory
+/// record with a comment of up to 64 KiB, preceded by the zip64 record and locator
+pub const MAX_TAIL_SIZE: u64 = (END_OF_CENTRAL_DIRECTORY_SIZE
+    + 0xffff
+    + ZIP64_LOCATOR_SIZE
+    + ZIP64_END_OF_CENTRAL_DIRECTORY_SIZE) as u64;
+
+/// Location of the central directory within a zip archive
+#[derive(Debug, PartialEq, Eq)]
+pub struct CentralDirectory {
+    pub offset: u64,
+    pub size: u64,
+}
+
+fn u16_at(data: &[u8], at: usize) -> Option<u16> {
+    data.get(at..at + 2)?
+        .try_into()
+        .ok()
+        .map(u16::from_le_bytes)
+}
+
+fn u32_at(data: &[u8], at: usize) -> Option<u32> {
+    data.get(at..at + 4)?
+        .try_into()
+        .ok()
+        .map(u32::from_le_bytes)
+}
+
+fn u64_at(data: &[u8], at: usize) -> Option<u64> {
+    data.get(at..at + 8)?
+        .try_into()
+        .ok()
+        .map(u64::from_le_bytes)
+}
+
+/// Locate the central directory by the `tail` of a zip archive, which starts at `tail_offset`
+/// within the archive. Returns `None` if the tail contains no end of central directory record
+pub fn central_directory(tail: &[u8], tail_offset: u64) -> Option<CentralDirectory> {
+    let end = (0..=tail.len().checked_sub(END_OF_CENTRAL_DIRECTORY_SIZE)?)
+        .rev()
+        .find(|&at| tail[at..].starts_with(&END_OF_CENTRAL_DIRECTORY_SIGNATURE))?;
+    let size = u32_at(tail, end + 12)?;
+    let offset = u32_at(tail, end + 16)?;
+    if size != u32::MAX && offset != u32::MAX {
+        return Some(CentralDirectory {
+            offset: offset.into(),
+            size: size.into(),
+        });
+    }
+    // The zip64 locator immediately precedes the end of central directory record
+    let locator = end.checked_sub(ZIP64_LOCATOR_SIZE)?;
+    if !tail[locator..].starts_with(&ZIP64_LOCATOR_SIGNATURE) {
+        return None;
+    }
+    let record = u64_at(tail, locator + 8)?.checked_sub(tail_offset)?;
+    let record = usize::try_from(record).ok()?;
+    if !tail
+        .get(record..)?
+        .starts_with(&ZIP64_END_OF_CENTRAL_DIRECTORY_SIGNATURE)
+    {
+        return None;
+    }
+    Some(CentralDirectory {
+        offset: u64_at(tail, record + 48)?,
+        size: u64_at(tail, record + 40)?,
+    })
+}
+
+/// Returns the paths of the symbolic links listed in the `central_directory` of a zip archive
+pub fn symlinks(central_directory: &[u8]) -> Result<HashSet<String>> {
+    let mut symlinks = HashSet::new();
+    let mut rest = central_directory;
+    while !rest.is_empty() {
+        ensure!(
+            rest.len() >= CENTRAL_HEADER_SIZE && rest.starts_with(&CENTRAL_HEADER_SIGNATURE),
+            "invalid central directory header"
+        );
+        // The upper byte of the version made by specifies the host system
+        let host = rest[5];
+        let name_len = u16_at(rest, 28).map_or(0, usize::from);
+        let extra_len = u16_at(rest, 30).map_or(0, usize::from);
+        let comment_len = u16_at(rest, 32).map_or(0, usize::from);
+        let attributes = u32_at(rest, 38).unwrap_or_default();
+        let name = rest
+            .get(CENTRAL_HEADER_SIZE..CENTRAL_HEADER_SIZE + name_len)
+            .context("invalid central directory header")?;
+        if host == HOST_UNIX && (attributes >> 16) & S_IFMT == S_IFLNK {
+            symlinks.insert(String::from_utf8_lossy(name).into_owned());
+        }
+        rest = rest
+            .get(CENTRAL_HEADER_SIZE + name_len + extra_len + comment_len..)
+            .context("invalid central directory header")?;
+    }
+    Ok(symlinks)
+}
+
+/// Write the deflate-compressed zip archive of `entries` to `writer`, streaming the entries one
+/// after another
+pub async fn pack(writer: impl AsyncWrite + Unpin + Send, entries: Vec<Source>) -> Result<()> {
+    let mut zip = ZipFileWriter::with_tokio(writer);
+    for Source { path, data, .. } in entries {
+        let (data, status) = data.await?;
+        let builder =
+            ZipEntryBuilder::new(path.clone().into(), Compression::Deflate).unix_permissions(0o644);
+        let mut entry = zip
+            .write_entry_stream(builder)
+            .await
+            .with_context(|| format!("failed to write entry `{path}`"))?;
+        futures::io::copy(StreamReader::new(data).compat(), &mut entry)
+            .await
+            .with_context(|| format!("failed to write entry `{path}`"))?;
+        entry
+            .close()
+            .await
+            .with_context(|| format!("failed to write entry `{path}`"))?;
+        status.await?;
+    }
+    let mut writer = zip
+        .close()
+        .await
+        .context("failed to finish archive")?
+        .into_inner();
+    writer.shutdown().await.context("failed to finish archive")
+}
+
+/// Read the zip archive from `reader`, passing its file entries to `unpacker`.
+///
+/// Directories are implied by the paths of files, entries with paths in `symlinks` are skipped
+pub async fn unpack(
+    reader: impl AsyncBufRead + Unpin + Send,
+    symlinks: &HashSet<String>,
+    unpacker: &mut Unpacker,
+) -> Result<()> {
+    let mut zip = ZipFileReader::with_tokio(reader);
+    while let Some(mut reading) = zip
+        .next_with_entry()
+        .await
+        .context("failed to read entry")?
+    {
+        let entry = reading.reader().entry();
+        let path = entry
+            .filename()
+            .as_str()
+            .context("entry path is not valid UTF-8")?
+            .to_string();
+        let is_dir = entry.dir().context("failed to read entry")?;
+        if is_dir {
+            zip = reading.skip().await.context("failed to read entry")?;
+            continue;
+        }
+        if symlinks.contains(&path) {
+            debug!(path, "skipping entry, which is a symbolic link");
+            zip = reading.skip().await.context("failed to read entry")?;
+            continue;
+        }
+        unpacker.entry(&path, reading.reader_mut().compat()).await?;
+        zip = reading.done().await.context("failed to read entry")?;
+    }
+    Ok(())
+}
@@ -0,0 +1,115 @@
+//! Configuration for archive-blobstore capability provider
+//!
+//! See README.md for the supported link configuration.
+
+use anyhow::{ensure, Context as _, Result};
+use wasmcloud_provider_sdk::LinkConfig;
+
+/// Maximum number of entries of an archive
+const CONFIG_MAX_ENTRIES_KEY: &str = "MAX_ENTRIES";
+
+/// Maximum uncompressed size of a single entry, in bytes
+const CONFIG_MAX_ENTRY_BYTES_KEY: &str = "MAX_ENTRY_BYTES";
+
+/// Maximum total uncompressed size of the entries of an archive, in bytes
+const CONFIG_MAX_TOTAL_BYTES_KEY: &str = "MAX_TOTAL_BYTES";
+
+const DEFAULT_MAX_ENTRIES: u32 = 10_000;
+
+const DEFAULT_MAX_ENTRY_BYTES: u64 = 1024 * 1024 * 1024;
+
+const DEFAULT_MAX_TOTAL_BYTES: u64 = 4 * 1024 * 1024 * 1024;
+
+/// Limits of the archives packed and extracted by a component
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArchiveConfig {
+    /// MAX_ENTRIES, maximum number of entries of an archive. Defaults to 10000
+    pub max_entries: u32,
+
+    /// MAX_ENTRY_BYTES, maximum uncompressed size of a single entry in bytes. Defaults to 1 GiB
+    pub max_entry_bytes: u64,
+
+    /// MAX_TOTAL_BYTES, maximum total uncompressed size of the entries of an archive in bytes.
+    /// Defaults to 4 GiB
+    pub max_total_bytes: u64,
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: DEFAULT_MAX_ENTRIES,
+            max_entry_bytes: DEFAULT_MAX_ENTRY_BYTES,
+            max_total_bytes: DEFAULT_MAX_TOTAL_BYTES,
+        }
+    }
+}
+
+impl ArchiveConfig {
+    /// Build an [`ArchiveConfig`] from a link configuration
+    pub fn from_link_config(LinkConfig { config, .. }: &LinkConfig) -> Result<Self> {
+        let mut archive = Self::default();
+        if let Some(max_entries) = config.get(CONFIG_MAX_ENTRIES_KEY) {
+            archive.max_entries = max_entries.parse().with_context(|| {
+                format!("invalid `{CONFIG_MAX_ENTRIES_KEY}` value `{max_entries}`")
+            })?;
+            ensure!(
+                archive.max_entries > 0,
+                "`{CONFIG_MAX_ENTRIES_KEY}` must be greater than 0"
+            );
+        }
+        if let Some(max_entry_bytes) = config.get(CONFIG_MAX_ENTRY_BYTES_KEY) {
+            archive.max_entry_bytes = max_entry_bytes.parse().with_context(|| {
+                format!("invalid `{CONFIG_MAX_ENTRY_BYTES_KEY}` value `{max_entry_bytes}`")
+            })?;
+        }
+        if let Some(max_total_bytes) = config.get(CONFIG_MAX_TOTAL_BYTES_KEY) {
+            archive.max_total_bytes = max_total_bytes.parse().with_context(|| {
+                format!("invalid `{CONFIG_MAX_TOTAL_BYTES_KEY}` value `{max_total_bytes}`")
+            })?;
+        }
+        Ok(archive)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn archive_config(config: &[(&str, &str)]) -> Result<ArchiveConfig> {
+        let config: HashMap<_, _> = config
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        ArchiveConfig::from_link_config(&LinkConfig {
+            source_id: "component",
+            target_id: "provider",
+            link_name: "default",
+            config: &config,
+            secrets: &HashMap::new(),
+            wit_metadata: (&"wasmcloud".to_string(), &"archive".to_string(), &vec![]),
+        })
+    }
+
+    #[test]
+    fn parse_config() {
+        assert_eq!(archive_config(&[]).unwrap(), ArchiveConfig::default());
+        assert_eq!(
+            archive_config(&[
+                ("MAX_ENTRIES", "100"),
+                ("MAX_ENTRY_BYTES", "1048576"),
+                ("MAX_TOTAL_BYTES", "10485760"),
+            ])
+            .unwrap(),
+            ArchiveConfig {
+                max_entries: 100,
+                max_entry_bytes: 1024 * 1024,
+                max_total_bytes: 10 * 1024 * 1024,
+            }
+        );
+        assert!(archive_config(&[("MAX_ENTRIES", "0")]).is_err());
+        assert!(archive_config(&[("MAX_ENTRY_BYTES", "-1")]).is_err());
+        assert!(archive_config(&[("MAX_TOTAL_BYTES", "1GiB")]).is_err());
+    }
+}
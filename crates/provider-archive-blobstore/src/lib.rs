@@ -0,0 +1,232 @@
+//! Archive provider implementing `wasmcloud:archive/archiver`, which packs objects of a linked
+//! blobstore into zip and tar archives and extracts archives into a linked blobstore.
+//!
+//! The provider is the source of links to blobstore providers implementing
+//! `wrpc:blobstore/blobstore`. Components access the blobstore linked to the provider with the
+//! same link name as the link of the component to the provider.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{bail, Context as _};
+use tokio::sync::RwLock;
+use tracing::{debug, error, instrument};
+use wasmcloud_provider_sdk::provider::WrpcClient;
+use wasmcloud_provider_sdk::{
+    get_connection, initialize_observability, propagate_trace_for_ctx, run_provider,
+    serve_provider_exports, Context, LinkConfig, LinkDeleteInfo, Provider,
+};
+
+mod archive;
+mod config;
+mod object;
+
+use config::ArchiveConfig;
+use object::{BlobstoreContainer, BlobstoreObject};
+
+mod bindings {
+    wit_bindgen_wrpc::generate!({
+        with: {
+            "wasmcloud:archive/archiver@0.1.0-draft": generate,
+        }
+    });
+}
+use bindings::exports::wasmcloud::archive::archiver::{
+    Entry, Format, Handler, ObjectId, PackEntry, Summary,
+};
+
+pub async fn run() -> anyhow::Result<()> {
+    ArchiveBlobstoreProvider::run().await
+}
+
+/// Archive provider packing and extracting archives in linked blobstores
+#[derive(Clone, Default)]
+pub struct ArchiveBlobstoreProvider {
+    /// Archive configuration of components, indexed by source ID
+    components: Arc<RwLock<HashMap<String, ArchiveConfig>>>,
+    /// Clients of linked blobstores, indexed by link name
+    blobstores: Arc<RwLock<HashMap<String, Arc<WrpcClient>>>>,
+}
+
+impl ArchiveBlobstoreProvider {
+    fn name() -> &'static str {
+        "archive-blobstore-provider"
+    }
+
+    /// Run [`ArchiveBlobstoreProvider`] as a wasmCloud provider
+    pub async fn run() -> anyhow::Result<()> {
+        initialize_observability!(
+            ArchiveBlobstoreProvider::name(),
+            std::env::var_os("PROVIDER_ARCHIVE_BLOBSTORE_FLAMEGRAPH_PATH")
+        );
+        let provider = ArchiveBlobstoreProvider::default();
+        let shutdown = run_provider(provider.clone(), ArchiveBlobstoreProvider::name())
+            .await
+            .context("failed to run provider")?;
+        let connection = get_connection();
+        let wrpc = connection
+            .get_wrpc_client(connection.provider_key())
+            .await?;
+        serve_provider_exports(&wrpc, provider, shutdown, bindings::serve)
+            .await
+            .context("failed to serve provider exports")
+    }
+
+    /// Get the configuration of the source of the invocation and the client of the blobstore
+    /// linked with the link name of the invocation
+    async fn blobstore(
+        &self,
+        context: Option<Context>,
+    ) -> anyhow::Result<(ArchiveConfig, Arc<WrpcClient>)> {
+        let context = context.context("failed to lookup source of invocation")?;
+        let source_id = context
+            .component
+            .as_deref()
+            .context("failed to lookup source of invocation")?;
+        let config = self
+            .components
+            .read()
+            .await
+            .get(source_id)
+            .cloned()
+            .with_context(|| format!("component `{source_id}` is not linked to the provider"))?;
+        let link_name = context.link_name();
+        let wrpc = self
+            .blobstores
+            .read()
+            .await
+            .get(link_name)
+            .cloned()
+            .with_context(|| format!("no blobstore linked with link name `{link_name}`"))?;
+        Ok((config, wrpc))
+    }
+}
+
+impl Provider for ArchiveBlobstoreProvider {
+    #[instrument(level = "info", skip_all, fields(source_id = link_config.source_id))]
+    async fn receive_link_config_as_target(
+        &self,
+        link_config: LinkConfig<'_>,
+    ) -> anyhow::Result<()> {
+        let config = match ArchiveConfig::from_link_config(&link_config) {
+            Ok(config) => config,
+            Err(e) => {
+                error!(error = %e, source_id = %link_config.source_id, "failed to parse archive configuration");
+                return Err(e);
+            }
+        };
+        self.components
+            .write()
+            .await
+            .insert(link_config.source_id.to_string(), config);
+        Ok(())
+    }
+
+    #[instrument(level = "info", skip_all, fields(target_id = link_config.target_id, link_name = link_config.link_name))]
+    async fn receive_link_config_as_source(
+        &self,
+        link_config: LinkConfig<'_>,
+    ) -> anyhow::Result<()> {
+        let (namespace, package, _) = link_config.wit_metadata;
+        if namespace.as_str() != "wrpc" || package.as_str() != "blobstore" {
+            bail!("unsupported link to `{namespace}:{package}`, only links to `wrpc:blobstore` are supported")
+        }
+        let wrpc = get_connection()
+            .get_wrpc_client(link_config.target_id)
+            .await
+            .context("failed to construct wRPC client")?;
+        debug!("linked blobstore");
+        self.blobstores
+            .write()
+            .await
+            .insert(link_config.link_name.to_string(), Arc::new(wrpc));
+        Ok(())
+    }
+
+    #[instrument(level = "info", skip_all, fields(source_id = info.get_source_id()))]
+    async fn delete_link_as_target(&self, info: impl LinkDeleteInfo) -> anyhow::Result<()> {
+        self.components.write().await.remove(info.get_source_id());
+        Ok(())
+    }
+
+    #[instrument(level = "info", skip_all, fields(target_id = info.get_target_id(), link_name = info.get_link_name()))]
+    async fn delete_link_as_source(&self, info: impl LinkDeleteInfo) -> anyhow::Result<()> {
+        self.blobstores.write().await.remove(info.get_link_name());
+        Ok(())
+    }
+
+    async fn shutdown(&self) -> anyhow::Result<()> {
+        self.components.write().await.drain();
+        self.blobstores.write().await.drain();
+        Ok(())
+    }
+}
+
+impl Handler<Option<Context>> for ArchiveBlobstoreProvider {
+    #[instrument(level = "debug", skip(self, cx, entries), fields(entries = entries.len()))]
+    async fn pack(
+        &self,
+        cx: Option<Context>,
+        entries: Vec<PackEntry>,
+        archive: ObjectId,
+        format: Option<Format>,
+    ) -> anyhow::Result<Result<Summary, String>> {
+        propagate_trace_for_ctx!(cx);
+        Ok(async {
+            let (config, wrpc) = self.blobstore(cx).await?;
+            let sources = entries
+                .into_iter()
+                .map(|PackEntry { source, path }| {
+                    let object =
+                        BlobstoreObject::new(Arc::clone(&wrpc), source.container, source.object);
+                    (path, object)
+                })
+                .collect();
+            let archive = BlobstoreObject::new(wrpc, archive.container, archive.object);
+            archive::pack(sources, archive, format, &config).await
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "debug", skip(self, cx))]
+    async fn unpack(
+        &self,
+        cx: Option<Context>,
+        archive: ObjectId,
+        format: Option<Format>,
+        container: String,
+        prefix: String,
+    ) -> anyhow::Result<Result<Summary, String>> {
+        propagate_trace_for_ctx!(cx);
+        Ok(async {
+            let (config, wrpc) = self.blobstore(cx).await?;
+            let container = BlobstoreContainer::new(Arc::clone(&wrpc), container);
+            let archive = BlobstoreObject::new(wrpc, archive.container, archive.object);
+            let (summary, _) =
+                archive::unpack(archive, format, Some(container), prefix, &config).await?;
+            anyhow::Ok(summary)
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "debug", skip(self, cx))]
+    async fn list_entries(
+        &self,
+        cx: Option<Context>,
+        archive: ObjectId,
+        format: Option<Format>,
+    ) -> anyhow::Result<Result<Vec<Entry>, String>> {
+        propagate_trace_for_ctx!(cx);
+        Ok(async {
+            let (config, wrpc) = self.blobstore(cx).await?;
+            let archive = BlobstoreObject::new(wrpc, archive.container, archive.object);
+            let (_, entries) =
+                archive::unpack(archive, format, None, String::new(), &config).await?;
+            anyhow::Ok(entries)
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+}
@@ -0,0 +1,367 @@
+//! Streaming access to the objects of a linked blobstore
+
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use std::io;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, bail, Context as _};
+use bytes::{Bytes, BytesMut};
+use futures::{stream, Stream, StreamExt as _, TryStreamExt as _};
+use tokio::io::{AsyncRead, AsyncReadExt as _};
+use tokio::join;
+use tokio::runtime::Handle;
+use tokio::sync::mpsc;
+use tracing::{instrument, warn};
+use wasmcloud_provider_sdk::provider::WrpcClient;
+use wrpc_interface_blobstore::bindings::wrpc::blobstore::{blobstore, types::ObjectId};
+
+/// Size of the chunks objects are written in
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Stream of the bytes of an object
+pub type DataStream = Pin<Box<dyn Stream<Item = io::Result<Bytes>> + Send>>;
+
+/// Future resolving to the status of a [`DataStream`] once it was consumed
+pub type DataStatus = Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send>>;
+
+/// Counter distinguishing the partial objects written by this process
+static PARTIAL_OBJECTS: AtomicU64 = AtomicU64::new(0);
+
+/// An object stored in a linked blobstore
+#[derive(Clone)]
+pub struct BlobstoreObject {
+    wrpc: Arc<WrpcClient>,
+    id: ObjectId,
+}
+
+impl BlobstoreObject {
+    pub fn new(wrpc: Arc<WrpcClient>, container: String, object: String) -> Self {
+        Self {
+            wrpc,
+            id: ObjectId { container, object },
+        }
+    }
+
+    /// Name of the object within its container
+    pub fn name(&self) -> &str {
+        &self.id.object
+    }
+
+    /// Get the size of the object, in bytes
+    #[instrument(level = "trace", skip(self), fields(container = %self.id.container, object = %self.id.object))]
+    pub async fn size(&self) -> anyhow::Result<u64> {
+        let metadata = blobstore::get_object_info(&*self.wrpc, None, &self.id)
+            .await
+            .context("failed to invoke `get-object-info`")?
+            .map_err(|err| anyhow!(err).context("failed to get object info"))?;
+        Ok(metadata.size)
+    }
+
+    /// Stream all `size` bytes of the object. The stream fails if the object ends early.
+    ///
+    /// Blobstore providers differ in whether the end of a range is inclusive, so any bytes
+    /// following the first `size` bytes are dropped. The returned status future must be awaited
+    /// after the stream was consumed
+    pub async fn stream(&self, size: u64) -> anyhow::Result<(DataStream, DataStatus)> {
+        self.stream_range(0, size).await
+    }
+
+    /// Stream the `len` bytes of the object starting at `offset`, see [`Self::stream`]
+    #[instrument(level = "trace", skip(self), fields(container = %self.id.container, object = %self.id.object))]
+    pub async fn stream_range(
+        &self,
+        offset: u64,
+        len: u64,
+    ) -> anyhow::Result<(DataStream, DataStatus)> {
+        let (res, io) = blobstore::get_container_data(
+            &*self.wrpc,
+            None,
+            &self.id,
+            offset,
+            offset.saturating_add(len),
+        )
+        .await
+        .context("failed to invoke `get-container-data`")?;
+        let (data, status) =
+            res.map_err(|err| anyhow!(err).context("failed to get object data"))?;
+        let io = io.map(tokio::spawn);
+        Ok((
+            Box::pin(exact(data, len)),
+            Box::pin(async move {
+                if let Some(io) = io {
+                    io.await
+                        .context("failed to join I/O task")?
+                        .context("failed to complete async I/O")?;
+                }
+                status
+                    .await
+                    .map_err(|err| anyhow!(err).context("failed to read object data"))
+            }),
+        ))
+    }
+
+    /// Read the `len` bytes of the object starting at `offset` into memory
+    pub async fn read(&self, offset: u64, len: u64) -> anyhow::Result<Bytes> {
+        let (data, status) = self.stream_range(offset, len).await?;
+        let chunks: Vec<_> = data
+            .try_collect()
+            .await
+            .context("failed to read object data")?;
+        status.await?;
+        Ok(chunks.concat().into())
+    }
+
+    /// Stream the bytes read from `data` into the object, replacing it. Fails once more than
+    /// `limit` bytes were read, returns the number of bytes written otherwise.
+    ///
+    /// The data is written to a [`PartialObject`] first, so the object is only replaced once
+    /// all data was written and never left partially written
+    pub async fn write(
+        &self,
+        data: impl AsyncRead + Unpin + Send,
+        limit: u64,
+    ) -> anyhow::Result<u64> {
+        let (partial, size) = self.write_partial(data, limit).await?;
+        partial.commit().await?;
+        Ok(size)
+    }
+
+    /// Stream the bytes read from `data` into a [`PartialObject`], which replaces the object
+    /// once committed. See [`Self::write`].
+    ///
+    /// The partial object is deleted if writing fails
+    pub async fn write_partial(
+        &self,
+        data: impl AsyncRead + Unpin + Send,
+        limit: u64,
+    ) -> anyhow::Result<(PartialObject, u64)> {
+        let part = self.partial();
+        // Constructed before writing, so that the partial object is deleted if writing is
+        // cancelled
+        let partial = PartialObject {
+            part: Some(part.clone()),
+            target: self.clone(),
+        };
+        match part.write_data(data, limit).await {
+            Ok(size) => Ok((partial, size)),
+            Err(err) => {
+                partial.discard().await;
+                Err(err)
+            }
+        }
+    }
+
+    /// Returns a uniquely named object next to this object, which data is written to before it
+    /// replaces this object
+    fn partial(&self) -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_nanos());
+        let n = PARTIAL_OBJECTS.fetch_add(1, Ordering::Relaxed);
+        Self::new(
+            Arc::clone(&self.wrpc),
+            self.id.container.clone(),
+            format!(
+                "{}.{nanos:x}-{}-{n}.partial",
+                self.id.object,
+                std::process::id()
+            ),
+        )
+    }
+
+    /// Stream the bytes read from `data` into the object, see [`Self::write`]. The object may be
+    /// left partially written if writing fails
+    #[instrument(level = "trace", skip(self, data), fields(container = %self.id.container, object = %self.id.object))]
+    async fn write_data(
+        &self,
+        data: impl AsyncRead + Unpin + Send,
+        limit: u64,
+    ) -> anyhow::Result<u64> {
+        let (tx, rx) = mpsc::channel::<Bytes>(16);
+        let upload = async {
+            let chunks = stream::unfold(rx, |mut rx| async move {
+                rx.recv().await.map(|chunk| (chunk, rx))
+            });
+            let (res, io) =
+                blobstore::write_container_data(&*self.wrpc, None, &self.id, Box::pin(chunks))
+                    .await
+                    .context("failed to invoke `write-container-data`")?;
+            let status = res.map_err(|err| anyhow!(err).context("failed to write object"))?;
+            if let Some(io) = io.map(tokio::spawn) {
+                io.await
+                    .context("failed to join I/O task")?
+                    .context("failed to complete async I/O")?;
+            }
+            status
+                .await
+                .map_err(|err| anyhow!(err).context("failed to write object data"))
+        };
+        let read = async move {
+            // Read one byte beyond the limit to detect data exceeding it
+            let mut data = data.take(limit.saturating_add(1));
+            let mut size = 0u64;
+            loop {
+                let mut buf = BytesMut::with_capacity(CHUNK_SIZE);
+                if data
+                    .read_buf(&mut buf)
+                    .await
+                    .context("failed to read data")?
+                    == 0
+                {
+                    // Dropping the sender completes the data stream
+                    return Ok(size);
+                }
+                size += buf.len() as u64;
+                if size > limit {
+                    bail!("data exceeds the maximum size of {limit} bytes");
+                }
+                tx.send(buf.freeze())
+                    .await
+                    .map_err(|_| anyhow!("blobstore stopped receiving object data"))?;
+            }
+        };
+        // The upload is completed even if reading fails, so that the object is not written
+        // after this returns
+        let (uploaded, read) = join!(upload, read);
+        uploaded?;
+        read
+    }
+
+    /// Move the object to `dest`, replacing it
+    async fn move_to(&self, dest: &Self) -> anyhow::Result<()> {
+        blobstore::move_object(&*self.wrpc, None, &self.id, &dest.id)
+            .await
+            .context("failed to invoke `move-object`")?
+            .map_err(|err| anyhow!(err).context("failed to move object"))
+    }
+
+    /// Delete the object
+    async fn delete(&self) -> anyhow::Result<()> {
+        blobstore::delete_object(&*self.wrpc, None, &self.id)
+            .await
+            .context("failed to invoke `delete-object`")?
+            .map_err(|err| anyhow!(err).context("failed to delete object"))
+    }
+}
+
+/// An object written in place of another object, which it only replaces once committed. It is
+/// deleted if it is discarded or dropped without being committed
+pub struct PartialObject {
+    /// The partial object, `None` once committed or discarded
+    part: Option<BlobstoreObject>,
+    target: BlobstoreObject,
+}
+
+impl PartialObject {
+    /// Replace the target object with the partial object
+    pub async fn commit(mut self) -> anyhow::Result<()> {
+        let Some(part) = self.part.take() else {
+            return Ok(());
+        };
+        if let Err(err) = part.move_to(&self.target).await {
+            delete_partial(part).await;
+            return Err(err);
+        }
+        Ok(())
+    }
+
+    /// Delete the partial object, leaving the target object unchanged
+    pub async fn discard(mut self) {
+        if let Some(part) = self.part.take() {
+            delete_partial(part).await;
+        }
+    }
+}
+
+impl Drop for PartialObject {
+    fn drop(&mut self) {
+        // Writing was cancelled, delete the partial object in the background
+        if let Some(part) = self.part.take() {
+            if let Ok(handle) = Handle::try_current() {
+                handle.spawn(delete_partial(part));
+            }
+        }
+    }
+}
+
+/// Delete partial object `part`, which is only logged on failure
+async fn delete_partial(part: BlobstoreObject) {
+    if let Err(err) = part.delete().await {
+        warn!(
+            ?err,
+            container = %part.id.container,
+            object = %part.id.object,
+            "failed to delete partial object"
+        );
+    }
+}
+
+/// A container of a linked blobstore
+#[derive(Clone)]
+pub struct BlobstoreContainer {
+    wrpc: Arc<WrpcClient>,
+    name: String,
+}
+
+impl BlobstoreContainer {
+    pub fn new(wrpc: Arc<WrpcClient>, name: String) -> Self {
+        Self { wrpc, name }
+    }
+
+    /// Get the object named `object` in the container
+    pub fn object(&self, object: String) -> BlobstoreObject {
+        BlobstoreObject::new(Arc::clone(&self.wrpc), self.name.clone(), object)
+    }
+}
+
+/// Truncates `data` to `size` bytes, failing if it ends before
+fn exact(
+    data: impl Stream<Item = Bytes> + Send + 'static,
+    size: u64,
+) -> impl Stream<Item = io::Result<Bytes>> + Send + 'static {
+    let mut remaining = size;
+    data.map(Some)
+        .chain(stream::once(async { None }))
+        .filter_map(move |chunk| {
+            let item = match chunk {
+                Some(_) if remaining == 0 => None,
+                Some(mut chunk) => {
+                    let len = usize::try_from(remaining).unwrap_or(usize::MAX);
+                    chunk.truncate(len);
+                    remaining -= chunk.len() as u64;
+                    Some(Ok(chunk))
+                }
+                None if remaining > 0 => Some(Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("object ended {remaining} bytes before its size"),
+                ))),
+                None => None,
+            };
+            async move { item }
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use futures::TryStreamExt as _;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn exact_stream() {
+        let chunks = || stream::iter([Bytes::from("abc"), Bytes::from("def")]);
+        let data: Vec<_> = exact(chunks(), 4).try_collect().await.unwrap();
+        assert_eq!(data, [Bytes::from("abc"), Bytes::from("d")]);
+        let data: Vec<_> = exact(chunks(), 6).try_collect().await.unwrap();
+        assert_eq!(data, [Bytes::from("abc"), Bytes::from("def")]);
+        let err = exact(chunks(), 7)
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
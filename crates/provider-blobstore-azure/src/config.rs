@@ -1,25 +1,71 @@
 //! Configuration for blobstore-azblob capability provider
 //!
-//! See README.md for configuration options using environment variables, aws credentials files,
-//! and EC2 IAM authorizations.
+//! The storage account is set with `STORAGE_ACCOUNT` in the link configuration. The provider
+//! authenticates to it with one of the following methods, selected with `AUTH`:
 //!
+//! - `access_key`: the shared key of the storage account, provided as the `storage_access_key`
+//!   secret (or `STORAGE_ACCESS_KEY` in configuration)
+//! - `sas_token`: a shared access signature, provided as the `storage_sas_token` secret (or
+//!   `STORAGE_SAS_TOKEN` in configuration)
+//! - `managed_identity`: the managed identity of the Azure VM or AKS node the provider runs on
+//! - `workload_identity`: Microsoft Entra Workload ID, configured through the `AZURE_TENANT_ID`,
+//!   `AZURE_CLIENT_ID` and `AZURE_FEDERATED_TOKEN_FILE` environment variables, which are injected
+//!   into pods on AKS
+//!
+//! If `AUTH` is not set, the access key is used if present, otherwise the SAS token.
+
+use core::fmt;
+
+use std::sync::Arc;
 
-use anyhow::Result;
-use serde::Deserialize;
+use anyhow::{bail, Context as _, Result};
 use tracing::warn;
 
+use azure_identity::{
+    TokenCredentialOptions, VirtualMachineManagedIdentityCredential, WorkloadIdentityCredential,
+};
 use azure_storage::StorageCredentials;
 use wasmcloud_provider_sdk::core::secrets::SecretValue;
 use wasmcloud_provider_sdk::LinkConfig;
 
+/// Method used to authenticate with the storage account
+#[derive(Clone, PartialEq, Eq)]
+pub enum Auth {
+    /// Shared key of the storage account
+    AccessKey(String),
+    /// Shared access signature (SAS) token
+    SasToken(String),
+    /// Managed identity of the Azure VM or AKS node the provider runs on
+    ManagedIdentity,
+    /// Microsoft Entra Workload ID, configured through the `AZURE_TENANT_ID`, `AZURE_CLIENT_ID`
+    /// and `AZURE_FEDERATED_TOKEN_FILE` environment variables injected into pods on AKS
+    WorkloadIdentity,
+}
+
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never log the access key or SAS token
+        match self {
+            Self::AccessKey(..) => f.write_str("AccessKey(..)"),
+            Self::SasToken(..) => f.write_str("SasToken(..)"),
+            Self::ManagedIdentity => f.write_str("ManagedIdentity"),
+            Self::WorkloadIdentity => f.write_str("WorkloadIdentity"),
+        }
+    }
+}
+
 /// Configuration for connecting to Azblob.
-#[derive(Clone, Default, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct StorageConfig {
     /// STORAGE_ACCOUNT, can be specified from environment
     pub storage_account: String,
 
-    /// STORAGE_ACCESS_KEY, can be in environment
-    pub storage_access_key: String,
+    /// Authentication method, selected with AUTH. Defaults to the access key if
+    /// STORAGE_ACCESS_KEY is set and to the SAS token otherwise
+    pub auth: Auth,
+
+    /// LIST_PREFIX, optional prefix that object listings are restricted to
+    pub list_prefix: Option<String>,
 }
 
 impl StorageConfig {
@@ -29,30 +75,135 @@ impl StorageConfig {
             config, secrets, ..
         }: &LinkConfig,
     ) -> Result<StorageConfig> {
-        // To support old workflows, accept but warn when getting the storage access key
-        // is not in secrets
-        if secrets.get("storage_access_key").is_none() {
-            warn!("secret [storage_access_key] was not found, checking for [STORAGE_ACCESS_KEY] in configuration. Please prefer using secrets for sensitive values.");
-        }
-        match (
-            config.get("STORAGE_ACCOUNT"),
+        let Some(account) = config.get("STORAGE_ACCOUNT") else {
+            bail!("STORAGE_ACCOUNT must be set");
+        };
+        // To support old workflows, accept but warn when credentials are not in secrets
+        let secret = |name: &str, key: &str| {
             secrets
-                .get("storage_access_key")
+                .get(name)
                 .and_then(SecretValue::as_string)
-                .or_else(|| config.get("STORAGE_ACCESS_KEY").map(String::as_str)),
-        ) {
-            (Some(account), Some(access_key)) => Ok(StorageConfig {
-                storage_account: account.to_string(),
-                storage_access_key: access_key.to_string(),
-            }),
-            _ => Err(anyhow::anyhow!(
-                "STORAGE_ACCOUNT and STORAGE_ACCESS_KEY must be set"
+                .map(ToString::to_string)
+                .or_else(|| {
+                    let value = config.get(key)?;
+                    warn!("secret [{name}] was not found, but [{key}] was present in configuration. Please prefer using secrets for sensitive values.");
+                    Some(value.to_string())
+                })
+        };
+        let auth = match config.get("AUTH").map(String::as_str) {
+            Some("access_key") => Auth::AccessKey(
+                secret("storage_access_key", "STORAGE_ACCESS_KEY")
+                    .context("STORAGE_ACCESS_KEY must be set for `access_key` authentication")?,
+            ),
+            Some("sas_token") => Auth::SasToken(
+                secret("storage_sas_token", "STORAGE_SAS_TOKEN")
+                    .context("STORAGE_SAS_TOKEN must be set for `sas_token` authentication")?,
+            ),
+            Some("managed_identity") => Auth::ManagedIdentity,
+            Some("workload_identity") => Auth::WorkloadIdentity,
+            Some(auth) => bail!(
+                "invalid AUTH `{auth}`, expected one of `access_key`, `sas_token`, `managed_identity` or `workload_identity`"
+            ),
+            None => {
+                if let Some(access_key) = secret("storage_access_key", "STORAGE_ACCESS_KEY") {
+                    Auth::AccessKey(access_key)
+                } else if let Some(sas_token) = secret("storage_sas_token", "STORAGE_SAS_TOKEN") {
+                    Auth::SasToken(sas_token)
+                } else {
+                    bail!("STORAGE_ACCESS_KEY, STORAGE_SAS_TOKEN or AUTH must be set")
+                }
+            }
+        };
+        Ok(StorageConfig {
+            storage_account: account.to_string(),
+            auth,
+            list_prefix: config
+                .get("LIST_PREFIX")
+                .filter(|prefix| !prefix.is_empty())
+                .cloned(),
+        })
+    }
+
+    /// Build the credentials for the storage account with the configured authentication method
+    pub fn credentials(&self) -> Result<StorageCredentials> {
+        match &self.auth {
+            Auth::AccessKey(access_key) => Ok(StorageCredentials::access_key(
+                self.storage_account.clone(),
+                access_key.clone(),
             )),
+            Auth::SasToken(sas_token) => {
+                StorageCredentials::sas_token(sas_token).context("invalid SAS token")
+            }
+            Auth::ManagedIdentity => Ok(StorageCredentials::token_credential(Arc::new(
+                VirtualMachineManagedIdentityCredential::new(TokenCredentialOptions::default()),
+            ))),
+            Auth::WorkloadIdentity => {
+                let credential =
+                    WorkloadIdentityCredential::create(TokenCredentialOptions::default())
+                        .context("failed to create workload identity credential")?;
+                Ok(StorageCredentials::token_credential(Arc::new(credential)))
+            }
         }
     }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn storage_config(config: &[(&str, &str)], secrets: &[(&str, &str)]) -> Result<StorageConfig> {
+        let config = config
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<HashMap<_, _>>();
+        let secrets = secrets
+            .iter()
+            .map(|(k, v)| (k.to_string(), SecretValue::String(v.to_string())))
+            .collect::<HashMap<_, _>>();
+        StorageConfig::from_link_config(&LinkConfig {
+            source_id: "component",
+            target_id: "provider",
+            link_name: "default",
+            config: &config,
+            secrets: &secrets,
+            wit_metadata: (&"wrpc".to_string(), &"blobstore".to_string(), &vec![]),
+        })
+    }
+
+    #[test]
+    fn select_auth() -> Result<()> {
+        let config = storage_config(
+            &[("STORAGE_ACCOUNT", "account")],
+            &[("storage_access_key", "key")],
+        )?;
+        assert_eq!(config.auth, Auth::AccessKey("key".into()));
+
+        let config = storage_config(
+            &[("STORAGE_ACCOUNT", "account")],
+            &[("storage_sas_token", "sv=2022-11-02&sig=abc")],
+        )?;
+        assert_eq!(config.auth, Auth::SasToken("sv=2022-11-02&sig=abc".into()));
+
+        let config = storage_config(
+            &[
+                ("STORAGE_ACCOUNT", "account"),
+                ("AUTH", "workload_identity"),
+            ],
+            &[],
+        )?;
+        assert_eq!(config.auth, Auth::WorkloadIdentity);
 
-    /// Build an access key with the stored storage account and access key
-    pub fn access_key(self) -> StorageCredentials {
-        StorageCredentials::access_key(self.storage_account, self.storage_access_key)
+        assert!(storage_config(&[("STORAGE_ACCOUNT", "account")], &[]).is_err());
+        assert!(storage_config(
+            &[("STORAGE_ACCOUNT", "account"), ("AUTH", "sas_token")],
+            &[("storage_access_key", "key")],
+        )
+        .is_err());
+        assert!(
+            storage_config(&[("STORAGE_ACCOUNT", "account"), ("AUTH", "password")], &[],).is_err()
+        );
+        Ok(())
     }
 }
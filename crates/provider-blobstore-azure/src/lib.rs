@@ -4,19 +4,22 @@ use core::future::Future;
 use core::pin::Pin;
 
 use std::collections::HashMap;
+use std::num::NonZeroU32;
 use std::sync::Arc;
 
 use anyhow::{bail, Context as _, Result};
 use azure_storage::CloudLocation;
 use azure_storage_blobs::prelude::*;
 use bytes::{Bytes, BytesMut};
-use futures::{Stream, StreamExt as _};
-use tokio::sync::{mpsc, RwLock};
-use tokio_stream::wrappers::ReceiverStream;
+use futures::{stream, Stream, StreamExt as _, TryStreamExt as _};
+use tokio::sync::RwLock;
 use tracing::{error, instrument};
+use wasmcloud_provider_sdk::stream::{
+    stream_batches, stream_bytes, DEFAULT_BATCH_SIZE, DEFAULT_BUFFER, DEFAULT_CHUNK_SIZE,
+};
 use wasmcloud_provider_sdk::{
     get_connection, initialize_observability, load_host_data, propagate_trace_for_ctx,
-    run_provider, serve_provider_exports, Context, HostData, LinkConfig, LinkDeleteInfo, Provider,
+    run_provider, serve_exports, Context, HostData, LinkConfig, LinkDeleteInfo, Provider,
 };
 use wrpc_interface_blobstore::bindings::{
     exports::wrpc::blobstore::blobstore::Handler,
@@ -24,10 +27,25 @@ use wrpc_interface_blobstore::bindings::{
     wrpc::blobstore::types::{ContainerMetadata, ObjectId, ObjectMetadata},
 };
 
+use bindings::exports::wasmcloud::blobstore::{tiering, writes};
 use config::StorageConfig;
 
 mod config;
 
+/// Size of the blocks staged when writing a block blob. At most one block is buffered in memory
+/// per write.
+const BLOCK_SIZE: usize = 4 * 1024 * 1024;
+
+mod bindings {
+    wit_bindgen_wrpc::generate!({
+        world: "extensions",
+        with: {
+            "wasmcloud:blobstore/tiering@0.1.0-draft": generate,
+            "wasmcloud:blobstore/writes@0.1.0-draft": generate,
+        }
+    });
+}
+
 /// Blobstore Azblob provider
 ///
 /// This struct will be the target of generated implementations (via wit-provider-bindgen)
@@ -36,6 +54,8 @@ mod config;
 pub struct BlobstoreAzblobProvider {
     /// Per-config storage for Azure connection clients
     config: Arc<RwLock<HashMap<String, BlobServiceClient>>>,
+    /// Per-config prefix that object listings are restricted to
+    list_prefixes: Arc<RwLock<HashMap<String, String>>>,
 }
 
 pub async fn run() -> anyhow::Result<()> {
@@ -58,18 +78,31 @@ impl Provider for BlobstoreAzblobProvider {
             }
         };
 
+        let credentials = match config.credentials() {
+            Ok(v) => v,
+            Err(e) => {
+                error!(error = %e, source_id = %link_config.source_id, auth = ?config.auth, "failed to build storage credentials");
+                return Err(e);
+            }
+        };
         let builder = match &link_config.config.get("CLOUD_LOCATION") {
             Some(custom_location) => ClientBuilder::with_location(
                 CloudLocation::Custom {
                     account: config.storage_account.clone(),
                     uri: custom_location.to_string(),
                 },
-                config.access_key(),
+                credentials,
             ),
-            None => ClientBuilder::new(config.storage_account.clone(), config.access_key()),
+            None => ClientBuilder::new(config.storage_account.clone(), credentials),
         };
         let client = builder.blob_service_client();
 
+        let mut list_prefixes = self.list_prefixes.write().await;
+        if let Some(prefix) = config.list_prefix {
+            list_prefixes.insert(link_config.source_id.to_string(), prefix);
+        } else {
+            list_prefixes.remove(link_config.source_id);
+        }
         let mut update_map = self.config.write().await;
         update_map.insert(link_config.source_id.to_string(), client);
 
@@ -79,11 +112,13 @@ impl Provider for BlobstoreAzblobProvider {
     #[instrument(level = "info", skip_all, fields(source_id = info.get_source_id()))]
     async fn delete_link_as_target(&self, info: impl LinkDeleteInfo) -> anyhow::Result<()> {
         let component_id = info.get_source_id();
+        self.list_prefixes.write().await.remove(component_id);
         self.config.write().await.remove(component_id);
         Ok(())
     }
 
     async fn shutdown(&self) -> anyhow::Result<()> {
+        self.list_prefixes.write().await.drain();
         self.config.write().await.drain();
         Ok(())
     }
@@ -106,7 +141,7 @@ impl BlobstoreAzblobProvider {
         let wrpc = connection
             .get_wrpc_client(connection.provider_key())
             .await?;
-        serve_provider_exports(&wrpc, provider, shutdown, serve)
+        serve_exports!(&wrpc, provider, shutdown, [serve, bindings::serve])
             .await
             .context("failed to serve provider exports")
     }
@@ -125,6 +160,12 @@ impl BlobstoreAzblobProvider {
             )
         }
     }
+
+    /// Get the prefix that object listings of the source of the invocation are restricted to
+    async fn get_list_prefix(&self, context: Option<&Context>) -> Option<String> {
+        let source_id = context.and_then(|Context { component, .. }| component.as_ref())?;
+        self.list_prefixes.read().await.get(source_id).cloned()
+    }
 }
 
 impl Handler<Option<Context>> for BlobstoreAzblobProvider {
@@ -287,38 +328,10 @@ impl Handler<Option<Context>> for BlobstoreAzblobProvider {
                 .await
                 .context("failed to retrieve azure blobstore client")?;
 
-            let mut names = client.container_client(name).list_blobs().into_stream();
-            let (tx, rx) = mpsc::channel(16);
-            anyhow::Ok((
-                Box::pin(ReceiverStream::new(rx)) as Pin<Box<dyn Stream<Item = _> + Send>>,
-                Box::pin(async move {
-                    let mut offset = offset.unwrap_or_default().try_into().unwrap_or(usize::MAX);
-                    let mut limit = limit
-                        .and_then(|limit| limit.try_into().ok())
-                        .unwrap_or(usize::MAX);
-                    while let Some(res) = names.next().await {
-                        let res = res
-                            .context("failed to receive response")
-                            .map_err(|err| format!("{err:#}"))?;
-                        let mut chunk = vec![];
-                        for name in res.blobs.blobs().map(|Blob { name, .. }| name) {
-                            if limit == 0 {
-                                break;
-                            }
-                            if offset > 0 {
-                                offset -= 1;
-                                continue;
-                            }
-                            chunk.push(name.clone());
-                            limit -= 1;
-                        }
-                        if !chunk.is_empty() && tx.send(chunk).await.is_err() {
-                            return Err("stream receiver closed".to_string());
-                        }
-                    }
-                    Ok(())
-                }) as Pin<Box<dyn Future<Output = _> + Send>>,
-            ))
+            let prefix = self.get_list_prefix(cx.as_ref()).await;
+            let names = list_blobs(&client.container_client(name), prefix, limit, offset)
+                .map_ok(|Blob { name, .. }| name);
+            anyhow::Ok(stream_batches(names, DEFAULT_BATCH_SIZE, DEFAULT_BUFFER))
         }
         .await
         .map_err(|err| format!("{err:#}")))
@@ -436,33 +449,20 @@ impl Handler<Option<Context>> for BlobstoreAzblobProvider {
                 .await
                 .context("failed to retrieve azure blobstore client")?;
 
-            let mut stream = client
+            let data = client
                 .container_client(id.container)
                 .blob_client(id.object)
                 .get()
                 .range(start..end)
-                .into_stream();
-
-            let (tx, rx) = mpsc::channel(16);
-            anyhow::Ok((
-                Box::pin(ReceiverStream::new(rx)) as Pin<Box<dyn Stream<Item = _> + Send>>,
-                Box::pin(async move {
-                    async move {
-                        while let Some(res) = stream.next().await {
-                            let res = res.context("failed to receive blob")?;
-                            let buf = res
-                                .data
-                                .collect()
-                                .await
-                                .context("failed to receive bytes")?;
-                            tx.send(buf).await.context("stream receiver closed")?;
-                        }
-                        anyhow::Ok(())
-                    }
-                    .await
-                    .map_err(|err| format!("{err:#}"))
-                }) as Pin<Box<dyn Future<Output = _> + Send>>,
-            ))
+                .into_stream()
+                .then(|res| async move {
+                    res.context("failed to receive blob")?
+                        .data
+                        .collect()
+                        .await
+                        .context("failed to receive bytes")
+                });
+            anyhow::Ok(stream_bytes(data, DEFAULT_CHUNK_SIZE, DEFAULT_BUFFER))
         }
         .await
         .map_err(|err| format!("{err:#}")))
@@ -587,18 +587,287 @@ impl Handler<Option<Context>> for BlobstoreAzblobProvider {
                 .context("failed to retrieve azure blobstore client")?;
             let client = client.container_client(id.container).blob_client(id.object);
             anyhow::Ok(Box::pin(async move {
-                // TODO: Stream data
-                let data: BytesMut = data.collect().await;
-                client
-                    .put_block_blob(data)
+                write_block_blob(&client, data)
                     .await
                     .map(|_| ())
-                    .context("failed to write container data")
-                    .map_err(|err| format!("{err:#}"))?;
-                Ok(())
+                    .map_err(|err| format!("{err:#}"))
+            }) as Pin<Box<dyn Future<Output = _> + Send>>)
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+}
+
+impl tiering::Handler<Option<Context>> for BlobstoreAzblobProvider {
+    #[instrument(level = "trace", skip(self))]
+    async fn get_object_details(
+        &self,
+        cx: Option<Context>,
+        id: tiering::ObjectId,
+    ) -> anyhow::Result<Result<tiering::ObjectDetails, String>> {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let client = self
+                .get_config(cx.as_ref())
+                .await
+                .context("failed to retrieve azure blobstore client")?;
+
+            let info = client
+                .container_client(id.container)
+                .blob_client(id.object)
+                .get_properties()
+                .await
+                .map_err(|e| anyhow::anyhow!(e))?;
+            object_details(&info.blob)
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn list_container_details(
+        &self,
+        cx: Option<Context>,
+        name: String,
+        limit: Option<u64>,
+        offset: Option<u64>,
+    ) -> anyhow::Result<
+        Result<
+            (
+                Pin<Box<dyn Stream<Item = Vec<tiering::ObjectDetails>> + Send>>,
+                Pin<Box<dyn Future<Output = Result<(), String>> + Send>>,
+            ),
+            String,
+        >,
+    > {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let client = self
+                .get_config(cx.as_ref())
+                .await
+                .context("failed to retrieve azure blobstore client")?;
+
+            let prefix = self.get_list_prefix(cx.as_ref()).await;
+            let details = list_blobs(&client.container_client(name), prefix, limit, offset)
+                .and_then(|blob| async move { object_details(&blob) });
+            anyhow::Ok(stream_batches(details, DEFAULT_BATCH_SIZE, DEFAULT_BUFFER))
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn set_tier(
+        &self,
+        cx: Option<Context>,
+        id: tiering::ObjectId,
+        tier: tiering::AccessTier,
+    ) -> anyhow::Result<Result<(), String>> {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let client = self
+                .get_config(cx.as_ref())
+                .await
+                .context("failed to retrieve azure blobstore client")?;
+
+            client
+                .container_client(id.container)
+                .blob_client(id.object)
+                .set_blob_tier(blob_access_tier(tier))
+                .await
+                .context("failed to set blob tier")?;
+            anyhow::Ok(())
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+}
+
+impl writes::Handler<Option<Context>> for BlobstoreAzblobProvider {
+    #[instrument(level = "trace", skip(self, data))]
+    async fn write_data(
+        &self,
+        cx: Option<Context>,
+        id: writes::ObjectId,
+        data: Pin<Box<dyn Stream<Item = Bytes> + Send>>,
+    ) -> anyhow::Result<
+        Result<Pin<Box<dyn Future<Output = Result<writes::WriteResult, String>> + Send>>, String>,
+    > {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let client = self
+                .get_config(cx.as_ref())
+                .await
+                .context("failed to retrieve azure blobstore client")?;
+            let client = client.container_client(id.container).blob_client(id.object);
+            anyhow::Ok(Box::pin(async move {
+                // Blob versions are not reported by the SDK, even if versioning is enabled for
+                // the storage account
+                write_block_blob(&client, data)
+                    .await
+                    .map(|(bytes_written, etag)| writes::WriteResult {
+                        bytes_written,
+                        etag: Some(etag),
+                        version_id: None,
+                    })
+                    .map_err(|err| format!("{err:#}"))
             }) as Pin<Box<dyn Future<Output = _> + Send>>)
         }
         .await
         .map_err(|err| format!("{err:#}")))
     }
 }
+
+/// Maximum number of blobs requested per page of a listing, which is the maximum supported by Azure
+const MAX_LIST_PAGE_SIZE: u32 = 5000;
+
+/// List blobs in the container, optionally restricted to names starting with `prefix`, skipping
+/// the first `offset` blobs and returning at most `limit` blobs.
+///
+/// Pages are requested lazily, following the continuation marker of the previous page, and no
+/// larger than necessary to satisfy `offset` and `limit`
+fn list_blobs(
+    client: &ContainerClient,
+    prefix: Option<String>,
+    limit: Option<u64>,
+    offset: Option<u64>,
+) -> impl Stream<Item = anyhow::Result<Blob>> + Send + 'static {
+    let offset = offset.unwrap_or_default().try_into().unwrap_or(usize::MAX);
+    let limit = limit
+        .and_then(|limit| limit.try_into().ok())
+        .unwrap_or(usize::MAX);
+    let page_size = offset
+        .saturating_add(limit)
+        .clamp(1, MAX_LIST_PAGE_SIZE as usize) as u32;
+    let mut builder = client
+        .list_blobs()
+        .max_results(NonZeroU32::new(page_size).unwrap_or(NonZeroU32::MIN));
+    if let Some(prefix) = prefix {
+        builder = builder.prefix(prefix);
+    }
+    builder
+        .into_stream()
+        .map(|res| {
+            let res = res.context("failed to receive response")?;
+            let blobs = res.blobs.blobs().cloned().collect::<Vec<_>>();
+            anyhow::Ok(stream::iter(blobs.into_iter().map(anyhow::Ok)))
+        })
+        .try_flatten()
+        .skip(offset)
+        .take(limit)
+}
+
+/// Write `data` to a block blob, staging a block every [`BLOCK_SIZE`] bytes and committing the
+/// block list once `data` ends. Data fitting in a single block is uploaded in one request.
+///
+/// Returns the number of bytes written and the ETag of the blob
+async fn write_block_blob(
+    client: &BlobClient,
+    mut data: Pin<Box<dyn Stream<Item = Bytes> + Send>>,
+) -> anyhow::Result<(u64, String)> {
+    let mut buf = BytesMut::new();
+    let mut blocks = Vec::new();
+    let mut size = 0;
+    while let Some(chunk) = data.next().await {
+        size += chunk.len() as u64;
+        buf.extend_from_slice(&chunk);
+        while buf.len() >= BLOCK_SIZE {
+            let block = buf.split_to(BLOCK_SIZE).freeze();
+            blocks.push(stage_block(client, blocks.len(), block).await?);
+        }
+    }
+    if blocks.is_empty() {
+        let res = client
+            .put_block_blob(buf.freeze())
+            .await
+            .context("failed to write container data")?;
+        return Ok((size, res.etag));
+    }
+    if !buf.is_empty() {
+        blocks.push(stage_block(client, blocks.len(), buf.freeze()).await?);
+    }
+    let res = client
+        .put_block_list(BlockList { blocks })
+        .await
+        .context("failed to commit block list")?;
+    Ok((size, res.etag))
+}
+
+/// Stage the block at `index` of a block blob, returning its entry in the block list
+async fn stage_block(
+    client: &BlobClient,
+    index: usize,
+    block: Bytes,
+) -> anyhow::Result<BlobBlockType> {
+    // All block IDs of a blob must have the same length
+    let id = BlockId::new(format!("{index:016x}"));
+    client
+        .put_block(id.clone(), block)
+        .await
+        .with_context(|| format!("failed to stage block {index}"))?;
+    Ok(BlobBlockType::new_uncommitted(id))
+}
+
+/// Convert blob properties to [`tiering::ObjectDetails`]
+fn object_details(blob: &Blob) -> anyhow::Result<tiering::ObjectDetails> {
+    let created_at = blob
+        .properties
+        .creation_time
+        .unix_timestamp()
+        .try_into()
+        .context("failed to convert created_at date to u64")?;
+    let last_modified = blob
+        .properties
+        .last_modified
+        .unix_timestamp()
+        .try_into()
+        .context("failed to convert last_modified date to u64")?;
+    Ok(tiering::ObjectDetails {
+        name: blob.name.clone(),
+        created_at,
+        last_modified,
+        size: blob.properties.content_length,
+        access_tier: blob.properties.access_tier.as_ref().and_then(access_tier),
+    })
+}
+
+/// Convert an Azure access tier to [`tiering::AccessTier`]. Tiers not representable in the
+/// interface (e.g. premium tiers) are reported as unknown
+fn access_tier(tier: &AccessTier) -> Option<tiering::AccessTier> {
+    match tier {
+        AccessTier::Hot => Some(tiering::AccessTier::Hot),
+        AccessTier::Cool => Some(tiering::AccessTier::Cool),
+        AccessTier::Archive => Some(tiering::AccessTier::Archive),
+        _ => None,
+    }
+}
+
+/// Convert a [`tiering::AccessTier`] to the Azure access tier
+fn blob_access_tier(tier: tiering::AccessTier) -> AccessTier {
+    match tier {
+        tiering::AccessTier::Hot => AccessTier::Hot,
+        tiering::AccessTier::Cool => AccessTier::Cool,
+        tiering::AccessTier::Archive => AccessTier::Archive,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn convert_access_tier() {
+        assert!(matches!(
+            access_tier(&blob_access_tier(tiering::AccessTier::Hot)),
+            Some(tiering::AccessTier::Hot)
+        ));
+        assert!(matches!(
+            access_tier(&blob_access_tier(tiering::AccessTier::Cool)),
+            Some(tiering::AccessTier::Cool)
+        ));
+        assert!(matches!(
+            access_tier(&blob_access_tier(tiering::AccessTier::Archive)),
+            Some(tiering::AccessTier::Archive)
+        ));
+    }
+}
@@ -933,6 +933,92 @@ async fn test_get_container_data() -> Result<()> {
     Ok(())
 }
 
+#[ignore]
+#[tokio::test]
+async fn test_write_container_data() -> Result<()> {
+    let test_suite_name = "test-write-container-data";
+    let test_container_name = test_suite_name;
+    let lattice_name = "default";
+    let test_blob_name = "test.blob";
+    // Larger than a single staged block, and not a multiple of the block size
+    let test_blob_body = Bytes::from(
+        (0..9 * 1024 * 1024 + 17)
+            .map(|i| (i % 251) as u8)
+            .collect::<Vec<_>>(),
+    );
+
+    let env = TestEnv::new(lattice_name, test_suite_name)
+        .await
+        .with_context(|| format!("should setup the test environment @ line {}", line!()))?;
+
+    // Start the provider and things a second to settle
+    let provider_handle = env.start_provider().await?;
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let wrpc = env.wrpc_client().await?;
+
+    let container = env
+        .azurite_blob_client()
+        .container_client(test_container_name);
+    container.create().await.with_context(|| {
+        format!(
+            "should create container '{test_container_name}' @ line {}",
+            line!()
+        )
+    })?;
+
+    let test_object = ObjectId {
+        container: test_container_name.to_string(),
+        object: test_blob_name.to_string(),
+    };
+    let chunks = test_blob_body
+        .chunks(64 * 1024)
+        .map(Bytes::copy_from_slice)
+        .collect::<Vec<_>>();
+    // Invoke `wrpc:blobstore/blobstore.write-container-data`
+    let (Ok(written), io) = tokio::time::timeout(
+        Duration::from_secs(10),
+        blobstore::write_container_data(
+            &wrpc,
+            env.wrpc_context(),
+            &test_object,
+            Box::pin(stream::iter(chunks)),
+        ),
+    )
+    .await??
+    else {
+        panic!("did not get results")
+    };
+    try_join! {
+        async {
+            if let Some(io) = io {
+                io.await.context("failed to complete async I/O")
+            } else {
+                Ok(())
+            }
+        },
+        async { written.await.map_err(anyhow::Error::msg) },
+    }?;
+
+    let stored_data = container
+        .blob_client(test_blob_name)
+        .get_content()
+        .await
+        .with_context(|| {
+            format!(
+                "should read blob '{test_blob_name}' in '{test_container_name}' @ line {}",
+                line!()
+            )
+        })?;
+    assert_eq!(stored_data.len(), test_blob_body.len());
+    assert!(stored_data == test_blob_body);
+
+    // Shutdown
+    provider_handle.abort();
+
+    Ok(())
+}
+
 #[ignore]
 #[tokio::test]
 async fn test_get_object_info() -> Result<()> {
@@ -0,0 +1,441 @@
+//! Layouts of object files within container directories
+
+use core::fmt;
+
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{bail, Context as _};
+use path_clean::PathClean;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+use tokio::fs;
+use tracing::{debug, info, warn};
+
+use crate::{split_version, METADATA_DIR, VERSIONS_DIR};
+
+/// Name of the file within the root, which records the layout of all containers below it
+pub(crate) const LAYOUT_FILE: &str = ".wasmcloud-layout";
+
+/// Name of the directory within a container, which stores objects while they are migrated
+/// between layouts
+const MIGRATION_DIR: &str = ".wasmcloud-migration";
+
+/// Default number of shard directory levels of the sharded layout
+const DEFAULT_SHARD_DEPTH: u8 = 2;
+
+/// Maximum number of shard directory levels of the sharded layout
+const MAX_SHARD_DEPTH: u8 = 4;
+
+/// Layout of object files within container directories
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case", tag = "layout")]
+pub(crate) enum Layout {
+    /// Objects are stored at their name within the container directory
+    #[default]
+    Flat,
+    /// Objects are stored below `depth` levels of shard directories. Each level is named by the
+    /// next byte, in hexadecimal, of the SHA-256 hash of the first component of the object name,
+    /// so that objects sharing a directory prefix are stored in the same shard
+    Sharded { depth: u8 },
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Flat => write!(f, "flat"),
+            Self::Sharded { depth } => write!(f, "sharded (depth {depth})"),
+        }
+    }
+}
+
+impl Layout {
+    /// Parse the layout from the `LAYOUT` and `SHARD_DEPTH` link configuration values. Keys are
+    /// matched case-insensitively
+    pub(crate) fn from_config(config: &HashMap<String, String>) -> anyhow::Result<Self> {
+        let value = |key: &str| {
+            config
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, v)| v.as_str())
+        };
+        match value("LAYOUT").map(str::to_ascii_lowercase).as_deref() {
+            None | Some("flat") => Ok(Self::Flat),
+            Some("sharded") => {
+                let depth = value("SHARD_DEPTH")
+                    .map(|depth| {
+                        depth
+                            .parse()
+                            .with_context(|| format!("invalid `SHARD_DEPTH` value `{depth}`"))
+                    })
+                    .transpose()?
+                    .unwrap_or(DEFAULT_SHARD_DEPTH);
+                if depth == 0 || depth > MAX_SHARD_DEPTH {
+                    bail!("`SHARD_DEPTH` must be between 1 and {MAX_SHARD_DEPTH}")
+                }
+                Ok(Self::Sharded { depth })
+            }
+            Some(layout) => bail!("invalid layout `{layout}`, expected `flat` or `sharded`"),
+        }
+    }
+
+    /// Path of the shard directory storing `object`, relative to the container
+    pub(crate) fn shard(&self, object: impl AsRef<Path>) -> PathBuf {
+        let Self::Sharded { depth } = *self else {
+            return PathBuf::new();
+        };
+        let object = object.as_ref().clean();
+        let key = match object.components().next() {
+            Some(Component::Normal(key)) => key.to_string_lossy(),
+            _ => object.to_string_lossy(),
+        };
+        Sha256::digest(key.as_bytes())
+            .iter()
+            .take(depth.into())
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
+    /// Returns the directories within `base`, which contain the top-level entries of objects
+    pub(crate) async fn object_dirs(&self, base: &Path) -> anyhow::Result<Vec<PathBuf>> {
+        let Self::Sharded { depth } = *self else {
+            return Ok(vec![base.to_path_buf()]);
+        };
+        let mut dirs = vec![base.to_path_buf()];
+        for _ in 0..depth {
+            let mut next = Vec::new();
+            for dir in dirs {
+                let mut entries = fs::read_dir(&dir)
+                    .await
+                    .with_context(|| format!("failed to read directory `{}`", dir.display()))?;
+                while let Some(entry) = entries
+                    .next_entry()
+                    .await
+                    .context("failed to lookup directory entry")?
+                {
+                    let name = entry.file_name();
+                    let is_shard = name.len() == 2
+                        && name
+                            .to_string_lossy()
+                            .bytes()
+                            .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b));
+                    if is_shard
+                        && entry
+                            .file_type()
+                            .await
+                            .context("failed to lookup directory entry type")?
+                            .is_dir()
+                    {
+                        next.push(entry.path());
+                    }
+                }
+            }
+            dirs = next;
+        }
+        Ok(dirs)
+    }
+
+    /// Read the layout recorded in `root`, if any
+    pub(crate) async fn read(root: &Path) -> anyhow::Result<Option<Self>> {
+        let path = root.join(LAYOUT_FILE);
+        match fs::read(&path).await {
+            Ok(buf) => serde_json::from_slice(&buf)
+                .map(Some)
+                .with_context(|| format!("failed to parse layout at `{}`", path.display())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(anyhow::Error::from(err)
+                .context(format!("failed to read layout at `{}`", path.display()))),
+        }
+    }
+
+    /// Record the layout in `root`
+    async fn write(&self, root: &Path) -> anyhow::Result<()> {
+        let path = root.join(LAYOUT_FILE);
+        let buf = serde_json::to_vec(self).context("failed to encode layout")?;
+        fs::write(&path, buf)
+            .await
+            .with_context(|| format!("failed to write layout at `{}`", path.display()))
+    }
+}
+
+/// Ensure that the containers below `root` use `layout`. Roots without a recorded layout use the
+/// flat layout. If the containers use a different layout, they are migrated if `migrate` is set,
+/// otherwise an error is returned
+pub(crate) async fn ensure_layout(
+    root: &Path,
+    layout: Layout,
+    migrate: bool,
+) -> anyhow::Result<()> {
+    let current = Layout::read(root).await?.unwrap_or_default();
+    if current == layout {
+        return Ok(());
+    }
+
+    let mut containers = Vec::new();
+    let mut entries = fs::read_dir(root)
+        .await
+        .with_context(|| format!("failed to read root `{}`", root.display()))?;
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .context("failed to lookup root entry")?
+    {
+        if entry
+            .file_type()
+            .await
+            .context("failed to lookup root entry type")?
+            .is_dir()
+        {
+            containers.push(entry.path());
+        }
+    }
+    if containers.is_empty() {
+        debug!(root = ?root.display(), %layout, "record layout of empty root");
+        return layout.write(root).await;
+    }
+    if !migrate {
+        bail!(
+            "containers below `{}` use the {current} layout, set `MIGRATE_LAYOUT=true` to migrate them to the {layout} layout",
+            root.display()
+        )
+    }
+    for container in containers {
+        info!(container = ?container.display(), from = %current, to = %layout, "migrate container layout");
+        migrate_tree(&container, current, layout, |name| name)
+            .await
+            .with_context(|| format!("failed to migrate container `{}`", container.display()))?;
+        let metadata = container.join(METADATA_DIR);
+        if fs::try_exists(&metadata).await.unwrap_or_default() {
+            migrate_tree(&metadata, current, layout, |name| {
+                name.strip_suffix(".json").unwrap_or(name)
+            })
+            .await
+            .with_context(|| {
+                format!(
+                    "failed to migrate object attributes in `{}`",
+                    metadata.display()
+                )
+            })?;
+        }
+        let versions = container.join(VERSIONS_DIR);
+        if fs::try_exists(&versions).await.unwrap_or_default() {
+            migrate_tree(&versions, current, layout, |name| {
+                split_version(name).map_or(name, |(name, _)| name)
+            })
+            .await
+            .with_context(|| {
+                format!(
+                    "failed to migrate object versions in `{}`",
+                    versions.display()
+                )
+            })?;
+        }
+    }
+    layout.write(root).await
+}
+
+/// Move the files within `base` from the `from` to the `to` layout. The object name of each file
+/// is returned by `object` for its path relative to its shard directory.
+///
+/// Files are first moved to a staging directory, so that files stored in the new layout never
+/// collide with files which are yet to be moved.
+async fn migrate_tree(
+    base: &Path,
+    from: Layout,
+    to: Layout,
+    object: impl Fn(&str) -> &str,
+) -> anyhow::Result<()> {
+    let staging = base.join(MIGRATION_DIR);
+    if fs::try_exists(&staging).await.unwrap_or_default() {
+        bail!(
+            "a previous layout migration was interrupted, move the contents of `{}` into `{}` to recover",
+            staging.display(),
+            base.display()
+        )
+    }
+
+    // Collect all files and directories stored in the current layout
+    let mut files = Vec::new();
+    let mut dirs = Vec::new();
+    for shard in from.object_dirs(base).await? {
+        let mut pending = vec![shard.clone()];
+        while let Some(dir) = pending.pop() {
+            let mut entries = fs::read_dir(&dir)
+                .await
+                .with_context(|| format!("failed to read directory `{}`", dir.display()))?;
+            while let Some(entry) = entries
+                .next_entry()
+                .await
+                .context("failed to lookup directory entry")?
+            {
+                let path = entry.path();
+                if dir == base
+                    && (entry.file_name() == METADATA_DIR
+                        || entry.file_name() == VERSIONS_DIR
+                        || entry.file_name() == MIGRATION_DIR)
+                {
+                    continue;
+                }
+                if entry
+                    .file_type()
+                    .await
+                    .context("failed to lookup directory entry type")?
+                    .is_dir()
+                {
+                    pending.push(path.clone());
+                    dirs.push(path);
+                } else {
+                    let name = path
+                        .strip_prefix(&shard)
+                        .context("file is not stored below its shard")?
+                        .to_path_buf();
+                    files.push((path, name));
+                }
+            }
+        }
+        if shard != base {
+            dirs.push(shard);
+        }
+    }
+
+    for (path, name) in files {
+        let file = name.to_string_lossy();
+        let dest = staging.join(to.shard(object(&file))).join(&name);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .context("failed to create staging directory")?;
+        }
+        fs::rename(&path, &dest).await.with_context(|| {
+            format!(
+                "failed to move `{}` to `{}`",
+                path.display(),
+                dest.display()
+            )
+        })?;
+    }
+
+    // Remove the emptied directories of the previous layout, deepest first
+    dirs.sort_by_key(|dir| std::cmp::Reverse(dir.components().count()));
+    dirs.dedup();
+    for dir in dirs {
+        if let Err(err) = fs::remove_dir(&dir).await {
+            warn!(?err, dir = ?dir.display(), "failed to remove directory after layout migration");
+        }
+    }
+
+    let mut entries = fs::read_dir(&staging)
+        .await
+        .context("failed to read staging directory")?;
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .context("failed to lookup staging directory entry")?
+    {
+        let dest = base.join(entry.file_name());
+        fs::rename(entry.path(), &dest)
+            .await
+            .with_context(|| format!("failed to move staged entry to `{}`", dest.display()))?;
+    }
+    fs::remove_dir(&staging)
+        .await
+        .context("failed to remove staging directory")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_layout() {
+        assert_eq!(Layout::from_config(&HashMap::new()).unwrap(), Layout::Flat);
+        assert_eq!(
+            Layout::from_config(&HashMap::from([(
+                "layout".to_string(),
+                "Sharded".to_string()
+            )]))
+            .unwrap(),
+            Layout::Sharded { depth: 2 }
+        );
+        assert_eq!(
+            Layout::from_config(&HashMap::from([
+                ("LAYOUT".to_string(), "sharded".to_string()),
+                ("SHARD_DEPTH".to_string(), "3".to_string()),
+            ]))
+            .unwrap(),
+            Layout::Sharded { depth: 3 }
+        );
+        assert!(Layout::from_config(&HashMap::from([
+            ("LAYOUT".to_string(), "sharded".to_string()),
+            ("SHARD_DEPTH".to_string(), "0".to_string()),
+        ]))
+        .is_err());
+        assert!(Layout::from_config(&HashMap::from([(
+            "LAYOUT".to_string(),
+            "nested".to_string()
+        )]))
+        .is_err());
+    }
+
+    #[test]
+    fn shard_by_first_component() {
+        let layout = Layout::Sharded { depth: 2 };
+        assert_eq!(Layout::Flat.shard("a/b.txt"), PathBuf::new());
+        assert_eq!(layout.shard("a/b.txt"), layout.shard("a/c.txt"));
+        assert_eq!(layout.shard("./a/b.txt"), layout.shard("a"));
+        assert_eq!(layout.shard("a").components().count(), 2);
+    }
+
+    #[tokio::test]
+    async fn migrate_layout() {
+        let root = tempfile::tempdir().unwrap();
+        let container = root.path().join("container");
+        fs::create_dir_all(container.join("dir")).await.unwrap();
+        fs::create_dir_all(container.join(METADATA_DIR))
+            .await
+            .unwrap();
+        fs::write(container.join("a.txt"), "a").await.unwrap();
+        fs::write(container.join("dir/b.txt"), "b").await.unwrap();
+        fs::write(container.join(METADATA_DIR).join("a.txt.json"), "{}")
+            .await
+            .unwrap();
+
+        let sharded = Layout::Sharded { depth: 2 };
+        assert!(ensure_layout(root.path(), sharded, false).await.is_err());
+        ensure_layout(root.path(), sharded, true).await.unwrap();
+        assert_eq!(Layout::read(root.path()).await.unwrap(), Some(sharded));
+        let a = container.join(sharded.shard("a.txt")).join("a.txt");
+        assert_eq!(fs::read_to_string(a).await.unwrap(), "a");
+        let b = container.join(sharded.shard("dir")).join("dir/b.txt");
+        assert_eq!(fs::read_to_string(b).await.unwrap(), "b");
+        let sidecar = container
+            .join(METADATA_DIR)
+            .join(sharded.shard("a.txt"))
+            .join("a.txt.json");
+        assert!(sidecar.exists());
+        assert!(!container.join("a.txt").exists());
+        assert!(!container.join("dir").exists());
+
+        ensure_layout(root.path(), Layout::Flat, true)
+            .await
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(container.join("a.txt")).await.unwrap(),
+            "a"
+        );
+        assert_eq!(
+            fs::read_to_string(container.join("dir/b.txt"))
+                .await
+                .unwrap(),
+            "b"
+        );
+        assert!(container.join(METADATA_DIR).join("a.txt.json").exists());
+        let mut entries = fs::read_dir(&container).await.unwrap();
+        let mut names = Vec::new();
+        while let Some(entry) = entries.next_entry().await.unwrap() {
+            names.push(entry.file_name().to_string_lossy().to_string());
+        }
+        names.sort();
+        assert_eq!(names, [METADATA_DIR, "a.txt", "dir"]);
+    }
+}
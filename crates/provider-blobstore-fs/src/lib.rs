@@ -6,25 +6,31 @@ use core::future::Future;
 use core::pin::Pin;
 use core::time::Duration;
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io::SeekFrom;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
 use std::time::SystemTime;
 
 use anyhow::{anyhow, bail, Context as _};
 use bytes::Bytes;
-use futures::{Stream, StreamExt as _, TryStreamExt as _};
+use futures::{future, stream, Stream, StreamExt as _, TryStreamExt as _};
 use path_clean::PathClean;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
 use tokio::fs::{self, create_dir_all, File};
-use tokio::io::{self, AsyncReadExt as _, AsyncSeekExt as _};
-use tokio::sync::{mpsc, RwLock};
-use tokio_stream::wrappers::{ReadDirStream, ReceiverStream};
-use tokio_util::io::{ReaderStream, StreamReader};
+use tokio::io::{AsyncReadExt as _, AsyncSeekExt as _, AsyncWriteExt as _};
+use tokio::sync::{OwnedMutexGuard, RwLock};
+use tokio_stream::wrappers::ReadDirStream;
+use tokio_util::io::ReaderStream;
 use tracing::{debug, error, info, instrument, trace};
+use wasmcloud_provider_sdk::stream::{
+    stream_batches, stream_bytes, DEFAULT_BATCH_SIZE, DEFAULT_BUFFER, DEFAULT_CHUNK_SIZE,
+};
 use wasmcloud_provider_sdk::{
-    get_connection, initialize_observability, propagate_trace_for_ctx, run_provider,
-    serve_provider_exports, Context, LinkConfig, LinkDeleteInfo, Provider,
+    get_connection, initialize_observability, propagate_trace_for_ctx, run_provider, serve_exports,
+    Context, LinkConfig, LinkDeleteInfo, LinkHealthCheck, LinkHealthStatus, Provider,
 };
 use wrpc_interface_blobstore::bindings::{
     exports::wrpc::blobstore::blobstore::Handler,
@@ -32,9 +38,183 @@ use wrpc_interface_blobstore::bindings::{
     wrpc::blobstore::types::{ContainerMetadata, ObjectId, ObjectMetadata},
 };
 
+use bindings::exports::wasmcloud::blobstore::{integrity, listing, metadata, versioning, writes};
+
+mod bindings {
+    wit_bindgen_wrpc::generate!({
+        world: "extensions",
+        with: {
+            "wasmcloud:blobstore/integrity@0.1.0-draft": generate,
+            "wasmcloud:blobstore/listing@0.1.0-draft": generate,
+            "wasmcloud:blobstore/metadata@0.1.0-draft": generate,
+            "wasmcloud:blobstore/versioning@0.1.0-draft": generate,
+            "wasmcloud:blobstore/writes@0.1.0-draft": generate,
+        }
+    });
+}
+
+mod layout;
+
+use layout::{Layout, LAYOUT_FILE};
+
+/// Name of the directory within each container, which stores object attributes and checksums in
+/// sidecar files
+const METADATA_DIR: &str = ".wasmcloud-metadata";
+
+/// Name of the directory within each container, which stores the previous versions of objects if
+/// versioning is enabled with `VERSIONS`
+const VERSIONS_DIR: &str = ".wasmcloud-versions";
+
+/// Prefix of the version IDs of objects, followed by the generation of the version
+const GENERATION_PREFIX: &str = "gen-";
+
+/// Counter used to name temporary files written before atomically replacing objects
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Locks serializing the mutations of versioned objects, indexed by object path
+static OBJECT_LOCKS: Mutex<BTreeMap<PathBuf, Arc<tokio::sync::Mutex<()>>>> =
+    Mutex::new(BTreeMap::new());
+
+/// Lock on an object, held while its versions, generation or attributes are updated.
+/// The entry of the object in [`OBJECT_LOCKS`] is removed once no task holds or awaits the lock
+struct ObjectLock {
+    path: PathBuf,
+    guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl ObjectLock {
+    /// Lock the object at `path`
+    async fn acquire(path: &Path) -> Self {
+        let lock = {
+            let mut locks = OBJECT_LOCKS.lock().unwrap_or_else(PoisonError::into_inner);
+            Arc::clone(locks.entry(path.to_path_buf()).or_default())
+        };
+        let guard = lock.lock_owned().await;
+        Self {
+            path: path.to_path_buf(),
+            guard: Some(guard),
+        }
+    }
+}
+
+impl Drop for ObjectLock {
+    fn drop(&mut self) {
+        let mut locks = OBJECT_LOCKS.lock().unwrap_or_else(PoisonError::into_inner);
+        drop(self.guard.take());
+        if locks
+            .get(&self.path)
+            .is_some_and(|lock| Arc::strong_count(lock) == 1)
+        {
+            locks.remove(&self.path);
+        }
+    }
+}
+
+/// Attributes of an object, stored as JSON in a sidecar file below [`METADATA_DIR`]
+#[derive(Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+struct ObjectAttributes {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content_type: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    metadata: BTreeMap<String, String>,
+    /// Hex-encoded SHA-256 checksum of the object computed on write, if enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sha256: Option<String>,
+    /// Generation of the object, if it was written while versioning was enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    generation: Option<u64>,
+}
+
+impl From<metadata::ObjectAttributes> for ObjectAttributes {
+    fn from(
+        metadata::ObjectAttributes {
+            content_type,
+            metadata,
+        }: metadata::ObjectAttributes,
+    ) -> Self {
+        Self {
+            content_type,
+            metadata: metadata.into_iter().collect(),
+            sha256: None,
+            generation: None,
+        }
+    }
+}
+
+impl From<ObjectAttributes> for metadata::ObjectAttributes {
+    fn from(
+        ObjectAttributes {
+            content_type,
+            metadata,
+            ..
+        }: ObjectAttributes,
+    ) -> Self {
+        Self {
+            content_type,
+            metadata: metadata.into_iter().collect(),
+        }
+    }
+}
+
+/// Storage quota of a link, configured with `MAX_BYTES`
+#[derive(Debug, Clone)]
+struct Quota {
+    max_bytes: u64,
+    /// Number of bytes stored below the root, shared by all links using the same root
+    used: Arc<AtomicU64>,
+}
+
+impl Quota {
+    /// Fail if the quota is exhausted, after `freed` bytes are released
+    fn check(&self, freed: u64) -> anyhow::Result<()> {
+        let used = self.used.load(Ordering::Relaxed).saturating_sub(freed);
+        if used >= self.max_bytes {
+            bail!(
+                "storage quota exceeded: {used} of {} bytes in use",
+                self.max_bytes
+            )
+        }
+        Ok(())
+    }
+
+    /// Reserve `n` bytes, failing if the quota would be exceeded
+    fn reserve(&self, n: u64) -> anyhow::Result<()> {
+        if let Err(used) = self
+            .used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                used.checked_add(n).filter(|used| *used <= self.max_bytes)
+            })
+        {
+            bail!(
+                "storage quota exceeded: storing {n} more bytes would exceed the limit of {} bytes, {used} bytes in use",
+                self.max_bytes
+            )
+        }
+        Ok(())
+    }
+
+    /// Release `n` previously used bytes
+    fn release(&self, n: u64) {
+        let _ = self
+            .used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                Some(used.saturating_sub(n))
+            });
+    }
+}
+
 #[derive(Default, Debug, Clone)]
 struct FsProviderConfig {
     root: Arc<PathBuf>,
+    quota: Option<Quota>,
+    layout: Layout,
+    /// Whether SHA-256 checksums of objects are computed on write, configured with `CHECKSUMS`
+    checksums: bool,
+    /// Number of previous versions kept when an object is replaced, configured with `VERSIONS`.
+    /// Versioning is disabled if 0
+    versions: u32,
+    /// Name of the link this configuration was received on
+    link_name: String,
 }
 
 /// fs capability provider implementation
@@ -62,7 +242,7 @@ impl FsProvider {
         let wrpc = connection
             .get_wrpc_client(connection.provider_key())
             .await?;
-        serve_provider_exports(&wrpc, provider, shutdown, serve)
+        serve_exports!(&wrpc, provider, shutdown, [serve, bindings::serve])
             .await
             .context("failed to serve provider exports")
     }
@@ -98,21 +278,652 @@ fn resolve_subpath(root: &Path, path: impl AsRef<Path>) -> Result<PathBuf, std::
     Ok(joined)
 }
 
+/// Resolve the path of an object within a container using `layout`, ensuring that the object is
+/// not stored in [`METADATA_DIR`] or [`VERSIONS_DIR`]
+fn resolve_object(
+    layout: Layout,
+    container: &Path,
+    object: impl AsRef<Path>,
+) -> anyhow::Result<PathBuf> {
+    let shard = container.join(layout.shard(&object));
+    let path = resolve_subpath(&shard, object).context("failed to resolve subpath")?;
+    for dir in [METADATA_DIR, VERSIONS_DIR] {
+        if path.starts_with(container.join(dir)) {
+            bail!("objects cannot be stored in `{dir}`")
+        }
+    }
+    Ok(path)
+}
+
+/// Resolve the path of the sidecar file storing the attributes of an object within a container
+fn resolve_sidecar(
+    layout: Layout,
+    container: &Path,
+    object: impl AsRef<Path>,
+) -> anyhow::Result<PathBuf> {
+    let shard = container.join(METADATA_DIR).join(layout.shard(&object));
+    let mut path = resolve_subpath(&shard, object)
+        .context("failed to resolve sidecar subpath")?
+        .into_os_string();
+    path.push(".json");
+    Ok(path.into())
+}
+
+/// Resolve the base path of the previous versions of an object within a container. Each version
+/// is stored at the base path suffixed by `.gen-N`, where `N` is the generation of the version
+fn resolve_versions(
+    layout: Layout,
+    container: &Path,
+    object: impl AsRef<Path>,
+) -> anyhow::Result<PathBuf> {
+    let shard = container.join(VERSIONS_DIR).join(layout.shard(&object));
+    resolve_subpath(&shard, object).context("failed to resolve versions subpath")
+}
+
+/// Path of the previous version of an object with `generation`, below its versions `base`
+fn version_path(base: &Path, generation: u64) -> PathBuf {
+    let mut path = base.as_os_str().to_os_string();
+    path.push(format!(".{}", version_id(generation)));
+    path.into()
+}
+
+/// Path of a new temporary file below the versions `base` of an object, which is written before
+/// atomically replacing the object
+fn temp_path(base: &Path) -> PathBuf {
+    let n = TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let mut path = base.as_os_str().to_os_string();
+    path.push(format!(".tmp-{}-{n}", std::process::id()));
+    path.into()
+}
+
+/// Format the version ID of the version of an object with `generation`
+fn version_id(generation: u64) -> String {
+    format!("{GENERATION_PREFIX}{generation}")
+}
+
+/// Parse the generation from a version ID
+fn parse_version_id(version_id: &str) -> anyhow::Result<u64> {
+    version_id
+        .strip_prefix(GENERATION_PREFIX)
+        .and_then(|generation| generation.parse().ok())
+        .with_context(|| {
+            format!("invalid version ID `{version_id}`, expected `{GENERATION_PREFIX}<N>`")
+        })
+}
+
+/// Split the name of a file storing a previous version of an object into the object name and the
+/// generation of the version
+pub(crate) fn split_version(name: &str) -> Option<(&str, u64)> {
+    let (object, generation) = name.rsplit_once(&format!(".{GENERATION_PREFIX}"))?;
+    Some((object, generation.parse().ok()?))
+}
+
+/// List the generations of the previous versions of an object stored below `base`, newest first
+async fn list_generations(base: &Path) -> anyhow::Result<Vec<u64>> {
+    let (Some(dir), Some(object)) = (base.parent(), base.file_name()) else {
+        return Ok(Vec::new());
+    };
+    let object = object.to_string_lossy();
+    let mut entries = match fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(
+                anyhow!(err).context(format!("failed to read directory `{}`", dir.display()))
+            )
+        }
+    };
+    let mut generations = Vec::new();
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .context("failed to lookup directory entry")?
+    {
+        let name = entry.file_name();
+        if let Some((name, generation)) = split_version(&name.to_string_lossy()) {
+            if name == object {
+                generations.push(generation);
+            }
+        }
+    }
+    generations.sort_unstable_by(|a, b| b.cmp(a));
+    Ok(generations)
+}
+
+/// Generation of the current version of an object with `attributes`, given the `generations` of
+/// its previous versions, newest first. Objects written while versioning was disabled follow their
+/// newest previous version
+fn current_generation(attributes: &ObjectAttributes, generations: &[u64]) -> u64 {
+    attributes
+        .generation
+        .unwrap_or_else(|| generations.first().map_or(1, |generation| generation + 1))
+}
+
+/// Atomically replace the object at `path` by the file at `tmp`, keeping the replaced object as
+/// its newest previous version below `base` and removing all but the `keep` newest versions.
+/// The object must be locked with [`ObjectLock`].
+///
+/// Returns the generation of the new object and the number of bytes freed by removed versions
+async fn replace_versioned(
+    path: &Path,
+    sidecar: &Path,
+    base: &Path,
+    tmp: &Path,
+    keep: u32,
+    attributes: ObjectAttributes,
+) -> anyhow::Result<(u64, u64)> {
+    let mut generations = list_generations(base).await?;
+    let previous = read_attributes(sidecar).await?;
+    let generation = if fs::try_exists(path)
+        .await
+        .context("failed to check if path exists")?
+    {
+        let current = current_generation(&previous, &generations);
+        let backup = version_path(base, current);
+        debug!(path = ?path.display(), backup = ?backup.display(), "keep previous version");
+        // The replaced object stays in place until it is atomically replaced below
+        if let Err(err) = fs::hard_link(path, &backup).await {
+            debug!(?err, "failed to link previous version, copying it instead");
+            fs::copy(path, &backup).await.with_context(|| {
+                format!("failed to copy previous version to `{}`", backup.display())
+            })?;
+        }
+        generations.insert(0, current);
+        current + 1
+    } else {
+        // The generation of a deleted object is retained, so that it is never reused
+        previous
+            .generation
+            .into_iter()
+            .chain(generations.first().copied())
+            .max()
+            .map_or(1, |generation| generation + 1)
+    };
+    fs::rename(tmp, path)
+        .await
+        .with_context(|| format!("failed to replace object at `{}`", path.display()))?;
+    write_attributes(
+        sidecar,
+        &ObjectAttributes {
+            generation: Some(generation),
+            ..attributes
+        },
+    )
+    .await?;
+    let mut freed = 0;
+    for generation in generations
+        .into_iter()
+        .skip(keep.try_into().unwrap_or(usize::MAX))
+    {
+        let path = version_path(base, generation);
+        let size = file_size(&path).await?;
+        debug!(path = ?path.display(), "remove previous version");
+        fs::remove_file(&path)
+            .await
+            .with_context(|| format!("failed to remove version at `{}`", path.display()))?;
+        freed += size;
+    }
+    Ok((generation, freed))
+}
+
+/// Replace the object at `path` by a copy of the file at `src`, see [`replace_versioned`]
+async fn copy_versioned(
+    src: &Path,
+    path: &Path,
+    sidecar: &Path,
+    base: &Path,
+    keep: u32,
+    attributes: ObjectAttributes,
+) -> anyhow::Result<(u64, u64)> {
+    if let Some(parent) = base.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .context("failed to create versions directory")?;
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .context("failed to create parent directories")?;
+    }
+    let tmp = temp_path(base);
+    let res = async {
+        fs::copy(src, &tmp)
+            .await
+            .with_context(|| format!("failed to copy `{}`", src.display()))?;
+        replace_versioned(path, sidecar, base, &tmp, keep, attributes).await
+    }
+    .await;
+    if res.is_err() {
+        if let Err(err) = fs::remove_file(&tmp).await {
+            debug!(?err, path = ?tmp.display(), "failed to remove temporary file");
+        }
+    }
+    res
+}
+
+/// Entry of a listing which is yet to be streamed
+enum Listed {
+    /// Name of an object
+    Object(String),
+    /// Directories whose merged entries are listed, along with the name prefix of their entries
+    Dirs(Vec<PathBuf>, String),
+}
+
+/// Stream the names of objects stored below the shard directories `dirs`, relative to their shard
+/// directory, in lexicographic order. Directories are read lazily, one at a time, and only if
+/// they may contain names starting with `prefix`
+fn walk_objects(
+    dirs: Vec<PathBuf>,
+    prefix: String,
+) -> impl Stream<Item = anyhow::Result<String>> + Send + 'static {
+    // Entries yet to be listed, the last entry is listed next
+    let pending = vec![Listed::Dirs(dirs, String::new())];
+    stream::try_unfold(pending, move |mut pending| {
+        let prefix = prefix.clone();
+        async move {
+            loop {
+                let (dirs, base) = match pending.pop() {
+                    None => return Ok(None),
+                    Some(Listed::Object(name)) => {
+                        trace!(name, "list file name");
+                        return Ok(Some((name, pending)));
+                    }
+                    Some(Listed::Dirs(dirs, base)) => (dirs, base),
+                };
+                let mut children = Vec::new();
+                for dir in dirs {
+                    let mut entries = fs::read_dir(&dir)
+                        .await
+                        .with_context(|| format!("failed to read directory `{}`", dir.display()))?;
+                    while let Some(entry) = entries
+                        .next_entry()
+                        .await
+                        .context("failed to lookup directory entry")?
+                    {
+                        let file_name = entry.file_name();
+                        if base.is_empty()
+                            && (file_name == METADATA_DIR || file_name == VERSIONS_DIR)
+                        {
+                            continue;
+                        }
+                        let is_dir = entry
+                            .file_type()
+                            .await
+                            .context("failed to lookup directory entry type")?
+                            .is_dir();
+                        let name = format!("{base}{}", file_name.to_string_lossy());
+                        if is_dir {
+                            let name = format!("{name}/");
+                            if name.starts_with(&prefix) || prefix.starts_with(&name) {
+                                children.push((name, Some(entry.path())));
+                            }
+                        } else if name.starts_with(&prefix) {
+                            children.push((name, None));
+                        }
+                    }
+                }
+                // Directories are sorted by their name including the trailing `/`, which all
+                // names within them start with, so that all names are listed in order
+                children.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+                pending.extend(children.into_iter().rev().map(|(name, dir)| match dir {
+                    Some(dir) => Listed::Dirs(vec![dir], name),
+                    None => Listed::Object(name),
+                }));
+            }
+        }
+    })
+}
+
+/// Group object `names`, which start with a prefix of `prefix_len` bytes, by `delimiter`. Names
+/// containing `delimiter` after the prefix are listed once, up to and including the delimiter
+fn group_by_delimiter(
+    names: impl Stream<Item = anyhow::Result<String>> + Send + 'static,
+    prefix_len: usize,
+    delimiter: String,
+) -> impl Stream<Item = anyhow::Result<String>> + Send + 'static {
+    let mut seen = HashSet::new();
+    names.try_filter_map(move |name| {
+        let name = match name[prefix_len..].find(&delimiter) {
+            Some(i) => {
+                let common = &name[..prefix_len + i + delimiter.len()];
+                seen.insert(common.to_string()).then(|| common.to_string())
+            }
+            None => Some(name),
+        };
+        future::ready(Ok(name))
+    })
+}
+
+/// Read object attributes from a sidecar file, objects without a sidecar file have no attributes
+async fn read_attributes(sidecar: &Path) -> anyhow::Result<ObjectAttributes> {
+    match fs::read(sidecar).await {
+        Ok(buf) => serde_json::from_slice(&buf)
+            .with_context(|| format!("failed to parse attributes at `{}`", sidecar.display())),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(ObjectAttributes::default()),
+        Err(err) => Err(anyhow!(err).context(format!(
+            "failed to read attributes at `{}`",
+            sidecar.display()
+        ))),
+    }
+}
+
+/// Write object attributes to a sidecar file, removing it if there are no attributes
+async fn write_attributes(sidecar: &Path, attributes: &ObjectAttributes) -> anyhow::Result<()> {
+    if *attributes == ObjectAttributes::default() {
+        return remove_attributes(sidecar).await;
+    }
+    if let Some(parent) = sidecar.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .context("failed to create sidecar parent directories")?;
+    }
+    let buf = serde_json::to_vec(attributes).context("failed to encode attributes")?;
+    debug!(path = ?sidecar.display(), "write object attributes");
+    fs::write(sidecar, buf)
+        .await
+        .with_context(|| format!("failed to write attributes at `{}`", sidecar.display()))
+}
+
+/// Remove the attributes of a deleted object. The generation of a versioned object is retained,
+/// so that later writes never reuse the generations of its versions
+async fn forget_attributes(sidecar: &Path) -> anyhow::Result<()> {
+    let ObjectAttributes { generation, .. } = read_attributes(sidecar).await?;
+    write_attributes(
+        sidecar,
+        &ObjectAttributes {
+            generation,
+            ..Default::default()
+        },
+    )
+    .await
+}
+
+/// Remove the sidecar file storing object attributes, if it exists
+async fn remove_attributes(sidecar: &Path) -> anyhow::Result<()> {
+    match fs::remove_file(sidecar).await {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(anyhow!(err).context(format!(
+            "failed to remove attributes at `{}`",
+            sidecar.display()
+        ))),
+    }
+}
+
+/// Compute the hex-encoded SHA-256 checksum of the file at `path`
+async fn file_sha256(path: &Path) -> anyhow::Result<String> {
+    let file = File::open(path)
+        .await
+        .with_context(|| format!("failed to open object file [{}]", path.display()))?;
+    let hasher = ReaderStream::new(file)
+        .map(|buf| buf.context("failed to read file"))
+        .try_fold(Sha256::new(), |mut hasher, buf| {
+            hasher.update(&buf);
+            future::ready(Ok(hasher))
+        })
+        .await?;
+    Ok(hex_digest(hasher))
+}
+
+/// Encode the digest computed by `hasher` as lowercase hex
+fn hex_digest(hasher: Sha256) -> String {
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Read the bytes of the file at `path` from `start` up to `end`
+async fn read_file(
+    path: &Path,
+    start: u64,
+    end: u64,
+) -> anyhow::Result<impl Stream<Item = anyhow::Result<Bytes>> + Send + 'static> {
+    let limit = end
+        .checked_sub(start)
+        .context("`end` must be greater than `start`")?;
+    debug!(path = ?path.display(), "open file");
+    let mut object = File::open(path)
+        .await
+        .with_context(|| format!("failed to open object file [{}]", path.display()))?;
+    if start > 0 {
+        debug!("seek file");
+        object
+            .seek(SeekFrom::Start(start))
+            .await
+            .context("failed to seek from start")?;
+    }
+    Ok(ReaderStream::new(object.take(limit)).map(|buf| buf.context("failed to read file")))
+}
+
+/// Lookup the file storing the version of an object with `generation`, given the path of the
+/// object, its sidecar file and the base path of its previous versions. Returns the path of the
+/// file and whether it is the current version of the object
+async fn find_version(
+    path: &Path,
+    sidecar: &Path,
+    base: &Path,
+    generation: u64,
+) -> anyhow::Result<(PathBuf, bool)> {
+    let version = version_path(base, generation);
+    if fs::try_exists(&version)
+        .await
+        .context("failed to check if path exists")?
+    {
+        return Ok((version, false));
+    }
+    if fs::try_exists(path)
+        .await
+        .context("failed to check if path exists")?
+    {
+        let generations = list_generations(base).await?;
+        if current_generation(&read_attributes(sidecar).await?, &generations) == generation {
+            return Ok((path.to_path_buf(), true));
+        }
+    }
+    bail!(
+        "version `{}` of object `{}` does not exist",
+        version_id(generation),
+        path.display()
+    )
+}
+
+/// List the current and previous versions of the objects in `container`, whose names start with
+/// `prefix`. Versions are listed by object name, newest version first
+async fn list_versions(
+    layout: Layout,
+    container: &Path,
+    prefix: String,
+) -> anyhow::Result<Vec<versioning::ObjectVersion>> {
+    /// Lookup the modification time and size of a version
+    async fn version_metadata(path: &Path) -> anyhow::Result<(u64, u64)> {
+        let md = fs::metadata(path)
+            .await
+            .with_context(|| format!("failed to lookup version at `{}`", path.display()))?;
+        let modified = md
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .unwrap_or_default();
+        Ok((modified.as_secs(), md.len()))
+    }
+
+    let mut objects: BTreeMap<String, Vec<u64>> = BTreeMap::new();
+    let dirs = layout
+        .object_dirs(container)
+        .await
+        .context("failed to read path")?;
+    let names: Vec<_> = walk_objects(dirs, prefix.clone()).try_collect().await?;
+    for name in names {
+        objects.entry(name).or_default();
+    }
+    let versions_dir = container.join(VERSIONS_DIR);
+    if fs::try_exists(&versions_dir)
+        .await
+        .context("failed to check if path exists")?
+    {
+        let dirs = layout
+            .object_dirs(&versions_dir)
+            .await
+            .context("failed to read versions directory")?;
+        let names: Vec<_> = walk_objects(dirs, prefix).try_collect().await?;
+        for name in names {
+            if let Some((object, generation)) = split_version(&name) {
+                objects
+                    .entry(object.to_string())
+                    .or_default()
+                    .push(generation);
+            }
+        }
+    }
+
+    let mut versions = Vec::new();
+    for (name, mut generations) in objects {
+        generations.sort_unstable_by(|a, b| b.cmp(a));
+        let path = resolve_object(layout, container, &name)?;
+        if fs::try_exists(&path)
+            .await
+            .context("failed to check if path exists")?
+        {
+            let sidecar = resolve_sidecar(layout, container, &name)?;
+            let generation = current_generation(&read_attributes(&sidecar).await?, &generations);
+            let (last_modified, size) = version_metadata(&path).await?;
+            versions.push(versioning::ObjectVersion {
+                name: name.clone(),
+                version_id: version_id(generation),
+                is_latest: true,
+                is_delete_marker: false,
+                last_modified,
+                size,
+            });
+        }
+        let base = resolve_versions(layout, container, &name)?;
+        for generation in generations {
+            let (last_modified, size) = version_metadata(&version_path(&base, generation)).await?;
+            versions.push(versioning::ObjectVersion {
+                name: name.clone(),
+                version_id: version_id(generation),
+                is_latest: false,
+                is_delete_marker: false,
+                last_modified,
+                size,
+            });
+        }
+    }
+    Ok(versions)
+}
+
+/// Lookup the size and creation time of an object
+async fn object_metadata(path: &Path) -> anyhow::Result<ObjectMetadata> {
+    let md = fs::metadata(path)
+        .await
+        .context("failed to lookup file metadata")?;
+
+    let created_at = match md.created() {
+        Ok(created_time) => created_time
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .context("creation time before Unix epoch")?,
+        Err(e) => {
+            // NOTE: Some platforms don't have support for creation time, so we default to the unix epoch
+            debug!(
+                error = ?e,
+                ?path,
+                "failed to get creation time for object, defaulting to 0"
+            );
+            Duration::from_secs(0)
+        }
+    };
+    // NOTE: The `created_at` format is currently undefined
+    // https://github.com/WebAssembly/wasi-blobstore/issues/7
+    #[cfg(unix)]
+    let size = std::os::unix::fs::MetadataExt::size(&md);
+    #[cfg(windows)]
+    let size = std::os::windows::fs::MetadataExt::file_size(&md);
+    Ok(ObjectMetadata {
+        created_at: created_at.as_secs(),
+        size,
+    })
+}
+
+/// Lookup the size of a file, returning 0 if it does not exist
+async fn file_size(path: &Path) -> anyhow::Result<u64> {
+    match fs::metadata(path).await {
+        Ok(md) if md.is_file() => Ok(md.len()),
+        Ok(_) => Ok(0),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(0),
+        Err(err) => Err(anyhow!(err).context(format!(
+            "failed to lookup file metadata at `{}`",
+            path.display()
+        ))),
+    }
+}
+
+/// Compute the total size of the objects stored below a directory, excluding object attributes
+async fn dir_size(path: &Path) -> anyhow::Result<u64> {
+    let mut size = 0;
+    let mut dirs = vec![path.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries = match fs::read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => {
+                return Err(anyhow!(err)
+                    .context(format!("failed to read directory at `{}`", dir.display())))
+            }
+        };
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .context("failed to lookup directory entry")?
+        {
+            let ty = entry
+                .file_type()
+                .await
+                .context("failed to lookup directory entry type")?;
+            if ty.is_dir() {
+                if entry.file_name() != METADATA_DIR {
+                    dirs.push(entry.path());
+                }
+            } else if ty.is_file() && entry.file_name() != LAYOUT_FILE {
+                let md = entry
+                    .metadata()
+                    .await
+                    .context("failed to lookup directory entry metadata")?;
+                size += md.len();
+            }
+        }
+    }
+    Ok(size)
+}
+
 impl FsProvider {
-    async fn get_root(&self, context: Option<Context>) -> anyhow::Result<Arc<PathBuf>> {
+    async fn get_config(&self, context: Option<Context>) -> anyhow::Result<FsProviderConfig> {
         if let Some(ref source_id) = context.and_then(|Context { component, .. }| component) {
             self.config
                 .read()
                 .await
                 .get(source_id)
                 .with_context(|| format!("failed to lookup {source_id} configuration"))
-                .map(|FsProviderConfig { root }| Arc::clone(root))
+                .cloned()
         } else {
             // TODO: Support a default here
             bail!("failed to lookup invocation source ID")
         }
     }
 
+    async fn get_root(&self, context: Option<Context>) -> anyhow::Result<Arc<PathBuf>> {
+        self.get_config(context)
+            .await
+            .map(|FsProviderConfig { root, .. }| root)
+    }
+
+    async fn get_quota(&self, context: Option<Context>) -> anyhow::Result<Option<Quota>> {
+        self.get_config(context)
+            .await
+            .map(|FsProviderConfig { quota, .. }| quota)
+    }
+
     async fn get_container(
         &self,
         context: Option<Context>,
@@ -130,11 +941,176 @@ impl FsProvider {
         context: Option<Context>,
         ObjectId { container, object }: ObjectId,
     ) -> anyhow::Result<PathBuf> {
-        let container = self
-            .get_container(context, container)
+        let FsProviderConfig { root, layout, .. } = self
+            .get_config(context)
             .await
-            .context("failed to get container")?;
-        resolve_subpath(&container, object).context("failed to resolve subpath")
+            .context("failed to get container root")?;
+        let container = resolve_subpath(&root, container).context("failed to resolve subpath")?;
+        resolve_object(layout, &container, object)
+    }
+
+    /// Get the path of an object and the sidecar file storing its attributes
+    async fn get_object_with_sidecar(
+        &self,
+        context: Option<Context>,
+        ObjectId { container, object }: ObjectId,
+    ) -> anyhow::Result<(PathBuf, PathBuf)> {
+        let FsProviderConfig { root, layout, .. } = self
+            .get_config(context)
+            .await
+            .context("failed to get container root")?;
+        let container = resolve_subpath(&root, container).context("failed to resolve subpath")?;
+        let path = resolve_object(layout, &container, &object)?;
+        let sidecar = resolve_sidecar(layout, &container, object)?;
+        Ok((path, sidecar))
+    }
+
+    /// Get the path of an object, the sidecar file storing its attributes and the base path of its
+    /// previous versions
+    async fn get_object_with_versions(
+        &self,
+        context: Option<Context>,
+        ObjectId { container, object }: ObjectId,
+    ) -> anyhow::Result<(PathBuf, PathBuf, PathBuf)> {
+        let FsProviderConfig { root, layout, .. } = self
+            .get_config(context)
+            .await
+            .context("failed to get container root")?;
+        let container = resolve_subpath(&root, container).context("failed to resolve subpath")?;
+        let path = resolve_object(layout, &container, &object)?;
+        let sidecar = resolve_sidecar(layout, &container, &object)?;
+        let versions = resolve_versions(layout, &container, object)?;
+        Ok((path, sidecar, versions))
+    }
+
+    /// Open an object for writing, returning a future which streams `data` to it and resolves to
+    /// the outcome of the write. The etag of the object is its SHA-256 checksum, if enabled.
+    ///
+    /// If versioning is enabled, data is streamed to a temporary file, which atomically replaces
+    /// the object once the write completes, and the replaced object is kept as a previous version
+    async fn write_object(
+        &self,
+        cx: Option<Context>,
+        id: ObjectId,
+        data: Pin<Box<dyn Stream<Item = Bytes> + Send>>,
+    ) -> anyhow::Result<Pin<Box<dyn Future<Output = Result<writes::WriteResult, String>> + Send>>>
+    {
+        let FsProviderConfig {
+            quota,
+            checksums,
+            versions,
+            ..
+        } = self.get_config(cx.clone()).await?;
+        let (path, sidecar, base) = self.get_object_with_versions(cx, id).await?;
+        let base = (versions > 0).then_some(base);
+        // The previous object, if any, is replaced on write, unless it is kept as a version
+        let freed = if let Some(ref quota) = quota {
+            let freed = if base.is_some() {
+                0
+            } else {
+                file_size(&path).await?
+            };
+            quota.check(freed)?;
+            freed
+        } else {
+            0
+        };
+        if let Some(parent) = path.parent() {
+            info!(parent = ?parent.display(), "creating directory");
+            fs::create_dir_all(parent)
+                .await
+                .context("failed to create parent directories")?;
+        }
+        let target = if let Some(ref base) = base {
+            if let Some(parent) = base.parent() {
+                fs::create_dir_all(parent)
+                    .await
+                    .context("failed to create versions directory")?;
+            }
+            temp_path(base)
+        } else {
+            // Attributes of the previous object, if any, are replaced on write
+            remove_attributes(&sidecar).await?;
+            path.clone()
+        };
+        let mut file = File::options()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .open(&target)
+            .await
+            .context("failed to open file")?;
+        if let Some(ref quota) = quota {
+            quota.release(freed);
+        }
+        Ok(Box::pin(async move {
+            debug!(path = ?path.display(), "streaming data to file");
+            let mut data = data;
+            let mut n = 0;
+            let mut hasher = checksums.then(Sha256::new);
+            let sha256 = async {
+                while let Some(chunk) = data.next().await {
+                    trace!(?chunk, "received data chunk");
+                    let len = u64::try_from(chunk.len()).unwrap_or(u64::MAX);
+                    if let Some(ref quota) = quota {
+                        quota.reserve(len)?;
+                    }
+                    n += len;
+                    if let Some(ref mut hasher) = hasher {
+                        hasher.update(&chunk);
+                    }
+                    file.write_all(&chunk)
+                        .await
+                        .context("failed to write file")?;
+                }
+                file.flush().await.context("failed to flush file")?;
+                drop(file);
+                let sha256 = hasher.map(hex_digest);
+                let attributes = ObjectAttributes {
+                    sha256: sha256.clone(),
+                    ..Default::default()
+                };
+                let generation = if let Some(ref base) = base {
+                    let _lock = ObjectLock::acquire(&path).await;
+                    let (generation, freed) =
+                        replace_versioned(&path, &sidecar, base, &target, versions, attributes)
+                            .await?;
+                    if let Some(ref quota) = quota {
+                        quota.release(freed);
+                    }
+                    Some(generation)
+                } else {
+                    if sha256.is_some() {
+                        write_attributes(&sidecar, &attributes).await?;
+                    }
+                    None
+                };
+                anyhow::Ok((sha256, generation))
+            }
+            .await;
+            let (sha256, generation) = match sha256 {
+                Ok(sha256) => sha256,
+                Err(err) => {
+                    if let Some(ref quota) = quota {
+                        // Incomplete objects do not count towards the quota
+                        quota.release(n);
+                    }
+                    // Temporary files of versioned writes are always removed
+                    if quota.is_some() || base.is_some() {
+                        if let Err(err) = fs::remove_file(&target).await {
+                            error!(?err, path = ?target.display(), "failed to remove incomplete file");
+                        }
+                    }
+                    return Err(format!("{err:#}"));
+                }
+            };
+            debug!(n, path = ?path.display(), "finished writing file");
+            Ok(writes::WriteResult {
+                bytes_written: n,
+                etag: sha256,
+                version_id: generation.map(version_id),
+            })
+        }))
     }
 }
 
@@ -147,9 +1123,15 @@ impl Handler<Option<Context>> for FsProvider {
     ) -> anyhow::Result<Result<(), String>> {
         Ok(async {
             propagate_trace_for_ctx!(cx);
+            let quota = self.get_quota(cx.clone()).await?;
             let path = self.get_container(cx, name).await?;
+            let size = if quota.is_some() {
+                dir_size(&path).await?
+            } else {
+                0
+            };
             debug!("read directory at `{}`", path.display());
-            let dir = fs::read_dir(path).await.context("failed to read path")?;
+            let dir = fs::read_dir(&path).await.context("failed to read path")?;
             ReadDirStream::new(dir)
                 .map(|entry| entry.context("failed to lookup directory entry"))
                 .try_for_each_concurrent(None, |entry| async move {
@@ -170,7 +1152,11 @@ impl Handler<Option<Context>> for FsProvider {
                     Ok(())
                 })
                 .await
-                .context("failed to remove directory contents")
+                .context("failed to remove directory contents")?;
+            if let Some(quota) = quota {
+                quota.release(size);
+            }
+            Ok(())
         }
         .await
         .map_err(|err| format!("{err:#}")))
@@ -218,10 +1204,20 @@ impl Handler<Option<Context>> for FsProvider {
     ) -> anyhow::Result<Result<(), String>> {
         Ok(async {
             propagate_trace_for_ctx!(cx);
+            let quota = self.get_quota(cx.clone()).await?;
             let path = self.get_container(cx, name).await?;
+            let size = if quota.is_some() {
+                dir_size(&path).await?
+            } else {
+                0
+            };
             fs::remove_dir_all(path)
                 .await
-                .context("failed to remove path")
+                .context("failed to remove path")?;
+            if let Some(quota) = quota {
+                quota.release(size);
+            }
+            Ok(())
         }
         .await
         .map_err(|err| format!("{err:#}")))
@@ -282,36 +1278,18 @@ impl Handler<Option<Context>> for FsProvider {
     > {
         Ok(async {
             propagate_trace_for_ctx!(cx);
-            let path = self.get_container(cx, name).await?;
+            let FsProviderConfig { root, layout, .. } =
+                self.get_config(cx).await.context("failed to get root")?;
+            let path = resolve_subpath(&root, name).context("failed to resolve subpath")?;
             let offset = offset.unwrap_or_default().try_into().unwrap_or(usize::MAX);
             let limit = limit.unwrap_or(u64::MAX).try_into().unwrap_or(usize::MAX);
-            debug!(path = ?path.display(), offset, limit, "read directory");
-            let dir = fs::read_dir(path).await.context("failed to read path")?;
-            let mut names = ReadDirStream::new(dir)
-                .skip(offset)
-                .take(limit)
-                .map(move |entry| {
-                    let entry = entry.context("failed to lookup directory entry")?;
-                    let name = entry.file_name().to_string_lossy().to_string();
-                    trace!(name, "list file name");
-                    anyhow::Ok(name)
-                });
-            let (tx, rx) = mpsc::channel(16);
-            anyhow::Ok((
-                Box::pin(ReceiverStream::new(rx).ready_chunks(128))
-                    as Pin<Box<dyn Stream<Item = _> + Send>>,
-                Box::pin(async move {
-                    async move {
-                        while let Some(name) = names.next().await {
-                            let name = name.context("failed to list file names")?;
-                            tx.send(name).await.context("stream receiver closed")?;
-                        }
-                        anyhow::Ok(())
-                    }
-                    .await
-                    .map_err(|err| format!("{err:#}"))
-                }) as Pin<Box<dyn Future<Output = _> + Send>>,
-            ))
+            debug!(path = ?path.display(), offset, limit, %layout, "read directory");
+            let dirs = layout
+                .object_dirs(&path)
+                .await
+                .context("failed to read path")?;
+            let names = walk_objects(dirs, String::new()).skip(offset).take(limit);
+            anyhow::Ok(stream_batches(names, DEFAULT_BATCH_SIZE, DEFAULT_BUFFER))
         }
         .await
         .map_err(|err| format!("{err:#}")))
@@ -326,21 +1304,97 @@ impl Handler<Option<Context>> for FsProvider {
     ) -> anyhow::Result<Result<(), String>> {
         Ok(async {
             propagate_trace_for_ctx!(cx);
-            let root = self.get_root(cx).await.context("failed to get root")?;
+            let FsProviderConfig {
+                root,
+                quota,
+                layout,
+                versions,
+                ..
+            } = self.get_config(cx).await.context("failed to get root")?;
             let src_container = resolve_subpath(&root, src.container)
                 .context("failed to resolve source container path")?;
-            let src = resolve_subpath(&src_container, src.object)
+            let src_sidecar = resolve_sidecar(layout, &src_container, &src.object)?;
+            let src = resolve_object(layout, &src_container, src.object)
                 .context("failed to resolve source object path")?;
 
             let dest_container = resolve_subpath(&root, dest.container)
                 .context("failed to resolve destination container path")?;
-            let dest = resolve_subpath(&dest_container, dest.object)
+            let dest_sidecar = resolve_sidecar(layout, &dest_container, &dest.object)?;
+            let dest_versions = resolve_versions(layout, &dest_container, &dest.object)?;
+            let dest = resolve_object(layout, &dest_container, dest.object)
                 .context("failed to resolve destination object path")?;
-            debug!("copy `{}` to `{}`", src.display(), dest.display());
-            fs::copy(src, dest).await.context("failed to copy")?;
-            anyhow::Ok(())
-        }
-        .await
+            if versions > 0 {
+                // The destination object, if any, is kept as a previous version
+                let added = if let Some(ref quota) = quota {
+                    let added = file_size(&src).await?;
+                    quota.reserve(added)?;
+                    added
+                } else {
+                    0
+                };
+                let attributes = read_attributes(&src_sidecar).await?;
+                let _lock = ObjectLock::acquire(&dest).await;
+                debug!("copy `{}` to `{}`", src.display(), dest.display());
+                return match copy_versioned(
+                    &src,
+                    &dest,
+                    &dest_sidecar,
+                    &dest_versions,
+                    versions,
+                    attributes,
+                )
+                .await
+                {
+                    Ok((_, freed)) => {
+                        if let Some(ref quota) = quota {
+                            quota.release(freed);
+                        }
+                        Ok(())
+                    }
+                    Err(err) => {
+                        if let Some(ref quota) = quota {
+                            quota.release(added);
+                        }
+                        Err(err)
+                    }
+                };
+            }
+            // The destination shard may not exist yet
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)
+                    .await
+                    .context("failed to create destination directories")?;
+            }
+            // The copy replaces the destination object, if any
+            let (added, freed) = if let Some(ref quota) = quota {
+                let added = file_size(&src).await?;
+                let freed = file_size(&dest).await?;
+                quota.reserve(added.saturating_sub(freed))?;
+                (added, freed)
+            } else {
+                (0, 0)
+            };
+            debug!("copy `{}` to `{}`", src.display(), dest.display());
+            if let Err(err) = fs::copy(src, dest).await {
+                if let Some(ref quota) = quota {
+                    quota.release(added.saturating_sub(freed));
+                }
+                return Err(anyhow!(err).context("failed to copy"));
+            }
+            if let Some(ref quota) = quota {
+                quota.release(freed.saturating_sub(added));
+            }
+            let attributes = read_attributes(&src_sidecar).await?;
+            write_attributes(
+                &dest_sidecar,
+                &ObjectAttributes {
+                    generation: None,
+                    ..attributes
+                },
+            )
+            .await
+        }
+        .await
         .map_err(|err| format!("{err:#}")))
     }
 
@@ -352,16 +1406,29 @@ impl Handler<Option<Context>> for FsProvider {
     ) -> anyhow::Result<Result<(), String>> {
         Ok(async {
             propagate_trace_for_ctx!(cx);
-            let path = self.get_object(cx, id).await?;
+            let quota = self.get_quota(cx.clone()).await?;
+            let (path, sidecar) = self.get_object_with_sidecar(cx, id).await?;
+            let _lock = ObjectLock::acquire(&path).await;
+            let size = if quota.is_some() {
+                file_size(&path).await?
+            } else {
+                0
+            };
             debug!("remove file at `{}`", path.display());
             match fs::remove_file(&path).await {
-                Ok(()) => Ok(()),
+                Ok(()) => {
+                    if let Some(quota) = quota {
+                        quota.release(size);
+                    }
+                    Ok(())
+                }
                 Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
                 Err(err) => {
                     Err(anyhow!(err)
                         .context(format!("failed to remove file at `{}`", path.display())))
                 }
-            }
+            }?;
+            forget_attributes(&sidecar).await
         }
         .await
         .map_err(|err| format!("{err:#}")))
@@ -376,17 +1443,37 @@ impl Handler<Option<Context>> for FsProvider {
     ) -> anyhow::Result<Result<(), String>> {
         Ok(async {
             propagate_trace_for_ctx!(cx);
-            let container = self.get_container(cx, container).await?;
+            let FsProviderConfig {
+                root,
+                quota,
+                layout,
+                ..
+            } = self.get_config(cx).await.context("failed to get root")?;
+            let container =
+                resolve_subpath(&root, container).context("failed to resolve subpath")?;
             for name in objects {
-                let path =
-                    resolve_subpath(&container, name).context("failed to resolve object path")?;
+                let sidecar = resolve_sidecar(layout, &container, &name)?;
+                let path = resolve_object(layout, &container, name)
+                    .context("failed to resolve object path")?;
+                let _lock = ObjectLock::acquire(&path).await;
+                let size = if quota.is_some() {
+                    file_size(&path).await?
+                } else {
+                    0
+                };
                 debug!("remove file at `{}`", path.display());
                 match fs::remove_file(&path).await {
-                    Ok(()) => Ok(()),
+                    Ok(()) => {
+                        if let Some(ref quota) = quota {
+                            quota.release(size);
+                        }
+                        Ok(())
+                    }
                     Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
                     Err(err) => Err(anyhow!(err)
                         .context(format!("failed to remove file at `{}`", path.display()))),
                 }?;
+                forget_attributes(&sidecar).await?;
             }
             anyhow::Ok(())
         }
@@ -412,39 +1499,9 @@ impl Handler<Option<Context>> for FsProvider {
     > {
         Ok(async {
             propagate_trace_for_ctx!(cx);
-            let limit = end
-                .checked_sub(start)
-                .context("`end` must be greater than `start`")?;
             let path = self.get_object(cx, id).await?;
-            debug!(path = ?path.display(), "open file");
-            let mut object = File::open(&path)
-                .await
-                .with_context(|| format!("failed to open object file [{}]", path.display()))?;
-            if start > 0 {
-                debug!("seek file");
-                object
-                    .seek(SeekFrom::Start(start))
-                    .await
-                    .context("failed to seek from start")?;
-            }
-            let mut data = ReaderStream::new(object.take(limit));
-            let (tx, rx) = mpsc::channel(16);
-            anyhow::Ok((
-                Box::pin(ReceiverStream::new(rx)) as Pin<Box<dyn Stream<Item = _> + Send>>,
-                Box::pin(async move {
-                    async move {
-                        while let Some(buf) = data.next().await {
-                            let buf = buf.context("failed to read file")?;
-                            debug!(?buf, "sending chunk");
-                            tx.send(buf).await.context("stream receiver closed")?;
-                        }
-                        debug!("finished reading file");
-                        anyhow::Ok(())
-                    }
-                    .await
-                    .map_err(|err| format!("{err:#}"))
-                }) as Pin<Box<dyn Future<Output = _> + Send>>,
-            ))
+            let data = read_file(&path, start, end).await?;
+            anyhow::Ok(stream_bytes(data, DEFAULT_CHUNK_SIZE, DEFAULT_BUFFER))
         }
         .await
         .map_err(|err| format!("{err:#}")))
@@ -459,34 +1516,7 @@ impl Handler<Option<Context>> for FsProvider {
         Ok(async {
             propagate_trace_for_ctx!(cx);
             let path = self.get_object(cx, id).await?;
-            let md = fs::metadata(&path)
-                .await
-                .context("failed to lookup file metadata")?;
-
-            let created_at = match md.created() {
-                Ok(created_time) => created_time
-                    .duration_since(SystemTime::UNIX_EPOCH)
-                    .context("creation time before Unix epoch")?,
-                Err(e) => {
-                    // NOTE: Some platforms don't have support for creation time, so we default to the unix epoch
-                    debug!(
-                        error = ?e,
-                        ?path,
-                        "failed to get creation time for object, defaulting to 0"
-                    );
-                    Duration::from_secs(0)
-                }
-            };
-            // NOTE: The `created_at` format is currently undefined
-            // https://github.com/WebAssembly/wasi-blobstore/issues/7
-            #[cfg(unix)]
-            let size = std::os::unix::fs::MetadataExt::size(&md);
-            #[cfg(windows)]
-            let size = std::os::windows::fs::MetadataExt::file_size(&md);
-            anyhow::Ok(ObjectMetadata {
-                created_at: created_at.as_secs(),
-                size,
-            })
+            object_metadata(&path).await
         }
         .await
         .map_err(|err| format!("{err:#}")))
@@ -518,22 +1548,76 @@ impl Handler<Option<Context>> for FsProvider {
     ) -> anyhow::Result<Result<(), String>> {
         Ok(async {
             propagate_trace_for_ctx!(cx);
-            let root = self.get_root(cx).await.context("failed to get root")?;
+            let FsProviderConfig {
+                root,
+                quota,
+                layout,
+                versions,
+                ..
+            } = self.get_config(cx).await.context("failed to get root")?;
             let src_container = resolve_subpath(&root, src.container)
                 .context("failed to resolve source container path")?;
-            let src = resolve_subpath(&src_container, src.object)
+            let src_sidecar = resolve_sidecar(layout, &src_container, &src.object)?;
+            let src = resolve_object(layout, &src_container, src.object)
                 .context("failed to resolve source object path")?;
 
             let dest_container = resolve_subpath(&root, dest.container)
                 .context("failed to resolve destination container path")?;
-            let dest = resolve_subpath(&dest_container, dest.object)
+            let dest_sidecar = resolve_sidecar(layout, &dest_container, &dest.object)?;
+            let dest_versions = resolve_versions(layout, &dest_container, &dest.object)?;
+            let dest = resolve_object(layout, &dest_container, dest.object)
                 .context("failed to resolve destination object path")?;
-            debug!("copy `{}` to `{}`", src.display(), dest.display());
-            fs::copy(&src, dest).await.context("failed to copy")?;
+            let attributes = read_attributes(&src_sidecar).await?;
+            if versions > 0 {
+                let _lock = ObjectLock::acquire(&dest).await;
+                // The destination object, if any, is kept as a previous version
+                debug!("copy `{}` to `{}`", src.display(), dest.display());
+                let (_, freed) = copy_versioned(
+                    &src,
+                    &dest,
+                    &dest_sidecar,
+                    &dest_versions,
+                    versions,
+                    attributes,
+                )
+                .await?;
+                // The source object is removed below, so the quota only changes by removed versions
+                if let Some(quota) = quota {
+                    quota.release(freed);
+                }
+            } else {
+                // The destination shard may not exist yet
+                if let Some(parent) = dest.parent() {
+                    fs::create_dir_all(parent)
+                        .await
+                        .context("failed to create destination directories")?;
+                }
+                // The moved object replaces the destination object, if any
+                let freed = if quota.is_some() {
+                    file_size(&dest).await?
+                } else {
+                    0
+                };
+                debug!("copy `{}` to `{}`", src.display(), dest.display());
+                fs::copy(&src, dest).await.context("failed to copy")?;
+                if let Some(quota) = quota {
+                    quota.release(freed);
+                }
+                write_attributes(
+                    &dest_sidecar,
+                    &ObjectAttributes {
+                        generation: None,
+                        ..attributes
+                    },
+                )
+                .await?;
+            }
+            let _lock = ObjectLock::acquire(&src).await;
             debug!("remove `{}`", src.display());
-            fs::remove_file(src)
+            fs::remove_file(&src)
                 .await
-                .context("failed to remove source")
+                .context("failed to remove source")?;
+            forget_attributes(&src_sidecar).await
         }
         .await
         .map_err(|err| format!("{err:#}")))
@@ -549,35 +1633,387 @@ impl Handler<Option<Context>> for FsProvider {
     {
         Ok(async {
             propagate_trace_for_ctx!(cx);
-            let path = self.get_object(cx, id).await?;
-            if let Some(parent) = path.parent() {
-                info!(parent = ?parent.display(), "creating directory");
-                fs::create_dir_all(parent)
-                    .await
-                    .context("failed to create parent directories")?;
+            let write = self.write_object(cx, id, data).await?;
+            anyhow::Ok(Box::pin(async move { write.await.map(|_| ()) })
+                as Pin<Box<dyn Future<Output = _> + Send>>)
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+}
+
+impl listing::Handler<Option<Context>> for FsProvider {
+    #[instrument(level = "trace", skip(self))]
+    async fn list_objects(
+        &self,
+        cx: Option<Context>,
+        name: String,
+        prefix: Option<String>,
+        delimiter: Option<String>,
+        limit: Option<u64>,
+        offset: Option<u64>,
+    ) -> anyhow::Result<
+        Result<
+            (
+                Pin<Box<dyn Stream<Item = Vec<String>> + Send>>,
+                Pin<Box<dyn Future<Output = Result<(), String>> + Send>>,
+            ),
+            String,
+        >,
+    > {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let FsProviderConfig { root, layout, .. } =
+                self.get_config(cx).await.context("failed to get root")?;
+            let path = resolve_subpath(&root, name).context("failed to resolve subpath")?;
+            let prefix = prefix.unwrap_or_default();
+            let offset = offset.unwrap_or_default().try_into().unwrap_or(usize::MAX);
+            let limit = limit.unwrap_or(u64::MAX).try_into().unwrap_or(usize::MAX);
+            debug!(path = ?path.display(), prefix, ?delimiter, offset, limit, %layout, "list objects");
+            let dirs = layout
+                .object_dirs(&path)
+                .await
+                .context("failed to read path")?;
+            let names = walk_objects(dirs, prefix.clone());
+            let names: Pin<Box<dyn Stream<Item = anyhow::Result<String>> + Send>> =
+                match delimiter.filter(|delimiter| !delimiter.is_empty()) {
+                    Some(delimiter) => {
+                        Box::pin(group_by_delimiter(names, prefix.len(), delimiter))
+                    }
+                    None => Box::pin(names),
+                };
+            anyhow::Ok(stream_batches(
+                names.skip(offset).take(limit),
+                DEFAULT_BATCH_SIZE,
+                DEFAULT_BUFFER,
+            ))
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+}
+
+impl integrity::Handler<Option<Context>> for FsProvider {
+    #[instrument(level = "trace", skip(self))]
+    async fn get_checksum(
+        &self,
+        cx: Option<Context>,
+        integrity::ObjectId { container, object }: integrity::ObjectId,
+    ) -> anyhow::Result<Result<Option<String>, String>> {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let (path, sidecar) = self
+                .get_object_with_sidecar(cx, ObjectId { container, object })
+                .await?;
+            if !fs::try_exists(&path)
+                .await
+                .context("failed to check if path exists")?
+            {
+                bail!("object `{}` does not exist", path.display())
+            }
+            let ObjectAttributes { sha256, .. } = read_attributes(&sidecar).await?;
+            anyhow::Ok(sha256)
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn verify_object(
+        &self,
+        cx: Option<Context>,
+        integrity::ObjectId { container, object }: integrity::ObjectId,
+    ) -> anyhow::Result<Result<bool, String>> {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let (path, sidecar) = self
+                .get_object_with_sidecar(cx, ObjectId { container, object })
+                .await?;
+            let ObjectAttributes { sha256, .. } = read_attributes(&sidecar).await?;
+            let expected = sha256.with_context(|| {
+                format!(
+                    "no checksum was computed when `{}` was written",
+                    path.display()
+                )
+            })?;
+            let actual = file_sha256(&path).await?;
+            if actual != expected {
+                error!(path = ?path.display(), expected, actual, "object checksum mismatch");
             }
-            let mut file = File::options()
-                .create(true)
-                .truncate(true)
-                .write(true)
-                .open(&path)
+            anyhow::Ok(actual == expected)
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+}
+
+impl writes::Handler<Option<Context>> for FsProvider {
+    #[instrument(level = "trace", skip(self, data))]
+    async fn write_data(
+        &self,
+        cx: Option<Context>,
+        writes::ObjectId { container, object }: writes::ObjectId,
+        data: Pin<Box<dyn Stream<Item = Bytes> + Send>>,
+    ) -> anyhow::Result<
+        Result<Pin<Box<dyn Future<Output = Result<writes::WriteResult, String>> + Send>>, String>,
+    > {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            self.write_object(cx, ObjectId { container, object }, data)
+                .await
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+}
+
+impl versioning::Handler<Option<Context>> for FsProvider {
+    #[instrument(level = "trace", skip(self))]
+    async fn list_object_versions(
+        &self,
+        cx: Option<Context>,
+        name: String,
+        prefix: Option<String>,
+        limit: Option<u64>,
+    ) -> anyhow::Result<
+        Result<
+            (
+                Pin<Box<dyn Stream<Item = Vec<versioning::ObjectVersion>> + Send>>,
+                Pin<Box<dyn Future<Output = Result<(), String>> + Send>>,
+            ),
+            String,
+        >,
+    > {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let FsProviderConfig { root, layout, .. } =
+                self.get_config(cx).await.context("failed to get root")?;
+            let path = resolve_subpath(&root, name).context("failed to resolve subpath")?;
+            let limit = limit.unwrap_or(u64::MAX).try_into().unwrap_or(usize::MAX);
+            debug!(path = ?path.display(), ?prefix, limit, "list object versions");
+            let versions = list_versions(layout, &path, prefix.unwrap_or_default()).await?;
+            anyhow::Ok(stream_batches(
+                stream::iter(versions.into_iter().map(anyhow::Ok)).take(limit),
+                DEFAULT_BATCH_SIZE,
+                DEFAULT_BUFFER,
+            ))
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_object_version(
+        &self,
+        cx: Option<Context>,
+        versioning::ObjectId { container, object }: versioning::ObjectId,
+        version_id: String,
+        start: u64,
+        end: u64,
+    ) -> anyhow::Result<
+        Result<
+            (
+                Pin<Box<dyn Stream<Item = Bytes> + Send>>,
+                Pin<Box<dyn Future<Output = Result<(), String>> + Send>>,
+            ),
+            String,
+        >,
+    > {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let generation = parse_version_id(&version_id)?;
+            let (path, sidecar, base) = self
+                .get_object_with_versions(cx, ObjectId { container, object })
+                .await?;
+            let (path, _) = find_version(&path, &sidecar, &base, generation).await?;
+            let data = read_file(&path, start, end).await?;
+            anyhow::Ok(stream_bytes(data, DEFAULT_CHUNK_SIZE, DEFAULT_BUFFER))
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn delete_object_version(
+        &self,
+        cx: Option<Context>,
+        versioning::ObjectId { container, object }: versioning::ObjectId,
+        version_id: String,
+    ) -> anyhow::Result<Result<(), String>> {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let generation = parse_version_id(&version_id)?;
+            let quota = self.get_quota(cx.clone()).await?;
+            let (path, sidecar, base) = self
+                .get_object_with_versions(cx, ObjectId { container, object })
+                .await?;
+            let _lock = ObjectLock::acquire(&path).await;
+            let (path, current) = find_version(&path, &sidecar, &base, generation).await?;
+            let size = file_size(&path).await?;
+            debug!("remove file at `{}`", path.display());
+            fs::remove_file(&path)
                 .await
-                .context("failed to open file")?;
+                .with_context(|| format!("failed to remove file at `{}`", path.display()))?;
+            if let Some(quota) = quota {
+                quota.release(size);
+            }
+            // Deleting the current version deletes the object, previous versions are retained
+            if current {
+                forget_attributes(&sidecar).await?;
+            }
+            anyhow::Ok(())
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn restore_object_version(
+        &self,
+        cx: Option<Context>,
+        versioning::ObjectId { container, object }: versioning::ObjectId,
+        version_id: String,
+    ) -> anyhow::Result<Result<Option<String>, String>> {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let generation = parse_version_id(&version_id)?;
+            let FsProviderConfig {
+                quota,
+                checksums,
+                versions,
+                ..
+            } = self.get_config(cx.clone()).await?;
+            if versions == 0 {
+                bail!("versioning is disabled, set `VERSIONS` to restore versions of objects")
+            }
+            let (path, sidecar, base) = self
+                .get_object_with_versions(cx, ObjectId { container, object })
+                .await?;
+            let _lock = ObjectLock::acquire(&path).await;
+            let (version, current) = find_version(&path, &sidecar, &base, generation).await?;
+            if current {
+                return Ok(Some(version_id));
+            }
+            let added = if let Some(ref quota) = quota {
+                let added = file_size(&version).await?;
+                quota.reserve(added)?;
+                added
+            } else {
+                0
+            };
+            let sha256 = if checksums {
+                Some(file_sha256(&version).await?)
+            } else {
+                None
+            };
+            let attributes = ObjectAttributes {
+                sha256,
+                ..Default::default()
+            };
+            debug!(version = ?version.display(), path = ?path.display(), "restore version");
+            match copy_versioned(&version, &path, &sidecar, &base, versions, attributes).await {
+                Ok((generation, freed)) => {
+                    if let Some(ref quota) = quota {
+                        quota.release(freed);
+                    }
+                    Ok(Some(self::version_id(generation)))
+                }
+                Err(err) => {
+                    if let Some(ref quota) = quota {
+                        quota.release(added);
+                    }
+                    Err(err)
+                }
+            }
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "trace", skip(self, data))]
+    async fn write_object(
+        &self,
+        cx: Option<Context>,
+        versioning::ObjectId { container, object }: versioning::ObjectId,
+        data: Pin<Box<dyn Stream<Item = Bytes> + Send>>,
+    ) -> anyhow::Result<
+        Result<Pin<Box<dyn Future<Output = Result<Option<String>, String>> + Send>>, String>,
+    > {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let write =
+                FsProvider::write_object(self, cx, ObjectId { container, object }, data).await?;
             anyhow::Ok(Box::pin(async move {
-                debug!(path = ?path.display(), "streaming data to file");
-                let n = io::copy(
-                    &mut StreamReader::new(data.map(|chunk| {
-                        trace!(?chunk, "received data chunk");
-                        std::io::Result::Ok(chunk)
-                    })),
-                    &mut file,
+                write
+                    .await
+                    .map(|writes::WriteResult { version_id, .. }| version_id)
+            }) as Pin<Box<dyn Future<Output = _> + Send>>)
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+}
+
+impl metadata::Handler<Option<Context>> for FsProvider {
+    #[instrument(level = "trace", skip(self))]
+    async fn get_object_info(
+        &self,
+        cx: Option<Context>,
+        metadata::ObjectId { container, object }: metadata::ObjectId,
+    ) -> anyhow::Result<Result<metadata::ObjectInfo, String>> {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let (path, sidecar) = self
+                .get_object_with_sidecar(
+                    cx,
+                    ObjectId {
+                        container,
+                        object: object.clone(),
+                    },
                 )
+                .await?;
+            let ObjectMetadata { created_at, size } = object_metadata(&path).await?;
+            let attributes = read_attributes(&sidecar).await?;
+            anyhow::Ok(metadata::ObjectInfo {
+                name: object,
+                created_at,
+                size,
+                attributes: attributes.into(),
+            })
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn set_object_attributes(
+        &self,
+        cx: Option<Context>,
+        metadata::ObjectId { container, object }: metadata::ObjectId,
+        attributes: metadata::ObjectAttributes,
+    ) -> anyhow::Result<Result<(), String>> {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let (path, sidecar) = self
+                .get_object_with_sidecar(cx, ObjectId { container, object })
+                .await?;
+            let _lock = ObjectLock::acquire(&path).await;
+            let md = fs::metadata(&path)
                 .await
-                .context("failed to write file")
-                .map_err(|err| format!("{err:#}"))?;
-                debug!(n, path = ?path.display(), "finished writing file");
-                Ok(())
-            }) as Pin<Box<dyn Future<Output = _> + Send>>)
+                .with_context(|| format!("failed to lookup object at `{}`", path.display()))?;
+            if !md.is_file() {
+                bail!("`{}` is not an object", path.display())
+            }
+            // The checksum and generation of the object are retained
+            let ObjectAttributes {
+                sha256, generation, ..
+            } = read_attributes(&sidecar).await?;
+            let attributes = ObjectAttributes {
+                sha256,
+                generation,
+                ..attributes.into()
+            };
+            write_attributes(&sidecar, &attributes).await
         }
         .await
         .map_err(|err| format!("{err:#}")))
@@ -585,11 +2021,14 @@ impl Handler<Option<Context>> for FsProvider {
 }
 
 impl Provider for FsProvider {
-    /// The fs provider has one configuration parameter, the root of the file system
+    /// The fs provider is configured with the root of the file system and an optional storage quota
     async fn receive_link_config_as_target(
         &self,
         LinkConfig {
-            source_id, config, ..
+            source_id,
+            link_name,
+            config,
+            ..
         }: LinkConfig<'_>,
     ) -> anyhow::Result<()> {
         for (k, v) in config {
@@ -622,9 +2061,72 @@ impl Provider for FsProvider {
             return Err(anyhow!(e).context("failed to create component directory"));
         }
 
+        let root_val = root_val.clean();
+
+        // Determine the storage quota, if any
+        let quota = match config
+            .iter()
+            .find(|(key, _)| key.to_uppercase() == "MAX_BYTES")
+        {
+            None => None,
+            Some((_, value)) => {
+                let max_bytes = value
+                    .parse()
+                    .with_context(|| format!("invalid `MAX_BYTES` value `{value}`"))?;
+                // Usage is shared by all links using the same root
+                let used = self.config.read().await.values().find_map(|config| {
+                    let quota = config.quota.as_ref()?;
+                    (*config.root == root_val).then(|| Arc::clone(&quota.used))
+                });
+                let used = match used {
+                    Some(used) => used,
+                    None => {
+                        let used = dir_size(&root_val)
+                            .await
+                            .context("failed to compute storage usage")?;
+                        info!(used, max_bytes, "computed storage usage of root");
+                        Arc::new(AtomicU64::new(used))
+                    }
+                };
+                Some(Quota { max_bytes, used })
+            }
+        };
+
+        // Determine the object layout, migrating existing containers if requested
+        let layout = Layout::from_config(config)?;
+        let migrate = config
+            .iter()
+            .find(|(key, _)| key.to_uppercase() == "MIGRATE_LAYOUT")
+            .is_some_and(|(_, value)| value.eq_ignore_ascii_case("true"));
+        layout::ensure_layout(&root_val, layout, migrate)
+            .await
+            .context("failed to ensure object layout")?;
+
+        // Determine whether checksums of objects are computed on write
+        let checksums = config
+            .iter()
+            .find(|(key, _)| key.to_uppercase() == "CHECKSUMS")
+            .is_some_and(|(_, value)| value.eq_ignore_ascii_case("true"));
+
+        // Determine the number of previous versions kept when objects are replaced
+        let versions = match config
+            .iter()
+            .find(|(key, _)| key.to_uppercase() == "VERSIONS")
+        {
+            None => 0,
+            Some((_, value)) => value
+                .parse()
+                .with_context(|| format!("invalid `VERSIONS` value `{value}`"))?,
+        };
+
         // Build configuration for FS Provider to use later
         let config = FsProviderConfig {
-            root: Arc::new(root_val.clean()),
+            root: Arc::new(root_val),
+            quota,
+            layout,
+            checksums,
+            versions,
+            link_name: link_name.into(),
         };
 
         info!("Saved FsProviderConfig: {:#?}", config);
@@ -649,6 +2151,49 @@ impl Provider for FsProvider {
         Ok(())
     }
 
+    /// Report the health of each link by checking that its root is a writable directory
+    async fn link_health(&self) -> anyhow::Result<Vec<LinkHealthCheck>> {
+        let configs: Vec<_> = self
+            .config
+            .read()
+            .await
+            .iter()
+            .map(|(source_id, config)| {
+                (
+                    source_id.clone(),
+                    config.link_name.clone(),
+                    Arc::clone(&config.root),
+                )
+            })
+            .collect();
+        let target = get_connection().provider_key().to_string();
+        let mut links = Vec::with_capacity(configs.len());
+        for (source_id, link_name, root) in configs {
+            let message = match fs::metadata(&*root).await {
+                Ok(md) if !md.is_dir() => {
+                    Some(format!("root `{}` is not a directory", root.display()))
+                }
+                Ok(md) if md.permissions().readonly() => {
+                    Some(format!("root `{}` is read-only", root.display()))
+                }
+                Ok(..) => None,
+                Err(err) => Some(format!("failed to access root `{}`: {err}", root.display())),
+            };
+            links.push(LinkHealthCheck {
+                source_id,
+                target: target.clone(),
+                link_name,
+                status: if message.is_some() {
+                    LinkHealthStatus::Unhealthy
+                } else {
+                    LinkHealthStatus::Healthy
+                },
+                message,
+            });
+        }
+        Ok(links)
+    }
+
     async fn shutdown(&self) -> anyhow::Result<()> {
         self.config.write().await.drain();
         Ok(())
@@ -687,6 +2232,11 @@ mod tests {
             "test_source".to_string(),
             FsProviderConfig {
                 root: Arc::new(root_path.clone()),
+                quota: None,
+                layout: Layout::Flat,
+                checksums: false,
+                versions: 0,
+                link_name: "default".into(),
             },
         );
         let provider = FsProvider { config };
@@ -726,4 +2276,805 @@ mod tests {
         let contents = tokio::fs::read_to_string(file_path).await.unwrap();
         assert_eq!(contents, "Hello, world!");
     }
+
+    #[tokio::test]
+    async fn object_attributes() {
+        let temp_dir = tempdir().unwrap();
+        let config = Arc::new(RwLock::new(HashMap::from([(
+            "test_source".to_string(),
+            FsProviderConfig {
+                root: Arc::new(temp_dir.path().to_path_buf()),
+                quota: None,
+                layout: Layout::Flat,
+                checksums: false,
+                versions: 0,
+                link_name: "default".into(),
+            },
+        )])));
+        let provider = FsProvider { config };
+        let cx = || {
+            Some(Context {
+                component: Some("test_source".to_string()),
+                ..Default::default()
+            })
+        };
+        let id = |object: &str| metadata::ObjectId {
+            container: "container".to_string(),
+            object: object.to_string(),
+        };
+        let object_id = |object: &str| ObjectId {
+            container: "container".to_string(),
+            object: object.to_string(),
+        };
+
+        provider
+            .write_container_data(
+                cx(),
+                object_id("a.txt"),
+                Box::pin(stream::iter([Bytes::from("hello")])),
+            )
+            .await
+            .unwrap()
+            .unwrap()
+            .await
+            .unwrap();
+
+        let attributes = metadata::ObjectAttributes {
+            content_type: Some("text/plain".to_string()),
+            metadata: vec![("owner".to_string(), "test".to_string())],
+        };
+        metadata::Handler::set_object_attributes(&provider, cx(), id("a.txt"), attributes.clone())
+            .await
+            .unwrap()
+            .unwrap();
+        metadata::Handler::set_object_attributes(
+            &provider,
+            cx(),
+            id("missing"),
+            attributes.clone(),
+        )
+        .await
+        .unwrap()
+        .expect_err("attributes of missing objects should not be set");
+
+        let info = metadata::Handler::get_object_info(&provider, cx(), id("a.txt"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(info.name, "a.txt");
+        assert_eq!(info.size, 5);
+        assert_eq!(info.attributes.content_type, attributes.content_type);
+        assert_eq!(info.attributes.metadata, attributes.metadata);
+
+        // Attributes follow copied objects and are not listed as objects
+        provider
+            .copy_object(cx(), object_id("a.txt"), object_id("b.txt"))
+            .await
+            .unwrap()
+            .unwrap();
+        let info = metadata::Handler::get_object_info(&provider, cx(), id("b.txt"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(info.attributes.content_type, attributes.content_type);
+        let (names, _) = provider
+            .list_container_objects(cx(), "container".to_string(), None, None)
+            .await
+            .unwrap()
+            .unwrap();
+        let mut names: Vec<_> = names.collect::<Vec<_>>().await.concat();
+        names.sort();
+        assert_eq!(names, ["a.txt", "b.txt"]);
+
+        // Attributes are replaced on write
+        provider
+            .write_container_data(
+                cx(),
+                object_id("b.txt"),
+                Box::pin(stream::iter([Bytes::from("world")])),
+            )
+            .await
+            .unwrap()
+            .unwrap()
+            .await
+            .unwrap();
+        let info = metadata::Handler::get_object_info(&provider, cx(), id("b.txt"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(info.attributes.content_type, None);
+        assert!(info.attributes.metadata.is_empty());
+
+        provider
+            .delete_object(cx(), object_id("a.txt"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!temp_dir
+            .path()
+            .join("container")
+            .join(METADATA_DIR)
+            .join("a.txt.json")
+            .exists());
+        assert!(
+            provider
+                .write_container_data(
+                    cx(),
+                    object_id(&format!("{METADATA_DIR}/a.txt.json")),
+                    Box::pin(stream::iter([Bytes::from("{}")])),
+                )
+                .await
+                .unwrap()
+                .is_err(),
+            "objects should not be written to the metadata directory"
+        );
+    }
+
+    #[tokio::test]
+    async fn checksums() {
+        let temp_dir = tempdir().unwrap();
+        let config = Arc::new(RwLock::new(HashMap::from([(
+            "test_source".to_string(),
+            FsProviderConfig {
+                root: Arc::new(temp_dir.path().to_path_buf()),
+                quota: None,
+                layout: Layout::Flat,
+                checksums: true,
+                versions: 0,
+                link_name: "default".into(),
+            },
+        )])));
+        let provider = FsProvider { config };
+        let cx = || {
+            Some(Context {
+                component: Some("test_source".to_string()),
+                ..Default::default()
+            })
+        };
+        let id = |object: &str| integrity::ObjectId {
+            container: "container".to_string(),
+            object: object.to_string(),
+        };
+        let object_id = |object: &str| ObjectId {
+            container: "container".to_string(),
+            object: object.to_string(),
+        };
+
+        provider
+            .write_container_data(
+                cx(),
+                object_id("a.txt"),
+                Box::pin(stream::iter([Bytes::from("hello"), Bytes::from(" world")])),
+            )
+            .await
+            .unwrap()
+            .unwrap()
+            .await
+            .unwrap();
+        let checksum = integrity::Handler::get_checksum(&provider, cx(), id("a.txt"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            checksum.as_deref(),
+            Some("b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9")
+        );
+        assert!(
+            integrity::Handler::verify_object(&provider, cx(), id("a.txt"))
+                .await
+                .unwrap()
+                .unwrap()
+        );
+
+        // Checksums are retained when attributes are set and follow copied objects
+        metadata::Handler::set_object_attributes(
+            &provider,
+            cx(),
+            metadata::ObjectId {
+                container: "container".to_string(),
+                object: "a.txt".to_string(),
+            },
+            metadata::ObjectAttributes {
+                content_type: Some("text/plain".to_string()),
+                metadata: vec![],
+            },
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        provider
+            .copy_object(cx(), object_id("a.txt"), object_id("b.txt"))
+            .await
+            .unwrap()
+            .unwrap();
+        let copied = integrity::Handler::get_checksum(&provider, cx(), id("b.txt"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(copied, checksum);
+
+        // Writes report the number of bytes written and the checksum as etag
+        let written = writes::Handler::write_data(
+            &provider,
+            cx(),
+            writes::ObjectId {
+                container: "container".to_string(),
+                object: "c.txt".to_string(),
+            },
+            Box::pin(stream::iter([Bytes::from("hello"), Bytes::from(" world")])),
+        )
+        .await
+        .unwrap()
+        .unwrap()
+        .await
+        .unwrap();
+        assert_eq!(written.bytes_written, 11);
+        assert_eq!(written.etag, checksum);
+        assert_eq!(written.version_id, None);
+
+        // Corruption of the object is detected
+        fs::write(temp_dir.path().join("container/b.txt"), "hello w0rld")
+            .await
+            .unwrap();
+        assert!(
+            !integrity::Handler::verify_object(&provider, cx(), id("b.txt"))
+                .await
+                .unwrap()
+                .unwrap()
+        );
+
+        integrity::Handler::get_checksum(&provider, cx(), id("missing"))
+            .await
+            .unwrap()
+            .expect_err("checksums of missing objects should not be returned");
+    }
+
+    #[tokio::test]
+    async fn quota() {
+        let temp_dir = tempdir().unwrap();
+        let used = Arc::new(AtomicU64::new(0));
+        let config = Arc::new(RwLock::new(HashMap::from([(
+            "test_source".to_string(),
+            FsProviderConfig {
+                root: Arc::new(temp_dir.path().to_path_buf()),
+                quota: Some(Quota {
+                    max_bytes: 10,
+                    used: Arc::clone(&used),
+                }),
+                layout: Layout::Flat,
+                checksums: false,
+                versions: 0,
+                link_name: "default".into(),
+            },
+        )])));
+        let provider = FsProvider { config };
+        let cx = || {
+            Some(Context {
+                component: Some("test_source".to_string()),
+                ..Default::default()
+            })
+        };
+        let id = |object: &str| ObjectId {
+            container: "container".to_string(),
+            object: object.to_string(),
+        };
+        let write = |object: &'static str, data: &'static str| {
+            let provider = provider.clone();
+            async move {
+                provider
+                    .write_container_data(
+                        cx(),
+                        id(object),
+                        Box::pin(stream::iter([Bytes::from(data)])),
+                    )
+                    .await
+                    .unwrap()?
+                    .await
+            }
+        };
+
+        write("a", "123456").await.unwrap();
+        assert_eq!(used.load(Ordering::Relaxed), 6);
+        // Overwriting an object releases its previous size
+        write("a", "1234").await.unwrap();
+        assert_eq!(used.load(Ordering::Relaxed), 4);
+
+        let err = write("b", "1234567").await.unwrap_err();
+        assert!(err.contains("storage quota exceeded"), "{err}");
+        assert_eq!(used.load(Ordering::Relaxed), 4);
+        assert!(!temp_dir.path().join("container/b").exists());
+
+        write("b", "123456").await.unwrap();
+        assert_eq!(used.load(Ordering::Relaxed), 10);
+        let err = write("c", "1").await.unwrap_err();
+        assert!(err.contains("storage quota exceeded"), "{err}");
+        assert!(provider
+            .copy_object(cx(), id("a"), id("c"))
+            .await
+            .unwrap()
+            .is_err());
+
+        provider
+            .delete_object(cx(), id("b"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(used.load(Ordering::Relaxed), 4);
+        provider
+            .copy_object(cx(), id("a"), id("c"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(used.load(Ordering::Relaxed), 8);
+        assert_eq!(dir_size(temp_dir.path()).await.unwrap(), 8);
+
+        provider
+            .clear_container(cx(), "container".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(used.load(Ordering::Relaxed), 0);
+    }
+
+    #[tokio::test]
+    async fn list_objects() {
+        let temp_dir = tempdir().unwrap();
+        let config = Arc::new(RwLock::new(HashMap::from([(
+            "test_source".to_string(),
+            FsProviderConfig {
+                root: Arc::new(temp_dir.path().to_path_buf()),
+                quota: None,
+                layout: Layout::Flat,
+                checksums: false,
+                versions: 0,
+                link_name: "default".into(),
+            },
+        )])));
+        let provider = FsProvider { config };
+        let cx = || {
+            Some(Context {
+                component: Some("test_source".to_string()),
+                ..Default::default()
+            })
+        };
+        for name in [
+            "a.txt",
+            "docs/b.txt",
+            "docs/img/c.png",
+            "docs/img/d.png",
+            "docs-old/e.txt",
+        ] {
+            provider
+                .write_container_data(
+                    cx(),
+                    ObjectId {
+                        container: "container".to_string(),
+                        object: name.to_string(),
+                    },
+                    Box::pin(stream::iter([Bytes::from("hello")])),
+                )
+                .await
+                .unwrap()
+                .unwrap()
+                .await
+                .unwrap();
+        }
+        let list = |prefix: Option<&str>,
+                    delimiter: Option<&str>,
+                    limit: Option<u64>,
+                    offset: Option<u64>| {
+            let provider = provider.clone();
+            let prefix = prefix.map(String::from);
+            let delimiter = delimiter.map(String::from);
+            async move {
+                let (names, done) = listing::Handler::list_objects(
+                    &provider,
+                    cx(),
+                    "container".to_string(),
+                    prefix,
+                    delimiter,
+                    limit,
+                    offset,
+                )
+                .await
+                .unwrap()
+                .unwrap();
+                let names = names.collect::<Vec<_>>().await.concat();
+                done.await.unwrap();
+                names
+            }
+        };
+
+        assert_eq!(
+            list(None, None, None, None).await,
+            [
+                "a.txt",
+                "docs-old/e.txt",
+                "docs/b.txt",
+                "docs/img/c.png",
+                "docs/img/d.png"
+            ]
+        );
+        assert_eq!(
+            list(None, Some("/"), None, None).await,
+            ["a.txt", "docs-old/", "docs/"]
+        );
+        assert_eq!(
+            list(Some("docs/"), Some("/"), None, None).await,
+            ["docs/b.txt", "docs/img/"]
+        );
+        assert_eq!(
+            list(Some("docs"), None, None, None).await,
+            [
+                "docs-old/e.txt",
+                "docs/b.txt",
+                "docs/img/c.png",
+                "docs/img/d.png"
+            ]
+        );
+        assert_eq!(
+            list(Some("docs/img/"), None, Some(1), Some(1)).await,
+            ["docs/img/d.png"]
+        );
+        assert!(list(Some("missing/"), Some("/"), None, None)
+            .await
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn sharded_layout() {
+        let temp_dir = tempdir().unwrap();
+        let layout = Layout::Sharded { depth: 2 };
+        let config = Arc::new(RwLock::new(HashMap::from([(
+            "test_source".to_string(),
+            FsProviderConfig {
+                root: Arc::new(temp_dir.path().to_path_buf()),
+                quota: None,
+                layout,
+                checksums: false,
+                versions: 0,
+                link_name: "default".into(),
+            },
+        )])));
+        let provider = FsProvider { config };
+        let cx = || {
+            Some(Context {
+                component: Some("test_source".to_string()),
+                ..Default::default()
+            })
+        };
+        let object_id = |object: &str| ObjectId {
+            container: "container".to_string(),
+            object: object.to_string(),
+        };
+
+        provider
+            .create_container(cx(), "container".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        for name in ["a.txt", "b.txt", "dir/c.txt"] {
+            provider
+                .write_container_data(
+                    cx(),
+                    object_id(name),
+                    Box::pin(stream::iter([Bytes::from("hello")])),
+                )
+                .await
+                .unwrap()
+                .unwrap()
+                .await
+                .unwrap();
+        }
+        let container = temp_dir.path().join("container");
+        assert!(container
+            .join(layout.shard("dir"))
+            .join("dir/c.txt")
+            .exists());
+        assert!(!container.join("a.txt").exists());
+
+        let (names, _) = provider
+            .list_container_objects(cx(), "container".to_string(), None, None)
+            .await
+            .unwrap()
+            .unwrap();
+        // Names are listed in order across shards
+        let names: Vec<_> = names.collect::<Vec<_>>().await.concat();
+        assert_eq!(names, ["a.txt", "b.txt", "dir/c.txt"]);
+
+        provider
+            .move_object(cx(), object_id("a.txt"), object_id("d.txt"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(container.join(layout.shard("d.txt")).join("d.txt").exists());
+        provider
+            .delete_objects(cx(), "container".to_string(), vec!["d.txt".to_string()])
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!provider
+            .has_object(cx(), object_id("d.txt"))
+            .await
+            .unwrap()
+            .unwrap());
+        assert!(provider
+            .has_object(cx(), object_id("b.txt"))
+            .await
+            .unwrap()
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn versions() {
+        let temp_dir = tempdir().unwrap();
+        let config = Arc::new(RwLock::new(HashMap::from([(
+            "test_source".to_string(),
+            FsProviderConfig {
+                root: Arc::new(temp_dir.path().to_path_buf()),
+                quota: None,
+                layout: Layout::Flat,
+                checksums: true,
+                versions: 2,
+                link_name: "default".into(),
+            },
+        )])));
+        let provider = FsProvider { config };
+        let cx = || {
+            Some(Context {
+                component: Some("test_source".to_string()),
+                ..Default::default()
+            })
+        };
+        let id = || versioning::ObjectId {
+            container: "container".to_string(),
+            object: "config.json".to_string(),
+        };
+        let write = |data: &'static str| {
+            let provider = provider.clone();
+            async move {
+                versioning::Handler::write_object(
+                    &provider,
+                    cx(),
+                    id(),
+                    Box::pin(stream::iter([Bytes::from(data)])),
+                )
+                .await
+                .unwrap()
+                .unwrap()
+                .await
+                .unwrap()
+            }
+        };
+        let read = |version_id: &str| {
+            let provider = provider.clone();
+            let version_id = version_id.to_string();
+            async move {
+                let (data, _) = versioning::Handler::get_object_version(
+                    &provider,
+                    cx(),
+                    id(),
+                    version_id,
+                    0,
+                    u64::MAX,
+                )
+                .await
+                .unwrap()
+                .unwrap();
+                data.map(|chunk| String::from_utf8(chunk.to_vec()).unwrap())
+                    .collect::<String>()
+                    .await
+            }
+        };
+        let list = || {
+            let provider = provider.clone();
+            async move {
+                let (versions, _) = versioning::Handler::list_object_versions(
+                    &provider,
+                    cx(),
+                    "container".to_string(),
+                    None,
+                    None,
+                )
+                .await
+                .unwrap()
+                .unwrap();
+                versions
+                    .flat_map(stream::iter)
+                    .map(|v| (v.version_id, v.is_latest))
+                    .collect::<Vec<_>>()
+                    .await
+            }
+        };
+
+        assert_eq!(write("v1").await.as_deref(), Some("gen-1"));
+        assert_eq!(write("v2").await.as_deref(), Some("gen-2"));
+        assert_eq!(write("v3").await.as_deref(), Some("gen-3"));
+        assert_eq!(read("gen-1").await, "v1");
+        assert_eq!(read("gen-3").await, "v3");
+
+        // Only the 2 newest previous versions are kept
+        assert_eq!(write("v4").await.as_deref(), Some("gen-4"));
+        assert_eq!(
+            list().await,
+            [
+                ("gen-4".to_string(), true),
+                ("gen-3".to_string(), false),
+                ("gen-2".to_string(), false),
+            ]
+        );
+        assert!(fs::try_exists(
+            temp_dir
+                .path()
+                .join("container/.wasmcloud-versions/config.json.gen-2")
+        )
+        .await
+        .unwrap());
+
+        // Previous versions are not listed as objects
+        let (names, _) = provider
+            .list_container_objects(cx(), "container".to_string(), None, None)
+            .await
+            .unwrap()
+            .unwrap();
+        let names: Vec<_> = names.flat_map(stream::iter).collect().await;
+        assert_eq!(names, ["config.json"]);
+
+        // Restoring a version writes it as a new generation, with its checksum
+        let restored =
+            versioning::Handler::restore_object_version(&provider, cx(), id(), "gen-2".to_string())
+                .await
+                .unwrap()
+                .unwrap();
+        assert_eq!(restored.as_deref(), Some("gen-5"));
+        assert_eq!(read("gen-5").await, "v2");
+        assert!(integrity::Handler::verify_object(
+            &provider,
+            cx(),
+            integrity::ObjectId {
+                container: "container".to_string(),
+                object: "config.json".to_string(),
+            },
+        )
+        .await
+        .unwrap()
+        .unwrap());
+        assert_eq!(
+            list().await,
+            [
+                ("gen-5".to_string(), true),
+                ("gen-4".to_string(), false),
+                ("gen-3".to_string(), false),
+            ]
+        );
+
+        // Deleted versions can no longer be read
+        versioning::Handler::delete_object_version(&provider, cx(), id(), "gen-3".to_string())
+            .await
+            .unwrap()
+            .unwrap();
+        versioning::Handler::get_object_version(&provider, cx(), id(), "gen-3".to_string(), 0, 1)
+            .await
+            .unwrap()
+            .expect_err("deleted versions should not be read");
+        versioning::Handler::get_object_version(&provider, cx(), id(), "v1".to_string(), 0, 1)
+            .await
+            .unwrap()
+            .expect_err("invalid version IDs should be rejected");
+
+        assert_eq!(split_version("a.gen-1.gen-12"), Some(("a.gen-1", 12)));
+        assert_eq!(split_version("a.gen-1.tmp-1-2"), None);
+    }
+
+    #[tokio::test]
+    async fn concurrent_versions() {
+        let temp_dir = tempdir().unwrap();
+        let config = Arc::new(RwLock::new(HashMap::from([(
+            "test_source".to_string(),
+            FsProviderConfig {
+                root: Arc::new(temp_dir.path().to_path_buf()),
+                quota: None,
+                layout: Layout::Flat,
+                checksums: false,
+                versions: 16,
+                link_name: "default".into(),
+            },
+        )])));
+        let provider = FsProvider { config };
+        let cx = || {
+            Some(Context {
+                component: Some("test_source".to_string()),
+                ..Default::default()
+            })
+        };
+        let id = || versioning::ObjectId {
+            container: "container".to_string(),
+            object: "log.txt".to_string(),
+        };
+        let write = |data: String| {
+            let provider = provider.clone();
+            async move {
+                versioning::Handler::write_object(
+                    &provider,
+                    cx(),
+                    id(),
+                    Box::pin(stream::iter([Bytes::from(data)])),
+                )
+                .await
+                .unwrap()
+                .unwrap()
+                .await
+                .unwrap()
+                .unwrap()
+            }
+        };
+        let list = || {
+            let provider = provider.clone();
+            async move {
+                let (versions, _) = versioning::Handler::list_object_versions(
+                    &provider,
+                    cx(),
+                    "container".to_string(),
+                    None,
+                    None,
+                )
+                .await
+                .unwrap()
+                .unwrap();
+                versions
+                    .flat_map(stream::iter)
+                    .map(|v| v.version_id)
+                    .collect::<Vec<_>>()
+                    .await
+            }
+        };
+
+        // Concurrent writes are assigned distinct generations and keep every replaced object
+        let writes = (0..8).map(|i| write(format!("v{i}")));
+        let mut generations: Vec<_> = future::join_all(writes)
+            .await
+            .into_iter()
+            .map(|version_id| parse_version_id(&version_id).unwrap())
+            .collect();
+        generations.sort_unstable();
+        assert_eq!(generations, (1..=8).collect::<Vec<_>>());
+        assert_eq!(
+            list().await,
+            (1..=8).rev().map(version_id).collect::<Vec<_>>()
+        );
+        assert!(!OBJECT_LOCKS
+            .lock()
+            .unwrap()
+            .contains_key(&temp_dir.path().join("container/log.txt")));
+
+        // Generations are not reused after the object is deleted
+        Handler::delete_object(
+            &provider,
+            cx(),
+            ObjectId {
+                container: "container".to_string(),
+                object: "log.txt".to_string(),
+            },
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(write("v8".to_string()).await, "gen-9");
+
+        // Nor after all of its versions are deleted
+        for generation in (1..=9).rev() {
+            versioning::Handler::delete_object_version(
+                &provider,
+                cx(),
+                id(),
+                version_id(generation),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        }
+        assert!(list().await.is_empty());
+        assert_eq!(write("v9".to_string()).await, "gen-10");
+        assert_eq!(list().await, ["gen-10"]);
+    }
 }
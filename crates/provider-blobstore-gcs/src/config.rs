@@ -0,0 +1,195 @@
+//! Configuration for blobstore-gcs capability provider
+//!
+//! See README.md for the supported link configuration and authentication options.
+//!
+
+use core::fmt;
+
+use anyhow::{bail, Context as _, Result};
+use google_cloud_storage::client::google_cloud_auth::credentials::CredentialsFile;
+use google_cloud_storage::client::ClientConfig;
+use tracing::warn;
+use wasmcloud_provider_sdk::core::secrets::SecretValue;
+use wasmcloud_provider_sdk::LinkConfig;
+
+/// Method used to authenticate with Google Cloud Storage
+#[derive(Clone, PartialEq, Eq)]
+pub enum Auth {
+    /// Service account key in JSON format
+    ServiceAccount(String),
+    /// Application default credentials, which use the service account bound to the workload via
+    /// workload identity when running on GKE, or the file referenced by
+    /// `GOOGLE_APPLICATION_CREDENTIALS`
+    WorkloadIdentity,
+    /// Unauthenticated access, e.g. for local storage emulators
+    Anonymous,
+}
+
+impl fmt::Debug for Auth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never log the service account key
+        match self {
+            Self::ServiceAccount(..) => f.write_str("ServiceAccount(..)"),
+            Self::WorkloadIdentity => f.write_str("WorkloadIdentity"),
+            Self::Anonymous => f.write_str("Anonymous"),
+        }
+    }
+}
+
+/// Configuration for connecting to Google Cloud Storage
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StorageConfig {
+    /// Authentication method, selected with AUTH. Defaults to a service account if
+    /// SERVICE_ACCOUNT_JSON is set and to workload identity otherwise
+    pub auth: Auth,
+
+    /// PROJECT_ID, project that containers are created in. Defaults to the project of the
+    /// credentials
+    pub project_id: Option<String>,
+
+    /// STORAGE_ENDPOINT, optional custom endpoint, e.g. of a local storage emulator
+    pub endpoint: Option<String>,
+}
+
+impl StorageConfig {
+    /// Build a [`StorageConfig`] from a link configuration
+    pub fn from_link_config(
+        LinkConfig {
+            config, secrets, ..
+        }: &LinkConfig,
+    ) -> Result<StorageConfig> {
+        let service_account_json = match secrets
+            .get("service_account_json")
+            .and_then(SecretValue::as_string)
+        {
+            Some(json) => Some(json),
+            None => config.get("SERVICE_ACCOUNT_JSON").map(|json| {
+                warn!("secret [service_account_json] was not found, using [SERVICE_ACCOUNT_JSON] from configuration. Please prefer using secrets for sensitive values.");
+                json.as_str()
+            }),
+        };
+        let auth = match (
+            config.get("AUTH").map(String::as_str),
+            service_account_json,
+        ) {
+            (Some("service_account") | None, Some(json)) => Auth::ServiceAccount(json.to_string()),
+            (Some("service_account"), None) => {
+                bail!("AUTH is `service_account`, but SERVICE_ACCOUNT_JSON is not set")
+            }
+            (Some("workload_identity"), _) | (None, None) => Auth::WorkloadIdentity,
+            (Some("anonymous"), _) => Auth::Anonymous,
+            (Some(auth), _) => bail!(
+                "unsupported AUTH `{auth}`, expected `service_account`, `workload_identity` or `anonymous`"
+            ),
+        };
+        Ok(StorageConfig {
+            auth,
+            project_id: config
+                .get("PROJECT_ID")
+                .filter(|project| !project.is_empty())
+                .cloned(),
+            endpoint: config
+                .get("STORAGE_ENDPOINT")
+                .filter(|endpoint| !endpoint.is_empty())
+                .cloned(),
+        })
+    }
+
+    /// Build a client configuration, fetching credentials as required by the authentication method
+    pub async fn client_config(self) -> Result<ClientConfig> {
+        let mut client_config = match self.auth {
+            Auth::ServiceAccount(json) => {
+                let credentials = CredentialsFile::new_from_str(&json)
+                    .await
+                    .context("failed to parse service account JSON")?;
+                ClientConfig::default()
+                    .with_credentials(credentials)
+                    .await
+                    .context("failed to authenticate with service account")?
+            }
+            Auth::WorkloadIdentity => ClientConfig::default()
+                .with_auth()
+                .await
+                .context("failed to authenticate with workload identity")?,
+            Auth::Anonymous => ClientConfig::default().anonymous(),
+        };
+        if let Some(endpoint) = self.endpoint {
+            client_config.storage_endpoint = endpoint;
+        }
+        if self.project_id.is_some() {
+            client_config.project_id = self.project_id;
+        }
+        Ok(client_config)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn storage_config(
+        config: &[(&str, &str)],
+        secrets: &[(&str, &str)],
+    ) -> anyhow::Result<StorageConfig> {
+        let config: HashMap<_, _> = config
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let secrets: HashMap<_, _> = secrets
+            .iter()
+            .map(|(k, v)| (k.to_string(), SecretValue::String(v.to_string())))
+            .collect();
+        StorageConfig::from_link_config(&LinkConfig {
+            source_id: "component",
+            target_id: "provider",
+            link_name: "default",
+            config: &config,
+            secrets: &secrets,
+            wit_metadata: (&"wrpc".to_string(), &"blobstore".to_string(), &vec![]),
+        })
+    }
+
+    #[test]
+    fn parse_auth() {
+        assert_eq!(
+            storage_config(&[], &[]).unwrap().auth,
+            Auth::WorkloadIdentity
+        );
+        assert_eq!(
+            storage_config(&[], &[("service_account_json", "{}")])
+                .unwrap()
+                .auth,
+            Auth::ServiceAccount("{}".into())
+        );
+        assert_eq!(
+            storage_config(
+                &[("AUTH", "workload_identity")],
+                &[("service_account_json", "{}")]
+            )
+            .unwrap()
+            .auth,
+            Auth::WorkloadIdentity
+        );
+        let config = storage_config(
+            &[
+                ("AUTH", "anonymous"),
+                ("STORAGE_ENDPOINT", "http://localhost:4443"),
+                ("PROJECT_ID", "test"),
+            ],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            StorageConfig {
+                auth: Auth::Anonymous,
+                project_id: Some("test".into()),
+                endpoint: Some("http://localhost:4443".into()),
+            }
+        );
+        assert!(storage_config(&[("AUTH", "service_account")], &[]).is_err());
+        assert!(storage_config(&[("AUTH", "hmac")], &[]).is_err());
+    }
+}
@@ -0,0 +1,774 @@
+#![allow(clippy::type_complexity)]
+
+use core::future::Future;
+use core::pin::Pin;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{bail, Context as _, Result};
+use bytes::{Bytes, BytesMut};
+use futures::{stream, Stream, StreamExt as _, TryStreamExt as _};
+use google_cloud_storage::client::Client;
+use google_cloud_storage::http::buckets::delete::DeleteBucketRequest;
+use google_cloud_storage::http::buckets::get::GetBucketRequest;
+use google_cloud_storage::http::buckets::insert::{InsertBucketParam, InsertBucketRequest};
+use google_cloud_storage::http::objects::delete::DeleteObjectRequest;
+use google_cloud_storage::http::objects::download::Range;
+use google_cloud_storage::http::objects::get::GetObjectRequest;
+use google_cloud_storage::http::objects::list::ListObjectsRequest;
+use google_cloud_storage::http::objects::rewrite::RewriteObjectRequest;
+use google_cloud_storage::http::objects::upload::{Media, UploadObjectRequest, UploadType};
+use google_cloud_storage::http::objects::Object;
+use google_cloud_storage::http::resumable_upload_client::{ChunkSize, UploadStatus};
+use google_cloud_storage::http::Error as GcsError;
+use tokio::sync::RwLock;
+use tracing::{error, instrument};
+use wasmcloud_provider_sdk::stream::{
+    stream_batches, stream_bytes, DEFAULT_BATCH_SIZE, DEFAULT_BUFFER, DEFAULT_CHUNK_SIZE,
+};
+use wasmcloud_provider_sdk::{
+    get_connection, initialize_observability, load_host_data, propagate_trace_for_ctx,
+    run_provider, serve_exports, Context, HostData, LinkConfig, LinkDeleteInfo, Provider,
+};
+use wrpc_interface_blobstore::bindings::{
+    exports::wrpc::blobstore::blobstore::Handler,
+    serve,
+    wrpc::blobstore::types::{ContainerMetadata, ObjectId, ObjectMetadata},
+};
+
+use bindings::exports::wasmcloud::blobstore::writes;
+use config::StorageConfig;
+
+mod config;
+
+mod bindings {
+    wit_bindgen_wrpc::generate!({
+        world: "extensions",
+        with: {
+            "wasmcloud:blobstore/writes@0.1.0-draft": generate,
+        }
+    });
+}
+
+/// Size of the chunks of a resumable upload. GCS requires all but the last chunk to be a multiple
+/// of 256 KiB. At most one chunk is buffered in memory per write.
+const UPLOAD_CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Maximum number of objects requested per page of a listing, which is the maximum supported by
+/// GCS
+const MAX_LIST_PAGE_SIZE: i32 = 1000;
+
+/// GCS client along with the project that containers are created in
+#[derive(Clone)]
+struct GcsClient {
+    client: Client,
+    project_id: Option<String>,
+}
+
+/// Blobstore GCS provider
+///
+/// This struct will be the target of generated implementations (via wit-provider-bindgen)
+/// for the blobstore provider WIT contract
+#[derive(Default, Clone)]
+pub struct BlobstoreGcsProvider {
+    /// Per-config storage for GCS clients
+    config: Arc<RwLock<HashMap<String, GcsClient>>>,
+}
+
+pub async fn run() -> anyhow::Result<()> {
+    BlobstoreGcsProvider::run().await
+}
+
+/// Handle provider control commands
+/// put_link (new component link command), del_link (remove link command), and shutdown
+impl Provider for BlobstoreGcsProvider {
+    #[instrument(level = "info", skip_all)]
+    async fn receive_link_config_as_target(
+        &self,
+        link_config: LinkConfig<'_>,
+    ) -> anyhow::Result<()> {
+        let config = match StorageConfig::from_link_config(&link_config) {
+            Ok(v) => v,
+            Err(e) => {
+                error!(error = %e, source_id = %link_config.source_id, "failed to read storage config");
+                return Err(e);
+            }
+        };
+        let client_config = match config.client_config().await {
+            Ok(v) => v,
+            Err(e) => {
+                error!(error = %e, source_id = %link_config.source_id, "failed to configure GCS client");
+                return Err(e);
+            }
+        };
+        let project_id = client_config.project_id.clone();
+        let client = Client::new(client_config);
+
+        let mut update_map = self.config.write().await;
+        update_map.insert(
+            link_config.source_id.to_string(),
+            GcsClient { client, project_id },
+        );
+
+        Ok(())
+    }
+
+    #[instrument(level = "info", skip_all, fields(source_id = info.get_source_id()))]
+    async fn delete_link_as_target(&self, info: impl LinkDeleteInfo) -> anyhow::Result<()> {
+        let component_id = info.get_source_id();
+        self.config.write().await.remove(component_id);
+        Ok(())
+    }
+
+    async fn shutdown(&self) -> anyhow::Result<()> {
+        self.config.write().await.drain();
+        Ok(())
+    }
+}
+
+impl BlobstoreGcsProvider {
+    pub async fn run() -> anyhow::Result<()> {
+        let HostData { config, .. } = load_host_data().context("failed to load host data")?;
+        let flamegraph_path = config
+            .get("FLAMEGRAPH_PATH")
+            .map(String::from)
+            .or_else(|| std::env::var("PROVIDER_BLOBSTORE_GCS_FLAMEGRAPH_PATH").ok());
+        initialize_observability!("blobstore-gcs-provider", flamegraph_path);
+
+        let provider = Self::default();
+        let shutdown = run_provider(provider.clone(), "blobstore-gcs-provider")
+            .await
+            .context("failed to run provider")?;
+        let connection = get_connection();
+        let wrpc = connection
+            .get_wrpc_client(connection.provider_key())
+            .await?;
+        serve_exports!(&wrpc, provider, shutdown, [serve, bindings::serve])
+            .await
+            .context("failed to serve provider exports")
+    }
+
+    async fn get_config(&self, context: Option<&Context>) -> anyhow::Result<GcsClient> {
+        if let Some(source_id) = context.and_then(|Context { component, .. }| component.as_ref()) {
+            self.config
+                .read()
+                .await
+                .get(source_id)
+                .with_context(|| format!("failed to lookup {source_id} configuration"))
+                .cloned()
+        } else {
+            bail!("failed to lookup source of invocation, could not construct GCS blobstore client")
+        }
+    }
+}
+
+impl Handler<Option<Context>> for BlobstoreGcsProvider {
+    #[instrument(level = "trace", skip(self))]
+    async fn clear_container(
+        &self,
+        cx: Option<Context>,
+        name: String,
+    ) -> anyhow::Result<Result<(), String>> {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let GcsClient { client, .. } = self
+                .get_config(cx.as_ref())
+                .await
+                .context("failed to retrieve GCS blobstore client")?;
+
+            let mut objects = Box::pin(list_objects(client.clone(), name.clone(), None, None));
+            while let Some(object) = objects.try_next().await? {
+                client
+                    .delete_object(&DeleteObjectRequest {
+                        bucket: name.clone(),
+                        object: object.name.clone(),
+                        ..Default::default()
+                    })
+                    .await
+                    .with_context(|| {
+                        format!("failed to delete object '{}' in '{name}'", object.name)
+                    })?;
+            }
+            Ok(())
+        }
+        .await
+        .map_err(|err: anyhow::Error| format!("{err:#}")))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn container_exists(
+        &self,
+        cx: Option<Context>,
+        name: String,
+    ) -> anyhow::Result<Result<bool, String>> {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let GcsClient { client, .. } = self
+                .get_config(cx.as_ref())
+                .await
+                .context("failed to retrieve GCS blobstore client")?;
+
+            match client
+                .get_bucket(&GetBucketRequest {
+                    bucket: name,
+                    ..Default::default()
+                })
+                .await
+            {
+                Ok(..) => Ok(true),
+                Err(err) if is_not_found(&err) => Ok(false),
+                Err(err) => Err(err).context("failed to check container existence"),
+            }
+        }
+        .await
+        .map_err(|err: anyhow::Error| format!("{err:#}")))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn create_container(
+        &self,
+        cx: Option<Context>,
+        name: String,
+    ) -> anyhow::Result<Result<(), String>> {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let GcsClient { client, project_id } = self
+                .get_config(cx.as_ref())
+                .await
+                .context("failed to retrieve GCS blobstore client")?;
+
+            let project = project_id.context(
+                "project to create container in is unknown, set PROJECT_ID in link configuration",
+            )?;
+            client
+                .insert_bucket(&InsertBucketRequest {
+                    name,
+                    param: InsertBucketParam {
+                        project,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .await
+                .map(|_| ())
+                .context("failed to create container")
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn delete_container(
+        &self,
+        cx: Option<Context>,
+        name: String,
+    ) -> anyhow::Result<Result<(), String>> {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let GcsClient { client, .. } = self
+                .get_config(cx.as_ref())
+                .await
+                .context("failed to retrieve GCS blobstore client")?;
+
+            client
+                .delete_bucket(&DeleteBucketRequest {
+                    bucket: name,
+                    ..Default::default()
+                })
+                .await
+                .context("failed to delete container")
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_container_info(
+        &self,
+        cx: Option<Context>,
+        name: String,
+    ) -> anyhow::Result<Result<ContainerMetadata, String>> {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let GcsClient { client, .. } = self
+                .get_config(cx.as_ref())
+                .await
+                .context("failed to retrieve GCS blobstore client")?;
+
+            let bucket = client
+                .get_bucket(&GetBucketRequest {
+                    bucket: name,
+                    ..Default::default()
+                })
+                .await
+                .context("failed to get container")?;
+
+            // NOTE: The `created_at` format is currently undefined
+            // https://github.com/WebAssembly/wasi-blobstore/issues/7
+            let created_at = bucket
+                .time_created
+                .map(|created| created.unix_timestamp().try_into())
+                .transpose()
+                .context("failed to convert created_at date to u64")?
+                .unwrap_or_default();
+            anyhow::Ok(ContainerMetadata { created_at })
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn list_container_objects(
+        &self,
+        cx: Option<Context>,
+        name: String,
+        limit: Option<u64>,
+        offset: Option<u64>,
+    ) -> anyhow::Result<
+        Result<
+            (
+                Pin<Box<dyn Stream<Item = Vec<String>> + Send>>,
+                Pin<Box<dyn Future<Output = Result<(), String>> + Send>>,
+            ),
+            String,
+        >,
+    > {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let GcsClient { client, .. } = self
+                .get_config(cx.as_ref())
+                .await
+                .context("failed to retrieve GCS blobstore client")?;
+
+            let names =
+                list_objects(client, name, limit, offset).map_ok(|Object { name, .. }| name);
+            anyhow::Ok(stream_batches(names, DEFAULT_BATCH_SIZE, DEFAULT_BUFFER))
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn copy_object(
+        &self,
+        cx: Option<Context>,
+        src: ObjectId,
+        dest: ObjectId,
+    ) -> anyhow::Result<Result<(), String>> {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let GcsClient { client, .. } = self
+                .get_config(cx.as_ref())
+                .await
+                .context("failed to retrieve GCS blobstore client")?;
+
+            copy(&client, src, dest)
+                .await
+                .context("failed to copy source object")
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn delete_object(
+        &self,
+        cx: Option<Context>,
+        id: ObjectId,
+    ) -> anyhow::Result<Result<(), String>> {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let GcsClient { client, .. } = self
+                .get_config(cx.as_ref())
+                .await
+                .context("failed to retrieve GCS blobstore client")?;
+
+            client
+                .delete_object(&DeleteObjectRequest {
+                    bucket: id.container,
+                    object: id.object,
+                    ..Default::default()
+                })
+                .await
+                .context("failed to delete object")
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn delete_objects(
+        &self,
+        cx: Option<Context>,
+        container: String,
+        objects: Vec<String>,
+    ) -> anyhow::Result<Result<(), String>> {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let GcsClient { client, .. } = self
+                .get_config(cx.as_ref())
+                .await
+                .context("failed to retrieve GCS blobstore client")?;
+
+            let deletes = objects.into_iter().map(|object| {
+                let client = &client;
+                let bucket = container.clone();
+                async move {
+                    client
+                        .delete_object(&DeleteObjectRequest {
+                            bucket,
+                            object,
+                            ..Default::default()
+                        })
+                        .await
+                }
+            });
+            futures::future::join_all(deletes)
+                .await
+                .into_iter()
+                .collect::<Result<Vec<_>, GcsError>>()
+                .map(|_| ())
+                .context("failed to delete objects")
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_container_data(
+        &self,
+        cx: Option<Context>,
+        id: ObjectId,
+        start: u64,
+        end: u64,
+    ) -> anyhow::Result<
+        Result<
+            (
+                Pin<Box<dyn Stream<Item = Bytes> + Send>>,
+                Pin<Box<dyn Future<Output = Result<(), String>> + Send>>,
+            ),
+            String,
+        >,
+    > {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let GcsClient { client, .. } = self
+                .get_config(cx.as_ref())
+                .await
+                .context("failed to retrieve GCS blobstore client")?;
+
+            let data = client
+                .download_streamed_object(
+                    &GetObjectRequest {
+                        bucket: id.container,
+                        object: id.object,
+                        ..Default::default()
+                    },
+                    &Range(Some(start), Some(end)),
+                )
+                .await
+                .context("failed to get object")?
+                .map(|res| res.context("failed to receive bytes"));
+            anyhow::Ok(stream_bytes(data, DEFAULT_CHUNK_SIZE, DEFAULT_BUFFER))
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_object_info(
+        &self,
+        cx: Option<Context>,
+        id: ObjectId,
+    ) -> anyhow::Result<Result<ObjectMetadata, String>> {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let GcsClient { client, .. } = self
+                .get_config(cx.as_ref())
+                .await
+                .context("failed to retrieve GCS blobstore client")?;
+
+            let object = client
+                .get_object(&GetObjectRequest {
+                    bucket: id.container,
+                    object: id.object,
+                    ..Default::default()
+                })
+                .await
+                .context("failed to get object")?;
+
+            // NOTE: The `created_at` format is currently undefined
+            // https://github.com/WebAssembly/wasi-blobstore/issues/7
+            let created_at = object
+                .time_created
+                .map(|created| created.unix_timestamp().try_into())
+                .transpose()
+                .context("failed to convert created_at date to u64")?
+                .unwrap_or_default();
+            let size = object
+                .size
+                .try_into()
+                .context("failed to convert object size to u64")?;
+            anyhow::Ok(ObjectMetadata { created_at, size })
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn has_object(
+        &self,
+        cx: Option<Context>,
+        id: ObjectId,
+    ) -> anyhow::Result<Result<bool, String>> {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let GcsClient { client, .. } = self
+                .get_config(cx.as_ref())
+                .await
+                .context("failed to retrieve GCS blobstore client")?;
+
+            match client
+                .get_object(&GetObjectRequest {
+                    bucket: id.container,
+                    object: id.object,
+                    ..Default::default()
+                })
+                .await
+            {
+                Ok(..) => Ok(true),
+                Err(err) if is_not_found(&err) => Ok(false),
+                Err(err) => Err(err).context("failed to check object existence"),
+            }
+        }
+        .await
+        .map_err(|err: anyhow::Error| format!("{err:#}")))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn move_object(
+        &self,
+        cx: Option<Context>,
+        src: ObjectId,
+        dest: ObjectId,
+    ) -> anyhow::Result<Result<(), String>> {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let GcsClient { client, .. } = self
+                .get_config(cx.as_ref())
+                .await
+                .context("failed to retrieve GCS blobstore client")?;
+
+            // Copy and then delete the source object
+            copy(&client, src.clone(), dest)
+                .await
+                .context("failed to copy source object to move")?;
+            client
+                .delete_object(&DeleteObjectRequest {
+                    bucket: src.container,
+                    object: src.object,
+                    ..Default::default()
+                })
+                .await
+                .context("failed to delete source object")
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "trace", skip(self, data))]
+    async fn write_container_data(
+        &self,
+        cx: Option<Context>,
+        id: ObjectId,
+        data: Pin<Box<dyn Stream<Item = Bytes> + Send>>,
+    ) -> anyhow::Result<Result<Pin<Box<dyn Future<Output = Result<(), String>> + Send>>, String>>
+    {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let GcsClient { client, .. } = self
+                .get_config(cx.as_ref())
+                .await
+                .context("failed to retrieve GCS blobstore client")?;
+            anyhow::Ok(Box::pin(async move {
+                write_object(&client, id, data)
+                    .await
+                    .map(|_| ())
+                    .map_err(|err| format!("{err:#}"))
+            }) as Pin<Box<dyn Future<Output = _> + Send>>)
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+}
+
+impl writes::Handler<Option<Context>> for BlobstoreGcsProvider {
+    #[instrument(level = "trace", skip(self, data))]
+    async fn write_data(
+        &self,
+        cx: Option<Context>,
+        writes::ObjectId { container, object }: writes::ObjectId,
+        data: Pin<Box<dyn Stream<Item = Bytes> + Send>>,
+    ) -> anyhow::Result<
+        Result<Pin<Box<dyn Future<Output = Result<writes::WriteResult, String>> + Send>>, String>,
+    > {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let GcsClient { client, .. } = self
+                .get_config(cx.as_ref())
+                .await
+                .context("failed to retrieve GCS blobstore client")?;
+            anyhow::Ok(Box::pin(async move {
+                async {
+                    let object =
+                        write_object(&client, ObjectId { container, object }, data).await?;
+                    // The generation of an object identifies its version, even if versioning is
+                    // not enabled for the bucket
+                    anyhow::Ok(writes::WriteResult {
+                        bytes_written: object.size.try_into().context("invalid object size")?,
+                        etag: Some(object.etag),
+                        version_id: Some(object.generation.to_string()),
+                    })
+                }
+                .await
+                .map_err(|err| format!("{err:#}"))
+            }) as Pin<Box<dyn Future<Output = _> + Send>>)
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+}
+
+/// Whether `err` is a response to a request for a missing bucket or object
+fn is_not_found(err: &GcsError) -> bool {
+    matches!(err, GcsError::Response(err) if err.code == 404)
+}
+
+/// List objects in the bucket, skipping the first `offset` objects and returning at most `limit`
+/// objects.
+///
+/// Pages are requested lazily, following the page token of the previous page, and no larger than
+/// necessary to satisfy `offset` and `limit`
+fn list_objects(
+    client: Client,
+    bucket: String,
+    limit: Option<u64>,
+    offset: Option<u64>,
+) -> impl Stream<Item = anyhow::Result<Object>> + Send + 'static {
+    let offset = offset.unwrap_or_default().try_into().unwrap_or(usize::MAX);
+    let limit = limit
+        .and_then(|limit| limit.try_into().ok())
+        .unwrap_or(usize::MAX);
+    let page_size = offset
+        .saturating_add(limit)
+        .clamp(1, MAX_LIST_PAGE_SIZE as usize) as i32;
+    // The state is `None` once the last page was received
+    stream::try_unfold(Some(None), move |page_token: Option<Option<String>>| {
+        let client = client.clone();
+        let bucket = bucket.clone();
+        async move {
+            let Some(page_token) = page_token else {
+                return Ok(None);
+            };
+            let res = client
+                .list_objects(&ListObjectsRequest {
+                    bucket,
+                    page_token,
+                    max_results: Some(page_size),
+                    ..Default::default()
+                })
+                .await
+                .context("failed to list objects")?;
+            let objects = res.items.unwrap_or_default();
+            anyhow::Ok(Some((
+                stream::iter(objects.into_iter().map(anyhow::Ok)),
+                res.next_page_token.map(Some),
+            )))
+        }
+    })
+    .try_flatten()
+    .skip(offset)
+    .take(limit)
+}
+
+/// Copy `src` to `dest`. Objects are copied by rewriting them, which may take multiple requests
+/// for large objects or objects copied across locations or storage classes
+async fn copy(client: &Client, src: ObjectId, dest: ObjectId) -> anyhow::Result<()> {
+    let mut req = RewriteObjectRequest {
+        source_bucket: src.container,
+        source_object: src.object,
+        destination_bucket: dest.container,
+        destination_object: dest.object,
+        ..Default::default()
+    };
+    loop {
+        let res = client
+            .rewrite_object(&req)
+            .await
+            .context("failed to rewrite object")?;
+        if res.done {
+            return Ok(());
+        }
+        req.rewrite_token = res.rewrite_token;
+    }
+}
+
+/// Write `data` to an object, returning the written object. Data fitting in a single chunk is
+/// uploaded in one request, larger data is uploaded in a resumable upload of
+/// [`UPLOAD_CHUNK_SIZE`] chunks.
+async fn write_object(
+    client: &Client,
+    id: ObjectId,
+    mut data: Pin<Box<dyn Stream<Item = Bytes> + Send>>,
+) -> anyhow::Result<Object> {
+    let req = UploadObjectRequest {
+        bucket: id.container,
+        ..Default::default()
+    };
+    let upload_type = UploadType::Simple(Media::new(id.object));
+    let mut buf = BytesMut::new();
+    let mut upload = None;
+    let mut uploaded = 0u64;
+    while let Some(chunk) = data.next().await {
+        buf.extend_from_slice(&chunk);
+        // A chunk is only uploaded once more data follows it, so that the last chunk, which
+        // completes the upload, is never empty
+        while buf.len() > UPLOAD_CHUNK_SIZE {
+            let uploader = match upload.take() {
+                Some(uploader) => uploader,
+                None => client
+                    .prepare_resumable_upload(&req, &upload_type)
+                    .await
+                    .context("failed to start resumable upload")?,
+            };
+            let chunk = buf.split_to(UPLOAD_CHUNK_SIZE).freeze();
+            let len = chunk.len() as u64;
+            uploader
+                .upload_multiple_chunk(chunk, &ChunkSize::new(uploaded, uploaded + len - 1, None))
+                .await
+                .with_context(|| format!("failed to upload chunk at offset {uploaded}"))?;
+            uploaded += len;
+            upload = Some(uploader);
+        }
+    }
+    let Some(uploader) = upload else {
+        return client
+            .upload_object(&req, buf.freeze(), &upload_type)
+            .await
+            .context("failed to write container data");
+    };
+    let len = buf.len() as u64;
+    let total = uploaded + len;
+    match uploader
+        .upload_multiple_chunk(
+            buf.freeze(),
+            &ChunkSize::new(uploaded, total - 1, Some(total)),
+        )
+        .await
+        .context("failed to complete resumable upload")?
+    {
+        UploadStatus::Ok(object) => Ok(object),
+        _ => bail!("resumable upload was not completed"),
+    }
+}
@@ -0,0 +1,306 @@
+//! Circuit breaker failing S3 operations fast while S3 is persistently unavailable.
+//!
+//! The breaker is installed as an interceptor of the S3 client, so that it applies to every
+//! operation after the retries of the operation are exhausted. Once `threshold` consecutive
+//! operations failed, the circuit opens and operations fail without being sent to S3 for
+//! `cooldown`. Afterwards a single probe operation is let through, which closes the circuit on
+//! success and opens it again on failure. A probe that is cancelled before completing lets the
+//! next operation probe instead.
+
+use core::time::Duration;
+
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use aws_sdk_s3::config::interceptors::{
+    BeforeSerializationInterceptorContextRef, FinalizerInterceptorContextRef,
+};
+use aws_sdk_s3::config::{ConfigBag, Intercept, RuntimeComponents};
+use aws_sdk_s3::error::BoxError;
+use aws_smithy_types::config_bag::{Storable, StoreReplace};
+use tracing::warn;
+
+/// Number of consecutive failed operations after which the circuit opens
+pub(crate) const DEFAULT_THRESHOLD: u32 = 5;
+/// Duration the circuit stays open before a probe operation is let through
+pub(crate) const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Default)]
+struct State {
+    /// Number of consecutive failed operations
+    failures: u32,
+    /// When the circuit was opened, if it is open
+    opened_at: Option<Instant>,
+    /// ID of the probe operation in flight after the cooldown elapsed, if any
+    probing: Option<u64>,
+    /// Number of probe operations let through so far, used to identify probes
+    probes: u64,
+}
+
+/// Outcome of acquiring the circuit for an operation
+#[derive(Debug)]
+enum Permit {
+    /// The circuit is closed, the operation may be sent
+    Closed,
+    /// The operation may be sent as the probe of an open circuit
+    Probe(Probe),
+    /// The circuit is open, the operation must fail fast
+    Rejected,
+}
+
+/// Probe operation of an open circuit. Dropping the probe before its outcome was recorded,
+/// e.g. because the operation was cancelled, lets another operation probe
+#[derive(Debug)]
+struct Probe {
+    breaker: Arc<CircuitBreaker>,
+    id: u64,
+}
+
+impl Drop for Probe {
+    fn drop(&mut self) {
+        let mut state = self.breaker.lock();
+        if state.probing == Some(self.id) {
+            state.probing = None;
+        }
+    }
+}
+
+impl Storable for Probe {
+    type Storer = StoreReplace<Self>;
+}
+
+#[derive(Debug)]
+pub(crate) struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    state: Mutex<State>,
+}
+
+impl CircuitBreaker {
+    /// Create a closed circuit breaker. A `threshold` of 0 disables the breaker
+    pub(crate) fn new(threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            threshold,
+            cooldown,
+            state: Mutex::default(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Acquire the circuit for an operation, determining whether it may be sent to S3
+    fn acquire(self: &Arc<Self>, now: Instant) -> Permit {
+        let mut state = self.lock();
+        match state.opened_at {
+            None => Permit::Closed,
+            Some(opened_at) if now.duration_since(opened_at) < self.cooldown => Permit::Rejected,
+            Some(_) if state.probing.is_some() => Permit::Rejected,
+            Some(_) => {
+                let id = state.probes;
+                state.probes = state.probes.wrapping_add(1);
+                state.probing = Some(id);
+                Permit::Probe(Probe {
+                    breaker: Arc::clone(self),
+                    id,
+                })
+            }
+        }
+    }
+
+    /// Record the outcome of an operation sent to S3
+    fn record(&self, ok: bool, now: Instant) {
+        if self.threshold == 0 {
+            return;
+        }
+        let mut state = self.lock();
+        if ok {
+            *state = State {
+                probes: state.probes,
+                ..State::default()
+            };
+            return;
+        }
+        state.failures = state.failures.saturating_add(1);
+        if state.probing.is_some()
+            || (state.opened_at.is_none() && state.failures >= self.threshold)
+        {
+            warn!(
+                failures = state.failures,
+                cooldown = ?self.cooldown,
+                "S3 is unavailable, opening circuit"
+            );
+            state.opened_at = Some(now);
+            state.probing = None;
+        }
+    }
+}
+
+/// Marks operations rejected by an open circuit, which must not be recorded as failures
+#[derive(Debug)]
+struct Rejected;
+
+impl Storable for Rejected {
+    type Storer = StoreReplace<Self>;
+}
+
+/// S3 client interceptor driving a [`CircuitBreaker`]
+#[derive(Clone, Debug)]
+pub(crate) struct CircuitBreakerInterceptor(Arc<CircuitBreaker>);
+
+impl From<CircuitBreaker> for CircuitBreakerInterceptor {
+    fn from(breaker: CircuitBreaker) -> Self {
+        Self(Arc::new(breaker))
+    }
+}
+
+impl Intercept for CircuitBreakerInterceptor {
+    fn name(&self) -> &'static str {
+        "CircuitBreakerInterceptor"
+    }
+
+    fn read_before_execution(
+        &self,
+        _context: &BeforeSerializationInterceptorContextRef<'_>,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        match self.0.acquire(Instant::now()) {
+            Permit::Closed => Ok(()),
+            Permit::Probe(probe) => {
+                // The probe is dropped along with the operation, even if it is cancelled
+                cfg.interceptor_state().store_put(probe);
+                Ok(())
+            }
+            Permit::Rejected => {
+                cfg.interceptor_state().store_put(Rejected);
+                Err("S3 circuit breaker is open after repeated failures, failing fast".into())
+            }
+        }
+    }
+
+    fn read_after_execution(
+        &self,
+        context: &FinalizerInterceptorContextRef<'_>,
+        _runtime_components: &RuntimeComponents,
+        cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        if cfg.load::<Rejected>().is_none() {
+            let status = context.response().map(|res| res.status().as_u16());
+            self.0.record(is_available(status), Instant::now());
+        }
+        Ok(())
+    }
+}
+
+/// Whether an operation with the final HTTP response `status` indicates S3 is available.
+///
+/// Operations without a response failed to connect or timed out. Client errors like a missing
+/// object are answered by an available S3
+fn is_available(status: Option<u16>) -> bool {
+    matches!(status, Some(status) if status != 429 && status < 500)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn availability() {
+        assert!(is_available(Some(200)));
+        assert!(is_available(Some(404)));
+        assert!(!is_available(Some(429)));
+        assert!(!is_available(Some(503)));
+        assert!(!is_available(None));
+    }
+
+    fn closed(permit: Permit) -> bool {
+        matches!(permit, Permit::Closed)
+    }
+
+    fn rejected(permit: Permit) -> bool {
+        matches!(permit, Permit::Rejected)
+    }
+
+    #[test]
+    fn open_after_threshold() {
+        let breaker = Arc::new(CircuitBreaker::new(3, Duration::from_secs(10)));
+        let now = Instant::now();
+        for _ in 0..2 {
+            assert!(closed(breaker.acquire(now)));
+            breaker.record(false, now);
+        }
+        assert!(closed(breaker.acquire(now)));
+        breaker.record(true, now);
+        for _ in 0..3 {
+            assert!(closed(breaker.acquire(now)));
+            breaker.record(false, now);
+        }
+        assert!(rejected(breaker.acquire(now)));
+        assert!(rejected(breaker.acquire(now + Duration::from_secs(9))));
+    }
+
+    #[test]
+    fn probe_after_cooldown() {
+        let breaker = Arc::new(CircuitBreaker::new(1, Duration::from_secs(10)));
+        let now = Instant::now();
+        breaker.record(false, now);
+        assert!(rejected(breaker.acquire(now)));
+
+        // Only a single probe is let through, which opens the circuit again on failure
+        let later = now + Duration::from_secs(10);
+        let Permit::Probe(probe) = breaker.acquire(later) else {
+            panic!("probe was not let through");
+        };
+        assert!(rejected(breaker.acquire(later)));
+        breaker.record(false, later);
+        drop(probe);
+        assert!(rejected(breaker.acquire(later + Duration::from_secs(5))));
+
+        // A successful probe closes the circuit
+        let later = later + Duration::from_secs(10);
+        let Permit::Probe(probe) = breaker.acquire(later) else {
+            panic!("probe was not let through");
+        };
+        breaker.record(true, later);
+        drop(probe);
+        assert!(closed(breaker.acquire(later)));
+        assert!(closed(breaker.acquire(later)));
+    }
+
+    #[test]
+    fn cancelled_probe() {
+        let breaker = Arc::new(CircuitBreaker::new(1, Duration::from_secs(10)));
+        let now = Instant::now();
+        breaker.record(false, now);
+
+        // A probe dropped without an outcome lets the next operation probe
+        let later = now + Duration::from_secs(10);
+        let probe = breaker.acquire(later);
+        assert!(matches!(probe, Permit::Probe(..)));
+        assert!(rejected(breaker.acquire(later)));
+        drop(probe);
+        let Permit::Probe(probe) = breaker.acquire(later) else {
+            panic!("probe was not let through after cancellation");
+        };
+
+        // Dropping a completed probe does not affect a later probe
+        breaker.record(false, later);
+        let later = later + Duration::from_secs(10);
+        let next = breaker.acquire(later);
+        assert!(matches!(next, Permit::Probe(..)));
+        drop(probe);
+        assert!(rejected(breaker.acquire(later)));
+        drop(next);
+    }
+
+    #[test]
+    fn disabled() {
+        let breaker = Arc::new(CircuitBreaker::new(0, Duration::from_secs(10)));
+        let now = Instant::now();
+        for _ in 0..100 {
+            breaker.record(false, now);
+        }
+        assert!(closed(breaker.acquire(now)));
+    }
+}
@@ -0,0 +1,353 @@
+//! Client-side envelope encryption of objects.
+//!
+//! Every object is encrypted with a fresh AES-256 data key generated by KMS, and the data key
+//! wrapped by the configured KMS key is stored in the user metadata of the object. Plaintext data
+//! keys never leave the provider.
+//!
+//! Objects are encrypted in segments of [`SEGMENT_SIZE`] bytes using AES-256-GCM, so that they
+//! can be streamed and read by range without downloading the whole object. Each segment is sealed
+//! with its index as nonce, which is unique since data keys are never reused, and with a flag
+//! marking the last segment as additional data, so that truncation of objects is detected.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, ensure, Context as _};
+use aws_sdk_kms::primitives::Blob;
+use aws_sdk_kms::types::DataKeySpec;
+use base64::Engine as _;
+use bytes::{Bytes, BytesMut};
+use futures::{stream, Stream, StreamExt as _};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+
+/// Size of plaintext segments encrypted individually
+pub(crate) const SEGMENT_SIZE: u64 = 64 * 1024;
+/// Size of the authentication tag appended to every segment
+const TAG_LEN: u64 = 16;
+/// Size of encrypted segments, except for the last segment of an object
+const SEALED_SEGMENT_SIZE: u64 = SEGMENT_SIZE + TAG_LEN;
+
+/// User metadata key of the wrapped data key of an object, encoded as Base64
+const KEY_METADATA: &str = "wasmcloud-cse-key";
+/// User metadata key of the encryption scheme of an object
+const SCHEME_METADATA: &str = "wasmcloud-cse-scheme";
+/// Encryption scheme implemented by this module
+const SCHEME: &str = "aes-256-gcm-64k";
+
+/// Wraps and unwraps data keys of objects with a KMS key
+#[derive(Clone, Debug)]
+pub(crate) struct Envelope {
+    kms: aws_sdk_kms::Client,
+    key_id: String,
+}
+
+impl Envelope {
+    pub(crate) fn new(kms: aws_sdk_kms::Client, key_id: String) -> Self {
+        Self { kms, key_id }
+    }
+
+    /// Generate a data key for a new object, returning it along with the user metadata to store
+    /// with the object
+    pub(crate) async fn generate_key(&self) -> anyhow::Result<(DataKey, HashMap<String, String>)> {
+        let out = self
+            .kms
+            .generate_data_key()
+            .key_id(&self.key_id)
+            .key_spec(DataKeySpec::Aes256)
+            .send()
+            .await
+            .context("failed to generate data key")?;
+        let plaintext = out.plaintext.context("plaintext data key missing")?;
+        let wrapped = out.ciphertext_blob.context("wrapped data key missing")?;
+        let key = DataKey::new(plaintext.as_ref())?;
+        let metadata = HashMap::from([
+            (
+                KEY_METADATA.to_string(),
+                base64::engine::general_purpose::STANDARD.encode(wrapped.as_ref()),
+            ),
+            (SCHEME_METADATA.to_string(), SCHEME.to_string()),
+        ]);
+        Ok((key, metadata))
+    }
+
+    /// Unwrap the data key of an object with user `metadata`, returning `None` if the object is
+    /// not encrypted
+    pub(crate) async fn unwrap_key(
+        &self,
+        metadata: Option<&HashMap<String, String>>,
+    ) -> anyhow::Result<Option<DataKey>> {
+        let Some(wrapped) = metadata.and_then(|m| m.get(KEY_METADATA)) else {
+            return Ok(None);
+        };
+        let scheme = metadata.and_then(|m| m.get(SCHEME_METADATA));
+        ensure!(
+            scheme.map(String::as_str) == Some(SCHEME),
+            "unsupported client-side encryption scheme {scheme:?}"
+        );
+        let wrapped = base64::engine::general_purpose::STANDARD
+            .decode(wrapped)
+            .context("invalid wrapped data key encoding")?;
+        let out = self
+            .kms
+            .decrypt()
+            .key_id(&self.key_id)
+            .ciphertext_blob(Blob::new(wrapped))
+            .send()
+            .await
+            .context("failed to unwrap data key")?;
+        let plaintext = out.plaintext.context("plaintext data key missing")?;
+        DataKey::new(plaintext.as_ref()).map(Some)
+    }
+}
+
+/// Returns whether an object with user `metadata` is encrypted
+pub(crate) fn is_encrypted(metadata: Option<&HashMap<String, String>>) -> bool {
+    metadata.is_some_and(|m| m.contains_key(KEY_METADATA))
+}
+
+/// Plaintext data key of an object
+pub(crate) struct DataKey(LessSafeKey);
+
+impl DataKey {
+    fn new(key: &[u8]) -> anyhow::Result<Self> {
+        let key = UnboundKey::new(&AES_256_GCM, key).map_err(|_| anyhow!("invalid data key"))?;
+        Ok(Self(LessSafeKey::new(key)))
+    }
+
+    fn nonce(index: u64) -> Nonce {
+        let mut nonce = [0; NONCE_LEN];
+        nonce[..8].copy_from_slice(&index.to_be_bytes());
+        Nonce::assume_unique_for_key(nonce)
+    }
+
+    fn seal(&self, index: u64, last: bool, segment: &[u8]) -> Bytes {
+        let mut buf = Vec::with_capacity(segment.len() + TAG_LEN as usize);
+        buf.extend_from_slice(segment);
+        self.0
+            .seal_in_place_append_tag(Self::nonce(index), Aad::from([u8::from(last)]), &mut buf)
+            .expect("segments are never larger than the AES-GCM limit");
+        buf.into()
+    }
+
+    fn open(&self, index: u64, last: bool, mut segment: BytesMut) -> anyhow::Result<Bytes> {
+        let len = self
+            .0
+            .open_in_place(
+                Self::nonce(index),
+                Aad::from([u8::from(last)]),
+                &mut segment[..],
+            )
+            .map_err(|_| anyhow!("failed to decrypt segment {index}, object is corrupted"))?
+            .len();
+        segment.truncate(len);
+        Ok(segment.freeze())
+    }
+}
+
+/// Returns the size of the encrypted form of an object with `size` bytes
+#[cfg(test)]
+fn sealed_size(size: u64) -> u64 {
+    size + segments(size) * TAG_LEN
+}
+
+/// Returns the number of segments of an object with `size` plaintext bytes
+fn segments(size: u64) -> u64 {
+    size.div_ceil(SEGMENT_SIZE).max(1)
+}
+
+/// Returns the plaintext size of an encrypted object with `sealed_size` bytes
+pub(crate) fn plaintext_size(sealed_size: u64) -> u64 {
+    let segments = sealed_size.div_ceil(SEALED_SEGMENT_SIZE).max(1);
+    sealed_size.saturating_sub(segments * TAG_LEN)
+}
+
+/// Returns the HTTP range of the encrypted object with `sealed_size` bytes holding the plaintext
+/// bytes `start..end`, along with the index of the first segment in the range
+pub(crate) fn sealed_range(sealed_size: u64, start: u64, end: u64) -> (String, u64) {
+    let size = plaintext_size(sealed_size);
+    let n = segments(size);
+    let first = (start / SEGMENT_SIZE).min(n - 1);
+    let last = (end.min(size).saturating_sub(1) / SEGMENT_SIZE).clamp(first, n - 1);
+    let range_end = ((last + 1) * SEALED_SEGMENT_SIZE).min(sealed_size) - 1;
+    (
+        format!("bytes={}-{range_end}", first * SEALED_SEGMENT_SIZE),
+        first,
+    )
+}
+
+/// Encrypt `data` with `key`, segment by segment
+pub(crate) fn encrypt(
+    key: DataKey,
+    data: impl Stream<Item = Bytes> + Unpin,
+) -> impl Stream<Item = Bytes> {
+    let segment_size = SEGMENT_SIZE as usize;
+    stream::unfold(
+        (key, data.fuse(), BytesMut::new(), 0, false),
+        move |(key, mut data, mut buf, index, done)| async move {
+            if done {
+                return None;
+            }
+            // Read one byte past the segment to determine whether it is the last one
+            while buf.len() <= segment_size {
+                match data.next().await {
+                    Some(chunk) => buf.extend_from_slice(&chunk),
+                    None => {
+                        let sealed = key.seal(index, true, &buf);
+                        return Some((sealed, (key, data, BytesMut::new(), index + 1, true)));
+                    }
+                }
+            }
+            let segment = buf.split_to(segment_size);
+            let sealed = key.seal(index, false, &segment);
+            Some((sealed, (key, data, buf, index + 1, false)))
+        },
+    )
+}
+
+/// Decrypt the encrypted object `body` with `sealed_size` bytes starting at segment `first` and
+/// return the plaintext bytes `start..end` of the object
+pub(crate) fn decrypt(
+    key: DataKey,
+    body: impl Stream<Item = anyhow::Result<Bytes>> + Send + Unpin,
+    sealed_size: u64,
+    first: u64,
+    start: u64,
+    end: u64,
+) -> impl Stream<Item = anyhow::Result<Bytes>> + Send {
+    let last = segments(plaintext_size(sealed_size)) - 1;
+    let segments = stream::try_unfold(
+        (key, body, BytesMut::new(), first),
+        move |(key, mut body, mut buf, index)| async move {
+            let segment_size = if index == last {
+                sealed_size - last * SEALED_SEGMENT_SIZE
+            } else {
+                SEALED_SEGMENT_SIZE
+            };
+            let segment_size = usize::try_from(segment_size).context("segment too large")?;
+            while buf.len() < segment_size {
+                match body.next().await {
+                    Some(chunk) => buf.extend_from_slice(&chunk?),
+                    None if buf.is_empty() => return Ok(None),
+                    None => bail!("object ended within segment {index}"),
+                }
+            }
+            let segment = key.open(index, index == last, buf.split_to(segment_size))?;
+            Ok(Some((segment, (key, body, buf, index + 1))))
+        },
+    );
+    // Trim the decrypted segments to the requested range
+    let skip = start.saturating_sub(first * SEGMENT_SIZE);
+    segments.scan(
+        (skip, end.saturating_sub(start)),
+        |(skip, remaining), segment| {
+            let res = segment.map(|mut segment| {
+                let n = (*skip).min(segment.len() as u64);
+                *skip -= n;
+                let mut segment = segment.split_off(n as usize);
+                segment.truncate((*remaining).try_into().unwrap_or(usize::MAX));
+                *remaining -= segment.len() as u64;
+                segment
+            });
+            futures::future::ready(Some(res))
+        },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use futures::TryStreamExt as _;
+
+    fn key() -> DataKey {
+        DataKey::new(&[42; 32]).expect("failed to create key")
+    }
+
+    async fn seal(data: &[u8]) -> Vec<u8> {
+        let chunks = data
+            .chunks(1000)
+            .map(Bytes::copy_from_slice)
+            .collect::<Vec<_>>();
+        encrypt(key(), stream::iter(chunks))
+            .collect::<Vec<_>>()
+            .await
+            .concat()
+    }
+
+    async fn read(sealed: &[u8], start: u64, end: u64) -> anyhow::Result<Vec<u8>> {
+        let (range, first) = sealed_range(sealed.len() as u64, start, end);
+        let (from, to) = range
+            .strip_prefix("bytes=")
+            .and_then(|range| range.split_once('-'))
+            .expect("invalid range");
+        let body = Bytes::copy_from_slice(
+            &sealed[from.parse::<usize>().unwrap()..=to.parse::<usize>().unwrap()],
+        );
+        decrypt(
+            key(),
+            stream::iter([Ok(body)]),
+            sealed.len() as u64,
+            first,
+            start,
+            end,
+        )
+        .try_collect::<Vec<_>>()
+        .await
+        .map(|chunks| chunks.concat())
+    }
+
+    #[test]
+    fn sizes() {
+        for size in [
+            0,
+            1,
+            SEGMENT_SIZE - 1,
+            SEGMENT_SIZE,
+            SEGMENT_SIZE + 1,
+            3 * SEGMENT_SIZE,
+        ] {
+            assert_eq!(plaintext_size(sealed_size(size)), size, "size {size}");
+        }
+    }
+
+    #[tokio::test]
+    async fn roundtrip() -> anyhow::Result<()> {
+        let data: Vec<u8> = (0..3 * SEGMENT_SIZE + 123).map(|i| i as u8).collect();
+        let sealed = seal(&data).await;
+        assert_eq!(sealed.len() as u64, sealed_size(data.len() as u64));
+
+        let len = data.len() as u64;
+        for (start, end) in [
+            (0, len),
+            (0, 10),
+            (SEGMENT_SIZE - 5, SEGMENT_SIZE + 5),
+            (2 * SEGMENT_SIZE, len),
+            (len - 1, len),
+            (10, len + 100),
+        ] {
+            let end_idx = end.min(len) as usize;
+            assert_eq!(
+                read(&sealed, start, end).await?,
+                &data[start as usize..end_idx],
+                "range {start}..{end}"
+            );
+        }
+
+        let empty = seal(&[]).await;
+        assert_eq!(read(&empty, 0, 100).await?, b"");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn detect_tampering() -> anyhow::Result<()> {
+        let data = vec![7; 2 * SEGMENT_SIZE as usize];
+        let mut sealed = seal(&data).await;
+        sealed[10] ^= 1;
+        assert!(read(&sealed, 0, 10).await.is_err());
+
+        // Dropping the last segment makes the first segment appear to be the last one
+        let sealed = seal(&data).await;
+        let truncated = &sealed[..SEALED_SEGMENT_SIZE as usize];
+        assert!(read(truncated, 0, 10).await.is_err());
+        Ok(())
+    }
+}
@@ -10,6 +10,7 @@
 use core::future::Future;
 use core::pin::Pin;
 use core::str::FromStr;
+use core::time::Duration;
 
 use std::collections::HashMap;
 use std::env;
@@ -26,25 +27,31 @@ use aws_sdk_s3::operation::create_bucket::{CreateBucketError, CreateBucketOutput
 use aws_sdk_s3::operation::get_object::GetObjectOutput;
 use aws_sdk_s3::operation::head_bucket::HeadBucketError;
 use aws_sdk_s3::operation::head_object::{HeadObjectError, HeadObjectOutput};
+use aws_sdk_s3::operation::list_object_versions::ListObjectVersionsOutput;
 use aws_sdk_s3::operation::list_objects_v2::ListObjectsV2Output;
+use aws_sdk_s3::primitives::{ByteStream, DateTime};
 use aws_sdk_s3::types::{
-    BucketLocationConstraint, CreateBucketConfiguration, Delete, Object, ObjectIdentifier,
+    BucketLocationConstraint, CompletedMultipartUpload, CompletedPart, CreateBucketConfiguration,
+    Delete, DeleteMarkerEntry, Object, ObjectIdentifier, ObjectVersion, RequestPayer,
+    ServerSideEncryption, ServerSideEncryptionByDefault, ServerSideEncryptionConfiguration,
+    ServerSideEncryptionRule,
 };
 use aws_smithy_runtime::client::http::hyper_014::HyperClientBuilder;
 use base64::Engine as _;
 use bytes::{Bytes, BytesMut};
-use futures::{stream, Stream, StreamExt as _};
+use futures::{stream, Stream, StreamExt as _, TryStreamExt as _};
 use serde::Deserialize;
-use tokio::io::AsyncReadExt as _;
-use tokio::sync::{mpsc, RwLock};
-use tokio_stream::wrappers::ReceiverStream;
-use tokio_util::io::ReaderStream;
+use tokio::sync::RwLock;
+use tokio::task::JoinSet;
 use tracing::{debug, error, instrument, warn};
 use wasmcloud_provider_sdk::core::secrets::SecretValue;
 use wasmcloud_provider_sdk::core::tls;
+use wasmcloud_provider_sdk::stream::{
+    stream_batches, stream_bytes, DEFAULT_BATCH_SIZE, DEFAULT_BUFFER, DEFAULT_CHUNK_SIZE,
+};
 use wasmcloud_provider_sdk::{
-    get_connection, initialize_observability, propagate_trace_for_ctx, run_provider,
-    serve_provider_exports, Context, LinkConfig, LinkDeleteInfo, Provider,
+    get_connection, initialize_observability, propagate_trace_for_ctx, run_provider, serve_exports,
+    Context, LinkConfig, LinkDeleteInfo, Provider,
 };
 use wrpc_interface_blobstore::bindings::{
     exports::wrpc::blobstore::blobstore::Handler,
@@ -52,9 +59,40 @@ use wrpc_interface_blobstore::bindings::{
     wrpc::blobstore::types::{ContainerMetadata, ObjectId, ObjectMetadata},
 };
 
+use bindings::exports::wasmcloud::blobstore::{versioning, writes};
+use circuit_breaker::{CircuitBreaker, CircuitBreakerInterceptor};
+use envelope::Envelope;
+use regions::{parse_alias, BucketClients};
+
+mod circuit_breaker;
+mod envelope;
+mod regions;
+
+mod bindings {
+    wit_bindgen_wrpc::generate!({
+        world: "extensions",
+        with: {
+            "wasmcloud:blobstore/versioning@0.1.0-draft": generate,
+            "wasmcloud:blobstore/writes@0.1.0-draft": generate,
+        }
+    });
+}
+
 const ALIAS_PREFIX: &str = "alias_";
 const DEFAULT_STS_SESSION: &str = "blobstore_s3_provider";
 
+/// Minimum size of every part but the last one in a multipart upload, as mandated by S3
+const MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+/// Maximum size of a single part in a multipart upload, as mandated by S3
+const MAX_PART_SIZE: usize = 5 * 1024 * 1024 * 1024;
+/// Maximum number of parts in a single multipart upload, as mandated by S3
+const MAX_PARTS: i32 = 10_000;
+/// Number of parts uploaded before the part size is doubled
+const PART_SIZE_GROWTH_INTERVAL: i32 = 1_000;
+const DEFAULT_PART_SIZE: usize = 8 * 1024 * 1024;
+const DEFAULT_MULTIPART_CONCURRENCY: usize = 4;
+const DEFAULT_MULTIPART_BUFFER_SIZE: usize = 128 * 1024 * 1024;
+
 /// Configuration for connecting to S3-compatible storage
 ///
 /// This value is meant to be parsed from link configuration, and can
@@ -73,6 +111,17 @@ pub struct StorageConfig {
     pub region: Option<String>,
     /// override default max_attempts (3) for retries
     pub max_attempts: Option<u32>,
+    /// Initial backoff in milliseconds between retries, doubled on every attempt and randomized
+    /// with full jitter (default 1000)
+    pub initial_backoff_ms: Option<u64>,
+    /// Maximum backoff in milliseconds between retries (default 20000)
+    pub max_backoff_ms: Option<u64>,
+    /// Number of consecutive failed operations after which S3 is considered unavailable and
+    /// operations fail fast (default 5, 0 disables the circuit breaker)
+    pub circuit_breaker_threshold: Option<u32>,
+    /// Milliseconds operations fail fast for once S3 is considered unavailable, before a single
+    /// operation is sent to probe S3 again (default 30000)
+    pub circuit_breaker_cooldown_ms: Option<u64>,
     /// optional configuration for STS Assume Role
     pub sts_config: Option<StsAssumeRoleConfig>,
     /// optional override for the AWS endpoint
@@ -82,6 +131,27 @@ pub struct StorageConfig {
     pub aliases: HashMap<String, String>,
     /// Region in which buckets will be created
     pub bucket_region: Option<String>,
+    /// Size in bytes of the initial parts used for multipart uploads (default 8MiB, minimum 5MiB).
+    /// Objects smaller than this are uploaded with a single `PutObject` request
+    pub multipart_part_size: Option<usize>,
+    /// Maximum number of parts of a single object uploaded concurrently (default 4)
+    pub multipart_concurrency: Option<usize>,
+    /// Maximum number of bytes of parts of a single object buffered in memory (default 128MiB).
+    /// Fewer parts are uploaded concurrently once parts grow larger, but at least one part is
+    /// always buffered
+    pub multipart_buffer_size: Option<usize>,
+    /// Server-side encryption algorithm applied to written objects and as the default encryption
+    /// of created buckets, one of `AES256`, `aws:kms` or `aws:kms:dsse`
+    pub sse_algorithm: Option<String>,
+    /// ID of the KMS key used for `aws:kms` and `aws:kms:dsse` encryption. Implies `aws:kms` if
+    /// `sse_algorithm` is not set. If unset, the AWS managed key is used
+    pub kms_key_id: Option<String>,
+    /// ID of the KMS key wrapping the data keys of objects encrypted client-side. If set, objects
+    /// are encrypted by the provider before upload and decrypted on read
+    pub cse_kms_key_id: Option<String>,
+    /// Whether the requester pays for requests to and data transferred from the buckets, which is
+    /// required to access requester-pays buckets (default false)
+    pub requester_pays: Option<bool>,
 }
 
 #[derive(Clone, Debug, Default, Deserialize)]
@@ -133,6 +203,23 @@ impl StorageConfig {
         if let Some(region) = config.get("BUCKET_REGION") {
             storage_config.bucket_region = Some(region.into());
         }
+        if let Some(algorithm) = config.get("SSE_ALGORITHM") {
+            storage_config.sse_algorithm = Some(algorithm.into());
+        }
+        if let Some(kms_key_id) = config.get("KMS_KEY_ID") {
+            storage_config.kms_key_id = Some(kms_key_id.into());
+        }
+        if let Some(cse_kms_key_id) = config.get("CSE_KMS_KEY_ID") {
+            storage_config.cse_kms_key_id = Some(cse_kms_key_id.into());
+        }
+        if let Some(requester_pays) = config.get("REQUESTER_PAYS") {
+            storage_config.requester_pays =
+                Some(requester_pays.parse().with_context(|| {
+                    format!("invalid `REQUESTER_PAYS` value `{requester_pays}`")
+                })?);
+        }
+        storage_config.validate_encryption()?;
+        storage_config.apply_retry_overrides(config)?;
 
         if let Ok(arn) = env::var("AWS_ROLE_ARN") {
             let mut sts_config = storage_config.sts_config.unwrap_or_default();
@@ -156,14 +243,104 @@ impl StorageConfig {
         // aliases are added from linkdefs in StorageClient::new()
         Ok(storage_config)
     }
+
+    /// Override retry and circuit breaker settings with top-level link configuration values, so
+    /// that they can be tuned per link without encoding a complete configuration
+    fn apply_retry_overrides(&mut self, config: &HashMap<String, String>) -> Result<()> {
+        fn parse<T: FromStr>(config: &HashMap<String, String>, key: &str) -> Result<Option<T>>
+        where
+            T::Err: std::error::Error + Send + Sync + 'static,
+        {
+            config
+                .get(key)
+                .map(|v| {
+                    v.parse()
+                        .with_context(|| format!("invalid `{key}` value `{v}`"))
+                })
+                .transpose()
+        }
+
+        if let Some(v) = parse(config, "MAX_ATTEMPTS")? {
+            self.max_attempts = Some(v);
+        }
+        if let Some(v) = parse(config, "INITIAL_BACKOFF_MS")? {
+            self.initial_backoff_ms = Some(v);
+        }
+        if let Some(v) = parse(config, "MAX_BACKOFF_MS")? {
+            self.max_backoff_ms = Some(v);
+        }
+        if let Some(v) = parse(config, "CIRCUIT_BREAKER_THRESHOLD")? {
+            self.circuit_breaker_threshold = Some(v);
+        }
+        if let Some(v) = parse(config, "CIRCUIT_BREAKER_COOLDOWN_MS")? {
+            self.circuit_breaker_cooldown_ms = Some(v);
+        }
+        if self.max_attempts == Some(0) {
+            bail!("`max_attempts` must be at least 1");
+        }
+        Ok(())
+    }
+
+    /// Validate the server-side encryption settings, defaulting to `aws:kms` if only a KMS key
+    /// is configured
+    fn validate_encryption(&mut self) -> Result<()> {
+        let algorithm = match (&self.sse_algorithm, &self.kms_key_id) {
+            (None, None) => return Ok(()),
+            (None, Some(_)) => ServerSideEncryption::AwsKms,
+            (Some(algorithm), _) => ServerSideEncryption::from(algorithm.as_str()),
+        };
+        if !ServerSideEncryption::values().contains(&algorithm.as_str()) {
+            bail!(
+                "unsupported server-side encryption algorithm `{}`, expected one of {:?}",
+                algorithm.as_str(),
+                ServerSideEncryption::values()
+            );
+        }
+        if self.kms_key_id.is_some() && algorithm == ServerSideEncryption::Aes256 {
+            bail!("`kms_key_id` requires the `aws:kms` or `aws:kms:dsse` encryption algorithm");
+        }
+        self.sse_algorithm = Some(algorithm.as_str().to_string());
+        Ok(())
+    }
+}
+
+/// Server-side encryption applied to objects written by the provider
+#[derive(Clone, Debug)]
+struct Encryption {
+    algorithm: ServerSideEncryption,
+    kms_key_id: Option<String>,
+}
+
+/// An object uploaded by [`StorageClient::put_object`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UploadedObject {
+    /// Number of bytes of the object uploaded
+    pub size: u64,
+    /// ETag of the object
+    pub e_tag: Option<String>,
+    /// ID of the created version, if versioning is enabled for the bucket
+    pub version_id: Option<String>,
 }
 
 #[derive(Clone)]
 pub struct StorageClient {
-    s3_client: aws_sdk_s3::Client,
+    /// S3 clients, resolved per bucket
+    clients: BucketClients,
     aliases: Arc<HashMap<String, String>>,
     /// Preferred region for bucket creation
     bucket_region: Option<BucketLocationConstraint>,
+    /// Size of the initial parts used for multipart uploads
+    part_size: usize,
+    /// Maximum number of concurrent part uploads per object
+    multipart_concurrency: usize,
+    /// Maximum number of bytes of parts buffered per object
+    multipart_buffer_size: usize,
+    /// Server-side encryption of written objects and created buckets
+    encryption: Option<Encryption>,
+    /// Client-side encryption of written objects
+    envelope: Option<Envelope>,
+    /// Set on object operations to access requester-pays buckets
+    request_payer: Option<RequestPayer>,
 }
 
 impl StorageClient {
@@ -174,10 +351,21 @@ impl StorageClient {
             session_token,
             region,
             max_attempts,
+            initial_backoff_ms,
+            max_backoff_ms,
+            circuit_breaker_threshold,
+            circuit_breaker_cooldown_ms,
             sts_config,
             endpoint,
             mut aliases,
             bucket_region,
+            multipart_part_size,
+            multipart_concurrency,
+            multipart_buffer_size,
+            sse_algorithm,
+            kms_key_id,
+            cse_kms_key_id,
+            requester_pays,
         }: StorageConfig,
         config_values: &HashMap<String, String>,
     ) -> Self {
@@ -226,40 +414,64 @@ impl StorageClient {
         if let Some(max_attempts) = max_attempts {
             retry_config = retry_config.with_max_attempts(max_attempts);
         }
+        if let Some(initial_backoff_ms) = initial_backoff_ms {
+            retry_config =
+                retry_config.with_initial_backoff(Duration::from_millis(initial_backoff_ms));
+        }
+        if let Some(max_backoff_ms) = max_backoff_ms {
+            retry_config = retry_config.with_max_backoff(Duration::from_millis(max_backoff_ms));
+        }
+        let circuit_breaker = CircuitBreaker::new(
+            circuit_breaker_threshold.unwrap_or(circuit_breaker::DEFAULT_THRESHOLD),
+            circuit_breaker_cooldown_ms
+                .map_or(circuit_breaker::DEFAULT_COOLDOWN, Duration::from_millis),
+        );
         let mut loader = aws_config::defaults(aws_config::BehaviorVersion::v2024_03_28())
             .region(region)
             .credentials_provider(cred_provider)
             .retry_config(retry_config);
+        // Regions of buckets are only discovered on AWS, S3-compatible storage is expected to
+        // serve all buckets at its endpoint
+        let discover_regions = endpoint.is_none();
         if let Some(endpoint) = endpoint {
             loader = loader.endpoint_url(endpoint);
         };
+        let sdk_config = loader.load().await;
+        let http_client = HyperClientBuilder::new().build(
+            hyper_rustls::HttpsConnectorBuilder::new()
+                .with_tls_config(
+                    // use `tls::DEFAULT_CLIENT_CONFIG` directly once `rustls` versions
+                    // are in sync
+                    rustls::ClientConfig::builder()
+                        .with_root_certificates(rustls::RootCertStore {
+                            roots: tls::DEFAULT_ROOTS.roots.clone(),
+                        })
+                        .with_no_client_auth(),
+                )
+                .https_or_http()
+                .enable_all_versions()
+                .build(),
+        );
         let s3_client = aws_sdk_s3::Client::from_conf(
-            aws_sdk_s3::Config::from(&loader.load().await)
+            aws_sdk_s3::Config::from(&sdk_config)
                 .to_builder()
                 // Since minio requires force path style,
                 // turn it on since it's disabled by default
                 // due to deprecation by AWS.
                 // https://github.com/awslabs/aws-sdk-rust/issues/390
                 .force_path_style(true)
-                .http_client(
-                    HyperClientBuilder::new().build(
-                        hyper_rustls::HttpsConnectorBuilder::new()
-                            .with_tls_config(
-                                // use `tls::DEFAULT_CLIENT_CONFIG` directly once `rustls` versions
-                                // are in sync
-                                rustls::ClientConfig::builder()
-                                    .with_root_certificates(rustls::RootCertStore {
-                                        roots: tls::DEFAULT_ROOTS.roots.clone(),
-                                    })
-                                    .with_no_client_auth(),
-                            )
-                            .https_or_http()
-                            .enable_all_versions()
-                            .build(),
-                    ),
-                )
+                .interceptor(CircuitBreakerInterceptor::from(circuit_breaker))
+                .http_client(http_client.clone())
                 .build(),
         );
+        let envelope = cse_kms_key_id.map(|key_id| {
+            let kms_client = aws_sdk_kms::Client::from_conf(
+                aws_sdk_kms::config::Builder::from(&sdk_config)
+                    .http_client(http_client)
+                    .build(),
+            );
+            Envelope::new(kms_client, key_id)
+        });
 
         // Process aliases
         for (k, v) in config_values {
@@ -271,14 +483,56 @@ impl StorageClient {
                 }
             }
         }
+        // Aliases may pin the bucket they refer to to a region and endpoint
+        let mut locations = HashMap::new();
+        aliases.retain(|alias, value| match parse_alias(value) {
+            Ok((bucket, location)) => {
+                if let Some(location) = location {
+                    locations.insert(bucket.clone(), location);
+                }
+                *value = bucket;
+                true
+            }
+            Err(err) => {
+                error!(?err, alias, "invalid bucket alias");
+                false
+            }
+        });
 
         StorageClient {
-            s3_client,
+            clients: BucketClients::new(s3_client, locations, discover_regions),
             aliases: Arc::new(aliases),
             bucket_region: bucket_region.and_then(|v| BucketLocationConstraint::from_str(&v).ok()),
+            part_size: multipart_part_size
+                .unwrap_or(DEFAULT_PART_SIZE)
+                .clamp(MIN_PART_SIZE, MAX_PART_SIZE),
+            multipart_concurrency: multipart_concurrency
+                .unwrap_or(DEFAULT_MULTIPART_CONCURRENCY)
+                .max(1),
+            multipart_buffer_size: multipart_buffer_size.unwrap_or(DEFAULT_MULTIPART_BUFFER_SIZE),
+            encryption: sse_algorithm.map(|algorithm| Encryption {
+                algorithm: ServerSideEncryption::from(algorithm.as_str()),
+                kms_key_id,
+            }),
+            envelope,
+            request_payer: requester_pays
+                .unwrap_or_default()
+                .then_some(RequestPayer::Requester),
         }
     }
 
+    fn sse_algorithm(&self) -> Option<ServerSideEncryption> {
+        self.encryption
+            .as_ref()
+            .map(|Encryption { algorithm, .. }| algorithm.clone())
+    }
+
+    fn kms_key_id(&self) -> Option<String> {
+        self.encryption
+            .as_ref()
+            .and_then(|Encryption { kms_key_id, .. }| kms_key_id.clone())
+    }
+
     /// perform alias lookup on bucket name
     /// This can be used either for giving shortcuts to actors in the linkdefs, for example:
     /// - component could use bucket names `alias_today`, `alias_images`, etc. and the linkdef aliases
@@ -300,7 +554,15 @@ impl StorageClient {
     /// Check whether a container exists
     #[instrument(level = "debug", skip(self))]
     pub async fn container_exists(&self, bucket: &str) -> anyhow::Result<bool> {
-        match self.s3_client.head_bucket().bucket(bucket).send().await {
+        match self
+            .clients
+            .client(bucket)
+            .await
+            .head_bucket()
+            .bucket(bucket)
+            .send()
+            .await
+        {
             Ok(_) => Ok(true),
             Err(se) => match se.into_service_error() {
                 HeadBucketError::NotFound(_) => Ok(false),
@@ -315,10 +577,23 @@ impl StorageClient {
     /// Create a bucket
     #[instrument(level = "debug", skip(self))]
     pub async fn create_container(&self, bucket: &str) -> anyhow::Result<()> {
-        let mut builder = self.s3_client.create_bucket();
+        let mut builder = self.clients.client(bucket).await.create_bucket();
 
+        // Buckets with a configured location are created in its region, all other buckets in
+        // `bucket_region`
+        // Buckets with a configured region are created in it, all other buckets in `bucket_region`
+        let bucket_region = match self
+            .clients
+            .location(bucket)
+            .and_then(|location| location.region.as_deref())
+        {
+            // Buckets in `us-east-1` are created without a location constraint
+            Some("us-east-1") => None,
+            Some(region) => Some(BucketLocationConstraint::from(region)),
+            None => self.bucket_region.clone(),
+        };
         // Only add BucketLocationConstraint if bucket_region was set.
-        if let Some(bucket_region) = &self.bucket_region {
+        if let Some(bucket_region) = &bucket_region {
             // Build bucket config, using location constraint if necessary
             let bucket_config = CreateBucketConfiguration::builder()
                 .set_location_constraint(Some(bucket_region.clone()))
@@ -330,10 +605,16 @@ impl StorageClient {
         match builder.bucket(bucket).send().await {
             Ok(CreateBucketOutput { location, .. }) => {
                 debug!(?location, "bucket created");
-                Ok(())
+                self.set_default_encryption(bucket).await.with_context(|| {
+                    format!("bucket `{bucket}` was created, but its default encryption was not set")
+                })
             }
             Err(se) => match se.into_service_error() {
-                CreateBucketError::BucketAlreadyOwnedByYou(..) => Ok(()),
+                // The encryption of existing buckets is not modified
+                CreateBucketError::BucketAlreadyOwnedByYou(..) => {
+                    debug!("bucket already exists");
+                    Ok(())
+                }
                 err => {
                     error!(?err, code = err.code(), "failed to create bucket");
                     bail!(anyhow!(err).context("failed to create bucket"))
@@ -342,9 +623,48 @@ impl StorageClient {
         }
     }
 
+    /// Set the default encryption of a newly created bucket, if server-side encryption is configured
+    async fn set_default_encryption(&self, bucket: &str) -> anyhow::Result<()> {
+        let Some(algorithm) = self.sse_algorithm() else {
+            return Ok(());
+        };
+        let default = ServerSideEncryptionByDefault::builder()
+            .sse_algorithm(algorithm)
+            .set_kms_master_key_id(self.kms_key_id())
+            .build()
+            .context("failed to build default encryption")?;
+        let config = ServerSideEncryptionConfiguration::builder()
+            .rules(
+                ServerSideEncryptionRule::builder()
+                    .apply_server_side_encryption_by_default(default)
+                    .build(),
+            )
+            .build()
+            .context("failed to build encryption configuration")?;
+        self.clients
+            .client(bucket)
+            .await
+            .put_bucket_encryption()
+            .bucket(bucket)
+            .server_side_encryption_configuration(config)
+            .send()
+            .await
+            .context("failed to set bucket default encryption")?;
+        debug!("bucket default encryption set");
+        Ok(())
+    }
+
     #[instrument(level = "debug", skip(self))]
     pub async fn get_container_info(&self, bucket: &str) -> anyhow::Result<ContainerMetadata> {
-        match self.s3_client.head_bucket().bucket(bucket).send().await {
+        match self
+            .clients
+            .client(bucket)
+            .await
+            .head_bucket()
+            .bucket(bucket)
+            .send()
+            .await
+        {
             Ok(_) => Ok(ContainerMetadata {
                 // unfortunately, HeadBucketOut doesn't include any information
                 // so we can't fill in creation date
@@ -372,8 +692,11 @@ impl StorageClient {
     ) -> anyhow::Result<impl Iterator<Item = String>> {
         // TODO: Stream names
         match self
-            .s3_client
+            .clients
+            .client(bucket)
+            .await
             .list_objects_v2()
+            .set_request_payer(self.request_payer.clone())
             .bucket(bucket)
             .set_max_keys(limit.map(|limit| limit.try_into().unwrap_or(i32::MAX)))
             .send()
@@ -404,11 +727,16 @@ impl StorageClient {
         dest_bucket: &str,
         dest_key: &str,
     ) -> anyhow::Result<()> {
-        self.s3_client
+        self.clients
+            .client(dest_bucket)
+            .await
             .copy_object()
+            .set_request_payer(self.request_payer.clone())
             .copy_source(format!("{src_bucket}/{src_key}"))
             .bucket(dest_bucket)
             .key(dest_key)
+            .set_server_side_encryption(self.sse_algorithm())
+            .set_ssekms_key_id(self.kms_key_id())
             .send()
             .await
             .context("failed to copy object")?;
@@ -417,8 +745,11 @@ impl StorageClient {
 
     #[instrument(level = "debug", skip(self, object))]
     pub async fn delete_object(&self, container: &str, object: String) -> anyhow::Result<()> {
-        self.s3_client
+        self.clients
+            .client(container)
+            .await
             .delete_object()
+            .set_request_payer(self.request_payer.clone())
             .bucket(container)
             .key(object)
             .send()
@@ -447,8 +778,11 @@ impl StorageClient {
             .build()
             .context("failed to build `delete_objects` command")?;
         let out = self
-            .s3_client
+            .clients
+            .client(container)
+            .await
             .delete_objects()
+            .set_request_payer(self.request_payer.clone())
             .bucket(container)
             .delete(delete)
             .send()
@@ -463,8 +797,19 @@ impl StorageClient {
 
     #[instrument(level = "debug", skip(self))]
     pub async fn delete_container(&self, bucket: &str) -> anyhow::Result<()> {
-        match self.s3_client.delete_bucket().bucket(bucket).send().await {
-            Ok(_) => Ok(()),
+        let res = self
+            .clients
+            .client(bucket)
+            .await
+            .delete_bucket()
+            .bucket(bucket)
+            .send()
+            .await;
+        match res {
+            Ok(_) => {
+                self.clients.evict(bucket);
+                Ok(())
+            }
             Err(SdkError::ServiceError(err)) => {
                 bail!("{err:?}")
             }
@@ -479,8 +824,11 @@ impl StorageClient {
     #[instrument(level = "debug", skip(self))]
     pub async fn has_object(&self, bucket: &str, key: &str) -> anyhow::Result<bool> {
         match self
-            .s3_client
+            .clients
+            .client(bucket)
+            .await
             .head_object()
+            .set_request_payer(self.request_payer.clone())
             .bucket(bucket)
             .key(key)
             .send()
@@ -501,24 +849,307 @@ impl StorageClient {
         }
     }
 
+    /// Upload an object, streaming `data` to S3. `data` is encrypted if client-side encryption
+    /// is configured, in which case the reported size is the size of the plaintext
+    #[instrument(level = "debug", skip(self, data))]
+    pub async fn put_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        data: impl Stream<Item = Bytes> + Unpin,
+    ) -> anyhow::Result<UploadedObject> {
+        let mut size = 0u64;
+        let data = data.inspect(|chunk| size += chunk.len() as u64);
+        let (e_tag, version_id) = if let Some(envelope) = &self.envelope {
+            let (data_key, metadata) = envelope.generate_key().await?;
+            let data = Box::pin(envelope::encrypt(data_key, data));
+            self.upload(bucket, key, data, Some(metadata)).await?
+        } else {
+            self.upload(bucket, key, data, None).await?
+        };
+        Ok(UploadedObject {
+            size,
+            e_tag,
+            version_id,
+        })
+    }
+
+    /// Upload an object with user `metadata`, streaming `data` to S3, returning the ETag of the
+    /// object and the ID of the created version if versioning is enabled for the bucket.
+    ///
+    /// Objects which fit in a single part are uploaded using a single `PutObject` request,
+    /// larger objects are uploaded using a multipart upload, which is aborted on failure
+    async fn upload(
+        &self,
+        bucket: &str,
+        key: &str,
+        data: impl Stream<Item = Bytes> + Unpin,
+        metadata: Option<HashMap<String, String>>,
+    ) -> anyhow::Result<(Option<String>, Option<String>)> {
+        let mut data = data.fuse();
+        let mut buf = BytesMut::new();
+        let first = read_part(&mut data, &mut buf, self.part_size).await;
+        if first.len() < self.part_size {
+            let out = self
+                .clients
+                .client(bucket)
+                .await
+                .put_object()
+                .set_request_payer(self.request_payer.clone())
+                .bucket(bucket)
+                .key(key)
+                .body(first.into())
+                .set_metadata(metadata)
+                .set_server_side_encryption(self.sse_algorithm())
+                .set_ssekms_key_id(self.kms_key_id())
+                .send()
+                .await
+                .context("failed to put object")?;
+            return Ok((out.e_tag, out.version_id));
+        }
+
+        let upload_id = self
+            .clients
+            .client(bucket)
+            .await
+            .create_multipart_upload()
+            .set_request_payer(self.request_payer.clone())
+            .bucket(bucket)
+            .key(key)
+            .set_metadata(metadata)
+            .set_server_side_encryption(self.sse_algorithm())
+            .set_ssekms_key_id(self.kms_key_id())
+            .send()
+            .await
+            .context("failed to create multipart upload")?
+            .upload_id
+            .context("multipart upload ID missing from response")?;
+        let res = async {
+            let parts = self
+                .upload_parts(bucket, key, &upload_id, first, &mut data, &mut buf)
+                .await?;
+            let out = self
+                .clients
+                .client(bucket)
+                .await
+                .complete_multipart_upload()
+                .set_request_payer(self.request_payer.clone())
+                .bucket(bucket)
+                .key(key)
+                .upload_id(&upload_id)
+                .multipart_upload(
+                    CompletedMultipartUpload::builder()
+                        .set_parts(Some(parts))
+                        .build(),
+                )
+                .send()
+                .await
+                .context("failed to complete multipart upload")?;
+            anyhow::Ok((out.e_tag, out.version_id))
+        }
+        .await;
+        if let Err(err) = &res {
+            if let Err(abort_err) = self
+                .clients
+                .client(bucket)
+                .await
+                .abort_multipart_upload()
+                .set_request_payer(self.request_payer.clone())
+                .bucket(bucket)
+                .key(key)
+                .upload_id(&upload_id)
+                .send()
+                .await
+            {
+                error!(?abort_err, %upload_id, "failed to abort multipart upload");
+            }
+        }
+        res
+    }
+
+    /// Upload all parts of a multipart upload, starting with `first`, with at most
+    /// `multipart_concurrency` part uploads and `multipart_buffer_size` bytes of parts in flight
+    /// at any point in time. The next part is only read from `data` once it fits in the buffer,
+    /// unless no other part is in flight.
+    ///
+    /// Outstanding part uploads are cancelled if any of the part uploads fails
+    async fn upload_parts(
+        &self,
+        bucket: &str,
+        key: &str,
+        upload_id: &str,
+        first: Bytes,
+        data: &mut (impl Stream<Item = Bytes> + Unpin),
+        buf: &mut BytesMut,
+    ) -> anyhow::Result<Vec<CompletedPart>> {
+        let mut tasks = JoinSet::new();
+        let mut parts = Vec::new();
+        let mut part = first;
+        let mut part_number = 1;
+        let mut buffered = 0;
+        loop {
+            let len = part.len();
+            buffered += len;
+            let req = self
+                .clients
+                .client(bucket)
+                .await
+                .upload_part()
+                .set_request_payer(self.request_payer.clone())
+                .bucket(bucket)
+                .key(key)
+                .upload_id(upload_id)
+                .part_number(part_number)
+                .body(part.into());
+            tasks.spawn(async move {
+                let out = req
+                    .send()
+                    .await
+                    .with_context(|| format!("failed to upload part {part_number}"))?;
+                anyhow::Ok((
+                    CompletedPart::builder()
+                        .set_e_tag(out.e_tag)
+                        .part_number(part_number)
+                        .build(),
+                    len,
+                ))
+            });
+
+            let size = part_size(self.part_size, part_number + 1);
+            while tasks.len() >= self.multipart_concurrency
+                || (!tasks.is_empty() && buffered + size > self.multipart_buffer_size)
+            {
+                if let Some(res) = tasks.join_next().await {
+                    let (part, len) = res.context("part upload task failed")??;
+                    buffered -= len;
+                    parts.push(part);
+                }
+            }
+            part = read_part(data, buf, size).await;
+            if part.is_empty() {
+                break;
+            }
+            if part_number == MAX_PARTS {
+                bail!("object exceeds the maximum number of parts ({MAX_PARTS}) in a multipart upload")
+            }
+            part_number += 1;
+        }
+        while let Some(res) = tasks.join_next().await {
+            let (part, _) = res.context("part upload task failed")??;
+            parts.push(part);
+        }
+        parts.sort_by_key(|part| part.part_number);
+        Ok(parts)
+    }
+
+    /// Stream the bytes `start..end` of an object, or of a version of it, decrypting them if the
+    /// object was encrypted client-side
+    #[instrument(level = "debug", skip(self))]
+    pub async fn get_object_data(
+        &self,
+        bucket: &str,
+        key: &str,
+        version_id: Option<String>,
+        start: u64,
+        end: u64,
+    ) -> anyhow::Result<Pin<Box<dyn Stream<Item = anyhow::Result<Bytes>> + Send>>> {
+        let limit = end
+            .checked_sub(start)
+            .context("`end` must be greater than `start`")?;
+        if let Some(envelope) = &self.envelope {
+            let HeadObjectOutput {
+                content_length,
+                metadata,
+                e_tag,
+                ..
+            } = self
+                .clients
+                .client(bucket)
+                .await
+                .head_object()
+                .set_request_payer(self.request_payer.clone())
+                .bucket(bucket)
+                .key(key)
+                .set_version_id(version_id.clone())
+                .send()
+                .await
+                .context("failed to get object metadata")?;
+            if let Some(data_key) = envelope.unwrap_key(metadata.as_ref()).await? {
+                let sealed_size = content_length
+                    .and_then(|v| v.try_into().ok())
+                    .unwrap_or_default();
+                let (range, first) = envelope::sealed_range(sealed_size, start, end);
+                // Fail instead of decrypting another object if the object was replaced meanwhile
+                let GetObjectOutput { body, .. } = self
+                    .clients
+                    .client(bucket)
+                    .await
+                    .get_object()
+                    .set_request_payer(self.request_payer.clone())
+                    .bucket(bucket)
+                    .key(key)
+                    .set_version_id(version_id)
+                    .set_if_match(e_tag)
+                    .range(range)
+                    .send()
+                    .await
+                    .context("failed to get object")?;
+                return Ok(Box::pin(envelope::decrypt(
+                    data_key,
+                    Box::pin(object_data(body, u64::MAX)),
+                    sealed_size,
+                    first,
+                    start,
+                    end,
+                )));
+            }
+        }
+        let GetObjectOutput { body, .. } = self
+            .clients
+            .client(bucket)
+            .await
+            .get_object()
+            .set_request_payer(self.request_payer.clone())
+            .bucket(bucket)
+            .key(key)
+            .set_version_id(version_id)
+            .range(format!("bytes={start}-{end}"))
+            .send()
+            .await
+            .context("failed to get object")?;
+        Ok(Box::pin(object_data(body, limit)))
+    }
+
     /// Retrieves metadata about the object
     #[instrument(level = "debug", skip(self))]
     pub async fn get_object_info(&self, bucket: &str, key: &str) -> anyhow::Result<ObjectMetadata> {
         match self
-            .s3_client
+            .clients
+            .client(bucket)
+            .await
             .head_object()
+            .set_request_payer(self.request_payer.clone())
             .bucket(bucket)
             .key(key)
             .send()
             .await
         {
-            Ok(HeadObjectOutput { content_length, .. }) => {
+            Ok(HeadObjectOutput {
+                content_length,
+                metadata,
+                ..
+            }) => {
+                let size = content_length
+                    .and_then(|v| v.try_into().ok())
+                    .unwrap_or_default();
                 Ok(ObjectMetadata {
                     // NOTE: The `created_at` value is not reported by S3
                     created_at: 0,
-                    size: content_length
-                        .and_then(|v| v.try_into().ok())
-                        .unwrap_or_default(),
+                    size: if envelope::is_encrypted(metadata.as_ref()) {
+                        envelope::plaintext_size(size)
+                    } else {
+                        size
+                    },
                 })
             }
             Err(se) => match se.into_service_error() {
@@ -539,6 +1170,136 @@ impl StorageClient {
             },
         }
     }
+
+    /// List the versions of objects in `bucket` whose keys start with `prefix`, including delete
+    /// markers, by key and newest version first. Pages are requested lazily as the stream is
+    /// consumed
+    pub fn list_object_versions(
+        &self,
+        bucket: String,
+        prefix: Option<String>,
+    ) -> impl Stream<Item = anyhow::Result<versioning::ObjectVersion>> + Send + 'static {
+        let clients = self.clients.clone();
+        let request_payer = self.request_payer.clone();
+        stream::try_unfold(
+            Some((None, None)),
+            move |markers: Option<(Option<String>, Option<String>)>| {
+                let clients = clients.clone();
+                let request_payer = request_payer.clone();
+                let bucket = bucket.clone();
+                let prefix = prefix.clone();
+                async move {
+                    let Some((key_marker, version_id_marker)) = markers else {
+                        return anyhow::Ok(None);
+                    };
+                    let ListObjectVersionsOutput {
+                        versions,
+                        delete_markers,
+                        is_truncated,
+                        next_key_marker,
+                        next_version_id_marker,
+                        ..
+                    } = clients
+                        .client(&bucket)
+                        .await
+                        .list_object_versions()
+                        .set_request_payer(request_payer.clone())
+                        .bucket(bucket)
+                        .set_prefix(prefix)
+                        .set_key_marker(key_marker)
+                        .set_version_id_marker(version_id_marker)
+                        .send()
+                        .await
+                        .context("failed to list object versions")?;
+                    let versions = merge_versions(
+                        versions.unwrap_or_default(),
+                        delete_markers.unwrap_or_default(),
+                    );
+                    let markers = is_truncated
+                        .unwrap_or_default()
+                        .then_some((next_key_marker, next_version_id_marker));
+                    Ok(Some((stream::iter(versions.into_iter().map(Ok)), markers)))
+                }
+            },
+        )
+        .try_flatten()
+    }
+
+    /// Delete a single version of an object
+    #[instrument(level = "debug", skip(self))]
+    pub async fn delete_object_version(
+        &self,
+        bucket: &str,
+        key: &str,
+        version_id: &str,
+    ) -> anyhow::Result<()> {
+        self.clients
+            .client(bucket)
+            .await
+            .delete_object()
+            .set_request_payer(self.request_payer.clone())
+            .bucket(bucket)
+            .key(key)
+            .version_id(version_id)
+            .send()
+            .await
+            .context("failed to delete object version")?;
+        Ok(())
+    }
+
+    /// Restores a version of an object by copying it onto the object, returning the ID of the
+    /// created version
+    #[instrument(level = "debug", skip(self))]
+    pub async fn restore_object_version(
+        &self,
+        bucket: &str,
+        key: &str,
+        version_id: &str,
+    ) -> anyhow::Result<Option<String>> {
+        let res = self
+            .clients
+            .client(bucket)
+            .await
+            .copy_object()
+            .set_request_payer(self.request_payer.clone())
+            .copy_source(format!("{bucket}/{key}?versionId={version_id}"))
+            .bucket(bucket)
+            .key(key)
+            .set_server_side_encryption(self.sse_algorithm())
+            .set_ssekms_key_id(self.kms_key_id())
+            .send()
+            .await
+            .context("failed to restore object version")?;
+        Ok(res.version_id)
+    }
+}
+
+/// Returns the size of the part with 1-based `part_number` in a multipart upload.
+///
+/// The size of the object is not known upfront, so the part size is doubled every
+/// [`PART_SIZE_GROWTH_INTERVAL`] parts, allowing objects of several TiB to be uploaded
+/// within [`MAX_PARTS`]
+fn part_size(base: usize, part_number: i32) -> usize {
+    let growth = (part_number.saturating_sub(1) / PART_SIZE_GROWTH_INTERVAL).unsigned_abs();
+    base.saturating_mul(2usize.saturating_pow(growth))
+        .min(MAX_PART_SIZE)
+}
+
+/// Reads up to `size` bytes from `data`, carrying over excess bytes between calls in `buf`.
+///
+/// Fewer than `size` bytes are returned only if `data` is exhausted
+async fn read_part(
+    data: &mut (impl Stream<Item = Bytes> + Unpin),
+    buf: &mut BytesMut,
+    size: usize,
+) -> Bytes {
+    while buf.len() < size {
+        let Some(chunk) = data.next().await else {
+            break;
+        };
+        buf.extend_from_slice(&chunk);
+    }
+    buf.split_to(size.min(buf.len())).freeze()
 }
 
 /// Blobstore S3 provider
@@ -570,7 +1331,7 @@ impl BlobstoreS3Provider {
         let wrpc = connection
             .get_wrpc_client(connection.provider_key())
             .await?;
-        serve_provider_exports(&wrpc, provider, shutdown, serve)
+        serve_exports!(&wrpc, provider, shutdown, [serve, bindings::serve])
             .await
             .context("failed to serve provider exports")
     }
@@ -777,36 +1538,11 @@ impl Handler<Option<Context>> for BlobstoreS3Provider {
     > {
         Ok(async {
             propagate_trace_for_ctx!(cx);
-            let limit = end
-                .checked_sub(start)
-                .context("`end` must be greater than `start`")?;
             let client = self.client(cx).await?;
-            let bucket = client.unalias(&id.container);
-            let GetObjectOutput { body, .. } = client
-                .s3_client
-                .get_object()
-                .bucket(bucket)
-                .key(id.object)
-                .range(format!("bytes={start}-{end}"))
-                .send()
-                .await
-                .context("failed to get object")?;
-            let mut data = ReaderStream::new(body.into_async_read().take(limit));
-            let (tx, rx) = mpsc::channel(16);
-            anyhow::Ok((
-                Box::pin(ReceiverStream::new(rx)) as Pin<Box<dyn Stream<Item = _> + Send>>,
-                Box::pin(async move {
-                    while let Some(buf) = data.next().await {
-                        let buf = buf
-                            .context("failed to read object")
-                            .map_err(|err| format!("{err:#}"))?;
-                        if tx.send(buf).await.is_err() {
-                            return Err("stream receiver closed".to_string());
-                        }
-                    }
-                    Ok(())
-                }) as Pin<Box<dyn Future<Output = _> + Send>>,
-            ))
+            let data = client
+                .get_object_data(client.unalias(&id.container), &id.object, None, start, end)
+                .await?;
+            anyhow::Ok(stream_bytes(data, DEFAULT_CHUNK_SIZE, DEFAULT_BUFFER))
         }
         .await
         .map_err(|err| format!("{err:#}")))
@@ -882,20 +1618,177 @@ impl Handler<Option<Context>> for BlobstoreS3Provider {
         Ok(async {
             propagate_trace_for_ctx!(cx);
             let client = self.client(cx).await?;
-            let req = client
-                .s3_client
-                .put_object()
-                .bucket(client.unalias(&id.container))
-                .key(&id.object);
-            anyhow::Ok(Box::pin(async {
-                // TODO: Stream data to S3
-                let data: BytesMut = data.collect().await;
-                req.body(data.freeze().into())
-                    .send()
+            anyhow::Ok(Box::pin(async move {
+                client
+                    .put_object(client.unalias(&id.container), &id.object, data)
                     .await
-                    .context("failed to put object")
-                    .map_err(|err| format!("{err:#}"))?;
-                Ok(())
+                    .map(|_| ())
+                    .map_err(|err| format!("{err:#}"))
+            }) as Pin<Box<dyn Future<Output = _> + Send>>)
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+}
+
+impl versioning::Handler<Option<Context>> for BlobstoreS3Provider {
+    #[instrument(level = "trace", skip(self))]
+    async fn list_object_versions(
+        &self,
+        cx: Option<Context>,
+        name: String,
+        prefix: Option<String>,
+        limit: Option<u64>,
+    ) -> anyhow::Result<
+        Result<
+            (
+                Pin<Box<dyn Stream<Item = Vec<versioning::ObjectVersion>> + Send>>,
+                Pin<Box<dyn Future<Output = Result<(), String>> + Send>>,
+            ),
+            String,
+        >,
+    > {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let client = self.client(cx).await?;
+            let limit = limit
+                .map(|limit| limit.try_into().unwrap_or(usize::MAX))
+                .unwrap_or(usize::MAX);
+            let versions = client
+                .list_object_versions(client.unalias(&name).to_string(), prefix)
+                .take(limit);
+            anyhow::Ok(stream_batches(versions, DEFAULT_BATCH_SIZE, DEFAULT_BUFFER))
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn get_object_version(
+        &self,
+        cx: Option<Context>,
+        id: versioning::ObjectId,
+        version_id: String,
+        start: u64,
+        end: u64,
+    ) -> anyhow::Result<
+        Result<
+            (
+                Pin<Box<dyn Stream<Item = Bytes> + Send>>,
+                Pin<Box<dyn Future<Output = Result<(), String>> + Send>>,
+            ),
+            String,
+        >,
+    > {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let client = self.client(cx).await?;
+            let data = client
+                .get_object_data(
+                    client.unalias(&id.container),
+                    &id.object,
+                    Some(version_id),
+                    start,
+                    end,
+                )
+                .await
+                .context("failed to get object version")?;
+            anyhow::Ok(stream_bytes(data, DEFAULT_CHUNK_SIZE, DEFAULT_BUFFER))
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn delete_object_version(
+        &self,
+        cx: Option<Context>,
+        id: versioning::ObjectId,
+        version_id: String,
+    ) -> anyhow::Result<Result<(), String>> {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let client = self.client(cx).await?;
+            client
+                .delete_object_version(client.unalias(&id.container), &id.object, &version_id)
+                .await
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "trace", skip(self))]
+    async fn restore_object_version(
+        &self,
+        cx: Option<Context>,
+        id: versioning::ObjectId,
+        version_id: String,
+    ) -> anyhow::Result<Result<Option<String>, String>> {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let client = self.client(cx).await?;
+            client
+                .restore_object_version(client.unalias(&id.container), &id.object, &version_id)
+                .await
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "trace", skip(self, data))]
+    async fn write_object(
+        &self,
+        cx: Option<Context>,
+        id: versioning::ObjectId,
+        data: Pin<Box<dyn Stream<Item = Bytes> + Send>>,
+    ) -> anyhow::Result<
+        Result<Pin<Box<dyn Future<Output = Result<Option<String>, String>> + Send>>, String>,
+    > {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let client = self.client(cx).await?;
+            anyhow::Ok(Box::pin(async move {
+                client
+                    .put_object(client.unalias(&id.container), &id.object, data)
+                    .await
+                    .map(|UploadedObject { version_id, .. }| version_id)
+                    .map_err(|err| format!("{err:#}"))
+            }) as Pin<Box<dyn Future<Output = _> + Send>>)
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+}
+
+impl writes::Handler<Option<Context>> for BlobstoreS3Provider {
+    #[instrument(level = "trace", skip(self, data))]
+    async fn write_data(
+        &self,
+        cx: Option<Context>,
+        id: writes::ObjectId,
+        data: Pin<Box<dyn Stream<Item = Bytes> + Send>>,
+    ) -> anyhow::Result<
+        Result<Pin<Box<dyn Future<Output = Result<writes::WriteResult, String>> + Send>>, String>,
+    > {
+        Ok(async {
+            propagate_trace_for_ctx!(cx);
+            let client = self.client(cx).await?;
+            anyhow::Ok(Box::pin(async move {
+                client
+                    .put_object(client.unalias(&id.container), &id.object, data)
+                    .await
+                    .map(
+                        |UploadedObject {
+                             size,
+                             e_tag,
+                             version_id,
+                         }| writes::WriteResult {
+                            bytes_written: size,
+                            etag: e_tag,
+                            version_id,
+                        },
+                    )
+                    .map_err(|err| format!("{err:#}"))
             }) as Pin<Box<dyn Future<Output = _> + Send>>)
         }
         .await
@@ -948,6 +1841,74 @@ impl Provider for BlobstoreS3Provider {
     }
 }
 
+/// Merge object `versions` and `delete_markers` of a page of `ListObjectVersions`, which S3 returns
+/// separately, ordering them by key and newest version first
+fn merge_versions(
+    versions: Vec<ObjectVersion>,
+    delete_markers: Vec<DeleteMarkerEntry>,
+) -> Vec<versioning::ObjectVersion> {
+    fn secs(last_modified: Option<DateTime>) -> u64 {
+        last_modified
+            .and_then(|t| t.secs().try_into().ok())
+            .unwrap_or_default()
+    }
+
+    let mut merged: Vec<_> = versions
+        .into_iter()
+        .map(|v| versioning::ObjectVersion {
+            name: v.key.unwrap_or_default(),
+            version_id: v.version_id.unwrap_or_default(),
+            is_latest: v.is_latest.unwrap_or_default(),
+            is_delete_marker: false,
+            last_modified: secs(v.last_modified),
+            size: v.size.and_then(|v| v.try_into().ok()).unwrap_or_default(),
+        })
+        .chain(
+            delete_markers
+                .into_iter()
+                .map(|m| versioning::ObjectVersion {
+                    name: m.key.unwrap_or_default(),
+                    version_id: m.version_id.unwrap_or_default(),
+                    is_latest: m.is_latest.unwrap_or_default(),
+                    is_delete_marker: true,
+                    last_modified: secs(m.last_modified),
+                    size: 0,
+                }),
+        )
+        .collect();
+    // The latest version always sorts first, since versions may share a modification time
+    merged.sort_by(|a, b| {
+        a.name
+            .cmp(&b.name)
+            .then(b.is_latest.cmp(&a.is_latest))
+            .then(b.last_modified.cmp(&a.last_modified))
+    });
+    merged
+}
+
+/// Forward the chunks of an object `body` as they are received, up to `limit` bytes in total.
+///
+/// Chunks are passed through without copying, so at most one chunk per transfer is held in memory
+/// in addition to the bounded buffer of the outgoing stream
+fn object_data(body: ByteStream, limit: u64) -> impl Stream<Item = anyhow::Result<Bytes>> + Send {
+    stream::unfold((body, limit), |(mut body, remaining)| async move {
+        if remaining == 0 {
+            return None;
+        }
+        match body.next().await? {
+            Ok(mut buf) => {
+                buf.truncate(remaining.try_into().unwrap_or(usize::MAX));
+                let remaining = remaining.saturating_sub(buf.len().try_into().unwrap_or(u64::MAX));
+                Some((Ok(buf), (body, remaining)))
+            }
+            Err(err) => Some((
+                Err(anyhow!(err).context("failed to read object")),
+                (body, 0),
+            )),
+        }
+    })
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -956,7 +1917,14 @@ mod test {
     async fn aliases() {
         let client = StorageClient::new(
             StorageConfig::default(),
-            &HashMap::from([(format!("{ALIAS_PREFIX}foo"), "bar".into())]),
+            &HashMap::from([
+                (format!("{ALIAS_PREFIX}foo"), "bar".into()),
+                (
+                    format!("{ALIAS_PREFIX}eu"),
+                    "bar-eu?region=eu-west-1".into(),
+                ),
+                (format!("{ALIAS_PREFIX}invalid"), "bar?zone=a".into()),
+            ]),
         )
         .await;
 
@@ -968,5 +1936,160 @@ mod test {
         assert_eq!(client.unalias(&format!("{ALIAS_PREFIX}foo")), "bar");
         // undefined alias
         assert_eq!(client.unalias(&format!("{ALIAS_PREFIX}baz")), "baz");
+        // alias with a location
+        assert_eq!(client.unalias("eu"), "bar-eu");
+        assert_eq!(
+            client
+                .clients
+                .location("bar-eu")
+                .and_then(|location| location.region.as_deref()),
+            Some("eu-west-1")
+        );
+        // invalid aliases are ignored
+        assert_eq!(client.unalias("invalid"), "invalid");
+    }
+
+    #[tokio::test]
+    async fn requester_pays() {
+        let client = StorageClient::new(StorageConfig::default(), &HashMap::new()).await;
+        assert_eq!(client.request_payer, None);
+
+        let client = StorageClient::new(
+            StorageConfig {
+                requester_pays: Some(true),
+                ..Default::default()
+            },
+            &HashMap::new(),
+        )
+        .await;
+        assert_eq!(client.request_payer, Some(RequestPayer::Requester));
+    }
+
+    #[test]
+    fn encryption_config() {
+        let mut config = StorageConfig {
+            kms_key_id: Some("key".into()),
+            ..Default::default()
+        };
+        config
+            .validate_encryption()
+            .expect("KMS key should imply aws:kms");
+        assert_eq!(config.sse_algorithm.as_deref(), Some("aws:kms"));
+
+        let mut config = StorageConfig {
+            sse_algorithm: Some("AES256".into()),
+            kms_key_id: Some("key".into()),
+            ..Default::default()
+        };
+        assert!(config.validate_encryption().is_err());
+
+        let mut config = StorageConfig {
+            sse_algorithm: Some("rot13".into()),
+            ..Default::default()
+        };
+        assert!(config.validate_encryption().is_err());
+    }
+
+    #[test]
+    fn retry_overrides() {
+        let mut config = StorageConfig {
+            max_attempts: Some(2),
+            initial_backoff_ms: Some(50),
+            ..Default::default()
+        };
+        config
+            .apply_retry_overrides(&HashMap::from([
+                ("MAX_ATTEMPTS".into(), "7".into()),
+                ("CIRCUIT_BREAKER_THRESHOLD".into(), "0".into()),
+            ]))
+            .expect("failed to apply overrides");
+        assert_eq!(config.max_attempts, Some(7));
+        assert_eq!(config.initial_backoff_ms, Some(50));
+        assert_eq!(config.circuit_breaker_threshold, Some(0));
+
+        assert!(config
+            .apply_retry_overrides(&HashMap::from([("MAX_BACKOFF_MS".into(), "soon".into())]))
+            .is_err());
+        assert!(config
+            .apply_retry_overrides(&HashMap::from([("MAX_ATTEMPTS".into(), "0".into())]))
+            .is_err());
+    }
+
+    #[test]
+    fn part_sizes() {
+        assert_eq!(part_size(DEFAULT_PART_SIZE, 1), DEFAULT_PART_SIZE);
+        assert_eq!(part_size(DEFAULT_PART_SIZE, 1_000), DEFAULT_PART_SIZE);
+        assert_eq!(part_size(DEFAULT_PART_SIZE, 1_001), 2 * DEFAULT_PART_SIZE);
+        assert_eq!(part_size(DEFAULT_PART_SIZE, 2_001), 4 * DEFAULT_PART_SIZE);
+        assert_eq!(part_size(MAX_PART_SIZE, MAX_PARTS), MAX_PART_SIZE);
+    }
+
+    #[test]
+    fn merge_object_versions() {
+        let version = |key: &str, id: &str, latest: bool, secs: i64| {
+            ObjectVersion::builder()
+                .key(key)
+                .version_id(id)
+                .is_latest(latest)
+                .last_modified(DateTime::from_secs(secs))
+                .size(42)
+                .build()
+        };
+        let merged = merge_versions(
+            vec![
+                version("b", "b1", true, 10),
+                version("a", "a1", false, 10),
+                version("a", "a2", false, 20),
+            ],
+            vec![DeleteMarkerEntry::builder()
+                .key("a")
+                .version_id("a3")
+                .is_latest(true)
+                .last_modified(DateTime::from_secs(20))
+                .build()],
+        );
+        let ids: Vec<_> = merged
+            .iter()
+            .map(|v| (v.version_id.as_str(), v.is_delete_marker, v.size))
+            .collect();
+        assert_eq!(
+            ids,
+            [
+                ("a3", true, 0),
+                ("a2", false, 42),
+                ("a1", false, 42),
+                ("b1", false, 42)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn object_data_limit() {
+        let body = ByteStream::from_static(b"foobarbaz");
+        let data: Vec<_> = object_data(body, 6).collect().await;
+        let data = data
+            .into_iter()
+            .collect::<anyhow::Result<Vec<_>>>()
+            .expect("failed to read object data");
+        assert_eq!(data.concat(), b"foobar");
+
+        let body = ByteStream::from_static(b"foo");
+        let data: Vec<_> = object_data(body, 0).collect().await;
+        assert!(data.is_empty());
+    }
+
+    #[tokio::test]
+    async fn read_parts() {
+        let mut data = stream::iter([
+            Bytes::from_static(b"foo"),
+            Bytes::from_static(b"barbaz"),
+            Bytes::from_static(b"q"),
+        ])
+        .fuse();
+        let mut buf = BytesMut::new();
+        assert_eq!(read_part(&mut data, &mut buf, 4).await, "foob");
+        assert_eq!(read_part(&mut data, &mut buf, 4).await, "arba");
+        assert_eq!(read_part(&mut data, &mut buf, 4).await, "zq");
+        assert!(read_part(&mut data, &mut buf, 4).await.is_empty());
     }
 }
@@ -0,0 +1,206 @@
+//! S3 clients for buckets outside the region of the link.
+//!
+//! Bucket aliases can pin the bucket they refer to to a region and endpoint, in which case
+//! operations on the bucket are sent with a dedicated client for that location. For all other
+//! buckets the region is discovered on first use: S3 answers `HeadBucket` requests sent to the
+//! wrong region with a `301 Moved Permanently`, which names the region of the bucket in the
+//! `x-amz-bucket-region` header. Clients are cached per bucket, so that a single link can
+//! address buckets in multiple regions.
+
+use std::collections::HashMap;
+use std::sync::{Arc, PoisonError, RwLock};
+
+use anyhow::bail;
+use aws_sdk_s3::config::Region;
+use aws_sdk_s3::operation::head_bucket::HeadBucketError;
+use tracing::{debug, warn};
+
+/// Header naming the region of a bucket in `HeadBucket` responses
+const BUCKET_REGION_HEADER: &str = "x-amz-bucket-region";
+
+/// Maximum number of buckets, whose client is cached. Clients of further buckets are resolved
+/// on every operation
+const MAX_CACHED_BUCKETS: usize = 1024;
+
+/// Region and endpoint of a bucket
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct BucketLocation {
+    /// Region of the bucket, the region of the link is used if unset
+    pub(crate) region: Option<String>,
+    /// Endpoint to reach the bucket at, the endpoint of the link is used if unset
+    pub(crate) endpoint: Option<String>,
+}
+
+/// Parses an alias value of the form `<bucket>[?region=<region>][&endpoint=<endpoint>]`,
+/// returning the bucket name and its location, if one was specified
+pub(crate) fn parse_alias(value: &str) -> anyhow::Result<(String, Option<BucketLocation>)> {
+    let Some((bucket, params)) = value.split_once('?') else {
+        return Ok((value.to_string(), None));
+    };
+    let mut location = BucketLocation::default();
+    for param in params.split('&').filter(|param| !param.is_empty()) {
+        match param.split_once('=') {
+            Some(("region", region)) if !region.is_empty() => {
+                location.region = Some(region.to_string());
+            }
+            Some(("endpoint", endpoint)) if !endpoint.is_empty() => {
+                location.endpoint = Some(endpoint.to_string());
+            }
+            _ => bail!("invalid alias parameter `{param}`, expected `region=<region>` or `endpoint=<endpoint>`"),
+        }
+    }
+    if bucket.is_empty() {
+        bail!("alias bucket name must not be empty");
+    }
+    Ok((bucket.to_string(), Some(location)))
+}
+
+/// S3 clients of a link, resolved per bucket
+#[derive(Clone)]
+pub(crate) struct BucketClients {
+    /// Client for the region and endpoint of the link
+    default: aws_sdk_s3::Client,
+    /// Locations of buckets configured by aliases
+    locations: Arc<HashMap<String, BucketLocation>>,
+    /// Whether the region of buckets without a configured location is discovered
+    discover_regions: bool,
+    /// Clients of buckets, which were resolved before
+    cache: Arc<RwLock<HashMap<String, aws_sdk_s3::Client>>>,
+}
+
+impl BucketClients {
+    pub(crate) fn new(
+        default: aws_sdk_s3::Client,
+        locations: HashMap<String, BucketLocation>,
+        discover_regions: bool,
+    ) -> Self {
+        Self {
+            default,
+            locations: Arc::new(locations),
+            discover_regions,
+            cache: Arc::default(),
+        }
+    }
+
+    /// Returns the configured location of `bucket`, if any
+    pub(crate) fn location(&self, bucket: &str) -> Option<&BucketLocation> {
+        self.locations.get(bucket)
+    }
+
+    /// Returns the client to use for operations on `bucket`, discovering its region on first use
+    pub(crate) async fn client(&self, bucket: &str) -> aws_sdk_s3::Client {
+        if let Some(client) = self
+            .cache
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(bucket)
+        {
+            return client.clone();
+        }
+        let client = if let Some(location) = self.locations.get(bucket) {
+            self.located_client(location)
+        } else if self.discover_regions {
+            match self.discover_region(bucket).await {
+                Ok(Some(region)) => self.located_client(&BucketLocation {
+                    region: Some(region),
+                    endpoint: None,
+                }),
+                Ok(None) => self.default.clone(),
+                // The bucket may still be created, so do not cache the default client
+                Err(_) => return self.default.clone(),
+            }
+        } else {
+            return self.default.clone();
+        };
+        let mut cache = self.cache.write().unwrap_or_else(PoisonError::into_inner);
+        if cache.len() < MAX_CACHED_BUCKETS {
+            cache.insert(bucket.to_string(), client.clone());
+        }
+        client
+    }
+
+    /// Forgets the client resolved for `bucket`, e.g. after the bucket was deleted
+    pub(crate) fn evict(&self, bucket: &str) {
+        self.cache
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(bucket);
+    }
+
+    /// Builds a client for `location`, based on the configuration of the link
+    fn located_client(
+        &self,
+        BucketLocation { region, endpoint }: &BucketLocation,
+    ) -> aws_sdk_s3::Client {
+        let mut config = self.default.config().to_builder();
+        if let Some(region) = region {
+            config = config.region(Region::new(region.clone()));
+        }
+        if let Some(endpoint) = endpoint {
+            config = config.endpoint_url(endpoint);
+        }
+        aws_sdk_s3::Client::from_conf(config.build())
+    }
+
+    /// Discovers the region of `bucket`, returning `None` if it is in the region of the link or
+    /// the region could not be determined. Fails if the bucket does not exist.
+    async fn discover_region(&self, bucket: &str) -> anyhow::Result<Option<String>> {
+        let region = match self.default.head_bucket().bucket(bucket).send().await {
+            Ok(out) => out.bucket_region,
+            Err(err) => {
+                let region = err
+                    .raw_response()
+                    .and_then(|res| res.headers().get(BUCKET_REGION_HEADER))
+                    .map(ToString::to_string);
+                if region.is_none() {
+                    if let Some(HeadBucketError::NotFound(..)) = err.as_service_error() {
+                        bail!("bucket [{bucket}] not found");
+                    }
+                    warn!(?err, bucket, "failed to discover bucket region");
+                }
+                region
+            }
+        };
+        let default_region: Option<&str> = self.default.config().region().map(AsRef::as_ref);
+        match region {
+            Some(region) if Some(region.as_str()) != default_region => {
+                debug!(bucket, region, "discovered bucket region");
+                Ok(Some(region))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_alias_location() -> anyhow::Result<()> {
+        assert_eq!(parse_alias("backup")?, ("backup".into(), None));
+        assert_eq!(
+            parse_alias("backup?region=eu-west-1")?,
+            (
+                "backup".into(),
+                Some(BucketLocation {
+                    region: Some("eu-west-1".into()),
+                    endpoint: None,
+                })
+            )
+        );
+        assert_eq!(
+            parse_alias("backup?region=eu-west-1&endpoint=https://s3.eu-west-1.amazonaws.com")?,
+            (
+                "backup".into(),
+                Some(BucketLocation {
+                    region: Some("eu-west-1".into()),
+                    endpoint: Some("https://s3.eu-west-1.amazonaws.com".into()),
+                })
+            )
+        );
+        assert!(parse_alias("backup?zone=a").is_err());
+        assert!(parse_alias("?region=eu-west-1").is_err());
+        Ok(())
+    }
+}
@@ -81,6 +81,7 @@ impl TestEnv {
             session_token: None,
             sts_config: None,
             bucket_region: Self::env_var_or_default("BUCKET_REGION", None),
+            ..Default::default()
         };
 
         StorageClient::new(conf, &HashMap::new()).await
@@ -0,0 +1,123 @@
+//! Configuration for dataset-blobstore capability provider
+//!
+//! See README.md for the supported link configuration.
+
+use anyhow::{bail, ensure, Context as _, Result};
+use wasmcloud_provider_sdk::LinkConfig;
+
+/// Delimiter of CSV values
+const CONFIG_CSV_DELIMITER_KEY: &str = "CSV_DELIMITER";
+
+/// Whether the first row of CSV datasets is a header
+const CONFIG_CSV_HEADER_KEY: &str = "CSV_HEADER";
+
+/// Maximum number of rows decoded at once
+const CONFIG_BATCH_SIZE_KEY: &str = "BATCH_SIZE";
+
+const DEFAULT_BATCH_SIZE: usize = 1024;
+
+/// Configuration of the datasets read by a component
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DatasetConfig {
+    /// CSV_DELIMITER, delimiter of CSV values. Defaults to `,`
+    pub csv_delimiter: u8,
+
+    /// CSV_HEADER, whether the first row of CSV datasets holds the column names. Defaults to
+    /// `true`, otherwise columns are named `column_1`, `column_2`, ...
+    pub csv_header: bool,
+
+    /// BATCH_SIZE, maximum number of rows decoded at once. Defaults to 1024
+    pub batch_size: usize,
+}
+
+impl Default for DatasetConfig {
+    fn default() -> Self {
+        Self {
+            csv_delimiter: b',',
+            csv_header: true,
+            batch_size: DEFAULT_BATCH_SIZE,
+        }
+    }
+}
+
+impl DatasetConfig {
+    /// Build a [`DatasetConfig`] from a link configuration
+    pub fn from_link_config(LinkConfig { config, .. }: &LinkConfig) -> Result<Self> {
+        let mut dataset = Self::default();
+        if let Some(delimiter) = config.get(CONFIG_CSV_DELIMITER_KEY) {
+            dataset.csv_delimiter = match delimiter.as_str() {
+                "\\t" | "tab" => b'\t',
+                delimiter => match delimiter.as_bytes() {
+                    [delimiter] => *delimiter,
+                    _ => bail!(
+                        "invalid `{CONFIG_CSV_DELIMITER_KEY}` value `{delimiter}`, expected a single ASCII character"
+                    ),
+                },
+            };
+        }
+        if let Some(header) = config.get(CONFIG_CSV_HEADER_KEY) {
+            dataset.csv_header = header.parse().with_context(|| {
+                format!("invalid `{CONFIG_CSV_HEADER_KEY}` value `{header}`, expected a boolean")
+            })?;
+        }
+        if let Some(batch_size) = config.get(CONFIG_BATCH_SIZE_KEY) {
+            dataset.batch_size = batch_size.parse().with_context(|| {
+                format!("invalid `{CONFIG_BATCH_SIZE_KEY}` value `{batch_size}`")
+            })?;
+            ensure!(
+                dataset.batch_size > 0,
+                "`{CONFIG_BATCH_SIZE_KEY}` must be greater than 0"
+            );
+        }
+        Ok(dataset)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn dataset_config(config: &[(&str, &str)]) -> Result<DatasetConfig> {
+        let config: HashMap<_, _> = config
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        DatasetConfig::from_link_config(&LinkConfig {
+            source_id: "component",
+            target_id: "provider",
+            link_name: "default",
+            config: &config,
+            secrets: &HashMap::new(),
+            wit_metadata: (&"wasmcloud".to_string(), &"dataset".to_string(), &vec![]),
+        })
+    }
+
+    #[test]
+    fn parse_config() {
+        assert_eq!(dataset_config(&[]).unwrap(), DatasetConfig::default());
+        assert_eq!(
+            dataset_config(&[
+                ("CSV_DELIMITER", "tab"),
+                ("CSV_HEADER", "false"),
+                ("BATCH_SIZE", "64"),
+            ])
+            .unwrap(),
+            DatasetConfig {
+                csv_delimiter: b'\t',
+                csv_header: false,
+                batch_size: 64,
+            }
+        );
+        assert_eq!(
+            dataset_config(&[("CSV_DELIMITER", ";")])
+                .unwrap()
+                .csv_delimiter,
+            b';'
+        );
+        assert!(dataset_config(&[("CSV_DELIMITER", ";;")]).is_err());
+        assert!(dataset_config(&[("CSV_HEADER", "yes")]).is_err());
+        assert!(dataset_config(&[("BATCH_SIZE", "0")]).is_err());
+    }
+}
@@ -0,0 +1,175 @@
+//! Reading of CSV datasets
+
+use std::sync::Arc;
+
+use anyhow::{Context as _, Result};
+use arrow::csv::reader::{Decoder, Format};
+use arrow::csv::ReaderBuilder;
+use arrow::datatypes::Schema;
+use arrow::record_batch::RecordBatch;
+use bytes::{Buf as _, Bytes};
+use futures::{stream, StreamExt as _};
+
+use super::BatchStream;
+use crate::config::DatasetConfig;
+use crate::object::{BlobstoreObject, DataStatus, DataStream};
+
+/// Number of bytes read from the start of a CSV dataset to infer its schema
+const INFER_SCHEMA_BYTES: u64 = 1024 * 1024;
+
+/// Maximum number of records used to infer the schema of a CSV dataset
+const INFER_SCHEMA_RECORDS: usize = 1000;
+
+/// A CSV dataset, which is streamed from the start of the object when read
+pub struct CsvDataset {
+    object: BlobstoreObject,
+    size: u64,
+    schema: Schema,
+    config: DatasetConfig,
+}
+
+impl CsvDataset {
+    /// Open a CSV dataset, inferring its schema from its first records
+    pub async fn open(object: BlobstoreObject, config: &DatasetConfig) -> Result<Self> {
+        let size = object.size().await?;
+        let sample = object.read(0..size.min(INFER_SCHEMA_BYTES)).await?;
+        let schema = infer_schema(&sample, size > INFER_SCHEMA_BYTES, config)?;
+        Ok(Self {
+            object,
+            size,
+            schema,
+            config: config.clone(),
+        })
+    }
+
+    pub fn schema(&self) -> &Schema {
+        &self.schema
+    }
+
+    /// Stream the record batches of the dataset, decoding the columns in `projection`
+    pub async fn read_batches(self, projection: Vec<usize>) -> Result<BatchStream> {
+        let decoder = ReaderBuilder::new(Arc::new(self.schema))
+            .with_header(self.config.csv_header)
+            .with_delimiter(self.config.csv_delimiter)
+            .with_batch_size(self.config.batch_size)
+            .with_projection(projection)
+            .build_decoder();
+        let (data, status) = self.object.stream(0, self.size).await?;
+        let batches = CsvBatches {
+            data,
+            status: Some(status),
+            decoder,
+            buf: Bytes::new(),
+            eof: false,
+        };
+        Ok(Box::pin(stream::try_unfold(
+            batches,
+            |mut batches| async move { Ok(batches.next().await?.map(|batch| (batch, batches))) },
+        )))
+    }
+}
+
+/// Infer the schema of a CSV dataset from `sample`, a prefix of the dataset, which is
+/// `truncated` unless it holds the whole dataset
+fn infer_schema(sample: &[u8], truncated: bool, config: &DatasetConfig) -> Result<Schema> {
+    let sample = if truncated {
+        // Only infer the schema from complete records
+        let end = sample
+            .iter()
+            .rposition(|b| *b == b'\n')
+            .context("first record of CSV dataset is too large to infer the schema")?;
+        &sample[..end]
+    } else {
+        sample
+    };
+    let (schema, _) = Format::default()
+        .with_header(config.csv_header)
+        .with_delimiter(config.csv_delimiter)
+        .infer_schema(sample, Some(INFER_SCHEMA_RECORDS))
+        .context("failed to infer schema of CSV dataset")?;
+    Ok(schema)
+}
+
+/// Incremental decoder of the record batches of a streamed CSV dataset
+struct CsvBatches {
+    data: DataStream,
+    status: Option<DataStatus>,
+    decoder: Decoder,
+    /// Bytes received, which were not decoded yet
+    buf: Bytes,
+    eof: bool,
+}
+
+impl CsvBatches {
+    /// Decode the next record batch, returning `None` once all records were decoded
+    async fn next(&mut self) -> Result<Option<RecordBatch>> {
+        loop {
+            if self.buf.is_empty() && !self.eof {
+                match self.data.next().await {
+                    Some(buf) => {
+                        self.buf = buf;
+                        continue;
+                    }
+                    None => self.eof = true,
+                }
+            }
+            // Decoding an empty buffer at the end of the dataset completes the last record
+            let decoded = self
+                .decoder
+                .decode(&self.buf)
+                .context("failed to decode CSV records")?;
+            self.buf.advance(decoded);
+            // Nothing is decoded once the batch is full or all records were decoded
+            if decoded == 0 {
+                break;
+            }
+        }
+        if let Some(batch) = self
+            .decoder
+            .flush()
+            .context("failed to decode CSV records")?
+        {
+            return Ok(Some(batch));
+        }
+        if let Some(status) = self.status.take() {
+            status.await?;
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use arrow::datatypes::DataType;
+
+    use super::*;
+
+    #[test]
+    fn infer_sample_schema() {
+        let config = DatasetConfig::default();
+        let schema = infer_schema(b"id,name,score\n1,a,0.5\n2,b,1\n3,c", true, &config).unwrap();
+        assert_eq!(
+            schema
+                .fields()
+                .iter()
+                .map(|field| (field.name().as_str(), field.data_type().clone()))
+                .collect::<Vec<_>>(),
+            [
+                ("id", DataType::Int64),
+                ("name", DataType::Utf8),
+                ("score", DataType::Float64),
+            ]
+        );
+
+        let config = DatasetConfig {
+            csv_delimiter: b';',
+            csv_header: false,
+            ..DatasetConfig::default()
+        };
+        let schema = infer_schema(b"1;true", false, &config).unwrap();
+        assert_eq!(schema.field(0).name(), "column_1");
+        assert_eq!(schema.field(1).data_type(), &DataType::Boolean);
+
+        assert!(infer_schema(b"id,name", true, &DatasetConfig::default()).is_err());
+    }
+}
@@ -0,0 +1,114 @@
+//! Datasets stored in a linked blobstore
+
+use core::pin::Pin;
+
+use anyhow::{bail, Result};
+use arrow::datatypes::Schema;
+use arrow::record_batch::RecordBatch;
+use futures::{stream, Stream, TryStreamExt as _};
+
+use crate::bindings::exports::wasmcloud::dataset::reader::{Format, Row};
+use crate::config::DatasetConfig;
+use crate::object::BlobstoreObject;
+use crate::rows::Selection;
+
+mod csv;
+mod parquet;
+
+use self::csv::CsvDataset;
+use self::parquet::ParquetDataset;
+
+/// Stream of the record batches of a dataset
+pub type BatchStream = Pin<Box<dyn Stream<Item = Result<RecordBatch>> + Send>>;
+
+/// A dataset of a supported [`Format`]
+pub enum Dataset {
+    Csv(CsvDataset),
+    Parquet(ParquetDataset),
+}
+
+impl Dataset {
+    /// Open the dataset stored in `object`, inferring the format from the object name unless
+    /// `format` is set
+    pub async fn open(
+        object: BlobstoreObject,
+        format: Option<Format>,
+        config: &DatasetConfig,
+    ) -> Result<Self> {
+        let format = match format {
+            Some(format) => format,
+            None => infer_format(object.name())?,
+        };
+        match format {
+            Format::Csv => CsvDataset::open(object, config).await.map(Self::Csv),
+            Format::Parquet => ParquetDataset::open(object, config)
+                .await
+                .map(Self::Parquet),
+        }
+    }
+
+    pub fn schema(&self) -> &Schema {
+        match self {
+            Self::Csv(dataset) => dataset.schema(),
+            Self::Parquet(dataset) => dataset.schema(),
+        }
+    }
+
+    /// Stream the rows of the dataset selected by `selection`
+    pub async fn read_rows(
+        self,
+        mut selection: Selection,
+    ) -> Result<impl Stream<Item = Result<Vec<Row>>> + Send + 'static> {
+        let projection = selection.projection(self.schema());
+        let batches = match self {
+            Self::Csv(dataset) => dataset.read_batches(projection).await?,
+            Self::Parquet(dataset) => dataset.read_batches(projection, &selection)?,
+        };
+        Ok(stream::try_unfold(
+            (batches, selection),
+            |(mut batches, mut selection)| async move {
+                while !selection.is_done() {
+                    let Some(batch) = batches.try_next().await? else {
+                        break;
+                    };
+                    let rows = selection.rows(&batch)?;
+                    if !rows.is_empty() {
+                        return Ok(Some((rows, (batches, selection))));
+                    }
+                }
+                Ok(None)
+            },
+        ))
+    }
+}
+
+/// Infer the format of a dataset from the extension of its object name
+fn infer_format(name: &str) -> Result<Format> {
+    match name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+    {
+        Some(ext) if ext == "csv" => Ok(Format::Csv),
+        Some(ext) if ext == "parquet" || ext == "pq" => Ok(Format::Parquet),
+        _ => bail!(
+            "failed to infer format of object `{name}` from its extension, `format` must be set"
+        ),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn infer_object_format() {
+        assert!(matches!(infer_format("data/2024.CSV"), Ok(Format::Csv)));
+        assert!(matches!(
+            infer_format("orders.parquet"),
+            Ok(Format::Parquet)
+        ));
+        assert!(matches!(infer_format("orders.pq"), Ok(Format::Parquet)));
+        assert!(infer_format("orders").is_err());
+        assert!(infer_format("orders.json").is_err());
+    }
+}
@@ -0,0 +1,209 @@
+//! Reading of Parquet datasets
+
+use core::ops::Range;
+
+use std::sync::Arc;
+
+use anyhow::{Context as _, Result};
+use arrow::datatypes::{DataType, Schema};
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use futures::{FutureExt as _, StreamExt as _};
+use parquet::arrow::async_reader::{AsyncFileReader, ParquetRecordBatchStreamBuilder};
+use parquet::arrow::ProjectionMask;
+use parquet::errors::ParquetError;
+use parquet::file::metadata::{ParquetMetaData, ParquetMetaDataReader, RowGroupMetaData};
+use parquet::file::statistics::Statistics;
+use tracing::debug;
+
+use super::BatchStream;
+use crate::bindings::exports::wasmcloud::dataset::reader::{Predicate, Value};
+use crate::config::DatasetConfig;
+use crate::object::BlobstoreObject;
+use crate::rows::{self, Selection};
+
+/// Number of bytes read from the end of a Parquet dataset when reading its metadata, which
+/// usually suffices to read the metadata with a single request
+const METADATA_PREFETCH: usize = 64 * 1024;
+
+/// A Parquet dataset, of which only the column chunks required are read
+pub struct ParquetDataset {
+    builder: ParquetRecordBatchStreamBuilder<ParquetObject>,
+    config: DatasetConfig,
+}
+
+impl ParquetDataset {
+    /// Open a Parquet dataset, reading its metadata
+    pub async fn open(object: BlobstoreObject, config: &DatasetConfig) -> Result<Self> {
+        let size = object.size().await?;
+        let size = usize::try_from(size).context("Parquet dataset is too large")?;
+        let builder = ParquetRecordBatchStreamBuilder::new(ParquetObject { object, size })
+            .await
+            .context("failed to read Parquet metadata")?;
+        Ok(Self {
+            builder,
+            config: config.clone(),
+        })
+    }
+
+    pub fn schema(&self) -> &Schema {
+        self.builder.schema()
+    }
+
+    /// Stream the record batches of the dataset, decoding the columns in `projection` of the
+    /// row groups which may contain rows matching the predicates of `selection`
+    pub fn read_batches(
+        self,
+        projection: Vec<usize>,
+        selection: &Selection,
+    ) -> Result<BatchStream> {
+        let Self { builder, config } = self;
+        let schema = Arc::clone(builder.schema());
+        let row_groups: Vec<_> = builder
+            .metadata()
+            .row_groups()
+            .iter()
+            .enumerate()
+            .filter(|(_, row_group)| may_match(row_group, &schema, selection.predicates()))
+            .map(|(i, _)| i)
+            .collect();
+        debug!(
+            row_groups = row_groups.len(),
+            total = builder.metadata().num_row_groups(),
+            "selected row groups"
+        );
+        let projection = ProjectionMask::roots(builder.parquet_schema(), projection);
+        let mut builder = builder
+            .with_projection(projection)
+            .with_row_groups(row_groups)
+            .with_batch_size(config.batch_size);
+        if let Some(limit) = selection.limit() {
+            builder = builder.with_limit(usize::try_from(limit).unwrap_or(usize::MAX));
+        }
+        let batches = builder
+            .build()
+            .context("failed to build Parquet reader")?
+            .map(|batch| batch.context("failed to decode Parquet record batch"));
+        Ok(Box::pin(batches))
+    }
+}
+
+/// A Parquet dataset stored in a blobstore, which is read in ranges
+struct ParquetObject {
+    object: BlobstoreObject,
+    size: usize,
+}
+
+impl AsyncFileReader for ParquetObject {
+    fn get_bytes(&mut self, range: Range<usize>) -> BoxFuture<'_, parquet::errors::Result<Bytes>> {
+        async move {
+            self.object
+                .read(range.start as u64..range.end as u64)
+                .await
+                .map_err(|err| ParquetError::External(err.into()))
+        }
+        .boxed()
+    }
+
+    fn get_metadata(&mut self) -> BoxFuture<'_, parquet::errors::Result<Arc<ParquetMetaData>>> {
+        async move {
+            let size = self.size;
+            let metadata = ParquetMetaDataReader::new()
+                .with_prefetch_hint(Some(METADATA_PREFETCH))
+                .load_and_finish(self, size)
+                .await?;
+            Ok(Arc::new(metadata))
+        }
+        .boxed()
+    }
+}
+
+/// Whether rows of `row_group` may match all `predicates`, based on the statistics of its
+/// column chunks
+fn may_match(row_group: &RowGroupMetaData, schema: &Schema, predicates: &[Predicate]) -> bool {
+    predicates.iter().all(|predicate| {
+        let Ok(field) = schema.field_with_name(&predicate.column) else {
+            return true;
+        };
+        let Some(statistics) = row_group
+            .columns()
+            .iter()
+            .find(|column| column.column_path().string() == predicate.column)
+            .and_then(|column| column.statistics())
+        else {
+            return true;
+        };
+        let (min, max) = bounds(field.data_type(), statistics);
+        rows::may_match(min.as_ref(), max.as_ref(), &predicate.op, &predicate.value)
+    })
+}
+
+/// Bounds of the values of a column chunk of type `data_type`. Bounds are only returned for
+/// types of which the statistics are ordered like the values returned to components
+fn bounds(data_type: &DataType, statistics: &Statistics) -> (Option<Value>, Option<Value>) {
+    match (data_type, statistics) {
+        (DataType::Boolean, Statistics::Boolean(s)) => (
+            s.min_opt().copied().map(Value::Boolean),
+            s.max_opt().copied().map(Value::Boolean),
+        ),
+        (DataType::Int8 | DataType::Int16 | DataType::Int32, Statistics::Int32(s)) => (
+            s.min_opt().map(|v| Value::Int((*v).into())),
+            s.max_opt().map(|v| Value::Int((*v).into())),
+        ),
+        (DataType::Int64, Statistics::Int64(s)) => (
+            s.min_opt().copied().map(Value::Int),
+            s.max_opt().copied().map(Value::Int),
+        ),
+        (DataType::Float32, Statistics::Float(s)) => (
+            s.min_opt().map(|v| Value::Float((*v).into())),
+            s.max_opt().map(|v| Value::Float((*v).into())),
+        ),
+        (DataType::Float64, Statistics::Double(s)) => (
+            s.min_opt().copied().map(Value::Float),
+            s.max_opt().copied().map(Value::Float),
+        ),
+        // Files written by old writers ordered string statistics as signed bytes
+        (DataType::Utf8 | DataType::LargeUtf8, Statistics::ByteArray(s))
+            if !s.is_min_max_deprecated() =>
+        {
+            let text = |v: &parquet::data_type::ByteArray| {
+                v.as_utf8().ok().map(|v| Value::Text(v.to_string()))
+            };
+            (s.min_opt().and_then(text), s.max_opt().and_then(text))
+        }
+        _ => (None, None),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use parquet::file::statistics::ValueStatistics;
+
+    use super::*;
+
+    #[test]
+    fn statistics_bounds() {
+        let statistics =
+            Statistics::Int32(ValueStatistics::new(Some(1), Some(9), None, None, false));
+        assert!(matches!(
+            bounds(&DataType::Int32, &statistics),
+            (Some(Value::Int(1)), Some(Value::Int(9)))
+        ));
+        // Dates are returned as text, so their statistics are not comparable
+        assert!(matches!(
+            bounds(&DataType::Date32, &statistics),
+            (None, None)
+        ));
+        let statistics = Statistics::ByteArray(ValueStatistics::new(
+            Some("a".into()),
+            Some("m".into()),
+            None,
+            None,
+            false,
+        ));
+        assert!(matches!(
+            bounds(&DataType::Utf8, &statistics),
+            (Some(Value::Text(min)), Some(Value::Text(max))) if min == "a" && max == "m"
+        ));
+    }
+}
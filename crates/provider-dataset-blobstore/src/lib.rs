@@ -0,0 +1,228 @@
+//! Dataset provider implementing `wasmcloud:dataset/reader`, which reads CSV and Parquet datasets
+//! stored in a linked blobstore.
+//!
+//! The provider is the source of links to blobstore providers implementing
+//! `wrpc:blobstore/blobstore`. Components read datasets from the blobstore linked to the provider
+//! with the same link name as the link of the component to the provider.
+
+use core::future::Future;
+use core::pin::Pin;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{bail, Context as _};
+use futures::Stream;
+use tokio::sync::RwLock;
+use tracing::{debug, error, instrument};
+use wasmcloud_provider_sdk::provider::WrpcClient;
+use wasmcloud_provider_sdk::stream::{stream_result, DEFAULT_BUFFER};
+use wasmcloud_provider_sdk::{
+    get_connection, initialize_observability, propagate_trace_for_ctx, run_provider,
+    serve_provider_exports, Context, LinkConfig, LinkDeleteInfo, Provider,
+};
+
+mod config;
+mod dataset;
+mod object;
+mod rows;
+
+use config::DatasetConfig;
+use dataset::Dataset;
+use object::BlobstoreObject;
+use rows::Selection;
+
+mod bindings {
+    wit_bindgen_wrpc::generate!({
+        with: {
+            "wasmcloud:dataset/reader@0.1.0-draft": generate,
+        }
+    });
+}
+use bindings::exports::wasmcloud::dataset::reader::{Column, Format, Handler, ReadOptions, Row};
+
+pub async fn run() -> anyhow::Result<()> {
+    DatasetBlobstoreProvider::run().await
+}
+
+/// Dataset provider reading from linked blobstores
+#[derive(Clone, Default)]
+pub struct DatasetBlobstoreProvider {
+    /// Dataset configuration of components, indexed by source ID
+    components: Arc<RwLock<HashMap<String, DatasetConfig>>>,
+    /// Clients of linked blobstores, indexed by link name
+    blobstores: Arc<RwLock<HashMap<String, Arc<WrpcClient>>>>,
+}
+
+impl DatasetBlobstoreProvider {
+    fn name() -> &'static str {
+        "dataset-blobstore-provider"
+    }
+
+    /// Run [`DatasetBlobstoreProvider`] as a wasmCloud provider
+    pub async fn run() -> anyhow::Result<()> {
+        initialize_observability!(
+            DatasetBlobstoreProvider::name(),
+            std::env::var_os("PROVIDER_DATASET_BLOBSTORE_FLAMEGRAPH_PATH")
+        );
+        let provider = DatasetBlobstoreProvider::default();
+        let shutdown = run_provider(provider.clone(), DatasetBlobstoreProvider::name())
+            .await
+            .context("failed to run provider")?;
+        let connection = get_connection();
+        let wrpc = connection
+            .get_wrpc_client(connection.provider_key())
+            .await?;
+        serve_provider_exports(&wrpc, provider, shutdown, bindings::serve)
+            .await
+            .context("failed to serve provider exports")
+    }
+
+    /// Get the configuration of the source of the invocation and the object in the blobstore
+    /// linked with the link name of the invocation
+    async fn object(
+        &self,
+        context: Option<Context>,
+        container: String,
+        object: String,
+    ) -> anyhow::Result<(DatasetConfig, BlobstoreObject)> {
+        let context = context.context("failed to lookup source of invocation")?;
+        let source_id = context
+            .component
+            .as_deref()
+            .context("failed to lookup source of invocation")?;
+        let config = self
+            .components
+            .read()
+            .await
+            .get(source_id)
+            .cloned()
+            .with_context(|| format!("component `{source_id}` is not linked to the provider"))?;
+        let link_name = context.link_name();
+        let wrpc = self
+            .blobstores
+            .read()
+            .await
+            .get(link_name)
+            .cloned()
+            .with_context(|| format!("no blobstore linked with link name `{link_name}`"))?;
+        Ok((config, BlobstoreObject::new(wrpc, container, object)))
+    }
+}
+
+impl Provider for DatasetBlobstoreProvider {
+    #[instrument(level = "info", skip_all, fields(source_id = link_config.source_id))]
+    async fn receive_link_config_as_target(
+        &self,
+        link_config: LinkConfig<'_>,
+    ) -> anyhow::Result<()> {
+        let config = match DatasetConfig::from_link_config(&link_config) {
+            Ok(config) => config,
+            Err(e) => {
+                error!(error = %e, source_id = %link_config.source_id, "failed to parse dataset configuration");
+                return Err(e);
+            }
+        };
+        self.components
+            .write()
+            .await
+            .insert(link_config.source_id.to_string(), config);
+        Ok(())
+    }
+
+    #[instrument(level = "info", skip_all, fields(target_id = link_config.target_id, link_name = link_config.link_name))]
+    async fn receive_link_config_as_source(
+        &self,
+        link_config: LinkConfig<'_>,
+    ) -> anyhow::Result<()> {
+        let (namespace, package, _) = link_config.wit_metadata;
+        if namespace.as_str() != "wrpc" || package.as_str() != "blobstore" {
+            bail!("unsupported link to `{namespace}:{package}`, only links to `wrpc:blobstore` are supported")
+        }
+        let wrpc = get_connection()
+            .get_wrpc_client(link_config.target_id)
+            .await
+            .context("failed to construct wRPC client")?;
+        debug!("linked blobstore");
+        self.blobstores
+            .write()
+            .await
+            .insert(link_config.link_name.to_string(), Arc::new(wrpc));
+        Ok(())
+    }
+
+    #[instrument(level = "info", skip_all, fields(source_id = info.get_source_id()))]
+    async fn delete_link_as_target(&self, info: impl LinkDeleteInfo) -> anyhow::Result<()> {
+        self.components.write().await.remove(info.get_source_id());
+        Ok(())
+    }
+
+    #[instrument(level = "info", skip_all, fields(target_id = info.get_target_id(), link_name = info.get_link_name()))]
+    async fn delete_link_as_source(&self, info: impl LinkDeleteInfo) -> anyhow::Result<()> {
+        self.blobstores.write().await.remove(info.get_link_name());
+        Ok(())
+    }
+
+    async fn shutdown(&self) -> anyhow::Result<()> {
+        self.components.write().await.drain();
+        self.blobstores.write().await.drain();
+        Ok(())
+    }
+}
+
+impl Handler<Option<Context>> for DatasetBlobstoreProvider {
+    #[instrument(level = "debug", skip(self, cx))]
+    async fn infer_schema(
+        &self,
+        cx: Option<Context>,
+        container: String,
+        object: String,
+        format: Option<Format>,
+    ) -> anyhow::Result<Result<Vec<Column>, String>> {
+        propagate_trace_for_ctx!(cx);
+        Ok(async {
+            let (config, object) = self.object(cx, container, object).await?;
+            let dataset = Dataset::open(object, format, &config).await?;
+            anyhow::Ok(rows::columns(dataset.schema()))
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "debug", skip(self, cx, options))]
+    async fn read_rows(
+        &self,
+        cx: Option<Context>,
+        container: String,
+        object: String,
+        options: ReadOptions,
+    ) -> anyhow::Result<
+        Result<
+            (
+                Vec<Column>,
+                Pin<Box<dyn Stream<Item = Vec<Row>> + Send>>,
+                Pin<Box<dyn Future<Output = Result<(), String>> + Send>>,
+            ),
+            String,
+        >,
+    > {
+        propagate_trace_for_ctx!(cx);
+        Ok(async {
+            let (config, object) = self.object(cx, container, object).await?;
+            let ReadOptions {
+                format,
+                columns,
+                predicates,
+                limit,
+            } = options;
+            let dataset = Dataset::open(object, format, &config).await?;
+            let selection = Selection::new(dataset.schema(), columns, predicates, limit)?;
+            let columns = selection.columns(dataset.schema());
+            let rows = dataset.read_rows(selection).await?;
+            let (rows, status) = stream_result(rows, DEFAULT_BUFFER);
+            anyhow::Ok((columns, rows, status))
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+}
@@ -0,0 +1,97 @@
+//! Access to the objects of a linked blobstore
+
+use core::future::Future;
+use core::ops::Range;
+use core::pin::Pin;
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context as _};
+use bytes::{Bytes, BytesMut};
+use futures::{Stream, StreamExt as _};
+use tracing::instrument;
+use wasmcloud_provider_sdk::provider::WrpcClient;
+use wrpc_interface_blobstore::bindings::wrpc::blobstore::{blobstore, types::ObjectId};
+
+/// Stream of the bytes of an object
+pub type DataStream = Pin<Box<dyn Stream<Item = Bytes> + Send>>;
+
+/// Future resolving to the status of a [`DataStream`] once it was consumed
+pub type DataStatus = Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send>>;
+
+/// An object stored in a linked blobstore
+#[derive(Clone)]
+pub struct BlobstoreObject {
+    wrpc: Arc<WrpcClient>,
+    id: ObjectId,
+}
+
+impl BlobstoreObject {
+    pub fn new(wrpc: Arc<WrpcClient>, container: String, object: String) -> Self {
+        Self {
+            wrpc,
+            id: ObjectId { container, object },
+        }
+    }
+
+    /// Name of the object within its container
+    pub fn name(&self) -> &str {
+        &self.id.object
+    }
+
+    /// Get the size of the object, in bytes
+    #[instrument(level = "trace", skip(self), fields(container = %self.id.container, object = %self.id.object))]
+    pub async fn size(&self) -> anyhow::Result<u64> {
+        let metadata = blobstore::get_object_info(&*self.wrpc, None, &self.id)
+            .await
+            .context("failed to invoke `get-object-info`")?
+            .map_err(|err| anyhow!(err).context("failed to get object info"))?;
+        Ok(metadata.size)
+    }
+
+    /// Stream the bytes of the object starting at offset `start`, up to offset `end`.
+    ///
+    /// Blobstore providers differ in whether `end` is inclusive, so the stream may contain the
+    /// byte at offset `end`. The returned status future must be awaited after the stream was
+    /// consumed
+    #[instrument(level = "trace", skip(self), fields(container = %self.id.container, object = %self.id.object))]
+    pub async fn stream(&self, start: u64, end: u64) -> anyhow::Result<(DataStream, DataStatus)> {
+        let (res, io) = blobstore::get_container_data(&*self.wrpc, None, &self.id, start, end)
+            .await
+            .context("failed to invoke `get-container-data`")?;
+        let (data, status) =
+            res.map_err(|err| anyhow!(err).context("failed to get object data"))?;
+        let io = io.map(tokio::spawn);
+        Ok((
+            data,
+            Box::pin(async move {
+                if let Some(io) = io {
+                    io.await
+                        .context("failed to join I/O task")?
+                        .context("failed to complete async I/O")?;
+                }
+                status
+                    .await
+                    .map_err(|err| anyhow!(err).context("failed to read object data"))
+            }),
+        ))
+    }
+
+    /// Read the bytes of the object in `range`
+    pub async fn read(&self, range: Range<u64>) -> anyhow::Result<Bytes> {
+        let len = usize::try_from(range.end.saturating_sub(range.start))
+            .context("range does not fit in memory")?;
+        if len == 0 {
+            return Ok(Bytes::new());
+        }
+        let (mut data, status) = self.stream(range.start, range.end).await?;
+        let mut buf = BytesMut::with_capacity(len);
+        while let Some(chunk) = data.next().await {
+            buf.extend_from_slice(&chunk);
+        }
+        status.await?;
+        // Drop the byte at `range.end` returned by blobstores with inclusive ranges
+        buf.truncate(len);
+        Ok(buf.freeze())
+    }
+}
@@ -0,0 +1,412 @@
+//! Conversion of record batches into the rows returned to components
+
+use core::cmp::Ordering;
+
+use anyhow::{ensure, Context as _, Result};
+use arrow::array::{Array, ArrayRef, AsArray as _};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Float64Type, Int64Type, Schema};
+use arrow::record_batch::RecordBatch;
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use bytes::Bytes;
+
+use crate::bindings::exports::wasmcloud::dataset::reader::{
+    Column, Comparison, Predicate, Row, Value,
+};
+
+/// Columns of a dataset
+pub fn columns(schema: &Schema) -> Vec<Column> {
+    schema
+        .fields()
+        .iter()
+        .map(|field| Column {
+            name: field.name().clone(),
+            data_type: field.data_type().to_string(),
+            nullable: field.is_nullable(),
+        })
+        .collect()
+}
+
+/// Selection of the rows and columns of a dataset returned to a component
+pub struct Selection {
+    /// Names of the columns returned, in order
+    columns: Vec<String>,
+    /// Predicates that all rows returned match
+    predicates: Vec<Predicate>,
+    /// Number of rows left to return
+    remaining: Option<u64>,
+}
+
+impl Selection {
+    /// Construct a [`Selection`] of the dataset with `schema`, ensuring that all columns exist
+    pub fn new(
+        schema: &Schema,
+        columns: Option<Vec<String>>,
+        predicates: Vec<Predicate>,
+        limit: Option<u64>,
+    ) -> Result<Self> {
+        let columns = columns.unwrap_or_else(|| {
+            schema
+                .fields()
+                .iter()
+                .map(|field| field.name().clone())
+                .collect()
+        });
+        ensure!(!columns.is_empty(), "at least one column must be read");
+        for name in columns
+            .iter()
+            .chain(predicates.iter().map(|predicate| &predicate.column))
+        {
+            schema
+                .field_with_name(name)
+                .with_context(|| format!("dataset has no column `{name}`"))?;
+        }
+        Ok(Self {
+            columns,
+            predicates,
+            remaining: limit,
+        })
+    }
+
+    /// Predicates that all rows returned match
+    pub fn predicates(&self) -> &[Predicate] {
+        &self.predicates
+    }
+
+    /// Whether rows may only be read up to the limit, which is the case unless rows are filtered
+    pub fn limit(&self) -> Option<u64> {
+        if self.predicates.is_empty() {
+            self.remaining
+        } else {
+            None
+        }
+    }
+
+    /// Sorted indices of the columns of `schema` that need to be decoded
+    pub fn projection(&self, schema: &Schema) -> Vec<usize> {
+        let mut indices: Vec<_> = self
+            .columns
+            .iter()
+            .chain(self.predicates.iter().map(|predicate| &predicate.column))
+            .filter_map(|name| schema.index_of(name).ok())
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+
+    /// Columns returned, in order
+    pub fn columns(&self, schema: &Schema) -> Vec<Column> {
+        let columns = columns(schema);
+        self.columns
+            .iter()
+            .filter_map(|name| columns.iter().find(|column| column.name == *name).cloned())
+            .collect()
+    }
+
+    /// Whether all rows up to the limit were returned
+    pub fn is_done(&self) -> bool {
+        self.remaining == Some(0)
+    }
+
+    /// Select the rows of `batch` matching all predicates, up to the limit
+    pub fn rows(&mut self, batch: &RecordBatch) -> Result<Vec<Row>> {
+        let column = |name: &str| {
+            batch
+                .column_by_name(name)
+                .with_context(|| format!("record batch has no column `{name}`"))
+                .and_then(values)
+        };
+        let filters = self
+            .predicates
+            .iter()
+            .map(|predicate| Ok((predicate, column(&predicate.column)?)))
+            .collect::<Result<Vec<_>>>()?;
+        let mut columns = self
+            .columns
+            .iter()
+            .map(|name| column(name).map(Vec::into_iter))
+            .collect::<Result<Vec<_>>>()?;
+        let mut rows = Vec::new();
+        for i in 0..batch.num_rows() {
+            if self.is_done() {
+                break;
+            }
+            let row: Row = columns
+                .iter_mut()
+                .map(|values| values.next().unwrap_or(Value::Null))
+                .collect();
+            if !filters
+                .iter()
+                .all(|(predicate, values)| matches(&values[i], &predicate.op, &predicate.value))
+            {
+                continue;
+            }
+            rows.push(row);
+            if let Some(remaining) = self.remaining.as_mut() {
+                *remaining -= 1;
+            }
+        }
+        Ok(rows)
+    }
+}
+
+/// Convert the values of `array`
+pub fn values(array: &ArrayRef) -> Result<Vec<Value>> {
+    let values = match array.data_type() {
+        DataType::Null => vec![Value::Null; array.len()],
+        DataType::Boolean => array
+            .as_boolean()
+            .iter()
+            .map(|v| v.map_or(Value::Null, Value::Boolean))
+            .collect(),
+        // Unsigned 64-bit integers exceeding the range of `s64` are converted to null
+        DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::UInt8
+        | DataType::UInt16
+        | DataType::UInt32
+        | DataType::UInt64 => cast(array, &DataType::Int64)
+            .context("failed to convert integers")?
+            .as_primitive::<Int64Type>()
+            .iter()
+            .map(|v| v.map_or(Value::Null, Value::Int))
+            .collect(),
+        DataType::Float16 | DataType::Float32 | DataType::Float64 => {
+            cast(array, &DataType::Float64)
+                .context("failed to convert floating point numbers")?
+                .as_primitive::<Float64Type>()
+                .iter()
+                .map(|v| v.map_or(Value::Null, Value::Float))
+                .collect()
+        }
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => cast(array, &DataType::Utf8)
+            .context("failed to convert strings")?
+            .as_string::<i32>()
+            .iter()
+            .map(|v| v.map_or(Value::Null, |v| Value::Text(v.to_string())))
+            .collect(),
+        DataType::Binary
+        | DataType::LargeBinary
+        | DataType::BinaryView
+        | DataType::FixedSizeBinary(..) => cast(array, &DataType::Binary)
+            .context("failed to convert binary values")?
+            .as_binary::<i32>()
+            .iter()
+            .map(|v| v.map_or(Value::Null, |v| Value::Bytes(Bytes::copy_from_slice(v))))
+            .collect(),
+        _ => {
+            let formatter = ArrayFormatter::try_new(array.as_ref(), &FormatOptions::default())
+                .context("failed to format values")?;
+            (0..array.len())
+                .map(|i| {
+                    if array.is_null(i) {
+                        Value::Null
+                    } else {
+                        Value::Text(formatter.value(i).to_string())
+                    }
+                })
+                .collect()
+        }
+    };
+    Ok(values)
+}
+
+/// Compare values of compatible types, returning `None` for nulls and incompatible types
+pub fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Boolean(a), Value::Boolean(b)) => a.partial_cmp(b),
+        (Value::Int(a), Value::Int(b)) => a.partial_cmp(b),
+        (Value::Float(a), Value::Float(b)) => a.partial_cmp(b),
+        (Value::Int(a), Value::Float(b)) => (*a as f64).partial_cmp(b),
+        (Value::Float(a), Value::Int(b)) => a.partial_cmp(&(*b as f64)),
+        (Value::Text(a), Value::Text(b)) => a.partial_cmp(b),
+        (Value::Bytes(a), Value::Bytes(b)) => a.partial_cmp(b),
+        _ => None,
+    }
+}
+
+/// Whether `value` compares to `operand` as required by `op`
+pub fn matches(value: &Value, op: &Comparison, operand: &Value) -> bool {
+    let Some(ordering) = compare(value, operand) else {
+        return false;
+    };
+    match op {
+        Comparison::Eq => ordering.is_eq(),
+        Comparison::Ne => ordering.is_ne(),
+        Comparison::Lt => ordering.is_lt(),
+        Comparison::Le => ordering.is_le(),
+        Comparison::Gt => ordering.is_gt(),
+        Comparison::Ge => ordering.is_ge(),
+    }
+}
+
+/// Whether any value between `min` and `max` may compare to `operand` as required by `op`.
+/// Returns `true` if the bounds are unknown or not comparable with `operand`
+pub fn may_match(
+    min: Option<&Value>,
+    max: Option<&Value>,
+    op: &Comparison,
+    operand: &Value,
+) -> bool {
+    let (Some(min), Some(max)) = (
+        min.and_then(|min| compare(min, operand)),
+        max.and_then(|max| compare(max, operand)),
+    ) else {
+        return true;
+    };
+    match op {
+        Comparison::Eq => min.is_le() && max.is_ge(),
+        Comparison::Ne => !(min.is_eq() && max.is_eq()),
+        Comparison::Lt => min.is_lt(),
+        Comparison::Le => min.is_le(),
+        Comparison::Gt => max.is_gt(),
+        Comparison::Ge => max.is_ge(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use arrow::array::{Int32Array, StringArray, TimestampSecondArray};
+    use arrow::datatypes::Field;
+
+    use super::*;
+
+    fn batch() -> RecordBatch {
+        RecordBatch::try_from_iter([
+            (
+                "id",
+                Arc::new(Int32Array::from(vec![1, 2, 3, 4])) as ArrayRef,
+            ),
+            (
+                "name",
+                Arc::new(StringArray::from(vec![
+                    Some("a"),
+                    None,
+                    Some("c"),
+                    Some("d"),
+                ])) as ArrayRef,
+            ),
+            (
+                "created",
+                Arc::new(TimestampSecondArray::from(vec![0, 60, 120, 180])) as ArrayRef,
+            ),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn select_rows() {
+        let batch = batch();
+        let mut selection = Selection::new(
+            &batch.schema(),
+            Some(vec!["name".into(), "created".into()]),
+            vec![Predicate {
+                column: "id".into(),
+                op: Comparison::Ge,
+                value: Value::Float(2.0),
+            }],
+            Some(2),
+        )
+        .unwrap();
+        assert_eq!(selection.projection(&batch.schema()), [0, 1, 2]);
+        assert_eq!(selection.limit(), None);
+        let rows = selection.rows(&batch).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert!(
+            matches!(rows[0][..], [Value::Null, Value::Text(ref created)] if created == "1970-01-01T00:01:00")
+        );
+        assert!(matches!(rows[1][..], [Value::Text(ref name), _] if name == "c"));
+        assert!(selection.is_done());
+
+        let mut selection = Selection::new(&batch.schema(), None, vec![], None).unwrap();
+        assert_eq!(selection.rows(&batch).unwrap().len(), 4);
+        assert_eq!(
+            selection
+                .columns(&batch.schema())
+                .into_iter()
+                .map(|column| column.name)
+                .collect::<Vec<_>>(),
+            ["id", "name", "created"]
+        );
+
+        assert!(
+            Selection::new(&batch.schema(), Some(vec!["missing".into()]), vec![], None).is_err()
+        );
+        assert!(Selection::new(&batch.schema(), Some(vec![]), vec![], None).is_err());
+    }
+
+    #[test]
+    fn schema_columns() {
+        let schema = Schema::new(vec![Field::new("id", DataType::Int64, false)]);
+        let columns = columns(&schema);
+        assert_eq!(columns.len(), 1);
+        assert_eq!(columns[0].name, "id");
+        assert_eq!(columns[0].data_type, "Int64");
+        assert!(!columns[0].nullable);
+    }
+
+    #[test]
+    fn compare_values() {
+        assert!(matches(&Value::Int(2), &Comparison::Lt, &Value::Float(2.5)));
+        assert!(matches(
+            &Value::Text("b".into()),
+            &Comparison::Gt,
+            &Value::Text("a".into())
+        ));
+        assert!(!matches(&Value::Null, &Comparison::Eq, &Value::Null));
+        assert!(!matches(
+            &Value::Int(1),
+            &Comparison::Ne,
+            &Value::Text("1".into())
+        ));
+
+        let (min, max) = (Value::Int(10), Value::Int(20));
+        assert!(may_match(
+            Some(&min),
+            Some(&max),
+            &Comparison::Eq,
+            &Value::Int(15)
+        ));
+        assert!(!may_match(
+            Some(&min),
+            Some(&max),
+            &Comparison::Eq,
+            &Value::Int(25)
+        ));
+        assert!(!may_match(
+            Some(&min),
+            Some(&max),
+            &Comparison::Lt,
+            &Value::Int(10)
+        ));
+        assert!(may_match(
+            Some(&min),
+            Some(&max),
+            &Comparison::Le,
+            &Value::Int(10)
+        ));
+        assert!(!may_match(
+            Some(&min),
+            Some(&max),
+            &Comparison::Gt,
+            &Value::Float(20.0)
+        ));
+        assert!(may_match(
+            None,
+            Some(&max),
+            &Comparison::Gt,
+            &Value::Int(30)
+        ));
+        assert!(may_match(
+            Some(&min),
+            Some(&max),
+            &Comparison::Gt,
+            &Value::Text("30".into())
+        ));
+    }
+}
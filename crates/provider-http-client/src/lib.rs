@@ -1,14 +1,18 @@
 use core::convert::Infallible;
 use core::pin::pin;
+use core::time::Duration;
 
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use anyhow::Context as _;
 use bytes::Bytes;
 use futures::StreamExt as _;
 use http_body::Frame;
-use http_body_util::{BodyExt as _, StreamBody};
+use http_body_util::{BodyExt as _, Either, Empty, StreamBody};
+use hyper_util::client::legacy::connect::HttpConnector;
 use hyper_util::rt::TokioExecutor;
+use tokio::sync::RwLock;
 use tokio::task::JoinSet;
 use tokio::{select, spawn};
 use tracing::{debug, error, instrument, trace, warn, Instrument};
@@ -16,19 +20,30 @@ use tracing::{debug, error, instrument, trace, warn, Instrument};
 use wasmcloud_provider_sdk::core::tls;
 use wasmcloud_provider_sdk::{
     get_connection, initialize_observability, load_host_data, propagate_trace_for_ctx,
-    run_provider, Context, Provider,
+    run_provider, Context, LinkConfig, LinkDeleteInfo, Provider,
 };
+use wrpc_interface_http::bindings::wasi::http::types::ErrorCode;
 use wrpc_interface_http::{
-    split_outgoing_http_body, try_fields_to_header_map, ServeHttp, ServeOutgoingHandlerHttp,
+    split_outgoing_http_body, try_fields_to_header_map, HttpBody, ServeHttp,
+    ServeOutgoingHandlerHttp,
 };
 
+use crate::proxy::{Proxies, Proxy, ProxyConnector};
+
+mod proxy;
+
+/// Body of outgoing requests, redirected requests are sent without a body
+type RequestBody = Either<HttpBody, Empty<Bytes>>;
+
 /// HTTP client capability provider implementation struct
 #[derive(Clone)]
 pub struct HttpClientProvider {
-    client: hyper_util::client::legacy::Client<
-        hyper_rustls::HttpsConnector<hyper_util::client::legacy::connect::HttpConnector>,
-        wrpc_interface_http::HttpBody,
-    >,
+    /// Client used by components linked without configuration
+    default_client: OutgoingClient,
+    /// Provider configuration, which link configuration is applied on top of
+    config: Arc<HashMap<String, String>>,
+    /// Clients of components linked with configuration, keyed by component ID
+    clients: Arc<RwLock<HashMap<String, OutgoingClient>>>,
 }
 
 const DEFAULT_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
@@ -36,6 +51,18 @@ const DEFAULT_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARG
 const LOAD_NATIVE_CERTS: &str = "load_native_certs";
 const LOAD_WEBPKI_CERTS: &str = "load_webpki_certs";
 const SSL_CERTS_FILE: &str = "ssl_certs_file";
+const HTTP_PROXY: &str = "http_proxy";
+const HTTPS_PROXY: &str = "https_proxy";
+const NO_PROXY: &str = "no_proxy";
+const CONNECT_TIMEOUT_MS: &str = "connect_timeout_ms";
+const REQUEST_TIMEOUT_MS: &str = "request_timeout_ms";
+const MAX_REDIRECTS: &str = "max_redirects";
+const POOL_MAX_IDLE_PER_HOST: &str = "pool_max_idle_per_host";
+const POOL_IDLE_TIMEOUT_SECS: &str = "pool_idle_timeout_secs";
+
+/// Headers which are not forwarded when a request is redirected to another host
+const SENSITIVE_HEADERS: [http::header::HeaderName; 2] =
+    [http::header::AUTHORIZATION, http::header::COOKIE];
 
 pub async fn run() -> anyhow::Result<()> {
     initialize_observability!(
@@ -83,64 +110,272 @@ pub async fn run() -> anyhow::Result<()> {
     }
 }
 
-impl HttpClientProvider {
-    pub async fn new(config: &HashMap<String, String>) -> anyhow::Result<Self> {
-        // Short circuit to the default connector if no configuration is provided
-        if config.is_empty() {
-            return Ok(Self {
-                client: hyper_util::client::legacy::Client::builder(TokioExecutor::new())
-                    .build(tls::DEFAULT_HYPER_CONNECTOR.clone()),
-            });
-        }
+/// Parse the optional numeric configuration value at `key`
+fn parse_config<T>(config: &HashMap<String, String>, key: &str) -> anyhow::Result<Option<T>>
+where
+    T: core::str::FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    config
+        .get(key)
+        .map(|v| {
+            v.trim()
+                .parse()
+                .with_context(|| format!("invalid value of `{key}`: `{v}`"))
+        })
+        .transpose()
+}
 
-        let mut ca = rustls::RootCertStore::empty();
+/// Look up a proxy configuration value, which may also be specified in uppercase, like the
+/// environment variables of the same name
+fn proxy_config<'a>(config: &'a HashMap<String, String>, key: &str) -> Option<&'a str> {
+    config
+        .get(key)
+        .or_else(|| config.get(&key.to_ascii_uppercase()))
+        .map(String::as_str)
+        .filter(|v| !v.is_empty())
+}
 
-        // Load native certificates
-        if config
-            .get(LOAD_NATIVE_CERTS)
-            .map(|v| v.eq_ignore_ascii_case("true"))
-            .unwrap_or(true)
-        {
-            let (added, ignored) = ca.add_parsable_certificates(tls::NATIVE_ROOTS.iter().cloned());
-            tracing::debug!(added, ignored, "loaded native root certificate store");
-        }
+/// HTTP client along with the request policy it was configured with
+#[derive(Clone)]
+struct OutgoingClient {
+    client: hyper_util::client::legacy::Client<
+        hyper_rustls::HttpsConnector<ProxyConnector>,
+        RequestBody,
+    >,
+    proxies: Arc<Proxies>,
+    /// Time to wait for response headers, including redirects
+    request_timeout: Option<Duration>,
+    /// Number of redirects to follow, redirects are returned to the component if zero
+    max_redirects: usize,
+}
 
-        // Load Mozilla trusted root certificates
-        if config
-            .get(LOAD_WEBPKI_CERTS)
-            .map(|v| v.eq_ignore_ascii_case("true"))
-            .unwrap_or(true)
+impl OutgoingClient {
+    fn new(config: &HashMap<String, String>) -> anyhow::Result<Self> {
+        // Short circuit to the default TLS configuration if no CA configuration is provided
+        let tls_config = if [LOAD_NATIVE_CERTS, LOAD_WEBPKI_CERTS, SSL_CERTS_FILE]
+            .iter()
+            .all(|key| !config.contains_key(*key))
         {
-            ca.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
-            tracing::debug!("loaded webpki root certificate store");
-        }
+            tls::DEFAULT_CLIENT_CONFIG.clone()
+        } else {
+            let mut ca = rustls::RootCertStore::empty();
 
-        // Load root certificates from a file
-        if let Some(file_path) = config.get(SSL_CERTS_FILE) {
-            let f = std::fs::File::open(file_path)?;
-            let mut reader = std::io::BufReader::new(f);
-            let certs = rustls_pemfile::certs(&mut reader).collect::<Result<Vec<_>, _>>()?;
-            let (added, ignored) = ca.add_parsable_certificates(certs);
-            tracing::debug!(
-                added,
-                ignored,
-                "added additional root certificates from file"
-            );
+            // Load native certificates
+            if config
+                .get(LOAD_NATIVE_CERTS)
+                .map(|v| v.eq_ignore_ascii_case("true"))
+                .unwrap_or(true)
+            {
+                let (added, ignored) =
+                    ca.add_parsable_certificates(tls::NATIVE_ROOTS.iter().cloned());
+                tracing::debug!(added, ignored, "loaded native root certificate store");
+            }
+
+            // Load Mozilla trusted root certificates
+            if config
+                .get(LOAD_WEBPKI_CERTS)
+                .map(|v| v.eq_ignore_ascii_case("true"))
+                .unwrap_or(true)
+            {
+                ca.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+                tracing::debug!("loaded webpki root certificate store");
+            }
+
+            // Load root certificates from a file
+            if let Some(file_path) = config.get(SSL_CERTS_FILE) {
+                let f = std::fs::File::open(file_path)
+                    .with_context(|| format!("failed to open `{file_path}`"))?;
+                let mut reader = std::io::BufReader::new(f);
+                let certs = rustls_pemfile::certs(&mut reader).collect::<Result<Vec<_>, _>>()?;
+                let (added, ignored) = ca.add_parsable_certificates(certs);
+                tracing::debug!(
+                    added,
+                    ignored,
+                    "added additional root certificates from file"
+                );
+            }
+
+            rustls::ClientConfig::builder()
+                .with_root_certificates(ca)
+                .with_no_client_auth()
+        };
+
+        let proxies = Arc::new(Proxies {
+            http: proxy_config(config, HTTP_PROXY)
+                .map(Proxy::parse)
+                .transpose()?,
+            https: proxy_config(config, HTTPS_PROXY)
+                .map(Proxy::parse)
+                .transpose()?,
+            no_proxy: proxy_config(config, NO_PROXY)
+                .map(Proxies::parse_no_proxy)
+                .unwrap_or_default(),
+        });
+        if !proxies.is_empty() {
+            debug!(no_proxy = ?proxies.no_proxy, "using HTTP proxy");
         }
 
-        let tls_config = rustls::ClientConfig::builder()
-            .with_root_certificates(ca)
-            .with_no_client_auth();
+        let mut http = HttpConnector::new();
+        http.set_connect_timeout(
+            parse_config(config, CONNECT_TIMEOUT_MS)?.map(Duration::from_millis),
+        );
         let https = hyper_rustls::HttpsConnectorBuilder::new()
             .with_tls_config(tls_config)
             .https_or_http()
             .enable_all_versions()
-            .build();
+            .wrap_connector(ProxyConnector::new(http, Arc::clone(&proxies)));
+
+        let mut builder = hyper_util::client::legacy::Client::builder(TokioExecutor::new());
+        if let Some(max_idle) = parse_config(config, POOL_MAX_IDLE_PER_HOST)? {
+            builder.pool_max_idle_per_host(max_idle);
+        }
+        if let Some(idle_timeout) = parse_config(config, POOL_IDLE_TIMEOUT_SECS)? {
+            builder.pool_idle_timeout(Duration::from_secs(idle_timeout));
+        }
+        Ok(Self {
+            client: builder.build(https),
+            proxies,
+            request_timeout: parse_config(config, REQUEST_TIMEOUT_MS)?.map(Duration::from_millis),
+            max_redirects: parse_config(config, MAX_REDIRECTS)?.unwrap_or_default(),
+        })
+    }
+
+    /// Send a single request
+    async fn request(
+        &self,
+        mut request: http::Request<RequestBody>,
+    ) -> Result<http::Response<hyper::body::Incoming>, ErrorCode> {
+        // Plain HTTP requests are forwarded by the proxy, so they carry the proxy credentials
+        if request.uri().scheme() == Some(&http::uri::Scheme::HTTP) {
+            if let Some(authorization) = self
+                .proxies
+                .proxy_for(request.uri())
+                .and_then(Proxy::authorization)
+            {
+                request
+                    .headers_mut()
+                    .insert(http::header::PROXY_AUTHORIZATION, authorization.clone());
+            }
+        }
+        debug!(uri = ?request.uri(), "sending HTTP request");
+        self.client
+            .request(request)
+            .instrument(tracing::debug_span!("http_request"))
+            .await
+            .map_err(|err| {
+                // TODO: Convert error
+                ErrorCode::InternalError(Some(err.to_string()))
+            })
+    }
 
+    /// Send a request, following redirects up to the configured limit
+    async fn send(
+        &self,
+        mut request: http::Request<RequestBody>,
+    ) -> Result<http::Response<hyper::body::Incoming>, ErrorCode> {
+        if self.max_redirects == 0 {
+            return self.request(request).await;
+        }
+        let mut redirects = 0;
+        loop {
+            let method = request.method().clone();
+            let uri = request.uri().clone();
+            let headers = request.headers().clone();
+            let res = self.request(request).await?;
+            let Some(next) = redirect(&method, &uri, headers, &res)? else {
+                return Ok(res);
+            };
+            if redirects == self.max_redirects {
+                return Err(ErrorCode::LoopDetected);
+            }
+            redirects += 1;
+            debug!(?uri, location = ?next.uri(), redirects, "following redirect");
+            request = next;
+        }
+    }
+}
+
+/// Construct the request following a redirect response, if `res` is a redirect which can be
+/// followed. Only requests without side effects are redirected, since the body of the original
+/// request is already consumed, except for `303 See Other`, which is followed with a `GET`
+fn redirect<T>(
+    method: &http::Method,
+    uri: &http::Uri,
+    mut headers: http::HeaderMap,
+    res: &http::Response<T>,
+) -> Result<Option<http::Request<RequestBody>>, ErrorCode> {
+    use http::{Method, StatusCode};
+
+    let method = match res.status() {
+        StatusCode::SEE_OTHER if *method == Method::HEAD => Method::HEAD,
+        StatusCode::SEE_OTHER => Method::GET,
+        StatusCode::MOVED_PERMANENTLY
+        | StatusCode::FOUND
+        | StatusCode::TEMPORARY_REDIRECT
+        | StatusCode::PERMANENT_REDIRECT
+            if *method == Method::GET || *method == Method::HEAD =>
+        {
+            method.clone()
+        }
+        _ => return Ok(None),
+    };
+    let Some(location) = res
+        .headers()
+        .get(http::header::LOCATION)
+        .and_then(|v| v.to_str().ok())
+    else {
+        return Ok(None);
+    };
+    let location = url::Url::parse(&uri.to_string())
+        .and_then(|base| base.join(location))
+        .map_err(|_| ErrorCode::HttpProtocolError)
+        .and_then(|location| {
+            location
+                .as_str()
+                .parse::<http::Uri>()
+                .map_err(|_| ErrorCode::HttpProtocolError)
+        })?;
+    if location.host() != uri.host() {
+        for name in &SENSITIVE_HEADERS {
+            headers.remove(name);
+        }
+    }
+    for name in [
+        http::header::CONTENT_LENGTH,
+        http::header::CONTENT_TYPE,
+        http::header::CONTENT_ENCODING,
+        http::header::TRANSFER_ENCODING,
+        http::header::HOST,
+        // Added again if the redirected request is sent through a proxy
+        http::header::PROXY_AUTHORIZATION,
+    ] {
+        headers.remove(name);
+    }
+    let mut req = http::Request::new(Either::Right(Empty::new()));
+    *req.method_mut() = method;
+    *req.uri_mut() = location;
+    *req.headers_mut() = headers;
+    Ok(Some(req))
+}
+
+impl HttpClientProvider {
+    pub async fn new(config: &HashMap<String, String>) -> anyhow::Result<Self> {
         Ok(Self {
-            client: hyper_util::client::legacy::Client::builder(TokioExecutor::new()).build(https),
+            default_client: OutgoingClient::new(config)?,
+            config: Arc::new(config.clone()),
+            clients: Arc::default(),
         })
     }
+
+    /// Get the client used for requests of `component`
+    async fn client(&self, component: Option<&str>) -> OutgoingClient {
+        let clients = self.clients.read().await;
+        component
+            .and_then(|component| clients.get(component))
+            .unwrap_or(&self.default_client)
+            .clone()
+    }
 }
 
 impl ServeOutgoingHandlerHttp<Option<Context>> for HttpClientProvider {
@@ -148,14 +383,17 @@ impl ServeOutgoingHandlerHttp<Option<Context>> for HttpClientProvider {
     async fn handle(
         &self,
         cx: Option<Context>,
-        mut request: http::Request<wrpc_interface_http::HttpBody>,
+        mut request: http::Request<HttpBody>,
         options: Option<wrpc_interface_http::bindings::wrpc::http::types::RequestOptions>,
     ) -> anyhow::Result<
         Result<
             http::Response<impl http_body::Body<Data = Bytes, Error = Infallible> + Send + 'static>,
-            wrpc_interface_http::bindings::wasi::http::types::ErrorCode,
+            ErrorCode,
         >,
     > {
+        let client = self
+            .client(cx.as_ref().and_then(|cx| cx.component.as_deref()))
+            .await;
         propagate_trace_for_ctx!(cx);
         wasmcloud_provider_sdk::wasmcloud_tracing::http::HeaderInjector(request.headers_mut())
             .inject_context();
@@ -168,18 +406,14 @@ impl ServeOutgoingHandlerHttp<Option<Context>> for HttpClientProvider {
             .entry(http::header::USER_AGENT)
             .or_insert(http::header::HeaderValue::from_static(DEFAULT_USER_AGENT));
         Ok(async {
-            debug!(uri = ?request.uri(), "sending HTTP request");
-            let res = self
-                .client
-                .request(request)
-                .instrument(tracing::debug_span!("http_request"))
-                .await
-                .map_err(|err| {
-                    // TODO: Convert error
-                    wrpc_interface_http::bindings::wasi::http::types::ErrorCode::InternalError(
-                        Some(err.to_string()),
-                    )
-                })?;
+            let request = request.map(Either::Left);
+            let res = if let Some(timeout) = client.request_timeout {
+                tokio::time::timeout(timeout, client.send(request))
+                    .await
+                    .map_err(|_| ErrorCode::HttpResponseTimeout)??
+            } else {
+                client.send(request).await?
+            };
             trace!("HTTP response received");
             Ok(res.map(|body| {
                 let (data, trailers, mut errs) = split_outgoing_http_body(body);
@@ -215,4 +449,102 @@ impl ServeOutgoingHandlerHttp<Option<Context>> for HttpClientProvider {
 }
 
 /// Handle provider control commands
-impl Provider for HttpClientProvider {}
+impl Provider for HttpClientProvider {
+    /// Configure the client of a linked component, applying the link configuration on top of
+    /// the provider configuration
+    #[instrument(level = "debug", skip_all, fields(source_id = link_config.source_id))]
+    async fn receive_link_config_as_target(
+        &self,
+        link_config: LinkConfig<'_>,
+    ) -> anyhow::Result<()> {
+        if link_config.config.is_empty() {
+            self.clients.write().await.remove(link_config.source_id);
+            return Ok(());
+        }
+        let mut config = HashMap::clone(&self.config);
+        config.extend(
+            link_config
+                .config
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone())),
+        );
+        let client = OutgoingClient::new(&config).context("failed to configure HTTP client")?;
+        self.clients
+            .write()
+            .await
+            .insert(link_config.source_id.to_string(), client);
+        Ok(())
+    }
+
+    #[instrument(level = "debug", skip_all, fields(source_id = info.get_source_id()))]
+    async fn delete_link_as_target(&self, info: impl LinkDeleteInfo) -> anyhow::Result<()> {
+        self.clients.write().await.remove(info.get_source_id());
+        Ok(())
+    }
+
+    async fn shutdown(&self) -> anyhow::Result<()> {
+        self.clients.write().await.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use http::{header, HeaderMap, Method, Response, StatusCode};
+
+    use super::redirect;
+
+    #[test]
+    fn redirects() {
+        let res = |status: StatusCode, location: &str| {
+            Response::builder()
+                .status(status)
+                .header(header::LOCATION, location)
+                .body(())
+                .unwrap()
+        };
+        let uri = "https://example.com/a/b?c=d".parse().unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, "Bearer token".parse().unwrap());
+        headers.insert(header::CONTENT_LENGTH, "3".parse().unwrap());
+
+        let req = redirect(
+            &Method::GET,
+            &uri,
+            headers.clone(),
+            &res(StatusCode::FOUND, "../e"),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(req.method(), Method::GET);
+        assert_eq!(req.uri(), "https://example.com/e");
+        assert!(req.headers().contains_key(header::AUTHORIZATION));
+        assert!(!req.headers().contains_key(header::CONTENT_LENGTH));
+
+        let req = redirect(
+            &Method::POST,
+            &uri,
+            headers.clone(),
+            &res(StatusCode::SEE_OTHER, "https://other.example.com/"),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(req.method(), Method::GET);
+        assert_eq!(req.uri(), "https://other.example.com/");
+        assert!(!req.headers().contains_key(header::AUTHORIZATION));
+
+        assert!(redirect(
+            &Method::POST,
+            &uri,
+            headers.clone(),
+            &res(StatusCode::TEMPORARY_REDIRECT, "/e"),
+        )
+        .unwrap()
+        .is_none());
+        assert!(
+            redirect(&Method::GET, &uri, headers, &res(StatusCode::OK, "/e"))
+                .unwrap()
+                .is_none()
+        );
+    }
+}
@@ -12,7 +12,6 @@ use std::sync::Arc;
 use anyhow::{bail, Context as _};
 use axum::extract;
 use axum::handler::Handler;
-use axum_server::tls_rustls::RustlsConfig;
 use tokio::sync::RwLock;
 use tracing::{debug, error, info, instrument};
 use wasmcloud_provider_sdk::core::LinkName;
@@ -20,6 +19,7 @@ use wasmcloud_provider_sdk::provider::WrpcClient;
 use wasmcloud_provider_sdk::{get_connection, HostData, LinkConfig, LinkDeleteInfo, Provider};
 
 use crate::settings::default_listen_address;
+use crate::tls::{self, CertificateReloader, TlsSource};
 use crate::{
     build_request, get_cors_layer, get_tcp_listener, invoke_component, load_settings,
     ServiceSettings,
@@ -95,6 +95,8 @@ impl Provider for HttpServerProvider {
                 bail!(e);
             }
         };
+        let tls = TlsSource::new(&settings, link_config.secrets)
+            .context("httpserver failed to load TLS configuration for component")?;
 
         let wrpc = get_connection()
             .get_wrpc_client(link_config.target_id)
@@ -135,6 +137,7 @@ impl Provider for HttpServerProvider {
                 // Start a server instance that calls the given component
                 let http_server = HttpServerCore::new(
                     Arc::new(settings),
+                    tls,
                     link_config.target_id,
                     self.handlers_by_socket.clone(),
                 )
@@ -234,14 +237,7 @@ async fn handle_request(
     let timeout = settings.timeout_ms.map(Duration::from_millis);
     let req = build_request(request, scheme, authority, &settings)?;
     axum::response::Result::<_, axum::response::ErrorResponse>::Ok(
-        invoke_component(
-            &wrpc,
-            &component_id,
-            req,
-            timeout,
-            settings.cache_control.as_ref(),
-        )
-        .await,
+        invoke_component(&wrpc, &component_id, req, timeout, &settings).await,
     )
 }
 
@@ -252,12 +248,15 @@ pub struct HttpServerCore {
     handle: axum_server::Handle,
     /// The asynchronous task running the server
     task: tokio::task::JoinHandle<()>,
+    /// The task reloading the TLS certificate of the server when it changes, if any
+    _tls_reloader: Option<CertificateReloader>,
 }
 
 impl HttpServerCore {
     #[instrument(skip(handlers_by_socket))]
-    pub async fn new(
+    pub(crate) async fn new(
         settings: Arc<ServiceSettings>,
+        tls: Option<TlsSource>,
         target: &str,
         handlers_by_socket: Arc<RwLock<HandlerLookup>>,
     ) -> anyhow::Result<Self> {
@@ -275,13 +274,12 @@ impl HttpServerCore {
 
         let target = target.to_owned();
         let task_handle = handle.clone();
-        let task = if let (Some(crt), Some(key)) =
-            (&settings.tls_cert_file, &settings.tls_priv_key_file)
-        {
+        let mut tls_reloader = None;
+        let task = if let Some(tls) = tls {
             debug!(?addr, "bind HTTPS listener");
-            let tls = RustlsConfig::from_pem_file(crt, key)
-                .await
-                .context("failed to construct TLS config")?;
+            let reload_interval = settings.tls_reload_interval_secs.map(Duration::from_secs);
+            let (tls, reloader) = tls::load(tls, reload_interval).await?;
+            tls_reloader = reloader;
 
             let srv = axum_server::from_tcp_rustls(listener, tls);
             tokio::spawn(async move {
@@ -327,7 +325,11 @@ impl HttpServerCore {
             })
         };
 
-        Ok(Self { handle, task })
+        Ok(Self {
+            handle,
+            task,
+            _tls_reloader: tls_reloader,
+        })
     }
 }
 
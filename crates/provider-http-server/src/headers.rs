@@ -0,0 +1,145 @@
+//! Header manipulation applied to requests before they are sent to components and to responses
+//! before they are sent to clients, as well as request ID injection.
+
+use std::collections::BTreeMap;
+
+use http::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+
+/// Name of the header carrying the ID of a request
+pub(crate) const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Rules manipulating the headers of a request or response.
+///
+/// Rules are applied in order: headers in `remove` are removed first, then values in `set`
+/// replace any existing values and finally values in `add` are appended to existing values.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HeaderRules {
+    /// Headers to remove
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remove: Vec<String>,
+    /// Headers to set, replacing any existing values
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub set: BTreeMap<String, String>,
+    /// Headers to add, keeping any existing values
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub add: BTreeMap<String, String>,
+}
+
+impl HeaderRules {
+    /// Returns a list of the invalid header names and values in the rules
+    pub(crate) fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for name in self
+            .remove
+            .iter()
+            .chain(self.set.keys())
+            .chain(self.add.keys())
+        {
+            if HeaderName::try_from(name.as_str()).is_err() {
+                errors.push(format!("invalid header name: '{name}'"));
+            }
+        }
+        for (name, value) in self.set.iter().chain(self.add.iter()) {
+            if HeaderValue::try_from(value.as_str()).is_err() {
+                errors.push(format!("invalid value for header '{name}': '{value}'"));
+            }
+        }
+        errors
+    }
+
+    /// Applies the rules to `headers`, skipping invalid names and values, which are rejected
+    /// when the settings are loaded
+    pub(crate) fn apply(&self, headers: &mut HeaderMap) {
+        for name in &self.remove {
+            if let Ok(name) = HeaderName::try_from(name.as_str()) {
+                headers.remove(name);
+            }
+        }
+        for (name, value) in &self.set {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::try_from(name.as_str()),
+                HeaderValue::try_from(value.as_str()),
+            ) {
+                headers.insert(name, value);
+            }
+        }
+        for (name, value) in &self.add {
+            if let (Ok(name), Ok(value)) = (
+                HeaderName::try_from(name.as_str()),
+                HeaderValue::try_from(value.as_str()),
+            ) {
+                headers.append(name, value);
+            }
+        }
+    }
+}
+
+/// Returns the ID of the request carried in the `x-request-id` header, generating and inserting
+/// a new one if the header is missing or not valid UTF-8
+pub(crate) fn ensure_request_id(headers: &mut HeaderMap) -> String {
+    if let Some(id) = headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|id| id.to_str().ok())
+        .filter(|id| !id.is_empty())
+    {
+        return id.to_string();
+    }
+    let id = uuid::Uuid::new_v4().to_string();
+    // UUIDs only contain ASCII hex digits and dashes, which are always valid header values
+    headers.insert(
+        REQUEST_ID_HEADER,
+        HeaderValue::from_str(&id).expect("UUID is a valid header value"),
+    );
+    id
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use http::{HeaderMap, HeaderValue};
+
+    use super::{ensure_request_id, HeaderRules, REQUEST_ID_HEADER};
+
+    #[test]
+    fn apply_rules() {
+        let rules = HeaderRules {
+            remove: vec!["server".into(), "x-powered-by".into()],
+            set: BTreeMap::from([("x-env".into(), "prod".into())]),
+            add: BTreeMap::from([("vary".into(), "origin".into())]),
+        };
+        assert!(rules.validate().is_empty());
+
+        let mut headers = HeaderMap::new();
+        headers.insert("server", HeaderValue::from_static("wasmcloud"));
+        headers.insert("x-env", HeaderValue::from_static("dev"));
+        headers.insert("vary", HeaderValue::from_static("accept"));
+        rules.apply(&mut headers);
+        assert!(headers.get("server").is_none());
+        assert_eq!(headers.get("x-env").unwrap(), "prod");
+        assert_eq!(
+            headers.get_all("vary").iter().collect::<Vec<_>>(),
+            ["accept", "origin"]
+        );
+
+        let rules = HeaderRules {
+            remove: vec!["bad header".into()],
+            set: BTreeMap::from([("x-env".into(), "line\nbreak".into())]),
+            ..Default::default()
+        };
+        assert_eq!(rules.validate().len(), 2);
+    }
+
+    #[test]
+    fn request_id() {
+        let mut headers = HeaderMap::new();
+        headers.insert(REQUEST_ID_HEADER, HeaderValue::from_static("abc"));
+        assert_eq!(ensure_request_id(&mut headers), "abc");
+
+        let mut headers = HeaderMap::new();
+        let id = ensure_request_id(&mut headers);
+        assert_eq!(headers.get(REQUEST_ID_HEADER).unwrap(), id.as_str());
+        assert_ne!(ensure_request_id(&mut HeaderMap::new()), id);
+    }
+}
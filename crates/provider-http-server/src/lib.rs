@@ -7,7 +7,7 @@
 //! ## Features:
 //!
 //! - HTTP/1 and HTTP/2
-//! - TLS
+//! - TLS, with certificates hot-reloaded when their files change
 //! - CORS support (select `allowed_origins`, `allowed_methods`,
 //!   `allowed_headers`.) Cors has sensible defaults so it should
 //!   work as-is for development purposes, and may need refinement
@@ -38,14 +38,19 @@ use pin_project_lite::pin_project;
 use tokio::task::JoinHandle;
 use tokio::{spawn, time};
 use tower_http::cors::{self, CorsLayer};
-use tracing::{debug, info, trace};
+use tracing::{debug, info, instrument, trace, Span};
 use wasmcloud_provider_sdk::provider::WrpcClient;
 use wasmcloud_provider_sdk::{initialize_observability, load_host_data, run_provider};
 use wrpc_interface_http::InvokeIncomingHandler as _;
 
+use crate::headers::{ensure_request_id, REQUEST_ID_HEADER};
+
 mod address;
+mod headers;
 mod path;
 mod settings;
+mod tls;
+pub use headers::HeaderRules;
 pub use settings::{default_listen_address, load_settings, ServiceSettings};
 
 pub async fn run() -> anyhow::Result<()> {
@@ -106,11 +111,18 @@ pub(crate) fn build_request(
         http::request::Parts {
             method,
             uri,
-            headers,
+            mut headers,
             ..
         },
         body,
     ) = request.into_parts();
+    if let Some(rules) = &settings.request_headers {
+        rules.apply(&mut headers);
+    }
+    // Inject the request ID after applying the rules, so that it cannot be removed by them
+    if !settings.disable_request_id.unwrap_or_default() {
+        ensure_request_id(&mut headers);
+    }
     let http::uri::Parts { path_and_query, .. } = uri.into_parts();
 
     let mut uri = http::Uri::builder().scheme(scheme);
@@ -138,12 +150,17 @@ pub(crate) fn build_request(
 }
 
 /// Invoke a component with the given request
+#[instrument(
+    level = "debug",
+    skip_all,
+    fields(component_id = target, request_id = tracing::field::Empty)
+)]
 pub(crate) async fn invoke_component(
     wrpc: &WrpcClient,
     target: &str,
     req: http::Request<axum::body::Body>,
     timeout: Option<Duration>,
-    cache_control: Option<&String>,
+    settings: &ServiceSettings,
 ) -> impl axum::response::IntoResponse {
     // Create a new wRPC client with all headers from the current span injected
     let mut cx = async_nats::HeaderMap::new();
@@ -155,6 +172,15 @@ pub(crate) async fn invoke_component(
     {
         cx.insert(k.as_str(), v.as_str());
     }
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .filter(|_| !settings.disable_request_id.unwrap_or_default())
+        .cloned();
+    if let Some(request_id) = request_id.as_ref().and_then(|id| id.to_str().ok()) {
+        Span::current().record("request_id", request_id);
+        cx.insert(REQUEST_ID_HEADER, request_id);
+    }
 
     trace!(?req, component_id = target, "httpserver calling component");
     let fut = wrpc.invoke_handle_http(Some(cx), req);
@@ -173,11 +199,17 @@ pub(crate) async fn invoke_component(
     // TODO: Convert this to http status code
     let mut res =
         res.map_err(|err| (http::StatusCode::INTERNAL_SERVER_ERROR, format!("{err:?}")))?;
-    if let Some(cache_control) = cache_control {
+    if let Some(cache_control) = settings.cache_control.as_ref() {
         let cache_control = http::HeaderValue::from_str(cache_control)
             .map_err(|err| (http::StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
         res.headers_mut().append("Cache-Control", cache_control);
     };
+    if let Some(rules) = &settings.response_headers {
+        rules.apply(res.headers_mut());
+    }
+    if let Some(request_id) = request_id {
+        res.headers_mut().insert(REQUEST_ID_HEADER, request_id);
+    }
     axum::response::Result::<_, axum::response::ErrorResponse>::Ok(res.map(|body| ResponseBody {
         body,
         errors,
@@ -1,8 +1,11 @@
 //! This module contains the implementation of the `wrpc:http/incoming-handler` provider in path-based mode.
 //!
 //! In path-based mode, the HTTP server listens on a single address and routes requests to different components
-//! based on the path of the request.
+//! based on the path of the request and, optionally, its host. Requests are routed to the link with the longest
+//! path prefix matching the request path, preferring links registered for the host of the request over links
+//! registered for any host.
 
+use core::fmt;
 use core::time::Duration;
 
 use std::collections::HashMap;
@@ -13,7 +16,6 @@ use std::sync::Arc;
 use anyhow::{bail, Context as _};
 use axum::extract::{self};
 use axum::handler::Handler;
-use axum_server::tls_rustls::RustlsConfig;
 use axum_server::Handle;
 use tokio::sync::RwLock;
 use tokio::task::JoinHandle;
@@ -21,19 +23,163 @@ use tracing::{debug, error, info, instrument};
 use wasmcloud_provider_sdk::provider::WrpcClient;
 use wasmcloud_provider_sdk::{get_connection, HostData, LinkConfig, LinkDeleteInfo, Provider};
 
+use crate::tls::{self, CertificateReloader, TlsSource};
 use crate::{
     build_request, get_cors_layer, get_tcp_listener, invoke_component, load_settings,
     ServiceSettings,
 };
 
+/// A route registered by a link, matching requests by path prefix and optionally by host
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+struct Route {
+    /// Host, without port, that requests must be addressed to, or `None` to match any host
+    host: Option<Arc<str>>,
+    /// Path prefix of matching requests, without trailing slash unless it is the root path
+    path: Arc<str>,
+}
+
+impl Route {
+    /// Parse a route from the `path` and `host` link configuration values, at least one of which
+    /// must be set. Routes without a path match all paths
+    fn new(path: Option<&str>, host: Option<&str>) -> anyhow::Result<Self> {
+        if path.is_none() && host.is_none() {
+            bail!("neither path nor host found in link config, cannot register route");
+        }
+        let path = path.unwrap_or("/");
+        if !path.starts_with('/') {
+            bail!("path `{path}` must start with `/`");
+        }
+        let path = match path.trim_end_matches('/') {
+            "" => "/",
+            path => path,
+        };
+        let host = host
+            .map(|host| {
+                let host = normalize_host(host);
+                if host.is_empty() {
+                    bail!("host must not be empty");
+                }
+                Ok(Arc::from(host))
+            })
+            .transpose()?;
+        Ok(Self {
+            host,
+            path: Arc::from(path),
+        })
+    }
+}
+
+impl fmt::Display for Route {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.host {
+            Some(host) => write!(f, "{host}{}", self.path),
+            None => write!(f, "{}", self.path),
+        }
+    }
+}
+
+/// Lowercase `host` and strip the port from it, if any
+fn normalize_host(host: &str) -> String {
+    let host = match host.rsplit_once(':') {
+        // Do not mistake the last segment of an IPv6 address without port for a port
+        Some((host, port)) if !port.contains(']') && port.bytes().all(|b| b.is_ascii_digit()) => {
+            host
+        }
+        _ => host,
+    };
+    host.to_lowercase()
+}
+
+/// Prefixes of a request path at segment boundaries, longest first, ending with the root path
+fn path_prefixes(path: &str) -> impl Iterator<Item = &str> {
+    let path = match path.trim_end_matches('/') {
+        "" => "/",
+        path => path,
+    };
+    let mut next = Some(path);
+    core::iter::from_fn(move || {
+        let prefix = next?;
+        next = match prefix.rfind('/') {
+            _ if prefix == "/" => None,
+            Some(0) => Some("/"),
+            Some(i) => Some(&prefix[..i]),
+            None => None,
+        };
+        Some(prefix)
+    })
+}
+
 /// This struct holds both the forward and reverse mappings for path-based routing
 /// so that they can be modified by just acquiring a single lock in the [`HttpServerProvider`]
-#[derive(Default)]
-struct Router {
-    /// Lookup from a path to the component ID that is handling that path
-    paths: HashMap<Arc<str>, (Arc<str>, WrpcClient)>,
-    /// Reverse lookup to find the path for a (component,link_name) pair
-    components: HashMap<(Arc<str>, Arc<str>), Arc<str>>,
+struct Router<T = (Arc<str>, WrpcClient)> {
+    /// Lookup from a route to the component that is handling requests matching it
+    routes: HashMap<Route, T>,
+    /// Reverse lookup to find the route for a (component,link_name) pair
+    components: HashMap<(Arc<str>, Arc<str>), Route>,
+}
+
+impl<T> Default for Router<T> {
+    fn default() -> Self {
+        Self {
+            routes: HashMap::default(),
+            components: HashMap::default(),
+        }
+    }
+}
+
+impl<T> Router<T> {
+    /// Register `route` for the link of `component` named `link_name`, failing if the link
+    /// already has a route or if the route is already registered by another link
+    fn insert(
+        &mut self,
+        component: Arc<str>,
+        link_name: Arc<str>,
+        route: Route,
+        target: T,
+    ) -> anyhow::Result<()> {
+        let key = (component, link_name);
+        if let Some(existing) = self.components.get(&key) {
+            bail!(
+                "component {} already has route {existing} registered with link name {}",
+                key.0,
+                key.1
+            );
+        }
+        if let Some((component, _)) = self
+            .components
+            .iter()
+            .find(|(_, existing)| **existing == route)
+            .map(|(key, _)| key)
+        {
+            bail!("route {route} already in use by component {component}");
+        }
+        self.components.insert(key, route.clone());
+        self.routes.insert(route, target);
+        Ok(())
+    }
+
+    /// Remove the route of the link of `component` named `link_name`, if any
+    fn remove(&mut self, component: &str, link_name: &str) -> Option<Route> {
+        let route = self
+            .components
+            .remove(&(Arc::from(component), Arc::from(link_name)))?;
+        self.routes.remove(&route);
+        Some(route)
+    }
+
+    /// Find the target of a request to `host` for `path`, matching the longest path prefix of
+    /// the routes registered for `host` first, and those of routes for any host second
+    fn lookup(&self, host: &str, path: &str) -> Option<&T> {
+        let host: Arc<str> = Arc::from(normalize_host(host));
+        [Some(host), None].into_iter().find_map(|host| {
+            path_prefixes(path).find_map(|prefix| {
+                self.routes.get(&Route {
+                    host: host.clone(),
+                    path: Arc::from(prefix),
+                })
+            })
+        })
+    }
 }
 
 /// `wrpc:http/incoming-handler` provider implementation with path-based routing
@@ -45,6 +191,8 @@ pub struct HttpServerProvider {
     handle: Handle,
     /// Task handle for the server task
     task: Arc<JoinHandle<()>>,
+    /// Task reloading the TLS certificate of the server when it changes, if any
+    _tls_reloader: Option<Arc<CertificateReloader>>,
 }
 
 impl Drop for HttpServerProvider {
@@ -64,6 +212,8 @@ impl HttpServerProvider {
             .context("failed to parse default_address")?;
         let settings = load_settings(default_address, &host_data.config)
             .context("failed to load settings in path mode")?;
+        let tls = TlsSource::new(&settings, &host_data.secrets)
+            .context("failed to load TLS configuration in path mode")?;
         let settings = Arc::new(settings);
 
         let path_router = Arc::default();
@@ -80,13 +230,12 @@ impl HttpServerProvider {
         let handle = axum_server::Handle::new();
         let task_handle = handle.clone();
         let task_router = Arc::clone(&path_router);
-        let task = if let (Some(crt), Some(key)) =
-            (&settings.tls_cert_file, &settings.tls_priv_key_file)
-        {
+        let mut tls_reloader = None;
+        let task = if let Some(tls) = tls {
             debug!(?addr, "bind HTTPS listener");
-            let tls = RustlsConfig::from_pem_file(crt, key)
-                .await
-                .context("failed to construct TLS config")?;
+            let reload_interval = settings.tls_reload_interval_secs.map(Duration::from_secs);
+            let (tls, reloader) = tls::load(tls, reload_interval).await?;
+            tls_reloader = reloader.map(Arc::new);
 
             tokio::spawn(async move {
                 if let Err(e) = axum_server::from_tcp_rustls(listener, tls)
@@ -131,6 +280,7 @@ impl HttpServerProvider {
             path_router,
             handle,
             task: Arc::new(task),
+            _tls_reloader: tls_reloader,
         })
     }
 }
@@ -144,43 +294,38 @@ impl Provider for HttpServerProvider {
         &self,
         link_config: LinkConfig<'_>,
     ) -> anyhow::Result<()> {
-        let Some(path) = link_config.config.get("path") else {
-            error!(?link_config.config, ?link_config.target_id, "path not found in link config, cannot register path");
-            bail!(
-                "path not found in link config, cannot register path for component {}",
+        let route = Route::new(
+            link_config.config.get("path").map(String::as_str),
+            link_config.config.get("host").map(String::as_str),
+        )
+        .map_err(|err| {
+            error!(?link_config.config, ?link_config.target_id, ?err, "invalid route in link config");
+            err.context(format!(
+                "failed to register route for component {}",
                 link_config.target_id
-            );
-        };
-
-        let target = Arc::from(link_config.target_id);
-        let name = Arc::from(link_config.link_name);
-
-        let key = (Arc::clone(&target), Arc::clone(&name));
-
-        let mut path_router = self.path_router.write().await;
-        if path_router.components.contains_key(&key) {
-            // When we can return errors from links, tell the host this was invalid
-            bail!("Component {target} already has a path registered with link name {name}");
-        }
-        if path_router.paths.contains_key(path.as_str()) {
-            // When we can return errors from links, tell the host this was invalid
-            bail!("Path {path} already in use by a different component");
-        }
+            ))
+        })?;
 
         let wrpc = get_connection()
             .get_wrpc_client(link_config.target_id)
             .await
             .context("failed to construct wRPC client")?;
 
-        let path = Arc::from(path.clone());
-        // Insert the path into the paths map for future lookups
-        path_router.components.insert(key, Arc::clone(&path));
-        path_router.paths.insert(path, (target, wrpc));
+        let target: Arc<str> = Arc::from(link_config.target_id);
+        let name = Arc::from(link_config.link_name);
+        // When we can return errors from links, tell the host this was invalid
+        self.path_router.write().await.insert(
+            Arc::clone(&target),
+            name,
+            route.clone(),
+            (target, wrpc),
+        )?;
+        info!(%route, target = link_config.target_id, "registered route");
 
         Ok(())
     }
 
-    /// Remove the path for a particular component/link_name pair
+    /// Remove the route for a particular component/link_name pair
     #[instrument(level = "debug", skip_all, fields(target_id = info.get_target_id()))]
     async fn delete_link_as_source(&self, info: impl LinkDeleteInfo) -> anyhow::Result<()> {
         debug!(
@@ -192,12 +337,13 @@ impl Provider for HttpServerProvider {
         let component_id = info.get_target_id();
         let link_name = info.get_link_name();
 
-        let mut path_router = self.path_router.write().await;
-        let path = path_router
-            .components
-            .remove(&(Arc::from(component_id), Arc::from(link_name)));
-        if let Some(path) = path {
-            path_router.paths.remove(&path);
+        if let Some(route) = self
+            .path_router
+            .write()
+            .await
+            .remove(component_id, link_name)
+        {
+            debug!(%route, "removed route");
         }
 
         Ok(())
@@ -219,7 +365,7 @@ struct RequestContext {
     settings: Arc<ServiceSettings>,
 }
 
-/// Handle an HTTP request by looking up the component ID for the path and invoking the component
+/// Handle an HTTP request by looking up the component ID for the host and path and invoking the component
 #[instrument(level = "debug", skip(router, settings))]
 async fn handle_request(
     extract::State(RequestContext {
@@ -231,19 +377,128 @@ async fn handle_request(
     request: extract::Request,
 ) -> impl axum::response::IntoResponse {
     let timeout = settings.timeout_ms.map(Duration::from_millis);
-    let req = build_request(request, scheme, authority, &settings)?;
+    let req = build_request(request, scheme, authority.clone(), &settings)?;
     let path = req.uri().path();
-    let Some((target_component, wrpc)) = router.read().await.paths.get(path).cloned() else {
+    let Some((target_component, wrpc)) = router.read().await.lookup(&authority, path).cloned()
+    else {
         Err((http::StatusCode::NOT_FOUND, "path not found"))?
     };
     axum::response::Result::<_, axum::response::ErrorResponse>::Ok(
-        invoke_component(
-            &wrpc,
-            &target_component,
-            req,
-            timeout,
-            settings.cache_control.as_ref(),
-        )
-        .await,
+        invoke_component(&wrpc, &target_component, req, timeout, &settings).await,
     )
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::{path_prefixes, Route, Router};
+
+    fn route(path: Option<&str>, host: Option<&str>) -> Route {
+        Route::new(path, host).expect("route should be valid")
+    }
+
+    #[test]
+    fn prefixes() {
+        assert_eq!(
+            path_prefixes("/foo/bar/baz").collect::<Vec<_>>(),
+            ["/foo/bar/baz", "/foo/bar", "/foo", "/"]
+        );
+        assert_eq!(path_prefixes("/foo/").collect::<Vec<_>>(), ["/foo", "/"]);
+        assert_eq!(path_prefixes("/").collect::<Vec<_>>(), ["/"]);
+        assert_eq!(path_prefixes("").collect::<Vec<_>>(), ["/"]);
+    }
+
+    #[test]
+    fn parse_routes() {
+        assert_eq!(route(Some("/api/"), None).path.as_ref(), "/api");
+        assert_eq!(route(Some("/"), None).path.as_ref(), "/");
+        let host_only = route(None, Some("Example.COM:8080"));
+        assert_eq!(host_only.host.as_deref(), Some("example.com"));
+        assert_eq!(host_only.path.as_ref(), "/");
+        assert!(Route::new(None, None).is_err());
+        assert!(Route::new(Some("api"), None).is_err());
+        assert!(Route::new(Some("/api"), Some("")).is_err());
+    }
+
+    #[test]
+    fn longest_prefix_routing() {
+        let mut router = Router::default();
+        for (component, path, host) in [
+            ("root", Some("/"), None),
+            ("api", Some("/api"), None),
+            ("api-v2", Some("/api/v2"), None),
+            ("example", None, Some("example.com")),
+            ("example-api", Some("/api"), Some("example.com")),
+        ] {
+            router
+                .insert(
+                    Arc::from(component),
+                    Arc::from("default"),
+                    route(path, host),
+                    component,
+                )
+                .expect("route should be registered");
+        }
+        let lookup = |host: &str, path: &str| router.lookup(host, path).copied();
+        assert_eq!(lookup("localhost:8000", "/"), Some("root"));
+        assert_eq!(lookup("localhost:8000", "/index.html"), Some("root"));
+        assert_eq!(lookup("localhost:8000", "/api"), Some("api"));
+        assert_eq!(lookup("localhost:8000", "/api/v1/users"), Some("api"));
+        assert_eq!(lookup("localhost:8000", "/api/v2/users"), Some("api-v2"));
+        // Prefixes only match at segment boundaries
+        assert_eq!(lookup("localhost:8000", "/apiary"), Some("root"));
+        // Routes of the host of the request take precedence
+        assert_eq!(lookup("EXAMPLE.com:8000", "/api/v2"), Some("example-api"));
+        assert_eq!(lookup("example.com", "/other"), Some("example"));
+
+        assert_eq!(
+            router.remove("root", "default"),
+            Some(route(Some("/"), None))
+        );
+        assert_eq!(router.lookup("localhost:8000", "/index.html"), None);
+        assert_eq!(router.remove("root", "default"), None);
+    }
+
+    #[test]
+    fn route_conflicts() {
+        let mut router = Router::default();
+        router
+            .insert(
+                Arc::from("a"),
+                Arc::from("default"),
+                route(Some("/api"), None),
+                "a",
+            )
+            .expect("route should be registered");
+        // The same route, even if written differently, can only be registered once
+        assert!(router
+            .insert(
+                Arc::from("b"),
+                Arc::from("default"),
+                route(Some("/api/"), None),
+                "b"
+            )
+            .is_err());
+        // A link can only register a single route
+        assert!(router
+            .insert(
+                Arc::from("a"),
+                Arc::from("default"),
+                route(Some("/other"), None),
+                "a"
+            )
+            .is_err());
+        // The same path may be registered for a specific host
+        router
+            .insert(
+                Arc::from("b"),
+                Arc::from("default"),
+                route(Some("/api"), Some("example.com")),
+                "b",
+            )
+            .expect("route should be registered");
+        assert_eq!(router.lookup("example.com", "/api"), Some(&"b"));
+        assert_eq!(router.lookup("localhost", "/api"), Some(&"a"));
+    }
+}
@@ -31,6 +31,8 @@ use serde::{de, de::Deserializer, de::Visitor, Deserialize, Serialize};
 use tracing::{instrument, trace};
 use unicase::UniCase;
 
+use crate::headers::HeaderRules;
+
 const CORS_ALLOWED_ORIGINS: &[&str] = &[];
 const CORS_ALLOWED_METHODS: &[&str] = &["GET", "POST", "PUT", "DELETE", "HEAD", "OPTIONS"];
 const CORS_ALLOWED_HEADERS: &[&str] = &[
@@ -69,6 +71,10 @@ pub struct ServiceSettings {
     pub tls_cert_file: Option<String>,
     #[serde(default)]
     pub tls_priv_key_file: Option<String>,
+    /// Interval (seconds) between checks for changes of the certificate files, which are reloaded
+    /// without dropping connections when they change. `0` disables reloading
+    #[serde(default)]
+    pub tls_reload_interval_secs: Option<u64>,
     /// Rpc timeout - how long (milliseconds) to wait for component's response
     /// before returning a status 503 to the http client
     /// If not set, uses the system-wide rpc timeout
@@ -83,6 +89,15 @@ pub struct ServiceSettings {
     pub cors: Cors,
     #[serde(default)]
     pub disable_keepalive: Option<bool>,
+    /// Rules manipulating the headers of requests before they are sent to the component
+    #[serde(default)]
+    pub request_headers: Option<HeaderRules>,
+    /// Rules manipulating the headers of responses before they are sent to the client
+    #[serde(default)]
+    pub response_headers: Option<HeaderRules>,
+    /// Flag disabling injection of the `x-request-id` header into requests and responses
+    #[serde(default)]
+    pub disable_request_id: Option<bool>,
 }
 
 impl Default for ServiceSettings {
@@ -97,12 +112,16 @@ impl Default for ServiceSettings {
             cors_max_age_secs: Some(CORS_DEFAULT_MAX_AGE_SECS),
             tls_cert_file: None,
             tls_priv_key_file: None,
+            tls_reload_interval_secs: None,
             timeout_ms: None,
             cache_control: None,
             readonly_mode: Some(false),
             tls: Tls::default(),
             cors: Cors::default(),
             disable_keepalive: None,
+            request_headers: None,
+            response_headers: None,
+            disable_request_id: None,
         }
     }
 }
@@ -121,6 +140,7 @@ impl ServiceSettings {
                 timeout_ms: s.timeout_ms,
                 tls_cert_file: s.tls_cert_file.or(s.tls.cert_file),
                 tls_priv_key_file: s.tls_priv_key_file.or(s.tls.priv_key_file),
+                tls_reload_interval_secs: s.tls_reload_interval_secs,
                 cors_allowed_origins: s.cors_allowed_origins.or(s.cors.allowed_origins),
                 cors_allowed_headers: s.cors_allowed_headers.or(s.cors.allowed_headers),
                 cors_allowed_methods: s.cors_allowed_methods.or(s.cors.allowed_methods),
@@ -129,6 +149,9 @@ impl ServiceSettings {
                 tls: Tls::default(),
                 cors: Cors::default(),
                 disable_keepalive: s.disable_keepalive,
+                request_headers: s.request_headers,
+                response_headers: s.response_headers,
+                disable_request_id: s.disable_request_id,
             })
             .map_err(|e| HttpServerError::Settings(format!("invalid json: {e}")))
     }
@@ -176,6 +199,14 @@ impl ServiceSettings {
                 errors.push(format!("Invalid Cache Control header : '{cache_control}'"));
             }
         }
+        for (key, rules) in [
+            ("request_headers", &self.request_headers),
+            ("response_headers", &self.response_headers),
+        ] {
+            if let Some(rules) = rules {
+                errors.extend(rules.validate().into_iter().map(|e| format!("{key}: {e}")));
+            }
+        }
         if !errors.is_empty() {
             Err(HttpServerError::Settings(format!(
                 "\nInvalid httpserver settings: \n{}\n",
@@ -269,6 +300,12 @@ pub fn load_settings(
     if let Some(tls_priv_key_file) = values.get(&UniCase::new("tls_priv_key_file")) {
         settings.tls_priv_key_file = Some(tls_priv_key_file.to_string());
     }
+    if let Some(interval) = values.get(&UniCase::new("tls_reload_interval_secs")) {
+        let interval: u64 = interval.parse().map_err(|_| {
+            HttpServerError::InvalidParameter("Invalid tls_reload_interval_secs".to_string())
+        })?;
+        settings.tls_reload_interval_secs = Some(interval);
+    }
 
     // CORS
     if let Some(cors_allowed_origins) = values.get(&UniCase::new("cors_allowed_origins")) {
@@ -301,6 +338,21 @@ pub fn load_settings(
         settings.disable_keepalive = Some(disable_keepalive.parse().unwrap_or(false));
     }
 
+    // Headers
+    if let Some(request_headers) = values.get(&UniCase::new("request_headers")) {
+        let rules: HeaderRules = serde_json::from_str(request_headers)
+            .map_err(|e| HttpServerError::Settings(format!("invalid request_headers: {e}")))?;
+        settings.request_headers = Some(rules);
+    }
+    if let Some(response_headers) = values.get(&UniCase::new("response_headers")) {
+        let rules: HeaderRules = serde_json::from_str(response_headers)
+            .map_err(|e| HttpServerError::Settings(format!("invalid response_headers: {e}")))?;
+        settings.response_headers = Some(rules);
+    }
+    if let Some(disable_request_id) = values.get(&UniCase::new("disable_request_id")) {
+        settings.disable_request_id = Some(disable_request_id.parse().unwrap_or(false));
+    }
+
     settings.validate()?;
     Ok(settings)
 }
@@ -532,7 +584,9 @@ where
 mod test {
     use std::str::FromStr;
 
-    use crate::settings::{CorsOrigin, ServiceSettings};
+    use std::collections::HashMap;
+
+    use crate::settings::{load_settings, CorsOrigin, ServiceSettings};
 
     const GOOD_ORIGINS: &[&str] = &[
         // origins that should be parsed correctly
@@ -582,6 +636,36 @@ mod test {
         );
     }
 
+    #[test]
+    fn settings_headers() {
+        let values = HashMap::from([
+            (
+                "request_headers".to_string(),
+                r#"{"remove":["cookie"],"set":{"x-env":"prod"}}"#.to_string(),
+            ),
+            (
+                "response_headers".to_string(),
+                r#"{"add":{"strict-transport-security":"max-age=63072000"}}"#.to_string(),
+            ),
+            ("disable_request_id".to_string(), "true".to_string()),
+        ]);
+        let s = load_settings(None, &values).expect("load settings");
+        let request_headers = s.request_headers.expect("request header rules");
+        assert_eq!(request_headers.remove, ["cookie"]);
+        assert_eq!(request_headers.set.get("x-env").unwrap(), "prod");
+        assert_eq!(
+            s.response_headers.expect("response header rules").add.len(),
+            1
+        );
+        assert_eq!(s.disable_request_id, Some(true));
+
+        let values = HashMap::from([(
+            "response_headers".to_string(),
+            r#"{"set":{"bad header":"value"}}"#.to_string(),
+        )]);
+        assert!(load_settings(None, &values).is_err());
+    }
+
     #[test]
     fn origins_deserialize() {
         // test CorsOrigin
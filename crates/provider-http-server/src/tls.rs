@@ -0,0 +1,204 @@
+//! TLS configuration of HTTPS listeners
+//!
+//! Certificates and private keys are read either from PEM files, configured with `tls_cert_file`
+//! and `tls_priv_key_file`, or from the `tls_cert` and `tls_priv_key` secrets. Certificates read
+//! from files are watched for changes and reloaded in place, so that renewed certificates are
+//! served to new connections without dropping live ones.
+
+use core::fmt;
+use core::time::Duration;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::Context as _;
+use axum_server::tls_rustls::RustlsConfig;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+use wasmcloud_provider_sdk::core::secrets::SecretValue;
+
+use crate::ServiceSettings;
+
+/// Secret containing the PEM-encoded certificate chain of the listener
+const TLS_CERT_SECRET: &str = "tls_cert";
+/// Secret containing the PEM-encoded private key of the listener
+const TLS_PRIV_KEY_SECRET: &str = "tls_priv_key";
+
+/// Interval between checks for changed certificate files, unless configured
+const DEFAULT_RELOAD_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Source of the certificate chain and private key of an HTTPS listener
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) enum TlsSource {
+    /// PEM files, which are reloaded when they change
+    Files { cert: PathBuf, key: PathBuf },
+    /// PEM-encoded contents, read from secrets
+    Pem { cert: Vec<u8>, key: Vec<u8> },
+}
+
+/// Debug implementation that doesn't log the private key
+impl fmt::Debug for TlsSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Files { cert, key } => f
+                .debug_struct("Files")
+                .field("cert", cert)
+                .field("key", key)
+                .finish(),
+            Self::Pem { .. } => f.write_str("Pem"),
+        }
+    }
+}
+
+impl TlsSource {
+    /// Determine the TLS source of a listener from its settings and secrets, returning `None` if
+    /// the listener does not use TLS. Secrets take precedence over files
+    pub(crate) fn new(
+        settings: &ServiceSettings,
+        secrets: &HashMap<String, SecretValue>,
+    ) -> anyhow::Result<Option<Self>> {
+        let secret = |key: &str| {
+            secrets.get(key).map(|secret| match secret {
+                SecretValue::String(s) => s.as_bytes().to_vec(),
+                SecretValue::Bytes(b) => b.clone(),
+            })
+        };
+        match (secret(TLS_CERT_SECRET), secret(TLS_PRIV_KEY_SECRET)) {
+            (Some(cert), Some(key)) => {
+                if settings.tls_cert_file.is_some() || settings.tls_priv_key_file.is_some() {
+                    warn!("TLS certificate found in both secrets and files, using secrets");
+                }
+                return Ok(Some(Self::Pem { cert, key }));
+            }
+            (Some(_), None) | (None, Some(_)) => {
+                anyhow::bail!(
+                    "for tls, both '{TLS_CERT_SECRET}' and '{TLS_PRIV_KEY_SECRET}' secrets must be set"
+                )
+            }
+            (None, None) => {}
+        }
+        Ok(settings
+            .tls_cert_file
+            .as_ref()
+            .zip(settings.tls_priv_key_file.as_ref())
+            .map(|(cert, key)| Self::Files {
+                cert: cert.into(),
+                key: key.into(),
+            }))
+    }
+}
+
+/// Task reloading the certificate of a listener when its files change, stopped when dropped
+#[derive(Debug)]
+pub(crate) struct CertificateReloader(JoinHandle<()>);
+
+impl Drop for CertificateReloader {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Modification time and size of a file, used to detect changes
+fn fingerprint(path: &Path) -> Option<(SystemTime, u64)> {
+    let md = std::fs::metadata(path).ok()?;
+    Some((md.modified().ok()?, md.len()))
+}
+
+/// Load the TLS configuration of a listener from `source`. For certificates read from files,
+/// a [`CertificateReloader`] is returned, which reloads the configuration in place when the
+/// files change, unless `reload_interval` is zero
+pub(crate) async fn load(
+    source: TlsSource,
+    reload_interval: Option<Duration>,
+) -> anyhow::Result<(RustlsConfig, Option<CertificateReloader>)> {
+    let (cert, key) = match source {
+        TlsSource::Pem { cert, key } => {
+            let config = RustlsConfig::from_pem(cert, key)
+                .await
+                .context("failed to construct TLS config from secrets")?;
+            return Ok((config, None));
+        }
+        TlsSource::Files { cert, key } => (cert, key),
+    };
+    let config = RustlsConfig::from_pem_file(&cert, &key)
+        .await
+        .context("failed to construct TLS config")?;
+    let reload_interval = reload_interval.unwrap_or(DEFAULT_RELOAD_INTERVAL);
+    if reload_interval.is_zero() {
+        return Ok((config, None));
+    }
+    let reloader = tokio::spawn({
+        let config = config.clone();
+        async move {
+            let mut loaded = (fingerprint(&cert), fingerprint(&key));
+            let mut interval = tokio::time::interval(reload_interval);
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                let current = (fingerprint(&cert), fingerprint(&key));
+                if current == loaded {
+                    continue;
+                }
+                debug!(cert = ?cert.display(), key = ?key.display(), "certificate files changed");
+                // Files may be replaced one at a time, failed reloads are retried on the next
+                // tick while the previous certificate keeps being served
+                match config.reload_from_pem_file(&cert, &key).await {
+                    Ok(()) => {
+                        info!(cert = ?cert.display(), "reloaded TLS certificate");
+                        loaded = current;
+                    }
+                    Err(err) => {
+                        warn!(?err, cert = ?cert.display(), "failed to reload TLS certificate");
+                    }
+                }
+            }
+        }
+    });
+    Ok((config, Some(CertificateReloader(reloader))))
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use wasmcloud_provider_sdk::core::secrets::SecretValue;
+
+    use super::TlsSource;
+    use crate::ServiceSettings;
+
+    #[test]
+    fn tls_source() {
+        let settings = ServiceSettings {
+            tls_cert_file: Some("cert.pem".into()),
+            tls_priv_key_file: Some("key.pem".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            TlsSource::new(&ServiceSettings::default(), &HashMap::new()).unwrap(),
+            None
+        );
+        assert_eq!(
+            TlsSource::new(&settings, &HashMap::new()).unwrap(),
+            Some(TlsSource::Files {
+                cert: "cert.pem".into(),
+                key: "key.pem".into(),
+            })
+        );
+
+        let secrets = HashMap::from([
+            ("tls_cert".into(), SecretValue::String("cert".into())),
+            ("tls_priv_key".into(), SecretValue::Bytes(b"key".to_vec())),
+        ]);
+        assert_eq!(
+            TlsSource::new(&settings, &secrets).unwrap(),
+            Some(TlsSource::Pem {
+                cert: b"cert".to_vec(),
+                key: b"key".to_vec(),
+            })
+        );
+
+        let secrets = HashMap::from([("tls_cert".into(), SecretValue::String("cert".into()))]);
+        assert!(TlsSource::new(&settings, &secrets).is_err());
+    }
+}
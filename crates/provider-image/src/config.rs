@@ -0,0 +1,147 @@
+//! Configuration for the image capability provider
+
+use core::time::Duration;
+
+use anyhow::{anyhow, Context as _, Result};
+use wasmcloud_provider_sdk::{ConfigField, ConfigSchema, LinkConfig};
+
+/// Configuration key of the maximum size of input images in bytes
+const CONFIG_MAX_INPUT_BYTES_KEY: &str = "MAX_INPUT_BYTES";
+
+/// Configuration key of the maximum number of pixels of decoded input images
+const CONFIG_MAX_PIXELS_KEY: &str = "MAX_PIXELS";
+
+/// Configuration key of the maximum width and height of input and output images in pixels
+const CONFIG_MAX_DIMENSION_KEY: &str = "MAX_DIMENSION";
+
+/// Configuration key of the time after which an operation is abandoned in milliseconds
+const CONFIG_TIMEOUT_KEY: &str = "TIMEOUT_MS";
+
+const DEFAULT_MAX_INPUT_BYTES: usize = 20 * 1024 * 1024;
+
+const DEFAULT_MAX_PIXELS: u64 = 40_000_000;
+
+const DEFAULT_MAX_DIMENSION: u32 = 8192;
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Limits of the image operations of a link
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ImageConfig {
+    /// Maximum size of input images, in bytes
+    pub max_input_bytes: usize,
+    /// Maximum number of pixels of decoded input images
+    pub max_pixels: u64,
+    /// Maximum width and height of input and output images, in pixels
+    pub max_dimension: u32,
+    /// Time after which an operation is abandoned
+    pub timeout: Duration,
+}
+
+impl Default for ImageConfig {
+    fn default() -> Self {
+        Self {
+            max_input_bytes: DEFAULT_MAX_INPUT_BYTES,
+            max_pixels: DEFAULT_MAX_PIXELS,
+            max_dimension: DEFAULT_MAX_DIMENSION,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+}
+
+/// Schema of the link configuration of the provider
+pub fn schema() -> ConfigSchema {
+    ConfigSchema::new()
+        .field(
+            ConfigField::integer(CONFIG_MAX_INPUT_BYTES_KEY, Some(1), None)
+                .default_value(DEFAULT_MAX_INPUT_BYTES.to_string())
+                .description("Maximum size of input images, in bytes"),
+        )
+        .field(
+            ConfigField::integer(CONFIG_MAX_PIXELS_KEY, Some(1), None)
+                .default_value(DEFAULT_MAX_PIXELS.to_string())
+                .description("Maximum number of pixels of input images"),
+        )
+        .field(
+            ConfigField::integer(CONFIG_MAX_DIMENSION_KEY, Some(1), Some(u32::MAX.into()))
+                .default_value(DEFAULT_MAX_DIMENSION.to_string())
+                .description("Maximum width and height of input and resized images, in pixels"),
+        )
+        .field(
+            ConfigField::integer(CONFIG_TIMEOUT_KEY, Some(1), None)
+                .default_value(DEFAULT_TIMEOUT.as_millis().to_string())
+                .description("Time after which an operation is abandoned, in milliseconds"),
+        )
+}
+
+impl ImageConfig {
+    /// Construct an [`ImageConfig`] from a link configuration validated against [`schema`].
+    /// Keys are matched case-insensitively
+    pub fn from_link_config(link_config: &LinkConfig) -> Result<Self> {
+        let config = link_config.validate(&schema()).map_err(|errors| {
+            anyhow!(errors
+                .into_iter()
+                .map(|err| err.message)
+                .collect::<Vec<_>>()
+                .join("; "))
+        })?;
+        // NOTE: All fields have defaults and were validated to be positive integers
+        let value = |key: &str| config.get_integer(key).unwrap_or_default().unsigned_abs();
+        Ok(Self {
+            max_input_bytes: value(CONFIG_MAX_INPUT_BYTES_KEY)
+                .try_into()
+                .with_context(|| format!("`{CONFIG_MAX_INPUT_BYTES_KEY}` is too large"))?,
+            max_pixels: value(CONFIG_MAX_PIXELS_KEY),
+            max_dimension: value(CONFIG_MAX_DIMENSION_KEY)
+                .try_into()
+                .with_context(|| format!("`{CONFIG_MAX_DIMENSION_KEY}` is too large"))?,
+            timeout: Duration::from_millis(value(CONFIG_TIMEOUT_KEY)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn image_config(config: &[(&str, &str)]) -> Result<ImageConfig> {
+        let config: HashMap<_, _> = config
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        ImageConfig::from_link_config(&LinkConfig {
+            source_id: "component",
+            target_id: "provider",
+            link_name: "default",
+            config: &config,
+            secrets: &HashMap::new(),
+            wit_metadata: (&"wasmcloud".to_string(), &"image".to_string(), &vec![]),
+        })
+    }
+
+    #[test]
+    fn parse_config() {
+        assert_eq!(
+            image_config(&[
+                ("MAX_INPUT_BYTES", "1048576"),
+                ("max_pixels", "1000000"),
+                ("MAX_DIMENSION", "2048"),
+                ("TIMEOUT_MS", "2500"),
+            ])
+            .unwrap(),
+            ImageConfig {
+                max_input_bytes: 1_048_576,
+                max_pixels: 1_000_000,
+                max_dimension: 2048,
+                timeout: Duration::from_millis(2500),
+            }
+        );
+        assert_eq!(image_config(&[]).unwrap(), ImageConfig::default());
+
+        assert!(image_config(&[("MAX_INPUT_BYTES", "lots")]).is_err());
+        assert!(image_config(&[("MAX_DIMENSION", "0")]).is_err());
+        assert!(image_config(&[("TIMEOUT_MS", "-1")]).is_err());
+    }
+}
@@ -0,0 +1,228 @@
+//! Image processing provider implementing `wasmcloud:image/processing`, which lets components
+//! offload CPU-heavy resizing, cropping and format conversion of images.
+//!
+//! Operations run on blocking threads, limited to the available parallelism, and are subject to
+//! the input size limits and timeout configured on the link of the invoking component.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context as _, Result};
+use bytes::Bytes;
+use tokio::sync::{RwLock, Semaphore};
+use tokio::task::spawn_blocking;
+use tokio::time::{timeout_at, Instant};
+use tracing::{error, instrument};
+use wasmcloud_provider_sdk::{
+    get_connection, initialize_observability, propagate_trace_for_ctx, run_provider,
+    serve_provider_exports, ConfigSchema, Context, LinkConfig, LinkDeleteInfo, Provider,
+};
+
+mod config;
+mod process;
+
+use config::ImageConfig;
+
+mod bindings {
+    wit_bindgen_wrpc::generate!({
+        with: {
+            "wasmcloud:image/processing@0.1.0-draft": generate,
+        }
+    });
+}
+use bindings::exports::wasmcloud::image::processing::{
+    Handler, ImageInfo, OutputOptions, Region, ResizeOptions,
+};
+
+pub async fn run() -> anyhow::Result<()> {
+    ImageProvider::run().await
+}
+
+/// Image processing provider
+#[derive(Clone)]
+pub struct ImageProvider {
+    /// Limits of the operations of linked components, indexed by source ID
+    configs: Arc<RwLock<HashMap<String, ImageConfig>>>,
+    /// Limits the number of concurrently running operations
+    permits: Arc<Semaphore>,
+}
+
+impl Default for ImageProvider {
+    fn default() -> Self {
+        let parallelism = std::thread::available_parallelism().map_or(1, usize::from);
+        Self {
+            configs: Arc::default(),
+            permits: Arc::new(Semaphore::new(parallelism)),
+        }
+    }
+}
+
+impl ImageProvider {
+    fn name() -> &'static str {
+        "image-provider"
+    }
+
+    /// Run [`ImageProvider`] as a wasmCloud provider
+    pub async fn run() -> anyhow::Result<()> {
+        initialize_observability!(
+            ImageProvider::name(),
+            std::env::var_os("PROVIDER_IMAGE_FLAMEGRAPH_PATH")
+        );
+        let provider = ImageProvider::default();
+        let shutdown = run_provider(provider.clone(), ImageProvider::name())
+            .await
+            .context("failed to run provider")?;
+        let connection = get_connection();
+        let wrpc = connection
+            .get_wrpc_client(connection.provider_key())
+            .await?;
+        serve_provider_exports(&wrpc, provider, shutdown, bindings::serve)
+            .await
+            .context("failed to serve provider exports")
+    }
+
+    /// Get the limits of the source of the invocation
+    async fn config(&self, context: Option<Context>) -> Result<ImageConfig> {
+        let source_id = context
+            .and_then(|Context { component, .. }| component)
+            .context("failed to lookup source of invocation")?;
+        self.configs
+            .read()
+            .await
+            .get(&source_id)
+            .copied()
+            .with_context(|| format!("no image link configured for component `{source_id}`"))
+    }
+
+    /// Run `f` on a blocking thread with the limits of the source of the invocation, abandoning
+    /// it once the configured timeout elapses
+    async fn process<T: Send + 'static>(
+        &self,
+        context: Option<Context>,
+        f: impl FnOnce(&ImageConfig) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        let config = self.config(context).await?;
+        let deadline = Instant::now() + config.timeout;
+        let timed_out = || {
+            anyhow!(
+                "image processing timed out after {}ms",
+                config.timeout.as_millis()
+            )
+        };
+        let permit = timeout_at(deadline, Arc::clone(&self.permits).acquire_owned())
+            .await
+            .map_err(|_| timed_out())?
+            .context("failed to acquire permit")?;
+        // NOTE: Blocking threads cannot be cancelled, so the permit is held until the operation
+        // completes, even if it is abandoned, to keep abandoned operations from piling up
+        let task = spawn_blocking(move || {
+            let _permit = permit;
+            f(&config)
+        });
+        timeout_at(deadline, task)
+            .await
+            .map_err(|_| timed_out())?
+            .context("image processing task failed")?
+    }
+}
+
+impl Provider for ImageProvider {
+    #[instrument(level = "info", skip_all, fields(source_id = link_config.source_id))]
+    async fn receive_link_config_as_target(
+        &self,
+        link_config: LinkConfig<'_>,
+    ) -> anyhow::Result<()> {
+        let config = match ImageConfig::from_link_config(&link_config) {
+            Ok(config) => config,
+            Err(e) => {
+                error!(error = %e, source_id = %link_config.source_id, "failed to parse image configuration");
+                return Err(e);
+            }
+        };
+        self.configs
+            .write()
+            .await
+            .insert(link_config.source_id.to_string(), config);
+        Ok(())
+    }
+
+    #[instrument(level = "info", skip_all, fields(source_id = info.get_source_id()))]
+    async fn delete_link_as_target(&self, info: impl LinkDeleteInfo) -> anyhow::Result<()> {
+        self.configs.write().await.remove(info.get_source_id());
+        Ok(())
+    }
+
+    fn config_schema(&self) -> Option<ConfigSchema> {
+        Some(config::schema())
+    }
+
+    async fn shutdown(&self) -> anyhow::Result<()> {
+        self.configs.write().await.drain();
+        Ok(())
+    }
+}
+
+impl Handler<Option<Context>> for ImageProvider {
+    #[instrument(level = "debug", skip_all)]
+    async fn info(
+        &self,
+        cx: Option<Context>,
+        image: Bytes,
+    ) -> anyhow::Result<Result<ImageInfo, String>> {
+        propagate_trace_for_ctx!(cx);
+        Ok(self
+            .process(cx, move |config| process::info(&image, config))
+            .await
+            .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn resize(
+        &self,
+        cx: Option<Context>,
+        image: Bytes,
+        options: ResizeOptions,
+        output: OutputOptions,
+    ) -> anyhow::Result<Result<Bytes, String>> {
+        propagate_trace_for_ctx!(cx);
+        Ok(self
+            .process(cx, move |config| {
+                process::resize(&image, &options, &output, config).map(Bytes::from)
+            })
+            .await
+            .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn crop(
+        &self,
+        cx: Option<Context>,
+        image: Bytes,
+        region: Region,
+        output: OutputOptions,
+    ) -> anyhow::Result<Result<Bytes, String>> {
+        propagate_trace_for_ctx!(cx);
+        Ok(self
+            .process(cx, move |config| {
+                process::crop(&image, &region, &output, config).map(Bytes::from)
+            })
+            .await
+            .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn convert(
+        &self,
+        cx: Option<Context>,
+        image: Bytes,
+        output: OutputOptions,
+    ) -> anyhow::Result<Result<Bytes, String>> {
+        propagate_trace_for_ctx!(cx);
+        Ok(self
+            .process(cx, move |config| {
+                process::convert(&image, &output, config).map(Bytes::from)
+            })
+            .await
+            .map_err(|err| format!("{err:#}")))
+    }
+}
@@ -0,0 +1,368 @@
+//! Image operations, which are CPU-heavy and expected to run on blocking threads
+
+use std::io::Cursor;
+
+use anyhow::{bail, ensure, Context as _, Result};
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageReader, Limits};
+
+use crate::bindings::exports::wasmcloud::image::processing::{
+    Filter, ImageFormat, ImageInfo, OutputOptions, Region, ResizeOptions,
+};
+use crate::config::ImageConfig;
+
+/// Quality of lossy encodings, unless specified by the component
+const DEFAULT_QUALITY: u8 = 80;
+
+/// Upper bound of the bytes allocated per decoded pixel, used to limit decoder allocations
+const MAX_BYTES_PER_PIXEL: u64 = 16;
+
+impl From<ImageFormat> for image::ImageFormat {
+    fn from(format: ImageFormat) -> Self {
+        match format {
+            ImageFormat::Png => Self::Png,
+            ImageFormat::Jpeg => Self::Jpeg,
+            ImageFormat::Gif => Self::Gif,
+            ImageFormat::Webp => Self::WebP,
+            ImageFormat::Bmp => Self::Bmp,
+            ImageFormat::Tiff => Self::Tiff,
+        }
+    }
+}
+
+impl TryFrom<image::ImageFormat> for ImageFormat {
+    type Error = anyhow::Error;
+
+    fn try_from(format: image::ImageFormat) -> Result<Self> {
+        match format {
+            image::ImageFormat::Png => Ok(Self::Png),
+            image::ImageFormat::Jpeg => Ok(Self::Jpeg),
+            image::ImageFormat::Gif => Ok(Self::Gif),
+            image::ImageFormat::WebP => Ok(Self::Webp),
+            image::ImageFormat::Bmp => Ok(Self::Bmp),
+            image::ImageFormat::Tiff => Ok(Self::Tiff),
+            format => bail!("unsupported image format `{format:?}`"),
+        }
+    }
+}
+
+impl From<Filter> for FilterType {
+    fn from(filter: Filter) -> Self {
+        match filter {
+            Filter::Nearest => Self::Nearest,
+            Filter::Triangle => Self::Triangle,
+            Filter::CatmullRom => Self::CatmullRom,
+            Filter::Gaussian => Self::Gaussian,
+            Filter::Lanczos3 => Self::Lanczos3,
+        }
+    }
+}
+
+/// Determine the format and dimensions of `data`, enforcing the input limits of `config`
+fn inspect(data: &[u8], config: &ImageConfig) -> Result<(ImageInfo, ImageReader<Cursor<&[u8]>>)> {
+    ensure!(
+        data.len() <= config.max_input_bytes,
+        "image of {} bytes exceeds the maximum of {} bytes",
+        data.len(),
+        config.max_input_bytes
+    );
+    let reader = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .context("failed to read image")?;
+    let format = reader
+        .format()
+        .context("failed to determine image format")?
+        .try_into()?;
+    // Only the header is decoded to determine the dimensions, so that oversized images are
+    // rejected before any pixels are allocated
+    let (width, height) = ImageReader::new(Cursor::new(data))
+        .with_guessed_format()
+        .context("failed to read image")?
+        .into_dimensions()
+        .context("failed to read image dimensions")?;
+    ensure!(
+        width <= config.max_dimension && height <= config.max_dimension,
+        "image of {width}x{height} pixels exceeds the maximum dimension of {} pixels",
+        config.max_dimension
+    );
+    ensure!(
+        u64::from(width) * u64::from(height) <= config.max_pixels,
+        "image of {width}x{height} pixels exceeds the maximum of {} pixels",
+        config.max_pixels
+    );
+    Ok((
+        ImageInfo {
+            format,
+            width,
+            height,
+        },
+        reader,
+    ))
+}
+
+/// Decode `data`, enforcing the input limits of `config`
+fn decode(data: &[u8], config: &ImageConfig) -> Result<(ImageFormat, DynamicImage)> {
+    let (ImageInfo { format, .. }, mut reader) = inspect(data, config)?;
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(config.max_dimension);
+    limits.max_image_height = Some(config.max_dimension);
+    limits.max_alloc = Some(config.max_pixels.saturating_mul(MAX_BYTES_PER_PIXEL));
+    reader.limits(limits);
+    let image = reader.decode().context("failed to decode image")?;
+    Ok((format, image))
+}
+
+/// Encode `image` as specified by `output`, defaulting to `format`
+fn encode(image: &DynamicImage, format: ImageFormat, output: &OutputOptions) -> Result<Vec<u8>> {
+    let format = output.format.unwrap_or(format);
+    let quality = output.quality.unwrap_or(DEFAULT_QUALITY);
+    ensure!(
+        (1..=100).contains(&quality),
+        "quality must be between 1 and 100, got {quality}"
+    );
+    let mut buf = Vec::new();
+    match format {
+        ImageFormat::Jpeg => {
+            // JPEG has no alpha channel
+            DynamicImage::ImageRgb8(image.to_rgb8())
+                .write_with_encoder(JpegEncoder::new_with_quality(&mut buf, quality))
+                .context("failed to encode image")?;
+        }
+        ImageFormat::Png => {
+            image
+                .write_to(&mut Cursor::new(&mut buf), format.into())
+                .context("failed to encode image")?;
+        }
+        // Encoders of other formats do not support all color types, but all support RGBA
+        _ => {
+            DynamicImage::ImageRgba8(image.to_rgba8())
+                .write_to(&mut Cursor::new(&mut buf), format.into())
+                .context("failed to encode image")?;
+        }
+    }
+    Ok(buf)
+}
+
+/// Determine the format and dimensions of `data` without decoding it
+pub fn info(data: &[u8], config: &ImageConfig) -> Result<ImageInfo> {
+    inspect(data, config).map(|(info, _)| info)
+}
+
+/// Resize the image `data`
+pub fn resize(
+    data: &[u8],
+    options: &ResizeOptions,
+    output: &OutputOptions,
+    config: &ImageConfig,
+) -> Result<Vec<u8>> {
+    let &ResizeOptions {
+        width,
+        height,
+        preserve_aspect_ratio,
+        filter,
+    } = options;
+    ensure!(
+        width > 0 && height > 0,
+        "resized width and height must be greater than 0"
+    );
+    ensure!(
+        width <= config.max_dimension && height <= config.max_dimension,
+        "resized image of {width}x{height} pixels exceeds the maximum dimension of {} pixels",
+        config.max_dimension
+    );
+    ensure!(
+        u64::from(width) * u64::from(height) <= config.max_pixels,
+        "resized image of {width}x{height} pixels exceeds the maximum of {} pixels",
+        config.max_pixels
+    );
+    let (format, image) = decode(data, config)?;
+    let filter = filter.unwrap_or(Filter::Lanczos3).into();
+    let image = if preserve_aspect_ratio {
+        image.resize(width, height, filter)
+    } else {
+        image.resize_exact(width, height, filter)
+    };
+    encode(&image, format, output)
+}
+
+/// Crop the image `data` to `region`
+pub fn crop(
+    data: &[u8],
+    region: &Region,
+    output: &OutputOptions,
+    config: &ImageConfig,
+) -> Result<Vec<u8>> {
+    let &Region {
+        x,
+        y,
+        width,
+        height,
+    } = region;
+    ensure!(
+        width > 0 && height > 0,
+        "region width and height must be greater than 0"
+    );
+    let (format, image) = decode(data, config)?;
+    ensure!(
+        x.checked_add(width)
+            .is_some_and(|right| right <= image.width())
+            && y.checked_add(height)
+                .is_some_and(|bottom| bottom <= image.height()),
+        "region of {width}x{height} pixels at ({x}, {y}) exceeds the image of {}x{} pixels",
+        image.width(),
+        image.height()
+    );
+    encode(&image.crop_imm(x, y, width, height), format, output)
+}
+
+/// Convert the image `data` to the format of `output`
+pub fn convert(data: &[u8], output: &OutputOptions, config: &ImageConfig) -> Result<Vec<u8>> {
+    let (format, image) = decode(data, config)?;
+    encode(&image, format, output)
+}
+
+#[cfg(test)]
+mod test {
+    use image::{GenericImageView as _, Rgba, RgbaImage};
+
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = RgbaImage::from_fn(width, height, |x, y| {
+            Rgba([(x % 256) as u8, (y % 256) as u8, 0x80, 0xff])
+        });
+        let mut buf = Vec::new();
+        DynamicImage::ImageRgba8(image)
+            .write_to(&mut Cursor::new(&mut buf), image::ImageFormat::Png)
+            .unwrap();
+        buf
+    }
+
+    fn output(format: Option<ImageFormat>) -> OutputOptions {
+        OutputOptions {
+            format,
+            quality: None,
+        }
+    }
+
+    #[test]
+    fn process_images() {
+        let config = ImageConfig::default();
+        let data = png(64, 32);
+        let ImageInfo {
+            format,
+            width,
+            height,
+        } = info(&data, &config).unwrap();
+        assert_eq!(format, ImageFormat::Png);
+        assert_eq!((width, height), (64, 32));
+
+        let resized = resize(
+            &data,
+            &ResizeOptions {
+                width: 16,
+                height: 16,
+                preserve_aspect_ratio: true,
+                filter: None,
+            },
+            &output(None),
+            &config,
+        )
+        .unwrap();
+        let resized = image::load_from_memory(&resized).unwrap();
+        assert_eq!(resized.dimensions(), (16, 8));
+
+        let cropped = crop(
+            &data,
+            &Region {
+                x: 8,
+                y: 8,
+                width: 10,
+                height: 20,
+            },
+            &output(Some(ImageFormat::Jpeg)),
+            &config,
+        )
+        .unwrap();
+        let info = info(&cropped, &config).unwrap();
+        assert_eq!(info.format, ImageFormat::Jpeg);
+        assert_eq!((info.width, info.height), (10, 20));
+
+        assert!(crop(
+            &data,
+            &Region {
+                x: 60,
+                y: 0,
+                width: 10,
+                height: 10,
+            },
+            &output(None),
+            &config,
+        )
+        .is_err());
+        assert!(convert(
+            &data,
+            &OutputOptions {
+                format: Some(ImageFormat::Jpeg),
+                quality: Some(0),
+            },
+            &config,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn enforce_limits() {
+        let data = png(64, 32);
+        assert!(info(
+            &data,
+            &ImageConfig {
+                max_input_bytes: 16,
+                ..Default::default()
+            }
+        )
+        .is_err());
+        assert!(convert(
+            &data,
+            &output(Some(ImageFormat::Webp)),
+            &ImageConfig {
+                max_pixels: 1024,
+                ..Default::default()
+            }
+        )
+        .is_err());
+        assert!(resize(
+            &data,
+            &ResizeOptions {
+                width: 4096,
+                height: 4096,
+                preserve_aspect_ratio: false,
+                filter: Some(Filter::Nearest),
+            },
+            &output(None),
+            &ImageConfig {
+                max_dimension: 1024,
+                ..Default::default()
+            }
+        )
+        .is_err());
+        // The input is within the limits, but the resized image is not
+        assert!(resize(
+            &data,
+            &ResizeOptions {
+                width: 1024,
+                height: 1024,
+                preserve_aspect_ratio: false,
+                filter: Some(Filter::Nearest),
+            },
+            &output(None),
+            &ImageConfig {
+                max_pixels: 4096,
+                ..Default::default()
+            }
+        )
+        .is_err());
+        assert!(info(b"not an image", &ImageConfig::default()).is_err());
+    }
+}
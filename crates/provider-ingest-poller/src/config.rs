@@ -0,0 +1,235 @@
+//! Configuration for ingest-poller capability provider
+//!
+//! See README.md for the supported link configuration.
+
+use core::time::Duration;
+
+use std::collections::HashMap;
+
+use anyhow::{bail, ensure, Context as _, Result};
+use tracing::warn;
+use url::Url;
+use wasmcloud_provider_sdk::core::secrets::SecretValue;
+use wasmcloud_provider_sdk::LinkConfig;
+
+/// Comma-separated URLs of the locations to poll
+const CONFIG_SOURCES_KEY: &str = "SOURCES";
+
+/// Blobstore container that files are written to
+const CONFIG_CONTAINER_KEY: &str = "CONTAINER";
+
+/// Prefix of the names of the objects that files are written to
+const CONFIG_PREFIX_KEY: &str = "PREFIX";
+
+/// Number of seconds between polls of the sources
+const CONFIG_POLL_INTERVAL_KEY: &str = "POLL_INTERVAL_SECS";
+
+/// Maximum size of ingested files, in bytes
+const CONFIG_MAX_FILE_SIZE_KEY: &str = "MAX_FILE_SIZE_BYTES";
+
+/// Username used to log in to FTP servers
+const CONFIG_FTP_USERNAME_KEY: &str = "FTP_USERNAME";
+
+/// Password used to log in to FTP servers
+const CONFIG_FTP_PASSWORD_KEY: &str = "FTP_PASSWORD";
+
+/// Value of the `Authorization` header sent to HTTP(S) servers
+const CONFIG_HTTP_AUTHORIZATION_KEY: &str = "HTTP_AUTHORIZATION";
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Files are held in memory while they are ingested, so their size is limited
+const DEFAULT_MAX_FILE_SIZE: u64 = 64 * 1024 * 1024; // 64 MiB
+
+/// Configuration of the ingestion of a linked component
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IngestConfig {
+    /// SOURCES, the `ftp://`, `http://` or `https://` locations to poll. FTP locations are
+    /// directories, of which all files are ingested, while HTTP(S) locations are single files
+    pub sources: Vec<Url>,
+
+    /// CONTAINER, the blobstore container that files are written to
+    pub container: String,
+
+    /// PREFIX, prefix of the names of the objects that files are written to. Defaults to none
+    pub prefix: String,
+
+    /// POLL_INTERVAL_SECS, time between polls of the sources. Defaults to 60 seconds
+    pub poll_interval: Duration,
+
+    /// MAX_FILE_SIZE_BYTES, maximum size of ingested files. Larger files are skipped. Defaults
+    /// to 64 MiB
+    pub max_file_size: u64,
+
+    /// FTP_USERNAME, username used to log in to FTP servers. Defaults to the username of the
+    /// source URL, or `anonymous`
+    pub ftp_username: Option<String>,
+
+    /// FTP_PASSWORD, password used to log in to FTP servers. Defaults to the password of the
+    /// source URL
+    pub ftp_password: Option<String>,
+
+    /// HTTP_AUTHORIZATION, value of the `Authorization` header sent to HTTP(S) servers
+    pub http_authorization: Option<String>,
+}
+
+impl IngestConfig {
+    /// Build an [`IngestConfig`] from a link configuration
+    pub fn from_link_config(
+        LinkConfig {
+            config, secrets, ..
+        }: &LinkConfig,
+    ) -> Result<Self> {
+        let sources = config
+            .get(CONFIG_SOURCES_KEY)
+            .with_context(|| format!("`{CONFIG_SOURCES_KEY}` is required"))?
+            .split(',')
+            .map(str::trim)
+            .filter(|source| !source.is_empty())
+            .map(|source| {
+                let url = Url::parse(source)
+                    .with_context(|| format!("invalid source URL `{source}`"))?;
+                match url.scheme() {
+                    "ftp" | "http" | "https" => {}
+                    scheme => bail!(
+                        "unsupported scheme `{scheme}` of source `{source}`, expected `ftp`, `http` or `https`"
+                    ),
+                }
+                ensure!(url.has_host(), "source URL `{source}` has no host");
+                Ok(url)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        ensure!(
+            !sources.is_empty(),
+            "`{CONFIG_SOURCES_KEY}` must contain at least one URL"
+        );
+        let container = config
+            .get(CONFIG_CONTAINER_KEY)
+            .filter(|container| !container.is_empty())
+            .with_context(|| format!("`{CONFIG_CONTAINER_KEY}` is required"))?
+            .clone();
+        let poll_interval = if let Some(interval) = config.get(CONFIG_POLL_INTERVAL_KEY) {
+            let secs: u64 = interval.parse().with_context(|| {
+                format!("invalid `{CONFIG_POLL_INTERVAL_KEY}` value `{interval}`")
+            })?;
+            ensure!(
+                secs > 0,
+                "`{CONFIG_POLL_INTERVAL_KEY}` must be greater than 0"
+            );
+            Duration::from_secs(secs)
+        } else {
+            DEFAULT_POLL_INTERVAL
+        };
+        let max_file_size = if let Some(size) = config.get(CONFIG_MAX_FILE_SIZE_KEY) {
+            size.parse()
+                .with_context(|| format!("invalid `{CONFIG_MAX_FILE_SIZE_KEY}` value `{size}`"))?
+        } else {
+            DEFAULT_MAX_FILE_SIZE
+        };
+        Ok(Self {
+            sources,
+            container,
+            prefix: config.get(CONFIG_PREFIX_KEY).cloned().unwrap_or_default(),
+            poll_interval,
+            max_file_size,
+            ftp_username: config.get(CONFIG_FTP_USERNAME_KEY).cloned(),
+            ftp_password: secret(config, secrets, CONFIG_FTP_PASSWORD_KEY),
+            http_authorization: secret(config, secrets, CONFIG_HTTP_AUTHORIZATION_KEY),
+        })
+    }
+}
+
+/// Look up a sensitive value, preferring secrets over configuration
+fn secret(
+    config: &HashMap<String, String>,
+    secrets: &HashMap<String, SecretValue>,
+    key: &str,
+) -> Option<String> {
+    if let Some(value) = secrets.get(key).and_then(SecretValue::as_string) {
+        return Some(value.to_string());
+    }
+    let value = config.get(key)?;
+    warn!("secret value [{key}] was missing, but was found configuration. Please prefer using secrets for sensitive values.");
+    Some(value.clone())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ingest_config(config: &[(&str, &str)]) -> Result<IngestConfig> {
+        let config: HashMap<_, _> = config
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        IngestConfig::from_link_config(&LinkConfig {
+            source_id: "provider",
+            target_id: "component",
+            link_name: "default",
+            config: &config,
+            secrets: &HashMap::new(),
+            wit_metadata: (&"wasmcloud".to_string(), &"ingest".to_string(), &vec![]),
+        })
+    }
+
+    #[test]
+    fn parse_config() {
+        assert_eq!(
+            ingest_config(&[
+                (
+                    "SOURCES",
+                    "ftp://ftp.example.com/exports/, https://example.com/daily.csv"
+                ),
+                ("CONTAINER", "ingest"),
+                ("PREFIX", "incoming/"),
+                ("POLL_INTERVAL_SECS", "300"),
+                ("MAX_FILE_SIZE_BYTES", "1024"),
+                ("FTP_USERNAME", "batch"),
+                ("FTP_PASSWORD", "secret"),
+            ])
+            .unwrap(),
+            IngestConfig {
+                sources: vec![
+                    Url::parse("ftp://ftp.example.com/exports/").unwrap(),
+                    Url::parse("https://example.com/daily.csv").unwrap(),
+                ],
+                container: "ingest".into(),
+                prefix: "incoming/".into(),
+                poll_interval: Duration::from_secs(300),
+                max_file_size: 1024,
+                ftp_username: Some("batch".into()),
+                ftp_password: Some("secret".into()),
+                http_authorization: None,
+            }
+        );
+        let config = ingest_config(&[
+            ("SOURCES", "https://example.com/daily.csv"),
+            ("CONTAINER", "ingest"),
+        ])
+        .unwrap();
+        assert_eq!(config.prefix, "");
+        assert_eq!(config.poll_interval, DEFAULT_POLL_INTERVAL);
+        assert_eq!(config.max_file_size, DEFAULT_MAX_FILE_SIZE);
+
+        assert!(ingest_config(&[("CONTAINER", "ingest")]).is_err());
+        assert!(ingest_config(&[("SOURCES", "https://example.com/daily.csv")]).is_err());
+        assert!(ingest_config(&[("SOURCES", " , "), ("CONTAINER", "ingest")]).is_err());
+        assert!(ingest_config(&[
+            ("SOURCES", "sftp://example.com/exports"),
+            ("CONTAINER", "ingest")
+        ])
+        .is_err());
+        assert!(ingest_config(&[
+            ("SOURCES", "https://example.com/daily.csv"),
+            ("CONTAINER", "ingest"),
+            ("POLL_INTERVAL_SECS", "0"),
+        ])
+        .is_err());
+        assert!(ingest_config(&[
+            ("SOURCES", "https://example.com/daily.csv"),
+            ("CONTAINER", "ingest"),
+            ("MAX_FILE_SIZE_BYTES", "64MiB"),
+        ])
+        .is_err());
+    }
+}
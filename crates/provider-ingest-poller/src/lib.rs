@@ -0,0 +1,348 @@
+//! Ingest poller provider, which periodically polls FTP and HTTP(S) locations for new and changed
+//! files, writes them into a linked blobstore and notifies linked components implementing
+//! `wasmcloud:ingest/handler`.
+//!
+//! The provider is the source of links to components and to blobstore providers implementing
+//! `wrpc:blobstore/blobstore`. Files polled for a component are written to the blobstore linked
+//! to the provider with the same link name as the link of the provider to the component.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Context as _};
+use bytes::Bytes;
+use futures::stream;
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{debug, error, info, instrument, warn};
+use wasmcloud_provider_sdk::provider::WrpcClient;
+use wasmcloud_provider_sdk::{
+    get_connection, initialize_observability, run_provider, LinkConfig, LinkDeleteInfo, Provider,
+};
+use wrpc_interface_blobstore::bindings::wrpc::blobstore::{blobstore, types::ObjectId};
+
+mod config;
+mod source;
+mod state;
+
+use config::IngestConfig;
+use source::{FetchedFile, Source};
+use state::Ingested;
+
+mod bindings {
+    wit_bindgen_wrpc::generate!({
+        with: {
+            "wasmcloud:ingest/handler@0.1.0-draft": generate,
+        }
+    });
+}
+use bindings::wasmcloud::ingest::handler::{self, IngestedFile};
+
+pub async fn run() -> anyhow::Result<()> {
+    IngestPollerProvider::run().await
+}
+
+/// Ingest provider polling FTP and HTTP(S) locations
+#[derive(Clone, Default)]
+pub struct IngestPollerProvider {
+    /// Clients of linked blobstores, indexed by link name
+    blobstores: Arc<RwLock<HashMap<String, Arc<WrpcClient>>>>,
+    /// Tasks polling the sources of linked components, indexed by component ID and link name
+    pollers: Arc<RwLock<HashMap<(String, String), JoinHandle<()>>>>,
+}
+
+impl IngestPollerProvider {
+    fn name() -> &'static str {
+        "ingest-poller-provider"
+    }
+
+    /// Run [`IngestPollerProvider`] as a wasmCloud provider
+    pub async fn run() -> anyhow::Result<()> {
+        initialize_observability!(
+            IngestPollerProvider::name(),
+            std::env::var_os("PROVIDER_INGEST_POLLER_FLAMEGRAPH_PATH")
+        );
+        let shutdown = run_provider(
+            IngestPollerProvider::default(),
+            IngestPollerProvider::name(),
+        )
+        .await
+        .context("failed to run provider")?;
+        shutdown.await;
+        Ok(())
+    }
+}
+
+impl Provider for IngestPollerProvider {
+    #[instrument(level = "info", skip_all, fields(target_id = link_config.target_id, link_name = link_config.link_name))]
+    async fn receive_link_config_as_source(
+        &self,
+        link_config: LinkConfig<'_>,
+    ) -> anyhow::Result<()> {
+        let (namespace, package, _) = link_config.wit_metadata;
+        let wrpc = get_connection()
+            .get_wrpc_client(link_config.target_id)
+            .await
+            .context("failed to construct wRPC client")?;
+        match (namespace.as_str(), package.as_str()) {
+            ("wrpc", "blobstore") => {
+                debug!("linked blobstore");
+                self.blobstores
+                    .write()
+                    .await
+                    .insert(link_config.link_name.to_string(), Arc::new(wrpc));
+            }
+            ("wasmcloud", "ingest") => {
+                let config = match IngestConfig::from_link_config(&link_config) {
+                    Ok(config) => config,
+                    Err(e) => {
+                        error!(error = %e, target_id = %link_config.target_id, "failed to parse ingest configuration");
+                        return Err(e);
+                    }
+                };
+                let poller = Poller::new(
+                    config,
+                    wrpc,
+                    link_config.target_id.to_string(),
+                    link_config.link_name.to_string(),
+                    Arc::clone(&self.blobstores),
+                );
+                let key = (
+                    link_config.target_id.to_string(),
+                    link_config.link_name.to_string(),
+                );
+                if let Some(task) = self
+                    .pollers
+                    .write()
+                    .await
+                    .insert(key, tokio::spawn(poller.run()))
+                {
+                    task.abort();
+                }
+            }
+            _ => bail!(
+                "unsupported link to `{namespace}:{package}`, only links to `wrpc:blobstore` and `wasmcloud:ingest` are supported"
+            ),
+        }
+        Ok(())
+    }
+
+    #[instrument(level = "info", skip_all, fields(target_id = info.get_target_id(), link_name = info.get_link_name()))]
+    async fn delete_link_as_source(&self, info: impl LinkDeleteInfo) -> anyhow::Result<()> {
+        let key = (
+            info.get_target_id().to_string(),
+            info.get_link_name().to_string(),
+        );
+        if let Some(task) = self.pollers.write().await.remove(&key) {
+            task.abort();
+        } else {
+            self.blobstores.write().await.remove(info.get_link_name());
+        }
+        Ok(())
+    }
+
+    async fn shutdown(&self) -> anyhow::Result<()> {
+        for (_, task) in self.pollers.write().await.drain() {
+            task.abort();
+        }
+        self.blobstores.write().await.drain();
+        Ok(())
+    }
+}
+
+/// Polls the sources of a linked component, ingesting new and changed files
+struct Poller {
+    config: IngestConfig,
+    sources: Vec<Source>,
+    /// Files ingested so far, loaded from the blobstore on the first poll
+    ingested: Option<Ingested>,
+    /// Client of the linked component
+    component: WrpcClient,
+    /// ID of the linked component
+    component_id: String,
+    /// Link name used to look up the blobstore that files are written to
+    link_name: String,
+    blobstores: Arc<RwLock<HashMap<String, Arc<WrpcClient>>>>,
+}
+
+impl Poller {
+    fn new(
+        config: IngestConfig,
+        component: WrpcClient,
+        component_id: String,
+        link_name: String,
+        blobstores: Arc<RwLock<HashMap<String, Arc<WrpcClient>>>>,
+    ) -> Self {
+        let client = reqwest::Client::new();
+        let sources = config
+            .sources
+            .iter()
+            .map(|url| Source::new(url.clone(), client.clone(), &config))
+            .collect();
+        Self {
+            config,
+            sources,
+            ingested: None,
+            component,
+            component_id,
+            link_name,
+            blobstores,
+        }
+    }
+
+    async fn run(mut self) {
+        let mut interval = interval(self.config.poll_interval);
+        // Polls may take longer than the interval, in which case they should not pile up
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            self.poll().await;
+        }
+    }
+
+    /// ID of the object storing the files ingested so far
+    fn state_id(&self) -> ObjectId {
+        ObjectId {
+            container: self.config.container.clone(),
+            object: state::state_object(&self.config.prefix, &self.component_id, &self.link_name),
+        }
+    }
+
+    /// Get the linked blobstore, creating the container if it does not exist
+    async fn blobstore(&self) -> anyhow::Result<Arc<WrpcClient>> {
+        let blobstore = self
+            .blobstores
+            .read()
+            .await
+            .get(&self.link_name)
+            .cloned()
+            .with_context(|| format!("no blobstore linked with link name `{}`", self.link_name))?;
+        let container = &self.config.container;
+        let exists = blobstore::container_exists(&*blobstore, None, container)
+            .await
+            .context("failed to invoke `container-exists`")?
+            .map_err(|err| anyhow!(err).context("failed to check if container exists"))?;
+        if !exists {
+            blobstore::create_container(&*blobstore, None, container)
+                .await
+                .context("failed to invoke `create-container`")?
+                .map_err(|err| anyhow!(err).context("failed to create container"))?;
+        }
+        Ok(blobstore)
+    }
+
+    /// Poll all sources once, ingesting new and changed files. Files which fail to be ingested
+    /// are retried on the next poll
+    #[instrument(level = "debug", skip_all, fields(link_name = self.link_name))]
+    async fn poll(&mut self) {
+        let blobstore = match self.blobstore().await {
+            Ok(blobstore) => blobstore,
+            Err(err) => {
+                warn!(error = ?err, "failed to access blobstore, skipping poll");
+                return;
+            }
+        };
+        let state_id = self.state_id();
+        if self.ingested.is_none() {
+            // Polling without knowing the files ingested before would ingest all files again
+            match state::load(&blobstore, &state_id).await {
+                Ok(ingested) => self.ingested = Some(ingested),
+                Err(err) => {
+                    warn!(error = ?err, "failed to load ingested files, skipping poll");
+                    return;
+                }
+            }
+        }
+        for source in &self.sources {
+            let source_url = source.url();
+            let ingested = self.ingested.get_or_insert_with(Ingested::default);
+            let files = match source.fetch_changed(ingested).await {
+                Ok(files) => files,
+                Err(err) => {
+                    warn!(source = source_url, error = ?err, "failed to poll source");
+                    continue;
+                }
+            };
+            let mut changed = false;
+            for file in files {
+                match ingest(
+                    &blobstore,
+                    &self.component,
+                    &self.config,
+                    &source_url,
+                    &file,
+                )
+                .await
+                {
+                    Ok(()) => {
+                        info!(url = file.url, "ingested file");
+                        ingested.insert(file.url, file.fingerprint);
+                        changed = true;
+                    }
+                    Err(err) => {
+                        warn!(url = file.url, error = ?err, "failed to ingest file");
+                    }
+                }
+            }
+            if changed {
+                if let Err(err) = state::save(&blobstore, &state_id, ingested).await {
+                    warn!(error = ?err, "failed to save ingested files, they may be ingested again after a restart");
+                }
+            }
+        }
+    }
+}
+
+/// Write `data` to the object `id`, replacing it
+pub(crate) async fn write_object(
+    blobstore: &WrpcClient,
+    id: &ObjectId,
+    data: Bytes,
+) -> anyhow::Result<()> {
+    let (res, io) =
+        blobstore::write_container_data(blobstore, None, id, Box::pin(stream::iter([data])))
+            .await
+            .context("failed to invoke `write-container-data`")?;
+    let status = res.map_err(|err| anyhow!(err).context("failed to write object"))?;
+    let io = io.map(tokio::spawn);
+    status
+        .await
+        .map_err(|err| anyhow!(err).context("failed to write object data"))?;
+    if let Some(io) = io {
+        io.await
+            .context("failed to join I/O task")?
+            .context("failed to complete async I/O")?;
+    }
+    Ok(())
+}
+
+/// Write `file` fetched from `source` to the linked blobstore and notify the component
+async fn ingest(
+    blobstore: &WrpcClient,
+    component: &WrpcClient,
+    config: &IngestConfig,
+    source: &str,
+    file: &FetchedFile,
+) -> anyhow::Result<()> {
+    let id = ObjectId {
+        container: config.container.clone(),
+        object: format!("{}{}", config.prefix, file.path),
+    };
+    write_object(blobstore, &id, file.data.clone()).await?;
+    handler::handle_file(
+        component,
+        None,
+        &IngestedFile {
+            source: source.to_string(),
+            url: file.url.clone(),
+            container: id.container,
+            object: id.object,
+            size: file.data.len() as u64,
+            changed: file.changed,
+        },
+    )
+    .await
+    .context("failed to invoke `handle-file`")?
+    .map_err(|err| anyhow!(err).context("component failed to handle file"))
+}
@@ -0,0 +1,452 @@
+//! Locations polled for new and changed files
+
+use std::collections::HashMap;
+
+use anyhow::{bail, ensure, Context as _, Result};
+use async_ftp::types::FileType;
+use async_ftp::FtpStream;
+use bytes::{Bytes, BytesMut};
+use reqwest::header::{AUTHORIZATION, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+use tracing::{debug, instrument, warn};
+use url::{Position, Url};
+
+use crate::config::IngestConfig;
+
+/// Fingerprint of an ingested file, used to detect changes
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    /// Version of the file, the modification time and size of FTP files, or the SHA-256 digest
+    /// of HTTP(S) files
+    version: String,
+    /// `ETag` of HTTP(S) files, used for conditional requests
+    etag: Option<String>,
+    /// `Last-Modified` of HTTP(S) files, used for conditional requests
+    last_modified: Option<String>,
+}
+
+/// A new or changed file fetched from a [`Source`]
+pub struct FetchedFile {
+    /// URL of the file
+    pub url: String,
+    /// Path of the file, consisting of its host, path and query, so that files of different
+    /// sources have different paths
+    pub path: String,
+    /// Contents of the file
+    pub data: Bytes,
+    /// Whether the file was ingested before
+    pub changed: bool,
+    /// Fingerprint to record once the file was ingested
+    pub fingerprint: Fingerprint,
+}
+
+/// A polled location
+pub enum Source {
+    /// All files of a directory on an FTP server
+    Ftp {
+        url: Url,
+        username: String,
+        password: String,
+        max_size: u64,
+    },
+    /// A single file on an HTTP(S) server
+    Http {
+        url: Url,
+        client: reqwest::Client,
+        authorization: Option<String>,
+        max_size: u64,
+    },
+}
+
+impl Source {
+    /// Build a [`Source`] polling `url` with the credentials of `config`
+    pub fn new(url: Url, client: reqwest::Client, config: &IngestConfig) -> Self {
+        match url.scheme() {
+            "ftp" => {
+                let username =
+                    config
+                        .ftp_username
+                        .clone()
+                        .unwrap_or_else(|| match url.username() {
+                            "" => "anonymous".into(),
+                            username => username.into(),
+                        });
+                let password = config
+                    .ftp_password
+                    .clone()
+                    .or_else(|| url.password().map(Into::into))
+                    .unwrap_or_default();
+                Self::Ftp {
+                    url,
+                    username,
+                    password,
+                    max_size: config.max_file_size,
+                }
+            }
+            _ => Self::Http {
+                url,
+                client,
+                authorization: config.http_authorization.clone(),
+                max_size: config.max_file_size,
+            },
+        }
+    }
+
+    /// URL of the polled location, without credentials
+    pub fn url(&self) -> String {
+        let (Self::Ftp { url, .. } | Self::Http { url, .. }) = self;
+        without_credentials(url).into()
+    }
+
+    /// Fetch the files which are not in `ingested` or have changed since. Files larger than the
+    /// maximum file size are skipped
+    pub async fn fetch_changed(
+        &self,
+        ingested: &HashMap<String, Fingerprint>,
+    ) -> Result<Vec<FetchedFile>> {
+        match self {
+            Self::Ftp {
+                url,
+                username,
+                password,
+                max_size,
+            } => fetch_ftp(url, username, password, *max_size, ingested).await,
+            Self::Http {
+                url,
+                client,
+                authorization,
+                max_size,
+            } => {
+                let file =
+                    fetch_http(client, url, authorization.as_deref(), *max_size, ingested).await?;
+                Ok(file.into_iter().collect())
+            }
+        }
+    }
+}
+
+/// Strip the credentials from `url`
+fn without_credentials(url: &Url) -> Url {
+    let mut url = url.clone();
+    // NOTE: Setting the credentials only fails for URLs without host, which sources cannot be
+    let _ = url.set_username("");
+    let _ = url.set_password(None);
+    url
+}
+
+/// Path of the file at `url`, consisting of its host and port, path and query
+fn file_path(url: &Url) -> String {
+    let path = &url[Position::BeforeHost..Position::AfterQuery];
+    if path.ends_with('/') {
+        format!("{path}index")
+    } else {
+        path.to_string()
+    }
+}
+
+#[instrument(level = "debug", skip_all, fields(host = url.host_str()))]
+async fn fetch_ftp(
+    url: &Url,
+    username: &str,
+    password: &str,
+    max_size: u64,
+    ingested: &HashMap<String, Fingerprint>,
+) -> Result<Vec<FetchedFile>> {
+    let host = url.host_str().context("FTP source has no host")?;
+    let port = url.port_or_known_default().unwrap_or(21);
+    let mut ftp = FtpStream::connect((host, port))
+        .await
+        .with_context(|| format!("failed to connect to FTP server `{host}:{port}`"))?;
+    ftp.login(username, password)
+        .await
+        .context("failed to log in to FTP server")?;
+    ftp.transfer_type(FileType::Binary)
+        .await
+        .context("failed to set binary transfer type")?;
+
+    let dir = url.path().trim_end_matches('/');
+    let entries = ftp
+        .nlst(Some(if dir.is_empty() { "/" } else { dir }))
+        .await
+        .with_context(|| format!("failed to list directory `{dir}`"))?;
+    let mut files = Vec::new();
+    for entry in entries {
+        // Servers differ in whether entries include the directory
+        let Some(name) = entry.rsplit('/').next().filter(|name| !name.is_empty()) else {
+            continue;
+        };
+        let path = format!("{dir}/{name}");
+        // Directories have no size, which is how they are told apart from files
+        let size = match ftp.size(&path).await {
+            Ok(Some(size)) => size,
+            Ok(None) | Err(..) => {
+                debug!(path, "skipping entry without size");
+                continue;
+            }
+        };
+        let modified = ftp
+            .mdtm(&path)
+            .await
+            .ok()
+            .flatten()
+            .map(|modified| modified.timestamp())
+            .unwrap_or_default();
+        let fingerprint = Fingerprint {
+            version: format!("{modified}:{size}"),
+            ..Default::default()
+        };
+        let mut file_url = without_credentials(url);
+        file_url.set_path(&path);
+        let previous = ingested.get(file_url.as_str());
+        if previous == Some(&fingerprint) {
+            continue;
+        }
+        if size as u64 > max_size {
+            warn!(
+                path,
+                size, max_size, "skipping file exceeding the maximum file size"
+            );
+            continue;
+        }
+        let data = match ftp.simple_retr(&path).await {
+            Ok(data) => Bytes::from(data.into_inner()),
+            Err(err) => {
+                warn!(path, ?err, "failed to retrieve file");
+                continue;
+            }
+        };
+        // The file may have grown since its size was retrieved
+        if data.len() as u64 > max_size {
+            warn!(
+                path,
+                max_size, "skipping file exceeding the maximum file size"
+            );
+            continue;
+        }
+        files.push(FetchedFile {
+            path: file_path(&file_url),
+            url: String::from(file_url),
+            data,
+            changed: previous.is_some(),
+            fingerprint,
+        });
+    }
+    if let Err(err) = ftp.quit().await {
+        debug!(?err, "failed to quit FTP session");
+    }
+    Ok(files)
+}
+
+#[instrument(level = "debug", skip_all, fields(url = %url))]
+async fn fetch_http(
+    client: &reqwest::Client,
+    url: &Url,
+    authorization: Option<&str>,
+    max_size: u64,
+    ingested: &HashMap<String, Fingerprint>,
+) -> Result<Option<FetchedFile>> {
+    let file_url = without_credentials(url);
+    let previous = ingested.get(file_url.as_str());
+    let mut req = client.get(url.clone());
+    if let Some(authorization) = authorization {
+        req = req.header(AUTHORIZATION, authorization);
+    }
+    if let Some(etag) = previous.and_then(|previous| previous.etag.as_ref()) {
+        req = req.header(IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = previous.and_then(|previous| previous.last_modified.as_ref()) {
+        req = req.header(IF_MODIFIED_SINCE, last_modified);
+    }
+    let mut res = req.send().await.context("failed to send request")?;
+    match res.status() {
+        StatusCode::NOT_MODIFIED => return Ok(None),
+        status if !status.is_success() => bail!("request failed with status `{status}`"),
+        _ => {}
+    }
+    let headers = res.headers();
+    let etag = headers
+        .get(ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .map(String::from);
+    let last_modified = headers
+        .get(LAST_MODIFIED)
+        .and_then(|last_modified| last_modified.to_str().ok())
+        .map(String::from);
+    if let Some(size) = res.content_length() {
+        ensure!(
+            size <= max_size,
+            "file size of {size} bytes exceeds the maximum file size of {max_size} bytes"
+        );
+    }
+    // Servers may not send the size of the file, so it is only read up to the maximum size
+    let mut data = BytesMut::new();
+    while let Some(chunk) = res.chunk().await.context("failed to read response body")? {
+        ensure!(
+            (data.len() + chunk.len()) as u64 <= max_size,
+            "file exceeds the maximum file size of {max_size} bytes"
+        );
+        data.extend_from_slice(&chunk);
+    }
+    let data = data.freeze();
+    // Servers may not support conditional requests, so changes are detected by digest
+    let fingerprint = Fingerprint {
+        version: Sha256::digest(&data)
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect(),
+        etag,
+        last_modified,
+    };
+    if previous.map(|previous| &previous.version) == Some(&fingerprint.version) {
+        return Ok(None);
+    }
+    Ok(Some(FetchedFile {
+        path: file_path(&file_url),
+        url: file_url.into(),
+        data,
+        changed: previous.is_some(),
+        fingerprint,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::Ipv4Addr;
+    use std::sync::{Arc, Mutex};
+
+    use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// Serve HTTP requests on a local port, answering each with `respond(request)`, where the
+    /// request is lowercase. Returns the URL of `/exports/daily.csv` on the server
+    async fn serve(respond: impl Fn(&str) -> String + Send + 'static) -> Url {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut conn, _)) = listener.accept().await {
+                let mut req = Vec::new();
+                let mut buf = [0; 1024];
+                while !req.ends_with(b"\r\n\r\n") {
+                    match conn.read(&mut buf).await {
+                        Ok(0) | Err(..) => break,
+                        Ok(n) => req.extend_from_slice(&buf[..n]),
+                    }
+                }
+                let res = respond(&String::from_utf8_lossy(&req).to_lowercase());
+                let _ = conn.write_all(res.as_bytes()).await;
+            }
+        });
+        Url::parse(&format!("http://{addr}/exports/daily.csv")).unwrap()
+    }
+
+    fn response(status: &str, headers: &[(&str, &str)], body: &str) -> String {
+        let headers: String = headers
+            .iter()
+            .map(|(k, v)| format!("{k}: {v}\r\n"))
+            .collect();
+        format!(
+            "HTTP/1.1 {status}\r\ncontent-length: {}\r\nconnection: close\r\n{headers}\r\n{body}",
+            body.len()
+        )
+    }
+
+    #[test]
+    fn file_paths() {
+        let path = |url: &str| file_path(&Url::parse(url).unwrap());
+        assert_eq!(
+            path("ftp://ftp.example.com/exports/daily.csv"),
+            "ftp.example.com/exports/daily.csv"
+        );
+        // Files of the same name on different sources have different paths
+        assert_ne!(
+            path("https://a.example.com/daily.csv"),
+            path("https://b.example.com/daily.csv")
+        );
+        assert_eq!(
+            path("https://example.com:8443/report?date=today"),
+            "example.com:8443/report?date=today"
+        );
+        assert_eq!(path("https://example.com/"), "example.com/index");
+    }
+
+    #[tokio::test]
+    async fn fetch_conditionally() {
+        let url = serve(|req| {
+            if req.contains("if-none-match: \"v1\"") {
+                response("304 Not Modified", &[], "")
+            } else {
+                response("200 OK", &[("etag", "\"v1\"")], "a,b")
+            }
+        })
+        .await;
+        let client = reqwest::Client::new();
+        let mut ingested = HashMap::new();
+        let file = fetch_http(&client, &url, None, 1024, &ingested)
+            .await
+            .unwrap()
+            .expect("new file should be fetched");
+        assert_eq!(file.data, Bytes::from("a,b"));
+        assert!(!file.changed);
+        assert_eq!(
+            file.path,
+            format!(
+                "{}/exports/daily.csv",
+                &url[Position::BeforeHost..Position::AfterPort]
+            )
+        );
+        ingested.insert(file.url, file.fingerprint);
+
+        assert!(fetch_http(&client, &url, None, 1024, &ingested)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn fetch_changed_digest() {
+        let body = Arc::new(Mutex::new("a,b"));
+        let url = serve({
+            let body = Arc::clone(&body);
+            move |_| response("200 OK", &[], &body.lock().unwrap())
+        })
+        .await;
+        let client = reqwest::Client::new();
+        let mut ingested = HashMap::new();
+        let file = fetch_http(&client, &url, None, 1024, &ingested)
+            .await
+            .unwrap()
+            .expect("new file should be fetched");
+        ingested.insert(file.url, file.fingerprint);
+
+        // Servers without conditional requests return unchanged files, which are skipped
+        assert!(fetch_http(&client, &url, None, 1024, &ingested)
+            .await
+            .unwrap()
+            .is_none());
+
+        *body.lock().unwrap() = "a,b,c";
+        let file = fetch_http(&client, &url, None, 1024, &ingested)
+            .await
+            .unwrap()
+            .expect("changed file should be fetched");
+        assert_eq!(file.data, Bytes::from("a,b,c"));
+        assert!(file.changed);
+    }
+
+    #[tokio::test]
+    async fn fetch_max_size() {
+        let url = serve(|_| response("200 OK", &[], "0123456789")).await;
+        let client = reqwest::Client::new();
+        assert!(fetch_http(&client, &url, None, 9, &HashMap::new())
+            .await
+            .is_err());
+        assert!(fetch_http(&client, &url, None, 10, &HashMap::new())
+            .await
+            .unwrap()
+            .is_some());
+    }
+}
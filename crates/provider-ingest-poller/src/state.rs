@@ -0,0 +1,103 @@
+//! Persistence of the files ingested for a link
+//!
+//! The fingerprints of the files ingested for a link are stored as JSON in an object of the
+//! container that files are written to, so that files are not ingested again after the provider or
+//! the link was restarted.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Context as _, Result};
+use bytes::{Bytes, BytesMut};
+use futures::StreamExt as _;
+use tracing::instrument;
+use wasmcloud_provider_sdk::provider::WrpcClient;
+use wrpc_interface_blobstore::bindings::wrpc::blobstore::{blobstore, types::ObjectId};
+
+use crate::source::Fingerprint;
+use crate::write_object;
+
+/// Fingerprints of the files ingested so far, indexed by URL
+pub type Ingested = HashMap<String, Fingerprint>;
+
+/// Name of the object storing the files ingested for component `component_id` over the link
+/// named `link_name`, below `prefix`
+pub fn state_object(prefix: &str, component_id: &str, link_name: &str) -> String {
+    format!("{prefix}.ingest-poller/{component_id}/{link_name}.json")
+}
+
+/// Load the files ingested so far from object `id`. No files were ingested if it does not exist
+#[instrument(level = "debug", skip(blobstore))]
+pub async fn load(blobstore: &WrpcClient, id: &ObjectId) -> Result<Ingested> {
+    let exists = blobstore::has_object(blobstore, None, id)
+        .await
+        .context("failed to invoke `has-object`")?
+        .map_err(|err| anyhow!(err).context("failed to check if state object exists"))?;
+    if !exists {
+        return Ok(Ingested::default());
+    }
+    let size = blobstore::get_object_info(blobstore, None, id)
+        .await
+        .context("failed to invoke `get-object-info`")?
+        .map_err(|err| anyhow!(err).context("failed to get state object info"))?
+        .size;
+    let (res, io) = blobstore::get_container_data(blobstore, None, id, 0, size)
+        .await
+        .context("failed to invoke `get-container-data`")?;
+    let (mut data, status) =
+        res.map_err(|err| anyhow!(err).context("failed to get state object data"))?;
+    let io = io.map(tokio::spawn);
+    let mut buf = BytesMut::new();
+    while let Some(chunk) = data.next().await {
+        buf.extend_from_slice(&chunk);
+    }
+    if let Some(io) = io {
+        io.await
+            .context("failed to join I/O task")?
+            .context("failed to complete async I/O")?;
+    }
+    status
+        .await
+        .map_err(|err| anyhow!(err).context("failed to read state object data"))?;
+    // Drop the byte at offset `size` returned by blobstores with inclusive ranges
+    buf.truncate(usize::try_from(size).unwrap_or(usize::MAX));
+    decode(&buf)
+}
+
+/// Store the files ingested so far in object `id`
+#[instrument(level = "debug", skip(blobstore, ingested))]
+pub async fn save(blobstore: &WrpcClient, id: &ObjectId, ingested: &Ingested) -> Result<()> {
+    let data = serde_json::to_vec(ingested).context("failed to encode ingested files")?;
+    write_object(blobstore, id, Bytes::from(data)).await
+}
+
+fn decode(data: &[u8]) -> Result<Ingested> {
+    serde_json::from_slice(data).context("failed to decode ingested files")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn state_objects() {
+        assert_eq!(
+            state_object("incoming/", "component", "default"),
+            "incoming/.ingest-poller/component/default.json"
+        );
+        assert_ne!(
+            state_object("", "component", "default"),
+            state_object("", "component", "nightly"),
+        );
+    }
+
+    #[test]
+    fn encode_ingested() {
+        let ingested = Ingested::from([(
+            "https://example.com/daily.csv".to_string(),
+            Fingerprint::default(),
+        )]);
+        let data = serde_json::to_vec(&ingested).unwrap();
+        assert_eq!(decode(&data).unwrap(), ingested);
+        assert!(decode(b"not json").is_err());
+    }
+}
@@ -1,6 +1,12 @@
+use core::fmt;
+use core::str::FromStr;
+use core::time::Duration;
+
 use std::collections::HashMap;
 
-use anyhow::{bail, Result};
+use anyhow::{bail, Context as _, Result};
+use async_nats::jetstream::kv;
+use async_nats::jetstream::stream::{External, Source, StorageType};
 use serde::{Deserialize, Serialize};
 
 use tracing::warn;
@@ -15,6 +21,54 @@ const CONFIG_NATS_CLIENT_JWT: &str = "client_jwt";
 const CONFIG_NATS_CLIENT_SEED: &str = "client_seed";
 const CONFIG_NATS_TLS_CA: &str = "tls_ca";
 const CONFIG_NATS_TLS_CA_FILE: &str = "tls_ca_file";
+const CONFIG_NATS_BUCKET_AUTO_CREATE: &str = "bucket_auto_create";
+/// Deprecated alias of [`CONFIG_NATS_BUCKET_AUTO_CREATE`]
+const CONFIG_NATS_ENABLE_BUCKET_AUTO_CREATE: &str = "enable_bucket_auto_create";
+const CONFIG_NATS_BUCKET_HISTORY: &str = "bucket_history";
+const CONFIG_NATS_BUCKET_TTL: &str = "bucket_ttl";
+const CONFIG_NATS_BUCKET_REPLICAS: &str = "bucket_replicas";
+const CONFIG_NATS_BUCKET_STORAGE: &str = "bucket_storage";
+const CONFIG_NATS_BUCKET_MIRROR: &str = "bucket_mirror";
+const CONFIG_NATS_BUCKET_SOURCES: &str = "bucket_sources";
+const CONFIG_NATS_BUCKET_SOURCE_API_PREFIX: &str = "bucket_source_api_prefix";
+
+/// Bucket replicated into an auto-created bucket, written as `<bucket>[@<js_domain>]`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BucketSource {
+    /// Name of the bucket
+    pub bucket: String,
+    /// JetStream domain hosting the bucket, if it is hosted in another domain
+    #[serde(default)]
+    pub js_domain: Option<String>,
+}
+
+impl FromStr for BucketSource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (bucket, js_domain) = match s.trim().split_once('@') {
+            Some((bucket, domain)) if !domain.is_empty() => (bucket, Some(domain.to_string())),
+            Some((bucket, _)) => (bucket, None),
+            None => (s.trim(), None),
+        };
+        if bucket.is_empty() {
+            bail!("missing bucket name in [{s}]");
+        }
+        Ok(Self {
+            bucket: bucket.to_string(),
+            js_domain,
+        })
+    }
+}
+
+impl fmt::Display for BucketSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.js_domain {
+            Some(domain) => write!(f, "{}@{domain}", self.bucket),
+            None => f.write_str(&self.bucket),
+        }
+    }
+}
 
 /// Configuration for connecting a NATS client.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -23,7 +77,8 @@ pub struct NatsConnectionConfig {
     #[serde(default)]
     pub cluster_uri: Option<String>,
 
-    /// JetStream Domain to connect to
+    /// JetStream Domain to connect to, e.g. the domain of a hub when connected via a leaf node.
+    /// An empty domain uses the JetStream context of the server connected to
     #[serde(default)]
     pub js_domain: Option<String>,
 
@@ -46,6 +101,40 @@ pub struct NatsConnectionConfig {
     /// TLS Certificate Authority, as a path on disk
     #[serde(default)]
     pub tls_ca_file: Option<String>,
+
+    /// Whether to create the bucket if it does not exist
+    #[serde(default)]
+    pub bucket_auto_create: Option<bool>,
+
+    /// Number of historical values kept per key in an auto-created bucket
+    #[serde(default)]
+    pub bucket_history: Option<i64>,
+
+    /// Maximum age of values in an auto-created bucket
+    #[serde(default)]
+    pub bucket_ttl: Option<Duration>,
+
+    /// Number of replicas of an auto-created bucket
+    #[serde(default)]
+    pub bucket_replicas: Option<usize>,
+
+    /// Storage backend of an auto-created bucket
+    #[serde(default)]
+    pub bucket_storage: Option<StorageType>,
+
+    /// Bucket mirrored by an auto-created bucket, which serves reads locally while writes are
+    /// forwarded to the mirrored bucket
+    #[serde(default)]
+    pub bucket_mirror: Option<BucketSource>,
+
+    /// Buckets sourced into an auto-created bucket, aggregating their values
+    #[serde(default)]
+    pub bucket_sources: Option<Vec<BucketSource>>,
+
+    /// JetStream API prefix of the account hosting the mirrored or sourced buckets, if they are
+    /// hosted in another account
+    #[serde(default)]
+    pub bucket_source_api_prefix: Option<String>,
 }
 
 impl NatsConnectionConfig {
@@ -77,6 +166,31 @@ impl NatsConnectionConfig {
         if extra.tls_ca_file.is_some() {
             out.tls_ca_file.clone_from(&extra.tls_ca_file);
         }
+        if extra.bucket_auto_create.is_some() {
+            out.bucket_auto_create = extra.bucket_auto_create;
+        }
+        if extra.bucket_history.is_some() {
+            out.bucket_history = extra.bucket_history;
+        }
+        if extra.bucket_ttl.is_some() {
+            out.bucket_ttl = extra.bucket_ttl;
+        }
+        if extra.bucket_replicas.is_some() {
+            out.bucket_replicas = extra.bucket_replicas;
+        }
+        if extra.bucket_storage.is_some() {
+            out.bucket_storage = extra.bucket_storage;
+        }
+        // A bucket is either a mirror or aggregates sources, so links configuring either
+        // replace both
+        if extra.bucket_mirror.is_some() || extra.bucket_sources.is_some() {
+            out.bucket_mirror.clone_from(&extra.bucket_mirror);
+            out.bucket_sources.clone_from(&extra.bucket_sources);
+        }
+        if extra.bucket_source_api_prefix.is_some() {
+            out.bucket_source_api_prefix
+                .clone_from(&extra.bucket_source_api_prefix);
+        }
         out
     }
 }
@@ -92,6 +206,14 @@ impl Default for NatsConnectionConfig {
             auth_seed: None,
             tls_ca: None,
             tls_ca_file: None,
+            bucket_auto_create: None,
+            bucket_history: None,
+            bucket_ttl: None,
+            bucket_replicas: None,
+            bucket_storage: None,
+            bucket_mirror: None,
+            bucket_sources: None,
+            bucket_source_api_prefix: None,
         }
     }
 }
@@ -128,6 +250,68 @@ impl NatsConnectionConfig {
         } else if let Some(tls_ca_file) = values.get(CONFIG_NATS_TLS_CA_FILE) {
             config.tls_ca_file = Some(tls_ca_file.clone());
         }
+        if let Some(auto_create) = values
+            .get(CONFIG_NATS_BUCKET_AUTO_CREATE)
+            .or_else(|| values.get(CONFIG_NATS_ENABLE_BUCKET_AUTO_CREATE))
+        {
+            config.bucket_auto_create = Some(auto_create.eq_ignore_ascii_case("true"));
+        }
+        if let Some(history) = values.get(CONFIG_NATS_BUCKET_HISTORY) {
+            let history = history
+                .parse()
+                .with_context(|| format!("invalid {CONFIG_NATS_BUCKET_HISTORY} [{history}]"))?;
+            if !(1..=64).contains(&history) {
+                bail!("{CONFIG_NATS_BUCKET_HISTORY} must be between 1 and 64");
+            }
+            config.bucket_history = Some(history);
+        }
+        if let Some(ttl) = values.get(CONFIG_NATS_BUCKET_TTL) {
+            config.bucket_ttl = Some(
+                humantime::parse_duration(ttl)
+                    .with_context(|| format!("invalid {CONFIG_NATS_BUCKET_TTL} [{ttl}]"))?,
+            );
+        }
+        if let Some(replicas) = values.get(CONFIG_NATS_BUCKET_REPLICAS) {
+            let replicas = replicas
+                .parse()
+                .with_context(|| format!("invalid {CONFIG_NATS_BUCKET_REPLICAS} [{replicas}]"))?;
+            if !(1..=5).contains(&replicas) {
+                bail!("{CONFIG_NATS_BUCKET_REPLICAS} must be between 1 and 5");
+            }
+            config.bucket_replicas = Some(replicas);
+        }
+        if let Some(storage) = values.get(CONFIG_NATS_BUCKET_STORAGE) {
+            config.bucket_storage = Some(match storage.to_lowercase().as_str() {
+                "file" => StorageType::File,
+                "memory" => StorageType::Memory,
+                _ => bail!(
+                    "invalid {CONFIG_NATS_BUCKET_STORAGE} [{storage}], expected `file` or `memory`"
+                ),
+            });
+        }
+        if let Some(mirror) = values.get(CONFIG_NATS_BUCKET_MIRROR) {
+            config.bucket_mirror = Some(
+                mirror
+                    .parse()
+                    .with_context(|| format!("invalid {CONFIG_NATS_BUCKET_MIRROR} [{mirror}]"))?,
+            );
+        }
+        if let Some(sources) = values.get(CONFIG_NATS_BUCKET_SOURCES) {
+            config.bucket_sources = Some(
+                sources
+                    .split(',')
+                    .filter(|source| !source.trim().is_empty())
+                    .map(BucketSource::from_str)
+                    .collect::<Result<_>>()
+                    .with_context(|| format!("invalid {CONFIG_NATS_BUCKET_SOURCES} [{sources}]"))?,
+            );
+        }
+        if config.bucket_mirror.is_some() && config.bucket_sources.is_some() {
+            bail!("{CONFIG_NATS_BUCKET_MIRROR} and {CONFIG_NATS_BUCKET_SOURCES} are mutually exclusive");
+        }
+        if let Some(prefix) = values.get(CONFIG_NATS_BUCKET_SOURCE_API_PREFIX) {
+            config.bucket_source_api_prefix = Some(prefix.clone());
+        }
         if config.auth_jwt.is_some() && config.auth_seed.is_none() {
             bail!("if you specify jwt, you must also specify a seed");
         }
@@ -140,6 +324,84 @@ impl NatsConnectionConfig {
         config: &HashMap<String, String>,
         secrets: &HashMap<String, SecretValue>,
     ) -> Result<NatsConnectionConfig> {
+        Self::from_map(&Self::merge_secrets(config, secrets))
+    }
+
+    /// Construct the default configuration of the provider from the configuration and secrets
+    /// supplied by the host. Unlike link configuration, the bucket is optional, so that settings
+    /// such as the cluster URI and JetStream domain can be shared by all links
+    pub fn defaults_from_config_and_secrets(
+        config: &HashMap<String, String>,
+        secrets: &HashMap<String, SecretValue>,
+    ) -> Result<NatsConnectionConfig> {
+        let mut map = Self::merge_secrets(config, secrets);
+        map.entry(CONFIG_NATS_KV_STORE.into()).or_default();
+        Self::from_map(&map)
+    }
+
+    /// Returns the JetStream domain to use, if any
+    pub fn js_domain(&self) -> Option<&str> {
+        self.js_domain
+            .as_deref()
+            .filter(|domain| !domain.is_empty())
+    }
+
+    /// Returns whether the bucket should be created if it does not exist
+    pub fn bucket_auto_create(&self) -> bool {
+        self.bucket_auto_create.unwrap_or_default()
+    }
+
+    /// Returns the configuration of the bucket to create if it does not exist, with the NATS
+    /// defaults for settings that are not configured
+    pub fn bucket_config(&self) -> kv::Config {
+        let defaults = kv::Config::default();
+        kv::Config {
+            bucket: self.bucket.clone(),
+            history: self.bucket_history.unwrap_or(defaults.history),
+            max_age: self.bucket_ttl.unwrap_or(defaults.max_age),
+            num_replicas: self.bucket_replicas.unwrap_or(defaults.num_replicas),
+            storage: self.bucket_storage.unwrap_or(defaults.storage),
+            mirror: self
+                .bucket_mirror
+                .as_ref()
+                .map(|mirror| self.stream_source(mirror)),
+            sources: self.bucket_sources.as_ref().map(|sources| {
+                sources
+                    .iter()
+                    .map(|source| self.stream_source(source))
+                    .collect()
+            }),
+            ..defaults
+        }
+    }
+
+    /// Returns the stream source replicating `source`. Buckets in another account are reached
+    /// through the configured API prefix, which takes precedence over their JetStream domain
+    fn stream_source(&self, source: &BucketSource) -> Source {
+        // Bucket streams are prefixed by `create_key_value`
+        let name = source.bucket.clone();
+        match &self.bucket_source_api_prefix {
+            Some(api_prefix) => Source {
+                name,
+                external: Some(External {
+                    api_prefix: api_prefix.clone(),
+                    delivery_prefix: None,
+                }),
+                ..Default::default()
+            },
+            None => Source {
+                name,
+                domain: source.js_domain.clone(),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Merge sensitive values supplied as secrets into the configuration
+    fn merge_secrets(
+        config: &HashMap<String, String>,
+        secrets: &HashMap<String, SecretValue>,
+    ) -> HashMap<String, String> {
         let mut map = HashMap::clone(config);
 
         if let Some(jwt) = secrets
@@ -175,7 +437,7 @@ impl NatsConnectionConfig {
             map.insert(CONFIG_NATS_TLS_CA.into(), tls_ca.to_string());
         }
 
-        Self::from_map(&map)
+        map
     }
 }
 
@@ -264,6 +526,182 @@ mod test {
         Ok(())
     }
 
+    // Verify that the default configuration does not require a bucket, and that links can override
+    // or clear its JetStream domain
+    #[test]
+    fn test_defaults_js_domain() -> anyhow::Result<()> {
+        let defaults = NatsConnectionConfig::defaults_from_config_and_secrets(
+            &HashMap::from([("js_domain".to_string(), "hub".to_string())]),
+            &HashMap::new(),
+        )?;
+        assert_eq!(defaults.js_domain(), Some("hub"));
+        assert!(defaults.bucket.is_empty());
+
+        let link = NatsConnectionConfig::from_map(&HashMap::from([(
+            "bucket".to_string(),
+            "kv_store".to_string(),
+        )]))?;
+        let merged = defaults.merge(&link);
+        assert_eq!(merged.js_domain(), Some("hub"));
+        assert_eq!(merged.bucket, "kv_store");
+
+        let link = NatsConnectionConfig::from_map(&HashMap::from([
+            ("bucket".to_string(), "kv_store".to_string()),
+            ("js_domain".to_string(), "leaf".to_string()),
+        ]))?;
+        assert_eq!(defaults.merge(&link).js_domain(), Some("leaf"));
+
+        let link = NatsConnectionConfig::from_map(&HashMap::from([
+            ("bucket".to_string(), "kv_store".to_string()),
+            ("js_domain".to_string(), String::new()),
+        ]))?;
+        assert_eq!(defaults.merge(&link).js_domain(), None);
+        Ok(())
+    }
+
+    // Verify that bucket settings are parsed, and used for the bucket configuration
+    #[test]
+    fn test_bucket_settings() -> anyhow::Result<()> {
+        let ncc = NatsConnectionConfig::from_map(&HashMap::from([
+            ("bucket".to_string(), "kv_store".to_string()),
+            ("bucket_auto_create".to_string(), "true".to_string()),
+            ("bucket_history".to_string(), "5".to_string()),
+            ("bucket_ttl".to_string(), "1h 30m".to_string()),
+            ("bucket_replicas".to_string(), "3".to_string()),
+            ("bucket_storage".to_string(), "Memory".to_string()),
+        ]))?;
+        assert!(ncc.bucket_auto_create());
+        let bucket = ncc.bucket_config();
+        assert_eq!(bucket.bucket, "kv_store");
+        assert_eq!(bucket.history, 5);
+        assert_eq!(bucket.max_age, Duration::from_secs(90 * 60));
+        assert_eq!(bucket.num_replicas, 3);
+        assert_eq!(bucket.storage, StorageType::Memory);
+
+        // The deprecated setting is still accepted, and unset settings use the NATS defaults
+        let ncc = NatsConnectionConfig::from_map(&HashMap::from([
+            ("bucket".to_string(), "kv_store".to_string()),
+            ("enable_bucket_auto_create".to_string(), "TRUE".to_string()),
+        ]))?;
+        assert!(ncc.bucket_auto_create());
+        let bucket = ncc.bucket_config();
+        assert_eq!(bucket.history, kv::Config::default().history);
+        assert_eq!(bucket.storage, StorageType::File);
+
+        // Links override the settings of the default configuration
+        let defaults = NatsConnectionConfig::defaults_from_config_and_secrets(
+            &HashMap::from([
+                ("bucket_auto_create".to_string(), "true".to_string()),
+                ("bucket_replicas".to_string(), "3".to_string()),
+            ]),
+            &HashMap::new(),
+        )?;
+        let link = NatsConnectionConfig::from_map(&HashMap::from([
+            ("bucket".to_string(), "kv_store".to_string()),
+            ("bucket_replicas".to_string(), "1".to_string()),
+        ]))?;
+        let merged = defaults.merge(&link);
+        assert!(merged.bucket_auto_create());
+        assert_eq!(merged.bucket_config().num_replicas, 1);
+
+        for (key, value) in [
+            ("bucket_history", "0"),
+            ("bucket_history", "65"),
+            ("bucket_ttl", "forever"),
+            ("bucket_replicas", "7"),
+            ("bucket_storage", "disk"),
+        ] {
+            assert!(
+                NatsConnectionConfig::from_map(&HashMap::from([
+                    ("bucket".to_string(), "kv_store".to_string()),
+                    (key.to_string(), value.to_string()),
+                ]))
+                .is_err(),
+                "{key}={value} should be rejected"
+            );
+        }
+        Ok(())
+    }
+
+    // Verify that mirror and source buckets are parsed, and used for the bucket configuration
+    #[test]
+    fn test_bucket_replication() -> anyhow::Result<()> {
+        let ncc = NatsConnectionConfig::from_map(&HashMap::from([
+            ("bucket".to_string(), "kv_store".to_string()),
+            ("bucket_mirror".to_string(), "global@hub".to_string()),
+        ]))?;
+        let mirror = ncc
+            .bucket_config()
+            .mirror
+            .expect("mirror should be configured");
+        assert_eq!(mirror.name, "global");
+        assert_eq!(mirror.domain.as_deref(), Some("hub"));
+        assert!(mirror.external.is_none());
+
+        let ncc = NatsConnectionConfig::from_map(&HashMap::from([
+            ("bucket".to_string(), "kv_store".to_string()),
+            ("bucket_sources".to_string(), "east@east, west,".to_string()),
+            (
+                "bucket_source_api_prefix".to_string(),
+                "$JS.global.API".to_string(),
+            ),
+        ]))?;
+        assert_eq!(
+            ncc.bucket_sources,
+            Some(vec![
+                BucketSource {
+                    bucket: "east".to_string(),
+                    js_domain: Some("east".to_string()),
+                },
+                BucketSource {
+                    bucket: "west".to_string(),
+                    js_domain: None,
+                },
+            ])
+        );
+        let bucket = ncc.bucket_config();
+        assert!(bucket.mirror.is_none());
+        let sources = bucket.sources.expect("sources should be configured");
+        assert_eq!(sources.len(), 2);
+        for source in sources {
+            assert_eq!(source.domain, None);
+            assert_eq!(
+                source
+                    .external
+                    .map(|external| external.api_prefix)
+                    .as_deref(),
+                Some("$JS.global.API")
+            );
+        }
+
+        // Links replace the replication of the default configuration
+        let defaults = NatsConnectionConfig::defaults_from_config_and_secrets(
+            &HashMap::from([("bucket_mirror".to_string(), "global".to_string())]),
+            &HashMap::new(),
+        )?;
+        let merged = defaults.merge(&ncc);
+        assert_eq!(merged.bucket_mirror, None);
+        assert_eq!(merged.bucket_sources, ncc.bucket_sources);
+
+        for (key, value) in [("bucket_mirror", "@hub"), ("bucket_sources", "a,@b")] {
+            assert!(
+                NatsConnectionConfig::from_map(&HashMap::from([
+                    ("bucket".to_string(), "kv_store".to_string()),
+                    (key.to_string(), value.to_string()),
+                ]))
+                .is_err(),
+                "{key}={value} should be rejected"
+            );
+        }
+        assert!(NatsConnectionConfig::from_map(&HashMap::from([
+            ("bucket".to_string(), "kv_store".to_string()),
+            ("bucket_mirror".to_string(), "global".to_string()),
+            ("bucket_sources".to_string(), "east".to_string()),
+        ]))
+        .is_err());
+        Ok(())
+    }
+
     // Verify that the NatsConnectionConfig's merge function prioritizes the new values over the old ones
     #[test]
     fn test_merge_non_default_values() {
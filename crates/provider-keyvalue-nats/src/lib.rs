@@ -12,7 +12,7 @@ use std::sync::Arc;
 
 use anyhow::{anyhow, bail, Context as _};
 use bytes::Bytes;
-use futures::{StreamExt as _, TryStreamExt as _};
+use futures::{stream, StreamExt as _, TryStreamExt as _};
 use tokio::fs;
 use tokio::sync::RwLock;
 use tracing::{debug, error, info, instrument, warn};
@@ -26,15 +26,21 @@ use wasmcloud_provider_sdk::{
 mod config;
 use config::NatsConnectionConfig;
 
+mod watcher;
+use watcher::{parse_watch_config, Watches};
+
 mod bindings {
     wit_bindgen_wrpc::generate!({
         with: {
             "wrpc:keyvalue/atomics@0.2.0-draft": generate,
             "wrpc:keyvalue/batch@0.2.0-draft": generate,
             "wrpc:keyvalue/store@0.2.0-draft": generate,
+            "wrpc:keyvalue/watcher@0.2.0-draft": generate,
+            "wasmcloud:keyvalue/cas@0.1.0-draft": generate,
         }
     });
 }
+use bindings::exports::wasmcloud::keyvalue::cas;
 use bindings::exports::wrpc::keyvalue;
 
 type Result<T, E = keyvalue::store::Error> = core::result::Result<T, E>;
@@ -46,6 +52,9 @@ pub async fn run() -> anyhow::Result<()> {
 /// The `atomic::increment` function's exponential backoff base interval
 const EXPONENTIAL_BACKOFF_BASE_INTERVAL: u64 = 5; // milliseconds
 
+/// Maximum number of concurrent NATS Kv operations performed by a single batch operation
+const BATCH_CONCURRENCY: usize = 32;
+
 /// [`NatsKvStores`] holds the handles to opened NATS Kv Stores, and their respective identifiers.
 type NatsKvStores = HashMap<String, async_nats::jetstream::kv::Store>;
 
@@ -53,6 +62,8 @@ type NatsKvStores = HashMap<String, async_nats::jetstream::kv::Store>;
 #[derive(Default, Clone)]
 pub struct KvNatsProvider {
     consumer_components: Arc<RwLock<HashMap<String, NatsKvStores>>>,
+    /// Watches on NATS Kv stores per watcher component and link name
+    watcher_components: Arc<RwLock<HashMap<String, HashMap<String, Watches>>>>,
     default_config: NatsConnectionConfig,
 }
 /// Implement the [`KvNatsProvider`] and [`Provider`] traits
@@ -80,8 +91,10 @@ impl KvNatsProvider {
 
     /// Build a [`KvNatsProvider`] from [`HostData`]
     pub fn from_host_data(host_data: &HostData) -> KvNatsProvider {
-        let config =
-            NatsConnectionConfig::from_config_and_secrets(&host_data.config, &host_data.secrets);
+        let config = NatsConnectionConfig::defaults_from_config_and_secrets(
+            &host_data.config,
+            &host_data.secrets,
+        );
         if let Ok(config) = config {
             KvNatsProvider {
                 default_config: config,
@@ -97,8 +110,11 @@ impl KvNatsProvider {
     async fn connect(
         &self,
         cfg: NatsConnectionConfig,
-        link_cfg: &LinkConfig<'_>,
     ) -> anyhow::Result<async_nats::jetstream::kv::Store> {
+        if cfg.bucket.is_empty() {
+            bail!("missing required configuration item: bucket");
+        }
+        let js_domain = cfg.js_domain().map(String::from);
         let mut opts = match (cfg.auth_jwt, cfg.auth_seed) {
             (Some(jwt), Some(seed)) => {
                 let seed = KeyPair::from_seed(&seed).context("failed to parse seed key pair")?;
@@ -130,34 +146,50 @@ impl KvNatsProvider {
             .await?;
 
         // Get the JetStream context based on js_domain
-        let js_context = if let Some(domain) = &cfg.js_domain {
-            async_nats::jetstream::with_domain(client.clone(), domain.clone())
+        let js_context = if let Some(domain) = &js_domain {
+            async_nats::jetstream::with_domain(client.clone(), domain)
         } else {
             async_nats::jetstream::new(client.clone())
         };
 
-        // If bucket auto-creation was specified in the link configuration,
-        // create a bucket
-        if link_cfg
-            .config
-            .get("enable_bucket_auto_create")
-            .is_some_and(|v| v.to_lowercase() == "true")
-        {
-            // Get the JetStream context based on js_domain
-            if let Err(e) = js_context
-                .create_key_value(async_nats::jetstream::kv::Config {
-                    bucket: cfg.bucket.clone(),
-                    ..Default::default()
-                })
-                .await
+        // Open the key-value store, creating it if it does not exist and auto-creation is enabled
+        let store = match js_context.get_key_value(&cfg.bucket).await {
+            Ok(store) => store,
+            Err(err)
+                if err.kind() == async_nats::jetstream::context::KeyValueErrorKind::GetBucket
+                    && cfg.bucket_auto_create() =>
             {
-                warn!("failed to auto create bucket [{}]: {e}", cfg.bucket);
+                let bucket = cfg.bucket_config();
+                info!(
+                    %cfg.bucket,
+                    ?js_domain,
+                    history = bucket.history,
+                    max_age = ?bucket.max_age,
+                    replicas = bucket.num_replicas,
+                    storage = ?bucket.storage,
+                    mirror = ?cfg.bucket_mirror.as_ref().map(ToString::to_string),
+                    sources = ?cfg.bucket_sources.as_ref().map(|sources| sources.iter().map(ToString::to_string).collect::<Vec<_>>()),
+                    "NATS Kv store not found, creating it"
+                );
+                js_context
+                    .create_key_value(bucket)
+                    .await
+                    .with_context(|| format!("failed to create bucket [{}]", cfg.bucket))?
+            }
+            Err(err) => {
+                return Err(anyhow!(err).context(match &js_domain {
+                    Some(domain) => format!(
+                        "failed to open bucket [{}] in JetStream domain [{domain}]",
+                        cfg.bucket
+                    ),
+                    None => format!(
+                        "failed to open bucket [{}] without a JetStream domain, set `js_domain` if the bucket is hosted in another domain",
+                        cfg.bucket
+                    ),
+                }))
             }
         };
-
-        // Open the key-value store
-        let store = js_context.get_key_value(&cfg.bucket).await?;
-        info!(%cfg.bucket, "NATS Kv store opened");
+        info!(%cfg.bucket, ?js_domain, "NATS Kv store opened");
 
         // Return the handle to the opened NATS Kv store
         Ok(store)
@@ -206,27 +238,6 @@ impl KvNatsProvider {
     ) -> anyhow::Result<Result<Option<Bytes>>> {
         keyvalue::store::Handler::get(self, context, bucket, key).await
     }
-
-    /// Helper function to set a value in the key-value store
-    async fn set(
-        &self,
-        context: Option<Context>,
-        bucket: String,
-        key: String,
-        value: Bytes,
-    ) -> anyhow::Result<Result<()>> {
-        keyvalue::store::Handler::set(self, context, bucket, key, value).await
-    }
-
-    /// Helper function to delete a key-value pair from the key-value store
-    async fn delete(
-        &self,
-        context: Option<Context>,
-        bucket: String,
-        key: String,
-    ) -> anyhow::Result<Result<()>> {
-        keyvalue::store::Handler::delete(self, context, bucket, key).await
-    }
 }
 
 /// Handle provider control commands
@@ -263,7 +274,7 @@ impl Provider for KvNatsProvider {
             ..
         }: LinkConfig<'_> = link_config;
 
-        let kv_store = match self.connect(nats_config, &link_config).await {
+        let kv_store = match self.connect(nats_config).await {
             Ok(b) => b,
             Err(e) => {
                 error!("Failed to connect to NATS: {e:?}");
@@ -287,6 +298,53 @@ impl Provider for KvNatsProvider {
         Ok(())
     }
 
+    /// Start watching the keys listed in the link configuration, notifying the target component
+    /// of changes via `wrpc:keyvalue/watcher`
+    #[instrument(level = "debug", skip_all, fields(target_id))]
+    async fn receive_link_config_as_source(
+        &self,
+        link_config: LinkConfig<'_>,
+    ) -> anyhow::Result<()> {
+        let watches = parse_watch_config(link_config.config)
+            .context("failed to parse watch configuration")?;
+        let nats_config = match NatsConnectionConfig::from_config_and_secrets(
+            link_config.config,
+            link_config.secrets,
+        ) {
+            Ok(ncc) => self.default_config.merge(&ncc),
+            Err(e) => {
+                error!("Failed to build NATS connection configuration: {e:?}");
+                return Err(anyhow!(e).context("failed to build NATS connection configuration"));
+            }
+        };
+
+        let LinkConfig {
+            target_id,
+            link_name,
+            ..
+        }: LinkConfig<'_> = link_config;
+
+        let kv_store = match self.connect(nats_config).await {
+            Ok(b) => b,
+            Err(e) => {
+                error!("Failed to connect to NATS: {e:?}");
+                bail!(anyhow!(e).context("failed to connect to NATS"))
+            }
+        };
+        // The link name is passed as the bucket, since that is how the store is identified
+        // by components using `wrpc:keyvalue/store`
+        let watches = Watches::start(&kv_store, link_name, target_id, watches).await?;
+
+        let mut watcher_components = self.watcher_components.write().await;
+        // Replacing existing watches for the link drops, and thereby stops, them
+        watcher_components
+            .entry(target_id.into())
+            .or_default()
+            .insert(link_name.into(), watches);
+
+        Ok(())
+    }
+
     /// Provider should perform any operations needed for a link deletion, including cleaning up
     /// per-component resources.
     #[instrument(level = "info", skip_all, fields(source_id = info.get_source_id()))]
@@ -305,12 +363,38 @@ impl Provider for KvNatsProvider {
         Ok(())
     }
 
+    /// Stop watches for a component when the link is deleted
+    #[instrument(level = "info", skip_all, fields(target_id = info.get_target_id()))]
+    async fn delete_link_as_source(&self, info: impl LinkDeleteInfo) -> anyhow::Result<()> {
+        let component_id = info.get_target_id();
+        let link_name = info.get_link_name();
+        let mut watchers = self.watcher_components.write().await;
+        if let Some(links) = watchers.get_mut(component_id) {
+            // Note: watches are stopped via Drop on `Watches`
+            links.remove(link_name);
+            if links.is_empty() {
+                watchers.remove(component_id);
+            }
+        }
+
+        debug!(
+            component_id,
+            link_name, "finished processing (watcher) link deletion for component"
+        );
+
+        Ok(())
+    }
+
     /// Handle shutdown request by closing all connections
     async fn shutdown(&self) -> anyhow::Result<()> {
         // clear the consumer components
         let mut consumers = self.consumer_components.write().await;
         consumers.clear();
 
+        // stop all watches
+        let mut watchers = self.watcher_components.write().await;
+        watchers.clear();
+
         Ok(())
     }
 }
@@ -510,12 +594,136 @@ impl keyvalue::atomics::Handler<Option<Context>> for KvNatsProvider {
     }
 }
 
+/// Implement the 'wasmcloud:keyvalue/cas' capability provider interface using NATS Kv revisions
+impl cas::Handler<Option<Context>> for KvNatsProvider {
+    /// Gets the value and revision of a key, the value of deleted keys is `None`
+    #[instrument(level = "debug", skip(self))]
+    async fn current(
+        &self,
+        context: Option<Context>,
+        bucket: String,
+        key: String,
+    ) -> anyhow::Result<Result<Option<cas::Entry>, cas::Error>> {
+        propagate_trace_for_ctx!(context);
+
+        let store = match self.get_kv_store(context, bucket).await {
+            Ok(store) => store,
+            Err(err) => return Ok(Err(err.into())),
+        };
+        match store.entry(key.clone()).await {
+            Ok(entry) => Ok(Ok(entry.map(
+                |async_nats::jetstream::kv::Entry {
+                     value,
+                     revision,
+                     operation,
+                     ..
+                 }| cas::Entry {
+                    value: (operation == async_nats::jetstream::kv::Operation::Put)
+                        .then_some(value),
+                    revision,
+                },
+            ))),
+            Err(err) => {
+                error!(%key, "failed to get key entry: {err:?}");
+                Ok(Err(cas::Error::Other(err.to_string())))
+            }
+        }
+    }
+
+    /// Sets a value if the key is at `revision`, returning the new revision or `None` if the key
+    /// is at another revision
+    #[instrument(level = "debug", skip(self, value))]
+    async fn compare_and_swap(
+        &self,
+        context: Option<Context>,
+        bucket: String,
+        key: String,
+        value: Bytes,
+        revision: u64,
+    ) -> anyhow::Result<Result<Option<u64>, cas::Error>> {
+        propagate_trace_for_ctx!(context);
+
+        let store = match self.get_kv_store(context, bucket).await {
+            Ok(store) => store,
+            Err(err) => return Ok(Err(err.into())),
+        };
+        // Unlike an update expecting revision 0, creating a key succeeds if the key was deleted
+        let res = if revision == 0 {
+            match store.create(key.clone(), value).await {
+                Ok(revision) => Ok(Some(revision)),
+                Err(err)
+                    if err.kind() == async_nats::jetstream::kv::CreateErrorKind::AlreadyExists =>
+                {
+                    Ok(None)
+                }
+                Err(err) => Err(err.to_string()),
+            }
+        } else {
+            match store.update(key.clone(), value, revision).await {
+                Ok(revision) => Ok(Some(revision)),
+                Err(err)
+                    if err.kind()
+                        == async_nats::jetstream::kv::UpdateErrorKind::WrongLastRevision =>
+                {
+                    Ok(None)
+                }
+                Err(err) => Err(err.to_string()),
+            }
+        };
+        match res {
+            Ok(None) => {
+                debug!(%key, revision, "key is not at the expected revision");
+                Ok(Ok(None))
+            }
+            Ok(revision) => Ok(Ok(revision)),
+            Err(err) => {
+                error!(%key, "failed to compare and swap key value: {err}");
+                Ok(Err(cas::Error::Other(err)))
+            }
+        }
+    }
+
+    /// Sets a value regardless of the revision of the key, returning the new revision
+    #[instrument(level = "debug", skip(self, value))]
+    async fn swap(
+        &self,
+        context: Option<Context>,
+        bucket: String,
+        key: String,
+        value: Bytes,
+    ) -> anyhow::Result<Result<u64, cas::Error>> {
+        propagate_trace_for_ctx!(context);
+
+        let store = match self.get_kv_store(context, bucket).await {
+            Ok(store) => store,
+            Err(err) => return Ok(Err(err.into())),
+        };
+        match store.put(key.clone(), value).await {
+            Ok(revision) => Ok(Ok(revision)),
+            Err(err) => {
+                error!(%key, "failed to swap key value: {err:?}");
+                Ok(Err(cas::Error::Other(err.to_string())))
+            }
+        }
+    }
+}
+
+impl From<keyvalue::store::Error> for cas::Error {
+    fn from(err: keyvalue::store::Error) -> Self {
+        match err {
+            keyvalue::store::Error::NoSuchStore => Self::NoSuchStore,
+            keyvalue::store::Error::AccessDenied => Self::AccessDenied,
+            keyvalue::store::Error::Other(err) => Self::Other(err),
+        }
+    }
+}
+
 /// Reducing type complexity for the `get_many` function of wasi:keyvalue/batch
 type KvResult = Vec<Option<(String, Bytes)>>;
 
 /// Implement the 'wasi:keyvalue/batch' capability provider interface
 impl keyvalue::batch::Handler<Option<Context>> for KvNatsProvider {
-    // Get multiple values from the key-value store
+    // Get multiple values from the key-value store, concurrently
     #[instrument(level = "debug", skip(self))]
     async fn get_many(
         &self,
@@ -523,48 +731,41 @@ impl keyvalue::batch::Handler<Option<Context>> for KvNatsProvider {
         bucket: String,
         keys: Vec<String>,
     ) -> anyhow::Result<Result<KvResult>> {
-        let ctx = ctx.clone();
-        let bucket = bucket.clone();
+        propagate_trace_for_ctx!(ctx);
 
-        // Get the values for the keys
-        let results: Result<Vec<_>, _> = keys
-            .into_iter()
+        let store = match self.get_kv_store(ctx, bucket).await {
+            Ok(store) => store,
+            Err(err) => return Ok(Err(err)),
+        };
+        // `buffered` preserves the order of the keys in the results
+        let results: Vec<_> = stream::iter(keys)
             .map(|key| {
-                let ctx = ctx.clone();
-                let bucket = bucket.clone();
+                let store = store.clone();
                 async move {
-                    self.get(ctx, bucket, key.clone())
-                        .await
-                        .map(|value| (key, value))
+                    let res = store.get(&key).await;
+                    (key, res)
                 }
             })
-            .collect::<futures::stream::FuturesUnordered<_>>()
-            .try_collect()
+            .buffered(BATCH_CONCURRENCY)
+            .collect()
             .await;
 
-        match results {
-            Ok(values) => {
-                let values: Result<Vec<_>, _> = values
-                    .into_iter()
-                    .map(|(k, res)| match res {
-                        Ok(Some(v)) => Ok(Some((k, v))),
-                        Ok(None) => Ok(None),
-                        Err(err) => {
-                            error!("failed to parse key-value pairs: {err:?}");
-                            Err(keyvalue::store::Error::Other(err.to_string()))
-                        }
-                    })
-                    .collect();
-                Ok(values)
-            }
-            Err(err) => {
-                error!("failed to get many keys: {err:?}");
-                Ok(Err(keyvalue::store::Error::Other(err.to_string())))
+        let mut values = Vec::with_capacity(results.len());
+        let mut errors = Vec::new();
+        for (key, res) in results {
+            match res {
+                Ok(value) => values.push(value.map(|value| (key, value))),
+                Err(err) => errors.push((key, err.to_string())),
             }
         }
+        if errors.is_empty() {
+            Ok(Ok(values))
+        } else {
+            Ok(Err(batch_error("get", errors)))
+        }
     }
 
-    // Set multiple values in the key-value store
+    // Set multiple values in the key-value store, concurrently
     #[instrument(level = "debug", skip(self))]
     async fn set_many(
         &self,
@@ -572,26 +773,36 @@ impl keyvalue::batch::Handler<Option<Context>> for KvNatsProvider {
         bucket: String,
         items: Vec<(String, Bytes)>,
     ) -> anyhow::Result<Result<()>> {
-        let ctx = ctx.clone();
-        let bucket = bucket.clone();
+        propagate_trace_for_ctx!(ctx);
 
-        // Set the values for the keys
-        let results: Result<Vec<_>, _> = items
-            .into_iter()
+        let store = match self.get_kv_store(ctx, bucket).await {
+            Ok(store) => store,
+            Err(err) => return Ok(Err(err)),
+        };
+        let errors: Vec<_> = stream::iter(items)
             .map(|(key, value)| {
-                let ctx = ctx.clone();
-                let bucket = bucket.clone();
-                async move { self.set(ctx, bucket, key, value).await }
+                let store = store.clone();
+                async move {
+                    store
+                        .put(&key, value)
+                        .await
+                        .err()
+                        .map(|err| (key, err.to_string()))
+                }
             })
-            .collect::<futures::stream::FuturesUnordered<_>>()
-            .try_collect()
+            .buffer_unordered(BATCH_CONCURRENCY)
+            .filter_map(|err| async move { err })
+            .collect()
             .await;
 
-        // If all set operations were successful, return Ok(())
-        results.map(|_| Ok(()))
+        if errors.is_empty() {
+            Ok(Ok(()))
+        } else {
+            Ok(Err(batch_error("set", errors)))
+        }
     }
 
-    // Delete multiple keys from the key-value store
+    // Delete multiple keys from the key-value store, concurrently
     #[instrument(level = "debug", skip(self))]
     async fn delete_many(
         &self,
@@ -599,24 +810,47 @@ impl keyvalue::batch::Handler<Option<Context>> for KvNatsProvider {
         bucket: String,
         keys: Vec<String>,
     ) -> anyhow::Result<Result<()>> {
-        let ctx = ctx.clone();
-        let bucket = bucket.clone();
+        propagate_trace_for_ctx!(ctx);
 
-        // Delete the keys
-        let results: Result<Vec<_>, _> = keys
-            .into_iter()
+        let store = match self.get_kv_store(ctx, bucket).await {
+            Ok(store) => store,
+            Err(err) => return Ok(Err(err)),
+        };
+        let errors: Vec<_> = stream::iter(keys)
             .map(|key| {
-                let ctx = ctx.clone();
-                let bucket = bucket.clone();
-                async move { self.delete(ctx, bucket, key).await }
+                let store = store.clone();
+                async move {
+                    store
+                        .purge(&key)
+                        .await
+                        .err()
+                        .map(|err| (key, err.to_string()))
+                }
             })
-            .collect::<futures::stream::FuturesUnordered<_>>()
-            .try_collect()
+            .buffer_unordered(BATCH_CONCURRENCY)
+            .filter_map(|err| async move { err })
+            .collect()
             .await;
 
-        // If all delete operations were successful, return Ok(())
-        results.map(|_| Ok(()))
+        if errors.is_empty() {
+            Ok(Ok(()))
+        } else {
+            Ok(Err(batch_error("delete", errors)))
+        }
+    }
+}
+
+/// Aggregate the per-key errors of a batch operation into a single error
+fn batch_error(op: &str, errors: Vec<(String, String)>) -> keyvalue::store::Error {
+    for (key, err) in &errors {
+        error!(%key, "failed to {op} key: {err}");
     }
+    let errors = errors
+        .into_iter()
+        .map(|(key, err)| format!("{key}: {err}"))
+        .collect::<Vec<_>>()
+        .join("; ");
+    keyvalue::store::Error::Other(format!("failed to {op} keys: {errors}"))
 }
 
 /// Helper function for adding the TLS CA to the NATS connection options
@@ -650,4 +884,20 @@ mod test {
         let opts = add_tls_ca(tls_ca, opts);
         assert!(opts.is_ok())
     }
+
+    // Verify that per-key errors of batch operations are aggregated
+    #[test]
+    fn test_batch_error() {
+        let err = batch_error(
+            "get",
+            vec![
+                ("a".into(), "timed out".into()),
+                ("b".into(), "no responders".into()),
+            ],
+        );
+        assert!(matches!(
+            err,
+            keyvalue::store::Error::Other(msg) if msg == "failed to get keys: a: timed out; b: no responders"
+        ));
+    }
 }
@@ -0,0 +1,176 @@
+//! Watches on NATS Kv stores, notifying linked components of changes via `wrpc:keyvalue/watcher`
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{bail, Context as _, Result};
+use async_nats::jetstream::kv::{Operation, Store};
+use futures::StreamExt as _;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, instrument, warn};
+use wasmcloud_provider_sdk::get_connection;
+use wasmcloud_provider_sdk::provider::WrpcClient;
+
+use crate::bindings::wrpc::keyvalue::watcher;
+
+/// Link configuration key listing the keys to watch
+const CONFIG_NATS_WATCH: &str = "watch";
+
+/// Events on a key which linked components are notified of
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct WatchEvents {
+    pub on_set: bool,
+    pub on_delete: bool,
+}
+
+/// Parse the keys to watch from link configuration.
+///
+/// The `watch` value is a comma-separated list of `ON_SET:<key>` and `ON_DELETE:<key>` entries,
+/// where `<key>` may contain NATS wildcards (e.g. `ON_SET:users.*,ON_DELETE:users.>`)
+pub(crate) fn parse_watch_config(
+    config: &HashMap<String, String>,
+) -> Result<HashMap<String, WatchEvents>> {
+    let Some(watch) = config.get(CONFIG_NATS_WATCH) else {
+        bail!("missing required configuration item: {CONFIG_NATS_WATCH}");
+    };
+    let mut watches = HashMap::<String, WatchEvents>::new();
+    for entry in watch.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let Some((event, key)) = entry.split_once(':') else {
+            bail!("invalid watch entry `{entry}`, expected `ON_SET:<key>` or `ON_DELETE:<key>`");
+        };
+        let key = key.trim();
+        if key.is_empty() {
+            bail!("invalid watch entry `{entry}`, key must not be empty");
+        }
+        let events = watches.entry(key.to_string()).or_default();
+        match event.trim().to_ascii_uppercase().as_str() {
+            "ON_SET" => events.on_set = true,
+            "ON_DELETE" => events.on_delete = true,
+            _ => bail!("invalid watch event `{event}`, expected `ON_SET` or `ON_DELETE`"),
+        }
+    }
+    if watches.is_empty() {
+        bail!("no keys to watch specified in `{CONFIG_NATS_WATCH}`");
+    }
+    Ok(watches)
+}
+
+/// Watches held for a link, which are stopped when dropped
+#[derive(Debug, Default)]
+pub(crate) struct Watches(Vec<JoinHandle<()>>);
+
+impl Drop for Watches {
+    fn drop(&mut self) {
+        for handle in &self.0 {
+            handle.abort();
+        }
+    }
+}
+
+impl Watches {
+    /// Start watching `watches` in `store`, notifying `target_id` of changes.
+    ///
+    /// `bucket` is the bucket identifier passed to the component, which can be used to access
+    /// the store via `wrpc:keyvalue/store`
+    pub(crate) async fn start(
+        store: &Store,
+        bucket: &str,
+        target_id: &str,
+        watches: HashMap<String, WatchEvents>,
+    ) -> Result<Self> {
+        let wrpc = get_connection()
+            .get_wrpc_client(target_id)
+            .await
+            .context("failed to construct wRPC client")?;
+        let wrpc = Arc::new(wrpc);
+        let bucket = Arc::<str>::from(bucket);
+        let mut handles = Vec::with_capacity(watches.len());
+        for (key, events) in watches {
+            let mut watch = store
+                .watch(&key)
+                .await
+                .with_context(|| format!("failed to watch key `{key}`"))?;
+            debug!(key, ?events, target_id, "watching key");
+            let wrpc = Arc::clone(&wrpc);
+            let bucket = Arc::clone(&bucket);
+            handles.push(tokio::spawn(async move {
+                while let Some(entry) = watch.next().await {
+                    match entry {
+                        Ok(entry) => notify(&wrpc, &bucket, events, entry).await,
+                        Err(err) => {
+                            error!(?err, key, "failed to receive watch entry");
+                        }
+                    }
+                }
+                warn!(key, "watch stream ended");
+            }));
+        }
+        Ok(Self(handles))
+    }
+}
+
+/// Notify the component of a change to an entry, if it watches the kind of change
+#[instrument(level = "debug", skip(wrpc, entry), fields(key = %entry.key, revision = entry.revision))]
+async fn notify(
+    wrpc: &WrpcClient,
+    bucket: &str,
+    events: WatchEvents,
+    entry: async_nats::jetstream::kv::Entry,
+) {
+    let res = match entry.operation {
+        Operation::Put if events.on_set => {
+            watcher::on_set(wrpc, None, bucket, &entry.key, &entry.value).await
+        }
+        Operation::Delete | Operation::Purge if events.on_delete => {
+            watcher::on_delete(wrpc, None, bucket, &entry.key).await
+        }
+        _ => return,
+    };
+    if let Err(err) = res {
+        error!(?err, "failed to notify component of change");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_watches() {
+        let watches = parse_watch_config(&HashMap::from([(
+            "watch".into(),
+            "ON_SET:users.*, on_delete:users.*,ON_DELETE:sessions.>".into(),
+        )]))
+        .expect("failed to parse watch config");
+        assert_eq!(
+            watches,
+            HashMap::from([
+                (
+                    "users.*".into(),
+                    WatchEvents {
+                        on_set: true,
+                        on_delete: true
+                    }
+                ),
+                (
+                    "sessions.>".into(),
+                    WatchEvents {
+                        on_set: false,
+                        on_delete: true
+                    }
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_invalid_watches() {
+        assert!(parse_watch_config(&HashMap::new()).is_err());
+        for watch in ["", "ON_SET", "ON_SET:", "ON_GET:foo"] {
+            assert!(
+                parse_watch_config(&HashMap::from([("watch".into(), watch.into())])).is_err(),
+                "`{watch}` should be invalid"
+            );
+        }
+    }
+}
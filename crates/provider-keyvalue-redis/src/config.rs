@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Context as _, Result};
+
+/// Configuration key selecting how buckets without an explicit mapping are handled
+const CONFIG_BUCKET_MODE_KEY: &str = "BUCKET_MODE";
+
+/// Prefix of configuration keys mapping a bucket to a Redis namespace, for example
+/// `BUCKET_MAP_sessions=db:2` or `BUCKET_MAP_cache=prefix:cache:`
+const CONFIG_BUCKET_MAP_PREFIX: &str = "BUCKET_MAP_";
+
+/// How buckets without an explicit mapping are handled
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BucketMode {
+    /// Bucket names are ignored and all buckets share the same keyspace
+    #[default]
+    Ignore,
+    /// Keys are prefixed with the bucket name, followed by a `:`
+    Prefix,
+    /// Bucket names are parsed as Redis logical database indexes
+    Database,
+}
+
+/// Redis namespace a bucket is mapped to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BucketMapping {
+    /// Redis logical database index
+    Database(i64),
+    /// Prefix prepended to all keys in the bucket
+    Prefix(String),
+}
+
+impl BucketMapping {
+    fn parse(value: &str) -> Result<Self> {
+        match value.split_once(':') {
+            Some(("db", index)) => index
+                .parse()
+                .map(Self::Database)
+                .with_context(|| format!("invalid Redis database index `{index}`")),
+            Some(("prefix", prefix)) => Ok(Self::Prefix(prefix.to_string())),
+            _ => bail!(
+                "invalid bucket mapping `{value}`, expected `db:<index>` or `prefix:<prefix>`"
+            ),
+        }
+    }
+}
+
+/// Mapping of `wrpc:keyvalue` bucket names to Redis namespaces, parsed from link configuration
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BucketConfig {
+    mode: BucketMode,
+    buckets: HashMap<String, BucketMapping>,
+}
+
+impl BucketConfig {
+    /// Construct a [`BucketConfig`] from link configuration. Keys are matched case-insensitively
+    pub fn from_config(config: &HashMap<String, String>) -> Result<Self> {
+        let mut bucket_config = Self::default();
+        for (k, v) in config {
+            if k.eq_ignore_ascii_case(CONFIG_BUCKET_MODE_KEY) {
+                bucket_config.mode = match v.to_ascii_lowercase().as_str() {
+                    "ignore" => BucketMode::Ignore,
+                    "prefix" => BucketMode::Prefix,
+                    "database" | "db" => BucketMode::Database,
+                    _ => bail!(
+                        "invalid bucket mode `{v}`, expected `ignore`, `prefix` or `database`"
+                    ),
+                };
+            } else if let Some(bucket) = k
+                .get(..CONFIG_BUCKET_MAP_PREFIX.len())
+                .filter(|prefix| prefix.eq_ignore_ascii_case(CONFIG_BUCKET_MAP_PREFIX))
+                .map(|_| &k[CONFIG_BUCKET_MAP_PREFIX.len()..])
+            {
+                if bucket.is_empty() {
+                    bail!("bucket name in `{k}` must not be empty");
+                }
+                let mapping = BucketMapping::parse(v)
+                    .with_context(|| format!("invalid mapping for bucket `{bucket}`"))?;
+                bucket_config.buckets.insert(bucket.to_string(), mapping);
+            }
+        }
+        Ok(bucket_config)
+    }
+
+    /// Resolve the Redis namespace for a bucket, returning `None` if the bucket shares the
+    /// keyspace of the connection
+    pub fn resolve(&self, bucket: &str) -> Result<Option<BucketMapping>> {
+        if let Some(mapping) = self.buckets.get(bucket) {
+            return Ok(Some(mapping.clone()));
+        }
+        if bucket.is_empty() {
+            return Ok(None);
+        }
+        match self.mode {
+            BucketMode::Ignore => Ok(None),
+            BucketMode::Prefix => Ok(Some(BucketMapping::Prefix(format!("{bucket}:")))),
+            BucketMode::Database => bucket
+                .parse()
+                .map(|index| Some(BucketMapping::Database(index)))
+                .with_context(|| format!("bucket `{bucket}` is not a valid Redis database index")),
+        }
+    }
+
+    /// Returns whether non-empty bucket names are ignored
+    pub fn ignores_buckets(&self) -> bool {
+        self.mode == BucketMode::Ignore && self.buckets.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_bucket_config() {
+        let config = BucketConfig::from_config(&HashMap::from([
+            ("bucket_mode".to_string(), "prefix".to_string()),
+            ("BUCKET_MAP_sessions".to_string(), "db:2".to_string()),
+            ("Bucket_Map_cache".to_string(), "prefix:c:".to_string()),
+            ("URL".to_string(), "redis://127.0.0.1:6379".to_string()),
+        ]))
+        .expect("failed to parse bucket config");
+        assert_eq!(
+            config.resolve("sessions").unwrap(),
+            Some(BucketMapping::Database(2))
+        );
+        assert_eq!(
+            config.resolve("cache").unwrap(),
+            Some(BucketMapping::Prefix("c:".into()))
+        );
+        assert_eq!(
+            config.resolve("other").unwrap(),
+            Some(BucketMapping::Prefix("other:".into()))
+        );
+        assert_eq!(config.resolve("").unwrap(), None);
+    }
+
+    #[test]
+    fn database_bucket_mode() {
+        let config = BucketConfig::from_config(&HashMap::from([(
+            "BUCKET_MODE".to_string(),
+            "database".to_string(),
+        )]))
+        .expect("failed to parse bucket config");
+        assert_eq!(
+            config.resolve("3").unwrap(),
+            Some(BucketMapping::Database(3))
+        );
+        assert!(config.resolve("three").is_err());
+    }
+
+    #[test]
+    fn default_ignores_buckets() {
+        let config = BucketConfig::from_config(&HashMap::new()).unwrap();
+        assert!(config.ignores_buckets());
+        assert_eq!(config.resolve("anything").unwrap(), None);
+    }
+
+    #[test]
+    fn invalid_bucket_config() {
+        assert!(BucketConfig::from_config(&HashMap::from([(
+            "BUCKET_MODE".to_string(),
+            "sharded".to_string(),
+        )]))
+        .is_err());
+        assert!(BucketConfig::from_config(&HashMap::from([(
+            "BUCKET_MAP_foo".to_string(),
+            "db:zero".to_string(),
+        )]))
+        .is_err());
+    }
+}
@@ -0,0 +1,464 @@
+//! Connections to single Redis servers, Redis Cluster and Sentinel-managed deployments
+
+use core::future::Future;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{bail, Context as _};
+use redis::aio::{ConnectionLike, ConnectionManager};
+use redis::cluster::ClusterClientBuilder;
+use redis::cluster_async::ClusterConnection;
+use redis::sentinel::{Sentinel, SentinelNodeConnectionInfo};
+use redis::{
+    Cmd, ErrorKind, IntoConnectionInfo, Pipeline, RedisConnectionInfo, RedisError, RedisFuture,
+    TlsMode, Value,
+};
+use tokio::sync::{Mutex, RwLock};
+use tracing::{info, warn};
+use wasmcloud_provider_sdk::core::secrets::SecretValue;
+
+/// Configuration key listing the comma-separated URLs of Redis Cluster nodes
+const CONFIG_CLUSTER_URLS_KEY: &str = "CLUSTER_URLS";
+
+/// Configuration key of the number of times a Redis Cluster command is retried after a redirection
+/// or a transient failure
+const CONFIG_CLUSTER_RETRIES_KEY: &str = "CLUSTER_RETRIES";
+
+/// Configuration key of the minimum wait between retries of a Redis Cluster command, in milliseconds
+const CONFIG_CLUSTER_RETRY_MIN_WAIT_MS_KEY: &str = "CLUSTER_RETRY_MIN_WAIT_MS";
+
+/// Configuration key of the maximum wait between retries of a Redis Cluster command, in milliseconds
+const CONFIG_CLUSTER_RETRY_MAX_WAIT_MS_KEY: &str = "CLUSTER_RETRY_MAX_WAIT_MS";
+
+/// Configuration key listing the comma-separated URLs of Redis Sentinel instances
+const CONFIG_SENTINEL_URLS_KEY: &str = "SENTINEL_URLS";
+
+/// Configuration key of the name of the master monitored by Redis Sentinel
+const CONFIG_SENTINEL_MASTER_KEY: &str = "SENTINEL_MASTER";
+
+/// Lookup a value, which may be sensitive, in secrets or configuration. Keys are matched
+/// case-insensitively and secrets take precedence
+pub(crate) fn lookup_value(
+    config: &HashMap<String, String>,
+    secrets: &HashMap<String, SecretValue>,
+    key: &str,
+) -> Option<String> {
+    if let Some(value) = secrets
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .and_then(|(_, v)| v.as_string())
+    {
+        return Some(value.to_string());
+    }
+    config
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, v)| v.to_string())
+}
+
+/// Split a comma-separated list of URLs
+fn split_urls(urls: &str) -> Vec<String> {
+    urls.split(',')
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Retry settings of a Redis Cluster connection, unset settings use the defaults of the client.
+///
+/// Commands are retried when redirected by `MOVED` and `ASK` or rejected with `TRYAGAIN` or
+/// `CLUSTERDOWN`, which happens while slots are migrated during resharding. Retries wait for an
+/// exponentially growing, jittered time between the minimum and maximum wait, and `MOVED`
+/// redirections additionally refresh the slot mapping
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ClusterRetries {
+    pub retries: Option<u32>,
+    pub min_wait_ms: Option<u64>,
+    pub max_wait_ms: Option<u64>,
+}
+
+impl ClusterRetries {
+    /// Parse the retry settings from configuration
+    fn from_config(config: &HashMap<String, String>) -> anyhow::Result<Self> {
+        fn parse<T: core::str::FromStr>(
+            config: &HashMap<String, String>,
+            key: &str,
+        ) -> anyhow::Result<Option<T>>
+        where
+            T::Err: std::error::Error + Send + Sync + 'static,
+        {
+            lookup_value(config, &HashMap::default(), key)
+                .map(|v| v.trim().parse().with_context(|| format!("invalid `{key}`")))
+                .transpose()
+        }
+
+        let retries = Self {
+            retries: parse(config, CONFIG_CLUSTER_RETRIES_KEY)?,
+            min_wait_ms: parse(config, CONFIG_CLUSTER_RETRY_MIN_WAIT_MS_KEY)?,
+            max_wait_ms: parse(config, CONFIG_CLUSTER_RETRY_MAX_WAIT_MS_KEY)?,
+        };
+        if let (Some(min), Some(max)) = (retries.min_wait_ms, retries.max_wait_ms) {
+            if min > max {
+                bail!("`{CONFIG_CLUSTER_RETRY_MIN_WAIT_MS_KEY}` must not exceed `{CONFIG_CLUSTER_RETRY_MAX_WAIT_MS_KEY}`");
+            }
+        }
+        Ok(retries)
+    }
+}
+
+/// Connection to a Redis deployment
+#[derive(Clone)]
+pub enum Connection {
+    /// Connection to a single Redis server, reconnecting on connection failures
+    Single {
+        client: redis::Client,
+        conn: ConnectionManager,
+    },
+    /// Connection to a Redis Cluster, which follows `MOVED` and `ASK` redirections and refreshes
+    /// the slot mapping on topology changes
+    Cluster(ClusterConnection),
+    /// Connection to the master of a Redis deployment monitored by Sentinel
+    Sentinel(SentinelConnection),
+}
+
+impl Connection {
+    /// Connect to a single Redis server
+    pub async fn single(client: redis::Client) -> anyhow::Result<Self> {
+        let conn = client
+            .get_connection_manager()
+            .await
+            .context("failed to construct Redis connection manager")?;
+        Ok(Self::Single { client, conn })
+    }
+
+    /// Connect to a Redis Cluster using the given node URLs as seed nodes
+    pub async fn cluster(urls: Vec<String>, retries: ClusterRetries) -> anyhow::Result<Self> {
+        if urls.is_empty() {
+            bail!("at least one Redis Cluster node URL is required");
+        }
+        let mut builder = ClusterClientBuilder::new(urls);
+        if let Some(n) = retries.retries {
+            builder = builder.retries(n);
+        }
+        if let Some(ms) = retries.min_wait_ms {
+            builder = builder.min_retry_wait(ms);
+        }
+        if let Some(ms) = retries.max_wait_ms {
+            builder = builder.max_retry_wait(ms);
+        }
+        let conn = builder
+            .build()
+            .context("failed to construct Redis Cluster client")?
+            .get_async_connection()
+            .await
+            .context("failed to connect to Redis Cluster")?;
+        Ok(Self::Cluster(conn))
+    }
+
+    /// Connect to the master named `master` using the given Sentinel URLs
+    pub async fn sentinel(urls: Vec<String>, master: String) -> anyhow::Result<Self> {
+        SentinelConnection::connect(urls, master)
+            .await
+            .map(Self::Sentinel)
+    }
+
+    /// Connect to a Redis Cluster or Sentinel-managed deployment, if configured via
+    /// `CLUSTER_URLS` or `SENTINEL_URLS` and `SENTINEL_MASTER`. Returns `None` if neither is
+    /// configured
+    pub async fn from_config(
+        config: &HashMap<String, String>,
+        secrets: &HashMap<String, SecretValue>,
+    ) -> anyhow::Result<Option<Self>> {
+        if let Some(urls) = lookup_value(config, secrets, CONFIG_CLUSTER_URLS_KEY) {
+            let retries = ClusterRetries::from_config(config)?;
+            return Self::cluster(split_urls(&urls), retries).await.map(Some);
+        }
+        if let Some(urls) = lookup_value(config, secrets, CONFIG_SENTINEL_URLS_KEY) {
+            let master = lookup_value(config, secrets, CONFIG_SENTINEL_MASTER_KEY).with_context(
+                || {
+                    format!(
+                        "`{CONFIG_SENTINEL_MASTER_KEY}` is required when `{CONFIG_SENTINEL_URLS_KEY}` is set"
+                    )
+                },
+            )?;
+            return Self::sentinel(split_urls(&urls), master).await.map(Some);
+        }
+        Ok(None)
+    }
+
+    /// Connect to logical database `db` of the same deployment
+    pub async fn database(&self, db: i64) -> anyhow::Result<Self> {
+        match self {
+            Self::Single { client, .. } => {
+                let mut info = client.get_connection_info().clone();
+                info.redis.db = db;
+                let client =
+                    redis::Client::open(info).context("failed to construct Redis client")?;
+                Self::single(client).await
+            }
+            Self::Cluster(..) => bail!("logical databases are not supported by Redis Cluster"),
+            Self::Sentinel(conn) => conn.database(db).await.map(Self::Sentinel),
+        }
+    }
+
+    /// Returns whether this is a connection to a Redis Cluster
+    pub fn is_cluster(&self) -> bool {
+        matches!(self, Self::Cluster(..))
+    }
+}
+
+impl ConnectionLike for Connection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        match self {
+            Self::Single { conn, .. } => conn.req_packed_command(cmd),
+            Self::Cluster(conn) => conn.req_packed_command(cmd),
+            Self::Sentinel(conn) => conn.req_packed_command(cmd),
+        }
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        match self {
+            Self::Single { conn, .. } => conn.req_packed_commands(cmd, offset, count),
+            Self::Cluster(conn) => conn.req_packed_commands(cmd, offset, count),
+            Self::Sentinel(conn) => conn.req_packed_commands(cmd, offset, count),
+        }
+    }
+
+    fn get_db(&self) -> i64 {
+        match self {
+            Self::Single { conn, .. } => conn.get_db(),
+            Self::Cluster(conn) => conn.get_db(),
+            Self::Sentinel(conn) => conn.get_db(),
+        }
+    }
+}
+
+/// Connection to the master of a Redis deployment monitored by Sentinel.
+///
+/// The master is resolved again via Sentinel when the connection fails or the server rejects
+/// writes because it was demoted to a replica, so that the connection follows failovers.
+/// Commands rejected by a demoted master are retried on the new master.
+#[derive(Clone)]
+pub struct SentinelConnection {
+    sentinel: Arc<Mutex<Sentinel>>,
+    master: Arc<str>,
+    node: Arc<SentinelNodeConnectionInfo>,
+    /// Connection to the current master, along with the number of times it was replaced
+    conn: Arc<RwLock<(u64, ConnectionManager)>>,
+}
+
+impl SentinelConnection {
+    async fn connect(urls: Vec<String>, master: String) -> anyhow::Result<Self> {
+        let Some(first) = urls.first() else {
+            bail!("at least one Redis Sentinel URL is required");
+        };
+        // Masters are connected to using the TLS mode and credentials of the Sentinel URLs
+        let info = first
+            .as_str()
+            .into_connection_info()
+            .context("invalid Redis Sentinel URL")?;
+        let tls_mode = match info.addr {
+            redis::ConnectionAddr::TcpTls { insecure, .. } => Some(if insecure {
+                TlsMode::Insecure
+            } else {
+                TlsMode::Secure
+            }),
+            _ => None,
+        };
+        let node = SentinelNodeConnectionInfo {
+            tls_mode,
+            redis_connection_info: Some(RedisConnectionInfo {
+                db: 0,
+                ..info.redis
+            }),
+        };
+        let sentinel =
+            Mutex::new(Sentinel::build(urls).context("failed to construct Redis Sentinel client")?);
+        let conn = resolve_master(&sentinel, &master, &node).await?;
+        Ok(Self {
+            sentinel: Arc::new(sentinel),
+            master: master.into(),
+            node: Arc::new(node),
+            conn: Arc::new(RwLock::new((0, conn))),
+        })
+    }
+
+    /// Connect to logical database `db` of the same master
+    async fn database(&self, db: i64) -> anyhow::Result<Self> {
+        let mut node = SentinelNodeConnectionInfo::clone(&self.node);
+        node.redis_connection_info
+            .get_or_insert_with(RedisConnectionInfo::default)
+            .db = db;
+        let conn = resolve_master(&self.sentinel, &self.master, &node).await?;
+        Ok(Self {
+            sentinel: Arc::clone(&self.sentinel),
+            master: Arc::clone(&self.master),
+            node: Arc::new(node),
+            conn: Arc::new(RwLock::new((0, conn))),
+        })
+    }
+
+    /// Resolve the current master and replace the connection, unless it was already replaced
+    /// since generation `failed` of the connection was used
+    async fn reconnect(&self, failed: u64) -> Result<(u64, ConnectionManager), RedisError> {
+        let mut conn = self.conn.write().await;
+        if conn.0 != failed {
+            return Ok(conn.clone());
+        }
+        match resolve_master(&self.sentinel, &self.master, &self.node).await {
+            Ok(new) => {
+                info!(master = ?self.master, "reconnected to Redis master resolved via Sentinel");
+                *conn = (failed.wrapping_add(1), new);
+                Ok(conn.clone())
+            }
+            Err(err) => {
+                warn!(?err, master = ?self.master, "failed to resolve Redis master via Sentinel");
+                Err(RedisError::from((
+                    ErrorKind::IoError,
+                    "failed to resolve Redis master via Sentinel",
+                    format!("{err:#}"),
+                )))
+            }
+        }
+    }
+
+    /// Execute a request on the current master, reconnecting if the master is unreachable or was
+    /// demoted. Requests are only retried if they were rejected by a demoted master, since other
+    /// failures may have occurred after the request was executed
+    async fn request<T, F>(&self, f: impl Fn(ConnectionManager) -> F) -> Result<T, RedisError>
+    where
+        F: Future<Output = Result<T, RedisError>>,
+    {
+        let (generation, conn) = self.conn.read().await.clone();
+        match f(conn).await {
+            Err(err) if err.kind() == ErrorKind::ReadOnly => {
+                let (_, conn) = self.reconnect(generation).await?;
+                f(conn).await
+            }
+            Err(err)
+                if err.is_io_error()
+                    || err.is_connection_dropped()
+                    || err.is_connection_refusal() =>
+            {
+                if let Err(err) = self.reconnect(generation).await {
+                    warn!(?err, "failed to reconnect to Redis master");
+                }
+                Err(err)
+            }
+            res => res,
+        }
+    }
+}
+
+impl ConnectionLike for SentinelConnection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        Box::pin(self.request(move |mut conn| async move { conn.req_packed_command(cmd).await }))
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        Box::pin(self.request(move |mut conn| async move {
+            conn.req_packed_commands(cmd, offset, count).await
+        }))
+    }
+
+    fn get_db(&self) -> i64 {
+        self.node
+            .redis_connection_info
+            .as_ref()
+            .map_or(0, |info| info.db)
+    }
+}
+
+/// Resolve the address of `master` via Sentinel and connect to it
+async fn resolve_master(
+    sentinel: &Mutex<Sentinel>,
+    master: &str,
+    node: &SentinelNodeConnectionInfo,
+) -> anyhow::Result<ConnectionManager> {
+    let client = sentinel
+        .lock()
+        .await
+        .async_master_for(master, Some(node))
+        .await
+        .with_context(|| format!("failed to resolve Redis master `{master}` via Sentinel"))?;
+    client
+        .get_connection_manager()
+        .await
+        .with_context(|| format!("failed to connect to Redis master `{master}`"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lookup_values() {
+        let config = HashMap::from([
+            (
+                "cluster_urls".to_string(),
+                "redis://a:6379, redis://b:6379,".to_string(),
+            ),
+            ("SENTINEL_MASTER".to_string(), "mymaster".to_string()),
+        ]);
+        let secrets = HashMap::from([(
+            "Sentinel_Master".to_string(),
+            SecretValue::String("secret-master".to_string()),
+        )]);
+        assert_eq!(
+            lookup_value(&config, &secrets, CONFIG_CLUSTER_URLS_KEY).map(|urls| split_urls(&urls)),
+            Some(vec![
+                "redis://a:6379".to_string(),
+                "redis://b:6379".to_string()
+            ])
+        );
+        assert_eq!(
+            lookup_value(&config, &secrets, CONFIG_SENTINEL_MASTER_KEY).as_deref(),
+            Some("secret-master")
+        );
+        assert_eq!(
+            lookup_value(&config, &secrets, CONFIG_SENTINEL_URLS_KEY),
+            None
+        );
+    }
+
+    #[test]
+    fn cluster_retries() -> anyhow::Result<()> {
+        assert_eq!(
+            ClusterRetries::from_config(&HashMap::new())?,
+            ClusterRetries::default()
+        );
+        let config = HashMap::from([
+            ("cluster_retries".to_string(), "8".to_string()),
+            ("CLUSTER_RETRY_MIN_WAIT_MS".to_string(), " 10".to_string()),
+            ("CLUSTER_RETRY_MAX_WAIT_MS".to_string(), "500".to_string()),
+        ]);
+        assert_eq!(
+            ClusterRetries::from_config(&config)?,
+            ClusterRetries {
+                retries: Some(8),
+                min_wait_ms: Some(10),
+                max_wait_ms: Some(500),
+            }
+        );
+        let config = HashMap::from([
+            ("CLUSTER_RETRY_MIN_WAIT_MS".to_string(), "1000".to_string()),
+            ("CLUSTER_RETRY_MAX_WAIT_MS".to_string(), "500".to_string()),
+        ]);
+        assert!(ClusterRetries::from_config(&config).is_err());
+        let config = HashMap::from([("CLUSTER_RETRIES".to_string(), "-1".to_string())]);
+        assert!(ClusterRetries::from_config(&config).is_err());
+        Ok(())
+    }
+}
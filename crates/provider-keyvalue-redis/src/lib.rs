@@ -8,31 +8,49 @@
 //! on the [exec](#exec) function for more information.
 
 use core::num::NonZeroU64;
+use core::time::Duration;
 
 use std::collections::HashMap;
 use std::sync::Arc;
 
 use anyhow::{bail, Context as _};
 use bytes::Bytes;
-use redis::aio::ConnectionManager;
 use redis::{Cmd, FromRedisValue};
 use tokio::sync::RwLock;
+use tokio::time::{timeout, Instant};
 use tracing::{debug, error, info, instrument, warn};
 use wasmcloud_provider_sdk::{
     get_connection, load_host_data, propagate_trace_for_ctx, run_provider, Context, LinkConfig,
-    LinkDeleteInfo, Provider,
+    LinkDeleteInfo, LinkHealthCheck, LinkHealthStatus, LinkValidationError, Provider,
 };
 use wasmcloud_provider_sdk::{initialize_observability, serve_provider_exports};
 
+mod config;
+pub use config::{BucketConfig, BucketMapping, BucketMode};
+
+mod connection;
+pub use connection::{ClusterRetries, Connection, SentinelConnection};
+
+mod pipeline;
+pub use pipeline::{PipelineConfig, Pipeliner};
+
+mod stats;
+pub use stats::{
+    ConnectionMode, ConnectionStats, ConnectionStatsSnapshot, DEFAULT_SATURATION_THRESHOLD,
+};
+
 mod bindings {
     wit_bindgen_wrpc::generate!({
         with: {
             "wrpc:keyvalue/atomics@0.2.0-draft": generate,
             "wrpc:keyvalue/batch@0.2.0-draft": generate,
             "wrpc:keyvalue/store@0.2.0-draft": generate,
+            "wasmcloud:keyvalue/ttl@0.1.0-draft": generate,
+            "wasmcloud:keyvalue/scan@0.1.0-draft": generate,
         }
     });
 }
+use bindings::exports::wasmcloud::keyvalue::{scan, ttl};
 use bindings::exports::wrpc::keyvalue;
 
 /// Default URL to use to connect to Redis
@@ -41,21 +59,122 @@ const DEFAULT_CONNECT_URL: &str = "redis://127.0.0.1:6379/";
 /// Configuration key that will be used to search for Redis config
 const CONFIG_REDIS_URL_KEY: &str = "URL";
 
+/// Lua script returning the type and remaining time to live in milliseconds of every key in
+/// `KEYS`, as pairs in the order of the keys
+const KEY_METADATA_SCRIPT: &str = "local r = {} for i, k in ipairs(KEYS) do r[i] = { redis.call('TYPE', k).ok, redis.call('PTTL', k) } end return r";
+
+/// Time after which a link health check `PING` is considered slow, marking the link degraded
+const LINK_HEALTH_SLOW_THRESHOLD: Duration = Duration::from_millis(500);
+
+/// Time after which a link health check `PING` is abandoned, marking the link unhealthy
+const LINK_HEALTH_TIMEOUT: Duration = Duration::from_secs(5);
+
 type Result<T, E = keyvalue::store::Error> = core::result::Result<T, E>;
 
 #[derive(Clone)]
 pub enum DefaultConnection {
     ClientConfig(HashMap<String, String>),
-    Conn(ConnectionManager),
+    Conn(RedisConnection),
+}
+
+/// Connection to a Redis namespace, along with the pipeliner coalescing commands issued on it, if
+/// pipelining is enabled, and the statistics of the commands issued on it
+#[derive(Clone)]
+struct NamespaceConnection {
+    conn: Connection,
+    pipeliner: Option<Pipeliner>,
+    stats: Arc<ConnectionStats>,
+}
+
+impl NamespaceConnection {
+    fn new(conn: Connection, pipeline: Option<PipelineConfig>) -> Self {
+        let pipeliner = pipeline.and_then(|config| Pipeliner::new(conn.clone(), config));
+        Self {
+            conn,
+            pipeliner,
+            stats: Arc::default(),
+        }
+    }
+}
+
+/// Redis connection of a link, along with the mapping of buckets to Redis namespaces
+#[derive(Clone)]
+pub struct RedisConnection {
+    conn: NamespaceConnection,
+    buckets: Arc<BucketConfig>,
+    pipeline: Option<PipelineConfig>,
+    /// Connections to the logical databases buckets are mapped to, established on first use
+    databases: Arc<RwLock<HashMap<i64, NamespaceConnection>>>,
+}
+
+impl RedisConnection {
+    fn new(conn: Connection, buckets: BucketConfig, pipeline: Option<PipelineConfig>) -> Self {
+        Self {
+            conn: NamespaceConnection::new(conn, pipeline),
+            buckets: Arc::new(buckets),
+            pipeline,
+            databases: Arc::default(),
+        }
+    }
+
+    /// Use the same connection with a different bucket mapping
+    fn with_buckets(&self, buckets: BucketConfig) -> Self {
+        Self {
+            conn: self.conn.clone(),
+            buckets: Arc::new(buckets),
+            pipeline: self.pipeline,
+            databases: Arc::default(),
+        }
+    }
+
+    /// Resolve the connection and key prefix to use for operations on `bucket`
+    async fn bucket(&self, bucket: &str) -> Result<(NamespaceConnection, String)> {
+        match self.buckets.resolve(bucket) {
+            Ok(None) => {
+                check_bucket_name(bucket);
+                Ok((self.conn.clone(), String::new()))
+            }
+            Ok(Some(BucketMapping::Prefix(prefix))) => Ok((self.conn.clone(), prefix)),
+            Ok(Some(BucketMapping::Database(db))) => {
+                let conn = self.database(db).await.map_err(|err| {
+                    error!(?err, db, "failed to connect to Redis database");
+                    keyvalue::store::Error::Other(format!("{err:#}"))
+                })?;
+                Ok((conn, String::new()))
+            }
+            Err(err) => {
+                error!(?err, bucket, "failed to resolve bucket");
+                Err(keyvalue::store::Error::NoSuchStore)
+            }
+        }
+    }
+
+    /// Get the connection to logical database `db`, establishing it if necessary
+    async fn database(&self, db: i64) -> anyhow::Result<NamespaceConnection> {
+        if let Some(conn) = self.databases.read().await.get(&db) {
+            return Ok(conn.clone());
+        }
+        let mut databases = self.databases.write().await;
+        if let Some(conn) = databases.get(&db) {
+            return Ok(conn.clone());
+        }
+        let conn = NamespaceConnection::new(self.conn.conn.database(db).await?, self.pipeline);
+        databases.insert(db, conn.clone());
+        Ok(conn)
+    }
 }
 
 /// Redis `wrpc:keyvalue` provider implementation.
 #[derive(Clone)]
 pub struct KvRedisProvider {
     // store redis connections per source ID & link name
-    sources: Arc<RwLock<HashMap<(String, String), ConnectionManager>>>,
+    sources: Arc<RwLock<HashMap<(String, String), RedisConnection>>>,
     // default connection, which may be uninitialized
     default_connection: Arc<RwLock<DefaultConnection>>,
+    // configuration of the default connection, used to connect links in per-link connection mode
+    default_config: Arc<HashMap<String, String>>,
+    // number of commands queued at once on a shared connection, at which it is considered saturated
+    saturation_threshold: u64,
 }
 
 pub async fn run() -> anyhow::Result<()> {
@@ -90,16 +209,37 @@ impl KvRedisProvider {
 
     #[must_use]
     pub fn new(initial_config: HashMap<String, String>) -> Self {
+        let saturation_threshold = stats::saturation_threshold_from_config(&initial_config)
+            .unwrap_or_else(|err| {
+                warn!(
+                    ?err,
+                    DEFAULT_SATURATION_THRESHOLD, "invalid saturation threshold, using default"
+                );
+                DEFAULT_SATURATION_THRESHOLD
+            });
         KvRedisProvider {
             sources: Arc::default(),
             default_connection: Arc::new(RwLock::new(DefaultConnection::ClientConfig(
-                initial_config,
+                initial_config.clone(),
             ))),
+            default_config: Arc::new(initial_config),
+            saturation_threshold,
         }
     }
 
+    /// Returns a snapshot of the statistics of the connection of each link, keyed by source ID and
+    /// link name. Links sharing a connection report the same statistics
+    pub async fn connection_stats(&self) -> HashMap<(String, String), ConnectionStatsSnapshot> {
+        self.sources
+            .read()
+            .await
+            .iter()
+            .map(|(key, conn)| (key.clone(), conn.conn.stats.snapshot()))
+            .collect()
+    }
+
     #[instrument(level = "trace", skip_all)]
-    async fn get_default_connection(&self) -> anyhow::Result<ConnectionManager> {
+    async fn get_default_connection(&self) -> anyhow::Result<RedisConnection> {
         // NOTE: The read lock is only held for the duration of the `if let` block so we can acquire
         // the write lock to update the default connection if needed.
         if let DefaultConnection::Conn(conn) = &*self.default_connection.read().await {
@@ -110,11 +250,7 @@ impl KvRedisProvider {
         match &mut *default_conn {
             DefaultConnection::Conn(conn) => Ok(conn.clone()),
             DefaultConnection::ClientConfig(cfg) => {
-                let conn = redis::Client::open(retrieve_default_url(cfg))
-                    .context("failed to construct default Redis client")?
-                    .get_connection_manager()
-                    .await
-                    .context("failed to construct Redis connection manager")?;
+                let conn = connect_default(cfg).await?;
                 *default_conn = DefaultConnection::Conn(conn.clone());
                 Ok(conn)
             }
@@ -122,7 +258,7 @@ impl KvRedisProvider {
     }
 
     #[instrument(level = "debug", skip(self))]
-    async fn invocation_conn(&self, context: Option<Context>) -> anyhow::Result<ConnectionManager> {
+    async fn invocation_conn(&self, context: Option<Context>) -> anyhow::Result<RedisConnection> {
         let ctx = context.context("unexpectedly missing context")?;
 
         let Some(ref source_id) = ctx.component else {
@@ -141,24 +277,98 @@ impl KvRedisProvider {
         Ok(conn.clone())
     }
 
-    /// Execute Redis async command
+    /// Resolve the connection and key prefix for an operation on `bucket`
+    async fn bucket_conn(
+        &self,
+        context: Option<Context>,
+        bucket: &str,
+    ) -> Result<(NamespaceConnection, String)> {
+        self.invocation_conn(context)
+            .await
+            .map_err(|err| keyvalue::store::Error::Other(format!("{err:#}")))?
+            .bucket(bucket)
+            .await
+    }
+
+    /// Execute Redis async command on the namespace `bucket` is mapped to. The command is
+    /// constructed by `cmd`, which is passed the key prefix of the bucket
     async fn exec_cmd<T: FromRedisValue>(
         &self,
         context: Option<Context>,
-        cmd: &mut Cmd,
+        bucket: &str,
+        cmd: impl FnOnce(&str) -> Cmd,
     ) -> Result<T, keyvalue::store::Error> {
-        let mut conn = self
-            .invocation_conn(context)
+        let (mut conn, prefix) = self.bucket_conn(context, bucket).await?;
+        query(&mut conn, cmd(&prefix)).await
+    }
+
+    /// Scan the keys of `bucket` matching the glob-style `pattern` starting at `cursor`,
+    /// examining about `count` keys. Returns the connection and key prefix of the bucket along
+    /// with the keys found, without the prefix, and the cursor to continue the scan with
+    async fn scan_keys(
+        &self,
+        context: Option<Context>,
+        bucket: &str,
+        cursor: Option<u64>,
+        pattern: Option<&str>,
+        count: Option<u32>,
+    ) -> Result<(NamespaceConnection, String, Vec<String>, Option<u64>)> {
+        let (mut conn, prefix) = self.bucket_conn(context, bucket).await?;
+        // `SCAN` is executed on a single node of a cluster, so keys stored on other nodes would
+        // be silently omitted
+        if conn.conn.is_cluster() {
+            return Err(keyvalue::store::Error::Other(
+                "listing keys is not supported on Redis Cluster".into(),
+            ));
+        }
+        let mut cmd = redis::cmd("SCAN");
+        cmd.cursor_arg(cursor.unwrap_or_default());
+        if !prefix.is_empty() || pattern.is_some() {
+            cmd.arg("MATCH").arg(format!(
+                "{}{}",
+                escape_pattern(&prefix),
+                pattern.unwrap_or("*")
+            ));
+        }
+        if let Some(count) = count {
+            cmd.arg("COUNT").arg(count);
+        }
+        let (cursor, keys) = query::<(u64, Vec<String>)>(&mut conn, cmd).await?;
+        let keys = keys
+            .into_iter()
+            .filter_map(|key| key.strip_prefix(&prefix).map(String::from))
+            .collect();
+        Ok((conn, prefix, keys, NonZeroU64::new(cursor).map(Into::into)))
+    }
+}
+
+/// Execute Redis async command on a connection, as part of a pipeline if pipelining is enabled
+async fn query<T: FromRedisValue>(
+    NamespaceConnection {
+        conn,
+        pipeliner,
+        stats,
+    }: &mut NamespaceConnection,
+    cmd: Cmd,
+) -> Result<T, keyvalue::store::Error> {
+    let queued = stats.start();
+    let res = if let Some(pipeliner) = pipeliner {
+        pipeliner
+            .query(cmd)
             .await
-            .map_err(|err| keyvalue::store::Error::Other(format!("{err:#}")))?;
-        match cmd.query_async(&mut conn).await {
-            Ok(v) => Ok(v),
-            Err(e) => {
-                error!("failed to execute Redis command: {e}");
-                Err(keyvalue::store::Error::Other(format!(
-                    "failed to execute Redis command: {e}"
-                )))
-            }
+            .and_then(T::from_owned_redis_value)
+    } else {
+        cmd.query_async(conn).await
+    };
+    drop(queued);
+    match res {
+        Ok(v) => Ok(v),
+        Err(e) => {
+            stats.record_error(&e);
+            error!("failed to execute Redis command: {e}");
+            Err(keyvalue::store::Error::Other(format!(
+                "failed to execute Redis command: {e}"
+            )))
         }
     }
 }
@@ -172,8 +382,11 @@ impl keyvalue::store::Handler<Option<Context>> for KvRedisProvider {
         key: String,
     ) -> anyhow::Result<Result<()>> {
         propagate_trace_for_ctx!(context);
-        check_bucket_name(&bucket);
-        Ok(self.exec_cmd(context, &mut Cmd::del(key)).await)
+        Ok(self
+            .exec_cmd(context, &bucket, |prefix| {
+                Cmd::del(format!("{prefix}{key}"))
+            })
+            .await)
     }
 
     #[instrument(level = "debug", skip(self))]
@@ -184,8 +397,11 @@ impl keyvalue::store::Handler<Option<Context>> for KvRedisProvider {
         key: String,
     ) -> anyhow::Result<Result<bool>> {
         propagate_trace_for_ctx!(context);
-        check_bucket_name(&bucket);
-        Ok(self.exec_cmd(context, &mut Cmd::exists(key)).await)
+        Ok(self
+            .exec_cmd(context, &bucket, |prefix| {
+                Cmd::exists(format!("{prefix}{key}"))
+            })
+            .await)
     }
 
     #[instrument(level = "debug", skip(self))]
@@ -196,9 +412,10 @@ impl keyvalue::store::Handler<Option<Context>> for KvRedisProvider {
         key: String,
     ) -> anyhow::Result<Result<Option<Bytes>>> {
         propagate_trace_for_ctx!(context);
-        check_bucket_name(&bucket);
         match self
-            .exec_cmd::<redis::Value>(context, &mut Cmd::get(key))
+            .exec_cmd::<redis::Value>(context, &bucket, |prefix| {
+                Cmd::get(format!("{prefix}{key}"))
+            })
             .await
         {
             Ok(redis::Value::Nil) => Ok(Ok(None)),
@@ -219,9 +436,10 @@ impl keyvalue::store::Handler<Option<Context>> for KvRedisProvider {
         value: Bytes,
     ) -> anyhow::Result<Result<()>> {
         propagate_trace_for_ctx!(context);
-        check_bucket_name(&bucket);
         Ok(self
-            .exec_cmd(context, &mut Cmd::set(key, value.to_vec()))
+            .exec_cmd(context, &bucket, |prefix| {
+                Cmd::set(format!("{prefix}{key}"), value.to_vec())
+            })
             .await)
     }
 
@@ -233,20 +451,10 @@ impl keyvalue::store::Handler<Option<Context>> for KvRedisProvider {
         cursor: Option<u64>,
     ) -> anyhow::Result<Result<keyvalue::store::KeyResponse>> {
         propagate_trace_for_ctx!(context);
-        check_bucket_name(&bucket);
-        match self
-            .exec_cmd(
-                context,
-                redis::cmd("SCAN").cursor_arg(cursor.unwrap_or_default()),
-            )
+        Ok(self
+            .scan_keys(context, &bucket, cursor, None, None)
             .await
-        {
-            Ok((cursor, keys)) => Ok(Ok(keyvalue::store::KeyResponse {
-                keys,
-                cursor: NonZeroU64::new(cursor).map(Into::into),
-            })),
-            Err(err) => Ok(Err(err)),
-        }
+            .map(|(_, _, keys, cursor)| keyvalue::store::KeyResponse { keys, cursor }))
     }
 }
 
@@ -261,13 +469,158 @@ impl keyvalue::atomics::Handler<Option<Context>> for KvRedisProvider {
         delta: u64,
     ) -> anyhow::Result<Result<u64, keyvalue::store::Error>> {
         propagate_trace_for_ctx!(context);
-        check_bucket_name(&bucket);
         Ok(self
-            .exec_cmd::<u64>(context, &mut Cmd::incr(key, delta))
+            .exec_cmd::<u64>(context, &bucket, |prefix| {
+                Cmd::incr(format!("{prefix}{key}"), delta)
+            })
             .await)
     }
 }
 
+impl ttl::Handler<Option<Context>> for KvRedisProvider {
+    /// Sets a value, expiring the key after `milliseconds`
+    #[instrument(level = "debug", skip(self, value))]
+    async fn set_with_ttl(
+        &self,
+        context: Option<Context>,
+        bucket: String,
+        key: String,
+        value: Bytes,
+        milliseconds: u64,
+    ) -> anyhow::Result<Result<(), ttl::Error>> {
+        propagate_trace_for_ctx!(context);
+        let milliseconds = match ttl_millis(milliseconds) {
+            Ok(milliseconds) => milliseconds,
+            Err(err) => return Ok(Err(err)),
+        };
+        Ok(self
+            .exec_cmd(context, &bucket, |prefix| {
+                set_with_ttl_cmd(format!("{prefix}{key}"), &value, milliseconds)
+            })
+            .await
+            .map_err(Into::into))
+    }
+
+    /// Sets the expiration of an existing key, returning `false` if the key does not exist
+    #[instrument(level = "debug", skip(self))]
+    async fn expire(
+        &self,
+        context: Option<Context>,
+        bucket: String,
+        key: String,
+        milliseconds: u64,
+    ) -> anyhow::Result<Result<bool, ttl::Error>> {
+        propagate_trace_for_ctx!(context);
+        let milliseconds = match ttl_millis(milliseconds) {
+            Ok(milliseconds) => milliseconds,
+            Err(err) => return Ok(Err(err)),
+        };
+        Ok(self
+            .exec_cmd(context, &bucket, |prefix| {
+                expire_cmd(format!("{prefix}{key}"), milliseconds)
+            })
+            .await
+            .map_err(Into::into))
+    }
+
+    /// Gets the remaining time to live of a key in milliseconds, if the key exists and expires
+    #[instrument(level = "debug", skip(self))]
+    async fn ttl(
+        &self,
+        context: Option<Context>,
+        bucket: String,
+        key: String,
+    ) -> anyhow::Result<Result<Option<u64>, ttl::Error>> {
+        propagate_trace_for_ctx!(context);
+        Ok(self
+            .exec_cmd::<i64>(context, &bucket, |prefix| {
+                Cmd::pttl(format!("{prefix}{key}"))
+            })
+            .await
+            .map(remaining_ttl)
+            .map_err(Into::into))
+    }
+}
+
+impl scan::Handler<Option<Context>> for KvRedisProvider {
+    /// Lists the keys matching a pattern, along with their type and TTL if requested
+    #[instrument(level = "debug", skip(self))]
+    async fn scan(
+        &self,
+        context: Option<Context>,
+        bucket: String,
+        options: scan::ScanOptions,
+        cursor: Option<u64>,
+    ) -> anyhow::Result<Result<scan::ScanResponse, scan::Error>> {
+        propagate_trace_for_ctx!(context);
+        let (mut conn, prefix, keys, cursor) = match self
+            .scan_keys(
+                context,
+                &bucket,
+                cursor,
+                options.pattern.as_deref(),
+                options.count,
+            )
+            .await
+        {
+            Ok(v) => v,
+            Err(err) => return Ok(Err(err.into())),
+        };
+        if !options.metadata || keys.is_empty() {
+            return Ok(Ok(scan::ScanResponse {
+                keys: keys
+                    .into_iter()
+                    .map(|key| scan::KeyInfo {
+                        key,
+                        metadata: None,
+                    })
+                    .collect(),
+                cursor,
+            }));
+        }
+        // Look up the metadata of all keys in a single round trip
+        let mut cmd = redis::cmd("EVAL");
+        cmd.arg(KEY_METADATA_SCRIPT)
+            .arg(keys.len())
+            .arg(prefixed_keys(&prefix, &keys));
+        let metadata = match query::<Vec<(String, i64)>>(&mut conn, cmd).await {
+            Ok(metadata) => metadata,
+            Err(err) => return Ok(Err(err.into())),
+        };
+        Ok(Ok(scan::ScanResponse {
+            keys: keys
+                .into_iter()
+                .zip(metadata)
+                .map(|(key, (value_type, ttl))| scan::KeyInfo {
+                    key,
+                    metadata: key_metadata(value_type, ttl),
+                })
+                .collect(),
+            cursor,
+        }))
+    }
+}
+
+impl From<keyvalue::store::Error> for scan::Error {
+    fn from(err: keyvalue::store::Error) -> Self {
+        match err {
+            keyvalue::store::Error::NoSuchStore => Self::NoSuchStore,
+            keyvalue::store::Error::AccessDenied => Self::AccessDenied,
+            keyvalue::store::Error::Other(err) => Self::Other(err),
+        }
+    }
+}
+
+impl From<keyvalue::store::Error> for ttl::Error {
+    fn from(err: keyvalue::store::Error) -> Self {
+        match err {
+            keyvalue::store::Error::NoSuchStore => Self::NoSuchStore,
+            keyvalue::store::Error::AccessDenied => Self::AccessDenied,
+            keyvalue::store::Error::Other(err) => Self::Other(err),
+        }
+    }
+}
+
 impl keyvalue::batch::Handler<Option<Context>> for KvRedisProvider {
     async fn get_many(
         &self,
@@ -275,9 +628,10 @@ impl keyvalue::batch::Handler<Option<Context>> for KvRedisProvider {
         bucket: String,
         keys: Vec<String>,
     ) -> anyhow::Result<Result<Vec<Option<(String, Bytes)>>>> {
-        check_bucket_name(&bucket);
         let data = match self
-            .exec_cmd::<Vec<Option<Bytes>>>(ctx, &mut Cmd::mget(&keys))
+            .exec_cmd::<Vec<Option<Bytes>>>(ctx, &bucket, |prefix| {
+                Cmd::mget(prefixed_keys(prefix, &keys))
+            })
             .await
         {
             Ok(v) => v
@@ -298,12 +652,15 @@ impl keyvalue::batch::Handler<Option<Context>> for KvRedisProvider {
         bucket: String,
         items: Vec<(String, Bytes)>,
     ) -> anyhow::Result<Result<()>> {
-        check_bucket_name(&bucket);
-        let items = items
-            .into_iter()
-            .map(|(name, buf)| (name, buf.to_vec()))
-            .collect::<Vec<_>>();
-        Ok(self.exec_cmd(ctx, &mut Cmd::mset(&items)).await)
+        Ok(self
+            .exec_cmd(ctx, &bucket, |prefix| {
+                let items = items
+                    .into_iter()
+                    .map(|(name, buf)| (format!("{prefix}{name}"), buf.to_vec()))
+                    .collect::<Vec<_>>();
+                Cmd::mset(&items)
+            })
+            .await)
     }
 
     async fn delete_many(
@@ -312,8 +669,11 @@ impl keyvalue::batch::Handler<Option<Context>> for KvRedisProvider {
         bucket: String,
         keys: Vec<String>,
     ) -> anyhow::Result<Result<()>> {
-        check_bucket_name(&bucket);
-        Ok(self.exec_cmd(ctx, &mut Cmd::del(keys)).await)
+        Ok(self
+            .exec_cmd(ctx, &bucket, |prefix| {
+                Cmd::del(prefixed_keys(prefix, &keys))
+            })
+            .await)
     }
 }
 
@@ -333,6 +693,14 @@ impl Provider for KvRedisProvider {
             ..
         }: LinkConfig<'_>,
     ) -> anyhow::Result<()> {
+        let buckets = BucketConfig::from_config(config).context("invalid bucket config")?;
+        let pipeline = PipelineConfig::from_config(config).context("invalid pipeline config")?;
+        let mode = match ConnectionMode::from_config(config).context("invalid connection mode")? {
+            Some(mode) => mode,
+            None => ConnectionMode::from_config(&self.default_config)
+                .context("invalid default connection mode")?
+                .unwrap_or_default(),
+        };
         let url = secrets
             .keys()
             .find(|k| k.eq_ignore_ascii_case(CONFIG_REDIS_URL_KEY))
@@ -345,12 +713,20 @@ impl Provider for KvRedisProvider {
                     .and_then(|url_key| config.get(url_key))
             });
 
-        let conn = if let Some(url) = url {
+        let topology = Connection::from_config(config, secrets)
+            .await
+            .with_context(|| {
+                format!("failed to connect to Redis deployment for source [{source_id}]")
+            })?;
+        let conn = if let Some(conn) = topology {
+            info!("established link");
+            RedisConnection::new(conn, buckets, pipeline)
+        } else if let Some(url) = url {
             match redis::Client::open(url.to_string()) {
-                Ok(client) => match client.get_connection_manager().await {
+                Ok(client) => match Connection::single(client).await {
                     Ok(conn) => {
                         info!(url, "established link");
-                        conn
+                        RedisConnection::new(conn, buckets, pipeline)
                     }
                     Err(err) => {
                         warn!(
@@ -369,11 +745,26 @@ impl Provider for KvRedisProvider {
                     bail!("failed to create redis client");
                 }
             }
+        } else if mode == ConnectionMode::PerLink {
+            let conn = connect_default(&self.default_config)
+                .await
+                .context("failed to establish dedicated default connection for link")?;
+            info!("established link with dedicated default connection");
+            if buckets == BucketConfig::default() {
+                conn
+            } else {
+                conn.with_buckets(buckets)
+            }
         } else {
-            self.get_default_connection().await.map_err(|err| {
+            let conn = self.get_default_connection().await.map_err(|err| {
                 error!(error = ?err, "failed to get default connection for link");
                 err
-            })?
+            })?;
+            if buckets == BucketConfig::default() {
+                conn
+            } else {
+                conn.with_buckets(buckets)
+            }
         };
         let mut sources = self.sources.write().await;
         sources.insert((source_id.to_string(), link_name.to_string()), conn);
@@ -381,6 +772,86 @@ impl Provider for KvRedisProvider {
         Ok(())
     }
 
+    /// Check that the bucket and pipeline configuration of a link are valid, and that the Redis
+    /// deployment configured for the link responds. Links without a URL use the default
+    /// connection of the provider, which is not checked.
+    #[instrument(level = "debug", skip(self, config, secrets))]
+    async fn validate_link_config_as_target(
+        &self,
+        LinkConfig {
+            source_id,
+            config,
+            secrets,
+            ..
+        }: LinkConfig<'_>,
+    ) -> anyhow::Result<Vec<LinkValidationError>> {
+        let mut errors = Vec::new();
+        if let Err(err) = BucketConfig::from_config(config) {
+            errors.push(LinkValidationError {
+                field: None,
+                message: format!("invalid bucket config: {err:#}"),
+            });
+        }
+        if let Err(err) = PipelineConfig::from_config(config) {
+            errors.push(LinkValidationError {
+                field: None,
+                message: format!("invalid pipeline config: {err:#}"),
+            });
+        }
+        if let Err(err) = ConnectionMode::from_config(config) {
+            errors.push(LinkValidationError {
+                field: None,
+                message: format!("{err:#}"),
+            });
+        }
+        let url = secrets
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(CONFIG_REDIS_URL_KEY))
+            .and_then(|(_, url)| url.as_string())
+            .or_else(|| {
+                config
+                    .iter()
+                    .find(|(k, _)| k.eq_ignore_ascii_case(CONFIG_REDIS_URL_KEY))
+                    .map(|(_, url)| url.as_str())
+            });
+        let conn = match Connection::from_config(config, secrets).await {
+            Ok(Some(conn)) => Ok(conn),
+            Ok(None) => match url {
+                Some(url) => match redis::Client::open(url) {
+                    Ok(client) => Connection::single(client).await,
+                    Err(err) => {
+                        errors.push(LinkValidationError {
+                            field: Some(CONFIG_REDIS_URL_KEY.into()),
+                            message: format!("invalid Redis URL: {err}"),
+                        });
+                        return Ok(errors);
+                    }
+                },
+                None => return Ok(errors),
+            },
+            Err(err) => Err(err),
+        };
+        let res = match conn {
+            Ok(mut conn) => redis::cmd("PING")
+                .query_async::<_, String>(&mut conn)
+                .await
+                .map_err(anyhow::Error::from),
+            Err(err) => Err(err),
+        };
+        if let Err(err) = res {
+            debug!(
+                source_id,
+                ?err,
+                "Redis deployment configured for link is unreachable"
+            );
+            errors.push(LinkValidationError {
+                field: url.map(|_| CONFIG_REDIS_URL_KEY.into()),
+                message: format!("failed to reach Redis deployment: {err:#}"),
+            });
+        }
+        Ok(errors)
+    }
+
     /// Handle notification that a link is dropped - close the connection
     #[instrument(level = "info", skip_all, fields(source_id = info.get_source_id()))]
     async fn delete_link_as_target(&self, info: impl LinkDeleteInfo) -> anyhow::Result<()> {
@@ -394,6 +865,102 @@ impl Provider for KvRedisProvider {
         Ok(())
     }
 
+    /// Report the health of each link by sending `PING` over its connection. Links sharing a
+    /// connection which had at least the saturation threshold of commands queued at once since
+    /// the previous check are reported as degraded
+    async fn link_health(&self) -> anyhow::Result<Vec<LinkHealthCheck>> {
+        let sources: Vec<_> = self
+            .sources
+            .read()
+            .await
+            .iter()
+            .map(|(key, conn)| (key.clone(), conn.conn.clone()))
+            .collect();
+        // Take the statistics of each connection once, counting the links sharing it
+        let mut connections: Vec<(Arc<ConnectionStats>, ConnectionStatsSnapshot, usize)> =
+            Vec::new();
+        for (_, conn) in &sources {
+            match connections
+                .iter_mut()
+                .find(|(stats, ..)| Arc::ptr_eq(stats, &conn.stats))
+            {
+                Some((.., links)) => *links += 1,
+                None => connections.push((Arc::clone(&conn.stats), conn.stats.take_snapshot(), 1)),
+            }
+        }
+        for (_, snapshot, links) in &connections {
+            debug!(?snapshot, links, "Redis connection stats");
+            if *links > 1 && snapshot.peak_queued >= self.saturation_threshold {
+                warn!(
+                    ?snapshot,
+                    links,
+                    "Redis connection shared by multiple links is saturated, consider setting `CONNECTION_MODE=per-link` to use a dedicated connection per link"
+                );
+            }
+        }
+        let saturated = |stats: &Arc<ConnectionStats>| {
+            connections
+                .iter()
+                .find(|(s, snapshot, links)| {
+                    Arc::ptr_eq(s, stats)
+                        && *links > 1
+                        && snapshot.peak_queued >= self.saturation_threshold
+                })
+                .map(|(_, snapshot, links)| (snapshot.peak_queued, *links))
+        };
+        let target = get_connection().provider_key().to_string();
+        let mut links = Vec::with_capacity(sources.len());
+        for (
+            (source_id, link_name),
+            NamespaceConnection {
+                mut conn, stats, ..
+            },
+        ) in sources
+        {
+            let start = Instant::now();
+            let res = timeout(
+                LINK_HEALTH_TIMEOUT,
+                redis::cmd("PING").query_async::<_, String>(&mut conn),
+            )
+            .await;
+            let elapsed = start.elapsed();
+            let (status, message) = match res {
+                Ok(Ok(_)) if elapsed > LINK_HEALTH_SLOW_THRESHOLD => (
+                    LinkHealthStatus::Degraded,
+                    Some(format!("PING took {}ms", elapsed.as_millis())),
+                ),
+                Ok(Ok(_)) => match saturated(&stats) {
+                    Some((peak_queued, shared_by)) => (
+                        LinkHealthStatus::Degraded,
+                        Some(format!(
+                            "connection shared by {shared_by} links is saturated with up to {peak_queued} queued commands, consider `CONNECTION_MODE=per-link`"
+                        )),
+                    ),
+                    None => (LinkHealthStatus::Healthy, None),
+                },
+                Ok(Err(err)) => (
+                    LinkHealthStatus::Unhealthy,
+                    Some(format!("PING failed: {err}")),
+                ),
+                Err(_) => (
+                    LinkHealthStatus::Unhealthy,
+                    Some(format!(
+                        "PING timed out after {}s",
+                        LINK_HEALTH_TIMEOUT.as_secs()
+                    )),
+                ),
+            };
+            links.push(LinkHealthCheck {
+                source_id,
+                target: target.clone(),
+                link_name,
+                status,
+                message,
+            });
+        }
+        Ok(links)
+    }
+
     /// Handle shutdown request by closing all connections
     async fn shutdown(&self) -> anyhow::Result<()> {
         info!("shutting down");
@@ -406,6 +973,23 @@ impl Provider for KvRedisProvider {
     }
 }
 
+/// Connect to the default Redis deployment configured by `cfg`
+async fn connect_default(cfg: &HashMap<String, String>) -> anyhow::Result<RedisConnection> {
+    let buckets = BucketConfig::from_config(cfg).context("invalid default bucket config")?;
+    let pipeline = PipelineConfig::from_config(cfg).context("invalid default pipeline config")?;
+    let conn = if let Some(conn) = Connection::from_config(cfg, &HashMap::new())
+        .await
+        .context("failed to connect to default Redis deployment")?
+    {
+        conn
+    } else {
+        let client = redis::Client::open(retrieve_default_url(cfg))
+            .context("failed to construct default Redis client")?;
+        Connection::single(client).await?
+    };
+    Ok(RedisConnection::new(conn, buckets, pipeline))
+}
+
 /// Fetch the default URL to use for connecting to Redis from the configuration, defaulting
 /// to `DEFAULT_CONNECT_URL` if no URL is found in the configuration.
 pub fn retrieve_default_url(config: &HashMap<String, String>) -> String {
@@ -424,19 +1008,98 @@ pub fn retrieve_default_url(config: &HashMap<String, String>) -> String {
     }
 }
 
-/// Check for unsupported bucket names,
-/// primarily warning on non-empty bucket names, since this provider does not yet properly support named buckets
+/// Check for ignored bucket names,
+/// warning on non-empty bucket names, since the link does not map buckets to Redis namespaces
 fn check_bucket_name(bucket: &str) {
     if !bucket.is_empty() {
-        warn!(bucket, "non-empty bucket names are not yet supported; ignoring non-empty bucket name (using a non-empty bucket name may become an error in the future).")
+        warn!(bucket, "no bucket mapping configured for link; ignoring non-empty bucket name (configure `BUCKET_MODE` or `BUCKET_MAP_<bucket>` to use named buckets).")
+    }
+}
+
+/// Prepend `prefix` to all `keys`
+fn prefixed_keys(prefix: &str, keys: &[String]) -> Vec<String> {
+    keys.iter().map(|key| format!("{prefix}{key}")).collect()
+}
+
+/// Convert a time to live in milliseconds to the signed 64-bit integer Redis expects, returning
+/// an error if it is out of range
+fn ttl_millis(milliseconds: u64) -> Result<i64, ttl::Error> {
+    i64::try_from(milliseconds).map_err(|_| {
+        ttl::Error::Other(format!(
+            "time to live of {milliseconds}ms exceeds the maximum of {}ms",
+            i64::MAX
+        ))
+    })
+}
+
+/// Build the `SET` command setting `key` to `value`, which expires after `milliseconds`.
+///
+/// `SET` rejects `PX 0`, so a key that would expire immediately is deleted instead
+fn set_with_ttl_cmd(key: String, value: &[u8], milliseconds: i64) -> Cmd {
+    if milliseconds == 0 {
+        return Cmd::del(key);
+    }
+    let mut cmd = Cmd::set(key, value);
+    cmd.arg("PX").arg(milliseconds);
+    cmd
+}
+
+/// Build the `PEXPIRE` command expiring an existing `key` after `milliseconds`, `PEXPIRE` deletes
+/// the key if `milliseconds` is 0
+fn expire_cmd(key: String, milliseconds: i64) -> Cmd {
+    let mut cmd = redis::cmd("PEXPIRE");
+    cmd.arg(key).arg(milliseconds);
+    cmd
+}
+
+/// Convert the reply of `PTTL` to the remaining time to live of a key in milliseconds.
+///
+/// `PTTL` returns -2 if the key does not exist and -1 if it has no expiration
+fn remaining_ttl(pttl: i64) -> Option<u64> {
+    u64::try_from(pttl).ok()
+}
+
+/// Build the metadata of a key from its `TYPE` and `PTTL`, returning `None` if the key no longer
+/// exists
+fn key_metadata(value_type: String, ttl: i64) -> Option<scan::KeyMetadata> {
+    // `TYPE` returns `none` and `PTTL` -2 if the key does not exist, `PTTL` returns -1 if the key
+    // has no expiration
+    if value_type == "none" || ttl == -2 {
+        return None;
     }
+    Some(scan::KeyMetadata {
+        value_type,
+        ttl: u64::try_from(ttl).ok(),
+    })
+}
+
+/// Escape glob-style special characters, for use of `s` in a `SCAN` `MATCH` pattern
+fn escape_pattern(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
 }
 
 #[cfg(test)]
 mod test {
+    use core::time::Duration;
+
     use std::collections::HashMap;
+    use std::net::{Ipv4Addr, TcpListener};
+
+    use anyhow::Context as _;
+    use bytes::Bytes;
+    use tokio::process::Command;
+    use wasmcloud_provider_sdk::Context;
 
-    use crate::retrieve_default_url;
+    use crate::{
+        escape_pattern, key_metadata, keyvalue, retrieve_default_url, ttl, KvRedisProvider,
+    };
 
     const PROPER_URL: &str = "redis://127.0.0.1:6379";
 
@@ -450,4 +1113,181 @@ mod test {
         assert_eq!(PROPER_URL, retrieve_default_url(&uppercase_config));
         assert_eq!(PROPER_URL, retrieve_default_url(&initial_caps_config));
     }
+
+    #[test]
+    fn can_escape_scan_pattern() {
+        assert_eq!(escape_pattern("cache:"), "cache:");
+        assert_eq!(escape_pattern("a*b?[c]\\"), "a\\*b\\?\\[c\\]\\\\");
+    }
+
+    #[test]
+    fn can_build_key_metadata() {
+        let metadata = key_metadata("hash".into(), -1).expect("metadata missing");
+        assert_eq!(metadata.value_type, "hash");
+        assert_eq!(metadata.ttl, None);
+        let metadata = key_metadata("string".into(), 1500).expect("metadata missing");
+        assert_eq!(metadata.ttl, Some(1500));
+        assert!(key_metadata("none".into(), -2).is_none());
+    }
+
+    #[tokio::test]
+    async fn expire_keys() -> anyhow::Result<()> {
+        use keyvalue::store::Handler as _;
+        use ttl::Handler as _;
+
+        let port = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?
+            .local_addr()?
+            .port();
+        let _server = Command::new(
+            std::env::var("WASMCLOUD_REDIS")
+                .as_deref()
+                .unwrap_or("redis-server"),
+        )
+        .args(["--port", &port.to_string(), "--save", ""])
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed to start Redis")?;
+        let url = format!("redis://{}:{port}", Ipv4Addr::LOCALHOST);
+        let client = redis::Client::open(url.as_str())?;
+        let mut started = false;
+        for _ in 0..50 {
+            if client.get_multiplexed_async_connection().await.is_ok() {
+                started = true;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        anyhow::ensure!(started, "failed to connect to Redis");
+
+        let provider = KvRedisProvider::new(HashMap::from([("URL".to_string(), url)]));
+        let cx = || Some(Context::default());
+        let value = Bytes::from("value");
+
+        provider
+            .set_with_ttl(cx(), String::new(), "a".into(), value.clone(), 60_000)
+            .await?
+            .expect("failed to set value with TTL");
+        assert_eq!(
+            provider
+                .get(cx(), String::new(), "a".into())
+                .await?
+                .expect("failed to get value"),
+            Some(value.clone())
+        );
+        let remaining = provider
+            .ttl(cx(), String::new(), "a".into())
+            .await?
+            .expect("failed to get TTL")
+            .expect("key does not expire");
+        assert!(remaining > 0 && remaining <= 60_000);
+
+        // A key without expiration and a missing key have no TTL
+        provider
+            .set(cx(), String::new(), "b".into(), value.clone())
+            .await?
+            .expect("failed to set value");
+        assert!(matches!(
+            provider.ttl(cx(), String::new(), "b".into()).await?,
+            Ok(None)
+        ));
+        assert!(matches!(
+            provider.ttl(cx(), String::new(), "missing".into()).await?,
+            Ok(None)
+        ));
+
+        // Expiring sets the TTL of existing keys only
+        assert!(matches!(
+            provider
+                .expire(cx(), String::new(), "b".into(), 60_000)
+                .await?,
+            Ok(true)
+        ));
+        assert!(matches!(
+            provider.ttl(cx(), String::new(), "b".into()).await?,
+            Ok(Some(1..=60_000))
+        ));
+        assert!(matches!(
+            provider
+                .expire(cx(), String::new(), "missing".into(), 60_000)
+                .await?,
+            Ok(false)
+        ));
+
+        // A TTL of 0 deletes the key
+        provider
+            .set_with_ttl(cx(), String::new(), "a".into(), value.clone(), 0)
+            .await?
+            .expect("failed to set value with TTL of 0");
+        assert_eq!(
+            provider
+                .get(cx(), String::new(), "a".into())
+                .await?
+                .expect("failed to get value"),
+            None
+        );
+        assert!(matches!(
+            provider.expire(cx(), String::new(), "b".into(), 0).await?,
+            Ok(true)
+        ));
+        assert_eq!(
+            provider
+                .get(cx(), String::new(), "b".into())
+                .await?
+                .expect("failed to get value"),
+            None
+        );
+
+        // A TTL out of range is rejected without changing the key
+        provider
+            .set(cx(), String::new(), "c".into(), value.clone())
+            .await?
+            .expect("failed to set value");
+        let out_of_range = u64::try_from(i64::MAX)? + 1;
+        assert!(matches!(
+            provider
+                .set_with_ttl(
+                    cx(),
+                    String::new(),
+                    "c".into(),
+                    Bytes::from("other"),
+                    out_of_range
+                )
+                .await?,
+            Err(ttl::Error::Other(..))
+        ));
+        assert!(matches!(
+            provider
+                .expire(cx(), String::new(), "c".into(), out_of_range)
+                .await?,
+            Err(ttl::Error::Other(..))
+        ));
+        assert_eq!(
+            provider
+                .get(cx(), String::new(), "c".into())
+                .await?
+                .expect("failed to get value"),
+            Some(value)
+        );
+        assert!(matches!(
+            provider.ttl(cx(), String::new(), "c".into()).await?,
+            Ok(None)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn can_convert_ttl_errors() {
+        assert!(matches!(
+            ttl::Error::from(keyvalue::store::Error::NoSuchStore),
+            ttl::Error::NoSuchStore
+        ));
+        assert!(matches!(
+            ttl::Error::from(keyvalue::store::Error::AccessDenied),
+            ttl::Error::AccessDenied
+        ));
+        assert!(matches!(
+            ttl::Error::from(keyvalue::store::Error::Other("failed".into())),
+            ttl::Error::Other(err) if err == "failed"
+        ));
+    }
 }
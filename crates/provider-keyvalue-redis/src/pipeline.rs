@@ -0,0 +1,351 @@
+//! Coalescing of concurrent commands issued on the same connection into Redis pipelines
+
+use core::time::Duration;
+
+use std::collections::HashMap;
+
+use anyhow::{bail, Context as _};
+use redis::{Arg, Cmd, ErrorKind, RedisError, RedisResult, Value};
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::Instant;
+use tracing::{trace, warn};
+
+use crate::Connection;
+
+/// Configuration key of the time in microseconds to wait for further commands once a command is
+/// queued, which enables pipelining when set
+const CONFIG_PIPELINE_WINDOW_KEY: &str = "PIPELINE_WINDOW_US";
+
+/// Configuration key of the maximum number of commands sent in a single pipeline
+const CONFIG_PIPELINE_MAX_BATCH_KEY: &str = "PIPELINE_MAX_BATCH";
+
+/// Default maximum number of commands sent in a single pipeline
+const DEFAULT_PIPELINE_MAX_BATCH: usize = 64;
+
+/// Maximum number of commands waiting to be pipelined
+const PIPELINE_QUEUE_SIZE: usize = 1024;
+
+/// Commands issued by the provider, which do not modify any data
+const READ_ONLY_COMMANDS: &[&str] = &["EXISTS", "GET", "MGET", "PING", "PTTL", "SCAN"];
+
+/// Configuration of command pipelining
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PipelineConfig {
+    /// Time to wait for further commands once a command is queued. With a zero window, only
+    /// commands queued while the previous pipeline is executing are coalesced
+    pub window: Duration,
+    /// Maximum number of commands sent in a single pipeline
+    pub max_batch: usize,
+}
+
+impl PipelineConfig {
+    /// Construct a [`PipelineConfig`] from link configuration, returning `None` if pipelining is
+    /// not enabled. Keys are matched case-insensitively
+    pub fn from_config(config: &HashMap<String, String>) -> anyhow::Result<Option<Self>> {
+        let value = |key: &str| {
+            config
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case(key))
+                .map(|(_, v)| v.as_str())
+        };
+        let Some(window) = value(CONFIG_PIPELINE_WINDOW_KEY) else {
+            return Ok(None);
+        };
+        let window = window
+            .parse()
+            .map(Duration::from_micros)
+            .with_context(|| format!("invalid `{CONFIG_PIPELINE_WINDOW_KEY}` value `{window}`"))?;
+        let max_batch = value(CONFIG_PIPELINE_MAX_BATCH_KEY)
+            .map(|max| {
+                max.parse().with_context(|| {
+                    format!("invalid `{CONFIG_PIPELINE_MAX_BATCH_KEY}` value `{max}`")
+                })
+            })
+            .transpose()?
+            .unwrap_or(DEFAULT_PIPELINE_MAX_BATCH);
+        if max_batch == 0 {
+            bail!("`{CONFIG_PIPELINE_MAX_BATCH_KEY}` must be greater than 0")
+        }
+        Ok(Some(Self { window, max_batch }))
+    }
+}
+
+/// Command queued for pipelining along with the channel to send its result on
+struct Request {
+    cmd: Cmd,
+    tx: oneshot::Sender<RedisResult<Value>>,
+}
+
+/// Handle to a task, which executes queued commands on a connection in pipelines. The task stops
+/// once all handles are dropped
+#[derive(Clone, Debug)]
+pub struct Pipeliner {
+    tx: mpsc::Sender<Request>,
+}
+
+impl Pipeliner {
+    /// Start pipelining commands on `conn`. Returns `None` for Redis Cluster connections, since
+    /// the commands of a pipeline may be stored on different nodes
+    pub fn new(conn: Connection, config: PipelineConfig) -> Option<Self> {
+        if conn.is_cluster() {
+            warn!("command pipelining is not supported on Redis Cluster, commands are sent individually");
+            return None;
+        }
+        let (tx, rx) = mpsc::channel(PIPELINE_QUEUE_SIZE);
+        tokio::spawn(run(conn, rx, config));
+        Some(Self { tx })
+    }
+
+    /// Queue `cmd` and wait for the result
+    pub async fn query(&self, cmd: Cmd) -> RedisResult<Value> {
+        let (tx, rx) = oneshot::channel();
+        self.tx
+            .send(Request { cmd, tx })
+            .await
+            .map_err(|_| pipeline_stopped())?;
+        rx.await.map_err(|_| pipeline_stopped())?
+    }
+}
+
+fn pipeline_stopped() -> RedisError {
+    RedisError::from((ErrorKind::ClientError, "command pipeline stopped"))
+}
+
+/// Receive queued commands and execute them in batches of up to `max_batch` commands
+async fn run(
+    mut conn: Connection,
+    mut rx: mpsc::Receiver<Request>,
+    PipelineConfig { window, max_batch }: PipelineConfig,
+) {
+    while let Some(req) = rx.recv().await {
+        let mut batch = vec![req];
+        if !window.is_zero() {
+            let deadline = Instant::now() + window;
+            while batch.len() < max_batch {
+                match tokio::time::timeout_at(deadline, rx.recv()).await {
+                    Ok(Some(req)) => batch.push(req),
+                    Ok(None) | Err(..) => break,
+                }
+            }
+        }
+        while batch.len() < max_batch {
+            let Ok(req) = rx.try_recv() else {
+                break;
+            };
+            batch.push(req);
+        }
+        execute(&mut conn, batch).await;
+    }
+}
+
+/// Returns whether `cmd` does not modify any data and can therefore be executed again
+fn is_read_only(cmd: &Cmd) -> bool {
+    let Some(Arg::Simple(name)) = cmd.args_iter().next() else {
+        return false;
+    };
+    READ_ONLY_COMMANDS
+        .iter()
+        .any(|cmd| cmd.as_bytes().eq_ignore_ascii_case(name))
+}
+
+/// Execute a batch of commands in a single pipeline.
+///
+/// A failing command fails the whole pipeline without returning the results of the other
+/// commands, although the server executed all of them. If the server returns an error, read-only
+/// commands are therefore executed again individually to deliver their results, while all other
+/// commands fail, since executing them again could apply them twice.
+async fn execute(conn: &mut Connection, mut batch: Vec<Request>) {
+    if batch.len() == 1 {
+        let Request { cmd, tx } = batch.remove(0);
+        _ = tx.send(cmd.query_async(conn).await);
+        return;
+    }
+    trace!(commands = batch.len(), "execute pipeline");
+    let mut pipe = redis::pipe();
+    for Request { cmd, .. } in &batch {
+        pipe.add_command(cmd.clone());
+    }
+    match pipe.query_async::<_, Vec<Value>>(conn).await {
+        Ok(values) if values.len() == batch.len() => {
+            for (Request { tx, .. }, value) in batch.into_iter().zip(values) {
+                _ = tx.send(Ok(value));
+            }
+        }
+        Ok(values) => {
+            warn!(
+                expected = batch.len(),
+                received = values.len(),
+                "unexpected number of pipeline results"
+            );
+            for Request { tx, .. } in batch {
+                _ = tx.send(Err(RedisError::from((
+                    ErrorKind::ResponseError,
+                    "unexpected number of pipeline results",
+                ))));
+            }
+        }
+        Err(err)
+            if err.is_io_error()
+                || err.is_connection_dropped()
+                || err.is_connection_refusal()
+                || err.is_timeout() =>
+        {
+            for Request { tx, .. } in batch {
+                _ = tx.send(Err(RedisError::from((
+                    err.kind(),
+                    "failed to execute pipeline",
+                    err.to_string(),
+                ))));
+            }
+        }
+        Err(err) => {
+            trace!(
+                ?err,
+                "pipeline failed, execute read-only commands individually"
+            );
+            for Request { cmd, tx } in batch {
+                if is_read_only(&cmd) {
+                    _ = tx.send(cmd.query_async(conn).await);
+                } else {
+                    _ = tx.send(Err(RedisError::from((
+                        err.kind(),
+                        "command pipeline failed, the command may have been applied",
+                        err.to_string(),
+                    ))));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::{Ipv4Addr, TcpListener};
+
+    use tokio::process::Command;
+
+    use super::*;
+
+    #[test]
+    fn read_only_commands() {
+        assert!(is_read_only(&Cmd::get("key")));
+        assert!(is_read_only(&redis::cmd("mget").arg("a").arg("b")));
+        assert!(is_read_only(&redis::cmd("SCAN").cursor_arg(0)));
+        assert!(!is_read_only(&Cmd::incr("key", 1)));
+        assert!(!is_read_only(&Cmd::set("key", "value")));
+        assert!(!is_read_only(&redis::cmd("EVAL").arg("return 1").arg(0)));
+        assert!(!is_read_only(&Cmd::new()));
+    }
+
+    /// Execute `cmds` in a single pipeline, returning the result of each command
+    async fn execute_batch(conn: &mut Connection, cmds: Vec<Cmd>) -> Vec<RedisResult<Value>> {
+        let (batch, rxs): (Vec<_>, Vec<_>) = cmds
+            .into_iter()
+            .map(|cmd| {
+                let (tx, rx) = oneshot::channel();
+                (Request { cmd, tx }, rx)
+            })
+            .unzip();
+        execute(conn, batch).await;
+        let mut results = Vec::with_capacity(rxs.len());
+        for rx in rxs {
+            results.push(rx.await.expect("result not sent"));
+        }
+        results
+    }
+
+    #[tokio::test]
+    async fn failing_command_in_pipeline() -> anyhow::Result<()> {
+        let port = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?
+            .local_addr()?
+            .port();
+        let _server = Command::new(
+            std::env::var("WASMCLOUD_REDIS")
+                .as_deref()
+                .unwrap_or("redis-server"),
+        )
+        .args(["--port", &port.to_string(), "--save", ""])
+        .kill_on_drop(true)
+        .spawn()
+        .context("failed to start Redis")?;
+        let client = redis::Client::open(format!("redis://{}:{port}", Ipv4Addr::LOCALHOST))?;
+        let mut conn = None;
+        for _ in 0..50 {
+            if let Ok(c) = Connection::single(client.clone()).await {
+                conn = Some(c);
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        let mut conn = conn.context("failed to connect to Redis")?;
+        Cmd::set("counter", 1)
+            .query_async::<_, ()>(&mut conn)
+            .await?;
+        Cmd::set("text", "abc")
+            .query_async::<_, ()>(&mut conn)
+            .await?;
+
+        // Incrementing `text` fails in the middle of the pipeline
+        let results = execute_batch(
+            &mut conn,
+            vec![
+                Cmd::incr("counter", 1),
+                Cmd::incr("text", 1),
+                Cmd::get("text"),
+                Cmd::incr("counter", 1),
+                Cmd::get("counter"),
+            ],
+        )
+        .await;
+        assert!(results[0].is_err());
+        assert!(results[1].is_err());
+        assert_eq!(results[2], Ok(Value::Data(b"abc".to_vec())));
+        assert!(results[3].is_err());
+        // Commands applied by the failed pipeline must not be applied again
+        assert_eq!(results[4], Ok(Value::Data(b"3".to_vec())));
+        let counter: u64 = Cmd::get("counter").query_async(&mut conn).await?;
+        assert_eq!(counter, 3);
+
+        // Results of successful pipelines are delivered to each caller
+        let results = execute_batch(
+            &mut conn,
+            vec![Cmd::incr("counter", 1), Cmd::get("counter")],
+        )
+        .await;
+        assert_eq!(results[0], Ok(Value::Int(4)));
+        assert_eq!(results[1], Ok(Value::Data(b"4".to_vec())));
+        Ok(())
+    }
+
+    #[test]
+    fn parse_pipeline_config() {
+        assert_eq!(PipelineConfig::from_config(&HashMap::new()).unwrap(), None);
+        assert_eq!(
+            PipelineConfig::from_config(&HashMap::from([(
+                "pipeline_window_us".to_string(),
+                "250".to_string()
+            )]))
+            .unwrap(),
+            Some(PipelineConfig {
+                window: Duration::from_micros(250),
+                max_batch: DEFAULT_PIPELINE_MAX_BATCH,
+            })
+        );
+        assert_eq!(
+            PipelineConfig::from_config(&HashMap::from([
+                ("PIPELINE_WINDOW_US".to_string(), "0".to_string()),
+                ("PIPELINE_MAX_BATCH".to_string(), "16".to_string()),
+            ]))
+            .unwrap(),
+            Some(PipelineConfig {
+                window: Duration::ZERO,
+                max_batch: 16,
+            })
+        );
+        assert!(PipelineConfig::from_config(&HashMap::from([
+            ("PIPELINE_WINDOW_US".to_string(), "0".to_string()),
+            ("PIPELINE_MAX_BATCH".to_string(), "0".to_string()),
+        ]))
+        .is_err());
+    }
+}
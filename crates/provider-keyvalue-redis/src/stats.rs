@@ -0,0 +1,213 @@
+//! Statistics of Redis connections, used to detect shared connections becoming a bottleneck
+
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use std::collections::HashMap;
+
+use anyhow::{bail, Context as _};
+use redis::RedisError;
+
+/// Configuration key selecting whether links without their own Redis deployment share the default
+/// connection of the provider or use a dedicated connection
+const CONFIG_CONNECTION_MODE_KEY: &str = "CONNECTION_MODE";
+
+/// Configuration key of the number of commands queued on a shared connection at once, at which
+/// the connection is considered saturated
+const CONFIG_SATURATION_THRESHOLD_KEY: &str = "CONNECTION_SATURATION_THRESHOLD";
+
+/// Default number of commands queued on a shared connection at once, at which the connection is
+/// considered saturated
+pub const DEFAULT_SATURATION_THRESHOLD: u64 = 128;
+
+/// How links without their own Redis deployment connect to the default deployment
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConnectionMode {
+    /// All links share the default connection of the provider
+    #[default]
+    Shared,
+    /// Each link uses a dedicated connection to the default deployment
+    PerLink,
+}
+
+impl ConnectionMode {
+    /// Parse the connection mode from configuration, returning `None` if it is not configured.
+    /// Keys are matched case-insensitively
+    pub fn from_config(config: &HashMap<String, String>) -> anyhow::Result<Option<Self>> {
+        let Some((_, mode)) = config
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(CONFIG_CONNECTION_MODE_KEY))
+        else {
+            return Ok(None);
+        };
+        match mode.to_ascii_lowercase().as_str() {
+            "shared" => Ok(Some(Self::Shared)),
+            "per-link" | "per_link" => Ok(Some(Self::PerLink)),
+            _ => bail!("invalid connection mode `{mode}`, expected `shared` or `per-link`"),
+        }
+    }
+}
+
+/// Parse the saturation threshold from configuration, defaulting to
+/// [`DEFAULT_SATURATION_THRESHOLD`]. Keys are matched case-insensitively
+pub fn saturation_threshold_from_config(config: &HashMap<String, String>) -> anyhow::Result<u64> {
+    let Some((_, threshold)) = config
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(CONFIG_SATURATION_THRESHOLD_KEY))
+    else {
+        return Ok(DEFAULT_SATURATION_THRESHOLD);
+    };
+    let threshold = threshold.trim().parse().with_context(|| {
+        format!("invalid `{CONFIG_SATURATION_THRESHOLD_KEY}` value `{threshold}`")
+    })?;
+    if threshold == 0 {
+        bail!("`{CONFIG_SATURATION_THRESHOLD_KEY}` must be greater than 0")
+    }
+    Ok(threshold)
+}
+
+/// Statistics of the commands issued on a connection, shared by all links using the connection
+#[derive(Debug, Default)]
+pub struct ConnectionStats {
+    queued: AtomicU64,
+    peak_queued: AtomicU64,
+    commands: AtomicU64,
+    reconnects: AtomicU64,
+    broken_pipes: AtomicU64,
+}
+
+/// Point-in-time copy of [`ConnectionStats`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ConnectionStatsSnapshot {
+    /// Number of commands currently queued on the connection, waiting for a response
+    pub queued: u64,
+    /// Highest number of commands queued at once since the previous peak was taken
+    pub peak_queued: u64,
+    /// Total number of commands issued on the connection
+    pub commands: u64,
+    /// Number of times the connection failed and was re-established
+    pub reconnects: u64,
+    /// Number of times the connection was dropped by the server or the network (broken pipe,
+    /// connection reset or unexpected EOF)
+    pub broken_pipes: u64,
+}
+
+impl ConnectionStats {
+    /// Record a command being queued, returning a guard which records the command as completed
+    /// once dropped
+    pub(crate) fn start(&self) -> QueuedCommand<'_> {
+        self.commands.fetch_add(1, Ordering::Relaxed);
+        let queued = self.queued.fetch_add(1, Ordering::Relaxed) + 1;
+        self.peak_queued.fetch_max(queued, Ordering::Relaxed);
+        QueuedCommand(self)
+    }
+
+    /// Record a failed command. The connection is re-established after the connection was
+    /// dropped or refused, or any other I/O error
+    pub(crate) fn record_error(&self, err: &RedisError) {
+        if err.is_connection_dropped() {
+            self.broken_pipes.fetch_add(1, Ordering::Relaxed);
+        }
+        if err.is_connection_dropped() || err.is_connection_refusal() || err.is_io_error() {
+            self.reconnects.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns a snapshot of the statistics
+    pub fn snapshot(&self) -> ConnectionStatsSnapshot {
+        ConnectionStatsSnapshot {
+            queued: self.queued.load(Ordering::Relaxed),
+            peak_queued: self.peak_queued.load(Ordering::Relaxed),
+            commands: self.commands.load(Ordering::Relaxed),
+            reconnects: self.reconnects.load(Ordering::Relaxed),
+            broken_pipes: self.broken_pipes.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Returns a snapshot of the statistics, resetting the peak number of queued commands
+    pub fn take_snapshot(&self) -> ConnectionStatsSnapshot {
+        let queued = self.queued.load(Ordering::Relaxed);
+        ConnectionStatsSnapshot {
+            queued,
+            peak_queued: self.peak_queued.swap(queued, Ordering::Relaxed),
+            commands: self.commands.load(Ordering::Relaxed),
+            reconnects: self.reconnects.load(Ordering::Relaxed),
+            broken_pipes: self.broken_pipes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Guard of a command queued on a connection, see [`ConnectionStats::start`]
+pub(crate) struct QueuedCommand<'a>(&'a ConnectionStats);
+
+impl Drop for QueuedCommand<'_> {
+    fn drop(&mut self) {
+        self.0.queued.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use redis::ErrorKind;
+
+    #[test]
+    fn track_queued_commands() {
+        let stats = ConnectionStats::default();
+        let a = stats.start();
+        let b = stats.start();
+        drop(a);
+        let c = stats.start();
+        drop(b);
+        drop(c);
+        stats.record_error(&RedisError::from(std::io::Error::from(
+            std::io::ErrorKind::BrokenPipe,
+        )));
+        stats.record_error(&RedisError::from((ErrorKind::ResponseError, "WRONGTYPE")));
+        let expected = ConnectionStatsSnapshot {
+            queued: 0,
+            peak_queued: 2,
+            commands: 3,
+            reconnects: 1,
+            broken_pipes: 1,
+        };
+        assert_eq!(stats.snapshot(), expected);
+        assert_eq!(stats.take_snapshot(), expected);
+        // The peak is reset once taken
+        assert_eq!(stats.snapshot().peak_queued, 0);
+    }
+
+    #[test]
+    fn parse_connection_config() -> anyhow::Result<()> {
+        assert_eq!(ConnectionMode::from_config(&HashMap::new())?, None);
+        assert_eq!(
+            ConnectionMode::from_config(&HashMap::from([(
+                "connection_mode".to_string(),
+                "Per-Link".to_string()
+            )]))?,
+            Some(ConnectionMode::PerLink)
+        );
+        assert!(ConnectionMode::from_config(&HashMap::from([(
+            "CONNECTION_MODE".to_string(),
+            "pooled".to_string()
+        )]))
+        .is_err());
+        assert_eq!(
+            saturation_threshold_from_config(&HashMap::new())?,
+            DEFAULT_SATURATION_THRESHOLD
+        );
+        assert_eq!(
+            saturation_threshold_from_config(&HashMap::from([(
+                "CONNECTION_SATURATION_THRESHOLD".to_string(),
+                "32".to_string()
+            )]))?,
+            32
+        );
+        assert!(saturation_threshold_from_config(&HashMap::from([(
+            "CONNECTION_SATURATION_THRESHOLD".to_string(),
+            "0".to_string()
+        )]))
+        .is_err());
+        Ok(())
+    }
+}
@@ -11,18 +11,37 @@ use tracing::warn;
 use url::Url;
 use wasmcloud_provider_sdk::{core::secrets::SecretValue, LinkConfig};
 
+use crate::database::DEFAULT_DATABASE_MOUNT;
 use crate::TOKEN_REFRESH_INTERVAL;
 
 /// Default address at which Vault is expected to be running,
 /// used if unspecified by configuration
 const DEFAULT_VAULT_ADDR: &str = "http://127.0.0.1:8200";
 
+/// Default mount point of the AppRole auth method
+const DEFAULT_APPROLE_MOUNT: &str = "approle";
+
+/// Method used to authenticate with Vault
+#[derive(Clone, Debug)]
+pub enum Auth {
+    /// Static token, renewed in the background
+    Token(String),
+    /// AppRole role and secret ID, exchanged for a token on login. The token is renewed in the
+    /// background, and a new token is issued by logging in again once it can no longer be renewed
+    AppRole {
+        mount: String,
+        role_id: String,
+        secret_id: String,
+    },
+}
+
 /// KV-Vault configuration
 #[derive(Clone, Debug)]
 pub struct Config {
-    /// Token for connecting to vault, can be set in environment with VAULT_TOKEN.
-    /// Required
-    pub token: String,
+    /// Authentication method. AppRole is used if a role ID is set, which can be set in environment
+    /// with VAULT_ROLE_ID. Otherwise a token is required, which can be set in environment with
+    /// VAULT_TOKEN.
+    pub auth: Auth,
     /// Url for connecting to vault, can be set in environment with VAULT_ADDR.
     /// Defaults to 'http://127.0.0.1:8200'
     pub addr: Url,
@@ -39,6 +58,19 @@ pub struct Config {
 
     /// Refresh interval for tokens used by this provider. Defaults to 12 hours.
     pub token_refresh_interval: Option<std::time::Duration>,
+
+    /// Mount point of the database secrets engine, can be set in environment with VAULT_DATABASE_MOUNT.
+    /// Defaults to "database"
+    pub database_mount: String,
+
+    /// Database roles to generate dynamic credentials for. The linkdef value `database_roles`
+    /// and the environment variable `VAULT_DATABASE_ROLES` are parsed as a comma-separated list.
+    pub database_roles: Vec<String>,
+
+    /// Maximum lifetime of dynamic database credentials, after which they are regenerated even if
+    /// their lease can still be renewed. If unset, credentials are regenerated once their lease
+    /// cannot be renewed anymore.
+    pub database_creds_rotation_interval: Option<std::time::Duration>,
 }
 
 impl Default for Config {
@@ -66,6 +98,15 @@ impl Config {
             warn!("Secret value [token] (ENV: VAULT_TOKEN) was not found in env or secrets. Please prefer ENV variables or secrets for sensitive values.")
         }
 
+        // Attempt to retrieve the AppRole secret ID from secrets
+        if let Some(secret_id) = link_config
+            .secrets
+            .get("secret_id")
+            .and_then(SecretValue::as_string)
+        {
+            map.insert("VAULT_SECRET_ID".into(), secret_id.into());
+        }
+
         Self::from_values(&map)
     }
 
@@ -84,11 +125,37 @@ impl Config {
             );
             DEFAULT_VAULT_ADDR.parse().unwrap()
         });
-        let token = env::var("VAULT_TOKEN")
+        let role_id = env::var("VAULT_ROLE_ID")
             .ok()
-            .or_else(|| values.get("token").cloned())
-            .or_else(|| values.get("TOKEN").cloned())
-            .context("missing setting for 'token' or VAULT_TOKEN")?;
+            .or_else(|| values.get("role_id").cloned())
+            .or_else(|| values.get("ROLE_ID").cloned());
+        let auth = if let Some(role_id) = role_id {
+            let secret_id = env::var("VAULT_SECRET_ID")
+                .ok()
+                .or_else(|| values.get("VAULT_SECRET_ID").cloned())
+                .or_else(|| values.get("secret_id").cloned())
+                .or_else(|| values.get("SECRET_ID").cloned())
+                .context("missing setting for 'secret_id' or VAULT_SECRET_ID")?;
+            let mount = env::var("VAULT_APPROLE_MOUNT")
+                .ok()
+                .or_else(|| values.get("approle_mount").cloned())
+                .or_else(|| values.get("APPROLE_MOUNT").cloned())
+                .unwrap_or_else(|| DEFAULT_APPROLE_MOUNT.to_string());
+            Auth::AppRole {
+                mount,
+                role_id,
+                secret_id,
+            }
+        } else {
+            env::var("VAULT_TOKEN")
+                .ok()
+                .or_else(|| values.get("token").cloned())
+                .or_else(|| values.get("TOKEN").cloned())
+                .map(Auth::Token)
+                .context(
+                    "missing setting for 'token' or VAULT_TOKEN, or 'role_id' or VAULT_ROLE_ID",
+                )?
+        };
         let mount = env::var("VAULT_MOUNT")
             .ok()
             .or_else(|| values.get("mount").cloned())
@@ -100,9 +167,45 @@ impl Config {
             .or_else(|| values.get("CERTS").cloned())
             .map(|certs| certs.split(',').map(|s| s.trim().to_string()).collect())
             .unwrap_or_default();
+        let database_mount = env::var("VAULT_DATABASE_MOUNT")
+            .ok()
+            .or_else(|| values.get("database_mount").cloned())
+            .or_else(|| values.get("DATABASE_MOUNT").cloned())
+            .unwrap_or_else(|| DEFAULT_DATABASE_MOUNT.to_string());
+        let database_roles = env::var("VAULT_DATABASE_ROLES")
+            .ok()
+            .or_else(|| values.get("database_roles").cloned())
+            .or_else(|| values.get("DATABASE_ROLES").cloned())
+            .map(|roles| {
+                roles
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty())
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let database_creds_rotation_interval = match env::var(
+            "VAULT_DATABASE_CREDS_ROTATION_INTERVAL",
+        )
+        .ok()
+        .or_else(|| values.get("database_creds_rotation_interval").cloned())
+        .or_else(|| values.get("DATABASE_CREDS_ROTATION_INTERVAL").cloned())
+        {
+            Some(val) => match val.parse::<u64>() {
+                Ok(secs) => Some(Duration::from_secs(secs)),
+                Err(_) => {
+                    eprintln!(
+                        "Could not parse VAULT_DATABASE_CREDS_ROTATION_INTERVAL as u64, credentials are rotated based on their lease"
+                    );
+                    None
+                }
+            },
+            _ => None,
+        };
         Ok(Config {
             addr,
-            token,
+            auth,
             mount,
             certs,
             token_increment_ttl: env::var("VAULT_TOKEN_INCREMENT_TTL")
@@ -126,6 +229,9 @@ impl Config {
                 }
                 _ => None,
             },
+            database_mount,
+            database_roles,
+            database_creds_rotation_interval,
         })
     }
 }
@@ -0,0 +1,436 @@
+//! Dynamic database credentials, generated by the Vault database secrets engine
+//!
+//! Credentials are generated for each configured role when a link is established, and exposed
+//! to the linked component as a read-only secret at [`creds_path`], with `username` and
+//! `password` keys. The lease of the credentials is renewed while renewing extends it, after
+//! which new credentials are generated and the lease of the replaced credentials is revoked, so
+//! that components always read unexpired credentials.
+
+use core::time::Duration;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Context as _;
+use base64::Engine as _;
+use rustify::endpoint::{Endpoint as _, Wrapper};
+use rustify_derive::Endpoint;
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::Deserialize;
+use tokio::sync::RwLock;
+use tokio::time::Instant;
+use tracing::{debug, error, info, instrument, warn};
+use vaultrs::client::{Client as _, VaultClient};
+
+/// Default mount point of the database secrets engine
+pub const DEFAULT_DATABASE_MOUNT: &str = "database";
+
+/// Delay before generating credentials again after generating them failed
+const ROTATION_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Request generating credentials for `role`
+#[derive(Debug, Endpoint)]
+#[endpoint(
+    path = "{self.mount}/creds/{self.role}",
+    response = "GeneratedCredentials"
+)]
+struct GenerateCredentialsRequest {
+    #[endpoint(skip)]
+    mount: String,
+    #[endpoint(skip)]
+    role: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeneratedCredentials {
+    username: String,
+    password: String,
+}
+
+/// Request renewing lease `lease_id` by `increment` seconds
+#[derive(Debug, Endpoint)]
+#[endpoint(path = "sys/leases/renew", method = "PUT", response = "IgnoredAny")]
+struct RenewLeaseRequest {
+    lease_id: String,
+    increment: u64,
+}
+
+/// Request revoking lease `lease_id`
+#[derive(Debug, Endpoint)]
+#[endpoint(path = "sys/leases/revoke", method = "PUT")]
+struct RevokeLeaseRequest {
+    lease_id: String,
+}
+
+/// Response of Vault, along with the lease of the returned secret
+#[derive(Debug, Deserialize)]
+struct LeasedResponse<T> {
+    data: Option<T>,
+    #[serde(default)]
+    lease_id: String,
+    #[serde(default)]
+    lease_duration: u64,
+    #[serde(default)]
+    renewable: bool,
+}
+
+impl<T: DeserializeOwned + Send + Sync> Wrapper for LeasedResponse<T> {
+    type Value = T;
+}
+
+impl<T> LeasedResponse<T> {
+    fn lease(&self) -> Lease {
+        Lease {
+            id: self.lease_id.clone(),
+            duration: Duration::from_secs(self.lease_duration),
+            renewable: self.renewable,
+        }
+    }
+}
+
+/// Lease of dynamic credentials
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lease {
+    pub id: String,
+    /// Time until the lease expires, zero if the credentials do not expire
+    pub duration: Duration,
+    pub renewable: bool,
+}
+
+/// Next step in keeping credentials valid
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    /// Renew the lease of the credentials
+    Renew,
+    /// Generate new credentials and revoke the lease of the current credentials
+    Rotate,
+}
+
+/// Schedule the next step in keeping credentials with `lease` valid, which were generated `age`
+/// ago. `increment` is the increment requested when the lease was last renewed, if it was.
+///
+/// Leases are renewed after two thirds of their duration, as long as renewing extends them by the
+/// requested increment. Credentials are rotated once their lease cannot be extended anymore, or
+/// when they reach `max_lifetime`. Returns `None` for credentials that do not expire
+pub fn schedule(
+    lease: &Lease,
+    increment: Option<Duration>,
+    age: Duration,
+    max_lifetime: Option<Duration>,
+) -> Option<(Duration, Step)> {
+    let delay = lease.duration * 2 / 3;
+    if let Some(max_lifetime) = max_lifetime {
+        if lease.duration.is_zero() || age + delay >= max_lifetime {
+            return Some((max_lifetime.saturating_sub(age), Step::Rotate));
+        }
+    }
+    if lease.duration.is_zero() {
+        return None;
+    }
+    // Renewals are capped by the maximum TTL of the lease, after which they stop extending it
+    let extended = increment.map_or(true, |increment| lease.duration >= increment);
+    if lease.renewable && extended {
+        Some((delay, Step::Renew))
+    } else {
+        Some((delay, Step::Rotate))
+    }
+}
+
+/// Renew `lease` by `increment`, returning the renewed lease
+async fn renew(client: &VaultClient, lease: &Lease, increment: Duration) -> anyhow::Result<Lease> {
+    let res = RenewLeaseRequest {
+        lease_id: lease.id.clone(),
+        increment: increment.as_secs(),
+    }
+    .with_middleware(client.middle())
+    .exec(client.http())
+    .await
+    .context("failed to renew lease")?
+    .wrap::<LeasedResponse<_>>()
+    .context("failed to parse renewed lease")?;
+    Ok(res.lease())
+}
+
+/// Revoke `lease`, invalidating the credentials it was issued for
+async fn revoke(client: &VaultClient, lease: &Lease) -> anyhow::Result<()> {
+    RevokeLeaseRequest {
+        lease_id: lease.id.clone(),
+    }
+    .with_middleware(client.middle())
+    .exec(client.http())
+    .await
+    .context("failed to revoke lease")?;
+    Ok(())
+}
+
+/// Path at which the credentials of `role` are exposed to components
+pub fn creds_path(role: &str) -> String {
+    format!("database/creds/{role}")
+}
+
+/// Current dynamic database credentials, by path
+#[derive(Clone, Debug, Default)]
+pub struct DatabaseCredentials {
+    mount: String,
+    roles: Vec<String>,
+    creds: Arc<RwLock<HashMap<String, HashMap<String, String>>>>,
+}
+
+impl DatabaseCredentials {
+    pub fn new(mount: String, roles: Vec<String>) -> Self {
+        Self {
+            mount,
+            roles,
+            creds: Arc::default(),
+        }
+    }
+
+    /// Returns whether there are any roles to generate credentials for
+    pub fn is_empty(&self) -> bool {
+        self.roles.is_empty()
+    }
+
+    /// Returns whether `path` is the path of dynamic credentials
+    pub fn contains_path(&self, path: &str) -> bool {
+        self.roles.iter().any(|role| creds_path(role) == path)
+    }
+
+    /// Get the current credentials at `path`, encoded like values set via `wrpc:keyvalue/store`
+    pub async fn get(&self, path: &str) -> Option<HashMap<String, String>> {
+        self.creds.read().await.get(path).cloned()
+    }
+
+    /// Replace the current credentials of `role`
+    async fn insert(&self, role: &str, username: &str, password: &str) {
+        self.creds
+            .write()
+            .await
+            .insert(creds_path(role), encode_creds(username, password));
+    }
+
+    /// Generate new credentials for `role`, replacing its current credentials. Returns the lease
+    /// of the new credentials
+    #[instrument(level = "debug", skip(self, client), fields(mount = %self.mount))]
+    async fn generate(&self, client: &VaultClient, role: &str) -> anyhow::Result<Lease> {
+        let res = GenerateCredentialsRequest {
+            mount: self.mount.clone(),
+            role: role.to_string(),
+        }
+        .with_middleware(client.middle())
+        .exec(client.http())
+        .await
+        .with_context(|| format!("failed to generate database credentials for role [{role}]"))?
+        .wrap::<LeasedResponse<_>>()
+        .context("failed to parse generated database credentials")?;
+        let lease = res.lease();
+        let creds = res
+            .data
+            .context("response is missing generated database credentials")?;
+        self.insert(role, &creds.username, &creds.password).await;
+        info!(
+            role,
+            lease_duration = lease.duration.as_secs(),
+            renewable = lease.renewable,
+            "generated database credentials"
+        );
+        debug!(role, username = creds.username, "generated database user");
+        Ok(lease)
+    }
+
+    /// Generate credentials for all roles, returning the leases of the credentials by role
+    pub async fn generate_all(&self, client: &VaultClient) -> anyhow::Result<Vec<(String, Lease)>> {
+        let mut leases = Vec::with_capacity(self.roles.len());
+        for role in &self.roles {
+            let lease = self.generate(client, role).await.inspect_err(|err| {
+                error!(role, error = %format!("{err:#}"), "failed to generate database credentials");
+            })?;
+            leases.push((role.clone(), lease));
+        }
+        Ok(leases)
+    }
+
+    /// Keep the credentials of `role`, generated with `lease`, valid until the task is aborted.
+    ///
+    /// Credentials are rotated at the latest once they reach `max_lifetime`, if set. See
+    /// [`schedule`] for how renewals and rotations are scheduled
+    pub async fn maintain(
+        self,
+        client: Arc<RwLock<VaultClient>>,
+        role: String,
+        mut lease: Lease,
+        max_lifetime: Option<Duration>,
+    ) {
+        let mut generated = Instant::now();
+        // Leases are renewed by the duration they were initially issued for
+        let mut ttl = lease.duration;
+        let mut increment = None;
+        loop {
+            let Some((delay, step)) =
+                schedule(&lease, increment, generated.elapsed(), max_lifetime)
+            else {
+                debug!(role, "database credentials do not expire");
+                return;
+            };
+            tokio::time::sleep(delay).await;
+            if step == Step::Renew {
+                let renewed = renew(&*client.read().await, &lease, ttl).await;
+                match renewed {
+                    Ok(renewed) => {
+                        debug!(
+                            role,
+                            lease_duration = renewed.duration.as_secs(),
+                            "renewed lease of database credentials"
+                        );
+                        lease = renewed;
+                        increment = Some(ttl);
+                        continue;
+                    }
+                    Err(err) => {
+                        warn!(
+                            role,
+                            error = %format!("{err:#}"),
+                            "failed to renew lease of database credentials, rotating them"
+                        );
+                    }
+                }
+            }
+            // Current credentials are kept until new credentials are generated
+            let replaced = lease;
+            lease = loop {
+                let res = self.generate(&*client.read().await, &role).await;
+                match res {
+                    Ok(lease) => break lease,
+                    Err(err) => {
+                        error!(
+                            role,
+                            error = %format!("{err:#}"),
+                            "failed to rotate database credentials"
+                        );
+                        tokio::time::sleep(ROTATION_RETRY_DELAY).await;
+                    }
+                }
+            };
+            generated = Instant::now();
+            ttl = lease.duration;
+            increment = None;
+            // Components read the new credentials from now on
+            if !replaced.id.is_empty() {
+                if let Err(err) = revoke(&*client.read().await, &replaced).await {
+                    warn!(
+                        role,
+                        error = %format!("{err:#}"),
+                        "failed to revoke lease of replaced database credentials"
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Encode credentials like values set via `wrpc:keyvalue/store`
+fn encode_creds(username: &str, password: &str) -> HashMap<String, String> {
+    let engine = base64::engine::general_purpose::STANDARD_NO_PAD;
+    HashMap::from([
+        ("username".to_string(), engine.encode(username)),
+        ("password".to_string(), engine.encode(password)),
+    ])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn creds_paths() {
+        let creds = DatabaseCredentials::new(
+            DEFAULT_DATABASE_MOUNT.into(),
+            vec!["readonly".into(), "readwrite".into()],
+        );
+        assert!(!creds.is_empty());
+        assert_eq!(creds_path("readonly"), "database/creds/readonly");
+        assert!(creds.contains_path("database/creds/readonly"));
+        assert!(creds.contains_path("database/creds/readwrite"));
+        assert!(!creds.contains_path("database/creds/admin"));
+        assert!(!creds.contains_path("readonly"));
+        assert!(DatabaseCredentials::new(DEFAULT_DATABASE_MOUNT.into(), vec![]).is_empty());
+    }
+
+    #[test]
+    fn lease_schedule() {
+        let lease = |secs, renewable| Lease {
+            id: "database/creds/readonly/1".into(),
+            duration: Duration::from_secs(secs),
+            renewable,
+        };
+        let hour = Duration::from_secs(3600);
+
+        // Fresh leases are renewed after two thirds of their duration
+        assert_eq!(
+            schedule(&lease(3600, true), None, Duration::ZERO, None),
+            Some((Duration::from_secs(2400), Step::Renew))
+        );
+        // Renewals extending the lease by the requested increment are followed by renewals
+        assert_eq!(
+            schedule(&lease(3600, true), Some(hour), hour, None),
+            Some((Duration::from_secs(2400), Step::Renew))
+        );
+        // Once renewals are capped by the maximum TTL, credentials are rotated before they expire
+        assert_eq!(
+            schedule(&lease(1800, true), Some(hour), hour * 23, None),
+            Some((Duration::from_secs(1200), Step::Rotate))
+        );
+        // Leases which cannot be renewed are rotated
+        assert_eq!(
+            schedule(&lease(3600, false), None, Duration::ZERO, None),
+            Some((Duration::from_secs(2400), Step::Rotate))
+        );
+        // Credentials are rotated when they reach their maximum lifetime
+        assert_eq!(
+            schedule(&lease(3600, true), None, Duration::ZERO, Some(hour / 2)),
+            Some((hour / 2, Step::Rotate))
+        );
+        assert_eq!(
+            schedule(&lease(3600, true), Some(hour), hour * 2, Some(hour * 2)),
+            Some((Duration::ZERO, Step::Rotate))
+        );
+        assert_eq!(
+            schedule(&lease(3600, true), None, Duration::ZERO, Some(hour * 2)),
+            Some((Duration::from_secs(2400), Step::Renew))
+        );
+        // Credentials without expiry are only rotated when a maximum lifetime is set
+        assert_eq!(schedule(&lease(0, false), None, hour, None), None);
+        assert_eq!(
+            schedule(&lease(0, false), None, hour, Some(hour * 3)),
+            Some((hour * 2, Step::Rotate))
+        );
+    }
+
+    #[tokio::test]
+    async fn rotated_creds() {
+        let creds =
+            DatabaseCredentials::new(DEFAULT_DATABASE_MOUNT.into(), vec!["readonly".into()]);
+        assert_eq!(creds.get("database/creds/readonly").await, None);
+
+        creds.insert("readonly", "v-user-1", "secret-1").await;
+        let engine = base64::engine::general_purpose::STANDARD_NO_PAD;
+        let secret = creds
+            .get("database/creds/readonly")
+            .await
+            .expect("credentials missing");
+        assert_eq!(
+            engine.decode(&secret["username"]).unwrap(),
+            b"v-user-1".to_vec()
+        );
+        assert_eq!(
+            engine.decode(&secret["password"]).unwrap(),
+            b"secret-1".to_vec()
+        );
+
+        // Rotated credentials replace the previous credentials
+        creds.insert("readonly", "v-user-2", "secret-2").await;
+        assert_eq!(
+            creds.get("database/creds/readonly").await,
+            Some(encode_creds("v-user-2", "secret-2"))
+        );
+    }
+}
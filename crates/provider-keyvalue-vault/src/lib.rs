@@ -1,4 +1,5 @@
 pub(crate) mod config;
+pub(crate) mod database;
 
 use core::str;
 use core::time::Duration;
@@ -20,7 +21,8 @@ use wasmcloud_provider_sdk::{
 };
 use wasmcloud_provider_sdk::{initialize_observability, serve_provider_exports};
 
-use crate::config::Config;
+use crate::config::{Auth, Config};
+use crate::database::DatabaseCredentials;
 
 mod bindings {
     wit_bindgen_wrpc::generate!({
@@ -47,11 +49,15 @@ pub async fn run() -> anyhow::Result<()> {
 /// Vault client connection information.
 #[derive(Clone)]
 pub struct Client {
-    inner: Arc<vaultrs::client::VaultClient>,
+    inner: Arc<RwLock<VaultClient>>,
+    auth: Auth,
     namespace: String,
     token_increment_ttl: String,
     token_refresh_interval: Duration,
     renew_task: Arc<Mutex<Option<JoinHandle<()>>>>,
+    database_creds: DatabaseCredentials,
+    database_creds_rotation_interval: Option<Duration>,
+    rotate_tasks: Arc<Mutex<Vec<JoinHandle<()>>>>,
 }
 
 impl Client {
@@ -59,9 +65,14 @@ impl Client {
     ///
     /// Note that this constructor does not attempt to connect to the vault server,
     /// so the vault server does not need to be running at the time a `LinkDefinition` to this provider is created.
+    /// Clients using AppRole authentication must [`Self::login`] before use.
     pub fn new(config: Config) -> Result<Self, vaultrs::error::ClientError> {
+        let token = match &config.auth {
+            Auth::Token(token) => token.clone(),
+            Auth::AppRole { .. } => String::new(),
+        };
         let client = VaultClient::new(VaultClientSettings {
-            token: config.token,
+            token,
             address: config.addr,
             ca_certs: config.certs,
             verify: false,
@@ -72,7 +83,8 @@ impl Client {
             identity: None,
         })?;
         Ok(Self {
-            inner: Arc::new(client),
+            inner: Arc::new(RwLock::new(client)),
+            auth: config.auth,
             namespace: config.mount,
             token_increment_ttl: config
                 .token_increment_ttl
@@ -81,12 +93,19 @@ impl Client {
                 .token_refresh_interval
                 .unwrap_or(TOKEN_REFRESH_INTERVAL),
             renew_task: Arc::default(),
+            database_creds: DatabaseCredentials::new(config.database_mount, config.database_roles),
+            database_creds_rotation_interval: config.database_creds_rotation_interval,
+            rotate_tasks: Arc::default(),
         })
     }
 
     /// Reads value of secret using namespace and key path
     pub async fn read_secret(&self, path: &str) -> Result<Option<HashMap<String, String>>> {
-        match vaultrs::kv2::read(self.inner.as_ref(), &self.namespace, path).await {
+        if self.database_creds.contains_path(path) {
+            return Ok(self.database_creds.get(path).await);
+        }
+        let client = self.inner.read().await;
+        match vaultrs::kv2::read(&*client, &self.namespace, path).await {
             Err(vaultrs::error::ClientError::APIError {
                 code: 404,
                 errors: _,
@@ -104,7 +123,12 @@ impl Client {
 
     /// Writes value of secret using namespace and key path
     pub async fn write_secret(&self, path: &str, data: &HashMap<String, String>) -> Result<()> {
-        let md = vaultrs::kv2::set(self.inner.as_ref(), &self.namespace, path, data)
+        if self.database_creds.contains_path(path) {
+            warn!(path, "attempted to write dynamic database credentials");
+            return Err(keyvalue::store::Error::AccessDenied);
+        }
+        let client = self.inner.read().await;
+        let md = vaultrs::kv2::set(&*client, &self.namespace, path, data)
             .await
             .map_err(|err| {
                 error!(error = %err, "failed to write secret");
@@ -117,27 +141,73 @@ impl Client {
         Ok(())
     }
 
-    /// Sets up a background task to renew the token at the configured interval. This function
-    /// attempts to lock the `renew_task` mutex and will deadlock if called without first ensuring
-    /// the lock is available.
+    /// Logs in using AppRole authentication, if configured, replacing the token of the client.
+    /// Clients authenticated by a static token are not affected
+    pub async fn login(&self) -> anyhow::Result<()> {
+        login(&self.inner, &self.auth).await.map(|_| ())
+    }
+
+    /// Sets up a background task to renew the token at the configured interval, or before half of
+    /// its lease has elapsed, whichever is sooner. Tokens issued via AppRole, which can no longer
+    /// be renewed, are replaced by logging in again. This function attempts to lock the
+    /// `renew_task` mutex and will deadlock if called without first ensuring the lock is available.
     pub async fn set_renewal(&self) {
         let mut renew_task = self.renew_task.lock().await;
         if let Some(handle) = renew_task.take() {
             handle.abort();
         }
         let client = self.inner.clone();
+        let auth = self.auth.clone();
         let interval = self.token_refresh_interval;
         let ttl = self.token_increment_ttl.clone();
 
         *renew_task = Some(tokio::spawn(async move {
-            let mut next_interval = tokio::time::interval(interval);
+            let mut delay = Duration::ZERO;
             loop {
-                next_interval.tick().await;
+                tokio::time::sleep(delay).await;
                 // NOTE(brooksmtownsend): Errors are appropriately logged in the function
-                let _ = renew_self(&client, ttl.as_str()).await;
+                let renewed = renew_self(&*client.read().await, ttl.as_str()).await;
+                let lease = match renewed {
+                    Ok(lease) => Some(lease),
+                    Err(_) if matches!(auth, Auth::AppRole { .. }) => {
+                        login(&client, &auth).await.ok().flatten()
+                    }
+                    Err(_) => None,
+                };
+                delay = match lease {
+                    Some(lease) if !lease.is_zero() => interval.min(lease / 2),
+                    _ => interval,
+                };
             }
         }));
     }
+
+    /// Generates dynamic database credentials for the configured roles and sets up a background
+    /// task per role renewing their lease, and regenerating them once the lease cannot be renewed
+    /// anymore or the configured rotation interval elapsed. Like [`Self::set_renewal`], this
+    /// function locks the `rotate_tasks` mutex.
+    pub async fn set_creds_rotation(&self) -> anyhow::Result<()> {
+        if self.database_creds.is_empty() {
+            return Ok(());
+        }
+        let mut rotate_tasks = self.rotate_tasks.lock().await;
+        for handle in rotate_tasks.drain(..) {
+            handle.abort();
+        }
+        let leases = self
+            .database_creds
+            .generate_all(&*self.inner.read().await)
+            .await?;
+        for (role, lease) in leases {
+            rotate_tasks.push(tokio::spawn(self.database_creds.clone().maintain(
+                self.inner.clone(),
+                role,
+                lease,
+                self.database_creds_rotation_interval,
+            )));
+        }
+        Ok(())
+    }
 }
 
 impl Drop for Client {
@@ -148,16 +218,49 @@ impl Drop for Client {
                 handle.abort();
             }
         }
+        if let Ok(mut rotate_tasks) = self.rotate_tasks.try_lock() {
+            for handle in rotate_tasks.drain(..) {
+                handle.abort();
+            }
+        }
     }
 }
 
-/// Helper function to renew a client's token, incrementing the validity by `increment`
+/// Helper function to log in using AppRole authentication, replacing the token of `client`.
+/// Returns the lease duration of the issued token, or `None` if `auth` is not AppRole
+async fn login(client: &RwLock<VaultClient>, auth: &Auth) -> anyhow::Result<Option<Duration>> {
+    let Auth::AppRole {
+        mount,
+        role_id,
+        secret_id,
+    } = auth
+    else {
+        return Ok(None);
+    };
+    debug!(mount, "logging in with AppRole");
+    let info = vaultrs::auth::approle::login(&*client.read().await, mount, role_id, secret_id)
+        .await
+        .map_err(|e| {
+            error!("error logging in with AppRole: {}", e);
+            anyhow!(e).context("failed to log in with AppRole")
+        })?;
+    client.write().await.set_token(&info.client_token);
+    info!(
+        lease_duration = info.lease_duration,
+        accessor = %info.accessor,
+        "logged in with AppRole"
+    );
+    Ok(Some(Duration::from_secs(info.lease_duration)))
+}
+
+/// Helper function to renew a client's token, incrementing the validity by `increment`. Returns
+/// the lease duration of the renewed token
 async fn renew_self(
     client: &VaultClient,
     increment: &str,
-) -> Result<(), vaultrs::error::ClientError> {
+) -> Result<Duration, vaultrs::error::ClientError> {
     debug!("renewing token");
-    client.renew(Some(increment)).await.map_err(|e| {
+    let auth = client.renew(Some(increment)).await.map_err(|e| {
         error!("error renewing self token: {}", e);
         e
     })?;
@@ -169,7 +272,7 @@ async fn renew_self(
 
     let expire_time = info.expire_time.unwrap_or_else(|| "None".to_string());
     info!(%expire_time, accessor = %info.accessor, "renewed token");
-    Ok(())
+    Ok(Duration::from_secs(auth.lease_duration))
 }
 
 /// Redis KV provider implementation which utilizes [Hashicorp Vault](https://developer.hashicorp.com/vault/docs)
@@ -440,7 +543,23 @@ impl Provider for KvVaultProvider {
                 return Err(anyhow!(e).context("failed to create new client config"));
             }
         };
+        if let Err(e) = client.login().await {
+            error!(
+                %source_id,
+                %link_name,
+                "failed to authenticate with Vault: {e:#}",
+            );
+            return Err(e);
+        }
         client.set_renewal().await;
+        if let Err(e) = client.set_creds_rotation().await {
+            error!(
+                %source_id,
+                %link_name,
+                "failed to generate database credentials: {e:#}",
+            );
+            return Err(e);
+        }
 
         let mut update_map = self.components.write().await;
         update_map.insert(source_id.to_string(), Arc::new(client));
@@ -0,0 +1,189 @@
+//! Configuration of Kafka connections of links
+
+use std::collections::{BTreeSet, HashMap};
+
+use anyhow::{Context as _, Result};
+use tracing::warn;
+use wasmcloud_provider_sdk::LinkConfig;
+
+/// Config value for hosts, accepted as a comma separated string
+const KAFKA_HOSTS_CONFIG_KEY: &str = "hosts";
+const DEFAULT_HOST: &str = "127.0.0.1:9092";
+
+/// Config value for topics, accepted as a comma separated string
+const KAFKA_TOPIC_CONFIG_KEY: &str = "topic";
+
+/// Config value for specifying a consumer group
+const KAFKA_CONSUMER_GROUP_CONFIG_KEY: &str = "consumer_group";
+
+/// Config value for specifying one or more comma delimited partition(s)
+/// to use when consuming values
+const KAFKA_CONSUMER_PARTITIONS_CONFIG_KEY: &str = "consumer_partitions";
+
+/// Config value for specifying one or more comma delimited partition(s)
+/// to use when producing values
+const KAFKA_PRODUCER_PARTITIONS_CONFIG_KEY: &str = "producer_partitions";
+
+/// Config value for the topic that replies to requests of the component are received on
+const KAFKA_REPLY_TOPIC_CONFIG_KEY: &str = "reply_topic";
+
+/// Configuration of the Kafka connection of a link
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConnectionConfig {
+    /// Bootstrap server hosts, used for both the consumer and producer connections
+    pub hosts: Vec<String>,
+    /// Topics that the component is subscribed to
+    pub topics: Vec<String>,
+    /// Consumer group of the subscriptions, defaults to the ID of the component
+    pub consumer_group: String,
+    /// Partitions of the topics that are consumed. If empty, partitions are assigned by the
+    /// consumer group
+    pub consumer_partitions: Vec<i32>,
+    /// Partitions that messages published by the component are sent to. If empty, messages
+    /// are distributed across all partitions of the topic
+    pub producer_partitions: Vec<i32>,
+    /// Topic that replies to requests of the component are received on
+    pub reply_topic: Option<String>,
+}
+
+impl ConnectionConfig {
+    /// Build a [`ConnectionConfig`] from the link configuration of the component `source_id`
+    pub fn from_link_config(link_config: &LinkConfig) -> Result<Self> {
+        let config = link_config.config;
+        Ok(Self {
+            hosts: extract_hosts_from_link_config(link_config),
+            topics: split(config.get(KAFKA_TOPIC_CONFIG_KEY))
+                .map(String::from)
+                .collect(),
+            consumer_group: config
+                .get(KAFKA_CONSUMER_GROUP_CONFIG_KEY)
+                .map(|group| group.trim())
+                .filter(|group| !group.is_empty())
+                .unwrap_or(link_config.source_id)
+                .to_string(),
+            consumer_partitions: parse_partitions(config, KAFKA_CONSUMER_PARTITIONS_CONFIG_KEY)?,
+            producer_partitions: parse_partitions(config, KAFKA_PRODUCER_PARTITIONS_CONFIG_KEY)?,
+            reply_topic: config
+                .get(KAFKA_REPLY_TOPIC_CONFIG_KEY)
+                .map(|topic| topic.trim())
+                .filter(|topic| !topic.is_empty())
+                .map(String::from),
+        })
+    }
+}
+
+/// Split a comma separated value, skipping empty values
+fn split(value: Option<&String>) -> impl Iterator<Item = &str> {
+    value
+        .map(String::as_str)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+/// Parse comma separated partitions found under `key`, deduplicating them
+fn parse_partitions(config: &HashMap<String, String>, key: &str) -> Result<Vec<i32>> {
+    let partitions = split(config.get(key))
+        .map(|partition| {
+            partition
+                .parse()
+                .with_context(|| format!("invalid partition `{partition}` in `{key}`"))
+        })
+        .collect::<Result<BTreeSet<i32>>>()?;
+    Ok(partitions.into_iter().collect())
+}
+
+/// Extract hostnames (separated by commas, found under key [`KAFKA_HOSTS_CONFIG_KEY`]) from config hashmap
+///
+/// If no hostnames are found [`DEFAULT_HOST`] is split (by ',') and returned.
+fn extract_hosts_from_link_config(link_config: &LinkConfig) -> Vec<String> {
+    // Collect comma separated hosts into a Vec<String>
+    //
+    // This value could come from either secrets or regular config (for backwards compat)
+    // but we want to make sure we warn if it is pulled from config.
+    let maybe_hosts = link_config
+        .secrets
+        .iter()
+        .find_map(|(k, v)| {
+            match (k, v.as_string()) {
+                (k, Some(v)) if *k == KAFKA_HOSTS_CONFIG_KEY  => Some(String::from(v)),
+                _ => None,
+            }
+        })
+    .or_else(|| {
+        warn!("secret value [{KAFKA_HOSTS_CONFIG_KEY}] was not found in secrets. Prefer storing sensitive values in secrets");
+        link_config
+            .config
+            .iter()
+            .find_map(|(k, v)| {
+                if *k == KAFKA_HOSTS_CONFIG_KEY {
+                    Some(v.to_string())
+                } else {
+                    None
+                }
+            })
+    });
+
+    maybe_hosts
+        .unwrap_or_else(|| DEFAULT_HOST.to_string())
+        .trim()
+        .split(',')
+        .map(std::string::ToString::to_string)
+        .collect::<Vec<String>>()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn connection_config(config: &[(&str, &str)]) -> Result<ConnectionConfig> {
+        let config: HashMap<_, _> = config
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        ConnectionConfig::from_link_config(&LinkConfig {
+            source_id: "component",
+            target_id: "provider",
+            link_name: "default",
+            config: &config,
+            secrets: &HashMap::new(),
+            wit_metadata: (&"wasmcloud".to_string(), &"messaging".to_string(), &vec![]),
+        })
+    }
+
+    #[test]
+    fn parse_config() {
+        assert_eq!(
+            connection_config(&[]).unwrap(),
+            ConnectionConfig {
+                hosts: vec![DEFAULT_HOST.into()],
+                topics: vec![],
+                consumer_group: "component".into(),
+                consumer_partitions: vec![],
+                producer_partitions: vec![],
+                reply_topic: None,
+            }
+        );
+        assert_eq!(
+            connection_config(&[
+                ("hosts", "kafka-0:9092,kafka-1:9092"),
+                ("topic", "orders, payments,"),
+                ("consumer_group", "billing"),
+                ("consumer_partitions", "2,0,2"),
+                ("producer_partitions", "1"),
+                ("reply_topic", "billing.replies"),
+            ])
+            .unwrap(),
+            ConnectionConfig {
+                hosts: vec!["kafka-0:9092".into(), "kafka-1:9092".into()],
+                topics: vec!["orders".into(), "payments".into()],
+                consumer_group: "billing".into(),
+                consumer_partitions: vec![0, 2],
+                producer_partitions: vec![1],
+                reply_topic: Some("billing.replies".into()),
+            }
+        );
+        assert!(connection_config(&[("consumer_partitions", "one")]).is_err());
+    }
+}
@@ -0,0 +1,665 @@
+//! Membership of Kafka consumer groups
+//!
+//! `rskafka` does not implement consumer groups, so members talk to the coordinator of their group
+//! directly. Partitions are assigned by the leader of the group the way the `range` assignor of
+//! Kafka consumers does, so that a group may be shared with other Kafka consumers.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+use anyhow::{anyhow, ensure, Context as _, Result};
+use bytes::{Buf as _, BufMut as _, Bytes, BytesMut};
+use rskafka::client::Client;
+use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+use tokio::net::TcpStream;
+use tokio::time::Duration;
+use tracing::{debug, warn};
+
+/// Partitions assigned to a member, keyed by topic
+pub type Assignment = BTreeMap<String, Vec<i32>>;
+
+/// Offsets of the next records to consume, keyed by topic and partition
+pub type Offsets = HashMap<(String, i32), i64>;
+
+/// Interval of the heartbeats sent by members to the coordinator of their group
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Number of milliseconds after which the coordinator removes members that stopped sending heartbeats
+const SESSION_TIMEOUT_MS: i32 = 30_000;
+
+/// Number of milliseconds members are given to rejoin their group when it is rebalanced
+const REBALANCE_TIMEOUT_MS: i32 = 60_000;
+
+/// Time to wait for responses of brokers. Joining a group takes up to the rebalance timeout
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Maximum size of the responses accepted from brokers
+const MAX_RESPONSE_SIZE: usize = 16 << 20;
+
+/// Client ID sent along with requests
+const CLIENT_ID: &str = "wasmcloud-messaging-kafka";
+
+/// Protocol type of groups of Kafka consumers
+const CONSUMER_PROTOCOL_TYPE: &str = "consumer";
+
+/// Name of the assignor of partitions to members
+const RANGE_ASSIGNOR: &str = "range";
+
+/// Version of the subscriptions and assignments of the consumer protocol
+const CONSUMER_PROTOCOL_VERSION: i16 = 0;
+
+// Keys and versions of the APIs used by members
+const OFFSET_COMMIT: (i16, i16) = (8, 2);
+const OFFSET_FETCH: (i16, i16) = (9, 1);
+const FIND_COORDINATOR: (i16, i16) = (10, 0);
+const JOIN_GROUP: (i16, i16) = (11, 2);
+const HEARTBEAT: (i16, i16) = (12, 0);
+const LEAVE_GROUP: (i16, i16) = (13, 0);
+const SYNC_GROUP: (i16, i16) = (14, 0);
+
+/// Error code returned by a broker
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ErrorCode(pub i16);
+
+impl ErrorCode {
+    const NONE: Self = Self(0);
+    /// The coordinator does not know the member, which must join the group as a new member
+    pub const UNKNOWN_MEMBER_ID: Self = Self(25);
+    /// The group is rebalancing, the member must rejoin the group
+    pub const REBALANCE_IN_PROGRESS: Self = Self(27);
+
+    /// Fail with error `code`, unless it is [`Self::NONE`]
+    fn check(code: i16) -> Result<()> {
+        if code == Self::NONE.0 {
+            Ok(())
+        } else {
+            Err(Self(code).into())
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "broker returned error code {}", self.0)
+    }
+}
+
+impl std::error::Error for ErrorCode {}
+
+/// Encoder of request bodies
+#[derive(Default)]
+struct Encoder(BytesMut);
+
+impl Encoder {
+    fn i16(&mut self, v: i16) -> &mut Self {
+        self.0.put_i16(v);
+        self
+    }
+
+    fn i32(&mut self, v: i32) -> &mut Self {
+        self.0.put_i32(v);
+        self
+    }
+
+    fn i64(&mut self, v: i64) -> &mut Self {
+        self.0.put_i64(v);
+        self
+    }
+
+    fn string(&mut self, v: &str) -> Result<&mut Self> {
+        let len = i16::try_from(v.len()).with_context(|| format!("`{v}` is too long"))?;
+        self.0.put_i16(len);
+        self.0.put_slice(v.as_bytes());
+        Ok(self)
+    }
+
+    fn null_string(&mut self) -> &mut Self {
+        self.i16(-1)
+    }
+
+    fn bytes(&mut self, v: &[u8]) -> Result<&mut Self> {
+        let len = i32::try_from(v.len()).context("byte array is too long")?;
+        self.0.put_i32(len);
+        self.0.put_slice(v);
+        Ok(self)
+    }
+
+    fn null_bytes(&mut self) -> &mut Self {
+        self.i32(-1)
+    }
+
+    fn array<T>(
+        &mut self,
+        items: impl ExactSizeIterator<Item = T>,
+        mut f: impl FnMut(&mut Self, T) -> Result<()>,
+    ) -> Result<&mut Self> {
+        let len = i32::try_from(items.len()).context("array is too long")?;
+        self.0.put_i32(len);
+        for item in items {
+            f(self, item)?;
+        }
+        Ok(self)
+    }
+
+    fn finish(self) -> Bytes {
+        self.0.freeze()
+    }
+}
+
+/// Decoder of response bodies
+struct Decoder(Bytes);
+
+impl Decoder {
+    fn take(&mut self, n: usize) -> Result<Bytes> {
+        ensure!(self.0.len() >= n, "unexpected end of response");
+        Ok(self.0.split_to(n))
+    }
+
+    fn i16(&mut self) -> Result<i16> {
+        Ok(self.take(2)?.get_i16())
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(self.take(4)?.get_i32())
+    }
+
+    fn i64(&mut self) -> Result<i64> {
+        Ok(self.take(8)?.get_i64())
+    }
+
+    /// Decode a string, null strings are decoded as empty strings
+    fn string(&mut self) -> Result<String> {
+        let Ok(len) = usize::try_from(self.i16()?) else {
+            return Ok(String::new());
+        };
+        String::from_utf8(self.take(len)?.to_vec()).context("string is not valid UTF-8")
+    }
+
+    /// Decode a byte array, null byte arrays are decoded as empty byte arrays
+    fn bytes(&mut self) -> Result<Bytes> {
+        let Ok(len) = usize::try_from(self.i32()?) else {
+            return Ok(Bytes::new());
+        };
+        self.take(len)
+    }
+
+    fn array<T>(&mut self, mut f: impl FnMut(&mut Self) -> Result<T>) -> Result<Vec<T>> {
+        let len = self.i32()?;
+        (0..len.max(0)).map(|_| f(self)).collect()
+    }
+}
+
+/// Connection to a broker
+struct Connection {
+    stream: TcpStream,
+    /// ID of the last request sent
+    correlation_id: i32,
+}
+
+impl Connection {
+    async fn connect(addr: &str) -> Result<Self> {
+        let stream = TcpStream::connect(addr)
+            .await
+            .with_context(|| format!("failed to connect to broker `{addr}`"))?;
+        Ok(Self {
+            stream,
+            correlation_id: 0,
+        })
+    }
+
+    /// Send request `body` to API `(key, version)`, returning the body of the response
+    async fn request(&mut self, (key, version): (i16, i16), body: Bytes) -> Result<Decoder> {
+        tokio::time::timeout(REQUEST_TIMEOUT, self.roundtrip(key, version, body))
+            .await
+            .context("timed out waiting for response of broker")?
+    }
+
+    async fn roundtrip(&mut self, key: i16, version: i16, body: Bytes) -> Result<Decoder> {
+        self.correlation_id = self.correlation_id.wrapping_add(1);
+        let mut header = Encoder::default();
+        header
+            .i16(key)
+            .i16(version)
+            .i32(self.correlation_id)
+            .string(CLIENT_ID)?;
+        let header = header.finish();
+        let len = i32::try_from(header.len() + body.len()).context("request is too large")?;
+        self.stream.write_i32(len).await?;
+        self.stream.write_all(&header).await?;
+        self.stream.write_all(&body).await?;
+        self.stream
+            .flush()
+            .await
+            .context("failed to send request")?;
+
+        let len = self
+            .stream
+            .read_i32()
+            .await
+            .context("failed to read response")?;
+        let len = usize::try_from(len)
+            .ok()
+            .filter(|len| *len <= MAX_RESPONSE_SIZE)
+            .with_context(|| format!("invalid response size {len}"))?;
+        let mut buf = vec![0; len];
+        self.stream
+            .read_exact(&mut buf)
+            .await
+            .context("failed to read response")?;
+        let mut res = Decoder(buf.into());
+        ensure!(
+            res.i32()? == self.correlation_id,
+            "response does not correlate to the request"
+        );
+        Ok(res)
+    }
+}
+
+/// Look up the address of the coordinator of a group through broker `host`
+async fn find_coordinator(host: &str, body: Bytes) -> Result<String> {
+    let mut res = Connection::connect(host)
+        .await?
+        .request(FIND_COORDINATOR, body)
+        .await?;
+    ErrorCode::check(res.i16()?)?;
+    let _node_id = res.i32()?;
+    let host = res.string()?;
+    let port = res.i32()?;
+    Ok(format!("{host}:{port}"))
+}
+
+/// Connect to the coordinator of `group`, looking it up through the bootstrap `hosts`
+async fn connect_coordinator(hosts: &[String], group: &str) -> Result<Connection> {
+    let mut body = Encoder::default();
+    body.string(group)?;
+    let body = body.finish();
+    let mut last_err = None;
+    for host in hosts {
+        match find_coordinator(host.trim(), body.clone()).await {
+            Ok(addr) => return Connection::connect(&addr).await,
+            Err(err) => {
+                debug!(?err, host, "failed to find group coordinator");
+                last_err = Some(err);
+            }
+        }
+    }
+    Err(last_err.unwrap_or_else(|| anyhow!("no bootstrap hosts configured")))
+        .with_context(|| format!("failed to find coordinator of group `{group}`"))
+}
+
+/// Encode the subscription of a member to `topics`
+fn encode_subscription(topics: &[String]) -> Result<Bytes> {
+    let mut enc = Encoder::default();
+    enc.i16(CONSUMER_PROTOCOL_VERSION)
+        .array(topics.iter(), |enc, topic| {
+            enc.string(topic)?;
+            Ok(())
+        })?
+        .null_bytes();
+    Ok(enc.finish())
+}
+
+/// Decode the topics a member is subscribed to, ignoring fields of later versions of the protocol
+fn decode_subscription(subscription: Bytes) -> Result<Vec<String>> {
+    let mut dec = Decoder(subscription);
+    let _version = dec.i16()?;
+    dec.array(Decoder::string)
+}
+
+/// Encode the partitions assigned to a member
+fn encode_assignment(assignment: &Assignment) -> Result<Bytes> {
+    let mut enc = Encoder::default();
+    enc.i16(CONSUMER_PROTOCOL_VERSION)
+        .array(assignment.iter(), |enc, (topic, partitions)| {
+            enc.string(topic)?
+                .array(partitions.iter(), |enc, partition| {
+                    enc.i32(*partition);
+                    Ok(())
+                })?;
+            Ok(())
+        })?
+        .null_bytes();
+    Ok(enc.finish())
+}
+
+/// Decode the partitions assigned to a member, ignoring fields of later versions of the protocol.
+/// An empty assignment carries no partitions
+fn decode_assignment(assignment: Bytes) -> Result<Assignment> {
+    if assignment.is_empty() {
+        return Ok(Assignment::new());
+    }
+    let mut dec = Decoder(assignment);
+    let _version = dec.i16()?;
+    let topics = dec.array(|dec| Ok((dec.string()?, dec.array(Decoder::i32)?)))?;
+    Ok(topics.into_iter().collect())
+}
+
+/// Assign the `partitions` of every topic to the members subscribed to it, in contiguous ranges
+/// ordered by member ID. Every member receives an assignment, even if it is empty
+fn assign_ranges(
+    subscriptions: &BTreeMap<String, Vec<String>>,
+    partitions: &HashMap<String, Vec<i32>>,
+) -> BTreeMap<String, Assignment> {
+    let mut assignments: BTreeMap<String, Assignment> = subscriptions
+        .keys()
+        .map(|id| (id.clone(), Assignment::new()))
+        .collect();
+    for (topic, partitions) in partitions {
+        let members: Vec<&String> = subscriptions
+            .iter()
+            .filter(|(_, topics)| topics.contains(topic))
+            .map(|(id, _)| id)
+            .collect();
+        if members.is_empty() {
+            continue;
+        }
+        let mut partitions = partitions.clone();
+        partitions.sort_unstable();
+        let per_member = partitions.len() / members.len();
+        let extra = partitions.len() % members.len();
+        let mut partitions = partitions.into_iter();
+        for (i, id) in members.into_iter().enumerate() {
+            let assigned: Vec<i32> = partitions
+                .by_ref()
+                .take(per_member + usize::from(i < extra))
+                .collect();
+            if !assigned.is_empty() {
+                assignments
+                    .entry(id.clone())
+                    .or_default()
+                    .insert(topic.clone(), assigned);
+            }
+        }
+    }
+    assignments
+}
+
+/// Member of a consumer group, along with the partitions assigned to it
+pub struct GroupMember {
+    coordinator: Connection,
+    group: String,
+    /// ID assigned to the member by the coordinator, empty if the member did not join the group
+    member_id: String,
+    /// Generation of the group the member joined, `-1` if the member did not join the group
+    generation_id: i32,
+    assignment: Assignment,
+}
+
+impl GroupMember {
+    /// Join `group` through the bootstrap `hosts`, subscribing to `topics`. `member_id` is the ID
+    /// assigned to the member when it last joined the group, if any.
+    ///
+    /// If the member is elected leader of the group, it assigns the partitions of the topics
+    /// subscribed by all members, which are listed through `client`
+    pub async fn join(
+        client: &Client,
+        hosts: &[String],
+        group: &str,
+        member_id: &str,
+        topics: &[String],
+    ) -> Result<Self> {
+        let mut coordinator = connect_coordinator(hosts, group).await?;
+        let subscription = encode_subscription(topics)?;
+        let mut body = Encoder::default();
+        body.string(group)?
+            .i32(SESSION_TIMEOUT_MS)
+            .i32(REBALANCE_TIMEOUT_MS)
+            .string(member_id)?
+            .string(CONSUMER_PROTOCOL_TYPE)?
+            .array([RANGE_ASSIGNOR].into_iter(), |body, name| {
+                body.string(name)?.bytes(&subscription)?;
+                Ok(())
+            })?;
+        let mut res = coordinator.request(JOIN_GROUP, body.finish()).await?;
+        let _throttle_time_ms = res.i32()?;
+        ErrorCode::check(res.i16()?).context("failed to join group")?;
+        let generation_id = res.i32()?;
+        let _protocol = res.string()?;
+        let leader = res.string()?;
+        let member_id = res.string()?;
+        let members = res.array(|res| Ok((res.string()?, res.bytes()?)))?;
+
+        // Only the leader assigns partitions, the other members receive their assignment from the
+        // coordinator
+        let assignments = if leader == member_id {
+            let subscriptions = members
+                .into_iter()
+                .map(|(id, subscription)| Ok((id, decode_subscription(subscription)?)))
+                .collect::<Result<BTreeMap<_, _>>>()?;
+            let mut partitions = HashMap::new();
+            for topic in subscriptions.values().flatten().collect::<BTreeSet<_>>() {
+                match crate::topic_partitions(client, topic).await {
+                    Ok(p) => {
+                        partitions.insert(topic.clone(), p);
+                    }
+                    Err(err) => warn!(?err, topic, "failed to list partitions of topic"),
+                }
+            }
+            debug!(group, generation_id, "assigning partitions as group leader");
+            assign_ranges(&subscriptions, &partitions)
+        } else {
+            BTreeMap::new()
+        };
+        let mut body = Encoder::default();
+        body.string(group)?
+            .i32(generation_id)
+            .string(&member_id)?
+            .array(assignments.iter(), |body, (id, assignment)| {
+                body.string(id)?.bytes(&encode_assignment(assignment)?)?;
+                Ok(())
+            })?;
+        let mut res = coordinator.request(SYNC_GROUP, body.finish()).await?;
+        ErrorCode::check(res.i16()?).context("failed to sync group")?;
+        let assignment = decode_assignment(res.bytes()?)?;
+        Ok(Self {
+            coordinator,
+            group: group.to_string(),
+            member_id,
+            generation_id,
+            assignment,
+        })
+    }
+
+    /// Consume `assignment` without joining `group` through the bootstrap `hosts`, the group is
+    /// only used to commit offsets
+    pub async fn assigned(hosts: &[String], group: &str, assignment: Assignment) -> Result<Self> {
+        Ok(Self {
+            coordinator: connect_coordinator(hosts, group).await?,
+            group: group.to_string(),
+            member_id: String::new(),
+            generation_id: -1,
+            assignment,
+        })
+    }
+
+    /// Whether the member joined the group
+    fn joined(&self) -> bool {
+        self.generation_id >= 0
+    }
+
+    pub fn member_id(&self) -> &str {
+        &self.member_id
+    }
+
+    pub fn assignment(&self) -> &Assignment {
+        &self.assignment
+    }
+
+    /// Notify the coordinator that the member is alive, failing if the group is rebalancing
+    pub async fn heartbeat(&mut self) -> Result<()> {
+        if !self.joined() {
+            return Ok(());
+        }
+        let mut body = Encoder::default();
+        body.string(&self.group)?
+            .i32(self.generation_id)
+            .string(&self.member_id)?;
+        let mut res = self.coordinator.request(HEARTBEAT, body.finish()).await?;
+        ErrorCode::check(res.i16()?).context("failed to send heartbeat")
+    }
+
+    /// Fetch the offsets committed to the group for the assigned partitions. Partitions without
+    /// committed offsets are omitted
+    pub async fn committed_offsets(&mut self) -> Result<Offsets> {
+        let mut body = Encoder::default();
+        body.string(&self.group)?
+            .array(self.assignment.iter(), |body, (topic, partitions)| {
+                body.string(topic)?
+                    .array(partitions.iter(), |body, partition| {
+                        body.i32(*partition);
+                        Ok(())
+                    })?;
+                Ok(())
+            })?;
+        let mut res = self
+            .coordinator
+            .request(OFFSET_FETCH, body.finish())
+            .await?;
+        let topics = res.array(|res| {
+            let topic = res.string()?;
+            let partitions = res.array(|res| {
+                let partition = res.i32()?;
+                let offset = res.i64()?;
+                let _metadata = res.string()?;
+                Ok((partition, offset, res.i16()?))
+            })?;
+            Ok((topic, partitions))
+        })?;
+        let mut offsets = Offsets::new();
+        for (topic, partitions) in topics {
+            for (partition, offset, error) in partitions {
+                ErrorCode::check(error).with_context(|| {
+                    format!("failed to fetch offset of partition [{partition}] of topic `{topic}`")
+                })?;
+                // Partitions without committed offsets have an offset of `-1`
+                if offset >= 0 {
+                    offsets.insert((topic.clone(), partition), offset);
+                }
+            }
+        }
+        Ok(offsets)
+    }
+
+    /// Commit the `offsets` of the next records to consume to the group
+    pub async fn commit(&mut self, offsets: &Offsets) -> Result<()> {
+        let mut topics: BTreeMap<&str, Vec<(i32, i64)>> = BTreeMap::new();
+        for ((topic, partition), offset) in offsets {
+            topics
+                .entry(topic.as_str())
+                .or_default()
+                .push((*partition, *offset));
+        }
+        let mut body = Encoder::default();
+        body.string(&self.group)?
+            .i32(self.generation_id)
+            .string(&self.member_id)?
+            // Retain the offsets as long as configured on the broker
+            .i64(-1)
+            .array(topics.iter(), |body, (topic, partitions)| {
+                body.string(topic)?
+                    .array(partitions.iter(), |body, (partition, offset)| {
+                        body.i32(*partition).i64(*offset).null_string();
+                        Ok(())
+                    })?;
+                Ok(())
+            })?;
+        let mut res = self
+            .coordinator
+            .request(OFFSET_COMMIT, body.finish())
+            .await?;
+        let topics = res.array(|res| {
+            let topic = res.string()?;
+            Ok((topic, res.array(|res| Ok((res.i32()?, res.i16()?)))?))
+        })?;
+        for (topic, partitions) in topics {
+            for (partition, error) in partitions {
+                ErrorCode::check(error).with_context(|| {
+                    format!("failed to commit offset of partition [{partition}] of topic `{topic}`")
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Leave the group, so that the partitions of the member are reassigned without waiting for
+    /// its session to time out
+    pub async fn leave(mut self) -> Result<()> {
+        if !self.joined() {
+            return Ok(());
+        }
+        let mut body = Encoder::default();
+        body.string(&self.group)?.string(&self.member_id)?;
+        let mut res = self.coordinator.request(LEAVE_GROUP, body.finish()).await?;
+        ErrorCode::check(res.i16()?).context("failed to leave group")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn range_assignment() {
+        let subscriptions: BTreeMap<String, Vec<String>> = BTreeMap::from([
+            ("a".into(), vec!["orders".into(), "payments".into()]),
+            ("b".into(), vec!["orders".into()]),
+            ("c".into(), vec!["orders".into()]),
+        ]);
+        let partitions: HashMap<String, Vec<i32>> = HashMap::from([
+            ("orders".into(), vec![3, 0, 1, 2]),
+            ("payments".into(), vec![0, 1]),
+            ("refunds".into(), vec![0]),
+        ]);
+        let assignments = assign_ranges(&subscriptions, &partitions);
+        assert_eq!(assignments.len(), 3);
+        assert_eq!(
+            assignments["a"],
+            Assignment::from([
+                ("orders".into(), vec![0, 1]),
+                ("payments".into(), vec![0, 1])
+            ])
+        );
+        assert_eq!(
+            assignments["b"],
+            Assignment::from([("orders".into(), vec![2])])
+        );
+        assert_eq!(
+            assignments["c"],
+            Assignment::from([("orders".into(), vec![3])])
+        );
+
+        // Members left without partitions still receive an assignment
+        let subscriptions: BTreeMap<String, Vec<String>> = BTreeMap::from([
+            ("a".into(), vec!["refunds".into()]),
+            ("b".into(), vec!["refunds".into()]),
+        ]);
+        let assignments = assign_ranges(&subscriptions, &partitions);
+        assert_eq!(
+            assignments["a"],
+            Assignment::from([("refunds".into(), vec![0])])
+        );
+        assert!(assignments["b"].is_empty());
+    }
+
+    #[test]
+    fn consumer_protocol() {
+        let subscription = encode_subscription(&["orders".into()]).unwrap();
+        assert_eq!(
+            subscription,
+            Bytes::from_static(b"\0\0\0\0\0\x01\0\x06orders\xff\xff\xff\xff")
+        );
+        assert_eq!(
+            decode_subscription(subscription).unwrap(),
+            ["orders".to_string()]
+        );
+        assert!(decode_subscription(Bytes::from_static(b"\0\0\0\0\0\x01\0\x06ord")).is_err());
+
+        let assignment =
+            Assignment::from([("orders".into(), vec![0, 2]), ("payments".into(), vec![1])]);
+        assert_eq!(
+            decode_assignment(encode_assignment(&assignment).unwrap()).unwrap(),
+            assignment
+        );
+        assert!(decode_assignment(Bytes::new()).unwrap().is_empty());
+    }
+}
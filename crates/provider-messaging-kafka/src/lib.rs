@@ -1,26 +1,33 @@
 //! Implementation for wasmcloud:messaging
 
-use std::collections::{HashMap, HashSet};
-use std::sync::Arc;
+use std::collections::{BTreeMap, HashMap};
+use std::future::{self, Future};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 
-use anyhow::{bail, Context as _, Result};
+use anyhow::{bail, ensure, Context as _, Result};
 use bytes::Bytes;
-use kafka::producer::{Producer, Record};
-use tokio::spawn;
-use tokio::sync::oneshot::Sender;
-use tokio::sync::RwLock;
+use futures::StreamExt as _;
+use rskafka::client::consumer::{StartOffset, StreamConsumerBuilder};
+use rskafka::client::partition::{Compression, PartitionClient, UnknownTopicHandling};
+use rskafka::client::{Client, ClientBuilder};
+use rskafka::record::{Record, RecordAndOffset};
+use tokio::sync::{oneshot, RwLock};
 use tokio::task::JoinHandle;
 use tokio::time::Duration;
-use tokio_stream::StreamExt;
 use tracing::{debug, error, instrument, warn};
+use uuid::Uuid;
 use wasmcloud_provider_sdk::{
     get_connection, run_provider, Context, LinkConfig, LinkDeleteInfo, Provider,
 };
 use wasmcloud_provider_sdk::{initialize_observability, serve_provider_exports};
 use wasmcloud_tracing::context::TraceContextInjector;
 
-mod client;
-use client::{AsyncKafkaClient, AsyncKafkaConsumer};
+mod config;
+use config::ConnectionConfig;
+
+mod group;
+use group::{Assignment, ErrorCode, GroupMember, Offsets, HEARTBEAT_INTERVAL};
 
 mod bindings {
     wit_bindgen_wrpc::generate!({
@@ -33,56 +40,167 @@ mod bindings {
 }
 use bindings::wasmcloud::messaging::types::BrokerMessage;
 
-/// Config value for hosts, accepted as a comma separated string
-const KAFKA_HOSTS_CONFIG_KEY: &str = "hosts";
-const DEFAULT_HOST: &str = "127.0.0.1:9092";
+/// Header holding the ID correlating a request with its reply
+const CORRELATION_ID_HEADER: &str = "correlation-id";
 
-/// Config value for topic, accepted as a single string
-const KAFKA_TOPIC_CONFIG_KEY: &str = "topic";
-const DEFAULT_TOPIC: &str = "my-topic";
+/// Header holding the topic that the reply to a request is expected on
+const REPLY_TO_HEADER: &str = "reply-to";
 
-/// Config value for specifying a consumer group
-const KAFKA_CONSUMER_GROUP_CONFIG_KEY: &str = "consumer_group";
+/// Separator of the topic and correlation ID in the `reply_to` of messages delivered to
+/// components. `#` is not a valid character of topic names, so subjects are split unambiguously
+const CORRELATION_ID_SEPARATOR: char = '#';
 
-/// Config value for specifying one or more comma delimited partition(s)
-/// to use when consuming values
-const KAFKA_CONSUMER_PARTITIONS_CONFIG_KEY: &str = "consumer_partitions";
+/// Maximum number of milliseconds the broker waits for new records before answering a fetch
+const CONSUMER_MAX_WAIT_MS: i32 = 500;
 
-/// Config value for specifying one or more comma delimited partition(s)
-/// to use when producing values
-const KAFKA_PRODUCER_PARTITIONS_CONFIG_KEY: &str = "producer_partitions";
+/// Number of seconds to wait before fetching records again after a fetch failed
+const CONSUMER_RETRY_DELAY_SECS: u64 = 1;
 
-/// Number of seconds to wait for a consumer to stop after triggering it
-const CONSUMER_STOP_TIMEOUT_SECS: u64 = 5;
+/// Number of seconds between commits of the offsets of records delivered to components
+const COMMIT_INTERVAL_SECS: u64 = 5;
 
 pub async fn run() -> Result<()> {
     KafkaMessagingProvider::run().await
 }
 
-/// A struct that contains a consumer task handler and the host connection strings
-#[allow(dead_code)]
+/// Requests of a component awaiting their replies
+#[derive(Clone)]
+struct Replies {
+    /// Topic that replies are received on
+    topic: String,
+    /// Senders of the replies, keyed by correlation ID
+    pending: Arc<Mutex<HashMap<String, oneshot::Sender<BrokerMessage>>>>,
+}
+
+impl Replies {
+    /// Complete the pending request that the reply `record` received on `topic` correlates to.
+    ///
+    /// Returns whether the reply was delivered to a pending request
+    fn complete(&self, topic: &str, record: Record) -> bool {
+        let Some(id) = header(&record, CORRELATION_ID_HEADER) else {
+            debug!(topic, "ignoring reply without correlation ID");
+            return false;
+        };
+        let Some(tx) = self
+            .pending
+            .lock()
+            .expect("pending requests lock poisoned")
+            .remove(id)
+        else {
+            debug!(correlation_id = id, "ignoring reply to unknown request");
+            return false;
+        };
+        // The request may have timed out in the meantime
+        tx.send(BrokerMessage {
+            subject: topic.to_string(),
+            reply_to: None,
+            body: record.value.map(Bytes::from).unwrap_or_default(),
+        })
+        .is_ok()
+    }
+}
+
+/// Producer of the messages published by a component
+struct Producer {
+    client: Arc<Client>,
+    /// Partitions that every message is sent to. If empty, messages are distributed across all
+    /// partitions of the topic in turn
+    partitions: Vec<i32>,
+    /// Clients of the partitions messages were sent to, keyed by topic and partition
+    partition_clients: Mutex<HashMap<(String, i32), Arc<PartitionClient>>>,
+    /// Counter used to pick the partition of the next message, if no partitions are configured
+    next: AtomicUsize,
+}
+
+impl Producer {
+    fn new(client: Arc<Client>, partitions: Vec<i32>) -> Self {
+        Self {
+            client,
+            partitions,
+            partition_clients: Mutex::default(),
+            next: AtomicUsize::default(),
+        }
+    }
+
+    /// Get the client of `partition` of `topic`, connecting to the partition leader on first use
+    async fn partition_client(&self, topic: &str, partition: i32) -> Result<Arc<PartitionClient>> {
+        let key = (topic.to_string(), partition);
+        if let Some(client) = self
+            .partition_clients
+            .lock()
+            .expect("partition clients lock poisoned")
+            .get(&key)
+        {
+            return Ok(Arc::clone(client));
+        }
+        let client = self
+            .client
+            .partition_client(topic, partition, UnknownTopicHandling::Error)
+            .await
+            .with_context(|| {
+                format!("failed to connect to partition [{partition}] of topic `{topic}`")
+            })?;
+        let client = Arc::new(client);
+        self.partition_clients
+            .lock()
+            .expect("partition clients lock poisoned")
+            .insert(key, Arc::clone(&client));
+        Ok(client)
+    }
+
+    /// Partitions of `topic` that the next message is sent to
+    async fn target_partitions(&self, topic: &str) -> Result<Vec<i32>> {
+        if !self.partitions.is_empty() {
+            return Ok(self.partitions.clone());
+        }
+        let partitions = topic_partitions(&self.client, topic).await?;
+        let next = self.next.fetch_add(1, Ordering::Relaxed);
+        Ok(vec![partitions[next % partitions.len()]])
+    }
+
+    /// Publish `record` to `topic`
+    async fn send(&self, topic: &str, record: Record) -> Result<()> {
+        for partition in self.target_partitions(topic).await? {
+            self.partition_client(topic, partition)
+                .await?
+                .produce(vec![record.clone()], Compression::NoCompression)
+                .await
+                .with_context(|| format!("failed to send record to partition [{partition}]"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Tasks that are aborted when dropped
+#[derive(Default)]
+struct Tasks(Vec<JoinHandle<()>>);
+
+impl Drop for Tasks {
+    fn drop(&mut self) {
+        for task in &self.0 {
+            task.abort();
+        }
+    }
+}
+
+/// Kafka clients of a link, along with the tasks consuming messages
 struct KafkaConnection {
-    /// Hosts that the connection is using
-    hosts: Vec<String>,
-    /// Kafka client that can be used for one-off things
-    client: AsyncKafkaClient,
-    /// Handle to a tokio consumer task handle
-    consumer: JoinHandle<anyhow::Result<()>>,
-    /// Stop the consumer
-    consumer_stop_tx: Sender<()>,
-    /// Topic partition(s) on which the consumer is consuming messages
-    consumer_partitions: Vec<i32>,
-    /// Topic partition(s) on which the producer is sending messages
-    producer_partitions: Vec<i32>,
-    /// Consumer group
-    consumer_group: Option<String>,
+    /// Producer used to publish messages of the component
+    producer: Arc<Producer>,
+    /// Pending requests of the component, if request-reply is enabled
+    replies: Option<Replies>,
+    /// Tasks consuming replies, aborted when the link is deleted
+    tasks: Tasks,
+    /// Stops the consumer group member of the subscriptions when dropped, which commits the
+    /// offsets of delivered messages and leaves the group
+    _stop_group: Option<oneshot::Sender<()>>,
 }
 
 #[derive(Clone, Default)]
 pub struct KafkaMessagingProvider {
-    // Map of Component ID to the JoinHandle where messages are consumed.
+    // Map of Component ID to the Kafka connection of the link.
     //
-    // When a link is put we spawn a tokio::task to handle messages, and on delete the task is closed
+    // When a link is put we spawn tokio tasks to handle messages, and on delete the tasks are aborted
     connections: Arc<RwLock<HashMap<String, KafkaConnection>>>,
 }
 
@@ -111,60 +229,303 @@ impl KafkaMessagingProvider {
     }
 }
 
-/// Extract hostnames (separated by commas, found under key [`KAFKA_HOSTS_CONFIG_KEY`]) from config hashmap
+/// Get the partitions of `topic`, failing if the topic does not exist
+async fn topic_partitions(client: &Client, topic: &str) -> Result<Vec<i32>> {
+    let topics = client
+        .list_topics()
+        .await
+        .context("failed to list topics")?;
+    let Some(topic) = topics.into_iter().find(|t| t.name == topic) else {
+        bail!("topic `{topic}` does not exist");
+    };
+    ensure!(
+        !topic.partitions.is_empty(),
+        "topic `{}` has no partitions",
+        topic.name
+    );
+    Ok(topic.partitions.into_iter().collect())
+}
+
+/// Select the partitions of `topic` to consume, out of the `available` partitions of the topic.
 ///
-/// If no hostnames are found [`DEFAULT_HOST`] is split (by ',') and returned.
-fn extract_hosts_from_link_config(link_config: &LinkConfig) -> Vec<String> {
-    // Collect comma separated hosts into a Vec<String>
-    //
-    // This value could come from either secrets or regular config (for backwards compat)
-    // but we want to make sure we warn if it is pulled from config.
-    let maybe_hosts = link_config
-        .secrets
-        .iter()
-        .find_map(|(k, v)| {
-            match (k, v.as_string()) {
-                (k, Some(v)) if *k == KAFKA_HOSTS_CONFIG_KEY  => Some(String::from(v)),
-                _ => None,
+/// If no partitions are `configured`, all partitions are consumed
+fn assign_partitions(topic: &str, available: &[i32], configured: &[i32]) -> Result<Vec<i32>> {
+    if configured.is_empty() {
+        return Ok(available.to_vec());
+    }
+    if let Some(partition) = configured.iter().find(|p| !available.contains(p)) {
+        bail!("partition [{partition}] of topic `{topic}` does not exist");
+    }
+    Ok(configured.to_vec())
+}
+
+/// Build the headers of a request with correlation ID `id`, expecting its reply on `reply_topic`
+fn request_headers(id: &str, reply_topic: &str) -> BTreeMap<String, Vec<u8>> {
+    BTreeMap::from([
+        (CORRELATION_ID_HEADER.to_string(), id.as_bytes().to_vec()),
+        (REPLY_TO_HEADER.to_string(), reply_topic.as_bytes().to_vec()),
+    ])
+}
+
+/// Build a record of `body` with `headers`
+fn record(body: &[u8], headers: BTreeMap<String, Vec<u8>>) -> Record {
+    Record {
+        key: None,
+        value: Some(body.to_vec()),
+        headers,
+        timestamp: chrono::Utc::now(),
+    }
+}
+
+/// Value of header `key` of `record`, if it is set and valid UTF-8
+fn header<'a>(record: &'a Record, key: &str) -> Option<&'a str> {
+    record
+        .headers
+        .get(key)
+        .and_then(|value| std::str::from_utf8(value).ok())
+}
+
+/// Build the `reply_to` of a message received on `topic` delivered to a component.
+///
+/// The reply is published to the `reply-to` header of the message, defaulting to `{topic}.reply`,
+/// and carries the correlation ID of the message, if any, as a suffix
+fn reply_to(topic: &str, reply_to: Option<&str>, correlation_id: Option<&str>) -> String {
+    let reply_to = reply_to.map_or_else(|| format!("{topic}.reply"), String::from);
+    match correlation_id {
+        Some(id) => format!("{reply_to}{CORRELATION_ID_SEPARATOR}{id}"),
+        None => reply_to,
+    }
+}
+
+/// Split the subject of a message published by a component into its topic and correlation ID
+fn parse_subject(subject: &str) -> (&str, Option<&str>) {
+    match subject.split_once(CORRELATION_ID_SEPARATOR) {
+        Some((topic, id)) => (topic, Some(id)),
+        None => (subject, None),
+    }
+}
+
+/// Build the message delivered to a component for `record` received on `topic`
+fn subscription_message(topic: &str, record: Record) -> BrokerMessage {
+    BrokerMessage {
+        subject: topic.to_string(),
+        reply_to: Some(reply_to(
+            topic,
+            header(&record, REPLY_TO_HEADER),
+            header(&record, CORRELATION_ID_HEADER),
+        )),
+        body: record.value.map(Bytes::from).unwrap_or_default(),
+    }
+}
+
+/// Consume the records published to `partition` of `topic` from `start` on, passing them to `handle`
+async fn consume<F: Future<Output = ()>>(
+    client: Arc<Client>,
+    topic: String,
+    partition: i32,
+    start: StartOffset,
+    handle: impl Fn(RecordAndOffset) -> F,
+) {
+    let partition_client = match client
+        .partition_client(topic.as_str(), partition, UnknownTopicHandling::Retry)
+        .await
+    {
+        Ok(partition_client) => partition_client,
+        Err(err) => {
+            error!(?err, topic, partition, "failed to connect to partition");
+            return;
+        }
+    };
+    let mut records = StreamConsumerBuilder::new(Arc::new(partition_client), start)
+        .with_max_wait_ms(CONSUMER_MAX_WAIT_MS)
+        .build();
+    while let Some(res) = records.next().await {
+        match res {
+            Ok((record, _high_watermark)) => handle(record).await,
+            Err(err) => {
+                warn!(?err, topic, partition, "failed to receive records");
+                tokio::time::sleep(Duration::from_secs(CONSUMER_RETRY_DELAY_SECS)).await;
             }
+        }
+    }
+}
+
+/// Spawn tasks consuming the records published to all partitions of `topic` from now on
+async fn spawn_consumers(
+    client: &Arc<Client>,
+    topic: &str,
+    handle: impl Fn(&str, Record) + Clone + Send + Sync + 'static,
+) -> Result<Vec<JoinHandle<()>>> {
+    let partitions = topic_partitions(client, topic).await?;
+    Ok(partitions
+        .into_iter()
+        .map(|partition| {
+            let handle = handle.clone();
+            let name = topic.to_string();
+            tokio::spawn(consume(
+                Arc::clone(client),
+                topic.to_string(),
+                partition,
+                StartOffset::Latest,
+                move |record| {
+                    handle(&name, record.record);
+                    future::ready(())
+                },
+            ))
         })
-    .or_else(|| {
-        warn!("secret value [{KAFKA_HOSTS_CONFIG_KEY}] was not found in secrets. Prefer storing sensitive values in secrets");
-        link_config
-            .config
-            .iter()
-            .find_map(|(k, v)| {
-                if *k == KAFKA_HOSTS_CONFIG_KEY {
-                    Some(v.to_string())
-                } else {
-                    None
-                }
-            })
-    });
+        .collect())
+}
 
-    maybe_hosts
-        .unwrap_or_else(|| DEFAULT_HOST.to_string())
-        .trim()
-        .split(',')
-        .map(std::string::ToString::to_string)
-        .collect::<Vec<String>>()
+/// Commit the offsets of the records `delivered` since the offsets were last `committed`
+async fn commit_delivered(
+    member: &mut GroupMember,
+    delivered: &Mutex<Offsets>,
+    committed: &mut Offsets,
+) -> Result<()> {
+    let offsets: Offsets = delivered
+        .lock()
+        .expect("delivered offsets lock poisoned")
+        .iter()
+        .filter(|(key, offset)| committed.get(*key) != Some(*offset))
+        .map(|(key, offset)| (key.clone(), *offset))
+        .collect();
+    if offsets.is_empty() {
+        return Ok(());
+    }
+    member
+        .commit(&offsets)
+        .await
+        .context("failed to commit offsets")?;
+    committed.extend(offsets);
+    Ok(())
 }
 
-/// Extract a topic (found under key [`KAFKA_TOPIC_CONFIG_KEY`]) from config hashmap
+/// Consume the partitions assigned to `member` until `stop` is dropped, passing the records to
+/// `deliver` and committing the offsets of delivered records.
 ///
-/// If no topic is found, [`DEFAULT_TOPIC`] is returned.
-fn extract_topic_from_config(config: &HashMap<String, String>) -> &str {
-    config
-        .iter()
-        .find_map(|(k, v)| {
-            if *k == KAFKA_TOPIC_CONFIG_KEY {
-                Some(v.as_str())
-            } else {
-                None
+/// Fails if the group is rebalanced, after committing the offsets of the records delivered so far
+async fn consume_assignment<F>(
+    client: &Arc<Client>,
+    member: &mut GroupMember,
+    deliver: &(impl Fn(String, Record) -> F + Clone + Send + Sync + 'static),
+    stop: &mut oneshot::Receiver<()>,
+) -> Result<()>
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let mut committed = member
+        .committed_offsets()
+        .await
+        .context("failed to fetch committed offsets")?;
+    // Offsets of the next records to consume, updated once records were delivered
+    let delivered: Arc<Mutex<Offsets>> = Arc::default();
+    let mut consumers = Tasks::default();
+    for (topic, partitions) in member.assignment() {
+        for &partition in partitions {
+            // Without a committed offset, only records published from now on are consumed
+            let start = committed
+                .get(&(topic.clone(), partition))
+                .map_or(StartOffset::Latest, |offset| StartOffset::At(*offset));
+            let deliver = deliver.clone();
+            let delivered = Arc::clone(&delivered);
+            let name = topic.clone();
+            consumers.0.push(tokio::spawn(consume(
+                Arc::clone(client),
+                topic.clone(),
+                partition,
+                start,
+                move |RecordAndOffset { record, offset }| {
+                    let delivery = deliver(name.clone(), record);
+                    let delivered = Arc::clone(&delivered);
+                    let key = (name.clone(), partition);
+                    async move {
+                        delivery.await;
+                        delivered
+                            .lock()
+                            .expect("delivered offsets lock poisoned")
+                            .insert(key, offset + 1);
+                    }
+                },
+            )));
+        }
+    }
+
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+    let mut commit = tokio::time::interval(Duration::from_secs(COMMIT_INTERVAL_SECS));
+    let res = loop {
+        tokio::select! {
+            _ = &mut *stop => break Ok(()),
+            _ = heartbeat.tick() => {
+                if let Err(err) = member.heartbeat().await {
+                    break Err(err);
+                }
             }
-        })
-        .unwrap_or(DEFAULT_TOPIC)
-        .trim()
+            _ = commit.tick() => {
+                if let Err(err) = commit_delivered(member, &delivered, &mut committed).await {
+                    break Err(err);
+                }
+            }
+        }
+    };
+    // Stop delivering records before committing the offsets of the delivered records
+    drop(consumers);
+    if let Err(err) = commit_delivered(member, &delivered, &mut committed).await {
+        warn!(?err, "failed to commit offsets of delivered records");
+    }
+    res
+}
+
+/// Consume `topics` as a member of consumer `group` until `stop` is dropped, passing the records
+/// to `deliver` and committing the offsets of delivered records to the group.
+///
+/// If an `assignment` is configured, its partitions are consumed without joining the group
+async fn consume_group<F>(
+    client: Arc<Client>,
+    hosts: Vec<String>,
+    group: String,
+    topics: Vec<String>,
+    assignment: Option<Assignment>,
+    deliver: impl Fn(String, Record) -> F + Clone + Send + Sync + 'static,
+    mut stop: oneshot::Receiver<()>,
+) where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let mut member_id = String::new();
+    loop {
+        let member = match &assignment {
+            Some(assignment) => GroupMember::assigned(&hosts, &group, assignment.clone()).await,
+            None => GroupMember::join(&client, &hosts, &group, &member_id, &topics).await,
+        };
+        let err = match member {
+            Ok(mut member) => {
+                member_id = member.member_id().to_string();
+                debug!(group, member_id, assignment = ?member.assignment(), "consuming assigned partitions");
+                match consume_assignment(&client, &mut member, &deliver, &mut stop).await {
+                    Ok(()) => {
+                        if let Err(err) = member.leave().await {
+                            warn!(?err, group, "failed to leave consumer group");
+                        }
+                        return;
+                    }
+                    Err(err) => err,
+                }
+            }
+            Err(err) => err,
+        };
+        match err.downcast_ref::<ErrorCode>() {
+            Some(&ErrorCode::REBALANCE_IN_PROGRESS) => {
+                debug!(group, "consumer group is rebalancing, rejoining");
+                continue;
+            }
+            Some(&ErrorCode::UNKNOWN_MEMBER_ID) => member_id.clear(),
+            _ => {}
+        }
+        warn!(?err, group, "consumer group member failed, retrying");
+        tokio::select! {
+            _ = &mut stop => return,
+            () = tokio::time::sleep(Duration::from_secs(CONSUMER_RETRY_DELAY_SECS)) => {}
+        }
+    }
 }
 
 impl Provider for KafkaMessagingProvider {
@@ -174,156 +535,110 @@ impl Provider for KafkaMessagingProvider {
         let LinkConfig {
             link_name,
             source_id,
-            config,
             ..
         } = link_config;
         debug!(link_name, source_id, "receiving link as target");
-        // Collect various values from config (if present)
-        let hosts = extract_hosts_from_link_config(&link_config);
-        let topic = extract_topic_from_config(config);
-        let consumer_group = config
-            .get(KAFKA_CONSUMER_GROUP_CONFIG_KEY)
-            .map(String::to_string);
-        let consumer_partitions = config
-            .get(KAFKA_CONSUMER_PARTITIONS_CONFIG_KEY)
-            .map(String::to_string)
-            .unwrap_or_default()
-            .split(',')
-            .map(|s| s.into())
-            .collect::<HashSet<String>>()
-            .iter()
-            .filter_map(|v| v.parse::<i32>().ok())
-            .collect::<Vec<i32>>();
-        let producer_partitions = config
-            .get(KAFKA_PRODUCER_PARTITIONS_CONFIG_KEY)
-            .map(String::to_string)
-            .unwrap_or_default()
-            .split(',')
-            .map(|s| s.into())
-            .collect::<HashSet<String>>()
-            .iter()
-            .filter_map(|v| v.parse::<i32>().ok())
-            .collect::<Vec<i32>>();
-
-        // Build client for use with the consumer
-        let client = AsyncKafkaClient::from_hosts(hosts.clone()).await.with_context(|| {
-            warn!(
-                source_id,
-                "failed to create Kafka client for component",
-            );
-            format!("failed to build async kafka client for component [{source_id}], messages won't be received")
-        })?;
-
-        // Build a consumer configured with our given client
-        let _consumer_group = consumer_group.clone();
-        let _consumer_partitions = consumer_partitions.clone();
-        debug!(topic, ?consumer_partitions, "creating kafka async consumer");
-        let consumer = AsyncKafkaConsumer::from_async_client(client, move |mut b| {
-            b = b.with_topic(topic.into());
-            b = b.with_topic_partitions(topic.into(), _consumer_partitions.as_slice());
-            if let Some(g) = _consumer_group {
-                b = b.with_group(g);
-            }
-            b
-        }).await.with_context(|| {
-            warn!(
-                source_id,
-                "failed to build consumer from Kafka client for component",
-            );
-            format!("failed to build consumer from kafka client for component [{source_id}], messages won't be received")
-        })?;
-
-        // Build a second client to store in the connection
-        let client = AsyncKafkaClient::from_hosts(hosts.clone()).await.with_context(|| {
-            warn!(
-                source_id,
-                "failed to create Kafka client for component",
-            );
-            format!("failed to build async kafka client for component [{source_id}], messages won't be received")
-        })?;
-
-        // Store reusable information for use when processing new messages
-        let component_id: Arc<str> = source_id.into();
-        let subject: Arc<str> = topic.into();
-
-        // Allow triggering listeners to stop
-        let (stop_listener_tx, mut stop_listener_rx) = tokio::sync::oneshot::channel();
+        let ConnectionConfig {
+            hosts,
+            topics,
+            consumer_group,
+            consumer_partitions,
+            producer_partitions,
+            reply_topic,
+        } = ConnectionConfig::from_link_config(&link_config)
+            .context("failed to parse link configuration")?;
 
-        // Start listening for incoming messages
-        let (mut stream, inner_stop_tx) = match consumer
-            .messages()
+        let client = ClientBuilder::new(hosts.clone())
+            .build()
             .await
-            .context("failed to start listening to consumer messages")
-        {
-            Ok(v) => v,
-            Err(e) => {
-                warn!("failed listening to consumer message stream: {e}");
-                bail!(e);
-            }
+            .with_context(|| format!("failed to build Kafka client for component [{source_id}]"))?;
+        let client = Arc::new(client);
+
+        // Consumers spawned so far are stopped when the connection is dropped on failure
+        let mut connection = KafkaConnection {
+            producer: Arc::new(Producer::new(Arc::clone(&client), producer_partitions)),
+            replies: None,
+            tasks: Tasks::default(),
+            _stop_group: None,
         };
-
-        // StartOffset::Latest only processes new messages, but Earliest will send every message.
-        // This could be a linkdef tunable value in the future
-        let task = spawn(async move {
-            let wrpc = get_connection().get_wrpc_client(&component_id).await?;
-
-            // Listen to messages forever until we're instructed to stop
-            loop {
-                tokio::select! {
-                    // Handle listening to calls to stop
-                    _ = &mut stop_listener_rx => {
-                        if let Err(()) = inner_stop_tx.send(()) {
-                            bail!("failed to send stop consumer");
-                        }
-                        return Ok(());
-                    },
-
-                    // Listen to the next messages in the stream
-                    //
-                    // This stream will essentially never stop producing values.
-                    Some(msg) = stream.next() => {
-                        let component_id = Arc::clone(&component_id);
-                        let wrpc = wrpc.clone();
-                        let subject = Arc::clone(&subject);
-                        tokio::spawn(async move {
-                            if let Err(e) = bindings::wasmcloud::messaging::handler::handle_message(
-                                &wrpc,
-                                None,
-                                &BrokerMessage {
-                                    body: msg.value.into(),
-                                    // By default, we always append '.reply' for reply topics
-                                    reply_to: Some(format!("{subject}.reply")),
-                                    subject: subject.to_string(),
-                                },
-                            )
-                                .await
-                            {
-                                warn!(
-                                    subject = subject.to_string(),
-                                    component_id = component_id.to_string(),
-                                    "unable to send subscription: {e:?}",
-                                );
-                            }
-                        });
+        if !topics.is_empty() {
+            // Check that the topics and configured partitions exist before joining the group
+            let mut assignment = Assignment::new();
+            for topic in &topics {
+                let available = topic_partitions(&client, topic).await.with_context(|| {
+                    format!("failed to consume topic `{topic}` for component [{source_id}]")
+                })?;
+                let partitions = assign_partitions(topic, &available, &consumer_partitions)?;
+                assignment.insert(topic.clone(), partitions);
+            }
+            // Partitions are assigned by the consumer group, unless they are configured
+            let assignment = (!consumer_partitions.is_empty()).then_some(assignment);
+
+            let wrpc = get_connection()
+                .get_wrpc_client(source_id)
+                .await
+                .context("failed to build wRPC client for component")?;
+            let component_id: Arc<str> = source_id.into();
+            let deliver = move |topic: String, record: Record| {
+                let msg = subscription_message(&topic, record);
+                let wrpc = wrpc.clone();
+                let component_id = Arc::clone(&component_id);
+                async move {
+                    if let Err(e) =
+                        bindings::wasmcloud::messaging::handler::handle_message(&wrpc, None, &msg)
+                            .await
+                    {
+                        warn!(
+                            subject = msg.subject,
+                            component_id = component_id.to_string(),
+                            "unable to send subscription: {e:?}",
+                        );
                     }
                 }
-            }
-        });
-
-        // Save the newly task that constantly listens for messages to the provider
-        let mut connections = self.connections.write().await;
-        connections.insert(
-            source_id.to_string(),
-            KafkaConnection {
-                client,
-                consumer: task,
-                consumer_stop_tx: stop_listener_tx,
+            };
+            let (stop, stopped) = oneshot::channel();
+            debug!(
+                ?topics,
+                consumer_group,
+                ?consumer_partitions,
+                "consuming topics"
+            );
+            tokio::spawn(consume_group(
+                Arc::clone(&client),
                 hosts,
-                consumer_partitions,
-                producer_partitions,
                 consumer_group,
-            },
-        );
+                topics,
+                assignment,
+                deliver,
+                stopped,
+            ));
+            connection._stop_group = Some(stop);
+        }
+
+        if let Some(topic) = reply_topic {
+            let replies = Replies {
+                topic,
+                pending: Arc::default(),
+            };
+            // Every instance of the provider consumes all partitions of the reply topic, since
+            // the request may have been sent by any of them
+            let consumers = spawn_consumers(&client, &replies.topic, {
+                let replies = replies.clone();
+                move |topic: &str, record| {
+                    replies.complete(topic, record);
+                }
+            })
+            .await
+            .with_context(|| {
+                format!("failed to consume reply topic for component [{source_id}]")
+            })?;
+            connection.tasks.0.extend(consumers);
+            connection.replies = Some(replies);
+        }
+
+        // Save the connection, replacing (and stopping) a previous connection of the component
+        let mut connections = self.connections.write().await;
+        connections.insert(source_id.to_string(), connection);
 
         Ok(())
     }
@@ -334,50 +649,17 @@ impl Provider for KafkaMessagingProvider {
         let component_id = info.get_source_id();
         debug!(component_id, "deleting link for component");
 
-        // Find the connection and remove it from the HashMap
+        // Dropping the connection stops its consumers
         let mut connections = self.connections.write().await;
-        let Some(KafkaConnection {
-            consumer,
-            consumer_stop_tx,
-            ..
-        }) = connections.remove(component_id)
-        else {
+        if connections.remove(component_id).is_none() {
             debug!("Linkdef deleted for non-existent consumer, ignoring");
-            return Ok(());
-        };
-
-        // Signal the consumer to stop, then wait for it to close out
-        if let Err(()) = consumer_stop_tx.send(()) {
-            bail!("failed to send stop consumer");
         }
-        let _ = tokio::time::timeout(Duration::from_secs(CONSUMER_STOP_TIMEOUT_SECS), consumer)
-            .await
-            .context("consumer task did not exit cleanly")?;
-
         Ok(())
     }
 
     /// Handle shutdown request with any cleanup necessary
     async fn shutdown(&self) -> Result<()> {
-        let mut connections = self.connections.write().await;
-        for (
-            _source_id,
-            KafkaConnection {
-                consumer,
-                consumer_stop_tx,
-                ..
-            },
-        ) in connections.drain()
-        {
-            consumer_stop_tx
-                .send(())
-                .map_err(|_| anyhow::anyhow!("failed to send consumer stop"))?;
-            if let Err(err) =
-                tokio::try_join!(consumer).context("consumer task did not exit cleanly")
-            {
-                error!(?err, "failed to stop consumer task cleanly");
-            };
-        }
+        self.connections.write().await.clear();
         Ok(())
     }
 }
@@ -415,13 +697,13 @@ impl bindings::exports::wasmcloud::messaging::consumer::Handler<Option<Context>>
             bail!("context unexpectedly missing component ID");
         };
 
-        // Retrieve a usable Kafka client from the kafka connection for our component
-        let connections = self.connections.read().await;
-        let Some(KafkaConnection {
-            hosts,
-            producer_partitions,
-            ..
-        }) = connections.get(component_id)
+        // Clone the producer, so that the connections are not locked while sending
+        let Some(producer) = self
+            .connections
+            .read()
+            .await
+            .get(component_id)
+            .map(|connection| Arc::clone(&connection.producer))
         else {
             warn!(component_id, "failed to get connection for component");
             return Ok(Err(format!(
@@ -429,54 +711,30 @@ impl bindings::exports::wasmcloud::messaging::consumer::Handler<Option<Context>>
             )));
         };
 
-        // Create a producer we'll use to send
-        let mut producer = Producer::from_hosts(hosts.clone())
-            .create()
-            .context("failed to build kafka producer")?;
-
-        // For every partition we're listening on, send out a record
-        // if we're listening on *no* partitions, then use the unspecified partition
-        debug!(subject = msg.subject, "sending message");
-        match producer_partitions[..] {
-            // Send to the default ("unspecified") partition
-            [] => {
-                producer
-                    .send(&Record::<(), Vec<u8>>::from_key_value(
-                        &msg.subject,
-                        (),
-                        msg.body.to_vec(),
-                    ))
-                    .context("failed to send record")?;
-            }
-            // If there are multiple partitions to publish to, then publish to each of them
-            _ => {
-                for partition in producer_partitions {
-                    producer
-                        .send(
-                            &Record::<(), Vec<u8>>::from_key_value(
-                                &msg.subject,
-                                (),
-                                msg.body.to_vec(),
-                            )
-                            .with_partition(*partition),
-                        )
-                        .with_context(|| {
-                            format!("failed to send record to partition [{partition}]")
-                        })?;
-                }
-            }
+        // Replies to requests carry the correlation ID of the request in their subject
+        let (topic, correlation_id) = parse_subject(&msg.subject);
+        let mut headers = BTreeMap::new();
+        if let Some(id) = correlation_id {
+            headers.insert(CORRELATION_ID_HEADER.to_string(), id.as_bytes().to_vec());
+        }
+        if let Some(reply_to) = &msg.reply_to {
+            headers.insert(REPLY_TO_HEADER.to_string(), reply_to.as_bytes().to_vec());
         }
 
-        Ok(Ok(()))
+        debug!(topic, "sending message");
+        Ok(producer
+            .send(topic, record(&msg.body, headers))
+            .await
+            .map_err(|err| format!("{err:#}")))
     }
 
-    #[instrument(skip_all)]
+    #[instrument(skip_all, fields(subject = %subject, timeout_ms))]
     async fn request(
         &self,
         ctx: Option<Context>,
-        _subject: String,
-        _body: Bytes,
-        _timeout_ms: u32,
+        subject: String,
+        body: Bytes,
+        timeout_ms: u32,
     ) -> Result<std::result::Result<BrokerMessage, String>> {
         // Extract tracing information from invocation context, if present
         let trace_ctx = match ctx {
@@ -492,13 +750,144 @@ impl bindings::exports::wasmcloud::messaging::consumer::Handler<Option<Context>>
         };
         wasmcloud_tracing::context::attach_span_context(&trace_ctx);
 
-        // Kafka does not support request-reply in the traditional sense. You can publish to a
-        // topic, and get an acknowledgement that it was received, but you can't get a
-        // reply from a consumer on the other side.
-        error!("not implemented (Kafka does not officially support the request-reply paradigm)");
-        Ok(Err(
-            "not implemented (Kafka does not officially support the request-reply paradigm)"
-                .to_string(),
-        ))
+        let ctx = ctx.as_ref().context("unexpectedly missing context")?;
+        let Some(component_id) = ctx.component.as_ref() else {
+            bail!("context unexpectedly missing component ID");
+        };
+
+        // Clone the clients, so that the connections are not locked while awaiting the reply
+        let (producer, replies) = {
+            let connections = self.connections.read().await;
+            let Some(KafkaConnection {
+                producer, replies, ..
+            }) = connections.get(component_id)
+            else {
+                warn!(component_id, "failed to get connection for component");
+                return Ok(Err(format!(
+                    "failed to get connection for component [{component_id}]"
+                )));
+            };
+            (Arc::clone(producer), replies.clone())
+        };
+        // Kafka has no notion of request-reply, it is emulated by correlating requests with
+        // replies received on the reply topic of the link
+        let Some(replies) = replies else {
+            return Ok(Err(format!(
+                "request-reply is not enabled for component [{component_id}], `reply_topic` must be configured"
+            )));
+        };
+
+        let id = Uuid::new_v4().to_string();
+        let (tx, rx) = oneshot::channel();
+        replies
+            .pending
+            .lock()
+            .expect("pending requests lock poisoned")
+            .insert(id.clone(), tx);
+        let headers = request_headers(&id, &replies.topic);
+        let res = async {
+            producer.send(&subject, record(&body, headers)).await?;
+            tokio::time::timeout(Duration::from_millis(timeout_ms.into()), rx)
+                .await
+                .context("timed out waiting for reply")?
+                .context("reply consumer stopped")
+        }
+        .await;
+        replies
+            .pending
+            .lock()
+            .expect("pending requests lock poisoned")
+            .remove(&id);
+        Ok(res.map_err(|err| format!("{err:#}")))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn correlate_replies() {
+        assert_eq!(reply_to("orders", None, None), "orders.reply");
+        assert_eq!(
+            reply_to("orders", Some("replies"), Some("42")),
+            "replies#42"
+        );
+        assert_eq!(parse_subject("replies#42"), ("replies", Some("42")));
+        assert_eq!(parse_subject("orders"), ("orders", None));
+    }
+
+    #[test]
+    fn assigned_partitions() {
+        assert_eq!(
+            assign_partitions("orders", &[0, 1, 2], &[]).unwrap(),
+            [0, 1, 2]
+        );
+        assert_eq!(
+            assign_partitions("orders", &[0, 1, 2], &[2, 0]).unwrap(),
+            [2, 0]
+        );
+        assert!(assign_partitions("orders", &[0, 1, 2], &[1, 3]).is_err());
+    }
+
+    #[test]
+    fn subscription_messages() {
+        let msg = subscription_message("orders", record(b"order", BTreeMap::new()));
+        assert_eq!(msg.subject, "orders");
+        assert_eq!(msg.reply_to.as_deref(), Some("orders.reply"));
+        assert_eq!(msg.body, Bytes::from("order"));
+
+        // Requests published by the provider are delivered with a `reply_to` completing them
+        let msg = subscription_message(
+            "orders",
+            record(b"order", request_headers("42", "billing.replies")),
+        );
+        assert_eq!(msg.reply_to.as_deref(), Some("billing.replies#42"));
+        assert_eq!(
+            parse_subject(msg.reply_to.as_deref().unwrap()),
+            ("billing.replies", Some("42"))
+        );
+
+        // Headers which are not valid UTF-8 are ignored
+        let msg = subscription_message(
+            "orders",
+            record(
+                b"order",
+                BTreeMap::from([(REPLY_TO_HEADER.to_string(), vec![0xff])]),
+            ),
+        );
+        assert_eq!(msg.reply_to.as_deref(), Some("orders.reply"));
+    }
+
+    #[test]
+    fn complete_replies() {
+        let replies = Replies {
+            topic: "billing.replies".into(),
+            pending: Arc::default(),
+        };
+        let (tx, mut rx) = oneshot::channel();
+        replies.pending.lock().unwrap().insert("42".into(), tx);
+
+        let reply = |id: Option<&str>| {
+            let headers = id
+                .map(|id| {
+                    BTreeMap::from([(CORRELATION_ID_HEADER.to_string(), id.as_bytes().to_vec())])
+                })
+                .unwrap_or_default();
+            record(b"paid", headers)
+        };
+        assert!(!replies.complete("billing.replies", reply(None)));
+        assert!(!replies.complete("billing.replies", reply(Some("43"))));
+        assert!(rx.try_recv().is_err());
+
+        assert!(replies.complete("billing.replies", reply(Some("42"))));
+        let msg = rx.try_recv().expect("reply was not delivered");
+        assert_eq!(msg.subject, "billing.replies");
+        assert_eq!(msg.reply_to, None);
+        assert_eq!(msg.body, Bytes::from("paid"));
+
+        // Replies are delivered to a request at most once
+        assert!(!replies.complete("billing.replies", reply(Some("42"))));
+        assert!(replies.pending.lock().unwrap().is_empty());
     }
 }
@@ -15,12 +15,31 @@ const CONFIG_NATS_CLIENT_SEED: &str = "client_seed";
 const CONFIG_NATS_TLS_CA: &str = "tls_ca";
 const CONFIG_NATS_CUSTOM_INBOX_PREFIX: &str = "custom_inbox_prefix";
 
+/// Configuration of a durable JetStream consumer, which is created if it does not exist.
+/// Messages are acknowledged once the component handled them successfully
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ConsumerConfig {
+    /// Stream to consume messages of
     pub stream: Box<str>,
+    /// Durable name of the consumer
     pub consumer: Box<str>,
+    /// Maximum number of messages requested at once by a pull consumer
     pub max_messages: Option<usize>,
+    /// Maximum number of bytes requested at once by a pull consumer
     pub max_bytes: Option<usize>,
+    /// Subject messages are pushed to. Consumers without a deliver subject are pull consumers
+    #[serde(default)]
+    pub deliver_subject: Option<Box<str>>,
+    /// Subject filtering the messages of the stream, used when creating the consumer
+    #[serde(default)]
+    pub filter_subject: Option<Box<str>>,
+    /// Maximum number of times a message is delivered, used when creating the consumer
+    #[serde(default)]
+    pub max_deliver: Option<i64>,
+    /// Time in milliseconds after which unacknowledged messages are redelivered, used when
+    /// creating the consumer
+    #[serde(default)]
+    pub ack_wait_ms: Option<u64>,
 }
 
 /// Configuration for connecting a nats client.
@@ -1,21 +1,31 @@
+use core::future::Future;
+use core::pin::Pin;
 use core::time::Duration;
 
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use anyhow::{anyhow, bail, ensure, Context as _};
+use anyhow::{anyhow, bail, Context as _};
+use async_nats::jetstream::consumer::{pull, push, AckPolicy, DeliverPolicy};
+use async_nats::jetstream::AckKind;
 use async_nats::subject::ToSubject;
 use bytes::Bytes;
-use futures::StreamExt as _;
+use futures::stream::BoxStream;
+use futures::{stream, Stream, StreamExt as _, TryStreamExt as _};
 use opentelemetry_nats::{attach_span_context, NatsHeaderInjector};
+use serde::{Deserialize, Serialize};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
 use tokio::fs;
 use tokio::sync::RwLock;
 use tokio::task::JoinHandle;
-use tracing::{debug, error, instrument, warn};
+use tracing::{debug, error, info, instrument, warn};
 use tracing_futures::Instrument;
 use wascap::prelude::KeyPair;
 use wasmcloud_provider_sdk::core::HostData;
+use wasmcloud_provider_sdk::deadline::request_timeout;
 use wasmcloud_provider_sdk::provider::WrpcClient;
+use wasmcloud_provider_sdk::stream::{stream_batches, DEFAULT_BATCH_SIZE, DEFAULT_BUFFER};
 use wasmcloud_provider_sdk::wasmcloud_tracing::context::TraceContextInjector;
 use wasmcloud_provider_sdk::{
     get_connection, initialize_observability, load_host_data, propagate_trace_for_ctx,
@@ -31,15 +41,87 @@ mod bindings {
             "wasmcloud:messaging/consumer@0.2.0": generate,
             "wasmcloud:messaging/handler@0.2.0": generate,
             "wasmcloud:messaging/types@0.2.0": generate,
+            "wasmcloud:messaging-ext/request-many@0.1.0-draft": generate,
         },
     });
 }
+use bindings::exports::wasmcloud::messaging_ext::request_many::{self, Reply, RequestOptions};
 use bindings::wasmcloud::messaging::types::BrokerMessage;
 
 pub async fn run() -> anyhow::Result<()> {
     NatsMessagingProvider::run().await
 }
 
+/// Header of the wRPC invocation marking messages replayed to a component, the value is
+/// `<stream>:<sequence>` of the replayed message
+pub const REPLAY_HEADER: &str = "wasmcloud-replay";
+
+/// Time after which a replay stops if no message was received
+const REPLAY_IDLE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Arguments of the `replay` admin operation, replaying a range of JetStream messages to the
+/// handler of a linked component
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ReplayArgs {
+    /// ID of the linked component to replay messages to
+    pub component_id: String,
+    /// Stream to replay messages of
+    pub stream: String,
+    /// Subject filtering the replayed messages
+    #[serde(default)]
+    pub filter_subject: Option<String>,
+    /// Stream sequence of the first message to replay
+    #[serde(default)]
+    pub start_sequence: Option<u64>,
+    /// Stream sequence of the last message to replay
+    #[serde(default)]
+    pub end_sequence: Option<u64>,
+    /// RFC 3339 time, at or after which the first replayed message was published
+    #[serde(default)]
+    pub start_time: Option<String>,
+    /// RFC 3339 time, at or before which the last replayed message was published
+    #[serde(default)]
+    pub end_time: Option<String>,
+}
+
+/// Range of messages to replay, parsed from [`ReplayArgs`]
+#[derive(Debug, PartialEq)]
+struct ReplayRange {
+    deliver_policy: DeliverPolicy,
+    end_sequence: Option<u64>,
+    end_time: Option<OffsetDateTime>,
+}
+
+impl ReplayRange {
+    fn new(args: &ReplayArgs) -> anyhow::Result<Self> {
+        let parse_time = |time: &Option<String>| {
+            time.as_deref()
+                .map(|time| OffsetDateTime::parse(time, &Rfc3339))
+                .transpose()
+                .with_context(|| format!("invalid RFC 3339 time `{time:?}`"))
+        };
+        let start_time = parse_time(&args.start_time)?;
+        let end_time = parse_time(&args.end_time)?;
+        let deliver_policy = match (args.start_sequence, start_time) {
+            (Some(_), Some(_)) => bail!("only one of `start_sequence` and `start_time` may be set"),
+            (Some(start_sequence), None) => DeliverPolicy::ByStartSequence { start_sequence },
+            (None, Some(start_time)) => DeliverPolicy::ByStartTime { start_time },
+            (None, None) => DeliverPolicy::All,
+        };
+        Ok(Self {
+            deliver_policy,
+            end_sequence: args.end_sequence,
+            end_time,
+        })
+    }
+
+    /// Whether a message with `sequence` published at `published` is past the end of the range
+    fn is_past_end(&self, sequence: u64, published: OffsetDateTime) -> bool {
+        self.end_sequence.is_some_and(|end| sequence > end)
+            || self.end_time.is_some_and(|end| published > end)
+    }
+}
+
 /// [`NatsClientBundle`]s hold a NATS client and information (subscriptions)
 /// related to it.
 ///
@@ -49,6 +131,8 @@ pub async fn run() -> anyhow::Result<()> {
 struct NatsClientBundle {
     pub client: async_nats::Client,
     pub sub_handles: Vec<(String, JoinHandle<()>)>,
+    /// Replays of messages to the component, which are aborted when the link is deleted
+    pub replay_handles: Vec<JoinHandle<()>>,
 }
 
 impl Drop for NatsClientBundle {
@@ -56,6 +140,9 @@ impl Drop for NatsClientBundle {
         for handle in &self.sub_handles {
             handle.1.abort();
         }
+        for handle in &self.replay_handles {
+            handle.abort();
+        }
     }
 }
 
@@ -108,10 +195,6 @@ impl NatsMessagingProvider {
         cfg: ConnectionConfig,
         component_id: &str,
     ) -> anyhow::Result<NatsClientBundle> {
-        ensure!(
-            cfg.consumers.is_empty(),
-            "JetStream consumers not supported by this provider"
-        );
         let mut opts = match (cfg.auth_jwt, cfg.auth_seed) {
             (Some(jwt), Some(seed)) => {
                 let seed = KeyPair::from_seed(&seed).context("failed to parse seed key pair")?;
@@ -160,10 +243,17 @@ impl NatsMessagingProvider {
                     .await?,
             ));
         }
+        for consumer in cfg.consumers.iter() {
+            sub_handles.push((
+                format!("{}/{}", consumer.stream, consumer.consumer),
+                self.consume(&client, component_id, consumer).await?,
+            ));
+        }
 
         Ok(NatsClientBundle {
             client,
             sub_handles,
+            replay_handles: Vec::new(),
         })
     }
 
@@ -205,7 +295,8 @@ impl NatsMessagingProvider {
                 let component_id = Arc::clone(&component_id);
                 let wrpc = Arc::clone(&wrpc);
                 tokio::spawn(async move {
-                    dispatch_msg(&wrpc, &component_id, msg)
+                    // Failures are logged, core NATS messages are not redelivered
+                    let _ = dispatch_msg(&wrpc, &component_id, msg, None)
                         .instrument(span)
                         .await;
                 });
@@ -214,10 +305,244 @@ impl NatsMessagingProvider {
 
         Ok(join_handle)
     }
+
+    /// Consume messages of a durable JetStream consumer, creating it if it does not exist.
+    /// Messages are acknowledged after the component handled them successfully, and negatively
+    /// acknowledged otherwise, so that they are redelivered up to the `max_deliver` of the consumer
+    async fn consume(
+        &self,
+        client: &async_nats::Client,
+        component_id: &str,
+        cfg: &ConsumerConfig,
+    ) -> anyhow::Result<JoinHandle<()>> {
+        let js = async_nats::jetstream::new(client.clone());
+        let stream = js
+            .get_stream(cfg.stream.as_ref())
+            .await
+            .with_context(|| format!("failed to get stream [{}]", cfg.stream))?;
+        let durable_name = Some(cfg.consumer.to_string());
+        let filter_subject = cfg
+            .filter_subject
+            .as_deref()
+            .map(String::from)
+            .unwrap_or_default();
+        let ack_wait = cfg
+            .ack_wait_ms
+            .map(Duration::from_millis)
+            .unwrap_or_default();
+        let max_deliver = cfg.max_deliver.unwrap_or_default();
+        let mut messages: BoxStream<'static, anyhow::Result<async_nats::jetstream::Message>> =
+            if let Some(deliver_subject) = cfg.deliver_subject.as_deref() {
+                let consumer = stream
+                    .get_or_create_consumer(
+                        &cfg.consumer,
+                        push::Config {
+                            durable_name,
+                            deliver_subject: deliver_subject.into(),
+                            filter_subject,
+                            ack_policy: AckPolicy::Explicit,
+                            ack_wait,
+                            max_deliver,
+                            ..Default::default()
+                        },
+                    )
+                    .await
+                    .with_context(|| format!("failed to get consumer [{}]", cfg.consumer))?;
+                consumer
+                    .messages()
+                    .await
+                    .context("failed to consume messages")?
+                    .map_err(anyhow::Error::from)
+                    .boxed()
+            } else {
+                let consumer = stream
+                    .get_or_create_consumer(
+                        &cfg.consumer,
+                        pull::Config {
+                            durable_name,
+                            filter_subject,
+                            ack_policy: AckPolicy::Explicit,
+                            ack_wait,
+                            max_deliver,
+                            ..Default::default()
+                        },
+                    )
+                    .await
+                    .with_context(|| format!("failed to get consumer [{}]", cfg.consumer))?;
+                let mut messages = consumer.stream();
+                if let Some(max_messages) = cfg.max_messages {
+                    messages = messages.max_messages_per_batch(max_messages);
+                }
+                if let Some(max_bytes) = cfg.max_bytes {
+                    messages = messages.max_bytes_per_batch(max_bytes);
+                }
+                messages
+                    .messages()
+                    .await
+                    .context("failed to consume messages")?
+                    .map_err(anyhow::Error::from)
+                    .boxed()
+            };
+
+        debug!(?component_id, stream = %cfg.stream, consumer = %cfg.consumer, "spawning consumer for component");
+
+        let component_id = Arc::from(component_id);
+        let join_handle = tokio::spawn(async move {
+            let wrpc = match get_connection()
+                .get_wrpc_client_custom(&component_id, None)
+                .await
+            {
+                Ok(wrpc) => Arc::new(wrpc),
+                Err(err) => {
+                    error!(?err, "failed to construct wRPC client");
+                    return;
+                }
+            };
+            while let Some(msg) = messages.next().await {
+                let msg = match msg {
+                    Ok(msg) => msg,
+                    Err(err) => {
+                        warn!(?err, "failed to receive JetStream message");
+                        continue;
+                    }
+                };
+                debug!(subject = %msg.subject, ?component_id, "received JetStream message");
+                let span = tracing::debug_span!("handle_message", ?component_id);
+
+                let component_id = Arc::clone(&component_id);
+                let wrpc = Arc::clone(&wrpc);
+                tokio::spawn(
+                    async move {
+                        // The reply subject of JetStream messages is used for acknowledgements
+                        let mut nats_msg = msg.message.clone();
+                        nats_msg.reply = None;
+                        let ack = match dispatch_msg(&wrpc, &component_id, nats_msg, None).await {
+                            Ok(()) => msg.ack().await,
+                            Err(_) => msg.ack_with(AckKind::Nak(None)).await,
+                        };
+                        if let Err(err) = ack {
+                            warn!(?err, "failed to acknowledge JetStream message");
+                        }
+                    }
+                    .instrument(span),
+                );
+            }
+        });
+
+        Ok(join_handle)
+    }
+
+    /// Replay a range of JetStream messages to the handler of a linked component using an
+    /// ephemeral ordered consumer, which leaves durable consumers of the stream untouched.
+    /// Replayed messages are marked with the [`REPLAY_HEADER`] and are not acknowledged.
+    ///
+    /// The replay stops after the end of the range, once all messages in the stream were
+    /// replayed, or when no message was received for [`REPLAY_IDLE_TIMEOUT`]. It is aborted if
+    /// the link to the component is deleted before.
+    #[instrument(level = "debug", skip(self))]
+    async fn replay(&self, args: ReplayArgs) -> anyhow::Result<()> {
+        let range = ReplayRange::new(&args)?;
+        let client = self
+            .handler_components
+            .read()
+            .await
+            .get(&args.component_id)
+            .map(|bundle| bundle.client.clone())
+            .with_context(|| {
+                format!(
+                    "component [{}] is not linked to the provider",
+                    args.component_id
+                )
+            })?;
+        let wrpc = get_connection()
+            .get_wrpc_client_custom(&args.component_id, None)
+            .await
+            .context("failed to construct wRPC client")?;
+        let js = async_nats::jetstream::new(client);
+        let stream = js
+            .get_stream(&args.stream)
+            .await
+            .with_context(|| format!("failed to get stream [{}]", args.stream))?;
+        let consumer = stream
+            .create_consumer(pull::OrderedConfig {
+                deliver_policy: range.deliver_policy,
+                filter_subject: args.filter_subject.clone().unwrap_or_default(),
+                ..Default::default()
+            })
+            .await
+            .context("failed to create replay consumer")?;
+        let mut messages = consumer
+            .messages()
+            .await
+            .context("failed to consume messages")?;
+
+        info!(
+            component_id = %args.component_id,
+            stream = %args.stream,
+            "replaying messages"
+        );
+        let component_id = args.component_id.clone();
+        let handle = tokio::spawn(async move {
+            let (mut replayed, mut failed) = (0_u64, 0_u64);
+            loop {
+                let msg = match tokio::time::timeout(REPLAY_IDLE_TIMEOUT, messages.next()).await {
+                    Ok(Some(Ok(msg))) => msg,
+                    Ok(Some(Err(err))) => {
+                        warn!(?err, "failed to receive replayed message");
+                        break;
+                    }
+                    Ok(None) | Err(_) => break,
+                };
+                let (sequence, published, pending) = match msg.info() {
+                    Ok(info) => (info.stream_sequence, info.published, info.pending),
+                    Err(err) => {
+                        warn!(?err, "failed to parse replayed message metadata");
+                        break;
+                    }
+                };
+                if range.is_past_end(sequence, published) {
+                    break;
+                }
+                // The reply subject of JetStream messages is used for acknowledgements
+                let mut nats_msg = msg.message.clone();
+                nats_msg.reply = None;
+                let replay = format!("{}:{sequence}", args.stream);
+                match dispatch_msg(&wrpc, &args.component_id, nats_msg, Some(&replay)).await {
+                    Ok(()) => replayed += 1,
+                    Err(_) => failed += 1,
+                }
+                if pending == 0 {
+                    break;
+                }
+            }
+            info!(
+                component_id = %args.component_id,
+                stream = %args.stream,
+                replayed,
+                failed,
+                "finished replaying messages"
+            );
+        });
+        let mut handlers = self.handler_components.write().await;
+        let Some(bundle) = handlers.get_mut(&component_id) else {
+            // The link was deleted while the replay was set up
+            handle.abort();
+            bail!("component [{component_id}] is not linked to the provider");
+        };
+        bundle.replay_handles.retain(|handle| !handle.is_finished());
+        bundle.replay_handles.push(handle);
+        Ok(())
+    }
 }
 
+/// Deliver a message to a component, returning an error if the component failed to handle it
 #[instrument(level = "debug", skip_all, fields(component_id = %component_id, subject = %nats_msg.subject, reply_to = ?nats_msg.reply))]
-async fn dispatch_msg(wrpc: &WrpcClient, component_id: &str, nats_msg: async_nats::Message) {
+async fn dispatch_msg(
+    wrpc: &WrpcClient,
+    component_id: &str,
+    nats_msg: async_nats::Message,
+    replay: Option<&str>,
+) -> anyhow::Result<()> {
     match nats_msg.headers {
         // If there are some headers on the message they might contain a span context
         // so attempt to attach them.
@@ -243,13 +568,22 @@ async fn dispatch_msg(wrpc: &WrpcClient, component_id: &str, nats_msg: async_nat
     for (k, v) in TraceContextInjector::default_with_span().iter() {
         cx.insert(k.as_str(), v.as_str())
     }
-    if let Err(e) =
-        bindings::wasmcloud::messaging::handler::handle_message(wrpc, Some(cx), &msg).await
-    {
-        error!(
-            error = %e,
-            "Unable to send message"
-        );
+    if let Some(replay) = replay {
+        cx.insert(REPLAY_HEADER, replay);
+    }
+    match bindings::wasmcloud::messaging::handler::handle_message(wrpc, Some(cx), &msg).await {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(e)) => {
+            warn!(error = %e, "component failed to handle message");
+            Err(anyhow!(e))
+        }
+        Err(e) => {
+            error!(
+                error = %e,
+                "Unable to send message"
+            );
+            Err(e)
+        }
     }
 }
 
@@ -272,6 +606,7 @@ impl Provider for NatsMessagingProvider {
             match ConnectionConfig::from_link_config(&link_config) {
                 Ok(cc) => self.default_config.merge(&ConnectionConfig {
                     subscriptions: Box::default(),
+                    consumers: Box::default(),
                     ..cc
                 }),
                 Err(e) => {
@@ -358,17 +693,18 @@ impl Provider for NatsMessagingProvider {
         let component_id = info.get_target_id();
         let mut links = self.handler_components.write().await;
         if let Some(bundle) = links.remove(component_id) {
-            // Note: subscriptions will be closed via Drop on the NatsClientBundle
+            // Note: subscriptions and replays will be closed via Drop on the NatsClientBundle
             let client = &bundle.client;
             debug!(
                 component_id,
-                "dropping NATS client [{}] and associated subscriptions [{}] for (handler) component",
+                "dropping NATS client [{}], associated subscriptions [{}] and replays [{}] for (handler) component",
                 format!(
                     "{}:{}",
                     client.server_info().server_id,
                     client.server_info().client_id
                 ),
                 &bundle.sub_handles.len(),
+                &bundle.replay_handles.len(),
             );
         }
 
@@ -380,6 +716,31 @@ impl Provider for NatsMessagingProvider {
         Ok(())
     }
 
+    /// Handle provider-specific admin operations, currently only `replay`
+    async fn admin_operation(
+        &self,
+        name: &str,
+        args: serde_json::Value,
+    ) -> anyhow::Result<Option<serde_json::Value>> {
+        match name {
+            "replay" => {
+                let args: ReplayArgs =
+                    serde_json::from_value(args).context("invalid replay arguments")?;
+                // Replays may take a long time, so they run in the background to not block
+                // the handling of other provider commands
+                let component_id = args.component_id.clone();
+                let stream = args.stream.clone();
+                self.replay(args).await?;
+                Ok(Some(serde_json::json!({
+                    "component_id": component_id,
+                    "stream": stream,
+                    "status": "started",
+                })))
+            }
+            _ => Ok(None),
+        }
+    }
+
     /// Handle shutdown request by closing all connections
     async fn shutdown(&self) -> anyhow::Result<()> {
         // clear the handler components
@@ -455,6 +816,8 @@ impl bindings::exports::wasmcloud::messaging::consumer::Handler<Option<Context>>
         body: Bytes,
         timeout_ms: u32,
     ) -> anyhow::Result<Result<BrokerMessage, String>> {
+        // Never wait for a reply longer than the invocation itself is waited for
+        let timeout = request_timeout(ctx.as_ref(), Duration::from_millis(timeout_ms.into()));
         let nats_client =
             if let Some(ref source_id) = ctx.and_then(|Context { component, .. }| component) {
                 let actors = self.consumer_components.read().await;
@@ -474,7 +837,10 @@ impl bindings::exports::wasmcloud::messaging::consumer::Handler<Option<Context>>
         // Inject OTEL headers
         let headers = NatsHeaderInjector::default_with_span().into();
 
-        let timeout = Duration::from_millis(timeout_ms.into());
+        let Some(timeout) = timeout else {
+            error!("invocation deadline exceeded before nats request");
+            return Ok(Err("invocation deadline exceeded".into()));
+        };
         // Perform the request with a timeout
         let request_with_timeout = if should_strip_headers(&subject) {
             tokio::time::timeout(timeout, nats_client.request(subject, body)).await
@@ -505,6 +871,125 @@ impl bindings::exports::wasmcloud::messaging::consumer::Handler<Option<Context>>
     }
 }
 
+/// Implement the 'wasmcloud:messaging-ext/request-many' interface for scatter-gather requests
+impl request_many::Handler<Option<Context>> for NatsMessagingProvider {
+    #[instrument(level = "debug", skip(self, ctx, body), fields(subject = %subject, body_len = %body.len()))]
+    async fn request_many(
+        &self,
+        ctx: Option<Context>,
+        subject: String,
+        body: Bytes,
+        options: RequestOptions,
+    ) -> anyhow::Result<
+        Result<
+            (
+                Pin<Box<dyn Stream<Item = Vec<Reply>> + Send>>,
+                Pin<Box<dyn Future<Output = Result<(), String>> + Send>>,
+            ),
+            String,
+        >,
+    > {
+        propagate_trace_for_ctx!(ctx);
+
+        // Never wait for replies longer than the invocation itself is waited for
+        let timeout = request_timeout(
+            ctx.as_ref(),
+            Duration::from_millis(options.timeout_ms.into()),
+        );
+        let nats_client =
+            if let Some(ref source_id) = ctx.and_then(|Context { component, .. }| component) {
+                let actors = self.consumer_components.read().await;
+                let nats_bundle = match actors.get(source_id) {
+                    Some(nats_bundle) => nats_bundle,
+                    None => {
+                        error!("component not linked: {source_id}");
+                        bail!("component not linked: {source_id}")
+                    }
+                };
+                nats_bundle.client.clone()
+            } else {
+                error!("no component in request");
+                bail!("no component in request")
+            };
+        let Some(timeout) = timeout else {
+            error!("invocation deadline exceeded before nats request");
+            return Ok(Err("invocation deadline exceeded".into()));
+        };
+
+        // Subscribe to the inbox before publishing, so that no replies are missed
+        let inbox = nats_client.new_inbox();
+        let replies = match nats_client.subscribe(inbox.clone()).await {
+            Ok(replies) => replies,
+            Err(err) => {
+                error!("failed to subscribe to reply inbox: {err}");
+                return Ok(Err(format!("failed to subscribe to reply inbox: {err}")));
+            }
+        };
+        let res = if should_strip_headers(&subject) {
+            nats_client.publish_with_reply(subject, inbox, body).await
+        } else {
+            let headers = NatsHeaderInjector::default_with_span().into();
+            nats_client
+                .publish_with_reply_and_headers(subject, inbox, headers, body)
+                .await
+        };
+        if let Err(err) = res {
+            error!("nats send error: {err}");
+            return Ok(Err(format!("nats send error: {err}")));
+        }
+        let _ = nats_client.flush().await;
+
+        let replies = limit_replies(
+            replies,
+            timeout,
+            options.max_replies,
+            options.stall_ms.map(|ms| Duration::from_millis(ms.into())),
+        );
+        Ok(Ok(stream_batches(
+            replies.map(anyhow::Ok),
+            DEFAULT_BATCH_SIZE,
+            DEFAULT_BUFFER,
+        )))
+    }
+}
+
+/// Limit the replies to a request to the limits of the request, ending the stream once `timeout`
+/// elapsed, `max_replies` replies were received or no reply was received within `stall` of the
+/// previous one. The stream also ends if the server reports that there are no responders
+fn limit_replies(
+    replies: impl Stream<Item = async_nats::Message> + Send + Unpin + 'static,
+    timeout: Duration,
+    max_replies: Option<u32>,
+    stall: Option<Duration>,
+) -> impl Stream<Item = Reply> + Send + 'static {
+    let deadline = tokio::time::Instant::now() + timeout;
+    let remaining = max_replies.map_or(usize::MAX, |max| max as usize);
+    stream::unfold(
+        (replies, remaining, false),
+        move |(mut replies, remaining, received)| async move {
+            if remaining == 0 {
+                return None;
+            }
+            let until = match stall {
+                Some(stall) if received => deadline.min(tokio::time::Instant::now() + stall),
+                _ => deadline,
+            };
+            let msg = tokio::time::timeout_at(until, replies.next())
+                .await
+                .ok()??;
+            if msg.status == Some(async_nats::StatusCode::NO_RESPONDERS) {
+                debug!("no responders for request");
+                return None;
+            }
+            let reply = Reply {
+                subject: msg.subject.into_string(),
+                body: msg.payload,
+            };
+            Some((reply, (replies, remaining - 1, true)))
+        },
+    )
+}
+
 // In the current version of the NATS server, using headers on certain $SYS.REQ topics will cause server-side
 // parse failures
 fn should_strip_headers(topic: &str) -> bool {
@@ -533,6 +1018,123 @@ mod test {
     use super::*;
     use std::collections::HashMap;
 
+    use futures::StreamExt as _;
+
+    fn reply(subject: &str, status: Option<async_nats::StatusCode>) -> async_nats::Message {
+        async_nats::Message {
+            subject: subject.into(),
+            reply: None,
+            payload: Bytes::from_static(b"ok"),
+            headers: None,
+            status,
+            description: None,
+            length: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_limit_replies() {
+        let replies = |n| {
+            stream::iter((0..n).map(|i| reply(&format!("reply.{i}"), None)))
+                .chain(stream::pending())
+                .boxed()
+        };
+        let subjects = |replies: Vec<Reply>| {
+            replies
+                .into_iter()
+                .map(|Reply { subject, .. }| subject)
+                .collect::<Vec<_>>()
+        };
+
+        // Stops after the maximum number of replies
+        let got = limit_replies(replies(5), Duration::from_secs(10), Some(3), None)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(subjects(got), ["reply.0", "reply.1", "reply.2"]);
+
+        // Stops once the timeout elapsed
+        let got = limit_replies(replies(2), Duration::from_millis(50), None, None)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(subjects(got), ["reply.0", "reply.1"]);
+
+        // Stops once no reply was received within the stall timeout
+        let got = limit_replies(
+            replies(2),
+            Duration::from_secs(10),
+            None,
+            Some(Duration::from_millis(50)),
+        )
+        .collect::<Vec<_>>()
+        .await;
+        assert_eq!(subjects(got), ["reply.0", "reply.1"]);
+
+        // Stops if there are no responders
+        let got = limit_replies(
+            stream::iter([reply("inbox", Some(async_nats::StatusCode::NO_RESPONDERS))])
+                .chain(stream::pending())
+                .boxed(),
+            Duration::from_secs(10),
+            None,
+            None,
+        )
+        .collect::<Vec<_>>()
+        .await;
+        assert!(got.is_empty());
+    }
+
+    #[test]
+    fn test_replay_range() {
+        use async_nats::jetstream::consumer::DeliverPolicy;
+        use time::format_description::well_known::Rfc3339;
+        use time::OffsetDateTime;
+
+        use super::{ReplayArgs, ReplayRange};
+
+        let datetime = |time: &str| OffsetDateTime::parse(time, &Rfc3339).unwrap();
+
+        let range = ReplayRange::new(&ReplayArgs {
+            start_sequence: Some(10),
+            end_sequence: Some(20),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(
+            range.deliver_policy,
+            DeliverPolicy::ByStartSequence { start_sequence: 10 }
+        );
+        let published = datetime("2024-06-01T12:00:00Z");
+        assert!(!range.is_past_end(20, published));
+        assert!(range.is_past_end(21, published));
+
+        let range = ReplayRange::new(&ReplayArgs {
+            start_time: Some("2024-06-01T00:00:00Z".into()),
+            end_time: Some("2024-06-01T12:00:00Z".into()),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(
+            range.deliver_policy,
+            DeliverPolicy::ByStartTime {
+                start_time: datetime("2024-06-01T00:00:00Z")
+            }
+        );
+        assert!(!range.is_past_end(1, published));
+        assert!(range.is_past_end(1, datetime("2024-06-01T12:00:01Z")));
+
+        assert!(ReplayRange::new(&ReplayArgs {
+            start_sequence: Some(1),
+            start_time: Some("2024-06-01T00:00:00Z".into()),
+            ..Default::default()
+        })
+        .is_err());
+        assert!(ReplayRange::new(&ReplayArgs {
+            end_time: Some("yesterday".into()),
+            ..Default::default()
+        })
+        .is_err());
+    }
+
     #[test]
     fn test_default_connection_serialize() {
         // test to verify that we can default a config with partial input
@@ -583,4 +1185,47 @@ mod test {
         assert_eq!(cc.custom_inbox_prefix, Some("_TEST.>".into()));
         Ok(())
     }
+
+    #[test]
+    fn test_consumers_from_map() -> anyhow::Result<()> {
+        let cc = ConnectionConfig::from_map(&HashMap::from([(
+            "consumers".into(),
+            r#"[
+                {"stream": "orders", "consumer": "billing", "max_messages": 10, "max_deliver": 5, "ack_wait_ms": 60000},
+                {"stream": "events", "consumer": "audit", "deliver_subject": "audit.deliver", "filter_subject": "events.>"}
+            ]"#
+            .into(),
+        )]))?;
+        assert_eq!(
+            *cc.consumers,
+            [
+                ConsumerConfig {
+                    stream: "orders".into(),
+                    consumer: "billing".into(),
+                    max_messages: Some(10),
+                    max_bytes: None,
+                    deliver_subject: None,
+                    filter_subject: None,
+                    max_deliver: Some(5),
+                    ack_wait_ms: Some(60000),
+                },
+                ConsumerConfig {
+                    stream: "events".into(),
+                    consumer: "audit".into(),
+                    max_messages: None,
+                    max_bytes: None,
+                    deliver_subject: Some("audit.deliver".into()),
+                    filter_subject: Some("events.>".into()),
+                    max_deliver: None,
+                    ack_wait_ms: None,
+                },
+            ]
+        );
+        assert!(ConnectionConfig::from_map(&HashMap::from([(
+            "consumers".into(),
+            r#"[{"stream": "orders"}]"#.into(),
+        )]))
+        .is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,145 @@
+//! Helpers for bounding work done by a provider on behalf of an invocation by the invocation's
+//! deadline.
+//!
+//! The host sets the [`DEADLINE_HEADER_NAME`] header on every invocation to the point in time after
+//! which the caller stops waiting for a response. Requests made by a provider while handling the
+//! invocation should not outlive it, since their result would be discarded anyway.
+
+use core::time::Duration;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use async_nats::subject::ToSubject;
+use async_nats::{HeaderMap, Message};
+use bytes::Bytes;
+
+use crate::error::{InvocationError, InvocationResult, NetworkError};
+use crate::{Context, DEFAULT_RPC_TIMEOUT_MILLIS};
+
+/// Name of the invocation header holding the deadline of the invocation, in milliseconds since
+/// Unix epoch
+pub const DEADLINE_HEADER_NAME: &str = "deadline";
+
+/// Time reserved for sending the response of an invocation once a request made while handling it
+/// completes
+pub const DEADLINE_SAFETY_MARGIN: Duration = Duration::from_millis(100);
+
+impl Context {
+    /// Get the deadline of the invocation, if set by the host
+    #[must_use]
+    pub fn deadline(&self) -> Option<SystemTime> {
+        let millis = self.tracing.get(DEADLINE_HEADER_NAME)?.parse().ok()?;
+        UNIX_EPOCH.checked_add(Duration::from_millis(millis))
+    }
+
+    /// Get the time remaining until the deadline of the invocation, if set by the host.
+    ///
+    /// Returns [`Duration::ZERO`] if the deadline has passed
+    #[must_use]
+    pub fn remaining(&self) -> Option<Duration> {
+        let deadline = self.deadline()?;
+        Some(
+            deadline
+                .duration_since(SystemTime::now())
+                .unwrap_or_default(),
+        )
+    }
+}
+
+/// Returns the timeout for a request made while handling the invocation with context `cx`, which
+/// is the time remaining until the invocation deadline minus [`DEADLINE_SAFETY_MARGIN`], capped at
+/// `default`. If the invocation has no deadline, `default` is returned.
+///
+/// Returns `None` if the invocation budget is exhausted and no request should be made anymore
+#[must_use]
+pub fn request_timeout(cx: Option<&Context>, default: Duration) -> Option<Duration> {
+    let Some(remaining) = cx.and_then(Context::remaining) else {
+        return Some(default);
+    };
+    remaining
+        .checked_sub(DEADLINE_SAFETY_MARGIN)
+        .filter(|timeout| !timeout.is_zero())
+        .map(|timeout| timeout.min(default))
+}
+
+/// Send a NATS request on behalf of the invocation with context `cx`, timing out before the
+/// deadline of the invocation, or after [`DEFAULT_RPC_TIMEOUT_MILLIS`] if it has none.
+///
+/// # Errors
+///
+/// Returns [`InvocationError::Timeout`] if the invocation budget is exhausted before or while
+/// the request is sent, and [`InvocationError::Network`] if the request fails
+pub async fn request(
+    nats: &async_nats::Client,
+    cx: Option<&Context>,
+    subject: impl ToSubject,
+    headers: Option<HeaderMap>,
+    payload: Bytes,
+) -> InvocationResult<Message> {
+    let timeout =
+        request_timeout(cx, DEFAULT_RPC_TIMEOUT_MILLIS).ok_or(InvocationError::Timeout)?;
+    let mut request = async_nats::Request::new()
+        .payload(payload)
+        .timeout(Some(timeout));
+    if let Some(headers) = headers {
+        request = request.headers(headers);
+    }
+    match nats.send_request(subject, request).await {
+        Ok(msg) => Ok(msg),
+        Err(err) if err.kind() == async_nats::RequestErrorKind::TimedOut => {
+            Err(InvocationError::Timeout)
+        }
+        Err(err) => Err(NetworkError::from(err).into()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn context(deadline: SystemTime) -> Context {
+        let millis = deadline
+            .duration_since(UNIX_EPOCH)
+            .expect("deadline before Unix epoch")
+            .as_millis();
+        Context {
+            component: None,
+            tracing: HashMap::from([(DEADLINE_HEADER_NAME.into(), millis.to_string())]),
+        }
+    }
+
+    #[test]
+    fn timeout_without_deadline() {
+        let default = Duration::from_secs(2);
+        assert_eq!(request_timeout(None, default), Some(default));
+        assert_eq!(
+            request_timeout(Some(&Context::default()), default),
+            Some(default)
+        );
+    }
+
+    #[test]
+    fn timeout_within_deadline() {
+        let default = Duration::from_secs(2);
+        let cx = context(SystemTime::now() + Duration::from_secs(60));
+        assert_eq!(request_timeout(Some(&cx), default), Some(default));
+
+        let cx = context(SystemTime::now() + Duration::from_secs(1));
+        let timeout = request_timeout(Some(&cx), default).expect("budget should remain");
+        assert!(timeout <= Duration::from_secs(1) - DEADLINE_SAFETY_MARGIN);
+        assert!(timeout > Duration::from_millis(500));
+    }
+
+    #[test]
+    fn timeout_after_deadline() {
+        let default = Duration::from_secs(2);
+        let cx = context(SystemTime::now() + DEADLINE_SAFETY_MARGIN / 2);
+        assert_eq!(request_timeout(Some(&cx), default), None);
+
+        let cx = context(SystemTime::now() - Duration::from_secs(1));
+        assert_eq!(cx.remaining(), Some(Duration::ZERO));
+        assert_eq!(request_timeout(Some(&cx), default), None);
+    }
+}
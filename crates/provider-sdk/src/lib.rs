@@ -9,8 +9,11 @@ use provider::ProviderInitState;
 use tracing::{error, info, warn};
 use wasmcloud_core::secrets::SecretValue;
 
+pub mod deadline;
 pub mod error;
+mod metrics;
 pub mod provider;
+pub mod stream;
 
 #[cfg(feature = "otel")]
 pub mod otel;
@@ -23,8 +26,9 @@ pub use tracing_subscriber;
 pub use wasmcloud_core as core;
 /// Re-export of types from [`wasmcloud_core`]
 pub use wasmcloud_core::{
-    HealthCheckRequest, HealthCheckResponse, HostData, InterfaceLinkDefinition, WitFunction,
-    WitInterface, WitNamespace, WitPackage,
+    ConfigField, ConfigFieldKind, ConfigSchema, HealthCheckRequest, HealthCheckResponse, HostData,
+    InterfaceLinkDefinition, LinkHealthCheck, LinkHealthStatus, LinkValidationError,
+    ValidatedConfig, WitFunction, WitInterface, WitNamespace, WitPackage,
 };
 pub use wasmcloud_tracing;
 
@@ -146,6 +150,21 @@ pub struct LinkConfig<'a> {
     pub wit_metadata: (&'a WitNamespace, &'a WitPackage, &'a Vec<WitInterface>),
 }
 
+impl LinkConfig<'_> {
+    /// Validate the configuration and secrets of the link against `schema`, returning the
+    /// values of all fields with defaults applied, or all field-level errors found
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`ConfigSchema::validate`]
+    pub fn validate(
+        &self,
+        schema: &ConfigSchema,
+    ) -> Result<ValidatedConfig, Vec<LinkValidationError>> {
+        schema.validate(self.config, self.secrets)
+    }
+}
+
 /// Configuration object is made available when a provider is started, to assist in init
 ///
 /// This trait exists to both obscure the underlying implementation and control what information
@@ -301,6 +320,51 @@ pub trait Provider<E = anyhow::Error>: Sync {
         async { Ok(()) }
     }
 
+    /// Validate a link where this provider is the source, without establishing it.
+    ///
+    /// Called by the host before a link is put with validation requested, e.g. using
+    /// `wash link put --validate`, with the configuration and secrets that
+    /// [`Provider::receive_link_config_as_source`] would receive. Implement this to check that
+    /// required configuration is present and that backends can be reached, returning the errors
+    /// found. The default implementation validates the link against
+    /// [`Provider::config_schema`], accepting every link if the provider declares no schema.
+    fn validate_link_config_as_source(
+        &self,
+        config: LinkConfig<'_>,
+    ) -> impl Future<Output = Result<Vec<LinkValidationError>, E>> + Send {
+        let errors = self
+            .config_schema()
+            .and_then(|schema| config.validate(&schema).err())
+            .unwrap_or_default();
+        async { Ok(errors) }
+    }
+
+    /// Validate a link where this provider is the target, without establishing it.
+    ///
+    /// See [`Provider::validate_link_config_as_source`], the configuration and secrets are the
+    /// ones [`Provider::receive_link_config_as_target`] would receive.
+    fn validate_link_config_as_target(
+        &self,
+        config: LinkConfig<'_>,
+    ) -> impl Future<Output = Result<Vec<LinkValidationError>, E>> + Send {
+        let errors = self
+            .config_schema()
+            .and_then(|schema| config.validate(&schema).err())
+            .unwrap_or_default();
+        async { Ok(errors) }
+    }
+
+    /// Schema of the link configuration and secrets supported by the provider.
+    ///
+    /// The schema is used to validate links by default, see
+    /// [`Provider::validate_link_config_as_source`], and is served to the lattice, so that tools
+    /// like `wash` can show the configuration the provider supports. Use
+    /// [`LinkConfig::validate`] to parse link configuration against it when receiving links.
+    /// The default implementation declares no schema.
+    fn config_schema(&self) -> Option<ConfigSchema> {
+        None
+    }
+
     /// Notify the provider that the link is dropped where the provider is the target
     fn delete_link_as_target(
         &self,
@@ -327,10 +391,53 @@ pub trait Provider<E = anyhow::Error>: Sync {
             Ok(HealthCheckResponse {
                 healthy: true,
                 message: None,
+                links: Vec::new(),
             })
         }
     }
 
+    /// Check the health of the links of the provider. Called at regular intervals by the host,
+    /// along with [`Provider::health_request`].
+    ///
+    /// Implement this to probe the backends of established links, e.g. by pinging a connection,
+    /// returning the health of each link. Degraded and unhealthy links are reported in the host
+    /// inventory, while the health of the provider itself is determined by
+    /// [`Provider::health_request`]. The default implementation reports no links.
+    fn link_health(&self) -> impl Future<Output = Result<Vec<LinkHealthCheck>, E>> + Send {
+        async { Ok(Vec::new()) }
+    }
+
+    /// Dump the internal state of the provider, e.g. the number of cached connections, to aid
+    /// debugging. Requested by operators using `wash provider admin dump-state`.
+    ///
+    /// The state must not contain secrets. The default implementation exposes no state.
+    fn dump_state(&self) -> impl Future<Output = Result<Option<serde_json::Value>, E>> + Send {
+        async { Ok(None) }
+    }
+
+    /// Flush the caches of the provider, e.g. cached clients or responses, so that they are
+    /// recreated on next use. Requested by operators using `wash provider admin flush-caches`.
+    ///
+    /// The default implementation does nothing.
+    fn flush_caches(&self) -> impl Future<Output = Result<(), E>> + Send {
+        async { Ok(()) }
+    }
+
+    /// Perform an administrative operation specific to the provider named `name`, e.g. replaying
+    /// messages to a component. Requested by operators using
+    /// `wash provider admin operation <name> [args]`.
+    ///
+    /// Returns the output of the operation, or `None` if the provider does not support the
+    /// operation. The default implementation supports no operations.
+    fn admin_operation(
+        &self,
+        name: &str,
+        args: serde_json::Value,
+    ) -> impl Future<Output = Result<Option<serde_json::Value>, E>> + Send {
+        let _ = (name, args);
+        async { Ok(None) }
+    }
+
     /// Handle system shutdown message
     fn shutdown(&self) -> impl Future<Output = Result<(), E>> + Send {
         async { Ok(()) }
@@ -0,0 +1,147 @@
+//! Metrics of the invocations served by providers, exported via the OpenTelemetry pipeline
+//! configured by [`initialize_observability!`](crate::initialize_observability).
+//!
+//! Invocations served with [`serve_provider_exports`](crate::serve_provider_exports) are counted
+//! and timed automatically, labeled by the WIT interface and function invoked and the name of the
+//! link the invocation was sent over.
+
+use core::cell::RefCell;
+use core::pin::Pin;
+use core::time::Duration;
+
+use std::sync::OnceLock;
+
+use futures::{stream, Stream, StreamExt as _};
+use wasmcloud_tracing::{global, Counter, Histogram, KeyValue};
+
+/// Name of the meter used for provider invocation metrics
+const METER_NAME: &str = "wasmcloud-provider-sdk";
+
+thread_local! {
+    /// Link name of the invocation most recently accepted on this thread.
+    ///
+    /// Invocations are accepted by [`WrpcClient`](crate::provider::WrpcClient) while the
+    /// invocation stream yielding them is polled, so the link name is cleared before and taken
+    /// after each poll of the stream and attached to the invocation it yields, see
+    /// [`with_link_names`].
+    static ACCEPTED_LINK_NAME: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Record the link name of an accepted invocation, see [`take_accepted_link_name`]
+pub(crate) fn set_accepted_link_name(link_name: &str) {
+    ACCEPTED_LINK_NAME.set(Some(link_name.to_string()));
+}
+
+/// Take the link name of the invocation most recently accepted on this thread
+pub(crate) fn take_accepted_link_name() -> Option<String> {
+    ACCEPTED_LINK_NAME.take()
+}
+
+/// Pair each invocation yielded by `invocations` with the link name it was accepted on, if known.
+/// Link names are only ever attributed to invocations accepted in polls of the same stream
+pub(crate) fn with_link_names<T>(
+    mut invocations: Pin<Box<dyn Stream<Item = T> + Send>>,
+) -> impl Stream<Item = (T, Option<String>)> + Send + Unpin {
+    let mut link_name = None;
+    stream::poll_fn(move |cx| {
+        take_accepted_link_name();
+        let item = invocations.poll_next_unpin(cx);
+        if let Some(accepted) = take_accepted_link_name() {
+            link_name = Some(accepted);
+        }
+        item.map(|item| item.map(|item| (item, link_name.take())))
+    })
+}
+
+/// Instruments of the invocations served by a provider
+struct ProviderMetrics {
+    invocations: Counter<u64>,
+    errors: Counter<u64>,
+    duration: Histogram<f64>,
+}
+
+/// Returns the provider invocation instruments, which are created on first use, so that they are
+/// created with the meter provider configured by the provider binary
+fn metrics() -> &'static ProviderMetrics {
+    static METRICS: OnceLock<ProviderMetrics> = OnceLock::new();
+    METRICS.get_or_init(|| {
+        let meter = global::meter(METER_NAME);
+        ProviderMetrics {
+            invocations: meter
+                .u64_counter("wasmcloud_provider.invocations")
+                .with_description("Number of invocations served by the provider")
+                .build(),
+            errors: meter
+                .u64_counter("wasmcloud_provider.invocation.errors")
+                .with_description("Number of invocations the provider failed to serve")
+                .build(),
+            duration: meter
+                .f64_histogram("wasmcloud_provider.invocation.duration")
+                .with_description("Duration the provider took to serve an invocation")
+                .with_unit("s")
+                .build(),
+        }
+    })
+}
+
+/// Attributes of the metrics of an invocation of `function` from `instance` sent over the link
+/// named `link_name`
+pub(crate) fn invocation_attributes(
+    instance: &str,
+    function: &str,
+    link_name: Option<String>,
+) -> [KeyValue; 3] {
+    [
+        KeyValue::new("interface", instance.to_string()),
+        KeyValue::new("function", function.to_string()),
+        KeyValue::new("link_name", link_name.unwrap_or_else(|| "unknown".into())),
+    ]
+}
+
+/// Record an invocation, which was served successfully if `ok` is `true`, taking `elapsed`
+pub(crate) fn record_invocation(attributes: &[KeyValue], elapsed: Duration, ok: bool) {
+    let metrics = metrics();
+    metrics.invocations.add(1, attributes);
+    if !ok {
+        metrics.errors.add(1, attributes);
+    }
+    metrics.duration.record(elapsed.as_secs_f64(), attributes);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn take_accepted_link_name_once() {
+        assert_eq!(take_accepted_link_name(), None);
+        set_accepted_link_name("default");
+        set_accepted_link_name("other");
+        assert_eq!(take_accepted_link_name().as_deref(), Some("other"));
+        assert_eq!(take_accepted_link_name(), None);
+
+        let attributes = invocation_attributes("wrpc:keyvalue/store@0.2.0-draft", "get", None);
+        assert_eq!(attributes[2], KeyValue::new("link_name", "unknown"));
+    }
+
+    #[tokio::test]
+    async fn attach_link_names_to_invocations() {
+        // Invocations accepted by one stream are never attributed to another
+        let accepting = with_link_names(Box::pin(stream::iter(["a", "b"]).map(|invocation| {
+            set_accepted_link_name(&format!("link-{invocation}"));
+            invocation
+        })));
+        let other = with_link_names(Box::pin(stream::iter(["c"])));
+        let mut invocations = stream::select(accepting, other).collect::<Vec<_>>().await;
+        invocations.sort();
+        assert_eq!(
+            invocations,
+            [
+                ("a", Some("link-a".to_string())),
+                ("b", Some("link-b".to_string())),
+                ("c", None),
+            ]
+        );
+        assert_eq!(take_accepted_link_name(), None);
+    }
+}
@@ -4,7 +4,7 @@ use core::future::Future;
 
 use core::pin::{pin, Pin};
 use core::time::Duration;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::BufRead;
 use std::sync::Arc;
 
@@ -13,20 +13,26 @@ use async_nats::subject::ToSubject as _;
 use async_nats::HeaderMap;
 use base64::Engine;
 use bytes::Bytes;
-use futures::{stream, Stream, StreamExt as _, TryStreamExt as _};
+use futures::{future, stream, Stream, StreamExt as _, TryStreamExt as _};
 use nkeys::XKey;
 use once_cell::sync::OnceCell;
 use serde::{Deserialize, Serialize};
 use tokio::sync::{broadcast, mpsc, oneshot, RwLock};
 use tokio::task::{spawn_blocking, JoinSet};
+use tokio::time::Instant;
 use tokio::{select, spawn, try_join};
 use tracing::{debug, error, info, instrument, trace, warn, Instrument as _};
 use wasmcloud_core::nats::convert_header_map_to_hashmap;
-use wasmcloud_core::rpc::{health_subject, link_del_subject, link_put_subject, shutdown_subject};
+use wasmcloud_core::rpc::{
+    config_schema_subject, health_subject, link_del_subject, link_put_subject,
+    link_validate_subject, provider_admin_subject, shutdown_subject, ProviderAdminLink,
+    ProviderAdminRequest, ProviderAdminResponse,
+};
 use wasmcloud_core::secrets::SecretValue;
 use wasmcloud_core::{
-    provider_config_update_subject, HealthCheckRequest, HealthCheckResponse, HostData,
-    InterfaceLinkDefinition, LatticeTarget,
+    prefixed_subject, provider_config_update_subject, ConfigSchema, HealthCheckRequest,
+    HealthCheckResponse, HostData, InterfaceLinkDefinition, LatticeTarget, LinkValidationError,
+    LinkValidationResponse, WitInterface, WitNamespace, WitPackage,
 };
 
 #[cfg(feature = "otel")]
@@ -36,6 +42,7 @@ use wasmcloud_tracing::context::attach_span_context;
 use wrpc_transport::InvokeExt as _;
 
 use crate::error::{ProviderInitError, ProviderInitResult};
+use crate::metrics;
 use crate::{with_connection_event_logging, Context, LinkConfig, Provider, DEFAULT_NATS_ADDR};
 
 /// Name of the header that should be passed for invocations that identifies the source
@@ -129,200 +136,369 @@ struct ShutdownMessage {
 /// `on_item` is an async handler
 macro_rules! process_until_quit {
     ($sub:ident, $channel:ident, $msg:ident, $on_item:tt) => {
-        spawn(async move {
-            loop {
-                select! {
-                    _ = $channel.recv() => {
-                        let _ = $sub.unsubscribe().await;
-                        break;
-                    },
-                    __msg = $sub.next() => {
-                        match __msg {
-                            None => break,
-                            Some($msg) => $on_item
-                        }
+        loop {
+            select! {
+                _ = $channel.recv() => {
+                    let _ = $sub.unsubscribe().await;
+                    break;
+                },
+                __msg = $sub.next() => {
+                    match __msg {
+                        None => break,
+                        Some($msg) => $on_item
                     }
                 }
             }
-        })
+        }
     };
 }
 
+/// A long-lived task processing provider commands, see [`ProviderCommandReceivers::with_spawner`]
+pub type CommandTask = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
 async fn subscribe_health(
     nats: Arc<async_nats::Client>,
     mut quit: broadcast::Receiver<()>,
-    lattice: &str,
-    provider_key: &str,
+    subject: String,
+    spawn: &(dyn Fn(&'static str, CommandTask) + Sync),
 ) -> ProviderInitResult<mpsc::Receiver<(HealthCheckRequest, oneshot::Sender<HealthCheckResponse>)>>
 {
-    let mut sub = nats
-        .subscribe(health_subject(lattice, provider_key))
-        .await?;
+    let mut sub = nats.subscribe(subject).await?;
     let (health_tx, health_rx) = mpsc::channel(1);
-    spawn({
-        let nats = Arc::clone(&nats);
-        async move {
-            process_until_quit!(sub, quit, msg, {
-                let (tx, rx) = oneshot::channel();
-                if let Err(err) = health_tx.send((HealthCheckRequest {}, tx)).await {
-                    error!(%err, "failed to send health check request");
-                    continue;
-                }
-                match rx.await.as_ref().map(serde_json::to_vec) {
-                    Err(err) => {
-                        error!(%err, "failed to receive health check response");
+    spawn(
+        "health_subscription",
+        Box::pin({
+            let nats = Arc::clone(&nats);
+            async move {
+                process_until_quit!(sub, quit, msg, {
+                    let (tx, rx) = oneshot::channel();
+                    if let Err(err) = health_tx.send((HealthCheckRequest {}, tx)).await {
+                        error!(%err, "failed to send health check request");
+                        continue;
                     }
-                    Ok(Ok(t)) => {
-                        if let Some(reply_to) = msg.reply {
-                            if let Err(err) = nats.publish(reply_to, t.into()).await {
-                                error!(%err, "failed sending health check response");
+                    match rx.await.as_ref().map(serde_json::to_vec) {
+                        Err(err) => {
+                            error!(%err, "failed to receive health check response");
+                        }
+                        Ok(Ok(t)) => {
+                            if let Some(reply_to) = msg.reply {
+                                if let Err(err) = nats.publish(reply_to, t.into()).await {
+                                    error!(%err, "failed sending health check response");
+                                }
                             }
                         }
+                        Ok(Err(err)) => {
+                            // extremely unlikely that InvocationResponse would fail to serialize
+                            error!(%err, "failed serializing HealthCheckResponse");
+                        }
                     }
-                    Ok(Err(err)) => {
-                        // extremely unlikely that InvocationResponse would fail to serialize
-                        error!(%err, "failed serializing HealthCheckResponse");
-                    }
-                }
-            });
-        }
-        .instrument(tracing::debug_span!("subscribe_health"))
-    });
+                });
+            }
+            .instrument(tracing::debug_span!("subscribe_health"))
+        }),
+    );
     Ok(health_rx)
 }
 
+async fn subscribe_config_schema(
+    nats: Arc<async_nats::Client>,
+    mut quit: broadcast::Receiver<()>,
+    subject: String,
+    spawn: &(dyn Fn(&'static str, CommandTask) + Sync),
+) -> ProviderInitResult<mpsc::Receiver<oneshot::Sender<Option<ConfigSchema>>>> {
+    let mut sub = nats.subscribe(subject).await?;
+    let (schema_tx, schema_rx) = mpsc::channel(1);
+    spawn(
+        "config_schema_subscription",
+        Box::pin({
+            let nats = Arc::clone(&nats);
+            async move {
+                process_until_quit!(sub, quit, msg, {
+                    let (tx, rx) = oneshot::channel();
+                    if let Err(err) = schema_tx.send(tx).await {
+                        error!(%err, "failed to send config schema request");
+                        continue;
+                    }
+                    match rx.await.as_ref().map(serde_json::to_vec) {
+                        Err(err) => {
+                            error!(%err, "failed to receive config schema response");
+                        }
+                        Ok(Ok(t)) => {
+                            if let Some(reply_to) = msg.reply {
+                                if let Err(err) = nats.publish(reply_to, t.into()).await {
+                                    error!(%err, "failed sending config schema response");
+                                }
+                            }
+                        }
+                        Ok(Err(err)) => {
+                            error!(%err, "failed serializing ConfigSchema");
+                        }
+                    }
+                });
+            }
+            .instrument(tracing::debug_span!("subscribe_config_schema"))
+        }),
+    );
+    Ok(schema_rx)
+}
+
+async fn subscribe_admin(
+    nats: Arc<async_nats::Client>,
+    mut quit: broadcast::Receiver<()>,
+    subject: String,
+    spawn: &(dyn Fn(&'static str, CommandTask) + Sync),
+) -> ProviderInitResult<
+    mpsc::Receiver<(ProviderAdminRequest, oneshot::Sender<ProviderAdminResponse>)>,
+> {
+    let mut sub = nats.subscribe(subject).await?;
+    let (admin_tx, admin_rx) = mpsc::channel(1);
+    spawn(
+        "admin_subscription",
+        Box::pin({
+            let nats = Arc::clone(&nats);
+            async move {
+                process_until_quit!(sub, quit, msg, {
+                    let res = match serde_json::from_slice::<ProviderAdminRequest>(&msg.payload) {
+                        Ok(req) => {
+                            let (tx, rx) = oneshot::channel();
+                            if let Err(err) = admin_tx.send((req, tx)).await {
+                                error!(%err, "failed to send admin request");
+                                continue;
+                            }
+                            match rx.await {
+                                Ok(res) => res,
+                                Err(err) => {
+                                    error!(%err, "failed to receive admin response");
+                                    continue;
+                                }
+                            }
+                        }
+                        Err(err) => {
+                            warn!(%err, "received invalid admin request");
+                            ProviderAdminResponse::error(format!("invalid admin request: {err}"))
+                        }
+                    };
+                    match serde_json::to_vec(&res) {
+                        Ok(t) => {
+                            if let Some(reply_to) = msg.reply {
+                                if let Err(err) = nats.publish(reply_to, t.into()).await {
+                                    error!(%err, "failed sending admin response");
+                                }
+                            }
+                        }
+                        Err(err) => {
+                            error!(%err, "failed serializing ProviderAdminResponse");
+                        }
+                    }
+                });
+            }
+            .instrument(tracing::debug_span!("subscribe_admin"))
+        }),
+    );
+    Ok(admin_rx)
+}
+
 async fn subscribe_shutdown(
     nats: Arc<async_nats::Client>,
     quit: broadcast::Sender<()>,
-    lattice: &str,
-    provider_key: &str,
+    subject: String,
     host_id: impl Into<Arc<str>>,
+    spawn: &(dyn Fn(&'static str, CommandTask) + Sync),
 ) -> ProviderInitResult<mpsc::Receiver<oneshot::Sender<()>>> {
-    let mut sub = nats
-        .subscribe(shutdown_subject(lattice, provider_key, "default"))
-        .await?;
+    let mut sub = nats.subscribe(subject).await?;
     let (shutdown_tx, shutdown_rx) = mpsc::channel(1);
     let host_id = host_id.into();
-    spawn({
-        async move {
-            loop {
-                let msg = sub.next().await;
-                // Check if we really need to shut down
-                if let Some(async_nats::Message {
-                    reply: Some(reply_to),
-                    payload,
-                    ..
-                }) = msg
-                {
-                    let ShutdownMessage {
-                        host_id: ref req_host_id,
-                    } = serde_json::from_slice(&payload).unwrap_or_default();
-                    if req_host_id == host_id.as_ref() {
-                        info!("Received termination signal and stopping");
-                        // Tell provider to shutdown - before we shut down nats subscriptions,
-                        // in case it needs to do any message passing during shutdown
-                        let (tx, rx) = oneshot::channel();
-                        match shutdown_tx.send(tx).await {
-                            Ok(()) => {
-                                if let Err(err) = rx.await {
-                                    error!(%err, "failed to await shutdown");
+    spawn(
+        "shutdown_subscription",
+        Box::pin({
+            async move {
+                loop {
+                    let msg = sub.next().await;
+                    // Check if we really need to shut down
+                    if let Some(async_nats::Message {
+                        reply: Some(reply_to),
+                        payload,
+                        ..
+                    }) = msg
+                    {
+                        let ShutdownMessage {
+                            host_id: ref req_host_id,
+                        } = serde_json::from_slice(&payload).unwrap_or_default();
+                        if req_host_id == host_id.as_ref() {
+                            info!("Received termination signal and stopping");
+                            // Tell provider to shutdown - before we shut down nats subscriptions,
+                            // in case it needs to do any message passing during shutdown
+                            let (tx, rx) = oneshot::channel();
+                            match shutdown_tx.send(tx).await {
+                                Ok(()) => {
+                                    if let Err(err) = rx.await {
+                                        error!(%err, "failed to await shutdown");
+                                    }
                                 }
+                                Err(err) => error!(%err, "failed to send shutdown"),
                             }
-                            Err(err) => error!(%err, "failed to send shutdown"),
-                        }
-                        if let Err(err) = nats.publish(reply_to, "shutting down".into()).await {
-                            warn!(%err, "failed to send shutdown ack");
-                        }
-                        // unsubscribe from shutdown topic
-                        if let Err(err) = sub.unsubscribe().await {
-                            warn!(%err, "failed to unsubscribe from shutdown topic");
-                        }
-                        // send shutdown signal to all listeners: quit all subscribers and signal main thread to quit
-                        if let Err(err) = quit.send(()) {
-                            error!(%err, "Problem shutting down:  failure to send signal");
+                            if let Err(err) = nats.publish(reply_to, "shutting down".into()).await {
+                                warn!(%err, "failed to send shutdown ack");
+                            }
+                            // unsubscribe from shutdown topic
+                            if let Err(err) = sub.unsubscribe().await {
+                                warn!(%err, "failed to unsubscribe from shutdown topic");
+                            }
+                            // send shutdown signal to all listeners: quit all subscribers and signal main thread to quit
+                            if let Err(err) = quit.send(()) {
+                                error!(%err, "Problem shutting down:  failure to send signal");
+                            }
+                            break;
                         }
-                        break;
+                        trace!("Ignoring termination signal (request targeted for different host)");
                     }
-                    trace!("Ignoring termination signal (request targeted for different host)");
                 }
             }
-        }
-        .instrument(tracing::debug_span!("shutdown_subscriber"))
-    });
+            .instrument(tracing::debug_span!("shutdown_subscriber"))
+        }),
+    );
     Ok(shutdown_rx)
 }
 
 async fn subscribe_link_put(
     nats: Arc<async_nats::Client>,
     mut quit: broadcast::Receiver<()>,
-    lattice: &str,
-    provider_xkey: &str,
+    subject: String,
+    spawn: &(dyn Fn(&'static str, CommandTask) + Sync),
 ) -> ProviderInitResult<mpsc::Receiver<(InterfaceLinkDefinition, oneshot::Sender<()>)>> {
     let (link_put_tx, link_put_rx) = mpsc::channel(1);
-    let mut sub = nats
-        .subscribe(link_put_subject(lattice, provider_xkey))
-        .await?;
-    spawn(async move {
-        process_until_quit!(sub, quit, msg, {
-            match serde_json::from_slice::<InterfaceLinkDefinition>(&msg.payload) {
-                Ok(ld) => {
-                    let span = tracing::Span::current();
-                    span.record("source_id", tracing::field::display(&ld.source_id));
-                    span.record("target", tracing::field::display(&ld.target));
-                    span.record("wit_namespace", tracing::field::display(&ld.wit_namespace));
-                    span.record("wit_package", tracing::field::display(&ld.wit_package));
-                    span.record(
-                        "wit_interfaces",
-                        tracing::field::display(&ld.interfaces.join(",")),
-                    );
-                    span.record("link_name", tracing::field::display(&ld.name));
-                    let (tx, rx) = oneshot::channel();
-                    if let Err(err) = link_put_tx.send((ld, tx)).await {
-                        error!(%err, "failed to send link put request");
-                        continue;
+    let mut sub = nats.subscribe(subject).await?;
+    spawn(
+        "link_put_subscription",
+        Box::pin(async move {
+            process_until_quit!(sub, quit, msg, {
+                match serde_json::from_slice::<InterfaceLinkDefinition>(&msg.payload) {
+                    Ok(ld) => {
+                        let span = tracing::Span::current();
+                        span.record("source_id", tracing::field::display(&ld.source_id));
+                        span.record("target", tracing::field::display(&ld.target));
+                        span.record("wit_namespace", tracing::field::display(&ld.wit_namespace));
+                        span.record("wit_package", tracing::field::display(&ld.wit_package));
+                        span.record(
+                            "wit_interfaces",
+                            tracing::field::display(&ld.interfaces.join(",")),
+                        );
+                        span.record("link_name", tracing::field::display(&ld.name));
+                        let (tx, rx) = oneshot::channel();
+                        if let Err(err) = link_put_tx.send((ld, tx)).await {
+                            error!(%err, "failed to send link put request");
+                            continue;
+                        }
+                        if let Err(err) = rx.await {
+                            error!(%err, "failed to await link_put");
+                        }
                     }
-                    if let Err(err) = rx.await {
-                        error!(%err, "failed to await link_put");
+                    Err(err) => {
+                        error!(%err, "received invalid link def data on message");
                     }
                 }
-                Err(err) => {
-                    error!(%err, "received invalid link def data on message");
-                }
-            }
-        });
-    });
+            });
+        }),
+    );
     Ok(link_put_rx)
 }
 
+async fn subscribe_link_validate(
+    nats: Arc<async_nats::Client>,
+    mut quit: broadcast::Receiver<()>,
+    subject: String,
+    spawn: &(dyn Fn(&'static str, CommandTask) + Sync),
+) -> ProviderInitResult<
+    mpsc::Receiver<(
+        InterfaceLinkDefinition,
+        oneshot::Sender<LinkValidationResponse>,
+    )>,
+> {
+    let (link_validate_tx, link_validate_rx) = mpsc::channel(1);
+    let mut sub = nats.subscribe(subject).await?;
+    spawn(
+        "link_validate_subscription",
+        Box::pin({
+            let nats = Arc::clone(&nats);
+            async move {
+                process_until_quit!(sub, quit, msg, {
+                    let res = match serde_json::from_slice::<InterfaceLinkDefinition>(&msg.payload)
+                    {
+                        Ok(ld) => {
+                            let (tx, rx) = oneshot::channel();
+                            if let Err(err) = link_validate_tx.send((ld, tx)).await {
+                                error!(%err, "failed to send link validate request");
+                                continue;
+                            }
+                            match rx.await {
+                                Ok(res) => res,
+                                Err(err) => {
+                                    error!(%err, "failed to await link validation");
+                                    continue;
+                                }
+                            }
+                        }
+                        Err(err) => LinkValidationResponse {
+                            errors: vec![LinkValidationError {
+                                field: None,
+                                message: format!("received invalid link def data: {err}"),
+                            }],
+                        },
+                    };
+                    let Some(reply_to) = msg.reply else {
+                        continue;
+                    };
+                    match serde_json::to_vec(&res) {
+                        Ok(buf) => {
+                            if let Err(err) = nats.publish(reply_to, buf.into()).await {
+                                error!(%err, "failed sending link validation response");
+                            }
+                        }
+                        Err(err) => {
+                            error!(%err, "failed serializing LinkValidationResponse");
+                        }
+                    }
+                });
+            }
+            .instrument(tracing::debug_span!("subscribe_link_validate"))
+        }),
+    );
+    Ok(link_validate_rx)
+}
+
 async fn subscribe_link_del(
     nats: Arc<async_nats::Client>,
     mut quit: broadcast::Receiver<()>,
-    lattice: &str,
-    provider_key: &str,
+    subject: String,
+    spawn: &(dyn Fn(&'static str, CommandTask) + Sync),
 ) -> ProviderInitResult<mpsc::Receiver<(InterfaceLinkDefinition, oneshot::Sender<()>)>> {
-    let subject = link_del_subject(lattice, provider_key).to_subject();
+    let subject = subject.to_subject();
     debug!(%subject, "subscribing for link del");
     let mut sub = nats.subscribe(subject.clone()).await?;
     let (link_del_tx, link_del_rx) = mpsc::channel(1);
     let span = tracing::trace_span!("subscribe_link_del", %subject);
     spawn(
-        async move {
-            process_until_quit!(sub, quit, msg, {
-                if let Ok(ld) = serde_json::from_slice::<InterfaceLinkDefinition>(&msg.payload) {
-                    let (tx, rx) = oneshot::channel();
-                    if let Err(err) = link_del_tx.send((ld, tx)).await {
-                        error!(%err, "failed to send link del request");
-                        continue;
-                    }
-                    if let Err(err) = rx.await {
-                        error!(%err, "failed to await link_del");
+        "link_del_subscription",
+        Box::pin(
+            async move {
+                process_until_quit!(sub, quit, msg, {
+                    if let Ok(ld) = serde_json::from_slice::<InterfaceLinkDefinition>(&msg.payload)
+                    {
+                        let (tx, rx) = oneshot::channel();
+                        if let Err(err) = link_del_tx.send((ld, tx)).await {
+                            error!(%err, "failed to send link del request");
+                            continue;
+                        }
+                        if let Err(err) = rx.await {
+                            error!(%err, "failed to await link_del");
+                        }
+                    } else {
+                        error!("received invalid link on link_del");
                     }
-                } else {
-                    error!("received invalid link on link_del");
-                }
-            });
-        }
-        .instrument(span),
+                });
+            }
+            .instrument(span),
+        ),
     );
     Ok(link_del_rx)
 }
@@ -335,37 +511,38 @@ async fn subscribe_link_del(
 async fn subscribe_config_update(
     nats: Arc<async_nats::Client>,
     mut quit: broadcast::Receiver<()>,
-    lattice: &str,
-    provider_key: &str,
+    subject: String,
+    spawn: &(dyn Fn(&'static str, CommandTask) + Sync),
 ) -> ProviderInitResult<mpsc::Receiver<(HashMap<String, String>, oneshot::Sender<()>)>> {
     let (config_update_tx, config_update_rx) = mpsc::channel(1);
-    let mut sub = nats
-        .subscribe(provider_config_update_subject(lattice, provider_key).to_subject())
-        .await?;
-    spawn({
-        async move {
-            process_until_quit!(sub, quit, msg, {
-                match serde_json::from_slice::<HashMap<String, String>>(&msg.payload) {
-                    Ok(update) => {
-                        let (tx, rx) = oneshot::channel();
-                        // Perform the config update on the host
-                        if let Err(err) = config_update_tx.send((update, tx)).await {
-                            error!(%err, "failed to send config update");
-                            continue;
+    let mut sub = nats.subscribe(subject).await?;
+    spawn(
+        "config_update_subscription",
+        Box::pin({
+            async move {
+                process_until_quit!(sub, quit, msg, {
+                    match serde_json::from_slice::<HashMap<String, String>>(&msg.payload) {
+                        Ok(update) => {
+                            let (tx, rx) = oneshot::channel();
+                            // Perform the config update on the host
+                            if let Err(err) = config_update_tx.send((update, tx)).await {
+                                error!(%err, "failed to send config update");
+                                continue;
+                            }
+                            // Wait for the response from the rx to perform it
+                            if let Err(err) = rx.await.as_ref() {
+                                error!(%err, "failed to receive config update response");
+                            }
                         }
-                        // Wait for the response from the rx to perform it
-                        if let Err(err) = rx.await.as_ref() {
-                            error!(%err, "failed to receive config update response");
+                        Err(err) => {
+                            error!(%err, "received invalid config update data on message");
                         }
                     }
-                    Err(err) => {
-                        error!(%err, "received invalid config update data on message");
-                    }
-                }
-            });
-        }
-        .instrument(tracing::debug_span!("subscribe_config_update"))
-    });
+                });
+            }
+            .instrument(tracing::debug_span!("subscribe_config_update"))
+        }),
+    );
 
     Ok(config_update_rx)
 }
@@ -374,58 +551,127 @@ pub struct ProviderCommandReceivers {
     health: mpsc::Receiver<(HealthCheckRequest, oneshot::Sender<HealthCheckResponse>)>,
     shutdown: mpsc::Receiver<oneshot::Sender<()>>,
     link_put: mpsc::Receiver<(InterfaceLinkDefinition, oneshot::Sender<()>)>,
+    link_validate: mpsc::Receiver<(
+        InterfaceLinkDefinition,
+        oneshot::Sender<LinkValidationResponse>,
+    )>,
     link_del: mpsc::Receiver<(InterfaceLinkDefinition, oneshot::Sender<()>)>,
     config_update: mpsc::Receiver<(HashMap<String, String>, oneshot::Sender<()>)>,
+    config_schema: mpsc::Receiver<oneshot::Sender<Option<ConfigSchema>>>,
+    admin: mpsc::Receiver<(ProviderAdminRequest, oneshot::Sender<ProviderAdminResponse>)>,
 }
 
 impl ProviderCommandReceivers {
+    /// Subscribe to the commands sent to the provider `provider_key` in `lattice`, on subjects
+    /// prefixed with `subject_prefix` if the lattice is isolated under a tenant subject prefix
     pub async fn new(
         nats: Arc<async_nats::Client>,
         quit_tx: &broadcast::Sender<()>,
         lattice: &str,
+        subject_prefix: Option<&str>,
         provider_key: &str,
         provider_link_put_id: &str,
         host_id: &str,
     ) -> ProviderInitResult<Self> {
-        let (health, shutdown, link_put, link_del, config_update) = try_join!(
+        Self::with_spawner(
+            nats,
+            quit_tx,
+            lattice,
+            subject_prefix,
+            provider_key,
+            provider_link_put_id,
+            host_id,
+            |_, task| {
+                spawn(task);
+            },
+        )
+        .await
+    }
+
+    /// Like [`Self::new`], but spawns the long-lived tasks processing the subscriptions with
+    /// `spawner`, which is called with the name of each task. This allows hosts running
+    /// providers in-process to keep track of the tasks
+    #[allow(clippy::too_many_arguments)]
+    pub async fn with_spawner(
+        nats: Arc<async_nats::Client>,
+        quit_tx: &broadcast::Sender<()>,
+        lattice: &str,
+        subject_prefix: Option<&str>,
+        provider_key: &str,
+        provider_link_put_id: &str,
+        host_id: &str,
+        spawner: impl Fn(&'static str, CommandTask) + Sync,
+    ) -> ProviderInitResult<Self> {
+        let subject = |subject| prefixed_subject(subject_prefix, subject);
+        let (
+            health,
+            shutdown,
+            link_put,
+            link_validate,
+            link_del,
+            config_update,
+            config_schema,
+            admin,
+        ) = try_join!(
             subscribe_health(
                 Arc::clone(&nats),
                 quit_tx.subscribe(),
-                lattice,
-                provider_key
+                subject(health_subject(lattice, provider_key)),
+                &spawner,
             ),
             subscribe_shutdown(
                 Arc::clone(&nats),
                 quit_tx.clone(),
-                lattice,
-                provider_key,
-                host_id
+                subject(shutdown_subject(lattice, provider_key, "default")),
+                host_id,
+                &spawner,
             ),
             subscribe_link_put(
                 Arc::clone(&nats),
                 quit_tx.subscribe(),
-                lattice,
-                provider_link_put_id
+                subject(link_put_subject(lattice, provider_link_put_id)),
+                &spawner,
+            ),
+            subscribe_link_validate(
+                Arc::clone(&nats),
+                quit_tx.subscribe(),
+                subject(link_validate_subject(lattice, provider_link_put_id)),
+                &spawner,
             ),
             subscribe_link_del(
                 Arc::clone(&nats),
                 quit_tx.subscribe(),
-                lattice,
-                provider_key
+                subject(link_del_subject(lattice, provider_key)),
+                &spawner,
             ),
             subscribe_config_update(
                 Arc::clone(&nats),
                 quit_tx.subscribe(),
-                lattice,
-                provider_key
+                subject(provider_config_update_subject(lattice, provider_key)),
+                &spawner,
+            ),
+            subscribe_config_schema(
+                Arc::clone(&nats),
+                quit_tx.subscribe(),
+                subject(config_schema_subject(lattice, provider_key)),
+                &spawner,
+            ),
+            subscribe_admin(
+                Arc::clone(&nats),
+                quit_tx.subscribe(),
+                subject(provider_admin_subject(lattice, provider_key)),
+                &spawner,
             ),
         )?;
         Ok(Self {
             health,
             shutdown,
             link_put,
+            link_validate,
             link_del,
             config_update,
+            config_schema,
+            admin,
         })
     }
 }
@@ -437,6 +683,7 @@ pub(crate) struct ProviderInitState {
     pub quit_tx: broadcast::Sender<()>,
     pub host_id: String,
     pub lattice_rpc_prefix: String,
+    pub lattice_subject_prefix: Option<String>,
     pub provider_key: String,
     pub link_definitions: Vec<InterfaceLinkDefinition>,
     pub commands: ProviderCommandReceivers,
@@ -467,6 +714,7 @@ async fn init_provider(name: &str) -> ProviderInitResult<ProviderInitState> {
         link_name: _link_name,
         host_xkey_public_key,
         provider_xkey_private_key,
+        lattice_subject_prefix,
         ..
     } = spawn_blocking(load_host_data).await.map_err(|e| {
         ProviderInitError::Initialization(format!("failed to load host data: {e}"))
@@ -521,7 +769,7 @@ async fn init_provider(name: &str) -> ProviderInitResult<ProviderInitState> {
         DEFAULT_NATS_ADDR
     };
 
-    let nats = with_connection_event_logging(
+    let mut opts = with_connection_event_logging(
         match (lattice_rpc_user_jwt.trim(), lattice_rpc_user_seed.trim()) {
             ("", "") => async_nats::ConnectOptions::default(),
             (rpc_jwt, rpc_seed) => {
@@ -534,9 +782,13 @@ async fn init_provider(name: &str) -> ProviderInitResult<ProviderInitState> {
             }
         },
     )
-    .name(name)
-    .connect(nats_addr)
-    .await?;
+    .name(name);
+    // Replies are received under the subject prefix as well, so that they are not visible to
+    // other tenants
+    if let Some(prefix) = lattice_subject_prefix {
+        opts = opts.custom_inbox_prefix(format!("{prefix}._INBOX"));
+    }
+    let nats = opts.connect(nats_addr).await?;
     let nats = Arc::new(nats);
 
     // Listen and process various provider events/functionality
@@ -544,6 +796,7 @@ async fn init_provider(name: &str) -> ProviderInitResult<ProviderInitState> {
         Arc::clone(&nats),
         &quit_tx,
         lattice_rpc_prefix,
+        lattice_subject_prefix.as_deref(),
         provider_key,
         &provider_link_put_id,
         host_id,
@@ -555,6 +808,7 @@ async fn init_provider(name: &str) -> ProviderInitResult<ProviderInitState> {
         quit_tx,
         host_id: host_id.clone(),
         lattice_rpc_prefix: lattice_rpc_prefix.clone(),
+        lattice_subject_prefix: lattice_subject_prefix.clone(),
         provider_key: provider_key.clone(),
         link_definitions: link_definitions.clone(),
         config: config.clone(),
@@ -615,6 +869,65 @@ where
     Ok(())
 }
 
+/// Validate a link (depending on if it's source/target) with a provider, without establishing it
+pub async fn validate_link_for_provider<P>(
+    provider: &P,
+    connection: &ProviderConnection,
+    ld: InterfaceLinkDefinition,
+) -> LinkValidationResponse
+where
+    P: Provider,
+{
+    let res = async {
+        if ld.source_id == *connection.provider_id {
+            provider
+                .validate_link_config_as_source(LinkConfig {
+                    source_id: &ld.source_id,
+                    target_id: &ld.target,
+                    link_name: &ld.name,
+                    config: &ld.source_config,
+                    secrets: &decrypt_link_secret(
+                        ld.source_secrets.as_deref(),
+                        &connection.provider_xkey,
+                        &connection.host_xkey,
+                    )?,
+                    wit_metadata: (&ld.wit_namespace, &ld.wit_package, &ld.interfaces),
+                })
+                .await
+        } else if ld.target == *connection.provider_id {
+            provider
+                .validate_link_config_as_target(LinkConfig {
+                    source_id: &ld.source_id,
+                    target_id: &ld.target,
+                    link_name: &ld.name,
+                    config: &ld.target_config,
+                    secrets: &decrypt_link_secret(
+                        ld.target_secrets.as_deref(),
+                        &connection.provider_xkey,
+                        &connection.host_xkey,
+                    )?,
+                    wit_metadata: (&ld.wit_namespace, &ld.wit_package, &ld.interfaces),
+                })
+                .await
+        } else {
+            bail!("received link validation where provider was neither source nor target");
+        }
+    }
+    .await;
+    match res {
+        Ok(errors) => LinkValidationResponse { errors },
+        Err(err) => {
+            warn!(error = %err, "validating link failed");
+            LinkValidationResponse {
+                errors: vec![LinkValidationError {
+                    field: None,
+                    message: format!("{err:#}"),
+                }],
+            }
+        }
+    }
+}
+
 /// Given a serialized and encrypted [`HashMap<String, SecretValue>`], decrypts the secrets and deserializes
 /// the inner bytes into a [`HashMap<String, SecretValue>`]. This can either fail due to a decryption error
 /// or a deserialization error.
@@ -661,6 +974,97 @@ where
     Ok(())
 }
 
+/// Perform an administrative operation on the provider
+async fn admin_for_provider<P>(
+    provider: &P,
+    connection: &ProviderConnection,
+    req: ProviderAdminRequest,
+) -> ProviderAdminResponse
+where
+    P: Provider,
+{
+    match req {
+        ProviderAdminRequest::DumpLinks => {
+            let source_links = connection.source_links.read().await;
+            let target_links = connection.target_links.read().await;
+            let mut links: Vec<_> = source_links
+                .values()
+                .chain(target_links.values())
+                .map(|ld| ProviderAdminLink {
+                    source_id: ld.source_id.clone(),
+                    target: ld.target.clone(),
+                    name: ld.name.clone(),
+                    wit_namespace: ld.wit_namespace.clone(),
+                    wit_package: ld.wit_package.clone(),
+                    interfaces: ld.interfaces.clone(),
+                })
+                .collect();
+            links.sort_by(|a, b| (&a.source_id, &a.target).cmp(&(&b.source_id, &b.target)));
+            ProviderAdminResponse {
+                links,
+                ..ProviderAdminResponse::success()
+            }
+        }
+        ProviderAdminRequest::DumpState => match provider.dump_state().await {
+            Ok(Some(state)) => ProviderAdminResponse {
+                state: Some(state),
+                ..ProviderAdminResponse::success()
+            },
+            Ok(None) => ProviderAdminResponse {
+                message: Some("provider does not expose state".into()),
+                ..ProviderAdminResponse::success()
+            },
+            Err(e) => {
+                error!(error = %e, "failed to dump provider state");
+                ProviderAdminResponse::error(format!("failed to dump provider state: {e}"))
+            }
+        },
+        ProviderAdminRequest::SetLogLevel { level } => {
+            match wasmcloud_tracing::set_log_level(&level) {
+                Ok(()) => {
+                    info!(?level, "changed log level");
+                    ProviderAdminResponse::success()
+                }
+                Err(e) => {
+                    error!(error = %e, "failed to change log level");
+                    ProviderAdminResponse::error(format!("failed to change log level: {e:#}"))
+                }
+            }
+        }
+        ProviderAdminRequest::FlushCaches => match provider.flush_caches().await {
+            Ok(()) => {
+                info!("flushed provider caches");
+                ProviderAdminResponse::success()
+            }
+            Err(e) => {
+                error!(error = %e, "failed to flush provider caches");
+                ProviderAdminResponse::error(format!("failed to flush provider caches: {e}"))
+            }
+        },
+        ProviderAdminRequest::Operation { name, args } => {
+            match provider.admin_operation(&name, args).await {
+                Ok(Some(output)) => {
+                    info!(name, "performed admin operation");
+                    ProviderAdminResponse {
+                        output: Some(output),
+                        ..ProviderAdminResponse::success()
+                    }
+                }
+                Ok(None) => ProviderAdminResponse::error(format!(
+                    "provider does not support operation `{name}`"
+                )),
+                Err(e) => {
+                    error!(error = %e, name, "failed to perform admin operation");
+                    ProviderAdminResponse::error(format!(
+                        "failed to perform operation `{name}`: {e}"
+                    ))
+                }
+            }
+        }
+        _ => ProviderAdminResponse::error("unsupported admin request"),
+    }
+}
+
 /// Handle provider commands in a loop.
 pub async fn handle_provider_commands(
     provider: impl Provider,
@@ -671,8 +1075,11 @@ pub async fn handle_provider_commands(
         mut health,
         mut shutdown,
         mut link_put,
+        mut link_validate,
         mut link_del,
         mut config_update,
+        mut config_schema,
+        mut admin,
     }: ProviderCommandReceivers,
 ) {
     loop {
@@ -685,13 +1092,17 @@ pub async fn handle_provider_commands(
             }
             req = health.recv() => {
                 if let Some((req, tx)) = req {
-                    let res = match provider.health_request(&req).await {
+                    let mut res = match provider.health_request(&req).await {
                         Ok(v) => v,
                         Err(e) => {
                             error!(error = %e, "provider health request failed");
                             return;
                         }
                     };
+                    match provider.link_health().await {
+                        Ok(links) => res.links.extend(links),
+                        Err(e) => warn!(error = %e, "provider link health check failed"),
+                    }
                     if tx.send(res).is_err() {
                         error!("failed to send health check response");
                     }
@@ -755,6 +1166,29 @@ pub async fn handle_provider_commands(
                     return;
                 };
             }
+            req = link_validate.recv() => {
+                if let Some((ld, tx)) = req {
+                    debug!(
+                        source = &ld.source_id,
+                        target = &ld.target,
+                        link_name = &ld.name,
+                        "Validating link with provider"
+                    );
+                    let res = validate_link_for_provider(&provider, connection, ld).await;
+                    if tx.send(res).is_err() {
+                        error!("failed to send link validate response");
+                    }
+                } else {
+                    error!("failed to handle link validate, shutdown");
+                    if let Err(e) = provider.shutdown().await {
+                        error!(error = %e, "failed to shutdown provider");
+                    }
+                    if quit_tx.send(()).is_err() {
+                        error!("failed to send quit");
+                    };
+                    return;
+                };
+            }
             req = link_del.recv() => {
                 if let Some((ld, tx)) = req {
                     // notify provider that link is deleted
@@ -797,6 +1231,39 @@ pub async fn handle_provider_commands(
                     return
                 };
             }
+            req = config_schema.recv() => {
+                if let Some(tx) = req {
+                    if tx.send(provider.config_schema()).is_err() {
+                        error!("failed to send config schema response");
+                    }
+                } else {
+                    error!("failed to handle config schema request, shutdown");
+                    if let Err(e) = provider.shutdown().await {
+                        error!(error = %e, "failed to shutdown provider");
+                    }
+                    if quit_tx.send(()).is_err() {
+                        error!("failed to send quit");
+                    };
+                    return
+                };
+            }
+            req = admin.recv() => {
+                if let Some((req, tx)) = req {
+                    let res = admin_for_provider(&provider, connection, req).await;
+                    if tx.send(res).is_err() {
+                        error!("failed to send admin response");
+                    }
+                } else {
+                    error!("failed to handle admin request, shutdown");
+                    if let Err(e) = provider.shutdown().await {
+                        error!(error = %e, "failed to shutdown provider");
+                    }
+                    if quit_tx.send(()).is_err() {
+                        error!("failed to send quit");
+                    };
+                    return
+                };
+            }
         }
     }
 }
@@ -822,6 +1289,7 @@ pub async fn run_provider(
         quit_tx,
         host_id,
         lattice_rpc_prefix,
+        lattice_subject_prefix,
         provider_key,
         link_definitions,
         commands,
@@ -839,7 +1307,8 @@ pub async fn run_provider(
         config,
         provider_xkey,
         host_xkey,
-    )?;
+    )?
+    .with_subject_prefix(lattice_subject_prefix);
     CONNECTION.set(connection).map_err(|_| {
         ProviderInitError::Initialization("Provider connection was already initialized".to_string())
     })?;
@@ -877,7 +1346,23 @@ pub type InvocationStreams = Vec<(
     >,
 )>;
 
+/// Delay before exports are served again after an invocation stream ended
+const RESERVE_EXPORTS_DELAY: Duration = Duration::from_secs(1);
+
 /// Serve exports of the provider using the `serve` function generated by [`wit-bindgen-wrpc`]
+///
+/// Every accepted invocation is handled in a separate task until `shutdown` resolves and counted
+/// and timed in the invocation metrics of the provider. If an invocation stream ends, for example
+/// because the underlying subscription was closed, `serve` is called again and only the streams
+/// of the ended functions are taken from the result to resubscribe them, while the remaining
+/// streams keep being served.
+///
+/// Since `serve` may be called multiple times, it must implement [`Fn`] and the provider must
+/// implement [`Clone`] (changed in 0.14.0, previously `serve` was only required to implement
+/// [`FnOnce`]).
+///
+/// See [`serve_exports!`](crate::serve_exports) for serving multiple worlds or interfaces
+/// generated by separate `wit-bindgen-wrpc` invocations.
 pub async fn serve_provider_exports<'a, P, F, Fut>(
     client: &'a WrpcClient,
     provider: P,
@@ -885,35 +1370,88 @@ pub async fn serve_provider_exports<'a, P, F, Fut>(
     serve: F,
 ) -> anyhow::Result<()>
 where
-    F: FnOnce(&'a WrpcClient, P) -> Fut,
+    P: Clone,
+    F: Fn(&'a WrpcClient, P) -> Fut,
     Fut: Future<Output = anyhow::Result<InvocationStreams>> + wrpc_transport::Captures<'a>,
 {
-    let invocations = serve(client, provider)
-        .await
-        .context("failed to serve exports")?;
-    let mut invocations = stream::select_all(
-        invocations
-            .into_iter()
-            .map(|(instance, name, invocations)| invocations.map(move |res| (instance, name, res))),
-    );
+    serve_invocations(client, provider, shutdown, serve, RESERVE_EXPORTS_DELAY).await
+}
+
+/// Serve the invocations returned by `serve` until `shutdown` resolves, resubscribing ended
+/// invocation streams by calling `serve` again after `delay`
+async fn serve_invocations<'a, C, P, F, Fut>(
+    client: &'a C,
+    provider: P,
+    shutdown: impl Future<Output = ()>,
+    serve: F,
+    delay: Duration,
+) -> anyhow::Result<()>
+where
+    C: ?Sized,
+    P: Clone,
+    F: Fn(&'a C, P) -> Fut,
+    Fut: Future<Output = anyhow::Result<InvocationStreams>> + wrpc_transport::Captures<'a>,
+{
+    let track = |instance: &'static str, name: &'static str, invocations| {
+        metrics::with_link_names(invocations)
+            .map(move |(res, link_name)| (instance, name, Some(res), link_name))
+            .chain(stream::once(future::ready((instance, name, None, None))))
+    };
     let mut shutdown = pin!(shutdown);
     let mut tasks = JoinSet::new();
+    let mut invocations = stream::SelectAll::new();
+    for (instance, name, stream) in serve(client, provider.clone())
+        .await
+        .context("failed to serve exports")?
+    {
+        invocations.push(track(instance, name, stream));
+    }
+    // functions whose invocation streams ended, resubscribed once `resubscribe` elapses
+    let mut ended = HashSet::new();
+    let mut resubscribe = pin!(tokio::time::sleep(delay));
     loop {
         select! {
-            Some((instance, name, res)) = invocations.next() => {
-                match res {
-                    Ok(fut) => {
+            Some(invocation) = invocations.next(), if !invocations.is_empty() => {
+                match invocation {
+                    (instance, name, Some(Ok(fut)), link_name) => {
+                        let attributes =
+                            metrics::invocation_attributes(instance, name, link_name);
                         tasks.spawn(async move {
-                            if let Err(err) = fut.await {
+                            let start = Instant::now();
+                            let res = fut.await;
+                            metrics::record_invocation(&attributes, start.elapsed(), res.is_ok());
+                            if let Err(err) = res {
                                 warn!(?err, instance, name, "failed to serve invocation");
+                                return;
                             }
                             trace!(instance, name, "successfully served invocation");
                         });
                     },
-                    Err(err) => {
+                    (instance, name, Some(Err(err)), _) => {
                         warn!(?err, instance, name, "failed to accept invocation");
+                    },
+                    (instance, name, None, _) => {
+                        warn!(instance, name, "invocation stream ended, resubscribing");
+                        if ended.is_empty() {
+                            resubscribe.as_mut().reset(Instant::now() + delay);
+                        }
+                        ended.insert((instance, name));
+                    },
+                }
+            },
+            () = &mut resubscribe, if !ended.is_empty() => {
+                // streams of functions that are still served are dropped, which unsubscribes them
+                for (instance, name, stream) in serve(client, provider.clone())
+                    .await
+                    .context("failed to serve exports")?
+                {
+                    if ended.remove(&(instance, name)) {
+                        invocations.push(track(instance, name, stream));
                     }
                 }
+                for (instance, name) in ended.drain() {
+                    warn!(instance, name, "exports do not contain ended invocation stream anymore");
+                }
             },
             () = &mut shutdown => {
                 return Ok(())
@@ -922,6 +1460,43 @@ where
     }
 }
 
+/// Serve exports of the provider generated by multiple [`wit-bindgen-wrpc`] `serve` functions
+/// using [`serve_provider_exports`].
+///
+/// This is useful for providers, which implement interfaces generated by separate
+/// `wit_bindgen_wrpc::generate!` invocations, for example to customize the generated types of
+/// some interfaces.
+///
+/// The macro does not map WIT functions to methods of the provider, this is done by the
+/// `Handler` traits generated by [`wit-bindgen-wrpc`], which the provider implements. It only
+/// calls the `serve` functions in order and concatenates the returned [`InvocationStreams`],
+/// which are then served like the streams returned by a single `serve` function.
+///
+/// # Example
+///
+/// ```ignore
+/// serve_exports!(&wrpc, provider, shutdown, [serve, bindings::serve])
+///     .await
+///     .context("failed to serve provider exports")
+/// ```
+#[macro_export]
+macro_rules! serve_exports {
+    ($client:expr, $provider:expr, $shutdown:expr, [$($serve:path),+ $(,)?]) => {
+        $crate::serve_provider_exports(
+            $client,
+            $provider,
+            $shutdown,
+            |client, provider| async move {
+                let mut invocations = $crate::provider::InvocationStreams::new();
+                $(
+                    invocations.extend($serve(client, ::core::clone::Clone::clone(&provider)).await?);
+                )+
+                ::core::result::Result::Ok(invocations)
+            },
+        )
+    };
+}
+
 /// Source ID for a link
 type SourceId = String;
 
@@ -939,6 +1514,8 @@ pub struct ProviderConnection {
 
     /// Lattice name
     pub lattice: Arc<str>,
+    /// Prefix of all lattice subjects, if the lattice is isolated under a tenant subject prefix
+    pub subject_prefix: Option<Arc<str>>,
     pub host_id: String,
     pub provider_id: Arc<str>,
 
@@ -986,6 +1563,7 @@ pub struct WrpcClient {
     timeout: Duration,
     provider_id: Arc<str>,
     target: Arc<str>,
+    link_name: Option<Arc<str>>,
 }
 
 impl wrpc_transport::Invoke for WrpcClient {
@@ -1007,6 +1585,9 @@ impl wrpc_transport::Invoke for WrpcClient {
         let mut headers = cx.unwrap_or_default();
         headers.insert("source-id", &*self.provider_id);
         headers.insert("target-id", &*self.target);
+        if let Some(link_name) = &self.link_name {
+            headers.insert("link-name", &**link_name);
+        }
         self.nats
             .timeout(self.timeout)
             .invoke(Some(headers), instance, func, params, paths)
@@ -1031,7 +1612,9 @@ impl wrpc_transport::Serve for WrpcClient {
     > {
         let invocations = self.nats.serve(instance, func, paths).await?;
         Ok(invocations.and_then(|(cx, tx, rx)| async move {
-            Ok((cx.as_ref().map(invocation_context), tx, rx))
+            let cx = cx.as_ref().map(invocation_context);
+            metrics::set_accepted_link_name(cx.as_ref().map_or("default", Context::link_name));
+            Ok((cx, tx, rx))
         }))
     }
 }
@@ -1051,6 +1634,7 @@ impl ProviderConnection {
             target_links: Arc::default(),
             nats: nats.into(),
             lattice: lattice.into(),
+            subject_prefix: None,
             host_id,
             provider_id: provider_id.into(),
             config,
@@ -1059,6 +1643,14 @@ impl ProviderConnection {
         })
     }
 
+    /// Set the prefix of all lattice subjects, if the lattice is isolated under a tenant subject
+    /// prefix. wRPC clients retrieved from the connection invoke targets under the prefix
+    #[must_use]
+    pub fn with_subject_prefix(mut self, subject_prefix: Option<impl Into<Arc<str>>>) -> Self {
+        self.subject_prefix = subject_prefix.map(Into::into);
+        self
+    }
+
     /// Retrieve a wRPC client that can be used based on the NATS client of this connection
     ///
     /// # Arguments
@@ -1080,7 +1672,10 @@ impl ProviderConnection {
         target: &str,
         timeout: Option<Duration>,
     ) -> anyhow::Result<WrpcClient> {
-        let prefix = Arc::from(format!("{}.{target}", &self.lattice));
+        let prefix = Arc::from(prefixed_subject(
+            self.subject_prefix.as_deref(),
+            format!("{}.{target}", &self.lattice),
+        ));
         let nats = wrpc_transport_nats::Client::new(
             Arc::clone(&self.nats),
             Arc::clone(&prefix),
@@ -1091,10 +1686,56 @@ impl ProviderConnection {
             nats,
             provider_id: Arc::clone(&self.provider_id),
             target: Arc::from(target),
+            link_name: None,
             timeout: timeout.unwrap_or_else(|| Duration::from_secs(10)),
         })
     }
 
+    /// Retrieve a wRPC client for invoking `instance` on the target of the link named `link_name`,
+    /// of which this provider is the source. This allows providers to call the interfaces of other
+    /// providers (or components) they are linked to, without resolving the target themselves.
+    ///
+    /// # Arguments
+    ///
+    /// * `link_name` - Name of the link to the target, e.g. `default`
+    /// * `instance` - Versioned WIT interface, which will be invoked, as it is named by wRPC
+    ///   bindings, e.g. `wasi:blobstore/blobstore@0.2.0-alpha`
+    ///
+    /// # Errors
+    ///
+    /// Returns `Err` if `instance` is not a valid interface name or if this provider has no link
+    /// named `link_name` on the interface
+    pub async fn get_linked_wrpc_client(
+        &self,
+        link_name: &str,
+        instance: &str,
+    ) -> anyhow::Result<WrpcClient> {
+        self.get_linked_wrpc_client_custom(link_name, instance, None)
+            .await
+    }
+
+    /// Retrieve a wRPC client for invoking `instance` on the target of the link named `link_name`,
+    /// customized with invocation timeout. See [`ProviderConnection::get_linked_wrpc_client`].
+    ///
+    /// # Arguments
+    ///
+    /// * `link_name` - Name of the link to the target, e.g. `default`
+    /// * `instance` - Versioned WIT interface, which will be invoked, e.g.
+    ///   `wasi:blobstore/blobstore@0.2.0-alpha`
+    /// * `timeout` - Timeout to be set on the client (by default if this is unset it will be 10 seconds)
+    pub async fn get_linked_wrpc_client_custom(
+        &self,
+        link_name: &str,
+        instance: &str,
+        timeout: Option<Duration>,
+    ) -> anyhow::Result<WrpcClient> {
+        let target =
+            select_link_target(self.source_links.read().await.values(), link_name, instance)?;
+        let mut client = self.get_wrpc_client_custom(&target, timeout).await?;
+        client.link_name = Some(Arc::from(link_name));
+        Ok(client)
+    }
+
     /// Get the provider key that was assigned to this host at startup
     #[must_use]
     pub fn provider_key(&self) -> &str {
@@ -1172,3 +1813,285 @@ impl ProviderConnection {
         }
     }
 }
+
+/// Select the target of the link named `link_name` among `links`, on which `instance` can be
+/// invoked.
+///
+/// Link interfaces may be versioned, e.g. `blobstore@0.2.0-alpha`. A link declaring the version of
+/// `instance` is preferred over a link declaring the interface without a version, links declaring
+/// a different version are never selected.
+fn select_link_target<'a>(
+    links: impl IntoIterator<Item = &'a InterfaceLinkDefinition>,
+    link_name: &str,
+    instance: &str,
+) -> anyhow::Result<LatticeTarget> {
+    let (wit_namespace, wit_package, wit_interface, version) = parse_wit_instance(instance)?;
+    // Rank of a link interface matching `instance`, higher is a better match
+    let rank = |interface: &str| match interface.split_once('@') {
+        Some((name, _)) if name != wit_interface => None,
+        Some((_, declared)) if version == Some(declared) => Some(2),
+        Some((_, _)) => version.is_none().then_some(1),
+        None if interface == wit_interface => Some(1),
+        None => None,
+    };
+    links
+        .into_iter()
+        .filter(|link| {
+            link.name == link_name
+                // In older host versions, the wit_namespace and wit_package are not provided
+                // so we should see if it's empty
+                && (link.wit_namespace.is_empty() || link.wit_namespace == wit_namespace)
+                && (link.wit_package.is_empty() || link.wit_package == wit_package)
+        })
+        .filter_map(|link| {
+            let rank = if link.interfaces.is_empty() {
+                Some(0)
+            } else {
+                link.interfaces.iter().filter_map(|i| rank(i)).max()
+            }?;
+            Some((rank, link))
+        })
+        .max_by_key(|(rank, _)| *rank)
+        .map(|(_, link)| link.target.clone())
+        .with_context(|| {
+            format!("provider is not linked to a target of `{instance}` on link `{link_name}`")
+        })
+}
+
+/// Parse a versioned WIT interface name, as used for wRPC instances, into its namespace, package,
+/// interface and optional version, e.g. `wasi:blobstore/blobstore@0.2.0-alpha`
+fn parse_wit_instance(
+    instance: &str,
+) -> anyhow::Result<(WitNamespace, WitPackage, WitInterface, Option<&str>)> {
+    let (name, version) = match instance.split_once('@') {
+        Some((_, "")) => bail!("invalid WIT interface `{instance}`, version must not be empty"),
+        Some((name, version)) => (name, Some(version)),
+        None => (instance, None),
+    };
+    let Some((wit_namespace, wit_package, wit_interface)) =
+        name.split_once(':')
+            .and_then(|(namespace, package_and_interface)| {
+                let (package, interface) = package_and_interface.split_once('/')?;
+                Some((namespace, package, interface))
+            })
+    else {
+        bail!("invalid WIT interface `{instance}`, expected `<namespace>:<package>/<interface>[@<version>]`");
+    };
+    if wit_namespace.is_empty() || wit_package.is_empty() || wit_interface.is_empty() {
+        bail!("invalid WIT interface `{instance}`, expected `<namespace>:<package>/<interface>[@<version>]`");
+    }
+    Ok((
+        wit_namespace.into(),
+        wit_package.into(),
+        wit_interface.into(),
+        version,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    type Invocation = Pin<Box<dyn Future<Output = anyhow::Result<()>> + Send + 'static>>;
+
+    /// Returns invocation streams serving a single invocation, which sends on `tx`
+    fn single_invocation(tx: mpsc::UnboundedSender<()>) -> InvocationStreams {
+        let invocation: Invocation = Box::pin(async move {
+            tx.send(()).context("receiver dropped")?;
+            Ok(())
+        });
+        vec![(
+            "wasmcloud:test/iface",
+            "f",
+            Box::pin(stream::once(async move { Ok(invocation) })) as _,
+        )]
+    }
+
+    #[tokio::test]
+    async fn serve_invocations_resubscribes() -> anyhow::Result<()> {
+        let calls = AtomicUsize::new(0);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        serve_invocations(
+            &(),
+            tx,
+            async {
+                for _ in 0..3 {
+                    rx.recv().await.expect("sender dropped");
+                }
+            },
+            |(), tx| {
+                calls.fetch_add(1, Ordering::Relaxed);
+                async move { Ok(single_invocation(tx)) }
+            },
+            Duration::from_millis(10),
+        )
+        .await?;
+        assert!(calls.load(Ordering::Relaxed) >= 3);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn serve_invocations_resubscribes_ended_streams() -> anyhow::Result<()> {
+        let calls = AtomicUsize::new(0);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let (pending_tx, mut pending_rx) = mpsc::unbounded_channel();
+        serve_invocations(
+            &(),
+            (tx, pending_tx),
+            async {
+                for _ in 0..3 {
+                    rx.recv().await.expect("sender dropped");
+                }
+            },
+            |(), (tx, pending_tx)| {
+                let call = calls.fetch_add(1, Ordering::Relaxed);
+                async move {
+                    let mut invocations = single_invocation(tx);
+                    // the stream of `g` never ends, so only the first one returned must be served
+                    let g = if call == 0 {
+                        Box::pin(stream::pending()) as _
+                    } else {
+                        single_invocation(pending_tx).remove(0).2
+                    };
+                    invocations.push(("wasmcloud:test/iface", "g", g));
+                    Ok(invocations)
+                }
+            },
+            Duration::from_millis(10),
+        )
+        .await?;
+        assert!(calls.load(Ordering::Relaxed) >= 3);
+        assert!(pending_rx.try_recv().is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn serve_invocations_stops_on_shutdown() -> anyhow::Result<()> {
+        let calls = AtomicUsize::new(0);
+        serve_invocations(
+            &(),
+            (),
+            async {},
+            |(), ()| {
+                calls.fetch_add(1, Ordering::Relaxed);
+                async {
+                    let invocations: InvocationStreams = vec![(
+                        "wasmcloud:test/iface",
+                        "f",
+                        Box::pin(stream::pending::<anyhow::Result<Invocation>>()) as _,
+                    )];
+                    Ok(invocations)
+                }
+            },
+            Duration::from_millis(10),
+        )
+        .await?;
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn serve_invocations_fails_to_resubscribe() {
+        let calls = AtomicUsize::new(0);
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let err = serve_invocations(
+            &(),
+            tx,
+            core::future::pending(),
+            |(), tx| {
+                let call = calls.fetch_add(1, Ordering::Relaxed);
+                async move {
+                    if call > 0 {
+                        bail!("subscription failed")
+                    }
+                    Ok(single_invocation(tx))
+                }
+            },
+            Duration::from_millis(10),
+        )
+        .await
+        .expect_err("resubscribing should fail");
+        assert_eq!(
+            format!("{err:#}"),
+            "failed to serve exports: subscription failed"
+        );
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn parse_instance() -> anyhow::Result<()> {
+        assert_eq!(
+            parse_wit_instance("wasi:blobstore/blobstore@0.2.0-alpha")?,
+            (
+                "wasi".into(),
+                "blobstore".into(),
+                "blobstore".into(),
+                Some("0.2.0-alpha")
+            )
+        );
+        assert_eq!(
+            parse_wit_instance("wrpc:keyvalue/store")?,
+            ("wrpc".into(), "keyvalue".into(), "store".into(), None)
+        );
+        assert!(parse_wit_instance("wrpc:keyvalue/store@").is_err());
+        assert!(parse_wit_instance("keyvalue/store").is_err());
+        assert!(parse_wit_instance("wrpc:keyvalue").is_err());
+        Ok(())
+    }
+
+    fn link(target: &str, name: &str, interfaces: &[&str]) -> InterfaceLinkDefinition {
+        InterfaceLinkDefinition {
+            source_id: "provider".into(),
+            target: target.into(),
+            name: name.into(),
+            wit_namespace: "wasi".into(),
+            wit_package: "blobstore".into(),
+            interfaces: interfaces.iter().map(|i| (*i).into()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn select_linked_target() -> anyhow::Result<()> {
+        let links = [
+            link("legacy", "default", &["blobstore"]),
+            link("alpha", "default", &["container", "blobstore@0.2.0-alpha"]),
+            link("stable", "default", &["blobstore@0.2.0"]),
+            link("backup", "backup", &[]),
+        ];
+        let select = |name, instance| select_link_target(&links, name, instance);
+        assert_eq!(
+            select("default", "wasi:blobstore/blobstore@0.2.0-alpha")?,
+            "alpha"
+        );
+        assert_eq!(
+            select("default", "wasi:blobstore/blobstore@0.2.0")?,
+            "stable"
+        );
+        assert_eq!(
+            select("default", "wasi:blobstore/blobstore@0.1.0")?,
+            "legacy"
+        );
+        assert_eq!(
+            select("default", "wasi:blobstore/container@0.2.0")?,
+            "alpha"
+        );
+        assert_eq!(
+            select("backup", "wasi:blobstore/blobstore@0.2.0")?,
+            "backup"
+        );
+        assert!(select("default", "wasi:blobstore/types@0.2.0").is_err());
+        assert!(select("default", "wasi:keyvalue/store@0.2.0").is_err());
+        assert!(select("other", "wasi:blobstore/blobstore@0.2.0").is_err());
+
+        let links = [link("stable", "default", &["blobstore@0.2.0"])];
+        assert!(select_link_target(&links, "default", "wasi:blobstore/blobstore@0.1.0").is_err());
+        assert_eq!(
+            select_link_target(&links, "default", "wasi:blobstore/blobstore")?,
+            "stable"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+//! Helpers for producing streamed results of wRPC invocations
+//!
+//! wRPC functions returning a `stream` are implemented by providers as a pair of a [`Stream`],
+//! which is consumed by the transport, and a [`Future`], which drives production of the stream
+//! and resolves to the status of the transmission. The helpers in this module construct such
+//! a pair from a fallible source stream. Items are sent to the consumer through a bounded
+//! buffer, so that the source is only polled as fast as the consumer receives items, and the
+//! source stops being polled as soon as the consumer disconnects.
+
+use core::fmt::Display;
+use core::future::Future;
+use core::pin::Pin;
+
+use bytes::Bytes;
+use futures::{stream, Stream, StreamExt as _};
+use tokio::sync::mpsc;
+use tracing::{debug, trace};
+
+/// Stream of items returned to the consumer of a wRPC result
+pub type ResultStream<T> = Pin<Box<dyn Stream<Item = T> + Send>>;
+
+/// Future driving the production of a [`ResultStream`], resolving to the status of the
+/// transmission
+pub type ResultFuture = Pin<Box<dyn Future<Output = Result<(), String>> + Send>>;
+
+/// Default number of items buffered before the producer waits for the consumer
+pub const DEFAULT_BUFFER: usize = 16;
+
+/// Default maximum size of a chunk of bytes sent to the consumer
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Default maximum number of items in a batch sent to the consumer
+pub const DEFAULT_BATCH_SIZE: usize = 128;
+
+/// Construct a streamed result from `items`, buffering up to `buffer` items.
+///
+/// The returned future resolves to an error if `items` returns an error, or if the consumer
+/// disconnects before all items were sent
+pub fn stream_result<T, E>(
+    items: impl Stream<Item = Result<T, E>> + Send + 'static,
+    buffer: usize,
+) -> (ResultStream<T>, ResultFuture)
+where
+    T: Send + 'static,
+    E: Display + Send + 'static,
+{
+    let (tx, mut rx) = mpsc::channel(buffer.max(1));
+    let mut items = Box::pin(items);
+    (
+        Box::pin(stream::poll_fn(move |cx| rx.poll_recv(cx))),
+        Box::pin(async move {
+            loop {
+                let item = tokio::select! {
+                    biased;
+                    () = tx.closed() => {
+                        debug!("stream receiver closed, cancelling stream");
+                        return Err("stream receiver closed".to_string());
+                    }
+                    item = items.next() => item,
+                };
+                match item {
+                    Some(Ok(item)) => {
+                        trace!("sending stream item");
+                        if tx.send(item).await.is_err() {
+                            debug!("stream receiver closed, cancelling stream");
+                            return Err("stream receiver closed".to_string());
+                        }
+                    }
+                    Some(Err(err)) => return Err(format!("{err:#}")),
+                    None => return Ok(()),
+                }
+            }
+        }),
+    )
+}
+
+/// Construct a streamed result of bytes from `data`, splitting buffers larger than
+/// `chunk_size` bytes and buffering up to `buffer` chunks. See [`stream_result`]
+pub fn stream_bytes<E>(
+    data: impl Stream<Item = Result<Bytes, E>> + Send + 'static,
+    chunk_size: usize,
+    buffer: usize,
+) -> (ResultStream<Bytes>, ResultFuture)
+where
+    E: Display + Send + 'static,
+{
+    stream_result(split_chunks(data, chunk_size), buffer)
+}
+
+/// Construct a streamed result of batches from `items`, sending all items available at once,
+/// up to `batch_size` items per batch, and buffering up to `buffer` items. See [`stream_result`]
+pub fn stream_batches<T, E>(
+    items: impl Stream<Item = Result<T, E>> + Send + 'static,
+    batch_size: usize,
+    buffer: usize,
+) -> (ResultStream<Vec<T>>, ResultFuture)
+where
+    T: Send + 'static,
+    E: Display + Send + 'static,
+{
+    let (items, fut) = stream_result(items, buffer);
+    (Box::pin(items.ready_chunks(batch_size.max(1))), fut)
+}
+
+/// Split buffers in `data` into chunks of at most `chunk_size` bytes
+fn split_chunks<E>(
+    data: impl Stream<Item = Result<Bytes, E>>,
+    chunk_size: usize,
+) -> impl Stream<Item = Result<Bytes, E>> {
+    let chunk_size = chunk_size.max(1);
+    data.flat_map(move |buf| {
+        let chunks = match buf {
+            Ok(mut buf) => {
+                let mut chunks = Vec::with_capacity(buf.len().div_ceil(chunk_size));
+                while buf.len() > chunk_size {
+                    chunks.push(Ok(buf.split_to(chunk_size)));
+                }
+                if !buf.is_empty() {
+                    chunks.push(Ok(buf));
+                }
+                chunks
+            }
+            Err(err) => vec![Err(err)],
+        };
+        stream::iter(chunks)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use anyhow::anyhow;
+
+    #[tokio::test]
+    async fn streams_all_items() {
+        let (items, fut) = stream_result(stream::iter([1, 2, 3].map(anyhow::Ok)), 1);
+        let (items, res) = tokio::join!(items.collect::<Vec<_>>(), fut);
+        assert_eq!(items, [1, 2, 3]);
+        assert_eq!(res, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn propagates_errors() {
+        let (items, fut) = stream_result(
+            stream::iter([Ok(1), Err(anyhow!("failed")), Ok(3)]),
+            DEFAULT_BUFFER,
+        );
+        let (items, res) = tokio::join!(items.collect::<Vec<_>>(), fut);
+        assert_eq!(items, [1]);
+        assert_eq!(res, Err("failed".to_string()));
+    }
+
+    #[tokio::test]
+    async fn cancels_on_disconnect() {
+        let (items, fut) = stream_result(stream::pending::<anyhow::Result<()>>(), 1);
+        drop(items);
+        assert_eq!(fut.await, Err("stream receiver closed".to_string()));
+    }
+
+    #[tokio::test]
+    async fn splits_chunks() {
+        let (data, fut) = stream_bytes(
+            stream::iter([
+                anyhow::Ok(Bytes::from_static(b"hello world")),
+                Ok(Bytes::from_static(b"!")),
+            ]),
+            4,
+            DEFAULT_BUFFER,
+        );
+        let (data, res) = tokio::join!(data.collect::<Vec<_>>(), fut);
+        assert_eq!(data, ["hell", "o wo", "rld", "!"]);
+        assert_eq!(res, Ok(()));
+    }
+}
@@ -1,8 +1,23 @@
+use std::time::Duration;
+
 use tracing::warn;
 use wasmcloud_provider_sdk::{core::secrets::SecretValue, LinkConfig};
 
 const POSTGRES_DEFAULT_PORT: u16 = 5432;
 
+/// Tuning of the connection pool of a link
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct PoolOptions {
+    /// Maximum number of connections in the pool, defaults to four times the number of CPUs
+    pub max_size: Option<usize>,
+    /// Time after which idle connections are closed, if any
+    pub idle_timeout: Option<Duration>,
+    /// Maximum number of statements cached per connection, `0` disables caching
+    pub statement_cache_size: usize,
+    /// Time after which transactions which are not used are rolled back, if set
+    pub transaction_idle_timeout: Option<Duration>,
+}
+
 /// Creation options for a Postgres connection
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) struct ConnectionCreateOptions {
@@ -18,6 +33,8 @@ pub(crate) struct ConnectionCreateOptions {
     pub database: String,
     /// Whether TLS is required for the connection
     pub tls_required: bool,
+    /// Tuning of the connection pool
+    pub pool: PoolOptions,
 }
 
 impl From<ConnectionCreateOptions> for deadpool_postgres::Config {
@@ -28,6 +45,7 @@ impl From<ConnectionCreateOptions> for deadpool_postgres::Config {
         cfg.password = Some(opts.password);
         cfg.dbname = Some(opts.database);
         cfg.port = Some(opts.port);
+        cfg.pool = opts.pool.max_size.map(deadpool_postgres::PoolConfig::new);
         cfg
     }
 }
@@ -85,6 +103,7 @@ pub(crate) fn extract_prefixed_conn_config(
                 password: password.to_string(),
                 tls_required: matches!(tls_required.to_lowercase().as_str(), "true" | "yes"),
                 database: database.to_string(),
+                pool: extract_prefixed_pool_options(prefix, link_config),
             })
         }
         _ => {
@@ -93,3 +112,36 @@ pub(crate) fn extract_prefixed_conn_config(
         }
     }
 }
+
+/// Parse the options of the connection pool from link configuration, with a given prefix to the keys.
+///
+/// Invalid values are ignored with a warning, falling back to the defaults.
+pub(crate) fn extract_prefixed_pool_options(
+    prefix: &str,
+    LinkConfig { config, .. }: &LinkConfig,
+) -> PoolOptions {
+    let parse = |key: String| {
+        let value = config.get(&key)?;
+        match value.parse::<u64>() {
+            Ok(value) => Some(value),
+            Err(_) => {
+                warn!("invalid value [{value}] for [{key}], using default");
+                None
+            }
+        }
+    };
+    PoolOptions {
+        max_size: parse(format!("{prefix}POOL_MAX_SIZE"))
+            .filter(|max_size| *max_size > 0)
+            .and_then(|max_size| max_size.try_into().ok()),
+        idle_timeout: parse(format!("{prefix}POOL_IDLE_TIMEOUT_SECS"))
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs),
+        statement_cache_size: parse(format!("{prefix}POOL_STATEMENT_CACHE_SIZE"))
+            .and_then(|size| size.try_into().ok())
+            .unwrap_or_default(),
+        transaction_idle_timeout: parse(format!("{prefix}TX_IDLE_TIMEOUT_SECS"))
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs),
+    }
+}
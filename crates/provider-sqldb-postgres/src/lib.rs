@@ -11,7 +11,6 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use anyhow::{Context as _, Result};
-use deadpool_postgres::Pool;
 use futures::TryStreamExt as _;
 use tokio::sync::RwLock;
 use tokio_postgres::Statement;
@@ -26,20 +25,28 @@ use wasmcloud_provider_sdk::{initialize_observability, serve_provider_exports};
 mod bindings;
 use bindings::{
     into_result_row, PgValue, PreparedStatementExecError, PreparedStatementToken, QueryError,
-    ResultRow, StatementPrepareError,
+    ResultRow, StatementPrepareError, TransactionError, TransactionHandle,
 };
 
 mod config;
 use config::{extract_prefixed_conn_config, ConnectionCreateOptions};
 
+mod pool;
+use pool::{ConnectionPool, PoolGauges};
+
+mod transaction;
+use transaction::{OpenTransaction, Transactions};
+
 use wasmcloud_provider_sdk::Context;
 
 #[derive(Clone, Default)]
 pub struct PostgresProvider {
-    /// Database connections indexed by source ID name
-    connections: Arc<RwLock<HashMap<String, Pool>>>,
+    /// Database connection pools indexed by source ID name
+    connections: Arc<RwLock<HashMap<String, ConnectionPool>>>,
     /// Lookup of prepared statements to the statement and the source ID that prepared them
     prepared_statements: Arc<RwLock<HashMap<PreparedStatementToken, (Statement, String)>>>,
+    /// Open transactions, each holding a connection from the pool of its source
+    transactions: Transactions,
 }
 
 impl PostgresProvider {
@@ -54,6 +61,17 @@ impl PostgresProvider {
             std::env::var_os("PROVIDER_SQLDB_POSTGRES_FLAMEGRAPH_PATH")
         );
         let provider = PostgresProvider::default();
+        let _gauges = PoolGauges::register(&provider.connections);
+        let idle_transactions = tokio::spawn({
+            let transactions = provider.transactions.clone();
+            async move {
+                let mut interval = tokio::time::interval(transaction::IDLE_CHECK_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    transactions.rollback_idle().await;
+                }
+            }
+        });
         let shutdown = run_provider(provider.clone(), PostgresProvider::name())
             .await
             .context("failed to run provider")?;
@@ -61,9 +79,11 @@ impl PostgresProvider {
         let wrpc = connection
             .get_wrpc_client(connection.provider_key())
             .await?;
-        serve_provider_exports(&wrpc, provider, shutdown, bindings::serve)
+        let res = serve_provider_exports(&wrpc, provider, shutdown, bindings::serve)
             .await
-            .context("failed to serve provider exports")
+            .context("failed to serve provider exports");
+        idle_transactions.abort();
+        res
     }
 
     /// Create and store a connection pool, if not already present
@@ -83,6 +103,7 @@ impl PostgresProvider {
         // Build the new connection pool
         let runtime = Some(deadpool_postgres::Runtime::Tokio1);
         let tls_required = create_opts.tls_required;
+        let pool_opts = create_opts.pool.clone();
         let cfg = deadpool_postgres::Config::from(create_opts);
         let pool = if tls_required {
             create_tls_pool(cfg, runtime)
@@ -93,7 +114,7 @@ impl PostgresProvider {
 
         // Save the newly created connection to the pool
         let mut connections = self.connections.write().await;
-        connections.insert(source_id.into(), pool);
+        connections.insert(source_id.into(), ConnectionPool::new(pool, &pool_opts));
         Ok(())
     }
 
@@ -115,8 +136,13 @@ impl PostgresProvider {
             QueryError::Unexpected(format!("failed to build client from pool: {e}"))
         })?;
 
+        let statement = pool
+            .prepare(&client, query)
+            .await
+            .map_err(|e| QueryError::Unexpected(format!("failed to prepare query: {e}")))?;
+
         let rows = client
-            .query_raw(query, params)
+            .query_raw(&statement, params)
             .await
             .map_err(|e| QueryError::Unexpected(format!("failed to perform query: {e}")))?;
 
@@ -212,6 +238,95 @@ impl PostgresProvider {
 
         Ok(rows_affected)
     }
+
+    /// Begin a transaction on a connection dedicated to it
+    async fn do_transaction_begin(
+        &self,
+        source_id: &str,
+    ) -> Result<TransactionHandle, TransactionError> {
+        let (client, idle_timeout) = {
+            let connections = self.connections.read().await;
+            let pool = connections.get(source_id).ok_or_else(|| {
+                TransactionError::Unexpected(format!(
+                    "missing connection pool for source [{source_id}] while beginning transaction"
+                ))
+            })?;
+            let client = pool.get().await.map_err(|e| {
+                TransactionError::Unexpected(format!("failed to build client from pool: {e}"))
+            })?;
+            (client, pool.transaction_idle_timeout())
+        };
+
+        let tx = OpenTransaction::begin(client, source_id, idle_timeout)
+            .await
+            .map_err(|e| {
+                TransactionError::Unexpected(format!("failed to begin transaction: {e}"))
+            })?;
+
+        Ok(self.transactions.insert(tx).await)
+    }
+
+    /// Perform a query in a transaction
+    async fn do_transaction_query(
+        &self,
+        source_id: &str,
+        handle: &str,
+        query: &str,
+        params: Vec<PgValue>,
+    ) -> Result<Vec<ResultRow>, TransactionError> {
+        let tx = self
+            .transactions
+            .get(source_id, handle)
+            .await
+            .ok_or(TransactionError::UnknownTransaction)?;
+
+        tx.query(query, params)
+            .await
+            .ok_or(TransactionError::UnknownTransaction)?
+            .map_err(|e| {
+                TransactionError::QueryError(QueryError::Unexpected(format!(
+                    "failed to perform query: {e}"
+                )))
+            })
+    }
+
+    /// Commit a transaction
+    async fn do_transaction_commit(
+        &self,
+        source_id: &str,
+        handle: &str,
+    ) -> Result<(), TransactionError> {
+        let tx = self
+            .transactions
+            .remove(source_id, handle)
+            .await
+            .ok_or(TransactionError::UnknownTransaction)?;
+
+        tx.commit()
+            .await
+            .ok_or(TransactionError::UnknownTransaction)?
+            .map_err(|e| TransactionError::Unexpected(format!("failed to commit transaction: {e}")))
+    }
+
+    /// Roll back a transaction
+    async fn do_transaction_rollback(
+        &self,
+        source_id: &str,
+        handle: &str,
+    ) -> Result<(), TransactionError> {
+        let tx = self
+            .transactions
+            .remove(source_id, handle)
+            .await
+            .ok_or(TransactionError::UnknownTransaction)?;
+
+        tx.rollback()
+            .await
+            .ok_or(TransactionError::UnknownTransaction)?
+            .map_err(|e| {
+                TransactionError::Unexpected(format!("failed to roll back transaction: {e}"))
+            })
+    }
 }
 
 impl Provider for PostgresProvider {
@@ -245,6 +360,7 @@ impl Provider for PostgresProvider {
     #[instrument(level = "info", skip_all, fields(source_id = info.get_source_id()))]
     async fn delete_link_as_target(&self, info: impl LinkDeleteInfo) -> anyhow::Result<()> {
         let component_id = info.get_source_id();
+        self.transactions.rollback_source(component_id).await;
         let mut prepared_statements = self.prepared_statements.write().await;
         prepared_statements.retain(|_stmt_token, (_conn, src_id)| component_id != *src_id);
         drop(prepared_statements);
@@ -257,6 +373,7 @@ impl Provider for PostgresProvider {
     /// Handle shutdown request by closing all connections
     #[instrument(level = "debug", skip_all)]
     async fn shutdown(&self) -> anyhow::Result<()> {
+        self.transactions.rollback_all().await;
         let mut prepared_statements = self.prepared_statements.write().await;
         prepared_statements.drain();
         let mut connections = self.connections.write().await;
@@ -342,10 +459,92 @@ impl bindings::prepared::Handler<Option<Context>> for PostgresProvider {
     }
 }
 
+/// Implement the `wasmcloud:postgres/transaction` interface for [`PostgresProvider`]
+impl bindings::transaction::Handler<Option<Context>> for PostgresProvider {
+    #[instrument(level = "debug", skip_all)]
+    async fn begin(
+        &self,
+        ctx: Option<Context>,
+    ) -> Result<Result<TransactionHandle, TransactionError>> {
+        propagate_trace_for_ctx!(ctx);
+        let Some(Context {
+            component: Some(source_id),
+            ..
+        }) = ctx
+        else {
+            return Ok(Err(TransactionError::Unexpected(
+                "unexpectedly missing source ID".into(),
+            )));
+        };
+        Ok(self.do_transaction_begin(&source_id).await)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(tx, query))]
+    async fn query_in_tx(
+        &self,
+        ctx: Option<Context>,
+        tx: TransactionHandle,
+        query: String,
+        params: Vec<PgValue>,
+    ) -> Result<Result<Vec<ResultRow>, TransactionError>> {
+        propagate_trace_for_ctx!(ctx);
+        let Some(Context {
+            component: Some(source_id),
+            ..
+        }) = ctx
+        else {
+            return Ok(Err(TransactionError::Unexpected(
+                "unexpectedly missing source ID".into(),
+            )));
+        };
+        Ok(self
+            .do_transaction_query(&source_id, &tx, &query, params)
+            .await)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(tx))]
+    async fn commit(
+        &self,
+        ctx: Option<Context>,
+        tx: TransactionHandle,
+    ) -> Result<Result<(), TransactionError>> {
+        propagate_trace_for_ctx!(ctx);
+        let Some(Context {
+            component: Some(source_id),
+            ..
+        }) = ctx
+        else {
+            return Ok(Err(TransactionError::Unexpected(
+                "unexpectedly missing source ID".into(),
+            )));
+        };
+        Ok(self.do_transaction_commit(&source_id, &tx).await)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(tx))]
+    async fn rollback(
+        &self,
+        ctx: Option<Context>,
+        tx: TransactionHandle,
+    ) -> Result<Result<(), TransactionError>> {
+        propagate_trace_for_ctx!(ctx);
+        let Some(Context {
+            component: Some(source_id),
+            ..
+        }) = ctx
+        else {
+            return Ok(Err(TransactionError::Unexpected(
+                "unexpectedly missing source ID".into(),
+            )));
+        };
+        Ok(self.do_transaction_rollback(&source_id, &tx).await)
+    }
+}
+
 fn create_tls_pool(
     cfg: deadpool_postgres::Config,
     runtime: Option<deadpool_postgres::Runtime>,
-) -> Result<Pool> {
+) -> Result<deadpool_postgres::Pool> {
     let mut store = rustls::RootCertStore::empty();
     store.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
     cfg.create_pool(
@@ -0,0 +1,156 @@
+//! Connection pools of links, along with the metrics describing them
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use deadpool_postgres::{Object, Pool, PoolError, Status};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tokio_postgres::Statement;
+use tracing::debug;
+use wasmcloud_provider_sdk::wasmcloud_tracing::{global, KeyValue, ObservableGauge};
+
+use crate::config::PoolOptions;
+
+/// Minimum interval between checks for idle connections
+const MIN_IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Time after which transactions which are not used are rolled back, unless configured
+const DEFAULT_TRANSACTION_IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Connection pool of a link
+pub(crate) struct ConnectionPool {
+    pool: Pool,
+    statement_cache_size: usize,
+    transaction_idle_timeout: Duration,
+    /// Task closing idle connections, if an idle timeout is configured
+    idle_reaper: Option<JoinHandle<()>>,
+}
+
+impl Drop for ConnectionPool {
+    fn drop(&mut self) {
+        if let Some(idle_reaper) = self.idle_reaper.take() {
+            idle_reaper.abort();
+        }
+        self.pool.close();
+    }
+}
+
+impl ConnectionPool {
+    /// Wrap `pool`, closing connections idle for longer than the configured idle timeout
+    pub(crate) fn new(pool: Pool, opts: &PoolOptions) -> Self {
+        let idle_reaper = opts.idle_timeout.map(|idle_timeout| {
+            let pool = pool.clone();
+            tokio::spawn(async move {
+                let mut interval =
+                    tokio::time::interval((idle_timeout / 2).max(MIN_IDLE_CHECK_INTERVAL));
+                loop {
+                    interval.tick().await;
+                    let removed = pool
+                        .retain(|_, metrics| metrics.last_used() < idle_timeout)
+                        .removed
+                        .len();
+                    if removed > 0 {
+                        debug!(removed, "closed idle connections");
+                    }
+                }
+            })
+        });
+        Self {
+            pool,
+            statement_cache_size: opts.statement_cache_size,
+            transaction_idle_timeout: opts
+                .transaction_idle_timeout
+                .unwrap_or(DEFAULT_TRANSACTION_IDLE_TIMEOUT),
+            idle_reaper,
+        }
+    }
+
+    /// Get a connection from the pool
+    pub(crate) async fn get(&self) -> Result<Object, PoolError> {
+        self.pool.get().await
+    }
+
+    /// Time after which transactions which are not used are rolled back
+    pub(crate) fn transaction_idle_timeout(&self) -> Duration {
+        self.transaction_idle_timeout
+    }
+
+    /// Prepare `query` on `client`, using the statement cache of the connection if enabled.
+    ///
+    /// Once the cache of the connection exceeds the configured size, it is cleared.
+    pub(crate) async fn prepare(
+        &self,
+        client: &Object,
+        query: &str,
+    ) -> Result<Statement, tokio_postgres::Error> {
+        if self.statement_cache_size == 0 {
+            return client.prepare(query).await;
+        }
+        let statement = client.prepare_cached(query).await?;
+        if client.statement_cache.size() > self.statement_cache_size {
+            client.statement_cache.clear();
+        }
+        Ok(statement)
+    }
+}
+
+/// Gauges reporting the state of the connection pools, registered for as long as they are held
+pub(crate) struct PoolGauges {
+    _gauges: Vec<ObservableGauge<u64>>,
+}
+
+impl PoolGauges {
+    /// Register gauges reporting the state of each of the `connections` pools
+    pub(crate) fn register(connections: &Arc<RwLock<HashMap<String, ConnectionPool>>>) -> Self {
+        let meter = global::meter("wasmcloud-provider-sqldb-postgres");
+        let gauges: [(&'static str, &'static str, fn(Status) -> usize); 4] = [
+            (
+                "wasmcloud_provider_sqldb_postgres.pool.max_size",
+                "Maximum number of connections in the pool of a link",
+                |status| status.max_size,
+            ),
+            (
+                "wasmcloud_provider_sqldb_postgres.pool.size",
+                "Number of open connections in the pool of a link",
+                |status| status.size,
+            ),
+            (
+                "wasmcloud_provider_sqldb_postgres.pool.available",
+                "Number of idle connections in the pool of a link",
+                |status| status.available,
+            ),
+            (
+                "wasmcloud_provider_sqldb_postgres.pool.waiting",
+                "Number of invocations waiting for a connection from the pool of a link",
+                |status| status.waiting,
+            ),
+        ];
+        let gauges = gauges
+            .into_iter()
+            .map(|(name, description, value)| {
+                let connections = Arc::clone(connections);
+                meter
+                    .u64_observable_gauge(name)
+                    .with_description(description)
+                    .with_callback(move |gauge| {
+                        // Gauges are observed synchronously, skip the observation if the pools
+                        // are being modified
+                        let Ok(connections) = connections.try_read() else {
+                            return;
+                        };
+                        for (source_id, pool) in connections.iter() {
+                            let value = value(pool.pool.status());
+                            gauge.observe(
+                                u64::try_from(value).unwrap_or(u64::MAX),
+                                &[KeyValue::new("source_id", source_id.clone())],
+                            );
+                        }
+                    })
+                    .build()
+            })
+            .collect();
+        Self { _gauges: gauges }
+    }
+}
@@ -0,0 +1,175 @@
+//! Transactions kept open across invocations, each on a connection dedicated to it
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use deadpool_postgres::Object;
+use futures::TryStreamExt as _;
+use tokio::sync::{Mutex, RwLock};
+use tokio::time::Instant;
+use tracing::{debug, warn};
+
+use crate::bindings::{into_result_row, PgValue, ResultRow, TransactionHandle};
+
+/// Interval between checks for idle transactions
+pub(crate) const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Connection of an open transaction, locked for the duration of each operation on it
+struct TransactionState {
+    client: Object,
+    last_used: Instant,
+}
+
+/// A transaction opened by a component
+pub(crate) struct OpenTransaction {
+    /// ID of the component which opened the transaction
+    source_id: String,
+    /// Time after which the transaction is rolled back if unused
+    idle_timeout: Duration,
+    /// Connection of the transaction, `None` once the transaction is finished
+    state: Mutex<Option<TransactionState>>,
+}
+
+impl OpenTransaction {
+    /// Begin a transaction on `client`
+    pub(crate) async fn begin(
+        client: Object,
+        source_id: &str,
+        idle_timeout: Duration,
+    ) -> Result<Self, tokio_postgres::Error> {
+        client.batch_execute("BEGIN").await?;
+        Ok(Self {
+            source_id: source_id.into(),
+            idle_timeout,
+            state: Mutex::new(Some(TransactionState {
+                client,
+                last_used: Instant::now(),
+            })),
+        })
+    }
+
+    /// Perform a query in the transaction, returning `None` if the transaction is finished
+    pub(crate) async fn query(
+        &self,
+        query: &str,
+        params: Vec<PgValue>,
+    ) -> Option<Result<Vec<ResultRow>, tokio_postgres::Error>> {
+        let mut state = self.state.lock().await;
+        let state = state.as_mut()?;
+        let rows = match state.client.query_raw(query, params).await {
+            Ok(rows) => rows.map_ok(into_result_row).try_collect::<Vec<_>>().await,
+            Err(err) => Err(err),
+        };
+        state.last_used = Instant::now();
+        Some(rows)
+    }
+
+    /// Whether the transaction is unused for longer than its idle timeout. Transactions with an
+    /// operation in progress are never idle
+    fn is_idle(&self) -> bool {
+        self.state.try_lock().is_ok_and(|state| {
+            state
+                .as_ref()
+                .is_some_and(|state| state.last_used.elapsed() > self.idle_timeout)
+        })
+    }
+
+    /// Finish the transaction with `statement`, either `COMMIT` or `ROLLBACK`, returning `None`
+    /// if the transaction is already finished.
+    ///
+    /// If the statement fails, the connection is in an unknown state and is therefore removed
+    /// from the pool instead of being returned to it.
+    async fn finish(&self, statement: &str) -> Option<Result<(), tokio_postgres::Error>> {
+        let TransactionState { client, .. } = self.state.lock().await.take()?;
+        if let Err(err) = client.batch_execute(statement).await {
+            drop(Object::take(client));
+            return Some(Err(err));
+        }
+        Some(Ok(()))
+    }
+
+    /// Commit the transaction, returning `None` if the transaction is already finished
+    pub(crate) async fn commit(&self) -> Option<Result<(), tokio_postgres::Error>> {
+        self.finish("COMMIT").await
+    }
+
+    /// Roll back the transaction, returning `None` if the transaction is already finished
+    pub(crate) async fn rollback(&self) -> Option<Result<(), tokio_postgres::Error>> {
+        self.finish("ROLLBACK").await
+    }
+}
+
+/// Open transactions indexed by handle
+#[derive(Clone, Default)]
+pub(crate) struct Transactions(Arc<RwLock<HashMap<TransactionHandle, Arc<OpenTransaction>>>>);
+
+impl Transactions {
+    /// Store an open transaction, returning its handle
+    pub(crate) async fn insert(&self, tx: OpenTransaction) -> TransactionHandle {
+        let handle = format!("transaction-{}", ulid::Ulid::new());
+        self.0.write().await.insert(handle.clone(), Arc::new(tx));
+        handle
+    }
+
+    /// Get the open transaction with `handle`, if it was opened by `source_id`
+    pub(crate) async fn get(&self, source_id: &str, handle: &str) -> Option<Arc<OpenTransaction>> {
+        self.0
+            .read()
+            .await
+            .get(handle)
+            .filter(|tx| tx.source_id == source_id)
+            .cloned()
+    }
+
+    /// Remove the open transaction with `handle`, if it was opened by `source_id`
+    pub(crate) async fn remove(
+        &self,
+        source_id: &str,
+        handle: &str,
+    ) -> Option<Arc<OpenTransaction>> {
+        let mut txs = self.0.write().await;
+        if txs.get(handle)?.source_id != source_id {
+            return None;
+        }
+        txs.remove(handle)
+    }
+
+    /// Roll back all transactions matching `f`
+    async fn rollback_matching(&self, f: impl Fn(&OpenTransaction) -> bool) {
+        let removed: Vec<_> = {
+            let mut txs = self.0.write().await;
+            let handles: Vec<_> = txs
+                .iter()
+                .filter(|(_, tx)| f(tx))
+                .map(|(handle, _)| handle.clone())
+                .collect();
+            handles
+                .into_iter()
+                .filter_map(|handle| txs.remove_entry(&handle))
+                .collect()
+        };
+        for (handle, tx) in removed {
+            match tx.rollback().await {
+                Some(Ok(())) => debug!(handle, "rolled back transaction"),
+                Some(Err(err)) => warn!(?err, handle, "failed to roll back transaction"),
+                None => {}
+            }
+        }
+    }
+
+    /// Roll back transactions which are unused for longer than their idle timeout
+    pub(crate) async fn rollback_idle(&self) {
+        self.rollback_matching(OpenTransaction::is_idle).await;
+    }
+
+    /// Roll back all transactions opened by `source_id`
+    pub(crate) async fn rollback_source(&self, source_id: &str) {
+        self.rollback_matching(|tx| tx.source_id == source_id).await;
+    }
+
+    /// Roll back all transactions
+    pub(crate) async fn rollback_all(&self) {
+        self.rollback_matching(|_| true).await;
+    }
+}
@@ -0,0 +1,277 @@
+//! Configuration for the SSE capability provider
+
+use core::fmt;
+use core::net::SocketAddr;
+use core::time::Duration;
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use wasmcloud_provider_sdk::core::secrets::SecretValue;
+use wasmcloud_provider_sdk::{ConfigField, ConfigSchema, LinkConfig};
+
+/// Configuration key of the address the SSE endpoints are served on
+const CONFIG_ADDRESS_KEY: &str = "ADDRESS";
+
+/// Configuration key of the path under which channels are served
+const CONFIG_PATH_PREFIX_KEY: &str = "PATH_PREFIX";
+
+/// Configuration key of the maximum number of connected subscribers
+const CONFIG_MAX_CONNECTIONS_KEY: &str = "MAX_CONNECTIONS";
+
+/// Configuration key of the maximum number of connected subscribers of a single channel
+const CONFIG_MAX_CHANNEL_CONNECTIONS_KEY: &str = "MAX_CHANNEL_CONNECTIONS";
+
+/// Configuration key of the interval at which keep-alive comments are sent, in seconds
+const CONFIG_KEEP_ALIVE_KEY: &str = "KEEP_ALIVE_SECS";
+
+/// Configuration key of the comma-separated origins allowed to subscribe from browsers
+const CONFIG_ALLOWED_ORIGINS_KEY: &str = "ALLOWED_ORIGINS";
+
+/// Secret name of the token required to subscribe to channels without a token of their own
+const SECRET_TOKEN_KEY: &str = "TOKEN";
+
+/// Prefix of the secret names of tokens required to subscribe to a single channel
+const SECRET_CHANNEL_TOKEN_PREFIX: &str = "TOKEN_";
+
+const DEFAULT_ADDRESS: &str = "0.0.0.0:8090";
+
+const DEFAULT_PATH_PREFIX: &str = "/events";
+
+const DEFAULT_MAX_CONNECTIONS: usize = 1024;
+
+const DEFAULT_MAX_CHANNEL_CONNECTIONS: usize = 256;
+
+const DEFAULT_KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// Tokens required to subscribe to channels
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct Tokens {
+    /// Token required for channels without a token of their own, channels are public if unset
+    pub default: Option<String>,
+    /// Tokens required for single channels, by channel
+    pub channels: HashMap<String, String>,
+}
+
+impl fmt::Debug for Tokens {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never log the tokens themselves
+        f.debug_struct("Tokens")
+            .field("default", &self.default.as_ref().map(|_| ".."))
+            .field("channels", &self.channels.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Tokens {
+    /// Returns the token required to subscribe to `channel`, if any
+    pub fn get(&self, channel: &str) -> Option<&str> {
+        self.channels
+            .get(channel)
+            .or(self.default.as_ref())
+            .map(String::as_str)
+    }
+}
+
+/// Configuration of the SSE server of a link
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SseConfig {
+    /// Address the SSE endpoints are served on
+    pub address: SocketAddr,
+    /// Path under which channels are served, as `<prefix>/<channel>`
+    pub path_prefix: String,
+    /// Maximum number of connected subscribers
+    pub max_connections: usize,
+    /// Maximum number of connected subscribers of a single channel
+    pub max_channel_connections: usize,
+    /// Interval at which keep-alive comments are sent to subscribers
+    pub keep_alive: Duration,
+    /// Origins allowed to subscribe from browsers, `*` allows any origin. Cross-origin
+    /// subscriptions are not allowed if empty
+    pub allowed_origins: Vec<String>,
+    /// Tokens required to subscribe to channels
+    pub tokens: Tokens,
+}
+
+/// Schema of the link configuration of the provider
+pub fn schema() -> ConfigSchema {
+    ConfigSchema::new()
+        .field(
+            ConfigField::string(CONFIG_ADDRESS_KEY)
+                .default_value(DEFAULT_ADDRESS)
+                .description("Address the SSE endpoints are served on"),
+        )
+        .field(
+            ConfigField::string(CONFIG_PATH_PREFIX_KEY)
+                .default_value(DEFAULT_PATH_PREFIX)
+                .description("Path under which channels are served, as `<prefix>/<channel>`"),
+        )
+        .field(
+            ConfigField::integer(CONFIG_MAX_CONNECTIONS_KEY, Some(1), None)
+                .default_value(DEFAULT_MAX_CONNECTIONS.to_string())
+                .description("Maximum number of connected subscribers"),
+        )
+        .field(
+            ConfigField::integer(CONFIG_MAX_CHANNEL_CONNECTIONS_KEY, Some(1), None)
+                .default_value(DEFAULT_MAX_CHANNEL_CONNECTIONS.to_string())
+                .description("Maximum number of connected subscribers of a single channel"),
+        )
+        .field(
+            ConfigField::integer(CONFIG_KEEP_ALIVE_KEY, Some(1), None)
+                .default_value(DEFAULT_KEEP_ALIVE.as_secs().to_string())
+                .description("Interval at which keep-alive comments are sent, in seconds"),
+        )
+        .field(ConfigField::string(CONFIG_ALLOWED_ORIGINS_KEY).description(
+            "Comma-separated origins allowed to subscribe from browsers, `*` allows any origin",
+        ))
+        .field(ConfigField::string(SECRET_TOKEN_KEY).secret().description(
+            "Token required to subscribe to channels without a `TOKEN_<channel>` secret",
+        ))
+}
+
+impl SseConfig {
+    /// Construct an [`SseConfig`] from a link configuration validated against [`schema`].
+    /// Keys are matched case-insensitively
+    pub fn from_link_config(link_config: &LinkConfig) -> Result<Self> {
+        let config = link_config.validate(&schema()).map_err(|errors| {
+            anyhow!(errors
+                .into_iter()
+                .map(|err| err.message)
+                .collect::<Vec<_>>()
+                .join("; "))
+        })?;
+        let address = config
+            .get(CONFIG_ADDRESS_KEY)
+            .unwrap_or(DEFAULT_ADDRESS)
+            .parse()
+            .with_context(|| format!("invalid `{CONFIG_ADDRESS_KEY}`"))?;
+        let path_prefix = config
+            .get(CONFIG_PATH_PREFIX_KEY)
+            .unwrap_or(DEFAULT_PATH_PREFIX)
+            .trim_end_matches('/');
+        if !path_prefix.is_empty() && !path_prefix.starts_with('/') {
+            bail!("`{CONFIG_PATH_PREFIX_KEY}` must start with `/`");
+        }
+        // NOTE: Integer fields have defaults and were validated to be positive
+        let value = |key: &str| config.get_integer(key).unwrap_or_default().unsigned_abs();
+        let allowed_origins = config
+            .get(CONFIG_ALLOWED_ORIGINS_KEY)
+            .map(|origins| {
+                origins
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|origin| !origin.is_empty())
+                    .map(ToString::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mut channels = HashMap::new();
+        for (name, value) in link_config.secrets {
+            let Some(channel) = name
+                .get(..SECRET_CHANNEL_TOKEN_PREFIX.len())
+                .filter(|prefix| prefix.eq_ignore_ascii_case(SECRET_CHANNEL_TOKEN_PREFIX))
+                .map(|_| &name[SECRET_CHANNEL_TOKEN_PREFIX.len()..])
+            else {
+                continue;
+            };
+            validate_channel(channel)
+                .with_context(|| format!("invalid channel of secret `{name}`"))?;
+            let SecretValue::String(token) = value else {
+                bail!("secret `{name}` must be a string");
+            };
+            channels.insert(channel.to_string(), token.clone());
+        }
+        Ok(Self {
+            address,
+            path_prefix: path_prefix.to_string(),
+            max_connections: value(CONFIG_MAX_CONNECTIONS_KEY)
+                .try_into()
+                .with_context(|| format!("`{CONFIG_MAX_CONNECTIONS_KEY}` is too large"))?,
+            max_channel_connections: value(CONFIG_MAX_CHANNEL_CONNECTIONS_KEY)
+                .try_into()
+                .with_context(|| format!("`{CONFIG_MAX_CHANNEL_CONNECTIONS_KEY}` is too large"))?,
+            keep_alive: Duration::from_secs(value(CONFIG_KEEP_ALIVE_KEY)),
+            allowed_origins,
+            tokens: Tokens {
+                default: config.get(SECRET_TOKEN_KEY).map(ToString::to_string),
+                channels,
+            },
+        })
+    }
+}
+
+/// Validates that `channel` is a non-empty name consisting of ASCII alphanumerics, `-`, `_`
+/// and `.`, so that it can be used as a single path segment
+pub fn validate_channel(channel: &str) -> Result<()> {
+    if channel.is_empty() || channel.len() > 128 {
+        bail!("channel names must be between 1 and 128 characters long");
+    }
+    if !channel
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+    {
+        bail!("channel `{channel}` must only contain ASCII alphanumerics, `-`, `_` and `.`");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sse_config(config: &[(&str, &str)], secrets: &[(&str, &str)]) -> Result<SseConfig> {
+        let config: HashMap<_, _> = config
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let secrets: HashMap<_, _> = secrets
+            .iter()
+            .map(|(k, v)| (k.to_string(), SecretValue::String(v.to_string())))
+            .collect();
+        SseConfig::from_link_config(&LinkConfig {
+            source_id: "component",
+            target_id: "provider",
+            link_name: "default",
+            config: &config,
+            secrets: &secrets,
+            wit_metadata: (&"wasmcloud".to_string(), &"sse".to_string(), &vec![]),
+        })
+    }
+
+    #[test]
+    fn parse_config() -> Result<()> {
+        let config = sse_config(
+            &[
+                ("address", "127.0.0.1:9000"),
+                ("PATH_PREFIX", "/live/"),
+                ("MAX_CHANNEL_CONNECTIONS", "10"),
+                (
+                    "ALLOWED_ORIGINS",
+                    "https://example.com, https://app.example.com",
+                ),
+            ],
+            &[("token", "secret"), ("TOKEN_scores", "scores-secret")],
+        )?;
+        assert_eq!(config.address, "127.0.0.1:9000".parse()?);
+        assert_eq!(config.path_prefix, "/live");
+        assert_eq!(config.max_connections, DEFAULT_MAX_CONNECTIONS);
+        assert_eq!(config.max_channel_connections, 10);
+        assert_eq!(config.keep_alive, DEFAULT_KEEP_ALIVE);
+        assert_eq!(
+            config.allowed_origins,
+            ["https://example.com", "https://app.example.com"]
+        );
+        assert_eq!(config.tokens.get("scores"), Some("scores-secret"));
+        assert_eq!(config.tokens.get("news"), Some("secret"));
+
+        let config = sse_config(&[], &[])?;
+        assert_eq!(config.address, DEFAULT_ADDRESS.parse()?);
+        assert_eq!(config.tokens.get("news"), None);
+
+        assert!(sse_config(&[("ADDRESS", "localhost")], &[]).is_err());
+        assert!(sse_config(&[("PATH_PREFIX", "events")], &[]).is_err());
+        assert!(sse_config(&[("MAX_CONNECTIONS", "0")], &[]).is_err());
+        assert!(sse_config(&[], &[("TOKEN_a/b", "secret")]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,190 @@
+//! SSE provider implementing `wasmcloud:sse/broadcaster`, which lets components broadcast
+//! server-sent events to browsers subscribed to channels, enabling live-updating UIs without
+//! WebSockets.
+//!
+//! Every link serves the channels of the linked component on its own address, with the
+//! subscriber limits and channel tokens configured on the link.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{Context as _, Result};
+use tokio::sync::RwLock;
+use tracing::{error, instrument};
+use wasmcloud_provider_sdk::{
+    get_connection, initialize_observability, propagate_trace_for_ctx, run_provider,
+    serve_provider_exports, ConfigSchema, Context, LinkConfig, LinkDeleteInfo, Provider,
+};
+
+mod config;
+mod server;
+
+use config::SseConfig;
+use server::{SseEvent, SseServer};
+
+mod bindings {
+    wit_bindgen_wrpc::generate!({
+        with: {
+            "wasmcloud:sse/broadcaster@0.1.0-draft": generate,
+        }
+    });
+}
+use bindings::exports::wasmcloud::sse::broadcaster::{Event, Handler};
+
+pub async fn run() -> anyhow::Result<()> {
+    SseProvider::run().await
+}
+
+/// SSE broadcaster provider
+#[derive(Clone, Default)]
+pub struct SseProvider {
+    /// SSE servers of linked components, indexed by source ID
+    servers: Arc<RwLock<HashMap<String, Arc<SseServer>>>>,
+}
+
+impl SseProvider {
+    fn name() -> &'static str {
+        "sse-provider"
+    }
+
+    /// Run [`SseProvider`] as a wasmCloud provider
+    pub async fn run() -> anyhow::Result<()> {
+        initialize_observability!(
+            SseProvider::name(),
+            std::env::var_os("PROVIDER_SSE_FLAMEGRAPH_PATH")
+        );
+        let provider = SseProvider::default();
+        let shutdown = run_provider(provider.clone(), SseProvider::name())
+            .await
+            .context("failed to run provider")?;
+        let connection = get_connection();
+        let wrpc = connection
+            .get_wrpc_client(connection.provider_key())
+            .await?;
+        serve_provider_exports(&wrpc, provider, shutdown, bindings::serve)
+            .await
+            .context("failed to serve provider exports")
+    }
+
+    /// Get the SSE server of the source of the invocation
+    async fn server(&self, context: Option<Context>) -> Result<Arc<SseServer>> {
+        let source_id = context
+            .and_then(|Context { component, .. }| component)
+            .context("failed to lookup source of invocation")?;
+        self.servers
+            .read()
+            .await
+            .get(&source_id)
+            .cloned()
+            .with_context(|| format!("no SSE link configured for component `{source_id}`"))
+    }
+}
+
+impl Provider for SseProvider {
+    #[instrument(level = "info", skip_all, fields(source_id = link_config.source_id))]
+    async fn receive_link_config_as_target(
+        &self,
+        link_config: LinkConfig<'_>,
+    ) -> anyhow::Result<()> {
+        let config = match SseConfig::from_link_config(&link_config) {
+            Ok(config) => config,
+            Err(e) => {
+                error!(error = %e, source_id = %link_config.source_id, "failed to parse SSE configuration");
+                return Err(e);
+            }
+        };
+        let mut servers = self.servers.write().await;
+        let previous = servers.remove(link_config.source_id);
+        // The server of a previous link of the component has to release the port first
+        if let Some(previous) = previous
+            .as_ref()
+            .filter(|previous| previous.address().port() == config.address.port())
+        {
+            previous.stop().await;
+        }
+        match SseServer::start(config).await {
+            Ok(server) => {
+                if let Some(previous) = previous {
+                    previous.stop().await;
+                }
+                servers.insert(link_config.source_id.to_string(), Arc::new(server));
+                Ok(())
+            }
+            Err(err) => {
+                // Keep serving the component with the configuration of the previous link
+                if let Some(previous) = previous {
+                    if previous.is_stopped() {
+                        match SseServer::start(previous.config().clone()).await {
+                            Ok(server) => {
+                                servers.insert(link_config.source_id.to_string(), Arc::new(server));
+                            }
+                            Err(restart_err) => {
+                                error!(?restart_err, "failed to restart previous SSE server");
+                            }
+                        }
+                    } else {
+                        servers.insert(link_config.source_id.to_string(), previous);
+                    }
+                }
+                Err(err.context("failed to start SSE server"))
+            }
+        }
+    }
+
+    #[instrument(level = "info", skip_all, fields(source_id = info.get_source_id()))]
+    async fn delete_link_as_target(&self, info: impl LinkDeleteInfo) -> anyhow::Result<()> {
+        let server = self.servers.write().await.remove(info.get_source_id());
+        if let Some(server) = server {
+            server.stop().await;
+        }
+        Ok(())
+    }
+
+    fn config_schema(&self) -> Option<ConfigSchema> {
+        Some(config::schema())
+    }
+
+    async fn shutdown(&self) -> anyhow::Result<()> {
+        let servers: Vec<_> = self.servers.write().await.drain().collect();
+        for (_, server) in servers {
+            server.stop().await;
+        }
+        Ok(())
+    }
+}
+
+impl Handler<Option<Context>> for SseProvider {
+    #[instrument(level = "debug", skip(self, cx, event))]
+    async fn broadcast(
+        &self,
+        cx: Option<Context>,
+        channel: String,
+        Event {
+            event_type,
+            data,
+            id,
+        }: Event,
+    ) -> anyhow::Result<Result<u32, String>> {
+        propagate_trace_for_ctx!(cx);
+        let event = SseEvent {
+            event_type,
+            data,
+            id,
+        };
+        Ok(async { self.server(cx).await?.broadcast(&channel, event) }
+            .await
+            .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "debug", skip(self, cx))]
+    async fn subscribers(
+        &self,
+        cx: Option<Context>,
+        channel: String,
+    ) -> anyhow::Result<Result<u32, String>> {
+        propagate_trace_for_ctx!(cx);
+        Ok(async { self.server(cx).await?.subscribers(&channel) }
+            .await
+            .map_err(|err| format!("{err:#}")))
+    }
+}
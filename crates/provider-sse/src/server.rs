@@ -0,0 +1,529 @@
+//! SSE server of a link, serving the channels of the linked component to browsers
+
+use core::convert::Infallible;
+use core::net::SocketAddr;
+use core::time::Duration;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use anyhow::{Context as _, Result};
+use axum::extract::{Path, Query, State};
+use axum::http::header::{ACCESS_CONTROL_ALLOW_ORIGIN, AUTHORIZATION, ORIGIN, VARY};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use futures::{Stream, StreamExt as _};
+use serde::Deserialize;
+use tokio::sync::{broadcast, watch, OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinHandle;
+use tokio::time::timeout;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio_stream::wrappers::BroadcastStream;
+use tracing::{debug, error, info, warn};
+
+use crate::config::{validate_channel, SseConfig};
+
+/// Number of events buffered per channel for subscribers, which are behind. Subscribers falling
+/// further behind skip the events they missed
+const CHANNEL_CAPACITY: usize = 128;
+
+/// Time given to the server to disconnect its subscribers and release its address when stopped,
+/// after which it is aborted
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// An event broadcast to the subscribers of a channel
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    pub event_type: Option<String>,
+    pub data: String,
+    pub id: Option<String>,
+}
+
+impl From<&SseEvent> for Event {
+    fn from(
+        SseEvent {
+            event_type,
+            data,
+            id,
+        }: &SseEvent,
+    ) -> Self {
+        let mut event = Event::default().data(data);
+        if let Some(event_type) = event_type {
+            event = event.event(event_type);
+        }
+        if let Some(id) = id {
+            event = event.id(id);
+        }
+        event
+    }
+}
+
+/// Subscribers of a channel
+struct Channel {
+    tx: broadcast::Sender<Arc<SseEvent>>,
+    /// Limits the number of connected subscribers of the channel
+    permits: Arc<Semaphore>,
+}
+
+/// Channels of a link and their subscribers
+struct Channels {
+    config: SseConfig,
+    channels: RwLock<HashMap<String, Channel>>,
+    /// Limits the number of connected subscribers of all channels
+    permits: Arc<Semaphore>,
+    /// Changes once the server is stopped, disconnecting all subscribers
+    stopped: watch::Receiver<()>,
+}
+
+impl Channels {
+    fn read(&self) -> RwLockReadGuard<'_, HashMap<String, Channel>> {
+        self.channels.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, HashMap<String, Channel>> {
+        self.channels
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Subscription of a connected subscriber, which releases its connection permits and forgets
+/// the channel once its last subscriber disconnected
+struct Subscription {
+    channels: Arc<Channels>,
+    channel: String,
+    _permit: OwnedSemaphorePermit,
+    channel_permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        drop(self.channel_permit.take());
+        // Subscribers acquire channel permits with the lock held, so no subscriber can connect
+        // to the channel before it is removed
+        let mut channels = self.channels.write();
+        if channels
+            .get(&self.channel)
+            .is_some_and(|Channel { permits, .. }| {
+                permits.available_permits() == self.channels.config.max_channel_connections
+            })
+        {
+            channels.remove(&self.channel);
+            debug!(channel = %self.channel, "last subscriber disconnected");
+        }
+    }
+}
+
+/// SSE server of a link. Dropping the server aborts it, [`SseServer::stop`] stops it gracefully
+pub struct SseServer {
+    channels: Arc<Channels>,
+    /// Address the server is bound to
+    address: SocketAddr,
+    /// Task serving the channels, `None` once the server is stopped
+    task: Mutex<Option<JoinHandle<()>>>,
+    stop: watch::Sender<()>,
+}
+
+impl Drop for SseServer {
+    fn drop(&mut self) {
+        if let Some(task) = self
+            .task
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+        {
+            task.abort();
+        }
+    }
+}
+
+impl SseServer {
+    /// Binds the address of `config` and starts serving the channels of the link
+    pub async fn start(config: SseConfig) -> Result<Self> {
+        let listener = tokio::net::TcpListener::bind(config.address)
+            .await
+            .with_context(|| format!("failed to bind SSE server to `{}`", config.address))?;
+        let address = listener
+            .local_addr()
+            .context("failed to get SSE server address")?;
+        let path = format!("{}/:channel", config.path_prefix);
+        let (stop, stopped) = watch::channel(());
+        let mut shutdown = stopped.clone();
+        let channels = Arc::new(Channels {
+            permits: Arc::new(Semaphore::new(config.max_connections)),
+            config,
+            channels: RwLock::default(),
+            stopped,
+        });
+        let router = Router::new()
+            .route(&path, get(subscribe))
+            .with_state(Arc::clone(&channels));
+        let task = tokio::spawn(async move {
+            if let Err(err) = axum::serve(listener, router)
+                .with_graceful_shutdown(async move {
+                    let _ = shutdown.changed().await;
+                })
+                .await
+            {
+                error!(?err, %address, "SSE server failed");
+            }
+        });
+        info!(%address, "SSE server listening");
+        Ok(Self {
+            channels,
+            address,
+            task: Mutex::new(Some(task)),
+            stop,
+        })
+    }
+
+    /// Address the server is bound to
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Configuration the server was started with
+    pub fn config(&self) -> &SseConfig {
+        &self.channels.config
+    }
+
+    /// Returns `true` if the server was stopped
+    pub fn is_stopped(&self) -> bool {
+        self.task
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_none()
+    }
+
+    /// Stops serving, disconnects all subscribers and waits for the server to release its
+    /// address, so that it can be bound again once this returns
+    pub async fn stop(&self) {
+        self.stop.send_replace(());
+        let task = self
+            .task
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        let Some(mut task) = task else {
+            return;
+        };
+        if timeout(SHUTDOWN_TIMEOUT, &mut task).await.is_err() {
+            warn!(address = %self.address, "SSE server did not stop in time, aborting it");
+            task.abort();
+            let _ = task.await;
+        }
+        info!(address = %self.address, "SSE server stopped");
+    }
+
+    /// Broadcasts `event` to the subscribers of `channel`, returning the number of subscribers
+    /// the event was sent to
+    pub fn broadcast(&self, channel: &str, event: SseEvent) -> Result<u32> {
+        validate_channel(channel)?;
+        let channels = self.channels.read();
+        let Some(Channel { tx, .. }) = channels.get(channel) else {
+            return Ok(0);
+        };
+        // Sending only fails if there are no subscribers
+        Ok(tx
+            .send(Arc::new(event))
+            .map_or(0, |subscribers| subscribers.try_into().unwrap_or(u32::MAX)))
+    }
+
+    /// Returns the number of subscribers of `channel`
+    pub fn subscribers(&self, channel: &str) -> Result<u32> {
+        validate_channel(channel)?;
+        Ok(self
+            .channels
+            .read()
+            .get(channel)
+            .map_or(0, |Channel { tx, .. }| tx.receiver_count())
+            .try_into()
+            .unwrap_or(u32::MAX))
+    }
+}
+
+#[derive(Deserialize)]
+struct SubscribeParams {
+    /// Token of the channel, since browsers cannot set headers on `EventSource` requests
+    token: Option<String>,
+}
+
+/// Subscribes to a channel, streaming its events until the client disconnects
+async fn subscribe(
+    State(channels): State<Arc<Channels>>,
+    Path(channel): Path<String>,
+    Query(SubscribeParams { token }): Query<SubscribeParams>,
+    headers: HeaderMap,
+) -> Response {
+    let cors = match cors_origin(&channels.config.allowed_origins, &headers) {
+        Ok(cors) => cors,
+        Err(()) => return (StatusCode::FORBIDDEN, "origin not allowed").into_response(),
+    };
+    if let Err(err) = validate_channel(&channel) {
+        return (StatusCode::NOT_FOUND, err.to_string()).into_response();
+    }
+    if let Some(expected) = channels.config.tokens.get(&channel) {
+        let token = token.as_deref().or_else(|| {
+            headers
+                .get(AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "))
+        });
+        if !token.is_some_and(|token| constant_time_eq(token.as_bytes(), expected.as_bytes())) {
+            return (StatusCode::UNAUTHORIZED, "invalid channel token").into_response();
+        }
+    }
+    let Ok(permit) = Arc::clone(&channels.permits).try_acquire_owned() else {
+        warn!(channel, "rejecting subscriber, connection limit reached");
+        return (StatusCode::SERVICE_UNAVAILABLE, "too many connections").into_response();
+    };
+    let (rx, subscription) = {
+        let mut subscribed = channels.write();
+        let Channel { tx, permits } =
+            subscribed
+                .entry(channel.clone())
+                .or_insert_with(|| Channel {
+                    tx: broadcast::channel(CHANNEL_CAPACITY).0,
+                    permits: Arc::new(Semaphore::new(channels.config.max_channel_connections)),
+                });
+        let Ok(channel_permit) = Arc::clone(permits).try_acquire_owned() else {
+            warn!(
+                channel,
+                "rejecting subscriber, channel connection limit reached"
+            );
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "too many connections to channel",
+            )
+                .into_response();
+        };
+        let subscription = Subscription {
+            channels: Arc::clone(&channels),
+            channel: channel.clone(),
+            _permit: permit,
+            channel_permit: Some(channel_permit),
+        };
+        (tx.subscribe(), subscription)
+    };
+    debug!(channel, "subscriber connected");
+    let mut stopped = channels.stopped.clone();
+    let events = events(rx, channel)
+        .map(move |event| {
+            // The subscription ends once the subscriber disconnects and the stream is dropped
+            let _ = &subscription;
+            event
+        })
+        .take_until(async move {
+            // Completes once the server is stopped or dropped
+            let _ = stopped.changed().await;
+        });
+    let mut response = Sse::new(events)
+        .keep_alive(KeepAlive::new().interval(channels.config.keep_alive))
+        .into_response();
+    if let Some(origin) = cors {
+        let headers = response.headers_mut();
+        headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+        headers.insert(VARY, HeaderValue::from_static("Origin"));
+    }
+    response
+}
+
+/// Streams the events received on `rx`, skipping those missed by a lagging subscriber
+fn events(
+    rx: broadcast::Receiver<Arc<SseEvent>>,
+    channel: String,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    BroadcastStream::new(rx).filter_map(move |event| {
+        let event = match event {
+            Ok(event) => Some(Ok(Event::from(event.as_ref()))),
+            Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+                warn!(channel, skipped, "subscriber is lagging, skipped events");
+                None
+            }
+        };
+        async move { event }
+    })
+}
+
+/// Returns the value of the `Access-Control-Allow-Origin` header for the request, if any.
+/// Fails if the request is sent from an origin, which is not allowed
+fn cors_origin(allowed_origins: &[String], headers: &HeaderMap) -> Result<Option<HeaderValue>, ()> {
+    let Some(origin) = headers.get(ORIGIN) else {
+        // Requests without an origin are not sent by browsers cross-origin
+        return Ok(None);
+    };
+    if allowed_origins.iter().any(|allowed| allowed == "*") {
+        return Ok(Some(HeaderValue::from_static("*")));
+    }
+    let allowed = origin
+        .to_str()
+        .is_ok_and(|origin| allowed_origins.iter().any(|allowed| allowed == origin));
+    if allowed {
+        Ok(Some(origin.clone()))
+    } else if allowed_origins.is_empty() {
+        // Same-origin requests may carry an origin, leave enforcement to the browser
+        Ok(None)
+    } else {
+        Err(())
+    }
+}
+
+/// Compares `a` and `b` in time independent of the position of the first difference
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+    use tokio::net::TcpStream;
+
+    use crate::config::Tokens;
+
+    use super::*;
+
+    fn config() -> SseConfig {
+        SseConfig {
+            address: "127.0.0.1:0".parse().unwrap(),
+            path_prefix: "/events".into(),
+            max_connections: 2,
+            max_channel_connections: 1,
+            // Send keep-alives often, so that disconnected subscribers are noticed quickly
+            keep_alive: Duration::from_millis(10),
+            allowed_origins: vec![],
+            tokens: Tokens {
+                default: None,
+                channels: HashMap::from([("private".into(), "secret".into())]),
+            },
+        }
+    }
+
+    /// Subscribes to `path` on the server at `address`, returning the response status and the
+    /// connection
+    async fn subscribe(
+        address: SocketAddr,
+        path: &str,
+        headers: &str,
+    ) -> anyhow::Result<(u16, TcpStream)> {
+        let mut stream = TcpStream::connect(address).await?;
+        stream
+            .write_all(
+                format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n{headers}\r\n").as_bytes(),
+            )
+            .await?;
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            let mut buf = [0; 1];
+            anyhow::ensure!(stream.read(&mut buf).await? == 1, "connection closed");
+            head.extend_from_slice(&buf);
+        }
+        let head = String::from_utf8(head)?;
+        let status = head
+            .split(' ')
+            .nth(1)
+            .context("invalid response")?
+            .parse()?;
+        Ok((status, stream))
+    }
+
+    /// Waits until `channel` of `server` is forgotten
+    async fn forgotten(server: &SseServer, channel: &str) -> anyhow::Result<()> {
+        timeout(Duration::from_secs(5), async {
+            while server.channels.read().contains_key(channel) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .context("channel was not forgotten")
+    }
+
+    #[tokio::test]
+    async fn tokens_and_limits() -> anyhow::Result<()> {
+        let server = SseServer::start(config()).await?;
+        let address = server.address();
+
+        let (status, _) = subscribe(address, "/events/private", "").await?;
+        assert_eq!(status, 401);
+        let (status, _) = subscribe(address, "/events/private?token=wrong", "").await?;
+        assert_eq!(status, 401);
+        let (status, private) = subscribe(
+            address,
+            "/events/private",
+            "Authorization: Bearer secret\r\n",
+        )
+        .await?;
+        assert_eq!(status, 200);
+        assert_eq!(server.subscribers("private")?, 1);
+
+        // Only a single subscriber is allowed per channel
+        let (status, _) = subscribe(address, "/events/private?token=secret", "").await?;
+        assert_eq!(status, 503);
+        let (status, _public) = subscribe(address, "/events/public", "").await?;
+        assert_eq!(status, 200);
+        assert_eq!(
+            server.broadcast("public", SseEvent::default())?,
+            1,
+            "event should be sent to the subscriber"
+        );
+        // Only two subscribers are allowed in total
+        let (status, _) = subscribe(address, "/events/news", "").await?;
+        assert_eq!(status, 503);
+        assert!(!server.channels.read().contains_key("news"));
+
+        // The channel is forgotten and its permits released once its subscriber disconnected
+        drop(private);
+        forgotten(&server, "private").await?;
+        assert_eq!(server.subscribers("private")?, 0);
+        let (status, _) = subscribe(address, "/events/private?token=secret", "").await?;
+        assert_eq!(status, 200);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn stop_releases_address() -> anyhow::Result<()> {
+        let server = SseServer::start(config()).await?;
+        let address = server.address();
+        let (status, mut subscriber) = subscribe(address, "/events/public", "").await?;
+        assert_eq!(status, 200);
+
+        server.stop().await;
+        assert!(server.is_stopped());
+        // The subscriber was disconnected
+        let mut events = Vec::new();
+        timeout(Duration::from_secs(5), subscriber.read_to_end(&mut events))
+            .await
+            .context("subscriber was not disconnected")??;
+        // The address can be bound again
+        let server = SseServer::start(SseConfig {
+            address,
+            ..config()
+        })
+        .await?;
+        assert_eq!(server.address(), address);
+        server.stop().await;
+        Ok(())
+    }
+
+    #[test]
+    fn allowed_origins() {
+        let headers = HeaderMap::from_iter([(ORIGIN, HeaderValue::from_static("https://a.dev"))]);
+        assert_eq!(cors_origin(&[], &HeaderMap::new()), Ok(None));
+        assert_eq!(cors_origin(&[], &headers), Ok(None));
+        assert_eq!(
+            cors_origin(&["*".into()], &headers),
+            Ok(Some(HeaderValue::from_static("*")))
+        );
+        assert_eq!(
+            cors_origin(&["https://a.dev".into()], &headers),
+            Ok(Some(HeaderValue::from_static("https://a.dev")))
+        );
+        assert_eq!(cors_origin(&["https://b.dev".into()], &headers), Err(()));
+
+        assert!(constant_time_eq(b"token", b"token"));
+        assert!(!constant_time_eq(b"token", b"tokem"));
+        assert!(!constant_time_eq(b"token", b"token2"));
+    }
+}
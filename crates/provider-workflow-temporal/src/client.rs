@@ -0,0 +1,229 @@
+//! Client of the HTTP API of a Temporal frontend
+
+use anyhow::{bail, Context as _};
+use reqwest::{Response, Url};
+use serde_json::{json, Map, Value};
+use tracing::{debug, instrument};
+
+use crate::bindings::exports::wasmcloud::temporal::client::{
+    StartWorkflowOptions, WorkflowExecution,
+};
+use crate::config::TemporalConfig;
+
+/// Client of the workflows of a Temporal namespace
+#[derive(Clone, Debug)]
+pub struct TemporalClient {
+    http: reqwest::Client,
+    config: TemporalConfig,
+}
+
+impl TemporalClient {
+    /// Construct a [`TemporalClient`] from link configuration
+    pub fn new(config: TemporalConfig) -> anyhow::Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(config.request_timeout)
+            .build()
+            .context("failed to build HTTP client")?;
+        Ok(Self { http, config })
+    }
+
+    /// Start a workflow, using the task queue of the link unless specified in `options`
+    #[instrument(level = "debug", skip_all, fields(workflow_id = %options.workflow_id, workflow_type = %options.workflow_type))]
+    pub async fn start_workflow(
+        &self,
+        options: StartWorkflowOptions,
+    ) -> anyhow::Result<WorkflowExecution> {
+        let task_queue = options
+            .task_queue
+            .as_deref()
+            .or(self.config.task_queue.as_deref())
+            .context("no task queue specified and no `TASK_QUEUE` set in link configuration")?;
+        let body = start_workflow_body(&options, task_queue)?;
+        let res = self
+            .post(&[options.workflow_id.as_str()], &body)
+            .await
+            .context("failed to start workflow")?;
+        let run_id = res.get("runId").and_then(Value::as_str).map(String::from);
+        debug!(?run_id, "started workflow");
+        Ok(WorkflowExecution {
+            workflow_id: options.workflow_id,
+            run_id,
+        })
+    }
+
+    /// Send a signal to a workflow
+    #[instrument(level = "debug", skip_all, fields(workflow_id = %execution.workflow_id, signal))]
+    pub async fn signal_workflow(
+        &self,
+        execution: &WorkflowExecution,
+        signal: &str,
+        args: &[String],
+    ) -> anyhow::Result<()> {
+        let mut body = Map::new();
+        body.insert("input".into(), payloads(args)?);
+        if let Some(run_id) = &execution.run_id {
+            body.insert("workflowExecution".into(), json!({ "runId": run_id }));
+        }
+        self.post(
+            &[execution.workflow_id.as_str(), "signal", signal],
+            &Value::Object(body),
+        )
+        .await
+        .context("failed to signal workflow")?;
+        Ok(())
+    }
+
+    /// Query a workflow, returning the JSON-encoded result
+    #[instrument(level = "debug", skip_all, fields(workflow_id = %execution.workflow_id, query))]
+    pub async fn query_workflow(
+        &self,
+        execution: &WorkflowExecution,
+        query: &str,
+        args: &[String],
+    ) -> anyhow::Result<String> {
+        let mut body = Map::new();
+        body.insert("query".into(), json!({ "queryArgs": payloads(args)? }));
+        if let Some(run_id) = &execution.run_id {
+            body.insert("execution".into(), json!({ "runId": run_id }));
+        }
+        let res = self
+            .post(
+                &[execution.workflow_id.as_str(), "query", query],
+                &Value::Object(body),
+            )
+            .await
+            .context("failed to query workflow")?;
+        query_result(res)
+    }
+
+    /// Send a request to the workflows resource at `path` of the namespace, returning the JSON
+    /// response
+    async fn post(&self, path: &[&str], body: &Value) -> anyhow::Result<Value> {
+        let url = self.workflows_url(path)?;
+        let mut req = self.http.post(url).json(body);
+        if let Some(api_key) = &self.config.api_key {
+            req = req.bearer_auth(api_key);
+        }
+        let res = req.send().await.context("failed to send request")?;
+        response_json(res).await
+    }
+
+    fn workflows_url(&self, path: &[&str]) -> anyhow::Result<Url> {
+        let mut url = self.config.address.clone();
+        url.path_segments_mut()
+            .map_err(|()| anyhow::anyhow!("Temporal address cannot be a base URL"))?
+            .pop_if_empty()
+            .extend([
+                "api",
+                "v1",
+                "namespaces",
+                self.config.namespace.as_str(),
+                "workflows",
+            ])
+            .extend(path);
+        Ok(url)
+    }
+}
+
+/// Parse JSON-encoded arguments into the payloads of a request. The HTTP API accepts plain JSON
+/// values as payloads, which are encoded as `json/plain` payloads
+fn payloads(args: &[String]) -> anyhow::Result<Value> {
+    args.iter()
+        .enumerate()
+        .map(|(i, arg)| {
+            serde_json::from_str(arg).with_context(|| format!("argument {i} is not valid JSON"))
+        })
+        .collect::<anyhow::Result<_>>()
+        .map(Value::Array)
+}
+
+fn start_workflow_body(options: &StartWorkflowOptions, task_queue: &str) -> anyhow::Result<Value> {
+    let mut body = Map::new();
+    body.insert(
+        "workflowType".into(),
+        json!({ "name": options.workflow_type }),
+    );
+    body.insert("taskQueue".into(), json!({ "name": task_queue }));
+    body.insert("input".into(), payloads(&options.args)?);
+    if let Some(secs) = options.execution_timeout_secs {
+        body.insert("workflowExecutionTimeout".into(), json!(format!("{secs}s")));
+    }
+    if let Some(secs) = options.start_delay_secs {
+        body.insert("workflowStartDelay".into(), json!(format!("{secs}s")));
+    }
+    Ok(Value::Object(body))
+}
+
+/// Extract the JSON-encoded result from the response to a query
+fn query_result(mut res: Value) -> anyhow::Result<String> {
+    if let Some(rejected) = res.get("queryRejected") {
+        bail!("query was rejected: {rejected}")
+    }
+    let result = match res.get_mut("queryResult").map(Value::take) {
+        // A query result holds a single payload
+        Some(Value::Array(mut payloads)) if !payloads.is_empty() => payloads.swap_remove(0),
+        Some(Value::Array(..)) | None => Value::Null,
+        Some(result) => result,
+    };
+    serde_json::to_string(&result).context("failed to encode query result")
+}
+
+/// Read the JSON body of a response, returning an error containing the message of the Temporal
+/// error for unsuccessful responses
+async fn response_json(res: Response) -> anyhow::Result<Value> {
+    let status = res.status();
+    let body = res.bytes().await.context("failed to read response")?;
+    if !status.is_success() {
+        let message = serde_json::from_slice::<Value>(&body)
+            .ok()
+            .and_then(|err| err.get("message").and_then(Value::as_str).map(String::from))
+            .unwrap_or_else(|| String::from_utf8_lossy(&body).into_owned());
+        bail!("Temporal responded with {status}: {message}")
+    }
+    if body.is_empty() {
+        return Ok(Value::Null);
+    }
+    serde_json::from_slice(&body).context("failed to decode response")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn start_body() {
+        let options = StartWorkflowOptions {
+            workflow_id: "reminder-42".into(),
+            workflow_type: "SendReminder".into(),
+            task_queue: None,
+            args: vec![r#"{"user":"42"}"#.into(), "3".into()],
+            execution_timeout_secs: None,
+            start_delay_secs: Some(3600),
+        };
+        assert_eq!(
+            start_workflow_body(&options, "reminders").unwrap(),
+            json!({
+                "workflowType": { "name": "SendReminder" },
+                "taskQueue": { "name": "reminders" },
+                "input": [{ "user": "42" }, 3],
+                "workflowStartDelay": "3600s",
+            })
+        );
+
+        let options = StartWorkflowOptions {
+            args: vec!["not json".into()],
+            ..options
+        };
+        assert!(start_workflow_body(&options, "reminders").is_err());
+    }
+
+    #[test]
+    fn parse_query_result() {
+        assert_eq!(
+            query_result(json!({ "queryResult": [{ "done": true }] })).unwrap(),
+            r#"{"done":true}"#
+        );
+        assert_eq!(query_result(json!({})).unwrap(), "null");
+        assert!(query_result(json!({ "queryRejected": { "status": "COMPLETED" } })).is_err());
+    }
+}
@@ -0,0 +1,162 @@
+//! Configuration for the workflow-temporal capability provider
+
+use core::time::Duration;
+
+use anyhow::{Context as _, Result};
+use reqwest::Url;
+use tracing::warn;
+use wasmcloud_provider_sdk::LinkConfig;
+
+/// Configuration key of the address of the HTTP API of the Temporal frontend
+const CONFIG_ADDRESS_KEY: &str = "ADDRESS";
+
+/// Configuration key of the Temporal namespace
+const CONFIG_NAMESPACE_KEY: &str = "NAMESPACE";
+
+/// Configuration key of the default task queue workflows are started on
+const CONFIG_TASK_QUEUE_KEY: &str = "TASK_QUEUE";
+
+/// Configuration key of the timeout of requests to Temporal in milliseconds
+const CONFIG_REQUEST_TIMEOUT_KEY: &str = "REQUEST_TIMEOUT_MS";
+
+/// Secret (or, discouraged, configuration key) of the API key used to authenticate with Temporal
+const API_KEY_SECRET: &str = "api_key";
+
+const DEFAULT_ADDRESS: &str = "http://127.0.0.1:7243";
+
+const DEFAULT_NAMESPACE: &str = "default";
+
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Configuration of the connection to a Temporal cluster for a link
+#[derive(Clone, PartialEq, Eq)]
+pub struct TemporalConfig {
+    /// Address of the HTTP API of the Temporal frontend
+    pub address: Url,
+    /// Namespace that workflows are started in
+    pub namespace: String,
+    /// Task queue that workflows are started on, unless specified by the component
+    pub task_queue: Option<String>,
+    /// Timeout of requests to Temporal
+    pub request_timeout: Duration,
+    /// API key used to authenticate with Temporal
+    pub api_key: Option<String>,
+}
+
+impl core::fmt::Debug for TemporalConfig {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TemporalConfig")
+            .field("address", &self.address.as_str())
+            .field("namespace", &self.namespace)
+            .field("task_queue", &self.task_queue)
+            .field("request_timeout", &self.request_timeout)
+            .field("api_key", &self.api_key.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+impl TemporalConfig {
+    /// Construct a [`TemporalConfig`] from a link configuration. Keys are matched
+    /// case-insensitively
+    pub fn from_link_config(
+        LinkConfig {
+            config, secrets, ..
+        }: &LinkConfig,
+    ) -> Result<Self> {
+        let value = |key: &str| {
+            config
+                .iter()
+                .find(|(k, v)| k.eq_ignore_ascii_case(key) && !v.is_empty())
+                .map(|(_, v)| v.as_str())
+        };
+        let address = value(CONFIG_ADDRESS_KEY).unwrap_or(DEFAULT_ADDRESS);
+        let address = address
+            .parse()
+            .with_context(|| format!("invalid `{CONFIG_ADDRESS_KEY}` value `{address}`"))?;
+        let request_timeout = value(CONFIG_REQUEST_TIMEOUT_KEY)
+            .map(|ms| {
+                ms.parse()
+                    .map(Duration::from_millis)
+                    .with_context(|| format!("invalid `{CONFIG_REQUEST_TIMEOUT_KEY}` value `{ms}`"))
+            })
+            .transpose()?
+            .unwrap_or(DEFAULT_REQUEST_TIMEOUT);
+        let api_key = match secrets
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(API_KEY_SECRET))
+            .and_then(|(_, v)| v.as_string())
+        {
+            Some(api_key) => Some(api_key.to_string()),
+            None => value(API_KEY_SECRET).map(|api_key| {
+                warn!("secret [{API_KEY_SECRET}] was not found, using it from configuration. Please prefer using secrets for sensitive values.");
+                api_key.to_string()
+            }),
+        };
+        Ok(Self {
+            address,
+            namespace: value(CONFIG_NAMESPACE_KEY)
+                .unwrap_or(DEFAULT_NAMESPACE)
+                .to_string(),
+            task_queue: value(CONFIG_TASK_QUEUE_KEY).map(String::from),
+            request_timeout,
+            api_key,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use wasmcloud_provider_sdk::core::secrets::SecretValue;
+
+    use super::*;
+
+    #[test]
+    fn parse_config() {
+        let config = HashMap::from([
+            (
+                "address".to_string(),
+                "https://temporal.example.com:7243".to_string(),
+            ),
+            ("Namespace".to_string(), "orders".to_string()),
+            ("TASK_QUEUE".to_string(), "activities".to_string()),
+        ]);
+        let secrets = HashMap::from([(
+            "api_key".to_string(),
+            SecretValue::String("secret".to_string()),
+        )]);
+        let config = TemporalConfig::from_link_config(&LinkConfig {
+            source_id: "component",
+            target_id: "provider",
+            link_name: "default",
+            config: &config,
+            secrets: &secrets,
+            wit_metadata: (&"wasmcloud".to_string(), &"temporal".to_string(), &vec![]),
+        })
+        .unwrap();
+        assert_eq!(
+            config.address.as_str(),
+            "https://temporal.example.com:7243/"
+        );
+        assert_eq!(config.namespace, "orders");
+        assert_eq!(config.task_queue.as_deref(), Some("activities"));
+        assert_eq!(config.request_timeout, DEFAULT_REQUEST_TIMEOUT);
+        assert_eq!(config.api_key.as_deref(), Some("secret"));
+        assert!(!format!("{config:?}").contains("secret"));
+
+        let config = TemporalConfig::from_link_config(&LinkConfig {
+            source_id: "component",
+            target_id: "provider",
+            link_name: "default",
+            config: &HashMap::new(),
+            secrets: &HashMap::new(),
+            wit_metadata: (&"wasmcloud".to_string(), &"temporal".to_string(), &vec![]),
+        })
+        .unwrap();
+        assert_eq!(config.address.as_str(), "http://127.0.0.1:7243/");
+        assert_eq!(config.namespace, DEFAULT_NAMESPACE);
+        assert_eq!(config.task_queue, None);
+        assert_eq!(config.api_key, None);
+    }
+}
@@ -0,0 +1,170 @@
+//! Temporal workflow provider implementing `wasmcloud:temporal/client`, which lets components
+//! start, signal and query workflows on a Temporal cluster.
+//!
+//! Each link holds its own connection configuration, so that components may drive workflows in
+//! different namespaces or clusters.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{Context as _, Result};
+use tokio::sync::RwLock;
+use tracing::{error, instrument};
+use wasmcloud_provider_sdk::{
+    get_connection, initialize_observability, propagate_trace_for_ctx, run_provider,
+    serve_provider_exports, Context, LinkConfig, LinkDeleteInfo, Provider,
+};
+
+mod client;
+mod config;
+
+use client::TemporalClient;
+use config::TemporalConfig;
+
+mod bindings {
+    wit_bindgen_wrpc::generate!({
+        with: {
+            "wasmcloud:temporal/client@0.1.0-draft": generate,
+        }
+    });
+}
+use bindings::exports::wasmcloud::temporal::client::{
+    Handler, StartWorkflowOptions, WorkflowExecution,
+};
+
+pub async fn run() -> anyhow::Result<()> {
+    TemporalProvider::run().await
+}
+
+/// Temporal workflow provider
+#[derive(Clone, Default)]
+pub struct TemporalProvider {
+    /// Temporal clients indexed by source ID
+    clients: Arc<RwLock<HashMap<String, TemporalClient>>>,
+}
+
+impl TemporalProvider {
+    fn name() -> &'static str {
+        "workflow-temporal-provider"
+    }
+
+    /// Run [`TemporalProvider`] as a wasmCloud provider
+    pub async fn run() -> anyhow::Result<()> {
+        initialize_observability!(
+            TemporalProvider::name(),
+            std::env::var_os("PROVIDER_WORKFLOW_TEMPORAL_FLAMEGRAPH_PATH")
+        );
+        let provider = TemporalProvider::default();
+        let shutdown = run_provider(provider.clone(), TemporalProvider::name())
+            .await
+            .context("failed to run provider")?;
+        let connection = get_connection();
+        let wrpc = connection
+            .get_wrpc_client(connection.provider_key())
+            .await?;
+        serve_provider_exports(&wrpc, provider, shutdown, bindings::serve)
+            .await
+            .context("failed to serve provider exports")
+    }
+
+    /// Get the client of the source of the invocation
+    async fn client(&self, context: Option<Context>) -> Result<TemporalClient> {
+        let source_id = context
+            .and_then(|Context { component, .. }| component)
+            .context("failed to lookup source of invocation")?;
+        self.clients
+            .read()
+            .await
+            .get(&source_id)
+            .with_context(|| format!("no Temporal link configured for component `{source_id}`"))
+            .cloned()
+    }
+}
+
+impl Provider for TemporalProvider {
+    #[instrument(level = "info", skip_all, fields(source_id = link_config.source_id))]
+    async fn receive_link_config_as_target(
+        &self,
+        link_config: LinkConfig<'_>,
+    ) -> anyhow::Result<()> {
+        let client = match TemporalConfig::from_link_config(&link_config)
+            .and_then(TemporalClient::new)
+        {
+            Ok(client) => client,
+            Err(e) => {
+                error!(error = %e, source_id = %link_config.source_id, "failed to configure Temporal client");
+                return Err(e);
+            }
+        };
+        self.clients
+            .write()
+            .await
+            .insert(link_config.source_id.to_string(), client);
+        Ok(())
+    }
+
+    #[instrument(level = "info", skip_all, fields(source_id = info.get_source_id()))]
+    async fn delete_link_as_target(&self, info: impl LinkDeleteInfo) -> anyhow::Result<()> {
+        self.clients.write().await.remove(info.get_source_id());
+        Ok(())
+    }
+
+    async fn shutdown(&self) -> anyhow::Result<()> {
+        self.clients.write().await.drain();
+        Ok(())
+    }
+}
+
+impl Handler<Option<Context>> for TemporalProvider {
+    #[instrument(level = "debug", skip_all)]
+    async fn start_workflow(
+        &self,
+        cx: Option<Context>,
+        options: StartWorkflowOptions,
+    ) -> anyhow::Result<Result<WorkflowExecution, String>> {
+        propagate_trace_for_ctx!(cx);
+        Ok(
+            async { self.client(cx).await?.start_workflow(options).await }
+                .await
+                .map_err(|err| format!("{err:#}")),
+        )
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn signal_workflow(
+        &self,
+        cx: Option<Context>,
+        execution: WorkflowExecution,
+        signal: String,
+        args: Vec<String>,
+    ) -> anyhow::Result<Result<(), String>> {
+        propagate_trace_for_ctx!(cx);
+        Ok(async {
+            self.client(cx)
+                .await?
+                .signal_workflow(&execution, &signal, &args)
+                .await
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn query_workflow(
+        &self,
+        cx: Option<Context>,
+        execution: WorkflowExecution,
+        query: String,
+        args: Vec<String>,
+    ) -> anyhow::Result<Result<String, String>> {
+        propagate_trace_for_ctx!(cx);
+        Ok(async {
+            self.client(cx)
+                .await?
+                .query_workflow(&execution, &query, &args)
+                .await
+        }
+        .await
+        .map_err(|err| format!("{err:#}")))
+    }
+}
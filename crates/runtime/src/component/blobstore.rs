@@ -7,9 +7,9 @@ use std::sync::Arc;
 
 use anyhow::{anyhow, bail, Context as _};
 use async_trait::async_trait;
-use bytes::Bytes;
+use bytes::{Bytes, BytesMut};
 use futures::future::OptionFuture;
-use futures::{future, FutureExt, Stream, StreamExt as _};
+use futures::{future, stream, FutureExt, Stream, StreamExt as _};
 use tokio::sync::mpsc;
 use tokio::{join, select, try_join};
 use tokio_stream::wrappers::ReceiverStream;
@@ -28,7 +28,10 @@ use crate::capability::blobstore::{blobstore, container, types};
 use crate::capability::wrpc::wrpc::blobstore::blobstore as blobstore_0_1_0;
 use crate::io::BufferedIncomingStream;
 
-use super::{Ctx, Handler, InvocationErrorIntrospect, InvocationErrorKind, ReplacedInstanceTarget};
+use super::{
+    Ctx, EmbeddedBlobstore as _, Handler, InvocationErrorIntrospect, InvocationErrorKind,
+    ReplacedInstanceTarget,
+};
 
 /// Maximum chunk size, pretty arbitrary number of bytes that should fit in a single transport
 /// packet. Some profiling is due to figure out the optimal value here.
@@ -133,6 +136,9 @@ where
             .table
             .get(&container)
             .context("failed to get container")?;
+        if let Some(store) = self.handler.memory_blobstore() {
+            return Ok(store.container_info(name));
+        }
         match invoke_with_fallback(
             "get-container-info",
             &self.handler,
@@ -176,6 +182,25 @@ where
             .table
             .get(&container)
             .context("failed to get container")?;
+        if let Some(store) = self.handler.memory_blobstore() {
+            let id = ObjectId {
+                container: container.to_string(),
+                object: name,
+            };
+            let data = match store.get_data(&id, start, end) {
+                Ok(data) => data,
+                Err(err) => return Ok(Err(err)),
+            };
+            let value = self
+                .table
+                .push(IncomingValue {
+                    stream: Box::pin(stream::iter((!data.is_empty()).then_some(data))),
+                    status: Box::pin(async { Ok(()) }),
+                    io: None,
+                })
+                .context("failed to push stream")?;
+            return Ok(Ok(value));
+        }
         let id = bindings::wasi::blobstore::types::ObjectId {
             container: container.to_string(),
             object: name,
@@ -247,6 +272,30 @@ where
         let HostOutgoingValue::Init(mut rx) = mem::take(host) else {
             bail!("outgoing-value.write-data was already called")
         };
+        if let Some(store) = self.handler.memory_blobstore() {
+            let id = ObjectId {
+                container: container.to_string(),
+                object,
+            };
+            // The value is collected as it is written, so that writers are not blocked
+            let io = wasmtime_wasi::runtime::spawn(async move {
+                let mut buf = BytesMut::new();
+                while let Some(chunk) = rx.recv().await {
+                    buf.extend_from_slice(&chunk);
+                    store
+                        .ensure_fits(buf.len() as u64)
+                        .map_err(anyhow::Error::msg)?;
+                }
+                store
+                    .write_data(&id, buf.freeze())
+                    .map_err(anyhow::Error::msg)
+            });
+            *host = HostOutgoingValue::Writing {
+                status: Box::pin(async { Ok(()) }),
+                io: Some(io),
+            };
+            return Ok(Ok(()));
+        }
         let id = bindings::wrpc::blobstore::types::ObjectId {
             container: container.to_string(),
             object,
@@ -311,6 +360,22 @@ where
             .table
             .get(&container)
             .context("failed to get container")?;
+        if let Some(store) = self.handler.memory_blobstore() {
+            let names = match store.list_objects(container) {
+                Ok(names) => names,
+                Err(err) => return Ok(Err(err)),
+            };
+            let stream = self
+                .table
+                .push(StreamObjectNames {
+                    stream: BufferedIncomingStream::new(Box::pin(stream::iter([names]))),
+                    status: (Box::pin(async { Ok(()) }) as Pin<Box<dyn Future<Output = _> + Send>>)
+                        .fuse(),
+                    io: None.into(),
+                })
+                .context("failed to push object name stream")?;
+            return Ok(Ok(stream));
+        }
         // TODO: implement a stream with limit and offset
         match invoke_with_fallback(
             "list-container-objects",
@@ -383,6 +448,9 @@ where
             .table
             .get(&container)
             .context("failed to get container")?;
+        if let Some(store) = self.handler.memory_blobstore() {
+            return Ok(store.delete_objects(container, &names));
+        }
         let names = names.iter().map(String::as_str).collect::<Vec<_>>();
         invoke_with_fallback(
             "delete-objects",
@@ -418,6 +486,12 @@ where
             .table
             .get(&container)
             .context("failed to get container")?;
+        if let Some(store) = self.handler.memory_blobstore() {
+            return Ok(store.has_object(&ObjectId {
+                container: container.to_string(),
+                object,
+            }));
+        }
         let id = bindings::wrpc::blobstore::types::ObjectId {
             container: container.to_string(),
             object,
@@ -454,6 +528,12 @@ where
             .table
             .get(&container)
             .context("failed to get container")?;
+        if let Some(store) = self.handler.memory_blobstore() {
+            return Ok(store.object_info(&ObjectId {
+                container: container.to_string(),
+                object: name,
+            }));
+        }
         let id = bindings::wrpc::blobstore::types::ObjectId {
             container: container.to_string(),
             object: name.clone(),
@@ -497,6 +577,9 @@ where
             .table
             .get(&container)
             .context("failed to get container")?;
+        if let Some(store) = self.handler.memory_blobstore() {
+            return Ok(store.clear(container));
+        }
         invoke_with_fallback(
             "clear-container",
             &self.handler,
@@ -884,26 +967,30 @@ where
         name: ContainerName,
     ) -> anyhow::Result<Result<Resource<Container>>> {
         self.attach_parent_context();
-        match invoke_with_fallback(
-            "create-container",
-            &self.handler,
-            || {
-                bindings::wrpc::blobstore::blobstore::create_container(
-                    &self.handler,
-                    Some(ReplacedInstanceTarget::BlobstoreBlobstore),
-                    &name,
-                )
-            },
-            || {
-                blobstore_0_1_0::create_container(
-                    &self.handler,
-                    Some(ReplacedInstanceTarget::BlobstoreBlobstore),
-                    &name,
-                )
-            },
-        )
-        .await?
-        {
+        let res = if let Some(store) = self.handler.memory_blobstore() {
+            store.create_container(&name)
+        } else {
+            invoke_with_fallback(
+                "create-container",
+                &self.handler,
+                || {
+                    bindings::wrpc::blobstore::blobstore::create_container(
+                        &self.handler,
+                        Some(ReplacedInstanceTarget::BlobstoreBlobstore),
+                        &name,
+                    )
+                },
+                || {
+                    blobstore_0_1_0::create_container(
+                        &self.handler,
+                        Some(ReplacedInstanceTarget::BlobstoreBlobstore),
+                        &name,
+                    )
+                },
+            )
+            .await?
+        };
+        match res {
             Ok(()) => {
                 let container = self
                     .table
@@ -921,26 +1008,30 @@ where
         name: ContainerName,
     ) -> anyhow::Result<Result<Resource<Container>>> {
         self.attach_parent_context();
-        match invoke_with_fallback(
-            "container-exists",
-            &self.handler,
-            || {
-                bindings::wrpc::blobstore::blobstore::container_exists(
-                    &self.handler,
-                    Some(ReplacedInstanceTarget::BlobstoreBlobstore),
-                    &name,
-                )
-            },
-            || {
-                blobstore_0_1_0::container_exists(
-                    &self.handler,
-                    Some(ReplacedInstanceTarget::BlobstoreBlobstore),
-                    &name,
-                )
-            },
-        )
-        .await?
-        {
+        let res = if let Some(store) = self.handler.memory_blobstore() {
+            store.container_exists(&name)
+        } else {
+            invoke_with_fallback(
+                "container-exists",
+                &self.handler,
+                || {
+                    bindings::wrpc::blobstore::blobstore::container_exists(
+                        &self.handler,
+                        Some(ReplacedInstanceTarget::BlobstoreBlobstore),
+                        &name,
+                    )
+                },
+                || {
+                    blobstore_0_1_0::container_exists(
+                        &self.handler,
+                        Some(ReplacedInstanceTarget::BlobstoreBlobstore),
+                        &name,
+                    )
+                },
+            )
+            .await?
+        };
+        match res {
             Ok(true) => {
                 let container = self
                     .table
@@ -956,6 +1047,9 @@ where
     #[instrument(skip(self))]
     async fn delete_container(&mut self, name: ContainerName) -> anyhow::Result<Result<()>> {
         self.attach_parent_context();
+        if let Some(store) = self.handler.memory_blobstore() {
+            return Ok(store.delete_container(&name));
+        }
         invoke_with_fallback(
             "delete-container",
             &self.handler,
@@ -980,6 +1074,9 @@ where
     #[instrument(skip(self))]
     async fn container_exists(&mut self, name: ContainerName) -> anyhow::Result<Result<bool>> {
         self.attach_parent_context();
+        if let Some(store) = self.handler.memory_blobstore() {
+            return Ok(store.container_exists(&name));
+        }
         invoke_with_fallback(
             "container-exists",
             &self.handler,
@@ -1004,6 +1101,9 @@ where
     #[instrument(skip(self))]
     async fn copy_object(&mut self, src: ObjectId, dest: ObjectId) -> anyhow::Result<Result<()>> {
         self.attach_parent_context();
+        if let Some(store) = self.handler.memory_blobstore() {
+            return Ok(store.copy_object(&src, &dest));
+        }
         let src = bindings::wasi::blobstore::types::ObjectId {
             container: src.container,
             object: src.object,
@@ -1038,6 +1138,9 @@ where
     #[instrument(skip(self))]
     async fn move_object(&mut self, src: ObjectId, dest: ObjectId) -> anyhow::Result<Result<()>> {
         self.attach_parent_context();
+        if let Some(store) = self.handler.memory_blobstore() {
+            return Ok(store.move_object(&src, &dest));
+        }
         let src = bindings::wasi::blobstore::types::ObjectId {
             container: src.container,
             object: src.object,
@@ -0,0 +1,146 @@
+//! Deterministic implementations of `wasi:clocks` and `wasi:random`
+//!
+//! Clocks start at a fixed point in time and advance by a fixed step on every read, random
+//! number generators are seeded with a fixed seed. Every store created for a component in
+//! deterministic mode therefore observes the exact same sequence of times and random values,
+//! which makes test runs reproducible.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng as _;
+use wasmtime_wasi::{HostMonotonicClock, HostWallClock, WasiCtxBuilder};
+
+/// Time reported by the wall clock on first read, 2024-01-01T00:00:00Z
+const WALL_CLOCK_START: Duration = Duration::from_secs(1_704_067_200);
+
+/// Time, which clocks advance by on every read
+const CLOCK_STEP: Duration = Duration::from_millis(1);
+
+/// Wall clock starting at [`WALL_CLOCK_START`], which advances by [`CLOCK_STEP`] on every read
+#[derive(Debug, Default)]
+struct WallClock {
+    reads: AtomicU64,
+}
+
+impl HostWallClock for WallClock {
+    fn resolution(&self) -> Duration {
+        CLOCK_STEP
+    }
+
+    fn now(&self) -> Duration {
+        let reads = self.reads.fetch_add(1, Ordering::Relaxed);
+        WALL_CLOCK_START
+            .saturating_add(CLOCK_STEP.saturating_mul(reads.try_into().unwrap_or(u32::MAX)))
+    }
+}
+
+/// Monotonic clock starting at 0, which advances by [`CLOCK_STEP`] on every read
+#[derive(Debug, Default)]
+struct MonotonicClock {
+    reads: AtomicU64,
+}
+
+impl HostMonotonicClock for MonotonicClock {
+    fn resolution(&self) -> u64 {
+        CLOCK_STEP.as_nanos().try_into().unwrap_or(u64::MAX)
+    }
+
+    fn now(&self) -> u64 {
+        let reads = self.reads.fetch_add(1, Ordering::Relaxed);
+        reads.saturating_mul(self.resolution())
+    }
+}
+
+/// Configures `builder` to use deterministic clocks and random number generators seeded
+/// with `seed`
+pub(crate) fn configure(builder: &mut WasiCtxBuilder, seed: u64) {
+    builder
+        .wall_clock(WallClock::default())
+        .monotonic_clock(MonotonicClock::default())
+        .secure_random(StdRng::seed_from_u64(seed))
+        .insecure_random(StdRng::seed_from_u64(seed.wrapping_add(1)))
+        .insecure_random_seed(u128::from(seed));
+}
+
+#[cfg(test)]
+mod test {
+    use core::time::Duration;
+
+    use wasmtime::component::ResourceTable;
+    use wasmtime_wasi::bindings::clocks::wall_clock::Host as _;
+    use wasmtime_wasi::bindings::random::insecure::Host as _;
+    use wasmtime_wasi::bindings::random::insecure_seed::Host as _;
+    use wasmtime_wasi::bindings::random::random::Host as _;
+    use wasmtime_wasi::{
+        HostMonotonicClock as _, HostWallClock as _, WasiCtx, WasiCtxBuilder, WasiImpl, WasiView,
+    };
+
+    use super::{configure, MonotonicClock, WallClock, CLOCK_STEP, WALL_CLOCK_START};
+
+    struct View {
+        table: ResourceTable,
+        ctx: WasiCtx,
+    }
+
+    impl WasiView for View {
+        fn table(&mut self) -> &mut ResourceTable {
+            &mut self.table
+        }
+
+        fn ctx(&mut self) -> &mut WasiCtx {
+            &mut self.ctx
+        }
+    }
+
+    /// Build a WASI context in deterministic mode, as the host does for every store
+    fn deterministic_view(seed: u64) -> View {
+        let mut builder = WasiCtxBuilder::new();
+        configure(&mut builder, seed);
+        View {
+            table: ResourceTable::new(),
+            ctx: builder.build(),
+        }
+    }
+
+    /// Values observed by a component through `wasi:random` and `wasi:clocks`
+    fn observe(view: &mut View) -> (Vec<u8>, Vec<u8>, (u64, u64), u64) {
+        let mut wasi = WasiImpl(view);
+        (
+            wasi.get_random_bytes(32).unwrap(),
+            wasi.get_insecure_random_bytes(32).unwrap(),
+            wasi.insecure_seed().unwrap(),
+            wasi.now().unwrap().seconds,
+        )
+    }
+
+    #[test]
+    fn clocks_advance_deterministically() {
+        let clock = WallClock::default();
+        assert_eq!(clock.now(), WALL_CLOCK_START);
+        assert_eq!(clock.now(), WALL_CLOCK_START + CLOCK_STEP);
+        assert_eq!(WallClock::default().now(), WALL_CLOCK_START);
+
+        let clock = MonotonicClock::default();
+        assert_eq!(clock.now(), 0);
+        assert_eq!(Duration::from_nanos(clock.now()), CLOCK_STEP);
+    }
+
+    #[test]
+    fn random_is_reproducible() {
+        let (random, insecure, seed, now) = observe(&mut deterministic_view(42));
+        assert_eq!(
+            observe(&mut deterministic_view(42)),
+            (random.clone(), insecure.clone(), seed, now)
+        );
+        assert_eq!(now, WALL_CLOCK_START.as_secs());
+        // Secure and insecure generators are seeded differently
+        assert_ne!(random, insecure);
+
+        let (other_random, other_insecure, other_seed, _) = observe(&mut deterministic_view(43));
+        assert_ne!(random, other_random);
+        assert_ne!(insecure, other_insecure);
+        assert_ne!(seed, other_seed);
+    }
+}
@@ -16,7 +16,7 @@ use wrpc_interface_http::ServeIncomingHandlerWasmtime;
 
 use crate::capability::http::types;
 
-use super::{new_store, Ctx, Handler, Instance, ReplacedInstanceTarget, WrpcServeEvent};
+use super::{Ctx, Handler, Instance, ReplacedInstanceTarget, WrpcServeEvent};
 
 pub mod incoming_http_bindings {
     wasmtime::component::bindgen!({
@@ -140,16 +140,15 @@ where
         let scheme = wrpc_interface_http::bindings::wrpc::http::types::Scheme::from(scheme).into();
 
         let (tx, rx) = oneshot::channel();
-        let mut store = new_store(&self.engine, self.handler.clone(), self.max_execution_time);
-        let pre = incoming_http_bindings::IncomingHttpPre::new(self.pre.clone())
-            .context("failed to pre-instantiate `wasi:http/incoming-handler`")?;
         trace!("instantiating `wasi:http/incoming-handler`");
-        let bindings = pre
-            .instantiate_async(&mut store)
-            .instrument(debug_span!("instantiate_async"))
+        let mut instance = self
+            .acquire()
             .await
             .context("failed to instantiate `wasi:http/incoming-handler`")?;
-        let data = store.data_mut();
+        let bindings =
+            incoming_http_bindings::IncomingHttp::new(&mut instance.store, &instance.instance)
+                .context("failed to get `wasi:http/incoming-handler` exports")?;
+        let data = instance.store.data_mut();
 
         // The below is adapted from `WasiHttpView::new_incoming_request`, which is unusable for
         // us, since it requires a `hyper::Error`
@@ -170,19 +169,20 @@ where
         // TODO: Replicate this for custom interface
         // Set the current invocation parent context for injection on outgoing wRPC requests
         let call_incoming_handle = info_span!("call_http_incoming_handle");
-        store.data_mut().parent_context = Some(call_incoming_handle.context());
+        instance.store.data_mut().parent_context = Some(call_incoming_handle.context());
         let handle = spawn(
             async move {
                 debug!("invoking `wasi:http/incoming-handler.handle`");
                 if let Err(err) = bindings
                     .wasi_http_incoming_handler()
-                    .call_handle(&mut store, request, response)
+                    .call_handle(&mut instance.store, request, response)
                     .instrument(call_incoming_handle)
                     .await
                 {
                     warn!(?err, "failed to call `wasi:http/incoming-handler.handle`");
                     bail!(err.context("failed to call `wasi:http/incoming-handler.handle`"));
                 }
+                instance.release();
                 Ok(())
             }
             .in_current_span(),
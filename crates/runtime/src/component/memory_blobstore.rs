@@ -0,0 +1,345 @@
+//! In-memory `wasi:blobstore` implementation embedded in the runtime
+//!
+//! Components, for which the [`Handler`](super::Handler) returns a [`MemoryBlobstore`] from
+//! [`EmbeddedBlobstore::memory_blobstore`], have their `wasi:blobstore` imports served from memory
+//! instead of being invoked on a linked provider over wRPC. This is intended for local development
+//! without a blobstore provider, objects are lost once the [`MemoryBlobstore`] is dropped.
+//!
+//! The total size of the objects in a [`MemoryBlobstore`] is limited by its quota, writes
+//! exceeding the quota fail.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+
+use crate::capability::blobstore::types::{ContainerMetadata, ObjectId, ObjectMetadata};
+
+type Result<T, E = String> = core::result::Result<T, E>;
+
+/// `wasi:blobstore` implementation embedded in the host
+pub trait EmbeddedBlobstore {
+    /// Returns the in-memory blobstore serving `wasi:blobstore` imports, if any. Imports are
+    /// invoked over wRPC if `None`
+    fn memory_blobstore(&self) -> Option<MemoryBlobstore>;
+}
+
+/// In-memory blobstore, which is cheaply-[Cloneable](Clone). Clones share the same containers
+#[derive(Clone, Debug)]
+pub struct MemoryBlobstore {
+    containers: Arc<RwLock<Containers>>,
+    quota: u64,
+}
+
+#[derive(Debug, Default)]
+struct Containers {
+    containers: BTreeMap<String, Container>,
+    /// Total size of all objects in bytes
+    size: u64,
+}
+
+#[derive(Debug)]
+struct Container {
+    created_at: u64,
+    objects: BTreeMap<String, Object>,
+}
+
+#[derive(Clone, Debug)]
+struct Object {
+    created_at: u64,
+    data: Bytes,
+}
+
+impl Container {
+    fn new() -> Self {
+        Self {
+            created_at: now(),
+            objects: BTreeMap::default(),
+        }
+    }
+
+    fn size(&self) -> u64 {
+        self.objects
+            .values()
+            .map(|object| object.data.len() as u64)
+            .sum()
+    }
+}
+
+impl Containers {
+    fn container(&self, name: &str) -> Result<&Container> {
+        self.containers
+            .get(name)
+            .ok_or_else(|| format!("container `{name}` does not exist"))
+    }
+
+    fn container_mut(&mut self, name: &str) -> Result<&mut Container> {
+        self.containers
+            .get_mut(name)
+            .ok_or_else(|| format!("container `{name}` does not exist"))
+    }
+
+    fn object(&self, ObjectId { container, object }: &ObjectId) -> Result<&Object> {
+        self.container(container)?
+            .objects
+            .get(object)
+            .ok_or_else(|| format!("object `{object}` does not exist in container `{container}`"))
+    }
+
+    /// Inserts `object` as `id`, replacing any previous version of it, if the quota of `quota`
+    /// bytes allows it
+    fn insert(&mut self, quota: u64, id: &ObjectId, object: Object) -> Result<()> {
+        let container = self
+            .containers
+            .get_mut(&id.container)
+            .ok_or_else(|| format!("container `{}` does not exist", id.container))?;
+        let replaced = container
+            .objects
+            .get(&id.object)
+            .map_or(0, |object| object.data.len() as u64);
+        let size = self.size - replaced + object.data.len() as u64;
+        if size > quota {
+            return Err(format!(
+                "object `{}` exceeds the in-memory blobstore quota of {quota} bytes",
+                id.object
+            ));
+        }
+        container.objects.insert(id.object.clone(), object);
+        self.size = size;
+        Ok(())
+    }
+
+    fn remove(&mut self, container: &str, object: &str) -> Result<Option<Object>> {
+        let object = self.container_mut(container)?.objects.remove(object);
+        if let Some(Object { data, .. }) = &object {
+            self.size -= data.len() as u64;
+        }
+        Ok(object)
+    }
+}
+
+impl MemoryBlobstore {
+    /// Creates a new, empty blobstore, which holds at most `quota` bytes of objects
+    #[must_use]
+    pub fn new(quota: u64) -> Self {
+        Self {
+            containers: Arc::default(),
+            quota,
+        }
+    }
+
+    /// Returns the total size of all objects in bytes
+    #[must_use]
+    pub fn size(&self) -> u64 {
+        self.read(|containers| containers.size)
+    }
+
+    fn read<T>(&self, f: impl FnOnce(&Containers) -> T) -> T {
+        f(&self
+            .containers
+            .read()
+            .unwrap_or_else(PoisonError::into_inner))
+    }
+
+    fn write<T>(&self, f: impl FnOnce(&mut Containers) -> T) -> T {
+        f(&mut self
+            .containers
+            .write()
+            .unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Fails if an object of `size` bytes can never fit in the quota
+    pub(crate) fn ensure_fits(&self, size: u64) -> Result<()> {
+        if size > self.quota {
+            return Err(format!(
+                "object exceeds the in-memory blobstore quota of {} bytes",
+                self.quota
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn create_container(&self, name: &str) -> Result<()> {
+        self.write(|containers| {
+            containers
+                .containers
+                .entry(name.to_string())
+                .or_insert_with(Container::new);
+        });
+        Ok(())
+    }
+
+    pub(crate) fn container_exists(&self, name: &str) -> Result<bool> {
+        Ok(self.read(|containers| containers.containers.contains_key(name)))
+    }
+
+    pub(crate) fn delete_container(&self, name: &str) -> Result<()> {
+        self.write(|containers| {
+            if let Some(container) = containers.containers.remove(name) {
+                containers.size -= container.size();
+            }
+        });
+        Ok(())
+    }
+
+    pub(crate) fn container_info(&self, name: &str) -> Result<ContainerMetadata> {
+        self.read(|containers| {
+            let Container { created_at, .. } = containers.container(name)?;
+            Ok(ContainerMetadata {
+                name: name.to_string(),
+                created_at: *created_at,
+            })
+        })
+    }
+
+    pub(crate) fn clear(&self, name: &str) -> Result<()> {
+        self.write(|containers| {
+            let container = containers.container_mut(name)?;
+            let size = container.size();
+            container.objects.clear();
+            containers.size -= size;
+            Ok(())
+        })
+    }
+
+    pub(crate) fn list_objects(&self, container: &str) -> Result<Vec<String>> {
+        self.read(|containers| {
+            Ok(containers
+                .container(container)?
+                .objects
+                .keys()
+                .cloned()
+                .collect())
+        })
+    }
+
+    /// Returns the bytes of the object in the range from `start` to `end`, exclusive
+    pub(crate) fn get_data(&self, id: &ObjectId, start: u64, end: u64) -> Result<Bytes> {
+        if end < start {
+            return Err("`end` must be greater than `start`".into());
+        }
+        self.read(|containers| {
+            let Object { data, .. } = containers.object(id)?;
+            let len = data.len();
+            let start = usize::try_from(start).unwrap_or(usize::MAX).min(len);
+            let end = usize::try_from(end).unwrap_or(usize::MAX).min(len);
+            Ok(data.slice(start..end))
+        })
+    }
+
+    pub(crate) fn write_data(&self, id: &ObjectId, data: Bytes) -> Result<()> {
+        let object = Object {
+            created_at: now(),
+            data,
+        };
+        self.write(|containers| containers.insert(self.quota, id, object))
+    }
+
+    pub(crate) fn has_object(&self, id: &ObjectId) -> Result<bool> {
+        self.read(|containers| {
+            Ok(containers
+                .container(&id.container)?
+                .objects
+                .contains_key(&id.object))
+        })
+    }
+
+    pub(crate) fn object_info(&self, id: &ObjectId) -> Result<ObjectMetadata> {
+        self.read(|containers| {
+            let Object { created_at, data } = containers.object(id)?;
+            Ok(ObjectMetadata {
+                name: id.object.clone(),
+                container: id.container.clone(),
+                created_at: *created_at,
+                size: data.len() as u64,
+            })
+        })
+    }
+
+    pub(crate) fn delete_objects(&self, container: &str, names: &[String]) -> Result<()> {
+        self.write(|containers| {
+            for name in names {
+                containers.remove(container, name)?;
+            }
+            Ok(())
+        })
+    }
+
+    pub(crate) fn copy_object(&self, src: &ObjectId, dest: &ObjectId) -> Result<()> {
+        self.write(|containers| {
+            let object = containers.object(src)?.clone();
+            containers.insert(self.quota, dest, object)
+        })
+    }
+
+    pub(crate) fn move_object(&self, src: &ObjectId, dest: &ObjectId) -> Result<()> {
+        self.write(|containers| {
+            // Ensure the destination container exists before removing the source
+            containers.container(&dest.container)?;
+            let object = containers.object(src)?.clone();
+            containers.remove(&src.container, &src.object)?;
+            containers.insert(self.quota, dest, object)
+        })
+    }
+}
+
+/// Returns the current time in seconds since the Unix epoch
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn id(container: &str, object: &str) -> ObjectId {
+        ObjectId {
+            container: container.into(),
+            object: object.into(),
+        }
+    }
+
+    #[test]
+    fn memory_blobstore() -> Result<()> {
+        let store = MemoryBlobstore::new(8);
+        assert!(store.write_data(&id("a", "x"), "data".into()).is_err());
+
+        store.create_container("a")?;
+        store.create_container("b")?;
+        assert!(store.container_exists("a")?);
+        assert!(!store.container_exists("c")?);
+
+        store.write_data(&id("a", "x"), "data".into())?;
+        assert_eq!(store.get_data(&id("a", "x"), 1, 3)?, "at");
+        assert_eq!(store.get_data(&id("a", "x"), 0, u64::MAX)?, "data");
+        assert_eq!(store.object_info(&id("a", "x"))?.size, 4);
+        assert!(store.get_data(&id("a", "y"), 0, 1).is_err());
+
+        store.copy_object(&id("a", "x"), &id("b", "x"))?;
+        assert_eq!(store.size(), 8);
+        // The quota applies to all containers of the blobstore
+        assert!(store.write_data(&id("a", "y"), "z".into()).is_err());
+        // Replacing an object only accounts for the difference in size
+        store.write_data(&id("a", "x"), "dat".into())?;
+        store.write_data(&id("a", "y"), "z".into())?;
+        assert_eq!(store.list_objects("a")?, ["x", "y"]);
+
+        store.move_object(&id("a", "y"), &id("b", "y"))?;
+        assert!(!store.has_object(&id("a", "y"))?);
+        assert!(store.has_object(&id("b", "y"))?);
+        assert!(store.move_object(&id("b", "y"), &id("c", "y")).is_err());
+        assert!(store.has_object(&id("b", "y"))?);
+
+        store.delete_objects("b", &["x".into(), "missing".into()])?;
+        assert_eq!(store.size(), 4);
+        store.clear("b")?;
+        assert_eq!(store.size(), 3);
+        store.delete_container("a")?;
+        assert_eq!(store.size(), 0);
+        assert!(store.list_objects("a").is_err());
+        Ok(())
+    }
+}
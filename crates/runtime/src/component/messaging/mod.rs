@@ -5,7 +5,7 @@ use tracing::{instrument, warn, Span};
 use tracing_opentelemetry::OpenTelemetrySpanExt as _;
 
 use crate::capability::wrpc;
-use crate::component::{new_store, Handler, Instance, WrpcServeEvent};
+use crate::component::{Handler, Instance, WrpcServeEvent};
 
 pub mod v0_2;
 pub mod v0_3;
@@ -23,23 +23,23 @@ where
     ) -> anyhow::Result<Result<(), String>> {
         // Set the parent of the current context to the span passed in
         Span::current().set_parent(cx.deref().context());
-        let mut store = new_store(&self.engine, self.handler.clone(), self.max_execution_time);
+        let mut instance = self
+            .acquire()
+            .await
+            .context("failed to instantiate `wasmcloud:messaging/handler`")?;
 
         // If wasmcloud:messaging@0.3.0 is enabled and we can instantiate the 0.3.0 bindings,
         // handle the message using 0.3.0. Otherwise, use the 0.2.0 bindings.
-        let res = if self.experimental_features.wasmcloud_messaging_v3 {
-            if let Ok(pre) = v0_3::bindings::MessagingHandlerPre::new(self.pre.clone()) {
-                v0_3::handle_message(pre, &mut store, msg).await
-            } else {
-                let pre = v0_2::bindings::MessagingHandlerOhTwoPre::new(self.pre.clone())
-                    .context("failed to pre-instantiate `wasmcloud:messaging/handler`")?;
-                v0_2::handle_message(pre, &mut store, msg).await
-            }
+        let res = if self.experimental_features.wasmcloud_messaging_v3
+            && v0_3::bindings::MessagingHandlerPre::new(self.pre.clone()).is_ok()
+        {
+            v0_3::handle_message(&mut instance.store, &instance.instance, msg).await
         } else {
-            let pre = v0_2::bindings::MessagingHandlerOhTwoPre::new(self.pre.clone())
-                .context("failed to pre-instantiate `wasmcloud:messaging/handler`")?;
-            v0_2::handle_message(pre, &mut store, msg).await
+            v0_2::handle_message(&mut instance.store, &instance.instance, msg).await
         };
+        if res.is_ok() {
+            instance.release();
+        }
 
         let success = res.is_ok();
         if let Err(err) =
@@ -25,8 +25,8 @@ pub mod bindings {
 
 #[instrument(level = "debug", skip_all)]
 pub(crate) async fn handle_message<H>(
-    pre: bindings::MessagingHandlerPre<Ctx<H>>,
     mut store: &mut Store<Ctx<H>>,
+    instance: &wasmtime::component::Instance,
     msg: wrpc::wasmcloud::messaging0_2_0::types::BrokerMessage,
 ) -> anyhow::Result<Result<(), String>>
 where
@@ -34,7 +34,8 @@ where
 {
     let call_handle_message = info_span!("call_handle_message");
     store.data_mut().parent_context = Some(call_handle_message.context());
-    let bindings = pre.instantiate_async(&mut store).await?;
+    let bindings = bindings::MessagingHandler::new(&mut store, instance)
+        .context("failed to get `wasmcloud:messaging/incoming-handler@0.3.0` exports")?;
     let msg = store
         .data_mut()
         .table
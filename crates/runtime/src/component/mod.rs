@@ -4,6 +4,9 @@ use core::ops::Deref;
 use core::pin::Pin;
 use core::time::Duration;
 
+use std::collections::HashMap;
+use std::sync::Arc;
+
 use anyhow::{ensure, Context as _};
 use futures::{Stream, TryStreamExt as _};
 use tokio::io::{AsyncRead, AsyncReadExt as _};
@@ -16,6 +19,7 @@ use wasi_preview1_component_adapter_provider::{
     WASI_SNAPSHOT_PREVIEW1_ADAPTER_NAME, WASI_SNAPSHOT_PREVIEW1_REACTOR_ADAPTER,
 };
 use wasmtime::component::{types, Linker, ResourceTable, ResourceTableError};
+use wasmtime::{CallHook, Trap, UpdateDeadline};
 use wasmtime_wasi::{WasiCtx, WasiCtxBuilder, WasiView};
 use wasmtime_wasi_http::WasiHttpCtx;
 use wrpc_runtime_wasmtime::{
@@ -30,22 +34,37 @@ pub use bus::Bus;
 pub use bus1_0_0::Bus as Bus1_0_0;
 pub use config::Config;
 pub use logging::Logging;
+pub use memory_blobstore::{EmbeddedBlobstore, MemoryBlobstore};
 pub use messaging::v0_2::Messaging as Messaging0_2;
 pub use messaging::v0_3::{
     Client as MessagingClient0_3, GuestMessage as MessagingGuestMessage0_3,
     HostMessage as MessagingHostMessage0_3, Messaging as Messaging0_3,
 };
+pub use pool::{InstancePoolConfig, InstancePoolStats};
+pub use scratch::{ScratchDirConfig, SCRATCH_DIR_GUEST_PATH};
 pub use secrets::Secrets;
+pub use validate::{
+    encoded_bytes, function_params, validate_params, Diagnostic, EncodedBytes, Schema, Validation,
+};
+
+use pool::{InstancePool, PooledInstance};
+use scratch::ScratchDir;
+use validate::ValidatingServe;
 
 pub(crate) mod blobstore;
 mod bus;
 mod bus1_0_0;
 mod config;
+mod deterministic;
 mod http;
 mod keyvalue;
 mod logging;
+mod memory_blobstore;
 pub(crate) mod messaging;
+mod pool;
+mod scratch;
 mod secrets;
+mod validate;
 
 /// Instance target, which is replaced in wRPC
 ///
@@ -104,6 +123,7 @@ pub trait Handler:
     wrpc_transport::Invoke<Context = Option<ReplacedInstanceTarget>>
     + Bus
     + Config
+    + EmbeddedBlobstore
     + Logging
     + Secrets
     + Messaging0_2
@@ -120,6 +140,7 @@ impl<
         T: wrpc_transport::Invoke<Context = Option<ReplacedInstanceTarget>>
             + Bus
             + Config
+            + EmbeddedBlobstore
             + Logging
             + Secrets
             + Messaging0_2
@@ -168,6 +189,23 @@ pub fn claims_token(wasm: impl AsRef<[u8]>) -> anyhow::Result<Option<jwt::Token<
     Ok(Some(claims))
 }
 
+/// Context of errors of components failing to instantiate
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InstantiationError;
+
+impl fmt::Display for InstantiationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to instantiate component")
+    }
+}
+
+/// Returns whether the failed invocation `err` was caused by the component trapping or failing
+/// to instantiate, as opposed to e.g. invalid parameters or an invocation denied by policy
+#[must_use]
+pub fn is_crash(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<Trap>().is_some() || err.downcast_ref::<InstantiationError>().is_some()
+}
+
 /// Pre-compiled component [Component], which is cheapily-[Cloneable](Clone)
 #[derive(Clone)]
 pub struct Component<H>
@@ -179,6 +217,10 @@ where
     instance_pre: wasmtime::component::InstancePre<Ctx<H>>,
     max_execution_time: Duration,
     experimental_features: Features,
+    validate_invocations: bool,
+    pool: Option<Arc<InstancePool<H>>>,
+    deterministic_seed: Option<u64>,
+    scratch_dir: Option<ScratchDirConfig>,
 }
 
 impl<H> Debug for Component<H>
@@ -190,6 +232,10 @@ where
             .field("claims", &self.claims)
             .field("runtime", &"wasmtime")
             .field("max_execution_time", &self.max_execution_time)
+            .field("validate_invocations", &self.validate_invocations)
+            .field("instance_pool", &self.instance_pool_stats())
+            .field("deterministic_seed", &self.deterministic_seed)
+            .field("scratch_dir", &self.scratch_dir)
             .finish_non_exhaustive()
     }
 }
@@ -198,12 +244,30 @@ fn new_store<H: Handler>(
     engine: &wasmtime::Engine,
     handler: H,
     max_execution_time: Duration,
+    deterministic_seed: Option<u64>,
+    scratch_dir: Option<&ScratchDirConfig>,
 ) -> wasmtime::Store<Ctx<H>> {
     let table = ResourceTable::new();
-    let wasi = WasiCtxBuilder::new()
-        .args(&["main.wasm"]) // TODO: Configure argv[0]
-        .inherit_stderr()
-        .build();
+    let mut wasi = WasiCtxBuilder::new();
+    wasi.args(&["main.wasm"]) // TODO: Configure argv[0]
+        .inherit_stderr();
+    if let Some(seed) = deterministic_seed {
+        deterministic::configure(&mut wasi, seed);
+    }
+    // The instance is created without a scratch directory if it cannot be set up, in which case
+    // filesystem operations of the component fail
+    let scratch = scratch_dir.and_then(|config| {
+        ScratchDir::create(config)
+            .and_then(|scratch| scratch.preopen(&mut wasi).map(|()| scratch))
+            .inspect_err(|err| {
+                warn!(
+                    ?err,
+                    "failed to set up component instance scratch directory, instance has no `/tmp`"
+                );
+            })
+            .ok()
+    });
+    let wasi = wasi.build();
 
     let mut store = wasmtime::Store::new(
         engine,
@@ -215,12 +279,52 @@ fn new_store<H: Handler>(
             shared_resources: SharedResourceTable::default(),
             timeout: max_execution_time,
             parent_context: None,
+            scratch,
+            epoch_ticks_left: 0,
         },
     );
-    store.set_epoch_deadline(max_execution_time.as_secs());
+    if store.data().scratch.is_some() {
+        // Writes to the scratch directory are host calls, after which the quota is checked
+        store.call_hook(|store, hook| {
+            if let (CallHook::ReturningFromHost, Some(scratch)) = (hook, &store.data().scratch) {
+                scratch.check_quota()?;
+            }
+            Ok(())
+        });
+        store.epoch_deadline_callback(|mut store| {
+            let ctx = store.data_mut();
+            if let Some(scratch) = &ctx.scratch {
+                scratch.check_quota()?;
+            }
+            ctx.epoch_ticks_left = ctx.epoch_ticks_left.saturating_sub(1);
+            if ctx.epoch_ticks_left == 0 {
+                return Err(Trap::Interrupt.into());
+            }
+            Ok(UpdateDeadline::Continue(1))
+        });
+    }
+    set_epoch_deadline(&mut store, max_execution_time);
     store
 }
 
+/// Sets the epoch deadline of `store` to `max_execution_time` from now.
+///
+/// Stores with a scratch directory are interrupted on every epoch tick instead, to check the
+/// scratch directory quota, until the execution time is exhausted.
+fn set_epoch_deadline<H: Handler>(
+    store: &mut wasmtime::Store<Ctx<H>>,
+    max_execution_time: Duration,
+) {
+    let ticks = max_execution_time.as_secs();
+    let ctx = store.data_mut();
+    if ctx.scratch.is_some() {
+        ctx.epoch_ticks_left = ticks;
+        store.set_epoch_deadline(1);
+    } else {
+        store.set_epoch_deadline(ticks);
+    }
+}
+
 /// Events sent by [`Component::serve_wrpc`]
 #[derive(Clone, Debug)]
 pub enum WrpcServeEvent<C> {
@@ -400,9 +504,37 @@ where
             instance_pre,
             max_execution_time: rt.max_execution_time,
             experimental_features: rt.experimental_features,
+            validate_invocations: rt.validate_invocations,
+            pool: None,
+            deterministic_seed: None,
+            scratch_dir: None,
         })
     }
 
+    /// Returns the parameter schemas of the functions the component imports from instances, by
+    /// instance and function name
+    #[must_use]
+    pub fn import_params(&self) -> HashMap<Box<str>, HashMap<Box<str>, Vec<(String, Schema)>>> {
+        let ty = self.instance_pre.component().component_type();
+        let mut imports = HashMap::new();
+        for (instance, item) in ty.imports(&self.engine) {
+            let types::ComponentItem::ComponentInstance(item) = item else {
+                continue;
+            };
+            let funcs: HashMap<_, _> = item
+                .exports(&self.engine)
+                .filter_map(|(func, item)| match item {
+                    types::ComponentItem::ComponentFunc(ty) => {
+                        Some((func.into(), function_params(&ty)))
+                    }
+                    _ => None,
+                })
+                .collect();
+            imports.insert(instance.into(), funcs);
+        }
+        imports
+    }
+
     /// Sets maximum execution time for functionality exported by this component.
     /// Values below 1 second will be interpreted as 1 second.
     #[instrument(level = "trace", skip_all)]
@@ -411,6 +543,45 @@ where
         self
     }
 
+    /// Configures a pool of warm instances to handle `wasi:http/incoming-handler` and
+    /// `wasmcloud:messaging/handler` invocations, instead of instantiating the component
+    /// on every invocation. Dynamically-served exports are always instantiated per invocation.
+    ///
+    /// A new, empty pool is created on every call and is shared by all clones of this [Component]
+    /// made afterwards. Pooling is disabled if `config` is `None`.
+    #[instrument(level = "trace", skip_all)]
+    pub fn set_instance_pool(&mut self, config: Option<InstancePoolConfig>) -> &mut Self {
+        self.pool = config.map(|config| Arc::new(InstancePool::new(config)));
+        self
+    }
+
+    /// Enables deterministic mode, in which `wasi:clocks` and `wasi:random` are virtualized with
+    /// deterministic implementations seeded with `seed`, so that every invocation observes the
+    /// same sequence of times and random values. Deterministic mode is disabled if `seed` is `None`.
+    ///
+    /// This is intended for reproducible test runs and must not be used in production, since
+    /// random values are predictable.
+    #[instrument(level = "trace", skip_all)]
+    pub fn set_deterministic_seed(&mut self, seed: Option<u64>) -> &mut Self {
+        self.deterministic_seed = seed;
+        self
+    }
+
+    /// Configures an ephemeral scratch directory for every instance of this [Component], which is
+    /// preopened at [`SCRATCH_DIR_GUEST_PATH`] and removed along with the instance. Executions
+    /// trap once the scratch directory exceeds the configured quota, which is checked after every
+    /// host call and epoch tick. Scratch directories are disabled if `config` is `None`.
+    #[instrument(level = "trace", skip_all)]
+    pub fn set_scratch_dir(&mut self, config: Option<ScratchDirConfig>) -> &mut Self {
+        self.scratch_dir = config;
+        self
+    }
+
+    /// Statistics of the instance pool of this [Component], if one is configured
+    pub fn instance_pool_stats(&self) -> Option<InstancePoolStats> {
+        self.pool.as_ref().map(|pool| pool.stats())
+    }
+
     /// Reads the WebAssembly binary asynchronously and calls [Component::new].
     ///
     /// # Errors
@@ -456,6 +627,9 @@ where
             max_execution_time: self.max_execution_time,
             events,
             experimental_features: self.experimental_features,
+            pool: self.pool.clone(),
+            deterministic_seed: self.deterministic_seed,
+            scratch_dir: self.scratch_dir.clone(),
         }
     }
 
@@ -465,6 +639,8 @@ where
     /// A [`WrpcServeEvent`] containing the incoming [`wrpc_transport::Serve::Context`] will be sent
     /// on completion of each invocation.
     /// The supplied [`Handler`] will be used to satisfy imports.
+    /// If invocation validation is enabled in the [Runtime], parameters of dynamically-served
+    /// functions are validated against the function signature before the function is called.
     #[instrument(level = "debug", skip_all)]
     pub async fn serve_wrpc<S>(
         &self,
@@ -477,8 +653,24 @@ where
         S::Context: Deref<Target = tracing::Span>,
     {
         let max_execution_time = self.max_execution_time;
+        let deterministic_seed = self.deterministic_seed;
         let mut invocations = vec![];
         let instance = self.instantiate(handler.clone(), events.clone());
+        if let Some(pool) = &self.pool {
+            if let Err(err) = pool
+                .prewarm(
+                    &self.engine,
+                    &self.instance_pre,
+                    handler.clone(),
+                    max_execution_time,
+                    self.deterministic_seed,
+                    self.scratch_dir.as_ref(),
+                )
+                .await
+            {
+                warn!(?err, "failed to pre-instantiate component instances");
+            }
+        }
         for (name, ty) in self
             .instance_pre
             .component()
@@ -516,13 +708,23 @@ where
                     let engine = self.engine.clone();
                     let handler = handler.clone();
                     let pre = self.instance_pre.clone();
+                    let scratch_dir = self.scratch_dir.clone();
                     debug!(?name, "serving root function");
+                    let srv = ValidatingServe::new(
+                        srv,
+                        self.validate_invocations.then(|| function_params(&ty)),
+                    );
                     let func = srv
                         .serve_function(
                             move || {
                                 let span = info_span!("call_instance_function");
-                                let mut store =
-                                    new_store(&engine, handler.clone(), max_execution_time);
+                                let mut store = new_store(
+                                    &engine,
+                                    handler.clone(),
+                                    max_execution_time,
+                                    deterministic_seed,
+                                    scratch_dir.as_ref(),
+                                );
                                 store.data_mut().parent_context = Some(span.context());
                                 store
                             },
@@ -576,7 +778,12 @@ where
                                 let engine = self.engine.clone();
                                 let handler = handler.clone();
                                 let pre = self.instance_pre.clone();
+                                let scratch_dir = self.scratch_dir.clone();
                                 debug!(?instance_name, ?name, "serving instance function");
+                                let srv = ValidatingServe::new(
+                                    srv,
+                                    self.validate_invocations.then(|| function_params(&ty)),
+                                );
                                 let func = srv
                                     .serve_function(
                                         move || {
@@ -585,6 +792,8 @@ where
                                                 &engine,
                                                 handler.clone(),
                                                 max_execution_time,
+                                                deterministic_seed,
+                                                scratch_dir.as_ref(),
                                             );
                                             store.data_mut().parent_context = Some(span.context());
                                             store
@@ -679,6 +888,9 @@ where
     max_execution_time: Duration,
     events: mpsc::Sender<WrpcServeEvent<C>>,
     experimental_features: Features,
+    pool: Option<Arc<InstancePool<H>>>,
+    deterministic_seed: Option<u64>,
+    scratch_dir: Option<ScratchDirConfig>,
 }
 
 impl<H, C> Clone for Instance<H, C>
@@ -693,6 +905,40 @@ where
             max_execution_time: self.max_execution_time,
             events: self.events.clone(),
             experimental_features: self.experimental_features,
+            pool: self.pool.clone(),
+            deterministic_seed: self.deterministic_seed,
+            scratch_dir: self.scratch_dir.clone(),
+        }
+    }
+}
+
+impl<H, C> Instance<H, C>
+where
+    H: Handler,
+{
+    /// Acquires a store and instance pair from the instance pool, if one is configured,
+    /// or instantiates the component in a new store otherwise
+    async fn acquire(&self) -> anyhow::Result<PooledInstance<H>> {
+        if let Some(pool) = &self.pool {
+            pool.acquire(
+                &self.engine,
+                &self.pre,
+                self.handler.clone(),
+                self.max_execution_time,
+                self.deterministic_seed,
+                self.scratch_dir.as_ref(),
+            )
+            .await
+        } else {
+            PooledInstance::instantiate(
+                &self.engine,
+                &self.pre,
+                self.handler.clone(),
+                self.max_execution_time,
+                self.deterministic_seed,
+                self.scratch_dir.as_ref(),
+            )
+            .await
         }
     }
 }
@@ -710,6 +956,11 @@ where
     shared_resources: SharedResourceTable,
     timeout: Duration,
     parent_context: Option<opentelemetry::Context>,
+    /// Scratch directory of the instance, removed once the store is dropped
+    scratch: Option<ScratchDir>,
+    /// Number of epoch ticks left until the execution times out, only used if the instance has
+    /// a scratch directory
+    epoch_ticks_left: u64,
 }
 
 impl<H: Handler> WasiView for Ctx<H> {
@@ -751,3 +1002,26 @@ impl<H: Handler> Ctx<H> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use anyhow::anyhow;
+
+    use super::*;
+
+    #[test]
+    fn crashes() {
+        assert!(is_crash(
+            &anyhow::Error::from(Trap::UnreachableCodeReached).context("failed to call function")
+        ));
+        assert!(is_crash(
+            &anyhow!("out of memory")
+                .context(InstantiationError)
+                .context("failed to acquire instance")
+        ));
+        assert!(!is_crash(&anyhow!("failed to decode parameters")));
+        assert!(!is_crash(
+            &anyhow!("policy denied").context("failed to handle invocation")
+        ));
+    }
+}
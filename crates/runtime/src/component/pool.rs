@@ -0,0 +1,361 @@
+use core::num::NonZeroUsize;
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use core::time::Duration;
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Instant;
+
+use anyhow::Context as _;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::{debug, debug_span, instrument, trace, Instrument as _};
+
+use super::{new_store, set_epoch_deadline, Ctx, Handler, InstantiationError, ScratchDirConfig};
+
+/// Configuration of a pool of warm component instances
+///
+/// Pooled instances are reused across invocations of `wasi:http/incoming-handler` and
+/// `wasmcloud:messaging/handler` exports, which means that any state kept by the component
+/// in its linear memory is preserved between invocations. Instances which trap are never
+/// returned to the pool.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InstancePoolConfig {
+    /// Minimum number of idle instances to pre-instantiate and keep warm
+    pub min_warm: usize,
+    /// Maximum number of instances handling invocations concurrently, unlimited if `None`
+    pub max_concurrent: Option<NonZeroUsize>,
+    /// Duration after which idle instances above [`Self::min_warm`] are dropped
+    pub idle_ttl: Duration,
+}
+
+/// Statistics of a pool of warm component instances
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct InstancePoolStats {
+    /// Number of idle instances ready to handle an invocation
+    pub warm: usize,
+    /// Number of instances currently handling an invocation
+    pub active: usize,
+    /// Total number of instances created by the pool
+    pub created: u64,
+    /// Total number of invocations handled by a reused instance
+    pub reused: u64,
+    /// Total number of idle instances dropped after exceeding the idle TTL
+    pub evicted: u64,
+}
+
+struct Idle<H>
+where
+    H: Handler,
+{
+    store: wasmtime::Store<Ctx<H>>,
+    instance: wasmtime::component::Instance,
+}
+
+/// Pool of pre-instantiated store and instance pairs
+pub(crate) struct InstancePool<H>
+where
+    H: Handler,
+{
+    config: InstancePoolConfig,
+    idle: Mutex<VecDeque<(Instant, Idle<H>)>>,
+    permits: Option<Arc<Semaphore>>,
+    active: AtomicUsize,
+    created: AtomicU64,
+    reused: AtomicU64,
+    evicted: AtomicU64,
+}
+
+/// Store and instance pair, which is returned to the pool on [`PooledInstance::release`]
+pub(crate) struct PooledInstance<H>
+where
+    H: Handler,
+{
+    pub(crate) store: wasmtime::Store<Ctx<H>>,
+    pub(crate) instance: wasmtime::component::Instance,
+    lease: Option<Lease<H>>,
+}
+
+impl<H> PooledInstance<H>
+where
+    H: Handler,
+{
+    /// Creates a new store and instantiates the component in it, bypassing the pool
+    pub(crate) async fn instantiate(
+        engine: &wasmtime::Engine,
+        pre: &wasmtime::component::InstancePre<Ctx<H>>,
+        handler: H,
+        max_execution_time: Duration,
+        deterministic_seed: Option<u64>,
+        scratch_dir: Option<&ScratchDirConfig>,
+    ) -> anyhow::Result<Self> {
+        let mut store = new_store(
+            engine,
+            handler,
+            max_execution_time,
+            deterministic_seed,
+            scratch_dir,
+        );
+        let instance = pre
+            .instantiate_async(&mut store)
+            .instrument(debug_span!("instantiate_async"))
+            .await
+            .context(InstantiationError)?;
+        Ok(Self {
+            store,
+            instance,
+            lease: None,
+        })
+    }
+
+    /// Returns the instance to the pool it was acquired from, if any.
+    ///
+    /// This must only be called after an invocation succeeded, instances which trapped
+    /// must be dropped instead.
+    pub(crate) fn release(self) {
+        let Self {
+            store,
+            instance,
+            lease,
+        } = self;
+        if let Some(lease) = lease {
+            lease.pool.put(Idle { store, instance });
+        }
+    }
+}
+
+/// Accounts for an active instance and holds the concurrency permit
+struct Lease<H>
+where
+    H: Handler,
+{
+    pool: Arc<InstancePool<H>>,
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl<H> Drop for Lease<H>
+where
+    H: Handler,
+{
+    fn drop(&mut self) {
+        self.pool.active.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Removes idle entries, which exceeded `ttl`, oldest first, while keeping at least `min`
+/// entries. Returns the number of entries removed.
+fn evict_idle<T>(
+    idle: &mut VecDeque<(Instant, T)>,
+    now: Instant,
+    min: usize,
+    ttl: Duration,
+) -> usize {
+    let mut evicted = 0;
+    while idle.len() > min {
+        match idle.front() {
+            Some((since, _)) if now.saturating_duration_since(*since) >= ttl => {
+                idle.pop_front();
+                evicted += 1;
+            }
+            _ => break,
+        }
+    }
+    evicted
+}
+
+impl<H> InstancePool<H>
+where
+    H: Handler,
+{
+    pub(crate) fn new(config: InstancePoolConfig) -> Self {
+        Self {
+            config,
+            idle: Mutex::default(),
+            permits: config
+                .max_concurrent
+                .map(|max| Arc::new(Semaphore::new(max.get().min(Semaphore::MAX_PERMITS)))),
+            active: AtomicUsize::default(),
+            created: AtomicU64::default(),
+            reused: AtomicU64::default(),
+            evicted: AtomicU64::default(),
+        }
+    }
+
+    /// Returns the current [`InstancePoolStats`]
+    pub(crate) fn stats(&self) -> InstancePoolStats {
+        let warm = self
+            .idle
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len();
+        InstancePoolStats {
+            warm,
+            active: self.active.load(Ordering::Relaxed),
+            created: self.created.load(Ordering::Relaxed),
+            reused: self.reused.load(Ordering::Relaxed),
+            evicted: self.evicted.load(Ordering::Relaxed),
+        }
+    }
+
+    fn evict(&self, idle: &mut VecDeque<(Instant, Idle<H>)>) {
+        let n = evict_idle(
+            idle,
+            Instant::now(),
+            self.config.min_warm,
+            self.config.idle_ttl,
+        );
+        if n > 0 {
+            trace!(evicted = n, "evicted idle component instances");
+            self.evicted.fetch_add(n as u64, Ordering::Relaxed);
+        }
+    }
+
+    fn put(&self, instance: Idle<H>) {
+        let mut idle = self.idle.lock().unwrap_or_else(PoisonError::into_inner);
+        idle.push_back((Instant::now(), instance));
+        self.evict(&mut idle);
+    }
+
+    /// Pre-instantiates instances until at least [`InstancePoolConfig::min_warm`] are idle
+    #[instrument(level = "debug", skip_all)]
+    pub(crate) async fn prewarm(
+        &self,
+        engine: &wasmtime::Engine,
+        pre: &wasmtime::component::InstancePre<Ctx<H>>,
+        handler: H,
+        max_execution_time: Duration,
+        deterministic_seed: Option<u64>,
+        scratch_dir: Option<&ScratchDirConfig>,
+    ) -> anyhow::Result<()> {
+        loop {
+            let warm = self
+                .idle
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .len();
+            if warm >= self.config.min_warm {
+                debug!(warm, "component instance pool is warm");
+                return Ok(());
+            }
+            let PooledInstance {
+                store, instance, ..
+            } = PooledInstance::instantiate(
+                engine,
+                pre,
+                handler.clone(),
+                max_execution_time,
+                deterministic_seed,
+                scratch_dir,
+            )
+            .await?;
+            self.created.fetch_add(1, Ordering::Relaxed);
+            self.put(Idle { store, instance });
+        }
+    }
+
+    /// Acquires an idle instance from the pool or instantiates a new one if none are idle.
+    ///
+    /// Waits for an active instance to be released if [`InstancePoolConfig::max_concurrent`]
+    /// instances are active.
+    #[instrument(level = "debug", skip_all)]
+    pub(crate) async fn acquire(
+        self: &Arc<Self>,
+        engine: &wasmtime::Engine,
+        pre: &wasmtime::component::InstancePre<Ctx<H>>,
+        handler: H,
+        max_execution_time: Duration,
+        deterministic_seed: Option<u64>,
+        scratch_dir: Option<&ScratchDirConfig>,
+    ) -> anyhow::Result<PooledInstance<H>> {
+        let permit = if let Some(permits) = &self.permits {
+            let permit = Arc::clone(permits)
+                .acquire_owned()
+                .instrument(debug_span!("acquire_permit"))
+                .await
+                .context("failed to acquire component instance pool permit")?;
+            Some(permit)
+        } else {
+            None
+        };
+        self.active.fetch_add(1, Ordering::Relaxed);
+        let lease = Lease {
+            pool: Arc::clone(self),
+            _permit: permit,
+        };
+        let idle = {
+            let mut idle = self.idle.lock().unwrap_or_else(PoisonError::into_inner);
+            self.evict(&mut idle);
+            // Reuse the most recently released instance, which is the most likely to be hot
+            idle.pop_back()
+        };
+        if let Some((
+            _,
+            Idle {
+                mut store,
+                instance,
+            },
+        )) = idle
+        {
+            trace!("reusing idle component instance");
+            self.reused.fetch_add(1, Ordering::Relaxed);
+            let ctx = store.data_mut();
+            ctx.handler = handler;
+            ctx.timeout = max_execution_time;
+            ctx.parent_context = None;
+            set_epoch_deadline(&mut store, max_execution_time);
+            return Ok(PooledInstance {
+                store,
+                instance,
+                lease: Some(lease),
+            });
+        }
+        trace!("no idle component instance available, instantiating");
+        let PooledInstance {
+            store, instance, ..
+        } = PooledInstance::instantiate(
+            engine,
+            pre,
+            handler,
+            max_execution_time,
+            deterministic_seed,
+            scratch_dir,
+        )
+        .await?;
+        self.created.fetch_add(1, Ordering::Relaxed);
+        Ok(PooledInstance {
+            store,
+            instance,
+            lease: Some(lease),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::VecDeque;
+    use std::time::{Duration, Instant};
+
+    use super::evict_idle;
+
+    #[test]
+    fn test_evict_idle() {
+        let now = Instant::now();
+        let ttl = Duration::from_secs(10);
+        let mut idle: VecDeque<_> = [
+            (now - Duration::from_secs(30), 1),
+            (now - Duration::from_secs(20), 2),
+            (now - Duration::from_secs(5), 3),
+            (now, 4),
+        ]
+        .into();
+
+        // Expired entries are evicted oldest first, but never below the minimum
+        assert_eq!(evict_idle(&mut idle, now, 3, ttl), 1);
+        assert_eq!(idle.iter().map(|(_, v)| *v).collect::<Vec<_>>(), [2, 3, 4]);
+
+        // Entries within the TTL are kept
+        assert_eq!(evict_idle(&mut idle, now, 0, ttl), 1);
+        assert_eq!(idle.iter().map(|(_, v)| *v).collect::<Vec<_>>(), [3, 4]);
+        assert_eq!(evict_idle(&mut idle, now + ttl, 0, ttl), 2);
+        assert!(idle.is_empty());
+    }
+}
@@ -0,0 +1,199 @@
+//! Ephemeral scratch directories of component instances
+//!
+//! Every instance of a component with a scratch directory configured gets its own, empty
+//! directory preopened at [`SCRATCH_DIR_GUEST_PATH`] via `wasi:filesystem`. The directory is
+//! created in [`ScratchDirConfig::root`] along with the instance and removed once the instance is
+//! dropped, so components can process temporary files without access to real host paths.
+//!
+//! The quota is checked whenever a host call of the instance returns, which includes every write
+//! to the directory, and on every epoch tick. The execution traps once the directory exceeds
+//! [`ScratchDirConfig::quota`]. Checks compare the quota against the size of the directory, which
+//! is measured in a blocking task after every check, so a component may briefly exceed the quota
+//! by as much as it writes until the measurement completes.
+
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{bail, Context as _};
+use tempfile::TempDir;
+use tracing::warn;
+use wasmtime_wasi::{DirPerms, FilePerms, WasiCtxBuilder};
+
+/// Path the scratch directory is preopened at in the component
+pub const SCRATCH_DIR_GUEST_PATH: &str = "/tmp";
+
+/// Configuration of the scratch directories of component instances
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScratchDirConfig {
+    /// Host directory, in which the scratch directories of instances are created
+    pub root: PathBuf,
+    /// Maximum total size in bytes of the files in the scratch directory of an instance
+    pub quota: u64,
+}
+
+/// Scratch directory of a single component instance, which is removed on drop
+#[derive(Debug)]
+pub(crate) struct ScratchDir {
+    dir: Arc<TempDir>,
+    quota: u64,
+    /// Size of the directory when it was last measured
+    usage: Arc<AtomicU64>,
+    /// Whether the directory is being measured
+    measuring: Arc<AtomicBool>,
+}
+
+impl ScratchDir {
+    /// Creates a new, empty scratch directory in [`ScratchDirConfig::root`]
+    pub(crate) fn create(
+        ScratchDirConfig { root, quota }: &ScratchDirConfig,
+    ) -> anyhow::Result<Self> {
+        fs::create_dir_all(root).with_context(|| {
+            format!(
+                "failed to create scratch directory root `{}`",
+                root.display()
+            )
+        })?;
+        let dir = tempfile::Builder::new()
+            .prefix("instance-")
+            .tempdir_in(root)
+            .context("failed to create scratch directory")?;
+        Ok(Self {
+            dir: Arc::new(dir),
+            quota: *quota,
+            usage: Arc::default(),
+            measuring: Arc::default(),
+        })
+    }
+
+    /// Preopens the scratch directory at [`SCRATCH_DIR_GUEST_PATH`]
+    pub(crate) fn preopen(&self, builder: &mut WasiCtxBuilder) -> anyhow::Result<()> {
+        builder
+            .preopened_dir(
+                self.dir.path(),
+                SCRATCH_DIR_GUEST_PATH,
+                DirPerms::all(),
+                FilePerms::all(),
+            )
+            .context("failed to preopen scratch directory")?;
+        Ok(())
+    }
+
+    /// Fails if the last measured size of the scratch directory exceeds the quota, measuring it
+    /// again in the background otherwise
+    pub(crate) fn check_quota(&self) -> anyhow::Result<()> {
+        let usage = self.usage.load(Ordering::Relaxed);
+        if usage > self.quota {
+            bail!(
+                "scratch directory size of {usage} bytes exceeds quota of {} bytes",
+                self.quota
+            );
+        }
+        self.measure();
+        Ok(())
+    }
+
+    /// Measures the size of the scratch directory in a blocking task, unless it is already being
+    /// measured. Failed measurements are logged and keep the last measured size
+    fn measure(&self) {
+        if self.measuring.swap(true, Ordering::Acquire) {
+            return;
+        }
+        let Ok(rt) = tokio::runtime::Handle::try_current() else {
+            self.measuring.store(false, Ordering::Relaxed);
+            return;
+        };
+        let dir = Arc::clone(&self.dir);
+        let usage = Arc::clone(&self.usage);
+        let measuring = Arc::clone(&self.measuring);
+        rt.spawn_blocking(move || {
+            match dir_size(dir.path()) {
+                Ok(size) => usage.store(size, Ordering::Relaxed),
+                Err(err) => warn!(?err, "failed to measure scratch directory size"),
+            }
+            measuring.store(false, Ordering::Release);
+        });
+    }
+}
+
+/// Returns the total size of the files in `path` and its subdirectories, without following
+/// symbolic links
+fn dir_size(path: &Path) -> io::Result<u64> {
+    let mut size = 0u64;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let meta = entry.metadata()?;
+        if meta.is_dir() {
+            size = size.saturating_add(dir_size(&entry.path())?);
+        } else {
+            size = size.saturating_add(meta.len());
+        }
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn scratch_dir_quota_and_cleanup() -> anyhow::Result<()> {
+        let root = tempfile::tempdir()?;
+        let config = ScratchDirConfig {
+            root: root.path().join("scratch"),
+            quota: 16,
+        };
+        let scratch = ScratchDir::create(&config)?;
+        let path = scratch.dir.path().to_path_buf();
+        assert!(path.starts_with(&config.root));
+
+        fs::create_dir(path.join("nested"))?;
+        fs::write(path.join("nested").join("a"), [0; 10])?;
+        scratch.check_quota()?;
+        wait_for_measurement(&scratch).await;
+        assert_eq!(scratch.usage.load(Ordering::Relaxed), 10);
+        fs::write(path.join("b"), [0; 10])?;
+        scratch.check_quota()?;
+        wait_for_measurement(&scratch).await;
+        assert!(scratch.check_quota().is_err());
+
+        // Every instance gets its own directory
+        let other = ScratchDir::create(&config)?;
+        assert_ne!(other.dir.path(), path);
+        other.check_quota()?;
+
+        drop(scratch);
+        assert!(!path.exists());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn scratch_dir_measurement_errors() -> anyhow::Result<()> {
+        let root = tempfile::tempdir()?;
+        let scratch = ScratchDir::create(&ScratchDirConfig {
+            root: root.path().to_path_buf(),
+            quota: 16,
+        })?;
+        fs::write(scratch.dir.path().join("a"), [0; 10])?;
+        scratch.check_quota()?;
+        wait_for_measurement(&scratch).await;
+
+        // Failing to measure the directory keeps the last measured size
+        fs::remove_dir_all(scratch.dir.path())?;
+        scratch.check_quota()?;
+        wait_for_measurement(&scratch).await;
+        assert_eq!(scratch.usage.load(Ordering::Relaxed), 10);
+        scratch.check_quota()?;
+        Ok(())
+    }
+
+    /// Wait until the scratch directory is no longer being measured
+    async fn wait_for_measurement(scratch: &ScratchDir) {
+        while scratch.measuring.load(Ordering::Acquire) {
+            tokio::task::yield_now().await;
+        }
+    }
+}
@@ -0,0 +1,813 @@
+//! Validation of wRPC invocation parameters against the WIT types of the invoked function
+//!
+//! When enabled via [`RuntimeBuilder::validate_invocations`](crate::RuntimeBuilder::validate_invocations),
+//! the parameters of each invocation of a dynamically-served export are buffered and checked
+//! against the function signature before the component is instantiated. Encoding mismatches are
+//! reported with the path of the offending value, the expected type and the byte offset at which
+//! decoding failed, which is far more actionable than a decoding error deep in the runtime.
+//!
+//! Each read re-validates the buffered parameters from the start, so this is intended as a
+//! debugging aid and not for production use.
+
+use core::fmt;
+use core::ops::Range;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use std::sync::Arc;
+
+use anyhow::{bail, Context as _};
+use bytes::{Bytes, BytesMut};
+use futures::{Stream, TryStreamExt as _};
+use tokio::io::{AsyncRead, AsyncReadExt as _, ReadBuf};
+use tracing::{trace, warn};
+use wasmtime::component::types;
+
+/// Shape of a WIT value, as encoded by wRPC
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Schema {
+    /// `bool`
+    Bool,
+    /// `u8`
+    U8,
+    /// `u16`
+    U16,
+    /// `u32`
+    U32,
+    /// `u64`
+    U64,
+    /// `s8`
+    S8,
+    /// `s16`
+    S16,
+    /// `s32`
+    S32,
+    /// `s64`
+    S64,
+    /// `f32`
+    F32,
+    /// `f64`
+    F64,
+    /// `char`
+    Char,
+    /// `string`
+    String,
+    /// `list<T>`
+    List(Box<Schema>),
+    /// `record`
+    Record(Vec<(String, Schema)>),
+    /// `tuple<...>`
+    Tuple(Vec<Schema>),
+    /// `variant`
+    Variant(Vec<(String, Option<Schema>)>),
+    /// `enum`
+    Enum(Vec<String>),
+    /// `option<T>`
+    Option(Box<Schema>),
+    /// `result<T, E>`
+    Result {
+        /// Type of the `ok` payload, if any
+        ok: Option<Box<Schema>>,
+        /// Type of the `err` payload, if any
+        err: Option<Box<Schema>>,
+    },
+    /// `flags`
+    Flags(Vec<String>),
+    /// `own<T>` or `borrow<T>`, encoded as an opaque byte string
+    Resource,
+}
+
+impl From<types::Type> for Schema {
+    fn from(ty: types::Type) -> Self {
+        match ty {
+            types::Type::Bool => Self::Bool,
+            types::Type::U8 => Self::U8,
+            types::Type::U16 => Self::U16,
+            types::Type::U32 => Self::U32,
+            types::Type::U64 => Self::U64,
+            types::Type::S8 => Self::S8,
+            types::Type::S16 => Self::S16,
+            types::Type::S32 => Self::S32,
+            types::Type::S64 => Self::S64,
+            types::Type::Float32 => Self::F32,
+            types::Type::Float64 => Self::F64,
+            types::Type::Char => Self::Char,
+            types::Type::String => Self::String,
+            types::Type::List(ty) => Self::List(Box::new(ty.ty().into())),
+            types::Type::Record(ty) => Self::Record(
+                ty.fields()
+                    .map(|types::Field { name, ty }| (name.to_string(), ty.into()))
+                    .collect(),
+            ),
+            types::Type::Tuple(ty) => Self::Tuple(ty.types().map(Into::into).collect()),
+            types::Type::Variant(ty) => Self::Variant(
+                ty.cases()
+                    .map(|types::Case { name, ty }| (name.to_string(), ty.map(Into::into)))
+                    .collect(),
+            ),
+            types::Type::Enum(ty) => Self::Enum(ty.names().map(ToString::to_string).collect()),
+            types::Type::Option(ty) => Self::Option(Box::new(ty.ty().into())),
+            types::Type::Result(ty) => Self::Result {
+                ok: ty.ok().map(|ty| Box::new(ty.into())),
+                err: ty.err().map(|ty| Box::new(ty.into())),
+            },
+            types::Type::Flags(ty) => Self::Flags(ty.names().map(ToString::to_string).collect()),
+            types::Type::Own(_) | types::Type::Borrow(_) => Self::Resource,
+        }
+    }
+}
+
+impl fmt::Display for Schema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bool => f.write_str("bool"),
+            Self::U8 => f.write_str("u8"),
+            Self::U16 => f.write_str("u16"),
+            Self::U32 => f.write_str("u32"),
+            Self::U64 => f.write_str("u64"),
+            Self::S8 => f.write_str("s8"),
+            Self::S16 => f.write_str("s16"),
+            Self::S32 => f.write_str("s32"),
+            Self::S64 => f.write_str("s64"),
+            Self::F32 => f.write_str("f32"),
+            Self::F64 => f.write_str("f64"),
+            Self::Char => f.write_str("char"),
+            Self::String => f.write_str("string"),
+            Self::List(ty) => write!(f, "list<{ty}>"),
+            Self::Record(fields) => {
+                f.write_str("record {")?;
+                for (i, (name, _)) in fields.iter().enumerate() {
+                    let sep = if i == 0 { " " } else { ", " };
+                    write!(f, "{sep}{name}")?;
+                }
+                f.write_str(" }")
+            }
+            Self::Tuple(types) => {
+                f.write_str("tuple<")?;
+                for (i, ty) in types.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{ty}")?;
+                }
+                f.write_str(">")
+            }
+            Self::Variant(cases) => {
+                f.write_str("variant {")?;
+                for (i, (name, _)) in cases.iter().enumerate() {
+                    let sep = if i == 0 { " " } else { ", " };
+                    write!(f, "{sep}{name}")?;
+                }
+                f.write_str(" }")
+            }
+            Self::Enum(names) => write!(f, "enum {{ {} }}", names.join(", ")),
+            Self::Option(ty) => write!(f, "option<{ty}>"),
+            Self::Result { ok, err } => match (ok, err) {
+                (None, None) => f.write_str("result"),
+                (Some(ok), None) => write!(f, "result<{ok}>"),
+                (None, Some(err)) => write!(f, "result<_, {err}>"),
+                (Some(ok), Some(err)) => write!(f, "result<{ok}, {err}>"),
+            },
+            Self::Flags(names) => write!(f, "flags {{ {} }}", names.join(", ")),
+            Self::Resource => f.write_str("resource"),
+        }
+    }
+}
+
+/// Returns the named parameter schemas of a function
+#[must_use]
+pub fn function_params(ty: &types::ComponentFunc) -> Vec<(String, Schema)> {
+    ty.params()
+        .map(|(name, ty)| (name.to_string(), ty.into()))
+        .collect()
+}
+
+/// Location and cause of a parameter encoding problem
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// Path of the value, e.g. `request.headers[2].0`
+    pub path: String,
+    /// Expected WIT type of the value
+    pub expected: String,
+    /// Byte offset in the parameter stream at which the value starts
+    pub offset: usize,
+    /// Description of the problem
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` (expected `{}`) at byte {}: {}",
+            self.path, self.expected, self.offset, self.message
+        )
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// Location of a `string` or `list<u8>` value within wRPC-encoded parameters
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncodedBytes {
+    /// Path of the value, e.g. `request.headers[2].0`
+    pub path: String,
+    /// Byte offset of the LEB128-encoded length, which the value starts with
+    pub offset: usize,
+    /// Range of the bytes of the value following its length
+    pub data: Range<usize>,
+}
+
+/// Outcome of validating a, possibly partial, parameter buffer
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Validation {
+    /// All parameters are valid and were encoded in the given number of bytes
+    Complete(usize),
+    /// The buffer ended before all parameters were decoded, more bytes are required to
+    /// complete the value described by the diagnostic
+    Incomplete(Diagnostic),
+}
+
+enum Error {
+    Incomplete(Diagnostic),
+    Invalid(Diagnostic),
+}
+
+struct Cursor<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let end = self.pos.checked_add(n)?;
+        let bytes = self.buf.get(self.pos..end)?;
+        self.pos = end;
+        Some(bytes)
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        self.take(1).and_then(|b| b.first().copied())
+    }
+}
+
+/// Validate wRPC-encoded `buf` against the parameters of a function.
+///
+/// # Errors
+///
+/// Returns a [`Diagnostic`] describing the first value, which is not encoded correctly
+pub fn validate_params(params: &[(String, Schema)], buf: &[u8]) -> Result<Validation, Diagnostic> {
+    let mut cur = Cursor { buf, pos: 0 };
+    for (name, schema) in params {
+        match validate(schema, &mut cur, name, &mut Vec::new()) {
+            Ok(()) => {}
+            Err(Error::Incomplete(diagnostic)) => return Ok(Validation::Incomplete(diagnostic)),
+            Err(Error::Invalid(diagnostic)) => return Err(diagnostic),
+        }
+    }
+    Ok(Validation::Complete(cur.pos))
+}
+
+/// Returns the locations of all `string` and `list<u8>` values of completely received,
+/// wRPC-encoded parameters `buf` of a function, in the order they are encoded in.
+///
+/// # Errors
+///
+/// Returns a [`Diagnostic`] describing the first value, which is not encoded correctly or not
+/// completely received
+pub fn encoded_bytes(
+    params: &[(String, Schema)],
+    buf: &[u8],
+) -> Result<Vec<EncodedBytes>, Diagnostic> {
+    let mut cur = Cursor { buf, pos: 0 };
+    let mut values = Vec::new();
+    for (name, schema) in params {
+        match validate(schema, &mut cur, name, &mut values) {
+            Ok(()) => {}
+            Err(Error::Incomplete(diagnostic) | Error::Invalid(diagnostic)) => {
+                return Err(diagnostic)
+            }
+        }
+    }
+    Ok(values)
+}
+
+/// Validate the value of `schema` at `cur`, appending the locations of the `string` and
+/// `list<u8>` values within it to `values`
+fn validate(
+    schema: &Schema,
+    cur: &mut Cursor<'_>,
+    path: &str,
+    values: &mut Vec<EncodedBytes>,
+) -> Result<(), Error> {
+    let offset = cur.pos;
+    let diagnostic = |message: String| Diagnostic {
+        path: path.to_string(),
+        expected: schema.to_string(),
+        offset,
+        message,
+    };
+    let incomplete = || Error::Incomplete(diagnostic("unexpected end of parameters".into()));
+    let invalid = |message: String| Error::Invalid(diagnostic(message));
+    match schema {
+        Schema::Bool => match cur.byte().ok_or_else(incomplete)? {
+            0 | 1 => Ok(()),
+            b => Err(invalid(format!("invalid boolean value `{b}`"))),
+        },
+        Schema::U8 | Schema::S8 => cur.take(1).map(|_| ()).ok_or_else(incomplete),
+        Schema::U16 => read_unsigned(cur, 16)
+            .map(|_| ())
+            .map_err(|e| e.into_error(&invalid, incomplete)),
+        Schema::U32 => read_unsigned(cur, 32)
+            .map(|_| ())
+            .map_err(|e| e.into_error(&invalid, incomplete)),
+        Schema::U64 => read_unsigned(cur, 64)
+            .map(|_| ())
+            .map_err(|e| e.into_error(&invalid, incomplete)),
+        Schema::S16 => read_signed(cur, 16).map_err(|e| e.into_error(&invalid, incomplete)),
+        Schema::S32 => read_signed(cur, 32).map_err(|e| e.into_error(&invalid, incomplete)),
+        Schema::S64 => read_signed(cur, 64).map_err(|e| e.into_error(&invalid, incomplete)),
+        Schema::F32 => cur.take(4).map(|_| ()).ok_or_else(incomplete),
+        Schema::F64 => cur.take(8).map(|_| ()).ok_or_else(incomplete),
+        Schema::Char => {
+            let first = cur.byte().ok_or_else(incomplete)?;
+            let len = match first {
+                0x00..=0x7f => return Ok(()),
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf7 => 4,
+                _ => {
+                    return Err(invalid(format!(
+                        "invalid UTF-8 leading byte `{first:#04x}`"
+                    )))
+                }
+            };
+            let rest = cur.take(len - 1).ok_or_else(incomplete)?;
+            let mut bytes = vec![first];
+            bytes.extend_from_slice(rest);
+            std::str::from_utf8(&bytes)
+                .map(|_| ())
+                .map_err(|err| invalid(format!("invalid UTF-8 encoded character: {err}")))
+        }
+        Schema::String => {
+            let len = read_len(cur).map_err(|e| e.into_error(&invalid, incomplete))?;
+            let start = cur.pos;
+            let bytes = cur.take(len).ok_or_else(incomplete)?;
+            std::str::from_utf8(bytes)
+                .map_err(|err| invalid(format!("invalid UTF-8 string: {err}")))?;
+            values.push(EncodedBytes {
+                path: path.to_string(),
+                offset,
+                data: start..cur.pos,
+            });
+            Ok(())
+        }
+        Schema::List(ty) => {
+            let len = read_len(cur).map_err(|e| e.into_error(&invalid, incomplete))?;
+            if **ty == Schema::U8 || **ty == Schema::S8 {
+                let start = cur.pos;
+                cur.take(len).ok_or_else(incomplete)?;
+                values.push(EncodedBytes {
+                    path: path.to_string(),
+                    offset,
+                    data: start..cur.pos,
+                });
+                return Ok(());
+            }
+            for i in 0..len {
+                validate(ty, cur, &format!("{path}[{i}]"), values)?;
+            }
+            Ok(())
+        }
+        Schema::Record(fields) => {
+            for (name, ty) in fields {
+                validate(ty, cur, &format!("{path}.{name}"), values)?;
+            }
+            Ok(())
+        }
+        Schema::Tuple(types) => {
+            for (i, ty) in types.iter().enumerate() {
+                validate(ty, cur, &format!("{path}.{i}"), values)?;
+            }
+            Ok(())
+        }
+        Schema::Variant(cases) => {
+            let disc = read_discriminant(cur).map_err(|e| e.into_error(&invalid, incomplete))?;
+            match cases.get(disc) {
+                Some((name, Some(ty))) => validate(ty, cur, &format!("{path}.{name}"), values),
+                Some((_, None)) => Ok(()),
+                None => Err(invalid(format!(
+                    "discriminant `{disc}` out of range, variant has {} cases",
+                    cases.len()
+                ))),
+            }
+        }
+        Schema::Enum(names) => {
+            let disc = read_discriminant(cur).map_err(|e| e.into_error(&invalid, incomplete))?;
+            if disc < names.len() {
+                Ok(())
+            } else {
+                Err(invalid(format!(
+                    "discriminant `{disc}` out of range, enum has {} cases",
+                    names.len()
+                )))
+            }
+        }
+        Schema::Option(ty) => match cur.byte().ok_or_else(incomplete)? {
+            0 => Ok(()),
+            1 => validate(ty, cur, &format!("{path}.some"), values),
+            b => Err(invalid(format!("invalid option discriminant `{b}`"))),
+        },
+        Schema::Result { ok, err } => match cur.byte().ok_or_else(incomplete)? {
+            0 => ok.as_ref().map_or(Ok(()), |ty| {
+                validate(ty, cur, &format!("{path}.ok"), values)
+            }),
+            1 => err.as_ref().map_or(Ok(()), |ty| {
+                validate(ty, cur, &format!("{path}.err"), values)
+            }),
+            b => Err(invalid(format!("invalid result discriminant `{b}`"))),
+        },
+        Schema::Flags(names) => {
+            let bytes = cur.take(names.len().div_ceil(8)).ok_or_else(incomplete)?;
+            for (i, b) in bytes.iter().enumerate() {
+                for bit in 0..8 {
+                    if b & (1 << bit) != 0 && i * 8 + bit >= names.len() {
+                        return Err(invalid(format!(
+                            "flag bit `{}` set, but flags only has {} members",
+                            i * 8 + bit,
+                            names.len()
+                        )));
+                    }
+                }
+            }
+            Ok(())
+        }
+        Schema::Resource => {
+            let len = read_len(cur).map_err(|e| e.into_error(&invalid, incomplete))?;
+            cur.take(len).map(|_| ()).ok_or_else(incomplete)
+        }
+    }
+}
+
+enum LebError {
+    Incomplete,
+    Invalid(String),
+}
+
+impl LebError {
+    fn into_error(
+        self,
+        invalid: &impl Fn(String) -> Error,
+        incomplete: impl FnOnce() -> Error,
+    ) -> Error {
+        match self {
+            Self::Incomplete => incomplete(),
+            Self::Invalid(message) => invalid(message),
+        }
+    }
+}
+
+/// Read an unsigned LEB128 integer of at most `bits` bits
+fn read_unsigned(cur: &mut Cursor<'_>, bits: u32) -> Result<u64, LebError> {
+    let max_len = bits.div_ceil(7);
+    let mut value: u128 = 0;
+    for i in 0..max_len {
+        let b = cur.byte().ok_or(LebError::Incomplete)?;
+        value |= u128::from(b & 0x7f) << (i * 7);
+        if b & 0x80 == 0 {
+            return u64::try_from(value)
+                .ok()
+                .filter(|value| bits == 64 || value >> bits == 0)
+                .ok_or_else(|| {
+                    LebError::Invalid(format!("LEB128 value does not fit in {bits} bits"))
+                });
+        }
+    }
+    Err(LebError::Invalid(format!(
+        "LEB128 value exceeds the maximum length of {max_len} bytes for {bits} bits"
+    )))
+}
+
+/// Read a signed LEB128 integer of at most `bits` bits
+fn read_signed(cur: &mut Cursor<'_>, bits: u32) -> Result<(), LebError> {
+    let max_len = bits.div_ceil(7);
+    let mut value: i128 = 0;
+    for i in 0..max_len {
+        let b = cur.byte().ok_or(LebError::Incomplete)?;
+        value |= i128::from(b & 0x7f) << (i * 7);
+        if b & 0x80 == 0 {
+            let shift = (i + 1) * 7;
+            if b & 0x40 != 0 {
+                value |= -1i128 << shift;
+            }
+            let min = -(1i128 << (bits - 1));
+            let max = (1i128 << (bits - 1)) - 1;
+            return if (min..=max).contains(&value) {
+                Ok(())
+            } else {
+                Err(LebError::Invalid(format!(
+                    "LEB128 value does not fit in {bits} bits"
+                )))
+            };
+        }
+    }
+    Err(LebError::Invalid(format!(
+        "LEB128 value exceeds the maximum length of {max_len} bytes for {bits} bits"
+    )))
+}
+
+/// Read the length of a string or list
+fn read_len(cur: &mut Cursor<'_>) -> Result<usize, LebError> {
+    let len = read_unsigned(cur, 32)?;
+    usize::try_from(len).map_err(|_| LebError::Invalid(format!("length `{len}` is too large")))
+}
+
+/// Read the discriminant of a variant or enum
+fn read_discriminant(cur: &mut Cursor<'_>) -> Result<usize, LebError> {
+    let disc = read_unsigned(cur, 32)?;
+    usize::try_from(disc)
+        .map_err(|_| LebError::Invalid(format!("discriminant `{disc}` is too large")))
+}
+
+/// [`wrpc_transport::Serve`], which validates invocation parameters before they are decoded
+pub(crate) struct ValidatingServe<'a, S> {
+    srv: &'a S,
+    params: Option<Arc<[(String, Schema)]>>,
+}
+
+impl<'a, S> ValidatingServe<'a, S> {
+    /// Wraps `srv`, validating parameters against `params`, if set. Invocations are passed
+    /// through unchanged otherwise.
+    pub(crate) fn new(srv: &'a S, params: Option<Vec<(String, Schema)>>) -> Self {
+        Self {
+            srv,
+            params: params.map(Into::into),
+        }
+    }
+}
+
+/// Incoming invocation stream, which yields the bytes buffered during validation first
+pub(crate) struct ValidatedIncoming<T> {
+    buffered: Bytes,
+    inner: T,
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for ValidatedIncoming<T> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        if !self.buffered.is_empty() {
+            let n = buf.remaining().min(self.buffered.len());
+            let chunk = self.buffered.split_to(n);
+            buf.put_slice(&chunk);
+            return Poll::Ready(Ok(()));
+        }
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<T: wrpc_transport::Index<T>> wrpc_transport::Index<Self> for ValidatedIncoming<T> {
+    fn index(&self, path: &[usize]) -> anyhow::Result<Self> {
+        let inner = self.inner.index(path)?;
+        Ok(Self {
+            buffered: Bytes::new(),
+            inner,
+        })
+    }
+}
+
+/// Buffer incoming parameters until they are completely received and valid
+async fn read_params(
+    params: &[(String, Schema)],
+    rx: &mut (impl AsyncRead + Unpin),
+) -> anyhow::Result<Bytes> {
+    let mut buf = BytesMut::new();
+    loop {
+        match validate_params(params, &buf)? {
+            Validation::Complete(n) => {
+                trace!(n, "invocation parameters are valid");
+                return Ok(buf.freeze());
+            }
+            Validation::Incomplete(diagnostic) => {
+                let n = rx
+                    .read_buf(&mut buf)
+                    .await
+                    .context("failed to read invocation parameters")?;
+                if n == 0 {
+                    return Err(diagnostic.into());
+                }
+            }
+        }
+    }
+}
+
+impl<S> wrpc_transport::Serve for ValidatingServe<'_, S>
+where
+    S: wrpc_transport::Serve,
+{
+    type Context = S::Context;
+    type Outgoing = S::Outgoing;
+    type Incoming = ValidatedIncoming<S::Incoming>;
+
+    async fn serve(
+        &self,
+        instance: &str,
+        func: &str,
+        paths: impl Into<Arc<[Box<[Option<usize>]>]>> + Send,
+    ) -> anyhow::Result<
+        impl Stream<Item = anyhow::Result<(Self::Context, Self::Outgoing, Self::Incoming)>>
+            + Send
+            + 'static,
+    > {
+        let invocations = self.srv.serve(instance, func, paths).await?;
+        let params = self.params.clone();
+        let name: Arc<str> = if instance.is_empty() {
+            func.into()
+        } else {
+            format!("{instance}#{func}").into()
+        };
+        Ok(invocations.and_then(move |(cx, tx, mut rx)| {
+            let params = params.clone();
+            let name = Arc::clone(&name);
+            async move {
+                let Some(params) = params else {
+                    return Ok((
+                        cx,
+                        tx,
+                        ValidatedIncoming {
+                            buffered: Bytes::new(),
+                            inner: rx,
+                        },
+                    ));
+                };
+                match read_params(&params, &mut rx).await {
+                    Ok(buffered) => Ok((
+                        cx,
+                        tx,
+                        ValidatedIncoming {
+                            buffered,
+                            inner: rx,
+                        },
+                    )),
+                    Err(err) => {
+                        if let Some(Diagnostic {
+                            path,
+                            expected,
+                            offset,
+                            message,
+                        }) = err.downcast_ref::<Diagnostic>()
+                        {
+                            warn!(
+                                func = %name,
+                                %path,
+                                %expected,
+                                offset,
+                                %message,
+                                "invalid invocation parameters"
+                            );
+                        }
+                        bail!("invalid parameters for `{name}`: {err:#}")
+                    }
+                }
+            }
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn params() -> Vec<(String, Schema)> {
+        vec![
+            (
+                "request".into(),
+                Schema::Record(vec![
+                    ("name".into(), Schema::String),
+                    ("count".into(), Schema::U32),
+                    (
+                        "tags".into(),
+                        Schema::List(Box::new(Schema::Option(Box::new(Schema::String)))),
+                    ),
+                ]),
+            ),
+            (
+                "mode".into(),
+                Schema::Variant(vec![
+                    ("fast".into(), None),
+                    ("slow".into(), Some(Schema::S64)),
+                ]),
+            ),
+        ]
+    }
+
+    #[test]
+    fn valid_params() {
+        let buf = [
+            2, b'h', b'i', // name
+            0xac, 0x02, // count = 300
+            2, 1, 1, b'a', 0, // tags = [some("a"), none]
+            1, 0x7f, // mode = slow(-1)
+        ];
+        assert_eq!(
+            validate_params(&params(), &buf),
+            Ok(Validation::Complete(buf.len()))
+        );
+    }
+
+    #[test]
+    fn incomplete_params() {
+        let buf = [2, b'h', b'i', 0xac];
+        let Ok(Validation::Incomplete(diagnostic)) = validate_params(&params(), &buf) else {
+            panic!("parameters should be incomplete")
+        };
+        assert_eq!(diagnostic.path, "request.count");
+        assert_eq!(diagnostic.expected, "u32");
+        assert_eq!(diagnostic.offset, 3);
+    }
+
+    #[test]
+    fn invalid_params() {
+        let buf = [2, b'h', b'i', 1, 1, 2, b'a'];
+        assert_eq!(
+            validate_params(&params(), &buf),
+            Err(Diagnostic {
+                path: "request.tags[0]".into(),
+                expected: "option<string>".into(),
+                offset: 5,
+                message: "invalid option discriminant `2`".into(),
+            })
+        );
+
+        let buf = [0, 0, 0, 2];
+        let diagnostic =
+            validate_params(&params(), &buf).expect_err("parameters should be invalid");
+        assert_eq!(diagnostic.path, "mode");
+        assert_eq!(
+            diagnostic.message,
+            "discriminant `2` out of range, variant has 2 cases"
+        );
+
+        let buf = [0, 0xff, 0xff, 0xff, 0xff, 0x1f];
+        let diagnostic =
+            validate_params(&params(), &buf).expect_err("parameters should be invalid");
+        assert_eq!(diagnostic.path, "request.count");
+        assert_eq!(diagnostic.message, "LEB128 value does not fit in 32 bits");
+
+        let buf = [1, 0xff];
+        let diagnostic =
+            validate_params(&params(), &buf).expect_err("parameters should be invalid");
+        assert_eq!(diagnostic.path, "request.name");
+        assert!(diagnostic.message.starts_with("invalid UTF-8 string"));
+    }
+
+    #[test]
+    fn locate_encoded_bytes() {
+        let buf = [
+            2, b'h', b'i', // name
+            0xac, 0x02, // count = 300
+            2, 1, 1, b'a', 0, // tags = [some("a"), none]
+            1, 0x7f, // mode = slow(-1)
+        ];
+        assert_eq!(
+            encoded_bytes(&params(), &buf),
+            Ok(vec![
+                EncodedBytes {
+                    path: "request.name".into(),
+                    offset: 0,
+                    data: 1..3,
+                },
+                EncodedBytes {
+                    path: "request.tags[0].some".into(),
+                    offset: 7,
+                    data: 8..9,
+                },
+            ])
+        );
+        let schema = [("body".to_string(), Schema::List(Box::new(Schema::U8)))];
+        assert_eq!(
+            encoded_bytes(&schema, &[3, 0xff, 0, 1]),
+            Ok(vec![EncodedBytes {
+                path: "body".into(),
+                offset: 0,
+                data: 1..4,
+            }])
+        );
+        // Incomplete parameters cannot be located
+        assert!(encoded_bytes(&params(), &buf[..4]).is_err());
+    }
+
+    #[test]
+    fn signed_range() {
+        let schema = [("x".to_string(), Schema::S16)];
+        // -32768
+        assert_eq!(
+            validate_params(&schema, &[0x80, 0x80, 0x7e]),
+            Ok(Validation::Complete(3))
+        );
+        // -32769
+        assert!(validate_params(&schema, &[0xff, 0xff, 0x7d]).is_err());
+    }
+}
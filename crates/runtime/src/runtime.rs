@@ -27,6 +27,7 @@ pub struct RuntimeBuilder {
     component_config: ComponentConfig,
     force_pooling_allocator: bool,
     experimental_features: Features,
+    validate_invocations: bool,
 }
 
 impl RuntimeBuilder {
@@ -49,6 +50,7 @@ impl RuntimeBuilder {
             component_config: ComponentConfig::default(),
             force_pooling_allocator: false,
             experimental_features: Features::default(),
+            validate_invocations: false,
         }
     }
 
@@ -117,6 +119,17 @@ impl RuntimeBuilder {
         }
     }
 
+    /// Validate the parameters of invocations of component exports against the WIT signature of
+    /// the invoked function before calling it, logging the path and expected type of incorrectly
+    /// encoded values. Intended for debugging, since parameters are buffered before dispatch.
+    #[must_use]
+    pub fn validate_invocations(self, validate_invocations: bool) -> Self {
+        Self {
+            validate_invocations,
+            ..self
+        }
+    }
+
     /// Turns this builder into a [`Runtime`]
     ///
     /// # Errors
@@ -192,6 +205,7 @@ impl RuntimeBuilder {
                 component_config: self.component_config,
                 max_execution_time: self.max_execution_time,
                 experimental_features: self.experimental_features,
+                validate_invocations: self.validate_invocations,
             },
             epoch,
         ))
@@ -213,6 +227,7 @@ pub struct Runtime {
     pub(crate) component_config: ComponentConfig,
     pub(crate) max_execution_time: Duration,
     pub(crate) experimental_features: Features,
+    pub(crate) validate_invocations: bool,
 }
 
 impl Debug for Runtime {
@@ -5,7 +5,7 @@ use heck::ToKebabCase;
 #[cfg(feature = "otel")]
 pub use opentelemetry::{
     global,
-    metrics::{Counter, Histogram, Meter},
+    metrics::{Counter, Histogram, Meter, ObservableGauge},
     InstrumentationScope, KeyValue,
 };
 use wasmcloud_core::logging::Level;
@@ -20,11 +20,15 @@ pub mod http;
 
 mod traces;
 
+pub use traces::set_log_level;
 #[cfg(feature = "otel")]
 pub use traces::FlushGuard;
 
 mod metrics;
 
+#[cfg(feature = "otel")]
+pub use metrics::{enable_prometheus_metrics, prometheus_registry, PrometheusRegistry};
+
 #[cfg(not(feature = "otel"))]
 pub fn configure_observability(
     _: &str,
@@ -56,7 +60,7 @@ pub fn configure_observability(
 ) -> anyhow::Result<(tracing::Dispatch, traces::FlushGuard)> {
     let normalized_service_name = service_name.to_kebab_case();
 
-    if otel_config.metrics_enabled() {
+    if otel_config.metrics_enabled() || metrics::prometheus_registry().is_some() {
         metrics::configure_metrics(&normalized_service_name, otel_config)?;
     }
 
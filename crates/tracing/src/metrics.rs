@@ -1,5 +1,268 @@
+#[cfg(feature = "otel")]
+use core::any::Any;
+#[cfg(feature = "otel")]
+use core::fmt::{Display, Write as _};
+
+#[cfg(feature = "otel")]
+use std::collections::HashMap;
+#[cfg(feature = "otel")]
+use std::sync::{Arc, Weak};
+
 #[cfg(feature = "otel")]
 use anyhow::Context;
+#[cfg(feature = "otel")]
+use once_cell::sync::OnceCell;
+#[cfg(feature = "otel")]
+use opentelemetry_sdk::metrics::data::{
+    DataPoint, Gauge, Histogram, HistogramDataPoint, Metric, ResourceMetrics, Sum,
+};
+#[cfg(feature = "otel")]
+use opentelemetry_sdk::metrics::reader::MetricReader;
+#[cfg(feature = "otel")]
+use opentelemetry_sdk::metrics::{
+    InstrumentKind, ManualReader, MetricResult, Pipeline, Temporality,
+};
+
+#[cfg(feature = "otel")]
+static PROMETHEUS_REGISTRY: OnceCell<PrometheusRegistry> = OnceCell::new();
+
+/// Registry of the metrics recorded by the process, which can be scraped in the Prometheus text
+/// exposition format.
+///
+/// Metrics are only recorded in the registry if it is enabled using [`enable_prometheus_metrics`]
+/// before observability is configured.
+#[cfg(feature = "otel")]
+#[derive(Clone, Debug)]
+pub struct PrometheusRegistry {
+    reader: Arc<ManualReader>,
+}
+
+#[cfg(feature = "otel")]
+impl PrometheusRegistry {
+    fn new() -> Self {
+        Self {
+            reader: Arc::new(ManualReader::builder().build()),
+        }
+    }
+
+    /// Collect the current value of all metrics and encode them in the Prometheus text
+    /// exposition format
+    pub fn encode(&self) -> anyhow::Result<String> {
+        let mut metrics = ResourceMetrics {
+            resource: opentelemetry_sdk::Resource::empty(),
+            scope_metrics: Vec::new(),
+        };
+        self.reader
+            .collect(&mut metrics)
+            .context("failed to collect metrics")?;
+        Ok(encode_prometheus(&metrics))
+    }
+}
+
+#[cfg(feature = "otel")]
+impl MetricReader for PrometheusRegistry {
+    fn register_pipeline(&self, pipeline: Weak<Pipeline>) {
+        self.reader.register_pipeline(pipeline);
+    }
+
+    fn collect(&self, rm: &mut ResourceMetrics) -> MetricResult<()> {
+        self.reader.collect(rm)
+    }
+
+    fn force_flush(&self) -> MetricResult<()> {
+        self.reader.force_flush()
+    }
+
+    fn shutdown(&self) -> MetricResult<()> {
+        self.reader.shutdown()
+    }
+
+    fn temporality(&self, kind: InstrumentKind) -> Temporality {
+        self.reader.temporality(kind)
+    }
+}
+
+/// Enable recording metrics in the [`PrometheusRegistry`] of the process. This must be called
+/// before observability is configured, metrics are recorded regardless of whether exporting
+/// metrics over OTLP is enabled.
+#[cfg(feature = "otel")]
+pub fn enable_prometheus_metrics() -> PrometheusRegistry {
+    PROMETHEUS_REGISTRY
+        .get_or_init(PrometheusRegistry::new)
+        .clone()
+}
+
+/// Returns the [`PrometheusRegistry`] of the process, if enabled
+#[cfg(feature = "otel")]
+pub fn prometheus_registry() -> Option<PrometheusRegistry> {
+    PROMETHEUS_REGISTRY.get().cloned()
+}
+
+/// Convert an OTEL metric or attribute name to a valid Prometheus name
+#[cfg(feature = "otel")]
+fn prometheus_name(name: &str) -> String {
+    let mut name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        name.insert(0, '_');
+    }
+    name
+}
+
+#[cfg(feature = "otel")]
+fn prometheus_labels<'a>(
+    attributes: impl IntoIterator<Item = &'a opentelemetry::KeyValue>,
+    extra: Option<(&str, &str)>,
+) -> String {
+    let labels: Vec<_> = attributes
+        .into_iter()
+        .map(|kv| (prometheus_name(kv.key.as_str()), kv.value.to_string()))
+        .chain(extra.map(|(k, v)| (k.to_string(), v.to_string())))
+        .map(|(k, v)| {
+            let v = v
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{k}=\"{v}\"")
+        })
+        .collect();
+    if labels.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", labels.join(","))
+    }
+}
+
+/// Metric families encoded in the Prometheus text exposition format. Metrics with the same
+/// Prometheus name, e.g. recorded by multiple meters, are encoded as a single family with one
+/// `HELP` and `TYPE` line followed by all samples
+#[cfg(feature = "otel")]
+#[derive(Default)]
+struct PrometheusEncoder {
+    families: Vec<(String, String)>,
+    names: HashMap<String, usize>,
+}
+
+#[cfg(feature = "otel")]
+impl PrometheusEncoder {
+    /// Returns the samples of the family `name`, adding the family if it was not encoded yet
+    fn family(&mut self, name: &str, metric: &Metric, ty: &str) -> &mut String {
+        let Self { families, names } = self;
+        let i = *names.entry(name.to_string()).or_insert_with(|| {
+            let mut header = String::new();
+            if !metric.description.is_empty() {
+                let _ = writeln!(
+                    header,
+                    "# HELP {name} {}",
+                    metric
+                        .description
+                        .replace('\\', "\\\\")
+                        .replace('\n', "\\n")
+                );
+            }
+            let _ = writeln!(header, "# TYPE {name} {ty}");
+            families.push((header, String::new()));
+            families.len() - 1
+        });
+        &mut families[i].1
+    }
+
+    fn encode_points<T: Display>(
+        &mut self,
+        name: &str,
+        metric: &Metric,
+        ty: &str,
+        points: &[DataPoint<T>],
+    ) {
+        let out = self.family(name, metric, ty);
+        for point in points {
+            let labels = prometheus_labels(&point.attributes, None);
+            let _ = writeln!(out, "{name}{labels} {}", point.value);
+        }
+    }
+
+    fn encode_histogram<T: Display>(
+        &mut self,
+        name: &str,
+        metric: &Metric,
+        points: &[HistogramDataPoint<T>],
+    ) {
+        let out = self.family(name, metric, "histogram");
+        for point in points {
+            let mut cumulative = 0;
+            for (bound, count) in point.bounds.iter().zip(&point.bucket_counts) {
+                cumulative += count;
+                let labels = prometheus_labels(&point.attributes, Some(("le", &bound.to_string())));
+                let _ = writeln!(out, "{name}_bucket{labels} {cumulative}");
+            }
+            let labels = prometheus_labels(&point.attributes, Some(("le", "+Inf")));
+            let _ = writeln!(out, "{name}_bucket{labels} {}", point.count);
+            let labels = prometheus_labels(&point.attributes, None);
+            let _ = writeln!(out, "{name}_sum{labels} {}", point.sum);
+            let _ = writeln!(out, "{name}_count{labels} {}", point.count);
+        }
+    }
+
+    fn encode_sum<T: Display>(&mut self, name: &str, metric: &Metric, sum: &Sum<T>) {
+        if sum.is_monotonic {
+            self.encode_points(
+                &format!("{name}_total"),
+                metric,
+                "counter",
+                &sum.data_points,
+            );
+        } else {
+            self.encode_points(name, metric, "gauge", &sum.data_points);
+        }
+    }
+
+    fn encode(&mut self, metric: &Metric) {
+        let name = prometheus_name(&metric.name);
+        let data: &dyn Any = metric.data.as_any();
+        if let Some(sum) = data.downcast_ref::<Sum<u64>>() {
+            self.encode_sum(&name, metric, sum);
+        } else if let Some(sum) = data.downcast_ref::<Sum<i64>>() {
+            self.encode_sum(&name, metric, sum);
+        } else if let Some(sum) = data.downcast_ref::<Sum<f64>>() {
+            self.encode_sum(&name, metric, sum);
+        } else if let Some(gauge) = data.downcast_ref::<Gauge<u64>>() {
+            self.encode_points(&name, metric, "gauge", &gauge.data_points);
+        } else if let Some(gauge) = data.downcast_ref::<Gauge<i64>>() {
+            self.encode_points(&name, metric, "gauge", &gauge.data_points);
+        } else if let Some(gauge) = data.downcast_ref::<Gauge<f64>>() {
+            self.encode_points(&name, metric, "gauge", &gauge.data_points);
+        } else if let Some(histogram) = data.downcast_ref::<Histogram<u64>>() {
+            self.encode_histogram(&name, metric, &histogram.data_points);
+        } else if let Some(histogram) = data.downcast_ref::<Histogram<i64>>() {
+            self.encode_histogram(&name, metric, &histogram.data_points);
+        } else if let Some(histogram) = data.downcast_ref::<Histogram<f64>>() {
+            self.encode_histogram(&name, metric, &histogram.data_points);
+        }
+    }
+
+    fn finish(self) -> String {
+        self.families
+            .into_iter()
+            .map(|(header, samples)| header + &samples)
+            .collect()
+    }
+}
+
+/// Encode metrics in the Prometheus text exposition format
+#[cfg(feature = "otel")]
+fn encode_prometheus(metrics: &ResourceMetrics) -> String {
+    let mut encoder = PrometheusEncoder::default();
+    for metric in metrics
+        .scope_metrics
+        .iter()
+        .flat_map(|scope| &scope.metrics)
+    {
+        encoder.encode(metric);
+    }
+    encoder.finish()
+}
 
 #[cfg(feature = "otel")]
 #[allow(clippy::missing_errors_doc)]
@@ -12,38 +275,114 @@ pub fn configure_metrics(
     use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
     use wasmcloud_core::OtelProtocol;
 
-    let exporter = match otel_config.protocol {
-        OtelProtocol::Http => {
-            let client = crate::get_http_client(otel_config)
-                .context("failed to get an http client for otel metrics exporter")?;
-            opentelemetry_otlp::MetricExporter::builder()
-                .with_http()
-                .with_http_client(client)
-                .with_protocol(opentelemetry_otlp::Protocol::HttpBinary)
-                .with_endpoint(otel_config.metrics_endpoint())
-                .build()
-                .context("failed to create OTEL http exporter")?
-        }
-        OtelProtocol::Grpc => {
-            // TODO(joonas): Configure tonic::transport::ClientTlsConfig via .with_tls_config(...), passing in additional certificates.
-            opentelemetry_otlp::MetricExporter::builder()
-                .with_tonic()
-                .with_endpoint(otel_config.metrics_endpoint())
-                .build()
-                .context("failed to create OTEL tonic exporter")?
-        }
-    };
+    let mut builder =
+        SdkMeterProvider::builder().with_resource(opentelemetry_sdk::Resource::new(vec![
+            opentelemetry::KeyValue::new("service.name", service_name.to_string()),
+        ]));
 
-    let reader = PeriodicReader::builder(exporter, opentelemetry_sdk::runtime::Tokio).build();
+    if otel_config.metrics_enabled() {
+        let exporter = match otel_config.protocol {
+            OtelProtocol::Http => {
+                let client = crate::get_http_client(otel_config)
+                    .context("failed to get an http client for otel metrics exporter")?;
+                opentelemetry_otlp::MetricExporter::builder()
+                    .with_http()
+                    .with_http_client(client)
+                    .with_protocol(opentelemetry_otlp::Protocol::HttpBinary)
+                    .with_endpoint(otel_config.metrics_endpoint())
+                    .build()
+                    .context("failed to create OTEL http exporter")?
+            }
+            OtelProtocol::Grpc => {
+                // TODO(joonas): Configure tonic::transport::ClientTlsConfig via .with_tls_config(...), passing in additional certificates.
+                opentelemetry_otlp::MetricExporter::builder()
+                    .with_tonic()
+                    .with_endpoint(otel_config.metrics_endpoint())
+                    .build()
+                    .context("failed to create OTEL tonic exporter")?
+            }
+        };
+        builder = builder.with_reader(
+            PeriodicReader::builder(exporter, opentelemetry_sdk::runtime::Tokio).build(),
+        );
+    }
 
-    let meter_provider = SdkMeterProvider::builder()
-        .with_resource(opentelemetry_sdk::Resource::new(vec![
-            opentelemetry::KeyValue::new("service.name", service_name.to_string()),
-        ]))
-        .with_reader(reader)
-        .build();
+    if let Some(registry) = prometheus_registry() {
+        builder = builder.with_reader(registry);
+    }
+
+    let meter_provider = builder.build();
 
     opentelemetry::global::set_meter_provider(meter_provider);
 
     Ok(())
 }
+
+#[cfg(all(test, feature = "otel"))]
+mod test {
+    use opentelemetry::metrics::MeterProvider as _;
+    use opentelemetry_sdk::metrics::SdkMeterProvider;
+
+    use super::*;
+
+    #[test]
+    fn encode_prometheus_metrics() {
+        let registry = PrometheusRegistry::new();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(registry.clone())
+            .build();
+        let meter = provider.meter("test");
+        let counter = meter
+            .u64_counter("wasmcloud_host.component.invocations")
+            .with_description("Number of component invocations")
+            .build();
+        counter.add(2, &[opentelemetry::KeyValue::new("component.id", "echo")]);
+        let histogram = meter.u64_histogram("duration").build();
+        histogram.record(3, &[]);
+
+        let text = registry.encode().expect("failed to encode metrics");
+        assert!(text.contains(
+            "# HELP wasmcloud_host_component_invocations_total Number of component invocations\n"
+        ));
+        assert!(text.contains("# TYPE wasmcloud_host_component_invocations_total counter\n"));
+        assert!(
+            text.contains("wasmcloud_host_component_invocations_total{component_id=\"echo\"} 2\n")
+        );
+        assert!(text.contains("# TYPE duration histogram\n"));
+        assert!(text.contains("duration_bucket{le=\"+Inf\"} 1\n"));
+        assert!(text.contains("duration_sum 3\n"));
+        assert!(text.contains("duration_count 1\n"));
+    }
+
+    #[test]
+    fn encode_prometheus_families_once() {
+        let registry = PrometheusRegistry::new();
+        let provider = SdkMeterProvider::builder()
+            .with_reader(registry.clone())
+            .build();
+        // The same metric recorded by multiple meters is a single metric family
+        for (scope, component) in [("a", "echo"), ("b", "pong")] {
+            provider
+                .meter(scope)
+                .u64_counter("invocations")
+                .with_description("Number of invocations")
+                .build()
+                .add(1, &[opentelemetry::KeyValue::new("component", component)]);
+        }
+
+        let text = registry.encode().expect("failed to encode metrics");
+        assert_eq!(text.matches("# HELP invocations_total ").count(), 1);
+        assert_eq!(
+            text.matches("# TYPE invocations_total counter\n").count(),
+            1
+        );
+        let samples: Vec<_> = text
+            .lines()
+            .skip_while(|line| !line.starts_with("# TYPE invocations_total"))
+            .skip(1)
+            .take(2)
+            .collect();
+        assert!(samples.contains(&"invocations_total{component=\"echo\"} 1"));
+        assert!(samples.contains(&"invocations_total{component=\"pong\"} 1"));
+    }
+}
@@ -17,9 +17,9 @@ use tracing_subscriber::fmt::time::SystemTime;
 use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields};
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::registry::LookupSpan;
-use tracing_subscriber::EnvFilter;
 #[cfg(feature = "otel")]
 use tracing_subscriber::Layer;
+use tracing_subscriber::{reload, EnvFilter};
 use wasmcloud_core::logging::Level;
 use wasmcloud_core::OtelConfig;
 #[cfg(feature = "otel")]
@@ -29,6 +29,27 @@ use wasmcloud_core::OtelProtocol;
 static LOG_PROVIDER: once_cell::sync::OnceCell<opentelemetry_sdk::logs::LoggerProvider> =
     once_cell::sync::OnceCell::new();
 
+/// Function replacing the log level filters of the global tracing subscriber
+type LogLevelReload = Box<dyn Fn(&Level) -> anyhow::Result<()> + Send + Sync>;
+
+static LOG_LEVEL_RELOAD: once_cell::sync::OnceCell<LogLevelReload> =
+    once_cell::sync::OnceCell::new();
+
+/// Change the log level of the tracing subscriber configured by
+/// [`configure_observability`](crate::configure_observability) at runtime.
+///
+/// `RUST_LOG` directives keep taking precedence over the level, like on startup
+///
+/// # Errors
+///
+/// This will return an error if tracing was not configured, or if the filters fail to reload
+pub fn set_log_level(level: &Level) -> anyhow::Result<()> {
+    let reload = LOG_LEVEL_RELOAD
+        .get()
+        .ok_or_else(|| anyhow::anyhow!("tracing is not configured"))?;
+    reload(level)
+}
+
 /// A struct that allows us to dynamically choose JSON formatting without using dynamic dispatch.
 /// This is just so we avoid any sort of possible slow down in logging code
 enum JsonOrNot {
@@ -81,8 +102,15 @@ pub fn configure_tracing(
 ) -> anyhow::Result<(tracing::Dispatch, FlushGuard)> {
     let flame = flame_graph.map(FlameLayer::with_file).transpose()?;
     let (flame, flame_guard) = flame.map(|(l, g)| (Some(l), Some(g))).unwrap_or_default();
+    let (log_level_filter, log_level_reload) =
+        reload::Layer::new(get_log_level_filter(log_level_override));
+    let _ = LOG_LEVEL_RELOAD.set(Box::new(move |level| {
+        log_level_reload
+            .reload(get_log_level_filter(Some(level)))
+            .map_err(|err| anyhow::anyhow!("failed to reload log level filter: {err}"))
+    }));
     let reg = tracing_subscriber::Registry::default()
-        .with(get_log_level_filter(log_level_override))
+        .with(log_level_filter)
         .with(flame);
     let stderr = std::io::stderr();
     let ansi = stderr.is_terminal();
@@ -134,7 +162,8 @@ pub fn configure_tracing(
 ) -> anyhow::Result<(tracing::Dispatch, FlushGuard)> {
     let service_name = Arc::from(service_name);
 
-    let log_level_filter = get_log_level_filter(log_level_override);
+    let (log_level_filter, fmt_level_reload) =
+        reload::Layer::new(get_log_level_filter(log_level_override));
     let traces = otel_config
         .traces_enabled()
         .then(|| {
@@ -158,8 +187,16 @@ pub fn configure_tracing(
             )
         })
         .unwrap_or_default();
+    let (registry_level_filter, registry_level_reload) =
+        reload::Layer::new(get_log_level_filter(log_level_override));
+    let _ = LOG_LEVEL_RELOAD.set(Box::new(move |level| {
+        registry_level_reload
+            .reload(get_log_level_filter(Some(level)))
+            .and_then(|()| fmt_level_reload.reload(get_log_level_filter(Some(level))))
+            .map_err(|err| anyhow::anyhow!("failed to reload log level filter: {err}"))
+    }));
     let registry = tracing_subscriber::Registry::default()
-        .with(get_log_level_filter(log_level_override))
+        .with(registry_level_filter)
         .with(traces)
         .with(logs)
         .with(flame);
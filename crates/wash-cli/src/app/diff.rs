@@ -0,0 +1,396 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::Context as _;
+use clap::Args;
+use serde::Serialize;
+use serde_json::{json, Value};
+use wadm_client::Result;
+use wadm_types::{Component, Manifest, Properties, TraitProperty};
+use wash_lib::app::{load_app_manifest, AppManifest};
+use wash_lib::cli::{CliConnectionOpts, CommandOutput};
+use wash_lib::config::WashConnectionOptions;
+
+#[derive(Args, Debug, Clone)]
+pub struct DiffCommand {
+    /// The source of the local application manifest, either a file path, remote file http url, or stdin ('-')
+    #[clap(name = "application")]
+    source: String,
+
+    #[clap(flatten)]
+    opts: CliConnectionOpts,
+}
+
+/// Changes to a single component between the deployed and the local manifest
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub(crate) struct ComponentDiff {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    image: Option<(Option<String>, Option<String>)>,
+    config_changed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scale: Option<(Option<String>, Option<String>)>,
+    links_added: Vec<String>,
+    links_removed: Vec<String>,
+    links_changed: Vec<String>,
+}
+
+impl ComponentDiff {
+    fn is_empty(&self) -> bool {
+        self.image.is_none()
+            && !self.config_changed
+            && self.scale.is_none()
+            && self.links_added.is_empty()
+            && self.links_removed.is_empty()
+            && self.links_changed.is_empty()
+    }
+}
+
+/// Structured diff between the deployed version of an application and a local manifest
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub(crate) struct ManifestDiff {
+    components_added: Vec<String>,
+    components_removed: Vec<String>,
+    components_changed: Vec<ComponentDiff>,
+}
+
+impl ManifestDiff {
+    fn is_empty(&self) -> bool {
+        self.components_added.is_empty()
+            && self.components_removed.is_empty()
+            && self.components_changed.is_empty()
+    }
+}
+
+/// Show the changes deploying a local manifest would make to the deployed version of the application
+pub(crate) async fn diff_model(cmd: DiffCommand) -> Result<CommandOutput> {
+    let connection_opts =
+        <CliConnectionOpts as TryInto<WashConnectionOptions>>::try_into(cmd.opts)?;
+    let lattice = Some(connection_opts.get_lattice());
+
+    let client = connection_opts.into_nats_client().await?;
+
+    let local = match load_app_manifest(cmd.source.parse()?).await? {
+        AppManifest::SerializedModel(manifest) => serde_yaml::from_value::<Manifest>(manifest)
+            .context("failed to parse local application manifest")?,
+        AppManifest::ModelName(name) => {
+            return Err(wadm_client::error::ClientError::ManifestLoad(anyhow::anyhow!("failed to retrieve manifest. Ensure `{name}` is a valid path to a Wadm application manifest.")));
+        }
+    };
+    let name = local.metadata.name.clone();
+    let local_version = local.metadata.annotations.get("version").cloned();
+
+    let deployed_version = wash_lib::app::get_models(&client, lattice.clone())
+        .await?
+        .into_iter()
+        .find(|m| m.name == name)
+        .and_then(|m| m.deployed_version);
+    let deployed = match &deployed_version {
+        Some(version) => Some(
+            wash_lib::app::get_model_details(&client, lattice, &name, Some(version.clone()))
+                .await?,
+        ),
+        None => None,
+    };
+
+    let diff = diff_manifests(deployed.as_ref(), &local);
+
+    let mut map = HashMap::new();
+    map.insert("model_name".to_string(), json!(name));
+    map.insert("deployed_version".to_string(), json!(deployed_version));
+    map.insert("local_version".to_string(), json!(local_version));
+    map.insert("changed".to_string(), json!(!diff.is_empty()));
+    map.insert("diff".to_string(), json!(diff));
+    Ok(CommandOutput::new(
+        diff_text(
+            &name,
+            deployed_version.as_deref(),
+            local_version.as_deref(),
+            &diff,
+        ),
+        map,
+    ))
+}
+
+/// Compute the changes from the `deployed` manifest to the `local` one. If the application is not
+/// deployed, all local components are reported as added.
+pub(crate) fn diff_manifests(deployed: Option<&Manifest>, local: &Manifest) -> ManifestDiff {
+    let deployed: BTreeMap<&str, &Component> = deployed
+        .map(|m| {
+            m.spec
+                .components
+                .iter()
+                .map(|c| (c.name.as_str(), c))
+                .collect()
+        })
+        .unwrap_or_default();
+    let local: BTreeMap<&str, &Component> = local
+        .spec
+        .components
+        .iter()
+        .map(|c| (c.name.as_str(), c))
+        .collect();
+
+    let mut diff = ManifestDiff {
+        components_added: local
+            .keys()
+            .filter(|name| !deployed.contains_key(*name))
+            .map(ToString::to_string)
+            .collect(),
+        components_removed: deployed
+            .keys()
+            .filter(|name| !local.contains_key(*name))
+            .map(ToString::to_string)
+            .collect(),
+        ..Default::default()
+    };
+    for (name, local) in &local {
+        let Some(deployed) = deployed.get(name) else {
+            continue;
+        };
+        let component_diff = diff_components(deployed, local);
+        if !component_diff.is_empty() {
+            diff.components_changed.push(component_diff);
+        }
+    }
+    diff
+}
+
+fn diff_components(deployed: &Component, local: &Component) -> ComponentDiff {
+    let (deployed_image, deployed_config) = image_and_config(&deployed.properties);
+    let (local_image, local_config) = image_and_config(&local.properties);
+    let (deployed_scale, deployed_links) = scale_and_links(deployed);
+    let (local_scale, local_links) = scale_and_links(local);
+
+    ComponentDiff {
+        name: local.name.clone(),
+        image: (deployed_image != local_image).then_some((deployed_image, local_image)),
+        config_changed: deployed_config != local_config,
+        scale: (deployed_scale != local_scale).then_some((deployed_scale, local_scale)),
+        links_added: local_links
+            .keys()
+            .filter(|link| !deployed_links.contains_key(*link))
+            .cloned()
+            .collect(),
+        links_removed: deployed_links
+            .keys()
+            .filter(|link| !local_links.contains_key(*link))
+            .cloned()
+            .collect(),
+        links_changed: local_links
+            .iter()
+            .filter(|(link, v)| deployed_links.get(*link).is_some_and(|d| d != *v))
+            .map(|(link, _)| link.clone())
+            .collect(),
+    }
+}
+
+/// Returns the image reference and the config (including secrets) of a component
+fn image_and_config(properties: &Properties) -> (Option<String>, Value) {
+    let (image, config, secrets) = match properties {
+        Properties::Component { properties } => (
+            properties.image.clone(),
+            json!(properties.config),
+            json!(properties.secrets),
+        ),
+        Properties::Capability { properties } => (
+            properties.image.clone(),
+            json!(properties.config),
+            json!(properties.secrets),
+        ),
+    };
+    (image, json!({ "config": config, "secrets": secrets }))
+}
+
+/// Returns a summary of the scalers of a component and its links, keyed by a description of the
+/// link, along with their full definition
+fn scale_and_links(component: &Component) -> (Option<String>, BTreeMap<String, Value>) {
+    let mut scalers = Vec::new();
+    let mut links = BTreeMap::new();
+    for t in component.traits.iter().flatten() {
+        match &t.properties {
+            TraitProperty::Link(link) => {
+                let mut key = format!(
+                    "{}:{}/{} -> {}",
+                    link.namespace,
+                    link.package,
+                    link.interfaces.join(","),
+                    link.target.name
+                );
+                if let Some(name) = link.name.as_ref().filter(|name| *name != "default") {
+                    key.push_str(&format!(" ({name})"));
+                }
+                links.insert(key, json!(link));
+            }
+            _ if t.trait_type.ends_with("scaler") => {
+                let properties = json!(t.properties);
+                let mut scaler = match properties.get("instances") {
+                    Some(instances) => format!("{} of {instances}", t.trait_type),
+                    None => t.trait_type.clone(),
+                };
+                if properties
+                    .get("spread")
+                    .and_then(Value::as_array)
+                    .is_some_and(|spread| !spread.is_empty())
+                {
+                    scaler.push_str(&format!(" spread {}", properties["spread"]));
+                }
+                scalers.push(scaler);
+            }
+            _ => {}
+        }
+    }
+    let scale = (!scalers.is_empty()).then(|| scalers.join(", "));
+    (scale, links)
+}
+
+fn diff_text(
+    name: &str,
+    deployed_version: Option<&str>,
+    local_version: Option<&str>,
+    diff: &ManifestDiff,
+) -> String {
+    let local_version = local_version.unwrap_or("<unversioned>");
+    let mut text = match deployed_version {
+        Some(deployed_version) => format!(
+            "Application \"{name}\": deployed version \"{deployed_version}\" -> local version \"{local_version}\"\n"
+        ),
+        None => format!(
+            "Application \"{name}\" is not deployed, deploying local version \"{local_version}\" would add:\n"
+        ),
+    };
+    if diff.is_empty() {
+        text.push_str("No changes\n");
+        return text;
+    }
+    for name in &diff.components_added {
+        text.push_str(&format!("+ component \"{name}\"\n"));
+    }
+    for name in &diff.components_removed {
+        text.push_str(&format!("- component \"{name}\"\n"));
+    }
+    let or_none = |v: &Option<String>| v.clone().unwrap_or_else(|| "<none>".into());
+    for component in &diff.components_changed {
+        text.push_str(&format!("~ component \"{}\"\n", component.name));
+        if let Some((deployed, local)) = &component.image {
+            text.push_str(&format!(
+                "    image: {} -> {}\n",
+                or_none(deployed),
+                or_none(local)
+            ));
+        }
+        if component.config_changed {
+            text.push_str("    config changed\n");
+        }
+        if let Some((deployed, local)) = &component.scale {
+            text.push_str(&format!(
+                "    scale: {} -> {}\n",
+                or_none(deployed),
+                or_none(local)
+            ));
+        }
+        for link in &component.links_added {
+            text.push_str(&format!("    + link {link}\n"));
+        }
+        for link in &component.links_removed {
+            text.push_str(&format!("    - link {link}\n"));
+        }
+        for link in &component.links_changed {
+            text.push_str(&format!("    ~ link {link}\n"));
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(yaml: &str) -> Manifest {
+        serde_yaml::from_str(yaml).expect("failed to parse manifest")
+    }
+
+    const DEPLOYED: &str = r#"
+apiVersion: core.oam.dev/v1beta1
+kind: Application
+metadata:
+  name: hello
+  annotations:
+    version: v0.0.1
+spec:
+  components:
+    - name: http-component
+      type: component
+      properties:
+        image: ghcr.io/wasmcloud/components/http-hello-world-rust:0.1.0
+      traits:
+        - type: spreadscaler
+          properties:
+            instances: 1
+    - name: httpserver
+      type: capability
+      properties:
+        image: ghcr.io/wasmcloud/http-server:0.22.0
+      traits:
+        - type: link
+          properties:
+            target:
+              name: http-component
+            namespace: wasi
+            package: http
+            interfaces: [incoming-handler]
+            source:
+              config:
+                - name: default-http
+                  properties:
+                    address: 0.0.0.0:8080
+    - name: old
+      type: capability
+      properties:
+        image: ghcr.io/wasmcloud/keyvalue-redis:0.27.0
+"#;
+
+    #[test]
+    fn diff_deployed_and_local() {
+        let local = manifest(
+            &DEPLOYED
+                .replace("version: v0.0.1", "version: v0.0.2")
+                .replace("instances: 1", "instances: 3")
+                .replace("0.0.0.0:8080", "0.0.0.0:8081")
+                .replace("name: old", "name: new"),
+        );
+        let diff = diff_manifests(Some(&manifest(DEPLOYED)), &local);
+        assert_eq!(diff.components_added, ["new"]);
+        assert_eq!(diff.components_removed, ["old"]);
+        assert_eq!(
+            diff.components_changed,
+            [
+                ComponentDiff {
+                    name: "http-component".into(),
+                    scale: Some((
+                        Some("spreadscaler of 1".into()),
+                        Some("spreadscaler of 3".into())
+                    )),
+                    ..Default::default()
+                },
+                ComponentDiff {
+                    name: "httpserver".into(),
+                    links_changed: vec!["wasi:http/incoming-handler -> http-component".into()],
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_undeployed_and_unchanged() {
+        let deployed = manifest(DEPLOYED);
+        assert!(diff_manifests(Some(&deployed), &deployed).is_empty());
+
+        let diff = diff_manifests(None, &deployed);
+        assert_eq!(
+            diff.components_added,
+            ["http-component", "httpserver", "old"]
+        );
+        assert!(diff.components_changed.is_empty());
+    }
+}
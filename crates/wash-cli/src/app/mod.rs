@@ -21,7 +21,12 @@ use crossterm::{
 };
 use std::io::Write;
 
+mod diff;
 mod output;
+mod template;
+
+pub use diff::DiffCommand;
+pub use template::TemplateCommand;
 
 #[derive(Debug, Clone, Subcommand)]
 pub enum AppCliCommand {
@@ -37,6 +42,9 @@ pub enum AppCliCommand {
     /// Get the version history of a given application
     #[clap(name = "history")]
     History(HistoryCommand),
+    /// Roll back an application to the version of a previous deployment
+    #[clap(name = "rollback")]
+    Rollback(RollbackCommand),
     /// Delete an application version
     #[clap(name = "delete", alias = "del")]
     Delete(DeleteCommand),
@@ -52,6 +60,12 @@ pub enum AppCliCommand {
     /// Validate an application manifest
     #[clap(name = "validate")]
     Validate(ValidateCommand),
+    /// Generate a starter application manifest from the WIT of a built component
+    #[clap(name = "template")]
+    Template(TemplateCommand),
+    /// Show the changes deploying a local application manifest would make to the deployed version of the application
+    #[clap(name = "diff")]
+    Diff(DiffCommand),
 }
 
 #[derive(Args, Debug, Clone)]
@@ -162,6 +176,26 @@ pub struct HistoryCommand {
     #[clap(name = "name")]
     app_name: String,
 
+    /// List the deployments of the application recorded by wash, including who deployed which
+    /// version when, instead of the versions stored by wadm
+    #[clap(long = "deployments")]
+    deployments: bool,
+
+    #[clap(flatten)]
+    opts: CliConnectionOpts,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct RollbackCommand {
+    /// The name of the application
+    #[clap(name = "name")]
+    app_name: String,
+
+    /// Revision of the deployment to roll back to, as listed by `wash app history --deployments`.
+    /// Defaults to the deployment before the latest one
+    #[clap(name = "revision")]
+    revision: Option<u64>,
+
     #[clap(flatten)]
     opts: CliConnectionOpts,
 }
@@ -200,10 +234,18 @@ pub async fn handle_command(
             sp.update_spinner_message("Getting application status ... ".to_string());
             get_model_status(cmd).await
         }
+        History(cmd) if cmd.deployments => {
+            sp.update_spinner_message("Getting application deployment history ... ".to_string());
+            get_application_deployments(cmd).await
+        }
         History(cmd) => {
             sp.update_spinner_message("Getting application version history ... ".to_string());
             get_application_versions(cmd).await
         }
+        Rollback(cmd) => {
+            sp.update_spinner_message("Rolling back application ... ".to_string());
+            rollback_model(cmd).await
+        }
         Delete(cmd) => {
             sp.update_spinner_message("Deleting application version ... ".to_string());
             delete_application_version(cmd).await
@@ -224,6 +266,14 @@ pub async fn handle_command(
             sp.update_spinner_message("Validating application manifest ... ".to_string());
             handle_validate(cmd).await
         }
+        Template(cmd) => {
+            sp.update_spinner_message("Generating application manifest ... ".to_string());
+            template::generate_template(cmd).await
+        }
+        Diff(cmd) => {
+            sp.update_spinner_message("Comparing application manifests ... ".to_string());
+            diff::diff_model(cmd).await
+        }
     };
 
     // Basic match to give a nicer error than "no responders"
@@ -357,7 +407,7 @@ pub(crate) async fn deploy_model_from_manifest(
     let (name, version) = match manifest {
         AppManifest::SerializedModel(manifest) => wash_lib::app::put_and_deploy_model(
             client,
-            lattice,
+            lattice.clone(),
             serde_yaml::to_string(&manifest)
                 .context("failed to convert manifest to string")?
                 .as_ref(),
@@ -365,10 +415,18 @@ pub(crate) async fn deploy_model_from_manifest(
         .await
         .map(|(name, version)| (name, Some(version))),
         AppManifest::ModelName(model_name) => {
-            wash_lib::app::deploy_model(client, lattice, &model_name, version.clone()).await
+            wash_lib::app::deploy_model(client, lattice.clone(), &model_name, version.clone()).await
         }
     }?;
 
+    // The deployment succeeded even if it could not be recorded
+    if let Err(e) =
+        wash_lib::app::history::record_deployment(client, lattice, &name, version.clone(), None)
+            .await
+    {
+        eprintln!("🟨 Failed to record deployment in application history: {e:#}");
+    }
+
     let mut map = HashMap::new();
     let version = version.unwrap_or_default();
     map.insert("deployed".to_string(), json!(true));
@@ -433,6 +491,49 @@ async fn get_application_versions(cmd: HistoryCommand) -> Result<CommandOutput>
     ))
 }
 
+async fn get_application_deployments(cmd: HistoryCommand) -> Result<CommandOutput> {
+    let connection_opts =
+        <CliConnectionOpts as TryInto<WashConnectionOptions>>::try_into(cmd.opts)?;
+    let lattice = Some(connection_opts.get_lattice());
+
+    let client = connection_opts.into_nats_client().await?;
+
+    let deployments =
+        wash_lib::app::history::get_deployment_history(&client, lattice, &cmd.app_name).await?;
+    let mut map = HashMap::new();
+    map.insert("deployments".to_string(), json!(deployments));
+    Ok(CommandOutput::new(
+        output::list_deployments_table(deployments),
+        map,
+    ))
+}
+
+async fn rollback_model(cmd: RollbackCommand) -> Result<CommandOutput> {
+    let connection_opts =
+        <CliConnectionOpts as TryInto<WashConnectionOptions>>::try_into(cmd.opts)?;
+    let lattice = Some(connection_opts.get_lattice());
+
+    let client = connection_opts.into_nats_client().await?;
+
+    let deployment =
+        wash_lib::app::history::rollback_model(&client, lattice, &cmd.app_name, cmd.revision)
+            .await?;
+    let mut map = HashMap::new();
+    map.insert("deployed".to_string(), json!(true));
+    map.insert("model_name".to_string(), json!(cmd.app_name));
+    map.insert("model_version".to_string(), json!(deployment.version));
+    map.insert("deployment".to_string(), json!(deployment));
+    Ok(CommandOutput::new(
+        format!(
+            "Rolled back application \"{}\" to version \"{}\" of revision {}",
+            cmd.app_name,
+            deployment.version,
+            deployment.rollback_of.unwrap_or_default()
+        ),
+        map,
+    ))
+}
+
 async fn get_model_status(cmd: StatusCommand) -> Result<CommandOutput> {
     let connection_opts =
         <CliConnectionOpts as TryInto<WashConnectionOptions>>::try_into(cmd.opts)?;
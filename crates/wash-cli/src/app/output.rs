@@ -4,6 +4,7 @@ use term_table::{
     Table,
 };
 use wadm_types::api::{Status, VersionInfo};
+use wash_lib::app::history::DeploymentRecord;
 
 use super::ModelSummary;
 
@@ -26,6 +27,43 @@ pub fn list_revisions_table(revisions: Vec<VersionInfo>) -> String {
     table.render()
 }
 
+pub fn list_deployments_table(deployments: Vec<DeploymentRecord>) -> String {
+    let mut table = Table::new();
+    crate::util::configure_table_style(&mut table, 6);
+
+    table.add_row(Row::new(vec![
+        TableCell::new_with_alignment("Revision", 1, Alignment::Left),
+        TableCell::new_with_alignment("Version", 1, Alignment::Left),
+        TableCell::new_with_alignment("Deployed By", 1, Alignment::Left),
+        TableCell::new_with_alignment("Deployed At", 1, Alignment::Left),
+        TableCell::new_with_alignment("Digest", 1, Alignment::Left),
+        TableCell::new_with_alignment("Changes", 1, Alignment::Left),
+    ]));
+
+    deployments.iter().for_each(|d| {
+        // Shorten digests like container image IDs, the full digest is part of the JSON output
+        let digest = d.digest.trim_start_matches("sha256:");
+        let changes = match d.rollback_of {
+            Some(revision) => format!("rollback to {revision}: {}", d.changes),
+            None => d.changes.to_string(),
+        };
+        table.add_row(Row::new(vec![
+            TableCell::new_with_alignment(d.revision, 1, Alignment::Left),
+            TableCell::new_with_alignment(d.version.clone(), 1, Alignment::Left),
+            TableCell::new_with_alignment(d.deployed_by.clone(), 1, Alignment::Left),
+            TableCell::new_with_alignment(
+                d.deployed_at.format("%Y-%m-%d %H:%M:%S UTC"),
+                1,
+                Alignment::Left,
+            ),
+            TableCell::new_with_alignment(&digest[..digest.len().min(12)], 1, Alignment::Left),
+            TableCell::new_with_alignment(changes, 1, Alignment::Left),
+        ]));
+    });
+
+    table.render()
+}
+
 pub fn list_models_table(models: Vec<ModelSummary>) -> String {
     let mut table = Table::new();
     crate::util::configure_table_style(&mut table, 3);
@@ -0,0 +1,299 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+use clap::Args;
+use serde_json::json;
+use wadm_client::Result;
+use wadm_types::{
+    Component, ComponentProperties, Manifest, Metadata, Properties, Specification,
+    SpreadScalerProperty, Trait, TraitProperty,
+};
+use wash_lib::cli::CommandOutput;
+
+use crate::cmd::dev::deps::DependencySpec;
+use crate::cmd::dev::wit::{discover_dependencies_from_wit, parse_component_wit};
+
+/// Prefix of the placeholder image reference used for dependencies that have no known provider
+const PLACEHOLDER_IMAGE_REF_PREFIX: &str = "REPLACE_WITH_IMAGE_REF_FOR_";
+
+#[derive(Args, Debug, Clone)]
+pub struct TemplateCommand {
+    /// Path to the built component (.wasm) to generate an application manifest for
+    #[clap(name = "component")]
+    component: PathBuf,
+
+    /// Name of the generated application, defaults to the file name of the component
+    #[clap(long = "name")]
+    name: Option<String>,
+
+    /// Image reference of the component to use in the manifest, defaults to a `file://` reference to the component
+    #[clap(long = "image")]
+    image: Option<String>,
+
+    /// Path to write the generated manifest to. If not specified, the manifest is printed
+    #[clap(short = 'o', long = "output")]
+    output: Option<PathBuf>,
+}
+
+/// Generate a starter application manifest from the WIT imports and exports of a built component
+pub(crate) async fn generate_template(cmd: TemplateCommand) -> Result<CommandOutput> {
+    let component_bytes = tokio::fs::read(&cmd.component).await.with_context(|| {
+        format!(
+            "failed to read component from [{}]",
+            cmd.component.display()
+        )
+    })?;
+    let (resolve, world_id) =
+        parse_component_wit(&component_bytes).context("failed to parse WIT from component")?;
+    let deps = discover_dependencies_from_wit(resolve, world_id)
+        .context("failed to discover dependencies from component WIT")?;
+
+    let name = match cmd.name {
+        Some(name) => name,
+        None => component_name_from_path(&cmd.component)?,
+    };
+    let image_ref = match cmd.image {
+        Some(image_ref) => image_ref,
+        None => format!(
+            "file://{}",
+            cmd.component
+                .canonicalize()
+                .context("failed to resolve component path")?
+                .display()
+        ),
+    };
+
+    let manifest = manifest_from_dependencies(&name, &image_ref, deps)?;
+    let yaml = serde_yaml::to_string(&manifest).context("failed to convert manifest to YAML")?;
+
+    let mut map = HashMap::new();
+    map.insert("application".to_string(), json!(manifest));
+    match cmd.output {
+        Some(output) => {
+            tokio::fs::write(&output, &yaml)
+                .await
+                .with_context(|| format!("failed to write manifest to [{}]", output.display()))?;
+            map.insert("path".to_string(), json!(output));
+            Ok(CommandOutput::new(
+                format!(
+                    "Generated application manifest \"{name}\" at [{}]",
+                    output.display()
+                ),
+                map,
+            ))
+        }
+        None => Ok(CommandOutput::new(yaml, map)),
+    }
+}
+
+/// Derive a manifest-friendly name from the file name of a component (ex. `http_hello_world_s.wasm` -> `http-hello-world-s`)
+fn component_name_from_path(path: &Path) -> anyhow::Result<String> {
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .with_context(|| format!("invalid component path [{}]", path.display()))?;
+    Ok(stem.to_lowercase().replace(['_', ' '], "-"))
+}
+
+/// Build a spreadscaler trait with a given number of instances
+fn spreadscaler(instances: usize) -> Trait {
+    Trait {
+        trait_type: "spreadscaler".into(),
+        properties: TraitProperty::SpreadScaler(SpreadScalerProperty {
+            instances,
+            spread: Vec::new(),
+        }),
+    }
+}
+
+/// Build an application manifest with the component and one provider (and link) per dependency
+///
+/// Dependencies without a well-known provider are given a placeholder image reference that must be filled in.
+fn manifest_from_dependencies(
+    name: &str,
+    image_ref: &str,
+    deps: impl IntoIterator<Item = DependencySpec>,
+) -> anyhow::Result<Manifest> {
+    let mut component = Component {
+        name: name.into(),
+        properties: Properties::Component {
+            properties: ComponentProperties {
+                image: Some(image_ref.into()),
+                application: None,
+                id: None,
+                config: Vec::new(),
+                secrets: Vec::new(),
+            },
+        },
+        traits: Some(vec![spreadscaler(1)]),
+    };
+
+    let mut dep_components = Vec::new();
+    for mut dep in deps {
+        let dep_name = dep.name();
+        if dep.image_ref().is_none() {
+            dep.set_image_ref(format!("{PLACEHOLDER_IMAGE_REF_PREFIX}{dep_name}"));
+        }
+        let mut dep_component = Component {
+            name: dep_name.clone(),
+            properties: dep
+                .generate_properties(&dep_name)
+                .with_context(|| format!("failed to generate component for [{dep_name}]"))?,
+            traits: Some(vec![spreadscaler(1)]),
+        };
+
+        let link_trait = Trait {
+            trait_type: "link".into(),
+            properties: TraitProperty::Link(
+                dep.generate_link_property(&component.name, &dep_component.name),
+            ),
+        };
+        match dep {
+            DependencySpec::Exports(_) => component.traits.get_or_insert(Vec::new()),
+            DependencySpec::Imports(_) => dep_component.traits.get_or_insert(Vec::new()),
+        }
+        .push(link_trait);
+
+        dep_components.push(dep_component);
+    }
+
+    let mut components = vec![component];
+    components.append(&mut dep_components);
+    Ok(Manifest {
+        api_version: "core.oam.dev/v1beta1".into(),
+        kind: "Application".into(),
+        metadata: Metadata {
+            name: name.into(),
+            annotations: BTreeMap::from([
+                ("version".into(), "v0.0.1".into()),
+                (
+                    "description".into(),
+                    format!("Application generated from the WIT of component [{name}]"),
+                ),
+            ]),
+            labels: BTreeMap::from([(
+                "wasmcloud.dev/generated-by".into(),
+                "wash-app-template".into(),
+            )]),
+        },
+        spec: Specification {
+            components,
+            policies: Vec::new(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Get the image reference of a component in a manifest
+    fn image(component: &Component) -> Option<&str> {
+        match &component.properties {
+            Properties::Component { properties } => properties.image.as_deref(),
+            Properties::Capability { properties } => properties.image.as_deref(),
+        }
+    }
+
+    /// Get the namespace, package and target of the links of a component in a manifest
+    fn links(component: &Component) -> Vec<(&str, &str, &str)> {
+        component
+            .traits
+            .iter()
+            .flatten()
+            .filter_map(|t| match &t.properties {
+                TraitProperty::Link(link) => Some((
+                    link.namespace.as_str(),
+                    link.package.as_str(),
+                    link.target.name.as_str(),
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_component_name_from_path() {
+        assert_eq!(
+            component_name_from_path(Path::new("build/http_hello_world_s.wasm")).unwrap(),
+            "http-hello-world-s"
+        );
+        assert_eq!(
+            component_name_from_path(Path::new("My Component.wasm")).unwrap(),
+            "my-component"
+        );
+        assert_eq!(
+            component_name_from_path(Path::new("component")).unwrap(),
+            "component"
+        );
+        assert!(component_name_from_path(Path::new("")).is_err());
+        assert!(component_name_from_path(Path::new("/")).is_err());
+    }
+
+    #[test]
+    fn test_manifest_from_dependencies() {
+        let deps = [
+            DependencySpec::from_wit_import_iface("wasi:keyvalue/store@0.2.0-draft")
+                .expect("keyvalue dependency"),
+            DependencySpec::from_wit_export_iface("wasi:http/incoming-handler@0.2.0")
+                .expect("http-server dependency"),
+            DependencySpec::from_wit_import_iface("acme:custom/thing").expect("custom dependency"),
+        ];
+        let manifest = manifest_from_dependencies("hello", "file:///tmp/hello.wasm", deps).unwrap();
+        assert_eq!(manifest.metadata.name, "hello");
+        assert_eq!(
+            manifest
+                .metadata
+                .annotations
+                .get("version")
+                .map(String::as_str),
+            Some("v0.0.1")
+        );
+
+        let components = &manifest.spec.components;
+        let names: Vec<_> = components.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "hello",
+                "keyvalue-nats",
+                "http-server",
+                "custom-acme-custom-thing"
+            ]
+        );
+        assert_eq!(image(&components[0]), Some("file:///tmp/hello.wasm"));
+        assert!(matches!(
+            components[0].properties,
+            Properties::Component { .. }
+        ));
+
+        // Dependencies receiving invocations are linked from the component
+        assert_eq!(
+            links(&components[0]),
+            [
+                ("wasi", "keyvalue", "keyvalue-nats"),
+                ("acme", "custom", "custom-acme-custom-thing"),
+            ]
+        );
+        // Dependencies performing invocations are linked to the component
+        assert_eq!(links(&components[2]), [("wasi", "http", "hello")]);
+        assert!(links(&components[1]).is_empty());
+
+        // Unknown dependencies are given a placeholder image reference
+        assert_eq!(
+            image(&components[3]),
+            Some("REPLACE_WITH_IMAGE_REF_FOR_custom-acme-custom-thing")
+        );
+        assert!(image(&components[1])
+            .is_some_and(|image| !image.starts_with(PLACEHOLDER_IMAGE_REF_PREFIX)));
+
+        // Every component is given a single instance
+        for component in components {
+            assert!(component.traits.iter().flatten().any(|t| matches!(
+                t.properties,
+                TraitProperty::SpreadScaler(SpreadScalerProperty { instances: 1, .. })
+            )));
+        }
+    }
+}
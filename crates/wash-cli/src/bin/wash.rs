@@ -15,6 +15,7 @@ use wash_lib::cli::get::GetCommand;
 use wash_lib::cli::inspect::InspectCliCommand;
 use wash_lib::cli::label::LabelHostCommand;
 use wash_lib::cli::link::LinkCommand;
+use wash_lib::cli::provider::ProviderCommand;
 use wash_lib::cli::registry::{RegistryPullCommand, RegistryPushCommand};
 use wash_lib::cli::scale::ScaleCommand;
 use wash_lib::cli::spy::SpyCommand;
@@ -39,7 +40,9 @@ use wash_cli::config::{NATS_SERVER_VERSION, WADM_VERSION, WASMCLOUD_HOST_VERSION
 use wash_cli::ctx::{self, CtxCommand};
 use wash_cli::down::{self, DownCommand};
 use wash_cli::drain;
+use wash_cli::explain::{self, ExplainCommand};
 use wash_cli::generate::{self, NewCliCommand};
+use wash_cli::help::with_subcommand_help;
 use wash_cli::keys::{self, KeysCliCommand};
 use wash_cli::par::{self, ParCliCommand};
 use wash_cli::plugin::{self, PluginCommand};
@@ -159,11 +162,12 @@ ________________________________________________________________________________
                 ("stop", "Stop a component, capability provider, or host"),
                 (
                     "update",
-                    "Update a component running in a host to newer image reference",
+                    "Update a component or provider running in a host to newer image reference",
                 ),
                 ("link", "Link one component to another on a set of interfaces"),
                 ("call", "Invoke a simple function on a component running in a wasmCloud host"),
                 ("label", "Label (or un-label) a host with a key=value label pair"),
+                ("provider", "Perform administrative operations on running capability providers"),
                 (
                     "config",
                     "Create configuration for components, capability providers and links",
@@ -187,6 +191,7 @@ ________________________________________________________________________________
                 ("completions", "Generate shell completions for wash"),
                 ("ctx", "Manage wasmCloud host configuration contexts"),
                 ("drain", "Manage contents of local wasmCloud caches"),
+                ("explain", "Explain an error code returned by wash or the control interface"),
                 ("keys", "Generate and manage signing keys"),
                 ("claims", "Generate and manage JWTs for wasmCloud components and capability providers"),
                 ("plugin", "Manage wash plugins"),
@@ -319,6 +324,9 @@ enum CliCommand {
     /// Manage contents of local wasmCloud caches
     #[clap(name = "drain", subcommand)]
     Drain(DrainSelection),
+    /// Explain an error code returned by wash or the control interface
+    #[clap(name = "explain")]
+    Explain(ExplainCommand),
     /// Get information about different running wasmCloud resources
     #[clap(name = "get", subcommand)]
     Get(GetCommand),
@@ -340,6 +348,9 @@ enum CliCommand {
     /// Manage wash plugins
     #[clap(name = "plugin", subcommand)]
     Plugin(PluginCommand),
+    /// Perform administrative operations on running capability providers
+    #[clap(name = "provider", subcommand)]
+    Provider(ProviderCommand),
     /// Push an artifact to an OCI compliant registry
     #[clap(name = "push")]
     RegPush(RegistryPushCommand),
@@ -364,7 +375,7 @@ enum CliCommand {
     /// Label (or un-label) a host with a key=value label pair
     #[clap(name = "label", alias = "tag")]
     Label(LabelHostCommand),
-    /// Update a component running in a host to newer image reference
+    /// Update a component or provider running in a host to newer image reference
     #[clap(name = "update", subcommand)]
     Update(UpdateCommand),
     /// Bootstrap a local wasmCloud environment
@@ -386,7 +397,7 @@ async fn main() {
         .with_env_filter(EnvFilter::from_default_env())
         .init();
 
-    let mut command = Cli::command();
+    let mut command = with_subcommand_help(Cli::command());
     // Load plugins if they are not disabled
     let plugins = if std::env::var("WASH_DISABLE_PLUGINS").is_err() {
         if let Some((plugins, dir)) = load_plugins().await {
@@ -466,7 +477,7 @@ async fn main() {
                 }
             };
 
-            let dir = match ensure_plugin_scratch_dir_exists(plugin_dir, id).await {
+            let dir = match ensure_plugin_scratch_dir_exists(&plugin_dir, id).await {
                 Ok(dir) => dir,
                 Err(e) => {
                     eprintln!("Error creating plugin scratch directory: {}", e);
@@ -505,7 +516,25 @@ async fn main() {
             // revisit this later with something if we need to. I did do some basic testing that
             // even if you wrap wash in a shell script, it still works.
             let args: Vec<String> = std::env::args().skip(1).collect();
-            if let Err(e) = plugins.run(id, dir, plugin_dirs, args).await {
+            let required = plugins
+                .permissions(id)
+                .map(ToOwned::to_owned)
+                .unwrap_or_default();
+            let granted = if required.is_empty() {
+                Vec::new()
+            } else {
+                match plugin::ensure_consent(&plugin_dir, id, &required, false).await {
+                    Ok(granted) => granted,
+                    Err(e) => {
+                        eprintln!("Error running plugin: {e}");
+                        std::process::exit(1);
+                    }
+                }
+            };
+            if let Err(e) = plugins
+                .run_with_permissions(id, dir, plugin_dirs, args, &granted)
+                .await
+            {
                 eprintln!("Error running plugin: {}", e);
                 std::process::exit(1);
             } else {
@@ -549,6 +578,8 @@ async fn main() {
                 experimental_error_message("capture")
             } else if let Some(CaptureSubcommand::Replay(cmd)) = capture_cli.replay {
                 wash_lib::cli::capture::handle_replay_command(cmd).await
+            } else if let Some(CaptureSubcommand::Payloads(cmd)) = capture_cli.replay {
+                wash_lib::cli::capture::handle_payloads_command(cmd).await
             } else {
                 wash_lib::cli::capture::handle_command(capture_cli).await
             }
@@ -564,6 +595,7 @@ async fn main() {
         CliCommand::Dev(dev_cli) => dev::handle_command(dev_cli, output_kind).await,
         CliCommand::Down(down_cli) => down::handle_command(down_cli, output_kind).await,
         CliCommand::Drain(drain_cli) => drain::handle_command(drain_cli),
+        CliCommand::Explain(explain_cli) => explain::handle_command(explain_cli),
         CliCommand::Get(get_cli) => common::get_cmd::handle_command(get_cli, output_kind).await,
         CliCommand::Inspect(inspect_cli) => {
             wash_lib::cli::inspect::handle_command(inspect_cli, output_kind).await
@@ -573,6 +605,9 @@ async fn main() {
         CliCommand::New(new_cli) => generate::handle_command(new_cli).await,
         CliCommand::Par(par_cli) => par::handle_command(par_cli, output_kind).await,
         CliCommand::Plugin(plugin_cli) => plugin::handle_command(plugin_cli, output_kind).await,
+        CliCommand::Provider(provider_cli) => {
+            wash_lib::cli::provider::handle_command(provider_cli).await
+        }
         CliCommand::RegPush(reg_push_cli) => {
             common::registry_cmd::registry_push(reg_push_cli, output_kind).await
         }
@@ -583,7 +618,7 @@ async fn main() {
             if !cli.experimental {
                 experimental_error_message("spy")
             } else {
-                wash_lib::cli::spy::handle_command(spy_cli).await
+                wash_lib::cli::spy::handle_command(spy_cli, output_kind).await
             }
         }
         CliCommand::Scale(scale_cli) => {
@@ -664,6 +699,10 @@ async fn main() {
                         map.insert("error_chain".to_string(), json!(error_chain));
                     }
 
+                    if let Some(error) = explain::classify_error(&e) {
+                        map.insert("error_code".to_string(), json!(error.code));
+                    }
+
                     let backtrace = e.backtrace().to_string();
 
                     if !backtrace.is_empty() && backtrace != "disabled backtrace" {
@@ -674,6 +713,9 @@ async fn main() {
                 }
                 OutputKind::Text => {
                     eprintln!("\n{e:?}");
+                    if let Some(error) = explain::classify_error(&e) {
+                        eprintln!("\nFor more information, run `wash explain {}`", error.code);
+                    }
                 }
             }
             1
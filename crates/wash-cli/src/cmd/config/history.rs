@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use anyhow::bail;
+use serde_json::json;
+use term_table::{
+    row::Row,
+    table_cell::{Alignment, TableCell},
+    Table,
+};
+use wash_lib::cli::{CliConnectionOpts, CommandOutput, OutputKind};
+use wash_lib::config::WashConnectionOptions;
+use wasmcloud_control_interface::ConfigRevision;
+
+use crate::appearance::spinner::Spinner;
+use crate::errors::suggest_run_host_error;
+
+/// Invoke `wash config history`
+pub(crate) async fn invoke(
+    opts: CliConnectionOpts,
+    name: &str,
+    output_kind: OutputKind,
+) -> anyhow::Result<CommandOutput> {
+    let sp: Spinner = Spinner::new(&output_kind)?;
+    sp.update_spinner_message("Getting configuration history...".to_string());
+
+    let wco: WashConnectionOptions = opts.try_into()?;
+    let ctl_client = wco.into_ctl_client(None).await?;
+
+    let history_response = ctl_client
+        .get_config_history(name)
+        .await
+        .map_err(suggest_run_host_error)?;
+
+    sp.finish_and_clear();
+
+    if !history_response.succeeded() {
+        bail!(
+            "Error getting configuration history: {}",
+            history_response.message()
+        );
+    }
+    let revisions = history_response.into_data().unwrap_or_default();
+    if revisions.is_empty() {
+        bail!("No configuration history found for name: {name}");
+    }
+    let text = revisions_table(&revisions);
+    Ok(CommandOutput::new(
+        text,
+        HashMap::from([("revisions".to_string(), json!(revisions))]),
+    ))
+}
+
+fn revisions_table(revisions: &[ConfigRevision]) -> String {
+    let mut table = Table::new();
+    crate::util::configure_table_style(&mut table, 3);
+
+    table.add_row(Row::new(vec![
+        TableCell::new_with_alignment("Revision", 1, Alignment::Left),
+        TableCell::new_with_alignment("Created", 1, Alignment::Left),
+        TableCell::new_with_alignment("Configuration", 1, Alignment::Left),
+    ]));
+
+    for revision in revisions {
+        let config = match revision.config() {
+            Some(config) => {
+                let mut values: Vec<_> = config.iter().map(|(k, v)| format!("{k}={v}")).collect();
+                values.sort();
+                values.join(", ")
+            }
+            None => "(deleted)".to_string(),
+        };
+        table.add_row(Row::new(vec![
+            TableCell::new_with_alignment(revision.revision(), 1, Alignment::Left),
+            TableCell::new_with_alignment(revision.created(), 1, Alignment::Left),
+            TableCell::new_with_alignment(config, 1, Alignment::Left),
+        ]));
+    }
+
+    table.render()
+}
@@ -8,7 +8,9 @@ use crate::secrets::ensure_not_secret;
 
 pub(crate) mod delete;
 pub(crate) mod get;
+pub(crate) mod history;
 pub(crate) mod put;
+pub(crate) mod rollback;
 
 #[derive(Debug, Clone, Subcommand)]
 #[allow(clippy::enum_variant_names)]
@@ -43,6 +45,27 @@ pub enum ConfigCliCommand {
         #[clap(name = "name")]
         name: String,
     },
+    /// List the retained revisions of a named configuration
+    #[clap(name = "history")]
+    HistoryCommand {
+        #[clap(flatten)]
+        opts: CliConnectionOpts,
+        /// The name of the configuration to list the revisions of
+        #[clap(name = "name")]
+        name: String,
+    },
+    /// Restore a named configuration to a previous revision
+    #[clap(name = "rollback")]
+    RollbackCommand {
+        #[clap(flatten)]
+        opts: CliConnectionOpts,
+        /// The name of the configuration to roll back
+        #[clap(name = "name")]
+        name: String,
+        /// The revision to restore, as listed by `wash config history`
+        #[clap(long = "to-rev")]
+        to_rev: u64,
+    },
 }
 
 /// Handle any `wash config` prefixed (sub)command
@@ -73,5 +96,13 @@ pub async fn handle_command(
             ensure_not_secret(&name)?;
             cmd::config::delete::invoke(opts, &name, output_kind).await
         }
+        ConfigCliCommand::HistoryCommand { opts, name } => {
+            ensure_not_secret(&name)?;
+            cmd::config::history::invoke(opts, &name, output_kind).await
+        }
+        ConfigCliCommand::RollbackCommand { opts, name, to_rev } => {
+            ensure_not_secret(&name)?;
+            cmd::config::rollback::invoke(opts, &name, to_rev, output_kind).await
+        }
     }
 }
@@ -1,4 +1,4 @@
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::hash::Hash;
 use std::path::{Path, PathBuf};
 
@@ -603,6 +603,142 @@ impl DependencySpec {
         Ok(properties)
     }
 
+    /// Build the link that connects the component under development with the component generated for this dependency
+    ///
+    /// Dependencies that receive invocations are linked *from* the component, while dependencies that
+    /// perform invocations are linked *to* the component. Well-known interfaces are given default configuration.
+    pub(crate) fn generate_link_property(
+        &self,
+        component_name: &str,
+        dep_component_name: &str,
+    ) -> LinkProperty {
+        match self {
+            DependencySpec::Exports(DependencySpecInner {
+                wit:
+                    WitInterfaceSpec {
+                        namespace,
+                        package,
+                        interfaces,
+                        ..
+                    },
+                image_ref,
+                ..
+            }) => {
+                let mut link_property = LinkProperty {
+                    namespace: namespace.clone(),
+                    package: package.clone(),
+                    interfaces: interfaces.clone().unwrap_or_default().into_iter().collect(),
+                    target: TargetConfig {
+                        name: dep_component_name.into(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                };
+
+                // Make interface-specific changes
+                match (namespace.as_ref(), package.as_ref(), interfaces.as_ref()) {
+                    (WIT_NS_WASI, "blobstore", interfaces)
+                    | (WIT_NS_WRPC, "blobstore", interfaces)
+                        if interfaces.is_some_and(|interfaces| {
+                            interfaces.iter().any(|i| i == "blobstore")
+                        }) =>
+                    {
+                        link_property.target.config.push(ConfigProperty {
+                            name: config_name(namespace.as_str(), package.as_str()),
+                            properties: Some(HashMap::from([(
+                                "root".into(),
+                                DEFAULT_BLOBSTORE_ROOT_DIR.into(),
+                            )])),
+                        });
+                    }
+                    // Use the default bucket for the NATS KV store
+                    (WIT_NS_WASI, "keyvalue", interfaces)
+                        if image_ref.as_ref().is_some_and(|image_ref| {
+                            image_ref == DEFAULT_KEYVALUE_PROVIDER_IMAGE
+                        }) && interfaces.is_some_and(|interfaces| {
+                            interfaces
+                                .iter()
+                                .any(|i| i == "atomics" || i == "store" || i == "batch")
+                        }) =>
+                    {
+                        link_property.target.config.push(ConfigProperty {
+                            name: config_name(namespace.as_str(), package.as_str()),
+                            properties: Some(HashMap::from([
+                                ("bucket".into(), DEFAULT_KEYVALUE_BUCKET.into()),
+                                ("enable_bucket_auto_create".into(), "true".into()),
+                            ])),
+                        });
+                    }
+                    _ => {}
+                }
+
+                link_property
+            }
+            DependencySpec::Imports(DependencySpecInner {
+                wit:
+                    WitInterfaceSpec {
+                        namespace,
+                        package,
+                        interfaces,
+                        ..
+                    },
+                ..
+            }) => {
+                let mut link_property = LinkProperty {
+                    namespace: namespace.clone(),
+                    package: package.clone(),
+                    interfaces: interfaces.clone().unwrap_or_default().into_iter().collect(),
+                    target: TargetConfig {
+                        name: component_name.into(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                };
+
+                // Make interface-specific tweaks to the generated trait
+                match (namespace.as_ref(), package.as_ref(), interfaces.as_ref()) {
+                    (WIT_NS_WASI, "http", interfaces)
+                        if interfaces.is_some_and(|interfaces| {
+                            interfaces.iter().any(|i| i == "incoming-handler")
+                        }) =>
+                    {
+                        link_property
+                            .source
+                            .get_or_insert(Default::default())
+                            .config
+                            .push(ConfigProperty {
+                                name: config_name(namespace.as_str(), package.as_str()),
+                                properties: Some(HashMap::from([(
+                                    "address".into(),
+                                    DEFAULT_INCOMING_HANDLER_ADDRESS.into(),
+                                )])),
+                            });
+                    }
+                    ("wasmcloud", "messaging", interfaces)
+                        if interfaces.is_some_and(|interfaces| {
+                            interfaces.iter().any(|i| i == "handler")
+                        }) =>
+                    {
+                        link_property
+                            .source
+                            .get_or_insert(Default::default())
+                            .config
+                            .push(ConfigProperty {
+                                name: config_name(namespace.as_str(), package.as_str()),
+                                properties: Some(HashMap::from([(
+                                    "subscriptions".into(),
+                                    DEFAULT_MESSAGING_HANDLER_SUBSCRIPTION.into(),
+                                )])),
+                            });
+                    }
+                    _ => {}
+                }
+
+                link_property
+            }
+        }
+    }
+
     /// Convert to a component that can be used in a [`Manifest`] with a given suffix for uniqueness
     fn generate_dep_component(&self, suffix: &str) -> Result<Component> {
         let name = format!("{}-dep-{}", suffix, self.name());
@@ -872,6 +1008,29 @@ impl ProjectDeps {
         Ok(())
     }
 
+    /// Retrieve the names of all dependencies tracked across every project/workspace
+    pub(crate) fn dependency_names(&self) -> BTreeSet<String> {
+        self.dependencies
+            .values()
+            .flatten()
+            .map(DependencySpec::name)
+            .collect()
+    }
+
+    /// Compare the dependencies tracked here against a previous set, returning the names of
+    /// dependencies that were (added, removed)
+    pub(crate) fn diff_dependency_names(
+        &self,
+        previous: &Self,
+    ) -> (BTreeSet<String>, BTreeSet<String>) {
+        let current = self.dependency_names();
+        let previous = previous.dependency_names();
+        (
+            current.difference(&previous).cloned().collect(),
+            previous.difference(&current).cloned().collect(),
+        )
+    }
+
     /// Merge another bundle of dependencies (possibly derived from some other source of metadata)
     ///
     /// Note that the `other` will override the values `self`, where necessary.
@@ -973,13 +1132,11 @@ impl ProjectDeps {
                 DependencySpec::Exports(DependencySpecInner {
                     wit:
                         WitInterfaceSpec {
-                            namespace,
-                            package,
-                            interfaces,
-                            version,
+                            ref namespace,
+                            ref package,
+                            ref interfaces,
                             ..
                         },
-                    image_ref,
                     ..
                 }) => {
                     // Check to see if this link (namespace, package, target) already exists,
@@ -990,8 +1147,8 @@ impl ProjectDeps {
                         .iter_mut()
                         .any(|trt| {
                             if let TraitProperty::Link(link) = &mut trt.properties {
-                                if link.namespace == namespace
-                                    && link.package == package
+                                if link.namespace == *namespace
+                                    && link.package == *package
                                     && link.target.name == dep_component.name
                                 {
                                     if let Some(interface) = interfaces.clone() {
@@ -1006,141 +1163,23 @@ impl ProjectDeps {
                         continue;
                     }
 
-                    // Build the relevant app->dep link trait
-                    let mut link_property = LinkProperty {
-                        namespace: namespace.clone(),
-                        package: package.clone(),
-                        interfaces: interfaces.clone().unwrap_or_default().into_iter().collect(),
-                        target: TargetConfig {
-                            name: dep_component.name.clone(),
-                            ..Default::default()
-                        },
-                        ..Default::default()
-                    };
-
-                    // Make interface-specific changes
-                    match (
-                        namespace.as_ref(),
-                        package.as_ref(),
-                        interfaces.as_ref(),
-                        version,
-                    ) {
-                        (WIT_NS_WASI, "blobstore", interfaces, _)
-                        | (WIT_NS_WRPC, "blobstore", interfaces, _)
-                            if interfaces.is_some_and(|interfaces| {
-                                interfaces.iter().any(|i| i == "blobstore")
-                            }) =>
-                        {
-                            link_property.target.config.push(ConfigProperty {
-                                name: config_name(namespace.as_str(), package.as_str()),
-                                properties: Some(HashMap::from([(
-                                    "root".into(),
-                                    DEFAULT_BLOBSTORE_ROOT_DIR.into(),
-                                )])),
-                            });
-                        }
-                        // Use the default bucket for the NATS KV store
-                        (WIT_NS_WASI, "keyvalue", interfaces, _)
-                            if image_ref.as_ref().is_some_and(|image_ref| {
-                                image_ref == DEFAULT_KEYVALUE_PROVIDER_IMAGE
-                            }) && interfaces.is_some_and(|interfaces| {
-                                interfaces
-                                    .iter()
-                                    .any(|i| i == "atomics" || i == "store" || i == "batch")
-                            }) =>
-                        {
-                            link_property.target.config.push(ConfigProperty {
-                                name: config_name(namespace.as_str(), package.as_str()),
-                                properties: Some(HashMap::from([
-                                    ("bucket".into(), DEFAULT_KEYVALUE_BUCKET.into()),
-                                    ("enable_bucket_auto_create".into(), "true".into()),
-                                ])),
-                            });
-                        }
-                        _ => {}
-                    }
-
+                    // Build the relevant app->dep link trait and add it to the app component
                     let link_trait = wadm_types::Trait {
                         trait_type: "link".into(),
-                        properties: TraitProperty::Link(link_property),
+                        properties: TraitProperty::Link(
+                            dep.generate_link_property(&component.name, &dep_component.name),
+                        ),
                     };
-
-                    // Add the trait to the app component
                     component.traits.get_or_insert(Vec::new()).push(link_trait);
                 }
-                DependencySpec::Imports(DependencySpecInner {
-                    wit:
-                        WitInterfaceSpec {
-                            namespace,
-                            package,
-                            interfaces,
-                            version,
-                            ..
-                        },
-                    ..
-                }) => {
-                    // Build the relevant dep->app link trait
-                    let mut link_property = LinkProperty {
-                        namespace: namespace.clone(),
-                        package: package.clone(),
-                        interfaces: interfaces.clone().unwrap_or_default().into_iter().collect(),
-                        target: TargetConfig {
-                            name: component.name.clone(),
-                            ..Default::default()
-                        },
-                        ..Default::default()
-                    };
-
-                    // Make interface-specific tweaks to the generated trait
-                    match (
-                        namespace.as_ref(),
-                        package.as_ref(),
-                        interfaces.as_ref(),
-                        version,
-                    ) {
-                        (WIT_NS_WASI, "http", interfaces, _)
-                            if interfaces.is_some_and(|interfaces| {
-                                interfaces.iter().any(|i| i == "incoming-handler")
-                            }) =>
-                        {
-                            link_property
-                                .source
-                                .get_or_insert(Default::default())
-                                .config
-                                .push(ConfigProperty {
-                                    name: config_name(namespace.as_str(), package.as_str()),
-                                    properties: Some(HashMap::from([(
-                                        "address".into(),
-                                        DEFAULT_INCOMING_HANDLER_ADDRESS.into(),
-                                    )])),
-                                });
-                        }
-                        ("wasmcloud", "messaging", interfaces, _)
-                            if interfaces.is_some_and(|interfaces| {
-                                interfaces.iter().any(|i| i == "handler")
-                            }) =>
-                        {
-                            link_property
-                                .source
-                                .get_or_insert(Default::default())
-                                .config
-                                .push(ConfigProperty {
-                                    name: config_name(namespace.as_str(), package.as_str()),
-                                    properties: Some(HashMap::from([(
-                                        "subscriptions".into(),
-                                        DEFAULT_MESSAGING_HANDLER_SUBSCRIPTION.into(),
-                                    )])),
-                                });
-                        }
-                        _ => {}
-                    }
-
+                DependencySpec::Imports(_) => {
+                    // Build the relevant dep->app link trait and add it to the dependency
                     let link_trait = wadm_types::Trait {
                         trait_type: "link".into(),
-                        properties: TraitProperty::Link(link_property),
+                        properties: TraitProperty::Link(
+                            dep.generate_link_property(&component.name, &dep_component.name),
+                        ),
                     };
-
-                    // Add the trait
                     dep_component
                         .traits
                         .get_or_insert(Vec::new())
@@ -1,4 +1,4 @@
-use std::collections::{BTreeSet, HashMap};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use anyhow::{bail, ensure, Context as _, Result};
@@ -21,7 +21,7 @@ use wash_lib::parser::{
 use crate::app::deploy_model_from_manifest;
 use crate::appearance::spinner::Spinner;
 
-use super::deps::{DependencySpec, ProjectDependencyKey, ProjectDeps};
+use super::deps::{ProjectDependencyKey, ProjectDeps};
 use super::manifest::{generate_component_from_project_cfg, generate_help_text_for_manifest};
 use super::session::WashDevSession;
 use super::wit::{discover_dependencies_from_wit, parse_component_wit, parse_project_wit};
@@ -98,12 +98,7 @@ pub(crate) async fn generate_manifests(
     eprintln!(
         "{} Detected component dependencies: {:?}",
         emoji::INFO_SQUARE,
-        current_project_deps
-            .dependencies
-            .values()
-            .flatten()
-            .map(DependencySpec::name)
-            .collect::<BTreeSet<String>>()
+        current_project_deps.dependency_names()
     );
 
     // After we've merged, we can update the session ID to belong to this session
@@ -126,6 +121,23 @@ pub(crate) async fn generate_manifests(
         return Ok(Vec::new());
     }
 
+    // Let the user know which providers will be started or stopped as a result of WIT changes
+    if let Some(previous_deps) = previous_deps.as_ref() {
+        let (added, removed) = current_project_deps.diff_dependency_names(previous_deps);
+        if !added.is_empty() {
+            eprintln!(
+                "{} New dependencies inferred, starting & linking: {added:?}",
+                emoji::INFO_SQUARE,
+            );
+        }
+        if !removed.is_empty() {
+            eprintln!(
+                "{} Dependencies no longer required, removing: {removed:?}",
+                emoji::INFO_SQUARE,
+            );
+        }
+    }
+
     // Convert the project deps into a fully-baked WADM manifests
     let manifests = current_project_deps
         .generate_wadm_manifests()
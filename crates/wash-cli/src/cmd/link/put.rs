@@ -4,7 +4,7 @@ use std::collections::HashMap;
 
 use anyhow::{anyhow, bail, Result};
 use serde_json::json;
-use wash_lib::cli::link::{put_link, LinkPutCommand};
+use wash_lib::cli::link::{put_link, validate_link, LinkPutCommand};
 use wash_lib::cli::{CommandOutput, OutputKind};
 use wasmcloud_control_interface::Link;
 
@@ -22,6 +22,7 @@ pub async fn invoke(
         interfaces,
         source_config,
         target_config,
+        validate,
     }: LinkPutCommand,
     output_kind: OutputKind,
 ) -> Result<CommandOutput> {
@@ -29,23 +30,37 @@ pub async fn invoke(
     sp.update_spinner_message(format!("Defining link {source_id} -> {target} ... ",));
 
     let name = link_name.unwrap_or_else(|| "default".to_string());
+    let link = Link::builder()
+        .source_id(&source_id)
+        .target(&target)
+        .name(&name)
+        .wit_namespace(&wit_namespace)
+        .wit_package(&wit_package)
+        .interfaces(interfaces)
+        .source_config(source_config)
+        .target_config(target_config)
+        .build()
+        .map_err(|e| anyhow!(e).context("failed to build link"))?;
 
-    let failure = put_link(
-        opts.try_into()?,
-        Link::builder()
-            .source_id(&source_id)
-            .target(&target)
-            .name(&name)
-            .wit_namespace(&wit_namespace)
-            .wit_package(&wit_package)
-            .interfaces(interfaces)
-            .source_config(source_config)
-            .target_config(target_config)
-            .build()
-            .map_err(|e| anyhow!(e).context("failed to build link"))?,
-    )
-    .await
-    .map_or_else(
+    if validate {
+        sp.update_spinner_message(format!("Validating link {source_id} -> {target} ... ",));
+        let validation = validate_link(opts.clone().try_into()?, link.clone()).await?;
+        if !validation.is_valid() {
+            sp.finish_and_clear();
+            let errors = validation
+                .errors()
+                .iter()
+                .map(|err| match err.field() {
+                    Some(field) => format!("\n  - {field}: {}", err.message()),
+                    None => format!("\n  - {}", err.message()),
+                })
+                .collect::<String>();
+            bail!("Link ({source_id}) -> ({target}) failed validation, not putting it:{errors}");
+        }
+        sp.update_spinner_message(format!("Defining link {source_id} -> {target} ... ",));
+    }
+
+    let failure = put_link(opts.try_into()?, link).await.map_or_else(
         |e| Some(format!("{e}")),
         // If the operation was unsuccessful, return the error message
         |ctl_response| (!ctl_response.succeeded()).then_some(ctl_response.message().to_string()),
@@ -4,12 +4,12 @@ use std::collections::HashMap;
 
 use anyhow::Result;
 use serde_json::json;
-use wash_lib::cli::link::{get_links, LinkQueryCommand};
+use wash_lib::cli::link::{get_component_routes, get_links, InterfaceRoute, LinkQueryCommand};
 use wash_lib::cli::{CommandOutput, OutputKind};
 use wasmcloud_control_interface::Link;
 
 use crate::appearance::spinner::Spinner;
-use crate::ctl::links_table;
+use crate::ctl::{component_routes_table, links_table};
 
 /// Generate output for the `wash link query` command
 pub fn link_query_output(list: Vec<Link>) -> CommandOutput {
@@ -17,12 +17,30 @@ pub fn link_query_output(list: Vec<Link>) -> CommandOutput {
     CommandOutput::new(links_table(list), map)
 }
 
+/// Generate output for the `wash link query <component-id>` command
+pub fn component_routes_output(component_id: &str, routes: Vec<InterfaceRoute>) -> CommandOutput {
+    let map = HashMap::from([
+        ("component_id".to_string(), json!(component_id)),
+        ("routes".to_string(), json!(routes)),
+    ]);
+    CommandOutput::new(component_routes_table(routes), map)
+}
+
 /// Invoke `wash link del` subcommand
 pub async fn invoke(
-    LinkQueryCommand { opts }: LinkQueryCommand,
+    LinkQueryCommand {
+        opts,
+        component_id,
+        insecure,
+    }: LinkQueryCommand,
     output_kind: OutputKind,
 ) -> Result<CommandOutput> {
     let sp: Spinner = Spinner::new(&output_kind)?;
+    if let Some(component_id) = component_id {
+        sp.update_spinner_message(format!("Querying routes of {component_id} ... "));
+        let routes = get_component_routes(opts.try_into()?, &component_id, insecure).await?;
+        return Ok(component_routes_output(&component_id, routes));
+    }
     sp.update_spinner_message("Querying Links ... ".to_string());
     let result = get_links(opts.try_into()?).await?;
     Ok(link_query_output(result))
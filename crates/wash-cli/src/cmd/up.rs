@@ -62,6 +62,11 @@ pub struct UpCommand {
     #[clap(short = 'd', long = "detached", alias = "detach")]
     pub detached: bool,
 
+    /// Show an interactive terminal dashboard of the hosts, components, providers, links and
+    /// events of the lattice instead of the host logs
+    #[clap(long = "ui", conflicts_with = "detached")]
+    pub ui: bool,
+
     #[clap(flatten)]
     pub nats_opts: NatsOpts,
 
@@ -493,6 +498,7 @@ pub async fn handle_up(cmd: UpCommand, output_kind: OutputKind) -> Result<Comman
         .clone()
         .unwrap_or_else(|| install_dir.join("wasmcloud.log"));
     let ctl_client = wasmcloud_opts.clone().into_ctl_client(None).await?;
+    let dashboard_client = cmd.ui.then(|| ctl_client.clone());
 
     if !cmd.wasmcloud_opts.multi_local
         && tokio::fs::try_exists(host_pid_file()?)
@@ -752,6 +758,7 @@ pub async fn handle_up(cmd: UpCommand, output_kind: OutputKind) -> Result<Comman
         cmd.wadm_opts.wadm_manifest,
         host_started.clone(),
         output_kind,
+        dashboard_client,
     )
     .await?;
 
@@ -992,12 +999,14 @@ async fn install_patch_or_default_wadm_version(
     }
 }
 
-/// Helper function to run wasmCloud in interactive mode
+/// Helper function to run wasmCloud in interactive mode. If a `dashboard` client is given, the
+/// terminal dashboard is shown instead of the host logs, until the user quits it
 async fn run_wasmcloud_interactive(
     wasmcloud_child: &mut Child,
     wadm_manifest: Option<PathBuf>,
     host_started: Arc<AtomicBool>,
     output_kind: OutputKind,
+    dashboard: Option<CtlClient>,
 ) -> Result<()> {
     use std::sync::mpsc::channel;
     let (running_sender, running_receiver) = channel();
@@ -1018,7 +1027,7 @@ async fn run_wasmcloud_interactive(
         Result::<_, anyhow::Error>::Ok(())
     });
 
-    if output_kind != OutputKind::Json {
+    if output_kind != OutputKind::Json && dashboard.is_none() {
         println!("🏃 Running in interactive mode.");
         if let Some(ref manifest_path) = wadm_manifest {
             println!(
@@ -1030,14 +1039,17 @@ async fn run_wasmcloud_interactive(
         println!("🚪 Press `CTRL+c` at any time to exit");
     }
 
-    // Create a separate thread to log host output
+    // Create a separate thread to log host output, which is discarded while the dashboard is shown
+    let print_logs = dashboard.is_none();
     let handle = wasmcloud_child.stderr.take().map(|stderr| {
-        tokio::spawn(async {
+        tokio::spawn(async move {
             let mut lines = BufReader::new(stderr).lines();
             loop {
                 if let Ok(Some(line)) = lines.next_line().await {
-                    // TODO(brooksmtownsend): in the future, would be great to print these in a prettier format
-                    println!("{line}");
+                    if print_logs {
+                        // TODO(brooksmtownsend): in the future, would be great to print these in a prettier format
+                        println!("{line}");
+                    }
                 }
             }
         })
@@ -1046,8 +1058,13 @@ async fn run_wasmcloud_interactive(
     // Mark the host as started
     host_started.store(true, Ordering::SeqCst);
 
-    // Wait for the user to send Ctrl+C in a thread where blocking is acceptable
-    let _ = running_receiver.recv();
+    if let Some(client) = dashboard {
+        // The dashboard handles CTRL+c itself, since the terminal is in raw mode
+        crate::ui::dashboard::run(client).await?;
+    } else {
+        // Wait for the user to send Ctrl+C in a thread where blocking is acceptable
+        let _ = running_receiver.recv();
+    }
 
     // Prevent extraneous messages from the host getting printed as the host shuts down
     if let Some(handle) = handle {
@@ -1320,4 +1337,12 @@ mod tests {
             "Invalid pid should not be running"
         );
     }
+
+    #[test]
+    fn test_up_ui() {
+        let cmd: UpCommand = Parser::try_parse_from(["up", "--ui"]).unwrap();
+        assert!(cmd.ui);
+        assert!(!cmd.detached);
+        assert!(UpCommand::try_parse_from(["up", "--ui", "--detached"]).is_err());
+    }
 }
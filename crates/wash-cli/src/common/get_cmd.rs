@@ -3,11 +3,17 @@ use crossterm::{
     cursor, execute,
     terminal::{Clear, ClearType},
 };
-use std::{collections::HashMap, io::Write, time::Duration};
+use std::{
+    collections::HashMap,
+    future::Future,
+    io::Write,
+    time::{Duration, Instant},
+};
 use tokio::time::sleep;
 use wash_lib::cli::claims::get_claims;
 use wash_lib::cli::get::{
-    get_host_inventories, get_hosts, GetCommand, GetHostInventoriesCommand, GetLinksCommand,
+    get_config_schema, get_host_inventories, get_hosts, GetCommand, GetHostInventoriesCommand,
+    GetHostsCommand, GetLinksCommand,
 };
 use wash_lib::cli::link::{LinkCommand, LinkQueryCommand};
 use wash_lib::cli::{CommandOutput, OutputKind};
@@ -15,13 +21,25 @@ use wash_lib::cli::{CommandOutput, OutputKind};
 use crate::appearance::spinner::Spinner;
 use crate::cmd::link::invoke as invoke_link_cmd;
 use crate::ctl::{
-    get_claims_output, get_host_inventories_output, get_hosts_output, host_inventories_table,
+    get_claims_output, get_config_schema_output, get_host_inventories_output, get_hosts_output,
+    watched_host_inventories_table, watched_hosts_table, LastSeen,
 };
 
+/// Time for which hosts that stopped responding are still listed while watching
+const STALE_HOST_RETENTION: Duration = Duration::from_secs(300);
+
 pub async fn handle_command(command: GetCommand, output_kind: OutputKind) -> Result<CommandOutput> {
     let out: CommandOutput = match command {
         GetCommand::Links(GetLinksCommand { opts }) => {
-            invoke_link_cmd(LinkCommand::Query(LinkQueryCommand { opts }), output_kind).await?
+            invoke_link_cmd(
+                LinkCommand::Query(LinkQueryCommand {
+                    opts,
+                    component_id: None,
+                    insecure: false,
+                }),
+                output_kind,
+            )
+            .await?
         }
         GetCommand::Claims(cmd) => {
             let sp: Spinner = Spinner::new(&output_kind)?;
@@ -32,8 +50,7 @@ pub async fn handle_command(command: GetCommand, output_kind: OutputKind) -> Res
         GetCommand::Hosts(cmd) => {
             let sp: Spinner = Spinner::new(&output_kind)?;
             sp.update_spinner_message(" Retrieving Hosts ...".to_string());
-            let hosts = get_hosts(cmd).await?;
-            get_hosts_output(hosts)
+            get_hosts_handler(cmd, sp).await?
         }
         GetCommand::HostInventories(cmd) => {
             let sp: Spinner = Spinner::new(&output_kind)?;
@@ -44,17 +61,60 @@ pub async fn handle_command(command: GetCommand, output_kind: OutputKind) -> Res
             }
             get_inventory_handler(cmd, sp).await?
         }
+        GetCommand::ConfigSchema(cmd) => {
+            let sp: Spinner = Spinner::new(&output_kind)?;
+            sp.update_spinner_message(format!(
+                " Retrieving config schema of provider {} ...",
+                cmd.provider_id
+            ));
+            let provider_id = cmd.provider_id.clone();
+            let schema = get_config_schema(cmd).await?;
+            get_config_schema_output(&provider_id, schema)
+        }
     };
 
     Ok(out)
 }
 
+async fn get_hosts_handler(cmd: GetHostsCommand, sp: Spinner) -> Result<CommandOutput> {
+    if let Some(interval) = cmd.watch {
+        let mut hosts = WatchedHosts::default();
+        watch(
+            sp,
+            interval,
+            || get_hosts(cmd.clone()),
+            |update| watched_hosts_table(hosts.update(update, |h| h.id(), Instant::now())),
+        )
+        .await?;
+        Ok(CommandOutput::new(
+            "Completed Watching Hosts".to_string(),
+            HashMap::new(),
+        ))
+    } else {
+        let hosts = get_hosts(cmd).await?;
+        Ok(get_hosts_output(hosts))
+    }
+}
+
 async fn get_inventory_handler(
     cmd: GetHostInventoriesCommand,
     sp: Spinner,
 ) -> Result<CommandOutput> {
-    if cmd.watch.is_some() {
-        watch_inventory(cmd, sp).await?;
+    if let Some(interval) = cmd.watch {
+        let mut invs = WatchedHosts::default();
+        watch(
+            sp,
+            interval,
+            || get_host_inventories(cmd.clone()),
+            |update| {
+                watched_host_inventories_table(invs.update(
+                    update,
+                    |inv| inv.host_id(),
+                    Instant::now(),
+                ))
+            },
+        )
+        .await?;
         Ok(CommandOutput::new(
             "Completed Watching Inventory".to_string(),
             HashMap::new(),
@@ -65,23 +125,62 @@ async fn get_inventory_handler(
     }
 }
 
-async fn watch_inventory(cmd: GetHostInventoriesCommand, sp: Spinner) -> Result<()> {
-    let mut stdout = std::io::stdout();
-    let invs = get_host_inventories(cmd.clone()).await?;
-    sp.finish_and_clear();
-    execute!(stdout, Clear(ClearType::FromCursorUp), cursor::MoveTo(0, 0))
-        .map_err(|e| anyhow::anyhow!("Failed to clear terminal: {}", e))?;
-    let output = host_inventories_table(invs);
-    stdout
-        .write_all(output.as_bytes())
-        .map_err(|e| anyhow::anyhow!("Failed to write inventory to stdout: {}", e))?;
+/// Hosts (or their inventories) listed while watching, which are retained for a while after they
+/// stop responding, so that operators notice dead hosts
+struct WatchedHosts<T> {
+    hosts: HashMap<String, (T, Instant)>,
+}
 
+impl<T> Default for WatchedHosts<T> {
+    fn default() -> Self {
+        Self {
+            hosts: HashMap::default(),
+        }
+    }
+}
+
+impl<T: Clone> WatchedHosts<T> {
+    /// Records the hosts that responded at `now`, returning all listed hosts along with the time
+    /// since they were last seen, if they did not respond
+    fn update(
+        &mut self,
+        hosts: Vec<T>,
+        id: impl Fn(&T) -> &str,
+        now: Instant,
+    ) -> Vec<(T, LastSeen)> {
+        for host in hosts {
+            self.hosts.insert(id(&host).to_string(), (host, now));
+        }
+        self.hosts
+            .retain(|_, (_, seen)| now.duration_since(*seen) <= STALE_HOST_RETENTION);
+        self.hosts
+            .values()
+            .map(|(host, seen)| {
+                let last_seen = (*seen < now).then(|| now.duration_since(*seen));
+                (host.clone(), last_seen)
+            })
+            .collect()
+    }
+}
+
+/// Repeatedly fetches a listing with `fetch` every `interval` and prints it as rendered by
+/// `render`, until interrupted with Ctrl-C
+async fn watch<T, Fut>(
+    sp: Spinner,
+    interval: Duration,
+    mut fetch: impl FnMut() -> Fut,
+    mut render: impl FnMut(T) -> String,
+) -> Result<()>
+where
+    Fut: Future<Output = Result<T>>,
+{
+    let mut stdout = std::io::stdout();
     let mut ctrlc = std::pin::pin!(tokio::signal::ctrl_c());
-    let watch_interval = cmd.watch.unwrap_or(Duration::from_millis(5000));
+    let mut first = true;
 
     loop {
-        let invs = tokio::select! {
-            res = get_host_inventories(cmd.clone()) => res?,
+        let update = tokio::select! {
+            res = fetch() => res?,
             res = &mut ctrlc => {
                 res?;
                 execute!(stdout, Clear(ClearType::Purge),Clear(ClearType::FromCursorUp), cursor::MoveTo(0, 0), cursor::Show)
@@ -92,13 +191,20 @@ async fn watch_inventory(cmd: GetHostInventoriesCommand, sp: Spinner) -> Result<
             }
         };
 
-        execute!(stdout, Clear(ClearType::Purge), cursor::MoveTo(0, 0))
-            .map_err(|e| anyhow::anyhow!("Failed to execute terminal commands: {}", e))?;
+        if first {
+            first = false;
+            sp.finish_and_clear();
+            execute!(stdout, Clear(ClearType::FromCursorUp), cursor::MoveTo(0, 0))
+                .map_err(|e| anyhow::anyhow!("Failed to clear terminal: {}", e))?;
+        } else {
+            execute!(stdout, Clear(ClearType::Purge), cursor::MoveTo(0, 0))
+                .map_err(|e| anyhow::anyhow!("Failed to execute terminal commands: {}", e))?;
+        }
 
-        let output = host_inventories_table(invs);
+        let output = render(update);
         stdout
             .write_all(output.as_bytes())
-            .map_err(|e| anyhow::anyhow!("Failed to write inventory to stdout: {}", e))?;
+            .map_err(|e| anyhow::anyhow!("Failed to write to stdout: {}", e))?;
 
         stdout
             .flush()
@@ -112,7 +218,7 @@ async fn watch_inventory(cmd: GetHostInventoriesCommand, sp: Spinner) -> Result<
         .map_err(|e| anyhow::anyhow!("Failed to clear terminal: {}", e))?;
 
         tokio::select! {
-            _ = sleep(watch_interval) => continue,
+            _ = sleep(interval) => continue,
             res = &mut ctrlc => {
                 res?;
                 execute!(stdout, Clear(ClearType::Purge),Clear(ClearType::FromCursorUp), cursor::MoveTo(0, 0), cursor::Show)
@@ -124,3 +230,27 @@ async fn watch_inventory(cmd: GetHostInventoriesCommand, sp: Spinner) -> Result<
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn watched_hosts_are_retained() {
+        let mut hosts = WatchedHosts::default();
+        let start = Instant::now();
+        let listed = hosts.update(vec!["a", "b"], |h| h, start);
+        assert!(listed.iter().all(|(_, last_seen)| last_seen.is_none()));
+
+        let now = start + Duration::from_secs(5);
+        let mut listed = hosts.update(vec!["a"], |h| h, now);
+        listed.sort();
+        assert_eq!(
+            listed,
+            vec![("a", None), ("b", Some(Duration::from_secs(5)))]
+        );
+
+        let now = start + STALE_HOST_RETENTION + Duration::from_secs(1);
+        assert_eq!(hosts.update(vec!["a"], |h| h, now), vec![("a", None)]);
+    }
+}
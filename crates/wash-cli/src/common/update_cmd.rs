@@ -1,7 +1,7 @@
 use anyhow::Result;
 
 use wash_lib::cli::{
-    update::{handle_update_component, UpdateCommand},
+    update::{handle_update_component, handle_update_provider, UpdateCommand},
     CommandOutput, OutputKind,
 };
 
@@ -21,6 +21,14 @@ pub async fn handle_command(
 
             handle_update_component(cmd).await?
         }
+        UpdateCommand::Provider(cmd) => {
+            sp.update_spinner_message(format!(
+                " Updating Provider {} to {} ... ",
+                cmd.provider_id, cmd.new_provider_ref
+            ));
+
+            handle_update_provider(cmd).await?
+        }
     };
 
     sp.finish_and_clear();
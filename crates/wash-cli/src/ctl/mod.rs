@@ -30,7 +30,7 @@ pub enum CtlCliCommand {
     #[clap(name = "stop", subcommand)]
     Stop(StopCommand),
 
-    /// Update an component running in a host to a new component
+    /// Update a component or provider running in a host to a newer version
     #[clap(name = "update", subcommand)]
     Update(UpdateCommand),
 
@@ -61,7 +61,7 @@ mod test {
         get::GetHostsCommand,
         scale::ScaleComponentCommand,
         stop::{StopComponentCommand, StopProviderCommand},
-        update::UpdateComponentCommand,
+        update::{UpdateComponentCommand, UpdateProviderCommand},
     };
 
     use super::*;
@@ -192,11 +192,12 @@ mod test {
             "2001",
         ])?;
         match get_hosts_all.command {
-            CtlCliCommand::Get(CtlGetCommand::Hosts(GetHostsCommand { opts })) => {
+            CtlCliCommand::Get(CtlGetCommand::Hosts(GetHostsCommand { opts, watch })) => {
                 assert_eq!(&opts.ctl_host.unwrap(), CTL_HOST);
                 assert_eq!(&opts.ctl_port.unwrap(), CTL_PORT);
                 assert_eq!(&opts.lattice.unwrap(), DEFAULT_LATTICE);
                 assert_eq!(opts.timeout_ms, 2001);
+                assert_eq!(watch, None);
             }
             cmd => panic!("ctl get hosts constructed incorrect command {cmd:?}"),
         }
@@ -286,6 +287,7 @@ mod test {
                 source_config,
                 target_config,
                 link_name,
+                validate,
             })) => {
                 assert_eq!(&opts.ctl_host.unwrap(), CTL_HOST);
                 assert_eq!(&opts.ctl_port.unwrap(), CTL_PORT);
@@ -299,6 +301,7 @@ mod test {
                 assert_eq!(interfaces.as_slice(), &["foo".to_string()]);
                 assert!(source_config.is_empty());
                 assert!(target_config.is_empty());
+                assert!(!validate);
             }
             cmd => panic!("ctl link put constructed incorrect command {cmd:?}"),
         }
@@ -340,6 +343,29 @@ mod test {
             cmd => panic!("ctl get claims constructed incorrect command {cmd:?}"),
         }
 
+        let update_provider: Cmd = Parser::try_parse_from([
+            "ctl",
+            "update",
+            "provider",
+            "--host-id",
+            HOST_ID,
+            PROVIDER_ID,
+            "wasmcloud.azurecr.io/provider:v2",
+        ])?;
+        match update_provider.command {
+            CtlCliCommand::Update(UpdateCommand::Provider(UpdateProviderCommand {
+                host_id,
+                provider_id,
+                new_provider_ref,
+                ..
+            })) => {
+                assert_eq!(host_id, Some(HOST_ID.to_string()));
+                assert_eq!(provider_id, PROVIDER_ID);
+                assert_eq!(new_provider_ref, "wasmcloud.azurecr.io/provider:v2");
+            }
+            cmd => panic!("ctl update provider constructed incorrect command {cmd:?}"),
+        }
+
         let scale_component_all: Cmd = Parser::try_parse_from([
             "ctl",
             "scale",
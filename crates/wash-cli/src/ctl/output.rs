@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::time::Duration;
 
 use serde_json::json;
 use term_table::{
@@ -6,9 +7,14 @@ use term_table::{
     table_cell::{Alignment, TableCell},
     Table,
 };
-use wash_lib::{cli::CommandOutput, plugin::subcommand::Metadata};
+use wash_lib::{
+    cli::{link::InterfaceRoute, CommandOutput},
+    plugin::subcommand::Metadata,
+};
 use wasmcloud_control_interface::{Host, HostInventory, Link};
+use wasmcloud_core::ConfigSchema;
 
+use crate::plugin::PluginIndexEntry;
 use crate::util::format_optional;
 
 pub fn get_hosts_output(hosts: Vec<Host>) -> CommandOutput {
@@ -29,6 +35,16 @@ pub fn get_claims_output(claims: Vec<HashMap<String, String>>) -> CommandOutput
     CommandOutput::new(claims_table(claims), map)
 }
 
+pub fn get_config_schema_output(provider_id: &str, schema: Option<ConfigSchema>) -> CommandOutput {
+    let mut map = HashMap::new();
+    map.insert("schema".to_string(), json!(schema));
+    let text = match schema {
+        Some(schema) => config_schema_table(schema),
+        None => format!("Provider [{provider_id}] does not declare a config schema"),
+    };
+    CommandOutput::new(text, map)
+}
+
 pub fn links_table(mut list: Vec<Link>) -> String {
     // Sort the list based on the `source_id` field in ascending order
     list.sort_by(|a, b| a.source_id().cmp(b.source_id()));
@@ -64,26 +80,162 @@ pub fn links_table(mut list: Vec<Link>) -> String {
     table.render()
 }
 
+/// Helper function to transform the routes of the interfaces imported by a component into a
+/// table string for printing
+pub fn component_routes_table(routes: Vec<InterfaceRoute>) -> String {
+    let mut table = Table::new();
+    crate::util::configure_table_style(&mut table, 5);
+
+    table.add_row(Row::new(vec![
+        TableCell::new_with_alignment("Interface", 1, Alignment::Left),
+        TableCell::new_with_alignment("Target", 1, Alignment::Left),
+        TableCell::new_with_alignment("Name", 1, Alignment::Left),
+        TableCell::new_with_alignment("Config", 1, Alignment::Left),
+        TableCell::new_with_alignment("Status", 1, Alignment::Left),
+    ]));
+
+    routes.into_iter().for_each(|route| {
+        let config = [
+            (!route.source_config.is_empty())
+                .then(|| format!("source: {}", route.source_config.join(","))),
+            (!route.target_config.is_empty())
+                .then(|| format!("target: {}", route.target_config.join(","))),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join("\n");
+        table.add_row(Row::new(vec![
+            TableCell::new_with_alignment(route.interface, 1, Alignment::Left),
+            TableCell::new_with_alignment(format_optional(route.target), 1, Alignment::Left),
+            TableCell::new_with_alignment(format_optional(route.link_name), 1, Alignment::Left),
+            TableCell::new_with_alignment(config, 1, Alignment::Left),
+            TableCell::new_with_alignment(route.health.to_string(), 1, Alignment::Left),
+        ]))
+    });
+
+    table.render()
+}
+
 /// Helper function to transform a Host list into a table string for printing
-pub fn hosts_table(mut hosts: Vec<Host>) -> String {
+pub fn hosts_table(hosts: Vec<Host>) -> String {
+    render_hosts_table(hosts.into_iter().map(|h| (h, None)).collect(), false)
+}
+
+/// Helper function to transform watched hosts into a table string for printing, along with when
+/// they were last seen
+pub fn watched_hosts_table(hosts: Vec<(Host, LastSeen)>) -> String {
+    render_hosts_table(hosts, true)
+}
+
+fn render_hosts_table(mut hosts: Vec<(Host, LastSeen)>, show_last_seen: bool) -> String {
     // Sort hosts by uptime_seconds in descending order
-    // hosts.sort_by(|a, b| b.uptime_seconds().cmp(&a.uptime_seconds()));
-    hosts.sort_by_key(|a| std::cmp::Reverse(a.uptime_seconds()));
+    hosts.sort_by_key(|(h, _)| std::cmp::Reverse(h.uptime_seconds()));
+    let newest = newest_version(hosts.iter().filter_map(|(h, _)| h.version()));
 
     let mut table = Table::new();
-    crate::util::configure_table_style(&mut table, 4);
+    crate::util::configure_table_style(&mut table, if show_last_seen { 6 } else { 5 });
 
-    table.add_row(Row::new(vec![
+    let mut header = vec![
         TableCell::new_with_alignment("Host ID", 2, Alignment::Left),
         TableCell::new_with_alignment("Friendly name", 1, Alignment::Left),
         TableCell::new_with_alignment("Uptime (seconds)", 1, Alignment::Left),
-    ]));
+        TableCell::new_with_alignment("Version", 1, Alignment::Left),
+    ];
+    if show_last_seen {
+        header.push(TableCell::new_with_alignment(
+            "Last seen",
+            1,
+            Alignment::Left,
+        ));
+    }
+    table.add_row(Row::new(header));
 
-    hosts.iter().for_each(|h| {
-        table.add_row(Row::new(vec![
+    hosts.iter().for_each(|(h, last_seen)| {
+        let mut row = vec![
             TableCell::new_with_alignment(h.id().to_string(), 2, Alignment::Left),
             TableCell::new_with_alignment(h.friendly_name().to_string(), 1, Alignment::Left),
             TableCell::new_with_alignment(format!("{}", h.uptime_seconds()), 1, Alignment::Left),
+            TableCell::new_with_alignment(
+                format_version(h.version(), newest.as_ref()),
+                1,
+                Alignment::Left,
+            ),
+        ];
+        if show_last_seen {
+            row.push(TableCell::new_with_alignment(
+                format_last_seen(*last_seen),
+                1,
+                Alignment::Left,
+            ));
+        }
+        table.add_row(Row::new(row));
+    });
+
+    table.render()
+}
+
+/// Time since a watched host was last seen, if it did not respond to the latest poll
+pub type LastSeen = Option<Duration>;
+
+/// Returns the newest of the semantic `versions`, ignoring versions which cannot be parsed
+fn newest_version<'a>(versions: impl IntoIterator<Item = &'a str>) -> Option<semver::Version> {
+    versions.into_iter().filter_map(parse_version).max()
+}
+
+/// Parses a host version, which may be prefixed with `v`
+fn parse_version(version: &str) -> Option<semver::Version> {
+    semver::Version::parse(version.trim_start_matches('v')).ok()
+}
+
+/// Returns whether `version` is older than the `newest` version in the lattice
+fn is_outdated(version: &str, newest: Option<&semver::Version>) -> bool {
+    matches!((parse_version(version), newest), (Some(version), Some(newest)) if version < *newest)
+}
+
+/// Formats `version`, warning if it is older than the `newest` version in the lattice
+fn format_version(version: Option<&str>, newest: Option<&semver::Version>) -> String {
+    match (version, newest) {
+        (Some(version), Some(newest)) if is_outdated(version, Some(newest)) => {
+            format!("{version} ⚠ outdated ({newest} in lattice)")
+        }
+        (version, _) => format_optional(version.map(String::from)),
+    }
+}
+
+/// Formats when a watched host was last seen
+fn format_last_seen(last_seen: LastSeen) -> String {
+    match last_seen {
+        None => "now".into(),
+        Some(elapsed) => format!("⚠ {}s ago, not responding", elapsed.as_secs()),
+    }
+}
+
+/// Helper function to transform a ConfigSchema into a table string for printing
+pub fn config_schema_table(schema: ConfigSchema) -> String {
+    let mut table = Table::new();
+    crate::util::configure_table_style(&mut table, 4);
+
+    table.add_row(Row::new(vec![
+        TableCell::new_with_alignment("Name", 1, Alignment::Left),
+        TableCell::new_with_alignment("Kind", 1, Alignment::Left),
+        TableCell::new_with_alignment("Default", 1, Alignment::Left),
+        TableCell::new_with_alignment("Description", 1, Alignment::Left),
+    ]));
+
+    schema.fields.into_iter().for_each(|field| {
+        let mut name = field.name;
+        if field.required {
+            name.push_str(" (required)");
+        }
+        if field.secret {
+            name.push_str(" (secret)");
+        }
+        table.add_row(Row::new(vec![
+            TableCell::new_with_alignment(name, 1, Alignment::Left),
+            TableCell::new_with_alignment(field.kind.to_string(), 1, Alignment::Left),
+            TableCell::new_with_alignment(format_optional(field.default), 1, Alignment::Left),
+            TableCell::new_with_alignment(format_optional(field.description), 1, Alignment::Left),
         ]))
     });
 
@@ -91,14 +243,30 @@ pub fn hosts_table(mut hosts: Vec<Host>) -> String {
 }
 
 /// Helper function to transform a HostInventory into a table string for printing
-pub fn host_inventories_table(mut invs: Vec<HostInventory>) -> String {
+pub fn host_inventories_table(invs: Vec<HostInventory>) -> String {
+    watched_host_inventories_table(invs.into_iter().map(|inv| (inv, None)).collect())
+}
+
+/// Helper function to transform watched host inventories into a table string for printing,
+/// warning about hosts which stopped responding and outdated hosts and components
+pub fn watched_host_inventories_table(mut invs: Vec<(HostInventory, LastSeen)>) -> String {
     let mut table = Table::new();
     crate::util::configure_table_style(&mut table, 3);
 
     // Sort the host inventories alphabetically by host_id
-    invs.sort_by(|a, b| a.host_id().cmp(b.host_id()));
-
-    invs.into_iter().for_each(|inv| {
+    invs.sort_by(|(a, _), (b, _)| a.host_id().cmp(b.host_id()));
+
+    let newest = newest_version(invs.iter().map(|(inv, _)| inv.version()));
+    // Latest revision of every component in the lattice, to detect outdated components
+    let mut latest_revisions: HashMap<String, i32> = HashMap::new();
+    for c in invs.iter().flat_map(|(inv, _)| inv.components()) {
+        let revision = latest_revisions
+            .entry(c.id().to_string())
+            .or_insert(c.revision());
+        *revision = (*revision).max(c.revision());
+    }
+
+    invs.into_iter().for_each(|(inv, last_seen)| {
         table.add_row(Row::new(vec![
             TableCell::new_with_alignment("Host ID", 2, Alignment::Left),
             TableCell::new_with_alignment("Friendly name", 1, Alignment::Left),
@@ -107,6 +275,23 @@ pub fn host_inventories_table(mut invs: Vec<HostInventory>) -> String {
             TableCell::new_with_alignment(inv.host_id().to_string(), 2, Alignment::Left),
             TableCell::new_with_alignment(inv.friendly_name().to_string(), 1, Alignment::Left),
         ]));
+        if last_seen.is_some() {
+            table.add_row(Row::new(vec![TableCell::new_with_alignment(
+                format!("Last seen {}", format_last_seen(last_seen)),
+                3,
+                Alignment::Left,
+            )]));
+        }
+        if is_outdated(inv.version(), newest.as_ref()) {
+            table.add_row(Row::new(vec![TableCell::new_with_alignment(
+                format!(
+                    "Version {}",
+                    format_version(Some(inv.version()), newest.as_ref())
+                ),
+                3,
+                Alignment::Left,
+            )]));
+        }
 
         // Sort the labels alphabetically by key
         let mut sorted_labels: Vec<_> = inv.labels().iter().collect();
@@ -155,13 +340,19 @@ pub fn host_inventories_table(mut invs: Vec<HostInventory>) -> String {
             ]));
             components.iter().for_each(|a| {
                 let a = a.clone();
+                let mut name = format_optional(a.name().map(String::from));
+                if let Some(latest) = latest_revisions
+                    .get(a.id())
+                    .filter(|latest| **latest > a.revision())
+                {
+                    name.push_str(&format!(
+                        " ⚠ outdated (revision {}, {latest} in lattice)",
+                        a.revision()
+                    ));
+                }
                 table.add_row(Row::new(vec![
                     TableCell::new_with_alignment(a.id(), 1, Alignment::Left),
-                    TableCell::new_with_alignment(
-                        format_optional(a.name().map(String::from)),
-                        1,
-                        Alignment::Left,
-                    ),
+                    TableCell::new_with_alignment(name, 1, Alignment::Left),
                     TableCell::new_with_alignment(a.max_instances(), 1, Alignment::Left),
                 ]))
             });
@@ -197,7 +388,25 @@ pub fn host_inventories_table(mut invs: Vec<HostInventory>) -> String {
                         1,
                         Alignment::Left,
                     ),
-                ]))
+                ]));
+                // Only degraded and unhealthy links are reported by the host
+                p.link_health().iter().for_each(|link| {
+                    table.add_row(Row::new(vec![
+                        TableCell::new_with_alignment(
+                            format!("  ↳ {} ({})", link.source_id(), link.link_name()),
+                            1,
+                            Alignment::Left,
+                        ),
+                        TableCell::new_with_alignment(
+                            match link.message() {
+                                Some(message) => format!("{}: {message}", link.status()),
+                                None => link.status().to_string(),
+                            },
+                            1,
+                            Alignment::Left,
+                        ),
+                    ]))
+                });
             });
         } else {
             table.add_row(Row::new(vec![TableCell::new_with_alignment(
@@ -315,3 +524,33 @@ pub fn plugins_table(list: Vec<&Metadata>) -> String {
 
     table.render()
 }
+
+/// Helper function to transform a list of plugin index entries into a table string for printing
+pub fn plugin_search_table(list: &[PluginIndexEntry]) -> String {
+    let mut table = Table::new();
+    crate::util::configure_table_style(&mut table, 4);
+
+    table.add_row(Row::new(vec![
+        TableCell::new_with_alignment("Name", 1, Alignment::Left),
+        TableCell::new_with_alignment("ID", 1, Alignment::Left),
+        TableCell::new_with_alignment("Version", 1, Alignment::Left),
+        TableCell::new_with_alignment("URL", 1, Alignment::Left),
+    ]));
+    list.iter().for_each(|entry| {
+        table.add_row(Row::new(vec![
+            TableCell::new_with_alignment(&entry.name, 1, Alignment::Left),
+            TableCell::new_with_alignment(&entry.id, 1, Alignment::Left),
+            TableCell::new_with_alignment(&entry.version, 1, Alignment::Left),
+            TableCell::new_with_alignment(&entry.url, 1, Alignment::Left),
+        ]));
+        if !entry.description.is_empty() {
+            table.add_row(Row::new(vec![TableCell::new_with_alignment(
+                format!("  └ {}", entry.description),
+                4,
+                Alignment::Left,
+            )]));
+        }
+    });
+
+    table.render()
+}
@@ -202,9 +202,16 @@ pub async fn handle_down(cmd: DownCommand, output_kind: OutputKind) -> Result<Co
         }
 
         if cmd.purge == PurgeJetstream::All || cmd.purge == PurgeJetstream::Wadm {
+            // Deployment history recorded by wash refers to manifest versions stored by wadm
+            let history_bucket = format!(
+                "{}{}",
+                wash_lib::app::history::HISTORY_BUCKET_PREFIX,
+                &cmd.lattice
+            );
             let kvs = join_all(vec![
                 delete_kv_idempotent(&js_client, "wadm_manifests"),
                 delete_kv_idempotent(&js_client, "wadm_state"),
+                delete_kv_idempotent(&js_client, history_bucket.as_str()),
             ])
             .await;
 
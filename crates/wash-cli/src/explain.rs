@@ -0,0 +1,348 @@
+//! Explanations of errors returned by `wash` and the wasmCloud control interface
+
+use std::collections::HashMap;
+use std::io;
+
+use anyhow::{bail, Result};
+use async_nats::RequestErrorKind;
+use clap::Args;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde_json::json;
+use wash_lib::cli::CommandOutput;
+
+/// A known class of error, identified by a stable code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorCode {
+    /// Stable identifier of the error, e.g. `WASH001`
+    pub code: &'static str,
+    /// Short description of the error
+    pub summary: &'static str,
+    /// Explanation of the circumstances that cause the error
+    pub explanation: &'static str,
+    /// Steps to resolve the error
+    pub troubleshooting: &'static [&'static str],
+    /// Patterns matching the start of error messages that identify the error, ignoring case.
+    /// A pattern must match up to the end of a word
+    patterns: &'static [&'static str],
+}
+
+/// All known error codes
+pub const ERROR_CODES: &[ErrorCode] = &[
+    ErrorCode {
+        code: "WASH001",
+        summary: "No responders for a control interface request",
+        explanation: "No wasmCloud host is subscribed to the control interface subject the request was published on. Either no host is running in the lattice, or the host is connected to a different NATS server, lattice or control topic prefix.",
+        troubleshooting: &[
+            "Run `wash get hosts` to list the hosts in the lattice",
+            "Start a local host with `wash up`",
+            "Ensure `--lattice` and `--ctl-host`/`--ctl-port` (or the current `wash ctx`) match the host configuration",
+        ],
+        patterns: &["no responders"],
+    },
+    ErrorCode {
+        code: "WASH002",
+        summary: "Control interface request timed out",
+        explanation: "A host received the request but did not respond within the timeout. Operations such as starting a component or provider may take longer than the default timeout when artifacts are downloaded from a registry.",
+        troubleshooting: &[
+            "Increase the timeout with `--timeout-ms`",
+            "Check the host logs for errors handling the request",
+        ],
+        patterns: &[
+            r"(?:request )?timed out",
+            r"deadline has elapsed",
+        ],
+    },
+    ErrorCode {
+        code: "WASH003",
+        summary: "No host accepted the auction",
+        explanation: "None of the hosts in the lattice can run the component or provider, either because no host satisfies the given constraints, or because the hosts already run it.",
+        troubleshooting: &[
+            "Run `wash get hosts` and compare the host labels to the `--constraint` flags",
+            "Run `wash get inventory` to check whether the component or provider is already running",
+        ],
+        patterns: &[
+            "no suitable hosts found",
+            "did not receive a response to auction",
+        ],
+    },
+    ErrorCode {
+        code: "WASH004",
+        summary: "Failed to fetch an artifact",
+        explanation: "The host could not download a component or provider from its OCI reference or file path.",
+        troubleshooting: &[
+            "Verify the reference with `wash pull <reference>`",
+            "Ensure the host is allowed to access the registry, e.g. `WASMCLOUD_OCI_ALLOWED_INSECURE` for local registries",
+            "File references require the host to be started with `--allow-file-load`",
+        ],
+        patterns: &[
+            "failed to (?:fetch|pull|download)",
+            "unauthorized",
+        ],
+    },
+    ErrorCode {
+        code: "WASH005",
+        summary: "Component or provider not found",
+        explanation: "The component or provider ID given does not match anything running on the host the request was sent to.",
+        troubleshooting: &[
+            "Run `wash get inventory` to list the IDs of running components and providers",
+            "Check that the request targets the host running the component or provider",
+        ],
+        patterns: &[
+            r"(?:component|provider)(?: \S+)? not found",
+            r"component \S+ is not running on this host",
+        ],
+    },
+    ErrorCode {
+        code: "WASH006",
+        summary: "Invalid link",
+        explanation: "The link definition was rejected, for example because the source and target are the same, the link name is already used for a different target, or the interfaces are empty.",
+        troubleshooting: &[
+            "Run `wash link query` to list existing links",
+            "Use `--link-name` to create multiple links on the same interface",
+        ],
+        patterns: &["invalid link", "link already exists"],
+    },
+    ErrorCode {
+        code: "WASH007",
+        summary: "Configuration or secret not found",
+        explanation: "A component, provider or link refers to named configuration or a secret reference which does not exist.",
+        troubleshooting: &[
+            "Create configuration with `wash config put <name> <key>=<value>`",
+            "Create secret references with `wash secrets put`",
+            "Run `wash config get <name>` to check the configuration exists",
+        ],
+        patterns: &[
+            r"(?:config|configuration)(?: \S+)? not found",
+            r"revision \d+ of configuration \S+ not found",
+            r"secret(?: config \S+)? not found",
+            r"failed to fetch secret",
+        ],
+    },
+    ErrorCode {
+        code: "WASH008",
+        summary: "Denied by policy",
+        explanation: "The policy service configured for the host denied the request.",
+        troubleshooting: &[
+            "Check the decision logs of the policy service",
+            "Verify the claims of the component or provider with `wash inspect`",
+        ],
+        patterns: &["policy denied"],
+    },
+    ErrorCode {
+        code: "WASH009",
+        summary: "Host resource limit reached",
+        explanation: "The host refused to run a component because it would exceed a configured limit, such as the maximum number of components or the maximum component size.",
+        troubleshooting: &[
+            "Start the component on a different host",
+            "Raise the limit with `--max-components` or `--max-component-size` when starting the host",
+        ],
+        patterns: &[
+            r"maximum number of components",
+            r"\S+(?: size)? exceeds the maximum",
+        ],
+    },
+];
+
+/// Look up an error code, ignoring case
+#[must_use]
+pub fn lookup(code: &str) -> Option<&'static ErrorCode> {
+    ERROR_CODES
+        .iter()
+        .find(|error| error.code.eq_ignore_ascii_case(code.trim()))
+}
+
+/// Patterns of [`ERROR_CODES`], anchored at the start of a message and the end of a word
+static PATTERNS: Lazy<Vec<(&'static ErrorCode, Regex)>> = Lazy::new(|| {
+    ERROR_CODES
+        .iter()
+        .flat_map(|error| {
+            error.patterns.iter().map(move |pattern| {
+                let pattern = Regex::new(&format!(r"^(?i:{pattern})(?:$|\W)"))
+                    .expect("error code pattern is invalid");
+                (error, pattern)
+            })
+        })
+        .collect()
+});
+
+/// Look up the error code of a typed error, if any
+fn lookup_error(err: &(dyn std::error::Error + 'static)) -> Option<&'static ErrorCode> {
+    if let Some(err) = err.downcast_ref::<async_nats::RequestError>() {
+        return match err.kind() {
+            RequestErrorKind::NoResponders => lookup("WASH001"),
+            RequestErrorKind::TimedOut => lookup("WASH002"),
+            _ => None,
+        };
+    }
+    if err.is::<tokio::time::error::Elapsed>() {
+        return lookup("WASH002");
+    }
+    match err.downcast_ref::<io::Error>() {
+        Some(err) if err.kind() == io::ErrorKind::TimedOut => lookup("WASH002"),
+        _ => None,
+    }
+}
+
+/// Find the error code that best describes an error message, if any.
+///
+/// Messages of an error chain formatted like `outer: inner` are matched separately
+#[must_use]
+pub fn classify(message: &str) -> Option<&'static ErrorCode> {
+    PATTERNS
+        .iter()
+        .filter_map(|(error, pattern)| {
+            message
+                .split(": ")
+                .filter_map(|message| pattern.find(message.trim()))
+                .map(|m| m.len())
+                .max()
+                .map(|len| (*error, len))
+        })
+        // Prefer the most specific, i.e. longest, matching pattern
+        .max_by(|(a_code, a_len), (b_code, b_len)| {
+            a_len.cmp(b_len).then_with(|| b_code.code.cmp(a_code.code))
+        })
+        .map(|(error, _)| error)
+}
+
+/// Find the error code that best describes an error, if any.
+///
+/// Known error types in the chain take precedence over the messages of the chain
+#[must_use]
+pub fn classify_error(err: &anyhow::Error) -> Option<&'static ErrorCode> {
+    err.chain()
+        .find_map(lookup_error)
+        .or_else(|| classify(&format!("{err:#}")))
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ExplainCommand {
+    /// Error code (e.g. WASH001) or error message to explain
+    #[clap(name = "code", required_unless_present = "list")]
+    code: Option<String>,
+
+    /// List all known error codes
+    #[clap(long = "list", conflicts_with = "code")]
+    list: bool,
+}
+
+pub fn handle_command(cmd: ExplainCommand) -> Result<CommandOutput> {
+    if cmd.list {
+        let text = ERROR_CODES
+            .iter()
+            .map(|error| format!("{}  {}", error.code, error.summary))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let codes = ERROR_CODES
+            .iter()
+            .map(|error| json!({ "code": error.code, "summary": error.summary }))
+            .collect::<Vec<_>>();
+        return Ok(CommandOutput::new(
+            text,
+            HashMap::from([("codes".to_string(), json!(codes))]),
+        ));
+    }
+    let Some(code) = cmd.code else {
+        bail!("an error code or message is required")
+    };
+    let Some(error) = lookup(&code).or_else(|| classify(&code)) else {
+        bail!("unknown error code `{code}`, run `wash explain --list` to list known codes")
+    };
+    Ok(CommandOutput::new(
+        format_explanation(error),
+        HashMap::from([
+            ("code".to_string(), json!(error.code)),
+            ("summary".to_string(), json!(error.summary)),
+            ("explanation".to_string(), json!(error.explanation)),
+            ("troubleshooting".to_string(), json!(error.troubleshooting)),
+        ]),
+    ))
+}
+
+fn format_explanation(error: &ErrorCode) -> String {
+    let mut text = format!(
+        "{}: {}\n\n{}\n",
+        error.code, error.summary, error.explanation
+    );
+    if !error.troubleshooting.is_empty() {
+        text.push_str("\nTroubleshooting:\n");
+        for step in error.troubleshooting {
+            text.push_str(&format!("  - {step}\n"));
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn codes_are_unique() {
+        // Compile all patterns
+        assert!(PATTERNS.len() >= ERROR_CODES.len());
+        for (i, error) in ERROR_CODES.iter().enumerate() {
+            assert!(
+                ERROR_CODES[i + 1..].iter().all(|e| e.code != error.code),
+                "duplicate error code {}",
+                error.code
+            );
+        }
+    }
+
+    #[test]
+    fn lookup_and_classify() {
+        assert_eq!(lookup("wash001").map(|e| e.code), Some("WASH001"));
+        assert_eq!(lookup("WASH999"), None);
+        assert_eq!(
+            classify("No responders found for config put request. Is a host running?")
+                .map(|e| e.code),
+            Some("WASH001")
+        );
+        assert_eq!(
+            classify("request timed out").map(|e| e.code),
+            Some("WASH002")
+        );
+        assert_eq!(
+            classify("failed to fetch secret `api-key`").map(|e| e.code),
+            Some("WASH007")
+        );
+        assert_eq!(
+            classify("failed to start component: component [Mxyz] is not running on this host")
+                .map(|e| e.code),
+            Some("WASH005")
+        );
+        assert_eq!(classify("everything is fine"), None);
+    }
+
+    #[test]
+    fn classify_only_anchored_matches() {
+        // Fragments of known messages within other messages do not identify an error
+        assert_eq!(classify("invalid policy file `policy.json`"), None);
+        assert_eq!(classify("failed to parse timeout value"), None);
+        assert_eq!(classify("unknown link name `default`"), None);
+        assert_eq!(classify("the build timed out"), None);
+        assert_eq!(classify("no responderslist"), None);
+        assert_eq!(
+            classify("failed to read policy file: unauthorizedaccess"),
+            None
+        );
+    }
+
+    #[test]
+    fn classify_typed_errors() {
+        let err = anyhow::Error::from(io::Error::new(io::ErrorKind::TimedOut, "request failed"))
+            .context("failed to stop component");
+        assert_eq!(classify_error(&err).map(|e| e.code), Some("WASH002"));
+
+        let err = anyhow::Error::from(async_nats::RequestError::new(
+            RequestErrorKind::NoResponders,
+        ))
+        .context("failed to get hosts");
+        assert_eq!(classify_error(&err).map(|e| e.code), Some("WASH001"));
+
+        let err = anyhow::anyhow!("something went wrong").context("failed to start provider");
+        assert_eq!(classify_error(&err), None);
+    }
+}
@@ -0,0 +1,249 @@
+//! Examples and troubleshooting notes appended to the `--help` output of subcommands
+
+use std::fmt::Write as _;
+
+use clap::Command;
+
+/// A runnable example of a subcommand
+#[derive(Debug, Clone, Copy)]
+pub struct Example {
+    /// What the example does
+    pub description: &'static str,
+    /// The command line of the example
+    pub command: &'static str,
+}
+
+/// Extended help of a subcommand
+#[derive(Debug, Clone, Copy)]
+pub struct SubcommandHelp {
+    /// Path of the subcommand, e.g. `["link", "put"]`
+    pub path: &'static [&'static str],
+    pub examples: &'static [Example],
+    pub troubleshooting: &'static [&'static str],
+}
+
+/// Extended help of subcommands
+pub const SUBCOMMAND_HELP: &[SubcommandHelp] = &[
+    SubcommandHelp {
+        path: &["up"],
+        examples: &[
+            Example {
+                description: "Start NATS, wadm and a wasmCloud host in the background",
+                command: "wash up -d",
+            },
+            Example {
+                description: "Start a host in a specific lattice with a label",
+                command: "wash up --lattice my-lattice --label env=dev",
+            },
+        ],
+        troubleshooting: &[
+            "If ports are already in use, stop a previous environment with `wash down`",
+            "Run `wash up` without `-d` to follow the host logs in the terminal",
+        ],
+    },
+    SubcommandHelp {
+        path: &["down"],
+        examples: &[Example {
+            description: "Stop the local host, wadm and NATS",
+            command: "wash down --all",
+        }],
+        troubleshooting: &[],
+    },
+    SubcommandHelp {
+        path: &["build"],
+        examples: &[
+            Example {
+                description: "Build and sign the project in the current directory",
+                command: "wash build",
+            },
+            Example {
+                description: "Build a project using a specific configuration file",
+                command: "wash build -p ./my-project/wasmcloud.toml",
+            },
+        ],
+        troubleshooting: &[
+            "Ensure the language toolchain (e.g. `cargo`, `tinygo`) is installed and on your PATH",
+            "Run `wash wit deps` if WIT dependencies fail to resolve",
+        ],
+    },
+    SubcommandHelp {
+        path: &["dev"],
+        examples: &[Example {
+            description: "Build, deploy and hot-reload the project in the current directory",
+            command: "wash dev",
+        }],
+        troubleshooting: &["`wash dev` starts a host if none is running, see `wash explain WASH001`"],
+    },
+    SubcommandHelp {
+        path: &["start", "component"],
+        examples: &[Example {
+            description: "Start a component with up to 10 concurrent instances",
+            command: "wash start component ghcr.io/wasmcloud/components/http-hello-world-rust:0.1.0 hello --max-instances 10",
+        }],
+        troubleshooting: &[
+            "If no host accepts the component, see `wash explain WASH003`",
+            "If the image fails to download, see `wash explain WASH004`",
+        ],
+    },
+    SubcommandHelp {
+        path: &["start", "provider"],
+        examples: &[Example {
+            description: "Start the HTTP server provider",
+            command: "wash start provider ghcr.io/wasmcloud/http-server:0.23.0 http-server",
+        }],
+        troubleshooting: &[
+            "If no host accepts the provider, see `wash explain WASH003`",
+            "If the image fails to download, see `wash explain WASH004`",
+        ],
+    },
+    SubcommandHelp {
+        path: &["link", "put"],
+        examples: &[
+            Example {
+                description: "Link the HTTP server provider to a component",
+                command: "wash link put http-server hello wasi http --interface incoming-handler --source-config default-http",
+            },
+            Example {
+                description: "Link a component to a key-value provider under a named link",
+                command: "wash link put hello kv-redis wasi keyvalue --interface store --link-name cache",
+            },
+        ],
+        troubleshooting: &[
+            "Run `wash link query` to list existing links",
+            "Named configuration must exist before it is used, see `wash explain WASH007`",
+        ],
+    },
+    SubcommandHelp {
+        path: &["config", "put"],
+        examples: &[Example {
+            description: "Create named configuration with two values",
+            command: "wash config put default-http address=0.0.0.0:8080 cache_control=no-cache",
+        }],
+        troubleshooting: &["If no host responds, see `wash explain WASH001`"],
+    },
+    SubcommandHelp {
+        path: &["app", "deploy"],
+        examples: &[
+            Example {
+                description: "Deploy an application manifest",
+                command: "wash app deploy ./wadm.yaml",
+            },
+            Example {
+                description: "Deploy a specific version of a stored application",
+                command: "wash app deploy my-app v0.1.0",
+            },
+        ],
+        troubleshooting: &["Run `wash app status <name>` to see why an application is not deployed"],
+    },
+    SubcommandHelp {
+        path: &["get", "inventory"],
+        examples: &[Example {
+            description: "Show the components and providers running on all hosts",
+            command: "wash get inventory",
+        }],
+        troubleshooting: &["If no hosts are found, see `wash explain WASH001`"],
+    },
+    SubcommandHelp {
+        path: &["call"],
+        examples: &[Example {
+            description: "Invoke a function exported by a component",
+            command: "wash call hello wasmcloud:example/invoke.call",
+        }],
+        troubleshooting: &["The component must export the function and be running in the lattice"],
+    },
+    SubcommandHelp {
+        path: &["push"],
+        examples: &[Example {
+            description: "Push a signed component to a local registry",
+            command: "wash push localhost:5000/hello:0.1.0 build/hello_s.wasm --insecure",
+        }],
+        troubleshooting: &["Pass registry credentials with `--user` and `--password`, or set `WASH_REG_USER` and `WASH_REG_PASSWORD`"],
+    },
+    SubcommandHelp {
+        path: &["pull"],
+        examples: &[Example {
+            description: "Pull a component from a registry",
+            command: "wash pull ghcr.io/wasmcloud/components/http-hello-world-rust:0.1.0",
+        }],
+        troubleshooting: &["If the pull is unauthorized, see `wash explain WASH004`"],
+    },
+];
+
+/// Render the extended help of a subcommand, appended after its `--help` output
+#[must_use]
+pub fn render(help: &SubcommandHelp) -> String {
+    let mut text = String::new();
+    if !help.examples.is_empty() {
+        text.push_str("Examples:\n");
+        for Example {
+            description,
+            command,
+        } in help.examples
+        {
+            let _ = writeln!(text, "  # {description}\n  {command}\n");
+        }
+    }
+    if !help.troubleshooting.is_empty() {
+        text.push_str("Troubleshooting:\n");
+        for note in help.troubleshooting {
+            let _ = writeln!(text, "  - {note}");
+        }
+    }
+    text
+}
+
+/// Append the extended help in [`SUBCOMMAND_HELP`] to the subcommands of `command`
+#[must_use]
+pub fn with_subcommand_help(mut command: Command) -> Command {
+    for help in SUBCOMMAND_HELP {
+        command = apply(command, help.path, &render(help));
+    }
+    command
+}
+
+fn apply(command: Command, path: &[&str], help: &str) -> Command {
+    match path {
+        [] => command.after_long_help(help.to_string()),
+        [name, rest @ ..] => {
+            if command.find_subcommand(name).is_none() {
+                return command;
+            }
+            command.mut_subcommand(*name, |sub| apply(sub, rest, help))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn renders_examples_and_troubleshooting() {
+        let text = render(&SubcommandHelp {
+            path: &["up"],
+            examples: &[Example {
+                description: "Start a host",
+                command: "wash up",
+            }],
+            troubleshooting: &["Check the logs"],
+        });
+        assert_eq!(
+            text,
+            "Examples:\n  # Start a host\n  wash up\n\nTroubleshooting:\n  - Check the logs\n"
+        );
+    }
+
+    #[test]
+    fn applies_to_nested_subcommands() {
+        let command =
+            Command::new("wash").subcommand(Command::new("link").subcommand(Command::new("put")));
+        let mut command = with_subcommand_help(command);
+        let put = command
+            .find_subcommand_mut("link")
+            .and_then(|link| link.find_subcommand_mut("put"))
+            .expect("missing subcommand");
+        assert!(put
+            .get_after_long_help()
+            .is_some_and(|help| help.to_string().contains("wash link put")));
+    }
+}
@@ -15,7 +15,9 @@ pub mod ctl;
 pub mod ctx;
 pub mod down;
 pub mod drain;
+pub mod explain;
 pub mod generate;
+pub mod help;
 pub mod keys;
 pub mod par;
 pub mod plugin;
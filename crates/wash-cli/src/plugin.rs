@@ -1,19 +1,23 @@
-use std::path::PathBuf;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 
 use anyhow::Context;
 use clap::{Parser, Subcommand};
 use futures::TryStreamExt;
 use oci_client::Reference;
+use serde::Deserialize;
 use sha2::{Digest, Sha256};
 use tokio::io::AsyncWriteExt;
 use wash_lib::{
     cli::{registry::AuthOpts, CommandOutput, OutputKind},
+    generate::interactive::user_question,
+    plugin::permissions::{Consent, Permission},
     registry::{pull_oci_artifact, OciPullOptions},
 };
 
 use crate::{
     appearance::spinner::Spinner,
-    ctl::plugins_table,
+    ctl::{plugin_search_table, plugins_table},
     util::{ensure_plugin_dir, load_plugins},
 };
 
@@ -28,6 +32,9 @@ pub enum PluginCommand {
     /// List installed plugins
     #[clap(name = "list", alias = "ls")]
     List(PluginListCommand),
+    /// Search for plugins in a plugin index
+    #[clap(name = "search")]
+    Search(PluginSearchCommand),
 }
 
 #[derive(Parser, Debug, Clone)]
@@ -59,6 +66,10 @@ pub struct PluginInstallCommand {
     #[clap(long = "update")]
     pub update: bool,
 
+    /// Grant the permissions declared by the plugin without prompting for consent
+    #[clap(short = 'y', long = "yes")]
+    pub yes: bool,
+
     #[clap(flatten)]
     pub opts: PluginCommonOpts,
 }
@@ -79,6 +90,90 @@ pub struct PluginListCommand {
     pub opts: PluginCommonOpts,
 }
 
+#[derive(Debug, Clone, Parser)]
+pub struct PluginSearchCommand {
+    /// Text to search for in the ID, name and description of plugins. Lists all plugins if omitted
+    #[clap(name = "query")]
+    pub query: Option<String>,
+
+    /// URL of the plugin index to search. Can be a file://, http:// or https:// URL.
+    #[clap(long = "index-url", env = "WASH_PLUGIN_INDEX_URL")]
+    pub index_url: Option<String>,
+}
+
+/// An index of plugins, listing plugins that can be installed with `wash plugin install`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PluginIndex {
+    pub plugins: Vec<PluginIndexEntry>,
+}
+
+/// A plugin listed in a [`PluginIndex`]
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PluginIndexEntry {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub version: String,
+    #[serde(default)]
+    pub author: String,
+    /// URL to install the plugin from, as accepted by `wash plugin install`
+    pub url: String,
+    /// Digest to verify the plugin against, if any
+    #[serde(default)]
+    pub digest: Option<String>,
+    /// Permissions declared by the plugin
+    #[serde(default)]
+    pub permissions: Vec<Permission>,
+}
+
+impl PluginIndexEntry {
+    /// Whether the entry matches the given query, ignoring case
+    fn matches(&self, query: &str) -> bool {
+        let query = query.to_lowercase();
+        [&self.id, &self.name, &self.description]
+            .iter()
+            .any(|field| field.to_lowercase().contains(&query))
+    }
+}
+
+/// Ask the user for consent to grant the given permissions to a plugin, returning whether consent
+/// was given. Consent is never given when not running in an interactive terminal.
+pub fn prompt_for_consent(plugin: &str, permissions: &[&Permission]) -> anyhow::Result<bool> {
+    if !std::io::stdin().is_terminal() {
+        return Ok(false);
+    }
+    eprintln!("Plugin {plugin} requests the following permissions:");
+    for permission in permissions {
+        eprintln!("  - {permission}");
+    }
+    let answer = user_question("Grant these permissions? (y/N)", &Some("n".to_string()))?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Ensure the user consented to all permissions required by a plugin, prompting for the missing
+/// ones and recording the consent. Returns the permissions that were granted.
+pub async fn ensure_consent(
+    plugin_dir: impl AsRef<Path>,
+    id: &str,
+    required: &[Permission],
+    yes: bool,
+) -> anyhow::Result<Vec<Permission>> {
+    let mut consent = Consent::load(&plugin_dir, id).await?;
+    let missing = consent.missing(required);
+    if missing.is_empty() {
+        return Ok(consent.granted);
+    }
+    if !yes && !prompt_for_consent(id, &missing)? {
+        anyhow::bail!(
+            "Permissions required by plugin {id} were not granted. Run `wash plugin install --update --yes` to grant them non-interactively"
+        );
+    }
+    consent.granted = required.to_vec();
+    consent.save(&plugin_dir, id).await?;
+    Ok(consent.granted)
+}
+
 pub async fn handle_command(
     cmd: PluginCommand,
     output_kind: OutputKind,
@@ -87,6 +182,7 @@ pub async fn handle_command(
         PluginCommand::Install(cmd) => handle_install(cmd, output_kind).await,
         PluginCommand::Uninstall(cmd) => handle_uninstall(cmd, output_kind).await,
         PluginCommand::List(cmd) => handle_list(cmd, output_kind).await,
+        PluginCommand::Search(cmd) => handle_search(cmd, output_kind).await,
     }
 }
 
@@ -225,6 +321,19 @@ pub async fn handle_install(
         plugins.add_plugin(&temp_location).await
     }
     .context("Unable to add plugin")?;
+    let permissions = plugins
+        .permissions(&metadata.id)
+        .map(ToOwned::to_owned)
+        .unwrap_or_default();
+
+    // Consent is asked for before installing, so that declined plugins are never installed
+    let spinner = if permissions.is_empty() {
+        spinner
+    } else {
+        spinner.finish_and_clear();
+        ensure_consent(&plugin_dir, &metadata.id, &permissions, cmd.yes).await?;
+        Spinner::new(&output_kind)?
+    };
 
     spinner.update_spinner_message(" Installing plugin");
 
@@ -245,6 +354,7 @@ pub async fn handle_install(
             ("name".to_string(), metadata.name.into()),
             ("version".to_string(), metadata.version.into()),
             ("description".to_string(), metadata.description.into()),
+            ("permissions".to_string(), serde_json::json!(permissions)),
         ]
         .into(),
     })
@@ -258,7 +368,7 @@ pub async fn handle_uninstall(
     let spinner = Spinner::new(&output_kind)?;
 
     spinner.update_spinner_message(" Loading plugins");
-    let plugins = load_plugins(plugin_dir)
+    let plugins = load_plugins(&plugin_dir)
         .await
         .context("Unable to load plugins")?;
 
@@ -283,6 +393,7 @@ pub async fn handle_uninstall(
     tokio::fs::remove_file(path)
         .await
         .context("Unable to remove plugin")?;
+    Consent::remove(&plugin_dir, &cmd.plugin).await?;
     spinner.finish_and_clear();
 
     Ok(CommandOutput {
@@ -322,9 +433,136 @@ pub async fn handle_list(
                         "id": m.id,
                         "name": m.name,
                         "author": m.author,
+                        "permissions": plugins.permissions(&m.id).unwrap_or_default(),
+                    }),
+                )
+            })
+            .collect(),
+    })
+}
+
+pub async fn handle_search(
+    cmd: PluginSearchCommand,
+    output_kind: OutputKind,
+) -> anyhow::Result<CommandOutput> {
+    // There is no default plugin index, so the index to search must be configured
+    let Some(index_url) = cmd.index_url.as_deref() else {
+        anyhow::bail!(
+            "No plugin index configured. Set the URL of a plugin index with `--index-url` or the `WASH_PLUGIN_INDEX_URL` environment variable"
+        );
+    };
+    let spinner = Spinner::new(&output_kind)?;
+    spinner.update_spinner_message(format!(" Fetching plugin index from {index_url}"));
+    let data = match index_url.split_once("://") {
+        Some(("file", path)) => tokio::fs::read(path)
+            .await
+            .with_context(|| format!("Unable to read plugin index at {path}"))?,
+        Some(("http" | "https", _)) => {
+            let resp = reqwest::get(index_url)
+                .await
+                .context("Unable to perform http request")?;
+            if !resp.status().is_success() {
+                anyhow::bail!(
+                    "Unable to fetch plugin index from {index_url}. HTTP status code: {}",
+                    resp.status()
+                );
+            }
+            resp.bytes()
+                .await
+                .context("Unable to read plugin index")?
+                .to_vec()
+        }
+        _ => anyhow::bail!(
+            "Invalid index URL {index_url}. It should be a file://, http:// or https:// URL"
+        ),
+    };
+    let index: PluginIndex =
+        serde_json::from_slice(&data).context("Unable to parse plugin index")?;
+    spinner.finish_and_clear();
+
+    let results: Vec<_> = index
+        .plugins
+        .into_iter()
+        .filter(|entry| {
+            cmd.query
+                .as_deref()
+                .map_or(true, |query| entry.matches(query))
+        })
+        .collect();
+
+    Ok(CommandOutput {
+        text: plugin_search_table(&results),
+        map: results
+            .into_iter()
+            .map(|entry| {
+                (
+                    entry.id.clone(),
+                    serde_json::json!({
+                        "version": entry.version,
+                        "description": entry.description,
+                        "id": entry.id,
+                        "name": entry.name,
+                        "author": entry.author,
+                        "url": entry.url,
+                        "digest": entry.digest,
+                        "permissions": entry.permissions,
                     }),
                 )
             })
             .collect(),
     })
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_and_search_index() {
+        let index: PluginIndex = serde_json::from_str(
+            r#"{
+                "plugins": [
+                    {
+                        "id": "hello",
+                        "name": "Hello",
+                        "description": "Says hello",
+                        "version": "0.1.0",
+                        "url": "oci://ghcr.io/example/hello:0.1.0",
+                        "permissions": [{ "type": "network", "host": "example.com:443" }]
+                    },
+                    {
+                        "id": "inspect",
+                        "name": "Inspector",
+                        "version": "0.2.0",
+                        "url": "https://example.com/inspect.wasm"
+                    }
+                ]
+            }"#,
+        )
+        .expect("failed to parse index");
+        assert_eq!(index.plugins.len(), 2);
+        assert_eq!(
+            index.plugins[0].permissions,
+            [Permission::Network {
+                host: "example.com:443".to_string()
+            }]
+        );
+        assert!(index.plugins[0].matches("HELLO"));
+        assert!(!index.plugins[1].matches("hello"));
+        assert!(index.plugins[1].matches("inspect"));
+    }
+
+    #[tokio::test]
+    async fn search_without_index() {
+        let err = handle_search(
+            PluginSearchCommand {
+                query: None,
+                index_url: None,
+            },
+            OutputKind::Json,
+        )
+        .await
+        .expect_err("search without an index should fail");
+        assert!(err.to_string().starts_with("No plugin index configured"));
+    }
+}
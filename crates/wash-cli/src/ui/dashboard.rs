@@ -0,0 +1,688 @@
+//! Interactive terminal dashboard of a lattice, shown by `wash up --ui`
+
+use std::collections::VecDeque;
+use std::io::Write;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    queue,
+    style::{Print, Stylize},
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use futures::StreamExt;
+use tokio::sync::mpsc;
+use wasmcloud_control_interface::{
+    Client as CtlClient, CtlResponse, EventFilter, HostInventory, LatticeEvent, LatticeEventKind,
+    Link,
+};
+
+/// Interval at which hosts, components, providers and links are refreshed
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Maximum number of recent lattice events kept by the dashboard
+const MAX_EVENTS: usize = 100;
+
+/// Number of lines used to display recent lattice events
+const EVENT_LINES: usize = 8;
+
+/// The tabs of the dashboard
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum Tab {
+    #[default]
+    Hosts,
+    Components,
+    Providers,
+    Links,
+}
+
+impl Tab {
+    const ALL: [Tab; 4] = [Tab::Hosts, Tab::Components, Tab::Providers, Tab::Links];
+
+    fn title(self) -> &'static str {
+        match self {
+            Tab::Hosts => "Hosts",
+            Tab::Components => "Components",
+            Tab::Providers => "Providers",
+            Tab::Links => "Links",
+        }
+    }
+
+    fn index(self) -> usize {
+        Self::ALL
+            .iter()
+            .position(|tab| *tab == self)
+            .unwrap_or_default()
+    }
+
+    fn next(self) -> Self {
+        Self::ALL[(self.index() + 1) % Self::ALL.len()]
+    }
+
+    fn previous(self) -> Self {
+        Self::ALL[(self.index() + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+}
+
+/// An operation on the lattice requested from the dashboard
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Action {
+    Quit,
+    Refresh,
+    ScaleComponent {
+        host_id: String,
+        component_id: String,
+        image_ref: String,
+        max_instances: u32,
+    },
+    StopProvider {
+        host_id: String,
+        provider_id: String,
+    },
+}
+
+/// Style of a line of the dashboard
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum LineStyle {
+    Normal,
+    Title,
+    Header,
+    Selected,
+    Dim,
+}
+
+/// The state of the dashboard
+#[derive(Debug, Default)]
+struct Dashboard {
+    lattice: String,
+    tab: Tab,
+    /// Index of the selected row of the current tab
+    selected: usize,
+    hosts: Vec<HostInventory>,
+    links: Vec<Link>,
+    /// Recent lattice events, newest first
+    events: VecDeque<String>,
+    /// Outcome of the last action or refresh
+    status: String,
+}
+
+impl Dashboard {
+    fn new(lattice: impl Into<String>) -> Self {
+        Self {
+            lattice: lattice.into(),
+            status: "Loading lattice...".to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Replace the hosts and links shown by the dashboard
+    fn update(&mut self, mut hosts: Vec<HostInventory>, links: Vec<Link>) {
+        hosts.sort_by(|a, b| a.friendly_name().cmp(b.friendly_name()));
+        self.hosts = hosts;
+        self.links = links;
+        self.selected = self.selected.min(self.row_count().saturating_sub(1));
+    }
+
+    /// Record a lattice event
+    fn push_event(&mut self, event: &LatticeEvent) {
+        self.events.push_front(format_event(event));
+        self.events.truncate(MAX_EVENTS);
+    }
+
+    /// Components running in the lattice, as `(host ID, component ID, image reference, max
+    /// instances)`
+    fn components(&self) -> Vec<(&str, &str, &str, u32)> {
+        self.hosts
+            .iter()
+            .flat_map(|host| {
+                host.components().iter().map(|component| {
+                    (
+                        host.host_id(),
+                        component.id(),
+                        component.image_ref(),
+                        component.max_instances(),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Providers running in the lattice, as `(host ID, provider ID, image reference)`
+    fn providers(&self) -> Vec<(&str, &str, &str)> {
+        self.hosts
+            .iter()
+            .flat_map(|host| {
+                host.providers().iter().map(|provider| {
+                    (
+                        host.host_id(),
+                        provider.id(),
+                        provider.image_ref().unwrap_or_default(),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Number of rows of the current tab
+    fn row_count(&self) -> usize {
+        match self.tab {
+            Tab::Hosts => self.hosts.len(),
+            Tab::Components => self.components().len(),
+            Tab::Providers => self.providers().len(),
+            Tab::Links => self.links.len(),
+        }
+    }
+
+    fn select_tab(&mut self, tab: Tab) {
+        self.tab = tab;
+        self.selected = 0;
+    }
+
+    /// Handle a key press, returning the action to perform, if any
+    fn handle_key(&mut self, key: KeyEvent) -> Option<Action> {
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Action::Quit)
+            }
+            KeyCode::Char('q') | KeyCode::Esc => Some(Action::Quit),
+            KeyCode::Char('r') => Some(Action::Refresh),
+            KeyCode::Tab | KeyCode::Right => {
+                self.select_tab(self.tab.next());
+                None
+            }
+            KeyCode::BackTab | KeyCode::Left => {
+                self.select_tab(self.tab.previous());
+                None
+            }
+            KeyCode::Char(c @ '1'..='4') => {
+                self.select_tab(Tab::ALL[usize::from(c as u8 - b'1')]);
+                None
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.selected = self.selected.saturating_sub(1);
+                None
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.selected = (self.selected + 1).min(self.row_count().saturating_sub(1));
+                None
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') => self.scale_selected(|n| n.saturating_add(1)),
+            KeyCode::Char('-') => self.scale_selected(|n| n.saturating_sub(1)),
+            KeyCode::Char('s') => match self.tab {
+                Tab::Components => self.scale_selected(|_| 0),
+                Tab::Providers => {
+                    let providers = self.providers();
+                    let (host_id, provider_id, _) = providers.get(self.selected)?;
+                    Some(Action::StopProvider {
+                        host_id: host_id.to_string(),
+                        provider_id: provider_id.to_string(),
+                    })
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Scale the selected component to the number of instances returned by `scale`
+    fn scale_selected(&self, scale: impl Fn(u32) -> u32) -> Option<Action> {
+        if self.tab != Tab::Components {
+            return None;
+        }
+        let components = self.components();
+        let (host_id, component_id, image_ref, max_instances) = components.get(self.selected)?;
+        Some(Action::ScaleComponent {
+            host_id: host_id.to_string(),
+            component_id: component_id.to_string(),
+            image_ref: image_ref.to_string(),
+            max_instances: scale(*max_instances),
+        })
+    }
+
+    /// Header and rows of the current tab
+    fn table(&self) -> (String, Vec<String>) {
+        match self.tab {
+            Tab::Hosts => (
+                table_row(&[
+                    "NAME",
+                    "HOST ID",
+                    "VERSION",
+                    "UPTIME",
+                    "COMPONENTS",
+                    "PROVIDERS",
+                ]),
+                self.hosts
+                    .iter()
+                    .map(|host| {
+                        table_row(&[
+                            host.friendly_name(),
+                            host.host_id(),
+                            host.version(),
+                            host.uptime_human(),
+                            &host.components().len().to_string(),
+                            &host.providers().len().to_string(),
+                        ])
+                    })
+                    .collect(),
+            ),
+            Tab::Components => (
+                table_row(&["COMPONENT ID", "IMAGE", "MAX", "HOST ID"]),
+                self.components()
+                    .into_iter()
+                    .map(|(host_id, id, image_ref, max_instances)| {
+                        table_row(&[id, image_ref, &max_instances.to_string(), host_id])
+                    })
+                    .collect(),
+            ),
+            Tab::Providers => (
+                table_row(&["PROVIDER ID", "IMAGE", "HOST ID"]),
+                self.providers()
+                    .into_iter()
+                    .map(|(host_id, id, image_ref)| table_row(&[id, image_ref, host_id]))
+                    .collect(),
+            ),
+            Tab::Links => (
+                table_row(&["SOURCE", "TARGET", "INTERFACES", "NAME"]),
+                self.links
+                    .iter()
+                    .map(|link| {
+                        table_row(&[
+                            link.source_id(),
+                            link.target(),
+                            &format!(
+                                "{}:{}/{}",
+                                link.wit_namespace(),
+                                link.wit_package(),
+                                link.interfaces().join(",")
+                            ),
+                            link.name(),
+                        ])
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Lay out the dashboard on a terminal of `width` columns and `height` rows
+    fn render(&self, width: usize, height: usize) -> Vec<(String, LineStyle)> {
+        let tabs = Tab::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, tab)| {
+                if *tab == self.tab {
+                    format!("[{} {}]", i + 1, tab.title())
+                } else {
+                    format!(" {} {} ", i + 1, tab.title())
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut lines = vec![
+            (
+                format!("wasmCloud lattice `{}`  {tabs}", self.lattice),
+                LineStyle::Title,
+            ),
+            (String::new(), LineStyle::Normal),
+        ];
+
+        let (header, rows) = self.table();
+        lines.push((header, LineStyle::Header));
+        // Rows are scrolled, so that the selected row is always visible
+        let visible = height.saturating_sub(EVENT_LINES + 7).max(1);
+        let skip = self.selected.saturating_sub(visible - 1);
+        for (i, row) in rows.into_iter().enumerate().skip(skip).take(visible) {
+            let style = if i == self.selected {
+                LineStyle::Selected
+            } else {
+                LineStyle::Normal
+            };
+            lines.push((row, style));
+        }
+        while lines.len() < visible + 3 {
+            lines.push((String::new(), LineStyle::Normal));
+        }
+
+        lines.push(("Recent events".to_string(), LineStyle::Header));
+        for i in 0..EVENT_LINES {
+            let event = self.events.get(i).cloned().unwrap_or_default();
+            lines.push((event, LineStyle::Normal));
+        }
+        lines.push((String::new(), LineStyle::Normal));
+        lines.push((self.status.clone(), LineStyle::Normal));
+        lines.push((
+            "q quit  tab/1-4 switch  ↑/↓ select  +/- scale component  s stop  r refresh"
+                .to_string(),
+            LineStyle::Dim,
+        ));
+
+        lines
+            .into_iter()
+            .take(height)
+            .map(|(line, style)| (truncate(&line, width), style))
+            .collect()
+    }
+}
+
+/// Format the cells of a table row with fixed column widths
+fn table_row(cells: &[&str]) -> String {
+    const WIDTHS: [usize; 6] = [24, 58, 10, 16, 12, 10];
+    cells
+        .iter()
+        .zip(WIDTHS)
+        .map(|(cell, width)| format!("{:<width$}", truncate(cell, width - 1)))
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
+
+/// Truncate `s` to at most `width` characters
+fn truncate(s: &str, width: usize) -> String {
+    if s.chars().count() <= width {
+        return s.to_string();
+    }
+    let mut s: String = s.chars().take(width.saturating_sub(1)).collect();
+    s.push('…');
+    s
+}
+
+/// Format a lattice event as a single line
+fn format_event(event: &LatticeEvent) -> String {
+    let time = event
+        .time()
+        .and_then(|time| chrono::DateTime::parse_from_rfc3339(time).ok())
+        .map(|time| {
+            time.with_timezone(&chrono::Local)
+                .format("%H:%M:%S")
+                .to_string()
+        })
+        .unwrap_or_else(|| "--:--:--".to_string());
+    let details = match event.kind() {
+        LatticeEventKind::ComponentScaled(scaled) => format!(
+            "{} scaled to {} on {}",
+            scaled.component_id(),
+            scaled.max_instances(),
+            scaled.host_id()
+        ),
+        LatticeEventKind::ProviderStarted(started) => {
+            format!("{} on {}", started.provider_id(), started.host_id())
+        }
+        LatticeEventKind::ProviderStopped(stopped) => {
+            format!("{} on {}", stopped.provider_id(), stopped.host_id())
+        }
+        LatticeEventKind::LinkPut(link) => format!("{} -> {}", link.source_id(), link.target()),
+        _ => format!("from {}", event.source()),
+    };
+    format!("{time}  {:<24} {details}", event.event_type())
+}
+
+/// Restores the terminal when the dashboard exits
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> Result<Self> {
+        terminal::enable_raw_mode().context("failed to enable raw terminal mode")?;
+        crossterm::execute!(std::io::stdout(), EnterAlternateScreen, cursor::Hide)
+            .context("failed to enter alternate screen")?;
+        Ok(Self)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = crossterm::execute!(std::io::stdout(), LeaveAlternateScreen, cursor::Show);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Draw the dashboard to the terminal
+fn draw(dashboard: &Dashboard) -> Result<()> {
+    let (width, height) = terminal::size().context("failed to get terminal size")?;
+    let mut stdout = std::io::stdout();
+    queue!(stdout, Clear(ClearType::All))?;
+    for (row, (line, style)) in dashboard
+        .render(width.into(), height.into())
+        .into_iter()
+        .enumerate()
+    {
+        let row = u16::try_from(row).unwrap_or(u16::MAX);
+        queue!(stdout, cursor::MoveTo(0, row))?;
+        match style {
+            LineStyle::Normal => queue!(stdout, Print(line))?,
+            LineStyle::Title => queue!(stdout, Print(line.bold()))?,
+            LineStyle::Header => queue!(stdout, Print(line.bold().underlined()))?,
+            LineStyle::Selected => queue!(stdout, Print(line.reverse()))?,
+            LineStyle::Dim => queue!(stdout, Print(line.dim()))?,
+        }
+    }
+    stdout.flush().context("failed to flush terminal")
+}
+
+/// Fetch the inventories of all hosts and all links of the lattice
+async fn fetch(client: &CtlClient) -> Result<(Vec<HostInventory>, Vec<Link>)> {
+    let hosts = client.get_hosts().await.map_err(|e| anyhow!(e))?;
+    let mut inventories = Vec::with_capacity(hosts.len());
+    for host in hosts.into_iter().filter_map(CtlResponse::into_data) {
+        let inventory = client
+            .get_host_inventory(host.id())
+            .await
+            .map_err(|e| anyhow!(e))?;
+        inventories.extend(inventory.into_data());
+    }
+    let links = client
+        .get_links()
+        .await
+        .map_err(|e| anyhow!(e))?
+        .into_data()
+        .unwrap_or_default();
+    Ok((inventories, links))
+}
+
+/// Perform an action requested from the dashboard, returning a status message
+async fn perform(client: &CtlClient, action: Action) -> String {
+    let (res, done) = match action {
+        Action::ScaleComponent {
+            host_id,
+            component_id,
+            image_ref,
+            max_instances,
+        } => (
+            client
+                .scale_component(
+                    &host_id,
+                    &image_ref,
+                    &component_id,
+                    max_instances,
+                    None,
+                    Vec::new(),
+                )
+                .await,
+            format!("Requested scaling {component_id} to {max_instances} instances"),
+        ),
+        Action::StopProvider {
+            host_id,
+            provider_id,
+        } => (
+            client.stop_provider(&host_id, &provider_id).await,
+            format!("Requested stopping {provider_id}"),
+        ),
+        Action::Quit | Action::Refresh => return String::new(),
+    };
+    match res {
+        Ok(res) if res.succeeded() => done,
+        Ok(res) => format!("Error: {}", res.message()),
+        Err(err) => format!("Error: {err}"),
+    }
+}
+
+/// Run the dashboard of the lattice of `client` until the user quits
+pub async fn run(client: CtlClient) -> Result<()> {
+    let mut events = client
+        .events(EventFilter::new())
+        .await
+        .map_err(|e| anyhow!(e))
+        .context("failed to subscribe to lattice events")?;
+
+    // Terminal events are read on a separate thread, since reading them blocks
+    let (keys_tx, mut keys) = mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        while !keys_tx.is_closed() {
+            match event::poll(Duration::from_millis(200)) {
+                Ok(true) => match event::read() {
+                    Ok(Event::Key(key)) if key.kind != KeyEventKind::Release => {
+                        if keys_tx.send(Some(key)).is_err() {
+                            break;
+                        }
+                    }
+                    // Redraw on resize
+                    Ok(Event::Resize(..)) => {
+                        if keys_tx.send(None).is_err() {
+                            break;
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(_) => break,
+                },
+                Ok(false) => {}
+                Err(_) => break,
+            }
+        }
+    });
+
+    let _guard = TerminalGuard::enter()?;
+    let mut dashboard = Dashboard::new(client.lattice());
+    let mut refresh = tokio::time::interval(REFRESH_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = refresh.tick() => {
+                match fetch(&client).await {
+                    Ok((hosts, links)) => {
+                        dashboard.update(hosts, links);
+                        if dashboard.status == "Loading lattice..." {
+                            dashboard.status.clear();
+                        }
+                    }
+                    Err(err) => dashboard.status = format!("Error: failed to refresh: {err:#}"),
+                }
+            }
+            Some(event) = events.next() => {
+                dashboard.push_event(&event);
+            }
+            key = keys.recv() => {
+                let Some(key) = key else {
+                    break;
+                };
+                match key.and_then(|key| dashboard.handle_key(key)) {
+                    Some(Action::Quit) => break,
+                    Some(Action::Refresh) => refresh.reset_immediately(),
+                    Some(action) => {
+                        dashboard.status = perform(&client, action).await;
+                        refresh.reset_immediately();
+                    }
+                    None => {}
+                }
+            }
+        }
+        draw(&dashboard)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use wasmcloud_control_interface::{ComponentDescription, HostInventory, ProviderDescription};
+
+    use super::{truncate, Action, Dashboard, LineStyle, Tab};
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn dashboard() -> Dashboard {
+        let host = HostInventory::builder()
+            .host_id("NHOST".into())
+            .friendly_name("dashing-host".into())
+            .version("1.0.0".into())
+            .uptime_human("1m".into())
+            .uptime_seconds(60)
+            .components(vec![ComponentDescription::builder()
+                .id("hello".into())
+                .image_ref("ghcr.io/wasmcloud/components/hello:0.1.0".into())
+                .max_instances(2)
+                .build()
+                .unwrap()])
+            .providers(vec![ProviderDescription::builder()
+                .id("http-server")
+                .image_ref("ghcr.io/wasmcloud/http-server:0.23.0")
+                .build()
+                .unwrap()])
+            .build()
+            .unwrap();
+        let mut dashboard = Dashboard::new("default");
+        dashboard.update(vec![host], Vec::new());
+        dashboard
+    }
+
+    #[test]
+    fn test_dashboard_keys() {
+        let mut dashboard = dashboard();
+        assert_eq!(dashboard.handle_key(key(KeyCode::Char('+'))), None);
+
+        dashboard.handle_key(key(KeyCode::Tab));
+        assert_eq!(dashboard.tab, Tab::Components);
+        assert_eq!(
+            dashboard.handle_key(key(KeyCode::Char('+'))),
+            Some(Action::ScaleComponent {
+                host_id: "NHOST".into(),
+                component_id: "hello".into(),
+                image_ref: "ghcr.io/wasmcloud/components/hello:0.1.0".into(),
+                max_instances: 3,
+            })
+        );
+        assert!(matches!(
+            dashboard.handle_key(key(KeyCode::Char('s'))),
+            Some(Action::ScaleComponent {
+                max_instances: 0,
+                ..
+            })
+        ));
+
+        dashboard.handle_key(key(KeyCode::Char('3')));
+        assert_eq!(dashboard.tab, Tab::Providers);
+        assert_eq!(
+            dashboard.handle_key(key(KeyCode::Char('s'))),
+            Some(Action::StopProvider {
+                host_id: "NHOST".into(),
+                provider_id: "http-server".into(),
+            })
+        );
+        // The selection is bounded by the number of rows
+        dashboard.handle_key(key(KeyCode::Down));
+        assert_eq!(dashboard.selected, 0);
+
+        assert_eq!(
+            dashboard.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            Some(Action::Quit)
+        );
+    }
+
+    #[test]
+    fn test_dashboard_render() {
+        let dashboard = dashboard();
+        let lines = dashboard.render(80, 30);
+        assert!(lines.len() <= 30);
+        assert!(lines.iter().all(|(line, _)| line.chars().count() <= 80));
+        assert!(lines[0].0.contains("[1 Hosts]"));
+        let selected: Vec<_> = lines
+            .iter()
+            .filter(|(_, style)| *style == LineStyle::Selected)
+            .collect();
+        assert_eq!(selected.len(), 1);
+        assert!(selected[0].0.starts_with("dashing-host"));
+
+        assert_eq!(truncate("hello", 5), "hello");
+        assert_eq!(truncate("hello world", 6), "hello…");
+    }
+}
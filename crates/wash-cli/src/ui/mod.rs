@@ -1,6 +1,8 @@
 mod config;
 pub use config::*;
 
+pub mod dashboard;
+
 use std::{io::Cursor, path::PathBuf};
 
 use anyhow::{bail, Context, Result};
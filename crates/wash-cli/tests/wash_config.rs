@@ -66,3 +66,61 @@ async fn test_config_secret_name_error() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_config_history_and_rollback() -> anyhow::Result<()> {
+    let wash_instance = TestWashInstance::create().await?;
+    let opts = || CliConnectionOpts {
+        ctl_port: Some(wash_instance.nats_port.to_string()),
+        ..Default::default()
+    };
+
+    for value in ["key=first", "key=second"] {
+        wash_cli::cmd::config::handle_command(
+            ConfigCliCommand::PutCommand {
+                opts: opts(),
+                name: "versioned".to_string(),
+                config_values: vec![value.to_string()],
+            },
+            OutputKind::Json,
+        )
+        .await?;
+    }
+
+    let history = wash_cli::cmd::config::handle_command(
+        ConfigCliCommand::HistoryCommand {
+            opts: opts(),
+            name: "versioned".to_string(),
+        },
+        OutputKind::Json,
+    )
+    .await?
+    .map;
+    let revisions: Vec<wasmcloud_control_interface::ConfigRevision> =
+        serde_json::from_value(history.get("revisions").unwrap().clone())?;
+    assert_eq!(revisions.len(), 2);
+    assert_eq!(revisions[0].config().unwrap().get("key").unwrap(), "first");
+
+    wash_cli::cmd::config::handle_command(
+        ConfigCliCommand::RollbackCommand {
+            opts: opts(),
+            name: "versioned".to_string(),
+            to_rev: revisions[0].revision(),
+        },
+        OutputKind::Json,
+    )
+    .await?;
+
+    let retrieved_config = wash_cli::cmd::config::handle_command(
+        ConfigCliCommand::GetCommand {
+            opts: opts(),
+            name: "versioned".to_string(),
+        },
+        OutputKind::Json,
+    )
+    .await?
+    .map;
+    assert_eq!(retrieved_config.get("key").unwrap(), "first");
+
+    Ok(())
+}
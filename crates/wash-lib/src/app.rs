@@ -21,6 +21,8 @@ use wasmcloud_core::OciFetcher;
 
 use crate::config::DEFAULT_LATTICE;
 
+pub mod history;
+
 #[derive(Debug)]
 pub enum AppManifest {
     SerializedModel(serde_yaml::Value),
@@ -0,0 +1,360 @@
+//! Deployment history of applications, recorded by wash whenever it deploys an application.
+//!
+//! wadm keeps every version of an application manifest that was put, but not when, by whom or in
+//! which order versions were deployed. wash records every deployment in the lattice-scoped
+//! `APPHISTORY_{lattice}` JetStream key-value bucket, keyed by application name, which allows
+//! rolling back to the version of a previous deployment.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+
+use anyhow::{bail, Context as _};
+use async_nats::jetstream::context::KeyValueErrorKind;
+use async_nats::jetstream::kv;
+use async_nats::Client;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::Digest as _;
+use wadm_types::Manifest;
+
+use crate::config::DEFAULT_LATTICE;
+
+/// Prefix of the key-value bucket holding the deployment history of a lattice
+pub const HISTORY_BUCKET_PREFIX: &str = "APPHISTORY_";
+
+/// Maximum number of deployments retained per application, oldest deployments are dropped first
+const MAX_HISTORY_LEN: usize = 50;
+
+/// Number of attempts to record a deployment when the history is updated concurrently
+const MAX_UPDATE_ATTEMPTS: usize = 5;
+
+/// A deployment of an application version
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DeploymentRecord {
+    /// Sequence number of the deployment, starting at 1 for the first deployment
+    pub revision: u64,
+    /// Version of the application manifest that was deployed
+    pub version: String,
+    /// User that deployed the application
+    pub deployed_by: String,
+    /// Time of the deployment
+    pub deployed_at: DateTime<Utc>,
+    /// SHA-256 digest of the deployed manifest
+    pub digest: String,
+    /// SHA-256 digests of the components of the deployed manifest, keyed by component name
+    #[serde(default)]
+    pub components: BTreeMap<String, String>,
+    /// Changes to components compared to the previous deployment
+    #[serde(default)]
+    pub changes: ManifestDiff,
+    /// Revision of the deployment this deployment rolled back to, if it was a rollback
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rollback_of: Option<u64>,
+}
+
+/// Summary of the changes to the components of an application between two deployments
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ManifestDiff {
+    /// Names of components added
+    pub added: Vec<String>,
+    /// Names of components removed
+    pub removed: Vec<String>,
+    /// Names of components whose definition changed
+    pub changed: Vec<String>,
+}
+
+impl ManifestDiff {
+    /// Compare the component digests of two deployments
+    pub fn between(
+        previous: &BTreeMap<String, String>,
+        current: &BTreeMap<String, String>,
+    ) -> Self {
+        let mut diff = Self::default();
+        for (name, digest) in current {
+            match previous.get(name) {
+                None => diff.added.push(name.clone()),
+                Some(previous) if previous != digest => diff.changed.push(name.clone()),
+                Some(_) => {}
+            }
+        }
+        diff.removed = previous
+            .keys()
+            .filter(|name| !current.contains_key(*name))
+            .cloned()
+            .collect();
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl Display for ManifestDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "no component changes");
+        }
+        let changes: Vec<_> = [
+            ("+", &self.added),
+            ("~", &self.changed),
+            ("-", &self.removed),
+        ]
+        .into_iter()
+        .flat_map(|(sign, names)| names.iter().map(move |name| format!("{sign}{name}")))
+        .collect();
+        write!(f, "{}", changes.join(" "))
+    }
+}
+
+/// Compute the SHA-256 digest of a manifest and of each of its components
+pub fn manifest_digests(manifest: &Manifest) -> anyhow::Result<(String, BTreeMap<String, String>)> {
+    fn digest(value: &impl Serialize) -> anyhow::Result<String> {
+        let bytes = serde_json::to_vec(value).context("failed to serialize manifest")?;
+        Ok(format!("sha256:{:x}", sha2::Sha256::digest(bytes)))
+    }
+
+    let components = manifest
+        .spec
+        .components
+        .iter()
+        .map(|component| Ok((component.name.clone(), digest(component)?)))
+        .collect::<anyhow::Result<_>>()?;
+    Ok((digest(manifest)?, components))
+}
+
+/// Name of the user deploying an application, as reported by the environment
+fn deployed_by() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+fn bucket_name(lattice: Option<&str>) -> String {
+    format!(
+        "{HISTORY_BUCKET_PREFIX}{}",
+        lattice.unwrap_or(DEFAULT_LATTICE)
+    )
+}
+
+/// Retrieve the deployment history bucket of a lattice, creating it if `create` is set.
+/// Returns `None` if the bucket does not exist and is not created
+async fn history_bucket(
+    client: &Client,
+    lattice: Option<&str>,
+    create: bool,
+) -> anyhow::Result<Option<kv::Store>> {
+    let js = async_nats::jetstream::new(client.clone());
+    let bucket = bucket_name(lattice);
+    match js.get_key_value(&bucket).await {
+        Ok(store) => Ok(Some(store)),
+        Err(err) if err.kind() == KeyValueErrorKind::GetBucket && create => js
+            .create_key_value(kv::Config {
+                bucket,
+                description: "Deployment history of applications, recorded by wash".into(),
+                history: 1,
+                ..Default::default()
+            })
+            .await
+            .map(Some)
+            .context("failed to create deployment history bucket"),
+        Err(err) if err.kind() == KeyValueErrorKind::GetBucket => Ok(None),
+        Err(err) => Err(anyhow::anyhow!(err).context("failed to get deployment history bucket")),
+    }
+}
+
+/// Read the deployment history of `model_name` along with the revision of its key-value entry
+async fn read_history(
+    store: &kv::Store,
+    model_name: &str,
+) -> anyhow::Result<(Vec<DeploymentRecord>, Option<u64>)> {
+    match store
+        .entry(model_name)
+        .await
+        .context("failed to read deployment history")?
+    {
+        Some(entry) if !entry.value.is_empty() => Ok((
+            serde_json::from_slice(&entry.value).context("failed to parse deployment history")?,
+            Some(entry.revision),
+        )),
+        Some(entry) => Ok((Vec::new(), Some(entry.revision))),
+        None => Ok((Vec::new(), None)),
+    }
+}
+
+/// Query the deployment history of a given model name, oldest deployment first
+///
+/// # Arguments
+/// * `client` - The [`Client`] to use in order to read the history
+/// * `lattice` - Optional lattice name that the application is deployed on, defaults to `default`
+/// * `model_name` - Name of the model to retrieve the deployment history for
+pub async fn get_deployment_history(
+    client: &Client,
+    lattice: Option<String>,
+    model_name: &str,
+) -> anyhow::Result<Vec<DeploymentRecord>> {
+    let Some(store) = history_bucket(client, lattice.as_deref(), false).await? else {
+        return Ok(Vec::new());
+    };
+    read_history(&store, model_name)
+        .await
+        .map(|(history, _)| history)
+}
+
+/// Record a deployment of an application in the deployment history
+///
+/// # Arguments
+/// * `client` - The [`Client`] to use in order to send the request messages
+/// * `lattice` - Optional lattice name that the application was deployed on, defaults to `default`
+/// * `model_name` - Name of the deployed model
+/// * `version` - Deployed version, defaults to the version wadm reports as deployed
+/// * `rollback_of` - Revision of the deployment that was rolled back to, if any
+pub async fn record_deployment(
+    client: &Client,
+    lattice: Option<String>,
+    model_name: &str,
+    version: Option<String>,
+    rollback_of: Option<u64>,
+) -> anyhow::Result<DeploymentRecord> {
+    let version = match version {
+        Some(version) => version,
+        None => super::get_model_history(client, lattice.clone(), model_name)
+            .await?
+            .into_iter()
+            .find(|v| v.deployed)
+            .map(|v| v.version)
+            .context("failed to find deployed version of application")?,
+    };
+    let manifest =
+        super::get_model_details(client, lattice.clone(), model_name, Some(version.clone()))
+            .await?;
+    let (digest, components) = manifest_digests(&manifest)?;
+
+    let store = history_bucket(client, lattice.as_deref(), true)
+        .await?
+        .context("deployment history bucket missing")?;
+    for _ in 0..MAX_UPDATE_ATTEMPTS {
+        let (mut history, entry_revision) = read_history(&store, model_name).await?;
+        let previous = history.last();
+        let record = DeploymentRecord {
+            revision: previous.map_or(1, |r| r.revision + 1),
+            version: version.clone(),
+            deployed_by: deployed_by(),
+            deployed_at: Utc::now(),
+            digest: digest.clone(),
+            changes: ManifestDiff::between(
+                &previous.map(|r| r.components.clone()).unwrap_or_default(),
+                &components,
+            ),
+            components: components.clone(),
+            rollback_of,
+        };
+        history.push(record.clone());
+        if history.len() > MAX_HISTORY_LEN {
+            history.drain(..history.len() - MAX_HISTORY_LEN);
+        }
+        let value = serde_json::to_vec(&history)
+            .context("failed to serialize deployment history")?
+            .into();
+        // Fails if the history was updated since it was read, in which case the update is retried
+        let updated = match entry_revision {
+            Some(revision) => store
+                .update(model_name, value, revision)
+                .await
+                .map(|_| ())
+                .map_err(anyhow::Error::from),
+            None => store
+                .create(model_name, value)
+                .await
+                .map(|_| ())
+                .map_err(anyhow::Error::from),
+        };
+        if updated.is_ok() {
+            return Ok(record);
+        }
+    }
+    bail!("failed to record deployment, deployment history was concurrently modified")
+}
+
+/// Roll back an application to the version of a previous deployment, recording the rollback as a
+/// new deployment
+///
+/// # Arguments
+/// * `client` - The [`Client`] to use in order to send the request messages
+/// * `lattice` - Optional lattice name that the application is deployed on, defaults to `default`
+/// * `model_name` - Name of the model to roll back
+/// * `revision` - Revision of the deployment to roll back to, defaults to the deployment before
+///   the latest one
+pub async fn rollback_model(
+    client: &Client,
+    lattice: Option<String>,
+    model_name: &str,
+    revision: Option<u64>,
+) -> anyhow::Result<DeploymentRecord> {
+    let history = get_deployment_history(client, lattice.clone(), model_name).await?;
+    let target =
+        match revision {
+            Some(revision) => history
+                .iter()
+                .find(|r| r.revision == revision)
+                .with_context(|| {
+                    format!("revision {revision} not found in deployment history of [{model_name}]")
+                })?,
+            None => history.iter().rev().nth(1).with_context(|| {
+                format!("no previous deployment of [{model_name}] to roll back to")
+            })?,
+        };
+    super::deploy_model(
+        client,
+        lattice.clone(),
+        model_name,
+        Some(target.version.clone()),
+    )
+    .await
+    .with_context(|| {
+        format!(
+            "failed to deploy version [{}] of [{model_name}], it may have been deleted",
+            target.version
+        )
+    })?;
+    record_deployment(
+        client,
+        lattice,
+        model_name,
+        Some(target.version.clone()),
+        Some(target.revision),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn digests(components: &[(&str, &str)]) -> BTreeMap<String, String> {
+        components
+            .iter()
+            .map(|(name, digest)| (name.to_string(), digest.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn diff_components() {
+        let previous = digests(&[("http", "a"), ("echo", "b"), ("kv", "c")]);
+        let current = digests(&[("http", "a"), ("echo", "x"), ("blob", "d")]);
+        let diff = ManifestDiff::between(&previous, &current);
+        assert_eq!(
+            diff,
+            ManifestDiff {
+                added: vec!["blob".into()],
+                removed: vec!["kv".into()],
+                changed: vec!["echo".into()],
+            }
+        );
+        assert_eq!(diff.to_string(), "+blob ~echo -kv");
+
+        let diff = ManifestDiff::between(&current, &current);
+        assert!(diff.is_empty());
+        assert_eq!(diff.to_string(), "no component changes");
+    }
+}
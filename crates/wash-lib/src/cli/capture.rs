@@ -1,6 +1,6 @@
 use std::{path::PathBuf, time::Duration};
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use async_nats::jetstream::{
     consumer::{pull::Config as ConsumerConfig, AckPolicy, DeliverPolicy},
     stream::Config,
@@ -10,8 +10,10 @@ use futures::TryStreamExt;
 use tokio::io::{stdin, stdout, AsyncReadExt, AsyncWriteExt};
 use tokio::time::Instant;
 use tracing::debug;
+use wasmcloud_control_interface::{PayloadCapture, DEFAULT_PAYLOAD_CAPTURE_CAPACITY};
 
 use super::{CliConnectionOpts, CommandOutput};
+use crate::common::{boxed_err_to_anyhow, find_host_id};
 use crate::config::WashConnectionOptions;
 use crate::{
     capture::{ReadCapture, WriteCapture},
@@ -46,6 +48,63 @@ pub struct CaptureCommand {
 #[derive(Debug, Subcommand, Clone)]
 pub enum CaptureSubcommand {
     Replay(CaptureReplayCommand),
+    /// Capture a sample of the payloads a component sends over a link on a specific host
+    #[clap(subcommand)]
+    Payloads(CapturePayloadsCommand),
+}
+
+#[derive(Debug, Subcommand, Clone)]
+pub enum CapturePayloadsCommand {
+    /// Start capturing the payloads a component sends over a link, replacing any existing capture
+    Start(CapturePayloadsStartCommand),
+    /// Stop capturing the payloads of a component, discarding all captured payloads
+    Stop(CapturePayloadsTargetArgs),
+    /// Get the payloads captured for a component, oldest first
+    Get(CapturePayloadsTargetArgs),
+}
+
+#[derive(Debug, Parser, Clone)]
+pub struct CapturePayloadsTargetArgs {
+    #[clap(flatten)]
+    pub opts: CliConnectionOpts,
+
+    /// ID of the host running the component. If a non-ID is provided, the host will be selected based
+    /// on matching the prefix of the ID or the friendly name and will return an error if more than
+    /// one host matches.
+    #[clap(name = "host-id")]
+    pub host_id: String,
+
+    /// ID of the component sending the invocations to capture
+    #[clap(name = "component-id")]
+    pub component_id: String,
+}
+
+#[derive(Debug, Parser, Clone)]
+pub struct CapturePayloadsStartCommand {
+    #[clap(flatten)]
+    pub target: CapturePayloadsTargetArgs,
+
+    /// Name of the link to capture the payloads of
+    #[clap(long = "link-name", default_value = "default")]
+    pub link_name: String,
+
+    /// Capture one of every `sample-rate` invocations
+    #[clap(long = "sample-rate", default_value = "1")]
+    pub sample_rate: u32,
+
+    /// Number of captured payloads kept by the host, the oldest payloads are dropped first
+    #[clap(long = "capacity", default_value_t = DEFAULT_PAYLOAD_CAPTURE_CAPACITY)]
+    pub capacity: usize,
+
+    /// Regular expression matching parts of payloads to redact before they are stored on the host,
+    /// e.g. `password=\S+`. Can be specified multiple times.
+    #[clap(long = "redact")]
+    pub redactions: Vec<String>,
+
+    /// JSONPath expression selecting values of JSON payloads to redact before they are stored on
+    /// the host, e.g. `$..password`. Can be specified multiple times.
+    #[clap(long = "redact-json")]
+    pub json_redactions: Vec<String>,
 }
 
 #[derive(Debug, Parser, Clone)]
@@ -143,6 +202,106 @@ Message: {}"#,
     Ok(CommandOutput::default())
 }
 
+/// Handles payload capture commands, which are sent to the control interface of a specific host
+pub async fn handle_payloads_command(cmd: CapturePayloadsCommand) -> Result<CommandOutput> {
+    let target = match &cmd {
+        CapturePayloadsCommand::Start(CapturePayloadsStartCommand { target, .. })
+        | CapturePayloadsCommand::Stop(target)
+        | CapturePayloadsCommand::Get(target) => target.clone(),
+    };
+    let wco: WashConnectionOptions = target.opts.try_into()?;
+    let client = wco.into_ctl_client(None).await?;
+    let (host_id, _friendly_name) = find_host_id(&target.host_id, &client).await?;
+    let component_id = target.component_id;
+
+    match cmd {
+        CapturePayloadsCommand::Start(start) => {
+            let capture = PayloadCapture::builder()
+                .source_id(&component_id)
+                .link_name(&start.link_name)
+                .sample_rate(start.sample_rate)
+                .capacity(start.capacity)
+                .redactions(start.redactions)
+                .json_redactions(start.json_redactions)
+                .build()
+                .map_err(boxed_err_to_anyhow)?;
+            let ack = client
+                .put_payload_capture(&host_id, capture)
+                .await
+                .map_err(boxed_err_to_anyhow)?;
+            if !ack.succeeded() {
+                bail!("failed to start payload capture: {}", ack.message());
+            }
+            Ok(CommandOutput::from_key_and_text(
+                "message",
+                format!(
+                    "Capturing payloads of component [{component_id}] over link [{}] on host [{host_id}]",
+                    start.link_name
+                ),
+            ))
+        }
+        CapturePayloadsCommand::Stop(_) => {
+            let ack = client
+                .delete_payload_capture(&host_id, &component_id)
+                .await
+                .map_err(boxed_err_to_anyhow)?;
+            if !ack.succeeded() {
+                bail!("failed to stop payload capture: {}", ack.message());
+            }
+            Ok(CommandOutput::from_key_and_text(
+                "message",
+                format!(
+                    "Stopped capturing payloads of component [{component_id}] on host [{host_id}]"
+                ),
+            ))
+        }
+        CapturePayloadsCommand::Get(_) => {
+            let response = client
+                .get_captured_payloads(&host_id, &component_id)
+                .await
+                .map_err(boxed_err_to_anyhow)?;
+            if !response.succeeded() {
+                bail!("failed to get captured payloads: {}", response.message());
+            }
+            let payloads = response.into_data().unwrap_or_default();
+            let text = payloads
+                .iter()
+                .map(|payload| {
+                    format!(
+                        r#"
+[{}]
+To: {}  Link: {}
+
+Operation: {}.{}
+Size: {} bytes{}
+Payload: {}"#,
+                        payload.captured_at(),
+                        payload.target(),
+                        payload.link_name(),
+                        payload.instance(),
+                        payload.function(),
+                        payload.size(),
+                        if payload.redacted() {
+                            " (redacted)"
+                        } else {
+                            ""
+                        },
+                        String::from_utf8_lossy(payload.payload()),
+                    )
+                })
+                .collect::<String>();
+            Ok(CommandOutput::new(
+                if payloads.is_empty() {
+                    format!("No payloads captured for component [{component_id}] yet")
+                } else {
+                    text
+                },
+                [("payloads".to_string(), serde_json::to_value(payloads)?)].into(),
+            ))
+        }
+    }
+}
+
 /// Handles the spy command, printing all output to stdout until the command is interrupted
 pub async fn handle_command(cmd: CaptureCommand) -> Result<CommandOutput> {
     let wco: WashConnectionOptions = cmd.opts.try_into()?;
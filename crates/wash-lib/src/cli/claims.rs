@@ -14,8 +14,9 @@ use wascap::{
     jwt::{Account, CapabilityProvider, Claims, Component, Operator},
     wasm::{days_from_now_to_jwt_time, sign_buffer_with_claims},
 };
+use wasmcloud_control_interface::ClaimsBundle;
 
-use super::{extract_keypair, get::GetClaimsCommand, CommandOutput, OutputKind};
+use super::{extract_keypair, get::GetClaimsCommand, CliConnectionOpts, CommandOutput, OutputKind};
 use crate::{
     cli::inspect,
     common::boxed_err_to_anyhow,
@@ -35,6 +36,31 @@ pub enum ClaimsCliCommand {
     /// Generate a signed JWT by supplying basic token information, a signing seed key, and metadata
     #[clap(name = "token", subcommand)]
     Token(TokenCommand),
+    /// Export the cached claims and issuers of a lattice as a bundle signed by a host in the lattice
+    #[clap(name = "export")]
+    Export(ExportClaimsCommand),
+    /// Import a signed claims bundle exported from another (e.g. connected) lattice
+    #[clap(name = "import")]
+    Import(ImportClaimsCommand),
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ExportClaimsCommand {
+    #[clap(flatten)]
+    pub opts: CliConnectionOpts,
+
+    /// File to write the signed claims bundle to
+    #[clap(short = 'd', long = "destination")]
+    pub destination: PathBuf,
+}
+
+#[derive(Args, Debug, Clone)]
+pub struct ImportClaimsCommand {
+    #[clap(flatten)]
+    pub opts: CliConnectionOpts,
+
+    /// Path to the signed claims bundle to import
+    pub bundle: PathBuf,
 }
 
 #[derive(Args, Debug, Clone)]
@@ -384,7 +410,81 @@ pub async fn handle_command(
         ClaimsCliCommand::Token(gencmd) => {
             generate_token(gencmd, output_kind, project_config.as_ref())
         }
+        ClaimsCliCommand::Export(cmd) => export_claims(cmd).await,
+        ClaimsCliCommand::Import(cmd) => import_claims(cmd).await,
+    }
+}
+
+/// Export the claims of a lattice as a signed bundle and write it to a file
+async fn export_claims(
+    ExportClaimsCommand { opts, destination }: ExportClaimsCommand,
+) -> Result<CommandOutput> {
+    let wco: WashConnectionOptions = opts.try_into()?;
+    let client = wco.into_ctl_client(None).await?;
+    let response = client
+        .export_claims()
+        .await
+        .map_err(boxed_err_to_anyhow)
+        .context("failed to export claims")?;
+    if !response.succeeded() {
+        bail!("failed to export claims: {}", response.message());
+    }
+    let bundle = response
+        .into_data()
+        .context("received claims export response without a bundle")?;
+    let contents = bundle.contents().map_err(boxed_err_to_anyhow)?;
+    let bytes = serde_json::to_vec_pretty(&bundle).context("failed to serialize claims bundle")?;
+    fs::write(&destination, bytes).with_context(|| {
+        format!(
+            "failed to write claims bundle to [{}]",
+            destination.display()
+        )
+    })?;
+
+    let mut map = HashMap::new();
+    map.insert("destination".to_string(), json!(destination));
+    map.insert("signer".to_string(), json!(bundle.signer()));
+    map.insert("lattice".to_string(), json!(contents.lattice()));
+    map.insert("claims".to_string(), json!(contents.claims().len()));
+    map.insert("issuers".to_string(), json!(contents.issuers()));
+    Ok(CommandOutput::new(
+        format!(
+            "Exported {} claims from {} issuer(s) in lattice {}, signed by host {}, to {}",
+            contents.claims().len(),
+            contents.issuers().len(),
+            contents.lattice(),
+            bundle.signer(),
+            destination.display(),
+        ),
+        map,
+    ))
+}
+
+/// Import the claims of a signed bundle read from a file into a lattice
+async fn import_claims(
+    ImportClaimsCommand { opts, bundle }: ImportClaimsCommand,
+) -> Result<CommandOutput> {
+    let bytes = fs::read(&bundle)
+        .with_context(|| format!("failed to read claims bundle [{}]", bundle.display()))?;
+    let bundle: ClaimsBundle =
+        serde_json::from_slice(&bytes).context("failed to parse claims bundle")?;
+    let signer = bundle.signer().to_string();
+
+    let wco: WashConnectionOptions = opts.try_into()?;
+    let client = wco.into_ctl_client(None).await?;
+    let response = client
+        .import_claims(bundle)
+        .await
+        .map_err(boxed_err_to_anyhow)
+        .context("failed to import claims")?;
+    if !response.succeeded() {
+        bail!("failed to import claims: {}", response.message());
     }
+
+    let mut map = HashMap::new();
+    map.insert("signer".to_string(), json!(signer));
+    map.insert("message".to_string(), json!(response.message()));
+    Ok(CommandOutput::new(response.message(), map))
 }
 
 fn generate_token(
@@ -774,6 +874,26 @@ mod test {
         assert!(sanitize_alias(None).unwrap().is_none());
     }
 
+    #[test]
+    fn test_claims_export_import() {
+        let cmd: Cmd =
+            Parser::try_parse_from(["claims", "export", "--destination", "bundle.json"]).unwrap();
+        match cmd.claims {
+            ClaimsCliCommand::Export(ExportClaimsCommand { destination, .. }) => {
+                assert_eq!(destination, PathBuf::from("bundle.json"));
+            }
+            cmd => panic!("claims constructed incorrect command: {cmd:?}"),
+        }
+
+        let cmd: Cmd = Parser::try_parse_from(["claims", "import", "bundle.json"]).unwrap();
+        match cmd.claims {
+            ClaimsCliCommand::Import(ImportClaimsCommand { bundle, .. }) => {
+                assert_eq!(bundle, PathBuf::from("bundle.json"));
+            }
+            cmd => panic!("claims constructed incorrect command: {cmd:?}"),
+        }
+    }
+
     #[test]
     /// Enumerates all options and flags of the `claims inspect` command
     /// to ensure command line arguments do not change between versions
@@ -8,8 +8,9 @@ use crate::{
 use anyhow::{Context, Result};
 use clap::Parser;
 use wasmcloud_control_interface::{Host, HostInventory};
+use wasmcloud_core::{config_schema_subject, ConfigSchema};
 
-use super::CliConnectionOpts;
+use super::{validate_component_id, CliConnectionOpts};
 
 #[derive(Debug, Clone, Parser)]
 pub struct GetClaimsCommand {
@@ -41,6 +42,21 @@ pub struct GetLinksCommand {
 pub struct GetHostsCommand {
     #[clap(flatten)]
     pub opts: CliConnectionOpts,
+
+    /// Enables Real-time updates, duration can be specified in ms or in humantime (eg: 2s, 5m, 54ms). Defaults to 5000 milliseconds.
+    /// Hosts that stop responding remain listed for a while, along with when they were last seen.
+    #[clap(long, short, num_args = 0..=1, default_missing_value = "5000", value_parser = parse_watch_interval)]
+    pub watch: Option<std::time::Duration>,
+}
+
+#[derive(Debug, Clone, Parser)]
+pub struct GetConfigSchemaCommand {
+    #[clap(flatten)]
+    pub opts: CliConnectionOpts,
+
+    /// ID of the running provider to retrieve the link configuration schema of
+    #[clap(name = "provider-id", value_parser = validate_component_id)]
+    pub provider_id: String,
 }
 
 #[derive(Debug, Clone, Parser)]
@@ -60,6 +76,10 @@ pub enum GetCommand {
     /// Retrieve inventory a given host on in the lattice
     #[clap(name = "inventory", alias = "inventories")]
     HostInventories(GetHostInventoriesCommand),
+
+    /// Retrieve the link configuration schema of a running provider
+    #[clap(name = "config-schema", alias = "schema")]
+    ConfigSchema(GetConfigSchemaCommand),
 }
 
 /// Retrieve host inventory
@@ -108,6 +128,27 @@ pub async fn get_hosts(cmd: GetHostsCommand) -> Result<Vec<Host>> {
         .context("Was able to connect to NATS, but failed to get hosts.")
 }
 
+/// Retrieve the link configuration schema of a running provider, if it declares one
+pub async fn get_config_schema(cmd: GetConfigSchemaCommand) -> Result<Option<ConfigSchema>> {
+    let wco: WashConnectionOptions = cmd.opts.try_into()?;
+    let lattice = wco.get_lattice();
+    let timeout = std::time::Duration::from_millis(wco.timeout_ms);
+    let nc = wco.into_nats_client().await?;
+    let res = tokio::time::timeout(
+        timeout,
+        nc.request(config_schema_subject(&lattice, &cmd.provider_id), "".into()),
+    )
+    .await
+    .with_context(|| {
+        format!(
+            "timed out waiting for provider [{}] to respond, is it running?",
+            cmd.provider_id
+        )
+    })?
+    .context("failed to request config schema from provider")?;
+    serde_json::from_slice(&res.payload).context("failed to parse config schema")
+}
+
 pub fn parse_watch_interval(arg: &str) -> Result<std::time::Duration, String> {
     if let Ok(duration) = humantime::Duration::from_str(arg) {
         return Ok(duration.into());
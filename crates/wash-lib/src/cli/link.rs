@@ -1,8 +1,17 @@
-use anyhow::{Context, Result};
+use core::fmt;
+
+use anyhow::{bail, Context, Result};
 use clap::Parser;
-use wasmcloud_control_interface::{CtlResponse, Link};
+use serde::Serialize;
+use wasmcloud_control_interface::{CtlResponse, Link, LinkHealthStatus, LinkValidation};
+use wit_parser::WorldKey;
 
-use crate::{cli::CliConnectionOpts, common::boxed_err_to_anyhow, config::WashConnectionOptions};
+use crate::{
+    cli::{cached_oci_file, CliConnectionOpts},
+    common::{boxed_err_to_anyhow, get_all_inventories},
+    config::WashConnectionOptions,
+    registry::{get_oci_artifact, OciPullOptions},
+};
 
 use super::validate_component_id;
 
@@ -74,12 +83,26 @@ pub struct LinkPutCommand {
     /// WIT namespace, package, and interface.
     #[clap(short = 'l', long = "link-name")]
     pub link_name: Option<String>,
+
+    /// Validate the link with the provider it connects before putting it. The link is not put
+    /// if the provider reports errors in its configuration or secrets
+    #[clap(long = "validate", default_value = "false")]
+    pub validate: bool,
 }
 
 #[derive(Parser, Debug, Clone)]
 pub struct LinkQueryCommand {
     #[clap(flatten)]
     pub opts: CliConnectionOpts,
+
+    /// ID of a running component to show the effective routing of, listing every interface it
+    /// imports along with the links resolving it and the health of their targets
+    #[clap(name = "component-id", value_parser = validate_component_id)]
+    pub component_id: Option<String>,
+
+    /// Allow insecure (HTTP) registry connections when fetching the component to list its imports
+    #[clap(long = "insecure", requires = "component-id")]
+    pub insecure: bool,
 }
 
 #[derive(Debug, Clone, Parser)]
@@ -218,3 +241,261 @@ pub async fn put_link(wco: WashConnectionOptions, link: Link) -> Result<CtlRespo
             )
         })
 }
+
+/// Validate a link with the provider it connects, without putting it
+///
+/// The link is validated by a host running the target of the link, or the source if the target
+/// is not a running provider.
+///
+/// # Arguments
+///
+/// * `wco` - Options for connecting to wash
+/// * `link` - The [`wasmcloud_control_interface::Link`] to validate
+pub async fn validate_link(wco: WashConnectionOptions, link: Link) -> Result<LinkValidation> {
+    let ctl_client = wco.into_ctl_client(None).await?;
+    let inventories = get_all_inventories(&ctl_client).await?;
+    let Some(host_id) = [link.target(), link.source_id()]
+        .into_iter()
+        .find_map(|id| {
+            inventories.iter().find_map(|inv| {
+                inv.providers()
+                    .iter()
+                    .any(|provider| provider.id() == id)
+                    .then(|| inv.host_id().to_string())
+            })
+        })
+    else {
+        bail!(
+            "neither {} nor {} is a provider running in the lattice, links can only be validated with running providers",
+            link.source_id(),
+            link.target()
+        )
+    };
+    let res = ctl_client
+        .validate_link(&host_id, link.clone())
+        .await
+        .map_err(boxed_err_to_anyhow)
+        .with_context(|| {
+            format!(
+                "Failed to validate link between {} and {} on host {host_id}",
+                link.source_id(),
+                link.target(),
+            )
+        })?;
+    if !res.succeeded() {
+        bail!("{}", res.message())
+    }
+    res.into_data()
+        .context("host did not return link validation result")
+}
+
+/// Health of the target resolving an interface imported by a component, as observed in the lattice
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "status", content = "message")]
+pub enum TargetHealth {
+    /// The interface is implemented by the host, no link is involved
+    Host,
+    /// No link resolves the interface, so calls to it fail
+    Unlinked,
+    /// The target of the link is not running in the lattice, so calls to it fail
+    NotRunning,
+    /// The target of the link is running and reported no problems with the link
+    Running,
+    /// The target of the link reported the link as degraded
+    Degraded(Option<String>),
+    /// The target of the link reported the link as unhealthy, or the target is quarantined
+    Unhealthy(Option<String>),
+}
+
+impl fmt::Display for TargetHealth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Host => write!(f, "provided by host"),
+            Self::Unlinked => write!(f, "unlinked"),
+            Self::NotRunning => write!(f, "target not running"),
+            Self::Running => write!(f, "running"),
+            Self::Degraded(None) => write!(f, "degraded"),
+            Self::Degraded(Some(message)) => write!(f, "degraded: {message}"),
+            Self::Unhealthy(None) => write!(f, "unhealthy"),
+            Self::Unhealthy(Some(message)) => write!(f, "unhealthy: {message}"),
+        }
+    }
+}
+
+/// Effective routing of an interface imported by a component
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InterfaceRoute {
+    /// The imported interface, e.g. `wasi:keyvalue/store@0.2.0-draft`
+    pub interface: String,
+    /// The name of the link resolving the interface, if linked
+    pub link_name: Option<String>,
+    /// The target of the link resolving the interface, if linked
+    pub target: Option<String>,
+    /// The named configuration of the link made available to the component
+    pub source_config: Vec<String>,
+    /// The named configuration of the link made available to the target
+    pub target_config: Vec<String>,
+    /// The health of the target
+    pub health: TargetHealth,
+}
+
+/// Whether an interface is implemented by the host itself rather than routed over a link
+fn is_host_interface(namespace: &str, package: &str, interface: &str) -> bool {
+    matches!(
+        (namespace, package, interface),
+        (
+            "wasi",
+            "cli" | "clocks" | "config" | "filesystem" | "io" | "logging" | "random" | "sockets",
+            _
+        ) | ("wasmcloud", "bus" | "secrets", _)
+            | ("wasi", "http", "types")
+    )
+}
+
+/// List the interfaces imported by a component, e.g. `wasi:keyvalue/store@0.2.0-draft`
+fn component_imports(wasm: &[u8]) -> Result<Vec<String>> {
+    let decoded = wit_component::decode(wasm).context("failed to decode component WIT")?;
+    let wit_component::DecodedWasm::Component(resolve, world) = decoded else {
+        bail!("artifact is not a component")
+    };
+    let mut imports: Vec<String> = resolve.worlds[world]
+        .imports
+        .keys()
+        .filter_map(|key| match key {
+            WorldKey::Interface(id) => resolve.id_of(*id),
+            WorldKey::Name(..) => None,
+        })
+        .collect();
+    imports.sort();
+    Ok(imports)
+}
+
+/// Query the effective routing of the interfaces imported by a running component
+///
+/// Every interface imported by the component is listed along with the links resolving it, their
+/// named configuration and the health of their targets, as reported by the hosts in the lattice.
+///
+/// # Arguments
+///
+/// * `wco` - Options for connecting to wash
+/// * `component_id` - The ID of the component
+/// * `insecure` - Whether to allow insecure (HTTP) registry connections when fetching the component
+pub async fn get_component_routes(
+    wco: WashConnectionOptions,
+    component_id: &str,
+    insecure: bool,
+) -> Result<Vec<InterfaceRoute>> {
+    let ctl_client = wco.into_ctl_client(None).await?;
+    let inventories = get_all_inventories(&ctl_client).await?;
+    let Some(component) = inventories
+        .iter()
+        .flat_map(|inv| inv.components())
+        .find(|component| component.id() == component_id)
+    else {
+        bail!("component {component_id} is not running in the lattice")
+    };
+    let links: Vec<Link> = ctl_client
+        .get_links()
+        .await
+        .map_err(boxed_err_to_anyhow)?
+        .into_data()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|link| link.source_id() == component_id)
+        .collect();
+
+    let image_ref = component.image_ref();
+    let wasm = if let Some(path) = image_ref.strip_prefix("file://") {
+        get_oci_artifact(path.to_string(), None, OciPullOptions::default()).await
+    } else {
+        get_oci_artifact(
+            image_ref.to_string(),
+            Some(cached_oci_file(image_ref)),
+            OciPullOptions {
+                allow_latest: true,
+                insecure,
+                ..Default::default()
+            },
+        )
+        .await
+    }
+    .with_context(|| format!("failed to fetch component {component_id} from {image_ref}"))?;
+
+    let health = |link: &Link| {
+        if let Some(provider) = inventories
+            .iter()
+            .flat_map(|inv| inv.providers())
+            .find(|provider| provider.id() == link.target())
+        {
+            let Some(health) = provider.link_health().iter().find(|health| {
+                health.source_id() == component_id && health.link_name() == link.name()
+            }) else {
+                return TargetHealth::Running;
+            };
+            let message = health.message().map(ToString::to_string);
+            return match health.status() {
+                LinkHealthStatus::Degraded => TargetHealth::Degraded(message),
+                LinkHealthStatus::Unhealthy => TargetHealth::Unhealthy(message),
+                _ => TargetHealth::Running,
+            };
+        }
+        match inventories
+            .iter()
+            .flat_map(|inv| inv.components())
+            .find(|component| component.id() == link.target())
+        {
+            Some(target) => match target.quarantine() {
+                Some(quarantine) => {
+                    TargetHealth::Unhealthy(Some(format!("quarantined: {}", quarantine.reason())))
+                }
+                None => TargetHealth::Running,
+            },
+            None => TargetHealth::NotRunning,
+        }
+    };
+
+    let mut routes = Vec::new();
+    for interface in component_imports(&wasm)? {
+        let Some((namespace, package, name)) = interface.split_once(':').and_then(|(ns, rest)| {
+            let (package, name) = rest.split_once('/')?;
+            let name = name.split_once('@').map_or(name, |(name, _)| name);
+            Some((ns, package, name))
+        }) else {
+            continue;
+        };
+        let route = |link_name, target, source_config, target_config, health| InterfaceRoute {
+            interface: interface.clone(),
+            link_name,
+            target,
+            source_config,
+            target_config,
+            health,
+        };
+        if is_host_interface(namespace, package, name) {
+            routes.push(route(None, None, vec![], vec![], TargetHealth::Host));
+            continue;
+        }
+        let mut linked = links
+            .iter()
+            .filter(|link| {
+                link.wit_namespace() == namespace
+                    && link.wit_package() == package
+                    && link.interfaces().iter().any(|iface| iface == name)
+            })
+            .peekable();
+        if linked.peek().is_none() {
+            routes.push(route(None, None, vec![], vec![], TargetHealth::Unlinked));
+            continue;
+        }
+        for link in linked {
+            routes.push(route(
+                Some(link.name().to_string()),
+                Some(link.target().to_string()),
+                link.source_config().clone(),
+                link.target_config().clone(),
+                health(link),
+            ));
+        }
+    }
+    Ok(routes)
+}
@@ -45,6 +45,7 @@ pub mod label;
 pub mod link;
 pub mod output;
 pub mod par;
+pub mod provider;
 pub mod registry;
 pub mod scale;
 pub mod spy;
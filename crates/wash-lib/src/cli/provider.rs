@@ -0,0 +1,366 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Context as _, Result};
+use clap::{Parser, Subcommand};
+use serde_json::json;
+use wasmcloud_core::logging::Level;
+use wasmcloud_core::{
+    prefixed_subject, provider_admin_subject, ProviderAdminRequest, ProviderAdminResponse,
+};
+
+use crate::config::WashConnectionOptions;
+
+use super::{validate_component_id, CliConnectionOpts, CommandOutput};
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum ProviderCommand {
+    /// Perform administrative operations on a running provider
+    #[clap(name = "admin")]
+    Admin(ProviderAdminCommand),
+    /// Replay a range of JetStream messages to a component linked to a NATS messaging provider
+    #[clap(name = "replay")]
+    Replay(ProviderReplayCommand),
+}
+
+#[derive(Debug, Clone, Parser)]
+pub struct ProviderAdminCommand {
+    #[clap(flatten)]
+    pub opts: CliConnectionOpts,
+
+    /// ID of the running provider to administer
+    #[clap(name = "provider-id", value_parser = validate_component_id)]
+    pub provider_id: String,
+
+    /// Subject prefix of the tenant the lattice is isolated under, as configured on the host
+    #[clap(long = "subject-prefix", env = "WASMCLOUD_SUBJECT_PREFIX")]
+    pub subject_prefix: Option<String>,
+
+    #[clap(subcommand)]
+    pub operation: ProviderAdminOperation,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum ProviderAdminOperation {
+    /// List the links the provider is the source or target of
+    #[clap(name = "dump-links")]
+    DumpLinks,
+    /// Dump the internal state of the provider, if it exposes any
+    #[clap(name = "dump-state")]
+    DumpState,
+    /// Change the log level of the provider without restarting it
+    #[clap(name = "set-log-level")]
+    SetLogLevel {
+        /// Log level to set, one of `error`, `warn`, `info`, `debug` or `trace`
+        #[clap(name = "level", value_parser = parse_log_level)]
+        level: Level,
+    },
+    /// Flush the caches of the provider, e.g. cached clients or responses
+    #[clap(name = "flush-caches")]
+    FlushCaches,
+    /// Perform an operation specific to the provider
+    #[clap(name = "operation")]
+    Operation {
+        /// Name of the operation
+        #[clap(name = "name")]
+        name: String,
+        /// Arguments of the operation as a JSON value, as defined by the provider
+        #[clap(name = "args", value_parser = parse_json_args)]
+        args: Option<serde_json::Value>,
+    },
+}
+
+#[derive(Debug, Clone, Parser)]
+pub struct ProviderReplayCommand {
+    #[clap(flatten)]
+    pub opts: CliConnectionOpts,
+
+    /// ID of the running NATS messaging provider to replay messages with
+    #[clap(name = "provider-id", value_parser = validate_component_id)]
+    pub provider_id: String,
+
+    /// Subject prefix of the tenant the lattice is isolated under, as configured on the host
+    #[clap(long = "subject-prefix", env = "WASMCLOUD_SUBJECT_PREFIX")]
+    pub subject_prefix: Option<String>,
+
+    /// ID of the linked component to replay messages to
+    #[clap(long = "component", value_parser = validate_component_id)]
+    pub component_id: String,
+
+    /// JetStream stream to replay messages of
+    #[clap(long = "stream")]
+    pub stream: String,
+
+    /// Only replay messages matching this subject filter
+    #[clap(long = "filter-subject")]
+    pub filter_subject: Option<String>,
+
+    /// Stream sequence of the first message to replay
+    #[clap(long = "start-sequence", conflicts_with = "start_time")]
+    pub start_sequence: Option<u64>,
+
+    /// Stream sequence of the last message to replay
+    #[clap(long = "end-sequence")]
+    pub end_sequence: Option<u64>,
+
+    /// Replay messages published at or after this RFC 3339 time, e.g. `2024-06-01T12:00:00Z`
+    #[clap(long = "start-time")]
+    pub start_time: Option<String>,
+
+    /// Replay messages published at or before this RFC 3339 time
+    #[clap(long = "end-time")]
+    pub end_time: Option<String>,
+}
+
+impl From<ProviderReplayCommand> for ProviderAdminRequest {
+    fn from(cmd: ProviderReplayCommand) -> Self {
+        let mut args = serde_json::Map::new();
+        args.insert("component_id".into(), json!(cmd.component_id));
+        args.insert("stream".into(), json!(cmd.stream));
+        for (key, value) in [
+            ("filter_subject", cmd.filter_subject.map(|v| json!(v))),
+            ("start_sequence", cmd.start_sequence.map(|v| json!(v))),
+            ("end_sequence", cmd.end_sequence.map(|v| json!(v))),
+            ("start_time", cmd.start_time.map(|v| json!(v))),
+            ("end_time", cmd.end_time.map(|v| json!(v))),
+        ] {
+            if let Some(value) = value {
+                args.insert(key.into(), value);
+            }
+        }
+        Self::Operation {
+            name: "replay".into(),
+            args: args.into(),
+        }
+    }
+}
+
+fn parse_json_args(args: &str) -> Result<serde_json::Value> {
+    serde_json::from_str(args).context("arguments must be a valid JSON value")
+}
+
+fn parse_log_level(level: &str) -> Result<Level> {
+    match level.to_ascii_lowercase().as_str() {
+        "error" => Ok(Level::Error),
+        "warn" => Ok(Level::Warn),
+        "info" => Ok(Level::Info),
+        "debug" => Ok(Level::Debug),
+        "trace" => Ok(Level::Trace),
+        "critical" => Ok(Level::Critical),
+        _ => {
+            bail!("invalid log level `{level}`, expected one of error, warn, info, debug or trace")
+        }
+    }
+}
+
+impl From<ProviderAdminOperation> for ProviderAdminRequest {
+    fn from(operation: ProviderAdminOperation) -> Self {
+        match operation {
+            ProviderAdminOperation::DumpLinks => Self::DumpLinks,
+            ProviderAdminOperation::DumpState => Self::DumpState,
+            ProviderAdminOperation::SetLogLevel { level } => Self::SetLogLevel { level },
+            ProviderAdminOperation::FlushCaches => Self::FlushCaches,
+            ProviderAdminOperation::Operation { name, args } => Self::Operation {
+                name,
+                args: args.unwrap_or_default(),
+            },
+        }
+    }
+}
+
+/// Send an administrative request to a running provider, prefixing the subjects with
+/// `subject_prefix` if the lattice is isolated under a tenant subject prefix
+pub async fn provider_admin(
+    opts: CliConnectionOpts,
+    subject_prefix: Option<&str>,
+    provider_id: &str,
+    req: &ProviderAdminRequest,
+) -> Result<ProviderAdminResponse> {
+    let wco: WashConnectionOptions = opts.try_into()?;
+    let lattice = wco.get_lattice();
+    let timeout = std::time::Duration::from_millis(wco.timeout_ms);
+    let nc = wco.into_nats_client().await?;
+    let req = serde_json::to_vec(req).context("failed to serialize admin request")?;
+    let subject = prefixed_subject(
+        subject_prefix,
+        provider_admin_subject(&lattice, provider_id),
+    );
+    // Tenants may only receive replies on inboxes under their subject prefix
+    let inbox = prefixed_subject(subject_prefix, nc.new_inbox());
+    let res = tokio::time::timeout(
+        timeout,
+        nc.send_request(
+            subject,
+            async_nats::Request::new().payload(req.into()).inbox(inbox),
+        ),
+    )
+    .await
+    .with_context(|| {
+        format!("timed out waiting for provider [{provider_id}] to respond, is it running?")
+    })?
+    .context("failed to send admin request to provider")?;
+    serde_json::from_slice(&res.payload).context("failed to parse admin response")
+}
+
+pub async fn handle_command(cmd: ProviderCommand) -> Result<CommandOutput> {
+    let (opts, subject_prefix, provider_id, req) = match cmd {
+        ProviderCommand::Admin(ProviderAdminCommand {
+            opts,
+            provider_id,
+            subject_prefix,
+            operation,
+        }) => (
+            opts,
+            subject_prefix,
+            provider_id,
+            ProviderAdminRequest::from(operation),
+        ),
+        ProviderCommand::Replay(cmd) => (
+            cmd.opts.clone(),
+            cmd.subject_prefix.clone(),
+            cmd.provider_id.clone(),
+            ProviderAdminRequest::from(cmd),
+        ),
+    };
+    let res = provider_admin(opts, subject_prefix.as_deref(), &provider_id, &req).await?;
+    if !res.success {
+        bail!(
+            "provider [{provider_id}] failed to perform operation: {}",
+            res.message.as_deref().unwrap_or("unknown error")
+        );
+    }
+
+    let mut map = HashMap::new();
+    let text = match req {
+        ProviderAdminRequest::DumpLinks => {
+            map.insert("links".to_string(), json!(res.links));
+            if res.links.is_empty() {
+                format!("Provider [{provider_id}] has no links")
+            } else {
+                res.links
+                    .iter()
+                    .map(|link| {
+                        format!(
+                            "{} -> {} ({}:{}/{}, link name: {})",
+                            link.source_id,
+                            link.target,
+                            link.wit_namespace,
+                            link.wit_package,
+                            link.interfaces.join(","),
+                            link.name
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+        }
+        ProviderAdminRequest::DumpState => {
+            map.insert("state".to_string(), json!(res.state));
+            match res.state {
+                Some(state) => serde_json::to_string_pretty(&state)
+                    .context("failed to format provider state")?,
+                None => format!("Provider [{provider_id}] does not expose state"),
+            }
+        }
+        ProviderAdminRequest::SetLogLevel { level } => {
+            map.insert("level".to_string(), json!(level));
+            format!("Log level of provider [{provider_id}] set to {level:?}")
+        }
+        ProviderAdminRequest::FlushCaches => format!("Flushed caches of provider [{provider_id}]"),
+        ProviderAdminRequest::Operation { name, .. } => {
+            map.insert("output".to_string(), json!(res.output));
+            match res.output {
+                Some(output) => serde_json::to_string_pretty(&output)
+                    .context("failed to format operation output")?,
+                None => format!("Provider [{provider_id}] performed operation `{name}`"),
+            }
+        }
+        _ => format!("Provider [{provider_id}] performed the operation"),
+    };
+    Ok(CommandOutput::new(text, map))
+}
+
+#[cfg(test)]
+mod test {
+    use clap::Parser;
+
+    use wasmcloud_core::ProviderAdminRequest;
+
+    use super::{ProviderAdminOperation, ProviderCommand};
+
+    #[derive(Parser)]
+    struct Cmd {
+        #[clap(subcommand)]
+        command: ProviderCommand,
+    }
+
+    #[test]
+    fn test_parse_admin() {
+        let ProviderCommand::Admin(cmd) =
+            Cmd::try_parse_from(["provider", "admin", "MPROVIDER", "set-log-level", "DEBUG"])
+                .unwrap()
+                .command
+        else {
+            panic!("expected admin command");
+        };
+        assert_eq!(cmd.provider_id, "MPROVIDER");
+        assert!(matches!(
+            cmd.operation,
+            ProviderAdminOperation::SetLogLevel {
+                level: wasmcloud_core::logging::Level::Debug
+            }
+        ));
+        assert!(
+            Cmd::try_parse_from(["provider", "admin", "MPROVIDER", "set-log-level", "loud"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_replay() {
+        let ProviderCommand::Replay(cmd) = Cmd::try_parse_from([
+            "provider",
+            "replay",
+            "MPROVIDER",
+            "--component",
+            "orders",
+            "--stream",
+            "ORDERS",
+            "--start-sequence",
+            "10",
+            "--end-sequence",
+            "20",
+        ])
+        .unwrap()
+        .command
+        else {
+            panic!("expected replay command");
+        };
+        let ProviderAdminRequest::Operation { name, args } = ProviderAdminRequest::from(cmd) else {
+            panic!("expected operation request");
+        };
+        assert_eq!(name, "replay");
+        assert_eq!(
+            args,
+            serde_json::json!({
+                "component_id": "orders",
+                "stream": "ORDERS",
+                "start_sequence": 10,
+                "end_sequence": 20,
+            })
+        );
+        assert!(Cmd::try_parse_from([
+            "provider",
+            "replay",
+            "MPROVIDER",
+            "--component",
+            "orders",
+            "--stream",
+            "ORDERS",
+            "--start-sequence",
+            "10",
+            "--start-time",
+            "2024-06-01T12:00:00Z",
+        ])
+        .is_err());
+    }
+}
@@ -1,9 +1,15 @@
+use std::time::Duration;
+
 use anyhow::Result;
 use clap::Parser;
 use futures::StreamExt;
+use serde_json::json;
 
-use super::{validate_component_id, CliConnectionOpts, CommandOutput};
-use crate::{config::WashConnectionOptions, spier::Spier};
+use super::{validate_component_id, CliConnectionOpts, CommandOutput, OutputKind};
+use crate::{
+    config::WashConnectionOptions,
+    spier::{InvocationStatus, ObservedInvocation, Spier, DEFAULT_INBOX_PREFIX},
+};
 
 #[derive(Debug, Parser, Clone)]
 pub struct SpyCommand {
@@ -12,33 +18,157 @@ pub struct SpyCommand {
     #[clap(name = "component_id", value_parser = validate_component_id)]
     pub component_id: String,
 
+    /// Only show invocations whose interface, function, source or target contains this value,
+    /// e.g. `wasi:http` or `handle`
+    #[clap(long = "filter")]
+    pub filter: Option<String>,
+
+    /// Prefix of the inboxes responses to invocations are sent to, which must match the inbox
+    /// prefix of the hosts in the lattice, e.g. `<subject-prefix>._INBOX` for lattices isolated
+    /// under a subject prefix
+    #[clap(
+        long = "inbox-prefix",
+        env = "WASMCLOUD_INBOX_PREFIX",
+        default_value = DEFAULT_INBOX_PREFIX
+    )]
+    pub inbox_prefix: String,
+
     #[clap(flatten)]
     pub opts: CliConnectionOpts,
 }
 
-/// Handles the spy command, printing all output to stdout until the command is interrupted
-pub async fn handle_command(cmd: SpyCommand) -> Result<CommandOutput> {
+/// Handles the spy command, printing all output to stdout until the command is interrupted.
+///
+/// With JSON output, every observed invocation is printed as a JSON object on a single line.
+pub async fn handle_command(cmd: SpyCommand, output_kind: OutputKind) -> Result<CommandOutput> {
     let wco: WashConnectionOptions = cmd.opts.try_into()?;
     let ctl_client = wco.clone().into_ctl_client(None).await?;
     let nats_client = wco.into_nats_client().await?;
 
-    let mut spier = Spier::new(&cmd.component_id, &ctl_client, &nats_client).await?;
+    let mut spier = Spier::new(
+        &cmd.component_id,
+        &ctl_client,
+        &nats_client,
+        &cmd.inbox_prefix,
+    )
+    .await?;
 
-    println!("Spying on component {}\n", spier.component_id());
+    if matches!(output_kind, OutputKind::Text) {
+        println!("Spying on component {}\n", spier.component_id());
+    }
 
     while let Some(msg) = spier.next().await {
-        println!(
-            r#"
+        if cmd
+            .filter
+            .as_deref()
+            .is_some_and(|filter| !msg.matches(filter))
+        {
+            continue;
+        }
+        match output_kind {
+            OutputKind::Text => println!("{}", format_invocation(&msg)),
+            OutputKind::Json => println!("{}", invocation_json(&msg)),
+        }
+    }
+
+    if matches!(output_kind, OutputKind::Text) {
+        println!("Message subscribers closed");
+    }
+
+    Ok(CommandOutput::default())
+}
+
+fn latency_ms(latency: Duration) -> f64 {
+    latency.as_micros() as f64 / 1000.0
+}
+
+fn format_latency(invocation: &ObservedInvocation) -> String {
+    invocation
+        .latency
+        .map(|latency| format!("{:.3}ms", latency_ms(latency)))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn format_invocation(msg: &ObservedInvocation) -> String {
+    format!(
+        r#"
 [{}]
 From: {:<25} To: {:<25}
 
-Operation: {}
+Interface: {}
+Function:  {}
+Size:      {} bytes
+Latency:   {}
+Status:    {}
 Message: {}"#,
-            msg.timestamp, msg.from, msg.to, msg.operation, msg.message
-        );
-    }
+        msg.timestamp,
+        msg.from,
+        msg.to,
+        msg.interface,
+        msg.function,
+        msg.payload_size,
+        format_latency(msg),
+        msg.status,
+        msg.message
+    )
+}
 
-    println!("Message subscribers closed");
+fn invocation_json(msg: &ObservedInvocation) -> serde_json::Value {
+    let (status, error) = match &msg.status {
+        InvocationStatus::Ok => ("ok", None),
+        InvocationStatus::Error(err) => ("error", Some(err.as_str())),
+        InvocationStatus::Unknown => ("unknown", None),
+    };
+    json!({
+        "timestamp": msg.timestamp.to_rfc3339(),
+        "from": msg.from,
+        "to": msg.to,
+        "interface": msg.interface,
+        "function": msg.function,
+        "payload_size": msg.payload_size,
+        "latency_ms": msg.latency.map(latency_ms),
+        "status": status,
+        "error": error,
+        "message": msg.message.to_string(),
+    })
+}
 
-    Ok(CommandOutput::default())
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use chrono::Local;
+    use clap::Parser;
+
+    use super::{invocation_json, SpyCommand};
+    use crate::spier::{InvocationStatus, ObservedInvocation, ObservedMessage};
+
+    #[test]
+    fn test_spy_command() {
+        let cmd = SpyCommand::try_parse_from(["spy", "hello", "--filter", "wasi:http"]).unwrap();
+        assert_eq!(cmd.component_id, "hello");
+        assert_eq!(cmd.filter.as_deref(), Some("wasi:http"));
+
+        let invocation = ObservedInvocation {
+            timestamp: Local::now(),
+            from: "http-server".into(),
+            to: "hello".into(),
+            operation: "wasi:http/incoming-handler@0.2.0.handle".into(),
+            interface: "wasi:http/incoming-handler@0.2.0".into(),
+            function: "handle".into(),
+            payload_size: 42,
+            latency: Some(Duration::from_millis(3)),
+            status: InvocationStatus::Error("boom".into()),
+            message: ObservedMessage::Raw(Vec::new()),
+        };
+        assert!(invocation.matches("wasi:http"));
+        assert!(invocation.matches("http-server"));
+        assert!(!invocation.matches("wasi:keyvalue"));
+
+        let json = invocation_json(&invocation);
+        assert_eq!(json["status"], "error");
+        assert_eq!(json["error"], "boom");
+        assert_eq!(json["payload_size"], 42);
+        assert_eq!(json["latency_ms"], 3.0);
+    }
 }
@@ -15,6 +15,10 @@ pub enum UpdateCommand {
     /// Update a component running in a host to a newer version
     #[clap(name = "component")]
     Component(UpdateComponentCommand),
+
+    /// Update a provider running in a host to a newer version without dropping its links
+    #[clap(name = "provider")]
+    Provider(UpdateProviderCommand),
 }
 
 #[derive(Debug, Clone, Parser)]
@@ -39,6 +43,26 @@ pub struct UpdateComponentCommand {
     pub new_component_ref: String,
 }
 
+#[derive(Debug, Clone, Parser)]
+pub struct UpdateProviderCommand {
+    #[clap(flatten)]
+    pub opts: CliConnectionOpts,
+
+    /// ID of host to update the provider on. If no host ID is passed, a host will be selected based
+    /// on whether or not the provider is running on it. If more than 1 host is running this provider,
+    /// an error will be returned with a list of hosts running the provider
+    #[clap(long = "host-id")]
+    pub host_id: Option<String>,
+
+    /// Unique ID of the provider to update
+    #[clap(name = "provider-id", value_parser = validate_component_id)]
+    pub provider_id: String,
+
+    /// Provider reference to replace the current running provider with, e.g. the absolute file path or OCI URL.
+    #[clap(name = "new-provider-ref")]
+    pub new_provider_ref: String,
+}
+
 pub async fn handle_update_component(cmd: UpdateComponentCommand) -> Result<CommandOutput> {
     let wco: WashConnectionOptions = cmd.opts.try_into()?;
     let client = wco.into_ctl_client(None).await?;
@@ -133,3 +157,97 @@ pub async fn handle_update_component(cmd: UpdateComponentCommand) -> Result<Comm
         format!("Host [{}]: {}", host_id, message),
     ))
 }
+
+pub async fn handle_update_provider(cmd: UpdateProviderCommand) -> Result<CommandOutput> {
+    let wco: WashConnectionOptions = cmd.opts.try_into()?;
+    let client = wco.into_ctl_client(None).await?;
+
+    let inventory = if let Some(host_id) = cmd.host_id {
+        client
+            .get_host_inventory(&host_id)
+            .await
+            .map(|inventory| inventory.into_data())
+            .map_err(boxed_err_to_anyhow)?
+            .context(format!(
+                "Supplied host [{}] did not respond to inventory query",
+                host_id
+            ))?
+    } else {
+        let mut inventories = get_all_inventories(&client)
+            .await?
+            .into_iter()
+            .filter(|inv| {
+                inv.providers()
+                    .iter()
+                    .any(|provider| provider.id() == cmd.provider_id)
+            })
+            .collect::<Vec<HostInventory>>();
+
+        match inventories[..] {
+            [] => {
+                bail!("No host found running provider [{}]", cmd.provider_id)
+            }
+            [_] => inventories.remove(0),
+            _ => {
+                bail!(
+                    "Provider [{}] cannot be updated because multiple hosts are running it: [{}]",
+                    cmd.provider_id,
+                    inventories
+                        .iter()
+                        .map(|h| h.host_id().to_string())
+                        .collect::<Vec<String>>()
+                        .join(","),
+                );
+            }
+        }
+    };
+
+    let Some((host_id, provider_ref)) = inventory
+        .providers()
+        .iter()
+        .find(|provider| provider.id() == cmd.provider_id)
+        .map(|provider| {
+            (
+                inventory.host_id().to_string(),
+                provider.image_ref().unwrap_or_default().to_string(),
+            )
+        })
+    else {
+        bail!(
+            "Provider {} not found on host [{}]",
+            cmd.provider_id,
+            inventory.host_id(),
+        );
+    };
+
+    if provider_ref == cmd.new_provider_ref {
+        return Ok(CommandOutput::from_key_and_text(
+            "result",
+            format!(
+                "Provider {} already updated to {} on host [{host_id}]",
+                cmd.provider_id, cmd.new_provider_ref,
+            ),
+        ));
+    }
+
+    let ack = client
+        .update_provider(&host_id, &cmd.provider_id, &cmd.new_provider_ref, None)
+        .await
+        .map_err(boxed_err_to_anyhow)?;
+    if !ack.succeeded() {
+        bail!("Operation failed on host [{host_id}]: {}", ack.message());
+    }
+
+    let message = match ack.message().to_string() {
+        message if message.is_empty() => format!(
+            "provider {} updating from {} to {}",
+            cmd.provider_id, provider_ref, cmd.new_provider_ref
+        ),
+        message => message,
+    };
+
+    Ok(CommandOutput::from_key_and_text(
+        "result",
+        format!("Host [{}]: {}", host_id, message),
+    ))
+}
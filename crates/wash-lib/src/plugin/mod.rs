@@ -1,6 +1,7 @@
 use wasmtime_wasi::{WasiCtx, WasiView};
 use wasmtime_wasi_http::{WasiHttpCtx, WasiHttpView};
 
+pub mod permissions;
 pub mod subcommand;
 
 /// The directory where plugins are stored.
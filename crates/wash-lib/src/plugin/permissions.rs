@@ -0,0 +1,206 @@
+//! Permissions declared by plugins and the consent given by users to grant them
+
+use std::collections::HashSet;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+/// The directory, relative to the plugin directory, where consent to plugin permissions is recorded
+pub const CONSENT_DIR: &str = "permissions";
+
+/// A permission required by a plugin
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum Permission {
+    /// Access to a file or directory on the host
+    Filesystem { path: String, write: bool },
+    /// Outgoing network connections to a host, given as `host` or `host:port`
+    Network { host: String },
+    /// Operations on the lattice
+    Lattice { operation: String },
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Filesystem { path, write: true } => write!(f, "read and write {path}"),
+            Self::Filesystem { path, write: false } => write!(f, "read {path}"),
+            Self::Network { host } => write!(f, "connect to {host}"),
+            Self::Lattice { operation } => write!(f, "perform `{operation}` on the lattice"),
+        }
+    }
+}
+
+/// Consent given by the user to grant permissions to a plugin
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Consent {
+    /// The permissions granted to the plugin
+    pub granted: Vec<Permission>,
+}
+
+impl Consent {
+    /// Path of the file recording consent for the plugin with the given ID
+    pub fn path(plugin_dir: impl AsRef<Path>, id: &str) -> PathBuf {
+        plugin_dir
+            .as_ref()
+            .join(CONSENT_DIR)
+            .join(format!("{id}.json"))
+    }
+
+    /// Load the recorded consent for the plugin with the given ID. If no consent was recorded, no
+    /// permissions are granted
+    pub async fn load(plugin_dir: impl AsRef<Path>, id: &str) -> anyhow::Result<Self> {
+        let path = Self::path(plugin_dir, id);
+        match tokio::fs::read(&path).await {
+            Ok(data) => serde_json::from_slice(&data)
+                .with_context(|| format!("Unable to parse plugin consent at {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(anyhow::Error::from(e).context(format!(
+                "Unable to read plugin consent at {}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Record consent for the plugin with the given ID
+    pub async fn save(&self, plugin_dir: impl AsRef<Path>, id: &str) -> anyhow::Result<()> {
+        let path = Self::path(plugin_dir, id);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .context("Unable to create plugin consent directory")?;
+        }
+        let data = serde_json::to_vec_pretty(self).context("Unable to serialize plugin consent")?;
+        tokio::fs::write(&path, data)
+            .await
+            .with_context(|| format!("Unable to write plugin consent to {}", path.display()))
+    }
+
+    /// Remove the recorded consent for the plugin with the given ID, if any
+    pub async fn remove(plugin_dir: impl AsRef<Path>, id: &str) -> anyhow::Result<()> {
+        match tokio::fs::remove_file(Self::path(plugin_dir, id)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(anyhow::Error::from(e).context("Unable to remove plugin consent")),
+        }
+    }
+
+    /// Returns the required permissions that have not been granted
+    pub fn missing<'a>(&self, required: &'a [Permission]) -> Vec<&'a Permission> {
+        required
+            .iter()
+            .filter(|permission| !self.granted.contains(permission))
+            .collect()
+    }
+}
+
+/// Socket addresses plugins are allowed to connect to
+#[derive(Debug, Clone, Default)]
+pub(crate) struct NetworkAllowList {
+    addrs: HashSet<SocketAddr>,
+    ips: HashSet<IpAddr>,
+}
+
+impl NetworkAllowList {
+    /// Resolve the hosts of the network permissions. Lattice permissions allow connections to the
+    /// NATS server given by `WASH_PLUGIN_LATTICE_HOST`, or `127.0.0.1:4222` by default
+    pub(crate) async fn resolve(granted: &[Permission]) -> anyhow::Result<Self> {
+        let mut allowed = Self::default();
+        let mut hosts = Vec::new();
+        for permission in granted {
+            match permission {
+                Permission::Network { host } => hosts.push(host.clone()),
+                Permission::Lattice { .. } => hosts.push(
+                    std::env::var("WASH_PLUGIN_LATTICE_HOST")
+                        .unwrap_or_else(|_| "127.0.0.1:4222".to_string()),
+                ),
+                Permission::Filesystem { .. } => {}
+            }
+        }
+        for host in hosts {
+            if let Ok(addr) = host.parse::<SocketAddr>() {
+                allowed.addrs.insert(addr);
+            } else if let Ok(ip) = host.trim_matches(['[', ']']).parse::<IpAddr>() {
+                allowed.ips.insert(ip);
+            } else if host.contains(':') {
+                allowed.addrs.extend(
+                    tokio::net::lookup_host(&host)
+                        .await
+                        .with_context(|| format!("Unable to resolve host {host}"))?,
+                );
+            } else {
+                allowed.ips.extend(
+                    tokio::net::lookup_host((host.as_str(), 0))
+                        .await
+                        .with_context(|| format!("Unable to resolve host {host}"))?
+                        .map(|addr| addr.ip()),
+                );
+            }
+        }
+        Ok(allowed)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.addrs.is_empty() && self.ips.is_empty()
+    }
+
+    pub(crate) fn allows(&self, addr: &SocketAddr) -> bool {
+        self.addrs.contains(addr) || self.ips.contains(&addr.ip())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn consent_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let required = vec![
+            Permission::Filesystem {
+                path: "/tmp/data".to_string(),
+                write: false,
+            },
+            Permission::Network {
+                host: "127.0.0.1:8080".to_string(),
+            },
+        ];
+
+        let consent = Consent::load(dir.path(), "test").await.unwrap();
+        assert_eq!(consent.missing(&required).len(), 2);
+
+        Consent {
+            granted: vec![required[0].clone()],
+        }
+        .save(dir.path(), "test")
+        .await
+        .unwrap();
+        let consent = Consent::load(dir.path(), "test").await.unwrap();
+        assert_eq!(consent.missing(&required), vec![&required[1]]);
+
+        Consent::remove(dir.path(), "test").await.unwrap();
+        let consent = Consent::load(dir.path(), "test").await.unwrap();
+        assert!(consent.granted.is_empty());
+    }
+
+    #[tokio::test]
+    async fn network_allow_list() {
+        let allowed = NetworkAllowList::resolve(&[
+            Permission::Network {
+                host: "127.0.0.1:8080".to_string(),
+            },
+            Permission::Network {
+                host: "10.0.0.1".to_string(),
+            },
+        ])
+        .await
+        .unwrap();
+        assert!(allowed.allows(&"127.0.0.1:8080".parse().unwrap()));
+        assert!(!allowed.allows(&"127.0.0.1:8081".parse().unwrap()));
+        assert!(allowed.allows(&"10.0.0.1:443".parse().unwrap()));
+        assert!(NetworkAllowList::resolve(&[]).await.unwrap().is_empty());
+    }
+}
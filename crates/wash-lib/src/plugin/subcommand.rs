@@ -5,8 +5,17 @@ mod bindings {
     });
 }
 
+mod permission_bindings {
+    wasmtime::component::bindgen!({
+        world: "plugin-permissions",
+        async: true,
+    });
+}
+
 pub use bindings::exports::wasmcloud::wash::subcommand::Metadata;
 use bindings::Subcommands;
+use permission_bindings::exports::wasmcloud::wash::permissions;
+use permission_bindings::PluginPermissions;
 
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -17,14 +26,28 @@ use wasmtime::{Config, Engine};
 use wasmtime_wasi::{DirPerms, FilePerms, WasiCtxBuilder};
 use wasmtime_wasi_http::WasiHttpCtx;
 
+use super::permissions::{NetworkAllowList, Permission};
 use super::Data;
 
 const DIRECTORY_ALLOW: DirPerms = DirPerms::all();
 const DIRECTORY_DENY: DirPerms = DirPerms::READ;
 
+impl From<permissions::Permission> for Permission {
+    fn from(permission: permissions::Permission) -> Self {
+        match permission {
+            permissions::Permission::Filesystem(permissions::PathAccess { path, write }) => {
+                Self::Filesystem { path, write }
+            }
+            permissions::Permission::Network(host) => Self::Network { host },
+            permissions::Permission::Lattice(operation) => Self::Lattice { operation },
+        }
+    }
+}
+
 struct InstanceData {
     instance: Subcommands,
     metadata: Metadata,
+    permissions: Vec<Permission>,
     loaded_path: PathBuf,
     store: wasmtime::Store<Data>,
 }
@@ -114,16 +137,31 @@ impl SubcommandRunner {
         wasmtime_wasi_http::add_only_http_to_linker_async(&mut linker)
             .context("failed to link `wasi:http`")?;
 
-        let instance = Subcommands::instantiate_async(&mut store, &component, &linker).await?;
+        let raw_instance = linker.instantiate_async(&mut store, &component).await?;
+        let instance = Subcommands::new(&mut store, &raw_instance)?;
         let metadata = instance
             .wasmcloud_wash_subcommand()
             .call_register(&mut store)
             .await?;
+        // Declaring permissions is optional, so plugins that do not export the interface require
+        // no permissions
+        let permissions = match PluginPermissions::new(&mut store, &raw_instance) {
+            Result::Ok(plugin) => plugin
+                .wasmcloud_wash_permissions()
+                .call_required_permissions(&mut store)
+                .await
+                .context("failed to get required permissions of plugin")?
+                .into_iter()
+                .map(Permission::from)
+                .collect(),
+            Err(_) => Vec::new(),
+        };
         let maybe_existing = self.plugins.insert(
             metadata.id.clone(),
             InstanceData {
                 instance,
                 metadata: metadata.clone(),
+                permissions,
                 loaded_path: path.as_ref().to_owned(),
                 store,
             },
@@ -152,6 +190,11 @@ impl SubcommandRunner {
         self.plugins.values().map(|data| &data.metadata).collect()
     }
 
+    /// Returns the permissions required by the plugin with the given ID.
+    pub fn permissions(&self, id: &str) -> Option<&[Permission]> {
+        self.plugins.get(id).map(|p| p.permissions.as_slice())
+    }
+
     /// Returns the path to the plugin with the given ID.
     pub fn path(&self, id: &str) -> Option<&Path> {
         self.plugins.get(id).map(|p| p.loaded_path.as_path())
@@ -165,12 +208,30 @@ impl SubcommandRunner {
     ///
     /// All plugins will be passed environment variables starting with
     /// `WASH_PLUGIN_${plugin_id.to_upper()}_` from the current process. Other vars will be ignored
+    ///
+    /// The plugin is run without any of the permissions it declares, use
+    /// [`run_with_permissions`](Self::run_with_permissions) to grant them.
     pub async fn run(
+        &mut self,
+        plugin_id: &str,
+        plugin_dir: PathBuf,
+        dirs: Vec<DirMapping>,
+        args: Vec<String>,
+    ) -> anyhow::Result<()> {
+        self.run_with_permissions(plugin_id, plugin_dir, dirs, args, &[])
+            .await
+    }
+
+    /// Same as [`run`](Self::run), but grants the given permissions to the plugin. Only
+    /// permissions that are declared by the plugin are granted, callers are responsible for
+    /// obtaining consent from the user.
+    pub async fn run_with_permissions(
         &mut self,
         plugin_id: &str,
         plugin_dir: PathBuf,
         dirs: Vec<DirMapping>,
         mut args: Vec<String>,
+        granted: &[Permission],
     ) -> anyhow::Result<()> {
         let plugin = self
             .plugins
@@ -247,9 +308,34 @@ impl SubcommandRunner {
                 })?;
             *matching = str_canonical;
         }
-        // Disable socket connections for now. We may gradually open this up later
-        ctx.socket_addr_check(|_, _| Box::pin(async { false }))
-            .inherit_stdio()
+        let granted: Vec<_> = granted
+            .iter()
+            .filter(|permission| plugin.permissions.contains(permission))
+            .cloned()
+            .collect();
+        for permission in &granted {
+            if let Permission::Filesystem { path, write } = permission {
+                let (dir_perms, file_perms) = if *write {
+                    (DirPerms::all(), FilePerms::all())
+                } else {
+                    (DirPerms::READ, FilePerms::READ)
+                };
+                ctx.preopened_dir(path, path, dir_perms, file_perms)
+                    .with_context(|| format!("Error when preopening permitted path {path}"))?;
+            }
+        }
+        // Socket connections are only allowed to hosts the plugin was granted access to
+        let allowed = NetworkAllowList::resolve(&granted).await?;
+        if allowed.is_empty() {
+            ctx.socket_addr_check(|_, _| Box::pin(async { false }));
+        } else {
+            ctx.allow_ip_name_lookup(true)
+                .socket_addr_check(move |addr, _| {
+                    let allowed = allowed.allows(&addr);
+                    Box::pin(async move { allowed })
+                });
+        }
+        ctx.inherit_stdio()
             .preopened_dir(plugin_dir, "/", DIRECTORY_ALLOW, FilePerms::all())
             .context("Error when preopening plugin dir")?
             .args(&args)
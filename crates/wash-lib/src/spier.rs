@@ -1,10 +1,40 @@
+use std::collections::HashMap;
 use std::task::Poll;
+use std::time::{Duration, Instant};
 
 use anyhow::Result;
 use chrono::{DateTime, Local};
 use futures::{Stream, StreamExt};
 use tracing::debug;
 
+/// Default prefix of the inbox subjects responses to wRPC invocations are sent to
+pub const DEFAULT_INBOX_PREFIX: &str = "_INBOX";
+
+/// Duration after which an invocation without an observed response is reported with an unknown
+/// status
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The outcome of an invocation that was observed by the spier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvocationStatus {
+    /// The invocation returned results
+    Ok,
+    /// The invocation failed with the contained error
+    Error(String),
+    /// No response to the invocation was observed
+    Unknown,
+}
+
+impl std::fmt::Display for InvocationStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvocationStatus::Ok => write!(f, "ok"),
+            InvocationStatus::Error(err) => write!(f, "error: {err}"),
+            InvocationStatus::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
 /// A struct that represents an invocation that was observed by the spier.
 #[derive(Debug)]
 pub struct ObservedInvocation {
@@ -14,13 +44,34 @@ pub struct ObservedInvocation {
     pub from: String,
     /// The name or id of the entity that received this invocation
     pub to: String,
-    /// The operation that was invoked
+    /// The operation that was invoked, i.e. `<interface>.<function>`
     pub operation: String,
+    /// The interface that was invoked, e.g. `wasi:http/incoming-handler@0.2.0`
+    pub interface: String,
+    /// The function that was invoked, e.g. `handle`
+    pub function: String,
+    /// The size of the invocation payload in bytes
+    pub payload_size: usize,
+    /// The time between the invocation and its response, `None` if no response was observed
+    pub latency: Option<Duration>,
+    /// The outcome of the invocation
+    pub status: InvocationStatus,
     /// The inner message that was received. We will attempt to parse the inner message from CBOR
     /// and JSON into a JSON string and fall back to the raw bytes if we are unable to do so
     pub message: ObservedMessage,
 }
 
+impl ObservedInvocation {
+    /// Returns whether the invocation matches `filter`, which is matched against the interface,
+    /// function, source and target of the invocation
+    #[must_use]
+    pub fn matches(&self, filter: &str) -> bool {
+        [&self.interface, &self.function, &self.from, &self.to]
+            .iter()
+            .any(|value| value.contains(filter))
+    }
+}
+
 /// A inner message that we've seen in an invocation message. This will either be a raw bytes or a
 /// parsed value if it was a format we recognized.
 ///
@@ -50,26 +101,66 @@ impl ObservedMessage {
     }
 }
 
+/// An invocation waiting for its response
+struct PendingInvocation {
+    started: Instant,
+    invocation: ObservedInvocation,
+}
+
+/// A response to a wRPC invocation, sent to `<reply>.results` or `<reply>.error`
+#[derive(Debug, PartialEq, Eq)]
+enum Response<'a> {
+    Results { reply: &'a str },
+    Error { reply: &'a str },
+}
+
+/// Parses the subject of a message sent to an inbox as a response to an invocation
+fn parse_response(subject: &str) -> Option<Response<'_>> {
+    // Results and errors of nested values are sent to indexed subjects, e.g. `<reply>.results.0`
+    let mut reply_len = 0;
+    for segment in subject.split('.') {
+        let reply = &subject[..reply_len.saturating_sub(1)];
+        match segment {
+            "results" => return Some(Response::Results { reply }),
+            "error" => return Some(Response::Error { reply }),
+            _ => reply_len += segment.len() + 1,
+        }
+    }
+    None
+}
+
+/// Splits a wRPC operation, `<interface>.<function>`, into interface and function
+fn split_operation(operation: &str) -> (&str, &str) {
+    operation.rsplit_once('.').unwrap_or(("", operation))
+}
+
 /// A struct that can spy on the RPC messages sent to and from an component, consumable as a stream
 pub struct Spier {
     stream: futures::stream::SelectAll<async_nats::Subscriber>,
     component_id: String,
     friendly_name: Option<String>,
+    pending: HashMap<String, PendingInvocation>,
+    expiry: tokio::time::Interval,
 }
 
 impl Spier {
-    /// Creates a new Spier instance for the given component. Will return an error if the component cannot
-    /// be found or if there are connection issues
+    /// Creates a new Spier instance for the given component, observing responses sent to inboxes
+    /// under `inbox_prefix`, e.g. [`DEFAULT_INBOX_PREFIX`]. Will return an error if the component
+    /// cannot be found or if there are connection issues
     pub async fn new(
         component_id: &str,
         ctl_client: &wasmcloud_control_interface::Client,
         nats_client: &async_nats::Client,
+        inbox_prefix: &str,
     ) -> Result<Self> {
         let linked_component = get_linked_components(component_id, ctl_client).await?;
 
         let lattice = ctl_client.lattice();
         let rpc_topic = format!("{lattice}.{component_id}.wrpc.>");
         let component_stream = nats_client.subscribe(rpc_topic).await?;
+        // Responses are sent to the inbox of the invoker, subscribe before any invocation is
+        // observed to not miss responses
+        let inbox_stream = nats_client.subscribe(format!("{inbox_prefix}.>")).await?;
 
         let mut subs = futures::future::join_all(linked_component.iter().map(|prov| {
             let topic = format!("{lattice}.{}.wrpc.>", &prov.id);
@@ -79,6 +170,7 @@ impl Spier {
         .into_iter()
         .collect::<Result<Vec<_>, _>>()?;
         subs.push(component_stream);
+        subs.push(inbox_stream);
 
         let stream = futures::stream::select_all(subs);
 
@@ -86,6 +178,8 @@ impl Spier {
             stream,
             component_id: component_id.to_string(),
             friendly_name: None,
+            pending: HashMap::new(),
+            expiry: tokio::time::interval(Duration::from_secs(1)),
         })
     }
 
@@ -95,6 +189,104 @@ impl Spier {
             .as_deref()
             .unwrap_or_else(|| self.component_id.as_ref())
     }
+
+    /// Handles an invocation, returning it if no response is expected
+    fn handle_invocation(&mut self, msg: async_nats::Message) -> Option<ObservedInvocation> {
+        // <lattice>.<component>.wrpc.0.0.1.<operation>@<versionX.Y.Z>.<function>
+        let mut subject_parts = msg.subject.split('.');
+        subject_parts.next(); // Skip the lattice
+        let component_id = subject_parts.next();
+        // Skip "wrpc.0.0.1", collect the rest
+        let operation = subject_parts.skip(4).collect::<Vec<_>>();
+
+        // The length assertion is to ensure that at least the `operation.function` is present since the
+        // version is technically optional.
+        let Some(component_id) = component_id.filter(|_| operation.len() >= 2) else {
+            debug!("Received invocation with invalid subject: {}", msg.subject);
+            return None;
+        };
+
+        // Attempt to get the source from the message header
+        let source = msg
+            .headers
+            .as_ref()
+            .and_then(|headers| headers.get("source-id").map(ToString::to_string));
+        let (from, to) = if component_id == self.component_id {
+            let from = source.unwrap_or_else(|| "linked component".to_string());
+            (from, component_id.to_string())
+        } else {
+            // Skip invocations of linked components by other components
+            if source
+                .as_ref()
+                .is_some_and(|source| *source != self.component_id)
+            {
+                return None;
+            }
+            (self.component_id.to_string(), component_id.to_string())
+        };
+
+        let operation = operation.join(".");
+        let (interface, function) = split_operation(&operation);
+        // NOTE(thomastaylor312): Ideally we'd consume `msg.payload` above with a
+        // `Cursor` and `from_reader` and then manually reconstruct the acking using the
+        // message context, but I didn't want to waste time optimizing yet
+        let invocation = ObservedInvocation {
+            timestamp: Local::now(),
+            from,
+            to,
+            interface: interface.to_string(),
+            function: function.to_string(),
+            operation,
+            payload_size: msg.payload.len(),
+            latency: None,
+            status: InvocationStatus::Unknown,
+            message: ObservedMessage::parse(msg.payload.to_vec()),
+        };
+        match msg.reply {
+            Some(reply) => {
+                self.pending.insert(
+                    reply.to_string(),
+                    PendingInvocation {
+                        started: Instant::now(),
+                        invocation,
+                    },
+                );
+                None
+            }
+            None => Some(invocation),
+        }
+    }
+
+    /// Handles a message sent to an inbox, returning the invocation it completes, if any
+    fn handle_response(&mut self, msg: async_nats::Message) -> Option<ObservedInvocation> {
+        let (reply, status) = match parse_response(&msg.subject)? {
+            Response::Results { reply } => (reply, InvocationStatus::Ok),
+            Response::Error { reply } => (
+                reply,
+                InvocationStatus::Error(String::from_utf8_lossy(&msg.payload).to_string()),
+            ),
+        };
+        let PendingInvocation {
+            started,
+            mut invocation,
+        } = self.pending.remove(reply)?;
+        invocation.latency = Some(started.elapsed());
+        invocation.status = status;
+        Some(invocation)
+    }
+
+    /// Removes and returns an invocation, which did not receive a response within
+    /// [`RESPONSE_TIMEOUT`]
+    fn expire(&mut self) -> Option<ObservedInvocation> {
+        let reply = self
+            .pending
+            .iter()
+            .find(|(_, pending)| pending.started.elapsed() >= RESPONSE_TIMEOUT)
+            .map(|(reply, _)| reply.clone())?;
+        self.pending
+            .remove(&reply)
+            .map(|PendingInvocation { invocation, .. }| invocation)
+    }
 }
 
 impl Stream for Spier {
@@ -103,49 +295,32 @@ impl Stream for Spier {
         mut self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Option<Self::Item>> {
-        match self.stream.poll_next_unpin(cx) {
-            Poll::Ready(None) => Poll::Ready(None),
-            Poll::Ready(Some(msg)) => {
-                // <lattice>.<component>.wrpc.0.0.1.<operation>@<versionX.Y.Z>.<function>
-                let mut subject_parts = msg.subject.split('.');
-                subject_parts.next(); // Skip the lattice
-                let component_id = subject_parts.next();
-                // Skip "wrpc.0.0.1", collect the rest
-                let operation = subject_parts.skip(4).collect::<Vec<_>>();
-
-                // The length assertion is to ensure that at least the `operation.function` is present since the
-                // version is technically optional.
-                if component_id.is_none() || operation.len() < 2 {
-                    debug!("Received invocation with invalid subject: {}", msg.subject);
-                    cx.waker().wake_by_ref();
-                    return Poll::Pending;
+        if let Some(invocation) = self.expire() {
+            return Poll::Ready(Some(invocation));
+        }
+        loop {
+            match self.stream.poll_next_unpin(cx) {
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Ready(Some(msg)) => {
+                    let invocation = if msg.subject.starts_with(INBOX_PREFIX) {
+                        self.handle_response(msg)
+                    } else {
+                        self.handle_invocation(msg)
+                    };
+                    if let Some(invocation) = invocation {
+                        return Poll::Ready(Some(invocation));
+                    }
                 }
-                let component_id = component_id.unwrap();
-
-                let (from, to) = if component_id == self.component_id {
-                    // Attempt to get the source from the message header
-                    let from = msg
-                        .headers
-                        .and_then(|headers| headers.get("source-id").map(ToString::to_string))
-                        .unwrap_or_else(|| "linked component".to_string());
-                    (from, (*component_id).to_string())
-                } else {
-                    (self.component_id.to_string(), (*component_id).to_string())
-                };
-
-                // NOTE(thomastaylor312): Ideally we'd consume `msg.payload` above with a
-                // `Cursor` and `from_reader` and then manually reconstruct the acking using the
-                // message context, but I didn't want to waste time optimizing yet
-                Poll::Ready(Some(ObservedInvocation {
-                    timestamp: Local::now(),
-                    from,
-                    to,
-                    operation: operation.join("."),
-                    message: ObservedMessage::parse(msg.payload.to_vec()),
-                }))
+                Poll::Pending => break,
             }
-            Poll::Pending => Poll::Pending,
         }
+        // Wake up periodically to report invocations without a response
+        while self.expiry.poll_tick(cx).is_ready() {
+            if let Some(invocation) = self.expire() {
+                return Poll::Ready(Some(invocation));
+            }
+        }
+        Poll::Pending
     }
 }
 
@@ -186,3 +361,41 @@ async fn get_linked_components(
 
     Ok(details)
 }
+
+#[cfg(test)]
+mod test {
+    use super::{parse_response, split_operation, Response};
+
+    #[test]
+    fn test_parse_response() {
+        assert_eq!(
+            parse_response("_INBOX.abc.def.results"),
+            Some(Response::Results {
+                reply: "_INBOX.abc.def"
+            })
+        );
+        assert_eq!(
+            parse_response("_INBOX.abc.def.results.0.1"),
+            Some(Response::Results {
+                reply: "_INBOX.abc.def"
+            })
+        );
+        assert_eq!(
+            parse_response("_INBOX.abc.def.error"),
+            Some(Response::Error {
+                reply: "_INBOX.abc.def"
+            })
+        );
+        assert_eq!(parse_response("_INBOX.abc.def"), None);
+        assert_eq!(parse_response("_INBOX.abc.def.params"), None);
+    }
+
+    #[test]
+    fn test_split_operation() {
+        assert_eq!(
+            split_operation("wasi:http/incoming-handler@0.2.0.handle"),
+            ("wasi:http/incoming-handler@0.2.0", "handle")
+        );
+        assert_eq!(split_operation("handle"), ("", "handle"));
+    }
+}
@@ -0,0 +1,10 @@
+use anyhow::Context as _;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    wasmcloud_provider_dataset_blobstore::run()
+        .await
+        .context("failed to run provider")?;
+    eprintln!("Dataset Blobstore Provider exiting");
+    Ok(())
+}
@@ -0,0 +1,10 @@
+use anyhow::Context as _;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    wasmcloud_provider_image::run()
+        .await
+        .context("failed to run provider")?;
+    eprintln!("Image Provider exiting");
+    Ok(())
+}
@@ -0,0 +1,10 @@
+use anyhow::Context as _;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    wasmcloud_provider_ingest_poller::run()
+        .await
+        .context("failed to run provider")?;
+    eprintln!("Ingest Poller Provider exiting");
+    Ok(())
+}
@@ -0,0 +1,10 @@
+use anyhow::Context as _;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    wasmcloud_provider_workflow_temporal::run()
+        .await
+        .context("failed to run provider")?;
+    eprintln!("Workflow Temporal Provider exiting");
+    Ok(())
+}
@@ -0,0 +1,102 @@
+#![cfg(feature = "provider-http-client")]
+
+//! This module contains tests for singleton providers, which are run by a single host in the
+//! lattice at a time
+//!
+//! The goal of these tests is to ensure that only one host acquires the lease of a singleton
+//! provider, and that a standby host takes over once the leader stops.
+
+use core::time::Duration;
+
+use std::collections::BTreeMap;
+
+use anyhow::{ensure, Context as _};
+use async_nats::jetstream::kv::Store;
+use tokio::time::{sleep, timeout};
+use wasmcloud_test_util::host::WasmCloudTestHost;
+
+pub mod common;
+use common::nats::start_nats;
+use common::providers;
+
+const LATTICE: &str = "singleton";
+const PROVIDER_ID: &str = "http-client";
+
+/// Wait until the lease of the provider is held by `host_id`
+async fn assert_lease_holder(leases: &Store, host_id: &str) -> anyhow::Result<()> {
+    timeout(Duration::from_secs(30), async {
+        loop {
+            let holder = leases
+                .get(format!("PROVIDER_{PROVIDER_ID}"))
+                .await
+                .context("failed to get lease entry")?;
+            if holder.as_deref() == Some(host_id.as_bytes()) {
+                return anyhow::Ok(());
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+    })
+    .await
+    .with_context(|| format!("lease was not acquired by host `{host_id}`"))?
+}
+
+/// Ensure a singleton provider started on multiple hosts is only run by the host holding the
+/// lease, and that a standby host takes over once the leader stops.
+#[tokio::test]
+async fn singleton_provider_failover() -> anyhow::Result<()> {
+    let (nats_server, nats_url, nats_client) =
+        start_nats().await.context("failed to start NATS")?;
+
+    let ctl_client = wasmcloud_control_interface::ClientBuilder::new(nats_client.clone())
+        .lattice(LATTICE.to_string())
+        .build();
+    let leader = WasmCloudTestHost::start(&nats_url, LATTICE)
+        .await
+        .context("failed to start leader host")?;
+    let standby = WasmCloudTestHost::start(&nats_url, LATTICE)
+        .await
+        .context("failed to start standby host")?;
+    let leader_id = leader.host_id();
+    let standby_id = standby.host_id();
+
+    let leases = async_nats::jetstream::new(nats_client)
+        .get_key_value(format!("PROVIDERLEASES_{LATTICE}"))
+        .await
+        .context("failed to get provider lease bucket")?;
+
+    let provider_url = providers::rust_http_client().await.url();
+    let annotations = BTreeMap::from([("wasmcloud.dev/singleton".to_string(), "true".to_string())]);
+    for host_id in [&leader_id, &standby_id] {
+        let resp = ctl_client
+            .start_provider(
+                host_id,
+                provider_url.as_str(),
+                PROVIDER_ID,
+                Some(annotations.clone()),
+                vec![],
+            )
+            .await
+            .map_err(|e| anyhow::anyhow!(e).context("failed to start provider"))?;
+        ensure!(resp.succeeded());
+        if host_id == &leader_id {
+            assert_lease_holder(&leases, &leader_id).await?;
+        }
+    }
+
+    // The standby host must not take over while the leader renews the lease
+    sleep(Duration::from_secs(2)).await;
+    assert_lease_holder(&leases, &leader_id).await?;
+
+    leader.stop().await.context("failed to stop leader host")?;
+    assert_lease_holder(&leases, &standby_id).await?;
+
+    standby
+        .stop()
+        .await
+        .context("failed to stop standby host")?;
+    nats_server
+        .stop()
+        .await
+        .context("failed to stop NATS server")?;
+    Ok(())
+}